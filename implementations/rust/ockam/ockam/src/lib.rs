@@ -75,7 +75,7 @@ mod unique;
 
 pub use error::OckamError;
 pub use metadata::OckamMessage;
-pub use relay_service::{RelayService, RelayServiceOptions};
+pub use relay_service::{RelayService, RelayServiceMetrics, RelayServiceOptions};
 pub use system::{SystemBuilder, SystemHandler, WorkerSystem};
 pub use unique::unique_with_prefix;
 
@@ -0,0 +1,45 @@
+use core::sync::atomic::{AtomicU64, Ordering};
+use ockam_core::compat::sync::Arc;
+
+/// Counters of the relays created by a Relay service
+#[derive(Clone, Debug, Default)]
+pub struct RelayServiceMetrics {
+    registrations: Arc<AtomicU64>,
+    active_relays: Arc<AtomicU64>,
+    forwarded_messages: Arc<AtomicU64>,
+}
+
+impl RelayServiceMetrics {
+    /// Number of relays registered on the service since it started
+    pub fn registrations(&self) -> u64 {
+        self.registrations.load(Ordering::Relaxed)
+    }
+
+    /// Number of relays currently running
+    pub fn active_relays(&self) -> u64 {
+        self.active_relays.load(Ordering::Relaxed)
+    }
+
+    /// Number of messages forwarded by all the relays
+    pub fn forwarded_messages(&self) -> u64 {
+        self.forwarded_messages.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn record_registration(&self) {
+        self.registrations.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(super) fn record_relay_started(&self) {
+        self.active_relays.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(super) fn record_relay_stopped(&self) {
+        let _ = self
+            .active_relays
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1));
+    }
+
+    pub(super) fn record_forwarded_message(&self) {
+        self.forwarded_messages.fetch_add(1, Ordering::Relaxed);
+    }
+}
@@ -1,7 +1,9 @@
+mod metrics;
 mod options;
 mod relay;
 #[allow(clippy::module_inception)]
 mod relay_service;
 
+pub use metrics::*;
 pub use options::*;
 pub use relay_service::*;
@@ -3,12 +3,15 @@ use ockam_core::compat::vec::Vec;
 use ockam_core::flow_control::{FlowControlId, FlowControls};
 use ockam_core::{Address, AllowAll, IncomingAccessControl};
 
+use crate::relay_service::RelayServiceMetrics;
+
 /// Trust Options for a Forwarding Service
 pub struct RelayServiceOptions {
     pub(super) service_incoming_access_control: Arc<dyn IncomingAccessControl>,
     pub(super) relays_incoming_access_control: Arc<dyn IncomingAccessControl>,
     pub(super) consumer_service: Vec<FlowControlId>,
     pub(super) consumer_relay: Vec<FlowControlId>,
+    pub(super) metrics: RelayServiceMetrics,
 }
 
 impl RelayServiceOptions {
@@ -19,6 +22,7 @@ impl RelayServiceOptions {
             relays_incoming_access_control: Arc::new(AllowAll),
             consumer_service: vec![],
             consumer_relay: vec![],
+            metrics: RelayServiceMetrics::default(),
         }
     }
 
@@ -72,6 +76,12 @@ impl RelayServiceOptions {
         self
     }
 
+    /// Record the activity of the Relay service and of its relays in the given metrics
+    pub fn with_metrics(mut self, metrics: RelayServiceMetrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
     pub(super) fn setup_flow_control_for_relay_service(
         &self,
         flow_controls: &FlowControls,
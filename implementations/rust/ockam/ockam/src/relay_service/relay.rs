@@ -1,8 +1,10 @@
+use crate::relay_service::RelayServiceMetrics;
+use crate::remote::RELAY_UNREGISTRATION;
 use crate::Context;
 use ockam_core::compat::sync::Arc;
 use ockam_core::compat::{boxed::Box, vec::Vec};
 use ockam_core::{
-    Address, AllowAll, AllowOnwardAddress, Any, IncomingAccessControl, LocalMessage,
+    Address, AllowAll, AllowOnwardAddress, Any, Decodable, IncomingAccessControl, LocalMessage,
     OutgoingAccessControl, Result, Route, Routed, TransportMessage, Worker,
 };
 use ockam_node::WorkerBuilder;
@@ -10,10 +12,13 @@ use tracing::info;
 
 pub(super) struct Relay {
     forward_route: Route,
+    // route to the worker which registered this relay, only this worker can unregister it
+    registration_route: Route,
     // this option will be `None` after this worker is initialized, because
     // while initializing, the worker will send the payload contained in this
     // field to the `forward_route`, to indicate a successful connection
     payload: Option<Vec<u8>>,
+    metrics: RelayServiceMetrics,
 }
 
 impl Relay {
@@ -23,6 +28,7 @@ impl Relay {
         forward_route: Route,
         registration_payload: Vec<u8>,
         incoming_access_control: Arc<dyn IncomingAccessControl>,
+        metrics: RelayServiceMetrics,
     ) -> Result<()> {
         info!("Created new alias {} for {}", address, forward_route);
 
@@ -36,8 +42,10 @@ impl Relay {
         };
 
         let relay = Self {
+            registration_route: forward_route.clone(),
             forward_route,
             payload: Some(registration_payload.clone()),
+            metrics,
         };
 
         WorkerBuilder::new(relay)
@@ -63,6 +71,7 @@ impl Worker for Relay {
             .expect("payload must be available on init");
         let msg = TransportMessage::v1(self.forward_route.clone(), ctx.address(), payload);
 
+        self.metrics.record_relay_started();
         ctx.forward(LocalMessage::new(msg, Vec::new())).await?;
 
         // Remove the last hop so that just route to the node itself is left
@@ -71,17 +80,31 @@ impl Worker for Relay {
         Ok(())
     }
 
+    async fn shutdown(&mut self, _ctx: &mut Self::Context) -> Result<()> {
+        self.metrics.record_relay_stopped();
+        Ok(())
+    }
+
     async fn handle_message(
         &mut self,
         ctx: &mut Self::Context,
         msg: Routed<Self::Message>,
     ) -> Result<()> {
+        let return_route = msg.return_route();
         let mut message = msg.into_local_message();
         let transport_message = message.transport_mut();
 
         // Remove my address from the onward_route
         transport_message.onward_route.step()?;
 
+        if transport_message.onward_route.next().is_err()
+            && return_route == self.registration_route
+            && is_unregistration(&transport_message.payload)
+        {
+            info!("Removing alias {} for {}", ctx.address(), return_route);
+            return ctx.stop_worker(ctx.address()).await;
+        }
+
         // Prepend forward route
         transport_message
             .onward_route
@@ -107,6 +130,14 @@ impl Worker for Relay {
                 .add_consumer(next_hop, info.flow_control_id());
         }
 
+        self.metrics.record_forwarded_message();
         ctx.forward(message).await
     }
 }
+
+/// Return true if a payload is the message sent by a remote relay when it stops
+fn is_unregistration(payload: &[u8]) -> bool {
+    Vec::<u8>::decode(payload)
+        .map(|payload| payload == RELAY_UNREGISTRATION.as_bytes())
+        .unwrap_or(false)
+}
@@ -69,12 +69,14 @@ impl Worker for RelayService {
         self.options
             .setup_flow_control_for_relay(ctx.flow_controls(), &address);
 
+        self.options.metrics.record_registration();
         Relay::create(
             ctx,
             address,
             forward_route,
             payload,
             self.options.relays_incoming_access_control.clone(),
+            self.options.metrics.clone(),
         )
         .await?;
 
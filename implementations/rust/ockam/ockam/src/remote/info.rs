@@ -1,7 +1,9 @@
 use crate::Message;
+use ockam_core::compat::format;
 use ockam_core::compat::string::String;
+use ockam_core::errcode::{Kind, Origin};
 use ockam_core::flow_control::FlowControlId;
-use ockam_core::{Address, Route};
+use ockam_core::{Address, Error, Result, Route};
 use serde::{Deserialize, Serialize};
 
 /// Information about a remotely forwarded worker.
@@ -45,3 +47,24 @@ impl RemoteRelayInfo {
         &self.flow_control_id
     }
 }
+
+/// Outcome of the registration of a relay, sent by the relay worker to its creator
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug, Message)]
+pub(super) enum RelayRegistration {
+    Registered(RemoteRelayInfo),
+    /// The relay service refused the registration, for the given reason
+    Denied(String),
+}
+
+impl RelayRegistration {
+    pub(super) fn into_result(self) -> Result<RemoteRelayInfo> {
+        match self {
+            RelayRegistration::Registered(info) => Ok(info),
+            RelayRegistration::Denied(reason) => Err(Error::new(
+                Origin::Ockam,
+                Kind::Invalid,
+                format!("the relay registration was denied: {reason}"),
+            )),
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::remote::info::RelayRegistration;
 use crate::remote::{Addresses, RemoteRelay, RemoteRelayInfo, RemoteRelayOptions};
 use crate::Context;
 use core::time::Duration;
@@ -78,6 +79,7 @@ impl RemoteRelay {
             completion_msg_sent: false,
             registration_route,
             registration_payload,
+            relay_route: None,
             flow_control_id,
             heartbeat,
             heartbeat_interval,
@@ -131,9 +133,11 @@ impl RemoteRelay {
             .start(ctx)
             .await?;
 
-        let resp = child_ctx.receive::<RemoteRelayInfo>().await?.body();
-
-        Ok(resp)
+        child_ctx
+            .receive::<RelayRegistration>()
+            .await?
+            .body()
+            .into_result()
     }
 
     /// Create and start new ephemeral RemoteRelay at random address with given Ockam Hub route
@@ -178,9 +182,11 @@ impl RemoteRelay {
             .start(ctx)
             .await?;
 
-        let resp = callback_ctx.receive::<RemoteRelayInfo>().await?.body();
-
-        Ok(resp)
+        callback_ctx
+            .receive::<RelayRegistration>()
+            .await?
+            .body()
+            .into_result()
     }
 
     /// Create and start new static RemoteRelay without heart beats
@@ -229,8 +235,10 @@ impl RemoteRelay {
             .start(ctx)
             .await?;
 
-        let resp = callback_ctx.receive::<RemoteRelayInfo>().await?.body();
-
-        Ok(resp)
+        callback_ctx
+            .receive::<RelayRegistration>()
+            .await?
+            .body()
+            .into_result()
     }
 }
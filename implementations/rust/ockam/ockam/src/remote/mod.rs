@@ -7,7 +7,7 @@ mod lifecycle;
 mod options;
 mod worker;
 
-pub use info::*;
+pub use info::RemoteRelayInfo;
 pub use options::*;
 
 use crate::remote::addresses::Addresses;
@@ -17,6 +17,14 @@ use ockam_core::flow_control::FlowControlId;
 use ockam_core::Route;
 use ockam_node::DelayedEvent;
 
+/// Prefix of the response of a relay service refusing to register a relay,
+/// followed by the reason of the refusal
+pub const RELAY_REGISTRATION_DENIED: &str = "denied: ";
+
+/// Message sent by a [`RemoteRelay`] to its relay when it stops, so that the relay
+/// service removes the relay instead of forwarding messages to a stopped worker
+pub const RELAY_UNREGISTRATION: &str = "unregister";
+
 /// This Worker is responsible for registering on Ockam Orchestrator and forwarding messages to local Worker
 pub struct RemoteRelay {
     /// Address used from other node
@@ -24,6 +32,8 @@ pub struct RemoteRelay {
     completion_msg_sent: bool,
     registration_route: Route,
     registration_payload: String,
+    /// Route to the relay, once it is registered
+    relay_route: Option<Route>,
     flow_control_id: Option<FlowControlId>,
     // We only use Heartbeat for static RemoteRelay
     heartbeat: Option<DelayedEvent<Vec<u8>>>,
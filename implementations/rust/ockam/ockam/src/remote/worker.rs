@@ -1,4 +1,7 @@
-use crate::remote::{RemoteRelay, RemoteRelayInfo};
+use crate::remote::info::RelayRegistration;
+use crate::remote::{
+    RemoteRelay, RemoteRelayInfo, RELAY_REGISTRATION_DENIED, RELAY_UNREGISTRATION,
+};
 use crate::{Context, OckamError};
 use ockam_core::compat::{
     boxed::Box,
@@ -6,7 +9,7 @@ use ockam_core::compat::{
     vec::Vec,
 };
 use ockam_core::{Any, Decodable, Result, Routed, Worker};
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 #[crate::worker]
 impl Worker for RemoteRelay {
@@ -26,6 +29,28 @@ impl Worker for RemoteRelay {
         Ok(())
     }
 
+    async fn shutdown(&mut self, ctx: &mut Self::Context) -> Result<()> {
+        if let Some(heartbeat) = &mut self.heartbeat {
+            heartbeat.cancel();
+        }
+        // Unregister the relay, the relay services which don't support it forward that
+        // message back to this worker, which is stopped
+        if let Some(relay_route) = self.relay_route.take() {
+            debug!(%relay_route, "unregistering the RemoteRelay");
+            if let Err(err) = ctx
+                .send_from_address(
+                    relay_route,
+                    RELAY_UNREGISTRATION.to_string(),
+                    self.addresses.main_remote.clone(),
+                )
+                .await
+            {
+                debug!(%err, "the RemoteRelay could not be unregistered");
+            }
+        }
+        Ok(())
+    }
+
     async fn handle_message(
         &mut self,
         ctx: &mut Context,
@@ -61,11 +86,25 @@ impl Worker for RemoteRelay {
                         .map_err(|_| OckamError::InvalidHubResponse)?;
                     let payload =
                         String::from_utf8(payload).map_err(|_| OckamError::InvalidHubResponse)?;
+                    if let Some(reason) = payload.strip_prefix(RELAY_REGISTRATION_DENIED) {
+                        warn!(%reason, "RemoteRelay registration was denied");
+                        if !self.completion_msg_sent {
+                            ctx.send_from_address(
+                                self.addresses.completion_callback.clone(),
+                                RelayRegistration::Denied(reason.to_string()),
+                                self.addresses.main_remote.clone(),
+                            )
+                            .await?;
+                            self.completion_msg_sent = true;
+                        }
+                        return ctx.stop_worker(self.addresses.main_internal.clone()).await;
+                    }
                     // using ends_with() instead of == to allow for prefixes
                     if !payload.ends_with(&self.registration_payload) {
                         return Err(OckamError::InvalidHubResponse.into());
                     }
 
+                    self.relay_route = Some(return_route.clone());
                     if !self.completion_msg_sent {
                         info!("RemoteRelay registered with route: {}", return_route);
                         let address = match return_route.recipient()?.to_string().strip_prefix("0#")
@@ -76,12 +115,12 @@ impl Worker for RemoteRelay {
 
                         ctx.send_from_address(
                             self.addresses.completion_callback.clone(),
-                            RemoteRelayInfo::new(
+                            RelayRegistration::Registered(RemoteRelayInfo::new(
                                 return_route,
                                 address,
                                 self.addresses.main_remote.clone(),
                                 self.flow_control_id.clone(),
-                            ),
+                            )),
                             self.addresses.main_remote.clone(),
                         )
                         .await?;
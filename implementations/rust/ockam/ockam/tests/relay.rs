@@ -1,8 +1,8 @@
 use ockam::identity::{secure_channels, SecureChannelListenerOptions, SecureChannelOptions};
-use ockam::remote::{RemoteRelay, RemoteRelayOptions};
+use ockam::remote::{RemoteRelay, RemoteRelayOptions, RELAY_REGISTRATION_DENIED};
 use ockam::workers::Echoer;
-use ockam::{RelayService, RelayServiceOptions};
-use ockam_core::{route, AllowAll, Result};
+use ockam::{RelayService, RelayServiceMetrics, RelayServiceOptions};
+use ockam_core::{route, AllowAll, Result, Routed, Worker};
 use ockam_node::{Context, MessageReceiveOptions};
 use ockam_transport_tcp::{TcpConnectionOptions, TcpListenerOptions, TcpTransport};
 use std::time::Duration;
@@ -28,6 +28,62 @@ async fn test1(ctx: &mut Context) -> Result<()> {
     ctx.stop().await
 }
 
+// Node creates a Relay service recording metrics, Echoer is reached through the Relay
+#[ockam_macros::test]
+async fn test_metrics(ctx: &mut Context) -> Result<()> {
+    let metrics = RelayServiceMetrics::default();
+    RelayService::create(
+        ctx,
+        "forwarding_service",
+        RelayServiceOptions::new().with_metrics(metrics.clone()),
+    )
+    .await?;
+
+    ctx.start_worker("echoer", Echoer).await?;
+
+    let remote_info = RemoteRelay::create(ctx, route![], RemoteRelayOptions::new()).await?;
+    assert_eq!(metrics.registrations(), 1);
+    assert_eq!(metrics.active_relays(), 1);
+
+    let resp = ctx
+        .send_and_receive::<String>(
+            route![remote_info.remote_address(), "echoer"],
+            "Hello".to_string(),
+        )
+        .await?;
+
+    assert_eq!(resp, "Hello");
+    assert_eq!(metrics.forwarded_messages(), 1);
+
+    ctx.stop().await
+}
+
+// The Relay is removed from the Relay service when the Remote Relay stops
+#[ockam_macros::test]
+async fn test_unregister(ctx: &mut Context) -> Result<()> {
+    let metrics = RelayServiceMetrics::default();
+    RelayService::create(
+        ctx,
+        "forwarding_service",
+        RelayServiceOptions::new().with_metrics(metrics.clone()),
+    )
+    .await?;
+
+    let remote_info = RemoteRelay::create(ctx, route![], RemoteRelayOptions::new()).await?;
+    assert_eq!(metrics.active_relays(), 1);
+
+    ctx.stop_worker(remote_info.worker_address().clone())
+        .await?;
+    ctx.sleep(Duration::from_millis(100)).await;
+    assert_eq!(metrics.active_relays(), 0);
+    assert!(!ctx
+        .list_workers()
+        .await?
+        .contains(&remote_info.remote_address().into()));
+
+    ctx.stop().await
+}
+
 // Cloud: Hosts a Relay service and listens on a tcp port. No flow control
 // Server: Connects to a Cloud using tcp and creates a dynamic Relay. Using flow control
 // Client: Connects to a Cloud using tcp and reaches to the Server's Echoer. Using flow control
@@ -253,3 +309,41 @@ async fn test4(ctx: &mut Context) -> Result<()> {
 
     ctx.stop().await
 }
+
+struct DenyingRelayService;
+
+#[ockam::worker]
+impl Worker for DenyingRelayService {
+    type Message = String;
+    type Context = Context;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<String>) -> Result<()> {
+        ctx.send(
+            msg.return_route(),
+            format!(
+                "{RELAY_REGISTRATION_DENIED}the alias {} is taken",
+                msg.body()
+            ),
+        )
+        .await
+    }
+}
+
+// A relay service denies the registration of a static relay, the creation of the relay fails
+#[ockam_macros::test]
+async fn test_denied_registration(ctx: &mut Context) -> Result<()> {
+    ctx.start_worker("forwarding_service", DenyingRelayService)
+        .await?;
+
+    let result = RemoteRelay::create_static_without_heartbeats(
+        ctx,
+        route![],
+        "alias",
+        RemoteRelayOptions::new(),
+    )
+    .await;
+    let error = result.unwrap_err().to_string();
+    assert!(error.contains("the alias alias is taken"), "{error}");
+
+    ctx.stop().await
+}
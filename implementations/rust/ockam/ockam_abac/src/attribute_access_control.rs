@@ -10,13 +10,18 @@ use ockam_core::Result;
 use ockam_core::{IncomingAccessControl, RelayMessage};
 use tracing as log;
 
-use crate::expr::str;
+use crate::eval::SUBJECT_GROUPS;
+use crate::explain::explain_denial;
+use crate::expr::{seq, str};
 use crate::Expr::*;
-use crate::{eval, Env, Expr};
+use crate::{eval, Decision, Env, ExplainMode, Expr, PolicyAudit};
 use ockam_core::compat::format;
 use ockam_core::compat::string::ToString;
 use ockam_core::compat::sync::Arc;
-use ockam_identity::{Identifier, IdentitiesRepository, IdentitySecureChannelLocalInfo};
+use ockam_identity::utils::now;
+use ockam_identity::{
+    GroupsRepository, Identifier, IdentitiesRepository, IdentitySecureChannelLocalInfo,
+};
 
 /// This AccessControl uses a storage for authenticated attributes in order
 /// to verify if a policy expression is valid
@@ -24,8 +29,11 @@ use ockam_identity::{Identifier, IdentitiesRepository, IdentitySecureChannelLoca
 /// as [`crate::PolicyStorage`] can be used to retrieve a specific policy for a given resource and action
 pub struct AbacAccessControl {
     repository: Arc<dyn IdentitiesRepository>,
+    groups: Option<Arc<dyn GroupsRepository>>,
     expression: Expr,
     environment: Env,
+    explain_mode: ExplainMode,
+    audit: Option<Arc<dyn PolicyAudit>>,
 }
 
 /// Debug implementation printing out the policy expression only
@@ -45,11 +53,33 @@ impl AbacAccessControl {
     ) -> Self {
         Self {
             repository,
+            groups: None,
             expression,
             environment,
+            explain_mode: ExplainMode::default(),
+            audit: None,
         }
     }
 
+    /// Read the groups of the subjects from a repository, so that they can be checked
+    /// with `(member_of "group")`
+    pub fn with_groups(mut self, groups: Arc<dyn GroupsRepository>) -> Self {
+        self.groups = Some(groups);
+        self
+    }
+
+    /// Explain the denied accesses in the audit log, and possibly to the caller
+    pub fn with_explain_mode(mut self, explain_mode: ExplainMode) -> Self {
+        self.explain_mode = explain_mode;
+        self
+    }
+
+    /// Send the explained denials to an audit, which records them and answers the denied messages
+    pub fn with_audit(mut self, audit: Arc<dyn PolicyAudit>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
     /// Create an AccessControl which will verify that the sender of
     /// a message has an authenticated attribute with the correct name and value
     pub fn create(
@@ -70,10 +100,35 @@ where {
 impl AbacAccessControl {
     /// Returns true if the identity is authorized
     pub async fn is_identity_authorized(&self, id: Identifier) -> Result<bool> {
+        Ok(self.decide(id).await?.is_authorized())
+    }
+
+    /// Returns the decision for an identity, with the reason of a denial
+    /// if the explain mode is [`ExplainMode::Reason`]
+    pub async fn decide(&self, id: Identifier) -> Result<Decision> {
+        let environment = self.environment(&id).await?;
+        if self.evaluate(&environment, &id) {
+            Ok(Decision::from(true))
+        } else {
+            Ok(explain_denial(
+                self.explain_mode,
+                self.audit.as_deref(),
+                &self.expression,
+                &environment,
+                &id,
+            ))
+        }
+    }
+
+    /// Return the environment of the evaluation of the expression for an identity
+    async fn environment(&self, id: &Identifier) -> Result<Env> {
         let mut environment = self.environment.clone();
 
         // Get identity attributes and populate the environment:
-        if let Some(attrs) = self.repository.get_attributes(&id).await? {
+        if let Some(attrs) = self.repository.get_attributes(id).await? {
+            // attributes which expired, or which are restricted to a validity period or a
+            // schedule, are only added to the environment when they are currently valid
+            let now = now()?;
             for (key, value) in attrs.attrs() {
                 let key = match from_utf8(key) {
                     Ok(key) => key,
@@ -94,6 +149,15 @@ impl AbacAccessControl {
                         "attribute key with whitespace ignored"
                     }
                 }
+                if !attrs.is_active(key.as_bytes(), now) {
+                    log::debug! {
+                        policy = %self.expression,
+                        id     = %id,
+                        key    = %key,
+                        "attribute ignored outside of its validity"
+                    }
+                    continue;
+                }
                 match str::from_utf8(value) {
                     Ok(s) => {
                         if environment.contains(key) {
@@ -120,11 +184,20 @@ impl AbacAccessControl {
             }
         };
 
+        // the groups of the subject replace any attribute with the same name
+        if let Some(groups) = &self.groups {
+            let groups = groups.get_groups(id).await?;
+            environment.put(SUBJECT_GROUPS, seq(groups.into_iter().map(str)));
+        }
+
         // add the identifier itself as a subject parameter
         environment.put("subject.identifier", str(id.to_string()));
+        Ok(environment)
+    }
 
-        // Finally, evaluate the expression and return the result:
-        match eval(&self.expression, &environment) {
+    /// Evaluate the expression and return the result
+    fn evaluate(&self, environment: &Env, id: &Identifier) -> bool {
+        match eval(&self.expression, environment) {
             Ok(Expr::Bool(b)) => {
                 log::debug! {
                     policy        = %self.expression,
@@ -132,7 +205,7 @@ impl AbacAccessControl {
                     is_authorized = %b,
                     "policy evaluated"
                 }
-                Ok(b)
+                b
             }
             Ok(x) => {
                 log::warn! {
@@ -141,7 +214,7 @@ impl AbacAccessControl {
                     expr   = %x,
                     "evaluation did not yield a boolean result"
                 }
-                Ok(false)
+                false
             }
             Err(e) => {
                 log::warn! {
@@ -150,7 +223,7 @@ impl AbacAccessControl {
                     err    = %e,
                     "policy evaluation failed"
                 }
-                Ok(false)
+                false
             }
         }
     }
@@ -171,6 +244,167 @@ impl IncomingAccessControl for AbacAccessControl {
             return Ok(false);
         };
 
-        self.is_identity_authorized(id).await
+        let decision = self.decide(id).await?;
+        if let (Some(reason), Some(audit)) = (decision.reason(), &self.audit) {
+            audit.reply_denial(msg, reason);
+        }
+        Ok(decision.is_authorized())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::member_of;
+    use crate::Explanation;
+    use ockam_core::compat::collections::BTreeMap;
+    use ockam_core::{route, LocalMessage, TransportMessage};
+    use ockam_identity::models::TimestampInSeconds;
+    use ockam_identity::{identities, AttributesEntry, GroupsStorage, IdentityAttributesWriter};
+
+    #[tokio::test]
+    async fn test_member_of() -> Result<()> {
+        let identities = identities();
+        let repository = identities.repository();
+        let groups = GroupsStorage::create();
+        let admin = identities.identities_creation().create_identity().await?;
+        let other = identities.identities_creation().create_identity().await?;
+        groups.create_group("admins").await?;
+        groups.add_member("admins", admin.identifier()).await?;
+
+        // an attribute can't be used to claim a membership
+        let attributes = BTreeMap::from([(b"groups".to_vec(), b"admins".to_vec())]);
+        repository
+            .put_attributes(
+                other.identifier(),
+                AttributesEntry::new(attributes, now()?, None, None),
+            )
+            .await?;
+
+        let access_control = AbacAccessControl::new(repository, member_of("admins"), Env::new())
+            .with_groups(groups.clone());
+        assert!(
+            access_control
+                .is_identity_authorized(admin.identifier().clone())
+                .await?
+        );
+        assert!(
+            !access_control
+                .is_identity_authorized(other.identifier().clone())
+                .await?
+        );
+
+        groups.remove_member("admins", admin.identifier()).await?;
+        assert!(
+            !access_control
+                .is_identity_authorized(admin.identifier().clone())
+                .await?
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_expired_attributes_are_ignored() -> Result<()> {
+        let identities = identities();
+        let repository = identities.repository();
+        let member = identities.identities_creation().create_identity().await?;
+        let authority1 = identities.identities_creation().create_identity().await?;
+        let authority2 = identities.identities_creation().create_identity().await?;
+        let now = now()?;
+
+        // the attributes attested by the second authority already expired
+        let team = AttributesEntry::new(
+            BTreeMap::from([(b"team".to_vec(), b"blue".to_vec())]),
+            now,
+            Some(TimestampInSeconds(*now + 100)),
+            Some(authority1.identifier().clone()),
+        );
+        let role = AttributesEntry::new(
+            BTreeMap::from([(b"role".to_vec(), b"admin".to_vec())]),
+            TimestampInSeconds(*now - 20),
+            Some(TimestampInSeconds(*now - 10)),
+            Some(authority2.identifier().clone()),
+        );
+        repository
+            .put_attributes(member.identifier(), team.merge(role))
+            .await?;
+
+        let team = AbacAccessControl::create(repository.clone(), "team", "blue");
+        assert!(
+            team.is_identity_authorized(member.identifier().clone())
+                .await?
+        );
+        let role = AbacAccessControl::create(repository, "role", "admin");
+        assert!(
+            !role
+                .is_identity_authorized(member.identifier().clone())
+                .await?
+        );
+        Ok(())
+    }
+
+    /// Audit keeping the denials it receives
+    #[derive(Default)]
+    struct TestAudit {
+        denials: std::sync::Mutex<Vec<(Identifier, String)>>,
+        replies: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl PolicyAudit for TestAudit {
+        fn record_denial(&self, id: &Identifier, _environment: &Env, explanation: &Explanation) {
+            self.denials
+                .lock()
+                .unwrap()
+                .push((id.clone(), explanation.expression().to_string()));
+        }
+
+        fn reply_denial(&self, _msg: &RelayMessage, reason: &str) {
+            self.replies.lock().unwrap().push(reason.to_string());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_denials_are_audited_and_answered() -> Result<()> {
+        let identities = identities();
+        let member = identities.identities_creation().create_identity().await?;
+        let audit = Arc::new(TestAudit::default());
+        let message = RelayMessage::new(
+            "client".into(),
+            "service".into(),
+            LocalMessage::new(
+                TransportMessage::v1(route!["service"], route!["client"], vec![]),
+                IdentitySecureChannelLocalInfo::mark(vec![], member.identifier().clone())?,
+            ),
+        );
+        let policy = List(vec![
+            Ident("=".into()),
+            Ident("subject.role".into()),
+            Str("admin".into()),
+        ]);
+
+        // the denials are only recorded when they are explained
+        let access_control =
+            AbacAccessControl::new(identities.repository(), policy.clone(), Env::new())
+                .with_audit(audit.clone());
+        assert!(!access_control.is_authorized(&message).await?);
+        assert!(audit.denials.lock().unwrap().is_empty());
+
+        let access_control = access_control.with_explain_mode(ExplainMode::Log);
+        assert!(!access_control.is_authorized(&message).await?);
+        assert_eq!(
+            audit.denials.lock().unwrap().clone(),
+            vec![(member.identifier().clone(), policy.to_string())]
+        );
+        assert!(audit.replies.lock().unwrap().is_empty());
+
+        // the denied message is answered with the redacted reason
+        let access_control = access_control.with_explain_mode(ExplainMode::Reason);
+        assert!(!access_control.is_authorized(&message).await?);
+        assert_eq!(
+            audit.replies.lock().unwrap().clone(),
+            vec!["the condition (= subject.role _) is not satisfied, missing attributes: subject.role"
+                .to_string()]
+        );
+        Ok(())
     }
 }
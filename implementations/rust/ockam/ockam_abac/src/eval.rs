@@ -6,6 +6,9 @@ use crate::expr::{unit, Expr};
 use ockam_core::compat::string::ToString;
 use ockam_core::compat::vec::Vec;
 
+/// Name of the sequence of groups of the subject in the environment, read by `member_of`
+pub(crate) const SUBJECT_GROUPS: &str = "subject.groups";
+
 #[rustfmt::skip]
 pub fn eval(expr: &Expr, env: &Env) -> Result<Expr, EvalError> {
     /// A stack operation.
@@ -23,6 +26,7 @@ pub fn eval(expr: &Expr, env: &Env) -> Result<Expr, EvalError> {
         Gt(usize),
         Lt(usize),
         Member,
+        MemberOf,
         Seq(usize),
     }
 
@@ -132,6 +136,13 @@ pub fn eval(expr: &Expr, env: &Env) -> Result<Expr, EvalError> {
                             }
                             ctrl.push(Op::Member)
                         }
+                        "member_of" => {
+                            if nargs != 1 {
+                                let msg = "'member_of' requires one argument";
+                                return Err(EvalError::malformed(msg))
+                            }
+                            ctrl.push(Op::MemberOf)
+                        }
                         "exists?" => {
                             let mut b = true;
                             for x in &xs[1 ..] {
@@ -255,6 +266,26 @@ pub fn eval(expr: &Expr, env: &Env) -> Result<Expr, EvalError> {
                     }
                 }
             }
+            Op::MemberOf => {
+                let g = pop(&mut args);
+                // a subject without groups is not a member of any group
+                let groups = match env.get(SUBJECT_GROUPS) {
+                    Ok(Expr::Seq(groups)) => groups.as_slice(),
+                    Ok(other) => {
+                        let msg = "'member_of' expects the groups of the subject to be a sequence";
+                        return Err(EvalError::InvalidType(other.clone(), msg))
+                    }
+                    Err(_) => &[],
+                };
+                let mut b = false;
+                for x in groups {
+                    if g.equals(x)? {
+                        b = true;
+                        break
+                    }
+                }
+                args.push(Expr::Bool(b))
+            }
             Op::Seq(n) => {
                 let s = args.split_off(args.len() - n);
                 args.push(Expr::Seq(s))
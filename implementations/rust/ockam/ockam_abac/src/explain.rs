@@ -0,0 +1,373 @@
+use core::fmt;
+use core::str::FromStr;
+
+use crate::env::Env;
+use crate::eval::eval;
+use crate::expr::Expr;
+use ockam_core::compat::collections::BTreeMap;
+use ockam_core::compat::format;
+use ockam_core::compat::string::{String, ToString};
+use ockam_core::compat::vec::Vec;
+use ockam_core::env::FromString;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::RelayMessage;
+use ockam_identity::Identifier;
+use tracing as log;
+
+/// Tracing target of the explained policy decisions, so that they can be filtered out of the other logs
+const AUDIT_TARGET: &str = "ockam_abac::audit";
+
+/// How much of a denied access is explained
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExplainMode {
+    /// Denials are not explained
+    #[default]
+    Off,
+    /// Denials are explained in the audit log
+    Log,
+    /// Denials are explained in the audit log, and a redacted reason is returned to the caller
+    Reason,
+}
+
+impl fmt::Display for ExplainMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExplainMode::Off => write!(f, "off"),
+            ExplainMode::Log => write!(f, "log"),
+            ExplainMode::Reason => write!(f, "reason"),
+        }
+    }
+}
+
+impl FromStr for ExplainMode {
+    type Err = ockam_core::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(ExplainMode::Off),
+            "log" => Ok(ExplainMode::Log),
+            "reason" => Ok(ExplainMode::Reason),
+            _ => Err(ockam_core::Error::new(
+                Origin::Application,
+                Kind::Invalid,
+                "the explain mode must be one of: off, log, reason",
+            )),
+        }
+    }
+}
+
+impl FromString for ExplainMode {
+    fn from_string(s: &str) -> ockam_core::Result<Self> {
+        s.parse()
+    }
+}
+
+/// Explanation of the evaluation of a policy expression.
+///
+/// It contains the attributes read by the expression, with their values, and the
+/// predicate which made the evaluation fail when the access is denied
+#[derive(Debug, Clone)]
+pub struct Explanation {
+    expression: Expr,
+    authorized: bool,
+    attributes: BTreeMap<String, Option<Expr>>,
+    failing_predicate: Option<Expr>,
+    error: Option<String>,
+}
+
+/// Evaluate an expression and explain its result
+pub fn explain(expr: &Expr, env: &Env) -> Explanation {
+    let (authorized, error) = match eval(expr, env) {
+        Ok(Expr::Bool(b)) => (b, None),
+        Ok(x) => (
+            false,
+            Some(format!("evaluation did not yield a boolean result: {x}")),
+        ),
+        Err(e) => (false, Some(e.to_string())),
+    };
+    let failing_predicate = if authorized {
+        None
+    } else {
+        Some(failing_predicate(expr, env))
+    };
+    Explanation {
+        expression: expr.clone(),
+        authorized,
+        attributes: attributes(expr, env),
+        failing_predicate,
+        error,
+    }
+}
+
+impl Explanation {
+    /// The evaluated expression
+    pub fn expression(&self) -> &Expr {
+        &self.expression
+    }
+
+    /// Return true if the expression evaluated to `true`
+    pub fn is_authorized(&self) -> bool {
+        self.authorized
+    }
+
+    /// The identifiers read by the expression, with their value, or `None` if they are unbound
+    pub fn attributes(&self) -> &BTreeMap<String, Option<Expr>> {
+        &self.attributes
+    }
+
+    /// The innermost predicate which did not evaluate to `true`, if the access is denied
+    pub fn failing_predicate(&self) -> Option<&Expr> {
+        self.failing_predicate.as_ref()
+    }
+
+    /// The evaluation error, if the expression could not be evaluated to a boolean
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Reason of a denial which can be returned to the denied party.
+    ///
+    /// It names the failing predicate and the missing attributes, but the literal values
+    /// of the policy and the values of the attributes are not disclosed
+    pub fn redacted_reason(&self) -> Option<String> {
+        let predicate = self.failing_predicate.as_ref()?;
+        let mut reason = match predicate {
+            Expr::Bool(false) => "the policy denies all access".to_string(),
+            _ => format!("the condition {} is not satisfied", redact(predicate)),
+        };
+        let missing: Vec<&str> = self
+            .attributes
+            .iter()
+            .filter(|(_, value)| value.is_none())
+            .map(|(name, _)| name.as_str())
+            .collect();
+        if !missing.is_empty() {
+            reason.push_str(&format!(", missing attributes: {}", missing.join(", ")));
+        }
+        Some(reason)
+    }
+
+    /// Record a denied access in the audit log
+    pub(crate) fn log_denial(&self, id: &Identifier) {
+        let attributes: Vec<String> = self
+            .attributes
+            .iter()
+            .map(|(name, value)| match value {
+                Some(value) => format!("{name}={value}"),
+                None => format!("{name} unbound"),
+            })
+            .collect();
+        let failing_predicate = self
+            .failing_predicate
+            .as_ref()
+            .map(|p| p.to_string())
+            .unwrap_or_default();
+        log::info! {
+            target: AUDIT_TARGET,
+            policy            = %self.expression,
+            id                = %id,
+            attributes        = %attributes.join(", "),
+            failing_predicate = %failing_predicate,
+            error             = ?self.error,
+            "access denied"
+        }
+    }
+}
+
+/// Receiver of the accesses denied by the policies, when they are explained
+pub trait PolicyAudit: Send + Sync + 'static {
+    /// Record a denied access, with the environment the policy was evaluated in
+    fn record_denial(&self, id: &Identifier, environment: &Env, explanation: &Explanation);
+
+    /// Answer a message denied with [`ExplainMode::Reason`] with the redacted reason of
+    /// the denial. The message is dropped without an answer by default
+    fn reply_denial(&self, _msg: &RelayMessage, _reason: &str) {}
+}
+
+/// The decision of an access control for an identity, with the reason of a denial
+/// when the access control is configured with [`ExplainMode::Reason`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decision {
+    authorized: bool,
+    reason: Option<String>,
+}
+
+impl Decision {
+    /// Return true if the access is authorized
+    pub fn is_authorized(&self) -> bool {
+        self.authorized
+    }
+
+    /// Redacted reason of a denial
+    pub fn reason(&self) -> Option<&str> {
+        self.reason.as_deref()
+    }
+}
+
+impl From<bool> for Decision {
+    fn from(authorized: bool) -> Self {
+        Self {
+            authorized,
+            reason: None,
+        }
+    }
+}
+
+/// Explain a denied access according to the explain mode
+pub(crate) fn explain_denial(
+    mode: ExplainMode,
+    audit: Option<&dyn PolicyAudit>,
+    expression: &Expr,
+    environment: &Env,
+    id: &Identifier,
+) -> Decision {
+    if mode == ExplainMode::Off {
+        return Decision::from(false);
+    }
+    let explanation = explain(expression, environment);
+    explanation.log_denial(id);
+    if let Some(audit) = audit {
+        audit.record_denial(id, environment, &explanation);
+    }
+    Decision {
+        authorized: false,
+        reason: match mode {
+            ExplainMode::Reason => explanation.redacted_reason(),
+            _ => None,
+        },
+    }
+}
+
+/// Return the innermost sub-expression responsible for a denial.
+///
+/// The first argument of an `and` which is not true and the branch taken by an `if`
+/// are explored, any other expression is the failing predicate
+fn failing_predicate(expr: &Expr, env: &Env) -> Expr {
+    let is_true = |x: &Expr| matches!(eval(x, env), Ok(Expr::Bool(true)));
+    let mut current = expr;
+    loop {
+        let next = match current {
+            Expr::List(xs) => match &xs[..] {
+                [Expr::Ident(op), args @ ..] if op == "and" => args.iter().find(|x| !is_true(x)),
+                [Expr::Ident(op), test, then, orelse] if op == "if" => match eval(test, env) {
+                    Ok(Expr::Bool(true)) => Some(then),
+                    Ok(Expr::Bool(false)) => Some(orelse),
+                    _ => Some(test),
+                },
+                _ => None,
+            },
+            _ => None,
+        };
+        match next {
+            Some(next) => current = next,
+            None => return current.clone(),
+        }
+    }
+}
+
+/// Return the identifiers of an expression, with their value in the environment
+fn attributes(expr: &Expr, env: &Env) -> BTreeMap<String, Option<Expr>> {
+    let mut attributes = BTreeMap::new();
+    let mut exprs = Vec::from([expr]);
+    while let Some(expr) = exprs.pop() {
+        match expr {
+            Expr::Ident(id) => {
+                attributes.insert(id.clone(), env.get(id).ok().cloned());
+            }
+            // the head of a list is an operator
+            Expr::List(xs) => exprs.extend(xs.iter().skip(1)),
+            Expr::Seq(xs) => exprs.extend(xs.iter()),
+            _ => {}
+        }
+    }
+    attributes
+}
+
+/// Replace the literal values of an expression, keeping its operators and identifiers
+fn redact(expr: &Expr) -> Expr {
+    match expr {
+        Expr::Ident(_) => expr.clone(),
+        Expr::List(xs) => Expr::List(
+            xs.iter()
+                .enumerate()
+                .map(|(i, x)| match x {
+                    Expr::Ident(_) if i == 0 => x.clone(),
+                    _ => redact(x),
+                })
+                .collect(),
+        ),
+        _ => Expr::Ident("_".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::str;
+    use crate::parser::parse;
+
+    fn policy(s: &str) -> Expr {
+        parse(s).unwrap().unwrap()
+    }
+
+    #[test]
+    fn explain_an_authorized_access() {
+        let mut env = Env::new();
+        env.put("subject.role", str("admin"));
+        let explanation = explain(&policy(r#"(= subject.role "admin")"#), &env);
+        assert!(explanation.is_authorized());
+        assert!(explanation.failing_predicate().is_none());
+        assert!(explanation.redacted_reason().is_none());
+    }
+
+    #[test]
+    fn explain_the_failing_clause_of_a_conjunction() {
+        let mut env = Env::new();
+        env.put("subject.role", str("user"));
+        env.put("subject.team", str("kafka"));
+        let expr = policy(r#"(and (= subject.team "kafka") (= subject.role "admin"))"#);
+        let explanation = explain(&expr, &env);
+
+        assert!(!explanation.is_authorized());
+        assert_eq!(
+            explanation.failing_predicate().unwrap().to_string(),
+            r#"(= subject.role "admin")"#
+        );
+        assert_eq!(
+            explanation.attributes()["subject.role"]
+                .as_ref()
+                .map(|v| v.to_string()),
+            Some(r#""user""#.to_string())
+        );
+        assert_eq!(
+            explanation.redacted_reason().unwrap(),
+            "the condition (= subject.role _) is not satisfied"
+        );
+    }
+
+    #[test]
+    fn explain_a_missing_attribute() {
+        let expr = policy(r#"(if (= subject.team "kafka") (= subject.role "admin") false)"#);
+        let explanation = explain(&expr, &Env::new());
+
+        assert!(!explanation.is_authorized());
+        assert!(explanation.error().unwrap().contains("subject.team"));
+        assert_eq!(
+            explanation.failing_predicate().unwrap().to_string(),
+            r#"(= subject.team "kafka")"#
+        );
+        assert_eq!(
+            explanation.redacted_reason().unwrap(),
+            "the condition (= subject.team _) is not satisfied, missing attributes: subject.role, subject.team"
+        );
+    }
+
+    #[test]
+    fn parse_explain_mode() {
+        assert_eq!(
+            "reason".parse::<ExplainMode>().unwrap(),
+            ExplainMode::Reason
+        );
+        assert!("all".parse::<ExplainMode>().is_err());
+    }
+}
@@ -229,6 +229,10 @@ where
     with_op(ident("="), exprs)
 }
 
+pub fn member_of<S: Into<String>>(group: S) -> Expr {
+    with_op(ident("member_of"), [str(group)])
+}
+
 fn with_op<I>(op: Expr, exprs: I) -> Expr
 where
     I: IntoIterator<Item = Expr>,
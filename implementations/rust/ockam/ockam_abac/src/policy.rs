@@ -1,7 +1,8 @@
+use crate::explain::explain_denial;
 use crate::traits::PolicyStorage;
 use crate::types::{Action, Resource};
 use crate::AbacAccessControl;
-use crate::{Env, Expr};
+use crate::{Decision, Env, ExplainMode, Expr, PolicyAudit};
 use core::fmt;
 use core::fmt::{Debug, Formatter};
 use ockam_core::compat::boxed::Box;
@@ -9,7 +10,7 @@ use ockam_core::compat::format;
 use ockam_core::compat::sync::Arc;
 use ockam_core::{async_trait, RelayMessage};
 use ockam_core::{IncomingAccessControl, Result};
-use ockam_identity::IdentitiesRepository;
+use ockam_identity::{GroupsRepository, Identifier, IdentitiesRepository};
 use tracing as log;
 
 /// Evaluates a policy expression against an environment of attributes.
@@ -21,7 +22,10 @@ pub struct PolicyAccessControl {
     action: Action,
     policies: Arc<dyn PolicyStorage>,
     repository: Arc<dyn IdentitiesRepository>,
+    groups: Option<Arc<dyn GroupsRepository>>,
     environment: Env,
+    explain_mode: ExplainMode,
+    audit: Option<Arc<dyn PolicyAudit>>,
 }
 
 /// Debug implementation writing out the resource, action and initial environment
@@ -54,39 +58,109 @@ impl PolicyAccessControl {
             action: a,
             policies,
             repository,
+            groups: None,
             environment: env,
+            explain_mode: ExplainMode::default(),
+            audit: None,
         }
     }
+
+    /// Read the groups of the subjects from a repository, so that they can be checked
+    /// with `(member_of "group")`
+    pub fn with_groups(mut self, groups: Arc<dyn GroupsRepository>) -> Self {
+        self.groups = Some(groups);
+        self
+    }
+
+    /// Explain the denied accesses in the audit log, and possibly to the caller
+    pub fn with_explain_mode(mut self, explain_mode: ExplainMode) -> Self {
+        self.explain_mode = explain_mode;
+        self
+    }
+
+    /// Send the explained denials to an audit, which records them and answers the denied messages
+    pub fn with_audit(mut self, audit: Arc<dyn PolicyAudit>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
 }
 
-#[async_trait]
-impl IncomingAccessControl for PolicyAccessControl {
-    async fn is_authorized(&self, msg: &RelayMessage) -> Result<bool> {
-        // Load the policy expression for resource and action:
-        let expr = if let Some(expr) = self
+impl PolicyAccessControl {
+    /// Return true if an identity is authorized by the policy of the resource and action.
+    ///
+    /// This is used to check again the sessions of an identity when its attributes change
+    pub async fn is_identity_authorized(&self, id: &Identifier) -> Result<bool> {
+        Ok(self.decide(id).await?.is_authorized())
+    }
+
+    /// Return the decision for an identity, with the reason of a denial
+    /// if the explain mode is [`ExplainMode::Reason`]
+    pub async fn decide(&self, id: &Identifier) -> Result<Decision> {
+        match self.expression().await? {
+            Ok(expr) => self.access_control(expr).decide(id.clone()).await,
+            Err(true) => Ok(Decision::from(true)),
+            Err(false) => Ok(explain_denial(
+                self.explain_mode,
+                self.audit.as_deref(),
+                &Expr::Bool(false),
+                &self.environment,
+                id,
+            )),
+        }
+    }
+
+    /// Return an access control evaluating a policy expression
+    fn access_control(&self, expr: Expr) -> AbacAccessControl {
+        let mut access_control =
+            AbacAccessControl::new(self.repository.clone(), expr, self.environment.clone())
+                .with_explain_mode(self.explain_mode);
+        if let Some(groups) = &self.groups {
+            access_control = access_control.with_groups(groups.clone());
+        }
+        if let Some(audit) = &self.audit {
+            access_control = access_control.with_audit(audit.clone());
+        }
+        access_control
+    }
+
+    /// Load the policy expression for the resource and action.
+    ///
+    /// Return the decision directly if no evaluation is needed: if the policy is a constant,
+    /// or if there is no policy, in which case access is denied
+    async fn expression(&self) -> Result<core::result::Result<Expr, bool>> {
+        match self
             .policies
             .get_policy(&self.resource, &self.action)
             .await?
         {
-            if let Expr::Bool(b) = expr {
-                // If the policy is a constant there is no need to populate
-                // the environment or look for message metadata.
-                return Ok(b);
-            } else {
-                expr
+            // If the policy is a constant there is no need to populate
+            // the environment or look for message metadata.
+            Some(Expr::Bool(b)) => Ok(Err(b)),
+            Some(expr) => Ok(Ok(expr)),
+            None => {
+                log::debug! {
+                    resource = %self.resource,
+                    action   = %self.action,
+                    "no policy found; access denied"
+                }
+                Ok(Err(false))
             }
-        } else {
-            // If no policy exists for this resource and action access is denied:
-            log::debug! {
-                resource = %self.resource,
-                action   = %self.action,
-                "no policy found; access denied"
-            }
-            return Ok(false);
-        };
+        }
+    }
+}
 
-        AbacAccessControl::new(self.repository.clone(), expr, self.environment.clone())
-            .is_authorized(msg)
-            .await
+#[async_trait]
+impl IncomingAccessControl for PolicyAccessControl {
+    async fn is_authorized(&self, msg: &RelayMessage) -> Result<bool> {
+        match self.expression().await? {
+            Ok(expr) => self.access_control(expr).is_authorized(msg).await,
+            // a denial is explained, and possibly answered, by an access control denying everything
+            Err(false) => {
+                self.access_control(Expr::Bool(false))
+                    .is_authorized(msg)
+                    .await
+            }
+            Err(true) => Ok(true),
+        }
     }
 }
@@ -0,0 +1,141 @@
+//! Measure the latency of secure channel handshakes, the throughput of the encryption
+//! and decryption of messages and the overhead of the encryption of kafka record batches.
+//!
+//! Run with `cargo bench -p ockam_api --features benchmarks --bench secure_channel`.
+//!
+//! The benchmarked vaults are set with `OCKAM_BENCH_VAULTS`, a comma-separated list of
+//! `software` and `aws-kms`. Only the software vault is benchmarked by default.
+
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use criterion::{BenchmarkId, Criterion, Throughput};
+use ockam_api::benchmarks::{BenchmarkVault, SecureChannelBenchmark};
+use ockam_core::Result;
+use ockam_node::tokio::runtime::Handle;
+use ockam_node::{Context, NodeBuilder};
+
+const PAYLOAD_SIZES: [usize; 3] = [64, 1024, 16 * 1024];
+const KAFKA_BATCH_SIZE: usize = 100;
+const KAFKA_RECORD_SIZE: usize = 512;
+
+fn main() {
+    let (ctx, mut executor) = NodeBuilder::new().no_logging().build();
+    executor
+        .execute(async move {
+            let vaults = vaults()?;
+            let mut benchmarks = vec![];
+            for vault in vaults {
+                let benchmark = SecureChannelBenchmark::create(&ctx, vault.create().await?).await?;
+                benchmarks.push((vault, benchmark));
+            }
+
+            // criterion blocks the current thread, the futures are run on the node runtime
+            let handle = Handle::current();
+            let (ctx, benchmarks) = ockam_node::tokio::task::spawn_blocking(move || {
+                let mut criterion = Criterion::default().configure_from_args();
+                for (vault, benchmark) in &benchmarks {
+                    bench(&mut criterion, &handle, &ctx, &vault.to_string(), benchmark);
+                }
+                criterion.final_summary();
+                (ctx, benchmarks)
+            })
+            .await
+            .unwrap();
+
+            for (_, benchmark) in &benchmarks {
+                benchmark.stop(&ctx).await?;
+            }
+            ctx.stop().await
+        })
+        .unwrap()
+        .unwrap();
+}
+
+fn vaults() -> Result<Vec<BenchmarkVault>> {
+    let vaults = std::env::var("OCKAM_BENCH_VAULTS").unwrap_or_else(|_| "software".to_string());
+    vaults
+        .split(',')
+        .map(|v| BenchmarkVault::from_str(v.trim()))
+        .collect()
+}
+
+fn bench(
+    criterion: &mut Criterion,
+    handle: &Handle,
+    ctx: &Context,
+    vault: &str,
+    benchmark: &SecureChannelBenchmark,
+) {
+    let mut group = criterion.benchmark_group(format!("handshake/{vault}"));
+    group.sample_size(20);
+    group.bench_function("latency", |b| {
+        b.iter_custom(|iterations| {
+            handle.block_on(async {
+                let mut elapsed = Duration::ZERO;
+                for _ in 0..iterations {
+                    elapsed += benchmark.handshake(ctx).await.unwrap();
+                }
+                elapsed
+            })
+        })
+    });
+    group.finish();
+
+    let mut group = criterion.benchmark_group(format!("encryption/{vault}"));
+    for size in PAYLOAD_SIZES {
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::new("encrypt", size), &size, |b, size| {
+            b.iter_custom(|iterations| {
+                handle.block_on(async {
+                    let start = Instant::now();
+                    for _ in 0..iterations {
+                        benchmark.encrypt(ctx, vec![0; *size]).await.unwrap();
+                    }
+                    start.elapsed()
+                })
+            })
+        });
+        // a message can only be decrypted once, so each iteration encrypts a new message
+        group.bench_with_input(
+            BenchmarkId::new("encrypt_decrypt", size),
+            &size,
+            |b, size| {
+                b.iter_custom(|iterations| {
+                    handle.block_on(async {
+                        let start = Instant::now();
+                        for _ in 0..iterations {
+                            let encrypted = benchmark.encrypt(ctx, vec![0; *size]).await.unwrap();
+                            benchmark.decrypt(ctx, encrypted).await.unwrap();
+                        }
+                        start.elapsed()
+                    })
+                })
+            },
+        );
+    }
+    group.finish();
+
+    let records = vec![vec![0; KAFKA_RECORD_SIZE]; KAFKA_BATCH_SIZE];
+    let overhead = handle
+        .block_on(benchmark.kafka_batch(ctx, &records))
+        .unwrap();
+    println!(
+        "kafka/{vault}: {} bytes added to each record of {KAFKA_RECORD_SIZE} bytes",
+        overhead.bytes_per_record()
+    );
+    let mut group = criterion.benchmark_group(format!("kafka/{vault}"));
+    group.throughput(Throughput::Elements(KAFKA_BATCH_SIZE as u64));
+    group.bench_function("encrypt_batch", |b| {
+        b.iter_custom(|iterations| {
+            handle.block_on(async {
+                let start = Instant::now();
+                for _ in 0..iterations {
+                    benchmark.kafka_batch(ctx, &records).await.unwrap();
+                }
+                start.elapsed()
+            })
+        })
+    });
+    group.finish();
+}
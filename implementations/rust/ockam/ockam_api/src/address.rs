@@ -1,4 +1,4 @@
-use std::net::{SocketAddr, TcpListener};
+use std::net::{Ipv6Addr, SocketAddr, TcpListener};
 use std::str::FromStr;
 
 use ockam_core::Result;
@@ -59,3 +59,19 @@ pub fn get_free_address() -> Result<SocketAddr, ApiError> {
         .map_err(ParseError::from)?;
     Ok(res)
 }
+
+/// Return true if a target is written `host:port`, IPv6 addresses being bracketed
+pub fn is_valid_target(target: &str) -> bool {
+    match target.rsplit_once(':') {
+        Some((host, port)) => is_valid_host(host) && port.parse::<u16>().is_ok(),
+        None => false,
+    }
+}
+
+/// A host is a DNS name, an IPv4 address or a bracketed IPv6 address
+pub fn is_valid_host(host: &str) -> bool {
+    match host.strip_prefix('[').and_then(|h| h.strip_suffix(']')) {
+        Some(ipv6) => ipv6.parse::<Ipv6Addr>().is_ok(),
+        None => !host.is_empty() && !host.contains([':', '[', ']']),
+    }
+}
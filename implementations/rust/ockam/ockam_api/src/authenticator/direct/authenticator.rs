@@ -11,11 +11,13 @@ use std::collections::HashMap;
 use tracing::trace;
 
 use crate::authenticator::direct::types::AddMember;
+use crate::authority_node::Leadership;
 
 pub struct DirectAuthenticator {
     trust_context: String,
     attributes_writer: Arc<dyn IdentityAttributesWriter>,
     attributes_reader: Arc<dyn IdentityAttributesReader>,
+    leadership: Leadership,
 }
 
 impl DirectAuthenticator {
@@ -28,9 +30,17 @@ impl DirectAuthenticator {
             trust_context,
             attributes_writer,
             attributes_reader,
+            leadership: Leadership::standalone(),
         })
     }
 
+    /// Only modify members when this node is the leader of the authority nodes
+    /// sharing the same members storage
+    pub fn with_leadership(mut self, leadership: Leadership) -> Self {
+        self.leadership = leadership;
+        self
+    }
+
     async fn add_member<'a>(
         &self,
         enroller: &Identifier,
@@ -79,7 +89,13 @@ impl Worker for DirectAuthenticator {
                 "request"
             }
             let path_segments = req.path_segments::<5>();
+            let is_write = matches!(req.method(), Some(Method::Post) | Some(Method::Delete));
             let res = match (req.method(), path_segments.as_slice()) {
+                _ if is_write && !self.leadership.is_leader() => Response::service_unavailable(
+                    &req,
+                    "this authority node is not the leader, members are modified by the leader",
+                )
+                .to_vec()?,
                 (Some(Method::Post), [""]) | (Some(Method::Post), ["members"]) => {
                     let add: AddMember = dec.decode()?;
                     self.add_member(&from, add.member(), add.attributes())
@@ -1,8 +1,10 @@
 mod acceptor;
 mod authenticator;
 mod issuer;
+mod storage;
 pub mod types;
 
 pub use acceptor::*;
 pub use authenticator::*;
 pub use issuer::*;
+pub use storage::*;
@@ -40,21 +40,18 @@ impl Worker for EnrollmentTokenAcceptor {
                 (Some(Method::Post), "/") | (Some(Method::Post), "/credential") => {
                     //TODO: move out of the worker handle_message implementation
                     let otc: OneTimeCode = dec.decode()?;
-                    let token = match self.0.tokens.write() {
-                        Ok(mut r) => {
-                            if let Some(tkn) = r.pop(otc.code()) {
-                                if tkn.time.elapsed() > tkn.max_token_duration {
-                                    Err(Response::forbidden(&req, "expired token"))
-                                } else {
-                                    Ok(tkn)
-                                }
+                    let token = match self.0.tokens.take(otc.code()).await {
+                        Ok(Some(tkn)) => {
+                            if tkn.is_expired(now()?) {
+                                Err(Response::forbidden(&req, "expired token"))
                             } else {
-                                Err(Response::forbidden(&req, "unknown token"))
+                                Ok(tkn)
                             }
                         }
-                        Err(_) => Err(Response::internal_error(
+                        Ok(None) => Err(Response::forbidden(&req, "unknown token")),
+                        Err(err) => Err(Response::internal_error(
                             &req,
-                            "Failed to get read lock on tokens table",
+                            &format!("Failed to retrieve the token: {err}"),
                         )),
                     };
                     match token {
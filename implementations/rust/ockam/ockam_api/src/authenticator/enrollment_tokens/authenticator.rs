@@ -1,31 +1,47 @@
-use lru::LruCache;
 use ockam::identity::IdentityAttributesWriter;
-use ockam_core::compat::sync::{Arc, RwLock};
-use std::num::NonZeroUsize;
+use ockam_core::compat::sync::Arc;
 use std::time::Duration;
 
-use crate::authenticator::enrollment_tokens::types::Token;
-use crate::authenticator::enrollment_tokens::{EnrollmentTokenAcceptor, EnrollmentTokenIssuer};
+use crate::authenticator::enrollment_tokens::{
+    EnrollmentTokenAcceptor, EnrollmentTokenIssuer, EnrollmentTokensStorage,
+    InMemoryEnrollmentTokens,
+};
+use crate::authority_node::Leadership;
 
-pub(super) const MAX_TOKEN_DURATION: Duration = Duration::from_secs(600);
+pub(crate) const MAX_TOKEN_DURATION: Duration = Duration::from_secs(600);
 
 #[derive(Clone)]
 pub struct EnrollmentTokenAuthenticator {
     pub(super) trust_context: String,
-    // TODO: Replace with something sane and standard + implement expiration
-    pub(super) tokens: Arc<RwLock<LruCache<[u8; 32], Token>>>,
+    pub(super) tokens: Arc<dyn EnrollmentTokensStorage>,
+    pub(super) leadership: Leadership,
 }
 
 impl EnrollmentTokenAuthenticator {
     pub fn new_worker_pair(
         trust_context: String,
         attributes_writer: Arc<dyn IdentityAttributesWriter>,
+    ) -> (EnrollmentTokenIssuer, EnrollmentTokenAcceptor) {
+        Self::new_worker_pair_with_storage(
+            trust_context,
+            attributes_writer,
+            Arc::new(InMemoryEnrollmentTokens::default()),
+            Leadership::standalone(),
+        )
+    }
+
+    /// Create an issuer and an acceptor using a tokens storage which might be shared with
+    /// other authority nodes. In that case tokens are only issued by the leader node
+    pub fn new_worker_pair_with_storage(
+        trust_context: String,
+        attributes_writer: Arc<dyn IdentityAttributesWriter>,
+        tokens: Arc<dyn EnrollmentTokensStorage>,
+        leadership: Leadership,
     ) -> (EnrollmentTokenIssuer, EnrollmentTokenAcceptor) {
         let base = Self {
             trust_context,
-            tokens: Arc::new(RwLock::new(LruCache::new(
-                NonZeroUsize::new(128).expect("0 < 128"),
-            ))),
+            tokens,
+            leadership,
         };
         (
             EnrollmentTokenIssuer(base.clone()),
@@ -1,14 +1,14 @@
 use miette::IntoDiagnostic;
 use minicbor::Decoder;
+use ockam::identity::utils::now;
 use ockam::identity::OneTimeCode;
 use ockam::identity::{secure_channel_required, AttributesEntry};
 use ockam::identity::{Identifier, IdentitySecureChannelLocalInfo};
 use ockam_core::api::{Method, Request, RequestHeader, Response};
-use ockam_core::errcode::{Kind, Origin};
 use ockam_core::{async_trait, Result, Routed, Worker};
 use ockam_node::Context;
 use std::collections::HashMap;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 use tracing::trace;
 
 use crate::authenticator::direct::types::{AddMember, CreateToken};
@@ -32,23 +32,11 @@ impl EnrollmentTokenIssuer {
         let tkn = Token {
             attrs,
             generated_by: enroller.clone(),
-            time: Instant::now(),
+            created_at: now()?,
             max_token_duration,
         };
-        self.0
-            .tokens
-            .write()
-            .map(|mut r| {
-                r.put(*otc.code(), tkn);
-                otc
-            })
-            .map_err(|_| {
-                ockam_core::Error::new(
-                    Origin::Other,
-                    Kind::Internal,
-                    "failed to get read lock on tokens table",
-                )
-            })
+        self.0.tokens.put(*otc.code(), tkn).await?;
+        Ok(otc)
     }
 }
 
@@ -72,6 +60,15 @@ impl Worker for EnrollmentTokenIssuer {
                 "request"
             }
             let res = match (req.method(), req.path()) {
+                (Some(Method::Post), "/") | (Some(Method::Post), "/tokens")
+                    if !self.0.leadership.is_leader() =>
+                {
+                    Response::service_unavailable(
+                        &req,
+                        "this authority node is not the leader, tokens are issued by the leader",
+                    )
+                    .to_vec()?
+                }
                 (Some(Method::Post), "/") | (Some(Method::Post), "/tokens") => {
                     let att: CreateToken = dec.decode()?;
                     let duration = att.token_duration();
@@ -0,0 +1,234 @@
+use lru::LruCache;
+use ockam_core::compat::sync::RwLock;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{async_trait, Result};
+use std::num::NonZeroUsize;
+
+use crate::authenticator::enrollment_tokens::types::Token;
+
+/// Storage for the enrollment tokens issued by an authority
+#[async_trait]
+pub trait EnrollmentTokensStorage: Send + Sync + 'static {
+    /// Store a token until it is presented or expires
+    async fn put(&self, one_time_code: [u8; 32], token: Token) -> Result<()>;
+
+    /// Remove a token and return it.
+    /// A token must only be returned once, even if the storage is shared by several authority nodes
+    async fn take(&self, one_time_code: &[u8; 32]) -> Result<Option<Token>>;
+}
+
+/// Tokens kept in memory. Only the most recent tokens are kept
+pub struct InMemoryEnrollmentTokens {
+    tokens: RwLock<LruCache<[u8; 32], Token>>,
+}
+
+impl Default for InMemoryEnrollmentTokens {
+    fn default() -> Self {
+        Self {
+            tokens: RwLock::new(LruCache::new(NonZeroUsize::new(128).expect("0 < 128"))),
+        }
+    }
+}
+
+#[async_trait]
+impl EnrollmentTokensStorage for InMemoryEnrollmentTokens {
+    async fn put(&self, one_time_code: [u8; 32], token: Token) -> Result<()> {
+        self.tokens
+            .write()
+            .map(|mut r| {
+                r.put(one_time_code, token);
+            })
+            .map_err(|_| lock_error())
+    }
+
+    async fn take(&self, one_time_code: &[u8; 32]) -> Result<Option<Token>> {
+        self.tokens
+            .write()
+            .map(|mut r| r.pop(one_time_code))
+            .map_err(|_| lock_error())
+    }
+}
+
+fn lock_error() -> ockam_core::Error {
+    ockam_core::Error::new(
+        Origin::Other,
+        Kind::Internal,
+        "failed to get a lock on the tokens table",
+    )
+}
+
+#[cfg(feature = "postgres")]
+mod postgres {
+    use super::*;
+    use ockam::identity::storage::PostgresClient;
+    use ockam::identity::utils::now;
+    use ockam::identity::{Identifier, TimestampInSeconds};
+    use std::collections::HashMap;
+    use std::str::FromStr;
+    use std::time::Duration;
+    use tokio_postgres::Row;
+
+    /// Tokens stored in a Postgres database which can be shared by several authority nodes.
+    ///
+    /// Taking a token is a single `DELETE ... RETURNING` statement so that a one-time code
+    /// can only be consumed once, whichever authority node receives it
+    pub struct PostgresEnrollmentTokens {
+        client: PostgresClient,
+    }
+
+    impl PostgresEnrollmentTokens {
+        const CREATE_TOKEN_TABLE_SQL: &'static str = "CREATE TABLE IF NOT EXISTS enrollment_token (
+            one_time_code BYTEA PRIMARY KEY,
+            attributes TEXT NOT NULL,
+            generated_by TEXT NOT NULL,
+            created_at BIGINT NOT NULL,
+            max_duration BIGINT NOT NULL
+        );";
+        // used to delete the expired tokens, without scanning the whole table
+        const CREATE_TOKEN_EXPIRATION_INDEX_SQL: &'static str =
+            "CREATE INDEX IF NOT EXISTS idx_enrollment_token_expiration
+            ON enrollment_token ((created_at + max_duration));";
+
+        /// Create the tokens table and its indexes if necessary
+        pub async fn new(client: PostgresClient) -> Result<Self> {
+            client
+                .run(|client| async move {
+                    client
+                        .batch_execute(
+                            &(Self::CREATE_TOKEN_TABLE_SQL.to_owned()
+                                + Self::CREATE_TOKEN_EXPIRATION_INDEX_SQL),
+                        )
+                        .await
+                })
+                .await?;
+            Ok(Self { client })
+        }
+
+        fn token_from_row(row: Row) -> Result<Token> {
+            let attributes: String = row.get(0);
+            let generated_by: String = row.get(1);
+            let created_at: i64 = row.get(2);
+            let max_duration: i64 = row.get(3);
+            Ok(Token {
+                attrs: serde_json::from_str::<HashMap<String, String>>(&attributes)
+                    .map_err(|e| ockam_core::Error::new(Origin::Api, Kind::Serialization, e))?,
+                generated_by: Identifier::from_str(&generated_by)?,
+                created_at: TimestampInSeconds(created_at as u64),
+                max_token_duration: Duration::from_secs(max_duration as u64),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl EnrollmentTokensStorage for PostgresEnrollmentTokens {
+        async fn put(&self, one_time_code: [u8; 32], token: Token) -> Result<()> {
+            // remove the tokens which expired without being used
+            let now = *now()? as i64;
+            self.client
+                .run(|client| async move {
+                    client
+                        .execute(
+                            "DELETE FROM enrollment_token WHERE created_at + max_duration < $1",
+                            &[&now],
+                        )
+                        .await
+                })
+                .await?;
+
+            let attributes = &serde_json::to_string(&token.attrs)
+                .map_err(|e| ockam_core::Error::new(Origin::Api, Kind::Serialization, e))?;
+            let generated_by = &token.generated_by.to_string();
+            let token = &token;
+            self.client
+                .run(|client| async move {
+                    client
+                        .execute(
+                            "INSERT INTO enrollment_token (one_time_code, attributes, generated_by, created_at, max_duration)
+                             VALUES ($1, $2, $3, $4, $5)",
+                            &[
+                                &one_time_code.as_slice(),
+                                attributes,
+                                generated_by,
+                                &(*token.created_at as i64),
+                                &(token.max_token_duration.as_secs() as i64),
+                            ],
+                        )
+                        .await
+                })
+                .await?;
+            Ok(())
+        }
+
+        async fn take(&self, one_time_code: &[u8; 32]) -> Result<Option<Token>> {
+            let row = self
+                .client
+                .run(|client| async move {
+                    client
+                        .query_opt(
+                            "DELETE FROM enrollment_token WHERE one_time_code = $1
+                             RETURNING attributes, generated_by, created_at, max_duration",
+                            &[&one_time_code.as_slice()],
+                        )
+                        .await
+                })
+                .await?;
+            row.map(Self::token_from_row).transpose()
+        }
+    }
+}
+
+#[cfg(feature = "postgres")]
+pub use postgres::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam::identity::utils::now;
+    use ockam::identity::Identifier;
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_in_memory_token_is_taken_once() -> Result<()> {
+        let storage = InMemoryEnrollmentTokens::default();
+        let token = Token {
+            attrs: [("key".to_string(), "value".to_string())].into(),
+            generated_by: Identifier::from_str("I0123456789abcdef0123456789abcdef01234567")?,
+            created_at: now()?,
+            max_token_duration: Duration::from_secs(60),
+        };
+        storage.put([1; 32], token.clone()).await?;
+
+        assert_eq!(storage.take(&[2; 32]).await?, None);
+        assert_eq!(storage.take(&[1; 32]).await?, Some(token));
+        assert_eq!(storage.take(&[1; 32]).await?, None);
+        Ok(())
+    }
+
+    /// This test runs against the Postgres database of the connection string given by
+    /// OCKAM_TEST_POSTGRES_CONNECTION, it is skipped when this variable is not set
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn test_postgres_token_is_taken_once() -> Result<()> {
+        use ockam::identity::storage::PostgresClient;
+
+        let Ok(connection_string) = std::env::var("OCKAM_TEST_POSTGRES_CONNECTION") else {
+            return Ok(());
+        };
+        let storage =
+            PostgresEnrollmentTokens::new(PostgresClient::connect(&connection_string).await?)
+                .await?;
+        let token = Token {
+            attrs: [("key".to_string(), "value".to_string())].into(),
+            generated_by: Identifier::from_str("I0123456789abcdef0123456789abcdef01234567")?,
+            created_at: now()?,
+            max_token_duration: Duration::from_secs(60),
+        };
+        let one_time_code: [u8; 32] = rand::random();
+        storage.put(one_time_code, token.clone()).await?;
+
+        assert_eq!(storage.take(&one_time_code).await?, Some(token));
+        assert_eq!(storage.take(&one_time_code).await?, None);
+        Ok(())
+    }
+}
@@ -1,10 +1,21 @@
-use ockam::identity::Identifier;
+use ockam::identity::{Identifier, TimestampInSeconds};
 use std::collections::HashMap;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
-pub(super) struct Token {
-    pub(super) attrs: HashMap<String, String>,
-    pub(super) generated_by: Identifier,
-    pub(super) time: Instant,
-    pub(super) max_token_duration: Duration,
+/// Enrollment token waiting to be presented by a new member
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub attrs: HashMap<String, String>,
+    pub generated_by: Identifier,
+    /// Creation time of the token. A wall-clock time is used so that the token
+    /// can be checked by another authority node sharing the same tokens storage
+    pub created_at: TimestampInSeconds,
+    pub max_token_duration: Duration,
+}
+
+impl Token {
+    /// Return true if the token cannot be used anymore at the given time
+    pub fn is_expired(&self, now: TimestampInSeconds) -> bool {
+        now.saturating_sub(*self.created_at) > self.max_token_duration.as_secs()
+    }
 }
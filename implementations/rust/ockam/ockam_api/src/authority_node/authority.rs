@@ -1,30 +1,47 @@
+use std::collections::HashMap;
 use std::path::Path;
+use std::time::Duration;
 
 use tracing::info;
 
-use ockam::identity::storage::LmdbStorage;
-use ockam::identity::Vault;
+use ockam::identity::storage::{LmdbStorage, Storage};
+use ockam::identity::utils::now;
 use ockam::identity::{
     CredentialsIssuer, Identifier, Identities, IdentitiesRepository, IdentitiesStorage,
     IdentityAttributesReader, IdentityAttributesWriter, SecureChannelListenerOptions,
     SecureChannels, TrustEveryonePolicy,
 };
+use ockam::identity::{OneTimeCode, Vault};
 use ockam_abac::expr::{and, eq, ident, str};
 use ockam_abac::{AbacAccessControl, Env};
 use ockam_core::compat::sync::Arc;
 use ockam_core::errcode::{Kind, Origin};
 use ockam_core::flow_control::FlowControlId;
-use ockam_core::{Error, Result, Worker};
+use ockam_core::{Address, AllowAll, AllowSourceAddress, Error, Result, Worker};
 use ockam_node::{Context, WorkerBuilder};
 use ockam_transport_tcp::{TcpListenerOptions, TcpTransport};
 
-use crate::authenticator::enrollment_tokens::EnrollmentTokenAuthenticator;
+use crate::authenticator::enrollment_tokens::types::Token;
+use crate::authenticator::enrollment_tokens::{
+    EnrollmentTokenAuthenticator, EnrollmentTokensStorage, InMemoryEnrollmentTokens,
+    MAX_TOKEN_DURATION,
+};
 use crate::authority_node::authority::EnrollerCheck::{AnyMember, EnrollerOnly};
-use crate::authority_node::Configuration;
+use crate::authority_node::{Configuration, FairQueue, Leadership};
 use crate::bootstrapped_identities_store::BootstrapedIdentityStore;
 use crate::echoer::Echoer;
 use crate::{actions, DefaultAddress};
 
+/// Environment variable setting the duration, in milliseconds, above which a statement
+/// executed on the Postgres database is logged as slow
+#[cfg(feature = "postgres")]
+const OCKAM_SQL_SLOW_QUERY_THRESHOLD_MS: &str = "OCKAM_SQL_SLOW_QUERY_THRESHOLD_MS";
+
+/// Environment variable enabling a JSON rendering of the members attributes, written next
+/// to their CBOR encoding in the Postgres database
+#[cfg(feature = "postgres")]
+const OCKAM_SQL_JSON_ATTRIBUTES: &str = "OCKAM_SQL_JSON_ATTRIBUTES";
+
 /// This struct represents an Authority, which is an
 /// Identity which other identities trust to authenticate attributes
 /// An Authority is able to start a few services
@@ -35,6 +52,16 @@ use crate::{actions, DefaultAddress};
 pub struct Authority {
     identifier: Identifier,
     secure_channels: Arc<SecureChannels>,
+    tokens: Arc<dyn EnrollmentTokensStorage>,
+    leadership: Leadership,
+}
+
+/// Storage used by an Authority.
+/// It can be shared with other authority nodes when it is backed by a Postgres database
+struct AuthorityStorage {
+    members: Arc<dyn Storage>,
+    tokens: Arc<dyn EnrollmentTokensStorage>,
+    leadership: Leadership,
 }
 
 /// Public functions to:
@@ -46,6 +73,11 @@ impl Authority {
         self.identifier.clone()
     }
 
+    /// Return the leadership of this node among the authority nodes sharing its storage
+    pub fn leadership(&self) -> Leadership {
+        self.leadership.clone()
+    }
+
     /// SecureChannels getter
     pub fn secure_channels(&self) -> Arc<SecureChannels> {
         self.secure_channels.clone()
@@ -57,7 +89,8 @@ impl Authority {
     pub async fn create(configuration: &Configuration) -> Result<Authority> {
         debug!(?configuration, "creating the authority");
         let vault = Self::create_secure_channels_vault(configuration).await?;
-        let repository = Self::create_identities_repository(configuration).await?;
+        let storage = Self::create_storage(configuration).await?;
+        let repository = Self::create_identities_repository(storage.members, configuration);
         let secure_channels = SecureChannels::builder()
             .with_vault(vault)
             .with_identities_repository(repository)
@@ -69,6 +102,8 @@ impl Authority {
         Ok(Authority {
             identifier,
             secure_channels,
+            tokens: storage.tokens,
+            leadership: storage.leadership,
         })
     }
 
@@ -123,13 +158,14 @@ impl Authority {
             self.attributes_writer(),
             self.attributes_reader(),
         )
-        .await?;
+        .await?
+        .with_leadership(self.leadership());
 
         let name = configuration.authenticator_name();
         ctx.flow_controls()
             .add_consumer(name.clone(), secure_channel_flow_control_id);
 
-        self.start(ctx, configuration, name.clone(), EnrollerOnly, direct)
+        self.start_with_fair_queue(ctx, configuration, name.clone(), EnrollerOnly, direct)
             .await?;
 
         info!("started a direct authenticator at '{name}'");
@@ -147,9 +183,11 @@ impl Authority {
             return Ok(());
         }
 
-        let (issuer, acceptor) = EnrollmentTokenAuthenticator::new_worker_pair(
+        let (issuer, acceptor) = EnrollmentTokenAuthenticator::new_worker_pair_with_storage(
             configuration.project_identifier(),
             self.attributes_writer(),
+            self.tokens.clone(),
+            self.leadership(),
         );
 
         // start an enrollment token issuer with an abac policy checking that
@@ -158,7 +196,7 @@ impl Authority {
         ctx.flow_controls()
             .add_consumer(issuer_address.clone(), secure_channel_flow_control_id);
 
-        self.start(
+        self.start_with_fair_queue(
             ctx,
             configuration,
             issuer_address.clone(),
@@ -175,7 +213,20 @@ impl Authority {
         ctx.flow_controls()
             .add_consumer(acceptor_address.clone(), secure_channel_flow_control_id);
 
-        ctx.start_worker(acceptor_address.clone(), acceptor).await?;
+        let acceptor_service_address = Address::random_tagged("EnrollmentTokenAcceptor");
+        WorkerBuilder::new(acceptor)
+            .with_address(acceptor_service_address.clone())
+            .with_incoming_access_control(AllowSourceAddress(acceptor_address.clone().into()))
+            .start(ctx)
+            .await?;
+        FairQueue::start(
+            ctx,
+            acceptor_address.clone().into(),
+            acceptor_service_address,
+            configuration.requests_limits(),
+            Arc::new(AllowAll),
+        )
+        .await?;
 
         info!("started an enrollment token issuer at '{issuer_address}'");
         info!("started an enrollment token acceptor at '{acceptor_address}'");
@@ -191,18 +242,22 @@ impl Authority {
         configuration: &Configuration,
     ) -> Result<()> {
         // create and start a credential issuer worker
-        let issuer = CredentialsIssuer::new(
+        let mut issuer = CredentialsIssuer::new(
             self.secure_channels.identities().repository(),
             self.secure_channels.identities().credentials(),
             &self.identifier,
             configuration.project_identifier(),
         );
+        if let Some(issuer_credential) = configuration.issuer_credential()? {
+            info!("issuing credentials as a sub-authority");
+            issuer = issuer.with_issuer_credential(issuer_credential);
+        }
 
         let address = DefaultAddress::CREDENTIAL_ISSUER.to_string();
         ctx.flow_controls()
             .add_consumer(address.clone(), secure_channel_flow_control_id);
 
-        self.start(ctx, configuration, address.clone(), AnyMember, issuer)
+        self.start_with_fair_queue(ctx, configuration, address.clone(), AnyMember, issuer)
             .await?;
 
         info!("started a credential issuer at '{address}'");
@@ -233,6 +288,32 @@ impl Authority {
         Ok(())
     }
 
+    /// Issue an enrollment token on behalf of the authority itself, for example to create
+    /// the first enrollment tickets of a project before any enroller is known.
+    /// The token is accepted by the enrollment token acceptor of this authority
+    pub async fn issue_enrollment_token(
+        &self,
+        attributes: HashMap<String, String>,
+        token_duration: Option<Duration>,
+    ) -> Result<OneTimeCode> {
+        if !self.leadership.is_leader() {
+            return Err(Error::new(
+                Origin::Node,
+                Kind::Conflict,
+                "this authority node is not the leader, tokens are issued by the leader",
+            ));
+        }
+        let one_time_code = OneTimeCode::new();
+        let token = Token {
+            attrs: attributes,
+            generated_by: self.identifier(),
+            created_at: now()?,
+            max_token_duration: token_duration.unwrap_or(MAX_TOKEN_DURATION),
+        };
+        self.tokens.put(*one_time_code.code(), token).await?;
+        Ok(one_time_code)
+    }
+
     /// Start an echo service
     pub async fn start_echo_service(
         &self,
@@ -278,15 +359,62 @@ impl Authority {
         Ok(vault)
     }
 
-    /// Create an authenticated storage backed by a Lmdb database
-    async fn create_identities_repository(
-        configuration: &Configuration,
-    ) -> Result<Arc<dyn IdentitiesRepository>> {
+    /// Create the storage for members and enrollment tokens.
+    /// Members are stored in a Lmdb database and tokens are kept in memory, unless a Postgres
+    /// database is configured
+    async fn create_storage(configuration: &Configuration) -> Result<AuthorityStorage> {
+        if let Some(connection) = &configuration.postgres_connection {
+            return Self::create_postgres_storage(connection).await;
+        }
         let storage_path = &configuration.storage_path;
         Self::create_ockam_directory_if_necessary(storage_path)?;
-        let storage = Arc::new(LmdbStorage::new(&storage_path).await?);
+        Ok(AuthorityStorage {
+            members: Arc::new(LmdbStorage::new(&storage_path).await?),
+            tokens: Arc::new(InMemoryEnrollmentTokens::default()),
+            leadership: Leadership::standalone(),
+        })
+    }
+
+    /// Create a storage shared with the other authority nodes connected to the same database
+    #[cfg(feature = "postgres")]
+    async fn create_postgres_storage(connection: &str) -> Result<AuthorityStorage> {
+        use crate::authenticator::enrollment_tokens::PostgresEnrollmentTokens;
+        use ockam::identity::storage::{AttributesJsonRenderer, PostgresStorage};
+        use ockam_core::env::{get_env, get_env_with_default};
+        use std::time::Duration;
+
+        let mut members = PostgresStorage::new(connection).await?;
+        if let Some(threshold) = get_env::<u64>(OCKAM_SQL_SLOW_QUERY_THRESHOLD_MS)? {
+            members = members.with_slow_query_threshold(Duration::from_millis(threshold));
+        }
+        if get_env_with_default(OCKAM_SQL_JSON_ATTRIBUTES, false)? {
+            members = members.with_json_renderer(Arc::new(AttributesJsonRenderer));
+        }
+        let tokens = PostgresEnrollmentTokens::new(members.client()).await?;
+        info!("using a Postgres database to store members and enrollment tokens");
+        Ok(AuthorityStorage {
+            members: Arc::new(members),
+            tokens: Arc::new(tokens),
+            leadership: Leadership::start_postgres_election(connection.to_string()),
+        })
+    }
+
+    #[cfg(not(feature = "postgres"))]
+    async fn create_postgres_storage(_connection: &str) -> Result<AuthorityStorage> {
+        Err(Error::new(
+            Origin::Node,
+            Kind::Unsupported,
+            "this authority node was built without Postgres support",
+        ))
+    }
+
+    /// Create an authenticated repository for the members attributes
+    fn create_identities_repository(
+        storage: Arc<dyn Storage>,
+        configuration: &Configuration,
+    ) -> Arc<dyn IdentitiesRepository> {
         let repository = Arc::new(IdentitiesStorage::new(storage));
-        Ok(Self::bootstrap_repository(repository, configuration))
+        Self::bootstrap_repository(repository, configuration)
     }
 
     /// Create a directory to save storage files if they haven't been  created before
@@ -312,10 +440,10 @@ impl Authority {
         ))
     }
 
-    /// Start a worker at a given address
-    /// The configuration is used to create an Abac incoming policy checking that
-    /// the sender can indeed call the authority services
-    async fn start<W>(
+    /// Start a worker behind a fair queue at a given address.
+    /// The fair queue checks the Abac incoming policy and limits the number of requests
+    /// that each identity can have in flight against the worker
+    async fn start_with_fair_queue<W>(
         &self,
         ctx: &Context,
         configuration: &Configuration,
@@ -327,11 +455,20 @@ impl Authority {
         W: Worker<Context = Context>,
     {
         let abac = self.create_abac_policy(configuration, address.clone(), enroller_check);
+        let service_address = Address::random_tagged("FairQueue.service");
         WorkerBuilder::new(worker)
-            .with_address(address)
-            .with_incoming_access_control_arc(abac)
+            .with_address(service_address.clone())
+            .with_incoming_access_control(AllowSourceAddress(address.clone().into()))
             .start(ctx)
-            .await
+            .await?;
+        FairQueue::start(
+            ctx,
+            address.into(),
+            service_address,
+            configuration.requests_limits(),
+            abac,
+        )
+        .await
     }
 
     /// Return an Abac incoming policy checking that for the authority services
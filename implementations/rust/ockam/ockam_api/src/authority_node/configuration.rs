@@ -1,11 +1,15 @@
+use crate::authority_node::RequestsLimits;
 use crate::bootstrapped_identities_store::PreTrustedIdentities;
 use crate::DefaultAddress;
 
+use ockam::identity::models::CredentialAndPurposeKey;
 use ockam::identity::utils::now;
 use ockam::identity::{AttributesEntry, Identifier, TRUST_CONTEXT_ID};
 use ockam_core::compat::collections::HashMap;
 use ockam_core::compat::fmt;
 use ockam_core::compat::fmt::{Display, Formatter};
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Error, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::path::PathBuf;
@@ -22,6 +26,13 @@ pub struct Configuration {
     /// path where secrets should be persisted
     pub vault_path: PathBuf,
 
+    /// Connection string to a Postgres database shared by several authority nodes of the project,
+    /// for example "host=localhost user=ockam dbname=authority".
+    /// When set, members and enrollment tokens are stored in that database instead of
+    /// `storage_path`, and members are only modified by the leader node
+    #[serde(default)]
+    pub postgres_connection: Option<String>,
+
     /// Project identifier on the Orchestrator node
     pub project_identifier: String,
 
@@ -47,6 +58,24 @@ pub struct Configuration {
 
     /// optional configuration for the okta service
     pub okta: Option<OktaConfiguration>,
+
+    /// Hex-encoded credential issued to this authority by another authority, delegating the
+    /// right to attest some attributes. When set, this authority acts as a sub-authority
+    /// and attaches that credential to all the credentials it issues
+    #[serde(default)]
+    pub issuer_credential: Option<String>,
+
+    /// Maximum number of requests of the same identity handled at the same time by the
+    /// credential issuer and the enrollment services.
+    /// The default is DEFAULT_MAX_IN_FLIGHT_REQUESTS_PER_IDENTITY
+    #[serde(default)]
+    pub max_in_flight_requests_per_identity: Option<usize>,
+
+    /// Maximum number of requests of the same identity waiting to be handled by the
+    /// credential issuer and the enrollment services. Further requests are rejected.
+    /// The default is DEFAULT_MAX_QUEUED_REQUESTS_PER_IDENTITY
+    #[serde(default)]
+    pub max_queued_requests_per_identity: Option<usize>,
 }
 
 /// Local and private functions for the authority configuration
@@ -79,6 +108,31 @@ impl Configuration {
             .clone()
             .unwrap_or(DefaultAddress::DIRECT_AUTHENTICATOR.to_string())
     }
+
+    /// Return the limits applied to the requests of each identity
+    pub(crate) fn requests_limits(&self) -> RequestsLimits {
+        let default = RequestsLimits::default();
+        RequestsLimits {
+            max_in_flight: self
+                .max_in_flight_requests_per_identity
+                .unwrap_or(default.max_in_flight),
+            max_queued: self
+                .max_queued_requests_per_identity
+                .unwrap_or(default.max_queued),
+        }
+    }
+
+    /// Return the decoded issuer credential if this authority is a sub-authority
+    pub(crate) fn issuer_credential(&self) -> Result<Option<CredentialAndPurposeKey>> {
+        match &self.issuer_credential {
+            Some(encoded) => {
+                let bytes =
+                    hex::decode(encoded).map_err(|e| Error::new(Origin::Node, Kind::Invalid, e))?;
+                Ok(Some(minicbor::decode(&bytes)?))
+            }
+            None => Ok(None),
+        }
+    }
 }
 
 /// Configuration for the Okta service
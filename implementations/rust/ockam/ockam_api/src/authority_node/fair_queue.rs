@@ -0,0 +1,406 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use minicbor::Decoder;
+use tracing::{debug, warn};
+
+use ockam::identity::{Identifier, IdentitySecureChannelLocalInfo};
+use ockam_core::api::{Id, RequestHeader, Response};
+use ockam_core::compat::sync::Arc;
+use ockam_core::{
+    Address, AllowAll, AllowSourceAddress, IncomingAccessControl, LocalMessage, Mailbox, Mailboxes,
+    Result, Route, Routed, Worker,
+};
+use ockam_node::{Context, WorkerBuilder};
+
+/// Default number of requests of an identity handled at the same time by a service
+pub const DEFAULT_MAX_IN_FLIGHT_REQUESTS_PER_IDENTITY: usize = 4;
+
+/// Default number of requests of an identity waiting to be handled by a service.
+/// Further requests are rejected until some of them are handled
+pub const DEFAULT_MAX_QUEUED_REQUESTS_PER_IDENTITY: usize = 100;
+
+/// Time after which a request without a response doesn't count anymore in the requests
+/// handled for its identity
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Limits applied to the requests of each identity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestsLimits {
+    pub max_in_flight: usize,
+    pub max_queued: usize,
+}
+
+impl Default for RequestsLimits {
+    fn default() -> Self {
+        Self {
+            max_in_flight: DEFAULT_MAX_IN_FLIGHT_REQUESTS_PER_IDENTITY,
+            max_queued: DEFAULT_MAX_QUEUED_REQUESTS_PER_IDENTITY,
+        }
+    }
+}
+
+/// Outcome of a request added to the queue of its identity
+#[derive(Debug, PartialEq, Eq)]
+enum Admission<T> {
+    /// The request can be handled now
+    Dispatch(T),
+    /// The request waits for the previous requests of the same identity to be handled
+    Queued,
+    /// The queue of the identity is full
+    Rejected(T),
+}
+
+/// Requests of one identity
+#[derive(Debug)]
+struct IdentityQueue<T> {
+    in_flight: usize,
+    waiting: VecDeque<T>,
+}
+
+/// Per-identity queues of requests.
+///
+/// Each identity can only have a limited number of requests handled at the same time,
+/// its other requests wait in its own queue. Since the requests of every identity are
+/// dispatched as soon as one of its previous requests is handled, an identity sending many
+/// requests only delays its own requests
+#[derive(Debug)]
+struct IdentityQueues<T> {
+    limits: RequestsLimits,
+    queues: BTreeMap<Identifier, IdentityQueue<T>>,
+}
+
+impl<T> IdentityQueues<T> {
+    fn new(limits: RequestsLimits) -> Self {
+        Self {
+            limits,
+            queues: BTreeMap::new(),
+        }
+    }
+
+    /// Add a request of an identity
+    fn push(&mut self, identifier: &Identifier, request: T) -> Admission<T> {
+        let queue = self
+            .queues
+            .entry(identifier.clone())
+            .or_insert_with(|| IdentityQueue {
+                in_flight: 0,
+                waiting: VecDeque::new(),
+            });
+        if queue.in_flight < self.limits.max_in_flight.max(1) {
+            queue.in_flight += 1;
+            Admission::Dispatch(request)
+        } else if queue.waiting.len() < self.limits.max_queued {
+            queue.waiting.push_back(request);
+            Admission::Queued
+        } else {
+            Admission::Rejected(request)
+        }
+    }
+
+    /// Mark a request of an identity as handled and return its next request, if any
+    fn complete(&mut self, identifier: &Identifier) -> Option<T> {
+        let queue = self.queues.get_mut(identifier)?;
+        let next = queue.waiting.pop_front();
+        if next.is_none() {
+            queue.in_flight = queue.in_flight.saturating_sub(1);
+            if queue.in_flight == 0 {
+                self.queues.remove(identifier);
+            }
+        }
+        next
+    }
+}
+
+/// Request forwarded to the service, waiting for its response
+struct PendingRequest {
+    identifier: Identifier,
+    sent_at: Instant,
+}
+
+/// This worker sits in front of an authority service and limits the number of requests
+/// each authenticated identity can have in flight against that service, so that one
+/// client sending many requests doesn't starve the others.
+///
+/// The requests are received on the service address and forwarded to the service worker
+/// with a return route going through this worker. The responses are then used to dispatch
+/// the next waiting requests
+pub struct FairQueue {
+    service_address: Address,
+    replies_address: Address,
+    queues: IdentityQueues<(Id, LocalMessage)>,
+    pending: BTreeMap<(Id, Route), PendingRequest>,
+}
+
+impl FairQueue {
+    /// Start a fair queue at `address` for a service worker started at `service_address`.
+    /// The access control is checked on the requests received by the fair queue
+    pub async fn start(
+        ctx: &Context,
+        address: Address,
+        service_address: Address,
+        limits: RequestsLimits,
+        incoming_access_control: Arc<dyn IncomingAccessControl>,
+    ) -> Result<()> {
+        let replies_address = Address::random_tagged("FairQueue.replies");
+        let mailboxes = Mailboxes::new(
+            Mailbox::new(address, incoming_access_control, Arc::new(AllowAll)),
+            vec![Mailbox::new(
+                replies_address.clone(),
+                Arc::new(AllowSourceAddress(service_address.clone())),
+                Arc::new(AllowAll),
+            )],
+        );
+        let worker = FairQueue {
+            service_address,
+            replies_address,
+            queues: IdentityQueues::new(limits),
+            pending: BTreeMap::new(),
+        };
+        WorkerBuilder::new(worker)
+            .with_mailboxes(mailboxes)
+            .start(ctx)
+            .await
+    }
+
+    async fn handle_request(&mut self, ctx: &Context, msg: Routed<Vec<u8>>) -> Result<()> {
+        let identifier = match IdentitySecureChannelLocalInfo::find_info(msg.local_message()) {
+            Ok(info) => info.their_identity_id(),
+            Err(_) => {
+                warn!("dropping a request which was not received via a secure channel");
+                return Ok(());
+            }
+        };
+        let req: RequestHeader = match Decoder::new(msg.as_body()).decode() {
+            Ok(req) => req,
+            // the service replies to invalid requests, they are not counted
+            Err(_) => return self.forward_request(ctx, msg.into_local_message()).await,
+        };
+        let return_route = msg.return_route();
+        match self
+            .queues
+            .push(&identifier, (req.id(), msg.into_local_message()))
+        {
+            Admission::Dispatch((id, request)) => {
+                self.track(&identifier, id, return_route);
+                self.forward_request(ctx, request).await
+            }
+            Admission::Queued => {
+                debug!(%identifier, id = %req.id(), "queueing a request");
+                Ok(())
+            }
+            Admission::Rejected(_) => {
+                warn!(%identifier, id = %req.id(), "too many requests, rejecting a request");
+                let response = Response::service_unavailable(
+                    &req,
+                    "too many requests are pending for this identity, retry later",
+                )
+                .to_vec()?;
+                ctx.send(return_route, response).await
+            }
+        }
+    }
+
+    async fn handle_response(&mut self, ctx: &Context, msg: Routed<Vec<u8>>) -> Result<()> {
+        let re = Response::parse_response_header(msg.as_body()).map(|(header, _)| header.re());
+        let mut response = msg.into_local_message();
+        response.transport_mut().onward_route.step()?;
+        if let Ok(re) = re {
+            let key = (re, response.transport().onward_route.clone());
+            if let Some(pending) = self.pending.remove(&key) {
+                self.dispatch_next(ctx, &pending.identifier).await?;
+            }
+        }
+        ctx.forward(response).await
+    }
+
+    /// Forward the next waiting request of an identity once one of its requests is handled
+    async fn dispatch_next(&mut self, ctx: &Context, identifier: &Identifier) -> Result<()> {
+        if let Some((id, request)) = self.queues.complete(identifier) {
+            self.track(identifier, id, request.transport().return_route.clone());
+            self.forward_request(ctx, request).await?;
+        }
+        Ok(())
+    }
+
+    /// Send a request to the service, with a return route going through this worker
+    async fn forward_request(&self, ctx: &Context, mut request: LocalMessage) -> Result<()> {
+        let transport = request.transport_mut();
+        transport.onward_route.step()?;
+        transport
+            .onward_route
+            .modify()
+            .prepend(self.service_address.clone());
+        transport
+            .return_route
+            .modify()
+            .prepend(self.replies_address.clone());
+        ctx.forward(request).await
+    }
+
+    fn track(&mut self, identifier: &Identifier, id: Id, return_route: Route) {
+        self.pending.insert(
+            (id, return_route),
+            PendingRequest {
+                identifier: identifier.clone(),
+                sent_at: Instant::now(),
+            },
+        );
+    }
+
+    /// Stop waiting for the responses of requests which were never answered
+    async fn expire_pending_requests(&mut self, ctx: &Context) -> Result<()> {
+        let expired: Vec<(Id, Route)> = self
+            .pending
+            .iter()
+            .filter(|(_, p)| p.sent_at.elapsed() >= REQUEST_TIMEOUT)
+            .map(|(k, _)| k.clone())
+            .collect();
+        for key in expired {
+            if let Some(pending) = self.pending.remove(&key) {
+                self.dispatch_next(ctx, &pending.identifier).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[ockam_core::worker]
+impl Worker for FairQueue {
+    type Context = Context;
+    type Message = Vec<u8>;
+
+    async fn handle_message(
+        &mut self,
+        ctx: &mut Context,
+        msg: Routed<Self::Message>,
+    ) -> Result<()> {
+        self.expire_pending_requests(ctx).await?;
+        if msg.msg_addr() == self.replies_address {
+            self.handle_response(ctx, msg).await
+        } else {
+            self.handle_request(ctx, msg).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam_core::api::{Request, Status};
+    use ockam_core::{route, Encodable, TransportMessage};
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_requests_are_queued_per_identity() -> Result<()> {
+        let noisy = Identifier::try_from("Ie86be15e83d1c93e24dd1967010b01b6df491b45")?;
+        let quiet = Identifier::try_from("I6c20e814b56579306f55c64e8747e6c1b4a53d9a")?;
+        let mut queues = IdentityQueues::new(RequestsLimits {
+            max_in_flight: 2,
+            max_queued: 1,
+        });
+
+        assert_eq!(queues.push(&noisy, 1), Admission::Dispatch(1));
+        assert_eq!(queues.push(&noisy, 2), Admission::Dispatch(2));
+        assert_eq!(queues.push(&noisy, 3), Admission::Queued);
+        assert_eq!(queues.push(&noisy, 4), Admission::Rejected(4));
+
+        // the requests of another identity are not delayed
+        assert_eq!(queues.push(&quiet, 10), Admission::Dispatch(10));
+
+        // the waiting request is dispatched when a request is handled
+        assert_eq!(queues.complete(&noisy), Some(3));
+        assert_eq!(queues.push(&noisy, 5), Admission::Queued);
+        assert_eq!(queues.complete(&noisy), Some(5));
+        assert_eq!(queues.complete(&noisy), None);
+        assert_eq!(queues.complete(&noisy), None);
+        assert_eq!(queues.push(&noisy, 6), Admission::Dispatch(6));
+
+        assert_eq!(queues.complete(&quiet), None);
+        assert!(!queues.queues.contains_key(&quiet));
+        Ok(())
+    }
+
+    /// Service holding its requests until it is asked to answer the oldest one
+    struct HoldingService {
+        held: Arc<Mutex<Vec<(RequestHeader, Route)>>>,
+    }
+
+    #[ockam_core::worker]
+    impl Worker for HoldingService {
+        type Context = Context;
+        type Message = Vec<u8>;
+
+        async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<Vec<u8>>) -> Result<()> {
+            let req: RequestHeader = Decoder::new(msg.as_body()).decode()?;
+            if req.path() == "/release" {
+                let (held, return_route) = self.held.lock().unwrap().remove(0);
+                ctx.send(return_route, Response::ok(&held).to_vec()?).await
+            } else {
+                self.held.lock().unwrap().push((req, msg.return_route()));
+                Ok(())
+            }
+        }
+    }
+
+    async fn send_request(ctx: &Context, identifier: &Identifier, path: &str) -> Result<()> {
+        let request = Request::post(path).to_vec()?;
+        ctx.forward(LocalMessage::new(
+            TransportMessage::v1(route!["queue"], route![ctx.address()], request.encode()?),
+            IdentitySecureChannelLocalInfo::mark(vec![], identifier.clone())?,
+        ))
+        .await
+    }
+
+    fn held_paths(held: &Mutex<Vec<(RequestHeader, Route)>>) -> Vec<String> {
+        held.lock()
+            .unwrap()
+            .iter()
+            .map(|(req, _)| req.path().to_string())
+            .collect()
+    }
+
+    #[ockam_macros::test]
+    async fn test_fair_queue_worker(ctx: &mut Context) -> Result<()> {
+        let noisy = Identifier::try_from("Ie86be15e83d1c93e24dd1967010b01b6df491b45")?;
+        let quiet = Identifier::try_from("I6c20e814b56579306f55c64e8747e6c1b4a53d9a")?;
+        let held = Arc::new(Mutex::new(vec![]));
+        ctx.start_worker("service", HoldingService { held: held.clone() })
+            .await?;
+        FairQueue::start(
+            ctx,
+            "queue".into(),
+            "service".into(),
+            RequestsLimits {
+                max_in_flight: 1,
+                max_queued: 1,
+            },
+            Arc::new(AllowAll),
+        )
+        .await?;
+
+        // the first request is handled, the second one waits and the third one is rejected
+        for path in ["/noisy/1", "/noisy/2", "/noisy/3"] {
+            send_request(ctx, &noisy, path).await?;
+        }
+        let response = ctx.receive::<Vec<u8>>().await?;
+        let (header, _) = Response::parse_response_header(response.as_body())?;
+        assert_eq!(header.status(), Some(Status::ServiceUnavailable));
+
+        // the requests of another identity are not delayed
+        send_request(ctx, &quiet, "/quiet").await?;
+        ctx.sleep(Duration::from_millis(100)).await;
+        assert_eq!(held_paths(&held), vec!["/noisy/1", "/quiet"]);
+
+        // the waiting request is handled once the first one is answered
+        ctx.send(route!["service"], Request::post("/release").to_vec()?)
+            .await?;
+        let response = ctx.receive::<Vec<u8>>().await?;
+        let (header, _) = Response::parse_response_header(response.as_body())?;
+        assert!(header.is_ok());
+        ctx.sleep(Duration::from_millis(100)).await;
+        assert_eq!(held_paths(&held), vec!["/quiet", "/noisy/2"]);
+
+        ctx.stop().await
+    }
+}
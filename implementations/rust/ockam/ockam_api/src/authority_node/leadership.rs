@@ -0,0 +1,99 @@
+use core::sync::atomic::{AtomicBool, Ordering};
+use ockam_core::compat::sync::Arc;
+
+/// When several authority nodes share the same members storage, only one of them,
+/// the leader, accepts the requests modifying members or issuing enrollment tokens.
+/// The other nodes keep on issuing credentials and accepting enrollment tokens.
+///
+/// A standalone authority node is always the leader
+#[derive(Clone, Debug)]
+pub struct Leadership {
+    is_leader: Arc<AtomicBool>,
+}
+
+impl Leadership {
+    /// Leadership of an authority node which does not share its storage
+    pub fn standalone() -> Self {
+        Self {
+            is_leader: Arc::new(AtomicBool::new(true)),
+        }
+    }
+
+    /// Return true if this node currently is the leader
+    pub fn is_leader(&self) -> bool {
+        self.is_leader.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(feature = "postgres")]
+mod postgres {
+    use super::*;
+    use ockam::identity::storage::{connect_to_postgres, map_postgres_err};
+    use ockam_core::Result;
+    use std::time::Duration;
+    use tokio_postgres::Client;
+    use tracing::{info, warn};
+
+    /// Key of the Postgres advisory lock held by the leader.
+    /// The database is dedicated to the authority nodes of a single project
+    const LEADER_LOCK_KEY: i64 = 0x6f636b616d;
+
+    /// Time between two attempts to become the leader, or to check that the lock is still held
+    const ELECTION_INTERVAL: Duration = Duration::from_secs(5);
+
+    impl Leadership {
+        /// Start electing a leader between the authority nodes sharing a Postgres database.
+        ///
+        /// The leader holds a session-level advisory lock which Postgres releases as soon as
+        /// its connection is closed, so another node takes over if the leader stops
+        pub fn start_postgres_election(connection_string: String) -> Self {
+            let leadership = Self {
+                is_leader: Arc::new(AtomicBool::new(false)),
+            };
+            let election = leadership.clone();
+            ockam_node::tokio::spawn(async move {
+                loop {
+                    if let Err(err) = election.run_election(&connection_string).await {
+                        warn!(%err, "the authority leader election failed, retrying");
+                    }
+                    if election.is_leader() {
+                        info!("this authority node is not the leader anymore");
+                    }
+                    election.set_leader(false);
+                    ockam_node::tokio::time::sleep(ELECTION_INTERVAL).await;
+                }
+            });
+            leadership
+        }
+
+        fn set_leader(&self, is_leader: bool) {
+            self.is_leader.store(is_leader, Ordering::Relaxed)
+        }
+
+        /// Try to acquire the lock until the connection fails
+        async fn run_election(&self, connection_string: &str) -> Result<()> {
+            let client = connect_to_postgres(connection_string).await?;
+            loop {
+                if !self.is_leader() && Self::try_lock(&client).await? {
+                    info!("this authority node is now the leader");
+                    self.set_leader(true);
+                } else if self.is_leader() {
+                    // the lock is held as long as the connection is alive
+                    client
+                        .simple_query("SELECT 1")
+                        .await
+                        .map_err(map_postgres_err)?;
+                }
+                ockam_node::tokio::time::sleep(ELECTION_INTERVAL).await;
+            }
+        }
+
+        async fn try_lock(client: &Client) -> Result<bool> {
+            let row = client
+                .query_one("SELECT pg_try_advisory_lock($1)", &[&LEADER_LOCK_KEY])
+                .await
+                .map_err(map_postgres_err)?;
+            Ok(row.get(0))
+        }
+    }
+}
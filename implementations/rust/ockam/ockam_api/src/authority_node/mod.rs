@@ -1,7 +1,11 @@
 mod authority;
 mod configuration;
+mod fair_queue;
+mod leadership;
 mod node;
 
 pub use authority::*;
 pub use configuration::*;
+pub use fair_queue::*;
+pub use leadership::*;
 pub use node::*;
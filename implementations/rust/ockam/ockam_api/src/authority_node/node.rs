@@ -4,7 +4,7 @@ use ockam_node::Context;
 use tracing::info;
 
 /// Start all the necessary services for an authority node
-pub async fn start_node(ctx: &Context, configuration: &Configuration) -> Result<()> {
+pub async fn start_node(ctx: &Context, configuration: &Configuration) -> Result<Authority> {
     debug!("starting authority node");
     // create the authority identity
     // or retrieve it from disk if the node has already been started before
@@ -48,5 +48,5 @@ pub async fn start_node(ctx: &Context, configuration: &Configuration) -> Result<
     debug!("echo service started");
 
     info!("authority node started");
-    Ok(())
+    Ok(authority)
 }
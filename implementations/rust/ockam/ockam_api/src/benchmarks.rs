@@ -0,0 +1,250 @@
+//! Harness measuring the performance of secure channels: the latency of their handshake,
+//! the throughput of their encryption and decryption, and the overhead added by the
+//! encryption of kafka record batches.
+//!
+//! This module is only compiled with the `benchmarks` feature.
+//! The `secure_channel` bench target of this crate runs this harness with `criterion`.
+//! It can also be used directly, for example to compare a vault backed by a specific
+//! hardware or KMS with the software vault.
+
+use core::fmt::{Display, Formatter};
+use core::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use ockam::identity::{
+    DecryptionRequest, DecryptionResponse, EncryptionRequest, EncryptionResponse, Identifier,
+    SecureChannelListenerOptions, SecureChannelOptions, SecureChannelRegistryEntry, SecureChannels,
+    Vault,
+};
+use ockam_core::{route, Address, Result};
+use ockam_node::Context;
+use ockam_vault_aws::AwsSigningVault;
+
+use crate::error::ApiError;
+use crate::kafka::wrap_encrypted_record;
+
+/// Vault implementations which can be benchmarked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BenchmarkVault {
+    /// In-memory software vault
+    Software,
+    /// Identity and credential keys stored in AWS KMS, the secure channel keys are
+    /// stored in a software vault
+    AwsKms,
+}
+
+impl BenchmarkVault {
+    pub async fn create(&self) -> Result<Vault> {
+        match self {
+            BenchmarkVault::Software => Ok(Vault::create()),
+            BenchmarkVault::AwsKms => {
+                let mut vault = Vault::create();
+                let aws_vault = Arc::new(AwsSigningVault::create().await?);
+                vault.identity_vault = aws_vault.clone();
+                vault.credential_vault = aws_vault;
+                Ok(vault)
+            }
+        }
+    }
+}
+
+impl Display for BenchmarkVault {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.write_str(match self {
+            BenchmarkVault::Software => "software",
+            BenchmarkVault::AwsKms => "aws-kms",
+        })
+    }
+}
+
+impl FromStr for BenchmarkVault {
+    type Err = ockam_core::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "software" => Ok(BenchmarkVault::Software),
+            "aws-kms" => Ok(BenchmarkVault::AwsKms),
+            _ => Err(ApiError::core(format!(
+                "unknown vault {s}, expected 'software' or 'aws-kms'"
+            ))),
+        }
+    }
+}
+
+/// Two identities sharing a vault, connected by a secure channel
+pub struct SecureChannelBenchmark {
+    secure_channels: Arc<SecureChannels>,
+    initiator: Identifier,
+    listener: Address,
+    initiator_channel: SecureChannelRegistryEntry,
+    responder_channel: SecureChannelRegistryEntry,
+}
+
+impl SecureChannelBenchmark {
+    /// Create the identities, the secure channel listener and a first secure channel
+    pub async fn create(ctx: &Context, vault: Vault) -> Result<Self> {
+        let secure_channels = SecureChannels::builder().with_vault(vault).build();
+        let identities_creation = secure_channels.identities().identities_creation();
+        let initiator = identities_creation.create_identity().await?;
+        let responder = identities_creation.create_identity().await?;
+
+        let listener = Address::random_tagged("SecureChannelBenchmark.listener");
+        secure_channels
+            .create_secure_channel_listener(
+                ctx,
+                responder.identifier(),
+                listener.clone(),
+                SecureChannelListenerOptions::new(),
+            )
+            .await?;
+
+        let channel = secure_channels
+            .create_secure_channel(
+                ctx,
+                initiator.identifier(),
+                route![listener.clone()],
+                SecureChannelOptions::new(),
+            )
+            .await?;
+        let registry = secure_channels.secure_channel_registry();
+        let initiator_channel = registry
+            .get_channel_by_encryptor_address(channel.encryptor_address())
+            .ok_or_else(|| ApiError::core("the initiator channel is not registered"))?;
+        let responder_channel = registry
+            .get_channel_list()
+            .into_iter()
+            .find(|entry| {
+                !entry.is_initiator()
+                    && &entry.their_decryptor_address()
+                        == initiator_channel.decryptor_messaging_address()
+            })
+            .ok_or_else(|| ApiError::core("the responder channel is not registered"))?;
+
+        Ok(Self {
+            secure_channels,
+            initiator: initiator.identifier().clone(),
+            listener,
+            initiator_channel,
+            responder_channel,
+        })
+    }
+
+    /// Create a new secure channel to the listener and return the duration of its handshake.
+    /// The channel is stopped afterwards
+    pub async fn handshake(&self, ctx: &Context) -> Result<Duration> {
+        let start = Instant::now();
+        let channel = self
+            .secure_channels
+            .create_secure_channel(
+                ctx,
+                &self.initiator,
+                route![self.listener.clone()],
+                SecureChannelOptions::new(),
+            )
+            .await?;
+        let elapsed = start.elapsed();
+        self.secure_channels
+            .stop_secure_channel(ctx, channel.encryptor_address())
+            .await?;
+        Ok(elapsed)
+    }
+
+    /// Encrypt a payload with the initiator side of the secure channel
+    pub async fn encrypt(&self, ctx: &Context, payload: Vec<u8>) -> Result<Vec<u8>> {
+        let response: EncryptionResponse = ctx
+            .send_and_receive(
+                route![self.initiator_channel.encryptor_api_address().clone()],
+                EncryptionRequest(payload),
+            )
+            .await?;
+        match response {
+            EncryptionResponse::Ok(encrypted) => Ok(encrypted),
+            EncryptionResponse::Err(e) => Err(e),
+        }
+    }
+
+    /// Decrypt a payload encrypted by [`SecureChannelBenchmark::encrypt`] with the responder
+    /// side of the secure channel. Each payload can only be decrypted once
+    pub async fn decrypt(&self, ctx: &Context, encrypted: Vec<u8>) -> Result<Vec<u8>> {
+        let response: DecryptionResponse = ctx
+            .send_and_receive(
+                route![self.responder_channel.decryptor_api_address().clone()],
+                DecryptionRequest(encrypted),
+            )
+            .await?;
+        match response {
+            DecryptionResponse::Ok(decrypted) => Ok(decrypted),
+            DecryptionResponse::Err(e) => Err(e),
+        }
+    }
+
+    /// Encrypt the values of a batch of kafka records the way a kafka inlet does,
+    /// and return the sizes of the batch before and after its encryption
+    pub async fn kafka_batch(
+        &self,
+        ctx: &Context,
+        records: &[Vec<u8>],
+    ) -> Result<KafkaBatchOverhead> {
+        let mut encrypted_bytes = 0;
+        for record in records {
+            let encrypted = self.encrypt(ctx, record.clone()).await?;
+            let wrapped = wrap_encrypted_record(
+                &self.initiator_channel.their_decryptor_address(),
+                encrypted,
+            )?;
+            encrypted_bytes += wrapped.len();
+        }
+        Ok(KafkaBatchOverhead {
+            records: records.len(),
+            plaintext_bytes: records.iter().map(|r| r.len()).sum(),
+            encrypted_bytes,
+        })
+    }
+
+    /// Stop the secure channel listener
+    pub async fn stop(&self, ctx: &Context) -> Result<()> {
+        ctx.stop_worker(self.listener.clone()).await
+    }
+}
+
+/// Sizes of the values of a batch of kafka records, before and after their encryption
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KafkaBatchOverhead {
+    pub records: usize,
+    pub plaintext_bytes: usize,
+    pub encrypted_bytes: usize,
+}
+
+impl KafkaBatchOverhead {
+    /// Number of bytes added to each record by its encryption
+    pub fn bytes_per_record(&self) -> usize {
+        if self.records == 0 {
+            return 0;
+        }
+        self.encrypted_bytes.saturating_sub(self.plaintext_bytes) / self.records
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[ockam_macros::test]
+    async fn test_secure_channel_benchmark(ctx: &mut Context) -> Result<()> {
+        let benchmark = SecureChannelBenchmark::create(ctx, Vault::create()).await?;
+        assert!(benchmark.handshake(ctx).await? > Duration::ZERO);
+
+        let encrypted = benchmark.encrypt(ctx, b"hello".to_vec()).await?;
+        assert_eq!(benchmark.decrypt(ctx, encrypted).await?, b"hello");
+
+        let overhead = benchmark
+            .kafka_batch(ctx, &[vec![0; 100], vec![0; 100]])
+            .await?;
+        assert_eq!(overhead.plaintext_bytes, 200);
+        assert!(overhead.bytes_per_record() > 0);
+
+        benchmark.stop(ctx).await?;
+        ctx.stop().await
+    }
+}
@@ -0,0 +1,41 @@
+//! Run a standalone relay node.
+//!
+//! The node is started with the configuration read from the JSON file passed as its only
+//! argument, for example:
+//!
+//! ```json
+//! {
+//!   "storage_path": "/var/lib/ockam/relay/storage",
+//!   "vault_path": "/var/lib/ockam/relay/vault",
+//!   "tcp_listener_address": "0.0.0.0:4000",
+//!   "metrics_path": "/var/lib/node_exporter/ockam_relay.prom"
+//! }
+//! ```
+
+use ockam::{Context, Result};
+use ockam_api::relay_node::{start_relay_node, Configuration};
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::Error;
+
+#[ockam::node]
+async fn main(ctx: Context) -> Result<()> {
+    let path = std::env::args().nth(1).ok_or_else(|| {
+        Error::new(
+            Origin::Application,
+            Kind::Invalid,
+            "usage: ockam_relay_node <CONFIGURATION_FILE>",
+        )
+    })?;
+    let contents =
+        std::fs::read_to_string(&path).map_err(|e| Error::new(Origin::Application, Kind::Io, e))?;
+    let configuration: Configuration = serde_json::from_str(&contents)
+        .map_err(|e| Error::new(Origin::Application, Kind::Invalid, e))?;
+
+    let relay_node = start_relay_node(&ctx, &configuration).await?;
+    println!(
+        "Relay node {} listening on {}",
+        relay_node.identifier(),
+        configuration.tcp_listener_address
+    );
+    Ok(())
+}
@@ -1,8 +1,8 @@
 use ockam::identity::models::ChangeHistory;
 use ockam::identity::utils::now;
 use ockam::identity::{
-    AttributesEntry, Identifier, IdentitiesReader, IdentitiesRepository, IdentitiesWriter,
-    IdentityAttributesReader, IdentityAttributesWriter,
+    AttributesChangesListener, AttributesEntry, Identifier, IdentitiesReader, IdentitiesRepository,
+    IdentitiesWriter, IdentityAttributesReader, IdentityAttributesWriter,
 };
 use ockam_core::async_trait;
 use ockam_core::compat::sync::Arc;
@@ -73,6 +73,27 @@ impl IdentityAttributesWriter for BootstrapedIdentityStore {
         }
     }
 
+    async fn merge_attributes(&self, identity: &Identifier, entry: AttributesEntry) -> Result<()> {
+        match self.bootstrapped.get_attributes(identity).await? {
+            None => self.repository.merge_attributes(identity, entry).await,
+            Some(_) => Err(ockam_core::Error::new(
+                Origin::Identity,
+                Kind::AlreadyExists,
+                "cant write attributes for a bootstrapped identity",
+            )),
+        }
+    }
+
+    async fn delete_attributes_attested_by(
+        &self,
+        identity: &Identifier,
+        attester: &Identifier,
+    ) -> Result<()> {
+        self.repository
+            .delete_attributes_attested_by(identity, attester)
+            .await
+    }
+
     async fn put_attribute_value(
         &self,
         subject: &Identifier,
@@ -128,6 +149,11 @@ impl IdentitiesRepository for BootstrapedIdentityStore {
     fn as_identities_writer(&self) -> Arc<dyn IdentitiesWriter> {
         Arc::new(self.clone())
     }
+
+    fn subscribe_to_attributes_changes(&self, listener: Arc<dyn AttributesChangesListener>) {
+        // the bootstrapped identities cannot be modified
+        self.repository.subscribe_to_attributes_changes(listener)
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
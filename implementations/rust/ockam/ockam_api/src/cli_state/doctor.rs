@@ -0,0 +1,347 @@
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::Result;
+use crate::cli_state::{
+    CliState, CliStateError, CredentialsState, IdentitiesState, NodeTemplatesState, NodesState,
+    ProjectsState, SpacesState, StateDirTrait, StateItemTrait, TrustContextsState, VaultsState,
+    DATA_DIR_NAME,
+};
+
+/// Suffix of the name of the files storing the secrets of a vault, in the vaults data directory
+const VAULT_STORAGE_SUFFIX: &str = "-storage.";
+
+/// Inconsistency found in the CLI state by [`CliState::doctor`]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "issue", rename_all = "snake_case")]
+pub enum DoctorIssue {
+    /// The identity used by a node can't be loaded
+    NodeMissingIdentity { node: String },
+    /// The vault used by a node can't be loaded
+    NodeMissingVault { node: String },
+    /// The default item of a state directory was deleted
+    DanglingDefault { resource: String, path: PathBuf },
+    /// The storage of the secrets of a vault doesn't exist
+    MissingVaultStorage { vault: String, path: PathBuf },
+    /// Secrets are stored for a vault which doesn't exist anymore
+    DanglingSecrets { vault: String, path: PathBuf },
+}
+
+impl DoctorIssue {
+    /// Return the action repairing this issue
+    pub fn repair_action(&self) -> RepairAction {
+        match self {
+            DoctorIssue::NodeMissingIdentity { node } | DoctorIssue::NodeMissingVault { node } => {
+                RepairAction::DeleteNode { node: node.clone() }
+            }
+            DoctorIssue::DanglingDefault { resource, .. } => RepairAction::ResetDefault {
+                resource: resource.clone(),
+            },
+            DoctorIssue::MissingVaultStorage { vault, .. } => RepairAction::CreateVaultStorage {
+                vault: vault.clone(),
+            },
+            DoctorIssue::DanglingSecrets { path, .. } => {
+                RepairAction::DeleteFile { path: path.clone() }
+            }
+        }
+    }
+}
+
+impl Display for DoctorIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DoctorIssue::NodeMissingIdentity { node } => {
+                write!(f, "The identity of the node {node} is missing")
+            }
+            DoctorIssue::NodeMissingVault { node } => {
+                write!(f, "The vault of the node {node} is missing")
+            }
+            DoctorIssue::DanglingDefault { resource, path } => {
+                write!(f, "The default {resource} at {path:?} was deleted")
+            }
+            DoctorIssue::MissingVaultStorage { vault, path } => {
+                write!(f, "The storage of the vault {vault} at {path:?} is missing")
+            }
+            DoctorIssue::DanglingSecrets { vault, path } => {
+                write!(
+                    f,
+                    "The secrets at {path:?} belong to the deleted vault {vault}"
+                )
+            }
+        }
+    }
+}
+
+/// Action repairing a [`DoctorIssue`]. Actions are only applied when passed to [`CliState::repair`]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum RepairAction {
+    /// Stop and delete a node
+    DeleteNode { node: String },
+    /// Remove the default link of a state directory, and make another item the default one
+    ResetDefault { resource: String },
+    /// Create an empty storage for a vault. The secrets which were stored for this vault are lost
+    CreateVaultStorage { vault: String },
+    /// Delete a file
+    DeleteFile { path: PathBuf },
+}
+
+impl Display for RepairAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RepairAction::DeleteNode { node } => write!(f, "Delete the node {node}"),
+            RepairAction::ResetDefault { resource } => write!(f, "Reset the default {resource}"),
+            RepairAction::CreateVaultStorage { vault } => {
+                write!(f, "Create an empty storage for the vault {vault}")
+            }
+            RepairAction::DeleteFile { path } => write!(f, "Delete {path:?}"),
+        }
+    }
+}
+
+/// Result of a [`RepairAction`]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RepairOutcome {
+    pub action: RepairAction,
+    pub applied: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Report returned by [`CliState::doctor`]: the issues found in the CLI state,
+/// each one with the action which would repair it
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct DoctorReport {
+    pub issues: Vec<DoctorIssueReport>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DoctorIssueReport {
+    #[serde(flatten)]
+    pub issue: DoctorIssue,
+    pub repair: RepairAction,
+}
+
+impl DoctorReport {
+    pub fn is_healthy(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Return the actions repairing all the issues of this report
+    pub fn repair_actions(&self) -> Vec<RepairAction> {
+        let mut actions: Vec<RepairAction> = vec![];
+        for issue in &self.issues {
+            if !actions.contains(&issue.repair) {
+                actions.push(issue.repair.clone());
+            }
+        }
+        actions
+    }
+
+    fn add(&mut self, issue: DoctorIssue) {
+        let repair = issue.repair_action();
+        self.issues.push(DoctorIssueReport { issue, repair });
+    }
+}
+
+impl CliState {
+    /// Scan the CLI state for inconsistencies. Nothing is modified: the returned report
+    /// lists the repair actions which can be applied with [`CliState::repair`]
+    pub fn doctor(&self) -> Result<DoctorReport> {
+        let mut report = DoctorReport::default();
+        for node in self.nodes.list()? {
+            if node.config().identifier().is_err() {
+                report.add(DoctorIssue::NodeMissingIdentity {
+                    node: node.name().to_string(),
+                });
+            }
+            if node.config().vault_path().is_err() {
+                report.add(DoctorIssue::NodeMissingVault {
+                    node: node.name().to_string(),
+                });
+            }
+        }
+
+        for default in [
+            dangling_default(&self.vaults)?,
+            dangling_default(&self.identities)?,
+            dangling_default(&self.nodes)?,
+            dangling_default(&self.spaces)?,
+            dangling_default(&self.projects)?,
+            dangling_default(&self.credentials)?,
+            dangling_default(&self.trust_contexts)?,
+            dangling_default(&self.node_templates)?,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            report.add(default);
+        }
+
+        for vault in self.vaults.list()? {
+            if !vault.is_aws() && !vault.vault_file_path().exists() {
+                report.add(DoctorIssue::MissingVaultStorage {
+                    vault: vault.name().to_string(),
+                    path: vault.vault_file_path().clone(),
+                });
+            }
+        }
+
+        let vaults_data_dir = self.vaults.dir().join(DATA_DIR_NAME);
+        if vaults_data_dir.exists() {
+            for entry in std::fs::read_dir(vaults_data_dir)? {
+                let path = entry?.path();
+                if let Some(vault) = vault_name_of_storage(&path) {
+                    if !self.vaults.exists(&vault) {
+                        report.add(DoctorIssue::DanglingSecrets { vault, path });
+                    }
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Apply some repair actions, usually the ones of a [`DoctorReport`].
+    /// A failing action doesn't prevent the next ones from being applied
+    pub async fn repair(&self, actions: &[RepairAction]) -> Vec<RepairOutcome> {
+        let mut outcomes = vec![];
+        for action in actions {
+            let result = self.apply_repair(action).await;
+            match &result {
+                Ok(()) => info!(%action, "repaired the cli state"),
+                Err(e) => warn!(%action, %e, "failed to repair the cli state"),
+            }
+            outcomes.push(RepairOutcome {
+                action: action.clone(),
+                applied: result.is_ok(),
+                error: result.err().map(|e| e.to_string()),
+            });
+        }
+        outcomes
+    }
+
+    async fn apply_repair(&self, action: &RepairAction) -> Result<()> {
+        match action {
+            RepairAction::DeleteNode { node } => self.nodes.delete_sigkill(node, false),
+            RepairAction::ResetDefault { resource } => match resource.as_str() {
+                r if r == VaultsState::default_filename() => reset_default(&self.vaults),
+                r if r == IdentitiesState::default_filename() => reset_default(&self.identities),
+                r if r == NodesState::default_filename() => reset_default(&self.nodes),
+                r if r == SpacesState::default_filename() => reset_default(&self.spaces),
+                r if r == ProjectsState::default_filename() => reset_default(&self.projects),
+                r if r == CredentialsState::default_filename() => reset_default(&self.credentials),
+                r if r == TrustContextsState::default_filename() => {
+                    reset_default(&self.trust_contexts)
+                }
+                r if r == NodeTemplatesState::default_filename() => {
+                    reset_default(&self.node_templates)
+                }
+                _ => Err(CliStateError::InvalidData(format!(
+                    "Unknown resource {resource}"
+                ))),
+            },
+            RepairAction::CreateVaultStorage { vault } => {
+                let vault = self.vaults.get(vault)?;
+                if vault.vault_file_path().exists() {
+                    return Err(CliStateError::InvalidOperation(format!(
+                        "The storage of the vault {} already exists",
+                        vault.name()
+                    )));
+                }
+                vault.get().await?;
+                Ok(())
+            }
+            RepairAction::DeleteFile { path } => Ok(std::fs::remove_file(path)?),
+        }
+    }
+}
+
+/// Return an issue if the default link of a state directory points to a deleted item
+fn dangling_default<T: StateDirTrait>(state: &T) -> Result<Option<DoctorIssue>> {
+    let path = state.default_path()?;
+    let is_link = std::fs::symlink_metadata(&path)
+        .map(|m| m.file_type().is_symlink())
+        .unwrap_or(false);
+    if is_link && !path.exists() {
+        Ok(Some(DoctorIssue::DanglingDefault {
+            resource: T::default_filename().to_string(),
+            path,
+        }))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Remove the default link of a state directory, then make the first valid item the default one
+fn reset_default<T: StateDirTrait>(state: &T) -> Result<()> {
+    let _ = std::fs::remove_file(state.default_path()?);
+    for name in state.list_items_names()? {
+        if state.get(&name).is_ok() {
+            return state.set_default(&name);
+        }
+    }
+    Ok(())
+}
+
+/// Return the name of the vault owning a file of the vaults data directory
+fn vault_name_of_storage(path: &Path) -> Option<String> {
+    let file_name = path.file_name()?.to_str()?;
+    file_name
+        .find(VAULT_STORAGE_SUFFIX)
+        .map(|i| file_name[..i].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli_state::VaultConfig;
+
+    #[tokio::test]
+    async fn test_doctor_and_repair() -> Result<()> {
+        let state = CliState::test()?;
+        let vault = state
+            .vaults
+            .create_async("vault-1", VaultConfig::default())
+            .await?;
+        state
+            .vaults
+            .create_async("vault-2", VaultConfig::default())
+            .await?;
+        assert!(state.doctor()?.is_healthy());
+
+        // delete the default vault configuration but not its secrets
+        std::fs::remove_file(vault.path())?;
+        // and the storage of the other vault
+        let other = state.vaults.get("vault-2")?;
+        std::fs::remove_file(other.vault_file_path())?;
+
+        let report = state.doctor()?;
+        assert!(report.issues.iter().any(|i| i.issue
+            == DoctorIssue::DanglingDefault {
+                resource: "vault".to_string(),
+                path: state.vaults.default_path()?,
+            }));
+        assert!(report.issues.iter().any(|i| i.issue
+            == DoctorIssue::MissingVaultStorage {
+                vault: "vault-2".to_string(),
+                path: other.vault_file_path().clone(),
+            }));
+        assert!(report.issues.iter().any(|i| matches!(
+            &i.issue,
+            DoctorIssue::DanglingSecrets { vault, .. } if vault == "vault-1"
+        )));
+
+        // the report is machine-readable
+        let json = serde_json::to_value(&report)?;
+        assert_eq!(json["issues"][0]["issue"], "dangling_default");
+        assert_eq!(json["issues"][0]["repair"]["action"], "reset_default");
+
+        let outcomes = state.repair(&report.repair_actions()).await;
+        assert!(outcomes.iter().all(|o| o.applied), "{outcomes:?}");
+        assert!(state.doctor()?.is_healthy());
+        assert_eq!(state.vaults.default()?.name(), "vault-2");
+        Ok(())
+    }
+}
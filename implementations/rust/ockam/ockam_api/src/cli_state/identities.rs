@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::fmt::{Display, Formatter};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -91,6 +92,28 @@ impl IdentityState {
             .map(|s| s.is_enrolled)
             .unwrap_or(false)
     }
+
+    /// Free-form metadata describing the identity, for example its owner or its purpose
+    pub fn metadata(&self) -> &BTreeMap<String, String> {
+        &self.config.metadata
+    }
+
+    /// Set a metadata entry, replacing the previous value of the key
+    pub fn set_metadata(&mut self, key: &str, value: &str) -> Result<()> {
+        self.config
+            .metadata
+            .insert(key.to_string(), value.to_string());
+        self.persist()
+    }
+
+    /// Remove a metadata entry. Return false if the key was not set
+    pub fn remove_metadata(&mut self, key: &str) -> Result<bool> {
+        let removed = self.config.metadata.remove(key).is_some();
+        if removed {
+            self.persist()?;
+        }
+        Ok(removed)
+    }
 }
 
 impl Display for IdentityState {
@@ -111,6 +134,12 @@ impl Display for IdentityState {
             }
             None => (),
         }
+        if !self.config.metadata.is_empty() {
+            writeln!(f, "Metadata:")?;
+            for (key, value) in &self.config.metadata {
+                writeln!(f, "{:2}{key}: {value}", "")?;
+            }
+        }
         Ok(())
     }
 }
@@ -119,6 +148,8 @@ impl Display for IdentityState {
 pub struct IdentityConfig {
     pub identifier: Identifier,
     pub enrollment_status: Option<EnrollmentStatus>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
 }
 
 impl PartialEq for IdentityConfig {
@@ -134,6 +165,7 @@ impl IdentityConfig {
         Self {
             identifier: identifier.clone(),
             enrollment_status: None,
+            metadata: BTreeMap::new(),
         }
     }
 
@@ -330,6 +362,23 @@ mod tests {
         assert_eq!(actual, expected)
     }
 
+    #[test]
+    fn test_metadata() {
+        let mut identity_config = create_identity_config();
+        identity_config
+            .metadata
+            .insert("team".to_string(), "payments".to_string());
+        let json = serde_json::to_string(&identity_config).unwrap();
+        assert!(json.ends_with(r#""metadata":{"team":"payments"}}"#));
+
+        let actual: IdentityConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(actual.metadata, identity_config.metadata);
+
+        // configurations written before the metadata was introduced are still readable
+        let actual: IdentityConfig = serde_json::from_str(&create_identity_config_json()).unwrap();
+        assert!(actual.metadata.is_empty());
+    }
+
     fn create_identity_config() -> IdentityConfig {
         let identifier = Identifier::try_from("Ifa804b7fca12a19eed206ae180b5b576860ae651").unwrap();
         IdentityConfig {
@@ -338,6 +387,7 @@ mod tests {
                 is_enrolled: true,
                 created_at: SystemTime::from(OffsetDateTime::from_unix_timestamp(0).unwrap()),
             }),
+            metadata: BTreeMap::new(),
         }
     }
 
@@ -1,6 +1,10 @@
 pub mod credentials;
+pub mod doctor;
 pub mod identities;
+pub mod node_templates;
 pub mod nodes;
+pub mod operations_queue;
+pub mod ports;
 pub mod projects;
 pub mod spaces;
 pub mod traits;
@@ -9,8 +13,12 @@ pub mod user_info;
 pub mod vaults;
 
 pub use crate::cli_state::credentials::*;
+pub use crate::cli_state::doctor::*;
 pub use crate::cli_state::identities::*;
+pub use crate::cli_state::node_templates::*;
 pub use crate::cli_state::nodes::*;
+pub use crate::cli_state::operations_queue::*;
+pub use crate::cli_state::ports::*;
 pub use crate::cli_state::projects::*;
 pub use crate::cli_state::spaces::*;
 pub use crate::cli_state::traits::*;
@@ -109,6 +117,7 @@ pub struct CliState {
     pub credentials: CredentialsState,
     pub trust_contexts: TrustContextsState,
     pub users_info: UsersInfoState,
+    pub node_templates: NodeTemplatesState,
     pub dir: PathBuf,
 }
 
@@ -137,6 +146,7 @@ impl CliState {
             credentials: CredentialsState::init(dir).await?,
             trust_contexts: TrustContextsState::init(dir).await?,
             users_info: UsersInfoState::init(dir).await?,
+            node_templates: NodeTemplatesState::init(dir).await?,
             dir: dir.to_path_buf(),
         };
         state.migrate()?;
@@ -215,7 +225,9 @@ impl CliState {
             CredentialsState::new(root_path).dir(),
             TrustContextsState::new(root_path).dir(),
             UsersInfoState::new(root_path).dir(),
+            NodeTemplatesState::new(root_path).dir(),
             &root_path.join("defaults"),
+            &root_path.join(TRASH_DIR_NAME),
         ] {
             let _ = std::fs::remove_dir_all(dir);
         }
@@ -223,6 +235,8 @@ impl CliState {
         // Delete config files located at the root of the state directory
         let config_file = root_path.join("config.json");
         let _ = std::fs::remove_file(config_file);
+        let _ = std::fs::remove_file(root_path.join(OPERATIONS_QUEUE_FILENAME));
+        let _ = std::fs::remove_file(root_path.join(OPERATIONS_QUEUE_LOCK_FILENAME));
 
         // If the state directory is now empty, delete it
         let is_empty = std::fs::read_dir(root_path)
@@ -239,7 +253,9 @@ impl CliState {
         Self::delete_at(&Self::default_dir()?)
     }
 
-    pub fn delete_identity(&self, identity_state: IdentityState) -> Result<()> {
+    /// Delete an identity which is not used by a node. Unless the deletion is permanent,
+    /// the identity is kept in the trash, from where it can be restored
+    pub fn delete_identity(&self, identity_state: IdentityState, permanent: bool) -> Result<()> {
         // Abort if identity is being used by some running node.
         for node in self.nodes.list()? {
             if node.config().identity_config()?.identifier() == identity_state.identifier() {
@@ -250,7 +266,12 @@ impl CliState {
                 )));
             }
         }
-        identity_state.delete()
+        if permanent {
+            self.identities.delete(identity_state.name())
+        } else {
+            // Keep the identity in the trash, so that an accidental deletion can be undone
+            self.identities.soft_delete(identity_state.name())
+        }
     }
 
     /// Returns the default directory for the CLI state.
@@ -381,6 +402,7 @@ impl CliState {
             credentials: CredentialsState::init(dir).await?,
             trust_contexts: TrustContextsState::init(dir).await?,
             users_info: UsersInfoState::init(dir).await?,
+            node_templates: NodeTemplatesState::init(dir).await?,
             dir: dir.to_path_buf(),
         };
         state.migrate()?;
@@ -399,6 +421,7 @@ impl CliState {
             credentials: CredentialsState::load(dir)?,
             trust_contexts: TrustContextsState::load(dir)?,
             users_info: UsersInfoState::load(dir)?,
+            node_templates: NodeTemplatesState::load(dir)?,
             dir: dir.to_path_buf(),
         })
     }
@@ -491,6 +514,93 @@ mod tests {
         assert_eq!(identity1.path(), identity2.path());
     }
 
+    #[tokio::test]
+    async fn test_restore_deleted_identity_state() {
+        let state = CliState::test().unwrap();
+        let alice = "Ie92f183eb4c324804ef4d62962dea94cf095a265"
+            .try_into()
+            .unwrap();
+        let identity = state
+            .create_identity_state(&alice, Some("alice"))
+            .await
+            .unwrap();
+        assert!(state.identities.is_default("alice").unwrap());
+
+        state.delete_identity(identity.clone(), false).unwrap();
+        assert!(state.identities.get("alice").is_err());
+        assert!(state.identities.default().is_err());
+        let deleted = state.identities.list_deleted().unwrap();
+        assert_eq!(deleted.len(), 1);
+        assert_eq!(deleted[0].name, "alice");
+
+        // the restored identity is set as the default one again
+        let restored = state.identities.restore("alice").unwrap();
+        assert_eq!(restored, identity);
+        assert!(state.identities.is_default("alice").unwrap());
+        assert!(state.identities.list_deleted().unwrap().is_empty());
+        assert!(state.identities.restore("alice").is_err());
+
+        // a permanently deleted identity can not be restored
+        state.delete_identity(restored, true).unwrap();
+        assert!(state.identities.get("alice").is_err());
+        assert!(state.identities.list_deleted().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_restore_deleted_node_state() {
+        let state = CliState::test().unwrap();
+        state
+            .vaults
+            .create_async("vault", VaultConfig::default())
+            .await
+            .unwrap();
+        let alice = "Ie92f183eb4c324804ef4d62962dea94cf095a265"
+            .try_into()
+            .unwrap();
+        state
+            .create_identity_state(&alice, Some("alice"))
+            .await
+            .unwrap();
+        let node = state
+            .nodes
+            .create("n1", NodeConfig::try_from(&state).unwrap())
+            .unwrap();
+        let address = state.nodes.allocate_port("n1", "tcp-inlet:db").unwrap();
+
+        // the ports of a deleted node are released, and reserved again when it is restored
+        state.nodes.soft_delete_sigkill("n1", false).unwrap();
+        assert!(state.nodes.get("n1").is_err());
+        assert_eq!(
+            state
+                .nodes
+                .port_reservations()
+                .unwrap()
+                .get("n1", "tcp-inlet:db"),
+            None
+        );
+        let deleted = state.nodes.list_deleted().unwrap();
+        assert_eq!(deleted.len(), 1);
+        assert_eq!(deleted[0].name, "n1");
+
+        let restored = state.nodes.restore("n1").unwrap();
+        assert_eq!(restored, node);
+        assert_eq!(
+            state
+                .nodes
+                .port_reservations()
+                .unwrap()
+                .get("n1", "tcp-inlet:db"),
+            Some(address)
+        );
+        assert!(state.nodes.list_deleted().unwrap().is_empty());
+
+        // a permanently deleted node can not be restored
+        state.nodes.delete_sigkill("n1", false).unwrap();
+        assert!(state.nodes.get("n1").is_err());
+        assert!(state.nodes.list_deleted().unwrap().is_empty());
+        assert!(state.nodes.restore("n1").is_err());
+    }
+
     #[tokio::test]
     async fn migrate_legacy_cli_config() {
         // Before this migration, there was a `config.json` file in the root $OCKAM_HOME directory
@@ -681,6 +791,7 @@ mod tests {
             "users_info".to_string(),
             format!("users_info/{user_info_email}.json"),
             "credentials".to_string(),
+            "node_templates".to_string(),
             "defaults".to_string(),
             "defaults/vault".to_string(),
             "defaults/identity".to_string(),
@@ -754,7 +865,7 @@ mod tests {
                     });
                 }
                 "defaults" | "spaces" | "projects" | "credentials" | "trust_contexts"
-                | "users_info" => {
+                | "users_info" | "node_templates" => {
                     assert!(entry.path().is_dir());
                     found_entries.push(dir_name.clone());
                     entry.path().read_dir().unwrap().for_each(|entry| {
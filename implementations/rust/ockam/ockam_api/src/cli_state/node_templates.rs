@@ -0,0 +1,213 @@
+use super::Result;
+use crate::cli_state::{CliStateError, StateDirTrait};
+use crate::nodes::service::ManagementAccess;
+use ockam_abac::Expr;
+use ockam_transport_tcp::AllowedNetwork;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Node templates describe how to create a fully configured node in one step:
+/// its vault and identity, its transports, the services started with it and its policies
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct NodeTemplatesState {
+    dir: PathBuf,
+}
+
+impl NodeTemplatesState {
+    /// Return the JSON representation of a template, which can be imported in another CLI state
+    pub fn export(&self, name: &str) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self.get(name)?.config())?)
+    }
+
+    /// Create a template from its JSON representation
+    pub fn import(&self, name: &str, contents: &str) -> Result<NodeTemplateState> {
+        let config: NodeTemplateConfig = serde_json::from_str(contents)?;
+        config.validate()?;
+        self.create(name, config)
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct NodeTemplateState {
+    name: String,
+    path: PathBuf,
+    config: NodeTemplateConfig,
+}
+
+impl NodeTemplateState {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct NodeTemplateConfig {
+    /// Name of the vault used by the nodes. The default vault is used if it is missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vault: Option<String>,
+    /// Name of the identity of the nodes. A new identity is created for each node if it is missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity: Option<String>,
+    /// Address of the TCP listener of the nodes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tcp_listener_address: Option<String>,
+    /// Networks, in the CIDR notation, from which the TCP listener accepts connections
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tcp_listener_allowed_networks: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub management_access: Option<ManagementAccess>,
+    /// Services started with the nodes, in the format of the `startup_services`
+    /// section of a launch configuration
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub startup_services: Option<serde_json::Value>,
+    /// Policies set on the nodes once they are started
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub policies: Vec<NodeTemplatePolicy>,
+}
+
+impl NodeTemplateConfig {
+    /// Check that the networks and the policy expressions of the template can be parsed
+    pub fn validate(&self) -> Result<()> {
+        for network in &self.tcp_listener_allowed_networks {
+            AllowedNetwork::from_str(network).map_err(|_| {
+                CliStateError::InvalidData(format!("Invalid network {network} in node template"))
+            })?;
+        }
+        for policy in &self.policies {
+            policy.expression()?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct NodeTemplatePolicy {
+    pub resource: String,
+    #[serde(default = "default_policy_action")]
+    pub action: String,
+    pub expression: String,
+}
+
+impl NodeTemplatePolicy {
+    pub fn expression(&self) -> Result<Expr> {
+        Expr::from_str(&self.expression).map_err(|e| {
+            CliStateError::InvalidData(format!(
+                "Invalid policy expression for the resource {} in node template: {e}",
+                self.resource
+            ))
+        })
+    }
+}
+
+fn default_policy_action() -> String {
+    "handle_message".to_string()
+}
+
+mod traits {
+    use super::*;
+    use crate::cli_state::file_stem;
+    use crate::cli_state::traits::*;
+    use ockam_core::async_trait;
+    use std::path::Path;
+
+    #[async_trait]
+    impl StateDirTrait for NodeTemplatesState {
+        type Item = NodeTemplateState;
+        const DEFAULT_FILENAME: &'static str = "node_template";
+        const DIR_NAME: &'static str = "node_templates";
+        const HAS_DATA_DIR: bool = false;
+
+        fn new(root_path: &Path) -> Self {
+            Self {
+                dir: Self::build_dir(root_path),
+            }
+        }
+
+        fn dir(&self) -> &PathBuf {
+            &self.dir
+        }
+    }
+
+    #[async_trait]
+    impl StateItemTrait for NodeTemplateState {
+        type Config = NodeTemplateConfig;
+
+        fn new(path: PathBuf, config: Self::Config) -> Result<Self> {
+            let contents = serde_json::to_string(&config)?;
+            std::fs::write(&path, contents)?;
+            let name = file_stem(&path)?;
+            Ok(Self { name, path, config })
+        }
+
+        fn load(path: PathBuf) -> Result<Self> {
+            let name = file_stem(&path)?;
+            let contents = std::fs::read_to_string(&path)?;
+            let config = serde_json::from_str(&contents)?;
+            Ok(Self { name, path, config })
+        }
+
+        fn path(&self) -> &PathBuf {
+            &self.path
+        }
+
+        fn config(&self) -> &Self::Config {
+            &self.config
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli_state::{CliState, StateItemTrait};
+
+    #[test]
+    fn test_export_import_node_template() {
+        let state = CliState::test().unwrap();
+        let config = NodeTemplateConfig {
+            vault: Some("edge".to_string()),
+            tcp_listener_address: Some("0.0.0.0:4000".to_string()),
+            tcp_listener_allowed_networks: vec!["10.0.0.0/8".to_string()],
+            management_access: Some(ManagementAccess::Admin),
+            startup_services: Some(serde_json::json!({"secure_channel_listener": {}})),
+            policies: vec![NodeTemplatePolicy {
+                resource: "tcp-outlet".to_string(),
+                action: default_policy_action(),
+                expression: "(= subject.component \"edge\")".to_string(),
+            }],
+            ..Default::default()
+        };
+        state
+            .node_templates
+            .create("edge-gateway", config.clone())
+            .unwrap();
+
+        let exported = state.node_templates.export("edge-gateway").unwrap();
+        let imported = state.node_templates.import("copy", &exported).unwrap();
+        assert_eq!(imported.config(), &config);
+
+        // the action of a policy defaults to `handle_message`
+        let imported = state
+            .node_templates
+            .import(
+                "minimal",
+                r#"{"policies": [{"resource": "r", "expression": "(= subject.a \"b\")"}]}"#,
+            )
+            .unwrap();
+        assert_eq!(imported.config().policies[0].action, "handle_message");
+
+        // invalid templates are rejected
+        assert!(state
+            .node_templates
+            .import(
+                "invalid",
+                r#"{"policies": [{"resource": "r", "expression": "(="}]}"#
+            )
+            .is_err());
+        assert!(state
+            .node_templates
+            .import("invalid", r#"{"tcp_listener_allowed_networks": ["nope"]}"#)
+            .is_err());
+    }
+}
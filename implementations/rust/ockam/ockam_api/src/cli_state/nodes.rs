@@ -4,14 +4,22 @@ use crate::cli_state::{
     StateDirTrait, StateItemTrait, VaultState,
 };
 use crate::config::lookup::ProjectLookup;
+use crate::error::ApiError;
 use crate::nodes::models::transport::CreateTransportJson;
+use crate::nodes::service::ManagementAccess;
 use backwards_compatibility::*;
 use miette::{IntoDiagnostic, WrapErr};
 use nix::errno::Errno;
 use ockam::identity::Identifier;
 use ockam::identity::Vault;
 use ockam::LmdbStorage;
-use ockam_core::compat::collections::HashSet;
+use ockam_core::compat::collections::{BTreeMap, HashSet};
+use ockam_core::compat::rand::{thread_rng, RngCore};
+use ockam_core::compat::sync::Arc;
+use ockam_vault::{
+    AeadSecretKeyHandle, HKDFNumberOfOutputs, SecretBufferHandle, VaultForSecureChannels,
+    X25519PublicKey, X25519SecretKeyHandle,
+};
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
 use std::path::{Path, PathBuf};
@@ -31,10 +39,36 @@ impl NodesState {
     }
 
     pub fn delete_sigkill(&self, name: &str, sigkill: bool) -> Result<()> {
-        self._delete(name, sigkill)
+        self._delete(name, sigkill, false)
+    }
+
+    /// Stop a node and move it to the trash, from where it can be restored
+    pub fn soft_delete_sigkill(&self, name: &str, sigkill: bool) -> Result<()> {
+        self._delete(name, sigkill, true)
+    }
+
+    /// Return all the nodes, sorted by name or by one of their timestamps.
+    /// Nodes with the same timestamp are sorted by name
+    pub fn list_sorted(&self, key: NodeSortKey) -> Result<Vec<NodeState>> {
+        let mut nodes = self.list()?;
+        let timestamp = |node: &NodeState| {
+            let setup = node.config().setup();
+            match key {
+                NodeSortKey::Name => None,
+                NodeSortKey::CreatedAt => setup.created_at,
+                NodeSortKey::LastStartedAt => setup.last_started_at,
+                NodeSortKey::LastStoppedAt => setup.last_stopped_at,
+            }
+        };
+        nodes.sort_by(|a, b| {
+            timestamp(a)
+                .cmp(&timestamp(b))
+                .then_with(|| a.name().cmp(b.name()))
+        });
+        Ok(nodes)
     }
 
-    fn _delete(&self, name: impl AsRef<str>, sigkill: bool) -> Result<()> {
+    fn _delete(&self, name: impl AsRef<str>, sigkill: bool, soft: bool) -> Result<()> {
         // If doesn't exist do nothing
         if !self.exists(&name) {
             return Ok(());
@@ -51,9 +85,56 @@ impl NodesState {
                 }
             }
         }
-        // Remove node directory
-        node.delete_sigkill(sigkill)?;
-        Ok(())
+        if soft {
+            // Keep the node directory in the trash
+            node.kill_process(sigkill)?;
+            self.move_to_trash(&name)?;
+            self.keep_released_ports(name.as_ref())?;
+        } else {
+            // Remove node directory
+            node.delete_sigkill(sigkill)?;
+        }
+        self.release_ports(name.as_ref())
+    }
+}
+
+/// Order of the nodes returned by [`NodesState::list_sorted`]
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum NodeSortKey {
+    #[default]
+    Name,
+    /// The oldest nodes first
+    CreatedAt,
+    /// The nodes which were never started first, then the nodes started the longest time ago
+    LastStartedAt,
+    /// The nodes which were never stopped first, then the nodes stopped the longest time ago
+    LastStoppedAt,
+}
+
+impl Display for NodeSortKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            NodeSortKey::Name => "name",
+            NodeSortKey::CreatedAt => "created",
+            NodeSortKey::LastStartedAt => "started",
+            NodeSortKey::LastStoppedAt => "stopped",
+        })
+    }
+}
+
+impl FromStr for NodeSortKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "name" => Ok(NodeSortKey::Name),
+            "created" => Ok(NodeSortKey::CreatedAt),
+            "started" => Ok(NodeSortKey::LastStartedAt),
+            "stopped" => Ok(NodeSortKey::LastStoppedAt),
+            _ => Err(format!(
+                "unknown sort key '{s}', expected one of: name, created, started, stopped"
+            )),
+        }
     }
 }
 
@@ -103,6 +184,7 @@ impl NodeState {
                 ))
             })?;
             std::fs::remove_file(self.paths.pid())?;
+            self.update_setup(|setup| setup.last_stopped_at = Some(now_in_seconds()))?;
         }
         info!(name = %self.name(), "node process killed");
         Ok(())
@@ -115,6 +197,49 @@ impl NodeState {
         Ok(())
     }
 
+    /// Read the setup configuration stored on disk, which can be more recent than
+    /// the configuration loaded with this state
+    fn read_setup(&self) -> Result<NodeSetupConfig> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(
+            self.paths.setup(),
+        )?)?)
+    }
+
+    /// Modify the setup configuration stored on disk
+    fn update_setup(&self, f: impl FnOnce(&mut NodeSetupConfig)) -> Result<()> {
+        let mut setup = self.read_setup()?;
+        f(&mut setup);
+        self.set_setup(&setup)
+    }
+
+    /// Project the node is associated with, if any
+    pub fn project(&self) -> Result<Option<ProjectLookup>> {
+        Ok(self.read_setup()?.project)
+    }
+
+    /// Identity used by the node when it acts as a client, if it is not its own identity
+    pub fn client_identity(&self) -> Result<Option<String>> {
+        Ok(self.read_setup()?.client_identity)
+    }
+
+    /// Set the identity used by the node when it acts as a client,
+    /// the node uses its own identity when it is `None`.
+    /// The identity is read when the node starts
+    pub fn set_client_identity(&self, identity_name: Option<String>) -> Result<()> {
+        self.update_setup(|setup| setup.client_identity = identity_name)?;
+        info!(name = %self.name(), "client identity updated");
+        Ok(())
+    }
+
+    /// Associate the node with a project
+    pub fn set_project(&self, project: ProjectLookup) -> Result<()> {
+        self.update_setup(|setup| {
+            setup.set_project(project);
+        })?;
+        info!(name = %self.name(), "project association updated");
+        Ok(())
+    }
+
     pub fn pid(&self) -> Result<Option<i32>> {
         let path = self.paths.pid();
         if path.exists() {
@@ -132,6 +257,17 @@ impl NodeState {
         Ok(())
     }
 
+    /// Record that the node was stopped without killing its process, for a node
+    /// which runs in a process hosting other code
+    pub fn set_stopped(&self) -> Result<()> {
+        if self.paths.pid().exists() {
+            std::fs::remove_file(self.paths.pid())?;
+        }
+        self.update_setup(|setup| setup.last_stopped_at = Some(now_in_seconds()))?;
+        info!(name = %self.name(), "node stopped");
+        Ok(())
+    }
+
     pub fn is_running(&self) -> bool {
         if let Ok(Some(pid)) = self.pid() {
             let mut sys = System::new();
@@ -157,10 +293,63 @@ impl NodeState {
         self.paths.stderr()
     }
 
+    /// File where the accesses denied by the policies of the node are recorded
+    pub fn policy_audit_log(&self) -> PathBuf {
+        self.paths.policy_audit_log()
+    }
+
     pub async fn policies_storage(&self) -> Result<LmdbStorage> {
         Ok(LmdbStorage::new(self.paths.policies_storage()).await?)
     }
 
+    pub async fn usage_storage(&self) -> Result<LmdbStorage> {
+        Ok(LmdbStorage::new(self.paths.usage_storage()).await?)
+    }
+
+    pub async fn peers_storage(&self) -> Result<LmdbStorage> {
+        Ok(LmdbStorage::new(self.paths.peers_storage()).await?)
+    }
+
+    pub async fn probes_storage(&self) -> Result<LmdbStorage> {
+        Ok(LmdbStorage::new(self.paths.probes_storage()).await?)
+    }
+
+    pub async fn credentials_cache_storage(&self) -> Result<LmdbStorage> {
+        Ok(LmdbStorage::new(self.paths.credentials_cache_storage()).await?)
+    }
+
+    pub async fn static_routes_storage(&self) -> Result<LmdbStorage> {
+        Ok(LmdbStorage::new(self.paths.static_routes_storage()).await?)
+    }
+
+    pub async fn outlet_routes_storage(&self) -> Result<LmdbStorage> {
+        Ok(LmdbStorage::new(self.paths.outlet_routes_storage()).await?)
+    }
+
+    pub async fn groups_storage(&self) -> Result<LmdbStorage> {
+        Ok(LmdbStorage::new(self.paths.groups_storage()).await?)
+    }
+
+    pub async fn schedules_storage(&self) -> Result<LmdbStorage> {
+        Ok(LmdbStorage::new(self.paths.schedules_storage()).await?)
+    }
+
+    pub async fn stats_storage(&self) -> Result<LmdbStorage> {
+        Ok(LmdbStorage::new(self.paths.stats_storage()).await?)
+    }
+
+    pub async fn idempotency_storage(&self) -> Result<LmdbStorage> {
+        Ok(LmdbStorage::new(self.paths.idempotency_storage()).await?)
+    }
+
+    pub async fn features_storage(&self) -> Result<LmdbStorage> {
+        Ok(LmdbStorage::new(self.paths.features_storage()).await?)
+    }
+
+    pub async fn token_leases_storage(&self) -> Result<LmdbStorage> {
+        Ok(LmdbStorage::new(self.paths.token_leases_storage()).await?)
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
@@ -309,6 +498,57 @@ pub struct NodeSetupConfig {
     pub authority_node: Option<bool>,
     pub project: Option<ProjectLookup>,
     pub api_transport: Option<CreateTransportJson>,
+
+    /// Times of the node creation and of the last start and stop of its process,
+    /// in seconds since the Unix epoch.
+    /// The fields might be missing in previous configuration files
+    pub created_at: Option<u64>,
+    pub last_started_at: Option<u64>,
+    pub last_stopped_at: Option<u64>,
+
+    /// Which requests the node manager accepts, kept when the node is restarted.
+    /// The field might be missing in previous configuration files
+    pub management_access: Option<ManagementAccess>,
+
+    /// Networks, in the CIDR notation, from which the api transport accepts connections.
+    /// Every peer is accepted if there is none.
+    /// The field might be missing in previous configuration files
+    #[serde(default)]
+    pub api_allowed_networks: Vec<String>,
+
+    /// Attribute, written `key=value`, of the identities allowed to open the api transport
+    /// with a knock, if it is protected by port knocking.
+    /// The field might be missing in previous configuration files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_knock_attribute: Option<String>,
+
+    /// Name of the identity used by the node to create secure channels, and the clients
+    /// of the controller, authorities and projects, when a request does not name one.
+    /// The node uses its own identity if there is none.
+    /// The field might be missing in previous configuration files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_identity: Option<String>,
+
+    /// Compress the messages of the secure channels created by the node, and of the
+    /// secure channels accepted by its default listener, when both sides agree on it.
+    /// The field might be missing in previous configuration files
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub secure_channel_compression: bool,
+
+    /// Share the TCP connections of the node between the secure channels and the portals
+    /// going to the same peer. The field might be missing in previous configuration files
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub share_tcp_connections: bool,
+
+    /// Environment variables, working directory and additional arguments of the node process,
+    /// recorded when the node is created and reused every time its process is started.
+    /// The fields might be missing in previous configuration files
+    #[serde(default, skip_serializing_if = "NodeEnvironment::is_empty")]
+    pub environment: NodeEnvironment,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub working_directory: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_args: Vec<String>,
 }
 
 impl NodeSetupConfig {
@@ -332,6 +572,56 @@ impl NodeSetupConfig {
         self
     }
 
+    pub fn set_management_access(mut self, management_access: ManagementAccess) -> Self {
+        self.management_access = Some(management_access);
+        self
+    }
+
+    pub fn set_api_allowed_networks(mut self, networks: Vec<String>) -> Self {
+        self.api_allowed_networks = networks;
+        self
+    }
+
+    pub fn set_api_knock_attribute(mut self, attribute: Option<String>) -> Self {
+        self.api_knock_attribute = attribute;
+        self
+    }
+
+    pub fn set_client_identity(mut self, identity_name: Option<String>) -> Self {
+        self.client_identity = identity_name;
+        self
+    }
+
+    pub fn set_secure_channel_compression(mut self, compression: bool) -> Self {
+        self.secure_channel_compression = compression;
+        self
+    }
+
+    pub fn set_share_tcp_connections(mut self, share: bool) -> Self {
+        self.share_tcp_connections = share;
+        self
+    }
+
+    pub fn set_environment(mut self, environment: NodeEnvironment) -> Self {
+        self.environment = environment;
+        self
+    }
+
+    pub fn set_working_directory(mut self, working_directory: PathBuf) -> Self {
+        self.working_directory = Some(working_directory);
+        self
+    }
+
+    pub fn set_extra_args(mut self, extra_args: Vec<String>) -> Self {
+        self.extra_args = extra_args;
+        self
+    }
+
+    pub fn set_started(mut self) -> Self {
+        self.last_started_at = Some(now_in_seconds());
+        self
+    }
+
     pub fn api_transport(&self) -> Result<&CreateTransportJson> {
         self.api_transport.as_ref().ok_or_else(|| {
             CliStateError::InvalidOperation(
@@ -341,6 +631,113 @@ impl NodeSetupConfig {
     }
 }
 
+/// Environment variables of a node process.
+///
+/// Their values are not stored in plaintext: they are encrypted with a key derived from a
+/// static X25519 key of the vault of the node, and can only be decrypted with that vault
+#[derive(Serialize, Deserialize, Debug, Clone, Default, Eq, PartialEq)]
+pub struct NodeEnvironment {
+    /// Public key of the vault key the values are encrypted with, hex encoded
+    key: String,
+    /// Nonce and encrypted value of each variable, hex encoded
+    variables: BTreeMap<String, String>,
+}
+
+impl NodeEnvironment {
+    /// Length of the nonce prepended to each encrypted value
+    const NONCE_LENGTH: usize = 12;
+
+    /// Encrypt the values of some environment variables with a new key of a vault
+    pub async fn encrypt(
+        vault: &Vault,
+        variables: &BTreeMap<String, String>,
+    ) -> ockam_core::Result<Self> {
+        let vault = &vault.secure_channel_vault;
+        let key = vault.generate_static_x25519_secret_key().await?;
+        let public_key = vault.get_x25519_public_key(&key).await?;
+        let aead_key = Self::aead_key(vault, &key, &public_key).await?;
+
+        let mut encrypted = BTreeMap::new();
+        for (name, value) in variables {
+            let mut nonce = [0u8; Self::NONCE_LENGTH];
+            thread_rng().fill_bytes(&mut nonce);
+            let mut value = vault
+                .aead_encrypt(&aead_key, value.as_bytes(), &nonce, name.as_bytes())
+                .await?;
+            value.splice(0..0, nonce);
+            encrypted.insert(name.clone(), hex::encode(value));
+        }
+        vault.delete_aead_secret_key(aead_key).await?;
+
+        Ok(Self {
+            key: hex::encode(public_key.0),
+            variables: encrypted,
+        })
+    }
+
+    /// Decrypt the values of the environment variables with the vault they were encrypted with
+    pub async fn decrypt(&self, vault: &Vault) -> ockam_core::Result<BTreeMap<String, String>> {
+        if self.is_empty() {
+            return Ok(BTreeMap::new());
+        }
+        let invalid = || ApiError::core("The environment of the node is invalid");
+        let vault = &vault.secure_channel_vault;
+        let public_key = X25519PublicKey(
+            hex::decode(&self.key)
+                .ok()
+                .and_then(|key| key.try_into().ok())
+                .ok_or_else(invalid)?,
+        );
+        let key = vault.get_x25519_secret_key_handle(&public_key).await?;
+        let aead_key = Self::aead_key(vault, &key, &public_key).await?;
+
+        let mut variables = BTreeMap::new();
+        for (name, value) in &self.variables {
+            let value = hex::decode(value).map_err(|_| invalid())?;
+            if value.len() < Self::NONCE_LENGTH {
+                return Err(invalid());
+            }
+            let (nonce, value) = value.split_at(Self::NONCE_LENGTH);
+            let value = vault
+                .aead_decrypt(&aead_key, value, nonce, name.as_bytes())
+                .await?;
+            variables.insert(
+                name.clone(),
+                String::from_utf8(value).map_err(|_| invalid())?,
+            );
+        }
+        vault.delete_aead_secret_key(aead_key).await?;
+        Ok(variables)
+    }
+
+    /// Names of the environment variables
+    pub fn names(&self) -> impl Iterator<Item = &String> {
+        self.variables.keys()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.variables.is_empty()
+    }
+
+    /// Derive the key encrypting the values from the static key of the environment
+    async fn aead_key(
+        vault: &Arc<dyn VaultForSecureChannels>,
+        key: &X25519SecretKeyHandle,
+        public_key: &X25519PublicKey,
+    ) -> ockam_core::Result<AeadSecretKeyHandle> {
+        let secret = vault.x25519_ecdh(key, public_key).await?;
+        let output = vault.hkdf(&secret, None, HKDFNumberOfOutputs::Two).await?;
+        vault.delete_secret_buffer(secret).await?;
+        let [aead_key, unused]: [SecretBufferHandle; 2] = output
+            .0
+             .0
+            .try_into()
+            .map_err(|_| ApiError::core("Cannot derive the key of the environment"))?;
+        vault.delete_secret_buffer(unused).await?;
+        vault.convert_secret_buffer_to_aead_key(aead_key).await
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 struct NodePaths {
     path: PathBuf,
@@ -381,9 +778,61 @@ impl NodePaths {
         self.path.join("stderr.log")
     }
 
+    fn policy_audit_log(&self) -> PathBuf {
+        self.path.join("policy_audit.log")
+    }
+
     fn policies_storage(&self) -> PathBuf {
         self.path.join("policies_storage.lmdb")
     }
+
+    fn usage_storage(&self) -> PathBuf {
+        self.path.join("usage_storage.lmdb")
+    }
+
+    fn peers_storage(&self) -> PathBuf {
+        self.path.join("peers_storage.lmdb")
+    }
+
+    fn probes_storage(&self) -> PathBuf {
+        self.path.join("probes_storage.lmdb")
+    }
+
+    fn credentials_cache_storage(&self) -> PathBuf {
+        self.path.join("credentials_cache.lmdb")
+    }
+
+    fn static_routes_storage(&self) -> PathBuf {
+        self.path.join("static_routes_storage.lmdb")
+    }
+
+    fn outlet_routes_storage(&self) -> PathBuf {
+        self.path.join("outlet_routes_storage.lmdb")
+    }
+
+    fn groups_storage(&self) -> PathBuf {
+        self.path.join("groups_storage.lmdb")
+    }
+
+    fn schedules_storage(&self) -> PathBuf {
+        self.path.join("schedules_storage.lmdb")
+    }
+
+    fn stats_storage(&self) -> PathBuf {
+        self.path.join("stats_storage.lmdb")
+    }
+
+    fn idempotency_storage(&self) -> PathBuf {
+        self.path.join("idempotency_storage.lmdb")
+    }
+
+    fn features_storage(&self) -> PathBuf {
+        self.path.join("features_storage.lmdb")
+    }
+
+    fn token_leases_storage(&self) -> PathBuf {
+        self.path.join("token_leases_storage.lmdb")
+    }
 }
 
 mod backwards_compatibility {
@@ -473,7 +922,18 @@ mod traits {
         }
 
         fn delete(&self, name: impl AsRef<str>) -> Result<()> {
-            self._delete(&name, false)
+            self._delete(&name, false, false)
+        }
+
+        fn soft_delete(&self, name: impl AsRef<str>) -> Result<()> {
+            self._delete(&name, false, true)
+        }
+
+        fn restore(&self, name: impl AsRef<str>) -> Result<NodeState> {
+            let ports = self.released_ports(name.as_ref());
+            let node = self.restore_from_trash(&name)?;
+            self.reserve_released_ports(name.as_ref(), ports);
+            Ok(node)
         }
 
         async fn migrate(&self, node_path: &Path) -> Result<()> {
@@ -492,6 +952,7 @@ mod traits {
                         authority_node: setup.authority_node,
                         project: setup.project,
                         api_transport: None,
+                        ..Default::default()
                     };
                     if let Some(t) = setup
                         .transports
@@ -516,6 +977,7 @@ mod traits {
             std::fs::create_dir_all(&path)?;
             let paths = NodePaths::new(&path);
             let name = file_stem(&path)?;
+            config.setup.created_at.get_or_insert_with(now_in_seconds);
             std::fs::write(paths.setup(), serde_json::to_string(config.setup())?)?;
             std::fs::write(paths.version(), config.version.to_string())?;
             let _ = std::fs::remove_file(paths.vault());
@@ -571,6 +1033,13 @@ mod traits {
     }
 }
 
+fn now_in_seconds() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
 pub async fn init_node_state(
     cli_state: &CliState,
     node_name: &str,
@@ -631,8 +1100,10 @@ pub async fn add_project_info_to_node_state(
                     CliStateError::InvalidData(format!("Failed to read project: {}", e))
                 })?;
             let proj_config = ProjectConfig::from(&proj_info);
-            let state = cli_state.nodes.get(node_name)?;
-            state.set_setup(state.config().setup_mut().set_project(proj_lookup.clone()))?;
+            cli_state
+                .nodes
+                .get(node_name)?
+                .set_project(proj_lookup.clone())?;
             cli_state
                 .projects
                 .overwrite(proj_lookup.name, proj_config)?;
@@ -703,6 +1174,24 @@ mod tests {
         assert_eq!(config.transports.len(), 1);
     }
 
+    #[test]
+    fn node_config_setup_stores_an_ipv6_api_transport() {
+        let transport =
+            CreateTransportJson::new(TransportType::Tcp, TransportMode::Listen, "[::1]:1020")
+                .unwrap();
+        assert!(transport.addr.is_ipv6());
+
+        let json = serde_json::to_string(&transport).unwrap();
+        assert_eq!(
+            json,
+            r#"{"tt":"Tcp","tm":"Listen","addr":{"V6":"[::1]:1020"}}"#
+        );
+        let parsed = serde_json::from_str::<CreateTransportJson>(&json).unwrap();
+        assert_eq!(parsed, transport);
+        assert_eq!(parsed.addr.to_string(), "[::1]:1020");
+        assert_eq!(parsed.maddr().unwrap().to_string(), "/ip6/::1/tcp/1020");
+    }
+
     #[tokio::test]
     async fn migrate_node_config_from_v1_to_v2() {
         // Create a v1 setup.json file
@@ -736,4 +1225,139 @@ mod tests {
             })
         );
     }
+
+    #[tokio::test]
+    async fn node_process_setup_is_optional() {
+        // the process setup is missing from previous configuration files
+        let setup: NodeSetupConfig = serde_json::from_str(r#"{"verbose":1}"#).unwrap();
+        assert!(setup.environment.is_empty());
+        assert_eq!(setup.working_directory, None);
+        assert!(setup.extra_args.is_empty());
+
+        let variables = BTreeMap::from([("RUST_LOG".to_string(), "debug".to_string())]);
+        let environment = NodeEnvironment::encrypt(&Vault::create(), &variables)
+            .await
+            .unwrap();
+        let setup = setup
+            .set_environment(environment)
+            .set_working_directory(PathBuf::from("/tmp"))
+            .set_extra_args(vec!["--shutdown-timeout".to_string(), "1s".to_string()]);
+        let json = serde_json::to_string(&setup).unwrap();
+        assert_eq!(
+            serde_json::from_str::<NodeSetupConfig>(&json).unwrap(),
+            setup
+        );
+    }
+
+    #[tokio::test]
+    async fn node_environment_is_encrypted() {
+        let vault = Vault::create();
+        let variables = BTreeMap::from([
+            ("API_TOKEN".to_string(), "secret-token".to_string()),
+            ("REGION".to_string(), "eu-west-3".to_string()),
+        ]);
+        let environment = NodeEnvironment::encrypt(&vault, &variables).await.unwrap();
+
+        // only the names of the variables are stored in plaintext
+        let json = serde_json::to_string(&environment).unwrap();
+        assert!(json.contains("API_TOKEN"));
+        assert!(!json.contains("secret-token"));
+        assert!(!json.contains("eu-west-3"));
+        assert_eq!(
+            environment.names().collect::<Vec<_>>(),
+            vec!["API_TOKEN", "REGION"]
+        );
+
+        // the values can only be decrypted with the vault of the node
+        let decrypted: NodeEnvironment = serde_json::from_str(&json).unwrap();
+        assert_eq!(decrypted.decrypt(&vault).await.unwrap(), variables);
+        assert!(decrypted.decrypt(&Vault::create()).await.is_err());
+    }
+
+    #[test]
+    fn list_nodes_sorted_by_timestamps() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let nodes_state = NodesState::new(tmp_dir.path());
+        for (name, created_at, last_started_at) in
+            [("n1", 30, Some(10)), ("n2", 10, None), ("n3", 20, Some(5))]
+        {
+            let node_dir = nodes_state.path(name);
+            std::fs::create_dir_all(&node_dir).unwrap();
+            let setup = NodeSetupConfig {
+                created_at: Some(created_at),
+                last_started_at,
+                ..Default::default()
+            };
+            let paths = NodePaths::new(&node_dir);
+            std::fs::write(paths.setup(), serde_json::to_string(&setup).unwrap()).unwrap();
+            std::fs::write(paths.version(), ConfigVersion::latest().to_string()).unwrap();
+        }
+
+        let names = |key| -> Vec<String> {
+            nodes_state
+                .list_sorted(key)
+                .unwrap()
+                .iter()
+                .map(|n| n.name().to_string())
+                .collect()
+        };
+        assert_eq!(names(NodeSortKey::Name), vec!["n1", "n2", "n3"]);
+        assert_eq!(names(NodeSortKey::CreatedAt), vec!["n2", "n3", "n1"]);
+        assert_eq!(names(NodeSortKey::LastStartedAt), vec!["n2", "n3", "n1"]);
+        assert_eq!(names(NodeSortKey::LastStoppedAt), vec!["n1", "n2", "n3"]);
+        assert_eq!(
+            "started".parse::<NodeSortKey>(),
+            Ok(NodeSortKey::LastStartedAt)
+        );
+    }
+
+    #[test]
+    fn set_node_project() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let nodes_state = NodesState::new(tmp_dir.path());
+        let node_dir = nodes_state.path("n1");
+        std::fs::create_dir_all(&node_dir).unwrap();
+        let paths = NodePaths::new(&node_dir);
+        let setup = NodeSetupConfig::default();
+        std::fs::write(paths.setup(), serde_json::to_string(&setup).unwrap()).unwrap();
+        std::fs::write(paths.version(), ConfigVersion::latest().to_string()).unwrap();
+
+        let node = nodes_state.get("n1").unwrap();
+        assert_eq!(node.project().unwrap(), None);
+
+        let project = ProjectLookup {
+            node_route: Some("/dnsaddr/localhost/tcp/4000".parse().unwrap()),
+            id: "project-id".to_string(),
+            name: "default".to_string(),
+            identity_id: None,
+            authority: None,
+            okta: None,
+        };
+        node.set_project(project.clone()).unwrap();
+        assert_eq!(node.project().unwrap(), Some(project.clone()));
+        // the association is kept when the node state is loaded again
+        let node = nodes_state.get("n1").unwrap();
+        assert_eq!(node.config().setup().project, Some(project));
+    }
+
+    #[test]
+    fn set_node_client_identity() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let nodes_state = NodesState::new(tmp_dir.path());
+        let node_dir = nodes_state.path("n1");
+        std::fs::create_dir_all(&node_dir).unwrap();
+        let paths = NodePaths::new(&node_dir);
+        let setup = NodeSetupConfig::default();
+        std::fs::write(paths.setup(), serde_json::to_string(&setup).unwrap()).unwrap();
+        std::fs::write(paths.version(), ConfigVersion::latest().to_string()).unwrap();
+
+        let node = nodes_state.get("n1").unwrap();
+        assert_eq!(node.client_identity().unwrap(), None);
+
+        node.set_client_identity(Some("alice".to_string())).unwrap();
+        assert_eq!(node.client_identity().unwrap(), Some("alice".to_string()));
+
+        node.set_client_identity(None).unwrap();
+        assert_eq!(node.client_identity().unwrap(), None);
+    }
 }
@@ -0,0 +1,195 @@
+use super::Result;
+use crate::cli_state::{CliState, CliStateError};
+use fs2::FileExt;
+use ockam::identity::utils::now;
+use rand::random;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::path::PathBuf;
+
+/// Name of the file storing the operations queued while the Orchestrator is unreachable
+pub(super) const OPERATIONS_QUEUE_FILENAME: &str = "operations_queue.json";
+
+/// Name of the file locked while the queue is read or modified, so that several commands
+/// can access the queue concurrently. The queue file itself is not locked since it is
+/// replaced when it is saved
+pub(super) const OPERATIONS_QUEUE_LOCK_FILENAME: &str = "operations_queue.json.lock";
+
+/// An operation which could not be sent to the Orchestrator because it was unreachable.
+///
+/// The operation is replayed with the same idempotency key until it is acknowledged,
+/// so that it is applied only once even if a previous attempt reached the Orchestrator
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct QueuedOperation {
+    /// Idempotency key of the operation, also used to identify it in the queue
+    pub id: String,
+    /// Description of the operation, for example `disable the addon okta`
+    pub description: String,
+    /// Orchestrator service receiving the request
+    pub api_service: String,
+    /// CBOR encoding of the request, hex encoded
+    request: String,
+    /// Time when the operation was queued, in seconds since the Unix epoch
+    pub queued_at: u64,
+    /// Number of attempts to replay the operation
+    pub attempts: u32,
+    /// Error returned by the last attempt
+    pub last_error: Option<String>,
+}
+
+impl QueuedOperation {
+    /// Create a new operation with a random idempotency key
+    pub fn new(
+        description: impl Into<String>,
+        api_service: impl Into<String>,
+        request: &[u8],
+    ) -> Result<Self> {
+        Ok(Self {
+            id: hex::encode(random::<[u8; 16]>()),
+            description: description.into(),
+            api_service: api_service.into(),
+            request: hex::encode(request),
+            queued_at: *now()?,
+            attempts: 0,
+            last_error: None,
+        })
+    }
+
+    /// Return the CBOR encoding of the request
+    pub fn request(&self) -> Result<Vec<u8>> {
+        hex::decode(&self.request).map_err(|e| CliStateError::InvalidData(e.to_string()))
+    }
+}
+
+impl CliState {
+    /// Append an operation to the queue of operations to replay
+    pub fn enqueue_operation(&self, operation: &QueuedOperation) -> Result<()> {
+        let _lock = self.lock_operations_queue(true)?;
+        let mut operations = self.read_queued_operations()?;
+        operations.push(operation.clone());
+        self.save_queued_operations(&operations)
+    }
+
+    /// Return the queued operations, the oldest first
+    pub fn queued_operations(&self) -> Result<Vec<QueuedOperation>> {
+        let _lock = self.lock_operations_queue(false)?;
+        self.read_queued_operations()
+    }
+
+    /// Update a queued operation, for example after a failed attempt
+    pub fn update_queued_operation(&self, operation: &QueuedOperation) -> Result<()> {
+        let _lock = self.lock_operations_queue(true)?;
+        let mut operations = self.read_queued_operations()?;
+        match operations.iter_mut().find(|o| o.id == operation.id) {
+            Some(o) => *o = operation.clone(),
+            None => {
+                return Err(CliStateError::ResourceNotFound {
+                    resource: "queued operation".to_string(),
+                    name: operation.id.clone(),
+                })
+            }
+        }
+        self.save_queued_operations(&operations)
+    }
+
+    /// Remove an operation from the queue. Return false if there was no such operation
+    pub fn remove_queued_operation(&self, id: &str) -> Result<bool> {
+        let _lock = self.lock_operations_queue(true)?;
+        let mut operations = self.read_queued_operations()?;
+        let count = operations.len();
+        operations.retain(|o| o.id != id);
+        if operations.len() == count {
+            return Ok(false);
+        }
+        self.save_queued_operations(&operations)?;
+        Ok(true)
+    }
+
+    fn read_queued_operations(&self) -> Result<Vec<QueuedOperation>> {
+        match std::fs::read_to_string(self.operations_queue_path()) {
+            Ok(contents) => Ok(serde_json::from_str(&contents)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(vec![]),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Write the queue to a temporary file first, then rename it, so that the queue
+    /// is never left half-written
+    fn save_queued_operations(&self, operations: &[QueuedOperation]) -> Result<()> {
+        let contents = serde_json::to_string_pretty(operations)?;
+        let path = self.operations_queue_path();
+        let temp_path = path.with_extension("json.tmp");
+        std::fs::write(&temp_path, contents)?;
+        std::fs::rename(temp_path, path)?;
+        Ok(())
+    }
+
+    /// Lock the queue until the returned file is dropped. The lock is exclusive
+    /// when the queue is modified, and shared when it is only read
+    fn lock_operations_queue(&self, exclusive: bool) -> Result<File> {
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .open(self.dir.join(OPERATIONS_QUEUE_LOCK_FILENAME))?;
+        if exclusive {
+            file.lock_exclusive()?;
+        } else {
+            file.lock_shared()?;
+        }
+        Ok(file)
+    }
+
+    fn operations_queue_path(&self) -> PathBuf {
+        self.dir.join(OPERATIONS_QUEUE_FILENAME)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operations_queue() -> Result<()> {
+        let state = CliState::test()?;
+        let first = QueuedOperation::new("first", "projects", &[1, 2])?;
+        let mut second = QueuedOperation::new("second", "projects", &[3])?;
+        state.enqueue_operation(&first)?;
+        state.enqueue_operation(&second)?;
+        assert_ne!(first.id, second.id);
+        assert_eq!(
+            state.queued_operations()?,
+            vec![first.clone(), second.clone()]
+        );
+        assert_eq!(state.queued_operations()?[0].request()?, vec![1, 2]);
+
+        second.attempts += 1;
+        second.last_error = Some("unreachable".to_string());
+        state.update_queued_operation(&second)?;
+        assert!(state.remove_queued_operation(&first.id)?);
+        assert!(!state.remove_queued_operation(&first.id)?);
+        assert_eq!(state.queued_operations()?, vec![second]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_concurrent_enqueue_operations() -> Result<()> {
+        let state = CliState::test()?;
+        let handles: Vec<_> = (0..8u8)
+            .map(|i| {
+                let state = state.clone();
+                std::thread::spawn(move || {
+                    let operation =
+                        QueuedOperation::new(format!("operation {i}"), "projects", &[i])?;
+                    state.enqueue_operation(&operation)
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap()?;
+        }
+        // no operation is lost when several commands enqueue operations at the same time
+        assert_eq!(state.queued_operations()?.len(), 8);
+        Ok(())
+    }
+}
@@ -0,0 +1,332 @@
+use super::Result;
+use crate::cli_state::{CliStateError, NodesState, StateDirTrait};
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::net::{SocketAddr, TcpListener};
+use std::path::PathBuf;
+
+/// Name of the file storing the port reservations, in the nodes directory
+const PORTS_FILENAME: &str = "ports.json";
+
+/// Name of the file locked while the port reservations are read or modified, so that
+/// several commands can reserve ports concurrently
+const PORTS_LOCK_FILENAME: &str = "ports.json.lock";
+
+/// Name of the file keeping the ports of a soft-deleted node, in its trash directory
+const RELEASED_PORTS_FILENAME: &str = "ports.json";
+
+/// Maximum number of attempts to find a free port which is not reserved by another node
+const MAX_ALLOCATION_ATTEMPTS: usize = 16;
+
+/// Ports reserved by each node, indexed by the purpose of the port,
+/// for example `tcp-inlet:my-db` for the bind address of an inlet.
+///
+/// The reservations are kept when a node is stopped so that a restarted node
+/// binds the same ports, and are released when the node is deleted
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PortReservations {
+    #[serde(flatten)]
+    nodes: BTreeMap<String, BTreeMap<String, SocketAddr>>,
+}
+
+impl PortReservations {
+    /// Return the address reserved by a node for a given purpose
+    pub fn get(&self, node: &str, purpose: &str) -> Option<SocketAddr> {
+        self.nodes.get(node)?.get(purpose).copied()
+    }
+
+    /// Return the node and the purpose of the reservation of a port, excluding
+    /// the reservation of `node` for `purpose`
+    fn reserved_by(&self, port: u16, node: &str, purpose: &str) -> Option<(&str, &str)> {
+        self.nodes.iter().find_map(|(n, ports)| {
+            ports.iter().find_map(|(p, address)| {
+                (address.port() == port && !(n == node && p == purpose))
+                    .then_some((n.as_str(), p.as_str()))
+            })
+        })
+    }
+
+    fn insert(&mut self, node: &str, purpose: &str, address: SocketAddr) {
+        let ports = self.nodes.entry(node.to_string()).or_default();
+        // a port is only used for one purpose at a time on a given node
+        ports.retain(|p, a| p == purpose || a.port() != address.port());
+        ports.insert(purpose.to_string(), address);
+    }
+}
+
+impl NodesState {
+    /// Return an address for a node to bind for a given purpose.
+    ///
+    /// The address previously reserved by the node for that purpose is returned if its port
+    /// is still free. Otherwise a new free port, not reserved by another node, is reserved
+    pub fn allocate_port(&self, node: &str, purpose: &str) -> Result<SocketAddr> {
+        let _lock = self.lock_port_reservations(true)?;
+        let mut reservations = self.read_port_reservations()?;
+        if let Some(address) = reservations.get(node, purpose) {
+            if is_port_free(&address) {
+                return Ok(address);
+            }
+            warn!(%node, %purpose, %address, "the previously reserved port is not free anymore");
+        }
+
+        for _ in 0..MAX_ALLOCATION_ATTEMPTS {
+            let address = TcpListener::bind("127.0.0.1:0")?.local_addr()?;
+            if reservations
+                .reserved_by(address.port(), node, purpose)
+                .is_none()
+            {
+                reservations.insert(node, purpose, address);
+                self.save_port_reservations(&reservations)?;
+                debug!(%node, %purpose, %address, "port reserved");
+                return Ok(address);
+            }
+        }
+        Err(CliStateError::InvalidOperation(
+            "Unable to find a free port which is not reserved by another node".to_string(),
+        ))
+    }
+
+    /// Reserve an address chosen by the user for a node.
+    ///
+    /// This fails if the port is reserved by another node, or if it is already in use
+    pub fn reserve_port(&self, node: &str, purpose: &str, address: &SocketAddr) -> Result<()> {
+        let _lock = self.lock_port_reservations(true)?;
+        let mut reservations = self.read_port_reservations()?;
+        if let Some((other_node, other_purpose)) =
+            reservations.reserved_by(address.port(), node, purpose)
+        {
+            if other_node != node {
+                return Err(CliStateError::InvalidOperation(format!(
+                    "The port {} is reserved by the node {other_node} for {other_purpose}",
+                    address.port()
+                )));
+            }
+        }
+        if !is_port_free(address) {
+            return Err(CliStateError::InvalidOperation(format!(
+                "Another process is already listening on port {}",
+                address.port()
+            )));
+        }
+        reservations.insert(node, purpose, *address);
+        self.save_port_reservations(&reservations)
+    }
+
+    /// Put back the reservation of a node for a given purpose, as it was before a call to
+    /// [`NodesState::reserve_port`], for example when the node could not bind the new port.
+    ///
+    /// The port is not checked since the node may still be listening on it
+    pub fn restore_port_reservation(
+        &self,
+        node: &str,
+        purpose: &str,
+        previous: Option<SocketAddr>,
+    ) -> Result<()> {
+        let _lock = self.lock_port_reservations(true)?;
+        let mut reservations = self.read_port_reservations()?;
+        match previous {
+            Some(address) => reservations.insert(node, purpose, address),
+            None => {
+                if let Some(ports) = reservations.nodes.get_mut(node) {
+                    ports.remove(purpose);
+                }
+            }
+        }
+        self.save_port_reservations(&reservations)
+    }
+
+    /// Release all the ports reserved by a node
+    pub fn release_ports(&self, node: &str) -> Result<()> {
+        let _lock = self.lock_port_reservations(true)?;
+        let mut reservations = self.read_port_reservations()?;
+        if reservations.nodes.remove(node).is_some() {
+            self.save_port_reservations(&reservations)?;
+        }
+        Ok(())
+    }
+
+    /// Keep the ports reserved by a node moved to the trash, so that they are reserved
+    /// again if the node is restored
+    pub(super) fn keep_released_ports(&self, node: &str) -> Result<()> {
+        if let Some(ports) = self.port_reservations()?.nodes.get(node) {
+            std::fs::write(
+                self.released_ports_path(node),
+                serde_json::to_string_pretty(ports)?,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Return the ports reserved by a node before it was moved to the trash
+    pub(super) fn released_ports(&self, node: &str) -> BTreeMap<String, SocketAddr> {
+        std::fs::read_to_string(self.released_ports_path(node))
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Reserve again the ports of a restored node. A port which was reserved by another node,
+    /// or used by another process, in the meantime is not reserved: a new port is allocated
+    /// when the node needs it
+    pub(super) fn reserve_released_ports(&self, node: &str, ports: BTreeMap<String, SocketAddr>) {
+        for (purpose, address) in ports {
+            if let Err(e) = self.reserve_port(node, &purpose, &address) {
+                warn!(%node, %purpose, %address, "the port of the restored node can not be reserved again: {e}");
+            }
+        }
+    }
+
+    /// Return the ports reserved by all the nodes
+    pub fn port_reservations(&self) -> Result<PortReservations> {
+        let _lock = self.lock_port_reservations(false)?;
+        self.read_port_reservations()
+    }
+
+    fn read_port_reservations(&self) -> Result<PortReservations> {
+        match std::fs::read_to_string(self.port_reservations_path()) {
+            Ok(contents) => Ok(serde_json::from_str(&contents)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(PortReservations::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Write the reservations to a temporary file first, then rename it, so that they
+    /// are never read half-written
+    fn save_port_reservations(&self, reservations: &PortReservations) -> Result<()> {
+        let contents = serde_json::to_string_pretty(reservations)?;
+        let path = self.port_reservations_path();
+        let temp_path = path.with_extension("json.tmp");
+        std::fs::write(&temp_path, contents)?;
+        std::fs::rename(temp_path, path)?;
+        Ok(())
+    }
+
+    /// Lock the port reservations until the returned file is dropped. The lock is exclusive
+    /// when the reservations are modified, and shared when they are only read
+    fn lock_port_reservations(&self, exclusive: bool) -> Result<File> {
+        std::fs::create_dir_all(self.dir())?;
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .open(self.dir().join(PORTS_LOCK_FILENAME))?;
+        if exclusive {
+            file.lock_exclusive()?;
+        } else {
+            file.lock_shared()?;
+        }
+        Ok(file)
+    }
+
+    fn port_reservations_path(&self) -> PathBuf {
+        self.dir().join(PORTS_FILENAME)
+    }
+
+    fn released_ports_path(&self, node: &str) -> PathBuf {
+        self.trash_dir().join(node).join(RELEASED_PORTS_FILENAME)
+    }
+}
+
+fn is_port_free(address: &SocketAddr) -> bool {
+    TcpListener::bind(address).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli_state::CliState;
+
+    #[test]
+    fn test_port_reservations() -> Result<()> {
+        let nodes = NodesState::new(&CliState::test_dir()?);
+
+        // a node gets the same address for the same purpose
+        let address = nodes.allocate_port("n1", "tcp-inlet:db")?;
+        assert_eq!(nodes.allocate_port("n1", "tcp-inlet:db")?, address);
+        assert_ne!(nodes.allocate_port("n1", "tcp-inlet:web")?, address);
+
+        // another node can not reserve that port
+        assert!(nodes.reserve_port("n2", "tcp-inlet:db", &address).is_err());
+
+        // a port which is in use is not returned again
+        let listener = TcpListener::bind(address)?;
+        assert_ne!(nodes.allocate_port("n1", "tcp-inlet:db")?, address);
+        drop(listener);
+
+        // the port can be reserved again once the node is deleted
+        nodes.release_ports("n1")?;
+        assert_eq!(nodes.port_reservations()?.get("n1", "tcp-inlet:web"), None);
+        nodes.reserve_port("n2", "tcp-inlet:db", &address)?;
+        assert_eq!(
+            nodes.port_reservations()?.get("n2", "tcp-inlet:db"),
+            Some(address)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_concurrent_port_reservations() -> Result<()> {
+        let dir = CliState::test_dir()?;
+        let nodes = NodesState::new(&dir);
+        let previous = nodes.allocate_port("n0", "tcp-inlet:db")?;
+        let address = TcpListener::bind("127.0.0.1:0")?.local_addr()?;
+
+        // only one node gets the port when several nodes try to reserve it at the same time
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let nodes = NodesState::new(&dir);
+                std::thread::spawn(move || {
+                    nodes.reserve_port(&format!("n{i}"), "tcp-inlet:db", &address)
+                })
+            })
+            .collect();
+        let reserved = handles
+            .into_iter()
+            .filter(|handle| handle.join().unwrap().is_ok())
+            .count();
+        assert_eq!(reserved, 1);
+
+        // a reservation can be put back as it was
+        let reservations = nodes.port_reservations()?;
+        let (node, _) = reservations.reserved_by(address.port(), "", "").unwrap();
+        let node = node.to_string();
+        let before = if node == "n0" { Some(previous) } else { None };
+        nodes.restore_port_reservation(&node, "tcp-inlet:db", before)?;
+        assert_eq!(
+            nodes.port_reservations()?.get(&node, "tcp-inlet:db"),
+            before
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_concurrent_port_allocations() -> Result<()> {
+        let dir = CliState::test_dir()?;
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let nodes = NodesState::new(&dir);
+                std::thread::spawn(move || nodes.allocate_port(&format!("n{i}"), "tcp-inlet:db"))
+            })
+            .collect();
+        let mut addresses = vec![];
+        for handle in handles {
+            addresses.push(handle.join().unwrap()?);
+        }
+
+        // no reservation is lost and no port is reserved twice when several commands
+        // allocate ports at the same time
+        let reservations = NodesState::new(&dir).port_reservations()?;
+        for (i, address) in addresses.iter().enumerate() {
+            assert_eq!(
+                reservations.get(&format!("n{i}"), "tcp-inlet:db"),
+                Some(*address)
+            );
+        }
+        addresses.sort();
+        addresses.dedup();
+        assert_eq!(addresses.len(), 8);
+        Ok(())
+    }
+}
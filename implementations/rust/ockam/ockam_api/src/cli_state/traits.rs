@@ -3,9 +3,24 @@ use ockam_core::errcode::{Kind, Origin};
 use ockam_core::{async_trait, Error};
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use super::Result;
 pub const DATA_DIR_NAME: &str = "data";
+pub const TRASH_DIR_NAME: &str = "trash";
+const DELETED_ITEM_NAME: &str = "item";
+const DELETED_AT_FILENAME: &str = "deleted_at";
+
+/// Time during which a soft-deleted item can be restored
+pub const DELETED_ITEMS_RETENTION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// An item which was soft-deleted and can still be restored
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DeletedItem {
+    pub name: String,
+    /// Time of the deletion, in seconds since the Unix epoch
+    pub deleted_at: u64,
+}
 
 /// Represents the directory of a type of state. This directory contains a list of items, uniquely
 /// identified by a name, and represented by the same `Item` type.
@@ -180,6 +195,123 @@ pub trait StateDirTrait: Sized + Send + Sync {
         s.delete()
     }
 
+    /// Directory keeping the soft-deleted items until they are restored or their retention expires.
+    /// It is located outside of the state directory so that it is never listed as an item
+    fn trash_dir(&self) -> PathBuf {
+        let root_path = self.dir().parent().expect("Should have parent");
+        root_path.join(TRASH_DIR_NAME).join(Self::DIR_NAME)
+    }
+
+    /// Delete an item but keep its files, so that it can be restored with `restore`
+    /// during [`DELETED_ITEMS_RETENTION`]
+    fn soft_delete(&self, name: impl AsRef<str>) -> Result<()> {
+        // Retrieve state. If doesn't exist do nothing.
+        let s = match self.get(&name) {
+            Ok(item) => item,
+            Err(CliStateError::ResourceNotFound { .. }) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        // If it's the default, remove link
+        if let Ok(default) = self.default() {
+            if default.path() == s.path() {
+                let _ = std::fs::remove_file(self.default_path()?);
+            }
+        }
+        self.move_to_trash(&name)
+    }
+
+    /// Move the files of an item to the trash, replacing a previously deleted item
+    /// with the same name
+    fn move_to_trash(&self, name: impl AsRef<str>) -> Result<()> {
+        self.purge_deleted()?;
+        let trash = self.trash_dir().join(name.as_ref());
+        let _ = std::fs::remove_dir_all(&trash);
+        std::fs::create_dir_all(&trash)?;
+        std::fs::rename(self.path(&name), trash.join(DELETED_ITEM_NAME))?;
+        std::fs::write(
+            trash.join(DELETED_AT_FILENAME),
+            now_in_seconds().to_string(),
+        )?;
+        info!(name = %name.as_ref(), "Moved config resource to the trash");
+        Ok(())
+    }
+
+    /// Restore a soft-deleted item. It fails if an item with the same name was created since
+    fn restore(&self, name: impl AsRef<str>) -> Result<Self::Item> {
+        self.restore_from_trash(name)
+    }
+
+    /// Move the files of a soft-deleted item back to the state directory
+    fn restore_from_trash(&self, name: impl AsRef<str>) -> Result<Self::Item> {
+        self.purge_deleted()?;
+        if self.exists(&name) {
+            return Err(CliStateError::AlreadyExists {
+                resource: Self::default_filename().to_string(),
+                name: name.as_ref().to_string(),
+            });
+        }
+        if !self
+            .list_deleted()?
+            .iter()
+            .any(|deleted| deleted.name == name.as_ref())
+        {
+            return Err(CliStateError::ResourceNotFound {
+                resource: format!("deleted {}", Self::default_filename()),
+                name: name.as_ref().to_string(),
+            });
+        }
+        let trash = self.trash_dir().join(name.as_ref());
+        std::fs::rename(trash.join(DELETED_ITEM_NAME), self.path(&name))?;
+        let _ = std::fs::remove_dir_all(&trash);
+        if !self.default_path()?.exists() {
+            self.set_default(&name)?;
+        }
+        info!(name = %name.as_ref(), "Restored config resource");
+        self.get(&name)
+    }
+
+    /// Return the soft-deleted items which can still be restored, the most recent deletions first
+    fn list_deleted(&self) -> Result<Vec<DeletedItem>> {
+        self.purge_deleted()?;
+        let mut items = Vec::default();
+        for (name, deleted_at) in self.deleted_items()? {
+            if let Some(deleted_at) = deleted_at.filter(|t| !is_expired(*t)) {
+                items.push(DeletedItem { name, deleted_at });
+            }
+        }
+        items.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+        Ok(items)
+    }
+
+    /// Permanently remove the soft-deleted items whose retention expired
+    fn purge_deleted(&self) -> Result<()> {
+        for (name, deleted_at) in self.deleted_items()? {
+            if deleted_at.map_or(true, is_expired) {
+                debug!(%name, "Removing expired deleted config resource");
+                let _ = std::fs::remove_dir_all(self.trash_dir().join(name));
+            }
+        }
+        Ok(())
+    }
+
+    /// Return the names of the items in the trash, with their deletion time if it can be read
+    fn deleted_items(&self) -> Result<Vec<(String, Option<u64>)>> {
+        let dir = self.trash_dir();
+        if !dir.exists() {
+            return Ok(vec![]);
+        }
+        let mut items = Vec::default();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let deleted_at = std::fs::read_to_string(path.join(DELETED_AT_FILENAME))
+                .ok()
+                .and_then(|t| t.trim().parse().ok());
+            items.push((name.to_string(), deleted_at));
+        }
+        Ok(items)
+    }
+
     fn default_path(&self) -> Result<PathBuf> {
         let root_path = self.dir().parent().expect("Should have parent");
         Ok(CliState::defaults_dir(root_path)?.join(Self::default_filename()))
@@ -238,6 +370,17 @@ pub trait StateDirTrait: Sized + Send + Sync {
     }
 }
 
+fn now_in_seconds() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+fn is_expired(deleted_at: u64) -> bool {
+    now_in_seconds().saturating_sub(deleted_at) >= DELETED_ITEMS_RETENTION.as_secs()
+}
+
 /// This trait defines the methods to retrieve an item from a state directory.
 /// The details of the item are defined in the `Config` type.
 #[async_trait]
@@ -1,4 +1,6 @@
 use super::Result;
+use crate::cli_state::StateDirTrait;
+use crate::config::bootstrap::TrustContextBootstrap;
 use crate::config::cli::TrustContextConfig;
 use std::fmt::{Display, Formatter};
 use std::path::PathBuf;
@@ -21,6 +23,21 @@ impl TrustContextState {
     }
 }
 
+impl TrustContextsState {
+    /// Create a trust context from a bootstrap URI, after verifying the identity of its
+    /// authority. When a fingerprint is provided, it must be the identifier of the authority
+    pub async fn create_from_bootstrap(
+        &self,
+        name: &str,
+        uri: &str,
+        fingerprint: Option<&str>,
+    ) -> Result<TrustContextState> {
+        let bootstrap: TrustContextBootstrap = uri.parse()?;
+        let config = bootstrap.to_config(fingerprint).await?;
+        self.create(name, config)
+    }
+}
+
 impl Display for TrustContextState {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "Name: {}", self.name)?;
@@ -1,17 +1,54 @@
 use std::fmt::{Display, Formatter};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
 
 use ockam::identity::Vault;
-use ockam_vault_aws::AwsSigningVault;
+use ockam_core::env::{get_env, get_env_with_default};
+use ockam_vault::legacy::SecretAttributes;
+use ockam_vault::storage::PersistentStorage;
+use ockam_vault::{
+    EscrowedSecret, KeyEscrowPolicy, KeyUsage, KeyUsageThreshold,
+    OffloadedVaultForVerifyingSignatures, SecretShare, SoftwareVaultForSecureChannels,
+    SoftwareVaultForSigning, VerifyingPoolOptions, X25519PublicKey,
+    DEFAULT_VERIFYING_QUEUE_CAPACITY, X25519_PUBLIC_KEY_LENGTH,
+};
+use ockam_vault_aws::{AwsKmsConfig, AwsSigningVault};
 
 use crate::cli_state::traits::StateItemTrait;
 use crate::cli_state::{CliStateError, StateDirTrait, DATA_DIR_NAME};
+use crate::error::ApiError;
 
 use super::Result;
 
+/// Environment variable setting the number of operations per hour with a key of a vault
+/// above which an alert is raised
+const OCKAM_KEY_USAGE_ALERT_THRESHOLD: &str = "OCKAM_KEY_USAGE_ALERT_THRESHOLD";
+
+/// Environment variable setting the interval, in seconds, at which the credentials used
+/// to access AWS KMS are refreshed. They are only refreshed when they are rejected if it is not set
+const OCKAM_AWS_KMS_CREDENTIALS_REFRESH_INTERVAL: &str =
+    "OCKAM_AWS_KMS_CREDENTIALS_REFRESH_INTERVAL";
+
+/// Environment variable setting the number of threads verifying signatures outside
+/// of the runtime threads. Signatures are verified inline if it is not set
+const OCKAM_VERIFYING_THREADS: &str = "OCKAM_VERIFYING_THREADS";
+
+/// Environment variable setting the maximum number of signature verifications
+/// waiting for one of the `OCKAM_VERIFYING_THREADS`
+const OCKAM_VERIFYING_QUEUE_CAPACITY: &str = "OCKAM_VERIFYING_QUEUE_CAPACITY";
+
+/// Environment variable listing the hex-encoded X25519 public keys the shares of the
+/// escrowed keys are encrypted to, for the vaults without a key escrow configuration.
+/// The keys are not escrowed if it is not set
+const OCKAM_KEY_ESCROW_RECOVERY_KEYS: &str = "OCKAM_KEY_ESCROW_RECOVERY_KEYS";
+
+/// Environment variable setting the number of recovery keys needed to recover an escrowed key.
+/// A majority of the recovery keys is needed if it is not set
+const OCKAM_KEY_ESCROW_THRESHOLD: &str = "OCKAM_KEY_ESCROW_THRESHOLD";
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct VaultsState {
     dir: PathBuf,
@@ -45,21 +82,123 @@ pub struct VaultState {
 
 impl VaultState {
     pub async fn get(&self) -> Result<Vault> {
+        let mut vault = self.create_vault().await?;
+        if let Some(parallelism) = get_env::<u64>(OCKAM_VERIFYING_THREADS)? {
+            let queue_capacity = get_env_with_default(
+                OCKAM_VERIFYING_QUEUE_CAPACITY,
+                DEFAULT_VERIFYING_QUEUE_CAPACITY as u64,
+            )?;
+            vault.verifying_vault = OffloadedVaultForVerifyingSignatures::create(
+                VerifyingPoolOptions::new(parallelism as usize, queue_capacity as usize),
+            )?;
+        }
+        Ok(vault)
+    }
+
+    async fn create_vault(&self) -> Result<Vault> {
         if self.config.aws_kms {
             let mut vault = Vault::create();
-            let aws_vault = Arc::new(AwsSigningVault::create().await?);
+            let mut config = AwsKmsConfig::default().await?;
+            if let Some(interval) = get_env::<u64>(OCKAM_AWS_KMS_CREDENTIALS_REFRESH_INTERVAL)? {
+                config = config.with_credentials_refresh_interval(Duration::from_secs(interval));
+            }
+            let aws_vault = Arc::new(AwsSigningVault::create_with_config(config).await?);
             vault.identity_vault = aws_vault.clone();
             vault.credential_vault = aws_vault;
 
             Ok(vault)
         } else {
-            let vault =
-                Vault::create_with_persistent_storage_path(self.vault_file_path().as_path())
-                    .await?;
+            let path = self.vault_file_path().as_path();
+            let vault = match self.key_escrow_policy()? {
+                Some(policy) => {
+                    Vault::create_with_persistent_storage_path_and_key_escrow(path, policy).await?
+                }
+                None => Vault::create_with_persistent_storage_path(path).await?,
+            };
+            if let (Some(key_usage), Some(max_operations)) = (
+                &vault.key_usage,
+                get_env::<u64>(OCKAM_KEY_USAGE_ALERT_THRESHOLD)?,
+            ) {
+                key_usage.set_threshold(Some(KeyUsageThreshold::new(
+                    max_operations,
+                    Duration::from_secs(3600),
+                )));
+            }
             Ok(vault)
         }
     }
 
+    /// Return the usage of the keys of the vault. The usage of the keys
+    /// stored in AWS KMS is not tracked
+    pub async fn key_usage(&self) -> Result<Vec<(String, KeyUsage)>> {
+        match self.get().await?.key_usage {
+            Some(key_usage) => Ok(key_usage.usages().await?),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Return the escrowed keys of the vault
+    pub async fn escrowed_keys(&self) -> Result<Vec<EscrowedSecret>> {
+        match self.get().await?.key_escrow {
+            Some(key_escrow) => Ok(key_escrow.escrowed_secrets().await?),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Import an escrowed key back into the vault, from the shares decrypted
+    /// by the holders of the recovery keys. Return the identifier of the key
+    pub async fn recover_key(
+        &self,
+        escrowed: &EscrowedSecret,
+        shares: &[SecretShare],
+    ) -> Result<String> {
+        if self.is_aws() {
+            return Err(ApiError::core("The keys of an AWS KMS vault can't be recovered").into());
+        }
+        let storage = PersistentStorage::create(self.vault_file_path()).await?;
+        if escrowed.attributes() == SecretAttributes::X25519 {
+            SoftwareVaultForSecureChannels::new(storage)
+                .recover_static_x25519_secret(escrowed, shares)
+                .await?;
+        } else {
+            SoftwareVaultForSigning::new(storage)
+                .recover_key(escrowed, shares)
+                .await?;
+        }
+        Ok(escrowed.key_id().clone())
+    }
+
+    /// Escrow the keys of the vault, or stop escrowing them.
+    /// The keys already in the vault are escrowed the next time the vault is opened
+    pub fn set_key_escrow(&self, key_escrow: Option<KeyEscrowConfig>) -> Result<()> {
+        if self.is_aws() {
+            return Err(ApiError::core("The keys of an AWS KMS vault can't be escrowed").into());
+        }
+        if let Some(key_escrow) = &key_escrow {
+            key_escrow.policy()?;
+        }
+        let config = VaultConfig {
+            key_escrow,
+            ..self.config.clone()
+        };
+        std::fs::write(&self.path, serde_json::to_string(&config)?)?;
+        Ok(())
+    }
+
+    /// Return the policy escrowing the keys of the vault: the policy configured for the vault,
+    /// or the policy given by the environment
+    fn key_escrow_policy(&self) -> Result<Option<KeyEscrowPolicy>> {
+        if let Some(key_escrow) = &self.config.key_escrow {
+            return Ok(Some(key_escrow.policy()?));
+        }
+        let recovery_keys = match get_env::<String>(OCKAM_KEY_ESCROW_RECOVERY_KEYS)? {
+            Some(recovery_keys) => parse_recovery_keys(recovery_keys.split(','))?,
+            None => return Ok(None),
+        };
+        let threshold = get_env::<u8>(OCKAM_KEY_ESCROW_THRESHOLD)?;
+        Ok(Some(key_escrow_policy(threshold, recovery_keys)?))
+    }
+
     fn build_data_path(name: &str, path: &Path) -> PathBuf {
         path.parent()
             .expect("Should have parent")
@@ -86,6 +225,34 @@ impl VaultState {
     }
 }
 
+/// Return a policy requiring `threshold` recovery keys to recover a key, or a majority of them
+fn key_escrow_policy(
+    threshold: Option<u8>,
+    recovery_keys: Vec<X25519PublicKey>,
+) -> Result<KeyEscrowPolicy> {
+    Ok(match threshold {
+        Some(threshold) => KeyEscrowPolicy::new(threshold, recovery_keys)?,
+        None => KeyEscrowPolicy::with_majority(recovery_keys)?,
+    })
+}
+
+fn parse_recovery_keys<'a>(
+    recovery_keys: impl IntoIterator<Item = &'a str>,
+) -> Result<Vec<X25519PublicKey>> {
+    recovery_keys
+        .into_iter()
+        .map(str::trim)
+        .filter(|k| !k.is_empty())
+        .map(|key| {
+            let bytes: [u8; X25519_PUBLIC_KEY_LENGTH] = hex::decode(key)
+                .ok()
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or_else(|| ApiError::core(format!("invalid recovery key {key}")))?;
+            Ok(X25519PublicKey(bytes))
+        })
+        .collect()
+}
+
 impl Display for VaultState {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "Name: {}", self.name)?;
@@ -105,16 +272,64 @@ impl Display for VaultState {
 pub struct VaultConfig {
     #[serde(default)]
     aws_kms: bool,
+    /// The field might be missing in previous configuration files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    key_escrow: Option<KeyEscrowConfig>,
 }
 
 impl VaultConfig {
     pub fn new(aws_kms: bool) -> Result<Self> {
-        Ok(Self { aws_kms })
+        Ok(Self {
+            aws_kms,
+            key_escrow: None,
+        })
     }
 
     pub fn is_aws(&self) -> bool {
         self.aws_kms
     }
+
+    pub fn key_escrow(&self) -> Option<&KeyEscrowConfig> {
+        self.key_escrow.as_ref()
+    }
+}
+
+/// Escrow of the keys of a vault: each key is split into one share per recovery key, and
+/// `threshold` holders of a recovery key must decrypt their share to recover the key
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct KeyEscrowConfig {
+    /// Hex-encoded X25519 public keys of the holders of the recovery keys
+    recovery_keys: Vec<String>,
+    /// Number of recovery keys needed to recover a key, a majority of them if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    threshold: Option<u8>,
+}
+
+impl KeyEscrowConfig {
+    pub fn new(recovery_keys: Vec<String>, threshold: Option<u8>) -> Result<Self> {
+        let config = Self {
+            recovery_keys,
+            threshold,
+        };
+        config.policy()?;
+        Ok(config)
+    }
+
+    pub fn recovery_keys(&self) -> &[String] {
+        &self.recovery_keys
+    }
+
+    /// Number of recovery keys needed to recover a key
+    pub fn threshold(&self) -> Result<u8> {
+        Ok(self.policy()?.threshold())
+    }
+
+    pub fn policy(&self) -> Result<KeyEscrowPolicy> {
+        key_escrow_policy(
+            self.threshold,
+            parse_recovery_keys(self.recovery_keys.iter().map(String::as_str))?,
+        )
+    }
 }
 
 mod traits {
@@ -203,6 +418,10 @@ mod traits {
             std::fs::remove_file(&self.path)?;
             std::fs::remove_file(&self.data_path)?;
             std::fs::remove_file(self.data_path.with_extension("json.lock"))?;
+            // a temporary file is left if the process stopped while compacting the usage
+            let key_usage_path = Vault::key_usage_path(&self.data_path);
+            let _ = std::fs::remove_file(key_usage_path.with_extension("jsonl.tmp"));
+            let _ = std::fs::remove_file(key_usage_path);
             Ok(())
         }
 
@@ -1,4 +1,6 @@
+use crate::cli_state::CliState;
 use crate::cloud::operation::CreateOperationResponse;
+use crate::cloud::operations_queue::QueuedReply;
 use crate::cloud::project::{InfluxDBTokenLeaseManagerConfig, OktaConfig};
 use crate::cloud::Controller;
 use miette::IntoDiagnostic;
@@ -108,6 +110,42 @@ pub trait Addons {
         project_id: String,
         addon_id: String,
     ) -> miette::Result<CreateOperationResponse>;
+
+    /// Configure the Confluent addon, or queue the operation if the Orchestrator is unreachable
+    async fn configure_confluent_addon_or_enqueue(
+        &self,
+        ctx: &Context,
+        state: &CliState,
+        project_id: String,
+        config: ConfluentConfig,
+    ) -> miette::Result<QueuedReply<CreateOperationResponse>>;
+
+    /// Configure the Okta addon, or queue the operation if the Orchestrator is unreachable
+    async fn configure_okta_addon_or_enqueue(
+        &self,
+        ctx: &Context,
+        state: &CliState,
+        project_id: String,
+        config: OktaConfig,
+    ) -> miette::Result<QueuedReply<CreateOperationResponse>>;
+
+    /// Configure the InfluxDB addon, or queue the operation if the Orchestrator is unreachable
+    async fn configure_influxdb_addon_or_enqueue(
+        &self,
+        ctx: &Context,
+        state: &CliState,
+        project_id: String,
+        config: InfluxDBTokenLeaseManagerConfig,
+    ) -> miette::Result<QueuedReply<CreateOperationResponse>>;
+
+    /// Disable an addon, or queue the operation if the Orchestrator is unreachable
+    async fn disable_addon_or_enqueue(
+        &self,
+        ctx: &Context,
+        state: &CliState,
+        project_id: String,
+        addon_id: String,
+    ) -> miette::Result<QueuedReply<CreateOperationResponse>>;
 }
 
 #[async_trait]
@@ -195,4 +233,68 @@ impl Addons for Controller {
             .success()
             .into_diagnostic()
     }
+
+    async fn configure_confluent_addon_or_enqueue(
+        &self,
+        ctx: &Context,
+        state: &CliState,
+        project_id: String,
+        config: ConfluentConfig,
+    ) -> miette::Result<QueuedReply<CreateOperationResponse>> {
+        trace!(target: TARGET, project_id, "configuring confluent addon");
+        let description = format!("configure the confluent addon of the project {project_id}");
+        let req = Request::post(format!(
+            "/v1/projects/{project_id}/configure_addon/confluent"
+        ))
+        .body(config);
+        self.ask_or_enqueue(ctx, state, &description, API_SERVICE, req)
+            .await
+    }
+
+    async fn configure_okta_addon_or_enqueue(
+        &self,
+        ctx: &Context,
+        state: &CliState,
+        project_id: String,
+        config: OktaConfig,
+    ) -> miette::Result<QueuedReply<CreateOperationResponse>> {
+        trace!(target: TARGET, project_id, "configuring okta addon");
+        let description = format!("configure the okta addon of the project {project_id}");
+        let req =
+            Request::post(format!("/v1/projects/{project_id}/configure_addon/okta")).body(config);
+        self.ask_or_enqueue(ctx, state, &description, API_SERVICE, req)
+            .await
+    }
+
+    async fn configure_influxdb_addon_or_enqueue(
+        &self,
+        ctx: &Context,
+        state: &CliState,
+        project_id: String,
+        config: InfluxDBTokenLeaseManagerConfig,
+    ) -> miette::Result<QueuedReply<CreateOperationResponse>> {
+        trace!(target: TARGET, project_id, "configuring influxdb addon");
+        let description = format!("configure the influxdb addon of the project {project_id}");
+        let req = Request::post(format!(
+            "/v1/projects/{project_id}/configure_addon/influxdb_token_lease_manager"
+        ))
+        .body(config);
+        self.ask_or_enqueue(ctx, state, &description, API_SERVICE, req)
+            .await
+    }
+
+    async fn disable_addon_or_enqueue(
+        &self,
+        ctx: &Context,
+        state: &CliState,
+        project_id: String,
+        addon_id: String,
+    ) -> miette::Result<QueuedReply<CreateOperationResponse>> {
+        trace!(target: TARGET, project_id, "disabling addon");
+        let description = format!("disable the addon {addon_id} of the project {project_id}");
+        let req = Request::post(format!("/v1/projects/{project_id}/disable_addon"))
+            .body(DisableAddon::new(addon_id));
+        self.ask_or_enqueue(ctx, state, &description, API_SERVICE, req)
+            .await
+    }
 }
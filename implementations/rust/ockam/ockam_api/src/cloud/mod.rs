@@ -4,6 +4,7 @@ pub mod addon;
 pub mod enroll;
 pub mod lease_manager;
 pub mod operation;
+pub mod operations_queue;
 pub mod project;
 pub mod secure_clients;
 pub mod share;
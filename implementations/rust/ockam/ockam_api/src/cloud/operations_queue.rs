@@ -0,0 +1,131 @@
+use miette::{miette, IntoDiagnostic};
+use minicbor::{Decode, Decoder, Encode};
+use serde::Serialize;
+use tracing::{debug, warn};
+
+use ockam_core::api::{Cbor, Method, Reply, Request, RequestHeader};
+use ockam_node::Context;
+
+use crate::cli_state::{CliState, QueuedOperation};
+use crate::cloud::Controller;
+
+/// Reply of the Orchestrator to an operation, unless the operation was queued
+/// because the Orchestrator was unreachable
+#[derive(Debug)]
+pub enum QueuedReply<T> {
+    Sent(T),
+    Queued(QueuedOperation),
+}
+
+/// Result of the replay of the queued operations
+#[derive(Debug, Default, Serialize)]
+pub struct ReplayedOperations {
+    /// Operations acknowledged by the Orchestrator
+    pub succeeded: Vec<QueuedOperation>,
+    /// Operations rejected by the Orchestrator, with its error. They are not replayed again
+    pub rejected: Vec<(QueuedOperation, String)>,
+    /// Operations still queued because the Orchestrator is unreachable
+    pub remaining: Vec<QueuedOperation>,
+}
+
+impl Controller {
+    /// Send a request to the Orchestrator.
+    ///
+    /// If the Orchestrator cannot be reached, the request is persisted in the operations queue
+    /// and can be replayed later with [`Controller::replay_queued_operations`]. The request is
+    /// sent with an idempotency key which is kept when it is replayed
+    pub async fn ask_or_enqueue<T, R>(
+        &self,
+        ctx: &Context,
+        state: &CliState,
+        description: &str,
+        api_service: &str,
+        req: Request<T>,
+    ) -> miette::Result<QueuedReply<R>>
+    where
+        T: Encode<()>,
+        R: for<'a> Decode<'a, ()>,
+    {
+        let operation =
+            QueuedOperation::new(description, api_service, &req.to_vec().into_diagnostic()?)?;
+        let req = req.idempotency_key(&operation.id);
+        match self.0.ask(ctx, api_service, req).await {
+            Ok(reply) => Ok(QueuedReply::Sent(reply.success().into_diagnostic()?)),
+            Err(err) => {
+                warn!(%err, %description, "the Orchestrator is unreachable, the operation is queued");
+                state.enqueue_operation(&operation)?;
+                Ok(QueuedReply::Queued(operation))
+            }
+        }
+    }
+
+    /// Replay the queued operations, the oldest first.
+    ///
+    /// The replay stops at the first operation which cannot be sent since the Orchestrator
+    /// is still unreachable. The operations acknowledged or rejected by the Orchestrator are
+    /// removed from the queue
+    pub async fn replay_queued_operations(
+        &self,
+        ctx: &Context,
+        state: &CliState,
+    ) -> miette::Result<ReplayedOperations> {
+        let mut replayed = ReplayedOperations::default();
+        let mut operations = state.queued_operations()?.into_iter();
+        for mut operation in operations.by_ref() {
+            debug!(id = %operation.id, description = %operation.description, "replaying an operation");
+            operation.attempts += 1;
+            match self.replay(ctx, &operation).await {
+                Ok(Reply::Successful(())) => {
+                    state.remove_queued_operation(&operation.id)?;
+                    replayed.succeeded.push(operation);
+                }
+                Ok(Reply::Failed(err, _)) => {
+                    state.remove_queued_operation(&operation.id)?;
+                    replayed.rejected.push((operation, err.to_string()));
+                }
+                Err(err) => {
+                    operation.last_error = Some(err.to_string());
+                    state.update_queued_operation(&operation)?;
+                    replayed.remaining.push(operation);
+                    break;
+                }
+            }
+        }
+        replayed.remaining.extend(operations);
+        Ok(replayed)
+    }
+
+    async fn replay(
+        &self,
+        ctx: &Context,
+        operation: &QueuedOperation,
+    ) -> miette::Result<Reply<()>> {
+        let bytes = operation.request()?;
+        let mut decoder = Decoder::new(&bytes);
+        let header: RequestHeader = decoder.decode().into_diagnostic()?;
+        let req = match header.method() {
+            Some(Method::Get) => Request::get(header.path()),
+            Some(Method::Post) => Request::post(header.path()),
+            Some(Method::Put) => Request::put(header.path()),
+            Some(Method::Delete) => Request::delete(header.path()),
+            Some(Method::Patch) => Request::patch(header.path()),
+            None => {
+                return Err(miette!(
+                    "The queued operation {} has no method",
+                    operation.id
+                ))
+            }
+        }
+        .idempotency_key(&operation.id);
+
+        let reply = if header.has_body() {
+            let body = Cbor(&bytes[decoder.position()..]);
+            self.0
+                .tell(ctx, &operation.api_service, req.body(body))
+                .await
+        } else {
+            self.0.tell(ctx, &operation.api_service, req).await
+        };
+        reply.into_diagnostic()
+    }
+}
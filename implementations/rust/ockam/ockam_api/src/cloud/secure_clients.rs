@@ -33,7 +33,7 @@ impl NodeManager {
         NodeManager::controller_node(
             &self.tcp_transport,
             self.secure_channels.clone(),
-            &self.get_identifier(None).await?,
+            &self.get_client_identifier(None).await?,
         )
         .await
     }
@@ -0,0 +1,271 @@
+//! Compact description of a trust context, used to onboard nodes on a self-hosted authority
+//!
+//! A bootstrap URI looks like:
+//!
+//! `ockam://trust-context?authority=<hex change history>&route=<multiaddr>&credential=issuer&id=<id>`
+//!
+//! Only the authority is mandatory. The route is the address of the authority credential issuer
+//! and the trust context id defaults to the identifier of the authority.
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use ockam::identity::{identities, Identifier};
+use ockam_core::Result;
+use ockam_multiaddr::MultiAddr;
+use url::Url;
+
+use crate::config::cli::{
+    CredentialIssuerConfig, CredentialRetrieverConfig, TrustAuthorityConfig, TrustContextConfig,
+};
+use crate::error::ApiError;
+
+const BOOTSTRAP_SCHEME: &str = "ockam";
+const BOOTSTRAP_HOST: &str = "trust-context";
+
+/// How the members of a trust context obtain their own credential
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CredentialScheme {
+    /// The credential is issued by the authority, at its route
+    Issuer,
+    /// The members don't present a credential, they only verify the credentials of others
+    None,
+}
+
+impl FromStr for CredentialScheme {
+    type Err = ockam_core::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "issuer" => Ok(CredentialScheme::Issuer),
+            "none" => Ok(CredentialScheme::None),
+            _ => Err(ApiError::core(format!(
+                "the credential scheme {s} is not supported, it must be one of: issuer, none"
+            ))),
+        }
+    }
+}
+
+impl Display for CredentialScheme {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CredentialScheme::Issuer => write!(f, "issuer"),
+            CredentialScheme::None => write!(f, "none"),
+        }
+    }
+}
+
+/// Everything needed to configure a trust context, parsed from or written as a bootstrap URI
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrustContextBootstrap {
+    id: Option<String>,
+    authority_identity: String,
+    authority_route: Option<MultiAddr>,
+    credential: CredentialScheme,
+}
+
+impl TrustContextBootstrap {
+    /// Create a bootstrap for an authority, given as its hex-encoded change history.
+    /// The credentials are issued by the authority when its route is known
+    pub fn new(authority_identity: impl Into<String>, authority_route: Option<MultiAddr>) -> Self {
+        let credential = if authority_route.is_some() {
+            CredentialScheme::Issuer
+        } else {
+            CredentialScheme::None
+        };
+        Self {
+            id: None,
+            authority_identity: authority_identity.into(),
+            authority_route,
+            credential,
+        }
+    }
+
+    /// Set the id of the trust context, instead of the identifier of the authority
+    pub fn with_id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    pub fn credential_scheme(&self) -> CredentialScheme {
+        self.credential
+    }
+
+    pub fn authority_route(&self) -> Option<&MultiAddr> {
+        self.authority_route.as_ref()
+    }
+
+    /// Import the authority identity, which verifies its change history, and return its
+    /// identifier. This identifier is the fingerprint which users confirm before trusting
+    /// the authority
+    pub async fn authority_identifier(&self) -> Result<Identifier> {
+        let change_history = hex::decode(&self.authority_identity)
+            .map_err(|_| ApiError::core("the authority identity is not hex-encoded"))?;
+        let identity = identities()
+            .identities_creation()
+            .import(None, &change_history)
+            .await
+            .map_err(|e| ApiError::core(format!("the authority identity is invalid: {e}")))?;
+        Ok(identity.identifier().clone())
+    }
+
+    /// Verify the authority identity and return the configuration of the trust context.
+    /// When a fingerprint is expected, it must be the identifier of the authority
+    pub async fn to_config(&self, fingerprint: Option<&str>) -> Result<TrustContextConfig> {
+        let identifier = self.authority_identifier().await?;
+        if let Some(fingerprint) = fingerprint {
+            if fingerprint != identifier.to_string() {
+                return Err(ApiError::core(format!(
+                    "the authority identifier {identifier} doesn't match the expected fingerprint {fingerprint}"
+                )));
+            }
+        }
+        let own_credential = match (&self.credential, &self.authority_route) {
+            (CredentialScheme::Issuer, Some(route)) => {
+                Some(CredentialRetrieverConfig::FromCredentialIssuer(
+                    CredentialIssuerConfig::new(self.authority_identity.clone(), route.clone()),
+                ))
+            }
+            _ => None,
+        };
+        let id = self.id.clone().unwrap_or_else(|| identifier.to_string());
+        Ok(TrustContextConfig::new(
+            id,
+            Some(TrustAuthorityConfig::new(
+                self.authority_identity.clone(),
+                own_credential,
+            )),
+        ))
+    }
+}
+
+impl FromStr for TrustContextBootstrap {
+    type Err = ockam_core::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let url = Url::parse(s.trim())
+            .map_err(|e| ApiError::core(format!("the bootstrap URI is invalid: {e}")))?;
+        if url.scheme() != BOOTSTRAP_SCHEME || url.host_str() != Some(BOOTSTRAP_HOST) {
+            return Err(ApiError::core(format!(
+                "a bootstrap URI must start with {BOOTSTRAP_SCHEME}://{BOOTSTRAP_HOST}"
+            )));
+        }
+
+        let mut id = None;
+        let mut authority_identity = None;
+        let mut authority_route = None;
+        let mut credential = None;
+        for (key, value) in url.query_pairs() {
+            match key.as_ref() {
+                "id" => id = Some(value.to_string()),
+                "authority" => authority_identity = Some(value.to_string()),
+                "route" => {
+                    let route = MultiAddr::from_str(&value).map_err(|e| {
+                        ApiError::core(format!("the authority route {value} is invalid: {e}"))
+                    })?;
+                    authority_route = Some(route)
+                }
+                "credential" => credential = Some(value.parse()?),
+                _ => {
+                    return Err(ApiError::core(format!(
+                        "the bootstrap URI parameter {key} is not supported"
+                    )))
+                }
+            }
+        }
+
+        let authority_identity = authority_identity
+            .ok_or_else(|| ApiError::core("the bootstrap URI is missing the authority"))?;
+        if hex::decode(&authority_identity).is_err() {
+            return Err(ApiError::core("the authority identity is not hex-encoded"));
+        }
+        let mut bootstrap = TrustContextBootstrap::new(authority_identity, authority_route);
+        bootstrap.id = id;
+        if let Some(credential) = credential {
+            if credential == CredentialScheme::Issuer && bootstrap.authority_route.is_none() {
+                return Err(ApiError::core(
+                    "the issuer credential scheme requires the route of the authority",
+                ));
+            }
+            bootstrap.credential = credential;
+        }
+        Ok(bootstrap)
+    }
+}
+
+impl Display for TrustContextBootstrap {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut url = Url::parse(&format!("{BOOTSTRAP_SCHEME}://{BOOTSTRAP_HOST}"))
+            .map_err(|_| std::fmt::Error)?;
+        {
+            let mut query = url.query_pairs_mut();
+            query.append_pair("authority", &self.authority_identity);
+            if let Some(route) = &self.authority_route {
+                query.append_pair("route", &route.to_string());
+            }
+            query.append_pair("credential", &self.credential.to_string());
+            if let Some(id) = &self.id {
+                query.append_pair("id", id);
+            }
+        }
+        write!(f, "{url}")
+    }
+}
+
+impl TryFrom<&TrustContextConfig> for TrustContextBootstrap {
+    type Error = ockam_core::Error;
+
+    fn try_from(config: &TrustContextConfig) -> Result<Self> {
+        let authority = config.authority()?;
+        let route = match authority.own_credential() {
+            Ok(CredentialRetrieverConfig::FromCredentialIssuer(issuer)) => {
+                Some(issuer.multiaddr.clone())
+            }
+            _ => None,
+        };
+        Ok(TrustContextBootstrap::new(authority.identity_str(), route).with_id(config.id()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_bootstrap_uri() -> Result<()> {
+        let authority = identities().identities_creation().create_identity().await?;
+        let authority_identity = hex::encode(authority.export()?);
+        let route = MultiAddr::from_str("/dnsaddr/authority.example.com/tcp/4000/service/api")?;
+
+        let bootstrap = TrustContextBootstrap::new(authority_identity.clone(), Some(route.clone()));
+        let uri = bootstrap.to_string();
+        assert!(uri.starts_with("ockam://trust-context?authority="));
+        let parsed = TrustContextBootstrap::from_str(&uri)?;
+        assert_eq!(parsed, bootstrap);
+        assert_eq!(parsed.credential_scheme(), CredentialScheme::Issuer);
+
+        let fingerprint = authority.identifier().to_string();
+        let config = parsed.to_config(Some(&fingerprint)).await?;
+        assert_eq!(config.id(), fingerprint);
+        assert_eq!(
+            TrustContextBootstrap::try_from(&config)?,
+            bootstrap.clone().with_id(fingerprint)
+        );
+        assert!(parsed
+            .to_config(Some("I0123456789abcdef0123456789abcdef01234567"))
+            .await
+            .is_err());
+
+        // the route is needed to retrieve credentials from the authority
+        let uri = format!("ockam://trust-context?authority={authority_identity}&credential=issuer");
+        assert!(TrustContextBootstrap::from_str(&uri).is_err());
+        let uri = format!("ockam://trust-context?authority={authority_identity}");
+        let parsed = TrustContextBootstrap::from_str(&uri)?;
+        assert_eq!(parsed.credential_scheme(), CredentialScheme::None);
+
+        assert!(TrustContextBootstrap::from_str("ockam://project?authority=00").is_err());
+        assert!(TrustContextBootstrap::from_str("ockam://trust-context?authority=zz").is_err());
+        assert!(TrustContextBootstrap::from_str("ockam://trust-context?authority=00&x=1").is_err());
+        Ok(())
+    }
+}
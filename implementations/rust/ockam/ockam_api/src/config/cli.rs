@@ -6,9 +6,9 @@ use crate::config::{lookup::ConfigLookup, ConfigValues};
 use crate::error::ApiError;
 use crate::{cli_state, multiaddr_to_transport_route, DefaultAddress, HexByteVec};
 use ockam::identity::{
-    identities, AuthorityService, CredentialsMemoryRetriever, CredentialsRetriever, Identifier,
-    Identities, Identity, RemoteCredentialsRetriever, RemoteCredentialsRetrieverInfo,
-    SecureChannels, TrustContext,
+    identities, AuthorityService, CredentialsCache, CredentialsMemoryRetriever,
+    CredentialsRetriever, Identifier, Identities, Identity, RemoteCredentialsRetriever,
+    RemoteCredentialsRetrieverInfo, SecureChannels, TrustContext,
 };
 use ockam_core::compat::sync::Arc;
 use ockam_core::{Result, Route};
@@ -113,6 +113,7 @@ impl TrustContextConfig {
         &self,
         secure_channels: Arc<SecureChannels>,
         tcp_transport: Option<TcpTransport>,
+        credentials_cache: Option<CredentialsCache>,
     ) -> Result<TrustContext> {
         let authority = if let Some(authority_config) = self.authority.as_ref() {
             let identity = authority_config.identity().await?;
@@ -127,11 +128,15 @@ impl TrustContextConfig {
                     None
                 };
 
-            Some(AuthorityService::new(
+            let authority = AuthorityService::new(
                 secure_channels.identities().credentials(),
                 identity.identifier().clone(),
                 credential_retriever,
-            ))
+            );
+            Some(match credentials_cache {
+                Some(credentials_cache) => authority.with_credentials_cache(credentials_cache),
+                None => authority,
+            })
         } else {
             None
         };
@@ -5,6 +5,7 @@ use bytes::Bytes;
 use miette::WrapErr;
 use ockam::identity::{identities, Identifier};
 use ockam_core::compat::collections::VecDeque;
+use ockam_multiaddr::proto::{DnsAddr, Ip4, Ip6, Tcp};
 use ockam_multiaddr::MultiAddr;
 use serde::{Deserialize, Serialize};
 use std::{
@@ -157,24 +158,20 @@ impl fmt::Display for InternetAddress {
 }
 
 impl InternetAddress {
+    /// Parse a `host:port` address. IPv6 addresses must be written as bracketed
+    /// literals, for example `[::1]:4000`, so that the host can be told apart from the port
     pub fn new(addr: &str) -> Option<Self> {
         // We try to parse a SocketAddress first, and if this fails
         // then assume it's a DNS address
         match SocketAddr::from_str(addr) {
-            Ok(addr) => match addr {
-                SocketAddr::V4(v4) => Some(Self::V4(v4)),
-                SocketAddr::V6(v6) => Some(Self::V6(v6)),
-            },
+            Ok(addr) => Some(addr.into()),
             Err(_) => {
-                let addr_parts: Vec<&str> = addr.split(':').collect();
-                if addr_parts.len() != 2 {
+                let (host, port) = addr.rsplit_once(':')?;
+                // an unbracketed IPv6 address or a bracketed host which is not an IPv6 address
+                if host.is_empty() || host.contains([':', '[', ']']) {
                     return None;
                 }
-
-                Some(Self::Dns(
-                    addr_parts[0].to_string(),
-                    addr_parts[1].parse().ok()?,
-                ))
+                Some(Self::Dns(host.to_string(), port.parse().ok()?))
             }
         }
     }
@@ -191,6 +188,40 @@ impl InternetAddress {
             Self::V6(v6) => v6.port(),
         }
     }
+
+    /// Get the host of this address: a DNS name or an IP address, without brackets
+    pub fn host(&self) -> String {
+        match self {
+            Self::Dns(dns, _) => dns.clone(),
+            Self::V4(v4) => v4.ip().to_string(),
+            Self::V6(v6) => v6.ip().to_string(),
+        }
+    }
+
+    /// Return true if this address is an IPv6 socket address
+    pub fn is_ipv6(&self) -> bool {
+        matches!(self, Self::V6(_))
+    }
+
+    /// Return this address as a multiaddr, for example `/ip6/::1/tcp/4000`
+    pub fn multiaddr(&self) -> ockam_core::Result<MultiAddr> {
+        let mut m = MultiAddr::default();
+        match self {
+            Self::Dns(dns, _) => m.push_back(DnsAddr::new(dns))?,
+            Self::V4(v4) => m.push_back(Ip4(*v4.ip()))?,
+            Self::V6(v6) => m.push_back(Ip6(*v6.ip()))?,
+        }
+        m.push_back(Tcp(self.port()))?;
+        Ok(m)
+    }
+}
+
+impl FromStr for InternetAddress {
+    type Err = ApiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s).ok_or_else(|| ApiError::message(format!("Invalid address '{s}'")))
+    }
 }
 
 impl From<SocketAddr> for InternetAddress {
@@ -313,3 +344,38 @@ impl ProjectAuthority {
         &self.address
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv6Addr;
+
+    #[test]
+    fn test_parse_internet_address() {
+        let v6 = InternetAddress::new("[::1]:4000").unwrap();
+        assert_eq!(
+            v6,
+            InternetAddress::V6(SocketAddrV6::new(Ipv6Addr::LOCALHOST, 4000, 0, 0))
+        );
+        assert_eq!(v6.to_string(), "[::1]:4000");
+        assert_eq!(v6.host(), "::1");
+        assert_eq!(v6.multiaddr().unwrap().to_string(), "/ip6/::1/tcp/4000");
+
+        let v4 = InternetAddress::new("127.0.0.1:4000").unwrap();
+        assert_eq!(v4.to_string(), "127.0.0.1:4000");
+        assert_eq!(
+            v4.multiaddr().unwrap().to_string(),
+            "/ip4/127.0.0.1/tcp/4000"
+        );
+
+        let dns = InternetAddress::new("localhost:4000").unwrap();
+        assert_eq!(dns, InternetAddress::Dns("localhost".to_string(), 4000));
+        assert_eq!(dns.to_string(), "localhost:4000");
+
+        // IPv6 addresses must be bracketed
+        assert!(InternetAddress::new("::1:4000").is_none());
+        assert!(InternetAddress::new("[localhost]:4000").is_none());
+        assert!(InternetAddress::new("[::1]").is_none());
+        assert!(InternetAddress::new(":4000").is_none());
+    }
+}
@@ -12,6 +12,7 @@ use serde::Serialize;
 use crate::config::atomic::AtomicUpdater;
 
 pub mod atomic;
+pub mod bootstrap;
 pub mod cli;
 pub mod lookup;
 
@@ -0,0 +1,17 @@
+use ockam::{Any, Context, Result, Routed, Worker};
+use tracing as log;
+
+/// Worker dropping all the messages it receives.
+/// It can be used to check that messages are delivered, and authorized, without a reply
+pub struct Discard;
+
+#[ockam::worker]
+impl Worker for Discard {
+    type Context = Context;
+    type Message = Any;
+
+    async fn handle_message(&mut self, _ctx: &mut Context, msg: Routed<Any>) -> Result<()> {
+        log::debug!(src = %msg.src_addr(), from = %msg.sender()?, len = msg.payload().len(), "discarding a message");
+        Ok(())
+    }
+}
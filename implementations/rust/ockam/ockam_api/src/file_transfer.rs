@@ -0,0 +1,146 @@
+//! Transfer of large files between two nodes, usually over a secure channel.
+//!
+//! A [`FileReceiver`] worker stores the files it receives in a directory. Files are sent
+//! in chunks by a [`FileSender`], each chunk carrying the SHA-256 hash of its data. Each
+//! transfer is identified by the hex-encoded SHA-256 hash of the whole file. The received
+//! bytes are kept in a hidden `.<name>.<transfer>.part` file until the whole file is received
+//! and its hash is checked, so that an interrupted transfer can be resumed from the last chunk
+//! received, and concurrent transfers of different files under the same name don't collide.
+//! The receiver rejects the files larger than its maximum file size.
+//!
+//! The receiver handles the following requests:
+//!
+//!  - `GET /<name>/<transfer>`: return the [`TransferStatus`] of a transfer.
+//!  - `PUT /<name>/<transfer>`: write a [`FileChunk`].
+//!  - `POST /<name>/<transfer>`: check the hash of the received file and complete the transfer.
+//!
+use ockam_core::errcode::{Kind, Origin};
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+mod receiver;
+mod sender;
+pub mod types;
+
+pub use receiver::*;
+pub use sender::*;
+pub use types::*;
+
+/// Size of the buffer used to hash a file
+const HASH_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Return the SHA-256 hash of a file and its size
+async fn sha256<R: AsyncRead + Unpin>(reader: &mut R) -> std::io::Result<([u8; 32], u64)> {
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; HASH_BUFFER_SIZE];
+    let mut size = 0;
+    loop {
+        let read = reader.read(&mut buffer).await?;
+        if read == 0 {
+            return Ok((hasher.finalize().into(), size));
+        }
+        hasher.update(&buffer[..read]);
+        size += read as u64;
+    }
+}
+
+/// A file name is only valid if it designates a file in the directory of the receiver.
+/// The hidden files are reserved for the partial files of the transfers
+fn is_valid_file_name(name: &str) -> bool {
+    !name.is_empty() && !name.starts_with('.') && !name.contains(['/', '\\'])
+}
+
+fn io_error(e: std::io::Error) -> ockam_core::Error {
+    ockam_core::Error::new(Origin::Application, Kind::Io, e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam_core::{route, Result};
+    use ockam_node::Context;
+    use rand::RngCore;
+
+    #[ockam_macros::test]
+    async fn test_resume_file_transfer(context: &mut Context) -> Result<()> {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        let mut contents = vec![0; 100_000];
+        rand::thread_rng().fill_bytes(&mut contents);
+        let path = source.path().join("data.bin");
+        std::fs::write(&path, &contents).unwrap();
+
+        // a previous transfer was interrupted after the first 10000 bytes
+        let partial = destination.path().join(format!(
+            ".data.bin.{}.part",
+            hex::encode(Sha256::digest(&contents))
+        ));
+        std::fs::write(&partial, &contents[..10_000]).unwrap();
+
+        // the partial file of another transfer of a file with the same name is not resumed
+        let other = destination
+            .path()
+            .join(format!(".data.bin.{}.part", hex::encode([1; 32])));
+        std::fs::write(&other, &contents[..50_000]).unwrap();
+
+        context
+            .start_worker("file_receiver", FileReceiver::new(destination.path()))
+            .await?;
+        let sender = FileSender::new(&route!["file_receiver"], None).with_chunk_size(16 * 1024);
+        let mut reported = vec![];
+        let status = sender
+            .send_file(context, &path, "data.bin", |p| reported.push(p.transferred))
+            .await?;
+
+        assert_eq!(status, TransferStatus::new("data.bin", 100_000, true));
+        assert_eq!(reported.first(), Some(&10_000));
+        assert_eq!(reported.last(), Some(&100_000));
+        assert_eq!(
+            std::fs::read(destination.path().join("data.bin")).unwrap(),
+            contents
+        );
+        assert!(!partial.exists());
+        assert!(other.exists());
+
+        // the file cannot be sent twice
+        assert!(sender
+            .send_file(context, &path, "data.bin", |_| {})
+            .await
+            .is_err());
+
+        context.stop().await
+    }
+
+    #[ockam_macros::test]
+    async fn test_files_larger_than_the_maximum_size_are_rejected(
+        context: &mut Context,
+    ) -> Result<()> {
+        let source = tempfile::tempdir().unwrap();
+        let destination = tempfile::tempdir().unwrap();
+        let path = source.path().join("data.bin");
+        std::fs::write(&path, vec![1; 50_000]).unwrap();
+
+        context
+            .start_worker(
+                "file_receiver",
+                FileReceiver::new(destination.path()).with_max_file_size(40_000),
+            )
+            .await?;
+        let sender = FileSender::new(&route!["file_receiver"], None).with_chunk_size(16 * 1024);
+        let mut reported = vec![];
+        assert!(sender
+            .send_file(context, &path, "data.bin", |p| reported.push(p.transferred))
+            .await
+            .is_err());
+        assert_eq!(reported.last(), Some(&32_768));
+        assert!(!destination.path().join("data.bin").exists());
+
+        // a hidden file name is rejected
+        assert!(sender
+            .send_file(context, &path, ".data.bin", |_| {})
+            .await
+            .is_err());
+
+        context.stop().await
+    }
+}
@@ -0,0 +1,237 @@
+use std::io::SeekFrom;
+use std::path::PathBuf;
+
+use minicbor::Decoder;
+use sha2::{Digest, Sha256};
+use tokio::fs::{self, File, OpenOptions};
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+use tracing::{debug, trace};
+
+use ockam_core::api::{Error, Method, RequestHeader, Response, Status};
+use ockam_core::{self, Result, Routed, Worker};
+use ockam_node::Context;
+
+use super::types::{CompleteTransfer, FileChunk, TransferStatus};
+use super::{io_error, is_valid_file_name, sha256};
+
+/// Default maximum size of a file received by a [`FileReceiver`]
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 1024 * 1024 * 1024;
+
+/// Worker storing the files sent by a [`FileSender`](super::FileSender) in a directory
+pub struct FileReceiver {
+    directory: PathBuf,
+    max_file_size: u64,
+}
+
+#[ockam_core::worker]
+impl Worker for FileReceiver {
+    type Context = Context;
+    type Message = Vec<u8>;
+
+    async fn handle_message(
+        &mut self,
+        ctx: &mut Context,
+        msg: Routed<Self::Message>,
+    ) -> Result<()> {
+        let r = self.on_request(msg.as_body()).await?;
+        ctx.send(msg.return_route(), r).await
+    }
+}
+
+impl FileReceiver {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+        }
+    }
+
+    /// Set the maximum size of a received file, in bytes.
+    /// The chunks written beyond this size are rejected
+    pub fn with_max_file_size(mut self, max_file_size: u64) -> Self {
+        self.max_file_size = max_file_size;
+        self
+    }
+
+    async fn on_request(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut dec = Decoder::new(data);
+        let req: RequestHeader = dec.decode()?;
+
+        trace! {
+            target: "ockam_api::file_transfer::receiver",
+            id     = %req.id(),
+            method = ?req.method(),
+            path   = %req.path(),
+            body   = %req.has_body(),
+            "request"
+        }
+
+        let transfer = match req.path_segments::<3>().as_slice() {
+            [name, id] if is_valid_file_name(name) && is_valid_transfer_id(id) => Transfer {
+                name: name.to_string(),
+                id: id.to_string(),
+            },
+            _ => return Response::bad_request(&req, "invalid file name or transfer id").to_vec(),
+        };
+        let res = match req.method() {
+            Some(Method::Get) => self.status(&req, &transfer).await,
+            Some(Method::Put) => self.write_chunk(&req, &transfer, &mut dec).await,
+            Some(Method::Post) => self.complete(&req, &transfer, &mut dec).await,
+            _ => Err(Response::invalid_method(&req)),
+        };
+        match res {
+            Ok(r) => r.to_vec(),
+            Err(e) => e.to_vec(),
+        }
+    }
+
+    async fn status(
+        &self,
+        req: &RequestHeader,
+        transfer: &Transfer,
+    ) -> Result<Response<TransferStatus>, Response<Error>> {
+        let name = &transfer.name;
+        let status = match self.received_file_size(name).await? {
+            Some(size) => TransferStatus::new(name, size, true),
+            None => TransferStatus::new(name, self.partial_file_size(transfer).await?, false),
+        };
+        Ok(Response::ok(req).body(status))
+    }
+
+    async fn write_chunk(
+        &self,
+        req: &RequestHeader,
+        transfer: &Transfer,
+        dec: &mut Decoder<'_>,
+    ) -> Result<Response<TransferStatus>, Response<Error>> {
+        let chunk: FileChunk = dec.decode()?;
+        let name = &transfer.name;
+        if self.received_file_size(name).await?.is_some() {
+            return Err(already_received(req, name));
+        }
+        let end = chunk.offset.saturating_add(chunk.data.len() as u64);
+        if end > self.max_file_size {
+            return Err(too_large(req, name, self.max_file_size));
+        }
+        if <[u8; 32]>::from(Sha256::digest(&chunk.data)) != chunk.sha256 {
+            return Err(Response::bad_request(
+                req,
+                "the hash of the chunk does not match",
+            ));
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .open(self.partial_file_path(transfer))
+            .await
+            .map_err(io_error)?;
+        let size = file.metadata().await.map_err(io_error)?.len();
+        if chunk.offset > size {
+            let msg = format!("expected a chunk at offset {size} at most");
+            return Err(Response::error(req, &msg, Status::Conflict));
+        }
+        // a chunk which was already received is written again, for example
+        // when its acknowledgement was lost
+        if chunk.offset < size {
+            file.set_len(chunk.offset).await.map_err(io_error)?;
+        }
+        file.seek(SeekFrom::Start(chunk.offset))
+            .await
+            .map_err(io_error)?;
+        file.write_all(&chunk.data).await.map_err(io_error)?;
+        file.flush().await.map_err(io_error)?;
+
+        Ok(Response::ok(req).body(TransferStatus::new(name, end, false)))
+    }
+
+    async fn complete(
+        &self,
+        req: &RequestHeader,
+        transfer: &Transfer,
+        dec: &mut Decoder<'_>,
+    ) -> Result<Response<TransferStatus>, Response<Error>> {
+        let complete: CompleteTransfer = dec.decode()?;
+        let name = &transfer.name;
+        if self.received_file_size(name).await?.is_some() {
+            return Err(already_received(req, name));
+        }
+        if complete.size > self.max_file_size {
+            return Err(too_large(req, name, self.max_file_size));
+        }
+        let path = self.partial_file_path(transfer);
+        let (hash, size) = match File::open(&path).await {
+            Ok(mut file) => sha256(&mut file).await.map_err(io_error)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => ([0; 32], 0),
+            Err(e) => return Err(io_error(e).into()),
+        };
+        if size != complete.size {
+            let msg = format!("received {size} bytes out of {}", complete.size);
+            return Err(Response::error(req, &msg, Status::Conflict));
+        }
+        if hash != complete.sha256 || hex::encode(hash) != transfer.id {
+            // the received data is corrupted, the transfer must start again
+            fs::remove_file(&path).await.map_err(io_error)?;
+            return Err(Response::bad_request(
+                req,
+                "the hash of the file does not match",
+            ));
+        }
+        // another transfer of a file with the same name may have completed in the meantime
+        if self.received_file_size(name).await?.is_some() {
+            fs::remove_file(&path).await.map_err(io_error)?;
+            return Err(already_received(req, name));
+        }
+        fs::rename(&path, self.directory.join(name))
+            .await
+            .map_err(io_error)?;
+        debug!(%name, %size, "file received");
+        Ok(Response::ok(req).body(TransferStatus::new(name, size, true)))
+    }
+
+    /// Return the size of a file if it was completely received
+    async fn received_file_size(&self, name: &str) -> Result<Option<u64>> {
+        match fs::metadata(self.directory.join(name)).await {
+            Ok(metadata) => Ok(Some(metadata.len())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(io_error(e)),
+        }
+    }
+
+    async fn partial_file_size(&self, transfer: &Transfer) -> Result<u64> {
+        match fs::metadata(self.partial_file_path(transfer)).await {
+            Ok(metadata) => Ok(metadata.len()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+            Err(e) => Err(io_error(e)),
+        }
+    }
+
+    /// Return the path of the file where the bytes of a transfer are written until the
+    /// whole file is received. Each transfer has its own partial file, so that concurrent
+    /// transfers of different files under the same name don't overwrite each other
+    fn partial_file_path(&self, transfer: &Transfer) -> PathBuf {
+        self.directory
+            .join(format!(".{}.{}.part", transfer.name, transfer.id))
+    }
+}
+
+/// Transfer of a file, identified by the hex-encoded SHA-256 hash of the whole file.
+/// An interrupted transfer is resumed when the same file is sent again
+struct Transfer {
+    name: String,
+    id: String,
+}
+
+fn is_valid_transfer_id(id: &str) -> bool {
+    id.len() == 64 && id.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f'))
+}
+
+fn already_received(req: &RequestHeader, name: &str) -> Response<Error> {
+    let msg = format!("the file {name} was already received");
+    Response::error(req, &msg, Status::Conflict)
+}
+
+fn too_large(req: &RequestHeader, name: &str, max_file_size: u64) -> Response<Error> {
+    let msg = format!("the file {name} is larger than the maximum size of {max_file_size} bytes");
+    Response::error(req, &msg, Status::Forbidden)
+}
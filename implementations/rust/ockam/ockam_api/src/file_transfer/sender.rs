@@ -0,0 +1,147 @@
+use std::io::SeekFrom;
+use std::path::Path;
+use std::time::Duration;
+
+use sha2::{Digest, Sha256};
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tracing::debug;
+
+use ockam_core::api::Request;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Result, Route};
+use ockam_node::api::Client;
+use ockam_node::Context;
+
+use super::types::{CompleteTransfer, FileChunk, TransferStatus};
+use super::{io_error, is_valid_file_name, sha256};
+
+/// Default size of the chunks sent to a [`FileReceiver`](super::FileReceiver).
+/// It is kept small enough for a chunk to fit in a single secure channel message
+pub const DEFAULT_CHUNK_SIZE: usize = 32 * 1024;
+
+/// Progress of a file transfer, reported after each chunk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransferProgress {
+    pub name: String,
+    /// Number of bytes received by the [`FileReceiver`](super::FileReceiver)
+    pub transferred: u64,
+    pub total: u64,
+}
+
+/// Client sending files to a [`FileReceiver`](super::FileReceiver).
+///
+/// A transfer which was interrupted is resumed from the last chunk received
+/// when the same file is sent again
+pub struct FileSender {
+    client: Client,
+    chunk_size: usize,
+}
+
+impl FileSender {
+    /// Create a sender for the receiver at the end of `route`, usually a secure channel
+    pub fn new(route: &Route, timeout: Option<Duration>) -> Self {
+        Self {
+            client: Client::new(route, timeout),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+        }
+    }
+
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// Return the status of the transfer of a file on the receiver side, for a file with the
+    /// given SHA-256 hash
+    pub async fn status(
+        &self,
+        ctx: &Context,
+        name: &str,
+        sha256: &[u8; 32],
+    ) -> Result<TransferStatus> {
+        self.client
+            .ask(ctx, Request::get(transfer_path(name, sha256)))
+            .await?
+            .success()
+    }
+
+    /// Send a file under the given name, starting from the bytes already received.
+    /// `progress` is called after each chunk acknowledged by the receiver
+    pub async fn send_file(
+        &self,
+        ctx: &Context,
+        path: &Path,
+        name: &str,
+        mut progress: impl FnMut(&TransferProgress) + Send,
+    ) -> Result<TransferStatus> {
+        if !is_valid_file_name(name) {
+            return Err(ockam_core::Error::new(
+                Origin::Application,
+                Kind::Invalid,
+                format!("{name} is not a valid file name"),
+            ));
+        }
+        let mut file = File::open(path).await.map_err(io_error)?;
+        let (hash, total) = sha256(&mut file).await.map_err(io_error)?;
+
+        let path = transfer_path(name, &hash);
+        let status = self.status(ctx, name, &hash).await?;
+        if status.complete {
+            return Err(ockam_core::Error::new(
+                Origin::Application,
+                Kind::AlreadyExists,
+                format!("the file {name} was already received"),
+            ));
+        }
+        let mut offset = status.offset.min(total);
+        if offset > 0 {
+            debug!(%name, %offset, "resuming a file transfer");
+        }
+
+        let mut report = |transferred| {
+            progress(&TransferProgress {
+                name: name.to_string(),
+                transferred,
+                total,
+            })
+        };
+        report(offset);
+        file.seek(SeekFrom::Start(offset)).await.map_err(io_error)?;
+        let mut data = Vec::with_capacity(self.chunk_size);
+        while offset < total {
+            data.clear();
+            (&mut file)
+                .take(self.chunk_size as u64)
+                .read_to_end(&mut data)
+                .await
+                .map_err(io_error)?;
+            if data.is_empty() {
+                break;
+            }
+            let chunk = FileChunk {
+                offset,
+                sha256: Sha256::digest(&data).into(),
+                data: data.clone(),
+            };
+            let status: TransferStatus = self
+                .client
+                .ask(ctx, Request::put(&path).body(chunk))
+                .await?
+                .success()?;
+            offset = status.offset;
+            report(offset);
+        }
+
+        let req = Request::post(&path).body(CompleteTransfer {
+            size: total,
+            sha256: hash,
+        });
+        self.client.ask(ctx, req).await?.success()
+    }
+}
+
+/// Path of the requests sent for the transfer of a file, identified by the hash of the file
+fn transfer_path(name: &str, sha256: &[u8; 32]) -> String {
+    format!("/{name}/{}", hex::encode(sha256))
+}
@@ -0,0 +1,45 @@
+use minicbor::{Decode, Encode};
+use serde::Serialize;
+
+/// A chunk of a file, written at a given offset
+#[derive(Debug, Clone, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct FileChunk {
+    #[n(1)] pub offset: u64,
+    #[cbor(n(2), with = "minicbor::bytes")] pub data: Vec<u8>,
+    /// SHA-256 hash of the data
+    #[cbor(n(3), with = "minicbor::bytes")] pub sha256: [u8; 32],
+}
+
+/// Request body sent once all the chunks of a file are sent
+#[derive(Debug, Clone, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct CompleteTransfer {
+    #[n(1)] pub size: u64,
+    /// SHA-256 hash of the whole file
+    #[cbor(n(2), with = "minicbor::bytes")] pub sha256: [u8; 32],
+}
+
+/// Status of a file on the receiver side
+#[derive(Debug, Clone, PartialEq, Eq, Decode, Encode, Serialize)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct TransferStatus {
+    #[n(1)] pub name: String,
+    /// Number of bytes received so far, from which the transfer can be resumed
+    #[n(2)] pub offset: u64,
+    /// True once the whole file is received and its hash is verified
+    #[n(3)] pub complete: bool,
+}
+
+impl TransferStatus {
+    pub fn new(name: impl Into<String>, offset: u64, complete: bool) -> Self {
+        Self {
+            name: name.into(),
+            offset,
+            complete,
+        }
+    }
+}
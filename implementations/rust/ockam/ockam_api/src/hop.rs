@@ -1,4 +1,4 @@
-use ockam::{Any, Context, Result, Routed, Worker};
+use ockam::{Address, Any, Context, Result, Routed, Worker};
 
 // TODO: Split into two workers to avoid cycles + there are many implementations of Hop worker, fix all of them
 pub struct Hop;
@@ -28,3 +28,35 @@ impl Worker for Hop {
         ctx.forward(message).await
     }
 }
+
+/// Forward all the messages sent to this hop to a given worker. The worker replies directly
+/// to the sender, and the hop can be guarded by an access control that the worker doesn't have
+pub struct ForwardingHop {
+    worker: Address,
+}
+
+impl ForwardingHop {
+    pub fn new(worker: Address) -> Self {
+        Self { worker }
+    }
+}
+
+#[ockam::worker]
+impl Worker for ForwardingHop {
+    type Context = Context;
+    type Message = Any;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<Any>) -> Result<()> {
+        let mut message = msg.into_local_message();
+        let transport_message = message.transport_mut();
+
+        // Replace my address with the address of the worker in the onward_route
+        transport_message.onward_route.step()?;
+        transport_message
+            .onward_route
+            .modify()
+            .prepend(self.worker.clone());
+
+        ctx.forward(message).await
+    }
+}
@@ -1,5 +1,6 @@
 use crate::cloud::lease_manager::models::influxdb::Token;
 use crate::cloud::ProjectNode;
+use crate::DefaultAddress;
 use miette::IntoDiagnostic;
 use ockam_core::api::Request;
 use ockam_core::async_trait;
@@ -20,7 +21,11 @@ pub trait InfluxDbTokenLease {
 impl InfluxDbTokenLease for ProjectNode {
     async fn create_token(&self, ctx: &Context) -> miette::Result<Token> {
         self.0
-            .ask(ctx, "influxdb_token_lease", Request::post("/"))
+            .ask(
+                ctx,
+                DefaultAddress::INFLUXDB_TOKEN_LEASE,
+                Request::post("/"),
+            )
             .await
             .into_diagnostic()?
             .success()
@@ -31,7 +36,7 @@ impl InfluxDbTokenLease for ProjectNode {
         self.0
             .ask(
                 ctx,
-                "influxdb_token_lease",
+                DefaultAddress::INFLUXDB_TOKEN_LEASE,
                 Request::get(format!("/{token_id}")),
             )
             .await
@@ -44,7 +49,7 @@ impl InfluxDbTokenLease for ProjectNode {
         self.0
             .tell(
                 ctx,
-                "influxdb_token_lease",
+                DefaultAddress::INFLUXDB_TOKEN_LEASE,
                 Request::delete(format!("/{token_id}")),
             )
             .await
@@ -55,7 +60,7 @@ impl InfluxDbTokenLease for ProjectNode {
 
     async fn list_tokens(&self, ctx: &Context) -> miette::Result<Vec<Token>> {
         self.0
-            .ask(ctx, "influxdb_token_lease", Request::get("/"))
+            .ask(ctx, DefaultAddress::INFLUXDB_TOKEN_LEASE, Request::get("/"))
             .await
             .into_diagnostic()?
             .success()
@@ -1,4 +1,10 @@
 #[allow(clippy::module_inception)]
 mod influxdb_token_lease;
+mod provider;
+mod service;
+mod storage;
 
 pub use influxdb_token_lease::*;
+pub use provider::*;
+pub use service::*;
+pub use storage::*;
@@ -0,0 +1,119 @@
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde_json::json;
+
+use ockam::identity::Identifier;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{async_trait, Error, Result};
+
+use crate::cloud::project::InfluxDBTokenLeaseManagerConfig;
+
+/// A token created by a token provider
+#[derive(Clone, Debug)]
+pub struct ProvidedToken {
+    pub id: String,
+    pub token: String,
+}
+
+/// Create and revoke the database tokens leased by a lease manager
+#[async_trait]
+pub trait TokenProvider: Send + Sync + 'static {
+    /// Create a new token for an identity
+    async fn create_token(&self, issued_for: &Identifier) -> Result<ProvidedToken>;
+
+    /// Revoke a token. Revoking a token which doesn't exist anymore is not an error
+    async fn revoke_token(&self, id: &str) -> Result<()>;
+}
+
+/// Tokens created as InfluxDB authorizations, with the permissions of the lease manager
+/// configuration
+pub struct InfluxDbTokenProvider {
+    client: reqwest::Client,
+    endpoint: String,
+    token: String,
+    org_id: String,
+    permissions: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct InfluxDbAuthorization {
+    id: String,
+    token: String,
+}
+
+impl InfluxDbTokenProvider {
+    /// Create a provider using the admin token of the configuration to manage authorizations
+    pub fn new(config: &InfluxDBTokenLeaseManagerConfig) -> Result<Self> {
+        let permissions = serde_json::from_str(&config.permissions).map_err(|e| {
+            Error::new(
+                Origin::Api,
+                Kind::Invalid,
+                format!("invalid InfluxDB permissions: {e}"),
+            )
+        })?;
+        Ok(Self {
+            client: reqwest::Client::new(),
+            endpoint: config.endpoint.trim_end_matches('/').to_string(),
+            token: config.token.clone(),
+            org_id: config.org_id.clone(),
+            permissions,
+        })
+    }
+
+    fn authorizations_url(&self) -> String {
+        format!("{}/api/v2/authorizations", self.endpoint)
+    }
+}
+
+#[async_trait]
+impl TokenProvider for InfluxDbTokenProvider {
+    async fn create_token(&self, issued_for: &Identifier) -> Result<ProvidedToken> {
+        let body = json!({
+            "orgID": self.org_id,
+            "description": format!("ockam lease for {issued_for}"),
+            "permissions": self.permissions,
+        });
+        let res = self
+            .client
+            .post(self.authorizations_url())
+            .header("Authorization", format!("Token {}", self.token))
+            .json(&body)
+            .send()
+            .await
+            .map_err(influxdb_error)?;
+        if res.status() != StatusCode::CREATED && res.status() != StatusCode::OK {
+            return Err(Error::new(
+                Origin::Api,
+                Kind::Io,
+                format!("InfluxDB refused to create a token: {}", res.status()),
+            ));
+        }
+        let authorization: InfluxDbAuthorization = res.json().await.map_err(influxdb_error)?;
+        Ok(ProvidedToken {
+            id: authorization.id,
+            token: authorization.token,
+        })
+    }
+
+    async fn revoke_token(&self, id: &str) -> Result<()> {
+        let res = self
+            .client
+            .delete(format!("{}/{id}", self.authorizations_url()))
+            .header("Authorization", format!("Token {}", self.token))
+            .send()
+            .await
+            .map_err(influxdb_error)?;
+        match res.status() {
+            StatusCode::NO_CONTENT | StatusCode::OK | StatusCode::NOT_FOUND => Ok(()),
+            status => Err(Error::new(
+                Origin::Api,
+                Kind::Io,
+                format!("InfluxDB refused to revoke the token {id}: {status}"),
+            )),
+        }
+    }
+}
+
+fn influxdb_error(e: reqwest::Error) -> Error {
+    Error::new(Origin::Api, Kind::Io, e)
+}
@@ -0,0 +1,469 @@
+use std::time::Duration;
+
+use minicbor::Decoder;
+use tokio::task::JoinHandle;
+use tracing::trace;
+
+use ockam::identity::utils::now;
+use ockam::identity::{
+    secure_channel_required, Identifier, IdentitiesRepository, IdentitySecureChannelLocalInfo,
+    TimestampInSeconds,
+};
+use ockam_abac::expr::str;
+use ockam_abac::{AbacAccessControl, Decision, Env};
+use ockam_core::api::{Method, RequestHeader, Response};
+use ockam_core::compat::sync::Arc;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::flow_control::FlowControlId;
+use ockam_core::{Error, Result, Routed, Worker};
+use ockam_node::Context;
+
+use crate::cloud::lease_manager::models::influxdb::Token;
+use crate::cloud::project::InfluxDBTokenLeaseManagerConfig;
+use crate::influxdb_token_lease::{Lease, LeaseStatus, LeasesRepository, TokenProvider};
+use crate::util::policy_explain_mode;
+use crate::{actions, DefaultAddress};
+
+/// Time between two checks of the expired leases
+const EXPIRATION_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The lease manager issues short-lived InfluxDB tokens to the identities allowed by its
+/// user access rule. Each token is revoked when its lease expires.
+///
+/// Identities allowed by the admin access rule can list and revoke the leases of all the
+/// identities, the other identities can only see and revoke their own leases.
+/// When no user access rule is configured, only the admins can lease a token.
+///
+/// The secret of a token is only sent in the response creating its lease, it is not stored
+pub struct InfluxDbTokenLeaseManager {
+    leases: Arc<dyn LeasesRepository>,
+    provider: Arc<dyn TokenProvider>,
+    user_access: Option<AbacAccessControl>,
+    admin_access: Option<AbacAccessControl>,
+    ttl: Duration,
+    expiration: Option<JoinHandle<()>>,
+}
+
+impl InfluxDbTokenLeaseManager {
+    /// Create a lease manager from the configuration of the InfluxDB add-on.
+    /// The access rules are evaluated against the attributes stored in the identities repository
+    pub fn new(
+        config: &InfluxDBTokenLeaseManagerConfig,
+        identities: Arc<dyn IdentitiesRepository>,
+        leases: Arc<dyn LeasesRepository>,
+        provider: Arc<dyn TokenProvider>,
+    ) -> Result<Self> {
+        if config.max_ttl_secs <= 0 {
+            return Err(Error::new(
+                Origin::Api,
+                Kind::Invalid,
+                "the time to live of the leases must be positive",
+            ));
+        }
+        Ok(Self {
+            leases,
+            provider,
+            user_access: access_control(identities.clone(), &config.user_access_rule)?,
+            admin_access: access_control(identities, &config.admin_access_rule)?,
+            ttl: Duration::from_secs(config.max_ttl_secs as u64),
+            expiration: None,
+        })
+    }
+
+    /// Start the lease manager. It can only be reached through the secure channels
+    /// created by the given listener
+    pub async fn start(
+        self,
+        ctx: &Context,
+        secure_channel_flow_control_id: &FlowControlId,
+    ) -> Result<()> {
+        let address = DefaultAddress::INFLUXDB_TOKEN_LEASE;
+        ctx.flow_controls()
+            .add_consumer(address, secure_channel_flow_control_id);
+        ctx.start_worker(address, self).await?;
+        info!("started an InfluxDB token lease manager at '{address}'");
+        Ok(())
+    }
+}
+
+/// Private lease manager functions
+impl InfluxDbTokenLeaseManager {
+    async fn is_admin(&self, identifier: &Identifier) -> Result<bool> {
+        match &self.admin_access {
+            Some(access) => access.is_identity_authorized(identifier.clone()).await,
+            None => Ok(false),
+        }
+    }
+
+    async fn user_decision(&self, identifier: &Identifier) -> Result<Decision> {
+        match &self.user_access {
+            Some(access) => access.decide(identifier.clone()).await,
+            None => Ok(Decision::from(false)),
+        }
+    }
+
+    async fn create_lease(&self, issued_for: &Identifier) -> Result<Token> {
+        let created_at = now()?;
+        let provided = self.provider.create_token(issued_for).await?;
+        let lease = Lease {
+            id: provided.id,
+            issued_for: issued_for.clone(),
+            created_at,
+            expires_at: TimestampInSeconds(*created_at + self.ttl.as_secs()),
+            status: LeaseStatus::Active,
+        };
+        // a token without a lease would never be revoked
+        if let Err(e) = self.leases.put(lease.clone()).await {
+            if let Err(revoke_error) = self.provider.revoke_token(&lease.id).await {
+                error!(id = %lease.id, %revoke_error, "cannot revoke an InfluxDB token which could not be leased");
+            }
+            return Err(e);
+        }
+        info!(id = %lease.id, issued_for = %issued_for, "leased an InfluxDB token");
+        let mut token = lease.to_token()?;
+        token.token = provided.token;
+        Ok(token)
+    }
+
+    /// Return a lease if it can be accessed by the caller
+    async fn find_lease(&self, from: &Identifier, id: &str) -> Result<Option<Lease>> {
+        match self.leases.get(id).await? {
+            Some(lease) if &lease.issued_for == from || self.is_admin(from).await? => {
+                Ok(Some(lease))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    async fn revoke_lease(&self, lease: &Lease) -> Result<()> {
+        if lease.status == LeaseStatus::Active {
+            self.provider.revoke_token(&lease.id).await?;
+            self.leases
+                .deactivate(&lease.id, LeaseStatus::Revoked)
+                .await?;
+            info!(id = %lease.id, issued_for = %lease.issued_for, "revoked an InfluxDB token");
+        }
+        Ok(())
+    }
+
+    async fn handle_request(&self, from: &Identifier, req: &RequestHeader) -> Result<Vec<u8>> {
+        // make sure that the statuses returned to the caller are up to date
+        expire_leases(self.leases.as_ref(), self.provider.as_ref()).await?;
+
+        let path_segments = req.path_segments::<2>();
+        let res = match (req.method(), path_segments.as_slice()) {
+            (Some(Method::Post), [""]) => {
+                let decision = self.user_decision(from).await?;
+                if !decision.is_authorized() && !self.is_admin(from).await? {
+                    let message = match decision.reason() {
+                        Some(reason) => format!("Forbidden: {reason}"),
+                        None => "Forbidden".to_string(),
+                    };
+                    return Ok(Response::forbidden(req, &message).to_vec()?);
+                }
+                Response::ok(req)
+                    .body(self.create_lease(from).await?)
+                    .to_vec()?
+            }
+            (Some(Method::Get), [""]) => {
+                let leases = if self.is_admin(from).await? {
+                    self.leases.list(None).await?
+                } else {
+                    self.leases.list(Some(from)).await?
+                };
+                let tokens = leases
+                    .iter()
+                    .map(Lease::to_token)
+                    .collect::<Result<Vec<Token>>>()?;
+                Response::ok(req).body(tokens).to_vec()?
+            }
+            (Some(Method::Get), [id]) => match self.find_lease(from, id).await? {
+                Some(lease) => Response::ok(req).body(lease.to_token()?).to_vec()?,
+                None => Response::not_found(req, &format!("Lease {id} not found")).to_vec()?,
+            },
+            (Some(Method::Delete), [id]) => match self.find_lease(from, id).await? {
+                Some(lease) => {
+                    self.revoke_lease(&lease).await?;
+                    Response::ok(req).to_vec()?
+                }
+                None => Response::not_found(req, &format!("Lease {id} not found")).to_vec()?,
+            },
+            (Some(_), _) => Response::unknown_path(req).to_vec()?,
+            (None, _) => Response::invalid_method(req).to_vec()?,
+        };
+        Ok(res)
+    }
+}
+
+#[ockam_core::worker]
+impl Worker for InfluxDbTokenLeaseManager {
+    type Context = Context;
+    type Message = Vec<u8>;
+
+    async fn initialize(&mut self, _ctx: &mut Context) -> Result<()> {
+        let leases = self.leases.clone();
+        let provider = self.provider.clone();
+        self.expiration = Some(tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(EXPIRATION_CHECK_INTERVAL).await;
+                if let Err(e) = expire_leases(leases.as_ref(), provider.as_ref()).await {
+                    warn!(%e, "cannot expire the InfluxDB token leases");
+                }
+            }
+        }));
+        Ok(())
+    }
+
+    async fn shutdown(&mut self, _ctx: &mut Self::Context) -> Result<()> {
+        if let Some(expiration) = self.expiration.take() {
+            expiration.abort();
+        }
+        Ok(())
+    }
+
+    async fn handle_message(&mut self, c: &mut Context, m: Routed<Self::Message>) -> Result<()> {
+        if let Ok(i) = IdentitySecureChannelLocalInfo::find_info(m.local_message()) {
+            let from = i.their_identity_id();
+            let mut dec = Decoder::new(m.as_body());
+            let req: RequestHeader = dec.decode()?;
+            trace! {
+                target: "ockam_api::influxdb_token_lease",
+                from   = %from,
+                id     = %req.id(),
+                method = ?req.method(),
+                path   = %req.path(),
+                body   = %req.has_body(),
+                "request"
+            }
+            let res = match self.handle_request(&from, &req).await {
+                Ok(res) => res,
+                Err(error) => Response::internal_error(&req, &error.to_string()).to_vec()?,
+            };
+            c.send(m.return_route(), res).await
+        } else {
+            secure_channel_required(c, m).await
+        }
+    }
+}
+
+/// Revoke the tokens of the active leases whose time is over.
+/// A lease stays active if its token can't be revoked, so that it is revoked at the next check
+async fn expire_leases(leases: &dyn LeasesRepository, provider: &dyn TokenProvider) -> Result<()> {
+    for lease in leases.expired(now()?).await? {
+        match provider.revoke_token(&lease.id).await {
+            Ok(()) => {
+                leases.deactivate(&lease.id, LeaseStatus::Expired).await?;
+                debug!(id = %lease.id, issued_for = %lease.issued_for, "an InfluxDB token lease expired");
+            }
+            Err(e) => warn!(id = %lease.id, %e, "cannot revoke an expired InfluxDB token"),
+        }
+    }
+    Ok(())
+}
+
+/// Return an access control evaluating an access rule of the lease manager
+fn access_control(
+    identities: Arc<dyn IdentitiesRepository>,
+    rule: &Option<String>,
+) -> Result<Option<AbacAccessControl>> {
+    let expression = match rule {
+        Some(rule) => {
+            ockam_abac::parse(rule).map_err(|e| Error::new(Origin::Api, Kind::Invalid, e))?
+        }
+        None => None,
+    };
+    Ok(expression.map(|expression| {
+        let mut env = Env::new();
+        env.put("resource.id", str(DefaultAddress::INFLUXDB_TOKEN_LEASE));
+        env.put("action.id", str(actions::HANDLE_MESSAGE.as_str()));
+        AbacAccessControl::new(identities, expression, env).with_explain_mode(policy_explain_mode())
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::influxdb_token_lease::{InMemoryLeases, ProvidedToken};
+    use ockam::identity::IdentitiesStorage;
+    use ockam_core::api::Status;
+    use ockam_core::async_trait;
+    use std::str::FromStr;
+    use std::sync::Mutex;
+
+    #[tokio::test]
+    async fn test_only_allowed_identities_can_lease_a_token() -> Result<()> {
+        let (identities, admin, user, other) = identities().await?;
+        let provider = Arc::new(FakeProvider::default());
+        let leases = Arc::new(InMemoryLeases::default());
+
+        // without a user access rule, only the admins can lease a token
+        let manager = lease_manager(identities.clone(), None, leases.clone(), provider.clone())?;
+        assert_eq!(create(&manager, &user).await?, Status::Forbidden);
+        assert_eq!(create(&manager, &admin).await?, Status::Ok);
+
+        let manager = lease_manager(
+            identities,
+            Some(r#"(= subject.role "user")"#),
+            leases.clone(),
+            provider,
+        )?;
+        assert_eq!(create(&manager, &user).await?, Status::Ok);
+        assert_eq!(create(&manager, &other).await?, Status::Forbidden);
+
+        // the secrets of the tokens are not stored
+        assert_eq!(leases.list(None).await?.len(), 2);
+        let user_leases = leases.list(Some(&user)).await?;
+        assert_eq!(user_leases.len(), 1);
+        assert!(user_leases[0].to_token()?.token.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_only_the_owner_or_an_admin_can_revoke_a_lease() -> Result<()> {
+        let (identities, admin, user, other) = identities().await?;
+        let provider = Arc::new(FakeProvider::default());
+        let leases = Arc::new(InMemoryLeases::default());
+        let manager = lease_manager(
+            identities,
+            Some(r#"(or (= subject.role "user") (= subject.role "other"))"#),
+            leases.clone(),
+            provider.clone(),
+        )?;
+        assert_eq!(create(&manager, &user).await?, Status::Ok);
+        let id = leases.list(Some(&user)).await?[0].id.clone();
+
+        let revoke = RequestHeader::new(Method::Delete, format!("/{id}"), false);
+        assert_eq!(
+            status(manager.handle_request(&other, &revoke).await?)?,
+            Status::NotFound
+        );
+        assert!(provider.revoked().is_empty());
+
+        assert_eq!(
+            status(manager.handle_request(&admin, &revoke).await?)?,
+            Status::Ok
+        );
+        assert_eq!(provider.revoked(), vec![id.clone()]);
+        assert_eq!(
+            leases.get(&id).await?.map(|l| l.status),
+            Some(LeaseStatus::Revoked)
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_a_token_is_revoked_if_its_lease_cannot_be_stored() -> Result<()> {
+        let (identities, admin, _, _) = identities().await?;
+        let provider = Arc::new(FakeProvider::default());
+        let manager = lease_manager(identities, None, Arc::new(FailingLeases), provider.clone())?;
+
+        let request = RequestHeader::new(Method::Post, "/", false);
+        assert!(manager.handle_request(&admin, &request).await.is_err());
+        assert_eq!(provider.revoked(), vec!["1".to_string()]);
+        Ok(())
+    }
+
+    async fn identities() -> Result<(
+        Arc<dyn IdentitiesRepository>,
+        Identifier,
+        Identifier,
+        Identifier,
+    )> {
+        let identities: Arc<dyn IdentitiesRepository> = IdentitiesStorage::create();
+        let admin = Identifier::from_str("I0123456789abcdef0123456789abcdef01234567")?;
+        let user = Identifier::from_str("I76543210fedcba9876543210fedcba9876543210")?;
+        let other = Identifier::from_str("Ie86be15e83d1c93e24dd1967010b01b6df491b45")?;
+        for (identifier, role) in [(&admin, "admin"), (&user, "user"), (&other, "other")] {
+            identities
+                .put_attribute_value(identifier, b"role".to_vec(), role.as_bytes().to_vec())
+                .await?;
+        }
+        Ok((identities, admin, user, other))
+    }
+
+    fn lease_manager(
+        identities: Arc<dyn IdentitiesRepository>,
+        user_access_rule: Option<&str>,
+        leases: Arc<dyn LeasesRepository>,
+        provider: Arc<dyn TokenProvider>,
+    ) -> Result<InfluxDbTokenLeaseManager> {
+        let config = InfluxDBTokenLeaseManagerConfig::new(
+            "http://localhost:8086",
+            "admin-token",
+            "org",
+            "[]",
+            3600,
+            user_access_rule,
+            Some(r#"(= subject.role "admin")"#),
+        );
+        InfluxDbTokenLeaseManager::new(&config, identities, leases, provider)
+    }
+
+    async fn create(manager: &InfluxDbTokenLeaseManager, from: &Identifier) -> Result<Status> {
+        let request = RequestHeader::new(Method::Post, "/", false);
+        status(manager.handle_request(from, &request).await?)
+    }
+
+    fn status(response: Vec<u8>) -> Result<Status> {
+        let (header, _) = Response::parse_response_header(&response)?;
+        Ok(header.status().unwrap())
+    }
+
+    #[derive(Default)]
+    struct FakeProvider {
+        created: Mutex<u32>,
+        revoked: Mutex<Vec<String>>,
+    }
+
+    impl FakeProvider {
+        fn revoked(&self) -> Vec<String> {
+            self.revoked.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl TokenProvider for FakeProvider {
+        async fn create_token(&self, _issued_for: &Identifier) -> Result<ProvidedToken> {
+            let mut created = self.created.lock().unwrap();
+            *created += 1;
+            Ok(ProvidedToken {
+                id: created.to_string(),
+                token: format!("secret-{created}"),
+            })
+        }
+
+        async fn revoke_token(&self, id: &str) -> Result<()> {
+            self.revoked.lock().unwrap().push(id.to_string());
+            Ok(())
+        }
+    }
+
+    struct FailingLeases;
+
+    #[async_trait]
+    impl LeasesRepository for FailingLeases {
+        async fn put(&self, _lease: Lease) -> Result<()> {
+            Err(Error::new(
+                Origin::Api,
+                Kind::Io,
+                "the storage is unavailable",
+            ))
+        }
+
+        async fn get(&self, _id: &str) -> Result<Option<Lease>> {
+            Ok(None)
+        }
+
+        async fn list(&self, _issued_for: Option<&Identifier>) -> Result<Vec<Lease>> {
+            Ok(vec![])
+        }
+
+        async fn expired(&self, _now: TimestampInSeconds) -> Result<Vec<Lease>> {
+            Ok(vec![])
+        }
+
+        async fn deactivate(&self, _id: &str, _status: LeaseStatus) -> Result<bool> {
+            Ok(false)
+        }
+    }
+}
@@ -0,0 +1,500 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use minicbor::{Decode, Encode};
+
+use ockam::identity::storage::Storage;
+use ockam::identity::{Identifier, TimestampInSeconds};
+use ockam_core::compat::sync::{Arc, RwLock};
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{async_trait, Result};
+use time::format_description::well_known::Iso8601;
+use time::{OffsetDateTime, PrimitiveDateTime};
+
+use crate::cloud::lease_manager::models::influxdb::Token;
+
+/// Status of a lease
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Encode, Decode)]
+#[rustfmt::skip]
+#[cbor(index_only)]
+pub enum LeaseStatus {
+    /// The token can be used until the lease expires
+    #[n(0)] Active,
+    /// The token was revoked before the end of the lease
+    #[n(1)] Revoked,
+    /// The lease expired and the token was revoked
+    #[n(2)] Expired,
+}
+
+impl Display for LeaseStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LeaseStatus::Active => f.write_str("active"),
+            LeaseStatus::Revoked => f.write_str("revoked"),
+            LeaseStatus::Expired => f.write_str("expired"),
+        }
+    }
+}
+
+impl FromStr for LeaseStatus {
+    type Err = ockam_core::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "active" => Ok(LeaseStatus::Active),
+            "revoked" => Ok(LeaseStatus::Revoked),
+            "expired" => Ok(LeaseStatus::Expired),
+            _ => Err(ockam_core::Error::new(
+                Origin::Api,
+                Kind::Invalid,
+                format!("unknown lease status {s}"),
+            )),
+        }
+    }
+}
+
+/// A token leased to an identity until `expires_at`.
+///
+/// The secret of the token is only returned to the identity when the lease is created,
+/// it is never stored with the lease
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct Lease {
+    /// Identifier of the token in the database issuing it
+    #[n(1)] pub id: String,
+    #[n(2)] pub issued_for: Identifier,
+    #[n(3)] pub created_at: TimestampInSeconds,
+    #[n(4)] pub expires_at: TimestampInSeconds,
+    #[n(5)] pub status: LeaseStatus,
+}
+
+impl Lease {
+    /// Return true if the lease is still active but its time is over
+    pub fn is_expired(&self, now: TimestampInSeconds) -> bool {
+        self.status == LeaseStatus::Active && self.expires_at <= now
+    }
+
+    /// Return the lease as it is sent to the clients of the lease manager.
+    /// The secret of the token is left empty since it is not stored
+    pub fn to_token(&self) -> Result<Token> {
+        Ok(Token {
+            id: self.id.clone(),
+            issued_for: self.issued_for.to_string(),
+            created_at: format_timestamp(self.created_at)?,
+            expires: format_timestamp(self.expires_at)?,
+            token: String::new(),
+            status: self.status.to_string(),
+        })
+    }
+}
+
+/// Format a timestamp as an ISO 8601 date, without an offset, as expected by `ockam lease`
+fn format_timestamp(timestamp: TimestampInSeconds) -> Result<String> {
+    let date = OffsetDateTime::from_unix_timestamp(*timestamp as i64)
+        .map_err(|e| ockam_core::Error::new(Origin::Api, Kind::Invalid, e))?;
+    PrimitiveDateTime::new(date.date(), date.time())
+        .format(&Iso8601::DEFAULT)
+        .map_err(|e| ockam_core::Error::new(Origin::Api, Kind::Serialization, e))
+}
+
+/// Storage for the leases issued by a lease manager
+#[async_trait]
+pub trait LeasesRepository: Send + Sync + 'static {
+    /// Store a new lease
+    async fn put(&self, lease: Lease) -> Result<()>;
+
+    /// Return a lease
+    async fn get(&self, id: &str) -> Result<Option<Lease>>;
+
+    /// Return all the leases, or only the leases issued for a given identity
+    async fn list(&self, issued_for: Option<&Identifier>) -> Result<Vec<Lease>>;
+
+    /// Return the active leases whose time is over
+    async fn expired(&self, now: TimestampInSeconds) -> Result<Vec<Lease>>;
+
+    /// Change the status of an active lease.
+    /// Return false if the lease doesn't exist or is not active anymore
+    async fn deactivate(&self, id: &str, status: LeaseStatus) -> Result<bool>;
+}
+
+/// Leases kept in memory
+#[derive(Default)]
+pub struct InMemoryLeases {
+    leases: RwLock<BTreeMap<String, Lease>>,
+}
+
+#[async_trait]
+impl LeasesRepository for InMemoryLeases {
+    async fn put(&self, lease: Lease) -> Result<()> {
+        self.leases
+            .write()
+            .map(|mut leases| {
+                leases.insert(lease.id.clone(), lease);
+            })
+            .map_err(|_| lock_error())
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<Lease>> {
+        self.leases
+            .read()
+            .map(|leases| leases.get(id).cloned())
+            .map_err(|_| lock_error())
+    }
+
+    async fn list(&self, issued_for: Option<&Identifier>) -> Result<Vec<Lease>> {
+        self.leases
+            .read()
+            .map(|leases| {
+                leases
+                    .values()
+                    .filter(|lease| issued_for.map_or(true, |i| &lease.issued_for == i))
+                    .cloned()
+                    .collect()
+            })
+            .map_err(|_| lock_error())
+    }
+
+    async fn expired(&self, now: TimestampInSeconds) -> Result<Vec<Lease>> {
+        self.leases
+            .read()
+            .map(|leases| {
+                leases
+                    .values()
+                    .filter(|lease| lease.is_expired(now))
+                    .cloned()
+                    .collect()
+            })
+            .map_err(|_| lock_error())
+    }
+
+    async fn deactivate(&self, id: &str, status: LeaseStatus) -> Result<bool> {
+        self.leases
+            .write()
+            .map(|mut leases| match leases.get_mut(id) {
+                Some(lease) if lease.status == LeaseStatus::Active => {
+                    lease.status = status;
+                    true
+                }
+                _ => false,
+            })
+            .map_err(|_| lock_error())
+    }
+}
+
+/// Leases persisted in the storage of a node, so that the tokens of the leases issued before
+/// a restart of the node are still revoked when the leases expire
+pub struct StorageLeases {
+    storage: Arc<dyn Storage>,
+}
+
+impl StorageLeases {
+    const LEASES_ID: &'static str = "influxdb_token_leases";
+
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self { storage }
+    }
+
+    async fn all(&self) -> Result<Vec<Lease>> {
+        let mut leases = vec![];
+        for id in self.storage.keys(Self::LEASES_ID).await? {
+            if let Some(lease) = self.get(&id).await? {
+                leases.push(lease);
+            }
+        }
+        Ok(leases)
+    }
+}
+
+#[async_trait]
+impl LeasesRepository for StorageLeases {
+    async fn put(&self, lease: Lease) -> Result<()> {
+        self.storage
+            .set(Self::LEASES_ID, lease.id.clone(), minicbor::to_vec(&lease)?)
+            .await
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<Lease>> {
+        match self.storage.get(Self::LEASES_ID, id).await? {
+            Some(bytes) => Ok(Some(minicbor::decode(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn list(&self, issued_for: Option<&Identifier>) -> Result<Vec<Lease>> {
+        Ok(self
+            .all()
+            .await?
+            .into_iter()
+            .filter(|lease| issued_for.map_or(true, |i| &lease.issued_for == i))
+            .collect())
+    }
+
+    async fn expired(&self, now: TimestampInSeconds) -> Result<Vec<Lease>> {
+        Ok(self
+            .all()
+            .await?
+            .into_iter()
+            .filter(|lease| lease.is_expired(now))
+            .collect())
+    }
+
+    async fn deactivate(&self, id: &str, status: LeaseStatus) -> Result<bool> {
+        match self.get(id).await? {
+            Some(mut lease) if lease.status == LeaseStatus::Active => {
+                lease.status = status;
+                self.put(lease).await?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+fn lock_error() -> ockam_core::Error {
+    ockam_core::Error::new(
+        Origin::Other,
+        Kind::Internal,
+        "failed to get a lock on the leases table",
+    )
+}
+
+#[cfg(feature = "postgres")]
+mod postgres {
+    use super::*;
+    use ockam::identity::storage::PostgresClient;
+    use tokio_postgres::Row;
+
+    /// Leases stored in a Postgres database, so that they survive a restart of the lease
+    /// manager and can be revoked by any lease manager sharing the database
+    pub struct PostgresLeases {
+        client: PostgresClient,
+    }
+
+    impl PostgresLeases {
+        const CREATE_LEASE_TABLE_SQL: &'static str = "CREATE TABLE IF NOT EXISTS token_lease (
+            id TEXT PRIMARY KEY,
+            issued_for TEXT NOT NULL,
+            created_at BIGINT NOT NULL,
+            expires_at BIGINT NOT NULL,
+            status TEXT NOT NULL
+        );";
+        // used to find the expired leases, without scanning the whole table
+        const CREATE_LEASE_EXPIRATION_INDEX_SQL: &'static str =
+            "CREATE INDEX IF NOT EXISTS idx_token_lease_expiration
+            ON token_lease (status, expires_at);";
+
+        const SELECT_LEASE_SQL: &'static str =
+            "SELECT id, issued_for, created_at, expires_at, status FROM token_lease";
+
+        /// Create the leases table and its indexes if necessary
+        pub async fn new(client: PostgresClient) -> Result<Self> {
+            client
+                .run(|client| async move {
+                    client
+                        .batch_execute(
+                            &(Self::CREATE_LEASE_TABLE_SQL.to_owned()
+                                + Self::CREATE_LEASE_EXPIRATION_INDEX_SQL),
+                        )
+                        .await
+                })
+                .await?;
+            Ok(Self { client })
+        }
+
+        fn lease_from_row(row: Row) -> Result<Lease> {
+            let issued_for: String = row.get(1);
+            let created_at: i64 = row.get(2);
+            let expires_at: i64 = row.get(3);
+            let status: String = row.get(4);
+            Ok(Lease {
+                id: row.get(0),
+                issued_for: Identifier::from_str(&issued_for)?,
+                created_at: TimestampInSeconds(created_at as u64),
+                expires_at: TimestampInSeconds(expires_at as u64),
+                status: LeaseStatus::from_str(&status)?,
+            })
+        }
+    }
+
+    #[async_trait]
+    impl LeasesRepository for PostgresLeases {
+        async fn put(&self, lease: Lease) -> Result<()> {
+            let issued_for = &lease.issued_for.to_string();
+            let status = &lease.status.to_string();
+            let lease = &lease;
+            self.client
+                .run(|client| async move {
+                    client
+                        .execute(
+                            "INSERT INTO token_lease (id, issued_for, created_at, expires_at, status)
+                             VALUES ($1, $2, $3, $4, $5)",
+                            &[
+                                &lease.id,
+                                issued_for,
+                                &(*lease.created_at as i64),
+                                &(*lease.expires_at as i64),
+                                status,
+                            ],
+                        )
+                        .await
+                })
+                .await?;
+            Ok(())
+        }
+
+        async fn get(&self, id: &str) -> Result<Option<Lease>> {
+            let row = self
+                .client
+                .run(|client| async move {
+                    client
+                        .query_opt(&format!("{} WHERE id = $1", Self::SELECT_LEASE_SQL), &[&id])
+                        .await
+                })
+                .await?;
+            row.map(Self::lease_from_row).transpose()
+        }
+
+        async fn list(&self, issued_for: Option<&Identifier>) -> Result<Vec<Lease>> {
+            let issued_for = &issued_for.map(|i| i.to_string());
+            let rows = self
+                .client
+                .run(|client| async move {
+                    match issued_for {
+                        Some(issued_for) => {
+                            client
+                                .query(
+                                    &format!("{} WHERE issued_for = $1", Self::SELECT_LEASE_SQL),
+                                    &[issued_for],
+                                )
+                                .await
+                        }
+                        None => client.query(Self::SELECT_LEASE_SQL, &[]).await,
+                    }
+                })
+                .await?;
+            rows.into_iter().map(Self::lease_from_row).collect()
+        }
+
+        async fn expired(&self, now: TimestampInSeconds) -> Result<Vec<Lease>> {
+            let active = &LeaseStatus::Active.to_string();
+            let now = *now as i64;
+            let rows = self
+                .client
+                .run(|client| async move {
+                    client
+                        .query(
+                            &format!(
+                                "{} WHERE status = $1 AND expires_at <= $2",
+                                Self::SELECT_LEASE_SQL
+                            ),
+                            &[active, &now],
+                        )
+                        .await
+                })
+                .await?;
+            rows.into_iter().map(Self::lease_from_row).collect()
+        }
+
+        async fn deactivate(&self, id: &str, status: LeaseStatus) -> Result<bool> {
+            let status = &status.to_string();
+            let active = &LeaseStatus::Active.to_string();
+            let updated = self
+                .client
+                .run(|client| async move {
+                    client
+                        .execute(
+                            "UPDATE token_lease SET status = $1 WHERE id = $2 AND status = $3",
+                            &[status, &id, active],
+                        )
+                        .await
+                })
+                .await?;
+            Ok(updated > 0)
+        }
+    }
+}
+
+#[cfg(feature = "postgres")]
+pub use postgres::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam::identity::storage::InMemoryStorage;
+
+    #[tokio::test]
+    async fn test_in_memory_leases() -> Result<()> {
+        check_leases_repository(&InMemoryLeases::default()).await
+    }
+
+    #[tokio::test]
+    async fn test_storage_leases() -> Result<()> {
+        let storage = InMemoryStorage::create();
+        check_leases_repository(&StorageLeases::new(storage.clone())).await?;
+
+        // the leases are kept by the storage
+        let leases = StorageLeases::new(storage);
+        assert_eq!(leases.list(None).await?.len(), 3);
+        assert_eq!(
+            leases.get("3").await?.map(|l| l.status),
+            Some(LeaseStatus::Revoked)
+        );
+        Ok(())
+    }
+
+    async fn check_leases_repository(storage: &dyn LeasesRepository) -> Result<()> {
+        let alice = Identifier::from_str("I0123456789abcdef0123456789abcdef01234567")?;
+        let bob = Identifier::from_str("I76543210fedcba9876543210fedcba9876543210")?;
+        let lease = |id: &str, issued_for: &Identifier, expires_at: u64| Lease {
+            id: id.to_string(),
+            issued_for: issued_for.clone(),
+            created_at: TimestampInSeconds(100),
+            expires_at: TimestampInSeconds(expires_at),
+            status: LeaseStatus::Active,
+        };
+        storage.put(lease("1", &alice, 200)).await?;
+        storage.put(lease("2", &alice, 300)).await?;
+        storage.put(lease("3", &bob, 200)).await?;
+
+        assert_eq!(storage.list(None).await?.len(), 3);
+        assert_eq!(storage.list(Some(&alice)).await?.len(), 2);
+
+        // a lease is only deactivated once
+        assert!(storage.deactivate("3", LeaseStatus::Revoked).await?);
+        assert!(!storage.deactivate("3", LeaseStatus::Expired).await?);
+        assert!(!storage.deactivate("4", LeaseStatus::Revoked).await?);
+        assert_eq!(
+            storage.get("3").await?.map(|l| l.status),
+            Some(LeaseStatus::Revoked)
+        );
+
+        // revoked leases are not returned as expired
+        let expired: Vec<String> = storage
+            .expired(TimestampInSeconds(250))
+            .await?
+            .into_iter()
+            .map(|l| l.id)
+            .collect();
+        assert_eq!(expired, vec!["1".to_string()]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_lease_to_token() -> Result<()> {
+        let lease = Lease {
+            id: "1".to_string(),
+            issued_for: Identifier::from_str("I0123456789abcdef0123456789abcdef01234567")?,
+            created_at: TimestampInSeconds(0),
+            expires_at: TimestampInSeconds(3600),
+            status: LeaseStatus::Active,
+        };
+        let token = lease.to_token()?;
+        assert_eq!(token.status, "active");
+        assert!(token.token.is_empty());
+        let expires = PrimitiveDateTime::parse(&token.expires, &Iso8601::DEFAULT).unwrap();
+        assert_eq!(expires.assume_utc().unix_timestamp(), 3600);
+        Ok(())
+    }
+}
@@ -0,0 +1,227 @@
+use core::fmt::{Display, Formatter};
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
+
+use serde::Serialize;
+use tracing::{debug, warn};
+
+use ockam::identity::utils::now;
+use ockam::identity::{AttributesEntry, Identifier};
+use ockam_core::env::get_env;
+
+/// Environment variable setting the file where the encryption and decryption of every kafka
+/// message is recorded, as one JSON object per line
+const OCKAM_KAFKA_AUDIT_LOG_PATH: &str = "OCKAM_KAFKA_AUDIT_LOG_PATH";
+
+/// Tracing target of the audit records, so that they can be filtered out of the other logs
+const AUDIT_TARGET: &str = "ockam_api::kafka::audit";
+
+/// Maximum number of records waiting to be written to the audit log file.
+/// The records are dropped when the file can't be written fast enough
+const AUDIT_LOG_CAPACITY: usize = 10_000;
+
+/// Operation performed by a kafka portal on a message
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum KafkaAuditOperation {
+    Encrypt,
+    Decrypt,
+}
+
+impl Display for KafkaAuditOperation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            KafkaAuditOperation::Encrypt => f.write_str("encrypt"),
+            KafkaAuditOperation::Decrypt => f.write_str("decrypt"),
+        }
+    }
+}
+
+/// Provenance of a kafka message: the topic partition of the message and the identity at the
+/// other end of the secure channel used to encrypt or decrypt it, with the attributes of its
+/// credential
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub(crate) struct KafkaAuditRecord {
+    pub(crate) timestamp: u64,
+    pub(crate) operation: KafkaAuditOperation,
+    pub(crate) topic: String,
+    pub(crate) partition: i32,
+    pub(crate) peer: Identifier,
+    pub(crate) attributes: BTreeMap<String, String>,
+}
+
+impl KafkaAuditRecord {
+    pub(crate) fn new(
+        operation: KafkaAuditOperation,
+        topic: &str,
+        partition: i32,
+        peer: Identifier,
+        attributes: Option<AttributesEntry>,
+    ) -> Self {
+        let attributes = attributes
+            .map(|entry| {
+                entry
+                    .attrs()
+                    .iter()
+                    .map(|(key, value)| {
+                        (
+                            String::from_utf8_lossy(key).to_string(),
+                            String::from_utf8_lossy(value).to_string(),
+                        )
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            timestamp: now().map(|t| *t).unwrap_or_default(),
+            operation,
+            topic: topic.to_string(),
+            partition,
+            peer,
+            attributes,
+        }
+    }
+}
+
+/// Audit log of the kafka messages encrypted and decrypted by a node.
+///
+/// Every record is logged at the debug level with the `ockam_api::kafka::audit` target, and
+/// appended to the file set with `OCKAM_KAFKA_AUDIT_LOG_PATH` when it is set.
+/// The audit is best-effort: the file is written by a separate thread, so that the messages
+/// are never delayed, and a record is dropped if it can't be written
+#[derive(Clone, Default)]
+pub(crate) struct KafkaAuditLog {
+    sender: Option<SyncSender<KafkaAuditRecord>>,
+}
+
+impl KafkaAuditLog {
+    /// Create an audit log writing to the file set with `OCKAM_KAFKA_AUDIT_LOG_PATH`, if any
+    pub(crate) fn from_env() -> Self {
+        match get_env::<PathBuf>(OCKAM_KAFKA_AUDIT_LOG_PATH) {
+            Ok(Some(path)) => match Self::create(path.clone()) {
+                Ok(audit_log) => audit_log,
+                Err(e) => {
+                    warn!("Kafka audit log file disabled, cannot open {path:?}: {e}");
+                    Self::default()
+                }
+            },
+            Ok(None) => Self::default(),
+            Err(e) => {
+                warn!("Kafka audit log file disabled: {e}");
+                Self::default()
+            }
+        }
+    }
+
+    /// Create an audit log appending its records to a file
+    pub(crate) fn create(path: PathBuf) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let (sender, receiver) = sync_channel(AUDIT_LOG_CAPACITY);
+        std::thread::Builder::new()
+            .name("kafka-audit-log".to_string())
+            .spawn(move || Self::write_records(file, receiver))?;
+        Ok(Self {
+            sender: Some(sender),
+        })
+    }
+
+    /// Return true if the records are written to a file or logged
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.sender.is_some() || tracing::enabled!(target: AUDIT_TARGET, tracing::Level::DEBUG)
+    }
+
+    /// Record the encryption or the decryption of a message
+    pub(crate) fn record(&self, record: KafkaAuditRecord) {
+        debug!(
+            target: AUDIT_TARGET,
+            operation = %record.operation,
+            topic = %record.topic,
+            partition = record.partition,
+            peer = %record.peer,
+            attributes = ?record.attributes,
+            "kafka message"
+        );
+        if let Some(sender) = &self.sender {
+            if let Err(TrySendError::Full(_)) = sender.try_send(record) {
+                debug!("The kafka audit log is full, a record was dropped");
+            }
+        }
+    }
+
+    /// Append the records to the file, until all the audit logs are dropped.
+    /// The file is flushed once there are no more records to write
+    fn write_records(file: File, receiver: Receiver<KafkaAuditRecord>) {
+        let mut writer = BufWriter::new(file);
+        while let Ok(record) = receiver.recv() {
+            let mut result = Self::write_record(&mut writer, &record);
+            while let Ok(record) = receiver.try_recv() {
+                result = result.and(Self::write_record(&mut writer, &record));
+            }
+            if let Err(e) = result.and(writer.flush()) {
+                warn!("Failed to write the kafka audit log: {e}");
+            }
+        }
+    }
+
+    fn write_record(writer: &mut impl Write, record: &KafkaAuditRecord) -> std::io::Result<()> {
+        let line = serde_json::to_string(record).map_err(std::io::Error::from)?;
+        writeln!(writer, "{line}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::str::FromStr;
+
+    #[test]
+    fn test_audit_log_file() -> ockam_core::Result<()> {
+        let path = std::env::temp_dir().join(format!(
+            "kafka-audit-{}.log",
+            hex::encode(rand::random::<[u8; 8]>())
+        ));
+        let audit_log = KafkaAuditLog::create(path.clone()).unwrap();
+        let peer = Identifier::from_str("I0123456789abcdef0123456789abcdef01234567")?;
+        let attributes = AttributesEntry::new(
+            [(b"role".to_vec(), b"consumer".to_vec())].into(),
+            now()?,
+            None,
+            None,
+        );
+        for operation in [KafkaAuditOperation::Encrypt, KafkaAuditOperation::Decrypt] {
+            audit_log.record(KafkaAuditRecord::new(
+                operation,
+                "orders",
+                3,
+                peer.clone(),
+                Some(attributes.clone()),
+            ));
+        }
+
+        // the records are written in the background
+        let mut contents = String::new();
+        for _ in 0..50 {
+            contents = std::fs::read_to_string(&path).unwrap();
+            if contents.lines().count() == 2 {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(100));
+        }
+        let _ = std::fs::remove_file(&path);
+        let records: Vec<serde_json::Value> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["operation"], "encrypt");
+        assert_eq!(records[1]["operation"], "decrypt");
+        assert_eq!(records[1]["topic"], "orders");
+        assert_eq!(records[1]["partition"], 3);
+        assert_eq!(records[1]["peer"], peer.to_string());
+        assert_eq!(records[1]["attributes"]["role"], "consumer");
+        Ok(())
+    }
+}
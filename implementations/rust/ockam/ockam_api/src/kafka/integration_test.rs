@@ -43,7 +43,7 @@ mod test {
     use crate::kafka::protocol_aware::utils::{encode_request, encode_response};
     use crate::kafka::secure_channel_map::RelayCreator;
     use crate::kafka::{
-        ConsumerNodeAddr, KafkaInletController, KafkaPortalListener,
+        ConsumerNodeAddr, KafkaInletController, KafkaMetrics, KafkaPortalListener,
         KafkaSecureChannelControllerImpl,
     };
     use crate::test_utils::NodeManagerHandle;
@@ -70,6 +70,7 @@ mod test {
         handler: &NodeManagerHandle,
         listener_address: Address,
         outlet_address: Address,
+        metrics: KafkaMetrics,
     ) -> ockam::Result<u16> {
         let secure_channel_controller = KafkaSecureChannelControllerImpl::new_extended(
             handler.secure_channels.clone(),
@@ -103,6 +104,10 @@ mod test {
             inlet_controller,
             secure_channel_controller.into_trait(),
             listener_address,
+            None,
+            Default::default(),
+            None,
+            metrics,
         )
         .await?;
 
@@ -115,12 +120,14 @@ mod test {
         context: &mut Context,
     ) -> ockam::Result<()> {
         let handler = crate::util::test_utils::start_manager_for_tests(context).await?;
+        let metrics = KafkaMetrics::default();
 
         let consumer_bootstrap_port = create_kafka_service(
             context,
             &handler,
             "kafka_consumer_listener".into(),
             "kafka_consumer_outlet".into(),
+            metrics.clone(),
         )
         .await?;
 
@@ -129,6 +136,7 @@ mod test {
             &handler,
             "kafka_producer_listener".into(),
             "kafka_producer_outlet".into(),
+            metrics.clone(),
         )
         .await?;
 
@@ -233,6 +241,11 @@ mod test {
             "hello world!".as_bytes()
         );
 
+        // the produced record batch was encrypted once and decrypted when fetched
+        assert_eq!(metrics.encryption().count(), 1);
+        assert!(metrics.decryption().count() >= 1);
+        assert!(metrics.portal_requests().count() >= 2);
+
         context.stop().await?;
         consumer_mock_kafka.destroy_and_wait().await;
         producer_mock_kafka.destroy_and_wait().await;
@@ -0,0 +1,219 @@
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use core::time::Duration;
+
+use ockam_core::compat::sync::Arc;
+use ockam_node::{Context, MetricsSource};
+
+/// Upper bounds, in seconds, of the buckets of the latency histograms
+const LATENCY_BUCKETS: [f64; 12] = [
+    0.0001, 0.00025, 0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 1.0,
+];
+
+/// Histogram of latencies, with cumulative buckets
+#[derive(Debug, Default)]
+pub struct LatencyHistogram {
+    buckets: [AtomicU64; LATENCY_BUCKETS.len()],
+    count: AtomicU64,
+    sum_nanos: AtomicU64,
+}
+
+impl LatencyHistogram {
+    /// Record a latency
+    pub fn observe(&self, latency: Duration) {
+        let seconds = latency.as_secs_f64();
+        for (bound, bucket) in LATENCY_BUCKETS.iter().zip(&self.buckets) {
+            if seconds <= *bound {
+                bucket.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_nanos
+            .fetch_add(latency.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// Number of recorded latencies
+    pub fn count(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    /// Sum of the recorded latencies
+    pub fn sum(&self) -> Duration {
+        Duration::from_nanos(self.sum_nanos.load(Ordering::Relaxed))
+    }
+
+    /// Collect the samples of the histogram, the sum of the latencies being in microseconds
+    fn collect(&self, name: &str, out: &mut Vec<(String, u64)>) {
+        for (bound, bucket) in LATENCY_BUCKETS.iter().zip(&self.buckets) {
+            out.push((format!("{name}_le_{bound}"), bucket.load(Ordering::Relaxed)));
+        }
+        out.push((format!("{name}_count"), self.count()));
+        out.push((format!("{name}_sum_us"), self.sum().as_micros() as u64));
+    }
+}
+
+/// Latencies added by the kafka services of a node to the messages exchanged
+/// between the kafka clients and the brokers.
+///
+/// The metrics are shared by all the kafka services of the node. Once a kafka service
+/// is started, they are reported by the metrics collector of the node
+#[derive(Debug, Clone, Default)]
+pub struct KafkaMetrics {
+    inner: Arc<KafkaMetricsState>,
+}
+
+#[derive(Debug, Default)]
+struct KafkaMetricsState {
+    portal_requests: LatencyHistogram,
+    portal_responses: LatencyHistogram,
+    encryption: LatencyHistogram,
+    decryption: LatencyHistogram,
+    secure_channel_evictions: AtomicU64,
+    skipped_records: AtomicU64,
+    tombstoned_records: AtomicU64,
+    failed_records: AtomicU64,
+    registered: AtomicBool,
+}
+
+impl KafkaMetrics {
+    /// Time spent by the portal workers to intercept and forward kafka requests
+    pub fn portal_requests(&self) -> &LatencyHistogram {
+        &self.inner.portal_requests
+    }
+
+    /// Time spent by the portal workers to intercept and forward kafka responses
+    pub fn portal_responses(&self) -> &LatencyHistogram {
+        &self.inner.portal_responses
+    }
+
+    /// Time spent encrypting the records of each produced record batch
+    pub fn encryption(&self) -> &LatencyHistogram {
+        &self.inner.encryption
+    }
+
+    /// Time spent decrypting the records of each fetched record batch
+    pub fn decryption(&self) -> &LatencyHistogram {
+        &self.inner.decryption
+    }
+
+    /// Number of secure channels closed to keep the number of channels used
+    /// to encrypt and decrypt the kafka messages under its maximum
+    pub fn secure_channel_evictions(&self) -> u64 {
+        self.inner.secure_channel_evictions.load(Ordering::Relaxed)
+    }
+
+    /// Record the eviction of a secure channel
+    pub(crate) fn record_secure_channel_eviction(&self) {
+        self.inner
+            .secure_channel_evictions
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Number of fetched records which could not be decrypted and were removed from the response
+    pub fn skipped_records(&self) -> u64 {
+        self.inner.skipped_records.load(Ordering::Relaxed)
+    }
+
+    /// Number of fetched records which could not be decrypted and were replaced with a tombstone
+    pub fn tombstoned_records(&self) -> u64 {
+        self.inner.tombstoned_records.load(Ordering::Relaxed)
+    }
+
+    /// Number of fetched records which failed their partition
+    pub fn failed_records(&self) -> u64 {
+        self.inner.failed_records.load(Ordering::Relaxed)
+    }
+
+    /// Record a fetched record which failed its partition
+    pub(crate) fn record_failed_record(&self) {
+        self.inner.failed_records.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a fetched record removed from the response
+    pub(crate) fn record_skipped_record(&self) {
+        self.inner.skipped_records.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a fetched record replaced with a tombstone
+    pub(crate) fn record_tombstoned_record(&self) {
+        self.inner
+            .tombstoned_records
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Register the metrics with the metrics collector of the node.
+    /// The metrics are registered once, when the first kafka service is started
+    pub(crate) fn register(&self, context: &Context) {
+        if !self.inner.registered.swap(true, Ordering::Relaxed) {
+            context.register_metrics_source(Arc::new(self.clone()));
+        }
+    }
+}
+
+impl MetricsSource for KafkaMetrics {
+    fn collect(&self) -> Vec<(String, u64)> {
+        let mut out = vec![];
+        self.portal_requests()
+            .collect("kafka_portal_request_latency", &mut out);
+        self.portal_responses()
+            .collect("kafka_portal_response_latency", &mut out);
+        self.encryption()
+            .collect("kafka_encryption_latency", &mut out);
+        self.decryption()
+            .collect("kafka_decryption_latency", &mut out);
+        out.push((
+            "kafka_secure_channel_evictions".to_string(),
+            self.secure_channel_evictions(),
+        ));
+        out.push(("kafka_skipped_records".to_string(), self.skipped_records()));
+        out.push((
+            "kafka_tombstoned_records".to_string(),
+            self.tombstoned_records(),
+        ));
+        out.push(("kafka_failed_records".to_string(), self.failed_records()));
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_histogram() {
+        let metrics = KafkaMetrics::default();
+        metrics.encryption().observe(Duration::from_micros(300));
+        metrics.encryption().observe(Duration::from_millis(20));
+        metrics.encryption().observe(Duration::from_secs(2));
+        metrics.portal_requests().observe(Duration::from_micros(50));
+
+        assert_eq!(metrics.encryption().count(), 3);
+        assert_eq!(metrics.encryption().sum(), Duration::from_micros(2_020_300));
+
+        let collected = metrics.collect();
+        let value = |name: &str| {
+            collected
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, value)| *value)
+        };
+        // the buckets are cumulative
+        assert_eq!(value("kafka_encryption_latency_le_0.00025"), Some(0));
+        assert_eq!(value("kafka_encryption_latency_le_0.0005"), Some(1));
+        assert_eq!(value("kafka_encryption_latency_le_0.025"), Some(2));
+        assert_eq!(value("kafka_encryption_latency_le_1"), Some(2));
+        assert_eq!(value("kafka_encryption_latency_count"), Some(3));
+        assert_eq!(value("kafka_encryption_latency_sum_us"), Some(2_020_300));
+        assert_eq!(value("kafka_portal_request_latency_le_0.0001"), Some(1));
+        assert_eq!(value("kafka_portal_response_latency_count"), Some(0));
+
+        metrics.record_secure_channel_eviction();
+        metrics.record_skipped_record();
+        metrics.record_skipped_record();
+        metrics.record_tombstoned_record();
+        let collected = metrics.collect();
+        assert!(collected.contains(&("kafka_secure_channel_evictions".to_string(), 1)));
+        assert!(collected.contains(&("kafka_skipped_records".to_string(), 2)));
+        assert!(collected.contains(&("kafka_tombstoned_records".to_string(), 1)));
+        assert!(collected.contains(&("kafka_failed_records".to_string(), 0)));
+    }
+}
@@ -1,23 +1,33 @@
 //!This service allows encrypted transparent communication from the kafka producer
 //! to the kafka consumer without any modification in the existing application.
 
+mod audit;
 mod inlet_controller;
 mod integration_test;
 mod length_delimited;
+mod metrics;
+mod offset_signing;
 mod outlet_controller;
 mod outlet_service;
 mod portal_listener;
 mod portal_worker;
 mod protocol_aware;
 mod secure_channel_map;
+mod topic_filter;
 
 pub(crate) use inlet_controller::KafkaInletController;
+pub use metrics::{KafkaMetrics, LatencyHistogram};
 use ockam_core::Address;
+pub(crate) use offset_signing::OffsetCommitSigner;
 pub(crate) use outlet_service::prefix_relay::PrefixRelayService;
 pub(crate) use outlet_service::OutletManagerService;
 pub(crate) use portal_listener::KafkaPortalListener;
+#[cfg(any(test, feature = "benchmarks"))]
+pub(crate) use protocol_aware::wrap_encrypted_record;
 pub(crate) use secure_channel_map::ConsumerNodeAddr;
+pub(crate) use secure_channel_map::KafkaSecureChannelController;
 pub(crate) use secure_channel_map::KafkaSecureChannelControllerImpl;
+pub use topic_filter::{TopicFilter, TopicFilters};
 
 pub const KAFKA_OUTLET_CONSUMERS: &str = "kafka_consumers";
 pub const KAFKA_OUTLET_INTERCEPTOR_ADDRESS: &str = "kafka_interceptor";
@@ -0,0 +1,363 @@
+use std::collections::HashMap;
+
+use minicbor::{Decode, Encode};
+use ockam::identity::models::{
+    CredentialSignature, PurposeKeyAttestation, PurposeKeyAttestationData, PurposePublicKey,
+};
+use ockam::identity::utils::now;
+use ockam::identity::{Identifier, Identities};
+use ockam_core::compat::sync::{Arc, Mutex};
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Error, Result};
+use ockam_node::Context;
+
+use crate::nodes::models::events::NodeEventType;
+use crate::nodes::service::NodeEventPublisher;
+
+/// Prefix of the metadata of the signed offset commits
+const SIGNED_METADATA_PREFIX: &str = "ockam-signed:";
+
+#[derive(Debug, Clone, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+///Replaces the metadata of a committed offset, hex encoded
+struct SignedOffsetMetadata {
+    #[n(1)] metadata: Option<String>,
+    #[n(2)] purpose_key_attestation: PurposeKeyAttestation,
+    #[n(3)] signature: CredentialSignature,
+    /// Time of the commit, in seconds since the Unix epoch
+    #[n(4)] signed_at: u64,
+}
+
+#[derive(Debug, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+///Data covered by the signature of a committed offset
+struct SignedOffsetData<'a> {
+    #[n(1)] topic: &'a str,
+    #[n(2)] partition: i32,
+    #[n(3)] offset: i64,
+    #[n(4)] metadata: Option<&'a str>,
+    #[n(5)] group_id: &'a str,
+    #[n(6)] signed_at: u64,
+}
+
+/// Partition of a topic for which a consumer group committed an offset
+type CommittedPartition = (String, String, i32);
+
+/// Signs the offsets committed by the kafka consumers with the identity of the node,
+/// and verifies the signature of the offsets fetched by the consumers, so that an
+/// intermediary modifying the committed offsets can be detected.
+///
+/// The signature covers the consumer group, the topic, the partition, the offset, the original
+/// metadata and the time of the commit. It is only accepted from the identity of this node or
+/// from a member of the trust context, whose attributes are attested by the authority.
+/// A committed offset older than the last one committed or fetched through this node for the
+/// same partition is rejected, so that an intermediary can't replay an old commit
+#[derive(Clone)]
+pub(crate) struct OffsetCommitSigner {
+    identities: Arc<Identities>,
+    identifier: Identifier,
+    authority: Option<Identifier>,
+    events: NodeEventPublisher,
+    last_commits: Arc<Mutex<HashMap<CommittedPartition, u64>>>,
+}
+
+impl OffsetCommitSigner {
+    pub(crate) fn new(
+        identities: Arc<Identities>,
+        identifier: Identifier,
+        authority: Option<Identifier>,
+        events: NodeEventPublisher,
+    ) -> Self {
+        Self {
+            identities,
+            identifier,
+            authority,
+            events,
+            last_commits: Default::default(),
+        }
+    }
+
+    /// Return the metadata to commit in place of the original metadata
+    pub(crate) async fn sign(
+        &self,
+        group_id: &str,
+        topic: &str,
+        partition: i32,
+        offset: i64,
+        metadata: Option<String>,
+    ) -> Result<String> {
+        let purpose_key = self
+            .identities
+            .purpose_keys()
+            .purpose_keys_creation()
+            .get_or_create_credential_purpose_key(&self.identifier)
+            .await?;
+
+        let signed_at = *now()?;
+        let vault = self.identities.vault();
+        let data = SignedOffsetData {
+            topic,
+            partition,
+            offset,
+            metadata: metadata.as_deref(),
+            group_id,
+            signed_at,
+        };
+        let hash = vault
+            .verifying_vault
+            .sha256(&minicbor::to_vec(data)?)
+            .await?;
+        let signature = vault
+            .credential_vault
+            .sign(purpose_key.key(), &hash.0)
+            .await?;
+        self.record_commit(group_id, topic, partition, signed_at);
+
+        let signed = SignedOffsetMetadata {
+            metadata,
+            purpose_key_attestation: purpose_key.attestation().clone(),
+            signature: signature.into(),
+            signed_at,
+        };
+        Ok(format!(
+            "{SIGNED_METADATA_PREFIX}{}",
+            hex::encode(minicbor::to_vec(signed)?)
+        ))
+    }
+
+    /// Verify the metadata of a committed offset and return its original metadata
+    pub(crate) async fn verify(
+        &self,
+        group_id: &str,
+        topic: &str,
+        partition: i32,
+        offset: i64,
+        metadata: &str,
+    ) -> Result<Option<String>> {
+        let encoded = metadata
+            .strip_prefix(SIGNED_METADATA_PREFIX)
+            .ok_or_else(|| invalid("the committed offset is not signed"))?;
+        let signed: SignedOffsetMetadata = minicbor::decode(
+            &hex::decode(encoded).map_err(|_| invalid("the signed metadata is not valid hex"))?,
+        )?;
+
+        // the signer must be trusted before its signature is verified
+        let signer = PurposeKeyAttestationData::get_data(
+            &signed.purpose_key_attestation.get_versioned_data()?,
+        )?
+        .subject;
+        self.check_signer(&signer).await?;
+        let purpose_key_data = self
+            .identities
+            .purpose_keys()
+            .purpose_keys_verification()
+            .verify_purpose_key_attestation(Some(&signer), &signed.purpose_key_attestation)
+            .await?;
+        let public_key = match purpose_key_data.public_key {
+            PurposePublicKey::CredentialSigning(public_key) => public_key.into(),
+            PurposePublicKey::SecureChannelStatic(_) => {
+                return Err(invalid("the offset was not signed with a signing key"))
+            }
+        };
+
+        let vault = self.identities.vault();
+        let data = SignedOffsetData {
+            topic,
+            partition,
+            offset,
+            metadata: signed.metadata.as_deref(),
+            group_id,
+            signed_at: signed.signed_at,
+        };
+        let hash = vault
+            .verifying_vault
+            .sha256(&minicbor::to_vec(data)?)
+            .await?;
+        if !vault
+            .verifying_vault
+            .verify_signature(&public_key, &hash.0, &signed.signature.into())
+            .await?
+        {
+            return Err(invalid(&format!(
+                "invalid signature from {}",
+                purpose_key_data.subject
+            )));
+        }
+
+        let key = (group_id.to_string(), topic.to_string(), partition);
+        let mut last_commits = self.last_commits.lock().unwrap();
+        match last_commits.get(&key) {
+            Some(last) if *last > signed.signed_at => {
+                return Err(invalid(&format!(
+                    "the offset was committed at {}, before the last commit at {last}",
+                    signed.signed_at
+                )))
+            }
+            _ => last_commits.insert(key, signed.signed_at),
+        };
+        Ok(signed.metadata)
+    }
+
+    /// Only the identity of this node and the members of its trust context can sign offsets
+    async fn check_signer(&self, signer: &Identifier) -> Result<()> {
+        if signer == &self.identifier {
+            return Ok(());
+        }
+        if let Some(authority) = &self.authority {
+            let attested_by = self
+                .identities
+                .repository()
+                .get_attributes(signer)
+                .await?
+                .and_then(|entry| entry.attested_by());
+            if attested_by.as_ref() == Some(authority) {
+                return Ok(());
+            }
+        }
+        Err(invalid(&format!(
+            "the offset was signed by {signer}, which is not a member of the trust context"
+        )))
+    }
+
+    fn record_commit(&self, group_id: &str, topic: &str, partition: i32, signed_at: u64) {
+        self.last_commits.lock().unwrap().insert(
+            (group_id.to_string(), topic.to_string(), partition),
+            signed_at,
+        );
+    }
+
+    /// Publish a node event for a committed offset which could not be verified
+    pub(crate) async fn report_failure(
+        &self,
+        context: &Context,
+        group_id: &str,
+        topic: &str,
+        partition: i32,
+        error: &Error,
+    ) {
+        warn!(%group_id, %topic, %partition, %error, "cannot verify a committed offset");
+        self.events
+            .publish(
+                context,
+                NodeEventType::OffsetCommitVerificationFailed,
+                format!("{group_id}/{topic}/{partition}"),
+                Some(error.to_string()),
+            )
+            .await
+    }
+}
+
+fn invalid(message: &str) -> Error {
+    Error::new(Origin::Application, Kind::Invalid, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::start_manager_for_tests;
+    use ockam::identity::AttributesEntry;
+    use std::collections::BTreeMap;
+
+    #[ockam_macros::test]
+    async fn test_offset_commit_signature(context: &mut Context) -> Result<()> {
+        let handler = start_manager_for_tests(context).await?;
+        let identities = handler.secure_channels.identities();
+        let authority = handler.identifier.clone();
+        let signer = OffsetCommitSigner::new(
+            identities.clone(),
+            handler.identifier.clone(),
+            Some(authority.clone()),
+            handler.node_manager.event_publisher(),
+        );
+
+        let metadata = signer
+            .sign("group", "topic", 1, 42, Some("meta".into()))
+            .await?;
+        let verified = signer.verify("group", "topic", 1, 42, &metadata).await?;
+        assert_eq!(verified, Some("meta".to_string()));
+
+        // the group, the offset, the partition and the metadata cannot be modified
+        assert!(signer
+            .verify("other", "topic", 1, 42, &metadata)
+            .await
+            .is_err());
+        assert!(signer
+            .verify("group", "topic", 1, 41, &metadata)
+            .await
+            .is_err());
+        assert!(signer
+            .verify("group", "topic", 2, 42, &metadata)
+            .await
+            .is_err());
+        assert!(signer
+            .verify("group", "topic", 1, 42, "meta")
+            .await
+            .is_err());
+
+        // a member of the trust context can sign offsets, another identity can't
+        let member = identities.identities_creation().create_identity().await?;
+        let stranger = identities.identities_creation().create_identity().await?;
+        identities
+            .repository()
+            .put_attributes(
+                member.identifier(),
+                AttributesEntry::new(BTreeMap::new(), now()?, None, Some(authority)),
+            )
+            .await?;
+        let events = handler.node_manager.event_publisher();
+        let member_signer = OffsetCommitSigner::new(
+            identities.clone(),
+            member.identifier().clone(),
+            None,
+            events.clone(),
+        );
+        let stranger_signer = OffsetCommitSigner::new(
+            identities.clone(),
+            stranger.identifier().clone(),
+            None,
+            events,
+        );
+        let metadata = member_signer.sign("group", "topic", 3, 10, None).await?;
+        assert_eq!(
+            signer.verify("group", "topic", 3, 10, &metadata).await?,
+            None
+        );
+        let metadata = stranger_signer.sign("group", "topic", 4, 10, None).await?;
+        assert!(signer
+            .verify("group", "topic", 4, 10, &metadata)
+            .await
+            .is_err());
+
+        context.stop().await
+    }
+
+    #[ockam_macros::test]
+    async fn test_replayed_offset_commits_are_rejected(context: &mut Context) -> Result<()> {
+        let handler = start_manager_for_tests(context).await?;
+        let signer = OffsetCommitSigner::new(
+            handler.secure_channels.identities(),
+            handler.identifier.clone(),
+            None,
+            handler.node_manager.event_publisher(),
+        );
+
+        let old = signer.sign("group", "topic", 1, 10, None).await?;
+        signer
+            .last_commits
+            .lock()
+            .unwrap()
+            .insert(("group".into(), "topic".into(), 1), *now()? + 60);
+        assert!(signer.verify("group", "topic", 1, 10, &old).await.is_err());
+
+        // the other partitions are not affected
+        let metadata = signer.sign("group", "topic", 2, 10, None).await?;
+        assert!(signer
+            .verify("group", "topic", 2, 10, &metadata)
+            .await
+            .is_ok());
+
+        context.stop().await
+    }
+}
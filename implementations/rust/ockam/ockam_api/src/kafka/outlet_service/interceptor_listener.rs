@@ -1,7 +1,9 @@
 use crate::kafka::outlet_controller::KafkaOutletController;
 use crate::kafka::portal_worker::KafkaPortalWorker;
 use crate::kafka::protocol_aware::OutletInterceptorImpl;
-use crate::kafka::{KAFKA_OUTLET_BOOTSTRAP_ADDRESS, KAFKA_OUTLET_INTERCEPTOR_ADDRESS};
+use crate::kafka::{
+    KafkaMetrics, KAFKA_OUTLET_BOOTSTRAP_ADDRESS, KAFKA_OUTLET_INTERCEPTOR_ADDRESS,
+};
 use ockam::identity::{SecureChannels, TRUST_CONTEXT_ID_UTF8};
 use ockam::{Any, Context, Result, Routed, Worker};
 use ockam_abac::AbacAccessControl;
@@ -20,6 +22,7 @@ pub(crate) struct OutletManagerService {
     flow_control_id: FlowControlId,
     spawner_flow_control_id: FlowControlId,
     outgoing_access_control: Arc<FlowControlOutgoingAccessControl>,
+    metrics: KafkaMetrics,
 }
 
 impl OutletManagerService {
@@ -28,7 +31,9 @@ impl OutletManagerService {
         secure_channels: Arc<SecureChannels>,
         trust_context_id: &str,
         default_secure_channel_listener_flow_control_id: FlowControlId,
+        metrics: KafkaMetrics,
     ) -> Result<()> {
+        metrics.register(context);
         let flow_controls = context.flow_controls();
 
         let worker_address = Address::from_string(KAFKA_OUTLET_INTERCEPTOR_ADDRESS);
@@ -59,6 +64,7 @@ impl OutletManagerService {
                 Some(spawner_flow_control_id.clone()),
             )),
             spawner_flow_control_id,
+            metrics,
         };
 
         let incoming = worker.incoming_access_control.clone();
@@ -110,6 +116,7 @@ impl Worker for OutletManagerService {
             Some(self.spawner_flow_control_id.clone()),
             self.incoming_access_control.clone(),
             self.outgoing_access_control.clone(),
+            self.metrics.clone(),
         )
         .await?;
 
@@ -2,22 +2,39 @@ use crate::kafka::KAFKA_OUTLET_CONSUMERS;
 
 use crate::DefaultAddress;
 use core::str::from_utf8;
+use ockam::identity::{IdentityAttributesReader, SecureChannelPeer};
+use ockam::remote::RELAY_REGISTRATION_DENIED;
 use ockam::{Context, Result, Routed, Worker};
+use ockam_core::compat::sync::Arc;
 use ockam_core::errcode::{Kind, Origin};
 use ockam_core::flow_control::FlowControlId;
-use ockam_core::{Address, AllowAll, AllowOnwardAddress};
+use ockam_core::{Address, AllowAll, AllowOnwardAddress, DenyAll, Mailbox, Mailboxes};
+use ockam_node::WorkerBuilder;
+
+/// Attribute, issued by the authority, listing the comma-separated topics for which
+/// an identity can register consumer relays, `*` allowing all the topics
+const KAFKA_CONSUMER_TOPICS_ATTRIBUTE: &str = "kafka.consumer_topics";
 
 /// This service applies a prefix to the provided static forwarding address.
 /// This service was created mainly to keep full compatibility with the existing
 /// erlang implementation.
+///
+/// When the node has a trust context, a relay `{topic}_{partition}` is only registered if
+/// the identity asking for it is entitled to consume the topic, so that an identity cannot
+/// take over the relays of the consumers of another topic. The other requests are denied
+/// with a response giving the reason of the refusal
 pub struct PrefixRelayService {
     prefix: String,
     secure_channel_listener_flow_control_id: FlowControlId,
+    attributes_reader: Option<Arc<dyn IdentityAttributesReader>>,
+    denials_address: Address,
 }
 impl PrefixRelayService {
+    /// Start the service. The consumer topics are checked when an attributes reader is given
     pub async fn create(
         context: &Context,
         secure_channel_listener_flow_control_id: FlowControlId,
+        attributes_reader: Option<Arc<dyn IdentityAttributesReader>>,
     ) -> Result<()> {
         // add the this worker as consumer for the secure channel listener
         let worker_address = Address::from_string(KAFKA_OUTLET_CONSUMERS);
@@ -26,20 +43,43 @@ impl PrefixRelayService {
             &secure_channel_listener_flow_control_id,
         );
 
+        // the denials are sent back from a separate address, so that the main address
+        // can only send messages to the relay service
+        let denials_address = Address::random_tagged("PrefixRelayService.denials");
         let worker = Self {
             prefix: "consumer_".to_string(),
             secure_channel_listener_flow_control_id,
+            attributes_reader,
+            denials_address: denials_address.clone(),
         };
 
-        context
-            .start_worker_with_access_control(
+        let mailboxes = Mailboxes::new(
+            Mailbox::new(
                 worker_address,
-                worker,
-                AllowAll,
-                AllowOnwardAddress(DefaultAddress::RELAY_SERVICE.into()),
-            )
+                Arc::new(AllowAll),
+                Arc::new(AllowOnwardAddress(DefaultAddress::RELAY_SERVICE.into())),
+            ),
+            vec![Mailbox::new(
+                denials_address.clone(),
+                Arc::new(DenyAll),
+                Arc::new(AllowAll),
+            )],
+        );
+        WorkerBuilder::new(worker)
+            .with_mailboxes(mailboxes)
+            .start(context)
             .await
     }
+
+    /// Tell the relay asking for a registration that it was denied
+    async fn deny(&self, ctx: &Context, msg: Routed<Vec<u8>>, reason: &str) -> Result<()> {
+        ctx.send_from_address(
+            msg.return_route(),
+            format!("{RELAY_REGISTRATION_DENIED}{reason}"),
+            self.denials_address.clone(),
+        )
+        .await
+    }
 }
 
 #[ockam::worker]
@@ -73,6 +113,29 @@ impl Worker for PrefixRelayService {
             }
         };
 
+        if let Some(attributes_reader) = &self.attributes_reader {
+            let peer = SecureChannelPeer::from_local_message(
+                msg.local_message(),
+                attributes_reader.clone(),
+            )
+            .await?;
+            let allowed_topics = peer
+                .attribute_str(KAFKA_CONSUMER_TOPICS_ATTRIBUTE)
+                .unwrap_or_default();
+            if !is_allowed_alias(&allowed_topics, &address) {
+                warn!(
+                    identifier = %peer.identifier(),
+                    %address,
+                    "the identity is not entitled to register a consumer relay for this topic"
+                );
+                let reason = format!(
+                    "the identity {} is not entitled to consume the topic of the relay {address}",
+                    peer.identifier()
+                );
+                return self.deny(ctx, msg, &reason).await;
+            }
+        }
+
         let new_address = format!("{}_{}", &self.prefix, address);
 
         debug!("prefix relay, renamed from {} to {}", address, new_address);
@@ -100,3 +163,34 @@ impl Worker for PrefixRelayService {
         Ok(())
     }
 }
+
+/// Return true if the relay alias `{topic}_{partition}` is for one of the comma-separated
+/// `allowed_topics`, `*` allowing all the topics
+fn is_allowed_alias(allowed_topics: &str, alias: &str) -> bool {
+    let topic = match alias.rsplit_once('_') {
+        Some((topic, partition)) if partition.parse::<i32>().is_ok() => topic,
+        _ => return false,
+    };
+    allowed_topics
+        .split(',')
+        .map(str::trim)
+        .any(|allowed| allowed == "*" || allowed == topic)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_allowed_alias() {
+        assert!(is_allowed_alias("orders,my_topic", "my_topic_3"));
+        assert!(is_allowed_alias("orders, payments", "payments_0"));
+        assert!(is_allowed_alias("*", "payments_0"));
+
+        assert!(!is_allowed_alias("orders", "payments_0"));
+        assert!(!is_allowed_alias("my", "my_topic_3"));
+        assert!(!is_allowed_alias("", "orders_0"));
+        // the alias must end with a partition
+        assert!(!is_allowed_alias("*", "orders"));
+    }
+}
@@ -7,6 +7,7 @@ use crate::kafka::inlet_controller::KafkaInletController;
 use crate::kafka::portal_worker::KafkaPortalWorker;
 use crate::kafka::protocol_aware::TopicUuidMap;
 use crate::kafka::secure_channel_map::KafkaSecureChannelController;
+use crate::kafka::{KafkaMetrics, OffsetCommitSigner, TopicFilters};
 
 ///First point of ingress of kafka connections, at the first message it spawns new stateful workers
 /// to take care of the connection.
@@ -14,6 +15,10 @@ pub(crate) struct KafkaPortalListener {
     inlet_controller: KafkaInletController,
     secure_channel_controller: Arc<dyn KafkaSecureChannelController>,
     uuid_to_name: TopicUuidMap,
+    cleartext_headers: Option<Vec<String>>,
+    topic_filters: TopicFilters,
+    offset_commit_signer: Option<OffsetCommitSigner>,
+    metrics: KafkaMetrics,
 }
 
 #[ockam::worker]
@@ -48,9 +53,13 @@ impl Worker for KafkaPortalListener {
             self.secure_channel_controller.clone(),
             self.uuid_to_name.clone(),
             self.inlet_controller.clone(),
+            self.cleartext_headers.clone(),
+            self.topic_filters.clone(),
+            self.offset_commit_signer.clone(),
             None,
             flow_control_id,
             route![inlet_responder_address],
+            self.metrics.clone(),
         )
         .await?;
 
@@ -79,7 +88,12 @@ impl KafkaPortalListener {
         inlet_controller: KafkaInletController,
         secure_channel_controller: Arc<dyn KafkaSecureChannelController>,
         listener_address: Address,
+        cleartext_headers: Option<Vec<String>>,
+        topic_filters: TopicFilters,
+        offset_commit_signer: Option<OffsetCommitSigner>,
+        metrics: KafkaMetrics,
     ) -> ockam_core::Result<()> {
+        metrics.register(context);
         context
             .start_worker(
                 listener_address,
@@ -87,6 +101,10 @@ impl KafkaPortalListener {
                     inlet_controller,
                     secure_channel_controller,
                     uuid_to_name: Default::default(),
+                    cleartext_headers,
+                    topic_filters,
+                    offset_commit_signer,
+                    metrics,
                 },
             )
             .await
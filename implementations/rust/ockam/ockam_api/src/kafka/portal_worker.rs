@@ -9,18 +9,33 @@ use ockam_core::{
     route, Address, AllowSourceAddress, AnyIncomingAccessControl, Encodable, Error, LocalInfo,
     LocalMessage, Route, Routed, TransportMessage, Worker,
 };
-use ockam_node::{Context, WorkerBuilder};
+use ockam_node::{Context, MailboxOptions, WorkerBuilder};
 use ockam_transport_tcp::{PortalMessage, MAX_PAYLOAD_SIZE};
+use std::time::Instant;
 
 use crate::kafka::inlet_controller::KafkaInletController;
 use crate::kafka::length_delimited::{length_encode, KafkaMessageDecoder};
 use crate::kafka::protocol_aware::{InletInterceptorImpl, KafkaMessageInterceptor, TopicUuidMap};
 use crate::kafka::secure_channel_map::KafkaSecureChannelController;
-use crate::kafka::KAFKA_OUTLET_BOOTSTRAP_ADDRESS;
+use crate::kafka::{
+    KafkaMetrics, OffsetCommitSigner, TopicFilters, KAFKA_OUTLET_BOOTSTRAP_ADDRESS,
+};
+use crate::util::mailbox_options_from_env;
 
 ///by default kafka supports up to 1MB messages, 16MB is the maximum suggested
 pub(crate) const MAX_KAFKA_MESSAGE_SIZE: u32 = 16 * 1024 * 1024;
 
+/// Environment variable setting the capacity of the mailboxes of the kafka portal workers
+const OCKAM_KAFKA_MAILBOX_CAPACITY: &str = "OCKAM_KAFKA_MAILBOX_CAPACITY";
+
+/// Environment variable setting the policy applied when the mailbox of a kafka portal worker is full
+const OCKAM_KAFKA_MAILBOX_OVERFLOW: &str = "OCKAM_KAFKA_MAILBOX_OVERFLOW";
+
+/// Return the options of the mailboxes of the kafka portal workers
+fn kafka_mailbox_options() -> MailboxOptions {
+    mailbox_options_from_env(OCKAM_KAFKA_MAILBOX_CAPACITY, OCKAM_KAFKA_MAILBOX_OVERFLOW)
+}
+
 enum Receiving {
     Requests,
     Responses,
@@ -56,6 +71,7 @@ pub(crate) struct KafkaPortalWorker {
     // Since we know the next step beforehand we simply ignore the provided onward route
     // and use the one we know.
     fixed_onward_route: Option<Route>,
+    metrics: KafkaMetrics,
 }
 
 #[ockam::worker]
@@ -82,6 +98,7 @@ impl Worker for KafkaPortalWorker {
 
         match portal_message {
             PortalMessage::Payload(message) => {
+                let started_at = Instant::now();
                 let result = self
                     .intercept_and_transform_messages(context, message)
                     .await;
@@ -97,6 +114,11 @@ impl Worker for KafkaPortalWorker {
                                 local_info.as_slice(),
                             )
                             .await?;
+                            let latency = match self.receiving {
+                                Receiving::Requests => self.metrics.portal_requests(),
+                                Receiving::Responses => self.metrics.portal_responses(),
+                            };
+                            latency.observe(started_at.elapsed());
                         }
                     }
                     Err(cause) => {
@@ -127,7 +149,9 @@ impl Worker for KafkaPortalWorker {
                     context.stop_worker(context.address()).await?;
                 }
             }
-            PortalMessage::Ping => self.forward(context, routed_message).await?,
+            PortalMessage::Ping | PortalMessage::PingWithTarget(_) => {
+                self.forward(context, routed_message).await?
+            }
 
             PortalMessage::Pong => {
                 match self.receiving {
@@ -318,6 +342,7 @@ impl KafkaPortalWorker {
         spawner_flow_control_id: Option<FlowControlId>,
         incoming_access_control: Arc<AbacAccessControl>,
         outgoing_access_control: Arc<FlowControlOutgoingAccessControl>,
+        metrics: KafkaMetrics,
     ) -> ockam_core::Result<Address> {
         let requests_worker_address = Address::random_tagged("KafkaPortalWorker.requests");
         let responses_worker_address = Address::random_tagged("KafkaPortalWorker.responses");
@@ -331,6 +356,7 @@ impl KafkaPortalWorker {
             decoder: KafkaMessageDecoder::new(),
             max_message_size: max_kafka_message_size.unwrap_or(MAX_KAFKA_MESSAGE_SIZE),
             fixed_onward_route: Some(fixed_outlet_route),
+            metrics: metrics.clone(),
         };
         let response_worker = Self {
             message_interceptor,
@@ -340,6 +366,7 @@ impl KafkaPortalWorker {
             decoder: KafkaMessageDecoder::new(),
             max_message_size: max_kafka_message_size.unwrap_or(MAX_KAFKA_MESSAGE_SIZE),
             fixed_onward_route: None,
+            metrics,
         };
 
         // allowing the other worker to allow forwarding of the `pong` message
@@ -350,6 +377,7 @@ impl KafkaPortalWorker {
                 incoming_access_control,
             ])))
             .with_outgoing_access_control_arc(outgoing_access_control)
+            .with_mailbox_options(kafka_mailbox_options())
             .start(context)
             .await?;
 
@@ -377,6 +405,7 @@ impl KafkaPortalWorker {
 
         WorkerBuilder::new(response_worker)
             .with_address(responses_worker_address)
+            .with_mailbox_options(kafka_mailbox_options())
             .start(context)
             .await?;
 
@@ -391,14 +420,22 @@ impl KafkaPortalWorker {
         secure_channel_controller: Arc<dyn KafkaSecureChannelController>,
         uuid_to_name: TopicUuidMap,
         inlet_map: KafkaInletController,
+        cleartext_headers: Option<Vec<String>>,
+        topic_filters: TopicFilters,
+        offset_commit_signer: Option<OffsetCommitSigner>,
         max_kafka_message_size: Option<u32>,
         flow_control_id: Option<FlowControlId>,
         inlet_responder_route: Route,
+        metrics: KafkaMetrics,
     ) -> ockam_core::Result<Address> {
         let shared_protocol_state = Arc::new(InletInterceptorImpl::new(
             secure_channel_controller,
             uuid_to_name,
             inlet_map,
+            cleartext_headers,
+            topic_filters,
+            offset_commit_signer,
+            metrics.clone(),
         ));
 
         let requests_worker_address = Address::random_tagged("KafkaPortalWorker.requests");
@@ -413,6 +450,7 @@ impl KafkaPortalWorker {
             decoder: KafkaMessageDecoder::new(),
             max_message_size: max_kafka_message_size.unwrap_or(MAX_KAFKA_MESSAGE_SIZE),
             fixed_onward_route: None,
+            metrics: metrics.clone(),
         };
         let response_worker = Self {
             message_interceptor: shared_protocol_state,
@@ -422,10 +460,13 @@ impl KafkaPortalWorker {
             decoder: KafkaMessageDecoder::new(),
             max_message_size: max_kafka_message_size.unwrap_or(MAX_KAFKA_MESSAGE_SIZE),
             fixed_onward_route: Some(inlet_responder_route),
+            metrics,
         };
 
-        context
-            .start_worker(requests_worker_address.clone(), request_worker)
+        WorkerBuilder::new(request_worker)
+            .with_address(requests_worker_address.clone())
+            .with_mailbox_options(kafka_mailbox_options())
+            .start(context)
             .await?;
 
         if let Some(flow_control_id) = flow_control_id {
@@ -433,8 +474,10 @@ impl KafkaPortalWorker {
             flow_controls.add_consumer(responses_worker_address.clone(), &flow_control_id);
             flow_controls.add_consumer(KAFKA_OUTLET_BOOTSTRAP_ADDRESS, &flow_control_id);
         }
-        context
-            .start_worker(responses_worker_address, response_worker)
+        WorkerBuilder::new(response_worker)
+            .with_address(responses_worker_address)
+            .with_mailbox_options(kafka_mailbox_options())
+            .start(context)
             .await?;
 
         Ok(requests_worker_address)
@@ -754,9 +797,13 @@ mod test {
             secure_channel_controller,
             Default::default(),
             inlet_map,
+            None,
+            Default::default(),
+            None,
             Some(TEST_MAX_KAFKA_MESSAGE_SIZE),
             None,
             route![context.address()],
+            Default::default(),
         )
         .await
         .unwrap()
@@ -817,8 +864,12 @@ mod test {
             Default::default(),
             inlet_map.clone(),
             None,
+            Default::default(),
+            None,
+            None,
             None,
             route![context.address()],
+            Default::default(),
         )
         .await?;
 
@@ -0,0 +1,146 @@
+use core::fmt;
+use core::str::FromStr;
+use std::io::{Read, Write};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use minicbor::{Decode, Encode};
+use ockam_core::env::{get_env, FromString};
+use ockam_core::errcode::{Kind, Origin};
+use tracing::warn;
+
+/// Environment variable setting the compression applied to the records before they are encrypted
+const OCKAM_KAFKA_CONTENT_COMPRESSION: &str = "OCKAM_KAFKA_CONTENT_COMPRESSION";
+
+/// Maximum size of a decompressed record value. A record decompressing to a larger value is
+/// rejected, so that a small record can't make the consumer allocate an unbounded buffer
+pub(crate) const MAX_DECOMPRESSED_CONTENT_SIZE: usize = 16 * 1024 * 1024;
+
+/// Compression applied to the value of a record before it is encrypted.
+///
+/// The compression is written in the wrapper of each encrypted record, so that a
+/// consumer can read the records of producers using different compressions
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Encode, Decode)]
+#[rustfmt::skip]
+#[cbor(index_only)]
+pub(crate) enum ContentCompression {
+    /// The value is encrypted as it is
+    #[default]
+    #[n(0)] None,
+    /// The value is compressed with gzip
+    #[n(1)] Gzip,
+}
+
+impl fmt::Display for ContentCompression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContentCompression::None => write!(f, "none"),
+            ContentCompression::Gzip => write!(f, "gzip"),
+        }
+    }
+}
+
+impl FromStr for ContentCompression {
+    type Err = ockam_core::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(ContentCompression::None),
+            "gzip" => Ok(ContentCompression::Gzip),
+            _ => Err(ockam_core::Error::new(
+                Origin::Application,
+                Kind::Invalid,
+                "the content compression must be one of: none, gzip",
+            )),
+        }
+    }
+}
+
+impl FromString for ContentCompression {
+    fn from_string(s: &str) -> ockam_core::Result<Self> {
+        s.parse()
+    }
+}
+
+impl ContentCompression {
+    /// Return the compression set with `OCKAM_KAFKA_CONTENT_COMPRESSION`,
+    /// the records are not compressed when it is not set or invalid
+    pub(crate) fn from_env() -> Self {
+        get_env::<ContentCompression>(OCKAM_KAFKA_CONTENT_COMPRESSION)
+            .unwrap_or_else(|e| {
+                warn!(%e, "invalid {OCKAM_KAFKA_CONTENT_COMPRESSION}, the records are not compressed");
+                None
+            })
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn compress(&self, content: Vec<u8>) -> std::io::Result<Vec<u8>> {
+        match self {
+            ContentCompression::None => Ok(content),
+            ContentCompression::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(&content)?;
+                encoder.finish()
+            }
+        }
+    }
+
+    /// Decompress a value, failing if it decompresses to more than `max_size` bytes
+    pub(crate) fn decompress(&self, content: Vec<u8>, max_size: usize) -> std::io::Result<Vec<u8>> {
+        match self {
+            ContentCompression::None => Ok(content),
+            ContentCompression::Gzip => {
+                let mut decompressed = Vec::new();
+                // one more byte is read to detect the values exceeding the limit
+                GzDecoder::new(content.as_slice())
+                    .take(max_size as u64 + 1)
+                    .read_to_end(&mut decompressed)?;
+                if decompressed.len() > max_size {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("the decompressed record value is larger than {max_size} bytes"),
+                    ));
+                }
+                Ok(decompressed)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_compression() {
+        let content = b"hello hello hello hello hello hello".to_vec();
+        for compression in [ContentCompression::None, ContentCompression::Gzip] {
+            let compressed = compression.compress(content.clone()).unwrap();
+            assert_eq!(
+                compression.decompress(compressed, content.len()).unwrap(),
+                content
+            );
+        }
+        assert!(ContentCompression::Gzip
+            .decompress(b"not compressed".to_vec(), MAX_DECOMPRESSED_CONTENT_SIZE)
+            .is_err());
+
+        // a value decompressing to more than the limit is rejected
+        let compressed = ContentCompression::Gzip.compress(vec![0; 1024]).unwrap();
+        assert!(compressed.len() < 1024);
+        assert!(ContentCompression::Gzip
+            .decompress(compressed.clone(), 1023)
+            .is_err());
+        assert_eq!(
+            ContentCompression::Gzip
+                .decompress(compressed, 1024)
+                .unwrap(),
+            vec![0; 1024]
+        );
+        assert_eq!(
+            "gzip".parse::<ContentCompression>().unwrap(),
+            ContentCompression::Gzip
+        );
+        assert!("lz4".parse::<ContentCompression>().is_err());
+    }
+}
@@ -100,6 +100,8 @@ impl KafkaMessageInterceptor for OutletInterceptorImpl {
                 RequestInfo {
                     request_api_key: ApiKey::MetadataKey,
                     request_api_version: header.request_api_version,
+                    group_id: None,
+                    denied_topics: None,
                 },
             );
         }
@@ -1,33 +1,116 @@
 use crate::kafka::portal_worker::InterceptError;
 use crate::kafka::secure_channel_map::KafkaSecureChannelController;
-use crate::kafka::KafkaInletController;
+use crate::kafka::{KafkaInletController, KafkaMetrics, OffsetCommitSigner, TopicFilters};
 use bytes::BytesMut;
-use kafka_protocol::messages::ApiKey;
+use core::fmt;
+use core::str::FromStr;
+use kafka_protocol::messages::fetch_response::FetchableTopicResponse;
+use kafka_protocol::messages::produce_response::TopicProduceResponse;
+use kafka_protocol::messages::{ApiKey, TopicName};
 use minicbor::{Decode, Encode};
 use ockam_core::compat::{
     collections::HashMap,
     fmt::Debug,
+    io,
     sync::{Arc, Mutex},
 };
+use ockam_core::env::{get_env, FromString};
+use ockam_core::errcode::{Kind, Origin};
 use ockam_core::{async_trait, Address};
 use ockam_node::Context;
+use tracing::warn;
 
+mod compression;
 mod metadata_interceptor;
 mod request;
 mod response;
 mod tests;
 
 pub(super) mod utils;
+use compression::{ContentCompression, MAX_DECOMPRESSED_CONTENT_SIZE};
 pub(crate) use metadata_interceptor::OutletInterceptorImpl;
 
 #[derive(Clone, Debug)]
 struct RequestInfo {
     pub request_api_key: ApiKey,
     pub request_api_version: i16,
+    /// Consumer group of an offset fetch request
+    pub group_id: Option<String>,
+    /// Answers for the topics removed from the request because they are not allowed by
+    /// the service, which are added to the response of the broker
+    pub denied_topics: Option<DeniedTopics>,
+}
+
+#[derive(Clone, Debug)]
+enum DeniedTopics {
+    Fetch(Vec<FetchableTopicResponse>),
+    Produce(Vec<(TopicName, TopicProduceResponse)>),
 }
 
 type CorrelationId = i32;
 
+/// Environment variable setting what is done with the fetched records which cannot be decrypted
+const OCKAM_KAFKA_DECRYPTION_FAILURE_POLICY: &str = "OCKAM_KAFKA_DECRYPTION_FAILURE_POLICY";
+
+/// What is done with a fetched record which cannot be decrypted
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum DecryptionFailurePolicy {
+    /// The partition of the record is returned with a `CORRUPT_MESSAGE` error code, or an
+    /// `UNSUPPORTED_FOR_MESSAGE_FORMAT` error code if the record format is not supported
+    #[default]
+    Fail,
+    /// The record is logged and removed from the response
+    Skip,
+    /// The value of the record is removed, the consumer receives a tombstone
+    Tombstone,
+}
+
+impl fmt::Display for DecryptionFailurePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecryptionFailurePolicy::Fail => write!(f, "fail"),
+            DecryptionFailurePolicy::Skip => write!(f, "skip"),
+            DecryptionFailurePolicy::Tombstone => write!(f, "tombstone"),
+        }
+    }
+}
+
+impl FromStr for DecryptionFailurePolicy {
+    type Err = ockam_core::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fail" => Ok(DecryptionFailurePolicy::Fail),
+            "skip" => Ok(DecryptionFailurePolicy::Skip),
+            "tombstone" => Ok(DecryptionFailurePolicy::Tombstone),
+            _ => Err(ockam_core::Error::new(
+                Origin::Application,
+                Kind::Invalid,
+                "the decryption failure policy must be one of: fail, skip, tombstone",
+            )),
+        }
+    }
+}
+
+impl FromString for DecryptionFailurePolicy {
+    fn from_string(s: &str) -> ockam_core::Result<Self> {
+        s.parse()
+    }
+}
+
+impl DecryptionFailurePolicy {
+    /// Return the policy set with `OCKAM_KAFKA_DECRYPTION_FAILURE_POLICY`,
+    /// a fetched partition fails when it is not set or invalid
+    fn from_env() -> Self {
+        get_env::<DecryptionFailurePolicy>(OCKAM_KAFKA_DECRYPTION_FAILURE_POLICY)
+            .unwrap_or_else(|e| {
+                warn!(%e, "invalid {OCKAM_KAFKA_DECRYPTION_FAILURE_POLICY}, undecryptable records fail their partition");
+                None
+            })
+            .unwrap_or_default()
+    }
+}
+
 /// map shared across all kafka workers, since the client might request it
 /// only from one connection
 pub(super) type TopicUuidMap = Arc<Mutex<HashMap<String, String>>>;
@@ -53,6 +136,12 @@ pub(crate) struct InletInterceptorImpl {
     uuid_to_name: TopicUuidMap,
     secure_channel_controller: Arc<dyn KafkaSecureChannelController>,
     inlet_map: KafkaInletController,
+    cleartext_headers: Option<Vec<String>>,
+    topic_filters: TopicFilters,
+    offset_commit_signer: Option<OffsetCommitSigner>,
+    metrics: KafkaMetrics,
+    decryption_failure_policy: DecryptionFailurePolicy,
+    content_compression: ContentCompression,
 }
 
 #[async_trait]
@@ -74,13 +163,74 @@ impl KafkaMessageInterceptor for InletInterceptorImpl {
     }
 }
 
+///Version of the format of the wrapper written by this node.
+///The wrappers written before the format was versioned have no version and are read as version 0
+const MESSAGE_WRAPPER_VERSION: u8 = 1;
+
 #[derive(Debug, Clone, Decode, Encode)]
 #[rustfmt::skip]
 #[cbor(map)]
-///Wraps the content within every record batch
+///Wraps the content within every record batch.
+///
+///The fields are indexed so that new optional fields can be added without breaking the
+///older consumers, which skip them. The version is only increased for changes which cannot
+///be read by the older consumers, which then fail with an explicit error
 struct MessageWrapper {
+    #[n(0)] version: Option<u8>,
+    ///decryptor address of the secure channel which encrypted the content
     #[n(1)] consumer_decryptor_address: Address,
-    #[n(2)] content: Vec<u8>
+    #[n(2)] content: Vec<u8>,
+    ///encrypted record headers, present when only some headers are allowed in clear text
+    #[n(3)] headers: Option<Vec<u8>>,
+    ///compression applied to the content before its encryption, none if absent
+    #[n(4)] compression: Option<ContentCompression>,
+}
+
+/// Encode the encrypted value of a record, without protected headers,
+/// as it is sent to the kafka broker
+#[cfg(any(test, feature = "benchmarks"))]
+pub(crate) fn wrap_encrypted_record(
+    consumer_decryptor_address: &Address,
+    content: Vec<u8>,
+) -> ockam_core::Result<Vec<u8>> {
+    let wrapper = MessageWrapper {
+        version: Some(MESSAGE_WRAPPER_VERSION),
+        consumer_decryptor_address: consumer_decryptor_address.clone(),
+        content,
+        headers: None,
+        compression: None,
+    };
+    minicbor::to_vec(wrapper).map_err(|e| crate::error::ApiError::core(e.to_string()))
+}
+
+impl MessageWrapper {
+    ///Decode the wrapper of a record, failing if it was written with a newer format
+    fn decode(bytes: &[u8]) -> Result<Self, InterceptError> {
+        let wrapper: MessageWrapper = minicbor::decode(bytes)
+            .map_err(|_| InterceptError::Io(io::Error::from(io::ErrorKind::InvalidData)))?;
+        match wrapper.version {
+            Some(version) if version > MESSAGE_WRAPPER_VERSION => {
+                Err(InterceptError::Ockam(ockam_core::Error::new(
+                    Origin::Application,
+                    Kind::Unsupported,
+                    format!(
+                        "the record was encrypted with the version {version} of the record format, \
+                         this node only supports the versions up to {MESSAGE_WRAPPER_VERSION}"
+                    ),
+                )))
+            }
+            _ => Ok(wrapper),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+///Record header removed from a record before it is sent to the broker
+struct RecordHeader {
+    #[n(1)] key: String,
+    #[n(2)] value: Option<Vec<u8>>,
 }
 
 impl InletInterceptorImpl {
@@ -88,12 +238,49 @@ impl InletInterceptorImpl {
         secure_channel_controller: Arc<dyn KafkaSecureChannelController>,
         uuid_to_name: TopicUuidMap,
         inlet_map: KafkaInletController,
+        cleartext_headers: Option<Vec<String>>,
+        topic_filters: TopicFilters,
+        offset_commit_signer: Option<OffsetCommitSigner>,
+        metrics: KafkaMetrics,
     ) -> InletInterceptorImpl {
         Self {
             request_map: Arc::new(Mutex::new(Default::default())),
             uuid_to_name,
             secure_channel_controller,
             inlet_map,
+            cleartext_headers,
+            topic_filters,
+            offset_commit_signer,
+            metrics,
+            decryption_failure_policy: DecryptionFailurePolicy::from_env(),
+            content_compression: ContentCompression::from_env(),
+        }
+    }
+
+    ///Return true if a record header can be sent to the broker in clear text.
+    ///When no allowlist is configured, all the headers are left in clear text,
+    ///otherwise the other headers are encrypted along with the record value
+    fn is_cleartext_header(&self, key: &str) -> bool {
+        self.cleartext_headers
+            .as_ref()
+            .map_or(true, |headers| headers.iter().any(|h| h == key))
+    }
+
+    ///Return true if the records of a topic, identified by its name or its id, are encrypted
+    fn is_encrypted_topic(&self, topic: &str) -> bool {
+        match self.uuid_to_name.lock().unwrap().get(topic) {
+            Some(name) => self.topic_filters.is_encrypted(name),
+            None => self.topic_filters.is_encrypted(topic),
+        }
+    }
+
+    ///Return true if a topic is allowed by this service, the other topics are answered with
+    ///a TOPIC_AUTHORIZATION_FAILED error without being sent to the broker
+    fn is_allowed_topic(&self, topic: &str) -> bool {
+        let allowed = self.topic_filters.is_allowed(topic);
+        if !allowed {
+            warn!("the topic {topic} is not allowed by this kafka service");
         }
+        allowed
     }
 }
@@ -1,6 +1,10 @@
 use bytes::{Bytes, BytesMut};
 use kafka_protocol::messages::fetch_request::FetchRequest;
+use kafka_protocol::messages::fetch_response::{FetchableTopicResponse, PartitionData};
+use kafka_protocol::messages::offset_commit_request::OffsetCommitRequest;
+use kafka_protocol::messages::offset_fetch_request::OffsetFetchRequest;
 use kafka_protocol::messages::produce_request::ProduceRequest;
+use kafka_protocol::messages::produce_response::{PartitionProduceResponse, TopicProduceResponse};
 use kafka_protocol::messages::request_header::RequestHeader;
 use kafka_protocol::messages::ApiKey;
 use kafka_protocol::protocol::buf::ByteBuf;
@@ -12,11 +16,17 @@ use minicbor::encode::Encoder;
 use ockam_node::Context;
 use std::convert::TryFrom;
 use std::io::{Error, ErrorKind};
+use std::time::Instant;
 use tracing::warn;
 
 use crate::kafka::portal_worker::InterceptError;
-use crate::kafka::protocol_aware::utils::{decode_body, encode_request};
-use crate::kafka::protocol_aware::{InletInterceptorImpl, MessageWrapper, RequestInfo};
+use crate::kafka::protocol_aware::response::TOPIC_AUTHORIZATION_FAILED;
+use crate::kafka::protocol_aware::utils::{decode_body, encode_request, string_to_str_bytes};
+use crate::kafka::protocol_aware::{
+    ContentCompression, DeniedTopics, InletInterceptorImpl, MessageWrapper, RecordHeader,
+    RequestInfo, MESSAGE_WRAPPER_VERSION,
+};
+use crate::kafka::OffsetCommitSigner;
 
 impl InletInterceptorImpl {
     ///Parse request and map request <=> response
@@ -69,8 +79,36 @@ impl InletInterceptorImpl {
                     .await;
             }
             ApiKey::FetchKey => {
-                self.handle_fetch_request(context, &mut buffer, &header)
-                    .await?;
+                if let Some(request) = self
+                    .handle_fetch_request(context, &mut buffer, &header)
+                    .await?
+                {
+                    return Ok(request);
+                }
+            }
+            ApiKey::OffsetCommitKey => {
+                if let Some(signer) = self.offset_commit_signer.as_ref() {
+                    return self
+                        .handle_offset_commit_request(signer, &mut buffer, &header)
+                        .await;
+                }
+            }
+            ApiKey::OffsetFetchKey => {
+                //the committed offsets are only verified when they are signed
+                if self.offset_commit_signer.is_some() {
+                    //since version 8 the group of each offset is part of the response
+                    let request: OffsetFetchRequest =
+                        decode_body(&mut buffer, header.request_api_version)?;
+                    self.request_map.lock().unwrap().insert(
+                        header.correlation_id,
+                        RequestInfo {
+                            request_api_key: api_key,
+                            request_api_version: header.request_api_version,
+                            group_id: Some(request.group_id.0.to_string()),
+                            denied_topics: None,
+                        },
+                    );
+                }
             }
             ApiKey::MetadataKey | ApiKey::FindCoordinatorKey => {
                 self.request_map.lock().unwrap().insert(
@@ -78,6 +116,8 @@ impl InletInterceptorImpl {
                     RequestInfo {
                         request_api_key: api_key,
                         request_api_version: header.request_api_version,
+                        group_id: None,
+                        denied_topics: None,
                     },
                 );
             }
@@ -99,17 +139,21 @@ impl InletInterceptorImpl {
         Ok(original)
     }
 
+    ///Start the relays of the fetched partitions. The topics which are not allowed are
+    /// removed from the request, which is then returned to be sent instead of the original one
     async fn handle_fetch_request(
         &self,
         context: &mut Context,
         buffer: &mut Bytes,
         header: &RequestHeader,
-    ) -> Result<(), InterceptError> {
-        let request: FetchRequest = decode_body(buffer, header.request_api_version)?;
+    ) -> Result<Option<BytesMut>, InterceptError> {
+        let mut request: FetchRequest = decode_body(buffer, header.request_api_version)?;
+        let mut denied_topics = vec![];
+        let mut allowed_topics = Vec::with_capacity(request.topics.len());
 
         //we intercept every partition interested by the kafka client
         //and create a relay for each
-        for topic in &request.topics {
+        for topic in request.topics.drain(..) {
             let topic_id = if header.request_api_version <= 12 {
                 topic.topic.0.to_string()
             } else {
@@ -127,27 +171,61 @@ impl InletInterceptorImpl {
                         InterceptError::Io(Error::from(ErrorKind::InvalidData))
                     })?
             };
-
             let partitions: Vec<i32> = topic
                 .partitions
                 .iter()
                 .map(|partition| partition.partition)
                 .collect();
 
-            self.secure_channel_controller
-                .start_relays_for(context, &topic_id, partitions)
-                .await
-                .map_err(InterceptError::Ockam)?
+            if !self.is_allowed_topic(&topic_id) {
+                denied_topics.push(FetchableTopicResponse {
+                    topic: topic.topic.clone(),
+                    topic_id: topic.topic_id,
+                    partitions: partitions
+                        .into_iter()
+                        .map(|partition_index| PartitionData {
+                            partition_index,
+                            error_code: TOPIC_AUTHORIZATION_FAILED,
+                            ..Default::default()
+                        })
+                        .collect(),
+                    ..Default::default()
+                });
+                continue;
+            }
+
+            //the records of cleartext topics are not decrypted, no relay is needed
+            if self.topic_filters.is_encrypted(&topic_id) {
+                self.secure_channel_controller
+                    .start_relays_for(context, &topic_id, partitions)
+                    .await
+                    .map_err(InterceptError::Ockam)?
+            }
+            allowed_topics.push(topic);
         }
+        request.topics = allowed_topics;
 
+        let has_denied_topics = !denied_topics.is_empty();
         self.request_map.lock().unwrap().insert(
             header.correlation_id,
             RequestInfo {
                 request_api_key: ApiKey::FetchKey,
                 request_api_version: header.request_api_version,
+                group_id: None,
+                denied_topics: has_denied_topics.then_some(DeniedTopics::Fetch(denied_topics)),
             },
         );
-        Ok(())
+        if has_denied_topics {
+            encode_request(
+                header,
+                &request,
+                header.request_api_version,
+                ApiKey::FetchKey,
+            )
+            .map(Some)
+        } else {
+            Ok(None)
+        }
     }
 
     async fn handle_produce_request(
@@ -158,35 +236,115 @@ impl InletInterceptorImpl {
     ) -> Result<BytesMut, InterceptError> {
         let mut request: ProduceRequest = decode_body(buffer, header.request_api_version)?;
 
+        //the records of the topics which are not allowed are not sent to the broker
+        let mut denied_topics = vec![];
+        request.topic_data.retain(|topic_name, topic| {
+            if self.is_allowed_topic(topic_name) {
+                return true;
+            }
+            let partition_responses = topic
+                .partition_data
+                .iter()
+                .map(|data| PartitionProduceResponse {
+                    index: data.index,
+                    error_code: TOPIC_AUTHORIZATION_FAILED,
+                    ..Default::default()
+                })
+                .collect();
+            denied_topics.push((
+                topic_name.clone(),
+                TopicProduceResponse {
+                    partition_responses,
+                    ..Default::default()
+                },
+            ));
+            false
+        });
+        //the broker doesn't respond when no acknowledgement is requested
+        if !denied_topics.is_empty() && request.acks != 0 {
+            self.request_map.lock().unwrap().insert(
+                header.correlation_id,
+                RequestInfo {
+                    request_api_key: ApiKey::ProduceKey,
+                    request_api_version: header.request_api_version,
+                    group_id: None,
+                    denied_topics: Some(DeniedTopics::Produce(denied_topics)),
+                },
+            );
+        }
+
         //the content can be set in multiple topics and partitions in a single message
         //for each we wrap the content and add the secure channel identifier of
-        //the encrypted content
+        //the encrypted content, the records of cleartext topics are passed through
         for (topic_name, topic) in request.topic_data.iter_mut() {
+            if !self.topic_filters.is_encrypted(topic_name) {
+                continue;
+            }
             for data in &mut topic.partition_data {
                 if let Some(content) = data.records.take() {
+                    let started_at = Instant::now();
                     let mut content = BytesMut::from(content.as_ref());
                     let mut records = RecordBatchDecoder::decode(&mut content)
                         .map_err(|_| InterceptError::Io(Error::from(ErrorKind::InvalidData)))?;
 
                     for record in records.iter_mut() {
                         if let Some(record_value) = record.value.take() {
+                            let mut protected_headers = vec![];
+                            record.headers.retain(|key, value| {
+                                let is_cleartext = self.is_cleartext_header(key);
+                                if !is_cleartext {
+                                    protected_headers.push(RecordHeader {
+                                        key: key.to_string(),
+                                        value: value.as_ref().map(|v| v.to_vec()),
+                                    });
+                                }
+                                is_cleartext
+                            });
+
+                            let content = self
+                                .content_compression
+                                .compress(record_value.to_vec())
+                                .map_err(InterceptError::Io)?;
                             let encrypted_content = self
                                 .secure_channel_controller
-                                .encrypt_content_for(
-                                    context,
-                                    topic_name,
-                                    data.index,
-                                    record_value.to_vec(),
-                                )
+                                .encrypt_content_for(context, topic_name, data.index, content)
                                 .await
                                 .map_err(InterceptError::Ockam)?;
 
+                            let headers = if protected_headers.is_empty() {
+                                None
+                            } else {
+                                let encoded_headers = minicbor::to_vec(&protected_headers)
+                                    .map_err(|_| {
+                                        InterceptError::Io(Error::from(ErrorKind::InvalidData))
+                                    })?;
+                                let encrypted_headers = self
+                                    .secure_channel_controller
+                                    .encrypt_content_for(
+                                        context,
+                                        topic_name,
+                                        data.index,
+                                        encoded_headers,
+                                    )
+                                    .await
+                                    .map_err(InterceptError::Ockam)?;
+                                Some(encrypted_headers.content)
+                            };
+
                             //TODO: to target multiple consumers we could duplicate
                             // the content with a dedicated encryption for each consumer
                             let wrapper = MessageWrapper {
+                                version: Some(MESSAGE_WRAPPER_VERSION),
                                 consumer_decryptor_address: encrypted_content
                                     .consumer_decryptor_address,
                                 content: encrypted_content.content,
+                                headers,
+                                // the compression is omitted when the content is not compressed
+                                // so that the record can be read by older consumers
+                                compression: match self.content_compression {
+                                    ContentCompression::None => None,
+                                    compression => Some(compression),
+                                },
                             };
 
                             let mut write_buffer = Vec::with_capacity(1024);
@@ -211,6 +369,7 @@ impl InletInterceptorImpl {
                     .map_err(|_| InterceptError::Io(Error::from(ErrorKind::InvalidData)))?;
 
                     data.records = Some(encoded.freeze());
+                    self.metrics.encryption().observe(started_at.elapsed());
                 }
             }
         }
@@ -222,4 +381,43 @@ impl InletInterceptorImpl {
             ApiKey::ProduceKey,
         )
     }
+
+    ///Replace the metadata of every committed offset with a signature of the offset,
+    /// the original metadata being part of the signed data
+    async fn handle_offset_commit_request(
+        &self,
+        signer: &OffsetCommitSigner,
+        buffer: &mut Bytes,
+        header: &RequestHeader,
+    ) -> Result<BytesMut, InterceptError> {
+        let mut request: OffsetCommitRequest = decode_body(buffer, header.request_api_version)?;
+
+        let group_id = request.group_id.0.to_string();
+        for topic in request.topics.iter_mut() {
+            for partition in topic.partitions.iter_mut() {
+                let metadata = partition
+                    .committed_metadata
+                    .take()
+                    .map(|metadata| metadata.to_string());
+                let signed_metadata = signer
+                    .sign(
+                        &group_id,
+                        &topic.name,
+                        partition.partition_index,
+                        partition.committed_offset,
+                        metadata,
+                    )
+                    .await
+                    .map_err(InterceptError::Ockam)?;
+                partition.committed_metadata = Some(string_to_str_bytes(signed_metadata));
+            }
+        }
+
+        encode_request(
+            header,
+            &request,
+            header.request_api_version,
+            ApiKey::OffsetCommitKey,
+        )
+    }
 }
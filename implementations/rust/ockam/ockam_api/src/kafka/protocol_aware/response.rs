@@ -1,25 +1,51 @@
 use std::io::{Error, ErrorKind};
 use std::net::SocketAddr;
+use std::time::Instant;
 
 use bytes::{Bytes, BytesMut};
 use kafka_protocol::messages::fetch_response::FetchResponse;
 use kafka_protocol::messages::find_coordinator_response::FindCoordinatorResponse;
 use kafka_protocol::messages::metadata_response::MetadataResponse;
+use kafka_protocol::messages::offset_fetch_response::OffsetFetchResponse;
+use kafka_protocol::messages::produce_response::ProduceResponse;
 use kafka_protocol::messages::response_header::ResponseHeader;
 use kafka_protocol::messages::ApiKey;
 use kafka_protocol::protocol::buf::ByteBuf;
-use kafka_protocol::protocol::Decodable;
+use kafka_protocol::protocol::{Decodable, StrBytes};
 use kafka_protocol::records::{
-    Compression, RecordBatchDecoder, RecordBatchEncoder, RecordEncodeOptions,
+    Compression, Record, RecordBatchDecoder, RecordBatchEncoder, RecordEncodeOptions,
 };
-use minicbor::decode::Decoder;
+use ockam_core::errcode::Kind;
 use ockam_node::Context;
 use tracing::{trace, warn};
 
 use crate::kafka::inlet_controller::KafkaInletController;
 use crate::kafka::portal_worker::InterceptError;
 use crate::kafka::protocol_aware::utils::{decode_body, encode_response, string_to_str_bytes};
-use crate::kafka::protocol_aware::{InletInterceptorImpl, MessageWrapper, RequestInfo};
+use crate::kafka::protocol_aware::{
+    DecryptionFailurePolicy, DeniedTopics, InletInterceptorImpl, MessageWrapper, RecordHeader,
+    RequestInfo,
+};
+use crate::kafka::OffsetCommitSigner;
+
+/// Kafka error code of a record whose checksum doesn't match its content
+pub(super) const CORRUPT_MESSAGE: i16 = 2;
+/// Kafka error code of a topic which the client is not allowed to access
+pub(super) const TOPIC_AUTHORIZATION_FAILED: i16 = 29;
+/// Kafka error code of a record written with a format which is not supported
+pub(super) const UNSUPPORTED_FOR_MESSAGE_FORMAT: i16 = 43;
+
+/// Return the kafka error code sent to the consumer for a record which cannot be decrypted:
+/// records using an unknown format or cipher suite are unsupported, the other records
+/// were tampered with or were not encrypted for this consumer
+fn decryption_error_code(error: &InterceptError) -> i16 {
+    match error {
+        InterceptError::Ockam(error) if error.code().kind == Kind::Unsupported => {
+            UNSUPPORTED_FOR_MESSAGE_FORMAT
+        }
+        _ => CORRUPT_MESSAGE,
+    }
+}
 
 impl InletInterceptorImpl {
     pub(crate) async fn intercept_response_impl(
@@ -74,6 +100,24 @@ impl InletInterceptorImpl {
                         .await;
                 }
 
+                ApiKey::ProduceKey => {
+                    return self.handle_produce_response(&mut buffer, &request_info, &header);
+                }
+
+                ApiKey::OffsetFetchKey => {
+                    if let Some(signer) = self.offset_commit_signer.as_ref() {
+                        return self
+                            .handle_offset_fetch_response(
+                                context,
+                                signer,
+                                &mut buffer,
+                                &request_info,
+                                &header,
+                            )
+                            .await;
+                    }
+                }
+
                 ApiKey::FindCoordinatorKey => {
                     return self
                         .handle_find_coordinator_response(
@@ -209,6 +253,64 @@ impl InletInterceptorImpl {
         )
     }
 
+    /// Decrypt the value of a fetched record, and add back its encrypted headers
+    async fn decrypt_record(
+        &self,
+        context: &mut Context,
+        topic_name: &str,
+        partition_index: i32,
+        record: &mut Record,
+    ) -> Result<(), InterceptError> {
+        let record_value = match record.value.take() {
+            Some(record_value) => record_value,
+            None => return Ok(()),
+        };
+        let message_wrapper = MessageWrapper::decode(record_value.as_ref())?;
+
+        let decrypted_content = self
+            .secure_channel_controller
+            .decrypt_content_for(
+                context,
+                topic_name,
+                partition_index,
+                &message_wrapper.consumer_decryptor_address,
+                message_wrapper.content,
+            )
+            .await
+            .map_err(InterceptError::Ockam)?;
+        let decrypted_content = message_wrapper
+            .compression
+            .unwrap_or_default()
+            .decompress(decrypted_content, MAX_DECOMPRESSED_CONTENT_SIZE)
+            .map_err(InterceptError::Io)?;
+
+        //the headers which were encrypted by the producer are added back
+        if let Some(headers) = message_wrapper.headers {
+            let decrypted_headers = self
+                .secure_channel_controller
+                .decrypt_content_for(
+                    context,
+                    topic_name,
+                    partition_index,
+                    &message_wrapper.consumer_decryptor_address,
+                    headers,
+                )
+                .await
+                .map_err(InterceptError::Ockam)?;
+            let headers: Vec<RecordHeader> = minicbor::decode(&decrypted_headers)
+                .map_err(|_| InterceptError::Io(Error::from(ErrorKind::InvalidData)))?;
+            for header in headers {
+                record.headers.insert(
+                    string_to_str_bytes(header.key),
+                    header.value.map(Bytes::from),
+                );
+            }
+        }
+
+        record.value = Some(decrypted_content.into());
+        Ok(())
+    }
+
     async fn handle_fetch_response(
         &self,
         context: &mut Context,
@@ -217,42 +319,103 @@ impl InletInterceptorImpl {
         header: &ResponseHeader,
     ) -> Result<BytesMut, InterceptError> {
         let mut response: FetchResponse = decode_body(buffer, request_info.request_api_version)?;
+        let denied_topics = match &request_info.denied_topics {
+            Some(DeniedTopics::Fetch(denied_topics)) => denied_topics.clone(),
+            _ => vec![],
+        };
 
         //in every response we want to decrypt the message content
         //we take every record batch content, unwrap and decode it
         //using the relative secure channel
         for response in response.responses.iter_mut() {
+            // the topic is only identified by its id starting with the version 13 of the API
+            let topic_name: &str = &response.topic;
+            let topic_name = if topic_name.is_empty() {
+                response.topic_id.to_string()
+            } else {
+                topic_name.to_string()
+            };
+            if !self.is_encrypted_topic(&topic_name) {
+                continue;
+            }
             for partition in response.partitions.iter_mut() {
                 if let Some(content) = partition.records.take() {
+                    let started_at = Instant::now();
                     let mut content = BytesMut::from(content.as_ref());
                     let mut records = RecordBatchDecoder::decode(&mut content)
                         .map_err(|_| InterceptError::Io(Error::from(ErrorKind::InvalidData)))?;
 
-                    for record in records.iter_mut() {
-                        if let Some(record_value) = record.value.take() {
-                            let message_wrapper: MessageWrapper =
-                                Decoder::new(record_value.as_ref()).decode().map_err(|_| {
-                                    InterceptError::Io(Error::from(ErrorKind::InvalidData))
-                                })?;
-
-                            let decrypted_content = self
-                                .secure_channel_controller
-                                .decrypt_content_for(
-                                    context,
-                                    &message_wrapper.consumer_decryptor_address,
-                                    message_wrapper.content,
-                                )
-                                .await
-                                .map_err(InterceptError::Ockam)?;
-
-                            record.value = Some(decrypted_content.into());
+                    let mut decrypted_records = Vec::with_capacity(records.len());
+                    let mut failure = None;
+                    for mut record in records {
+                        let result = self
+                            .decrypt_record(
+                                context,
+                                &topic_name,
+                                partition.partition_index,
+                                &mut record,
+                            )
+                            .await;
+                        let error = match result {
+                            Ok(()) => {
+                                decrypted_records.push(record);
+                                continue;
+                            }
+                            Err(error) => error,
+                        };
+                        match self.decryption_failure_policy {
+                            DecryptionFailurePolicy::Fail => {
+                                failure = Some((record.offset, error));
+                                break;
+                            }
+                            DecryptionFailurePolicy::Skip => {
+                                warn!(
+                                    topic = %topic_name,
+                                    partition = partition.partition_index,
+                                    offset = record.offset,
+                                    ?error,
+                                    "skipping a record which cannot be decrypted"
+                                );
+                                self.metrics.record_skipped_record();
+                            }
+                            DecryptionFailurePolicy::Tombstone => {
+                                warn!(
+                                    topic = %topic_name,
+                                    partition = partition.partition_index,
+                                    offset = record.offset,
+                                    ?error,
+                                    "replacing a record which cannot be decrypted with a tombstone"
+                                );
+                                record.value = None;
+                                decrypted_records.push(record);
+                                self.metrics.record_tombstoned_record();
+                            }
                         }
                     }
 
+                    //the whole partition is returned as an error, with a code which can be
+                    //handled by the consumer, the failure is logged with the correlation id
+                    //of the request so that it can be found from the consumer side
+                    if let Some((offset, error)) = failure {
+                        let error_code = decryption_error_code(&error);
+                        warn!(
+                            topic = %topic_name,
+                            partition = partition.partition_index,
+                            offset,
+                            error_code,
+                            correlation_id = header.correlation_id,
+                            ?error,
+                            "returning an error for a partition with a record which cannot be decrypted"
+                        );
+                        partition.error_code = error_code;
+                        self.metrics.record_failed_record();
+                        continue;
+                    }
+
                     let mut encoded = BytesMut::new();
                     RecordBatchEncoder::encode(
                         &mut encoded,
-                        records.iter(),
+                        decrypted_records.iter(),
                         &RecordEncodeOptions {
                             version: 2,
                             compression: Compression::None,
@@ -260,9 +423,12 @@ impl InletInterceptorImpl {
                     )
                     .map_err(|_| InterceptError::Io(Error::from(ErrorKind::InvalidData)))?;
                     partition.records = Some(encoded.freeze());
+                    self.metrics.decryption().observe(started_at.elapsed());
                 }
             }
         }
+        //the topics which were not sent to the broker are answered with an error
+        response.responses.extend(denied_topics);
 
         encode_response(
             header,
@@ -271,4 +437,109 @@ impl InletInterceptorImpl {
             ApiKey::FetchKey,
         )
     }
+
+    ///Add an error for each topic which was not sent to the broker because it is not allowed
+    fn handle_produce_response(
+        &self,
+        buffer: &mut Bytes,
+        request_info: &RequestInfo,
+        header: &ResponseHeader,
+    ) -> Result<BytesMut, InterceptError> {
+        let mut response: ProduceResponse = decode_body(buffer, request_info.request_api_version)?;
+        if let Some(DeniedTopics::Produce(denied_topics)) = &request_info.denied_topics {
+            response.responses.extend(denied_topics.iter().cloned());
+        }
+
+        encode_response(
+            header,
+            &response,
+            request_info.request_api_version,
+            ApiKey::ProduceKey,
+        )
+    }
+
+    ///Verify the signature of the committed offsets and restore their original metadata.
+    /// A failed verification is reported but doesn't prevent the consumer from
+    /// receiving the offset
+    async fn handle_offset_fetch_response(
+        &self,
+        context: &mut Context,
+        signer: &OffsetCommitSigner,
+        buffer: &mut Bytes,
+        request_info: &RequestInfo,
+        header: &ResponseHeader,
+    ) -> Result<BytesMut, InterceptError> {
+        let mut response: OffsetFetchResponse =
+            decode_body(buffer, request_info.request_api_version)?;
+
+        let group_id = request_info.group_id.clone().unwrap_or_default();
+        for topic in response.topics.iter_mut() {
+            for partition in topic.partitions.iter_mut() {
+                partition.metadata = Self::verify_committed_offset(
+                    context,
+                    signer,
+                    &group_id,
+                    &topic.name,
+                    partition.partition_index,
+                    partition.committed_offset,
+                    partition.metadata.take(),
+                )
+                .await;
+            }
+        }
+        //since version 8 the offsets of several groups can be fetched at once
+        for group in response.groups.iter_mut() {
+            let group_id = group.group_id.0.to_string();
+            for topic in group.topics.iter_mut() {
+                for partition in topic.partitions.iter_mut() {
+                    partition.metadata = Self::verify_committed_offset(
+                        context,
+                        signer,
+                        &group_id,
+                        &topic.name,
+                        partition.partition_index,
+                        partition.committed_offset,
+                        partition.metadata.take(),
+                    )
+                    .await;
+                }
+            }
+        }
+
+        encode_response(
+            header,
+            &response,
+            request_info.request_api_version,
+            ApiKey::OffsetFetchKey,
+        )
+    }
+
+    ///Return the original metadata of a committed offset
+    async fn verify_committed_offset(
+        context: &Context,
+        signer: &OffsetCommitSigner,
+        group_id: &str,
+        topic: &str,
+        partition: i32,
+        offset: i64,
+        metadata: Option<StrBytes>,
+    ) -> Option<StrBytes> {
+        //a negative offset means that no offset was committed for this partition
+        if offset < 0 {
+            return metadata;
+        }
+        let signed_metadata = metadata.as_ref().map(|m| m.to_string()).unwrap_or_default();
+        match signer
+            .verify(group_id, topic, partition, offset, &signed_metadata)
+            .await
+        {
+            Ok(original) => original.map(string_to_str_bytes),
+            Err(error) => {
+                signer
+                    .report_failure(context, group_id, topic, partition, &error)
+                    .await;
+                metadata
+            }
+        }
+    }
 }
@@ -1,16 +1,35 @@
 #[cfg(test)]
 mod test {
     use crate::kafka::inlet_controller::KafkaInletController;
+    use crate::kafka::portal_worker::InterceptError;
+    use crate::kafka::protocol_aware::response::{
+        CORRUPT_MESSAGE, TOPIC_AUTHORIZATION_FAILED, UNSUPPORTED_FOR_MESSAGE_FORMAT,
+    };
+    use crate::kafka::protocol_aware::utils::{decode_body, string_to_str_bytes};
     use crate::kafka::protocol_aware::utils::{encode_request, encode_response};
-    use crate::kafka::protocol_aware::InletInterceptorImpl;
     use crate::kafka::protocol_aware::KafkaMessageInterceptor;
+    use crate::kafka::protocol_aware::{
+        ContentCompression, DecryptionFailurePolicy, InletInterceptorImpl, MessageWrapper,
+        RequestInfo, MESSAGE_WRAPPER_VERSION,
+    };
     use crate::kafka::secure_channel_map::{KafkaEncryptedContent, KafkaSecureChannelController};
+    use crate::kafka::wrap_encrypted_record;
+    use crate::kafka::{KafkaMetrics, TopicFilters};
     use crate::port_range::PortRange;
+    use bytes::{Bytes, BytesMut};
+    use indexmap::IndexMap;
+    use kafka_protocol::messages::fetch_response::{FetchableTopicResponse, PartitionData};
+    use kafka_protocol::messages::produce_request::{PartitionProduceData, TopicProduceData};
     use kafka_protocol::messages::ApiKey;
     use kafka_protocol::messages::BrokerId;
     use kafka_protocol::messages::{ApiVersionsRequest, MetadataRequest, MetadataResponse};
     use kafka_protocol::messages::{ApiVersionsResponse, RequestHeader, ResponseHeader};
-    use kafka_protocol::protocol::{Builder, StrBytes};
+    use kafka_protocol::messages::{FetchResponse, ProduceRequest, ProduceResponse, TopicName};
+    use kafka_protocol::protocol::{Builder, Decodable, StrBytes};
+    use kafka_protocol::records::{
+        Compression, Record, RecordBatchDecoder, RecordBatchEncoder, RecordEncodeOptions,
+        TimestampType,
+    };
     use ockam_core::compat::sync::Arc;
     use ockam_core::route;
     use ockam_core::{async_trait, Address};
@@ -37,6 +56,8 @@ mod test {
         async fn decrypt_content_for(
             &self,
             _context: &mut Context,
+            _topic_name: &str,
+            _partition_id: i32,
             _consumer_decryptor_address: &Address,
             encrypted_content: Vec<u8>,
         ) -> ockam_core::Result<Vec<u8>> {
@@ -51,6 +72,10 @@ mod test {
         ) -> ockam_core::Result<()> {
             Ok(())
         }
+
+        async fn rekey(&self, _context: &Context) -> ockam_core::Result<usize> {
+            Ok(0)
+        }
     }
 
     #[allow(non_snake_case)]
@@ -70,6 +95,10 @@ mod test {
             Arc::new(DummySecureChannelController {}),
             Default::default(),
             inlet_map,
+            None,
+            Default::default(),
+            None,
+            Default::default(),
         );
 
         let mut correlation_id = 0;
@@ -204,4 +233,655 @@ mod test {
 
         context.stop().await
     }
+
+    #[allow(non_snake_case)]
+    #[ockam_macros::test(timeout = 5_000)]
+    async fn interceptor__cleartext_headers__other_headers_encrypted_and_restored(
+        context: &mut Context,
+    ) -> ockam::Result<()> {
+        let mut interceptor = interceptor();
+        interceptor.cleartext_headers = Some(vec!["trace-id".to_string()]);
+
+        let mut headers = IndexMap::new();
+        headers.insert(
+            string_to_str_bytes("trace-id".to_string()),
+            Some(Bytes::from("1234")),
+        );
+        headers.insert(
+            string_to_str_bytes("customer".to_string()),
+            Some(Bytes::from("alice")),
+        );
+        let records = encode_records(headers, Bytes::from("hello world!"));
+
+        let mut topic_data = IndexMap::new();
+        topic_data.insert(
+            TopicName::from(StrBytes::from_str("my-topic-name")),
+            TopicProduceData::builder()
+                .partition_data(vec![PartitionProduceData::builder()
+                    .index(1)
+                    .records(Some(records))
+                    .unknown_tagged_fields(Default::default())
+                    .build()
+                    .unwrap()])
+                .unknown_tagged_fields(Default::default())
+                .build()
+                .unwrap(),
+        );
+
+        let api_version = 7;
+        let mut request = interceptor
+            .intercept_request(
+                context,
+                encode_request(
+                    &RequestHeader::builder()
+                        .request_api_version(api_version)
+                        .correlation_id(1)
+                        .request_api_key(ApiKey::ProduceKey as i16)
+                        .unknown_tagged_fields(Default::default())
+                        .client_id(None)
+                        .build()
+                        .unwrap(),
+                    &ProduceRequest::builder()
+                        .transactional_id(None)
+                        .acks(0)
+                        .timeout_ms(0)
+                        .topic_data(topic_data)
+                        .unknown_tagged_fields(Default::default())
+                        .build()
+                        .unwrap(),
+                    api_version,
+                    ApiKey::ProduceKey,
+                )
+                .unwrap(),
+            )
+            .await
+            .unwrap()
+            .freeze();
+
+        // only the allowlisted header is sent in clear text to the broker
+        RequestHeader::decode(
+            &mut request,
+            ApiKey::ProduceKey.request_header_version(api_version),
+        )
+        .unwrap();
+        let request: ProduceRequest = decode_body(&mut request, api_version).unwrap();
+        let produced = request.topic_data[0].partition_data[0]
+            .records
+            .clone()
+            .unwrap();
+        let record = decode_records(produced.clone()).pop().unwrap();
+        let header_keys: Vec<String> = record.headers.keys().map(|k| k.to_string()).collect();
+        assert_eq!(header_keys, vec!["trace-id".to_string()]);
+        let wrapper: MessageWrapper = minicbor::decode(&record.value.unwrap()).unwrap();
+        assert!(wrapper.headers.is_some());
+
+        // the encrypted headers are added back when the record is fetched
+        interceptor.request_map.lock().unwrap().insert(
+            2,
+            RequestInfo {
+                request_api_key: ApiKey::FetchKey,
+                request_api_version: 11,
+                group_id: None,
+                denied_topics: None,
+            },
+        );
+        let mut response = interceptor
+            .intercept_response(
+                context,
+                encode_response(
+                    &ResponseHeader::builder()
+                        .correlation_id(2)
+                        .unknown_tagged_fields(Default::default())
+                        .build()
+                        .unwrap(),
+                    &FetchResponse::builder()
+                        .throttle_time_ms(0)
+                        .error_code(0)
+                        .session_id(0)
+                        .responses(vec![FetchableTopicResponse::builder()
+                            .topic(TopicName::from(StrBytes::from_str("my-topic-name")))
+                            .topic_id(Default::default())
+                            .partitions(vec![PartitionData::builder()
+                                .partition_index(1)
+                                .error_code(0)
+                                .high_watermark(0)
+                                .last_stable_offset(0)
+                                .log_start_offset(0)
+                                .diverging_epoch(Default::default())
+                                .current_leader(Default::default())
+                                .snapshot_id(Default::default())
+                                .aborted_transactions(None)
+                                .preferred_read_replica(Default::default())
+                                .records(Some(produced))
+                                .unknown_tagged_fields(Default::default())
+                                .build()
+                                .unwrap()])
+                            .unknown_tagged_fields(Default::default())
+                            .build()
+                            .unwrap()])
+                        .unknown_tagged_fields(Default::default())
+                        .build()
+                        .unwrap(),
+                    11,
+                    ApiKey::FetchKey,
+                )
+                .unwrap(),
+            )
+            .await
+            .unwrap()
+            .freeze();
+
+        ResponseHeader::decode(&mut response, ApiKey::FetchKey.response_header_version(11))
+            .unwrap();
+        let response: FetchResponse = decode_body(&mut response, 11).unwrap();
+        let fetched = response.responses[0].partitions[0].records.clone().unwrap();
+        let record = decode_records(fetched).pop().unwrap();
+        assert_eq!(record.value, Some(Bytes::from("hello world!")));
+        assert_eq!(record.headers.len(), 2);
+        assert_eq!(
+            record.headers[&string_to_str_bytes("customer".to_string())],
+            Some(Bytes::from("alice"))
+        );
+
+        context.stop().await
+    }
+
+    #[allow(non_snake_case)]
+    #[ockam_macros::test(timeout = 5_000)]
+    async fn interceptor__undecryptable_record__handled_with_the_decryption_failure_policy(
+        context: &mut Context,
+    ) -> ockam::Result<()> {
+        let metrics = KafkaMetrics::default();
+        let mut interceptor = interceptor();
+        interceptor.metrics = metrics.clone();
+
+        let encrypted = wrap_encrypted_record(
+            &Address::from_string("arbitrary string"),
+            b"hello world!".to_vec(),
+        )?;
+        let records = encode_values(vec![
+            Bytes::from(encrypted),
+            Bytes::from("not an encrypted record"),
+        ]);
+
+        interceptor.decryption_failure_policy = DecryptionFailurePolicy::Fail;
+        let partition = fetch_partition(&interceptor, context, 1, records.clone())
+            .await
+            .unwrap();
+        assert_eq!(partition.error_code, CORRUPT_MESSAGE);
+        assert_eq!(partition.records, None);
+        assert_eq!(metrics.failed_records(), 1);
+
+        interceptor.decryption_failure_policy = DecryptionFailurePolicy::Skip;
+        let fetched = fetch(&interceptor, context, 2, records.clone())
+            .await
+            .unwrap();
+        assert_eq!(fetched.len(), 1);
+        assert_eq!(fetched[0].value, Some(Bytes::from("hello world!")));
+        assert_eq!(metrics.skipped_records(), 1);
+
+        interceptor.decryption_failure_policy = DecryptionFailurePolicy::Tombstone;
+        let fetched = fetch(&interceptor, context, 3, records).await.unwrap();
+        assert_eq!(fetched.len(), 2);
+        assert_eq!(fetched[0].value, Some(Bytes::from("hello world!")));
+        assert_eq!(fetched[1].value, None);
+        assert_eq!(fetched[1].offset, 1);
+        assert_eq!(metrics.tombstoned_records(), 1);
+
+        context.stop().await
+    }
+
+    #[allow(non_snake_case)]
+    #[ockam_macros::test(timeout = 5_000)]
+    async fn interceptor__compressed_content__decompressed_by_any_consumer(
+        context: &mut Context,
+    ) -> ockam::Result<()> {
+        let mut producer = interceptor();
+        producer.content_compression = ContentCompression::Gzip;
+
+        let value = Bytes::from("hello hello hello hello hello hello");
+        let produced = produce(
+            &producer,
+            context,
+            encode_records(Default::default(), value.clone()),
+        )
+        .await;
+        let record = decode_records(produced.clone()).pop().unwrap();
+        let wrapper: MessageWrapper = minicbor::decode(&record.value.unwrap()).unwrap();
+        assert_eq!(wrapper.compression, Some(ContentCompression::Gzip));
+        assert_ne!(wrapper.content, value.to_vec());
+
+        // the compression of the consumer only applies to the records it produces
+        let consumer = interceptor();
+        let fetched = fetch(&consumer, context, 2, produced).await.unwrap();
+        assert_eq!(fetched[0].value, Some(value));
+
+        context.stop().await
+    }
+
+    #[allow(non_snake_case)]
+    #[ockam_macros::test(timeout = 5_000)]
+    async fn interceptor__record_format_version__newer_versions_rejected(
+        context: &mut Context,
+    ) -> ockam::Result<()> {
+        let interceptor = interceptor();
+
+        let wrap = |version: Option<u8>| {
+            minicbor::to_vec(MessageWrapper {
+                version,
+                consumer_decryptor_address: Address::from_string("arbitrary string"),
+                content: b"hello world!".to_vec(),
+                headers: None,
+                compression: None,
+            })
+            .unwrap()
+        };
+
+        // the records written before the format was versioned can still be read
+        let records = encode_records(Default::default(), Bytes::from(wrap(None)));
+        let fetched = fetch(&interceptor, context, 1, records).await.unwrap();
+        assert_eq!(fetched[0].value, Some(Bytes::from("hello world!")));
+
+        let newer_version = wrap(Some(MESSAGE_WRAPPER_VERSION + 1));
+        match MessageWrapper::decode(&newer_version) {
+            Err(InterceptError::Ockam(error)) => {
+                assert!(error.to_string().contains("version 2 of the record format"))
+            }
+            result => panic!("unexpected result: {result:?}"),
+        }
+        let records = encode_records(Default::default(), Bytes::from(newer_version));
+        let partition = fetch_partition(&interceptor, context, 2, records)
+            .await
+            .unwrap();
+        assert_eq!(partition.error_code, UNSUPPORTED_FOR_MESSAGE_FORMAT);
+
+        context.stop().await
+    }
+
+    #[allow(non_snake_case)]
+    #[ockam_macros::test(timeout = 5_000)]
+    async fn interceptor__cleartext_topics__records_are_passed_through(
+        context: &mut Context,
+    ) -> ockam::Result<()> {
+        let records = encode_records(Default::default(), Bytes::from("hello world!"));
+
+        // the records of a topic matching a cleartext pattern are not modified
+        let cleartext = interceptor_with_topic_filters(
+            TopicFilters::default().with_cleartext_topics(vec!["my-topic-*".to_string()]),
+        );
+        let produced = produce(&cleartext, context, records.clone()).await;
+        assert_eq!(produced, records);
+        let fetched = fetch(&cleartext, context, 1, produced).await.unwrap();
+        assert_eq!(fetched[0].value, Some(Bytes::from("hello world!")));
+
+        // the records of a topic not matching an encrypted pattern are not modified either
+        let cleartext = interceptor_with_topic_filters(
+            TopicFilters::default().with_encrypted_topics(vec!["orders.*".to_string()]),
+        );
+        let produced = produce(&cleartext, context, records.clone()).await;
+        assert_eq!(produced, records);
+
+        // the records of the other topics are wrapped
+        let encrypted = interceptor_with_topic_filters(
+            TopicFilters::default().with_encrypted_topics(vec!["my-topic-????".to_string()]),
+        );
+        let produced = produce(&encrypted, context, records.clone()).await;
+        assert_ne!(produced, records);
+        let fetched = fetch(&encrypted, context, 2, produced).await.unwrap();
+        assert_eq!(fetched[0].value, Some(Bytes::from("hello world!")));
+
+        context.stop().await
+    }
+
+    /// Create an interceptor encrypting the records of all the topics with the dummy
+    /// secure channel controller
+    fn interceptor() -> InletInterceptorImpl {
+        interceptor_with_topic_filters(Default::default())
+    }
+
+    /// Create an interceptor deciding which topics are accepted and encrypted with some
+    /// topic filters
+    fn interceptor_with_topic_filters(topic_filters: TopicFilters) -> InletInterceptorImpl {
+        InletInterceptorImpl::new(
+            Arc::new(DummySecureChannelController {}),
+            Default::default(),
+            inlet_map(),
+            None,
+            topic_filters,
+            None,
+            Default::default(),
+        )
+    }
+
+    /// Create an inlet controller which does not start any inlet
+    fn inlet_map() -> KafkaInletController {
+        KafkaInletController::new(
+            MultiAddr::default(),
+            route![],
+            route![],
+            [127, 0, 0, 1].into(),
+            PortRange::new(0, 0).unwrap(),
+        )
+    }
+
+    /// Send a produce request containing `records` through the interceptor and return the
+    /// records sent to the broker
+    async fn produce(
+        interceptor: &InletInterceptorImpl,
+        context: &mut Context,
+        records: Bytes,
+    ) -> Bytes {
+        let mut topic_data = IndexMap::new();
+        topic_data.insert(
+            TopicName::from(StrBytes::from_str("my-topic-name")),
+            TopicProduceData::builder()
+                .partition_data(vec![PartitionProduceData::builder()
+                    .index(1)
+                    .records(Some(records))
+                    .unknown_tagged_fields(Default::default())
+                    .build()
+                    .unwrap()])
+                .unknown_tagged_fields(Default::default())
+                .build()
+                .unwrap(),
+        );
+
+        let api_version = 7;
+        let mut request = interceptor
+            .intercept_request(
+                context,
+                encode_request(
+                    &RequestHeader::builder()
+                        .request_api_version(api_version)
+                        .correlation_id(1)
+                        .request_api_key(ApiKey::ProduceKey as i16)
+                        .unknown_tagged_fields(Default::default())
+                        .client_id(None)
+                        .build()
+                        .unwrap(),
+                    &ProduceRequest::builder()
+                        .transactional_id(None)
+                        .acks(0)
+                        .timeout_ms(0)
+                        .topic_data(topic_data)
+                        .unknown_tagged_fields(Default::default())
+                        .build()
+                        .unwrap(),
+                    api_version,
+                    ApiKey::ProduceKey,
+                )
+                .unwrap(),
+            )
+            .await
+            .unwrap()
+            .freeze();
+
+        RequestHeader::decode(
+            &mut request,
+            ApiKey::ProduceKey.request_header_version(api_version),
+        )
+        .unwrap();
+        let request: ProduceRequest = decode_body(&mut request, api_version).unwrap();
+        request.topic_data[0].partition_data[0]
+            .records
+            .clone()
+            .unwrap()
+    }
+
+    /// Send a fetch response containing `records` through the interceptor and return the
+    /// fetched records
+    async fn fetch(
+        interceptor: &InletInterceptorImpl,
+        context: &mut Context,
+        correlation_id: i32,
+        records: Bytes,
+    ) -> Result<Vec<Record>, InterceptError> {
+        let partition = fetch_partition(interceptor, context, correlation_id, records).await?;
+        assert_eq!(partition.error_code, 0);
+        Ok(decode_records(partition.records.unwrap()))
+    }
+
+    /// Send a fetch response containing `records` through the interceptor and return the
+    /// fetched partition
+    async fn fetch_partition(
+        interceptor: &InletInterceptorImpl,
+        context: &mut Context,
+        correlation_id: i32,
+        records: Bytes,
+    ) -> Result<PartitionData, InterceptError> {
+        let api_version = 11;
+        interceptor.request_map.lock().unwrap().insert(
+            correlation_id,
+            RequestInfo {
+                request_api_key: ApiKey::FetchKey,
+                request_api_version: api_version,
+                group_id: None,
+                denied_topics: None,
+            },
+        );
+        let mut response = interceptor
+            .intercept_response(
+                context,
+                encode_response(
+                    &ResponseHeader::builder()
+                        .correlation_id(correlation_id)
+                        .unknown_tagged_fields(Default::default())
+                        .build()
+                        .unwrap(),
+                    &FetchResponse::builder()
+                        .throttle_time_ms(0)
+                        .error_code(0)
+                        .session_id(0)
+                        .responses(vec![FetchableTopicResponse::builder()
+                            .topic(TopicName::from(StrBytes::from_str("my-topic-name")))
+                            .topic_id(Default::default())
+                            .partitions(vec![PartitionData::builder()
+                                .partition_index(1)
+                                .error_code(0)
+                                .high_watermark(0)
+                                .last_stable_offset(0)
+                                .log_start_offset(0)
+                                .diverging_epoch(Default::default())
+                                .current_leader(Default::default())
+                                .snapshot_id(Default::default())
+                                .aborted_transactions(None)
+                                .preferred_read_replica(Default::default())
+                                .records(Some(records))
+                                .unknown_tagged_fields(Default::default())
+                                .build()
+                                .unwrap()])
+                            .unknown_tagged_fields(Default::default())
+                            .build()
+                            .unwrap()])
+                        .unknown_tagged_fields(Default::default())
+                        .build()
+                        .unwrap(),
+                    api_version,
+                    ApiKey::FetchKey,
+                )
+                .unwrap(),
+            )
+            .await?
+            .freeze();
+
+        ResponseHeader::decode(
+            &mut response,
+            ApiKey::FetchKey.response_header_version(api_version),
+        )
+        .unwrap();
+        let response: FetchResponse = decode_body(&mut response, api_version).unwrap();
+        Ok(response.responses[0].partitions[0].clone())
+    }
+
+    fn encode_records(headers: IndexMap<StrBytes, Option<Bytes>>, value: Bytes) -> Bytes {
+        let mut encoded = BytesMut::new();
+        RecordBatchEncoder::encode(
+            &mut encoded,
+            vec![Record {
+                transactional: false,
+                control: false,
+                partition_leader_epoch: 0,
+                producer_id: 0,
+                producer_epoch: 0,
+                timestamp_type: TimestampType::Creation,
+                offset: 0,
+                sequence: 0,
+                timestamp: 0,
+                key: None,
+                value: Some(value),
+                headers,
+            }]
+            .iter(),
+            &RecordEncodeOptions {
+                version: 2,
+                compression: Compression::None,
+            },
+        )
+        .unwrap();
+        encoded.freeze()
+    }
+
+    fn encode_values(values: Vec<Bytes>) -> Bytes {
+        let records: Vec<Record> = values
+            .into_iter()
+            .enumerate()
+            .map(|(offset, value)| Record {
+                transactional: false,
+                control: false,
+                partition_leader_epoch: 0,
+                producer_id: 0,
+                producer_epoch: 0,
+                timestamp_type: TimestampType::Creation,
+                offset: offset as i64,
+                sequence: offset as i32,
+                timestamp: 0,
+                key: None,
+                value: Some(value),
+                headers: Default::default(),
+            })
+            .collect();
+        let mut encoded = BytesMut::new();
+        RecordBatchEncoder::encode(
+            &mut encoded,
+            records.iter(),
+            &RecordEncodeOptions {
+                version: 2,
+                compression: Compression::None,
+            },
+        )
+        .unwrap();
+        encoded.freeze()
+    }
+
+    #[allow(non_snake_case)]
+    #[ockam_macros::test(timeout = 5_000)]
+    async fn interceptor__topic_filter__only_allowed_topics_are_accepted(
+        context: &mut Context,
+    ) -> ockam::Result<()> {
+        let interceptor = |topics: Vec<&str>| {
+            interceptor_with_topic_filters(
+                TopicFilters::default()
+                    .with_allowed_topics(topics.into_iter().map(|t| t.to_string()).collect()),
+            )
+        };
+
+        let allowed = interceptor(vec!["orders", "my-topic-*"]);
+        let records = encode_records(Default::default(), Bytes::from("hello world!"));
+        produce(&allowed, context, records.clone()).await;
+
+        let denied = interceptor(vec!["orders"]);
+        let mut topic_data = IndexMap::new();
+        topic_data.insert(
+            TopicName::from(StrBytes::from_str("my-topic-name")),
+            TopicProduceData::builder()
+                .partition_data(vec![PartitionProduceData::builder()
+                    .index(1)
+                    .records(Some(records))
+                    .unknown_tagged_fields(Default::default())
+                    .build()
+                    .unwrap()])
+                .unknown_tagged_fields(Default::default())
+                .build()
+                .unwrap(),
+        );
+        let api_version = 7;
+        let mut request = denied
+            .intercept_request(
+                context,
+                encode_request(
+                    &RequestHeader::builder()
+                        .request_api_version(api_version)
+                        .correlation_id(1)
+                        .request_api_key(ApiKey::ProduceKey as i16)
+                        .unknown_tagged_fields(Default::default())
+                        .client_id(None)
+                        .build()
+                        .unwrap(),
+                    &ProduceRequest::builder()
+                        .transactional_id(None)
+                        .acks(1)
+                        .timeout_ms(0)
+                        .topic_data(topic_data)
+                        .unknown_tagged_fields(Default::default())
+                        .build()
+                        .unwrap(),
+                    api_version,
+                    ApiKey::ProduceKey,
+                )
+                .unwrap(),
+            )
+            .await
+            .unwrap()
+            .freeze();
+
+        // the records of the denied topic are not sent to the broker
+        RequestHeader::decode(
+            &mut request,
+            ApiKey::ProduceKey.request_header_version(api_version),
+        )
+        .unwrap();
+        let request: ProduceRequest = decode_body(&mut request, api_version).unwrap();
+        assert!(request.topic_data.is_empty());
+
+        // and the client receives an authorization error for the topic
+        let mut response = denied
+            .intercept_response(
+                context,
+                encode_response(
+                    &ResponseHeader::builder()
+                        .correlation_id(1)
+                        .unknown_tagged_fields(Default::default())
+                        .build()
+                        .unwrap(),
+                    &ProduceResponse::default(),
+                    api_version,
+                    ApiKey::ProduceKey,
+                )
+                .unwrap(),
+            )
+            .await
+            .unwrap()
+            .freeze();
+        ResponseHeader::decode(
+            &mut response,
+            ApiKey::ProduceKey.response_header_version(api_version),
+        )
+        .unwrap();
+        let response: ProduceResponse = decode_body(&mut response, api_version).unwrap();
+        let topic = response
+            .responses
+            .get(&TopicName::from(StrBytes::from_str("my-topic-name")))
+            .unwrap();
+        assert_eq!(topic.partition_responses[0].index, 1);
+        assert_eq!(
+            topic.partition_responses[0].error_code,
+            TOPIC_AUTHORIZATION_FAILED
+        );
+
+        context.stop().await
+    }
+
+    fn decode_records(records: Bytes) -> Vec<Record> {
+        RecordBatchDecoder::decode(&mut BytesMut::from(records.as_ref())).unwrap()
+    }
 }
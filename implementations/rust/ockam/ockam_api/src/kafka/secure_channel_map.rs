@@ -1,4 +1,5 @@
-use crate::kafka::KAFKA_OUTLET_CONSUMERS;
+use crate::kafka::audit::{KafkaAuditLog, KafkaAuditOperation, KafkaAuditRecord};
+use crate::kafka::{KafkaMetrics, KAFKA_OUTLET_CONSUMERS};
 use crate::nodes::models::relay::{CreateRelay, RelayInfo};
 use crate::nodes::models::secure_channel::{
     CreateSecureChannelRequest, CreateSecureChannelResponse, DeleteSecureChannelRequest,
@@ -6,6 +7,9 @@ use crate::nodes::models::secure_channel::{
 };
 use crate::nodes::NODEMANAGER_ADDR;
 use crate::DefaultAddress;
+use core::hash::Hash;
+use core::num::NonZeroUsize;
+use lru::LruCache;
 use minicbor::Decoder;
 use ockam::identity::{
     DecryptionRequest, DecryptionResponse, EncryptionRequest, EncryptionResponse,
@@ -13,15 +17,25 @@ use ockam::identity::{
 };
 use ockam_abac::AbacAccessControl;
 use ockam_core::api::{Request, ResponseHeader, Status};
-use ockam_core::compat::collections::{HashMap, HashSet};
+use ockam_core::compat::collections::HashSet;
 use ockam_core::compat::sync::Arc;
+use ockam_core::env::get_env;
 use ockam_core::errcode::{Kind, Origin};
-use ockam_core::{async_trait, route, Address, Error, Result};
+use ockam_core::{async_trait, route, Address, AllowAll, DenyAll, Error, Result};
 use ockam_multiaddr::proto::{Project, Service};
 use ockam_multiaddr::{MultiAddr, Protocol};
 use ockam_node::compat::tokio::sync::Mutex;
 use ockam_node::compat::tokio::sync::MutexGuard;
 use ockam_node::Context;
+use tokio::task::JoinSet;
+
+/// Environment variable setting the maximum number of secure channels used to encrypt, and to
+/// decrypt, kafka messages. The least recently used channel is closed when a new channel is needed.
+/// It also bounds the number of topic partitions whose relay is remembered as created
+const OCKAM_KAFKA_MAX_SECURE_CHANNELS: &str = "OCKAM_KAFKA_MAX_SECURE_CHANNELS";
+
+/// Maximum number of relays created at the same time for the partitions of a topic
+const MAX_CONCURRENT_RELAY_CREATIONS: usize = 16;
 
 pub(crate) struct KafkaEncryptedContent {
     /// The encrypted content
@@ -55,9 +69,12 @@ pub(crate) trait KafkaSecureChannelController: Send + Sync {
 
     /// Decrypts the content based on the consumer decryptor address
     /// the secure channel is expected to be already initialized.
+    /// The topic name and partition are only used to audit the decryption.
     async fn decrypt_content_for(
         &self,
         context: &mut Context,
+        topic_name: &str,
+        partition_id: i32,
         consumer_decryptor_address: &Address,
         encrypted_content: Vec<u8>,
     ) -> Result<Vec<u8>>;
@@ -65,12 +82,22 @@ pub(crate) trait KafkaSecureChannelController: Send + Sync {
     /// Starts relays in the orchestrator for each {topic_name}_{partition} combination
     /// should be used only by the consumer.
     /// does nothing if they were already created, but fails it they already exist.
+    /// The relays are only registered for the topics listed in the `kafka.consumer_topics`
+    /// attribute of the consumer identity when the outlet node has a trust context, the other
+    /// relays are denied with an error.
     async fn start_relays_for(
         &self,
         context: &mut Context,
         topic_id: &str,
         partitions: Vec<i32>,
     ) -> Result<()>;
+
+    /// Replaces the secure channels used to encrypt the content, so that new channels,
+    /// with new keys, are created for the next messages.
+    /// The replaced channels are only closed at the next rekeying, so that the messages
+    /// being encrypted with them, or still being consumed, are not lost.
+    /// Returns the number of replaced channels.
+    async fn rekey(&self, context: &Context) -> Result<usize>;
 }
 
 #[async_trait]
@@ -135,6 +162,7 @@ impl RelayCreator for NodeManagerRelayCreator {
 
 pub(crate) struct KafkaSecureChannelControllerImpl<F: RelayCreator> {
     inner: Arc<Mutex<InnerSecureChannelControllerImpl<F>>>,
+    metrics: KafkaMetrics,
 }
 
 //had to manually implement since #[derive(Clone)] doesn't work well in this situation
@@ -142,6 +170,7 @@ impl<F: RelayCreator> Clone for KafkaSecureChannelControllerImpl<F> {
     fn clone(&self) -> Self {
         Self {
             inner: self.inner.clone(),
+            metrics: self.metrics.clone(),
         }
     }
 }
@@ -155,16 +184,134 @@ pub(crate) enum ConsumerNodeAddr {
 }
 
 type TopicPartition = (String, i32);
+
+/// Encryptor addresses of secure channels, by key.
+///
+/// When a maximum number of channels is set, the least recently used channel is
+/// evicted to make room for a new one
+struct EncryptorMap<K: Hash + Eq> {
+    encryptors: LruCache<K, Address>,
+}
+
+impl<K: Hash + Eq> EncryptorMap<K> {
+    fn new(max_channels: Option<NonZeroUsize>) -> Self {
+        let encryptors = match max_channels {
+            Some(max_channels) => LruCache::new(max_channels),
+            None => LruCache::unbounded(),
+        };
+        Self { encryptors }
+    }
+
+    /// Return the encryptor address of a channel and mark it as recently used
+    fn get(&mut self, key: &K) -> Option<Address> {
+        self.encryptors.get(key).cloned()
+    }
+
+    /// Return the encryptor address of the least recently used channel if the map is full
+    fn evict(&mut self) -> Option<Address> {
+        if self.encryptors.len() < self.encryptors.cap().get() {
+            return None;
+        }
+        self.encryptors.pop_lru().map(|(_, address)| address)
+    }
+
+    fn insert(&mut self, key: K, address: Address) {
+        self.encryptors.put(key, address);
+    }
+
+    /// Remove all the channels and return their encryptor addresses
+    fn drain(&mut self) -> Vec<Address> {
+        let addresses = self
+            .encryptors
+            .iter()
+            .map(|(_, address)| address.clone())
+            .collect();
+        self.encryptors.clear();
+        addresses
+    }
+}
+
+/// Topic partitions whose relay is created, or being created, so that each relay is only
+/// created once.
+///
+/// When a maximum number of channels is set, the least recently requested topic partitions
+/// are forgotten, and their relay is created again if they are requested again
+#[derive(Clone)]
+struct TopicRelaySet {
+    relays: Arc<std::sync::Mutex<LruCache<TopicPartition, ()>>>,
+}
+
+impl TopicRelaySet {
+    fn new(max_relays: Option<NonZeroUsize>) -> Self {
+        let relays = match max_relays {
+            Some(max_relays) => LruCache::new(max_relays),
+            None => LruCache::unbounded(),
+        };
+        Self {
+            relays: Arc::new(std::sync::Mutex::new(relays)),
+        }
+    }
+
+    /// Reserve the relays which are not created, or being created, yet
+    fn reserve(&self, keys: impl IntoIterator<Item = TopicPartition>) -> RelayReservations {
+        let mut relays = self.relays.lock().unwrap();
+        let pending = keys
+            .into_iter()
+            .filter(|key| relays.put(key.clone(), ()).is_none())
+            .collect();
+        RelayReservations {
+            relays: self.clone(),
+            pending,
+        }
+    }
+}
+
+/// Relays reserved to be created.
+/// The reservations of the relays which were not created are released when this value is
+/// dropped, whether their creation failed, panicked or was cancelled, so that they are
+/// created again on the next request
+struct RelayReservations {
+    relays: TopicRelaySet,
+    pending: HashSet<TopicPartition>,
+}
+
+impl RelayReservations {
+    fn keys(&self) -> Vec<TopicPartition> {
+        self.pending.iter().cloned().collect()
+    }
+
+    fn created(&mut self, key: &TopicPartition) {
+        self.pending.remove(key);
+    }
+}
+
+impl Drop for RelayReservations {
+    fn drop(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let mut relays = self.relays.relays.lock().unwrap();
+        for key in self.pending.drain() {
+            relays.pop(&key);
+        }
+    }
+}
+
 struct InnerSecureChannelControllerImpl<F: RelayCreator> {
     // we identity the secure channel instance by using the decryptor of the consumer
     // which is known to both parties
-    topic_encryptor_map: HashMap<TopicPartition, Address>,
+    topic_encryptor_map: EncryptorMap<TopicPartition>,
+    // encryptor addresses of the channels used to decrypt messages, by decryptor address
+    consumer_encryptor_map: EncryptorMap<Address>,
+    // channels replaced by the last rekeying, closed by the next one
+    rekeyed_encryptors: Vec<Address>,
     // describes how to reach the consumer node
     consumer_node_multiaddr: ConsumerNodeAddr,
-    topic_relay_set: HashSet<TopicPartition>,
-    relay_creator: Option<F>,
+    topic_relay_set: TopicRelaySet,
+    relay_creator: Option<Arc<F>>,
     secure_channels: Arc<SecureChannels>,
     access_control: AbacAccessControl,
+    audit_log: KafkaAuditLog,
 }
 
 impl KafkaSecureChannelControllerImpl<NodeManagerRelayCreator> {
@@ -207,18 +354,36 @@ impl<F: RelayCreator> KafkaSecureChannelControllerImpl<F> {
             &trust_context_id,
         );
 
+        let max_channels = match get_env::<u64>(OCKAM_KAFKA_MAX_SECURE_CHANNELS) {
+            Ok(max_channels) => max_channels.and_then(|m| NonZeroUsize::new(m as usize)),
+            Err(e) => {
+                warn!(%e, "invalid {OCKAM_KAFKA_MAX_SECURE_CHANNELS}, the number of secure channels is not limited");
+                None
+            }
+        };
+
         Self {
             inner: Arc::new(Mutex::new(InnerSecureChannelControllerImpl {
-                topic_encryptor_map: Default::default(),
-                topic_relay_set: Default::default(),
+                topic_encryptor_map: EncryptorMap::new(max_channels),
+                consumer_encryptor_map: EncryptorMap::new(max_channels),
+                rekeyed_encryptors: vec![],
+                topic_relay_set: TopicRelaySet::new(max_channels),
                 secure_channels,
-                relay_creator,
+                relay_creator: relay_creator.map(Arc::new),
                 consumer_node_multiaddr,
                 access_control,
+                audit_log: KafkaAuditLog::from_env(),
             })),
+            metrics: KafkaMetrics::default(),
         }
     }
 
+    /// Count the evicted secure channels in the given metrics
+    pub(crate) fn with_metrics(mut self, metrics: KafkaMetrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
     pub(crate) fn into_trait(self) -> Arc<dyn KafkaSecureChannelController> {
         Arc::new(self)
     }
@@ -323,7 +488,7 @@ impl<F: RelayCreator> KafkaSecureChannelControllerImpl<F> {
 
         let encryptor_address = {
             if let Some(encryptor_address) = inner.topic_encryptor_map.get(&topic_partition_key) {
-                encryptor_address.clone()
+                encryptor_address
             } else {
                 let destination = match inner.consumer_node_multiaddr.clone() {
                     ConsumerNodeAddr::Direct(destination) => {
@@ -369,6 +534,15 @@ impl<F: RelayCreator> KafkaSecureChannelControllerImpl<F> {
                     }
                 };
 
+                if let Some(evicted_address) = inner.topic_encryptor_map.evict() {
+                    debug!("closing the least recently used secure channel {evicted_address}");
+                    self.metrics.record_secure_channel_eviction();
+                    if let Err(e) =
+                        Self::request_secure_channel_deletion(context, &evicted_address).await
+                    {
+                        warn!("cannot close the secure channel {evicted_address}: {e}");
+                    }
+                }
                 inner
                     .topic_encryptor_map
                     .insert(topic_partition_key, producer_encryptor_address.clone());
@@ -424,6 +598,76 @@ impl<F: RelayCreator> KafkaSecureChannelControllerImpl<F> {
         }
     }
 
+    /// Record which identity, with which attributes, is at the other end of the secure
+    /// channel used to encrypt or decrypt a message.
+    /// The audit is best-effort and never fails the encryption or the decryption
+    async fn audit(
+        &self,
+        operation: KafkaAuditOperation,
+        topic_name: &str,
+        partition_id: i32,
+        secure_channel_entry: &SecureChannelRegistryEntry,
+    ) {
+        let (repository, audit_log) = {
+            let inner = self.inner.lock().await;
+            (
+                inner.secure_channels.identities().repository(),
+                inner.audit_log.clone(),
+            )
+        };
+        if !audit_log.is_enabled() {
+            return;
+        }
+        let peer = secure_channel_entry.their_id().clone();
+        let attributes = match repository.get_attributes(&peer).await {
+            Ok(attributes) => attributes,
+            Err(e) => {
+                debug!("cannot get the attributes of {peer} for the kafka audit log: {e}");
+                None
+            }
+        };
+        audit_log.record(KafkaAuditRecord::new(
+            operation,
+            topic_name,
+            partition_id,
+            peer,
+            attributes,
+        ));
+    }
+
+    /// Mark the secure channel used to decrypt a message as recently used, and close the
+    /// least recently used channel if there are too many channels
+    async fn track_consumer_secure_channel(
+        &self,
+        context: &Context,
+        secure_channel_entry: &SecureChannelRegistryEntry,
+    ) {
+        let decryptor_address = secure_channel_entry.decryptor_messaging_address();
+        let evicted_address = {
+            let mut inner = self.inner.lock().await;
+            if inner
+                .consumer_encryptor_map
+                .get(decryptor_address)
+                .is_some()
+            {
+                return;
+            }
+            let evicted_address = inner.consumer_encryptor_map.evict();
+            inner.consumer_encryptor_map.insert(
+                decryptor_address.clone(),
+                secure_channel_entry.encryptor_messaging_address().clone(),
+            );
+            evicted_address
+        };
+        if let Some(evicted_address) = evicted_address {
+            debug!("closing the least recently used secure channel {evicted_address}");
+            self.metrics.record_secure_channel_eviction();
+            if let Err(e) = Self::request_secure_channel_deletion(context, &evicted_address).await {
+                warn!("cannot close the secure channel {evicted_address}: {e}");
+            }
+        }
+    }
+
     ///return decryptor api address
     async fn get_secure_channel_for(
         &self,
@@ -494,6 +738,13 @@ impl<F: RelayCreator> KafkaSecureChannelController for KafkaSecureChannelControl
         };
 
         trace!("encrypted content with {consumer_decryptor_address}");
+        self.audit(
+            KafkaAuditOperation::Encrypt,
+            topic_name,
+            partition_id,
+            &secure_channel_entry,
+        )
+        .await;
         Ok(KafkaEncryptedContent {
             content: encrypted_content,
             consumer_decryptor_address,
@@ -503,12 +754,16 @@ impl<F: RelayCreator> KafkaSecureChannelController for KafkaSecureChannelControl
     async fn decrypt_content_for(
         &self,
         context: &mut Context,
+        topic_name: &str,
+        partition_id: i32,
         consumer_decryptor_address: &Address,
         encrypted_content: Vec<u8>,
     ) -> Result<Vec<u8>> {
         let secure_channel_entry = self
             .get_secure_channel_for(consumer_decryptor_address)
             .await?;
+        self.track_consumer_secure_channel(context, &secure_channel_entry)
+            .await;
 
         let decrypt_response = context
             .send_and_receive(
@@ -524,6 +779,13 @@ impl<F: RelayCreator> KafkaSecureChannelController for KafkaSecureChannelControl
                 return Err(cause);
             }
         };
+        self.audit(
+            KafkaAuditOperation::Decrypt,
+            topic_name,
+            partition_id,
+            &secure_channel_entry,
+        )
+        .await;
 
         Ok(decrypted_content)
     }
@@ -534,26 +796,230 @@ impl<F: RelayCreator> KafkaSecureChannelController for KafkaSecureChannelControl
         topic_name: &str,
         partitions: Vec<i32>,
     ) -> Result<()> {
-        let mut inner = self.inner.lock().await;
-        // when using direct mode there is no need to create a relay
-        if inner.relay_creator.is_none() {
+        // the lock is only held to select the missing relays, so that the encryption and
+        // the decryption of the messages are not blocked while the relays are created.
+        // The selected relays are reserved to be created only once
+        let (relay_creator, mut reservations) = {
+            let inner = self.inner.lock().await;
+            // when using direct mode there is no need to create a relay
+            let relay_creator = match &inner.relay_creator {
+                Some(relay_creator) => relay_creator.clone(),
+                None => return Ok(()),
+            };
+            let keys = partitions
+                .into_iter()
+                .map(|partition| (topic_name.to_string(), partition));
+            (relay_creator, inner.topic_relay_set.reserve(keys))
+        };
+        let missing = reservations.keys();
+        if missing.is_empty() {
             return Ok(());
         }
 
-        for partition in partitions {
-            let topic_key: TopicPartition = (topic_name.to_string(), partition);
-            if inner.topic_relay_set.contains(&topic_key) {
-                continue;
+        let context = Arc::new(
+            context
+                .new_detached(
+                    Address::random_tagged("KafkaRelayCreator"),
+                    DenyAll,
+                    AllowAll,
+                )
+                .await?,
+        );
+        let mut missing = missing.into_iter();
+        let mut creations = JoinSet::new();
+        let mut result = Ok(());
+        loop {
+            while creations.len() < MAX_CONCURRENT_RELAY_CREATIONS {
+                let topic_key = match missing.next() {
+                    Some(topic_key) => topic_key,
+                    None => break,
+                };
+                let relay_creator = relay_creator.clone();
+                let context = context.clone();
+                creations.spawn(async move {
+                    let alias = format!("{}_{}", topic_key.0, topic_key.1);
+                    let created = relay_creator.create_relay(&context, alias).await;
+                    (topic_key, created)
+                });
+            }
+            let created = match creations.join_next().await {
+                Some(Ok((topic_key, Ok(())))) => {
+                    reservations.created(&topic_key);
+                    Ok(())
+                }
+                Some(Ok((_, Err(e)))) => Err(e),
+                Some(Err(e)) => Err(Error::new(Origin::Transport, Kind::Internal, e)),
+                None => break,
+            };
+            if result.is_ok() {
+                result = created;
             }
-            let alias = format!("{topic_name}_{partition}");
-            inner
-                .relay_creator
-                .as_ref()
-                .unwrap()
-                .create_relay(context, alias)
-                .await?;
-            inner.topic_relay_set.insert(topic_key);
         }
-        Ok(())
+
+        // the relays which could not be created are released by the reservations, to be
+        // created again on the next request
+        result
+    }
+
+    async fn rekey(&self, context: &Context) -> Result<usize> {
+        let (replaced, closed) = {
+            let mut inner = self.inner.lock().await;
+            let encryptors = inner.topic_encryptor_map.drain();
+            let replaced = encryptors.len();
+            (
+                replaced,
+                core::mem::replace(&mut inner.rekeyed_encryptors, encryptors),
+            )
+        };
+        for encryptor_address in closed.iter() {
+            if let Err(e) = Self::request_secure_channel_deletion(context, encryptor_address).await
+            {
+                warn!("cannot close the secure channel {encryptor_address}: {e}");
+            }
+        }
+        debug!(
+            "replaced {replaced} secure channels to rekey, closed {} channels replaced previously",
+            closed.len()
+        );
+        Ok(replaced)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam::identity::secure_channels;
+    use std::time::{Duration, Instant};
+
+    /// Relay creator taking some time to create each relay
+    struct SlowRelayCreator {
+        aliases: Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl RelayCreator for SlowRelayCreator {
+        async fn create_relay(&self, _context: &Context, alias: String) -> Result<()> {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            self.aliases.lock().unwrap().push(alias);
+            Ok(())
+        }
+    }
+
+    /// Relay creator failing, then panicking, the first time it creates some relays
+    struct FlakyRelayCreator {
+        aliases: Arc<std::sync::Mutex<Vec<String>>>,
+        failed: std::sync::Mutex<HashSet<String>>,
+    }
+
+    #[async_trait]
+    impl RelayCreator for FlakyRelayCreator {
+        async fn create_relay(&self, _context: &Context, alias: String) -> Result<()> {
+            if self.failed.lock().unwrap().insert(alias.clone()) {
+                if alias == "topic_1" {
+                    return Err(Error::new(Origin::Transport, Kind::Io, "cannot connect"));
+                }
+                if alias == "topic_2" {
+                    panic!("cannot create the relay {alias}");
+                }
+            }
+            self.aliases.lock().unwrap().push(alias);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_topic_encryptor_map_evicts_the_least_recently_used_channel() {
+        let key = |topic: &str| (topic.to_string(), 0);
+        let mut map = EncryptorMap::new(NonZeroUsize::new(2));
+        map.insert(key("a"), "encryptor_a".into());
+        map.insert(key("b"), "encryptor_b".into());
+        assert_eq!(map.get(&key("a")), Some("encryptor_a".into()));
+
+        assert_eq!(map.evict(), Some("encryptor_b".into()));
+        map.insert(key("c"), "encryptor_c".into());
+        assert_eq!(map.get(&key("b")), None);
+        assert_eq!(map.evict(), Some("encryptor_a".into()));
+
+        let mut unbounded = EncryptorMap::new(None);
+        unbounded.insert(key("a"), "encryptor_a".into());
+        assert_eq!(unbounded.evict(), None);
+    }
+
+    #[test]
+    fn test_topic_relay_set_forgets_the_least_recently_requested_relays() {
+        let key = |topic: &str| (topic.to_string(), 0);
+        let relays = TopicRelaySet::new(NonZeroUsize::new(2));
+        let mut reservations = relays.reserve([key("a"), key("b")]);
+        reservations.created(&key("a"));
+        reservations.created(&key("b"));
+        drop(reservations);
+
+        // "a" is requested again, then "c" replaces "b", which must be created again
+        assert!(relays.reserve([key("a")]).keys().is_empty());
+        let mut reservations = relays.reserve([key("c")]);
+        reservations.created(&key("c"));
+        drop(reservations);
+        assert_eq!(relays.reserve([key("a"), key("b")]).keys(), vec![key("b")]);
+    }
+
+    #[ockam_macros::test]
+    async fn test_relays_are_created_concurrently(context: &mut Context) -> Result<()> {
+        let aliases = Arc::new(std::sync::Mutex::new(vec![]));
+        let controller = KafkaSecureChannelControllerImpl::new_extended(
+            secure_channels(),
+            ConsumerNodeAddr::Relay(MultiAddr::default()),
+            Some(SlowRelayCreator {
+                aliases: aliases.clone(),
+            }),
+            "test_trust_context_id".to_string(),
+        );
+
+        let started_at = Instant::now();
+        controller
+            .start_relays_for(context, "topic", (0..32).collect())
+            .await?;
+        // created one after the other, the relays would take 3.2 seconds
+        assert!(started_at.elapsed() < Duration::from_secs(2));
+        assert_eq!(aliases.lock().unwrap().len(), 32);
+
+        // the relays are only created once
+        controller
+            .start_relays_for(context, "topic", vec![0, 32])
+            .await?;
+        assert_eq!(aliases.lock().unwrap().len(), 33);
+        assert!(aliases.lock().unwrap().contains(&"topic_32".to_string()));
+
+        context.stop().await
+    }
+
+    #[ockam_macros::test]
+    async fn test_failed_relays_are_created_again(context: &mut Context) -> Result<()> {
+        let aliases = Arc::new(std::sync::Mutex::new(vec![]));
+        let controller = KafkaSecureChannelControllerImpl::new_extended(
+            secure_channels(),
+            ConsumerNodeAddr::Relay(MultiAddr::default()),
+            Some(FlakyRelayCreator {
+                aliases: aliases.clone(),
+                failed: Default::default(),
+            }),
+            "test_trust_context_id".to_string(),
+        );
+
+        // the relay of the partition 1 fails, the one of the partition 2 panics
+        assert!(controller
+            .start_relays_for(context, "topic", vec![0, 1, 2])
+            .await
+            .is_err());
+        assert_eq!(*aliases.lock().unwrap(), vec!["topic_0".to_string()]);
+
+        // they are not reserved anymore
+        controller
+            .start_relays_for(context, "topic", vec![0, 1, 2])
+            .await?;
+        let mut created = aliases.lock().unwrap().clone();
+        created.sort();
+        assert_eq!(created, vec!["topic_0", "topic_1", "topic_2"]);
+
+        context.stop().await
     }
 }
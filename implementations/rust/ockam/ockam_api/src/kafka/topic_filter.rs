@@ -0,0 +1,139 @@
+/// Glob patterns matching kafka topic names.
+/// `*` matches any sequence of characters and `?` matches exactly one character
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TopicFilter {
+    patterns: Vec<String>,
+}
+
+impl TopicFilter {
+    pub fn new(patterns: Vec<String>) -> Self {
+        Self { patterns }
+    }
+
+    /// Return true if the topic matches at least one pattern
+    pub fn matches(&self, topic: &str) -> bool {
+        self.patterns
+            .iter()
+            .any(|pattern| glob_matches(pattern.as_bytes(), topic.as_bytes()))
+    }
+}
+
+/// Filters applied by a kafka consumer or producer service to the topics of the records
+/// it intercepts.
+///
+/// By default all the topics are allowed and have their records encrypted
+#[derive(Debug, Clone, Default)]
+pub struct TopicFilters {
+    allowed: Option<TopicFilter>,
+    encrypted: Option<TopicFilter>,
+    cleartext: Option<TopicFilter>,
+}
+
+impl TopicFilters {
+    /// Reject the topics which don't match these patterns
+    pub fn with_allowed_topics(mut self, patterns: Vec<String>) -> Self {
+        self.allowed = Some(TopicFilter::new(patterns));
+        self
+    }
+
+    /// Only encrypt the records of the topics matching these patterns,
+    /// the records of the other topics are passed through
+    pub fn with_encrypted_topics(mut self, patterns: Vec<String>) -> Self {
+        self.encrypted = Some(TopicFilter::new(patterns));
+        self
+    }
+
+    /// Pass through the records of the topics matching these patterns,
+    /// even if they also match an encrypted topic pattern
+    pub fn with_cleartext_topics(mut self, patterns: Vec<String>) -> Self {
+        self.cleartext = Some(TopicFilter::new(patterns));
+        self
+    }
+
+    /// Return true if records can be produced to or fetched from a topic
+    pub fn is_allowed(&self, topic: &str) -> bool {
+        self.allowed
+            .as_ref()
+            .map_or(true, |filter| filter.matches(topic))
+    }
+
+    /// Return true if the records of a topic are encrypted end-to-end
+    pub fn is_encrypted(&self, topic: &str) -> bool {
+        let is_cleartext = self
+            .cleartext
+            .as_ref()
+            .map_or(false, |filter| filter.matches(topic));
+        let is_encrypted = self
+            .encrypted
+            .as_ref()
+            .map_or(true, |filter| filter.matches(topic));
+        is_encrypted && !is_cleartext
+    }
+}
+
+fn glob_matches(pattern: &[u8], topic: &[u8]) -> bool {
+    // iterative matching, backtracking to the last `*` on a mismatch
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < topic.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(c) if *c == b'?' || *c == topic[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(patterns: &[&str]) -> Vec<String> {
+        patterns.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        let topics = TopicFilter::new(filter(&["orders", "payments.*", "audit-??", "*.pii.*"]));
+        assert!(topics.matches("orders"));
+        assert!(topics.matches("payments.eu"));
+        assert!(topics.matches("payments."));
+        assert!(topics.matches("audit-01"));
+        assert!(topics.matches("customers.pii.eu"));
+        assert!(!topics.matches("orders.eu"));
+        assert!(!topics.matches("audit-001"));
+        assert!(!topics.matches("customers.pii"));
+        assert!(!TopicFilter::default().matches("orders"));
+    }
+
+    #[test]
+    fn test_topic_filters() {
+        let filters = TopicFilters::default();
+        assert!(filters.is_allowed("orders"));
+        assert!(filters.is_encrypted("orders"));
+
+        let filters = TopicFilters::default()
+            .with_allowed_topics(filter(&["orders.*", "metrics.*"]))
+            .with_encrypted_topics(filter(&["orders.*"]))
+            .with_cleartext_topics(filter(&["orders.public"]));
+        assert!(filters.is_allowed("metrics.cpu"));
+        assert!(!filters.is_allowed("payments"));
+        assert!(filters.is_encrypted("orders.eu"));
+        assert!(!filters.is_encrypted("orders.public"));
+        assert!(!filters.is_encrypted("metrics.cpu"));
+    }
+}
@@ -0,0 +1,247 @@
+//! Port knocking with identities: a TCP listener stays closed to a peer until the peer
+//! sends a knock signed by an identity holding a given attribute.
+//!
+//! The knock is a single UDP datagram, sent to the port of the listener, containing the
+//! change history of the identity and a signature of a fresh timestamp, a nonce and the
+//! address of the listener, so that a knock can't be replayed against another listener.
+//! The attributes of the identity are read from the attributes repository of the
+//! listening node, for example the attributes of its pre-trusted identities.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use minicbor::{Decode, Encode};
+use ockam::identity::utils::now;
+use ockam::identity::{
+    Identifier, Identities, Identity, IdentityAttributesReader, TimestampInSeconds,
+};
+use ockam_core::{async_trait, Result};
+use ockam_transport_tcp::KnockVerifier;
+use ockam_vault::Signature;
+use tracing::debug;
+
+use crate::error::ApiError;
+
+/// Maximum age of a knock, which also bounds the drift between the clocks of the peers
+const MAX_KNOCK_AGE: TimestampInSeconds = TimestampInSeconds(30);
+
+/// A knock sent by an identity to open a TCP listener
+#[derive(Debug, Clone, Encode, Decode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct Knock {
+    /// Exported change history of the identity
+    #[cbor(with = "minicbor::bytes")]
+    #[n(1)] identity: Vec<u8>,
+    /// CBOR serialized [`KnockData`]
+    #[cbor(with = "minicbor::bytes")]
+    #[n(2)] data: Vec<u8>,
+    /// Signature of the data with the primary key of the identity
+    #[n(3)] signature: Signature,
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+#[rustfmt::skip]
+#[cbor(map)]
+struct KnockData {
+    #[n(1)] created_at: TimestampInSeconds,
+    #[n(2)] nonce: u64,
+    /// Address of the listener the knock is sent to
+    #[n(3)] listener: String,
+}
+
+impl Knock {
+    /// Create a knock for the listener at `listener`, signed by an identity whose secret key
+    /// must be in the vault of `identities`
+    pub async fn create(
+        identities: &Identities,
+        identifier: &Identifier,
+        listener: SocketAddr,
+    ) -> Result<Vec<u8>> {
+        let identity = identities.get_identity(identifier).await?;
+        let secret_key = identities
+            .identities_keys()
+            .get_secret_key(&identity)
+            .await?;
+        let data = minicbor::to_vec(KnockData {
+            created_at: now()?,
+            nonce: rand::random(),
+            listener: listener.to_string(),
+        })?;
+        let identities_creation = identities.identities_creation();
+        let hash = identities_creation.verifying_vault().sha256(&data).await?;
+        let signature = identities_creation
+            .identity_vault()
+            .sign(&secret_key, &hash.0)
+            .await?;
+        Ok(minicbor::to_vec(Knock {
+            identity: identity.export()?,
+            data,
+            signature,
+        })?)
+    }
+}
+
+/// Verify that knocks are signed by identities holding a required attribute.
+///
+/// Each knock can only be used once, and must have been created recently
+pub struct IdentityKnockVerifier {
+    identities: Arc<Identities>,
+    attributes_reader: Arc<dyn IdentityAttributesReader>,
+    attribute: (String, String),
+    used_nonces: Mutex<HashMap<u64, TimestampInSeconds>>,
+}
+
+impl IdentityKnockVerifier {
+    /// Accept the knocks of the identities having the attribute `key=value`
+    pub fn new(
+        identities: Arc<Identities>,
+        attributes_reader: Arc<dyn IdentityAttributesReader>,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        Self {
+            identities,
+            attributes_reader,
+            attribute: (key.into(), value.into()),
+            used_nonces: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Verify the freshness, the target and the signature of a knock, and return its identity.
+    /// The cheap checks are done first, before importing the identity of the knock
+    async fn verify_knock(&self, listener: SocketAddr, knock: &[u8]) -> Result<Identifier> {
+        let knock: Knock = minicbor::decode(knock)?;
+        let data: KnockData = minicbor::decode(&knock.data)?;
+        let now = now()?;
+        if data.created_at + MAX_KNOCK_AGE < now || now + MAX_KNOCK_AGE < data.created_at {
+            return Err(ApiError::core("the knock is expired"));
+        }
+        if !is_knock_target(listener, &data.listener) {
+            return Err(ApiError::core("the knock was sent to another listener"));
+        }
+
+        let verifying_vault = self.identities.identities_creation().verifying_vault();
+        let identity = Identity::import_with_limits(
+            None,
+            &knock.identity,
+            verifying_vault.clone(),
+            &self.identities.change_history_limits(),
+        )
+        .await?;
+
+        let hash = verifying_vault.sha256(&knock.data).await?;
+        if !verifying_vault
+            .verify_signature(
+                &identity.get_latest_public_key()?,
+                &hash.0,
+                &knock.signature,
+            )
+            .await?
+        {
+            return Err(ApiError::core("the signature of the knock is invalid"));
+        }
+
+        let mut used_nonces = self.used_nonces.lock().unwrap();
+        used_nonces.retain(|_, created_at| *created_at + MAX_KNOCK_AGE >= now);
+        if used_nonces.insert(data.nonce, data.created_at).is_some() {
+            return Err(ApiError::core("the knock was already used"));
+        }
+        Ok(identity.identifier().clone())
+    }
+
+    /// Return true if an identity currently holds the required attribute
+    async fn has_attribute(&self, identifier: &Identifier) -> Result<bool> {
+        let (key, value) = &self.attribute;
+        let entry = match self.attributes_reader.get_attributes(identifier).await? {
+            Some(entry) => entry,
+            None => return Ok(false),
+        };
+        Ok(
+            entry.attrs().get(key.as_bytes()) == Some(&value.as_bytes().to_vec())
+                && entry.is_active(key.as_bytes(), now()?),
+        )
+    }
+}
+
+#[async_trait]
+impl KnockVerifier for IdentityKnockVerifier {
+    async fn verify(&self, listener: SocketAddr, peer: SocketAddr, knock: &[u8]) -> Result<bool> {
+        let identifier = self.verify_knock(listener, knock).await?;
+        let has_attribute = self.has_attribute(&identifier).await?;
+        if !has_attribute {
+            debug!(%peer, %identifier, "the identity of the knock does not have the required attribute");
+        }
+        Ok(has_attribute)
+    }
+}
+
+/// Return true if a knock for the address `target` was sent to the listener bound to `listener`.
+/// The ports must be the same, and the IP addresses too unless the listener is bound to
+/// all the interfaces of its host
+fn is_knock_target(listener: SocketAddr, target: &str) -> bool {
+    let target: SocketAddr = match target.parse() {
+        Ok(target) => target,
+        Err(_) => return false,
+    };
+    listener.port() == target.port()
+        && (listener.ip().is_unspecified() || listener.ip() == target.ip())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam::identity::{identities, AttributesEntry, IdentityAttributesWriter};
+    use std::collections::BTreeMap;
+
+    #[tokio::test]
+    async fn test_identity_knock() -> Result<()> {
+        let identities = identities();
+        let repository = identities.repository();
+        let admin = identities.identities_creation().create_identity().await?;
+        let other = identities.identities_creation().create_identity().await?;
+        repository
+            .put_attributes(
+                admin.identifier(),
+                AttributesEntry::new(
+                    BTreeMap::from([(b"role".to_vec(), b"admin".to_vec())]),
+                    now()?,
+                    None,
+                    None,
+                ),
+            )
+            .await?;
+
+        let verifier = IdentityKnockVerifier::new(
+            identities.clone(),
+            repository.as_attributes_reader(),
+            "role",
+            "admin",
+        );
+        let peer: SocketAddr = "10.0.0.7:4000".parse().unwrap();
+        let listener: SocketAddr = "10.0.0.1:5000".parse().unwrap();
+
+        let knock = Knock::create(&identities, admin.identifier(), listener).await?;
+        assert!(verifier.verify(listener, peer, &knock).await?);
+        // a knock can't be replayed
+        assert!(verifier.verify(listener, peer, &knock).await.is_err());
+
+        // a knock can't be used on another listener
+        let knock = Knock::create(&identities, admin.identifier(), listener).await?;
+        let other_listener: SocketAddr = "10.0.0.1:5001".parse().unwrap();
+        assert!(verifier.verify(other_listener, peer, &knock).await.is_err());
+        // a listener bound to all the interfaces accepts the knocks sent to its port
+        let any_listener: SocketAddr = "0.0.0.0:5000".parse().unwrap();
+        assert!(verifier.verify(any_listener, peer, &knock).await?);
+
+        let knock = Knock::create(&identities, other.identifier(), listener).await?;
+        assert!(!verifier.verify(listener, peer, &knock).await?);
+
+        assert!(verifier
+            .verify(listener, peer, b"not a knock")
+            .await
+            .is_err());
+        Ok(())
+    }
+}
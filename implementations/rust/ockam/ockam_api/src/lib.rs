@@ -126,25 +126,34 @@
 pub mod address;
 pub mod auth;
 pub mod authenticator;
+#[cfg(feature = "benchmarks")]
+pub mod benchmarks;
 pub mod bootstrapped_identities_store;
 pub mod cli_state;
 pub mod cloud;
 pub mod config;
+pub mod discard;
 pub mod echoer;
 pub mod enroll;
 pub mod error;
+pub mod file_transfer;
 pub mod hop;
 pub mod identity;
 pub mod kafka;
+pub mod knock;
 pub mod minicbor_url;
 pub mod nodes;
 pub mod okta;
 pub mod port_range;
+pub mod resource_list;
+pub mod tracer;
 pub mod trust_context;
 pub mod uppercase;
 
 pub mod authority_node;
 mod influxdb_token_lease;
+pub mod relay_node;
+pub mod self_hosted;
 
 mod schema;
 mod session;
@@ -163,7 +172,11 @@ impl DefaultAddress {
     pub const RELAY_SERVICE: &'static str = "forwarding_service";
     pub const UPPERCASE_SERVICE: &'static str = "uppercase";
     pub const ECHO_SERVICE: &'static str = "echo";
+    pub const DISCARD_SERVICE: &'static str = "discard";
+    pub const FILE_RECEIVER_SERVICE: &'static str = "file_receiver";
     pub const HOP_SERVICE: &'static str = "hop";
+    pub const TRACER_SERVICE: &'static str = "tracer";
+    pub const HOLE_PUNCH_SERVICE: &'static str = "hole_punch";
     pub const CREDENTIALS_SERVICE: &'static str = "credentials";
     pub const SECURE_CHANNEL_LISTENER: &'static str = "api";
     pub const DIRECT_AUTHENTICATOR: &'static str = "direct_authenticator";
@@ -175,6 +188,7 @@ impl DefaultAddress {
     pub const KAFKA_CONSUMER: &'static str = "kafka_consumer";
     pub const KAFKA_PRODUCER: &'static str = "kafka_producer";
     pub const KAFKA_DIRECT: &'static str = "kafka_direct";
+    pub const INFLUXDB_TOKEN_LEASE: &'static str = "influxdb_token_lease";
 
     pub fn is_valid(name: &str) -> bool {
         matches!(
@@ -183,7 +197,11 @@ impl DefaultAddress {
                 | Self::RELAY_SERVICE
                 | Self::UPPERCASE_SERVICE
                 | Self::ECHO_SERVICE
+                | Self::DISCARD_SERVICE
+                | Self::FILE_RECEIVER_SERVICE
                 | Self::HOP_SERVICE
+                | Self::TRACER_SERVICE
+                | Self::HOLE_PUNCH_SERVICE
                 | Self::CREDENTIALS_SERVICE
                 | Self::SECURE_CHANNEL_LISTENER
                 | Self::DIRECT_AUTHENTICATOR
@@ -195,6 +213,7 @@ impl DefaultAddress {
                 | Self::KAFKA_PRODUCER
                 | Self::KAFKA_OUTLET
                 | Self::KAFKA_DIRECT
+                | Self::INFLUXDB_TOKEN_LEASE
         )
     }
 
@@ -204,7 +223,11 @@ impl DefaultAddress {
             Self::RELAY_SERVICE,
             Self::UPPERCASE_SERVICE,
             Self::ECHO_SERVICE,
+            Self::DISCARD_SERVICE,
+            Self::FILE_RECEIVER_SERVICE,
             Self::HOP_SERVICE,
+            Self::TRACER_SERVICE,
+            Self::HOLE_PUNCH_SERVICE,
             Self::CREDENTIALS_SERVICE,
             Self::SECURE_CHANNEL_LISTENER,
             Self::DIRECT_AUTHENTICATOR,
@@ -216,6 +239,7 @@ impl DefaultAddress {
             Self::KAFKA_PRODUCER,
             Self::KAFKA_OUTLET,
             Self::KAFKA_DIRECT,
+            Self::INFLUXDB_TOKEN_LEASE,
         ]
         .iter()
         .copied()
@@ -303,7 +327,13 @@ mod test {
         assert!(DefaultAddress::is_valid(DefaultAddress::RELAY_SERVICE));
         assert!(DefaultAddress::is_valid(DefaultAddress::UPPERCASE_SERVICE));
         assert!(DefaultAddress::is_valid(DefaultAddress::ECHO_SERVICE));
+        assert!(DefaultAddress::is_valid(DefaultAddress::DISCARD_SERVICE));
+        assert!(DefaultAddress::is_valid(
+            DefaultAddress::FILE_RECEIVER_SERVICE
+        ));
         assert!(DefaultAddress::is_valid(DefaultAddress::HOP_SERVICE));
+        assert!(DefaultAddress::is_valid(DefaultAddress::TRACER_SERVICE));
+        assert!(DefaultAddress::is_valid(DefaultAddress::HOLE_PUNCH_SERVICE));
         assert!(DefaultAddress::is_valid(
             DefaultAddress::CREDENTIALS_SERVICE
         ));
@@ -1,6 +1,6 @@
 use crate::error::ApiError;
 use crate::nodes::connection::{Changes, ConnectionBuilder, Instantiator};
-use crate::{multiaddr_to_route, route_to_multiaddr};
+use crate::{multiaddr_to_route_with_sharing, route_to_multiaddr};
 use std::sync::Arc;
 
 use crate::nodes::NodeManager;
@@ -37,13 +37,17 @@ impl Instantiator for PlainTcpInstantiator {
     ) -> Result<Changes, Error> {
         let (before, tcp_piece, after) = extracted;
 
-        let mut tcp = multiaddr_to_route(&tcp_piece, &node_manager.tcp_transport)
-            .await
-            .ok_or_else(|| {
-                ApiError::core(format!(
-                    "Couldn't convert MultiAddr to route: tcp_piece={tcp_piece}"
-                ))
-            })?;
+        let mut tcp = multiaddr_to_route_with_sharing(
+            &tcp_piece,
+            &node_manager.tcp_transport,
+            node_manager.share_tcp_connections,
+        )
+        .await
+        .ok_or_else(|| {
+            ApiError::core(format!(
+                "Couldn't convert MultiAddr to route: tcp_piece={tcp_piece}"
+            ))
+        })?;
 
         let multiaddr = route_to_multiaddr(&tcp.route).ok_or_else(|| {
             ApiError::core(format!(
@@ -1,7 +1,7 @@
 use crate::error::ApiError;
 use crate::nodes::connection::{Changes, Instantiator};
 use crate::nodes::NodeManager;
-use crate::{multiaddr_to_route, try_address_to_multiaddr};
+use crate::{multiaddr_to_route_with_sharing, try_address_to_multiaddr};
 use std::sync::Arc;
 
 use ockam_core::{async_trait, Error, Route};
@@ -61,13 +61,17 @@ impl Instantiator for ProjectInstantiator {
             node_manager.resolve_project(&project).await?;
 
         debug!(addr = %project_multiaddr, "creating secure channel");
-        let tcp = multiaddr_to_route(&project_multiaddr, &node_manager.tcp_transport)
-            .await
-            .ok_or_else(|| {
-                ApiError::core(format!(
-                    "Couldn't convert MultiAddr to route: project_multiaddr={project_multiaddr}"
-                ))
-            })?;
+        let tcp = multiaddr_to_route_with_sharing(
+            &project_multiaddr,
+            &node_manager.tcp_transport,
+            node_manager.share_tcp_connections,
+        )
+        .await
+        .ok_or_else(|| {
+            ApiError::core(format!(
+                "Couldn't convert MultiAddr to route: project_multiaddr={project_multiaddr}"
+            ))
+        })?;
 
         debug!("create a secure channel to the project {project_identifier}");
         let sc = node_manager
@@ -78,6 +82,8 @@ impl Instantiator for ProjectInstantiator {
                 Some(vec![project_identifier]),
                 self.timeout,
                 self.credential.clone(),
+                node_manager.secure_channel_compression,
+                None,
             )
             .await?;
 
@@ -64,6 +64,8 @@ impl Instantiator for SecureChannelInstantiator {
                 self.authorized_identities.clone(),
                 self.timeout,
                 self.credential.clone(),
+                node_manager.secure_channel_compression,
+                None,
             )
             .await?;
 
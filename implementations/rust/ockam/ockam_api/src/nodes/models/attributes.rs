@@ -0,0 +1,33 @@
+//! Attributes request/response types
+
+use minicbor::{Decode, Encode};
+use ockam::identity::Identifier;
+use ockam_multiaddr::MultiAddr;
+
+/// Request body to fetch the attributes of an identity from another node
+#[derive(Debug, Clone, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct FetchAttributes {
+    /// Address of the authenticated service of the other node
+    #[n(1)] pub addr: MultiAddr,
+    /// Identity whose attributes are fetched
+    #[n(2)] pub identifier: Identifier,
+    /// Maximum time to wait for the other node, in seconds
+    #[n(3)] pub timeout: Option<u64>,
+}
+
+impl FetchAttributes {
+    pub fn new(addr: MultiAddr, identifier: Identifier) -> Self {
+        Self {
+            addr,
+            identifier,
+            timeout: None,
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Option<u64>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
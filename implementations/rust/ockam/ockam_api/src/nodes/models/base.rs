@@ -1,11 +1,12 @@
 //! Nodemanager API types
 
 use minicbor::{Decode, Encode};
+use serde::Serialize;
 
 ///////////////////-!  RESPONSE BODIES
 
 /// Response body for a node status
-#[derive(Debug, Clone, Decode, Encode)]
+#[derive(Debug, Clone, Decode, Encode, Serialize)]
 #[rustfmt::skip]
 #[cbor(map)]
 pub struct NodeStatus {
@@ -13,6 +14,12 @@ pub struct NodeStatus {
     #[n(2)] pub status: String,
     #[n(3)] pub workers: u32,
     #[n(4)] pub pid: i32,
+    /// Readiness of the services started by the node manager, missing for older nodes
+    #[n(5)] pub services: Option<Vec<ServiceReadinessStatus>>,
+    /// Progress of the pre-warm of the node, missing for older nodes
+    #[n(6)] pub pre_warm: Option<Vec<PreWarmStepStatus>>,
+    /// Health of the databases of the node, missing if the node doesn't check them
+    #[n(7)] pub storage: Option<Vec<StorageHealthStatus>>,
 }
 
 impl NodeStatus {
@@ -27,6 +34,115 @@ impl NodeStatus {
             status: status.into(),
             workers,
             pid,
+            services: None,
+            pre_warm: None,
+            storage: None,
         }
     }
+
+    pub fn with_services(mut self, services: Vec<ServiceReadinessStatus>) -> Self {
+        self.services = Some(services);
+        self
+    }
+
+    /// Return true if some services of the node are still starting
+    pub fn is_starting(&self) -> bool {
+        self.services
+            .iter()
+            .flatten()
+            .any(|s| s.readiness == STARTING)
+    }
+
+    pub fn with_pre_warm(mut self, pre_warm: Vec<PreWarmStepStatus>) -> Self {
+        self.pre_warm = Some(pre_warm);
+        self
+    }
+
+    pub fn with_storage(mut self, storage: Vec<StorageHealthStatus>) -> Self {
+        self.storage = Some(storage);
+        self
+    }
+}
+
+/// Readiness of a service which is still starting
+pub const STARTING: &str = "starting";
+
+/// Readiness of a service started by a node manager
+#[derive(Debug, Clone, Decode, Encode, Serialize)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct ServiceReadinessStatus {
+    #[n(1)] pub service: String,
+    /// `starting`, `ready` or the reason why the service failed to start
+    #[n(2)] pub readiness: String,
+}
+
+impl ServiceReadinessStatus {
+    pub fn new(service: impl Into<String>, readiness: impl Into<String>) -> Self {
+        Self {
+            service: service.into(),
+            readiness: readiness.into(),
+        }
+    }
+}
+
+/// Status of a step of the pre-warm of a node
+#[derive(Debug, Clone, Decode, Encode, Serialize)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct PreWarmStepStatus {
+    #[n(1)] pub step: String,
+    /// `starting`, `ready` or the reason why the step failed
+    #[n(2)] pub status: String,
+}
+
+impl PreWarmStepStatus {
+    pub fn new(step: impl Into<String>, status: impl Into<String>) -> Self {
+        Self {
+            step: step.into(),
+            status: status.into(),
+        }
+    }
+}
+
+/// Health of a database of a node
+#[derive(Debug, Clone, Decode, Encode, Serialize)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct StorageHealthStatus {
+    #[n(1)] pub name: String,
+    /// `healthy`, or the reason why the database can't be used
+    #[n(2)] pub health: String,
+}
+
+impl StorageHealthStatus {
+    pub fn new(name: impl Into<String>, health: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            health: health.into(),
+        }
+    }
+}
+
+/// Clock skew measured with an identity issuing credentials
+#[derive(Debug, Clone, Decode, Encode, Serialize)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct ClockSkewStatus {
+    #[n(1)] pub identifier: String,
+    /// Difference, in seconds, between the clock of the identity and the clock of the node
+    #[n(2)] pub skew: i64,
+    #[n(3)] pub measured_at: u64,
+    /// True if the skew is larger than the tolerance of the node
+    #[n(4)] pub exceeds_tolerance: bool,
+}
+
+/// Response body for listing the clock skews measured by a node
+#[derive(Debug, Clone, Decode, Encode, Serialize)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct ClockSkewList {
+    /// Tolerance, in seconds, applied when checking the validity period of credentials
+    #[n(1)] pub tolerance: u64,
+    #[n(2)] pub list: Vec<ClockSkewStatus>,
 }
@@ -0,0 +1,50 @@
+use minicbor::{Decode, Encode};
+
+/// Default number of lines kept from the end of each log file of a node
+pub const DEFAULT_DIAGNOSTICS_LOG_LINES: u32 = 1000;
+
+/// Request body to export the diagnostic bundle of a node
+#[derive(Debug, Clone, Default, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct GetDiagnostics {
+    /// Replace the IP addresses and host names found in the bundle
+    #[n(1)] pub redact_addresses: bool,
+}
+
+impl GetDiagnostics {
+    pub fn new(redact_addresses: bool) -> Self {
+        Self { redact_addresses }
+    }
+}
+
+/// Response body containing the diagnostic bundle of a node.
+///
+/// The bundle is a gzip-compressed JSON document gathering the status of the node, its
+/// workers, resources, routes, secure channels, statistics and version, so that it can be
+/// attached as a single file to a support request. The recent logs of the node are added
+/// to the bundle by the command line, since they can be larger than a response
+#[derive(Debug, Clone, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct DiagnosticBundle {
+    #[n(1)] pub node_name: String,
+    /// Creation time of the bundle, in seconds since the Unix epoch
+    #[n(2)] pub created_at: u64,
+    #[cbor(n(3), with = "minicbor::bytes")] pub data: Vec<u8>,
+}
+
+impl DiagnosticBundle {
+    pub fn new(node_name: impl Into<String>, created_at: u64, data: Vec<u8>) -> Self {
+        Self {
+            node_name: node_name.into(),
+            created_at,
+            data,
+        }
+    }
+
+    /// Default name of the file where the bundle is saved
+    pub fn file_name(&self) -> String {
+        format!("{}-diagnostics-{}.json.gz", self.node_name, self.created_at)
+    }
+}
@@ -0,0 +1,37 @@
+use minicbor::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+use crate::nodes::models::portal::PortalSession;
+
+/// Response body of a delete or modify request sent as a dry run.
+/// It describes what the request would change, nothing is changed on the node
+#[derive(Debug, Clone, Default, Decode, Encode, Serialize, Deserialize, PartialEq, Eq)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct DryRun {
+    /// Changes which would be applied by the request
+    #[n(1)] pub changes: Vec<String>,
+    /// Resources depending on the changed resources, which would be affected
+    #[n(2)] pub dependents: Vec<String>,
+    /// Portal sessions which would be cut, or whose messages would be checked by a new policy
+    #[n(3)] pub sessions: Vec<PortalSession>,
+}
+
+impl DryRun {
+    pub fn new(changes: Vec<String>) -> Self {
+        Self {
+            changes,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_dependents(mut self, dependents: Vec<String>) -> Self {
+        self.dependents = dependents;
+        self
+    }
+
+    pub fn with_sessions(mut self, sessions: Vec<PortalSession>) -> Self {
+        self.sessions = sessions;
+        self
+    }
+}
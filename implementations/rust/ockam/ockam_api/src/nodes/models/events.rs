@@ -0,0 +1,82 @@
+use minicbor::{Decode, Encode};
+use std::fmt::{self, Display};
+
+/// Type of the events which can be published by a node
+#[derive(Copy, Clone, Debug, Decode, Encode, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
+#[rustfmt::skip]
+#[cbor(index_only)]
+pub enum NodeEventType {
+    #[n(0)] SecureChannelOpened,
+    #[n(1)] SecureChannelClosed,
+    #[n(2)] InletConnectionAccepted,
+    #[n(3)] CredentialRefreshed,
+    #[n(4)] OffsetCommitVerificationFailed,
+    #[n(5)] AuthorizationRevoked,
+    #[n(6)] KeyUsageAlert,
+}
+
+impl Display for NodeEventType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::SecureChannelOpened => "secure channel opened",
+            Self::SecureChannelClosed => "secure channel closed",
+            Self::InletConnectionAccepted => "inlet connection accepted",
+            Self::CredentialRefreshed => "credential refreshed",
+            Self::OffsetCommitVerificationFailed => "offset commit verification failed",
+            Self::AuthorizationRevoked => "authorization revoked",
+            Self::KeyUsageAlert => "key usage alert",
+        })
+    }
+}
+
+/// Event message sent by a node to its subscribers
+#[derive(Debug, Clone, Decode, Encode, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct NodeEvent {
+    /// Identifier of the subscription which received this event
+    #[n(1)] pub subscription_id: String,
+    #[n(2)] pub event_type: NodeEventType,
+    /// Time of the event, in seconds since the Unix epoch
+    #[n(3)] pub timestamp: u64,
+    /// Main resource concerned by the event: a secure channel address, an inlet address, an identifier
+    #[n(4)] pub resource: String,
+    /// Additional information, e.g. the socket address of an inlet client
+    #[n(5)] pub detail: Option<String>,
+}
+
+/// Request body to subscribe to the events of a node
+///
+/// The events are sent back to the route used to send this request, for as long as the
+/// subscription exists
+#[derive(Debug, Clone, Default, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct SubscribeToEvents {
+    /// Only send the events of these types. All the events are sent if the list is empty
+    #[n(1)] pub event_types: Vec<NodeEventType>,
+}
+
+impl SubscribeToEvents {
+    pub fn new(event_types: Vec<NodeEventType>) -> Self {
+        Self { event_types }
+    }
+}
+
+/// Response body when subscribing to the events of a node
+#[derive(Debug, Clone, Decode, Encode, serde::Serialize, serde::Deserialize)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct EventSubscription {
+    #[n(1)] pub id: String,
+    #[n(2)] pub event_types: Vec<NodeEventType>,
+}
+
+impl EventSubscription {
+    pub fn new(id: impl Into<String>, event_types: Vec<NodeEventType>) -> Self {
+        Self {
+            id: id.into(),
+            event_types,
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use minicbor::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+use crate::resource_list::ResourceList;
+
+/// Request body to enable or disable a feature of a node
+#[derive(Debug, Clone, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct SetFeature {
+    #[n(1)] pub enabled: bool,
+}
+
+impl SetFeature {
+    pub fn new(enabled: bool) -> Self {
+        Self { enabled }
+    }
+}
+
+/// State of an experimental feature on a node
+#[derive(Debug, Clone, Decode, Encode, Serialize, Deserialize, PartialEq, Eq)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct FeatureStatus {
+    #[n(1)] pub name: String,
+    #[n(2)] pub description: String,
+    #[n(3)] pub enabled: bool,
+    /// True if the feature was enabled or disabled on this node, false if it has its default state
+    #[n(4)] pub is_set: bool,
+}
+
+impl FeatureStatus {
+    pub fn new(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        enabled: bool,
+        is_set: bool,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            enabled,
+            is_set,
+        }
+    }
+}
+
+pub type FeatureList = ResourceList<FeatureStatus>;
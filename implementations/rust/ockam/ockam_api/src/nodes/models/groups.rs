@@ -0,0 +1,35 @@
+use minicbor::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+/// A group of identities, which policies can check with `(member_of "<group>")`
+#[derive(Debug, Clone, Decode, Encode, Serialize, Deserialize, PartialEq, Eq)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct Group {
+    #[n(1)] pub name: String,
+    /// Identifiers of the members of the group
+    #[n(2)] pub members: Vec<String>,
+}
+
+impl Group {
+    pub fn new(name: impl Into<String>, members: Vec<String>) -> Self {
+        Self {
+            name: name.into(),
+            members,
+        }
+    }
+}
+
+/// Response body listing the groups of a node
+#[derive(Debug, Clone, Decode, Encode, Serialize, Deserialize)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct GroupList {
+    #[n(1)] pub list: Vec<Group>,
+}
+
+impl GroupList {
+    pub fn new(list: Vec<Group>) -> Self {
+        Self { list }
+    }
+}
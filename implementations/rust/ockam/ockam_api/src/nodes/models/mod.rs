@@ -2,13 +2,24 @@
 ///
 /// This module is only a type facade and should not have any logic of
 /// its own
+pub mod attributes;
 pub mod base;
 pub mod credentials;
+pub mod diagnostics;
+pub mod dry_run;
+pub mod events;
+pub mod features;
 pub mod flow_controls;
+pub mod groups;
+pub mod peers;
 pub mod policy;
 pub mod portal;
 pub mod relay;
+pub mod route;
+pub mod schedules;
 pub mod secure_channel;
 pub mod services;
+pub mod stats;
 pub mod transport;
+pub mod usage;
 pub mod workers;
@@ -0,0 +1,46 @@
+use minicbor::{Decode, Encode};
+
+/// Request body to list the peer identities of a node
+#[derive(Debug, Clone, Default, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct GetPeers {
+    /// Only return the peers seen at or after this time, in seconds since the Unix epoch
+    #[n(1)] pub seen_since: Option<u64>,
+}
+
+impl GetPeers {
+    pub fn new(seen_since: Option<u64>) -> Self {
+        Self { seen_since }
+    }
+}
+
+/// Secure channels established by a node with a peer identity
+#[derive(Debug, Clone, Decode, Encode, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct PeerStatus {
+    #[n(1)] pub identifier: String,
+    /// Time of the first channel, in seconds since the Unix epoch
+    #[n(2)] pub first_seen: u64,
+    /// Time of the last channel, in seconds since the Unix epoch
+    #[n(3)] pub last_seen: u64,
+    /// Number of channels initiated by the node
+    #[n(4)] pub channels_initiated: u64,
+    /// Number of channels initiated by the peer
+    #[n(5)] pub channels_accepted: u64,
+}
+
+/// Response body when listing the peer identities of a node
+#[derive(Debug, Clone, Decode, Encode, serde::Serialize, serde::Deserialize)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct PeerList {
+    #[n(1)] pub list: Vec<PeerStatus>,
+}
+
+impl PeerList {
+    pub fn new(list: Vec<PeerStatus>) -> Self {
+        Self { list }
+    }
+}
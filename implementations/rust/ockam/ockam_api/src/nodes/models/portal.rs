@@ -11,6 +11,7 @@ use ockam_multiaddr::MultiAddr;
 use serde::{Deserialize, Serialize};
 
 use crate::error::ApiError;
+use crate::resource_list::ResourceList;
 use crate::route_to_multiaddr;
 
 /// Request body to create an inlet
@@ -38,6 +39,14 @@ pub struct CreateInlet {
     #[n(6)] pub(crate) suffix_route: Route,
     /// The maximum duration to wait for an outlet to be available
     #[n(7)] pub(crate) wait_for_outlet_duration: Option<Duration>,
+    /// The `host:port` target which the outlet should connect to, instead of its own target.
+    /// It must be allowed by the outlet
+    #[n(8)] pub(crate) outlet_target: Option<String>,
+    /// A synthetic probe periodically checking the portal
+    #[n(9)] pub(crate) probe: Option<InletProbe>,
+    /// `host:port` address of a UDP Rendezvous service, used to open a direct UDP path
+    /// to the node of the outlet instead of going through a relay
+    #[n(10)] pub(crate) udp_rendezvous: Option<String>,
 }
 
 impl CreateInlet {
@@ -55,6 +64,9 @@ impl CreateInlet {
             prefix_route,
             suffix_route,
             wait_for_outlet_duration: None,
+            outlet_target: None,
+            probe: None,
+            udp_rendezvous: None,
         }
     }
 
@@ -73,6 +85,9 @@ impl CreateInlet {
             prefix_route,
             suffix_route,
             wait_for_outlet_duration: None,
+            outlet_target: None,
+            probe: None,
+            udp_rendezvous: None,
         }
     }
 
@@ -84,6 +99,18 @@ impl CreateInlet {
         self.wait_for_outlet_duration = Some(Duration::from_millis(ms))
     }
 
+    pub fn set_outlet_target(&mut self, target: impl Into<String>) {
+        self.outlet_target = Some(target.into())
+    }
+
+    pub fn set_probe(&mut self, probe: InletProbe) {
+        self.probe = Some(probe)
+    }
+
+    pub fn set_udp_rendezvous(&mut self, rendezvous_address: impl Into<String>) {
+        self.udp_rendezvous = Some(rendezvous_address.into())
+    }
+
     pub fn listen_addr(&self) -> String {
         self.listen_addr.clone()
     }
@@ -111,6 +138,146 @@ impl CreateInlet {
     pub fn wait_for_outlet_duration(&self) -> Option<Duration> {
         self.wait_for_outlet_duration
     }
+
+    pub fn outlet_target(&self) -> Option<&str> {
+        self.outlet_target.as_deref()
+    }
+
+    pub fn probe(&self) -> Option<&InletProbe> {
+        self.probe.as_ref()
+    }
+
+    pub fn udp_rendezvous(&self) -> Option<&str> {
+        self.udp_rendezvous.as_deref()
+    }
+}
+
+/// Request body to move an inlet to a new bind address.
+/// The connections accepted on the previous address are kept until they are closed
+#[derive(Clone, Debug, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct RebindInlet {
+    /// The new address the portal should listen at.
+    #[n(1)] pub(crate) listen_addr: String,
+}
+
+impl RebindInlet {
+    pub fn new(listen_addr: impl Into<String>) -> Self {
+        Self {
+            listen_addr: listen_addr.into(),
+        }
+    }
+
+    pub fn listen_addr(&self) -> &str {
+        &self.listen_addr
+    }
+}
+
+/// Request sent by the node of an inlet to the node of its outlet, to start a UDP hole
+/// puncher towards the puncher of the inlet node
+#[derive(Clone, Debug, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct HolePunchRequest {
+    /// Name of the puncher of the inlet node
+    #[n(1)] pub puncher_name: String,
+    /// Name of the puncher to start on the outlet node
+    #[n(2)] pub peer_puncher_name: String,
+    /// `host:port` address of the UDP Rendezvous service used by both punchers
+    #[n(3)] pub rendezvous_address: String,
+}
+
+impl HolePunchRequest {
+    pub fn new(
+        puncher_name: impl Into<String>,
+        peer_puncher_name: impl Into<String>,
+        rendezvous_address: impl Into<String>,
+    ) -> Self {
+        Self {
+            puncher_name: puncher_name.into(),
+            peer_puncher_name: peer_puncher_name.into(),
+            rendezvous_address: rendezvous_address.into(),
+        }
+    }
+}
+
+/// Response of the node of an outlet to a [`HolePunchRequest`]
+#[derive(Clone, Debug, Default, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct HolePunchResponse {
+    /// Reason why the puncher could not be started
+    #[n(1)] pub error: Option<String>,
+}
+
+impl HolePunchResponse {
+    pub fn error(error: impl Into<String>) -> Self {
+        Self {
+            error: Some(error.into()),
+        }
+    }
+}
+
+/// A synthetic probe periodically opening a connection to an inlet, in order to measure
+/// the latency of the whole portal path, up to the outlet target
+#[derive(Clone, Debug, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct InletProbe {
+    /// Time between two probes
+    #[n(1)] pub interval: Duration,
+    /// Maximum time to wait for the first byte sent by the target
+    #[n(2)] pub timeout: Duration,
+    /// Data sent once the connection is open, for targets waiting for the client to speak first
+    #[n(3)] pub payload: Option<String>,
+}
+
+impl InletProbe {
+    pub fn new(interval: Duration, timeout: Duration) -> Self {
+        Self {
+            interval,
+            timeout,
+            payload: None,
+        }
+    }
+
+    pub fn with_payload(mut self, payload: impl Into<String>) -> Self {
+        self.payload = Some(payload.into());
+        self
+    }
+}
+
+/// Result of an inlet probe
+#[derive(Clone, Debug, Decode, Encode, Serialize, Deserialize, PartialEq, Eq)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct ProbeResult {
+    /// Time of the probe, in seconds since the Unix epoch
+    #[n(1)] pub timestamp: u64,
+    /// Time taken by the portal handshake with the outlet, over the route of the inlet,
+    /// in milliseconds
+    #[n(2)] pub handshake_ms: Option<u64>,
+    /// Time between the connection to the inlet and the first byte received from the target,
+    /// in milliseconds
+    #[n(3)] pub first_byte_ms: Option<u64>,
+    /// Reason of the failure, when the probe failed
+    #[n(4)] pub error: Option<String>,
+}
+
+impl ProbeResult {
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Response body when returning the results of an inlet probe, the most recent last
+#[derive(Clone, Debug, Decode, Encode, Serialize, Deserialize)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct InletProbeResults {
+    #[n(1)] pub alias: String,
+    #[n(2)] pub results: Vec<ProbeResult>,
 }
 
 /// Request body to create an outlet
@@ -127,6 +294,12 @@ pub struct CreateOutlet {
     /// Allow the outlet to be reachable from the default secure channel, useful when we want to
     /// tighten the flow control
     #[n(4)] pub reachable_from_default_secure_channel: bool,
+    /// Targets which can be requested by the inlets, instead of `socket_addr`
+    #[n(5)] pub targets: Option<OutletTargets>,
+    /// Protocol which must be used by the connections, checked on their first bytes
+    #[n(6)] pub protocol: Option<String>,
+    /// Accept the requests of the inlets to open a direct UDP path to this node
+    #[n(7)] pub accept_hole_punching: Option<bool>,
 }
 
 impl CreateOutlet {
@@ -141,8 +314,100 @@ impl CreateOutlet {
             worker_addr,
             alias: alias.into(),
             reachable_from_default_secure_channel,
+            targets: None,
+            protocol: None,
+            accept_hole_punching: None,
         }
     }
+
+    pub fn set_targets(&mut self, targets: OutletTargets) {
+        self.targets = Some(targets)
+    }
+
+    pub fn set_protocol(&mut self, protocol: impl Into<String>) {
+        self.protocol = Some(protocol.into())
+    }
+
+    pub fn set_accept_hole_punching(&mut self) {
+        self.accept_hole_punching = Some(true)
+    }
+}
+
+/// Targets which can be requested by inlets when they connect to an outlet.
+/// Each target is written `host:port`, or `host:*` to allow any port
+#[derive(Clone, Debug, Default, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct OutletTargets {
+    /// Targets which can be requested by any inlet allowed to use the outlet
+    #[n(1)] pub allowed: Vec<String>,
+    /// Targets which can only be requested by a given identity
+    #[n(2)] pub by_identity: Vec<IdentityOutletTarget>,
+}
+
+impl OutletTargets {
+    pub fn allow(mut self, target: impl Into<String>) -> Self {
+        self.allowed.push(target.into());
+        self
+    }
+
+    pub fn allow_for(mut self, identifier: Identifier, target: impl Into<String>) -> Self {
+        self.by_identity.push(IdentityOutletTarget {
+            identifier,
+            target: target.into(),
+        });
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.allowed.is_empty() && self.by_identity.is_empty()
+    }
+}
+
+/// A target which can only be requested by the inlets of a given identity
+#[derive(Clone, Debug, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct IdentityOutletTarget {
+    #[n(1)] pub identifier: Identifier,
+    #[n(2)] pub target: String,
+}
+
+/// Targets of an outlet selected by the attributes of the identities connecting to it.
+/// The routes are tried in order, and the first route matching an attribute of the
+/// identity is used. The configured target of the outlet is used when none matches
+#[derive(Clone, Debug, Default, Decode, Encode, Serialize, Deserialize, PartialEq, Eq)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct OutletRoutes {
+    #[n(1)] pub routes: Vec<OutletRoute>,
+}
+
+impl OutletRoutes {
+    pub fn route(
+        mut self,
+        attribute: impl Into<String>,
+        value: impl Into<String>,
+        target: impl Into<String>,
+    ) -> Self {
+        self.routes.push(OutletRoute {
+            attribute: attribute.into(),
+            value: value.into(),
+            target: target.into(),
+        });
+        self
+    }
+}
+
+/// Route the connections of the identities having the attribute `attribute=value`
+/// to a `host:port` target
+#[derive(Clone, Debug, Decode, Encode, Serialize, Deserialize, PartialEq, Eq)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct OutletRoute {
+    #[n(1)] pub attribute: String,
+    #[n(2)] pub value: String,
+    #[n(3)] pub target: String,
 }
 
 /// Response body when interacting with a portal endpoint
@@ -156,6 +421,8 @@ pub struct InletStatus {
     /// An optional status payload
     #[n(4)] pub payload: Option<String>,
     #[n(5)] pub outlet_route: String,
+    /// The result of the last probe, if the inlet is probed
+    #[n(6)] pub last_probe: Option<ProbeResult>,
 }
 
 impl InletStatus {
@@ -166,6 +433,7 @@ impl InletStatus {
             alias: "".into(),
             payload: Some(reason.into()),
             outlet_route: "".into(),
+            last_probe: None,
         }
     }
 
@@ -182,6 +450,7 @@ impl InletStatus {
             alias: alias.into(),
             payload: payload.into(),
             outlet_route: outlet_route.into(),
+            last_probe: None,
         }
     }
 }
@@ -236,30 +505,35 @@ impl OutletStatus {
     }
 }
 
-/// Response body when returning a list of Inlets
-#[derive(Debug, Clone, Decode, Encode)]
+/// Active portal session, corresponding to one TCP connection accepted by an inlet
+/// or opened by an outlet
+#[derive(Clone, Debug, Decode, Encode, Serialize, Deserialize, PartialEq, Eq)]
 #[rustfmt::skip]
 #[cbor(map)]
-pub struct InletList {
-    #[n(1)] pub list: Vec<InletStatus>
+pub struct PortalSession {
+    /// Address of the portal worker, identifying the session
+    #[n(1)] pub address: String,
+    /// Type of the portal: inlet or outlet
+    #[n(2)] pub portal_type: String,
+    /// Alias of the inlet or outlet which created the session
+    #[n(3)] pub alias: Option<String>,
+    /// Socket address of the TCP client for an inlet, or of the target for an outlet
+    #[n(4)] pub peer: Option<String>,
+    /// Identity of the other side of the portal, when it is reached via a secure channel
+    #[n(5)] pub peer_identity: Option<Identifier>,
+    /// Number of bytes read from the TCP connection
+    #[n(6)] pub bytes_received: u64,
+    /// Number of bytes written to the TCP connection
+    #[n(7)] pub bytes_sent: u64,
+    /// Start time of the session, in seconds since the Unix epoch
+    #[n(8)] pub started_at: u64,
 }
 
-impl InletList {
-    pub fn new(list: Vec<InletStatus>) -> Self {
-        Self { list }
-    }
-}
+/// Response body when returning a list of Inlets
+pub type InletList = ResourceList<InletStatus>;
 
 /// Response body when returning a list of Outlets
-#[derive(Debug, Clone, Decode, Encode)]
-#[rustfmt::skip]
-#[cbor(map)]
-pub struct OutletList {
-    #[n(1)] pub list: Vec<OutletStatus>
-}
+pub type OutletList = ResourceList<OutletStatus>;
 
-impl OutletList {
-    pub fn new(list: Vec<OutletStatus>) -> Self {
-        Self { list }
-    }
-}
+/// Response body when returning a list of portal sessions
+pub type PortalSessionList = ResourceList<PortalSession>;
@@ -0,0 +1,309 @@
+use minicbor::{Decode, Encode};
+use std::fmt::{self, Display};
+
+use ockam::identity::Identifier;
+use ockam_multiaddr::MultiAddr;
+
+/// Request body to resolve a [`MultiAddr`] into its hop-by-hop route
+#[derive(Debug, Clone, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct ResolveRoute {
+    #[n(1)] pub addr: MultiAddr,
+}
+
+impl ResolveRoute {
+    pub fn new(addr: MultiAddr) -> Self {
+        Self { addr }
+    }
+}
+
+/// Kind of hop found while resolving a route
+#[derive(Copy, Clone, Debug, Decode, Encode, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
+#[rustfmt::skip]
+#[cbor(index_only)]
+pub enum HopKind {
+    /// A worker or service running on this node
+    #[n(0)] LocalWorker,
+    /// A secure channel started by this node
+    #[n(1)] SecureChannel,
+    /// A transport connection to another node
+    #[n(2)] Transport,
+    /// A relay, reached through a project or another node
+    #[n(3)] Relay,
+    /// A hop which could not be resolved
+    #[n(4)] Unknown,
+    /// A worker or service running on another node, after a transport or a secure channel
+    #[n(5)] RemoteWorker,
+}
+
+impl Display for HopKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::LocalWorker => "local worker",
+            Self::SecureChannel => "secure channel",
+            Self::Transport => "transport",
+            Self::Relay => "relay",
+            Self::Unknown => "unknown",
+            Self::RemoteWorker => "remote worker",
+        })
+    }
+}
+
+/// A single hop of a resolved route
+#[derive(Debug, Clone, Decode, Encode, serde::Serialize, serde::Deserialize)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct ResolvedHop {
+    /// Multiaddr representation of the hop
+    #[n(1)] pub addr: String,
+    #[n(2)] pub kind: HopKind,
+    /// `None` if reachability cannot be determined without sending messages
+    #[n(3)] pub reachable: Option<bool>,
+    /// Additional information, e.g. the worker address backing a transport hop
+    #[n(4)] pub detail: Option<String>,
+}
+
+impl ResolvedHop {
+    pub fn new(
+        addr: impl Into<String>,
+        kind: HopKind,
+        reachable: Option<bool>,
+        detail: Option<String>,
+    ) -> Self {
+        Self {
+            addr: addr.into(),
+            kind,
+            reachable,
+            detail,
+        }
+    }
+}
+
+/// Response body when resolving a route
+#[derive(Debug, Clone, Decode, Encode, serde::Serialize, serde::Deserialize)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct ResolvedRoute {
+    #[n(1)] pub addr: String,
+    #[n(2)] pub hops: Vec<ResolvedHop>,
+}
+
+impl ResolvedRoute {
+    pub fn new(addr: impl Into<String>, hops: Vec<ResolvedHop>) -> Self {
+        Self {
+            addr: addr.into(),
+            hops,
+        }
+    }
+
+    /// Return true if none of the hops is known to be unreachable
+    pub fn is_reachable(&self) -> bool {
+        self.hops.iter().all(|h| h.reachable != Some(false))
+    }
+}
+
+/// Request body to register a static route under a name
+#[derive(Debug, Clone, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct SetStaticRoute {
+    #[n(1)] pub addr: MultiAddr,
+}
+
+impl SetStaticRoute {
+    pub fn new(addr: MultiAddr) -> Self {
+        Self { addr }
+    }
+}
+
+/// A named route, referenced as `/route/<name>` in a [`MultiAddr`]
+#[derive(Debug, Clone, Decode, Encode, serde::Serialize, serde::Deserialize)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct StaticRoute {
+    #[n(1)] pub name: String,
+    /// Address registered for this route, which can itself reference other routes
+    #[n(2)] pub addr: String,
+    /// Address with all the referenced routes replaced by their addresses,
+    /// `None` if a referenced route does not exist
+    #[n(3)] pub expanded_addr: Option<String>,
+}
+
+impl StaticRoute {
+    pub fn new(
+        name: impl Into<String>,
+        addr: &MultiAddr,
+        expanded_addr: Option<&MultiAddr>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            addr: addr.to_string(),
+            expanded_addr: expanded_addr.map(|a| a.to_string()),
+        }
+    }
+}
+
+/// Response body when listing the static routes of a node
+#[derive(Debug, Clone, Decode, Encode, serde::Serialize, serde::Deserialize)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct StaticRouteList {
+    #[n(1)] pub list: Vec<StaticRoute>,
+}
+
+impl StaticRouteList {
+    pub fn new(list: Vec<StaticRoute>) -> Self {
+        Self { list }
+    }
+}
+
+/// Request body to select a route to a node, among direct addresses,
+/// relays and the relay of a project
+#[derive(Debug, Clone, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct SelectRoute {
+    /// Identifier of the destination node
+    #[n(1)] pub identifier: Identifier,
+    /// Addresses of the destination node, tried first
+    #[n(2)] pub direct: Vec<MultiAddr>,
+    /// Addresses of the nodes where the destination node created a relay, tried next
+    #[n(3)] pub relays: Vec<MultiAddr>,
+    /// Name of the relay created by the destination node
+    #[n(4)] pub relay_name: Option<String>,
+    /// Project where the destination node created a relay, tried last
+    #[n(5)] pub project: Option<String>,
+    /// Time during which the selected route is reused, in seconds
+    #[n(6)] pub ttl: Option<u64>,
+    /// Ignore a previously selected route
+    #[n(7)] pub refresh: bool,
+}
+
+impl SelectRoute {
+    pub fn new(identifier: Identifier) -> Self {
+        Self {
+            identifier,
+            direct: vec![],
+            relays: vec![],
+            relay_name: None,
+            project: None,
+            ttl: None,
+            refresh: false,
+        }
+    }
+
+    pub fn with_direct(mut self, direct: Vec<MultiAddr>) -> Self {
+        self.direct = direct;
+        self
+    }
+
+    pub fn with_relays(mut self, relays: Vec<MultiAddr>, relay_name: Option<String>) -> Self {
+        self.relays = relays;
+        self.relay_name = relay_name;
+        self
+    }
+
+    pub fn with_project(mut self, project: Option<String>) -> Self {
+        self.project = project;
+        self
+    }
+
+    pub fn with_ttl(mut self, ttl: Option<u64>) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    pub fn with_refresh(mut self, refresh: bool) -> Self {
+        self.refresh = refresh;
+        self
+    }
+}
+
+/// Kind of route selected to reach a node
+#[derive(Copy, Clone, Debug, Decode, Encode, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
+#[rustfmt::skip]
+#[cbor(index_only)]
+pub enum RouteSelectionKind {
+    /// A direct connection to the node
+    #[n(0)] Direct,
+    /// A relay created by the node on another node
+    #[n(1)] Relay,
+    /// A relay created by the node in a project
+    #[n(2)] ProjectRelay,
+}
+
+impl Display for RouteSelectionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Direct => "direct",
+            Self::Relay => "relay",
+            Self::ProjectRelay => "project relay",
+        })
+    }
+}
+
+/// Response body when selecting a route to a node
+#[derive(Debug, Clone, Decode, Encode, serde::Serialize, serde::Deserialize)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct SelectedRoute {
+    #[n(1)] pub identifier: String,
+    /// Address to use to reach the node
+    #[n(2)] pub addr: String,
+    #[n(3)] pub kind: RouteSelectionKind,
+    /// True if the route was selected by a previous request
+    #[n(4)] pub cached: bool,
+    /// Time, in seconds since the Unix epoch, after which the route is selected again
+    #[n(5)] pub expires_at: u64,
+}
+
+/// Request body to trace a route, measuring the time at which a probe goes through its hops
+#[derive(Debug, Clone, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct TraceRoute {
+    #[n(1)] pub addr: MultiAddr,
+    /// Maximum time to wait for the probe to come back, in seconds
+    #[n(2)] pub timeout: Option<u64>,
+}
+
+impl TraceRoute {
+    pub fn new(addr: MultiAddr) -> Self {
+        Self {
+            addr,
+            timeout: None,
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Option<u64>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+/// Response body when tracing a route
+#[derive(Debug, Clone, Decode, Encode, serde::Serialize, serde::Deserialize)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct TracedRoute {
+    /// Address of the tracer at the end of the route
+    #[n(1)] pub addr: String,
+    #[n(2)] pub hops: Vec<TracedHop>,
+    /// Time, in microseconds, taken by the probe sent to the end of the route to come back
+    #[n(3)] pub round_trip: u64,
+}
+
+/// Hop of a traced route
+#[derive(Debug, Clone, Decode, Encode, serde::Serialize, serde::Deserialize)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct TracedHop {
+    /// Segment of the address reaching this hop, ending with a secure channel
+    #[n(1)] pub segment: String,
+    /// Name of the node at the end of the secure channel
+    #[n(2)] pub node: String,
+    /// Time, in microseconds, taken by the probe to reach this hop from the previous one.
+    /// It is measured with the clocks of both nodes, and set to 0 if they are too far apart
+    #[n(3)] pub latency: u64,
+}
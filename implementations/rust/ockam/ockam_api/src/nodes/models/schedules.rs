@@ -0,0 +1,173 @@
+//! Schedules request/response types
+
+use core::fmt;
+use core::str::FromStr;
+
+use minicbor::{Decode, Encode};
+use ockam_core::errcode::{Kind, Origin};
+use serde::{Deserialize, Serialize};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Task executed by a schedule
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+#[rustfmt::skip]
+#[cbor(index_only)]
+#[serde(rename_all = "kebab-case")]
+pub enum ScheduledTask {
+    /// Rotate the key of the node identity
+    #[n(0)] RotateIdentityKey,
+    /// Retrieve a new credential for the node identity from the trust context authority
+    #[n(1)] RefreshCredentials,
+    /// Close the secure channels used to encrypt kafka records, so that new keys are used
+    #[n(2)] RekeyKafkaChannels,
+}
+
+impl fmt::Display for ScheduledTask {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScheduledTask::RotateIdentityKey => write!(f, "rotate-identity-key"),
+            ScheduledTask::RefreshCredentials => write!(f, "refresh-credentials"),
+            ScheduledTask::RekeyKafkaChannels => write!(f, "rekey-kafka-channels"),
+        }
+    }
+}
+
+impl FromStr for ScheduledTask {
+    type Err = ockam_core::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rotate-identity-key" => Ok(ScheduledTask::RotateIdentityKey),
+            "refresh-credentials" => Ok(ScheduledTask::RefreshCredentials),
+            "rekey-kafka-channels" => Ok(ScheduledTask::RekeyKafkaChannels),
+            _ => Err(ockam_core::Error::new(
+                Origin::Application,
+                Kind::Invalid,
+                "the task must be one of: rotate-identity-key, refresh-credentials, rekey-kafka-channels",
+            )),
+        }
+    }
+}
+
+/// When a scheduled task is executed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+#[rustfmt::skip]
+#[serde(rename_all = "snake_case")]
+pub enum Recurrence {
+    /// Every given number of seconds
+    #[n(0)] Every(#[n(0)] u64),
+    /// Every day, at a given number of minutes after midnight UTC
+    #[n(1)] DailyAt(#[n(0)] u16),
+}
+
+impl Recurrence {
+    /// Return the first execution time, in seconds since the Unix epoch, strictly after `after`
+    pub fn next_run(&self, after: u64) -> u64 {
+        match self {
+            Recurrence::Every(seconds) => after + (*seconds).max(1),
+            Recurrence::DailyAt(minutes) => {
+                let at = (*minutes as u64 * 60) % SECONDS_PER_DAY;
+                let today = after - after % SECONDS_PER_DAY + at;
+                if today > after {
+                    today
+                } else {
+                    today + SECONDS_PER_DAY
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Recurrence::Every(seconds) => write!(f, "every {seconds}s"),
+            Recurrence::DailyAt(minutes) => {
+                write!(f, "daily at {:02}:{:02} UTC", minutes / 60, minutes % 60)
+            }
+        }
+    }
+}
+
+/// A task executed periodically by a node
+#[derive(Clone, Debug, Encode, Decode, Serialize, Deserialize, PartialEq, Eq)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct Schedule {
+    #[n(1)] pub name: String,
+    #[n(2)] pub task: ScheduledTask,
+    #[n(3)] pub recurrence: Recurrence,
+    /// Time of the last execution, in seconds since the Unix epoch
+    #[n(4)] pub last_run: Option<u64>,
+    /// Time of the next execution, in seconds since the Unix epoch
+    #[n(5)] pub next_run: u64,
+    /// Reason of the failure, when the last execution failed
+    #[n(6)] pub last_error: Option<String>,
+}
+
+/// Request body to create a schedule
+#[derive(Clone, Debug, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct CreateSchedule {
+    #[n(1)] pub name: String,
+    #[n(2)] pub task: ScheduledTask,
+    #[n(3)] pub recurrence: Recurrence,
+}
+
+impl CreateSchedule {
+    pub fn new(name: impl Into<String>, task: ScheduledTask, recurrence: Recurrence) -> Self {
+        Self {
+            name: name.into(),
+            task,
+            recurrence,
+        }
+    }
+}
+
+/// Response body when returning the schedules of a node
+#[derive(Clone, Debug, Decode, Encode, Serialize, Deserialize)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct ScheduleList {
+    #[n(1)] pub schedules: Vec<Schedule>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_run() {
+        // 2023-11-14T22:13:20Z
+        let now = 1_700_000_000;
+        assert_eq!(Recurrence::Every(3600).next_run(now), now + 3600);
+
+        // 02:00 is on the next day
+        let next = Recurrence::DailyAt(120).next_run(now);
+        assert_eq!(next, 1_699_920_000 + SECONDS_PER_DAY + 7200);
+        // a task running at 02:00 runs again the next day
+        assert_eq!(
+            Recurrence::DailyAt(120).next_run(next),
+            next + SECONDS_PER_DAY
+        );
+        // 23:00 is on the same day
+        assert_eq!(
+            Recurrence::DailyAt(23 * 60).next_run(now),
+            1_699_920_000 + 23 * 3600
+        );
+    }
+
+    #[test]
+    fn test_parse_scheduled_task() {
+        for task in [
+            ScheduledTask::RotateIdentityKey,
+            ScheduledTask::RefreshCredentials,
+            ScheduledTask::RekeyKafkaChannels,
+        ] {
+            assert_eq!(task.to_string().parse::<ScheduledTask>().unwrap(), task);
+        }
+        assert!("rotate".parse::<ScheduledTask>().is_err());
+    }
+}
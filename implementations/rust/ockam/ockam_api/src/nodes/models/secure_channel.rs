@@ -3,7 +3,10 @@ use std::time::Duration;
 use minicbor::{Decode, Encode};
 use serde::Serialize;
 
-use ockam::identity::{Identifier, DEFAULT_TIMEOUT};
+use ockam::identity::{
+    CompressionStats, Identifier, ReplayProtectionStats, SecureChannelListenerLimits,
+    DEFAULT_TIMEOUT,
+};
 use ockam_core::flow_control::FlowControlId;
 use ockam_core::{route, Address, Result};
 use ockam_multiaddr::MultiAddr;
@@ -22,6 +25,10 @@ pub struct CreateSecureChannelRequest {
     #[n(4)] pub timeout: Option<Duration>,
     #[n(5)] pub identity_name: Option<String>,
     #[n(6)] pub credential_name: Option<String>,
+    /// Propose to compress the messages of the channel
+    #[n(7)] pub compression: Option<bool>,
+    /// Accept messages arriving at most that many messages behind the most recent one
+    #[n(8)] pub replay_window: Option<u64>,
 }
 
 impl CreateSecureChannelRequest {
@@ -38,8 +45,20 @@ impl CreateSecureChannelRequest {
             timeout: Some(DEFAULT_TIMEOUT),
             identity_name,
             credential_name,
+            compression: None,
+            replay_window: None,
         }
     }
+
+    pub fn with_compression(mut self, compression: bool) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    pub fn with_replay_window(mut self, replay_window: Option<u64>) -> Self {
+        self.replay_window = replay_window;
+        self
+    }
 }
 
 /// Response body when instructing a node to create a Secure Channel
@@ -78,6 +97,9 @@ pub struct CreateSecureChannelListenerRequest {
     #[n(2)] pub authorized_identifiers: Option<Vec<String>>,
     #[n(3)] pub vault_name: Option<String>,
     #[n(4)] pub identity_name: Option<String>,
+    #[n(5)] pub limits: Option<ListenerLimits>,
+    /// Accept to compress the messages of the channels, when their initiator proposes it
+    #[n(6)] pub compression: Option<bool>,
 }
 
 impl CreateSecureChannelListenerRequest {
@@ -86,6 +108,7 @@ impl CreateSecureChannelListenerRequest {
         authorized_identifiers: Option<Vec<Identifier>>,
         vault_name: Option<String>,
         identity_name: Option<String>,
+        limits: Option<ListenerLimits>,
     ) -> Self {
         Self {
             addr: addr.to_string(),
@@ -93,6 +116,70 @@ impl CreateSecureChannelListenerRequest {
                 .map(|x| x.into_iter().map(|y| y.to_string()).collect()),
             vault_name,
             identity_name,
+            limits,
+            compression: None,
+        }
+    }
+
+    pub fn with_compression(mut self, compression: bool) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+}
+
+/// Limits on the handshakes and channels of a Secure Channel Listener.
+/// A missing value leaves the current limit unchanged, and a maximum number of
+/// handshakes or channels set to 0 removes the limit
+#[derive(Debug, Clone, Default, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct ListenerLimits {
+    #[n(1)] pub max_handshakes: Option<u64>,
+    #[n(2)] pub max_channels: Option<u64>,
+    #[n(3)] pub max_queued_handshakes: Option<u64>,
+}
+
+impl ListenerLimits {
+    pub fn new(
+        max_handshakes: Option<u64>,
+        max_channels: Option<u64>,
+        max_queued_handshakes: Option<u64>,
+    ) -> Self {
+        Self {
+            max_handshakes,
+            max_channels,
+            max_queued_handshakes,
+        }
+    }
+
+    /// Apply these limits to the limits of a running listener
+    pub fn apply(&self, limits: &SecureChannelListenerLimits) {
+        if let Some(max) = self.max_handshakes {
+            limits.set_max_handshakes(Some(max as usize).filter(|max| *max > 0));
+        }
+        if let Some(max) = self.max_channels {
+            limits.set_max_channels(Some(max as usize).filter(|max| *max > 0));
+        }
+        if let Some(max) = self.max_queued_handshakes {
+            limits.set_max_queued_handshakes(max as usize);
+        }
+    }
+}
+
+/// Request body when changing the limits of a Secure Channel Listener
+#[derive(Debug, Clone, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct UpdateSecureChannelListenerLimitsRequest {
+    #[n(1)] pub addr: String,
+    #[n(2)] pub limits: ListenerLimits,
+}
+
+impl UpdateSecureChannelListenerLimitsRequest {
+    pub fn new(addr: &Address, limits: ListenerLimits) -> Self {
+        Self {
+            addr: addr.to_string(),
+            limits,
         }
     }
 }
@@ -150,13 +237,26 @@ impl ShowSecureChannelListenerRequest {
 pub struct ShowSecureChannelListenerResponse {
     #[n(1)] pub addr: Address,
     #[n(2)] pub flow_control_id: FlowControlId,
+    #[n(3)] pub max_handshakes: Option<u64>,
+    #[n(4)] pub max_channels: Option<u64>,
+    #[n(5)] pub max_queued_handshakes: Option<u64>,
+    #[n(6)] pub handshakes: Option<u64>,
+    #[n(7)] pub channels: Option<u64>,
+    #[n(8)] pub rejected_handshakes: Option<u64>,
 }
 
 impl ShowSecureChannelListenerResponse {
     pub(crate) fn new(info: &SecureChannelListenerInfo) -> Self {
+        let limits = info.listener().limits();
         Self {
             addr: info.listener().address().to_string().into(),
             flow_control_id: info.listener().flow_control_id().clone(),
+            max_handshakes: limits.and_then(|l| l.max_handshakes()).map(|m| m as u64),
+            max_channels: limits.and_then(|l| l.max_channels()).map(|m| m as u64),
+            max_queued_handshakes: limits.map(|l| l.max_queued_handshakes() as u64),
+            handshakes: limits.map(|l| l.handshakes() as u64),
+            channels: limits.map(|l| l.channels() as u64),
+            rejected_handshakes: limits.map(|l| l.rejected()),
         }
     }
 }
@@ -214,6 +314,10 @@ pub struct ShowSecureChannelResponse {
     #[n(2)] pub route: Option<String>,
     #[n(3)] pub authorized_identifiers: Option<Vec<String>>,
     #[n(4)] pub flow_control_id: Option<FlowControlId>,
+    /// Time, in seconds since the epoch, of the last heartbeat received on that channel
+    #[n(5)] pub last_heartbeat: Option<u64>,
+    #[n(6)] pub replay_protection: Option<ReplayProtection>,
+    #[n(7)] pub compression: Option<Compression>,
 }
 
 impl ShowSecureChannelResponse {
@@ -232,6 +336,90 @@ impl ShowSecureChannelResponse {
                 })
                 .unwrap_or(None),
             flow_control_id: info.map(|info| info.sc().flow_control_id().clone()),
+            last_heartbeat: None,
+            replay_protection: None,
+            compression: None,
+        }
+    }
+
+    pub fn with_last_heartbeat(mut self, last_heartbeat: Option<u64>) -> Self {
+        self.last_heartbeat = last_heartbeat;
+        self
+    }
+
+    pub fn with_replay_protection(mut self, stats: Option<ReplayProtectionStats>) -> Self {
+        self.replay_protection = stats.map(ReplayProtection::from);
+        self
+    }
+
+    pub fn with_compression(mut self, stats: Option<CompressionStats>) -> Self {
+        self.compression = stats.map(Compression::from);
+        self
+    }
+}
+
+/// Replay protection statistics of a Secure Channel
+#[derive(Debug, Clone, Decode, Encode, Serialize)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct ReplayProtection {
+    /// Number of messages a message can lag behind the most recent one and still be accepted
+    #[n(1)] pub window: u64,
+    /// Messages dropped because they were outside of the window
+    #[n(2)] pub out_of_window: u64,
+    /// Messages dropped because they had already been received
+    #[n(3)] pub duplicates: u64,
+    /// Messages accepted after a more recent message
+    #[n(4)] pub reordered: u64,
+}
+
+impl From<ReplayProtectionStats> for ReplayProtection {
+    fn from(stats: ReplayProtectionStats) -> Self {
+        Self {
+            window: stats.window,
+            out_of_window: stats.out_of_window,
+            duplicates: stats.duplicates,
+            reordered: stats.reordered,
+        }
+    }
+}
+
+/// Compression of the messages sent on a Secure Channel
+#[derive(Debug, Clone, Decode, Encode, Serialize)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct Compression {
+    /// Compression negotiated with the other side, if any
+    #[n(1)] pub algorithm: Option<String>,
+    /// Messages sent compressed
+    #[n(2)] pub compressed_messages: u64,
+    /// Messages sent uncompressed, because they were too small or not compressible
+    #[n(3)] pub uncompressed_messages: u64,
+    /// Size of the compressed messages before compression
+    #[n(4)] pub bytes_in: u64,
+    /// Size of the compressed messages after compression
+    #[n(5)] pub bytes_out: u64,
+}
+
+impl Compression {
+    /// Size of the compressed messages after compression relative to their original size
+    pub fn ratio(&self) -> Option<f64> {
+        if self.bytes_in == 0 {
+            None
+        } else {
+            Some(self.bytes_out as f64 / self.bytes_in as f64)
+        }
+    }
+}
+
+impl From<CompressionStats> for Compression {
+    fn from(stats: CompressionStats) -> Self {
+        Self {
+            algorithm: stats.compression.map(|c| c.to_string()),
+            compressed_messages: stats.compressed_messages,
+            uncompressed_messages: stats.uncompressed_messages,
+            bytes_in: stats.bytes_in,
+            bytes_out: stats.bytes_out,
         }
     }
 }
@@ -248,3 +436,17 @@ impl SecureChannelListenersList {
         Self { list }
     }
 }
+
+/// Response body for the credentials refreshes of the secure channels of a node
+#[derive(Debug, Clone, Decode, Encode, Serialize)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct CredentialsRefreshStatus {
+    /// Default interval, in seconds, between two refreshes, if credentials are refreshed
+    #[n(1)] pub interval: Option<u64>,
+    /// Maximum random variation, in seconds, of the interval
+    #[n(2)] pub jitter: Option<u64>,
+    #[n(3)] pub requested: u64,
+    #[n(4)] pub succeeded: u64,
+    #[n(5)] pub failed: u64,
+}
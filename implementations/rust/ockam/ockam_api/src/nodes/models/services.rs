@@ -1,10 +1,14 @@
 use minicbor::{Decode, Encode};
+use ockam_abac::Expr;
 use ockam_core::compat::net::SocketAddr;
 use ockam_core::Address;
 use ockam_multiaddr::MultiAddr;
 
 use serde::Serialize;
 
+use crate::cloud::project::InfluxDBTokenLeaseManagerConfig;
+use crate::kafka::TopicFilters;
+
 #[derive(Debug, Clone, Decode, Encode)]
 #[rustfmt::skip]
 #[cbor(map)]
@@ -66,16 +70,38 @@ impl StartKafkaOutletRequest {
     }
 }
 
-#[derive(Debug, Clone, Decode, Encode)]
+/// Configuration of a kafka consumer or producer service, sent to start the service and
+/// returned with the values used by the node once it is started.
+///
+/// The fields added after the first version of the request are optional, so that the
+/// requests of older clients can still be decoded
+#[derive(Debug, Clone, Decode, Encode, Serialize)]
 #[rustfmt::skip]
 #[cbor(map)]
-pub struct StartKafkaConsumerRequest {
+pub struct KafkaServiceConfig {
+    /// Address where the kafka clients connect to the service
     #[n(1)] pub bootstrap_server_addr: SocketAddr,
-    #[n(2)] brokers_port_range: (u16, u16),
-    #[n(3)] project_route: String,
-}
-
-impl StartKafkaConsumerRequest {
+    /// Local ports allocated to the kafka brokers, must not contain the bootstrap port
+    #[n(2)] pub brokers_port_range: (u16, u16),
+    /// Route to the project, or to the node, running the kafka outlet
+    #[n(3)] pub project_route: String,
+    /// Record headers sent in clear text to the broker, the other headers are encrypted
+    /// along with the record value. All the headers are in clear text if absent
+    #[n(4)] pub cleartext_headers: Option<Vec<String>>,
+    /// Sign the offsets committed by the consumers and verify the offsets they fetch
+    #[n(5)] pub sign_offset_commits: Option<bool>,
+    /// Topics which can be produced to or consumed from, all the topics if absent.
+    /// A topic can be a glob pattern, see [`KafkaServiceConfig::encrypted_topics`]
+    #[n(6)] pub topics: Option<Vec<String>>,
+    /// Glob patterns of the topics which have their records encrypted, all the topics if
+    /// absent. `*` matches any sequence of characters and `?` matches a single character
+    #[n(7)] pub encrypted_topics: Option<Vec<String>>,
+    /// Glob patterns of the topics which have their records passed through in clear text,
+    /// even if they match an encrypted topic pattern
+    #[n(8)] pub cleartext_topics: Option<Vec<String>>,
+}
+
+impl KafkaServiceConfig {
     pub fn new(
         bootstrap_server_addr: SocketAddr,
         brokers_port_range: impl Into<(u16, u16)>,
@@ -85,50 +111,150 @@ impl StartKafkaConsumerRequest {
             bootstrap_server_addr,
             brokers_port_range: brokers_port_range.into(),
             project_route: project_route.to_string(),
+            cleartext_headers: None,
+            sign_offset_commits: None,
+            topics: None,
+            encrypted_topics: None,
+            cleartext_topics: None,
         }
     }
 
-    pub fn bootstrap_server_addr(&self) -> SocketAddr {
-        self.bootstrap_server_addr
+    /// Only send these record headers in clear text to the broker.
+    /// The other headers are encrypted along with the record value
+    pub fn with_cleartext_headers(mut self, headers: Vec<String>) -> Self {
+        self.cleartext_headers = Some(headers);
+        self
     }
-    pub fn brokers_port_range(&self) -> (u16, u16) {
-        self.brokers_port_range
+
+    /// Sign the offsets committed by the consumers and verify the offsets they fetch
+    pub fn with_signed_offset_commits(mut self) -> Self {
+        self.sign_offset_commits = Some(true);
+        self
     }
-    pub fn project_route(&self) -> &String {
-        &self.project_route
+
+    /// Only allow these topics
+    pub fn with_topics(mut self, topics: Vec<String>) -> Self {
+        self.topics = Some(topics);
+        self
     }
-}
 
-#[derive(Debug, Clone, Decode, Encode)]
-#[rustfmt::skip]
-#[cbor(map)]
-pub struct StartKafkaProducerRequest {
-    #[n(1)] pub bootstrap_server_addr: SocketAddr,
-    #[n(2)] brokers_port_range: (u16, u16),
-    #[n(3)] project_route: String,
-}
+    /// Only encrypt the records of the topics matching these patterns
+    pub fn with_encrypted_topics(mut self, topics: Vec<String>) -> Self {
+        self.encrypted_topics = Some(topics);
+        self
+    }
 
-impl StartKafkaProducerRequest {
-    pub fn new(
-        bootstrap_server_addr: SocketAddr,
-        brokers_port_range: impl Into<(u16, u16)>,
-        project_route: MultiAddr,
-    ) -> Self {
-        Self {
-            bootstrap_server_addr,
-            brokers_port_range: brokers_port_range.into(),
-            project_route: project_route.to_string(),
-        }
+    /// Pass through the records of the topics matching these patterns
+    pub fn with_cleartext_topics(mut self, topics: Vec<String>) -> Self {
+        self.cleartext_topics = Some(topics);
+        self
     }
 
-    pub fn bootstrap_server_addr(&self) -> SocketAddr {
-        self.bootstrap_server_addr
+    pub fn sign_offset_commits(&self) -> bool {
+        self.sign_offset_commits.unwrap_or(false)
     }
-    pub fn brokers_port_range(&self) -> (u16, u16) {
-        self.brokers_port_range
+
+    /// Filters applied to the topics of the records handled by the service
+    pub fn topic_filters(&self) -> TopicFilters {
+        let mut filters = TopicFilters::default();
+        if let Some(topics) = non_empty(&self.topics) {
+            filters = filters.with_allowed_topics(topics);
+        }
+        if let Some(topics) = non_empty(&self.encrypted_topics) {
+            filters = filters.with_encrypted_topics(topics);
+        }
+        if let Some(topics) = non_empty(&self.cleartext_topics) {
+            filters = filters.with_cleartext_topics(topics);
+        }
+        filters
+    }
+
+    /// Check the configuration and return the parsed project route, or the list of
+    /// everything which is invalid
+    pub fn validate(&self) -> Result<MultiAddr, Vec<String>> {
+        let mut errors = vec![];
+        let (start, end) = self.brokers_port_range;
+        let bootstrap_port = self.bootstrap_server_addr.port();
+        if bootstrap_port == 0 {
+            errors.push("the bootstrap server port must not be 0".to_string());
+        }
+        if start > end {
+            errors.push(format!(
+                "the brokers port range {start}-{end} starts after its end"
+            ));
+        } else if start == 0 {
+            errors.push(format!(
+                "the brokers port range {start}-{end} must not contain the port 0"
+            ));
+        } else if (start..=end).contains(&bootstrap_port) {
+            errors.push(format!(
+                "the brokers port range {start}-{end} contains the bootstrap server port {bootstrap_port}"
+            ));
+        }
+
+        let project_route = match self.project_route.parse::<MultiAddr>() {
+            Ok(route) if route.is_empty() => {
+                errors.push("the project route is empty".to_string());
+                None
+            }
+            Ok(route) => Some(route),
+            Err(e) => {
+                errors.push(format!(
+                    "the project route {} is invalid: {e}",
+                    self.project_route
+                ));
+                None
+            }
+        };
+
+        for header in self.cleartext_headers.iter().flatten() {
+            if header.is_empty() {
+                errors.push("a cleartext header name is empty".to_string());
+            }
+        }
+
+        let topics = self
+            .topics
+            .iter()
+            .chain(self.encrypted_topics.iter())
+            .chain(self.cleartext_topics.iter())
+            .flatten();
+        for topic in topics {
+            if let Err(e) = validate_topic_pattern(topic) {
+                errors.push(e);
+            }
+        }
+
+        match project_route {
+            Some(route) if errors.is_empty() => Ok(route),
+            _ => Err(errors),
+        }
     }
-    pub fn project_route(&self) -> &String {
-        &self.project_route
+}
+
+fn non_empty(topics: &Option<Vec<String>>) -> Option<Vec<String>> {
+    topics.clone().filter(|topics| !topics.is_empty())
+}
+
+/// Check that a topic pattern is a valid kafka topic name, possibly containing the
+/// `*` and `?` wildcards
+fn validate_topic_pattern(topic: &str) -> Result<(), String> {
+    let name = topic.replace(['*', '?'], "");
+    if topic.is_empty() {
+        Err("a topic is empty".to_string())
+    } else if name.len() > 249 {
+        Err(format!("the topic {topic} is longer than 249 characters"))
+    } else if topic == "." || topic == ".." {
+        Err(format!("the topic {topic} is not a valid topic name"))
+    } else if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '_' || c == '-')
+    {
+        Err(format!(
+            "the topic {topic} can only contain ASCII letters, digits, '.', '_', '-', '*' and '?'"
+        ))
+    } else {
+        Ok(())
     }
 }
 
@@ -191,11 +317,21 @@ impl StartIdentityServiceRequest {
 #[cbor(map)]
 pub struct StartAuthenticatedServiceRequest {
     #[n(1)] pub addr: String,
+    /// Policy checked for each incoming request, instead of the default policy of the node
+    #[n(2)] pub policy: Option<Expr>,
 }
 
 impl StartAuthenticatedServiceRequest {
     pub fn new(addr: impl Into<String>) -> Self {
-        Self { addr: addr.into() }
+        Self {
+            addr: addr.into(),
+            policy: None,
+        }
+    }
+
+    pub fn with_policy(mut self, policy: Expr) -> Self {
+        self.policy = Some(policy);
+        self
     }
 }
 
@@ -205,11 +341,21 @@ impl StartAuthenticatedServiceRequest {
 #[cbor(map)]
 pub struct StartUppercaseServiceRequest {
     #[n(1)] pub addr: String,
+    /// Policy checked for each incoming message, instead of the default policy of the node
+    #[n(2)] pub policy: Option<Expr>,
 }
 
 impl StartUppercaseServiceRequest {
     pub fn new(addr: impl Into<String>) -> Self {
-        Self { addr: addr.into() }
+        Self {
+            addr: addr.into(),
+            policy: None,
+        }
+    }
+
+    pub fn with_policy(mut self, policy: Expr) -> Self {
+        self.policy = Some(policy);
+        self
     }
 }
 
@@ -219,11 +365,110 @@ impl StartUppercaseServiceRequest {
 #[cbor(map)]
 pub struct StartEchoerServiceRequest {
     #[n(1)] pub addr: String,
+    /// Policy checked for each incoming message, instead of the default policy of the node
+    #[n(2)] pub policy: Option<Expr>,
 }
 
 impl StartEchoerServiceRequest {
     pub fn new(addr: impl Into<String>) -> Self {
-        Self { addr: addr.into() }
+        Self {
+            addr: addr.into(),
+            policy: None,
+        }
+    }
+
+    pub fn with_policy(mut self, policy: Expr) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+}
+
+/// Request body when instructing a node to start a Discard service
+#[derive(Debug, Clone, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct StartDiscardServiceRequest {
+    #[n(1)] pub addr: String,
+    /// Policy checked for each incoming message, instead of the default policy of the node
+    #[n(2)] pub policy: Option<Expr>,
+}
+
+impl StartDiscardServiceRequest {
+    pub fn new(addr: impl Into<String>) -> Self {
+        Self {
+            addr: addr.into(),
+            policy: None,
+        }
+    }
+
+    pub fn with_policy(mut self, policy: Expr) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+}
+
+/// Request body when instructing a node to start a File Receiver service
+#[derive(Debug, Clone, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct StartFileReceiverServiceRequest {
+    #[n(1)] pub addr: String,
+    /// Directory where the received files are stored
+    #[n(2)] pub directory: String,
+    /// Policy checked for each incoming message, instead of the default policy of the node
+    #[n(3)] pub policy: Option<Expr>,
+    /// Maximum size of a received file, in bytes.
+    /// The default is DEFAULT_MAX_FILE_SIZE
+    #[n(4)] pub max_file_size: Option<u64>,
+}
+
+impl StartFileReceiverServiceRequest {
+    pub fn new(addr: impl Into<String>, directory: impl Into<String>) -> Self {
+        Self {
+            addr: addr.into(),
+            directory: directory.into(),
+            policy: None,
+            max_file_size: None,
+        }
+    }
+
+    pub fn with_policy(mut self, policy: Expr) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    pub fn with_max_file_size(mut self, max_file_size: u64) -> Self {
+        self.max_file_size = Some(max_file_size);
+        self
+    }
+}
+
+/// Request body when instructing a node to start its tracer
+#[derive(Debug, Clone, Default, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct StartTracerServiceRequest {
+    /// Policy checked for each incoming probe, instead of the default policy of the node
+    #[n(1)] pub policy: Option<Expr>,
+}
+
+impl StartTracerServiceRequest {
+    pub fn new(policy: Option<Expr>) -> Self {
+        Self { policy }
+    }
+}
+
+/// Request body when instructing a node to start an InfluxDB token lease manager
+#[derive(Debug, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct StartInfluxDbTokenLeaseManagerRequest {
+    #[n(1)] pub config: InfluxDBTokenLeaseManagerConfig,
+}
+
+impl StartInfluxDbTokenLeaseManagerRequest {
+    pub fn new(config: InfluxDBTokenLeaseManagerConfig) -> Self {
+        Self { config }
     }
 }
 
@@ -361,6 +606,56 @@ impl StartOktaIdentityProviderRequest {
     }
 }
 
+/// Request body when registering a worker, started by the application embedding
+/// the node, as a custom service. The service is reached at its own address, where
+/// the policy is checked before the messages are forwarded to the worker
+#[derive(Debug, Clone, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct RegisterCustomServiceRequest {
+    #[n(1)] addr: String,
+    #[n(2)] service_type: String,
+    #[n(3)] worker_addr: String,
+    /// Policy checked for each incoming message, instead of the default policy of the node
+    #[n(4)] policy: Option<Expr>,
+}
+
+impl RegisterCustomServiceRequest {
+    pub fn new(
+        addr: impl Into<String>,
+        worker_addr: impl Into<String>,
+        service_type: impl Into<String>,
+    ) -> Self {
+        Self {
+            addr: addr.into(),
+            service_type: service_type.into(),
+            worker_addr: worker_addr.into(),
+            policy: None,
+        }
+    }
+
+    pub fn with_policy(mut self, policy: Expr) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    pub fn address(&self) -> Address {
+        Address::from(self.addr.clone())
+    }
+
+    pub fn worker_address(&self) -> Address {
+        Address::from(self.worker_addr.clone())
+    }
+
+    pub fn service_type(&self) -> &str {
+        &self.service_type
+    }
+
+    pub fn policy(&self) -> Option<&Expr> {
+        self.policy.as_ref()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Decode, Encode)]
 #[rustfmt::skip]
 #[cbor(map)]
@@ -391,3 +686,66 @@ impl ServiceList {
         Self { list }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_kafka_service_config() {
+        let project_route: MultiAddr = "/project/default".parse().unwrap();
+        let config = KafkaServiceConfig::new(
+            "127.0.0.1:4000".parse().unwrap(),
+            (4001, 4100),
+            project_route.clone(),
+        )
+        .with_topics(vec!["orders".to_string(), "payments.*".to_string()])
+        .with_cleartext_topics(vec!["payments.??.public".to_string()]);
+        assert_eq!(config.validate(), Ok(project_route.clone()));
+        let filters = config.topic_filters();
+        assert!(filters.is_encrypted("payments.eu"));
+        assert!(!filters.is_encrypted("payments.eu.public"));
+        assert!(!filters.is_allowed("invoices"));
+
+        let config = KafkaServiceConfig::new(
+            "127.0.0.1:4050".parse().unwrap(),
+            (4001, 4100),
+            project_route,
+        )
+        .with_cleartext_headers(vec!["".to_string()])
+        .with_topics(vec!["orders/eu".to_string()]);
+        let errors = config.validate().unwrap_err();
+        assert_eq!(errors.len(), 3);
+        assert!(errors[0].contains("contains the bootstrap server port 4050"));
+    }
+
+    /// Request sent by the clients released before the topics were configurable
+    #[derive(Encode)]
+    #[rustfmt::skip]
+    #[cbor(map)]
+    struct StartKafkaConsumerRequestV1 {
+        #[n(1)] bootstrap_server_addr: SocketAddr,
+        #[n(2)] brokers_port_range: (u16, u16),
+        #[n(3)] project_route: String,
+        #[n(4)] cleartext_headers: Option<Vec<String>>,
+        #[n(5)] sign_offset_commits: Option<bool>,
+    }
+
+    #[test]
+    fn test_decode_kafka_service_config_of_older_clients() {
+        let request = StartKafkaConsumerRequestV1 {
+            bootstrap_server_addr: "127.0.0.1:4000".parse().unwrap(),
+            brokers_port_range: (4001, 4100),
+            project_route: "/project/default".to_string(),
+            cleartext_headers: Some(vec!["trace-id".to_string()]),
+            sign_offset_commits: Some(true),
+        };
+        let config: KafkaServiceConfig =
+            minicbor::decode(&minicbor::to_vec(request).unwrap()).unwrap();
+        assert_eq!(config.brokers_port_range, (4001, 4100));
+        assert_eq!(config.cleartext_headers, Some(vec!["trace-id".to_string()]));
+        assert!(config.sign_offset_commits());
+        assert!(config.topics.is_none());
+        assert!(config.topic_filters().is_allowed("orders"));
+    }
+}
@@ -0,0 +1,56 @@
+use minicbor::{Decode, Encode};
+
+/// Request body to get the statistics recorded by a node
+#[derive(Debug, Clone, Default, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct GetNodeStats {
+    /// Start of the time range, in seconds since the Unix epoch (inclusive)
+    #[n(1)] pub from: Option<u64>,
+    /// End of the time range, in seconds since the Unix epoch (exclusive)
+    #[n(2)] pub until: Option<u64>,
+}
+
+impl GetNodeStats {
+    pub fn new(from: Option<u64>, until: Option<u64>) -> Self {
+        Self { from, until }
+    }
+}
+
+/// Statistics of a node over an interval ending at `timestamp`.
+///
+/// The counts and the memory are sampled at the end of the interval, or averaged over the
+/// interval once old snapshots are downsampled. The portal bytes are summed over the interval
+#[derive(Debug, Clone, Default, Decode, Encode, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct NodeStats {
+    /// End of the interval, in seconds since the Unix epoch
+    #[n(1)] pub timestamp: u64,
+    /// Length of the interval, in seconds
+    #[n(2)] pub duration: u64,
+    #[n(3)] pub secure_channels: u64,
+    #[n(4)] pub inlets: u64,
+    #[n(5)] pub outlets: u64,
+    #[n(6)] pub portal_sessions: u64,
+    /// Bytes read from the TCP connections of the portals during the interval
+    #[n(7)] pub portal_bytes_received: u64,
+    /// Bytes written to the TCP connections of the portals during the interval
+    #[n(8)] pub portal_bytes_sent: u64,
+    /// Resident memory of the node process, in bytes
+    #[n(9)] pub memory: u64,
+}
+
+/// Response body when getting the statistics recorded by a node
+#[derive(Debug, Clone, Decode, Encode, serde::Serialize, serde::Deserialize)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct NodeStatsList {
+    #[n(1)] pub list: Vec<NodeStats>,
+}
+
+impl NodeStatsList {
+    pub fn new(list: Vec<NodeStats>) -> Self {
+        Self { list }
+    }
+}
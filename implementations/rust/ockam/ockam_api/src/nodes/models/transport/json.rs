@@ -2,7 +2,6 @@ use crate::cli_state::CliStateError;
 use crate::config::lookup::InternetAddress;
 use crate::nodes::models::transport::{TransportMode, TransportType};
 use ockam_core::Result;
-use ockam_multiaddr::proto::{DnsAddr, Ip4, Ip6, Tcp};
 use ockam_multiaddr::MultiAddr;
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash)]
@@ -19,21 +18,13 @@ impl CreateTransportJson {
         Ok(Self {
             tt,
             tm,
-            addr: InternetAddress::new(addr).ok_or(CliStateError::InvalidOperation(
-                "Invalid address '{addr}'".to_string(),
-            ))?,
+            addr: InternetAddress::new(addr).ok_or_else(|| {
+                CliStateError::InvalidOperation(format!("Invalid address '{addr}'"))
+            })?,
         })
     }
 
     pub fn maddr(&self) -> Result<MultiAddr> {
-        let mut m = MultiAddr::default();
-        let addr = &self.addr;
-        match addr {
-            InternetAddress::Dns(dns, _) => m.push_back(DnsAddr::new(dns))?,
-            InternetAddress::V4(v4) => m.push_back(Ip4(*v4.ip()))?,
-            InternetAddress::V6(v6) => m.push_back(Ip6(*v6.ip()))?,
-        }
-        m.push_back(Tcp(addr.port()))?;
-        Ok(m)
+        self.addr.multiaddr()
     }
 }
@@ -6,7 +6,7 @@ use ockam_core::flow_control::FlowControlId;
 use ockam_core::{Error, Result};
 use ockam_multiaddr::proto::Worker;
 use ockam_multiaddr::MultiAddr;
-use std::net::SocketAddrV4;
+use std::net::SocketAddr;
 
 /// Response body when interacting with a transport
 #[derive(Debug, Clone, Decode, Encode)]
@@ -39,9 +39,9 @@ impl TransportStatus {
         }
     }
 
-    pub fn socket_addr(&self) -> Result<SocketAddrV4> {
+    pub fn socket_addr(&self) -> Result<SocketAddr> {
         self.socket_addr
-            .parse::<SocketAddrV4>()
+            .parse::<SocketAddr>()
             .map_err(|err| Error::new(Origin::Transport, Kind::Invalid, err))
     }
 
@@ -0,0 +1,58 @@
+use minicbor::{Decode, Encode};
+
+/// Request body to get the bandwidth used by peer identities
+#[derive(Debug, Clone, Default, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct GetBandwidthUsage {
+    /// Only return the usage of this identity
+    #[n(1)] pub identifier: Option<String>,
+    /// Start of the time range, in seconds since the Unix epoch (inclusive)
+    #[n(2)] pub from: Option<u64>,
+    /// End of the time range, in seconds since the Unix epoch (exclusive)
+    #[n(3)] pub until: Option<u64>,
+}
+
+impl GetBandwidthUsage {
+    pub fn new(identifier: Option<String>, from: Option<u64>, until: Option<u64>) -> Self {
+        Self {
+            identifier,
+            from,
+            until,
+        }
+    }
+}
+
+/// Bytes exchanged with a peer identity over a time range
+#[derive(Debug, Clone, Decode, Encode, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct IdentityBandwidthUsage {
+    #[n(1)] pub identifier: String,
+    #[n(2)] pub bytes_sent: u64,
+    #[n(3)] pub bytes_received: u64,
+}
+
+impl IdentityBandwidthUsage {
+    pub fn new(identifier: impl Into<String>, bytes_sent: u64, bytes_received: u64) -> Self {
+        Self {
+            identifier: identifier.into(),
+            bytes_sent,
+            bytes_received,
+        }
+    }
+}
+
+/// Response body when getting the bandwidth used by peer identities
+#[derive(Debug, Clone, Decode, Encode, serde::Serialize, serde::Deserialize)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct BandwidthUsageList {
+    #[n(1)] pub list: Vec<IdentityBandwidthUsage>,
+}
+
+impl BandwidthUsageList {
+    pub fn new(list: Vec<IdentityBandwidthUsage>) -> Self {
+        Self { list }
+    }
+}
@@ -1,20 +1,62 @@
 use minicbor::{Decode, Encode};
+use serde::Serialize;
 
-#[derive(Debug, Clone, Decode, Encode)]
+use ockam_node::WorkerInfo;
+
+#[derive(Debug, Clone, Decode, Encode, Serialize)]
 #[rustfmt::skip]
 #[cbor(map)]
 pub struct WorkerStatus {
     #[n(2)] pub addr: String,
+    /// Type of the messages handled by the worker, if known
+    #[n(3)] pub message_type: Option<String>,
+    /// Number of messages waiting in the mailbox of the worker
+    #[n(4)] pub mailbox_depth: Option<u64>,
+    /// Number of messages handled by the worker so far
+    #[n(5)] pub processed_messages: Option<u64>,
+    #[n(6)] pub processor: Option<bool>,
+    #[n(7)] pub detached: Option<bool>,
+    #[n(8)] pub mailbox_capacity: Option<u64>,
+    /// Policy applied when the mailbox is full
+    #[n(9)] pub overflow_policy: Option<String>,
+    /// Number of messages rejected because the mailbox was full
+    #[n(10)] pub overflow_messages: Option<u64>,
 }
 
 impl WorkerStatus {
     pub fn new(addr: impl Into<String>) -> Self {
-        Self { addr: addr.into() }
+        Self {
+            addr: addr.into(),
+            message_type: None,
+            mailbox_depth: None,
+            processed_messages: None,
+            processor: None,
+            detached: None,
+            mailbox_capacity: None,
+            overflow_policy: None,
+            overflow_messages: None,
+        }
+    }
+}
+
+impl From<WorkerInfo> for WorkerStatus {
+    fn from(info: WorkerInfo) -> Self {
+        Self {
+            addr: info.address.address().to_string(),
+            message_type: info.message_type,
+            mailbox_depth: info.mailbox_depth.map(|d| d as u64),
+            processed_messages: info.processed_messages.map(|p| p as u64),
+            processor: Some(info.processor),
+            detached: Some(info.detached),
+            mailbox_capacity: info.mailbox_options.map(|o| o.capacity() as u64),
+            overflow_policy: info.mailbox_options.map(|o| o.overflow().to_string()),
+            overflow_messages: info.overflow_messages.map(|o| o as u64),
+        }
     }
 }
 
 /// Response body for listing workers
-#[derive(Debug, Clone, Decode, Encode)]
+#[derive(Debug, Clone, Decode, Encode, Serialize)]
 #[rustfmt::skip]
 #[cbor(map)]
 pub struct WorkerList {
@@ -1,10 +1,17 @@
+use crate::kafka::KafkaSecureChannelController;
+use crate::nodes::models::events::NodeEventType;
+use crate::nodes::models::portal::InletProbe;
+use crate::nodes::service::portals::AttributeOutletTargetRouter;
+use crate::nodes::service::shutdown::ShutdownHooks;
 use crate::nodes::service::Alias;
 use ockam::identity::Identifier;
 use ockam::identity::{SecureChannel, SecureChannelListener};
 use ockam::remote::RemoteRelayInfo;
 use ockam_core::compat::collections::BTreeMap;
-use ockam_core::{Address, Route};
+use ockam_core::compat::sync::Arc;
+use ockam_core::{Address, IncomingAccessControl, Route};
 use ockam_node::compat::asynchronous::RwLock;
+use ockam_transport_udp::UdpHolePuncher;
 use std::borrow::Borrow;
 use std::fmt::Display;
 use std::net::SocketAddr;
@@ -108,15 +115,81 @@ pub(crate) struct UppercaseServiceInfo {}
 #[derive(Default, Clone)]
 pub(crate) struct EchoerServiceInfo {}
 
+#[derive(Default, Clone)]
+pub(crate) struct DiscardServiceInfo {}
+
+#[derive(Default, Clone)]
+pub(crate) struct FileReceiverServiceInfo {}
+
 #[derive(Default, Clone)]
 pub(crate) struct HopServiceInfo {}
 
+#[derive(Default, Clone)]
+pub(crate) struct TracerServiceInfo {}
+
+#[derive(Default, Clone)]
+pub(crate) struct InfluxDbTokenLeaseServiceInfo {}
+
 #[derive(Default, Clone)]
 pub(crate) struct VerifierServiceInfo {}
 
 #[derive(Default, Clone)]
 pub(crate) struct CredentialsServiceInfo {}
 
+#[derive(Clone)]
+pub(crate) struct CustomServiceInfo {
+    service_type: String,
+    /// Worker of the application, when the service is a hop forwarding messages to it
+    worker: Option<Address>,
+}
+
+impl CustomServiceInfo {
+    pub fn new(service_type: impl Into<String>) -> Self {
+        Self {
+            service_type: service_type.into(),
+            worker: None,
+        }
+    }
+
+    pub fn with_worker(mut self, worker: Address) -> Self {
+        self.worker = Some(worker);
+        self
+    }
+
+    pub fn service_type(&self) -> &str {
+        &self.service_type
+    }
+
+    pub fn worker(&self) -> Option<&Address> {
+        self.worker.as_ref()
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct EventSubscriberInfo {
+    route: Route,
+    event_types: Vec<NodeEventType>,
+}
+
+impl EventSubscriberInfo {
+    pub fn new(route: Route, event_types: Vec<NodeEventType>) -> Self {
+        Self { route, event_types }
+    }
+
+    pub fn route(&self) -> &Route {
+        &self.route
+    }
+
+    pub fn event_types(&self) -> &[NodeEventType] {
+        &self.event_types
+    }
+
+    /// Return true if the subscriber wants to receive events of this type
+    pub fn accepts(&self, event_type: NodeEventType) -> bool {
+        self.event_types.is_empty() || self.event_types.contains(&event_type)
+    }
+}
+
 #[derive(Eq, PartialEq, Clone)]
 pub(crate) enum KafkaServiceKind {
     Consumer,
@@ -139,16 +212,33 @@ impl Display for KafkaServiceKind {
 #[derive(Clone)]
 pub(crate) struct KafkaServiceInfo {
     kind: KafkaServiceKind,
+    secure_channel_controller: Option<Arc<dyn KafkaSecureChannelController>>,
 }
 
 impl KafkaServiceInfo {
     pub fn new(kind: KafkaServiceKind) -> Self {
-        Self { kind }
+        Self {
+            kind,
+            secure_channel_controller: None,
+        }
+    }
+
+    /// Keep the controller of the secure channels encrypting the records, to rekey them
+    pub fn with_secure_channel_controller(
+        mut self,
+        secure_channel_controller: Arc<dyn KafkaSecureChannelController>,
+    ) -> Self {
+        self.secure_channel_controller = Some(secure_channel_controller);
+        self
     }
 
     pub fn kind(&self) -> &KafkaServiceKind {
         &self.kind
     }
+
+    pub fn secure_channel_controller(&self) -> Option<&Arc<dyn KafkaSecureChannelController>> {
+        self.secure_channel_controller.as_ref()
+    }
 }
 
 #[derive(Clone)]
@@ -156,6 +246,10 @@ pub(crate) struct InletInfo {
     pub(crate) bind_addr: String,
     pub(crate) worker_addr: Address,
     pub(crate) outlet_route: Route,
+    /// Access control of the inlet listener, reused when the listener is recreated
+    pub(crate) access_control: Arc<dyn IncomingAccessControl>,
+    pub(crate) outlet_target: Option<String>,
+    pub(crate) probe: Option<InletProbe>,
 }
 
 impl InletInfo {
@@ -163,6 +257,8 @@ impl InletInfo {
         bind_addr: &str,
         worker_addr: Option<&Address>,
         outlet_route: &Route,
+        access_control: Arc<dyn IncomingAccessControl>,
+        outlet_target: Option<String>,
     ) -> Self {
         let worker_addr = match worker_addr {
             Some(addr) => addr.clone(),
@@ -172,6 +268,26 @@ impl InletInfo {
             bind_addr: bind_addr.to_owned(),
             worker_addr,
             outlet_route: outlet_route.to_owned(),
+            access_control,
+            outlet_target,
+            probe: None,
+        }
+    }
+}
+
+/// A UDP hole opened by an inlet to the node of its outlet, and the secure channel
+/// created through it
+#[derive(Clone)]
+pub(crate) struct InletHolePunchInfo {
+    pub(crate) puncher: Arc<UdpHolePuncher>,
+    pub(crate) secure_channel: Address,
+}
+
+impl InletHolePunchInfo {
+    pub(crate) fn new(puncher: UdpHolePuncher, secure_channel: Address) -> Self {
+        Self {
+            puncher: Arc::new(puncher),
+            secure_channel,
         }
     }
 }
@@ -180,10 +296,15 @@ impl InletInfo {
 pub struct OutletInfo {
     pub(crate) socket_addr: SocketAddr,
     pub(crate) worker_addr: Address,
+    pub(crate) router: AttributeOutletTargetRouter,
 }
 
 impl OutletInfo {
-    pub(crate) fn new(socket_addr: &SocketAddr, worker_addr: Option<&Address>) -> Self {
+    pub(crate) fn new(
+        socket_addr: &SocketAddr,
+        worker_addr: Option<&Address>,
+        router: AttributeOutletTargetRouter,
+    ) -> Self {
         let worker_addr = match worker_addr {
             Some(addr) => addr.clone(),
             None => Address::from_string(""),
@@ -191,23 +312,34 @@ impl OutletInfo {
         Self {
             socket_addr: *socket_addr,
             worker_addr,
+            router,
         }
     }
 }
 
 #[derive(Default)]
 pub(crate) struct Registry {
-    pub(crate) secure_channels: SecureChannelRegistry,
+    pub(crate) secure_channels: Arc<SecureChannelRegistry>,
     pub(crate) secure_channel_listeners: RegistryOf<Address, SecureChannelListenerInfo>,
     pub(crate) authenticated_services: RegistryOf<Address, AuthenticatedServiceInfo>,
     pub(crate) uppercase_services: RegistryOf<Address, UppercaseServiceInfo>,
     pub(crate) echoer_services: RegistryOf<Address, EchoerServiceInfo>,
+    pub(crate) discard_services: RegistryOf<Address, DiscardServiceInfo>,
+    pub(crate) file_receiver_services: RegistryOf<Address, FileReceiverServiceInfo>,
     pub(crate) kafka_services: RegistryOf<Address, KafkaServiceInfo>,
     pub(crate) hop_services: RegistryOf<Address, HopServiceInfo>,
+    pub(crate) tracer_services: RegistryOf<Address, TracerServiceInfo>,
+    pub(crate) influxdb_token_lease_services: RegistryOf<Address, InfluxDbTokenLeaseServiceInfo>,
     pub(crate) credentials_services: RegistryOf<Address, CredentialsServiceInfo>,
+    pub(crate) custom_services: RegistryOf<Address, CustomServiceInfo>,
     pub(crate) relays: RegistryOf<String, RemoteRelayInfo>,
     pub(crate) inlets: RegistryOf<Alias, InletInfo>,
     pub(crate) outlets: RegistryOf<Alias, OutletInfo>,
+    pub(crate) inlet_hole_punchers: RegistryOf<Alias, InletHolePunchInfo>,
+    /// Punchers started by this node at the request of inlets, by puncher name
+    pub(crate) outlet_hole_punchers: Arc<RegistryOf<String, Arc<UdpHolePuncher>>>,
+    pub(crate) event_subscribers: Arc<RegistryOf<String, EventSubscriberInfo>>,
+    pub(crate) shutdown_hooks: ShutdownHooks,
 }
 
 pub(crate) struct RegistryOf<K, V> {
@@ -9,60 +9,118 @@ use std::time::Duration;
 
 use minicbor::{Decoder, Encode};
 
+use attributes_changes::AuthorizedSessions;
+pub use authorization::{ManagementAccess, ADMIN_ATTRIBUTE};
+pub use diagnostics::add_logs_to_diagnostics;
+pub(crate) use events::NodeEventPublisher;
+use features::FeatureFlags;
+pub use features::{NodeFeature, NODE_FEATURES};
+use idempotency::{Idempotency, IdempotentRequests};
 pub use node_identities::*;
 use ockam::identity::models::CredentialAndPurposeKey;
+use ockam::identity::storage::LmdbStorage;
+use ockam::identity::CredentialsCache;
 use ockam::identity::CredentialsServerModule;
 use ockam::identity::TrustContext;
 use ockam::identity::Vault;
 use ockam::identity::{
-    Credentials, CredentialsServer, Identities, IdentitiesRepository, IdentityAttributesReader,
+    Credentials, CredentialsServer, GroupsRepository, GroupsStorage, Identities,
+    IdentitiesRepository, IdentitiesStorage, IdentityAttributesReader,
 };
-use ockam::identity::{Identifier, SecureChannels};
+use ockam::identity::{Identifier, SecureChannelPeer, SecureChannels};
 use ockam::{
     Address, Context, RelayService, RelayServiceOptions, Result, Routed, TcpTransport, Worker,
 };
 use ockam_abac::expr::{eq, ident, str};
-use ockam_abac::{Action, Env, Expr, PolicyAccessControl, PolicyStorage, Resource};
+use ockam_abac::{Action, Env, ExplainMode, Expr, PolicyAccessControl, PolicyStorage, Resource};
 use ockam_core::api::{Method, RequestHeader, Response};
 use ockam_core::compat::{string::String, sync::Arc};
 use ockam_core::flow_control::FlowControlId;
 use ockam_core::IncomingAccessControl;
-use ockam_core::{AllowAll, AsyncTryClone};
+use ockam_core::{AllowAll, AsyncTryClone, LocalMessage, Route};
 use ockam_multiaddr::MultiAddr;
+use ockam_transport_udp::UdpTransport;
+use peers::PeersStorage;
+use policy_audit::PolicyAuditLog;
+use portals::OutletRoutesStorage;
+pub use pre_warm::{PreWarmProgress, PreWarmStep};
+use probes::ProbeStorage;
+pub use readiness::{NodeService, Readiness, ServicesReadiness};
+use route_selection::RouteSelections;
+use schedules::{ScheduleStorage, ScheduleTasks};
+pub use shutdown::DEFAULT_SHUTDOWN_TIMEOUT;
+use static_routes::StaticRoutesStorage;
+use stats::StatsStorage;
+pub use storage_health::StorageHealth;
+use tokio::sync::OnceCell;
+use usage::UsageStorage;
 
 use crate::bootstrapped_identities_store::BootstrapedIdentityStore;
 use crate::bootstrapped_identities_store::PreTrustedIdentities;
-use crate::cli_state::{CliState, StateDirTrait, StateItemTrait};
+use crate::cli_state::{CliState, StateDirTrait, StateItemTrait, VaultState};
 use crate::cloud::{AuthorityNode, ProjectNode};
 use crate::config::cli::TrustContextConfig;
 use crate::config::lookup::ProjectLookup;
 use crate::error::ApiError;
+use crate::kafka::KafkaMetrics;
 use crate::nodes::connection::{
     Connection, ConnectionBuilder, PlainTcpInstantiator, ProjectInstantiator,
     SecureChannelInstantiator,
 };
 use crate::nodes::models::base::NodeStatus;
 use crate::nodes::models::portal::{OutletList, OutletStatus};
+use crate::nodes::models::secure_channel::ListenerLimits;
 use crate::nodes::models::transport::{TransportMode, TransportType};
 use crate::nodes::models::workers::{WorkerList, WorkerStatus};
 use crate::nodes::registry::KafkaServiceKind;
 use crate::nodes::{InMemoryNode, NODEMANAGER_ADDR};
+use crate::util::policy_explain_mode;
 use crate::DefaultAddress;
 
 use super::registry::Registry;
 
+mod attributes_changes;
+mod authorization;
 pub(crate) mod background_node;
+mod change_history_limits;
+mod clock_skew;
 pub(crate) mod credentials;
+mod credentials_refresh;
+mod diagnostics;
+mod dns;
+mod dry_run;
+mod events;
+mod features;
 mod flow_controls;
+mod groups;
+mod hole_punching;
+mod idempotency;
 pub(crate) mod in_memory_node;
 pub mod message;
 mod node_identities;
 mod node_services;
+mod peer_attributes;
+mod peers;
 mod policy;
-mod portals;
+mod policy_audit;
+pub mod portal_pair;
+mod portal_sessions;
+pub(crate) mod portals;
+mod pre_warm;
+mod probes;
+mod readiness;
 pub mod relay;
+mod route;
+mod route_selection;
+mod schedules;
 mod secure_channel;
+pub(crate) mod shutdown;
+mod static_routes;
+mod stats;
+mod storage_health;
+mod traceroute;
 mod transport;
+mod usage;
 
 const TARGET: &str = "ockam_api::nodemanager::service";
 
@@ -95,12 +153,40 @@ pub struct NodeManager {
     node_name: String,
     api_transport_flow_control_id: FlowControlId,
     pub(crate) tcp_transport: TcpTransport,
+    /// Transport used to open UDP holes to other nodes, created when it is first needed
+    udp_transport: OnceCell<UdpTransport>,
     enable_credential_checks: bool,
     identifier: Identifier,
     pub(crate) secure_channels: Arc<SecureChannels>,
     trust_context: Option<TrustContext>,
     pub(crate) registry: Registry,
     policies: Arc<dyn PolicyStorage>,
+    usage_storage: UsageStorage,
+    peers_storage: PeersStorage,
+    probe_storage: ProbeStorage,
+    schedule_storage: ScheduleStorage,
+    schedule_tasks: ScheduleTasks,
+    stats_storage: StatsStorage,
+    static_routes: StaticRoutesStorage,
+    outlet_routes: OutletRoutesStorage,
+    feature_flags: FeatureFlags,
+    route_selections: RouteSelections,
+    authorized_sessions: AuthorizedSessions,
+    kafka_metrics: KafkaMetrics,
+    management_access: ManagementAccess,
+    /// Compress the messages of the secure channels when the other side agrees on it
+    pub(crate) secure_channel_compression: bool,
+    /// Share the TCP connections to the same peer, only when it was asked for
+    pub(crate) share_tcp_connections: bool,
+    /// Identity used to act as a client when a request does not name one,
+    /// read from the configuration of the node when it starts
+    client_identity: Option<String>,
+    readiness: ServicesReadiness,
+    pre_warm: PreWarmProgress,
+    storage_health: StorageHealth,
+    idempotent_requests: IdempotentRequests,
+    /// Audit of the accesses denied by the policies, when they are explained
+    policy_audit: Option<Arc<PolicyAuditLog>>,
 }
 
 impl NodeManager {
@@ -112,6 +198,11 @@ impl NodeManager {
         self.node_name.clone()
     }
 
+    /// Latencies added by the kafka services of this node
+    pub fn kafka_metrics(&self) -> &KafkaMetrics {
+        &self.kafka_metrics
+    }
+
     pub(super) fn identities(&self) -> Arc<Identities> {
         self.secure_channels.identities()
     }
@@ -120,10 +211,24 @@ impl NodeManager {
         self.identities().repository().clone()
     }
 
+    pub(super) fn groups_repository(&self) -> Arc<dyn GroupsRepository> {
+        self.secure_channels.identities().groups_repository()
+    }
+
     pub(super) fn attributes_reader(&self) -> Arc<dyn IdentityAttributesReader> {
         self.identities_repository().as_attributes_reader()
     }
 
+    /// Return the authenticated peer of the secure channel which delivered a message,
+    /// with its attributes. This can be used by services started on this node to
+    /// implement their own authorization
+    pub async fn secure_channel_peer(
+        &self,
+        local_message: &LocalMessage,
+    ) -> Result<SecureChannelPeer> {
+        SecureChannelPeer::from_local_message(local_message, self.attributes_reader()).await
+    }
+
     pub(super) fn credentials(&self) -> Arc<Credentials> {
         self.identities().credentials()
     }
@@ -136,6 +241,19 @@ impl NodeManager {
         self.secure_channels.identities().vault()
     }
 
+    /// Return the status of this node
+    pub async fn status(&self, ctx: &Context) -> Result<NodeStatus> {
+        Ok(NodeStatus::new(
+            self.node_name(),
+            "Running",
+            ctx.list_workers().await?.len() as u32,
+            std::process::id() as i32,
+        )
+        .with_services(self.readiness().list())
+        .with_pre_warm(self.pre_warm_progress().list())
+        .with_storage(self.storage_health().list()))
+    }
+
     pub fn tcp_transport(&self) -> &TcpTransport {
         &self.tcp_transport
     }
@@ -174,7 +292,7 @@ impl NodeManager {
             authority_identifier,
             authority_multiaddr,
             &self
-                .get_identifier(caller_identity_name)
+                .get_client_identifier(caller_identity_name)
                 .await
                 .into_diagnostic()?,
         )
@@ -192,7 +310,7 @@ impl NodeManager {
             project_identifier,
             project_multiaddr,
             &self
-                .get_identifier(caller_identity_name)
+                .get_client_identifier(caller_identity_name)
                 .await
                 .into_diagnostic()?,
         )
@@ -250,10 +368,7 @@ impl NodeManager {
                 };
                 self.policies.set_policy(r, a, &fallback).await?
             }
-            let policies = self.policies.clone();
-            Ok(Arc::new(PolicyAccessControl::new(
-                policies,
-                self.identities_repository(),
+            Ok(self.authorized_sessions.track(self.policy_access_control(
                 r.clone(),
                 a.clone(),
                 env,
@@ -263,6 +378,29 @@ impl NodeManager {
         }
     }
 
+    /// Return the access control of a resource and action, evaluating their policy in
+    /// an environment. Its denials are explained as configured for the node
+    pub(super) fn policy_access_control(
+        &self,
+        resource: Resource,
+        action: Action,
+        env: Env,
+    ) -> PolicyAccessControl {
+        let access_control = PolicyAccessControl::new(
+            self.policies.clone(),
+            self.identities_repository(),
+            resource,
+            action,
+            env,
+        )
+        .with_groups(self.groups_repository())
+        .with_explain_mode(policy_explain_mode());
+        match &self.policy_audit {
+            Some(audit) => access_control.with_audit(audit.clone()),
+            None => access_control,
+        }
+    }
+
     pub(crate) fn trust_context(&self) -> Result<&TrustContext> {
         self.trust_context
             .as_ref()
@@ -276,6 +414,9 @@ pub struct NodeManagerGeneralOptions {
     pre_trusted_identities: Option<PreTrustedIdentities>,
     start_default_services: bool,
     persistent: bool,
+    management_access: ManagementAccess,
+    secure_channel_compression: bool,
+    share_tcp_connections: bool,
 }
 
 impl NodeManagerGeneralOptions {
@@ -292,8 +433,30 @@ impl NodeManagerGeneralOptions {
             pre_trusted_identities,
             start_default_services,
             persistent,
+            management_access: ManagementAccess::default(),
+            secure_channel_compression: false,
+            share_tcp_connections: false,
         }
     }
+
+    /// Restrict the requests accepted by the node manager
+    pub fn with_management_access(mut self, management_access: ManagementAccess) -> Self {
+        self.management_access = management_access;
+        self
+    }
+
+    /// Propose, and accept, to compress the messages of the secure channels of the node
+    pub fn with_secure_channel_compression(mut self, compression: bool) -> Self {
+        self.secure_channel_compression = compression;
+        self
+    }
+
+    /// Reuse the TCP connection to a peer for all the secure channels and portals going
+    /// to that peer, instead of opening one connection for each of them
+    pub fn with_share_tcp_connections(mut self, share: bool) -> Self {
+        self.share_tcp_connections = share;
+        self
+    }
 }
 
 #[derive(Clone)]
@@ -359,12 +522,20 @@ impl NodeManager {
         let cli_state = general_options.cli_state;
         let node_state = cli_state.nodes.get(&general_options.node_name)?;
 
+        let storage_health = StorageHealth::default();
         let repository: Arc<dyn IdentitiesRepository> =
-            cli_state.identities.identities_repository().await?;
+            Arc::new(IdentitiesStorage::new(Arc::new(storage_health.add(
+                "identities",
+                LmdbStorage::new(cli_state.identities.identities_repository_path()?).await?,
+            ))));
 
         //TODO: fix this.  Either don't require it to be a bootstrappedidentitystore (and use the
         //trait instead),  or pass it from the general_options always.
-        let vault: Vault = node_state.config().vault().await?;
+        let vault_state = VaultState::load(node_state.config().vault_path()?)?;
+        let vault: Vault = vault_state.get().await?;
+        if !vault_state.is_aws() {
+            storage_health.add_file("vault", vault_state.vault_file_path().clone());
+        }
         let identities_repository: Arc<dyn IdentitiesRepository> =
             Arc::new(match general_options.pre_trusted_identities {
                 None => BootstrapedIdentityStore::new(
@@ -375,18 +546,69 @@ impl NodeManager {
             });
 
         debug!("create the secure channels service");
-        let secure_channels = SecureChannels::builder()
+        let mut builder = SecureChannels::builder()
             .with_vault(vault)
             .with_identities_repository(identities_repository.clone())
-            .build();
+            .with_groups_repository(Arc::new(GroupsStorage::new(Arc::new(
+                storage_health.add("groups", node_state.groups_storage().await?),
+            ))))
+            .with_clock_skew_tolerance(clock_skew::clock_skew_tolerance())
+            .with_change_history_limits(change_history_limits::change_history_limits());
+        if let Some(refresh) = credentials_refresh::credentials_refresh() {
+            builder = builder.with_credentials_refresh(refresh);
+        }
+        let secure_channels = builder.build();
+
+        let readiness = ServicesReadiness::default();
+        let policies: Arc<dyn PolicyStorage> =
+            Arc::new(storage_health.add("policies", node_state.policies_storage().await?));
+        readiness.ready(NodeService::Policies);
+        let usage_storage = UsageStorage::new(Arc::new(
+            storage_health.add("usage", node_state.usage_storage().await?),
+        ));
+        let peers_storage = PeersStorage::new(Arc::new(
+            storage_health.add("peers", node_state.peers_storage().await?),
+        ));
+        let probe_storage = ProbeStorage::new(Arc::new(
+            storage_health.add("probes", node_state.probes_storage().await?),
+        ));
+        let schedule_storage = ScheduleStorage::new(Arc::new(
+            storage_health.add("schedules", node_state.schedules_storage().await?),
+        ));
+        let stats_storage = StatsStorage::new(Arc::new(
+            storage_health.add("stats", node_state.stats_storage().await?),
+        ));
+        let static_routes = StaticRoutesStorage::new(Arc::new(
+            storage_health.add("static routes", node_state.static_routes_storage().await?),
+        ));
+        let outlet_routes = OutletRoutesStorage::new(Arc::new(
+            storage_health.add("outlet routes", node_state.outlet_routes_storage().await?),
+        ));
+        let idempotent_requests = IdempotentRequests::new(Arc::new(
+            storage_health.add("idempotency", node_state.idempotency_storage().await?),
+        ));
+        let feature_flags = FeatureFlags::load(Arc::new(
+            storage_health.add("features", node_state.features_storage().await?),
+        ))
+        .await?;
+
+        let tcp_transport = transport_options.tcp_transport;
+        tcp_transport.set_dns_options(dns::dns_options()?);
 
-        let policies: Arc<dyn PolicyStorage> = Arc::new(node_state.policies_storage().await?);
+        let policy_audit = if policy_explain_mode() == ExplainMode::Off {
+            None
+        } else {
+            Some(Arc::new(
+                PolicyAuditLog::start(ctx, node_state.policy_audit_log()).await?,
+            ))
+        };
 
         let mut s = Self {
             cli_state,
             node_name: general_options.node_name,
             api_transport_flow_control_id: transport_options.api_transport_flow_control_id,
-            tcp_transport: transport_options.tcp_transport,
+            tcp_transport,
+            udp_transport: OnceCell::new(),
             enable_credential_checks: trust_options.trust_context_config.is_some()
                 && trust_options
                     .trust_context_config
@@ -399,11 +621,39 @@ impl NodeManager {
             trust_context: None,
             registry: Default::default(),
             policies,
+            usage_storage,
+            peers_storage,
+            probe_storage,
+            schedule_storage,
+            schedule_tasks: Default::default(),
+            stats_storage,
+            static_routes,
+            outlet_routes,
+            feature_flags,
+            route_selections: Default::default(),
+            authorized_sessions: Default::default(),
+            kafka_metrics: Default::default(),
+            management_access: general_options.management_access,
+            secure_channel_compression: general_options.secure_channel_compression,
+            share_tcp_connections: general_options.share_tcp_connections,
+            client_identity: node_state.client_identity()?,
+            readiness,
+            pre_warm: Default::default(),
+            storage_health,
+            idempotent_requests,
+            policy_audit,
         };
 
         if let Some(tc) = trust_options.trust_context_config {
             debug!("configuring trust context");
-            s.configure_trust_context(&tc).await?;
+            let credentials_cache = CredentialsCache::new(
+                s.secure_channels.vault().secure_channel_vault,
+                Arc::new(s.storage_health.add(
+                    "credentials cache",
+                    node_state.credentials_cache_storage().await?,
+                )),
+            );
+            s.configure_trust_context(&tc, credentials_cache).await?;
         }
 
         s.initialize_services(ctx, general_options.start_default_services)
@@ -413,11 +663,16 @@ impl NodeManager {
         Ok(s)
     }
 
-    async fn configure_trust_context(&mut self, tc: &TrustContextConfig) -> Result<()> {
+    async fn configure_trust_context(
+        &mut self,
+        tc: &TrustContextConfig,
+        credentials_cache: CredentialsCache,
+    ) -> Result<()> {
         self.trust_context = Some(
             tc.to_trust_context(
                 self.secure_channels.clone(),
                 Some(self.tcp_transport.async_try_clone().await?),
+                Some(credentials_cache),
             )
             .await?,
         );
@@ -427,47 +682,127 @@ impl NodeManager {
         Ok(())
     }
 
-    async fn initialize_default_services(
-        &self,
+    /// Services started by default on a node
+    fn default_services(&self) -> Vec<NodeService> {
+        let mut services = vec![
+            NodeService::SecureChannelListener,
+            NodeService::Relay,
+            NodeService::Uppercase,
+        ];
+        if self.trust_context().is_ok() {
+            services.push(NodeService::Credentials);
+        }
+        services
+    }
+
+    /// Start the default services of the node, which were declared as starting when the
+    /// node manager was created.
+    ///
+    /// A persistent node starts them in the background, once it can answer requests, so that
+    /// their readiness can be queried while they are starting
+    pub(crate) async fn start_default_services(
+        self: &Arc<Self>,
         ctx: &Context,
-        api_flow_control_id: &FlowControlId,
+        in_background: bool,
     ) -> Result<()> {
-        // Start services
-        ctx.flow_controls()
-            .add_consumer(DefaultAddress::UPPERCASE_SERVICE, api_flow_control_id);
-        self.start_uppercase_service_impl(ctx, DefaultAddress::UPPERCASE_SERVICE.into())
-            .await?;
+        if !in_background {
+            self.initialize_default_services(ctx).await;
+            return Ok(());
+        }
+        let node_manager = self.clone();
+        let ctx = ctx.async_try_clone().await?;
+        tokio::spawn(async move { node_manager.initialize_default_services(&ctx).await });
+        Ok(())
+    }
 
-        RelayService::create(
-            ctx,
-            DefaultAddress::RELAY_SERVICE,
-            RelayServiceOptions::new()
-                .service_as_consumer(api_flow_control_id)
-                .relay_as_consumer(api_flow_control_id),
-        )
-        .await?;
+    /// Start the default services in the order of their dependencies.
+    ///
+    /// A service failing to start is recorded as failed in the node status, and the services
+    /// depending on it are not started, but the other services are still available
+    async fn initialize_default_services(&self, ctx: &Context) {
+        let api_flow_control_id = &self.api_transport_flow_control_id;
+        let mut failed = vec![];
+
+        if let Err(e) = self
+            .start_service(
+                NodeService::SecureChannelListener,
+                self.create_secure_channel_listener(
+                    DefaultAddress::SECURE_CHANNEL_LISTENER.into(),
+                    None, // Not checking identifiers here in favor of credential check
+                    None,
+                    None,
+                    ListenerLimits::default(),
+                    self.secure_channel_compression,
+                    ctx,
+                ),
+            )
+            .await
+        {
+            failed.push(format!("{}: {e}", NodeService::SecureChannelListener));
+        }
 
-        self.create_secure_channel_listener(
-            DefaultAddress::SECURE_CHANNEL_LISTENER.into(),
-            None, // Not checking identifiers here in favor of credential check
-            None,
-            None,
-            ctx,
-        )
-        .await?;
+        if let Err(e) = self
+            .start_service(
+                NodeService::Relay,
+                RelayService::create(
+                    ctx,
+                    DefaultAddress::RELAY_SERVICE,
+                    RelayServiceOptions::new()
+                        .service_as_consumer(api_flow_control_id)
+                        .relay_as_consumer(api_flow_control_id),
+                ),
+            )
+            .await
+        {
+            failed.push(format!("{}: {e}", NodeService::Relay));
+        }
+
+        ctx.flow_controls()
+            .add_consumer(DefaultAddress::UPPERCASE_SERVICE, api_flow_control_id);
+        if let Err(e) = self
+            .start_service(
+                NodeService::Uppercase,
+                self.start_uppercase_service_impl(
+                    ctx,
+                    DefaultAddress::UPPERCASE_SERVICE.into(),
+                    None,
+                ),
+            )
+            .await
+        {
+            failed.push(format!("{}: {e}", NodeService::Uppercase));
+        }
 
         // If we've been configured with a trust context, we can start Credential Exchange service
         if let Ok(tc) = self.trust_context() {
-            self.start_credentials_service_impl(
-                ctx,
-                tc.clone(),
-                DefaultAddress::CREDENTIALS_SERVICE.into(),
-                false,
-            )
-            .await?;
+            if let Err(e) = self
+                .start_service(
+                    NodeService::Credentials,
+                    self.start_credentials_service_impl(
+                        ctx,
+                        tc.clone(),
+                        DefaultAddress::CREDENTIALS_SERVICE.into(),
+                        false,
+                    ),
+                )
+                .await
+            {
+                failed.push(format!("{}: {e}", NodeService::Credentials));
+            }
         }
 
-        Ok(())
+        if failed.is_empty() {
+            info!(
+                "the default services of the node {} are ready",
+                self.node_name
+            );
+        } else {
+            warn!(
+                "some default services of the node {} failed to start: {}",
+                self.node_name,
+                failed.join(", ")
+            );
+        }
     }
 
     async fn initialize_services(
@@ -477,17 +812,27 @@ impl NodeManager {
     ) -> Result<()> {
         let api_flow_control_id = self.api_transport_flow_control_id.clone();
 
+        // The default services are started once the node manager is created,
+        // see `start_default_services`
         if start_default_services {
-            self.initialize_default_services(ctx, &api_flow_control_id)
-                .await?;
+            self.readiness.starting(&self.default_services());
         }
 
+        self.start_usage_recorder(ctx).await?;
+        self.start_inlet_events_worker(ctx).await?;
+        self.start_key_usage_alerts(ctx).await?;
+        self.start_attributes_changes_processor(ctx).await?;
+
         // Always start the echoer service as ockam_api::Medic assumes it will be
         // started unconditionally on every node. It's used for liveliness checks.
         ctx.flow_controls()
             .add_consumer(DefaultAddress::ECHO_SERVICE, &api_flow_control_id);
-        self.start_echoer_service_impl(ctx, DefaultAddress::ECHO_SERVICE.into())
-            .await?;
+        self.readiness.starting(&[NodeService::Echoer]);
+        self.start_service(
+            NodeService::Echoer,
+            self.start_echoer_service_impl(ctx, DefaultAddress::ECHO_SERVICE.into(), None),
+        )
+        .await?;
 
         Ok(())
     }
@@ -501,9 +846,16 @@ impl NodeManager {
         credential: Option<CredentialAndPurposeKey>,
         timeout: Option<Duration>,
     ) -> Result<Connection> {
+        // the pre-warm connects to the project with the default identity
+        if identifier.is_none() && authorized.is_none() && credential.is_none() {
+            if let Some(connection) = self.take_pre_warmed_connection(addr, timeout).await {
+                debug!("reusing the pre-warmed connection to {addr}");
+                return Ok(connection);
+            }
+        }
         let identifier = match identifier {
             Some(identifier) => identifier,
-            None => self.get_identifier(None).await?,
+            None => self.get_client_identifier(None).await?,
         };
         let authorized = authorized.map(|authorized| vec![authorized]);
         self.connect(ctx, addr, identifier, authorized, credential, timeout)
@@ -522,7 +874,8 @@ impl NodeManager {
         timeout: Option<Duration>,
     ) -> Result<Connection> {
         debug!(?timeout, "connecting to {}", &addr);
-        let connection = ConnectionBuilder::new(addr.clone())
+        let addr = self.expand_static_routes(addr).await?;
+        let connection = ConnectionBuilder::new(addr)
             .instantiate(
                 ctx.clone(),
                 self,
@@ -544,11 +897,26 @@ impl NodeManager {
         Ok(connection)
     }
 
+    /// Project this node is associated with, if any
+    pub(crate) fn node_project(&self) -> Result<Option<ProjectLookup>> {
+        Ok(self.cli_state.nodes.get(&self.node_name)?.project()?)
+    }
+
+    /// Return the route and the identifier of a project.
+    /// The project of this node is resolved with the information stored with the node,
+    /// the other projects with the information stored in the projects state
     pub(crate) async fn resolve_project(&self, name: &str) -> Result<(MultiAddr, Identifier)> {
-        let projects = ProjectLookup::from_state(self.cli_state.projects.list()?)
-            .await
-            .map_err(|e| ApiError::core(format!("Cannot load projects: {:?}", e)))?;
-        if let Some(info) = projects.get(name) {
+        let node_project = self.node_project()?.filter(|project| {
+            project.name == name && project.node_route.is_some() && project.identity_id.is_some()
+        });
+        let info = match node_project {
+            Some(project) => Some(project),
+            None => ProjectLookup::from_state(self.cli_state.projects.list()?)
+                .await
+                .map_err(|e| ApiError::core(format!("Cannot load projects: {:?}", e)))?
+                .remove(name),
+        };
+        if let Some(info) = info {
             let node_route = info
                 .node_route
                 .as_ref()
@@ -574,6 +942,7 @@ impl NodeManagerWorker {
         ctx: &mut Context,
         req: &RequestHeader,
         dec: &mut Decoder<'_>,
+        return_route: Route,
     ) -> Result<Vec<u8>> {
         debug! {
             target: TARGET,
@@ -595,17 +964,13 @@ impl NodeManagerWorker {
         let r = match (method, path_segments.as_slice()) {
             // ==*== Basic node information ==*==
             // TODO: create, delete, destroy remote nodes
-            (Get, ["node"]) => {
-                let node_name = &self.node_manager.node_name();
-                Response::ok(req)
-                    .body(NodeStatus::new(
-                        node_name,
-                        "Running",
-                        ctx.list_workers().await?.len() as u32,
-                        std::process::id() as i32,
-                    ))
-                    .to_vec()?
-            }
+            (Get, ["node"]) => Response::ok(req)
+                .body(self.node_manager.status(ctx).await?)
+                .to_vec()?,
+
+            (Delete, ["node", "dry_run"]) => encode_response(self.dry_run_delete_node(req).await)?,
+            (Get, ["node", "clock_skew"]) => self.get_clock_skews(req).to_vec()?,
+            (Get, ["node", "credentials_refresh"]) => self.get_credentials_refresh(req).to_vec()?,
 
             // ==*== Tcp Connection ==*==
             (Get, ["node", "tcp", "connection"]) => self.get_tcp_connections(req).await.to_vec()?,
@@ -657,6 +1022,9 @@ impl NodeManagerWorker {
             (Post, ["node", "secure_channel_listener"]) => {
                 encode_response(self.create_secure_channel_listener(req, dec, ctx).await)?
             }
+            (Put, ["node", "secure_channel_listener"]) => {
+                encode_response(self.update_secure_channel_listener_limits(req, dec).await)?
+            }
             (Delete, ["node", "secure_channel_listener"]) => self
                 .delete_secure_channel_listener(ctx, req, dec)
                 .await?
@@ -675,12 +1043,30 @@ impl NodeManagerWorker {
             (Post, ["node", "services", DefaultAddress::ECHO_SERVICE]) => {
                 encode_response(self.start_echoer_service(ctx, req, dec).await)?
             }
+            (Post, ["node", "services", DefaultAddress::DISCARD_SERVICE]) => {
+                encode_response(self.start_discard_service(ctx, req, dec).await)?
+            }
+            (Post, ["node", "services", DefaultAddress::FILE_RECEIVER_SERVICE]) => {
+                encode_response(self.start_file_receiver_service(ctx, req, dec).await)?
+            }
+            (Post, ["node", "services", DefaultAddress::TRACER_SERVICE]) => {
+                encode_response(self.start_tracer_service(ctx, req, dec).await)?
+            }
+            (Post, ["node", "services", DefaultAddress::INFLUXDB_TOKEN_LEASE]) => {
+                encode_response(self.start_influxdb_token_lease_manager(ctx, req, dec).await)?
+            }
             (Post, ["node", "services", DefaultAddress::HOP_SERVICE]) => {
                 encode_response(self.start_hop_service(ctx, req, dec).await)?
             }
             (Post, ["node", "services", DefaultAddress::CREDENTIALS_SERVICE]) => {
                 encode_response(self.start_credentials_service(ctx, req, dec).await)?
             }
+            (Post, ["node", "services", "custom"]) => {
+                encode_response(self.register_custom_service(ctx, req, dec).await)?
+            }
+            (Delete, ["node", "services", "custom"]) => {
+                encode_response(self.unregister_custom_service(ctx, req, dec).await)?
+            }
             (Post, ["node", "services", DefaultAddress::KAFKA_OUTLET]) => {
                 self.start_kafka_outlet_service(ctx, req, dec).await?
             }
@@ -730,38 +1116,149 @@ impl NodeManagerWorker {
             // ==*== Inlets & Outlets ==*==
             (Get, ["node", "inlet"]) => self.get_inlets(req).await.to_vec()?,
             (Get, ["node", "inlet", alias]) => encode_response(self.show_inlet(req, alias).await)?,
+            (Get, ["node", "inlet", alias, "probes"]) => {
+                encode_response(self.get_inlet_probe_results(req, alias).await)?
+            }
             (Get, ["node", "outlet"]) => self.get_outlets(req).await.to_vec()?,
             (Get, ["node", "outlet", alias]) => {
                 encode_response(self.show_outlet(req, alias).await)?
             }
             (Post, ["node", "inlet"]) => encode_response(self.create_inlet(req, dec, ctx).await)?,
+            (Put, ["node", "inlet", alias]) => {
+                encode_response(self.rebind_inlet(ctx, req, alias, dec.decode()?).await)?
+            }
             (Post, ["node", "outlet"]) => {
                 encode_response(self.create_outlet(ctx, req, dec.decode()?).await)?
             }
+            (Get, ["node", "outlet", alias, "routes"]) => {
+                encode_response(self.show_outlet_routes(req, alias).await)?
+            }
+            (Put, ["node", "outlet", alias, "routes"]) => {
+                encode_response(self.set_outlet_routes(req, alias, dec.decode()?).await)?
+            }
             (Delete, ["node", "outlet", alias]) => {
                 encode_response(self.delete_outlet(req, alias).await)?
             }
+            (Delete, ["node", "inlet", alias, "dry_run"]) => {
+                encode_response(self.dry_run_delete_inlet(req, alias).await)?
+            }
             (Delete, ["node", "inlet", alias]) => {
-                encode_response(self.delete_inlet(req, alias).await)?
+                encode_response(self.delete_inlet(ctx, req, alias).await)?
             }
             (Delete, ["node", "portal"]) => todo!(),
+            (Get, ["node", "portals", "sessions"]) => {
+                self.get_portal_sessions(req).await.to_vec()?
+            }
+            (Delete, ["node", "portals", "sessions", address]) => {
+                encode_response(self.delete_portal_session(req, address).await)?
+            }
 
             // ==*== Flow Controls ==*==
             (Post, ["node", "flow_controls", "add_consumer"]) => {
                 encode_response(self.add_consumer(ctx, req, dec))?
             }
 
-            // ==*== Workers ==*==
-            (Get, ["node", "workers"]) => {
-                let workers = ctx.list_workers().await?;
+            // ==*== Routes ==*==
+            (Get, ["node", "resolve_route"]) => {
+                encode_response(self.resolve_route(ctx, req, dec).await)?
+            }
+            (Post, ["node", "select_route"]) => {
+                encode_response(self.select_route(ctx, req, dec).await)?
+            }
+            (Post, ["node", "traceroute"]) => {
+                encode_response(self.trace_route(ctx, req, dec).await)?
+            }
 
-                let mut list = Vec::new();
-                workers
-                    .iter()
-                    .for_each(|addr| list.push(WorkerStatus::new(addr.address())));
+            // ==*== Attributes ==*==
+            (Post, ["node", "attributes", "fetch"]) => {
+                encode_response(self.fetch_attributes(ctx, req, dec).await)?
+            }
 
+            // ==*== Schedules ==*==
+            (Get, ["node", "schedules"]) => encode_response(self.list_schedules(req).await)?,
+            (Post, ["node", "schedules"]) => encode_response(self.create_schedule(req, dec).await)?,
+            (Delete, ["node", "schedules", name]) => {
+                encode_response(self.delete_schedule(req, name).await)?
+            }
+            (Post, ["node", "schedules", name, "run"]) => {
+                encode_response(self.run_schedule(ctx, req, name).await)?
+            }
+
+            // ==*== Static routes ==*==
+            (Get, ["node", "routes"]) => encode_response(self.list_static_routes(req).await)?,
+            (Get, ["node", "routes", name]) => {
+                encode_response(self.get_static_route(req, name).await)?
+            }
+            (Put, ["node", "routes", name]) => {
+                encode_response(self.set_static_route(req, name, dec).await)?
+            }
+            (Delete, ["node", "routes", name]) => {
+                encode_response(self.delete_static_route(req, name).await)?
+            }
+
+            // ==*== Groups ==*==
+            (Get, ["node", "groups"]) => encode_response(self.list_groups(req).await)?,
+            (Get, ["node", "groups", group]) => encode_response(self.get_group(req, group).await)?,
+            (Put, ["node", "groups", group]) => {
+                encode_response(self.create_group(req, group).await)?
+            }
+            (Delete, ["node", "groups", group]) => {
+                encode_response(self.delete_group(req, group).await)?
+            }
+            (Put, ["node", "groups", group, "members", member]) => {
+                encode_response(self.add_group_member(req, group, member).await)?
+            }
+            (Delete, ["node", "groups", group, "members", member]) => {
+                encode_response(self.remove_group_member(req, group, member).await)?
+            }
+
+            // ==*== Bandwidth usage ==*==
+            (Get, ["node", "usage"]) => encode_response(self.get_bandwidth_usage(req, dec).await)?,
+
+            // ==*== Peers ==*==
+            (Get, ["node", "peers"]) => encode_response(self.list_peers(req, dec).await)?,
+            (Get, ["node", "peers", identifier]) => {
+                encode_response(self.get_peer(req, identifier).await)?
+            }
+
+            // ==*== Statistics ==*==
+            (Get, ["node", "stats"]) => encode_response(self.get_node_stats(req, dec).await)?,
+
+            // ==*== Features ==*==
+            (Get, ["node", "features"]) => encode_response(self.list_features(req).await)?,
+            (Get, ["node", "features", name]) => {
+                encode_response(self.get_feature(req, name).await)?
+            }
+            (Put, ["node", "features", name]) => {
+                encode_response(self.set_feature(req, name, dec).await)?
+            }
+            (Delete, ["node", "features", name]) => {
+                encode_response(self.reset_feature(req, name).await)?
+            }
+
+            // ==*== Diagnostics ==*==
+            (Get, ["node", "diagnostics"]) => {
+                encode_response(self.get_diagnostics(ctx, req, dec).await)?
+            }
+
+            // ==*== Events ==*==
+            (Get, ["node", "events"]) => self.list_event_subscriptions(req).await.to_vec()?,
+            (Post, ["node", "events"]) => {
+                encode_response(self.subscribe_to_events(req, dec, return_route).await)?
+            }
+            (Delete, ["node", "events", id]) => {
+                encode_response(self.unsubscribe_from_events(req, id).await)?
+            }
+
+            // ==*== Workers ==*==
+            (Get, ["node", "workers"]) => {
+                let workers = ctx.list_workers_info().await?;
+                let list = workers.into_iter().map(WorkerStatus::from).collect();
                 Response::ok(req).body(WorkerList::new(list)).to_vec()?
             }
+            (Post, ["policy", resource, action, "dry_run"]) => {
+                encode_response(self.dry_run_set_policy(req, resource, action, dec).await)?
+            }
             (Post, ["policy", resource, action]) => encode_response(
                 self.node_manager
                     .add_policy(resource, action, req, dec)
@@ -775,6 +1272,9 @@ impl NodeManagerWorker {
                 .get_policy(req, resource, action)
                 .await?
                 .either(Response::to_vec, Response::to_vec)?,
+            (Delete, ["policy", resource, action, "dry_run"]) => {
+                encode_response(self.dry_run_delete_policy(req, resource, action).await)?
+            }
             (Delete, ["policy", resource, action]) => {
                 encode_response(self.node_manager.del_policy(req, resource, action).await)?
             }
@@ -812,7 +1312,57 @@ impl Worker for NodeManagerWorker {
             }
         };
 
-        let r = match self.handle_request(ctx, &req, &mut dec).await {
+        if !self
+            .node_manager
+            .is_request_authorized(&req, msg.local_message())
+            .await?
+        {
+            let r = Response::forbidden(&req, "The request is not authorized").to_vec()?;
+            return ctx.send(msg.return_route(), r).await;
+        }
+
+        // A create request retried with the same idempotency key returns its first response
+        let idempotency_key = req
+            .idempotency_key()
+            .filter(|_| IdempotentRequests::is_create_request(&req))
+            .map(|key| key.to_string());
+        let body_position = dec.position();
+        if let Some(key) = &idempotency_key {
+            if let Idempotency::Replay(r) = self
+                .node_manager
+                .idempotent_requests
+                .check(
+                    &req,
+                    msg.local_message(),
+                    key,
+                    &msg.as_body()[body_position..],
+                )
+                .await?
+            {
+                return ctx.send(msg.return_route(), r).await;
+            }
+        }
+
+        // The request is always processed until its end, in order not to leave a partial
+        // state. The handlers which can wait for a long time stop waiting at the deadline
+        let started_at = std::time::Instant::now();
+        let r = self
+            .handle_request(ctx, &req, &mut dec, msg.return_route())
+            .await;
+        if let Some(deadline) = req.deadline() {
+            if started_at.elapsed() > deadline {
+                warn! {
+                    target: TARGET,
+                    re       = %req.id(),
+                    method   = ?req.method(),
+                    path     = %req.path(),
+                    deadline = ?deadline,
+                    "request deadline exceeded"
+                }
+            }
+        }
+
+        let r = match r {
             Ok(r) => r,
             Err(err) => {
                 error! {
@@ -828,6 +1378,18 @@ impl Worker for NodeManagerWorker {
                     .to_vec()?
             }
         };
+        if let Some(key) = &idempotency_key {
+            self.node_manager
+                .idempotent_requests
+                .record(
+                    &req,
+                    msg.local_message(),
+                    key,
+                    &msg.as_body()[body_position..],
+                    &r,
+                )
+                .await?;
+        }
         debug! {
             target: TARGET,
             re     = %req.id(),
@@ -0,0 +1,350 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Mutex, Weak};
+
+use ockam::identity::{
+    AttributesChangesListener, Identifier, IdentitySecureChannelLocalInfo, SecureChannels,
+};
+use ockam::{Address, Context, Processor, Result};
+use ockam_abac::PolicyAccessControl;
+use ockam_core::compat::sync::Arc;
+use ockam_core::{async_trait, IncomingAccessControl, RelayMessage};
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+use crate::nodes::models::events::NodeEventType;
+use crate::nodes::registry::SecureChannelRegistry;
+
+use super::{NodeEventPublisher, NodeManager};
+
+/// Address of the processor re-evaluating the policies when attributes change
+const ATTRIBUTES_CHANGES_ADDRESS: &str = "attributes_changes";
+
+/// Policy access control remembering the identities it authorized, and the routes of
+/// their messages, so that their authorization can be checked again when their attributes change
+#[derive(Debug)]
+pub(crate) struct SessionsAccessControl {
+    policy: PolicyAccessControl,
+    /// Addresses of the return routes of the authorized messages, for each identity.
+    /// They contain the encryptors of the secure channels the messages came through
+    sessions: Mutex<BTreeMap<Identifier, BTreeSet<Address>>>,
+}
+
+impl SessionsAccessControl {
+    /// Check again the authorization of an identity, if it was authorized before.
+    /// Return the addresses of its sessions if that authorization does not hold anymore
+    async fn reevaluate(&self, identifier: &Identifier) -> Result<BTreeSet<Address>> {
+        if !self.sessions.lock().unwrap().contains_key(identifier)
+            || self.policy.is_identity_authorized(identifier).await?
+        {
+            return Ok(BTreeSet::new());
+        }
+        Ok(self
+            .sessions
+            .lock()
+            .unwrap()
+            .remove(identifier)
+            .unwrap_or_default())
+    }
+}
+
+#[async_trait]
+impl IncomingAccessControl for SessionsAccessControl {
+    async fn is_authorized(&self, msg: &RelayMessage) -> Result<bool> {
+        let identifier = match IdentitySecureChannelLocalInfo::find_info(msg.local_message()) {
+            Ok(info) => info.their_identity_id(),
+            Err(_) => return self.policy.is_authorized(msg).await,
+        };
+        let authorized = self.policy.is_identity_authorized(&identifier).await?;
+        if authorized {
+            self.sessions
+                .lock()
+                .unwrap()
+                .entry(identifier)
+                .or_default()
+                .extend(msg.return_route().iter().cloned());
+        }
+        Ok(authorized)
+    }
+}
+
+/// Access controls created by the node for its outlets, inlets and services.
+///
+/// They are only referenced weakly so that the access controls of deleted
+/// portals are dropped with their workers
+#[derive(Clone, Default)]
+pub(crate) struct AuthorizedSessions {
+    access_controls: Arc<Mutex<Vec<Weak<SessionsAccessControl>>>>,
+}
+
+impl AuthorizedSessions {
+    /// Return an access control for a policy, re-evaluated when attributes change
+    pub(crate) fn track(&self, policy: PolicyAccessControl) -> Arc<SessionsAccessControl> {
+        let access_control = Arc::new(SessionsAccessControl {
+            policy,
+            sessions: Default::default(),
+        });
+        let mut access_controls = self.access_controls.lock().unwrap();
+        access_controls.retain(|a| a.strong_count() > 0);
+        access_controls.push(Arc::downgrade(&access_control));
+        access_control
+    }
+
+    /// Return the addresses of the sessions of an identity which lost the authorization
+    /// given by an access control. There are none if all its authorizations still hold
+    async fn revoked_sessions(&self, identifier: &Identifier) -> Result<BTreeSet<Address>> {
+        let access_controls: Vec<_> = self
+            .access_controls
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect();
+        let mut revoked = BTreeSet::new();
+        for access_control in access_controls {
+            revoked.extend(access_control.reevaluate(identifier).await?);
+        }
+        Ok(revoked)
+    }
+}
+
+/// Forward the identities whose attributes changed to the [`AttributesChangesProcessor`]
+struct AttributesChangesSender(mpsc::UnboundedSender<Identifier>);
+
+impl AttributesChangesListener for AttributesChangesSender {
+    fn attributes_changed(&self, identity: &Identifier) {
+        // the processor is only stopped with the node
+        let _ = self.0.send(identity.clone());
+    }
+}
+
+/// This processor re-evaluates the policies authorizing an identity when its attributes
+/// change. If an authorization does not hold anymore, the secure channels which were used
+/// by that identity for the revoked sessions are closed, which terminates the connections
+/// going through them. The other secure channels of that identity are kept open
+struct AttributesChangesProcessor {
+    changes: mpsc::UnboundedReceiver<Identifier>,
+    sessions: AuthorizedSessions,
+    secure_channels: Arc<SecureChannels>,
+    registry: Arc<SecureChannelRegistry>,
+    events: NodeEventPublisher,
+}
+
+impl AttributesChangesProcessor {
+    async fn revoke(
+        &self,
+        ctx: &Context,
+        identifier: &Identifier,
+        sessions: &BTreeSet<Address>,
+    ) -> Result<()> {
+        let channels = self
+            .secure_channels
+            .secure_channel_registry()
+            .get_channel_list()
+            .into_iter()
+            .filter(|channel| {
+                channel.their_id() == identifier
+                    && sessions.contains(channel.encryptor_messaging_address())
+            });
+        for channel in channels {
+            let address = channel.encryptor_messaging_address();
+            debug!(%identifier, %address, "closing a secure channel");
+            self.secure_channels
+                .stop_secure_channel(ctx, address)
+                .await?;
+            self.registry.remove_by_addr(address).await;
+            self.events
+                .publish(
+                    ctx,
+                    NodeEventType::SecureChannelClosed,
+                    address.to_string(),
+                    None,
+                )
+                .await;
+        }
+        self.events
+            .publish(
+                ctx,
+                NodeEventType::AuthorizationRevoked,
+                identifier.to_string(),
+                None,
+            )
+            .await;
+        Ok(())
+    }
+}
+
+#[ockam_core::processor]
+impl Processor for AttributesChangesProcessor {
+    type Context = Context;
+
+    async fn process(&mut self, ctx: &mut Context) -> Result<bool> {
+        let identifier = match self.changes.recv().await {
+            Some(identifier) => identifier,
+            None => return Ok(false),
+        };
+        match self.sessions.revoked_sessions(&identifier).await {
+            Ok(sessions) if sessions.is_empty() => {}
+            Ok(sessions) => {
+                info!(%identifier, "the attributes changed, the authorization is revoked");
+                if let Err(err) = self.revoke(ctx, &identifier, &sessions).await {
+                    warn!(%identifier, %err, "cannot close the secure channels");
+                }
+            }
+            Err(err) => warn!(%identifier, %err, "cannot re-evaluate the policies"),
+        }
+        Ok(true)
+    }
+}
+
+impl NodeManager {
+    /// Start re-evaluating the policies of the node when the attributes of an identity change
+    pub(super) async fn start_attributes_changes_processor(&self, ctx: &Context) -> Result<()> {
+        let (sender, changes) = mpsc::unbounded_channel();
+        self.identities_repository()
+            .subscribe_to_attributes_changes(Arc::new(AttributesChangesSender(sender)));
+        let processor = AttributesChangesProcessor {
+            changes,
+            sessions: self.authorized_sessions.clone(),
+            secure_channels: self.secure_channels.clone(),
+            registry: self.registry.secure_channels.clone(),
+            events: self.event_publisher(),
+        };
+        ctx.start_processor(ATTRIBUTES_CHANGES_ADDRESS, processor)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::time::Duration;
+    use ockam::identity::utils::now;
+    use ockam::identity::{AttributesEntry, SecureChannelListenerOptions, SecureChannelOptions};
+    use ockam_abac::expr::{eq, ident, str};
+    use ockam_abac::{Action, Env, Resource};
+    use ockam_core::compat::collections::BTreeMap;
+    use ockam_core::{route, LocalMessage, TransportMessage};
+
+    use crate::test_utils::start_manager_for_tests;
+
+    #[ockam_macros::test]
+    async fn test_reevaluate_authorizations(context: &mut Context) -> Result<()> {
+        let handler = start_manager_for_tests(context).await?;
+        let node_manager = &handler.node_manager;
+        let repository = node_manager.identities_repository();
+
+        let (resource, action) = (Resource::new("db"), Action::new("handle_message"));
+        let admin_only = eq([ident("subject.role"), str("admin")]);
+        node_manager
+            .policies
+            .set_policy(&resource, &action, &admin_only)
+            .await?;
+        let sessions = AuthorizedSessions::default();
+        let access_control = sessions.track(PolicyAccessControl::new(
+            node_manager.policies.clone(),
+            repository.clone(),
+            resource,
+            action,
+            Env::new(),
+        ));
+
+        let subject = node_manager
+            .identities()
+            .identities_creation()
+            .create_identity()
+            .await?;
+        let subject = subject.identifier();
+        let role = |role: &str| {
+            let attributes = BTreeMap::from([(b"role".to_vec(), role.as_bytes().to_vec())]);
+            Ok::<_, ockam_core::Error>(AttributesEntry::new(attributes, now()?, None, None))
+        };
+        repository.put_attributes(subject, role("admin")?).await?;
+        let message = RelayMessage::new(
+            "channel".into(),
+            "db".into(),
+            LocalMessage::new(
+                TransportMessage::v1(route!["db"], route!["channel"], vec![]),
+                IdentitySecureChannelLocalInfo::mark(vec![], subject.clone())?,
+            ),
+        );
+        assert!(access_control.is_authorized(&message).await?);
+        assert!(sessions.revoked_sessions(subject).await?.is_empty());
+
+        // the authorization is revoked once the identity is not an admin anymore,
+        // for the sessions which came through the secure channel of the message
+        repository.put_attributes(subject, role("guest")?).await?;
+        assert_eq!(
+            sessions.revoked_sessions(subject).await?,
+            BTreeSet::from(["channel".into()])
+        );
+        assert!(access_control.sessions.lock().unwrap().is_empty());
+        assert!(sessions.revoked_sessions(subject).await?.is_empty());
+
+        context.stop().await
+    }
+
+    #[ockam_macros::test]
+    async fn test_revoke_closes_affected_channels(context: &mut Context) -> Result<()> {
+        let handler = start_manager_for_tests(context).await?;
+        let node_manager = &handler.node_manager;
+        let secure_channels = &handler.secure_channels;
+
+        // the subject establishes two secure channels with the node
+        let subject = node_manager
+            .identities()
+            .identities_creation()
+            .create_identity()
+            .await?;
+        let subject = subject.identifier();
+        secure_channels
+            .create_secure_channel_listener(
+                context,
+                &handler.identifier,
+                "listener",
+                SecureChannelListenerOptions::new(),
+            )
+            .await?;
+        for _ in 0..2 {
+            secure_channels
+                .create_secure_channel(
+                    context,
+                    subject,
+                    route!["listener"],
+                    SecureChannelOptions::new(),
+                )
+                .await?;
+        }
+        let channels_of_subject = || {
+            secure_channels
+                .secure_channel_registry()
+                .get_channel_list()
+                .into_iter()
+                .filter(|c| c.their_id() == subject)
+                .map(|c| c.encryptor_messaging_address().clone())
+                .collect::<Vec<_>>()
+        };
+        while channels_of_subject().len() < 2 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let channels = channels_of_subject();
+
+        // only the secure channel used by a revoked session is closed
+        let (_, changes) = mpsc::unbounded_channel();
+        let processor = AttributesChangesProcessor {
+            changes,
+            sessions: AuthorizedSessions::default(),
+            secure_channels: secure_channels.clone(),
+            registry: node_manager.registry.secure_channels.clone(),
+            events: node_manager.event_publisher(),
+        };
+        processor
+            .revoke(context, subject, &BTreeSet::from([channels[0].clone()]))
+            .await?;
+        while context.list_workers().await?.contains(&channels[0]) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(context.list_workers().await?.contains(&channels[1]));
+
+        context.stop().await
+    }
+}
@@ -0,0 +1,312 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use ockam::identity::utils::now;
+use ockam::identity::IdentitySecureChannelLocalInfo;
+use ockam::Result;
+use ockam_core::api::{Method, RequestHeader};
+use ockam_core::{LocalMessage, Route};
+use ockam_transport_tcp::TcpSenderInfo;
+use serde::{Deserialize, Serialize};
+
+use super::NodeManager;
+
+/// Attribute which an identity must hold, with the value `true`, to manage a node
+/// over a secure channel
+pub const ADMIN_ATTRIBUTE: &str = "ockam-admin";
+
+/// Which requests the node manager accepts
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ManagementAccess {
+    /// Every request reaching the node manager is accepted
+    Open,
+    /// Requests must be sent over a secure channel by an identity holding the admin attribute,
+    /// or be local requests: requests sent without a secure channel over a TCP connection from
+    /// a loopback address, like the requests of the command line, or sent by the workers of
+    /// the node itself
+    #[default]
+    AdminOrLocal,
+    /// Requests must be sent over a secure channel by an identity holding the admin attribute
+    Admin,
+}
+
+impl Display for ManagementAccess {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ManagementAccess::Open => "open",
+            ManagementAccess::AdminOrLocal => "admin-or-local",
+            ManagementAccess::Admin => "admin",
+        })
+    }
+}
+
+impl FromStr for ManagementAccess {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "open" => Ok(ManagementAccess::Open),
+            "admin-or-local" => Ok(ManagementAccess::AdminOrLocal),
+            "admin" => Ok(ManagementAccess::Admin),
+            _ => Err(format!(
+                "unknown management access '{s}', expected 'open', 'admin-or-local' or 'admin'"
+            )),
+        }
+    }
+}
+
+impl NodeManager {
+    /// Which requests this node manager accepts
+    pub fn management_access(&self) -> ManagementAccess {
+        self.management_access
+    }
+
+    /// Return true if a request received by the node manager can be handled.
+    /// The status of the node is always returned, so that it can be checked that the node is up
+    pub(super) async fn is_request_authorized(
+        &self,
+        req: &RequestHeader,
+        msg: &LocalMessage,
+    ) -> Result<bool> {
+        self.is_request_authorized_with(self.management_access, req, msg)
+            .await
+    }
+
+    async fn is_request_authorized_with(
+        &self,
+        management_access: ManagementAccess,
+        req: &RequestHeader,
+        msg: &LocalMessage,
+    ) -> Result<bool> {
+        let is_status_request = matches!(req.method(), Some(Method::Get)) && req.path() == "/node";
+        if is_status_request {
+            return Ok(true);
+        }
+        // the diagnostics of a node expose its whole configuration, they are never open
+        let is_diagnostics_request =
+            matches!(req.method(), Some(Method::Get)) && req.path() == "/node/diagnostics";
+        let management_access =
+            if management_access == ManagementAccess::Open && is_diagnostics_request {
+                ManagementAccess::AdminOrLocal
+            } else {
+                management_access
+            };
+        if management_access == ManagementAccess::Open {
+            return Ok(true);
+        }
+        let their_identity_id = match IdentitySecureChannelLocalInfo::find_info(msg) {
+            Ok(info) => info.their_identity_id(),
+            Err(_) => {
+                let is_authorized = management_access == ManagementAccess::AdminOrLocal
+                    && is_local_request(
+                        &msg.transport().return_route,
+                        &self.tcp_transport.registry().get_all_sender_workers(),
+                    );
+                if !is_authorized {
+                    warn!(return_route = %msg.transport().return_route, "rejecting a request which was not received over a secure channel");
+                }
+                return Ok(is_authorized);
+            }
+        };
+        // the admin attribute may be restricted to a validity period or a schedule
+        let now = now()?;
+        let is_admin = self
+            .identities_repository()
+            .get_attributes(&their_identity_id)
+            .await?
+            .filter(|entry| entry.is_active(ADMIN_ATTRIBUTE.as_bytes(), now))
+            .and_then(|entry| entry.attrs().get(ADMIN_ATTRIBUTE.as_bytes()).cloned())
+            .map(|value| value == b"true")
+            .unwrap_or(false);
+        if !is_admin {
+            warn!(%their_identity_id, "rejecting a request from an identity which is not a node administrator");
+        }
+        Ok(is_admin)
+    }
+}
+
+/// Return true if a request received without a secure channel comes from this machine.
+///
+/// A request received over TCP has the sender of its connection as the first hop of its
+/// return route, and it is local if the connection comes from a loopback address. Otherwise
+/// the request is only local if it was sent by a worker of this node, in which case its
+/// return route is the address of that worker. A request forwarded by a worker of this node,
+/// like a hop, has a longer return route and is not local
+fn is_local_request(return_route: &Route, tcp_senders: &[TcpSenderInfo]) -> bool {
+    let first_hop = match return_route.iter().next() {
+        Some(first_hop) => first_hop,
+        None => return false,
+    };
+    match tcp_senders.iter().find(|s| s.address() == first_hop) {
+        Some(sender) => sender.socket_address().ip().is_loopback(),
+        None => return_route.len() == 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::start_manager_for_tests;
+    use ockam::identity::{AttributeValidity, AttributesEntry, Identifier, TimestampInSeconds};
+    use ockam_core::api::Request;
+    use ockam_core::flow_control::FlowControls;
+    use ockam_core::{route, Address, TransportMessage};
+    use ockam_node::Context;
+    use ockam_transport_tcp::TcpConnectionMode;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_local_requests() {
+        let sender = |address: &str, socket_address: &str| {
+            TcpSenderInfo::new(
+                address.into(),
+                Address::random_local(),
+                socket_address.parse().unwrap(),
+                TcpConnectionMode::Incoming,
+                FlowControls::generate_flow_control_id(),
+            )
+        };
+        let senders = [
+            sender("local_sender", "127.0.0.1:53000"),
+            sender("remote_sender", "10.0.0.4:53000"),
+        ];
+
+        // a request sent by the command line over a loopback connection
+        assert!(is_local_request(&route!["local_sender"], &senders));
+        assert!(is_local_request(&route!["local_sender", "app"], &senders));
+        // a request sent by a worker of the node
+        assert!(is_local_request(&route!["kafka_controller"], &senders));
+
+        // a request sent by a remote node, directly or through a hop of this node
+        assert!(!is_local_request(&route!["remote_sender"], &senders));
+        assert!(!is_local_request(&route!["hop", "remote_sender"], &senders));
+        assert!(!is_local_request(&route!["hop", "local_sender"], &senders));
+        assert!(!is_local_request(&route![], &senders));
+    }
+
+    #[ockam_macros::test]
+    async fn test_remote_local_and_admin_requests(context: &mut Context) -> ockam_core::Result<()> {
+        let handle = start_manager_for_tests(context).await?;
+        let node_manager = &handle.node_manager;
+        assert_eq!(
+            node_manager.management_access(),
+            ManagementAccess::AdminOrLocal
+        );
+
+        let identities_creation = handle.secure_channels.identities().identities_creation();
+        let admin = identities_creation.create_identity().await?;
+        let member = identities_creation.create_identity().await?;
+        let former_admin = identities_creation.create_identity().await?;
+        node_manager
+            .identities_repository()
+            .put_attribute_value(
+                admin.identifier(),
+                ADMIN_ATTRIBUTE.as_bytes().to_vec(),
+                b"true".to_vec(),
+            )
+            .await?;
+        // an admin grant which is not valid anymore
+        let now = now()?;
+        node_manager
+            .identities_repository()
+            .put_attributes(
+                former_admin.identifier(),
+                AttributesEntry::new(
+                    BTreeMap::from([(ADMIN_ATTRIBUTE.as_bytes().to_vec(), b"true".to_vec())]),
+                    now,
+                    None,
+                    None,
+                )
+                .with_validity(BTreeMap::from([(
+                    ADMIN_ATTRIBUTE.to_string(),
+                    AttributeValidity::new().with_not_after(TimestampInSeconds(*now - 10)),
+                )])),
+            )
+            .await?;
+
+        let request = Request::get("/node/tcp/listener");
+        let req = request.header();
+        let message = |return_route: Route, identifier: Option<&Identifier>| {
+            let local_info = match identifier {
+                Some(identifier) => {
+                    IdentitySecureChannelLocalInfo::mark(vec![], identifier.clone()).unwrap()
+                }
+                None => vec![],
+            };
+            LocalMessage::new(
+                TransportMessage::v1(route!["_internal.nodemanager"], return_route, vec![]),
+                local_info,
+            )
+        };
+
+        // local request
+        assert!(
+            node_manager
+                .is_request_authorized(req, &message(route!["app"], None))
+                .await?
+        );
+        // remote request without a secure channel
+        assert!(
+            !node_manager
+                .is_request_authorized(req, &message(route!["hop", "remote"], None))
+                .await?
+        );
+        // requests over a secure channel
+        assert!(
+            node_manager
+                .is_request_authorized(req, &message(route!["encryptor"], Some(admin.identifier())))
+                .await?
+        );
+        assert!(
+            !node_manager
+                .is_request_authorized(
+                    req,
+                    &message(route!["encryptor"], Some(member.identifier()))
+                )
+                .await?
+        );
+        assert!(
+            !node_manager
+                .is_request_authorized(
+                    req,
+                    &message(route!["encryptor"], Some(former_admin.identifier()))
+                )
+                .await?
+        );
+
+        // the status of the node is always returned
+        let status = Request::get("/node");
+        assert!(
+            node_manager
+                .is_request_authorized(status.header(), &message(route!["hop", "remote"], None))
+                .await?
+        );
+
+        // the diagnostics are restricted even when the management access is open
+        let diagnostics = Request::get("/node/diagnostics");
+        let is_authorized = |req: RequestHeader, msg: LocalMessage| async move {
+            node_manager
+                .is_request_authorized_with(ManagementAccess::Open, &req, &msg)
+                .await
+        };
+        assert!(is_authorized(req.clone(), message(route!["hop", "remote"], None)).await?);
+        assert!(
+            !is_authorized(
+                diagnostics.header().clone(),
+                message(route!["hop", "remote"], None)
+            )
+            .await?
+        );
+        assert!(is_authorized(diagnostics.header().clone(), message(route!["app"], None)).await?);
+        assert!(
+            is_authorized(
+                diagnostics.header().clone(),
+                message(route!["encryptor"], Some(admin.identifier()))
+            )
+            .await?
+        );
+
+        context.stop().await
+    }
+}
@@ -4,12 +4,18 @@ use miette::IntoDiagnostic;
 use minicbor::{Decode, Encode};
 use ockam_core::api::{Reply, Request};
 use ockam_core::{AsyncTryClone, Route};
-use ockam_node::api::Client;
+use ockam_node::api::{CancellationToken, Client};
 use ockam_node::Context;
 use ockam_transport_tcp::{TcpConnectionOptions, TcpTransport};
 use std::sync::Arc;
 use std::time::Duration;
 
+/// Return a new idempotency key for a request creating a resource on a node, so that the
+/// resource is created only once when the request is sent again with the same key
+pub fn random_idempotency_key() -> String {
+    hex::encode(rand::random::<[u8; 16]>())
+}
+
 /// This struct represents a node that has been started
 /// on the same machine with a given node name
 ///
@@ -21,6 +27,7 @@ pub struct BackgroundNode {
     node_name: String,
     to: Route,
     timeout: Option<Duration>,
+    cancellation: Option<CancellationToken>,
     tcp_transport: Arc<TcpTransport>,
 }
 
@@ -49,10 +56,16 @@ impl BackgroundNode {
             node_name: node_name.to_string(),
             to: NODEMANAGER_ADDR.into(),
             timeout: None,
+            cancellation: None,
             tcp_transport: Arc::new(tcp_transport.async_try_clone().await.into_diagnostic()?),
         })
     }
 
+    /// Name of the node receiving the requests
+    pub fn node_name(&self) -> String {
+        self.node_name.clone()
+    }
+
     // Set a different node name
     pub fn set_node_name(&mut self, node_name: &str) -> &Self {
         self.node_name = node_name.to_string();
@@ -65,6 +78,12 @@ impl BackgroundNode {
         self
     }
 
+    /// Abort the requests waiting for a response when the token is cancelled
+    pub fn set_cancellation_token(&mut self, token: CancellationToken) -> &Self {
+        self.cancellation = Some(token);
+        self
+    }
+
     /// Send a request and expect a decodable response
     pub async fn ask<T, R>(&self, ctx: &Context, req: Request<T>) -> miette::Result<R>
     where
@@ -156,6 +175,10 @@ impl BackgroundNode {
         timeout: Option<Duration>,
     ) -> miette::Result<Client> {
         let route = self.create_route().await?;
-        Ok(Client::new(&route, timeout))
+        let client = Client::new(&route, timeout);
+        Ok(match &self.cancellation {
+            Some(token) => client.with_cancellation(token.clone()),
+            None => client,
+        })
     }
 }
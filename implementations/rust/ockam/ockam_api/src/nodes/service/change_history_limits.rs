@@ -0,0 +1,28 @@
+use ockam::identity::{ChangeHistoryLimits, DEFAULT_MAX_CHANGES, DEFAULT_MAX_CHANGE_HISTORY_SIZE};
+use ockam_core::env::get_env_with_default;
+
+/// Environment variable overriding the maximum number of changes accepted
+/// in the change history of the identities of the other nodes
+const OCKAM_MAX_IDENTITY_CHANGES: &str = "OCKAM_MAX_IDENTITY_CHANGES";
+
+/// Environment variable overriding the maximum size, in bytes, of the change history
+/// of the identities of the other nodes
+const OCKAM_MAX_IDENTITY_CHANGE_HISTORY_SIZE: &str = "OCKAM_MAX_IDENTITY_CHANGE_HISTORY_SIZE";
+
+/// Return the limits on the change histories of the identities of the other nodes
+pub(super) fn change_history_limits() -> ChangeHistoryLimits {
+    let max_changes = get_env_with_default(OCKAM_MAX_IDENTITY_CHANGES, DEFAULT_MAX_CHANGES as u64)
+        .unwrap_or_else(|e| {
+            warn!(%e, "invalid {OCKAM_MAX_IDENTITY_CHANGES}, using the default limit");
+            DEFAULT_MAX_CHANGES as u64
+        });
+    let max_size = get_env_with_default(
+        OCKAM_MAX_IDENTITY_CHANGE_HISTORY_SIZE,
+        DEFAULT_MAX_CHANGE_HISTORY_SIZE as u64,
+    )
+    .unwrap_or_else(|e| {
+        warn!(%e, "invalid {OCKAM_MAX_IDENTITY_CHANGE_HISTORY_SIZE}, using the default limit");
+        DEFAULT_MAX_CHANGE_HISTORY_SIZE as u64
+    });
+    ChangeHistoryLimits::new(max_changes as usize, max_size as usize)
+}
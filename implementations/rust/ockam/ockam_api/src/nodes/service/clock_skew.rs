@@ -0,0 +1,43 @@
+use ockam::identity::TimestampInSeconds;
+use ockam_core::api::{RequestHeader, Response};
+use ockam_core::env::get_env_with_default;
+
+use crate::nodes::models::base::{ClockSkewList, ClockSkewStatus};
+
+use super::NodeManagerWorker;
+
+/// Environment variable overriding the tolerance, in seconds, on the clock skew
+/// with the issuers of credentials
+const OCKAM_CLOCK_SKEW_TOLERANCE: &str = "OCKAM_CLOCK_SKEW_TOLERANCE";
+
+/// Return the tolerance on the clock skew with the issuers of credentials
+pub(super) fn clock_skew_tolerance() -> TimestampInSeconds {
+    let default = *ockam::identity::DEFAULT_CLOCK_SKEW_TOLERANCE;
+    TimestampInSeconds(
+        get_env_with_default(OCKAM_CLOCK_SKEW_TOLERANCE, default).unwrap_or_else(|e| {
+            warn!(%e, "invalid clock skew tolerance, using the default one");
+            default
+        }),
+    )
+}
+
+impl NodeManagerWorker {
+    /// Return the clock skews measured with the issuers of the credentials received by the node
+    pub(super) fn get_clock_skews(&self, req: &RequestHeader) -> Response<ClockSkewList> {
+        let clock_skew = self.node_manager.identities().clock_skew();
+        let list = clock_skew
+            .measurements()
+            .into_iter()
+            .map(|(identifier, measurement)| ClockSkewStatus {
+                identifier: identifier.to_string(),
+                skew: measurement.skew,
+                measured_at: *measurement.measured_at,
+                exceeds_tolerance: clock_skew.exceeds_tolerance(&measurement),
+            })
+            .collect();
+        Response::ok(req).body(ClockSkewList {
+            tolerance: *clock_skew.tolerance(),
+            list,
+        })
+    }
+}
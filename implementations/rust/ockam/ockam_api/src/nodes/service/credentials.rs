@@ -16,6 +16,7 @@ use crate::cloud::AuthorityNode;
 use crate::error::ApiError;
 use crate::local_multiaddr_to_route;
 use crate::nodes::models::credentials::{GetCredentialRequest, PresentCredentialRequest};
+use crate::nodes::models::events::NodeEventType;
 use crate::nodes::BackgroundNode;
 
 use super::NodeManagerWorker;
@@ -138,7 +139,17 @@ impl NodeManagerWorker {
             .credential(ctx, &identifier)
             .await
         {
-            Ok(c) => Ok(Either::Right(Response::ok(req).body(c))),
+            Ok(c) => {
+                self.node_manager
+                    .publish_event(
+                        ctx,
+                        NodeEventType::CredentialRefreshed,
+                        identifier.to_string(),
+                        None,
+                    )
+                    .await;
+                Ok(Either::Right(Response::ok(req).body(c)))
+            }
             Err(e) => Ok(Either::Left(Response::internal_error(
                 req,
                 &format!(
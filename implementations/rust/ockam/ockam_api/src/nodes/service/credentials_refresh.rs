@@ -0,0 +1,63 @@
+use std::time::Duration;
+
+use ockam::identity::SecureChannelCredentialsRefresh;
+use ockam_core::api::{RequestHeader, Response};
+use ockam_core::env::get_env;
+
+use crate::nodes::models::secure_channel::CredentialsRefreshStatus;
+
+use super::NodeManagerWorker;
+
+/// Environment variable setting the interval, in seconds, at which fresh credentials are
+/// requested on the secure channels of the node. Credentials are not refreshed if it is not set
+const OCKAM_CREDENTIALS_REFRESH_INTERVAL: &str = "OCKAM_CREDENTIALS_REFRESH_INTERVAL";
+
+/// Environment variable setting the maximum random variation, in seconds, of the interval
+/// between two refreshes. It is a tenth of the interval by default
+const OCKAM_CREDENTIALS_REFRESH_JITTER: &str = "OCKAM_CREDENTIALS_REFRESH_JITTER";
+
+/// Return the default credentials refresh of the secure channels of the node
+pub(super) fn credentials_refresh() -> Option<SecureChannelCredentialsRefresh> {
+    let interval = match get_env::<u64>(OCKAM_CREDENTIALS_REFRESH_INTERVAL) {
+        Ok(Some(interval)) if interval > 0 => Duration::from_secs(interval),
+        Ok(_) => return None,
+        Err(e) => {
+            warn!(%e, "invalid {OCKAM_CREDENTIALS_REFRESH_INTERVAL}, credentials are not refreshed");
+            return None;
+        }
+    };
+    match get_env::<u64>(OCKAM_CREDENTIALS_REFRESH_JITTER) {
+        Ok(Some(jitter)) => Some(SecureChannelCredentialsRefresh::new(
+            interval,
+            Duration::from_secs(jitter),
+        )),
+        Ok(None) => Some(SecureChannelCredentialsRefresh::with_default_jitter(
+            interval,
+        )),
+        Err(e) => {
+            warn!(%e, "invalid {OCKAM_CREDENTIALS_REFRESH_JITTER}, using the default jitter");
+            Some(SecureChannelCredentialsRefresh::with_default_jitter(
+                interval,
+            ))
+        }
+    }
+}
+
+impl NodeManagerWorker {
+    /// Return the default credentials refresh of the node and the results of the refreshes
+    pub(super) fn get_credentials_refresh(
+        &self,
+        req: &RequestHeader,
+    ) -> Response<CredentialsRefreshStatus> {
+        let secure_channels = &self.node_manager.secure_channels;
+        let refresh = secure_channels.credentials_refresh();
+        let metrics = secure_channels.credentials_refresh_metrics();
+        Response::ok(req).body(CredentialsRefreshStatus {
+            interval: refresh.map(|r| r.interval().as_secs()),
+            jitter: refresh.map(|r| r.jitter().as_secs()),
+            requested: metrics.requested(),
+            succeeded: metrics.succeeded(),
+            failed: metrics.failed(),
+        })
+    }
+}
@@ -0,0 +1,444 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use minicbor::Decoder;
+use serde::Serialize;
+use serde_json::Value;
+
+use ockam::identity::utils::now;
+use ockam::{Context, Result};
+use ockam_abac::Resource;
+use ockam_core::api::{Error, RequestHeader, Response};
+
+use crate::cli_state::NodeState;
+use crate::error::ApiError;
+use crate::nodes::models::base::NodeStatus;
+use crate::nodes::models::diagnostics::{DiagnosticBundle, GetDiagnostics};
+use crate::nodes::models::peers::PeerStatus;
+use crate::nodes::models::portal::{InletStatus, OutletStatus};
+use crate::nodes::models::relay::RelayInfo;
+use crate::nodes::models::route::StaticRoute;
+use crate::nodes::models::services::ServiceStatus;
+use crate::nodes::models::stats::NodeStats;
+use crate::nodes::models::workers::WorkerStatus;
+use crate::resources;
+
+use super::{NodeManager, NodeManagerWorker};
+
+/// Period of the statistics included in a diagnostic bundle, in seconds
+const DIAGNOSTICS_STATS_PERIOD: u64 = 3600;
+
+/// Replacement of the addresses of a redacted bundle
+const REDACTED: &str = "<redacted>";
+
+/// Multiaddr protocols followed by an IP address or a host name
+const MULTIADDR_HOST_PROTOCOLS: [&str; 6] = ["ip4", "ip6", "dns", "dns4", "dns6", "dnsaddr"];
+
+/// Maximum size of the compressed bundle sent by a node.
+/// A response must fit in a single message of the TCP transport, whose length is a u16
+const MAX_DIAGNOSTIC_BUNDLE_SIZE: usize = 48 * 1024;
+
+/// Sections removed from a bundle which is too large, in this order
+const TRUNCATED_SECTIONS: [&str; 2] = ["stats", "workers"];
+
+/// Size of the blocks read from the end of a log file
+const LOG_READ_BLOCK_SIZE: u64 = 64 * 1024;
+
+/// Content of a diagnostic bundle sent by a node.
+/// The logs are added by the command line, which reads them from the node directory
+#[derive(Serialize)]
+struct Diagnostics {
+    node_name: String,
+    created_at: u64,
+    version: VersionInfo,
+    status: NodeStatus,
+    workers: Vec<WorkerStatus>,
+    resources: ResourcesDiagnostics,
+    routes: Vec<StaticRoute>,
+    secure_channels: SecureChannelsDiagnostics,
+    stats: Vec<NodeStats>,
+}
+
+#[derive(Serialize)]
+struct VersionInfo {
+    version: String,
+    os: String,
+    arch: String,
+}
+
+/// Resources created on the node, and their policies
+#[derive(Serialize)]
+struct ResourcesDiagnostics {
+    inlets: Vec<InletStatus>,
+    outlets: Vec<OutletStatus>,
+    relays: Vec<RelayInfo>,
+    services: Vec<ServiceStatus>,
+    policies: Vec<PolicyDefinition>,
+}
+
+#[derive(Serialize)]
+struct PolicyDefinition {
+    resource: String,
+    action: String,
+    expression: String,
+}
+
+#[derive(Serialize)]
+struct SecureChannelsDiagnostics {
+    channels: Vec<SecureChannelDiagnostics>,
+    listeners: Vec<String>,
+    /// Number of secure channels initiated and accepted with each identity
+    peers: Vec<PeerStatus>,
+}
+
+#[derive(Serialize)]
+struct SecureChannelDiagnostics {
+    encryptor_address: String,
+    route: String,
+    authorized_identifiers: Option<Vec<String>>,
+}
+
+/// Last lines of a log file of the node
+#[derive(Serialize)]
+struct LogFile {
+    name: String,
+    lines: Vec<String>,
+}
+
+impl NodeManagerWorker {
+    pub(super) async fn get_diagnostics(
+        &self,
+        ctx: &Context,
+        req: &RequestHeader,
+        dec: &mut Decoder<'_>,
+    ) -> Result<Response<DiagnosticBundle>, Response<Error>> {
+        let request: GetDiagnostics = dec.decode()?;
+        match self
+            .node_manager
+            .diagnostics(ctx, request.redact_addresses)
+            .await
+        {
+            Ok(bundle) => Ok(Response::ok(req).body(bundle)),
+            Err(err) => Err(Response::internal_error(req, &err.to_string())),
+        }
+    }
+}
+
+impl NodeManager {
+    /// Gather the state of this node into a compressed diagnostic bundle.
+    ///
+    /// When the bundle is too large to be sent in a response, its statistics and then its
+    /// workers are removed, and the removed sections are listed in its `truncated` field
+    pub async fn diagnostics(
+        &self,
+        ctx: &Context,
+        redact_addresses: bool,
+    ) -> Result<DiagnosticBundle> {
+        let created_at = *now()?;
+        let workers = ctx
+            .list_workers_info()
+            .await?
+            .into_iter()
+            .map(WorkerStatus::from)
+            .collect();
+        let diagnostics = Diagnostics {
+            node_name: self.node_name(),
+            created_at,
+            version: VersionInfo {
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                os: std::env::consts::OS.to_string(),
+                arch: std::env::consts::ARCH.to_string(),
+            },
+            status: self.status(ctx).await?,
+            workers,
+            resources: self.resources_diagnostics().await?,
+            routes: self.static_routes().await?,
+            secure_channels: self.secure_channels_diagnostics().await?,
+            stats: self
+                .get_node_stats(
+                    Some(created_at.saturating_sub(DIAGNOSTICS_STATS_PERIOD)),
+                    None,
+                )
+                .await?,
+        };
+
+        let mut json = serde_json::to_value(diagnostics).map_err(ApiError::core)?;
+        if redact_addresses {
+            self::redact_addresses(&mut json);
+        }
+        let mut data = compress(&json)?;
+        let mut truncated = vec![];
+        for section in TRUNCATED_SECTIONS {
+            if data.len() <= MAX_DIAGNOSTIC_BUNDLE_SIZE {
+                break;
+            }
+            json[section] = Value::Array(vec![]);
+            truncated.push(Value::String(section.to_string()));
+            json["truncated"] = Value::Array(truncated.clone());
+            data = compress(&json)?;
+        }
+        if data.len() > MAX_DIAGNOSTIC_BUNDLE_SIZE {
+            return Err(ApiError::core(format!(
+                "the diagnostic bundle is too large to be sent: {} bytes",
+                data.len()
+            )));
+        }
+        Ok(DiagnosticBundle::new(self.node_name(), created_at, data))
+    }
+
+    async fn resources_diagnostics(&self) -> Result<ResourcesDiagnostics> {
+        let inlets = self.list_inlets().await.items;
+        let outlets = self.list_outlets().await.items;
+
+        // the inlets and outlets are either protected by the policies of their alias
+        // or by the policies of their default resource
+        let mut policy_resources = vec![
+            resources::INLET.as_str().to_string(),
+            resources::OUTLET.as_str().to_string(),
+        ];
+        policy_resources.extend(inlets.iter().map(|i| i.alias.clone()));
+        policy_resources.extend(outlets.iter().map(|o| o.alias.clone()));
+        let mut policies = vec![];
+        for resource in policy_resources {
+            for (action, expression) in self.policies.policies(&Resource::new(&resource)).await? {
+                policies.push(PolicyDefinition {
+                    resource: resource.clone(),
+                    action: action.as_str().to_string(),
+                    expression: expression.to_string(),
+                });
+            }
+        }
+
+        Ok(ResourcesDiagnostics {
+            inlets,
+            outlets,
+            relays: self.get_relays().await,
+            services: NodeManagerWorker::list_services_impl(&self.registry).await,
+            policies,
+        })
+    }
+
+    async fn secure_channels_diagnostics(&self) -> Result<SecureChannelsDiagnostics> {
+        let channels = self
+            .list_secure_channels()
+            .await
+            .iter()
+            .map(|info| SecureChannelDiagnostics {
+                encryptor_address: info.sc().encryptor_address().to_string(),
+                route: info.route().to_string(),
+                authorized_identifiers: info
+                    .authorized_identifiers()
+                    .map(|ids| ids.iter().map(|id| id.to_string()).collect()),
+            })
+            .collect();
+        let listeners = self
+            .registry
+            .secure_channel_listeners
+            .keys()
+            .await
+            .iter()
+            .map(|address| address.to_string())
+            .collect();
+        Ok(SecureChannelsDiagnostics {
+            channels,
+            listeners,
+            peers: self.list_peers(None).await?,
+        })
+    }
+}
+
+/// Add the last lines of the log files of a node to the diagnostic bundle it sent.
+///
+/// The logs are read from the node directory by the caller, so that they don't have to be
+/// sent by the node. Their addresses are redacted when `redact_addresses` is true
+pub fn add_logs_to_diagnostics(
+    bundle: &DiagnosticBundle,
+    node_state: &NodeState,
+    max_lines: usize,
+    redact_addresses: bool,
+) -> Result<Vec<u8>> {
+    let mut json: Value =
+        serde_json::from_reader(GzDecoder::new(bundle.data.as_slice())).map_err(ApiError::core)?;
+    let logs: Vec<LogFile> = [
+        ("stdout", node_state.stdout_log()),
+        ("stderr", node_state.stderr_log()),
+    ]
+    .into_iter()
+    .filter_map(|(name, path)| {
+        last_lines(&path, max_lines).map(|lines| LogFile {
+            name: name.to_string(),
+            lines,
+        })
+    })
+    .collect();
+    let mut logs = serde_json::to_value(logs).map_err(ApiError::core)?;
+    if redact_addresses {
+        self::redact_addresses(&mut logs);
+    }
+    json["logs"] = logs;
+    compress(&json)
+}
+
+fn compress(json: &Value) -> Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(vec![], Compression::default());
+    serde_json::to_writer_pretty(&mut encoder, json).map_err(ApiError::core)?;
+    encoder.finish().map_err(ApiError::core)
+}
+
+/// Return the last lines of a file, if it can be read.
+/// The file is read backwards by blocks, so that only its end is loaded
+fn last_lines(path: &Path, max_lines: usize) -> Option<Vec<String>> {
+    let mut file = File::open(path).ok()?;
+    let mut start = file.metadata().ok()?.len();
+    let mut content = vec![];
+    let mut new_lines = 0;
+    // one more line break than the number of lines is needed to know that the first line is complete
+    while start > 0 && new_lines <= max_lines {
+        let size = LOG_READ_BLOCK_SIZE.min(start);
+        start -= size;
+        let mut block = vec![0; size as usize];
+        file.seek(SeekFrom::Start(start)).ok()?;
+        file.read_exact(&mut block).ok()?;
+        new_lines += block.iter().filter(|b| **b == b'\n').count();
+        block.extend(content);
+        content = block;
+    }
+    let content = String::from_utf8_lossy(&content);
+    let lines: Vec<&str> = content.lines().collect();
+    let skip = lines.len().saturating_sub(max_lines);
+    Some(lines[skip..].iter().map(|l| l.to_string()).collect())
+}
+
+/// Replace the IP addresses and host names found in the strings of a JSON document
+fn redact_addresses(value: &mut Value) {
+    match value {
+        Value::String(s) => *s = redact(s),
+        Value::Array(values) => values.iter_mut().for_each(redact_addresses),
+        Value::Object(map) => map.values_mut().for_each(redact_addresses),
+        _ => {}
+    }
+}
+
+/// Redact the addresses of a text: IP addresses, socket addresses, host names followed by
+/// a port and the hosts of multiaddrs, like /ip4/10.0.0.1/tcp/4000 or /dnsaddr/example.com
+fn redact(text: &str) -> String {
+    text.split_inclusive(char::is_whitespace)
+        .map(|word| {
+            let mut redact_next = false;
+            word.split('/')
+                .map(|segment| {
+                    let redacted = redact_word(segment, redact_next);
+                    redact_next = MULTIADDR_HOST_PROTOCOLS.contains(&segment);
+                    redacted
+                })
+                .collect::<Vec<_>>()
+                .join("/")
+        })
+        .collect()
+}
+
+/// Redact a word if it is an address, keeping the punctuation and spaces around it
+fn redact_word(word: &str, is_host: bool) -> String {
+    let is_delimiter = |c: char| c.is_whitespace() || "\"'(),;<>".contains(c);
+    let start = word
+        .char_indices()
+        .find(|(_, c)| !is_delimiter(*c))
+        .map_or(word.len(), |(i, _)| i);
+    let end = word
+        .char_indices()
+        .rev()
+        .find(|(_, c)| !is_delimiter(*c))
+        .map_or(start, |(i, c)| i + c.len_utf8());
+    let candidate = &word[start..end];
+    if !candidate.is_empty() && (is_host || is_address(candidate)) {
+        format!("{}{REDACTED}{}", &word[..start], &word[end..])
+    } else {
+        word.to_string()
+    }
+}
+
+fn is_address(candidate: &str) -> bool {
+    if candidate.parse::<IpAddr>().is_ok() || candidate.parse::<SocketAddr>().is_ok() {
+        return true;
+    }
+    // a host name followed by a port, like localhost:4000 or example.com:443
+    match candidate.rsplit_once(':') {
+        Some((host, port)) => {
+            port.parse::<u16>().is_ok()
+                && (host == "localhost" || (host.contains('.') && is_host_name(host)))
+        }
+        None => false,
+    }
+}
+
+fn is_host_name(host: &str) -> bool {
+    host.split('.').all(|label| {
+        !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_last_lines() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        // more than one block, so that the file is read backwards in several steps
+        for i in 0..20_000 {
+            writeln!(file, "line {i}").unwrap();
+        }
+        file.flush().unwrap();
+
+        let lines = last_lines(file.path(), 3).unwrap();
+        assert_eq!(lines, vec!["line 19997", "line 19998", "line 19999"]);
+        let lines = last_lines(file.path(), 15_000).unwrap();
+        assert_eq!(lines.len(), 15_000);
+        assert_eq!(lines[0], "line 5000");
+        assert_eq!(last_lines(file.path(), 30_000).unwrap().len(), 20_000);
+        assert_eq!(last_lines(file.path(), 0).unwrap(), Vec::<String>::new());
+        assert!(last_lines(Path::new("/does/not/exist.log"), 3).is_none());
+    }
+
+    #[test]
+    fn test_redact_addresses() {
+        assert_eq!(
+            redact("listening on 127.0.0.1:4000"),
+            "listening on <redacted>"
+        );
+        assert_eq!(
+            redact("/dnsaddr/relay.example.com/tcp/4000/service/api"),
+            "/dnsaddr/<redacted>/tcp/4000/service/api"
+        );
+        assert_eq!(
+            redact("connected to (10.0.0.2), fe80::1 and localhost:6000"),
+            "connected to (<redacted>), <redacted> and <redacted>"
+        );
+        assert_eq!(redact("/ip4/192.168.1.5/tcp/80"), "/ip4/<redacted>/tcp/80");
+        assert_eq!(
+            redact("worker 0#c5b4a2 handled 12 messages at 12:30"),
+            "worker 0#c5b4a2 handled 12 messages at 12:30"
+        );
+
+        let mut json = serde_json::json!({
+            "bind_addr": "0.0.0.0:5000",
+            "routes": ["/service/forward_to_n1", "/ip6/::1/tcp/4000"],
+            "count": 3
+        });
+        redact_addresses(&mut json);
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "bind_addr": "<redacted>",
+                "routes": ["/service/forward_to_n1", "/ip6/<redacted>/tcp/4000"],
+                "count": 3
+            })
+        );
+    }
+}
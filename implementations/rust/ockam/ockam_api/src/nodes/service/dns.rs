@@ -0,0 +1,115 @@
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::time::Duration;
+
+use ockam::Result;
+use ockam_core::env::get_env;
+use ockam_transport_tcp::{DnsOptions, IpPreference};
+
+use crate::error::ApiError;
+
+/// Environment variable setting the IP version, `ipv4` or `ipv6`, used when a host
+/// resolves to both IPv4 and IPv6 addresses
+const OCKAM_DNS_IP_PREFERENCE: &str = "OCKAM_DNS_IP_PREFERENCE";
+
+/// Environment variable setting a comma-separated list of DNS servers queried instead
+/// of the resolver of the system. The port of a server defaults to 53
+const OCKAM_DNS_RESOLVERS: &str = "OCKAM_DNS_RESOLVERS";
+
+/// Environment variable setting static addresses for some hosts, as a semicolon-separated
+/// list of `host=address,address`
+const OCKAM_DNS_OVERRIDES: &str = "OCKAM_DNS_OVERRIDES";
+
+/// Environment variable setting the maximum time, in seconds, spent resolving a host name
+const OCKAM_DNS_TIMEOUT: &str = "OCKAM_DNS_TIMEOUT";
+
+const DEFAULT_DNS_PORT: u16 = 53;
+
+/// Return the options used by the TCP transport of the node to resolve host names
+pub(super) fn dns_options() -> Result<DnsOptions> {
+    let mut options = DnsOptions::new();
+    if let Some(preference) = get_env::<String>(OCKAM_DNS_IP_PREFERENCE)? {
+        options =
+            options.with_ip_preference(IpPreference::from_str(&preference).map_err(|_| {
+                ApiError::core(format!(
+                    "invalid {OCKAM_DNS_IP_PREFERENCE} {preference}, expected 'ipv4' or 'ipv6'"
+                ))
+            })?);
+    }
+    if let Some(resolvers) = get_env::<String>(OCKAM_DNS_RESOLVERS)? {
+        options = options.with_resolvers(parse_resolvers(&resolvers)?);
+    }
+    if let Some(overrides) = get_env::<String>(OCKAM_DNS_OVERRIDES)? {
+        for (host, addresses) in parse_overrides(&overrides)? {
+            options = options.with_override(host, addresses);
+        }
+    }
+    if let Some(timeout) = get_env::<u64>(OCKAM_DNS_TIMEOUT)? {
+        options = options.with_timeout(Duration::from_secs(timeout));
+    }
+    Ok(options)
+}
+
+fn parse_resolvers(resolvers: &str) -> Result<Vec<SocketAddr>> {
+    resolvers
+        .split(',')
+        .map(str::trim)
+        .filter(|r| !r.is_empty())
+        .map(|resolver| {
+            SocketAddr::from_str(resolver)
+                .or_else(|_| {
+                    IpAddr::from_str(resolver).map(|ip| SocketAddr::new(ip, DEFAULT_DNS_PORT))
+                })
+                .map_err(|_| ApiError::core(format!("invalid DNS resolver address {resolver}")))
+        })
+        .collect()
+}
+
+fn parse_overrides(overrides: &str) -> Result<Vec<(String, Vec<IpAddr>)>> {
+    overrides
+        .split(';')
+        .map(str::trim)
+        .filter(|o| !o.is_empty())
+        .map(|entry| {
+            let (host, addresses) = entry.split_once('=').ok_or_else(|| {
+                ApiError::core(format!(
+                    "invalid DNS override {entry}, expected host=address"
+                ))
+            })?;
+            let addresses = addresses
+                .split(',')
+                .map(|a| {
+                    IpAddr::from_str(a.trim()).map_err(|_| {
+                        ApiError::core(format!("invalid address {a} for the host {host}"))
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Ok((host.trim().to_string(), addresses))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_dns_options() -> Result<()> {
+        assert_eq!(
+            parse_resolvers("10.0.0.2, [::1]:5353")?,
+            vec![
+                SocketAddr::from_str("10.0.0.2:53").unwrap(),
+                SocketAddr::from_str("[::1]:5353").unwrap()
+            ]
+        );
+        assert!(parse_resolvers("resolver.local").is_err());
+
+        let overrides = parse_overrides("db.internal=10.0.0.7,fd00::7; cache.internal=10.0.0.8")?;
+        assert_eq!(overrides.len(), 2);
+        assert_eq!(overrides[0].0, "db.internal");
+        assert_eq!(overrides[0].1.len(), 2);
+        assert!(parse_overrides("db.internal").is_err());
+        assert!(parse_overrides("db.internal=nope").is_err());
+        Ok(())
+    }
+}
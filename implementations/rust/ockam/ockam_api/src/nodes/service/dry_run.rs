@@ -0,0 +1,283 @@
+use minicbor::Decoder;
+
+use ockam::Result;
+use ockam_abac::{Action, Resource};
+use ockam_core::api::{Error, RequestHeader, Response};
+use ockam_core::errcode::{Kind, Origin};
+
+use crate::nodes::models::dry_run::DryRun;
+use crate::nodes::models::policy::Policy;
+use crate::nodes::models::portal::PortalSession;
+use crate::resources;
+
+use super::{NodeManager, NodeManagerWorker};
+
+impl NodeManagerWorker {
+    pub(super) async fn dry_run_delete_node(
+        &self,
+        req: &RequestHeader,
+    ) -> Result<Response<DryRun>, Response<Error>> {
+        match self.node_manager.dry_run_delete_node().await {
+            Ok(dry_run) => Ok(Response::ok(req).body(dry_run)),
+            Err(err) => Err(Response::internal_error(req, &err.to_string())),
+        }
+    }
+
+    pub(super) async fn dry_run_delete_inlet(
+        &self,
+        req: &RequestHeader,
+        alias: &str,
+    ) -> Result<Response<DryRun>, Response<Error>> {
+        match self.node_manager.dry_run_delete_inlet(alias).await {
+            Ok(dry_run) => Ok(Response::ok(req).body(dry_run)),
+            Err(err) if err.code().kind == Kind::NotFound => {
+                Err(Response::not_found(req, &err.to_string()))
+            }
+            Err(err) => Err(Response::internal_error(req, &err.to_string())),
+        }
+    }
+
+    pub(super) async fn dry_run_set_policy(
+        &self,
+        req: &RequestHeader,
+        resource: &str,
+        action: &str,
+        dec: &mut Decoder<'_>,
+    ) -> Result<Response<DryRun>, Response<Error>> {
+        let policy: Policy = dec.decode()?;
+        let dry_run = self
+            .node_manager
+            .dry_run_update_policy(resource, action, Some(policy))
+            .await?;
+        Ok(Response::ok(req).body(dry_run))
+    }
+
+    pub(super) async fn dry_run_delete_policy(
+        &self,
+        req: &RequestHeader,
+        resource: &str,
+        action: &str,
+    ) -> Result<Response<DryRun>, Response<Error>> {
+        let dry_run = self
+            .node_manager
+            .dry_run_update_policy(resource, action, None)
+            .await?;
+        Ok(Response::ok(req).body(dry_run))
+    }
+}
+
+impl NodeManager {
+    /// Return what would be stopped if this node was deleted
+    pub async fn dry_run_delete_node(&self) -> Result<DryRun> {
+        let node_name = self.node_name();
+        let mut dependents = vec![];
+        for alias in self.registry.inlets.keys().await {
+            dependents.push(format!("inlet {alias}"));
+        }
+        for alias in self.registry.outlets.keys().await {
+            dependents.push(format!("outlet {alias}"));
+        }
+        for remote_address in self.registry.relays.keys().await {
+            dependents.push(format!("relay {remote_address}"));
+        }
+        for (address, info) in self.registry.kafka_services.entries().await {
+            dependents.push(format!("kafka {} {address}", info.kind()));
+        }
+        for address in self.registry.secure_channel_listeners.keys().await {
+            dependents.push(format!("secure channel listener {address}"));
+        }
+        let sessions = self.list_portal_sessions().await.items;
+        Ok(DryRun::new(vec![
+            format!("stop the node {node_name}"),
+            format!("delete the configuration and the data of the node {node_name}"),
+        ])
+        .with_dependents(dependents)
+        .with_sessions(sessions))
+    }
+
+    /// Return what would be stopped if an inlet was deleted
+    pub async fn dry_run_delete_inlet(&self, alias: &str) -> Result<DryRun> {
+        let inlet = match self.registry.inlets.get(alias).await {
+            Some(inlet) => inlet,
+            None => {
+                return Err(ockam_core::Error::new(
+                    Origin::Node,
+                    Kind::NotFound,
+                    format!("Inlet with alias {alias} not found"),
+                ))
+            }
+        };
+        let mut dependents = vec![];
+        if inlet.probe.is_some() {
+            dependents.push(format!("probe of the inlet {alias}"));
+        }
+        let sessions = self.portal_sessions_of(&[alias.to_string()]).await;
+        Ok(DryRun::new(vec![format!(
+            "delete the inlet {alias} listening on {}",
+            inlet.bind_addr
+        )])
+        .with_dependents(dependents)
+        .with_sessions(sessions))
+    }
+
+    /// Return what would change if a policy was set, or deleted when no policy is given.
+    ///
+    /// The inlets and outlets whose alias is the policy resource depend on it. When the resource
+    /// is the default resource of the inlets or outlets, all of them are reported, since the
+    /// inlets and outlets created without an alias use it
+    pub async fn dry_run_update_policy(
+        &self,
+        resource: &str,
+        action: &str,
+        policy: Option<Policy>,
+    ) -> Result<DryRun> {
+        let r = Resource::new(resource);
+        let a = Action::new(action);
+        let current = self.policies.get_policy(&r, &a).await?;
+        let change = match (current, policy) {
+            (None, Some(policy)) => {
+                format!(
+                    "set the policy {resource}/{action} to {}",
+                    policy.expression()
+                )
+            }
+            (Some(current), Some(policy)) => format!(
+                "replace the policy {resource}/{action} {current} with {}",
+                policy.expression()
+            ),
+            (Some(current), None) => format!("delete the policy {resource}/{action} {current}"),
+            (None, None) => return Ok(DryRun::default()),
+        };
+
+        let mut aliases = vec![];
+        let mut dependents = vec![];
+        for alias in self.registry.inlets.keys().await {
+            if alias == resource || resource == resources::INLET.as_str() {
+                dependents.push(format!("inlet {alias}"));
+                aliases.push(alias);
+            }
+        }
+        for alias in self.registry.outlets.keys().await {
+            if alias == resource || resource == resources::OUTLET.as_str() {
+                dependents.push(format!("outlet {alias}"));
+                aliases.push(alias);
+            }
+        }
+        let sessions = self.portal_sessions_of(&aliases).await;
+        Ok(DryRun::new(vec![change])
+            .with_dependents(dependents)
+            .with_sessions(sessions))
+    }
+
+    /// Return the sessions of the inlets and outlets with the given aliases
+    async fn portal_sessions_of(&self, aliases: &[String]) -> Vec<PortalSession> {
+        self.list_portal_sessions()
+            .await
+            .items
+            .into_iter()
+            .filter(|s| s.alias.as_ref().map_or(false, |a| aliases.contains(a)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::registry::InletInfo;
+    use crate::test_utils::start_manager_for_tests;
+    use ockam_abac::Expr;
+    use ockam_core::{route, AllowAll};
+    use ockam_node::Context;
+    use std::sync::Arc;
+
+    #[ockam_macros::test]
+    async fn test_dry_run_update_policy(context: &mut Context) -> Result<()> {
+        let handle = start_manager_for_tests(context).await?;
+        let node_manager = &handle.node_manager;
+        let policy = |b: bool| Policy::new(Expr::Bool(b));
+
+        // nothing to delete
+        let dry_run = node_manager
+            .dry_run_update_policy("my_inlet", "handle_message", None)
+            .await?;
+        assert_eq!(dry_run, DryRun::default());
+
+        // a new policy is only reported, not set
+        let dry_run = node_manager
+            .dry_run_update_policy("my_inlet", "handle_message", Some(policy(true)))
+            .await?;
+        assert_eq!(
+            dry_run.changes,
+            vec!["set the policy my_inlet/handle_message to true".to_string()]
+        );
+        let (r, a) = (Resource::new("my_inlet"), Action::new("handle_message"));
+        assert!(node_manager.policies.get_policy(&r, &a).await?.is_none());
+
+        // the inlet using the policy is reported when it is replaced or deleted
+        node_manager
+            .policies
+            .set_policy(&r, &a, &Expr::Bool(true))
+            .await?;
+        node_manager
+            .registry
+            .inlets
+            .insert(
+                "my_inlet".to_string(),
+                InletInfo::new("127.0.0.1:5000", None, &route![], Arc::new(AllowAll), None),
+            )
+            .await;
+        let dry_run = node_manager
+            .dry_run_update_policy("my_inlet", "handle_message", Some(policy(false)))
+            .await?;
+        assert_eq!(
+            dry_run.changes,
+            vec!["replace the policy my_inlet/handle_message true with false".to_string()]
+        );
+        assert_eq!(dry_run.dependents, vec!["inlet my_inlet".to_string()]);
+
+        let dry_run = node_manager
+            .dry_run_update_policy("my_inlet", "handle_message", None)
+            .await?;
+        assert_eq!(
+            dry_run.changes,
+            vec!["delete the policy my_inlet/handle_message true".to_string()]
+        );
+        assert_eq!(dry_run.dependents, vec!["inlet my_inlet".to_string()]);
+        assert!(node_manager.policies.get_policy(&r, &a).await?.is_some());
+
+        context.stop().await
+    }
+
+    #[ockam_macros::test]
+    async fn test_dry_run_delete_inlet(context: &mut Context) -> Result<()> {
+        let handle = start_manager_for_tests(context).await?;
+        let node_manager = &handle.node_manager;
+
+        let err = node_manager
+            .dry_run_delete_inlet("my_inlet")
+            .await
+            .unwrap_err();
+        assert_eq!(err.code().kind, Kind::NotFound);
+
+        node_manager
+            .registry
+            .inlets
+            .insert(
+                "my_inlet".to_string(),
+                InletInfo::new("127.0.0.1:5000", None, &route![], Arc::new(AllowAll), None),
+            )
+            .await;
+        let dry_run = node_manager.dry_run_delete_inlet("my_inlet").await?;
+        assert_eq!(
+            dry_run.changes,
+            vec!["delete the inlet my_inlet listening on 127.0.0.1:5000".to_string()]
+        );
+        assert!(dry_run.dependents.is_empty());
+        assert!(dry_run.sessions.is_empty());
+
+        // the inlet is still registered
+        assert!(node_manager.registry.inlets.get("my_inlet").await.is_some());
+
+        context.stop().await
+    }
+}
@@ -0,0 +1,336 @@
+use minicbor::Decoder;
+
+use ockam::identity::utils::now;
+use ockam::{Address, Context, Result, Routed, Worker};
+use ockam_core::api::{Error, RequestHeader, Response};
+use ockam_core::compat::sync::Arc;
+use ockam_core::{AllowAll, DenyAll, Route};
+use ockam_transport_tcp::InletConnectionAccepted;
+use ockam_vault::{KeyUsageAlert, KeyUsageAlertHandler};
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+use crate::nodes::models::events::{
+    EventSubscription, NodeEvent, NodeEventType, SubscribeToEvents,
+};
+use crate::nodes::registry::{EventSubscriberInfo, RegistryOf};
+use crate::resource_list::ResourceList;
+
+use super::{random_alias, NodeManager, NodeManagerWorker};
+
+/// Address of the worker receiving the connection notifications of the TCP inlets
+pub(super) const INLET_EVENTS_ADDRESS: &str = "inlet_events";
+
+/// Send an event to all the subscribers accepting its type.
+/// Subscribers which cannot be reached anymore are removed
+async fn publish(
+    ctx: &Context,
+    subscribers: &RegistryOf<String, EventSubscriberInfo>,
+    event_type: NodeEventType,
+    resource: String,
+    detail: Option<String>,
+) -> Result<()> {
+    let timestamp = *now()?;
+    for (id, subscriber) in subscribers.entries().await {
+        if !subscriber.accepts(event_type) {
+            continue;
+        }
+        let event = NodeEvent {
+            subscription_id: id.clone(),
+            event_type,
+            timestamp,
+            resource: resource.clone(),
+            detail: detail.clone(),
+        };
+        let route = subscriber.route().clone();
+        if let Err(err) = ctx.send(route.clone(), minicbor::to_vec(&event)?).await {
+            warn!(%id, %route, %err, "cannot send an event, removing the subscription");
+            subscribers.remove(&id).await;
+        }
+    }
+    Ok(())
+}
+
+/// Publisher of node events, for the components which cannot access the node manager
+#[derive(Clone)]
+pub(crate) struct NodeEventPublisher {
+    subscribers: Arc<RegistryOf<String, EventSubscriberInfo>>,
+}
+
+impl NodeEventPublisher {
+    /// Publish an event to the subscribers. Failures are only logged since publishing
+    /// an event must not interrupt the operation which triggered it
+    pub(crate) async fn publish(
+        &self,
+        ctx: &Context,
+        event_type: NodeEventType,
+        resource: impl Into<String>,
+        detail: Option<String>,
+    ) {
+        let subscribers = &self.subscribers;
+        if let Err(err) = publish(ctx, subscribers, event_type, resource.into(), detail).await {
+            warn!(%event_type, %err, "cannot publish an event");
+        }
+    }
+}
+
+/// This worker receives the notifications sent by the TCP inlets when they accept
+/// a new connection and publishes them to the event subscribers
+pub(super) struct InletEventsWorker {
+    subscribers: Arc<RegistryOf<String, EventSubscriberInfo>>,
+}
+
+#[ockam_core::worker]
+impl Worker for InletEventsWorker {
+    type Message = InletConnectionAccepted;
+    type Context = Context;
+
+    async fn handle_message(
+        &mut self,
+        ctx: &mut Context,
+        msg: Routed<InletConnectionAccepted>,
+    ) -> Result<()> {
+        let notification = msg.body();
+        publish(
+            ctx,
+            &self.subscribers,
+            NodeEventType::InletConnectionAccepted,
+            notification.inlet_address,
+            Some(notification.peer),
+        )
+        .await
+    }
+}
+
+/// Maximum number of key usage alerts waiting to be published
+const KEY_USAGE_ALERTS_CAPACITY: usize = 16;
+
+/// Handler of the alerts raised by the key usage tracker of the node vault,
+/// passing them to a task publishing them as node events
+struct KeyUsageAlertForwarder {
+    sender: mpsc::Sender<KeyUsageAlert>,
+}
+
+impl KeyUsageAlertHandler for KeyUsageAlertForwarder {
+    fn handle_alert(&self, alert: &KeyUsageAlert) {
+        warn!("key usage anomaly: {alert}");
+        // the alert is dropped rather than blocking the operation using the key
+        if self.sender.try_send(alert.clone()).is_err() {
+            warn!(key_id = %alert.key_id, "cannot publish a key usage alert");
+        }
+    }
+}
+
+impl NodeManagerWorker {
+    pub(super) async fn subscribe_to_events(
+        &self,
+        req: &RequestHeader,
+        dec: &mut Decoder<'_>,
+        return_route: Route,
+    ) -> Result<Response<EventSubscription>, Response<Error>> {
+        let request: SubscribeToEvents = dec.decode()?;
+        let subscription = self
+            .node_manager
+            .subscribe_to_events(return_route, request.event_types)
+            .await;
+        Ok(Response::ok(req).body(subscription))
+    }
+
+    pub(super) async fn list_event_subscriptions(
+        &self,
+        req: &RequestHeader,
+    ) -> Response<ResourceList<EventSubscription>> {
+        Response::ok(req).body(ResourceList::new(
+            self.node_manager.list_event_subscriptions().await,
+        ))
+    }
+
+    pub(super) async fn unsubscribe_from_events(
+        &self,
+        req: &RequestHeader,
+        id: &str,
+    ) -> Result<Response, Response<Error>> {
+        if self.node_manager.unsubscribe_from_events(id).await {
+            Ok(Response::ok(req))
+        } else {
+            Err(Response::not_found(
+                req,
+                &format!("Event subscription {id} not found"),
+            ))
+        }
+    }
+}
+
+impl NodeManager {
+    pub(super) async fn start_inlet_events_worker(&self, ctx: &Context) -> Result<()> {
+        let worker = InletEventsWorker {
+            subscribers: self.registry.event_subscribers.clone(),
+        };
+        ctx.start_worker(Address::from_string(INLET_EVENTS_ADDRESS), worker)
+            .await
+    }
+
+    /// Publish the alerts raised on the usage of the keys of the node vault as node events
+    pub(super) async fn start_key_usage_alerts(&self, ctx: &Context) -> Result<()> {
+        let key_usage = match self.secure_channels_vault().key_usage {
+            Some(key_usage) => key_usage,
+            None => return Ok(()),
+        };
+        let (sender, mut receiver) = mpsc::channel(KEY_USAGE_ALERTS_CAPACITY);
+        key_usage.set_alert_handler(Arc::new(KeyUsageAlertForwarder { sender }));
+        let ctx = ctx
+            .new_detached(
+                Address::random_tagged("KeyUsageAlerts.ctx"),
+                DenyAll,
+                AllowAll,
+            )
+            .await?;
+        let publisher = self.event_publisher();
+        tokio::spawn(async move {
+            while let Some(alert) = receiver.recv().await {
+                publisher
+                    .publish(
+                        &ctx,
+                        NodeEventType::KeyUsageAlert,
+                        alert.key_id.clone(),
+                        Some(alert.to_string()),
+                    )
+                    .await;
+            }
+        });
+        Ok(())
+    }
+
+    /// Register a new subscriber receiving the events of the given types on `route`.
+    /// All the events are sent if no type is specified
+    pub async fn subscribe_to_events(
+        &self,
+        route: Route,
+        event_types: Vec<NodeEventType>,
+    ) -> EventSubscription {
+        let id = random_alias();
+        debug!(%id, %route, "subscribing to the node events");
+        self.registry
+            .event_subscribers
+            .insert(
+                id.clone(),
+                EventSubscriberInfo::new(route, event_types.clone()),
+            )
+            .await;
+        EventSubscription::new(id, event_types)
+    }
+
+    /// Remove a subscription. Return false if it did not exist
+    pub async fn unsubscribe_from_events(&self, id: &str) -> bool {
+        self.registry.event_subscribers.remove(id).await.is_some()
+    }
+
+    pub async fn list_event_subscriptions(&self) -> Vec<EventSubscription> {
+        self.registry
+            .event_subscribers
+            .entries()
+            .await
+            .into_iter()
+            .map(|(id, s)| EventSubscription::new(id, s.event_types().to_vec()))
+            .collect()
+    }
+
+    /// Publish an event to the subscribers. Failures are only logged since publishing
+    /// an event must not interrupt the operation which triggered it
+    pub(crate) async fn publish_event(
+        &self,
+        ctx: &Context,
+        event_type: NodeEventType,
+        resource: impl Into<String>,
+        detail: Option<String>,
+    ) {
+        self.event_publisher()
+            .publish(ctx, event_type, resource, detail)
+            .await
+    }
+
+    pub(crate) fn event_publisher(&self) -> NodeEventPublisher {
+        NodeEventPublisher {
+            subscribers: self.registry.event_subscribers.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::start_manager_for_tests;
+    use core::time::Duration;
+    use ockam::route;
+    use ockam_vault::KeyUsageThreshold;
+
+    #[ockam::test]
+    async fn test_events_are_filtered_by_type(ctx: &mut Context) -> Result<()> {
+        let subscribers = RegistryOf::default();
+        let mut channels = ctx
+            .new_detached("channels_subscriber", AllowAll, DenyAll)
+            .await?;
+        let mut all = ctx
+            .new_detached("all_subscriber", AllowAll, DenyAll)
+            .await?;
+        subscribers
+            .insert(
+                "channels".to_string(),
+                EventSubscriberInfo::new(
+                    route!["channels_subscriber"],
+                    vec![NodeEventType::SecureChannelOpened],
+                ),
+            )
+            .await;
+        subscribers
+            .insert(
+                "all".to_string(),
+                EventSubscriberInfo::new(route!["all_subscriber"], vec![]),
+            )
+            .await;
+
+        let credential = NodeEventType::CredentialRefreshed;
+        publish(ctx, &subscribers, credential, "id".into(), None).await?;
+        let opened = NodeEventType::SecureChannelOpened;
+        publish(ctx, &subscribers, opened, "sc".into(), None).await?;
+
+        let event: NodeEvent = minicbor::decode(&all.receive::<Vec<u8>>().await?.body())?;
+        assert_eq!(event.subscription_id, "all");
+        assert_eq!(event.event_type, credential);
+        let event: NodeEvent = minicbor::decode(&all.receive::<Vec<u8>>().await?.body())?;
+        assert_eq!(event.event_type, opened);
+
+        let event: NodeEvent = minicbor::decode(&channels.receive::<Vec<u8>>().await?.body())?;
+        assert_eq!(event.subscription_id, "channels");
+        assert_eq!(event.event_type, opened);
+        assert_eq!(event.resource, "sc");
+
+        ctx.stop().await
+    }
+
+    #[ockam::test]
+    async fn test_key_usage_alerts_are_published(context: &mut Context) -> Result<()> {
+        let handle = start_manager_for_tests(context).await?;
+        let node_manager = &handle.node_manager;
+        let mut subscriber = context
+            .new_detached("alerts_subscriber", AllowAll, DenyAll)
+            .await?;
+        node_manager
+            .subscribe_to_events(
+                route!["alerts_subscriber"],
+                vec![NodeEventType::KeyUsageAlert],
+            )
+            .await;
+
+        let key_usage = node_manager.secure_channels_vault().key_usage.unwrap();
+        key_usage.set_threshold(Some(KeyUsageThreshold::new(0, Duration::from_secs(3600))));
+        key_usage.record_sign(&"key".to_string()).await;
+
+        let message = subscriber.receive::<Vec<u8>>().await?;
+        let event: NodeEvent = minicbor::decode(&message.body())?;
+        assert_eq!(event.event_type, NodeEventType::KeyUsageAlert);
+        assert_eq!(event.resource, "key");
+
+        context.stop().await
+    }
+}
@@ -0,0 +1,284 @@
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+use minicbor::Decoder;
+
+use ockam::identity::storage::Storage;
+use ockam::Result;
+use ockam_core::api::{Error, RequestHeader, Response};
+use ockam_core::compat::sync::Arc;
+use ockam_core::errcode::{Kind, Origin};
+use tracing::info;
+
+use crate::nodes::models::features::{FeatureList, FeatureStatus, SetFeature};
+
+use super::{NodeManager, NodeManagerWorker};
+
+const FEATURES_ID: &str = "features";
+const FEATURES_KEY: &str = "settings";
+
+/// Experimental subsystem of a node which can be enabled or disabled at runtime
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeFeature {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub enabled_by_default: bool,
+}
+
+impl NodeFeature {
+    pub const ROUTE_TRACING: &'static str = "route-tracing";
+
+    /// Return the feature with the given name
+    pub fn find(name: &str) -> Result<NodeFeature> {
+        NODE_FEATURES
+            .iter()
+            .find(|f| f.name == name)
+            .copied()
+            .ok_or_else(|| {
+                ockam_core::Error::new(
+                    Origin::Node,
+                    Kind::NotFound,
+                    format!(
+                        "the feature {name} does not exist, it must be one of: {}",
+                        NODE_FEATURES
+                            .iter()
+                            .map(|f| f.name)
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                )
+            })
+    }
+}
+
+/// Features which can be toggled on a node
+pub const NODE_FEATURES: [NodeFeature; 1] = [NodeFeature {
+    name: NodeFeature::ROUTE_TRACING,
+    description: "Start the tracer service and trace the routes of the node",
+    enabled_by_default: false,
+}];
+
+/// Persisted settings of the features of a node.
+///
+/// Only the features which were enabled or disabled on the node are stored, the other ones
+/// have their default state. The settings are loaded when the node starts and kept in memory,
+/// so that the subsystems can check them on each use
+#[derive(Clone)]
+pub(crate) struct FeatureFlags {
+    storage: Arc<dyn Storage>,
+    settings: Arc<RwLock<BTreeMap<String, bool>>>,
+}
+
+impl FeatureFlags {
+    pub(crate) async fn load(storage: Arc<dyn Storage>) -> Result<Self> {
+        let settings = match storage.get(FEATURES_ID, FEATURES_KEY).await? {
+            Some(bytes) => minicbor::decode(&bytes)?,
+            None => BTreeMap::new(),
+        };
+        Ok(Self {
+            storage,
+            settings: Arc::new(RwLock::new(settings)),
+        })
+    }
+
+    fn status(&self, feature: &NodeFeature) -> FeatureStatus {
+        let setting = self.settings.read().unwrap().get(feature.name).copied();
+        FeatureStatus::new(
+            feature.name,
+            feature.description,
+            setting.unwrap_or(feature.enabled_by_default),
+            setting.is_some(),
+        )
+    }
+
+    /// Enable or disable a feature, or restore its default state when no setting is given
+    async fn set(&self, feature: &NodeFeature, enabled: Option<bool>) -> Result<()> {
+        let settings = {
+            let mut settings = self.settings.write().unwrap();
+            match enabled {
+                Some(enabled) => settings.insert(feature.name.to_string(), enabled),
+                None => settings.remove(feature.name),
+            };
+            settings.clone()
+        };
+        self.storage
+            .set(
+                FEATURES_ID,
+                FEATURES_KEY.to_string(),
+                minicbor::to_vec(&settings)?,
+            )
+            .await
+    }
+}
+
+impl NodeManagerWorker {
+    pub(super) async fn list_features(
+        &self,
+        req: &RequestHeader,
+    ) -> Result<Response<FeatureList>, Response<Error>> {
+        Ok(Response::ok(req).body(FeatureList::new(self.node_manager.features())))
+    }
+
+    pub(super) async fn get_feature(
+        &self,
+        req: &RequestHeader,
+        name: &str,
+    ) -> Result<Response<FeatureStatus>, Response<Error>> {
+        match self.node_manager.feature(name) {
+            Ok(feature) => Ok(Response::ok(req).body(feature)),
+            Err(err) => Err(Response::not_found(req, &err.to_string())),
+        }
+    }
+
+    pub(super) async fn set_feature(
+        &self,
+        req: &RequestHeader,
+        name: &str,
+        dec: &mut Decoder<'_>,
+    ) -> Result<Response<FeatureStatus>, Response<Error>> {
+        let request: SetFeature = dec.decode()?;
+        self.update_feature(req, name, Some(request.enabled)).await
+    }
+
+    pub(super) async fn reset_feature(
+        &self,
+        req: &RequestHeader,
+        name: &str,
+    ) -> Result<Response<FeatureStatus>, Response<Error>> {
+        self.update_feature(req, name, None).await
+    }
+
+    async fn update_feature(
+        &self,
+        req: &RequestHeader,
+        name: &str,
+        enabled: Option<bool>,
+    ) -> Result<Response<FeatureStatus>, Response<Error>> {
+        match self.node_manager.set_feature(name, enabled).await {
+            Ok(feature) => Ok(Response::ok(req).body(feature)),
+            Err(err) if err.code().kind == Kind::NotFound => {
+                Err(Response::not_found(req, &err.to_string()))
+            }
+            Err(err) => Err(Response::internal_error(req, &err.to_string())),
+        }
+    }
+}
+
+impl NodeManager {
+    /// Return the state of all the features of this node
+    pub fn features(&self) -> Vec<FeatureStatus> {
+        NODE_FEATURES
+            .iter()
+            .map(|f| self.feature_flags.status(f))
+            .collect()
+    }
+
+    /// Return the state of a feature
+    pub fn feature(&self, name: &str) -> Result<FeatureStatus> {
+        Ok(self.feature_flags.status(&NodeFeature::find(name)?))
+    }
+
+    /// Return true if a feature is enabled on this node.
+    /// The subsystems behind a feature check it each time they are used, so that a change
+    /// applies without restarting the node
+    pub fn is_feature_enabled(&self, name: &str) -> bool {
+        self.feature(name).map_or(false, |f| f.enabled)
+    }
+
+    /// Return an error if a feature is disabled on this node
+    pub(crate) fn check_feature(&self, name: &str) -> Result<()> {
+        if self.is_feature_enabled(name) {
+            Ok(())
+        } else {
+            Err(ockam_core::Error::new(
+                Origin::Node,
+                Kind::Unsupported,
+                format!(
+                    "the feature {name} is disabled on this node, it can be enabled with `ockam feature enable {name}`"
+                ),
+            ))
+        }
+    }
+
+    /// Enable or disable a feature, or restore its default state when no setting is given.
+    /// The setting is kept when the node is restarted
+    pub async fn set_feature(&self, name: &str, enabled: Option<bool>) -> Result<FeatureStatus> {
+        let feature = NodeFeature::find(name)?;
+        self.feature_flags.set(&feature, enabled).await?;
+        info!(%name, ?enabled, "feature updated");
+        Ok(self.feature_flags.status(&feature))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::models::route::TraceRoute;
+    use crate::test_utils::start_manager_for_tests;
+    use ockam::identity::storage::InMemoryStorage;
+    use ockam::Context;
+    use ockam_multiaddr::MultiAddr;
+    use std::str::FromStr;
+
+    #[tokio::test]
+    async fn test_feature_settings_are_persisted() -> Result<()> {
+        let storage = InMemoryStorage::create();
+        let flags = FeatureFlags::load(storage.clone()).await?;
+        let tracing = NodeFeature::find(NodeFeature::ROUTE_TRACING)?;
+        assert!(NodeFeature::find("unknown").is_err());
+        assert!(!flags.status(&tracing).enabled);
+        assert!(!flags.status(&tracing).is_set);
+
+        flags.set(&tracing, Some(true)).await?;
+
+        // the settings survive a restart
+        let flags = FeatureFlags::load(storage.clone()).await?;
+        assert_eq!(
+            flags.status(&tracing),
+            FeatureStatus::new(tracing.name, tracing.description, true, true)
+        );
+
+        flags.set(&tracing, Some(false)).await?;
+        let flags = FeatureFlags::load(storage.clone()).await?;
+        assert!(!flags.status(&tracing).enabled);
+        assert!(flags.status(&tracing).is_set);
+
+        // a feature can be restored to its default state
+        flags.set(&tracing, None).await?;
+        let flags = FeatureFlags::load(storage).await?;
+        assert!(!flags.status(&tracing).enabled);
+        assert!(!flags.status(&tracing).is_set);
+        Ok(())
+    }
+
+    #[ockam_macros::test]
+    async fn test_disabled_features_are_refused(context: &mut Context) -> Result<()> {
+        let handle = start_manager_for_tests(context).await?;
+        let node_manager = &handle.node_manager;
+
+        let err = node_manager
+            .start_tracer_service(context, None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code().kind, Kind::Unsupported);
+
+        // the tracer can be started once the feature is enabled, without restarting the node
+        node_manager
+            .set_feature(NodeFeature::ROUTE_TRACING, Some(true))
+            .await?;
+        node_manager.start_tracer_service(context, None).await?;
+
+        // and routes can't be traced anymore when it is disabled again
+        node_manager
+            .set_feature(NodeFeature::ROUTE_TRACING, Some(false))
+            .await?;
+        let request = TraceRoute::new(MultiAddr::from_str("/secure/api")?);
+        let err = node_manager
+            .trace_route(context, request)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code().kind, Kind::Unsupported);
+
+        context.stop().await
+    }
+}
@@ -0,0 +1,186 @@
+use std::str::FromStr;
+
+use ockam::identity::Identifier;
+use ockam::Result;
+use ockam_core::api::{Error, RequestHeader, Response};
+use tracing::debug;
+
+use crate::nodes::models::groups::{Group, GroupList};
+
+use super::{NodeManager, NodeManagerWorker};
+
+impl NodeManagerWorker {
+    pub(super) async fn list_groups(
+        &self,
+        req: &RequestHeader,
+    ) -> Result<Response<GroupList>, Response<Error>> {
+        let groups = self.node_manager.groups().await?;
+        Ok(Response::ok(req).body(GroupList::new(groups)))
+    }
+
+    pub(super) async fn get_group(
+        &self,
+        req: &RequestHeader,
+        name: &str,
+    ) -> Result<Response<Group>, Response<Error>> {
+        match self.node_manager.group(name).await? {
+            Some(group) => Ok(Response::ok(req).body(group)),
+            None => Err(Response::not_found(req, &format!("Group {name} not found"))),
+        }
+    }
+
+    pub(super) async fn create_group(
+        &self,
+        req: &RequestHeader,
+        name: &str,
+    ) -> Result<Response<Group>, Response<Error>> {
+        let group = self.node_manager.create_group(name).await?;
+        Ok(Response::ok(req).body(group))
+    }
+
+    pub(super) async fn delete_group(
+        &self,
+        req: &RequestHeader,
+        name: &str,
+    ) -> Result<Response<()>, Response<Error>> {
+        if self.node_manager.delete_group(name).await? {
+            Ok(Response::ok(req))
+        } else {
+            Err(Response::not_found(req, &format!("Group {name} not found")))
+        }
+    }
+
+    pub(super) async fn add_group_member(
+        &self,
+        req: &RequestHeader,
+        name: &str,
+        member: &str,
+    ) -> Result<Response<Group>, Response<Error>> {
+        let member = Identifier::from_str(member)
+            .map_err(|e| Response::bad_request(req, &format!("Invalid identifier: {e}")))?;
+        match self.node_manager.add_group_member(name, &member).await? {
+            Some(group) => Ok(Response::ok(req).body(group)),
+            None => Err(Response::not_found(req, &format!("Group {name} not found"))),
+        }
+    }
+
+    pub(super) async fn remove_group_member(
+        &self,
+        req: &RequestHeader,
+        name: &str,
+        member: &str,
+    ) -> Result<Response<Group>, Response<Error>> {
+        let member = Identifier::from_str(member)
+            .map_err(|e| Response::bad_request(req, &format!("Invalid identifier: {e}")))?;
+        match self.node_manager.remove_group_member(name, &member).await? {
+            Some(group) => Ok(Response::ok(req).body(group)),
+            None => Err(Response::not_found(req, &format!("Group {name} not found"))),
+        }
+    }
+}
+
+impl NodeManager {
+    /// Return all the groups of the node with their members
+    pub async fn groups(&self) -> Result<Vec<Group>> {
+        let mut groups = vec![];
+        for name in self.groups_repository().list_groups().await? {
+            if let Some(group) = self.group(&name).await? {
+                groups.push(group);
+            }
+        }
+        Ok(groups)
+    }
+
+    /// Return a group with its members
+    pub async fn group(&self, name: &str) -> Result<Option<Group>> {
+        let repository = self.groups_repository();
+        if !repository.has_group(name).await? {
+            return Ok(None);
+        }
+        let members = repository.list_members(name).await?;
+        Ok(Some(Group::new(
+            name,
+            members.iter().map(|m| m.to_string()).collect(),
+        )))
+    }
+
+    /// Create a group, or return the existing group with this name
+    pub async fn create_group(&self, name: &str) -> Result<Group> {
+        self.groups_repository().create_group(name).await?;
+        debug!(%name, "group created");
+        Ok(self
+            .group(name)
+            .await?
+            .unwrap_or_else(|| Group::new(name, vec![])))
+    }
+
+    /// Delete a group and all its memberships, returning false if it does not exist
+    pub async fn delete_group(&self, name: &str) -> Result<bool> {
+        if self.group(name).await?.is_none() {
+            return Ok(false);
+        }
+        self.groups_repository().delete_group(name).await?;
+        debug!(%name, "group deleted");
+        Ok(true)
+    }
+
+    /// Add an identity to a group, returning `None` if the group does not exist
+    pub async fn add_group_member(&self, name: &str, member: &Identifier) -> Result<Option<Group>> {
+        if self.group(name).await?.is_none() {
+            return Ok(None);
+        }
+        self.groups_repository().add_member(name, member).await?;
+        debug!(%name, %member, "group member added");
+        self.group(name).await
+    }
+
+    /// Remove an identity from a group, returning `None` if the group does not exist
+    pub async fn remove_group_member(
+        &self,
+        name: &str,
+        member: &Identifier,
+    ) -> Result<Option<Group>> {
+        if self.group(name).await?.is_none() {
+            return Ok(None);
+        }
+        self.groups_repository().remove_member(name, member).await?;
+        debug!(%name, %member, "group member removed");
+        self.group(name).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam_node::Context;
+
+    use crate::test_utils::start_manager_for_tests;
+
+    #[ockam_macros::test]
+    async fn test_groups(context: &mut Context) -> Result<()> {
+        let handle = start_manager_for_tests(context).await?;
+        let node_manager = &handle.node_manager;
+        let alice = Identifier::from_str("I0123456789abcdef0123456789abcdef01234567")?;
+
+        assert!(node_manager
+            .add_group_member("admins", &alice)
+            .await?
+            .is_none());
+        node_manager.create_group("admins").await?;
+        let group = node_manager.add_group_member("admins", &alice).await?;
+        assert_eq!(group, Some(Group::new("admins", vec![alice.to_string()])));
+        assert_eq!(node_manager.groups().await?.len(), 1);
+        assert_eq!(
+            node_manager.groups_repository().get_groups(&alice).await?,
+            vec!["admins"]
+        );
+
+        let group = node_manager.remove_group_member("admins", &alice).await?;
+        assert_eq!(group, Some(Group::new("admins", vec![])));
+        assert!(node_manager.delete_group("admins").await?);
+        assert!(!node_manager.delete_group("admins").await?);
+        assert!(node_manager.group("admins").await?.is_none());
+
+        context.stop().await
+    }
+}
@@ -0,0 +1,374 @@
+//! Direct UDP paths between the node of an inlet and the node of its outlet.
+//!
+//! When an outlet is reached through a relay, its inlet can be created with the address of a
+//! UDP Rendezvous service. The node of the inlet then asks the node of the outlet, through the
+//! secure channel reaching the outlet, to start a UDP hole puncher using the same Rendezvous
+//! service. If both punchers open a hole, a new secure channel is created through it and the
+//! inlet sends its messages directly to the node of the outlet. Otherwise the inlet keeps
+//! using the relayed route.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use ockam::{Address, Context, Result, Routed, Worker};
+use ockam_core::{async_trait, route, AllowAll, DenyAll, NeutralMessage, Route};
+use ockam_node::{MessageSendReceiveOptions, WorkerBuilder};
+use ockam_transport_udp::{
+    PeerRoute, UdpHolePuncher, UdpTransport, DEFAULT_HOLE_OPEN_TIMEOUT, UDP,
+};
+
+use crate::cli_state::random_name;
+use crate::error::ApiError;
+use crate::nodes::models::portal::{HolePunchRequest, HolePunchResponse};
+use crate::nodes::registry::{InletHolePunchInfo, RegistryOf};
+use crate::DefaultAddress;
+
+use super::NodeManager;
+
+/// Address of the UDP Rendezvous service, on the node running it
+const RENDEZVOUS_SERVICE: &str = "rendezvous";
+
+/// Route to the UDP Rendezvous service listening at `host:port`
+fn rendezvous_route(rendezvous_address: &str) -> Route {
+    route![(UDP, rendezvous_address), RENDEZVOUS_SERVICE]
+}
+
+/// Split a route at its last secure channel: return the encryptor of this secure channel
+/// and the part of the route following it, which is local to the node at the other end
+fn split_at_last_secure_channel(
+    route: &Route,
+    is_encryptor: impl Fn(&Address) -> bool,
+) -> Option<(Address, Route)> {
+    let addresses: Vec<Address> = route.iter().cloned().collect();
+    let position = addresses.iter().rposition(is_encryptor)?;
+    Some((
+        addresses[position].clone(),
+        Route::create(addresses[position + 1..].to_vec()),
+    ))
+}
+
+impl NodeManager {
+    /// Return the UDP transport of the node, created the first time that it is needed
+    async fn udp_transport(&self, ctx: &Context) -> Result<&UdpTransport> {
+        self.udp_transport
+            .get_or_try_init(|| UdpTransport::create(ctx))
+            .await
+    }
+
+    /// Start the service opening UDP holes to the nodes of the inlets asking for it.
+    ///
+    /// The service only receives the messages sent through the secure channels accepted by the
+    /// default secure channel listener of the node. Starting it again has no effect
+    pub async fn start_hole_punch_responder(&self, ctx: &Context) -> Result<()> {
+        let addr: Address = DefaultAddress::HOLE_PUNCH_SERVICE.into();
+        if ctx.list_workers().await?.contains(&addr) {
+            return Ok(());
+        }
+        self.udp_transport(ctx).await?;
+        let ac = self.service_access_control(&addr, None).await?;
+        WorkerBuilder::new(HolePunchResponder {
+            punchers: self.registry.outlet_hole_punchers.clone(),
+            timeout: DEFAULT_HOLE_OPEN_TIMEOUT,
+        })
+        .with_address(addr)
+        .with_incoming_access_control_arc(ac)
+        .start(ctx)
+        .await
+    }
+
+    /// Try to open a UDP hole to the node at the end of the last secure channel of
+    /// `connection_route`, and return the route to use instead of `connection_route`.
+    ///
+    /// When the hole is opened, the returned route goes through a new secure channel created
+    /// through the hole, with the default secure channel listener of the other node. Otherwise
+    /// `connection_route` is returned unchanged
+    pub(super) async fn punch_hole_to_outlet(
+        &self,
+        ctx: &Context,
+        alias: &str,
+        connection_route: Route,
+        rendezvous_address: &str,
+        timeout: Duration,
+    ) -> Route {
+        match self
+            .open_direct_route(ctx, alias, &connection_route, rendezvous_address, timeout)
+            .await
+        {
+            Ok(Some(route)) => {
+                info!(%alias, %route, "Using a direct UDP route to the outlet");
+                route
+            }
+            Ok(None) => {
+                info!(%alias, "The UDP hole could not be opened. Using the relayed route to the outlet");
+                connection_route
+            }
+            Err(err) => {
+                warn!(%alias, %err, "Failed to open a UDP hole. Using the relayed route to the outlet");
+                connection_route
+            }
+        }
+    }
+
+    async fn open_direct_route(
+        &self,
+        ctx: &Context,
+        alias: &str,
+        connection_route: &Route,
+        rendezvous_address: &str,
+        timeout: Duration,
+    ) -> Result<Option<Route>> {
+        let registry = self.secure_channels.secure_channel_registry();
+        let (encryptor, route_on_outlet_node) =
+            split_at_last_secure_channel(connection_route, |addr| {
+                registry.get_channel_by_encryptor_address(addr).is_some()
+            })
+            .ok_or_else(|| ApiError::core("the route to the outlet has no secure channel"))?;
+        let channel = registry
+            .get_channel_by_encryptor_address(&encryptor)
+            .ok_or_else(|| ApiError::core("the secure channel to the outlet was closed"))?;
+        self.udp_transport(ctx).await?;
+
+        // the node of the outlet starts its puncher first, so that both punchers
+        // are registered by the Rendezvous service at about the same time
+        let request = HolePunchRequest::new(random_name(), random_name(), rendezvous_address);
+        let response: NeutralMessage = ctx
+            .send_and_receive_extended::<NeutralMessage>(
+                route![encryptor, DefaultAddress::HOLE_PUNCH_SERVICE],
+                NeutralMessage::from(minicbor::to_vec(&request)?),
+                MessageSendReceiveOptions::new().with_timeout(timeout),
+            )
+            .await?
+            .body();
+        let response: HolePunchResponse = minicbor::decode(&Vec::<u8>::from(response))?;
+        if let Some(error) = response.error {
+            return Err(ApiError::core(format!(
+                "the node of the outlet could not start a UDP hole puncher: {error}"
+            )));
+        }
+
+        let mut puncher_ctx = ctx
+            .new_detached(Address::random_tagged("HolePunch.ctx"), DenyAll, AllowAll)
+            .await?;
+        let puncher = match UdpHolePuncher::create_or_fallback(
+            &mut puncher_ctx,
+            request.puncher_name.as_str(),
+            request.peer_puncher_name.as_str(),
+            rendezvous_route(rendezvous_address),
+            timeout,
+            connection_route.clone(),
+        )
+        .await?
+        {
+            PeerRoute::Direct(puncher) => puncher,
+            PeerRoute::Relayed(_) => return Ok(None),
+        };
+
+        // the secure channel through the hole must reach the same identity as the relayed one
+        let secure_channel = match self
+            .create_secure_channel_internal(
+                ctx,
+                route![puncher.address(), DefaultAddress::SECURE_CHANNEL_LISTENER],
+                channel.my_id(),
+                Some(vec![channel.their_id().clone()]),
+                Some(timeout),
+                None,
+                self.secure_channel_compression,
+                None,
+            )
+            .await
+        {
+            Ok(secure_channel) => secure_channel,
+            Err(err) => {
+                puncher.stop().await?;
+                return Err(err);
+            }
+        };
+        let encryptor = secure_channel.encryptor_address().clone();
+        self.registry
+            .inlet_hole_punchers
+            .insert(
+                alias.to_string(),
+                InletHolePunchInfo::new(puncher, encryptor.clone()),
+            )
+            .await;
+        Ok(Some(route![encryptor, route_on_outlet_node]))
+    }
+
+    /// Stop the UDP hole puncher of an inlet, and the secure channel created through it
+    pub(super) async fn stop_inlet_hole_punch(&self, ctx: &Context, alias: &str) {
+        if let Some(info) = self.registry.inlet_hole_punchers.remove(alias).await {
+            if let Err(err) = self.delete_secure_channel(ctx, &info.secure_channel).await {
+                debug!(%alias, %err, "Failed to delete the secure channel of the UDP hole");
+            }
+            if let Err(err) = info.puncher.stop().await {
+                debug!(%alias, %err, "Failed to stop the UDP hole puncher");
+            }
+        }
+    }
+}
+
+/// Worker starting a UDP hole puncher on the node of an outlet, at the request of an inlet
+struct HolePunchResponder {
+    punchers: Arc<RegistryOf<String, Arc<UdpHolePuncher>>>,
+    timeout: Duration,
+}
+
+#[async_trait]
+impl Worker for HolePunchResponder {
+    type Message = NeutralMessage;
+    type Context = Context;
+
+    async fn handle_message(
+        &mut self,
+        ctx: &mut Context,
+        msg: Routed<NeutralMessage>,
+    ) -> Result<()> {
+        let return_route = msg.return_route();
+        let request: HolePunchRequest = minicbor::decode(&Vec::<u8>::from(msg.body()))?;
+        let response = match self.start_puncher(ctx, request).await {
+            Ok(()) => HolePunchResponse::default(),
+            Err(err) => {
+                warn!(%err, "Failed to start a UDP hole puncher");
+                HolePunchResponse::error(err.to_string())
+            }
+        };
+        ctx.send(
+            return_route,
+            NeutralMessage::from(minicbor::to_vec(&response)?),
+        )
+        .await
+    }
+}
+
+impl HolePunchResponder {
+    /// Start a puncher towards the puncher of the inlet node. The puncher is kept
+    /// if the hole is opened before the timeout, and stopped otherwise
+    async fn start_puncher(&self, ctx: &mut Context, request: HolePunchRequest) -> Result<()> {
+        let mut puncher = UdpHolePuncher::create(
+            ctx,
+            request.peer_puncher_name.as_str(),
+            request.puncher_name.as_str(),
+            rendezvous_route(&request.rendezvous_address),
+        )
+        .await?;
+        let punchers = self.punchers.clone();
+        let timeout = self.timeout;
+        let name = request.peer_puncher_name;
+        tokio::spawn(async move {
+            match puncher.wait_for_hole_open_timeout(timeout).await {
+                Ok(()) => {
+                    punchers.insert(name, Arc::new(puncher)).await;
+                }
+                Err(err) => {
+                    debug!(%name, %err, "The UDP hole could not be opened");
+                    if let Err(err) = puncher.stop().await {
+                        debug!(%name, %err, "Failed to stop the UDP hole puncher");
+                    }
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::start_manager_for_tests;
+    use ockam::identity::{SecureChannelListenerOptions, SecureChannelOptions};
+    use ockam_transport_udp::UdpRendezvousService;
+
+    #[test]
+    fn test_split_at_last_secure_channel() {
+        let route = route!["tcp", "relayed_channel", "relay", "channel", "outlet"];
+        let encryptors = ["relayed_channel", "channel"];
+        assert_eq!(
+            split_at_last_secure_channel(&route, |a| encryptors.contains(&a.address())),
+            Some(("channel".into(), route!["outlet"]))
+        );
+        assert_eq!(split_at_last_secure_channel(&route, |_| false), None);
+    }
+
+    /// Peer accepting the hole punching requests, but never starting its puncher
+    struct SilentPeer;
+
+    #[async_trait]
+    impl Worker for SilentPeer {
+        type Message = NeutralMessage;
+        type Context = Context;
+
+        async fn handle_message(
+            &mut self,
+            ctx: &mut Context,
+            msg: Routed<NeutralMessage>,
+        ) -> Result<()> {
+            let response = minicbor::to_vec(HolePunchResponse::default())?;
+            ctx.send(msg.return_route(), NeutralMessage::from(response))
+                .await
+        }
+    }
+
+    #[ockam_macros::test]
+    async fn test_relayed_route_is_used_when_the_hole_is_not_opened(
+        context: &mut Context,
+    ) -> Result<()> {
+        let handler = start_manager_for_tests(context).await?;
+        let node_manager = &handler.node_manager;
+        let secure_channels = &handler.secure_channels;
+
+        // a Rendezvous service is reachable, but the peer never punches its side of the hole
+        let udp = node_manager.udp_transport(context).await?;
+        let rendezvous_address = std::net::UdpSocket::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .to_string();
+        udp.listen(&rendezvous_address).await?;
+        UdpRendezvousService::start(context, RENDEZVOUS_SERVICE).await?;
+
+        let listener = secure_channels
+            .create_secure_channel_listener(
+                context,
+                &handler.identifier,
+                "listener",
+                SecureChannelListenerOptions::new(),
+            )
+            .await?;
+        context
+            .start_worker(DefaultAddress::HOLE_PUNCH_SERVICE, SilentPeer)
+            .await?;
+        context.flow_controls().add_consumer(
+            DefaultAddress::HOLE_PUNCH_SERVICE,
+            listener.flow_control_id(),
+        );
+        let channel = secure_channels
+            .create_secure_channel(
+                context,
+                &handler.identifier,
+                route!["listener"],
+                SecureChannelOptions::new(),
+            )
+            .await?;
+        let relayed_route = route![channel.encryptor_address().clone(), "outlet"];
+
+        let route = node_manager
+            .punch_hole_to_outlet(
+                context,
+                "inlet",
+                relayed_route.clone(),
+                &rendezvous_address,
+                Duration::from_secs(1),
+            )
+            .await;
+        assert_eq!(route, relayed_route);
+        assert!(
+            !node_manager
+                .registry
+                .inlet_hole_punchers
+                .contains_key("inlet")
+                .await
+        );
+
+        context.stop().await
+    }
+}
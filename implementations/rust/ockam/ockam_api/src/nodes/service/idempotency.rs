@@ -0,0 +1,310 @@
+use minicbor::{Decode, Encode, Encoder};
+
+use ockam::identity::storage::Storage;
+use ockam::identity::utils::now;
+use ockam::identity::IdentitySecureChannelLocalInfo;
+use ockam::Result;
+use ockam_core::api::{Method, RequestHeader, Response, ResponseHeader, Status};
+use ockam_core::compat::sync::Arc;
+use ockam_core::LocalMessage;
+
+use crate::DefaultAddress;
+
+/// Time during which a request can be retried with the same idempotency key, in seconds
+const IDEMPOTENCY_KEY_TTL: u64 = 24 * 60 * 60;
+
+/// Maximum number of results kept for retried requests, the oldest ones are dropped first
+const MAX_IDEMPOTENT_RESULTS: usize = 1000;
+
+const IDEMPOTENT_RESULT_KEY: &str = "idempotent_result";
+
+/// Scope of the keys sent by the callers which are not using a secure channel
+const LOCAL_CALLER: &str = "local";
+
+/// Result of a create request, returned again when it is retried with the same key
+#[derive(Debug, Clone, Encode, Decode)]
+#[rustfmt::skip]
+#[cbor(map)]
+struct IdempotentResult {
+    #[n(1)] path: String,
+    #[cbor(n(2), with = "minicbor::bytes")] request_body: Vec<u8>,
+    #[cbor(n(3), with = "minicbor::bytes")] response_body: Option<Vec<u8>>,
+    /// Time of the request, in seconds since the Unix epoch
+    #[n(4)] created_at: u64,
+}
+
+impl IdempotentResult {
+    fn is_expired(&self, now: u64) -> bool {
+        now.saturating_sub(self.created_at) >= IDEMPOTENCY_KEY_TTL
+    }
+}
+
+/// Outcome of a request carrying an idempotency key
+pub(super) enum Idempotency {
+    /// The request was not handled yet with this key
+    New,
+    /// The request was already handled, this is its encoded response
+    Replay(Vec<u8>),
+}
+
+/// Persisted results of the create requests sent with an idempotency key.
+///
+/// Clients with at-least-once semantics retry a request when they didn't receive its
+/// response. Such a retry returns the response of the first successful request instead
+/// of creating the resource twice or failing because it already exists, even if the node
+/// was restarted in between. The keys are scoped by the identifier of the caller, so that
+/// a caller never gets the response sent to another caller
+#[derive(Clone)]
+pub(super) struct IdempotentRequests {
+    storage: Arc<dyn Storage>,
+}
+
+impl IdempotentRequests {
+    pub(super) fn new(storage: Arc<dyn Storage>) -> Self {
+        Self { storage }
+    }
+
+    /// Return true if the request creates a resource and can be retried with an idempotency key
+    pub(super) fn is_create_request(req: &RequestHeader) -> bool {
+        use Method::*;
+        let segments = req.path_segments::<5>();
+        matches!(
+            (req.method(), segments.as_slice()),
+            (Some(Post), ["node", "inlet"])
+                | (Some(Post), ["node", "outlet"])
+                | (Some(Post), ["node", "forwarder"])
+                | (
+                    Some(Post),
+                    [
+                        "node",
+                        "services",
+                        DefaultAddress::KAFKA_OUTLET
+                            | DefaultAddress::KAFKA_CONSUMER
+                            | DefaultAddress::KAFKA_PRODUCER
+                            | DefaultAddress::KAFKA_DIRECT
+                    ]
+                )
+        )
+    }
+
+    /// Check if a request was already handled for the same caller with the same key.
+    /// The same key cannot be used for a different request
+    pub(super) async fn check(
+        &self,
+        req: &RequestHeader,
+        msg: &LocalMessage,
+        key: &str,
+        request_body: &[u8],
+    ) -> Result<Idempotency> {
+        let id = Self::id(msg, key);
+        let result = match self.get(&id).await? {
+            Some(result) if !result.is_expired(*now()?) => result,
+            _ => return Ok(Idempotency::New),
+        };
+        if result.path != req.path() || result.request_body != request_body {
+            return Ok(Idempotency::Replay(
+                Response::error(
+                    req,
+                    &format!("The idempotency key {key} was already used for another request"),
+                    Status::Conflict,
+                )
+                .to_vec()?,
+            ));
+        }
+        debug!(%key, path = %req.path(), "replaying the response of an idempotent request");
+        // the response refers to the id of the retried request
+        let header = ResponseHeader::new(req.id(), Status::Ok, result.response_body.is_some());
+        let mut bytes = vec![];
+        Encoder::new(&mut bytes).encode(&header)?;
+        if let Some(body) = &result.response_body {
+            bytes.extend_from_slice(body);
+        }
+        Ok(Idempotency::Replay(bytes))
+    }
+
+    /// Keep the response of a request for its retries.
+    /// Failed requests are not kept, so that they can be retried after a transient error
+    pub(super) async fn record(
+        &self,
+        req: &RequestHeader,
+        msg: &LocalMessage,
+        key: &str,
+        request_body: &[u8],
+        response: &[u8],
+    ) -> Result<()> {
+        let (header, dec) = Response::parse_response_header(response)?;
+        if !header.is_ok() {
+            return Ok(());
+        }
+        let response_body = if header.has_body() {
+            Some(dec.input()[dec.position()..].to_vec())
+        } else {
+            None
+        };
+
+        let now = *now()?;
+        self.remove_expired_and_oldest(now).await?;
+        let result = IdempotentResult {
+            path: req.path().to_string(),
+            request_body: request_body.to_vec(),
+            response_body,
+            created_at: now,
+        };
+        self.storage
+            .set(
+                &Self::id(msg, key),
+                IDEMPOTENT_RESULT_KEY.to_string(),
+                minicbor::to_vec(&result)?,
+            )
+            .await
+    }
+
+    /// Remove the expired results, and the oldest results when there is no room for a new one
+    async fn remove_expired_and_oldest(&self, now: u64) -> Result<()> {
+        let mut results = vec![];
+        for id in self.storage.keys(IDEMPOTENT_RESULT_KEY).await? {
+            match self.get(&id).await? {
+                Some(result) if !result.is_expired(now) => results.push((id, result.created_at)),
+                _ => self.storage.del(&id, IDEMPOTENT_RESULT_KEY).await?,
+            }
+        }
+        if results.len() >= MAX_IDEMPOTENT_RESULTS {
+            results.sort_by_key(|(_, created_at)| *created_at);
+            let excess = results.len() + 1 - MAX_IDEMPOTENT_RESULTS;
+            for (id, _) in results.into_iter().take(excess) {
+                self.storage.del(&id, IDEMPOTENT_RESULT_KEY).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn get(&self, id: &str) -> Result<Option<IdempotentResult>> {
+        match self.storage.get(id, IDEMPOTENT_RESULT_KEY).await? {
+            Some(bytes) => Ok(Some(minicbor::decode(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Identify a key sent by a caller, with the identifier of the caller when it uses a
+    /// secure channel
+    fn id(msg: &LocalMessage, key: &str) -> String {
+        let caller = match IdentitySecureChannelLocalInfo::find_info(msg) {
+            Ok(info) => info.their_identity_id().to_string(),
+            Err(_) => LOCAL_CALLER.to_string(),
+        };
+        format!("{caller}.{key}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam::identity::storage::InMemoryStorage;
+    use ockam::identity::Identifier;
+    use ockam_core::api::Request;
+    use ockam_core::{route, TransportMessage};
+    use std::str::FromStr;
+
+    fn request_header(path: &str) -> RequestHeader {
+        Request::post(path).header().clone()
+    }
+
+    fn message(caller: Option<&Identifier>) -> LocalMessage {
+        let local_info = match caller {
+            Some(caller) => IdentitySecureChannelLocalInfo::mark(vec![], caller.clone()).unwrap(),
+            None => vec![],
+        };
+        LocalMessage::new(
+            TransportMessage::v1(route!["_internal.nodemanager"], route![], vec![]),
+            local_info,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_retried_requests_return_the_first_response() -> Result<()> {
+        let storage = InMemoryStorage::create();
+        let requests = IdempotentRequests::new(storage.clone());
+        let msg = message(None);
+        let req = request_header("/node/outlet");
+        assert!(IdempotentRequests::is_create_request(&req));
+        assert!(!IdempotentRequests::is_create_request(
+            &Request::get("/node/outlet").header().clone()
+        ));
+
+        assert!(matches!(
+            requests.check(&req, &msg, "key", b"body").await?,
+            Idempotency::New
+        ));
+        let response = Response::ok(&req).body("created".to_string()).to_vec()?;
+        requests
+            .record(&req, &msg, "key", b"body", &response)
+            .await?;
+
+        // the results are persisted, a retry after a restart gets the first response
+        let requests = IdempotentRequests::new(storage);
+        let retry = request_header("/node/outlet");
+        match requests.check(&retry, &msg, "key", b"body").await? {
+            Idempotency::Replay(bytes) => {
+                let (header, _) = Response::parse_response_header(&bytes)?;
+                assert!(header.is_ok());
+                assert_eq!(header.re(), retry.id());
+                let body: String = Response::parse_response_body(&bytes)?;
+                assert_eq!(body, "created");
+            }
+            Idempotency::New => panic!("the response should be replayed"),
+        }
+
+        // the key cannot be reused for another request
+        match requests.check(&retry, &msg, "key", b"other body").await? {
+            Idempotency::Replay(bytes) => {
+                let (header, _) = Response::parse_response_header(&bytes)?;
+                assert_eq!(header.status(), Some(Status::Conflict));
+            }
+            Idempotency::New => panic!("the request should be rejected"),
+        }
+
+        // failed requests can be retried
+        let failed = Response::bad_request(&req, "failed").to_vec()?;
+        requests
+            .record(&req, &msg, "other key", b"body", &failed)
+            .await?;
+        assert!(matches!(
+            requests.check(&req, &msg, "other key", b"body").await?,
+            Idempotency::New
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_idempotency_keys_are_scoped_by_caller() -> Result<()> {
+        let requests = IdempotentRequests::new(InMemoryStorage::create());
+        let alice = Identifier::from_str("I0123456789abcdef0123456789abcdef01234567")?;
+        let bob = Identifier::from_str("I76543210fedcba9876543210fedcba9876543210")?;
+        let req = request_header("/node/inlet");
+        let response = Response::ok(&req)
+            .body("alice's inlet".to_string())
+            .to_vec()?;
+        requests
+            .record(&req, &message(Some(&alice)), "key", b"body", &response)
+            .await?;
+
+        assert!(matches!(
+            requests
+                .check(&req, &message(Some(&alice)), "key", b"body")
+                .await?,
+            Idempotency::Replay(_)
+        ));
+        // another caller using the same key sends a new request
+        assert!(matches!(
+            requests
+                .check(&req, &message(Some(&bob)), "key", b"body")
+                .await?,
+            Idempotency::New
+        ));
+        assert!(matches!(
+            requests.check(&req, &message(None), "key", b"body").await?,
+            Idempotency::New
+        ));
+        Ok(())
+    }
+}
@@ -141,6 +141,7 @@ impl InMemoryNode {
 
     pub async fn stop(&self, ctx: &Context) -> Result<()> {
         self.medic_handle.stop_medic(ctx).await?;
+        self.node_manager.schedule_tasks.abort();
         for addr in DefaultAddress::iter() {
             let result = ctx.stop_worker(addr).await;
             // when stopping we can safely ignore missing services
@@ -163,12 +164,26 @@ impl InMemoryNode {
         trust_options: NodeManagerTrustOptions,
     ) -> Result<Self> {
         let persistent = general_options.persistent;
+        let start_default_services = general_options.start_default_services;
         let node_manager =
             NodeManager::create(ctx, general_options, transport_options, trust_options).await?;
+        let node_manager = Arc::new(node_manager);
+        if start_default_services {
+            node_manager.start_default_services(ctx, persistent).await?;
+        }
+        // only the nodes started with `ockam node create` are pre-warmed, run their
+        // schedules, record their statistics and check their databases, the other in
+        // memory nodes are used for a single command
+        if persistent {
+            node_manager.start_pre_warm(ctx).await?;
+            node_manager.start_scheduler(ctx).await?;
+            node_manager.start_stats_recorder()?;
+            node_manager.start_storage_health_checks()?;
+        }
         debug!("start the Medic");
         let medic_handle = MedicHandle::start_medic(ctx).await?;
         Ok(Self {
-            node_manager: Arc::new(node_manager),
+            node_manager,
             medic_handle,
             persistent,
         })
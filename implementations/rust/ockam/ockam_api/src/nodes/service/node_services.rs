@@ -1,34 +1,45 @@
 use std::net::IpAddr;
+use std::path::PathBuf;
 
 use minicbor::Decoder;
 
 use ockam::identity::{identities, AuthorityService, TrustContext};
-use ockam::{Address, Context, Result};
+use ockam::{Address, Context, Result, Worker};
 use ockam_abac::expr::{eq, ident, str};
-use ockam_abac::Resource;
+use ockam_abac::{Env, Expr, Resource};
 use ockam_core::api::{Error, RequestHeader, Response};
 use ockam_core::compat::net::SocketAddr;
+use ockam_core::compat::sync::Arc;
 use ockam_core::route;
+use ockam_core::{DenyAll, IncomingAccessControl};
 use ockam_multiaddr::MultiAddr;
 use ockam_node::WorkerBuilder;
 
 use crate::auth::Server;
+use crate::cloud::project::InfluxDBTokenLeaseManagerConfig;
+use crate::discard::Discard;
 use crate::echoer::Echoer;
 use crate::error::ApiError;
-use crate::hop::Hop;
+use crate::file_transfer::{FileReceiver, DEFAULT_MAX_FILE_SIZE};
+use crate::hop::{ForwardingHop, Hop};
+use crate::influxdb_token_lease::{
+    InfluxDbTokenLeaseManager, InfluxDbTokenProvider, StorageLeases,
+};
 use crate::kafka::{
     ConsumerNodeAddr, KafkaInletController, KafkaPortalListener, KafkaSecureChannelControllerImpl,
-    KAFKA_OUTLET_BOOTSTRAP_ADDRESS, KAFKA_OUTLET_INTERCEPTOR_ADDRESS,
+    OffsetCommitSigner, TopicFilters, KAFKA_OUTLET_BOOTSTRAP_ADDRESS,
+    KAFKA_OUTLET_INTERCEPTOR_ADDRESS,
 };
 use crate::kafka::{OutletManagerService, PrefixRelayService};
 use crate::nodes::models::services::{
-    DeleteServiceRequest, ServiceList, ServiceStatus, StartAuthenticatedServiceRequest,
-    StartCredentialsService, StartEchoerServiceRequest, StartHopServiceRequest,
-    StartKafkaConsumerRequest, StartKafkaDirectRequest, StartKafkaOutletRequest,
-    StartKafkaProducerRequest, StartServiceRequest, StartUppercaseServiceRequest,
+    DeleteServiceRequest, KafkaServiceConfig, RegisterCustomServiceRequest, ServiceList,
+    ServiceStatus, StartAuthenticatedServiceRequest, StartCredentialsService,
+    StartDiscardServiceRequest, StartEchoerServiceRequest, StartFileReceiverServiceRequest,
+    StartHopServiceRequest, StartInfluxDbTokenLeaseManagerRequest, StartKafkaDirectRequest,
+    StartKafkaOutletRequest, StartServiceRequest, StartUppercaseServiceRequest,
 };
 use crate::nodes::registry::{
-    CredentialsServiceInfo, KafkaServiceInfo, KafkaServiceKind, Registry,
+    CredentialsServiceInfo, CustomServiceInfo, KafkaServiceInfo, KafkaServiceKind, Registry,
 };
 use crate::nodes::NodeManager;
 use crate::port_range::PortRange;
@@ -36,7 +47,7 @@ use crate::uppercase::Uppercase;
 use crate::DefaultAddress;
 use crate::{actions, resources};
 
-use super::NodeManagerWorker;
+use super::{NodeManagerWorker, NodeService};
 
 impl NodeManager {
     pub(super) async fn start_credentials_service_impl<'a>(
@@ -72,6 +83,7 @@ impl NodeManager {
         &self,
         ctx: &Context,
         addr: Address,
+        policy: Option<Expr>,
     ) -> Result<()> {
         if self
             .registry
@@ -84,8 +96,12 @@ impl NodeManager {
             ));
         }
 
-        let server = Server::new(self.attributes_reader());
-        ctx.start_worker(addr.clone(), server).await?;
+        let ac = self.service_access_control(&addr, policy).await?;
+        WorkerBuilder::new(Server::new(self.attributes_reader()))
+            .with_address(addr.clone())
+            .with_incoming_access_control_arc(ac)
+            .start(ctx)
+            .await?;
 
         self.registry
             .authenticated_services
@@ -99,12 +115,20 @@ impl NodeManager {
         &self,
         ctx: &Context,
         addr: Address,
+        policy: Option<Expr>,
     ) -> Result<()> {
         if self.registry.uppercase_services.contains_key(&addr).await {
             return Err(ApiError::core("Uppercase service exists at this address"));
         }
 
-        ctx.start_worker(addr.clone(), Uppercase).await?;
+        let ac = self
+            .default_service_or_policy_access_control(&addr, policy)
+            .await?;
+        WorkerBuilder::new(Uppercase)
+            .with_address(addr.clone())
+            .with_incoming_access_control_arc(ac)
+            .start(ctx)
+            .await?;
 
         self.registry
             .uppercase_services
@@ -118,22 +142,15 @@ impl NodeManager {
         &self,
         ctx: &Context,
         addr: Address,
+        policy: Option<Expr>,
     ) -> Result<()> {
         if self.registry.echoer_services.contains_key(&addr).await {
             return Err(ApiError::core("Echoer service exists at this address"));
         }
 
-        let maybe_trust_context_id = self.trust_context.as_ref().map(|c| c.id());
-        let resource = Resource::assert_inline(addr.address());
         let ac = self
-            .access_control(
-                &resource,
-                &actions::HANDLE_MESSAGE,
-                maybe_trust_context_id,
-                None,
-            )
+            .default_service_or_policy_access_control(&addr, policy)
             .await?;
-
         WorkerBuilder::new(Echoer)
             .with_address(addr.clone())
             .with_incoming_access_control_arc(ac)
@@ -148,6 +165,208 @@ impl NodeManager {
         Ok(())
     }
 
+    pub(super) async fn start_discard_service_impl(
+        &self,
+        ctx: &Context,
+        addr: Address,
+        policy: Option<Expr>,
+    ) -> Result<()> {
+        if self.registry.discard_services.contains_key(&addr).await {
+            return Err(ApiError::core("Discard service exists at this address"));
+        }
+
+        ctx.flow_controls()
+            .add_consumer(addr.clone(), &self.api_transport_flow_control_id);
+
+        let ac = self.service_access_control(&addr, policy).await?;
+        WorkerBuilder::new(Discard)
+            .with_address(addr.clone())
+            .with_incoming_access_control_arc(ac)
+            .start(ctx)
+            .await?;
+
+        self.registry
+            .discard_services
+            .insert(addr, Default::default())
+            .await;
+
+        Ok(())
+    }
+
+    pub(super) async fn start_file_receiver_service_impl(
+        &self,
+        ctx: &Context,
+        addr: Address,
+        directory: PathBuf,
+        max_file_size: Option<u64>,
+        policy: Option<Expr>,
+    ) -> Result<()> {
+        if self
+            .registry
+            .file_receiver_services
+            .contains_key(&addr)
+            .await
+        {
+            return Err(ApiError::core(
+                "File Receiver service exists at this address",
+            ));
+        }
+        if !directory.is_dir() {
+            return Err(ApiError::core(format!(
+                "The directory {} does not exist",
+                directory.display()
+            )));
+        }
+
+        // the files are only received through secure channels, see the consumers
+        // of the default secure channel listener
+        let ac = self.service_access_control(&addr, policy).await?;
+        let receiver = FileReceiver::new(directory)
+            .with_max_file_size(max_file_size.unwrap_or(DEFAULT_MAX_FILE_SIZE));
+        WorkerBuilder::new(receiver)
+            .with_address(addr.clone())
+            .with_incoming_access_control_arc(ac)
+            .start(ctx)
+            .await?;
+
+        self.registry
+            .file_receiver_services
+            .insert(addr, Default::default())
+            .await;
+
+        Ok(())
+    }
+
+    /// Return the access control of a service: either the given policy, or the default
+    /// policy of the node when no policy is given
+    pub(super) async fn service_access_control(
+        &self,
+        addr: &Address,
+        policy: Option<Expr>,
+    ) -> Result<Arc<dyn IncomingAccessControl>> {
+        match policy {
+            Some(policy) => self.service_policy_access_control(addr, policy).await,
+            None => self.default_service_access_control(addr).await,
+        }
+    }
+
+    /// Return the access control of a service which is started by default on every node, like
+    /// the echoer used for liveness checks: either the given policy, or the policies of the
+    /// trust context of the node. The service is left open when the node has no trust context
+    async fn default_service_or_policy_access_control(
+        &self,
+        addr: &Address,
+        policy: Option<Expr>,
+    ) -> Result<Arc<dyn IncomingAccessControl>> {
+        match policy {
+            Some(policy) => self.service_policy_access_control(addr, policy).await,
+            None => {
+                let maybe_trust_context_id = self.trust_context.as_ref().map(|c| c.id());
+                self.access_control(
+                    &Resource::new(addr.address()),
+                    &actions::HANDLE_MESSAGE,
+                    maybe_trust_context_id,
+                    None,
+                )
+                .await
+            }
+        }
+    }
+
+    /// Access control of a service started without a policy: only the members of the trust
+    /// context of the node are authorized, and nobody is authorized if there is no trust context
+    async fn default_service_access_control(
+        &self,
+        addr: &Address,
+    ) -> Result<Arc<dyn IncomingAccessControl>> {
+        match &self.trust_context {
+            Some(trust_context) => {
+                self.access_control(
+                    &Resource::new(addr.address()),
+                    &actions::HANDLE_MESSAGE,
+                    Some(trust_context.id()),
+                    None,
+                )
+                .await
+            }
+            None => Ok(Arc::new(DenyAll)),
+        }
+    }
+
+    /// Access control checking the messages sent to a service against a specific policy.
+    /// The policy is stored for a resource named after the service address so that it
+    /// can be listed and updated like any other policy
+    async fn service_policy_access_control(
+        &self,
+        addr: &Address,
+        policy: Expr,
+    ) -> Result<Arc<dyn IncomingAccessControl>> {
+        let resource = Resource::new(addr.address());
+        let action = actions::HANDLE_MESSAGE;
+        self.policies
+            .set_policy(&resource, &action, &policy)
+            .await?;
+
+        let mut env = Env::new();
+        env.put("resource.id", str(resource.as_str()));
+        env.put("action.id", str(action.as_str()));
+        if let Some(trust_context) = &self.trust_context {
+            env.put("resource.trust_context_id", str(trust_context.id()));
+        }
+        Ok(Arc::new(self.policy_access_control(resource, action, env)))
+    }
+
+    /// Start an InfluxDB token lease manager, reachable through the secure channels of the
+    /// default secure channel listener. The leases are kept in the storage of the node so
+    /// that their tokens are revoked when they expire, even after a restart of the node
+    pub async fn start_influxdb_token_lease_manager(
+        &self,
+        ctx: &Context,
+        config: InfluxDBTokenLeaseManagerConfig,
+    ) -> Result<()> {
+        let addr: Address = DefaultAddress::INFLUXDB_TOKEN_LEASE.into();
+        if self
+            .registry
+            .influxdb_token_lease_services
+            .contains_key(&addr)
+            .await
+        {
+            return Err(ApiError::core(
+                "The InfluxDB token lease manager is already started",
+            ));
+        }
+        let listener = self
+            .registry
+            .secure_channel_listeners
+            .get(&Address::from(DefaultAddress::SECURE_CHANNEL_LISTENER))
+            .await
+            .ok_or_else(|| {
+                ApiError::core(
+                    "The InfluxDB token lease manager requires the default secure channel listener",
+                )
+            })?;
+        let storage = self
+            .cli_state
+            .nodes
+            .get(&self.node_name)?
+            .token_leases_storage()
+            .await?;
+        let manager = InfluxDbTokenLeaseManager::new(
+            &config,
+            self.identities_repository(),
+            Arc::new(StorageLeases::new(Arc::new(storage))),
+            Arc::new(InfluxDbTokenProvider::new(&config)?),
+        )?;
+        manager
+            .start(ctx, listener.listener().flow_control_id())
+            .await?;
+        self.registry
+            .influxdb_token_lease_services
+            .insert(addr, Default::default())
+            .await;
+        Ok(())
+    }
+
     pub(super) async fn start_hop_service_impl(&self, ctx: &Context, addr: Address) -> Result<()> {
         if self.registry.hop_services.contains_key(&addr).await {
             return Err(ApiError::core("Hop service exists at this address"));
@@ -165,6 +384,119 @@ impl NodeManager {
 
         Ok(())
     }
+
+    /// Start a worker provided by the application embedding this node and register it
+    /// as a custom service. Incoming messages are checked against the policies set
+    /// for a resource named after the service address
+    pub async fn start_custom_service<W>(
+        &self,
+        ctx: &Context,
+        addr: Address,
+        service_type: &str,
+        worker: W,
+    ) -> Result<()>
+    where
+        W: Worker<Context = Context>,
+    {
+        self.check_custom_service(&addr, service_type).await?;
+
+        let ac = self.custom_service_access_control(&addr).await?;
+        WorkerBuilder::new(worker)
+            .with_address(addr.clone())
+            .with_incoming_access_control_arc(ac)
+            .start(ctx)
+            .await?;
+
+        self.registry
+            .custom_services
+            .insert(addr, CustomServiceInfo::new(service_type))
+            .await;
+
+        Ok(())
+    }
+
+    /// Access control for a custom service, based on the policies set for a resource
+    /// named after the service address
+    pub async fn custom_service_access_control(
+        &self,
+        addr: &Address,
+    ) -> Result<Arc<dyn IncomingAccessControl>> {
+        let maybe_trust_context_id = self.trust_context.as_ref().map(|c| c.id());
+        let resource = Resource::new(addr.address());
+        self.access_control(
+            &resource,
+            &actions::HANDLE_MESSAGE,
+            maybe_trust_context_id,
+            None,
+        )
+        .await
+    }
+
+    /// Register a worker which is already running on this node as a custom service,
+    /// so that it is listed with the other services of the node.
+    /// The service is a hop started at its own address, which checks the given policy, or the
+    /// default policy of the node, before forwarding the messages to the worker
+    pub async fn register_custom_service(
+        &self,
+        ctx: &Context,
+        addr: Address,
+        worker: Address,
+        service_type: &str,
+        policy: Option<Expr>,
+    ) -> Result<()> {
+        self.check_custom_service(&addr, service_type).await?;
+
+        if !ctx.list_workers().await?.contains(&worker) {
+            return Err(ApiError::core(format!(
+                "No worker is running at address {worker}"
+            )));
+        }
+
+        let ac = self.service_access_control(&addr, policy).await?;
+        WorkerBuilder::new(ForwardingHop::new(worker.clone()))
+            .with_address(addr.clone())
+            .with_incoming_access_control_arc(ac)
+            .start(ctx)
+            .await?;
+
+        self.registry
+            .custom_services
+            .insert(
+                addr,
+                CustomServiceInfo::new(service_type).with_worker(worker),
+            )
+            .await;
+
+        Ok(())
+    }
+
+    /// Remove a custom service from the list of services of this node, and stop the hop
+    /// of a registered service. The worker itself is owned by the application and is not stopped
+    pub async fn unregister_custom_service(&self, ctx: &Context, addr: &Address) -> Result<()> {
+        match self.registry.custom_services.remove(addr).await {
+            Some(info) => {
+                if info.worker().is_some() {
+                    ctx.stop_worker(addr.clone()).await?;
+                }
+                Ok(())
+            }
+            None => Err(ApiError::core(format!(
+                "No custom service is registered at address {addr}"
+            ))),
+        }
+    }
+
+    async fn check_custom_service(&self, addr: &Address, service_type: &str) -> Result<()> {
+        if service_type.is_empty() || DefaultAddress::is_valid(service_type) {
+            return Err(ApiError::core(format!(
+                "Invalid custom service type '{service_type}'"
+            )));
+        }
+        if self.registry.custom_services.contains_key(addr).await {
+            return Err(ApiError::core("Custom service exists at this address"));
+        }
+        Ok(())
+    }
 }
 
 impl NodeManagerWorker {
@@ -177,7 +509,7 @@ impl NodeManagerWorker {
         let req_body: StartAuthenticatedServiceRequest = dec.decode()?;
         let addr = req_body.addr.to_string().into();
         self.node_manager
-            .start_authenticated_service_impl(ctx, addr)
+            .start_authenticated_service_impl(ctx, addr, req_body.policy)
             .await?;
         Ok(Response::ok(req))
     }
@@ -191,7 +523,7 @@ impl NodeManagerWorker {
         let req_body: StartUppercaseServiceRequest = dec.decode()?;
         let addr = req_body.addr.to_string().into();
         self.node_manager
-            .start_uppercase_service_impl(ctx, addr)
+            .start_uppercase_service_impl(ctx, addr, req_body.policy)
             .await?;
         Ok(Response::ok(req))
     }
@@ -205,11 +537,62 @@ impl NodeManagerWorker {
         let req_body: StartEchoerServiceRequest = dec.decode()?;
         let addr = req_body.addr.to_string().into();
         self.node_manager
-            .start_echoer_service_impl(ctx, addr)
+            .start_echoer_service_impl(ctx, addr, req_body.policy)
+            .await?;
+        Ok(Response::ok(req))
+    }
+
+    pub(super) async fn start_discard_service(
+        &self,
+        ctx: &Context,
+        req: &RequestHeader,
+        dec: &mut Decoder<'_>,
+    ) -> Result<Response, Response<Error>> {
+        let req_body: StartDiscardServiceRequest = dec.decode()?;
+        let addr = req_body.addr.to_string().into();
+        self.node_manager
+            .start_discard_service_impl(ctx, addr, req_body.policy)
             .await?;
         Ok(Response::ok(req))
     }
 
+    pub(super) async fn start_file_receiver_service(
+        &self,
+        ctx: &Context,
+        req: &RequestHeader,
+        dec: &mut Decoder<'_>,
+    ) -> Result<Response, Response<Error>> {
+        let req_body: StartFileReceiverServiceRequest = dec.decode()?;
+        let addr = req_body.addr.to_string().into();
+        self.node_manager
+            .start_file_receiver_service_impl(
+                ctx,
+                addr,
+                req_body.directory.into(),
+                req_body.max_file_size,
+                req_body.policy,
+            )
+            .await?;
+        Ok(Response::ok(req))
+    }
+
+    pub(super) async fn start_influxdb_token_lease_manager(
+        &self,
+        ctx: &Context,
+        req: &RequestHeader,
+        dec: &mut Decoder<'_>,
+    ) -> Result<Response, Response<Error>> {
+        let req_body: StartInfluxDbTokenLeaseManagerRequest = dec.decode()?;
+        match self
+            .node_manager
+            .start_influxdb_token_lease_manager(ctx, req_body.config)
+            .await
+        {
+            Ok(()) => Ok(Response::ok(req)),
+            Err(err) => Err(Response::bad_request(req, &err.to_string())),
+        }
+    }
+
     pub(super) async fn start_hop_service(
         &self,
         ctx: &Context,
@@ -222,6 +605,46 @@ impl NodeManagerWorker {
         Ok(Response::ok(req))
     }
 
+    pub(super) async fn register_custom_service(
+        &self,
+        ctx: &Context,
+        req: &RequestHeader,
+        dec: &mut Decoder<'_>,
+    ) -> Result<Response, Response<Error>> {
+        let req_body: RegisterCustomServiceRequest = dec.decode()?;
+        match self
+            .node_manager
+            .register_custom_service(
+                ctx,
+                req_body.address(),
+                req_body.worker_address(),
+                req_body.service_type(),
+                req_body.policy().cloned(),
+            )
+            .await
+        {
+            Ok(()) => Ok(Response::ok(req)),
+            Err(err) => Err(Response::bad_request(req, &err.to_string())),
+        }
+    }
+
+    pub(super) async fn unregister_custom_service(
+        &self,
+        ctx: &Context,
+        req: &RequestHeader,
+        dec: &mut Decoder<'_>,
+    ) -> Result<Response, Response<Error>> {
+        let req_body: DeleteServiceRequest = dec.decode()?;
+        match self
+            .node_manager
+            .unregister_custom_service(ctx, &req_body.address())
+            .await
+        {
+            Ok(()) => Ok(Response::ok(req)),
+            Err(err) => Err(Response::not_found(req, &err.to_string())),
+        }
+    }
+
     pub(super) async fn start_credentials_service(
         &self,
         ctx: &Context,
@@ -261,6 +684,9 @@ impl NodeManagerWorker {
         request: &RequestHeader,
         dec: &mut Decoder<'_>,
     ) -> Result<Vec<u8>> {
+        if let Err(e) = self.check_readiness(request, NodeService::KafkaServices) {
+            return Ok(e.to_vec()?);
+        }
         let body: StartServiceRequest<StartKafkaOutletRequest> = dec.decode()?;
 
         let default_secure_channel_listener_flow_control_id = context
@@ -270,9 +696,16 @@ impl NodeManagerWorker {
                 ApiError::core("Unable to get flow control for secure channel listener")
             })?;
 
+        // the consumer topics can only be checked against the attributes issued by an authority
+        let attributes_reader = self
+            .node_manager
+            .trust_context()
+            .ok()
+            .map(|_| self.node_manager.attributes_reader());
         PrefixRelayService::create(
             context,
             default_secure_channel_listener_flow_control_id.clone(),
+            attributes_reader,
         )
         .await?;
 
@@ -282,6 +715,7 @@ impl NodeManagerWorker {
                 self.node_manager.secure_channels.clone(),
                 self.node_manager.trust_context()?.id(),
                 default_secure_channel_listener_flow_control_id,
+                self.node_manager.kafka_metrics().clone(),
             )
             .await?;
         }
@@ -294,6 +728,8 @@ impl NodeManagerWorker {
                 KAFKA_OUTLET_BOOTSTRAP_ADDRESS.into(),
                 Some(KAFKA_OUTLET_BOOTSTRAP_ADDRESS.to_string()),
                 false,
+                None,
+                None,
             )
             .await
         {
@@ -309,6 +745,9 @@ impl NodeManagerWorker {
                     KafkaServiceInfo::new(KafkaServiceKind::Outlet),
                 )
                 .await;
+            self.node_manager
+                .stop_worker_on_shutdown(format!("kafka {}", body.address()), body.address().into())
+                .await;
         }
 
         Ok(Response::ok(request).to_vec()?)
@@ -320,6 +759,9 @@ impl NodeManagerWorker {
         req: &RequestHeader,
         dec: &mut Decoder<'_>,
     ) -> Result<Vec<u8>> {
+        if let Err(e) = self.check_readiness(req, NodeService::KafkaServices) {
+            return Ok(e.to_vec()?);
+        }
         let body: StartServiceRequest<StartKafkaDirectRequest> = dec.decode()?;
         let listener_address: Address = body.address().into();
         let body_req = body.request();
@@ -373,6 +815,7 @@ impl NodeManagerWorker {
                 self.node_manager.secure_channels.clone(),
                 self.node_manager.trust_context()?.id(),
                 default_secure_channel_listener_flow_control_id,
+                self.node_manager.kafka_metrics().clone(),
             )
             .await?;
         }
@@ -384,6 +827,8 @@ impl NodeManagerWorker {
                 KAFKA_OUTLET_BOOTSTRAP_ADDRESS.into(),
                 Some(KAFKA_OUTLET_BOOTSTRAP_ADDRESS.to_string()),
                 false,
+                None,
+                None,
             )
             .await?;
 
@@ -398,7 +843,9 @@ impl NodeManagerWorker {
             secure_channels,
             ConsumerNodeAddr::Direct(consumer_route.clone()),
             trust_context_id,
-        );
+        )
+        .with_metrics(self.node_manager.kafka_metrics().clone())
+        .into_trait();
 
         let inlet_controller = KafkaInletController::new(
             "/secure/api".parse().unwrap(),
@@ -424,14 +871,20 @@ impl NodeManagerWorker {
                 "/secure/api".parse().unwrap(),
                 None,
                 None,
+                None,
+                None,
             )
             .await?;
 
         KafkaPortalListener::create(
             context,
             inlet_controller,
-            secure_channel_controller.into_trait(),
+            secure_channel_controller.clone(),
             local_interceptor_address.clone(),
+            None,
+            Default::default(),
+            None,
+            self.node_manager.kafka_metrics().clone(),
         )
         .await?;
 
@@ -440,8 +893,15 @@ impl NodeManagerWorker {
                 .registry
                 .kafka_services
                 .insert(
+                    local_interceptor_address.clone(),
+                    KafkaServiceInfo::new(KafkaServiceKind::Direct)
+                        .with_secure_channel_controller(secure_channel_controller),
+                )
+                .await;
+            self.node_manager
+                .stop_worker_on_shutdown(
+                    format!("kafka {local_interceptor_address}"),
                     local_interceptor_address,
-                    KafkaServiceInfo::new(KafkaServiceKind::Direct),
                 )
                 .await;
         }
@@ -455,27 +915,12 @@ impl NodeManagerWorker {
         req: &RequestHeader,
         dec: &mut Decoder<'_>,
     ) -> Result<Vec<u8>> {
-        let body: StartServiceRequest<StartKafkaConsumerRequest> = dec.decode()?;
-        let listener_address: Address = body.address().into();
-        let body_req = body.request();
-        let outlet_node_multiaddr = body_req.project_route().to_string().parse()?;
-
-        if let Err(e) = self
-            .start_kafka_service_impl(
-                context,
-                listener_address,
-                body_req.bootstrap_server_addr.ip(),
-                body_req.bootstrap_server_addr.port(),
-                body_req.brokers_port_range(),
-                outlet_node_multiaddr,
-                KafkaServiceKind::Consumer,
-            )
-            .await
-        {
+        if let Err(e) = self.check_readiness(req, NodeService::KafkaServices) {
             return Ok(e.to_vec()?);
-        };
-
-        Ok(Response::ok(req).to_vec()?)
+        }
+        let body: StartServiceRequest<KafkaServiceConfig> = dec.decode()?;
+        self.start_kafka_service(context, req, body, KafkaServiceKind::Consumer)
+            .await
     }
 
     pub(super) async fn start_kafka_producer_service(
@@ -484,27 +929,56 @@ impl NodeManagerWorker {
         req: &RequestHeader,
         dec: &mut Decoder<'_>,
     ) -> Result<Vec<u8>> {
-        let body: StartServiceRequest<StartKafkaProducerRequest> = dec.decode()?;
+        if let Err(e) = self.check_readiness(req, NodeService::KafkaServices) {
+            return Ok(e.to_vec()?);
+        }
+        let body: StartServiceRequest<KafkaServiceConfig> = dec.decode()?;
+        self.start_kafka_service(context, req, body, KafkaServiceKind::Producer)
+            .await
+    }
+
+    /// Validate the configuration of a kafka consumer or producer, start the service
+    /// and respond with the configuration used by the node
+    async fn start_kafka_service(
+        &self,
+        context: &Context,
+        req: &RequestHeader,
+        body: StartServiceRequest<KafkaServiceConfig>,
+        kind: KafkaServiceKind,
+    ) -> Result<Vec<u8>> {
         let listener_address: Address = body.address().into();
-        let body_req = body.request();
-        let outlet_node_multiaddr = body_req.project_route().to_string().parse()?;
+        let config = body.request().clone();
+        let outlet_node_multiaddr = match config.validate() {
+            Ok(route) => route,
+            Err(errors) => {
+                let message = format!("invalid kafka {kind} configuration: {}", errors.join(", "));
+                return Ok(Response::bad_request(req, &message).to_vec()?);
+            }
+        };
+        let config = KafkaServiceConfig {
+            project_route: outlet_node_multiaddr.to_string(),
+            ..config
+        };
 
         if let Err(e) = self
             .start_kafka_service_impl(
                 context,
                 listener_address,
-                body_req.bootstrap_server_addr.ip(),
-                body_req.bootstrap_server_addr.port(),
-                body_req.brokers_port_range(),
+                config.bootstrap_server_addr.ip(),
+                config.bootstrap_server_addr.port(),
+                config.brokers_port_range,
                 outlet_node_multiaddr,
-                KafkaServiceKind::Producer,
+                config.cleartext_headers.clone(),
+                config.topic_filters(),
+                config.sign_offset_commits(),
+                kind,
             )
             .await
         {
             return Ok(e.to_vec()?);
         };
 
-        Ok(Response::ok(req).to_vec()?)
+        Ok(Response::ok(req).body(config).to_vec()?)
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -516,6 +990,9 @@ impl NodeManagerWorker {
         server_bootstrap_port: u16,
         brokers_port_range: (u16, u16),
         outlet_node_multiaddr: MultiAddr,
+        cleartext_headers: Option<Vec<String>>,
+        topic_filters: TopicFilters,
+        sign_offset_commits: bool,
         kind: KafkaServiceKind,
     ) -> Result<(), Response<Error>> {
         debug!(
@@ -548,11 +1025,26 @@ impl NodeManagerWorker {
             }
         }
 
+        let offset_commit_signer = sign_offset_commits.then(|| {
+            OffsetCommitSigner::new(
+                self.node_manager.identities(),
+                self.node_manager.identifier().clone(),
+                self.node_manager
+                    .trust_context()
+                    .and_then(|trust_context| trust_context.authority())
+                    .map(|authority| authority.identifier().clone())
+                    .ok(),
+                self.node_manager.event_publisher(),
+            )
+        });
+
         let secure_channel_controller = KafkaSecureChannelControllerImpl::new(
             secure_channels,
             ConsumerNodeAddr::Relay(outlet_node_multiaddr.clone()),
             trust_context_id,
-        );
+        )
+        .with_metrics(self.node_manager.kafka_metrics().clone())
+        .into_trait();
 
         let inlet_controller = KafkaInletController::new(
             outlet_node_multiaddr.clone(),
@@ -578,14 +1070,20 @@ impl NodeManagerWorker {
                 outlet_node_multiaddr,
                 None,
                 None,
+                None,
+                None,
             )
             .await?;
 
         KafkaPortalListener::create(
             context,
             inlet_controller,
-            secure_channel_controller.into_trait(),
+            secure_channel_controller.clone(),
             local_interceptor_address.clone(),
+            cleartext_headers,
+            topic_filters,
+            offset_commit_signer,
+            self.node_manager.kafka_metrics().clone(),
         )
         .await?;
 
@@ -593,7 +1091,17 @@ impl NodeManagerWorker {
             self.node_manager
                 .registry
                 .kafka_services
-                .insert(local_interceptor_address, KafkaServiceInfo::new(kind))
+                .insert(
+                    local_interceptor_address.clone(),
+                    KafkaServiceInfo::new(kind)
+                        .with_secure_channel_controller(secure_channel_controller),
+                )
+                .await;
+            self.node_manager
+                .stop_worker_on_shutdown(
+                    format!("kafka {local_interceptor_address}"),
+                    local_interceptor_address,
+                )
                 .await;
         }
 
@@ -635,6 +1143,9 @@ impl NodeManagerWorker {
                         .kafka_services
                         .remove(&address)
                         .await;
+                    self.node_manager
+                        .remove_shutdown_hook(&format!("kafka {address}"))
+                        .await;
                     Response::ok(req)
                 } else {
                     error!(address = %address, "Service is not a kafka {}", kind.to_string());
@@ -653,7 +1164,15 @@ impl NodeManagerWorker {
         req: &RequestHeader,
         service_type: &str,
     ) -> Result<Vec<u8>> {
-        if !DefaultAddress::is_valid(service_type) {
+        let is_custom_service_type = self
+            .node_manager
+            .registry
+            .custom_services
+            .values()
+            .await
+            .iter()
+            .any(|info| info.service_type() == service_type);
+        if !DefaultAddress::is_valid(service_type) && !is_custom_service_type {
             return Ok(Response::bad_request(
                 req,
                 &format!("Service type '{service_type}' doesn't exist"),
@@ -677,7 +1196,7 @@ impl NodeManagerWorker {
             .to_vec()?)
     }
 
-    async fn list_services_impl(registry: &Registry) -> Vec<ServiceStatus> {
+    pub(super) async fn list_services_impl(registry: &Registry) -> Vec<ServiceStatus> {
         let mut list = Vec::new();
         registry
             .authenticated_services
@@ -712,6 +1231,50 @@ impl NodeManagerWorker {
                     DefaultAddress::ECHO_SERVICE,
                 ))
             });
+        registry
+            .discard_services
+            .keys()
+            .await
+            .iter()
+            .for_each(|addr| {
+                list.push(ServiceStatus::new(
+                    addr.address(),
+                    DefaultAddress::DISCARD_SERVICE,
+                ))
+            });
+        registry
+            .file_receiver_services
+            .keys()
+            .await
+            .iter()
+            .for_each(|addr| {
+                list.push(ServiceStatus::new(
+                    addr.address(),
+                    DefaultAddress::FILE_RECEIVER_SERVICE,
+                ))
+            });
+        registry
+            .tracer_services
+            .keys()
+            .await
+            .iter()
+            .for_each(|addr| {
+                list.push(ServiceStatus::new(
+                    addr.address(),
+                    DefaultAddress::TRACER_SERVICE,
+                ))
+            });
+        registry
+            .influxdb_token_lease_services
+            .keys()
+            .await
+            .iter()
+            .for_each(|addr| {
+                list.push(ServiceStatus::new(
+                    addr.address(),
+                    DefaultAddress::INFLUXDB_TOKEN_LEASE,
+                ))
+            });
         registry.hop_services.keys().await.iter().for_each(|addr| {
             list.push(ServiceStatus::new(
                 addr.address(),
@@ -745,7 +1308,166 @@ impl NodeManagerWorker {
                     },
                 ))
             });
+        registry
+            .custom_services
+            .entries()
+            .await
+            .iter()
+            .for_each(|(address, info)| {
+                list.push(ServiceStatus::new(address.address(), info.service_type()))
+            });
 
         list
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::time::Duration;
+    use ockam_node::MessageSendReceiveOptions;
+
+    use crate::test_utils::start_manager_for_tests;
+
+    #[ockam_macros::test]
+    async fn test_register_custom_service(context: &mut Context) -> Result<()> {
+        let handler = start_manager_for_tests(context).await?;
+        let node_manager = &handler.node_manager;
+        context.start_worker("app_worker", Echoer).await?;
+
+        // the service type is checked, and the worker must already be running
+        assert!(node_manager
+            .register_custom_service(
+                context,
+                "invalid".into(),
+                "app_worker".into(),
+                DefaultAddress::ECHO_SERVICE,
+                None
+            )
+            .await
+            .is_err());
+        assert!(node_manager
+            .register_custom_service(
+                context,
+                "missing".into(),
+                "missing_worker".into(),
+                "app",
+                None
+            )
+            .await
+            .is_err());
+
+        // the policy of the service is stored, and the messages it allows reach the worker
+        node_manager
+            .register_custom_service(
+                context,
+                "custom_echo".into(),
+                "app_worker".into(),
+                "app_echo",
+                Some(Expr::Bool(true)),
+            )
+            .await?;
+        assert!(matches!(
+            node_manager
+                .policies
+                .get_policy(&Resource::new("custom_echo"), &actions::HANDLE_MESSAGE)
+                .await?,
+            Some(Expr::Bool(true))
+        ));
+        let reply: String = context
+            .send_and_receive(route!["custom_echo"], "hello".to_string())
+            .await?;
+        assert_eq!(reply, "hello");
+
+        // the messages denied by the policy don't reach the worker
+        node_manager
+            .register_custom_service(
+                context,
+                "custom_denied".into(),
+                "app_worker".into(),
+                "app_echo",
+                Some(Expr::Bool(false)),
+            )
+            .await?;
+        let denied = context
+            .send_and_receive_extended::<String>(
+                route!["custom_denied"],
+                "hello".to_string(),
+                MessageSendReceiveOptions::new().with_timeout(Duration::from_millis(500)),
+            )
+            .await;
+        assert!(denied.is_err());
+
+        // unregistering a service stops its hop, but not the worker of the application
+        node_manager
+            .unregister_custom_service(context, &"custom_echo".into())
+            .await?;
+        let workers = context.list_workers().await?;
+        assert!(!workers.contains(&"custom_echo".into()));
+        assert!(workers.contains(&"app_worker".into()));
+        assert!(node_manager
+            .unregister_custom_service(context, &"custom_echo".into())
+            .await
+            .is_err());
+
+        context.stop().await
+    }
+
+    #[ockam_macros::test]
+    async fn test_diagnostic_services_policies(context: &mut Context) -> Result<()> {
+        let handler = start_manager_for_tests(context).await?;
+        let node_manager = &handler.node_manager;
+
+        // a service started with a policy only accepts the messages allowed by that policy
+        node_manager
+            .start_uppercase_service_impl(context, "upper_open".into(), Some(Expr::Bool(true)))
+            .await?;
+        assert_eq!(send(context, "upper_open").await?, "HELLO");
+        node_manager
+            .start_echoer_service_impl(context, "echo_closed".into(), Some(Expr::Bool(false)))
+            .await?;
+        assert!(send(context, "echo_closed").await.is_err());
+
+        // without a policy, the default policy of the trust context is stored and applied,
+        // so a message which is not sent by a member of the trust context is denied
+        node_manager
+            .start_uppercase_service_impl(context, "upper_default".into(), None)
+            .await?;
+        assert!(node_manager
+            .policies
+            .get_policy(&Resource::new("upper_default"), &actions::HANDLE_MESSAGE)
+            .await?
+            .is_some());
+        assert!(send(context, "upper_default").await.is_err());
+
+        // the discard service stores its policy like the other services
+        node_manager
+            .start_discard_service_impl(context, "discard".into(), Some(Expr::Bool(false)))
+            .await?;
+        assert!(matches!(
+            node_manager
+                .policies
+                .get_policy(&Resource::new("discard"), &actions::HANDLE_MESSAGE)
+                .await?,
+            Some(Expr::Bool(false))
+        ));
+        assert!(node_manager
+            .start_discard_service_impl(context, "discard".into(), None)
+            .await
+            .is_err());
+
+        context.stop().await
+    }
+
+    /// Send a message to a local service and return its reply, or an error if it doesn't reply
+    async fn send(context: &Context, addr: &str) -> Result<String> {
+        let reply = context
+            .send_and_receive_extended::<String>(
+                route![addr],
+                "hello".to_string(),
+                MessageSendReceiveOptions::new().with_timeout(Duration::from_millis(500)),
+            )
+            .await?;
+        Ok(reply.body())
+    }
+}
@@ -0,0 +1,310 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use minicbor::Decoder;
+
+use ockam::identity::utils::now;
+use ockam::identity::{AttributesEntry, Identifier, TimestampInSeconds};
+use ockam::Result;
+use ockam_core::api::{Error, Request, RequestHeader, Response};
+use ockam_core::AsyncTryClone;
+use ockam_node::api::Client;
+use ockam_node::Context;
+
+use crate::error::ApiError;
+use crate::nodes::connection::Connection;
+use crate::nodes::models::attributes::FetchAttributes;
+
+use super::{NodeManager, NodeManagerWorker};
+
+/// Default maximum time to wait for the attributes of an identity from another node
+const DEFAULT_FETCH_ATTRIBUTES_TIMEOUT: Duration = Duration::from_secs(10);
+
+impl NodeManagerWorker {
+    pub(super) async fn fetch_attributes(
+        &self,
+        ctx: &Context,
+        req: &RequestHeader,
+        dec: &mut Decoder<'_>,
+    ) -> Result<Response<AttributesEntry>, Response<Error>> {
+        let request: FetchAttributes = dec.decode()?;
+        let identifier = request.identifier.clone();
+        match self.node_manager.fetch_attributes(ctx, request).await {
+            Ok(Some(entry)) => Ok(Response::ok(req).body(entry)),
+            Ok(None) => Err(Response::not_found(
+                req,
+                &format!("No attributes are known for {identifier}"),
+            )),
+            Err(err) => Err(Response::bad_request(req, &err.to_string())),
+        }
+    }
+}
+
+impl NodeManager {
+    /// Fetch the attributes of an identity from the authenticated service of the authority of
+    /// the trust context of this node, and merge them into the attributes of this node.
+    ///
+    /// The attributes are fetched over a secure channel, which must be established with the
+    /// authority: the attributes stored on this node are checked by its policies, so they can't
+    /// be supplied by any other node. They are stored as attested by the authority and don't
+    /// replace the attributes attested by other identities.
+    /// Return the attributes of the identity after the merge
+    pub async fn fetch_attributes(
+        &self,
+        ctx: &Context,
+        request: FetchAttributes,
+    ) -> Result<Option<AttributesEntry>> {
+        let timeout = request
+            .timeout
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_FETCH_ATTRIBUTES_TIMEOUT);
+
+        let connection_ctx = Arc::new(ctx.async_try_clone().await?);
+        let connection = self
+            .make_connection(
+                connection_ctx,
+                &request.addr,
+                None,
+                None,
+                None,
+                Some(timeout),
+            )
+            .await?;
+        let result = self
+            .request_attributes(ctx, &connection, &request.identifier, timeout)
+            .await;
+        self.close_probe(ctx, &connection).await;
+        let (attester, fetched) = result?;
+
+        let repository = self.identities_repository();
+        let local = repository.get_attributes(&request.identifier).await?;
+        let fetched = match fetched {
+            Some(fetched) => fetched,
+            None => return Ok(local),
+        };
+        match attested_entry(local.as_ref(), &attester, fetched, now()?) {
+            Some(entry) => {
+                debug!(identifier = %request.identifier, %attester, "merging the fetched attributes");
+                repository
+                    .merge_attributes(&request.identifier, entry)
+                    .await?;
+                repository.get_attributes(&request.identifier).await
+            }
+            None => Ok(local),
+        }
+    }
+
+    /// Ask the attributes of an identity through a connection to the authority, and return them
+    /// with the identifier of the authority
+    async fn request_attributes(
+        &self,
+        ctx: &Context,
+        connection: &Connection,
+        identifier: &Identifier,
+        timeout: Duration,
+    ) -> Result<(Identifier, Option<AttributesEntry>)> {
+        let attester = connection
+            .secure_channel_encryptors
+            .last()
+            .and_then(|encryptor| {
+                self.secure_channels
+                    .secure_channel_registry()
+                    .get_channel_by_encryptor_address(encryptor)
+            })
+            .map(|channel| channel.their_id().clone())
+            .ok_or_else(|| {
+                ApiError::core(format!(
+                    "the attributes of {identifier} can only be fetched over a secure channel"
+                ))
+            })?;
+        let authority = self.trust_context()?.authority()?.identifier();
+        if &attester != authority {
+            return Err(ApiError::core(format!(
+                "the attributes of {identifier} can only be fetched from the authority {authority}, not from {attester}"
+            )));
+        }
+
+        let route = connection.route(self.tcp_transport()).await?;
+        let fetched = Client::new(&route, Some(timeout))
+            .ask::<(), AttributesEntry>(ctx, Request::get(format!("/{identifier}")))
+            .await?
+            .found()?;
+        Ok((attester, fetched))
+    }
+}
+
+/// Return the fetched attributes as an entry attested by the authority which sent them.
+///
+/// The attributes attested locally by other identities are kept, for example the attributes
+/// set by an enroller. `None` is returned if no attributes can be merged
+fn attested_entry(
+    local: Option<&AttributesEntry>,
+    attester: &Identifier,
+    fetched: AttributesEntry,
+    now: TimestampInSeconds,
+) -> Option<AttributesEntry> {
+    let is_mergeable = |key: &[u8]| match local.and_then(|local| local.provenance(key)) {
+        Some(provenance) => provenance.attested_by() == Some(attester),
+        None => true,
+    };
+    let attrs: BTreeMap<Vec<u8>, Vec<u8>> = fetched
+        .attrs()
+        .iter()
+        .filter(|(key, _)| is_mergeable(key))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    if attrs.is_empty() {
+        return None;
+    }
+    let validity = fetched
+        .attributes_validity()
+        .cloned()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|(key, _)| attrs.contains_key(key.as_bytes()))
+        .collect();
+    Some(
+        AttributesEntry::new(attrs, now, fetched.expires(), Some(attester.clone()))
+            .with_validity(validity),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    use ockam::identity::{
+        IdentitiesStorage, IdentityAttributesWriter, SecureChannelListenerOptions,
+    };
+    use ockam_abac::expr::{eq, ident, str};
+    use ockam_abac::{Action, Env, PolicyAccessControl, Resource};
+    use ockam_core::AllowAll;
+    use ockam_multiaddr::MultiAddr;
+    use ockam_node::WorkerBuilder;
+
+    use crate::auth::Server;
+    use crate::test_utils::start_manager_for_tests;
+
+    fn entry(attrs: &[(&str, &str)], attested_by: &Identifier) -> AttributesEntry {
+        AttributesEntry::new(
+            attrs
+                .iter()
+                .map(|(k, v)| (k.as_bytes().to_vec(), v.as_bytes().to_vec()))
+                .collect(),
+            TimestampInSeconds(10),
+            Some(TimestampInSeconds(1000)),
+            Some(attested_by.clone()),
+        )
+    }
+
+    #[test]
+    fn test_attested_entry() -> Result<()> {
+        let authority = Identifier::from_str("I0123456789abcdef0123456789abcdef01234567")?;
+        let peer = Identifier::from_str("I89abcdef0123456789abcdef0123456789abcdef")?;
+        let fetched = entry(&[("role", "admin"), ("team", "kafka")], &authority);
+
+        // the fetched attributes are attested by the node which sent them
+        let attested =
+            attested_entry(None, &peer, fetched.clone(), TimestampInSeconds(20)).unwrap();
+        assert_eq!(attested.attested_by(), Some(peer.clone()));
+        assert_eq!(attested.added(), TimestampInSeconds(20));
+        assert_eq!(attested.expires(), Some(TimestampInSeconds(1000)));
+        assert_eq!(attested.attrs().len(), 2);
+
+        // the attributes attested locally by another identity are kept
+        let local = entry(&[("role", "user")], &authority);
+        let attested =
+            attested_entry(Some(&local), &peer, fetched.clone(), TimestampInSeconds(20)).unwrap();
+        assert_eq!(attested.attrs().len(), 1);
+        assert!(attested.attrs().contains_key(b"team".as_slice()));
+
+        // the attributes previously fetched from the same node are refreshed
+        let local = entry(&[("role", "user")], &peer);
+        let attested =
+            attested_entry(Some(&local), &peer, fetched.clone(), TimestampInSeconds(20)).unwrap();
+        assert_eq!(attested.attrs().len(), 2);
+
+        let local = entry(&[("role", "user"), ("team", "web")], &authority);
+        assert!(attested_entry(Some(&local), &peer, fetched, TimestampInSeconds(20)).is_none());
+        Ok(())
+    }
+
+    #[ockam_macros::test]
+    async fn test_attributes_supplied_by_a_peer_are_rejected(context: &mut Context) -> Result<()> {
+        let handler = start_manager_for_tests(context).await?;
+        let node_manager = &handler.node_manager;
+        let identities_creation = node_manager.identities().identities_creation();
+        let peer = identities_creation.create_identity().await?;
+        let member = identities_creation.create_identity().await?;
+
+        // a peer which is not the authority claims that the member is an admin
+        let peer_attributes = IdentitiesStorage::create();
+        peer_attributes
+            .put_attributes(
+                member.identifier(),
+                entry(
+                    &[
+                        ("role", "admin"),
+                        ("trust_context_id", "test_trust_context_id"),
+                    ],
+                    peer.identifier(),
+                ),
+            )
+            .await?;
+        WorkerBuilder::new(Server::new(peer_attributes))
+            .with_address("peer_authenticated")
+            .with_incoming_access_control(AllowAll)
+            .start(context)
+            .await?;
+        let options = SecureChannelListenerOptions::new()
+            .with_trust_context(node_manager.trust_context()?.clone());
+        context
+            .flow_controls()
+            .add_consumer("peer_authenticated", &options.spawner_flow_control_id());
+        node_manager
+            .secure_channels
+            .create_secure_channel_listener(context, peer.identifier(), "peer_listener", options)
+            .await?;
+
+        let request = FetchAttributes::new(
+            MultiAddr::from_str("/secure/peer_listener/service/peer_authenticated")?,
+            member.identifier().clone(),
+        );
+        assert!(node_manager
+            .fetch_attributes(context, request)
+            .await
+            .is_err());
+
+        // the attributes supplied by the peer don't satisfy a policy of the node
+        let repository = node_manager.identities_repository();
+        assert!(repository
+            .get_attributes(member.identifier())
+            .await?
+            .is_none());
+        let (resource, action) = (Resource::new("db"), Action::new("handle_message"));
+        node_manager
+            .policies
+            .set_policy(
+                &resource,
+                &action,
+                &eq([ident("subject.role"), str("admin")]),
+            )
+            .await?;
+        let access_control = PolicyAccessControl::new(
+            node_manager.policies.clone(),
+            repository,
+            resource,
+            action,
+            Env::new(),
+        );
+        assert!(
+            !access_control
+                .is_identity_authorized(member.identifier())
+                .await?
+        );
+
+        context.stop().await
+    }
+}
@@ -0,0 +1,286 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use minicbor::Decoder;
+
+use ockam::identity::storage::Storage;
+use ockam::identity::{Identifier, PeerActivity, TimestampInSeconds};
+use ockam::Result;
+use ockam_core::api::{Error, RequestHeader, Response};
+use ockam_core::compat::sync::Arc;
+
+use crate::nodes::models::peers::{GetPeers, PeerList, PeerStatus};
+
+use super::{NodeManager, NodeManagerWorker};
+
+const PEERS_KEY: &str = "peer";
+
+/// Maximum number of identities kept in the inventory. The least recently seen identities
+/// are removed above this number
+const MAX_PEERS: usize = 10_000;
+
+/// Persisted inventory of the identities a node established secure channels with
+///
+/// The activity of each identity is stored as one record, keyed by its identifier
+#[derive(Clone)]
+pub(crate) struct PeersStorage {
+    storage: Arc<dyn Storage>,
+    max_peers: usize,
+}
+
+impl PeersStorage {
+    pub(crate) fn new(storage: Arc<dyn Storage>) -> Self {
+        Self {
+            storage,
+            max_peers: MAX_PEERS,
+        }
+    }
+
+    /// Merge the activity of each identity into its record
+    pub(super) async fn save(&self, peers: Vec<(String, PeerActivity)>) -> Result<()> {
+        let mut new_peers = false;
+        for (identifier, activity) in peers {
+            let activity = match self.get(&identifier).await? {
+                Some(mut stored) => {
+                    stored.merge(&activity);
+                    stored
+                }
+                None => {
+                    new_peers = true;
+                    activity
+                }
+            };
+            let record = peer_status(identifier.clone(), &activity);
+            self.storage
+                .set(
+                    &identifier,
+                    PEERS_KEY.to_string(),
+                    minicbor::to_vec(&record)?,
+                )
+                .await?;
+        }
+        if new_peers {
+            self.remove_least_recently_seen().await?;
+        }
+        Ok(())
+    }
+
+    /// Remove the least recently seen identities above the maximum number of identities
+    async fn remove_least_recently_seen(&self) -> Result<()> {
+        if self.storage.keys(PEERS_KEY).await?.len() <= self.max_peers {
+            return Ok(());
+        }
+        let mut peers: Vec<_> = self.list().await?.into_iter().collect();
+        peers.sort_by_key(|(_, activity)| activity.last_seen);
+        let excess = peers.len().saturating_sub(self.max_peers);
+        for (identifier, _) in peers.into_iter().take(excess) {
+            self.storage.del(&identifier, PEERS_KEY).await?;
+        }
+        Ok(())
+    }
+
+    /// Return the persisted activity of all the identities
+    async fn list(&self) -> Result<BTreeMap<String, PeerActivity>> {
+        let mut result = BTreeMap::new();
+        for identifier in self.storage.keys(PEERS_KEY).await? {
+            if let Some(activity) = self.get(&identifier).await? {
+                result.insert(identifier, activity);
+            }
+        }
+        Ok(result)
+    }
+
+    async fn get(&self, identifier: &str) -> Result<Option<PeerActivity>> {
+        match self.storage.get(identifier, PEERS_KEY).await? {
+            Some(bytes) => {
+                let record: PeerStatus = minicbor::decode(&bytes)?;
+                Ok(Some(PeerActivity {
+                    first_seen: TimestampInSeconds(record.first_seen),
+                    last_seen: TimestampInSeconds(record.last_seen),
+                    channels_initiated: record.channels_initiated,
+                    channels_accepted: record.channels_accepted,
+                }))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+fn peer_status(identifier: String, activity: &PeerActivity) -> PeerStatus {
+    PeerStatus {
+        identifier,
+        first_seen: *activity.first_seen,
+        last_seen: *activity.last_seen,
+        channels_initiated: activity.channels_initiated,
+        channels_accepted: activity.channels_accepted,
+    }
+}
+
+impl NodeManagerWorker {
+    pub(super) async fn list_peers(
+        &self,
+        req: &RequestHeader,
+        dec: &mut Decoder<'_>,
+    ) -> Result<Response<PeerList>, Response<Error>> {
+        let request: GetPeers = dec.decode()?;
+        match self.node_manager.list_peers(request.seen_since).await {
+            Ok(peers) => Ok(Response::ok(req).body(PeerList::new(peers))),
+            Err(err) => Err(Response::internal_error(req, &err.to_string())),
+        }
+    }
+
+    pub(super) async fn get_peer(
+        &self,
+        req: &RequestHeader,
+        identifier: &str,
+    ) -> Result<Response<PeerStatus>, Response<Error>> {
+        let identifier = match Identifier::from_str(identifier) {
+            Ok(identifier) => identifier,
+            Err(_) => {
+                return Err(Response::bad_request(
+                    req,
+                    &format!("Invalid identifier {identifier}"),
+                ))
+            }
+        };
+        match self.node_manager.get_peer(&identifier).await {
+            Ok(Some(peer)) => Ok(Response::ok(req).body(peer)),
+            Ok(None) => Err(Response::not_found(
+                req,
+                &format!("No secure channel was established with {identifier}"),
+            )),
+            Err(err) => Err(Response::internal_error(req, &err.to_string())),
+        }
+    }
+}
+
+impl NodeManager {
+    /// Return the identities this node established secure channels with, seen at or after
+    /// `seen_since`. The channels which have not been persisted yet are included
+    pub async fn list_peers(&self, seen_since: Option<u64>) -> Result<Vec<PeerStatus>> {
+        let mut peers = self.peers_storage.list().await?;
+        for (identifier, current) in self.secure_channels.peers().list() {
+            peers
+                .entry(identifier.to_string())
+                .and_modify(|activity| activity.merge(&current))
+                .or_insert(current);
+        }
+        Ok(peers
+            .into_iter()
+            .filter(|(_, activity)| seen_since.map_or(true, |s| *activity.last_seen >= s))
+            .map(|(identifier, activity)| peer_status(identifier, &activity))
+            .collect())
+    }
+
+    /// Return the secure channels established with an identity
+    pub async fn get_peer(&self, identifier: &Identifier) -> Result<Option<PeerStatus>> {
+        let mut peer = self.peers_storage.get(&identifier.to_string()).await?;
+        if let Some(current) = self.secure_channels.peers().get(identifier) {
+            match peer.as_mut() {
+                Some(activity) => activity.merge(&current),
+                None => peer = Some(current),
+            }
+        }
+        Ok(peer.map(|activity| peer_status(identifier.to_string(), &activity)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam::identity::storage::InMemoryStorage;
+    use ockam::identity::utils::now;
+    use ockam_node::Context;
+
+    use crate::test_utils::start_manager_for_tests;
+
+    fn activity(first_seen: u64, last_seen: u64, initiated: u64, accepted: u64) -> PeerActivity {
+        PeerActivity {
+            first_seen: TimestampInSeconds(first_seen),
+            last_seen: TimestampInSeconds(last_seen),
+            channels_initiated: initiated,
+            channels_accepted: accepted,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_peers_storage_merges_the_activity() -> Result<()> {
+        let storage = PeersStorage::new(InMemoryStorage::create());
+
+        storage
+            .save(vec![("alice".into(), activity(100, 150, 2, 0))])
+            .await?;
+        storage
+            .save(vec![
+                ("alice".into(), activity(200, 250, 0, 1)),
+                ("bob".into(), activity(220, 220, 1, 0)),
+            ])
+            .await?;
+
+        let peers = storage.list().await?;
+        assert_eq!(peers.len(), 2);
+        assert_eq!(peers.get("alice"), Some(&activity(100, 250, 2, 1)));
+        assert_eq!(peers.get("bob"), Some(&activity(220, 220, 1, 0)));
+        assert_eq!(storage.get("carol").await?, None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_peers_storage_removes_the_least_recently_seen_peers() -> Result<()> {
+        let storage = PeersStorage {
+            storage: InMemoryStorage::create(),
+            max_peers: 2,
+        };
+        storage
+            .save(vec![
+                ("alice".into(), activity(100, 100, 1, 0)),
+                ("bob".into(), activity(200, 200, 1, 0)),
+            ])
+            .await?;
+        storage
+            .save(vec![("alice".into(), activity(300, 300, 1, 0))])
+            .await?;
+        storage
+            .save(vec![("carol".into(), activity(400, 400, 1, 0))])
+            .await?;
+
+        let peers = storage.list().await?;
+        assert_eq!(peers.keys().collect::<Vec<_>>(), vec!["alice", "carol"]);
+        Ok(())
+    }
+
+    #[ockam_macros::test]
+    async fn test_node_peers(context: &mut Context) -> Result<()> {
+        let handle = start_manager_for_tests(context).await?;
+        let node_manager = &handle.node_manager;
+        let alice = Identifier::from_str("I0123456789abcdef0123456789abcdef01234567")?;
+        let bob = Identifier::from_str("I76543210fedcba9876543210fedcba9876543210")?;
+        let peers = node_manager.secure_channels.peers();
+        let t = now()?;
+
+        assert!(node_manager.get_peer(&alice).await?.is_none());
+        peers.record_channel(&alice, true, t);
+        peers.record_channel(&bob, false, t);
+
+        // the peers which are not persisted yet are returned
+        let alice_status = node_manager.get_peer(&alice).await?.unwrap();
+        assert_eq!(alice_status.channels_initiated, 1);
+        assert_eq!(node_manager.list_peers(None).await?.len(), 2);
+        assert!(node_manager.list_peers(Some(*t + 1)).await?.is_empty());
+
+        // the persisted activity is merged with the current activity
+        let taken = peers
+            .take()
+            .into_iter()
+            .map(|(identifier, activity)| (identifier.to_string(), activity))
+            .collect();
+        node_manager.peers_storage.save(taken).await?;
+        peers.record_channel(&alice, false, t);
+        let alice_status = node_manager.get_peer(&alice).await?.unwrap();
+        assert_eq!(alice_status.channels_initiated, 1);
+        assert_eq!(alice_status.channels_accepted, 1);
+        assert_eq!(node_manager.list_peers(None).await?.len(), 2);
+
+        context.stop().await
+    }
+}
@@ -0,0 +1,253 @@
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
+
+use minicbor::Decoder;
+use serde::Serialize;
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+use ockam::identity::utils::now;
+use ockam::identity::Identifier;
+use ockam::{Address, Context, Result};
+use ockam_abac::{Env, Explanation, Expr, PolicyAudit};
+use ockam_core::api::{RequestHeader, Response};
+use ockam_core::env::get_env;
+use ockam_core::{AllowAll, Decodable, DenyAll, RelayMessage, Route};
+
+/// Environment variable setting the file where the accesses denied by the policies of a node
+/// are recorded, as one JSON object per line. The `policy_audit.log` file of the node is used
+/// when it is not set
+const OCKAM_POLICY_AUDIT_LOG_PATH: &str = "OCKAM_POLICY_AUDIT_LOG_PATH";
+
+/// Maximum number of records waiting to be written to the audit log file.
+/// The records are dropped when the file can't be written fast enough
+const AUDIT_LOG_CAPACITY: usize = 10_000;
+
+/// Maximum number of denied requests waiting to be answered
+const DENIAL_REPLIES_CAPACITY: usize = 1_000;
+
+/// Access denied by a policy: the evaluated policy, the attribute values it read, and its
+/// failing condition
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub(crate) struct PolicyDenialRecord {
+    pub(crate) timestamp: u64,
+    pub(crate) identifier: Identifier,
+    pub(crate) resource: Option<String>,
+    pub(crate) action: Option<String>,
+    pub(crate) policy: String,
+    pub(crate) attributes: BTreeMap<String, Option<String>>,
+    pub(crate) failing_predicate: Option<String>,
+    pub(crate) error: Option<String>,
+}
+
+impl PolicyDenialRecord {
+    pub(crate) fn new(id: &Identifier, environment: &Env, explanation: &Explanation) -> Self {
+        let value = |name: &str| match environment.get(name) {
+            Ok(Expr::Str(s)) => Some(s.clone()),
+            Ok(x) => Some(x.to_string()),
+            Err(_) => None,
+        };
+        Self {
+            timestamp: now().map(|t| *t).unwrap_or_default(),
+            identifier: id.clone(),
+            resource: value("resource.id"),
+            action: value("action.id"),
+            policy: explanation.expression().to_string(),
+            attributes: explanation
+                .attributes()
+                .iter()
+                .map(|(name, value)| (name.clone(), value.as_ref().map(|v| v.to_string())))
+                .collect(),
+            failing_predicate: explanation.failing_predicate().map(|p| p.to_string()),
+            error: explanation.error().map(|e| e.to_string()),
+        }
+    }
+}
+
+/// Audit of the accesses denied by the policies of a node.
+///
+/// Every denial is appended to the audit log file of the node by a separate thread, so that
+/// the messages are never delayed, and a record is dropped if it can't be written.
+/// The denied requests are answered with a forbidden response, containing the redacted reason
+/// of the denial, when the policies are configured to return it
+pub(crate) struct PolicyAuditLog {
+    records: Option<SyncSender<PolicyDenialRecord>>,
+    replies: mpsc::Sender<(Route, Vec<u8>)>,
+}
+
+impl PolicyAuditLog {
+    /// Start the audit of the denials of a node, appending its records to the file set with
+    /// `OCKAM_POLICY_AUDIT_LOG_PATH`, or to the given file
+    pub(crate) async fn start(ctx: &Context, default_path: PathBuf) -> Result<Self> {
+        let path = match get_env::<PathBuf>(OCKAM_POLICY_AUDIT_LOG_PATH) {
+            Ok(path) => path.unwrap_or(default_path),
+            Err(e) => {
+                warn!("Invalid {OCKAM_POLICY_AUDIT_LOG_PATH}, using {default_path:?}: {e}");
+                default_path
+            }
+        };
+        let records = match Self::write_to(path.clone()) {
+            Ok(records) => Some(records),
+            Err(e) => {
+                warn!("Policy audit log file disabled, cannot open {path:?}: {e}");
+                None
+            }
+        };
+
+        let (replies, mut receiver) = mpsc::channel::<(Route, Vec<u8>)>(DENIAL_REPLIES_CAPACITY);
+        let ctx = ctx
+            .new_detached(Address::random_tagged("PolicyAudit.ctx"), DenyAll, AllowAll)
+            .await?;
+        tokio::spawn(async move {
+            while let Some((return_route, response)) = receiver.recv().await {
+                if let Err(e) = ctx.send(return_route, response).await {
+                    debug!("Failed to answer a request denied by a policy: {e}");
+                }
+            }
+        });
+        Ok(Self { records, replies })
+    }
+
+    /// Start a thread appending the records to a file
+    fn write_to(path: PathBuf) -> std::io::Result<SyncSender<PolicyDenialRecord>> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let (sender, receiver) = sync_channel(AUDIT_LOG_CAPACITY);
+        std::thread::Builder::new()
+            .name("policy-audit-log".to_string())
+            .spawn(move || Self::write_records(file, receiver))?;
+        Ok(sender)
+    }
+
+    /// Append the records to the file, until the audit log is dropped.
+    /// The file is flushed once there are no more records to write
+    fn write_records(file: File, receiver: Receiver<PolicyDenialRecord>) {
+        let mut writer = BufWriter::new(file);
+        while let Ok(record) = receiver.recv() {
+            let mut result = Self::write_record(&mut writer, &record);
+            while let Ok(record) = receiver.try_recv() {
+                result = result.and(Self::write_record(&mut writer, &record));
+            }
+            if let Err(e) = result.and(writer.flush()) {
+                warn!("Failed to write the policy audit log: {e}");
+            }
+        }
+    }
+
+    fn write_record(writer: &mut impl Write, record: &PolicyDenialRecord) -> std::io::Result<()> {
+        let line = serde_json::to_string(record).map_err(std::io::Error::from)?;
+        writeln!(writer, "{line}")
+    }
+}
+
+impl PolicyAudit for PolicyAuditLog {
+    fn record_denial(&self, id: &Identifier, environment: &Env, explanation: &Explanation) {
+        if let Some(records) = &self.records {
+            let record = PolicyDenialRecord::new(id, environment, explanation);
+            if let Err(TrySendError::Full(_)) = records.try_send(record) {
+                debug!("The policy audit log is full, a record was dropped");
+            }
+        }
+    }
+
+    /// Answer a denied request with a forbidden response. Messages which are not requests,
+    /// like the messages of a portal, are dropped without an answer
+    fn reply_denial(&self, msg: &RelayMessage, reason: &str) {
+        let response = forbidden_response(&msg.local_message().transport().payload, reason);
+        if let Some(response) = response {
+            if let Err(mpsc::error::TrySendError::Full(_)) = self
+                .replies
+                .try_send((msg.return_route().clone(), response))
+            {
+                debug!("Too many denied requests, a request is not answered");
+            }
+        }
+    }
+}
+
+/// Return the forbidden response to a message if it is a request
+fn forbidden_response(payload: &[u8], reason: &str) -> Option<Vec<u8>> {
+    let body = Vec::<u8>::decode(payload).ok()?;
+    let header: RequestHeader = Decoder::new(&body).decode().ok()?;
+    header.method()?;
+    Response::forbidden(&header, &format!("Forbidden: {reason}"))
+        .to_vec()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam_abac::expr::str;
+    use ockam_abac::{explain, parse};
+    use ockam_core::api::{Request, ResponseHeader, Status};
+    use ockam_core::{route, Encodable, LocalMessage, TransportMessage};
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    #[ockam_macros::test]
+    async fn test_policy_audit_log(context: &mut Context) -> Result<()> {
+        let path = std::env::temp_dir().join(format!(
+            "policy-audit-{}.log",
+            hex::encode(rand::random::<[u8; 8]>())
+        ));
+        let audit_log = PolicyAuditLog::start(context, path.clone()).await?;
+
+        // a denial is recorded in the file
+        let id = Identifier::from_str("I0123456789abcdef0123456789abcdef01234567")?;
+        let mut environment = Env::new();
+        environment.put("resource.id", str("echo"));
+        environment.put("action.id", str("handle_message"));
+        environment.put("subject.role", str("user"));
+        let policy = parse(r#"(= subject.role "admin")"#).unwrap().unwrap();
+        audit_log.record_denial(&id, &environment, &explain(&policy, &environment));
+
+        let mut contents = String::new();
+        for _ in 0..50 {
+            contents = std::fs::read_to_string(&path).unwrap();
+            if contents.lines().count() == 1 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        let _ = std::fs::remove_file(&path);
+        let record: serde_json::Value = serde_json::from_str(contents.trim()).unwrap();
+        assert_eq!(record["identifier"], id.to_string());
+        assert_eq!(record["resource"], "echo");
+        assert_eq!(record["action"], "handle_message");
+        assert_eq!(record["policy"], r#"(= subject.role "admin")"#);
+        assert_eq!(record["attributes"]["subject.role"], r#""user""#);
+        assert_eq!(record["failing_predicate"], r#"(= subject.role "admin")"#);
+
+        // a denied request is answered with the reason of the denial
+        let request = Request::get("/echo").to_vec()?;
+        let message = RelayMessage::new(
+            "client".into(),
+            "echo".into(),
+            LocalMessage::new(
+                TransportMessage::v1(route!["echo"], route![context.address()], request.encode()?),
+                vec![],
+            ),
+        );
+        audit_log.reply_denial(
+            &message,
+            "the condition (= subject.role _) is not satisfied",
+        );
+        let response = context.receive::<Vec<u8>>().await?;
+        let mut decoder = Decoder::new(response.as_body());
+        let header: ResponseHeader = decoder.decode()?;
+        assert_eq!(header.status(), Some(Status::Forbidden));
+        let error: ockam_core::api::Error = decoder.decode()?;
+        assert_eq!(
+            error.message(),
+            Some("Forbidden: the condition (= subject.role _) is not satisfied")
+        );
+
+        // the other messages are not answered
+        assert!(forbidden_response(&b"portal payload".to_vec().encode()?, "denied").is_none());
+
+        context.stop().await
+    }
+}
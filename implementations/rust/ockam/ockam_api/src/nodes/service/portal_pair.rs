@@ -0,0 +1,558 @@
+//! Provisioning of a complete portal: an outlet on one node, a relay to that node,
+//! and an inlet on another node connected to the outlet through the relay
+
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::time::Duration;
+
+use miette::{miette, IntoDiagnostic};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use ockam::identity::Identifier;
+use ockam_core::api::{Reply, Request};
+use ockam_core::{async_trait, route, Address};
+use ockam_multiaddr::proto::{Project, Secure, Service};
+use ockam_multiaddr::{MultiAddr, Protocol};
+use ockam_node::Context;
+
+use crate::cli_state::random_name;
+use crate::nodes::models::portal::{CreateInlet, CreateOutlet, InletStatus, OutletStatus};
+use crate::nodes::models::relay::RelayInfo;
+use crate::nodes::service::relay::Relays;
+use crate::nodes::BackgroundNode;
+use crate::DefaultAddress;
+
+/// Parameters of a portal pair.
+///
+/// By default the relay is created in the default project and the outlet worker address
+/// is derived from the relay name
+#[derive(Clone, Debug)]
+pub struct CreatePortalPair {
+    to: SocketAddr,
+    from: SocketAddr,
+    relay_name: String,
+    relay_at: MultiAddr,
+    relay_authorized: Option<Identifier>,
+    outlet_address: Option<Address>,
+    outlet_identifier: Option<Identifier>,
+    alias: Option<String>,
+    wait_for_outlet: Duration,
+}
+
+impl CreatePortalPair {
+    /// Forward the connections accepted on `from` by the inlet node to `to`,
+    /// through a relay named `relay_name`
+    pub fn new(to: SocketAddr, from: SocketAddr, relay_name: impl Into<String>) -> Self {
+        Self {
+            to,
+            from,
+            relay_name: relay_name.into(),
+            relay_at: MultiAddr::from_str("/project/default").expect("valid address"),
+            relay_authorized: None,
+            outlet_address: None,
+            outlet_identifier: None,
+            alias: None,
+            wait_for_outlet: Duration::from_secs(5),
+        }
+    }
+
+    /// Create the relay at this address: a project or a node
+    pub fn with_relay_at(mut self, relay_at: MultiAddr) -> Self {
+        self.relay_at = relay_at;
+        self
+    }
+
+    /// Identity of the node hosting the relay, when it is not a project
+    pub fn with_relay_authorized(mut self, identifier: Identifier) -> Self {
+        self.relay_authorized = Some(identifier);
+        self
+    }
+
+    /// Address of the outlet worker
+    pub fn with_outlet_address(mut self, address: impl Into<Address>) -> Self {
+        self.outlet_address = Some(address.into());
+        self
+    }
+
+    /// Identity of the outlet node, authorized for the secure channel created by the inlet
+    pub fn with_outlet_identifier(mut self, identifier: Identifier) -> Self {
+        self.outlet_identifier = Some(identifier);
+        self
+    }
+
+    /// Alias given to both the outlet and the inlet
+    pub fn with_alias(mut self, alias: impl Into<String>) -> Self {
+        self.alias = Some(alias.into());
+        self
+    }
+
+    /// Maximum time given to the inlet to reach the outlet
+    pub fn with_wait_for_outlet(mut self, wait_for_outlet: Duration) -> Self {
+        self.wait_for_outlet = wait_for_outlet;
+        self
+    }
+
+    fn outlet_address(&self) -> Address {
+        self.outlet_address
+            .clone()
+            .unwrap_or_else(|| format!("outlet_{}", self.relay_name).into())
+    }
+
+    fn is_project_relay(&self) -> bool {
+        self.relay_at.starts_with(Project::CODE)
+    }
+}
+
+/// All the resources created for a portal pair
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PortalPair {
+    pub outlet_node: String,
+    pub inlet_node: String,
+    pub outlet: OutletStatus,
+    pub relay: RelayInfo,
+    pub inlet: InletStatus,
+    /// Address used by the inlet to reach the outlet
+    pub outlet_route: String,
+}
+
+/// Requests sent to the nodes of a portal pair
+#[async_trait]
+pub trait PortalPairNode: Relays + Send + Sync {
+    /// Name of the node receiving the requests
+    fn node_name(&self) -> String;
+
+    async fn create_outlet(
+        &self,
+        ctx: &Context,
+        create_outlet: CreateOutlet,
+    ) -> miette::Result<OutletStatus>;
+
+    async fn create_inlet(
+        &self,
+        ctx: &Context,
+        create_inlet: CreateInlet,
+    ) -> miette::Result<InletStatus>;
+
+    /// Return true if the node has an inlet with that alias
+    async fn has_inlet(&self, ctx: &Context, alias: &str) -> miette::Result<bool>;
+
+    async fn delete_outlet(&self, ctx: &Context, alias: &str) -> miette::Result<()>;
+
+    async fn delete_relay(&self, ctx: &Context, remote_address: &str) -> miette::Result<()>;
+
+    async fn delete_inlet(&self, ctx: &Context, alias: &str) -> miette::Result<()>;
+}
+
+#[async_trait]
+impl PortalPairNode for BackgroundNode {
+    fn node_name(&self) -> String {
+        BackgroundNode::node_name(self)
+    }
+
+    async fn create_outlet(
+        &self,
+        ctx: &Context,
+        create_outlet: CreateOutlet,
+    ) -> miette::Result<OutletStatus> {
+        self.ask(ctx, Request::post("/node/outlet").body(create_outlet))
+            .await
+    }
+
+    async fn create_inlet(
+        &self,
+        ctx: &Context,
+        create_inlet: CreateInlet,
+    ) -> miette::Result<InletStatus> {
+        self.ask(ctx, Request::post("/node/inlet").body(create_inlet))
+            .await
+    }
+
+    async fn has_inlet(&self, ctx: &Context, alias: &str) -> miette::Result<bool> {
+        let reply: Reply<InletStatus> = self
+            .ask_and_get_reply(ctx, Request::get(format!("/node/inlet/{alias}")))
+            .await?;
+        Ok(matches!(reply, Reply::Successful(_)))
+    }
+
+    async fn delete_outlet(&self, ctx: &Context, alias: &str) -> miette::Result<()> {
+        self.tell(ctx, Request::delete(format!("/node/outlet/{alias}")))
+            .await
+    }
+
+    async fn delete_relay(&self, ctx: &Context, remote_address: &str) -> miette::Result<()> {
+        self.tell(
+            ctx,
+            Request::delete(format!("/node/forwarder/{remote_address}")),
+        )
+        .await
+    }
+
+    async fn delete_inlet(&self, ctx: &Context, alias: &str) -> miette::Result<()> {
+        self.tell(ctx, Request::delete(format!("/node/inlet/{alias}")))
+            .await
+    }
+}
+
+/// Create an outlet on `outlet_node`, a relay to `outlet_node` and an inlet on `inlet_node`.
+///
+/// If one of the steps fails, the resources which were already created are deleted,
+/// including the inlet when its creation failed after the inlet was created, for example
+/// when the response timed out. Both clients must share the same transport, for example
+/// by cloning the first client and calling `set_node_name` on the copy
+pub async fn create_portal_pair(
+    ctx: &Context,
+    outlet_node: &impl PortalPairNode,
+    inlet_node: &impl PortalPairNode,
+    request: &CreatePortalPair,
+) -> miette::Result<PortalPair> {
+    if request.is_project_relay() && request.relay_authorized.is_some() {
+        return Err(miette!(
+            "An authorized identity can not be used with a project relay"
+        ));
+    }
+
+    // the inlet alias is known in advance so that the inlet can be deleted if its
+    // creation fails. An existing inlet must not be deleted by the rollback
+    let inlet_alias = match request.alias.clone() {
+        Some(alias) => {
+            if inlet_node.has_inlet(ctx, &alias).await? {
+                return Err(miette!(
+                    "An inlet with alias '{alias}' already exists on the node {}",
+                    inlet_node.node_name()
+                ));
+            }
+            alias
+        }
+        None => random_name(),
+    };
+
+    let create_outlet = CreateOutlet::new(
+        request.to,
+        request.outlet_address(),
+        request.alias.clone(),
+        true,
+    );
+    let outlet = outlet_node.create_outlet(ctx, create_outlet).await?;
+    debug!(alias = %outlet.alias, "portal pair: outlet created");
+
+    let relay_alias = if request.is_project_relay() {
+        request.relay_name.clone()
+    } else {
+        format!("forward_to_{}", request.relay_name)
+    };
+    let relay = match outlet_node
+        .create_relay(
+            ctx,
+            &request.relay_at,
+            Some(relay_alias),
+            request.relay_authorized.clone(),
+        )
+        .await
+    {
+        Ok(relay) => relay,
+        Err(err) => {
+            delete_outlet(ctx, outlet_node, &outlet).await;
+            return Err(err);
+        }
+    };
+    debug!(remote_address = %relay.remote_address(), "portal pair: relay created");
+
+    let inlet = async {
+        let outlet_route = outlet_route(
+            &request.relay_at,
+            relay.remote_address(),
+            &outlet.worker_addr,
+        )?;
+        let mut create_inlet = if request.is_project_relay() {
+            CreateInlet::via_project(
+                request.from.to_string(),
+                outlet_route.clone(),
+                route![],
+                route![],
+            )
+        } else {
+            CreateInlet::to_node(
+                request.from.to_string(),
+                outlet_route.clone(),
+                route![],
+                route![],
+                request.outlet_identifier.clone(),
+            )
+        };
+        create_inlet.set_alias(&inlet_alias);
+        create_inlet.set_wait_ms(request.wait_for_outlet.as_millis() as u64);
+        let inlet = inlet_node.create_inlet(ctx, create_inlet).await?;
+        Ok::<_, miette::Report>((inlet, outlet_route))
+    };
+
+    match inlet.await {
+        Ok((inlet, outlet_route)) => {
+            debug!(alias = %inlet.alias, "portal pair: inlet created");
+            Ok(PortalPair {
+                outlet_node: outlet_node.node_name(),
+                inlet_node: inlet_node.node_name(),
+                outlet,
+                relay,
+                inlet,
+                outlet_route: outlet_route.to_string(),
+            })
+        }
+        Err(err) => {
+            delete_inlet(ctx, inlet_node, &inlet_alias).await;
+            delete_relay(ctx, outlet_node, &relay).await;
+            delete_outlet(ctx, outlet_node, &outlet).await;
+            Err(err)
+        }
+    }
+}
+
+/// Delete all the resources of a portal pair.
+/// All the deletions are attempted, and the first error is returned
+pub async fn delete_portal_pair(
+    ctx: &Context,
+    outlet_node: &impl PortalPairNode,
+    inlet_node: &impl PortalPairNode,
+    pair: &PortalPair,
+) -> miette::Result<()> {
+    let inlet = inlet_node.delete_inlet(ctx, &pair.inlet.alias).await;
+    let relay = outlet_node
+        .delete_relay(ctx, pair.relay.remote_address())
+        .await;
+    let outlet = outlet_node.delete_outlet(ctx, &pair.outlet.alias).await;
+    inlet.and(relay).and(outlet)
+}
+
+/// The inlet is usually not created when its creation fails, so failing to delete it is expected
+async fn delete_inlet(ctx: &Context, node: &impl PortalPairNode, alias: &str) {
+    if let Err(err) = node.delete_inlet(ctx, alias).await {
+        debug!(%alias, %err, "no inlet to roll back");
+    }
+}
+
+async fn delete_relay(ctx: &Context, node: &impl PortalPairNode, relay: &RelayInfo) {
+    if let Err(err) = node.delete_relay(ctx, relay.remote_address()).await {
+        warn!(remote_address = %relay.remote_address(), %err, "cannot roll back the relay creation");
+    }
+}
+
+async fn delete_outlet(ctx: &Context, node: &impl PortalPairNode, outlet: &OutletStatus) {
+    if let Err(err) = node.delete_outlet(ctx, &outlet.alias).await {
+        warn!(alias = %outlet.alias, %err, "cannot roll back the outlet creation");
+    }
+}
+
+/// Return the address of an outlet reached with a secure channel through a relay
+fn outlet_route(
+    relay_at: &MultiAddr,
+    relay_address: &str,
+    outlet_address: &Address,
+) -> miette::Result<MultiAddr> {
+    let mut addr = relay_at.clone();
+    addr.push_back(Service::new(relay_address))
+        .into_diagnostic()?;
+    addr.push_back(Secure::new(DefaultAddress::SECURE_CHANNEL_LISTENER))
+        .into_diagnostic()?;
+    addr.push_back(Service::new(outlet_address.address()))
+        .into_diagnostic()?;
+    Ok(addr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam::remote::RemoteRelayInfo;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_outlet_route() -> miette::Result<()> {
+        let relay_at = MultiAddr::from_str("/project/default").into_diagnostic()?;
+        let route = outlet_route(&relay_at, "forward_to_db", &"outlet_db".into())?;
+        assert_eq!(
+            route.to_string(),
+            "/project/default/service/forward_to_db/secure/api/service/outlet_db"
+        );
+        Ok(())
+    }
+
+    #[ockam_macros::test]
+    async fn test_inlet_is_removed_when_it_cannot_reach_the_outlet(
+        ctx: &mut Context,
+    ) -> ockam_core::Result<()> {
+        let node = FakeNode::new(Some(InletFailure::NotCreated));
+        let request = CreatePortalPair::new(to(), from(), "db").with_alias("db");
+
+        assert!(create_portal_pair(ctx, &node, &node, &request)
+            .await
+            .is_err());
+        assert_eq!(node.created(), vec!["outlet db", "relay db"]);
+        assert!(node.resources().is_empty(), "{:?}", node.resources());
+
+        ctx.stop().await
+    }
+
+    #[ockam_macros::test]
+    async fn test_inlet_is_removed_when_its_creation_times_out(
+        ctx: &mut Context,
+    ) -> ockam_core::Result<()> {
+        let node = FakeNode::new(Some(InletFailure::TimedOut));
+        let request = CreatePortalPair::new(to(), from(), "db");
+
+        assert!(create_portal_pair(ctx, &node, &node, &request)
+            .await
+            .is_err());
+        assert_eq!(node.created().len(), 3);
+        assert!(node.resources().is_empty(), "{:?}", node.resources());
+
+        ctx.stop().await
+    }
+
+    #[ockam_macros::test]
+    async fn test_existing_inlet_is_not_removed(ctx: &mut Context) -> ockam_core::Result<()> {
+        let node = FakeNode::new(None);
+        node.resources.lock().unwrap().push("inlet db".to_string());
+        let request = CreatePortalPair::new(to(), from(), "db").with_alias("db");
+
+        assert!(create_portal_pair(ctx, &node, &node, &request)
+            .await
+            .is_err());
+        assert!(node.created().is_empty());
+        assert_eq!(node.resources(), vec!["inlet db"]);
+
+        ctx.stop().await
+    }
+
+    fn to() -> SocketAddr {
+        "127.0.0.1:5000".parse().unwrap()
+    }
+
+    fn from() -> SocketAddr {
+        "127.0.0.1:6000".parse().unwrap()
+    }
+
+    #[derive(Clone, Copy)]
+    enum InletFailure {
+        /// The inlet is not created
+        NotCreated,
+        /// The inlet is created but the response is not received in time
+        TimedOut,
+    }
+
+    /// Node keeping track of the resources created and deleted by the portal pair requests
+    struct FakeNode {
+        inlet_failure: Option<InletFailure>,
+        created: Mutex<Vec<String>>,
+        resources: Mutex<Vec<String>>,
+    }
+
+    impl FakeNode {
+        fn new(inlet_failure: Option<InletFailure>) -> Self {
+            Self {
+                inlet_failure,
+                created: Mutex::new(vec![]),
+                resources: Mutex::new(vec![]),
+            }
+        }
+
+        fn created(&self) -> Vec<String> {
+            self.created.lock().unwrap().clone()
+        }
+
+        fn resources(&self) -> Vec<String> {
+            self.resources.lock().unwrap().clone()
+        }
+
+        fn create(&self, resource: String) {
+            self.created.lock().unwrap().push(resource.clone());
+            self.resources.lock().unwrap().push(resource);
+        }
+
+        fn delete(&self, resource: String) -> miette::Result<()> {
+            let mut resources = self.resources.lock().unwrap();
+            let index = resources
+                .iter()
+                .position(|r| r == &resource)
+                .ok_or_else(|| miette!("{resource} not found"))?;
+            resources.remove(index);
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl Relays for FakeNode {
+        async fn create_relay(
+            &self,
+            _ctx: &Context,
+            _address: &MultiAddr,
+            alias: Option<String>,
+            _authorized: Option<Identifier>,
+        ) -> miette::Result<RelayInfo> {
+            let alias = alias.unwrap();
+            self.create(format!("relay {alias}"));
+            Ok(RemoteRelayInfo::new(route![], alias, "relay".into(), None).into())
+        }
+    }
+
+    #[async_trait]
+    impl PortalPairNode for FakeNode {
+        fn node_name(&self) -> String {
+            "fake".to_string()
+        }
+
+        async fn create_outlet(
+            &self,
+            _ctx: &Context,
+            create_outlet: CreateOutlet,
+        ) -> miette::Result<OutletStatus> {
+            let alias = create_outlet.alias.unwrap_or_else(random_name);
+            self.create(format!("outlet {alias}"));
+            Ok(OutletStatus::new(
+                create_outlet.socket_addr,
+                create_outlet.worker_addr,
+                alias,
+                None,
+            ))
+        }
+
+        async fn create_inlet(
+            &self,
+            _ctx: &Context,
+            create_inlet: CreateInlet,
+        ) -> miette::Result<InletStatus> {
+            let alias = create_inlet.alias.unwrap();
+            match self.inlet_failure {
+                Some(InletFailure::NotCreated) => Err(miette!("cannot reach the outlet")),
+                Some(InletFailure::TimedOut) => {
+                    self.create(format!("inlet {alias}"));
+                    Err(miette!("timeout"))
+                }
+                None => {
+                    self.create(format!("inlet {alias}"));
+                    Ok(InletStatus::new(
+                        create_inlet.listen_addr,
+                        "inlet",
+                        alias,
+                        None,
+                        create_inlet.outlet_addr.to_string(),
+                    ))
+                }
+            }
+        }
+
+        async fn has_inlet(&self, _ctx: &Context, alias: &str) -> miette::Result<bool> {
+            Ok(self.resources().contains(&format!("inlet {alias}")))
+        }
+
+        async fn delete_outlet(&self, _ctx: &Context, alias: &str) -> miette::Result<()> {
+            self.delete(format!("outlet {alias}"))
+        }
+
+        async fn delete_relay(&self, _ctx: &Context, remote_address: &str) -> miette::Result<()> {
+            self.delete(format!("relay {remote_address}"))
+        }
+
+        async fn delete_inlet(&self, _ctx: &Context, alias: &str) -> miette::Result<()> {
+            self.delete(format!("inlet {alias}"))
+        }
+    }
+}
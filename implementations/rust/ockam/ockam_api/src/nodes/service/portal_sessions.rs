@@ -0,0 +1,89 @@
+use ockam::identity::IdentitySecureChannelLocalInfo;
+use ockam::{Address, Result};
+use ockam_core::api::{Error, RequestHeader, Response};
+use ockam_core::errcode::Kind;
+use ockam_transport_tcp::{TcpPortalSessionInfo, TcpPortalType};
+
+use crate::nodes::models::portal::{PortalSession, PortalSessionList};
+
+use super::{NodeManager, NodeManagerWorker};
+
+impl NodeManagerWorker {
+    pub(super) async fn get_portal_sessions(
+        &self,
+        req: &RequestHeader,
+    ) -> Response<PortalSessionList> {
+        Response::ok(req).body(self.node_manager.list_portal_sessions().await)
+    }
+
+    pub(super) async fn delete_portal_session(
+        &self,
+        req: &RequestHeader,
+        address: &str,
+    ) -> Result<Response, Response<Error>> {
+        match self.node_manager.disconnect_portal_session(address).await {
+            Ok(()) => Ok(Response::ok(req)),
+            Err(err) if err.code().kind == Kind::NotFound => {
+                Err(Response::not_found(req, &err.to_string()))
+            }
+            Err(err) => Err(Response::internal_error(req, &err.to_string())),
+        }
+    }
+}
+
+impl NodeManager {
+    /// List the sessions of the inlets and outlets of the node
+    pub async fn list_portal_sessions(&self) -> PortalSessionList {
+        let mut sessions = vec![];
+        for session in self.tcp_transport.registry().get_all_portal_sessions() {
+            let alias = self.portal_alias(&session).await;
+            let peer_identity =
+                IdentitySecureChannelLocalInfo::find_info_from_list(&session.remote_local_info())
+                    .ok()
+                    .map(|info| info.their_identity_id());
+            sessions.push(PortalSession {
+                address: session.address().to_string(),
+                portal_type: session.portal_type().to_string(),
+                alias,
+                peer: session.peer().map(|peer| peer.to_string()),
+                peer_identity,
+                bytes_received: session.bytes_received(),
+                bytes_sent: session.bytes_sent(),
+                started_at: session.started_at(),
+            });
+        }
+        PortalSessionList::new(sessions)
+    }
+
+    /// Forcibly terminate a portal session, without deleting the inlet or outlet
+    /// which created it
+    pub async fn disconnect_portal_session(&self, address: &str) -> Result<()> {
+        info!(%address, "Handling request to disconnect a portal session");
+        self.tcp_transport
+            .disconnect_portal_session(&Address::from_string(address))
+            .await
+    }
+
+    /// Return the alias of the inlet or outlet which created a session
+    async fn portal_alias(&self, session: &TcpPortalSessionInfo) -> Option<String> {
+        let listener_address = session.listener_address()?;
+        match session.portal_type() {
+            TcpPortalType::Inlet => self
+                .registry
+                .inlets
+                .entries()
+                .await
+                .into_iter()
+                .find(|(_, info)| &info.worker_addr == listener_address)
+                .map(|(alias, _)| alias),
+            TcpPortalType::Outlet => self
+                .registry
+                .outlets
+                .entries()
+                .await
+                .into_iter()
+                .find(|(_, info)| &info.worker_addr == listener_address)
+                .map(|(alias, _)| alias),
+        }
+    }
+}
@@ -1,34 +1,58 @@
 use minicbor::Decoder;
 use std::net::SocketAddr;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, RwLock};
 use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::time::timeout;
 
-use ockam::identity::Identifier;
+use ockam::identity::storage::Storage;
+use ockam::identity::utils::now;
+use ockam::identity::{Identifier, IdentityAttributesReader, IdentitySecureChannelLocalInfo};
 use ockam::{Address, Result};
 use ockam_abac::Resource;
 use ockam_core::api::{Error, RequestHeader, Response};
 use ockam_core::errcode::{Kind, Origin};
-use ockam_core::{route, AsyncTryClone, IncomingAccessControl, Route};
+use ockam_core::{async_trait, route, AsyncTryClone, IncomingAccessControl, LocalMessage, Route};
 use ockam_multiaddr::proto::Project;
 use ockam_multiaddr::MultiAddr;
-use ockam_node::Context;
-use ockam_transport_tcp::{TcpInletOptions, TcpOutletOptions};
+use ockam_node::{Context, MailboxOptions};
+use ockam_transport_tcp::{
+    AllowedTarget, OutletProtocol, OutletTargetAccessControl, OutletTargetRouter, StreamInlet,
+    TcpInletOptions, TcpOutletOptions,
+};
+use ockam_transport_udp::DEFAULT_HOLE_OPEN_TIMEOUT;
 
+use crate::address::is_valid_target;
 use crate::cli_state::StateDirTrait;
 use crate::config::lookup::ProjectLookup;
 use crate::error::ApiError;
 use crate::nodes::connection::Connection;
 use crate::nodes::models::portal::{
-    CreateInlet, CreateOutlet, InletList, InletStatus, OutletList, OutletStatus,
+    CreateInlet, CreateOutlet, InletList, InletStatus, OutletList, OutletRoute, OutletRoutes,
+    OutletStatus, OutletTargets, RebindInlet,
 };
 use crate::nodes::registry::{InletInfo, OutletInfo};
 use crate::nodes::service::random_alias;
 use crate::nodes::InMemoryNode;
 use crate::session::sessions::{Replacer, Session, MAX_CONNECT_TIME, MAX_RECOVERY_TIME};
+use crate::util::mailbox_options_from_env;
 use crate::{actions, resources, DefaultAddress};
 
-use super::{NodeManager, NodeManagerWorker};
+use super::events::INLET_EVENTS_ADDRESS;
+use super::{NodeManager, NodeManagerWorker, NodeService};
+
+/// Environment variable setting the capacity of the mailboxes of the portal workers
+const OCKAM_PORTAL_MAILBOX_CAPACITY: &str = "OCKAM_PORTAL_MAILBOX_CAPACITY";
+
+/// Environment variable setting the policy applied when the mailbox of a portal worker is full
+const OCKAM_PORTAL_MAILBOX_OVERFLOW: &str = "OCKAM_PORTAL_MAILBOX_OVERFLOW";
+
+const OUTLET_ROUTES_ID: &str = "outlet_routes";
+
+/// Return the options of the mailboxes of the portal workers
+fn portal_mailbox_options() -> MailboxOptions {
+    mailbox_options_from_env(OCKAM_PORTAL_MAILBOX_CAPACITY, OCKAM_PORTAL_MAILBOX_OVERFLOW)
+}
 
 /// INLETS
 impl NodeManagerWorker {
@@ -52,7 +76,15 @@ impl NodeManagerWorker {
             prefix_route,
             suffix_route,
             wait_for_outlet_duration,
+            outlet_target,
+            probe,
+            udp_rendezvous,
         } = create_inlet_req;
+        // stop waiting for the outlet once the client is not waiting for the response
+        let wait_for_outlet_duration = match (wait_for_outlet_duration, req.deadline()) {
+            (Some(wait), Some(deadline)) => Some(wait.min(deadline)),
+            (wait, deadline) => wait.or(deadline),
+        };
         match self
             .node_manager
             .create_inlet(
@@ -64,25 +96,59 @@ impl NodeManagerWorker {
                 outlet_addr,
                 wait_for_outlet_duration,
                 authorized,
+                outlet_target,
+                udp_rendezvous,
             )
             .await
         {
-            Ok(status) => Ok(Response::ok(req).body(status)),
+            Ok(status) => {
+                if let Some(probe) = probe {
+                    self.node_manager
+                        .start_inlet_probe(ctx, &status.alias, probe)
+                        .await?;
+                }
+                Ok(Response::ok(req).body(status))
+            }
             Err(e) => Err(Response::bad_request(req, &format!("{e:?}"))),
         }
     }
 
     pub(super) async fn delete_inlet(
         &self,
+        ctx: &Context,
         req: &RequestHeader,
         alias: &str,
     ) -> Result<Response<InletStatus>, Response<Error>> {
+        if let Err(err) = self.node_manager.stop_inlet_probe(ctx, alias).await {
+            warn!(%alias, %err, "Failed to stop the inlet probe");
+        }
+        self.node_manager.stop_inlet_hole_punch(ctx, alias).await;
         match self.node_manager.delete_inlet(alias).await {
             Ok(status) => Ok(Response::ok(req).body(status)),
             Err(e) => Err(Response::bad_request(req, &format!("{e:?}"))),
         }
     }
 
+    pub(super) async fn rebind_inlet(
+        &self,
+        ctx: &Context,
+        req: &RequestHeader,
+        alias: &str,
+        rebind_inlet: RebindInlet,
+    ) -> Result<Response<InletStatus>, Response<Error>> {
+        match self
+            .node_manager
+            .rebind_inlet(ctx, alias, rebind_inlet.listen_addr)
+            .await
+        {
+            Ok(status) => Ok(Response::ok(req).body(status)),
+            Err(e) if e.code().kind == Kind::NotFound => {
+                Err(Response::not_found(req, &e.to_string()))
+            }
+            Err(e) => Err(Response::bad_request(req, &format!("{e:?}"))),
+        }
+    }
+
     pub(super) async fn show_inlet(
         &self,
         req: &RequestHeader,
@@ -106,14 +172,28 @@ impl NodeManagerWorker {
         req: &RequestHeader,
         create_outlet: CreateOutlet,
     ) -> Result<Response<OutletStatus>, Response<Error>> {
+        self.check_readiness(req, NodeService::Outlets)?;
         let CreateOutlet {
             socket_addr,
             worker_addr,
             alias,
             reachable_from_default_secure_channel,
-            ..
+            targets,
+            protocol,
+            accept_hole_punching,
         } = create_outlet;
 
+        let protocol = match protocol.map(|p| p.parse::<OutletProtocol>()).transpose() {
+            Ok(protocol) => protocol,
+            Err(e) => return Err(Response::bad_request(req, &e.to_string())),
+        };
+
+        if accept_hole_punching == Some(true) {
+            if let Err(e) = self.node_manager.start_hole_punch_responder(ctx).await {
+                return Err(Response::bad_request(req, &format!("{e:?}")));
+            }
+        }
+
         match self
             .node_manager
             .create_outlet(
@@ -122,6 +202,8 @@ impl NodeManagerWorker {
                 worker_addr,
                 alias,
                 reachable_from_default_secure_channel,
+                targets,
+                protocol,
             )
             .await
         {
@@ -169,6 +251,36 @@ impl NodeManagerWorker {
     pub(super) async fn get_outlets(&self, req: &RequestHeader) -> Response<OutletList> {
         Response::ok(req).body(self.node_manager.list_outlets().await)
     }
+
+    pub(super) async fn show_outlet_routes(
+        &self,
+        req: &RequestHeader,
+        alias: &str,
+    ) -> Result<Response<OutletRoutes>, Response<Error>> {
+        match self.node_manager.registry.outlets.get(alias).await {
+            Some(outlet) => Ok(Response::ok(req).body(outlet.router.routes())),
+            None => Err(Response::not_found(
+                req,
+                &format!("Outlet with alias {alias} not found"),
+            )),
+        }
+    }
+
+    pub(super) async fn set_outlet_routes(
+        &self,
+        req: &RequestHeader,
+        alias: &str,
+        routes: OutletRoutes,
+    ) -> Result<Response<OutletRoutes>, Response<Error>> {
+        match self.node_manager.set_outlet_routes(alias, routes).await {
+            Ok(Some(routes)) => Ok(Response::ok(req).body(routes)),
+            Ok(None) => Err(Response::not_found(
+                req,
+                &format!("Outlet with alias {alias} not found"),
+            )),
+            Err(e) => Err(Response::bad_request(req, &e.to_string())),
+        }
+    }
 }
 
 /// OUTLETS
@@ -180,6 +292,8 @@ impl NodeManager {
         worker_addr: Address,
         alias: Option<String>,
         reachable_from_default_secure_channel: bool,
+        targets: Option<OutletTargets>,
+        protocol: Option<OutletProtocol>,
     ) -> Result<OutletStatus> {
         info!(
             "Handling request to create outlet portal at {:?}",
@@ -213,7 +327,9 @@ impl NodeManager {
             .access_control(&resource, &actions::HANDLE_MESSAGE, trust_context_id, None)
             .await?;
 
-        let options = TcpOutletOptions::new().with_incoming_access_control(access_control);
+        let options = TcpOutletOptions::new()
+            .with_incoming_access_control(access_control)
+            .with_mailbox_options(portal_mailbox_options());
         let options = if !check_credential {
             options.as_consumer(&self.api_transport_flow_control_id)
         } else {
@@ -234,6 +350,37 @@ impl NodeManager {
             options
         };
 
+        // Let the inlets request other targets than `socket_addr`
+        let options = match targets {
+            Some(targets) if !targets.is_empty() => {
+                let access_control = IdentityOutletTargetAccessControl::new(&targets)?;
+                access_control
+                    .all_targets()
+                    .into_iter()
+                    .fold(options, |options, target| {
+                        options.with_allowed_target(target)
+                    })
+                    .with_target_access_control(Arc::new(access_control))
+            }
+            _ => options,
+        };
+
+        // Let the routes configured later, or saved for an outlet with the same alias,
+        // select the target of each connection
+        let router = AttributeOutletTargetRouter::new(self.attributes_reader());
+        match self.outlet_routes.get(&alias).await {
+            Ok(Some(routes)) => router.set_routes(routes),
+            Ok(None) => {}
+            Err(e) => warn!(%alias, %e, "Failed to read the saved routes of the outlet"),
+        }
+        let options = options.with_target_router(Arc::new(router.clone()));
+
+        // Reject the connections which don't use the expected protocol
+        let options = match protocol {
+            Some(protocol) => options.with_protocol_validator(protocol.validator()),
+            None => options,
+        };
+
         let res = self
             .tcp_transport
             .create_tcp_outlet(worker_addr.clone(), socket_addr, options)
@@ -246,9 +393,11 @@ impl NodeManager {
                     .outlets
                     .insert(
                         alias.clone(),
-                        OutletInfo::new(&socket_addr, Some(&worker_addr)),
+                        OutletInfo::new(&socket_addr, Some(&worker_addr), router),
                     )
                     .await;
+                self.stop_worker_on_shutdown(format!("outlet {alias}"), worker_addr.clone())
+                    .await;
 
                 OutletStatus::new(socket_addr, worker_addr, alias, None)
             }
@@ -268,6 +417,7 @@ impl NodeManager {
         info!(%alias, "Handling request to delete outlet portal");
         if let Some(deleted_outlet) = self.registry.outlets.remove(alias).await {
             debug!(%alias, "Successfully removed outlet from node registry");
+            self.remove_shutdown_hook(&format!("outlet {alias}")).await;
             if let Err(e) = self
                 .tcp_transport
                 .stop_outlet(deleted_outlet.worker_addr.clone())
@@ -276,6 +426,9 @@ impl NodeManager {
                 warn!(%alias, %e, "Failed to stop outlet worker");
             }
             trace!(%alias, "Successfully stopped outlet");
+            if let Err(e) = self.outlet_routes.delete(alias).await {
+                warn!(%alias, %e, "Failed to delete the saved routes of the outlet");
+            }
             Ok(Some(deleted_outlet))
         } else {
             warn!(%alias, "Outlet not found in the node registry");
@@ -283,6 +436,33 @@ impl NodeManager {
         }
     }
 
+    /// Replace the routes selecting the target of the connections of an outlet
+    /// depending on the attributes of their identity.
+    /// Return `None` if there is no outlet with this alias
+    pub async fn set_outlet_routes(
+        &self,
+        alias: &str,
+        routes: OutletRoutes,
+    ) -> Result<Option<OutletRoutes>> {
+        for route in &routes.routes {
+            if !is_valid_target(&route.target) {
+                return Err(ApiError::core(format!(
+                    "the target of a route must be written host:port or [ipv6]:port, got '{}'",
+                    route.target
+                )));
+            }
+        }
+        match self.registry.outlets.get(alias).await {
+            Some(outlet) => {
+                info!(%alias, routes = routes.routes.len(), "Setting the routes of an outlet");
+                self.outlet_routes.set(alias, &routes).await?;
+                outlet.router.set_routes(routes.clone());
+                Ok(Some(routes))
+            }
+            None => Ok(None),
+        }
+    }
+
     pub(super) async fn show_outlet(&self, alias: &str) -> Option<OutletStatus> {
         info!(%alias, "Handling request to show outlet portal");
         if let Some(outlet_to_show) = self.registry.outlets.get(alias).await {
@@ -302,14 +482,18 @@ impl NodeManager {
 
 /// INLETS
 impl NodeManager {
+    #[allow(clippy::too_many_arguments)]
     pub async fn create_inlet(
         &self,
+        ctx: &Context,
         connection: Connection,
         listen_addr: String,
         requested_alias: Option<String>,
         prefix_route: Route,
         suffix_route: Route,
         outlet_addr: MultiAddr,
+        outlet_target: Option<String>,
+        udp_rendezvous: Option<String>,
     ) -> Result<(InletStatus, Arc<dyn IncomingAccessControl>)> {
         info!("Handling request to create inlet portal");
 
@@ -354,41 +538,34 @@ impl NodeManager {
         }
 
         let outlet_route = connection.route(self.tcp_transport()).await?;
-        let outlet_route = route![prefix_route.clone(), outlet_route, suffix_route.clone()];
-
-        let projects = self.cli_state.projects.list()?;
-        let projects = ProjectLookup::from_state(projects)
-            .await
-            .map_err(|e| ockam_core::Error::new(Origin::Node, Kind::NotFound, e))?;
-        let check_credential = self.enable_credential_checks;
-        let project_id = if check_credential {
-            let pid = outlet_addr
-                .first()
-                .and_then(|p| {
-                    if let Some(p) = p.cast::<Project>() {
-                        projects.get(&*p).map(|info| &*info.id)
-                    } else {
-                        None
-                    }
-                })
-                .or_else(|| Some(self.trust_context().ok()?.id()));
-            if pid.is_none() {
-                let message = "Credential check requires a project or trust context";
-                return Err(ockam_core::Error::new(Origin::Node, Kind::Invalid, message));
+        let outlet_route = match udp_rendezvous {
+            Some(rendezvous) => {
+                self.punch_hole_to_outlet(
+                    ctx,
+                    &alias,
+                    outlet_route,
+                    &rendezvous,
+                    DEFAULT_HOLE_OPEN_TIMEOUT,
+                )
+                .await
             }
-            pid
-        } else {
-            None
+            None => outlet_route,
         };
+        let outlet_route = route![prefix_route.clone(), outlet_route, suffix_route.clone()];
 
         let resource = requested_alias
             .map(|a| Resource::new(a.as_str()))
             .unwrap_or(resources::INLET);
-        let access_control = self
-            .access_control(&resource, &actions::HANDLE_MESSAGE, project_id, None)
-            .await?;
+        let access_control = self.inlet_access_control(&outlet_addr, &resource).await?;
 
-        let options = TcpInletOptions::new().with_incoming_access_control(access_control.clone());
+        let options = TcpInletOptions::new()
+            .with_incoming_access_control(access_control.clone())
+            .with_connection_notifications(INLET_EVENTS_ADDRESS)
+            .with_mailbox_options(portal_mailbox_options());
+        let options = match outlet_target.clone() {
+            Some(target) => options.with_outlet_target(target),
+            None => options,
+        };
         let res = self
             .tcp_transport
             .create_inlet(listen_addr.clone(), outlet_route.clone(), options)
@@ -405,9 +582,17 @@ impl NodeManager {
                     .inlets
                     .insert(
                         alias.clone(),
-                        InletInfo::new(&listen_addr, Some(&worker_addr), &outlet_route),
+                        InletInfo::new(
+                            &listen_addr,
+                            Some(&worker_addr),
+                            &outlet_route,
+                            access_control.clone(),
+                            outlet_target,
+                        ),
                     )
                     .await;
+                self.stop_processor_on_shutdown(format!("inlet {alias}"), worker_addr.clone())
+                    .await;
                 (
                     InletStatus::new(
                         listen_addr,
@@ -421,6 +606,7 @@ impl NodeManager {
             }
             Err(e) => {
                 warn!(to = %outlet_addr, err = %e, "Failed to create TCP inlet");
+                self.stop_inlet_hole_punch(ctx, &alias).await;
                 let message = format!("Failed to create TCP inlet: {}", e);
                 return Err(ockam_core::Error::new(
                     Origin::Node,
@@ -431,10 +617,153 @@ impl NodeManager {
         })
     }
 
+    /// Return the access control of the messages sent by an outlet to an inlet.
+    /// When credentials are checked, the outlet must be a member of the project
+    /// of the outlet address, or of the trust context of the node
+    async fn inlet_access_control(
+        &self,
+        outlet_addr: &MultiAddr,
+        resource: &Resource,
+    ) -> Result<Arc<dyn IncomingAccessControl>> {
+        // the project of the outlet can be given by a static route
+        let outlet_addr = self.expand_static_routes(outlet_addr).await?;
+        let projects = self.cli_state.projects.list()?;
+        let projects = ProjectLookup::from_state(projects)
+            .await
+            .map_err(|e| ockam_core::Error::new(Origin::Node, Kind::NotFound, e))?;
+        let check_credential = self.enable_credential_checks;
+        let project_id = if check_credential {
+            let pid = outlet_addr
+                .first()
+                .and_then(|p| {
+                    if let Some(p) = p.cast::<Project>() {
+                        projects.get(&*p).map(|info| &*info.id)
+                    } else {
+                        None
+                    }
+                })
+                .or_else(|| Some(self.trust_context().ok()?.id()));
+            if pid.is_none() {
+                let message = "Credential check requires a project or trust context";
+                return Err(ockam_core::Error::new(Origin::Node, Kind::Invalid, message));
+            }
+            pid
+        } else {
+            None
+        };
+
+        self.access_control(resource, &actions::HANDLE_MESSAGE, project_id, None)
+            .await
+    }
+
+    /// Move an inlet to a new bind address.
+    ///
+    /// A new listener is started on `listen_addr` before the previous one is stopped.
+    /// The connections accepted by the previous listener are not interrupted and
+    /// are closed by their clients or by the outlet
+    pub async fn rebind_inlet(
+        &self,
+        ctx: &Context,
+        alias: &str,
+        listen_addr: String,
+    ) -> Result<InletStatus> {
+        info!(%alias, %listen_addr, "Handling request to rebind inlet portal");
+        let mut info = match self.registry.inlets.get(alias).await {
+            Some(info) => info,
+            None => {
+                let message = format!("Inlet with alias {alias} not found");
+                return Err(ockam_core::Error::new(
+                    Origin::Node,
+                    Kind::NotFound,
+                    message,
+                ));
+            }
+        };
+
+        // Check that no other inlet is bound to the new TCP bind address
+        if self
+            .registry
+            .inlets
+            .entries()
+            .await
+            .iter()
+            .any(|(a, inlet)| a != alias && inlet.bind_addr == listen_addr)
+        {
+            let message =
+                format!("A TCP inlet with bind tcp address '{listen_addr}' already exists");
+            return Err(ockam_core::Error::new(
+                Origin::Node,
+                Kind::AlreadyExists,
+                message,
+            ));
+        }
+
+        let options = TcpInletOptions::new()
+            .with_incoming_access_control(info.access_control.clone())
+            .with_connection_notifications(INLET_EVENTS_ADDRESS)
+            .with_mailbox_options(portal_mailbox_options());
+        let options = match info.outlet_target.clone() {
+            Some(target) => options.with_outlet_target(target),
+            None => options,
+        };
+        let (socket_address, worker_addr) = match self
+            .tcp_transport
+            .create_inlet(listen_addr.clone(), info.outlet_route.clone(), options)
+            .await
+        {
+            Ok(res) => res,
+            Err(e) => {
+                warn!(%alias, at = %listen_addr, err = %e, "Failed to rebind TCP inlet");
+                let message = format!("Failed to rebind TCP inlet: {}", e);
+                return Err(ockam_core::Error::new(
+                    Origin::Node,
+                    Kind::Internal,
+                    message,
+                ));
+            }
+        };
+
+        // Only the previous listener is stopped, its portal workers keep running
+        if let Err(e) = self
+            .tcp_transport
+            .stop_inlet(info.worker_addr.clone())
+            .await
+        {
+            debug!(%alias, %e, "Failed to stop the previous inlet listener");
+        }
+
+        info.bind_addr = socket_address.to_string();
+        info.worker_addr = worker_addr.clone();
+        self.registry
+            .inlets
+            .insert(alias.to_string(), info.clone())
+            .await;
+        self.remove_shutdown_hook(&format!("inlet {alias}")).await;
+        self.stop_processor_on_shutdown(format!("inlet {alias}"), worker_addr.clone())
+            .await;
+
+        if let Some(probe) = info.probe {
+            if let Err(err) = self.restart_inlet_probe(ctx, alias, probe).await {
+                warn!(%alias, %err, "Failed to restart the inlet probe");
+            }
+        }
+
+        let mut status = InletStatus::new(
+            info.bind_addr,
+            worker_addr.to_string(),
+            alias,
+            None,
+            info.outlet_route.to_string(),
+        );
+        status.last_probe = self.last_inlet_probe_result(alias).await;
+        Ok(status)
+    }
+
     pub async fn delete_inlet(&self, alias: &str) -> Result<InletStatus> {
         info!(%alias, "Handling request to delete inlet portal");
         if let Some(inlet_to_delete) = self.registry.inlets.remove(alias).await {
             debug!(%alias, "Successfully removed inlet from node registry");
+            self.remove_shutdown_hook(&format!("inlet {alias}")).await;
             match self
                 .tcp_transport
                 .stop_inlet(inlet_to_delete.worker_addr.clone())
@@ -475,13 +804,15 @@ impl NodeManager {
         info!(%alias, "Handling request to show inlet portal");
         if let Some(inlet_to_show) = self.registry.inlets.get(alias).await {
             debug!(%alias, "Inlet not found in node registry");
-            Some(InletStatus::new(
+            let mut status = InletStatus::new(
                 inlet_to_show.bind_addr.to_string(),
                 inlet_to_show.worker_addr.to_string(),
                 alias,
                 None,
                 inlet_to_show.outlet_route.to_string(),
-            ))
+            );
+            status.last_probe = self.last_inlet_probe_result(alias).await;
+            Some(status)
         } else {
             error!(%alias, "Inlet not found in the node registry");
             None
@@ -489,23 +820,19 @@ impl NodeManager {
     }
 
     pub async fn list_inlets(&self) -> InletList {
-        InletList::new(
-            self.registry
-                .inlets
-                .entries()
-                .await
-                .iter()
-                .map(|(alias, info)| {
-                    InletStatus::new(
-                        &info.bind_addr,
-                        info.worker_addr.to_string(),
-                        alias,
-                        None,
-                        info.outlet_route.to_string(),
-                    )
-                })
-                .collect(),
-        )
+        let mut inlets = vec![];
+        for (alias, info) in self.registry.inlets.entries().await {
+            let mut status = InletStatus::new(
+                &info.bind_addr,
+                info.worker_addr.to_string(),
+                &alias,
+                None,
+                info.outlet_route.to_string(),
+            );
+            status.last_probe = self.last_inlet_probe_result(&alias).await;
+            inlets.push(status);
+        }
+        InletList::new(inlets)
     }
 }
 
@@ -521,6 +848,8 @@ impl InMemoryNode {
         outlet_addr: MultiAddr,
         wait_for_outlet_duration: Option<Duration>,
         authorized: Option<Identifier>,
+        outlet_target: Option<String>,
+        udp_rendezvous: Option<String>,
     ) -> Result<InletStatus> {
         // The addressing scheme is very flexible. Typically the node connects to
         // the cloud via secure channel and the with another secure channel via
@@ -543,12 +872,15 @@ impl InMemoryNode {
         let (inlet, access_control) = self
             .node_manager
             .create_inlet(
+                ctx,
                 connection.clone(),
                 listen_addr.clone(),
                 requested_alias,
                 prefix_route.clone(),
                 suffix_route.clone(),
                 outlet_addr.clone(),
+                outlet_target.clone(),
+                udp_rendezvous.clone(),
             )
             .await?;
         if !connection.route(self.tcp_transport()).await?.is_empty() {
@@ -565,6 +897,7 @@ impl InMemoryNode {
                 self.node_manager.clone(),
                 connection_ctx,
                 connection,
+                inlet.alias.clone(),
                 Address::from_string(inlet.worker_addr.clone()),
                 listen_addr,
                 outlet_addr,
@@ -572,6 +905,8 @@ impl InMemoryNode {
                 suffix_route,
                 authorized,
                 access_control,
+                outlet_target,
+                udp_rendezvous,
             );
             session.set_replacer(repl);
             self.add_session(session);
@@ -579,6 +914,50 @@ impl InMemoryNode {
         Ok(inlet)
     }
 
+    /// Create an inlet for a single connection, streaming `reader` and `writer` to the
+    /// outlet, for example the standard input and output of the process.
+    ///
+    /// Unlike [`InMemoryNode::create_inlet`], no port is bound and the connection to the outlet
+    /// is not recreated if it fails. The inlet is not registered by the node
+    pub async fn create_stream_inlet<R, W>(
+        &self,
+        ctx: &Context,
+        outlet_addr: &MultiAddr,
+        wait_for_outlet_duration: Option<Duration>,
+        authorized: Option<Identifier>,
+        reader: R,
+        writer: W,
+    ) -> Result<StreamInlet>
+    where
+        R: AsyncRead + Send + Unpin + 'static,
+        W: AsyncWrite + Send + Unpin + 'static,
+    {
+        let duration = wait_for_outlet_duration.unwrap_or(Duration::from_secs(5));
+        let connection = self
+            .make_connection(
+                Arc::new(ctx.async_try_clone().await?),
+                outlet_addr,
+                None,
+                authorized,
+                None,
+                Some(duration),
+            )
+            .await?;
+        let outlet_route = connection.route(self.tcp_transport()).await?;
+        debug!(%outlet_addr, %outlet_route, "Creating stream inlet");
+
+        let access_control = self
+            .node_manager
+            .inlet_access_control(outlet_addr, &resources::INLET)
+            .await?;
+        let options = TcpInletOptions::new()
+            .with_incoming_access_control(access_control)
+            .with_mailbox_options(portal_mailbox_options());
+        self.tcp_transport()
+            .create_stream_inlet(outlet_route, options, reader, writer)
+            .await
+    }
+
     /// Create a session replacer.
     ///
     /// This returns a function that accepts the previous ping address (e.g.
@@ -589,6 +968,7 @@ impl InMemoryNode {
         node_manager: Arc<NodeManager>,
         ctx: Arc<Context>,
         connection: Connection,
+        alias: String,
         inlet_address: Address,
         bind: String,
         addr: MultiAddr,
@@ -596,6 +976,8 @@ impl InMemoryNode {
         suffix_route: Route,
         authorized: Option<Identifier>,
         access: Arc<dyn IncomingAccessControl>,
+        outlet_target: Option<String>,
+        udp_rendezvous: Option<String>,
     ) -> Replacer {
         let connection_arc = Arc::new(Mutex::new(connection.clone()));
         let inlet_address_arc = Arc::new(Mutex::new(inlet_address));
@@ -604,8 +986,11 @@ impl InMemoryNode {
         Box::new(move |previous_addr| {
             let addr = addr.clone();
             let authorized = authorized.clone();
+            let alias = alias.clone();
             let bind = bind.clone();
             let access = access.clone();
+            let outlet_target = outlet_target.clone();
+            let udp_rendezvous = udp_rendezvous.clone();
             let ctx = ctx.clone();
             let connection_arc = connection_arc.clone();
             let inlet_address_arc = inlet_address_arc.clone();
@@ -626,6 +1011,7 @@ impl InMemoryNode {
                             debug!("cannot delete secure channel `{encryptor}`: {error}");
                         }
                     }
+                    node_manager.stop_inlet_hole_punch(&ctx, &alias).await;
                     if let Some(tcp_connection) = previous_connection.tcp_connection.as_ref() {
                         if let Err(error) = node_manager
                             .tcp_transport
@@ -636,6 +1022,13 @@ impl InMemoryNode {
                         }
                     }
 
+                    // The inlet may have been rebound to another address since it was created
+                    let (inlet_address, bind) = match node_manager.registry.inlets.get(&alias).await
+                    {
+                        Some(info) => (info.worker_addr, info.bind_addr),
+                        None => (inlet_address, bind),
+                    };
+
                     // The previous inlet worker needs to be stopped:
                     if let Err(error) = node_manager
                         .tcp_transport
@@ -659,17 +1052,65 @@ impl InMemoryNode {
                     *connection_arc.lock().unwrap() = new_connection.clone();
                     let connection_route =
                         new_connection.route(node_manager.tcp_transport()).await?;
+                    let connection_route = match &udp_rendezvous {
+                        Some(rendezvous) => {
+                            node_manager
+                                .punch_hole_to_outlet(
+                                    &ctx,
+                                    &alias,
+                                    connection_route,
+                                    rendezvous,
+                                    DEFAULT_HOLE_OPEN_TIMEOUT,
+                                )
+                                .await
+                        }
+                        None => connection_route,
+                    };
 
                     //we expect a fully normalized MultiAddr
                     let normalized_route = route![prefix_route, connection_route, suffix_route];
-                    let options = TcpInletOptions::new().with_incoming_access_control(access);
+                    let options = TcpInletOptions::new()
+                        .with_incoming_access_control(access)
+                        .with_connection_notifications(INLET_EVENTS_ADDRESS)
+                        .with_mailbox_options(portal_mailbox_options());
+                    let options = match outlet_target {
+                        Some(target) => options.with_outlet_target(target),
+                        None => options,
+                    };
 
                     // Finally attempt to create a new inlet using the new route:
                     let new_inlet_address = node_manager
                         .tcp_transport
-                        .create_inlet(bind, normalized_route, options)
+                        .create_inlet(bind, normalized_route.clone(), options)
                         .await?
                         .1;
+                    if let Some(mut info) = node_manager.registry.inlets.get(&alias).await {
+                        info.worker_addr = new_inlet_address.clone();
+                        info.outlet_route = normalized_route;
+                        let probe = info.probe.clone();
+                        node_manager
+                            .registry
+                            .inlets
+                            .insert(alias.clone(), info)
+                            .await;
+                        // the probe must use the new route to the outlet
+                        if let Some(probe) = probe {
+                            if let Err(err) =
+                                node_manager.restart_inlet_probe(&ctx, &alias, probe).await
+                            {
+                                warn!(%alias, %err, "Failed to restart the inlet probe");
+                            }
+                        }
+                        node_manager
+                            .remove_shutdown_hook(&format!("inlet {alias}"))
+                            .await;
+                        node_manager
+                            .stop_processor_on_shutdown(
+                                format!("inlet {alias}"),
+                                new_inlet_address.clone(),
+                            )
+                            .await;
+                    }
                     *inlet_address_arc.lock().unwrap() = new_inlet_address;
 
                     Ok(new_connection.transport_route())
@@ -691,3 +1132,280 @@ impl InMemoryNode {
         })
     }
 }
+
+/// Restrict the targets which can be requested from an outlet depending on the
+/// identity of the secure channel used by the inlet
+#[derive(Debug)]
+struct IdentityOutletTargetAccessControl {
+    shared: Vec<AllowedTarget>,
+    by_identity: Vec<(Identifier, AllowedTarget)>,
+}
+
+impl IdentityOutletTargetAccessControl {
+    fn new(targets: &OutletTargets) -> Result<Self> {
+        let shared = targets
+            .allowed
+            .iter()
+            .map(|t| t.parse())
+            .collect::<Result<Vec<AllowedTarget>>>()?;
+        let by_identity = targets
+            .by_identity
+            .iter()
+            .map(|t| Ok((t.identifier.clone(), t.target.parse::<AllowedTarget>()?)))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            shared,
+            by_identity,
+        })
+    }
+
+    /// All the targets which can be requested by at least one identity
+    fn all_targets(&self) -> Vec<AllowedTarget> {
+        self.shared
+            .iter()
+            .chain(self.by_identity.iter().map(|(_, t)| t))
+            .cloned()
+            .collect()
+    }
+}
+
+impl OutletTargetAccessControl for IdentityOutletTargetAccessControl {
+    fn is_target_allowed(&self, target: &str, msg: &LocalMessage) -> bool {
+        if self.shared.iter().any(|t| t.matches(target)) {
+            return true;
+        }
+        match IdentitySecureChannelLocalInfo::find_info(msg) {
+            Ok(info) => {
+                let identifier = info.their_identity_id();
+                self.by_identity
+                    .iter()
+                    .any(|(i, t)| i == &identifier && t.matches(target))
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+/// Persisted routes of the outlets, by alias, so that they are set again when an outlet
+/// with the same alias is created, for example when the node is restarted
+#[derive(Clone)]
+pub(crate) struct OutletRoutesStorage {
+    storage: Arc<dyn Storage>,
+}
+
+impl OutletRoutesStorage {
+    pub(crate) fn new(storage: Arc<dyn Storage>) -> Self {
+        Self { storage }
+    }
+
+    async fn get(&self, alias: &str) -> Result<Option<OutletRoutes>> {
+        match self.storage.get(OUTLET_ROUTES_ID, alias).await? {
+            Some(bytes) => Ok(Some(minicbor::decode(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn set(&self, alias: &str, routes: &OutletRoutes) -> Result<()> {
+        if routes.routes.is_empty() {
+            return self.delete(alias).await;
+        }
+        self.storage
+            .set(
+                OUTLET_ROUTES_ID,
+                alias.to_string(),
+                minicbor::to_vec(routes)?,
+            )
+            .await
+    }
+
+    async fn delete(&self, alias: &str) -> Result<()> {
+        self.storage.del(OUTLET_ROUTES_ID, alias).await
+    }
+}
+
+/// Select the target of the connections of an outlet with the attributes of the
+/// identity of the secure channel used by the inlet.
+///
+/// The routes are shared with the node registry, so that they can be changed
+/// after the creation of the outlet
+#[derive(Clone)]
+pub(crate) struct AttributeOutletTargetRouter {
+    attributes_reader: Arc<dyn IdentityAttributesReader>,
+    routes: Arc<RwLock<OutletRoutes>>,
+}
+
+impl AttributeOutletTargetRouter {
+    fn new(attributes_reader: Arc<dyn IdentityAttributesReader>) -> Self {
+        Self {
+            attributes_reader,
+            routes: Default::default(),
+        }
+    }
+
+    pub(crate) fn routes(&self) -> OutletRoutes {
+        self.routes.read().unwrap().clone()
+    }
+
+    fn set_routes(&self, routes: OutletRoutes) {
+        *self.routes.write().unwrap() = routes
+    }
+}
+
+impl std::fmt::Debug for AttributeOutletTargetRouter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AttributeOutletTargetRouter")
+            .field("routes", &self.routes())
+            .finish()
+    }
+}
+
+#[async_trait]
+impl OutletTargetRouter for AttributeOutletTargetRouter {
+    async fn route(&self, msg: &LocalMessage) -> Result<Option<String>> {
+        let routes = self.routes();
+        if routes.routes.is_empty() {
+            return Ok(None);
+        }
+        let identifier = match IdentitySecureChannelLocalInfo::find_info(msg) {
+            Ok(info) => info.their_identity_id(),
+            Err(_) => return Ok(None),
+        };
+        let entry = match self.attributes_reader.get_attributes(&identifier).await? {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+        let now = now()?;
+        let is_matching = |route: &&OutletRoute| {
+            entry.attrs().get(route.attribute.as_bytes()) == Some(&route.value.as_bytes().to_vec())
+                && entry.is_active(route.attribute.as_bytes(), now)
+        };
+        Ok(routes
+            .routes
+            .iter()
+            .find(is_matching)
+            .map(|route| route.target.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam::identity::{identities, AttributesEntry, IdentityAttributesWriter};
+    use ockam_core::{LocalInfo, TransportMessage};
+    use std::collections::BTreeMap;
+    use std::str::FromStr;
+
+    use crate::test_utils::start_manager_for_tests;
+
+    #[test]
+    fn test_outlet_targets_by_identity() -> Result<()> {
+        let alice = Identifier::from_str("I0123456789abcdef0123456789abcdef01234567")?;
+        let bob = Identifier::from_str("I89abcdef0123456789abcdef0123456789abcdef")?;
+        let targets = OutletTargets::default()
+            .allow("web.internal:*")
+            .allow_for(alice.clone(), "db.internal:5432");
+        let access_control = IdentityOutletTargetAccessControl::new(&targets)?;
+        assert_eq!(access_control.all_targets().len(), 2);
+
+        let message = |local_info: Vec<LocalInfo>| {
+            LocalMessage::new(TransportMessage::v1(route![], route![], vec![]), local_info)
+        };
+        let from = |identifier: &Identifier| -> Result<LocalMessage> {
+            let info = IdentitySecureChannelLocalInfo::mark(vec![], identifier.clone())?;
+            Ok(message(info))
+        };
+        let no_identity = message(vec![]);
+
+        assert!(access_control.is_target_allowed("web.internal:80", &no_identity));
+        assert!(access_control.is_target_allowed("db.internal:5432", &from(&alice)?));
+        assert!(!access_control.is_target_allowed("db.internal:5432", &from(&bob)?));
+        assert!(!access_control.is_target_allowed("db.internal:5432", &no_identity));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_outlet_routes_by_attribute() -> Result<()> {
+        let identities = identities();
+        let repository = identities.repository();
+        let gold = identities.identities_creation().create_identity().await?;
+        let silver = identities.identities_creation().create_identity().await?;
+        let tier = |tier: &str| {
+            let attributes = BTreeMap::from([(b"tier".to_vec(), tier.as_bytes().to_vec())]);
+            Ok::<_, ockam_core::Error>(AttributesEntry::new(attributes, now()?, None, None))
+        };
+        repository
+            .put_attributes(gold.identifier(), tier("gold")?)
+            .await?;
+        repository
+            .put_attributes(silver.identifier(), tier("silver")?)
+            .await?;
+
+        let router = AttributeOutletTargetRouter::new(repository.as_attributes_reader());
+        let from = |identifier: &Identifier| -> Result<LocalMessage> {
+            let info = IdentitySecureChannelLocalInfo::mark(vec![], identifier.clone())?;
+            Ok(LocalMessage::new(
+                TransportMessage::v1(route![], route![], vec![]),
+                info,
+            ))
+        };
+        assert_eq!(router.route(&from(gold.identifier())?).await?, None);
+
+        router.set_routes(OutletRoutes::default().route("tier", "gold", "fast.internal:5432"));
+        assert_eq!(
+            router.route(&from(gold.identifier())?).await?,
+            Some("fast.internal:5432".to_string())
+        );
+        assert_eq!(router.route(&from(silver.identifier())?).await?, None);
+        Ok(())
+    }
+
+    #[ockam_macros::test]
+    async fn test_outlet_routes_are_restored(context: &mut Context) -> Result<()> {
+        let handle = start_manager_for_tests(context).await?;
+        let node_manager = &handle.node_manager;
+        let ctx: &Context = context;
+        let create_outlet = move |worker_addr: &str| {
+            node_manager.create_outlet(
+                ctx,
+                "127.0.0.1:5432".parse().unwrap(),
+                worker_addr.into(),
+                Some("db".to_string()),
+                false,
+                None,
+                None,
+            )
+        };
+        create_outlet("db_outlet").await?;
+
+        let routes = OutletRoutes::default().route("tier", "gold", "[fd00::7]:5432");
+        assert!(node_manager
+            .set_outlet_routes(
+                "db",
+                OutletRoutes::default().route("tier", "gold", "::1:5432")
+            )
+            .await
+            .is_err());
+        node_manager.set_outlet_routes("db", routes.clone()).await?;
+        assert_eq!(
+            node_manager.outlet_routes.get("db").await?,
+            Some(routes.clone())
+        );
+
+        // an outlet created again with the same alias, as when the node restarts, uses the
+        // saved routes
+        let outlet = node_manager.registry.outlets.remove("db").await.unwrap();
+        node_manager
+            .tcp_transport
+            .stop_outlet(outlet.worker_addr)
+            .await?;
+        create_outlet("db_outlet_2").await?;
+        let outlet = node_manager.registry.outlets.get("db").await.unwrap();
+        assert_eq!(outlet.router.routes(), routes);
+
+        // the routes are deleted with the outlet
+        node_manager.delete_outlet("db").await?;
+        assert_eq!(node_manager.outlet_routes.get("db").await?, None);
+
+        context.stop().await
+    }
+}
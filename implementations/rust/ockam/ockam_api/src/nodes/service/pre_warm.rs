@@ -0,0 +1,240 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+use tokio::sync::Notify;
+
+use ockam::{Context, Result};
+use ockam_core::env::get_env_with_default;
+use ockam_core::AsyncTryClone;
+use ockam_multiaddr::MultiAddr;
+
+use crate::cli_state::{StateDirTrait, StateItemTrait};
+use crate::error::ApiError;
+use crate::nodes::connection::Connection;
+use crate::nodes::models::base::PreWarmStepStatus;
+
+use super::{NodeManager, NodeService, Readiness};
+
+/// Environment variable setting the maximum time, in seconds, spent by a node at startup to
+/// connect to its project, retrieve its credential and store its attributes. The pre-warm is
+/// disabled when it is set to 0
+const OCKAM_PRE_WARM_TIMEOUT: &str = "OCKAM_PRE_WARM_TIMEOUT";
+
+/// Default pre-warm timeout, in seconds
+const DEFAULT_PRE_WARM_TIMEOUT: u64 = 30;
+
+/// Steps of the pre-warm of a node, in their execution order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PreWarmStep {
+    /// Create a secure channel to the project, which also stores the project identity
+    ProjectConnection,
+    /// Retrieve the credential of the node from the trust context authority
+    Credential,
+    /// Store the attributes of the node credential
+    Attributes,
+}
+
+impl Display for PreWarmStep {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            PreWarmStep::ProjectConnection => "project connection",
+            PreWarmStep::Credential => "credential",
+            PreWarmStep::Attributes => "attributes",
+        })
+    }
+}
+
+/// Progress of the pre-warm of a node
+#[derive(Debug, Clone, Default)]
+pub struct PreWarmProgress {
+    steps: Arc<RwLock<BTreeMap<PreWarmStep, Readiness>>>,
+    /// Connection to the project opened by the pre-warm, until it is used
+    connection: Arc<Mutex<Option<Connection>>>,
+    /// Notified when the pre-warm has succeeded, failed or timed out
+    finished: Arc<Notify>,
+}
+
+impl PreWarmProgress {
+    /// Status of each step, in their execution order
+    pub fn list(&self) -> Vec<PreWarmStepStatus> {
+        self.steps
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(step, readiness)| {
+                PreWarmStepStatus::new(step.to_string(), readiness.to_string())
+            })
+            .collect()
+    }
+
+    fn starting(&self, steps: &[PreWarmStep]) {
+        let mut guard = self.steps.write().unwrap();
+        for step in steps {
+            guard.insert(*step, Readiness::Starting);
+        }
+    }
+
+    fn set(&self, step: PreWarmStep, readiness: Readiness) {
+        self.steps.write().unwrap().insert(step, readiness);
+    }
+
+    /// Mark the steps which are still running as failed
+    fn timed_out(&self, timeout: Duration) {
+        let mut guard = self.steps.write().unwrap();
+        for readiness in guard.values_mut() {
+            if *readiness == Readiness::Starting {
+                *readiness = Readiness::Failed(format!("timed out after {}s", timeout.as_secs()));
+            }
+        }
+    }
+}
+
+impl NodeManager {
+    /// Progress of the pre-warm of this node
+    pub fn pre_warm_progress(&self) -> &PreWarmProgress {
+        &self.pre_warm
+    }
+
+    /// Start a background task connecting to the project of the node, retrieving its
+    /// credential and storing its attributes, so that the first requests sent to the node
+    /// don't have to wait for them.
+    ///
+    /// The node is reported as ready once the pre-warm has succeeded. A failed pre-warm
+    /// is reported in the node status, the credential is then retrieved on first use.
+    /// The connections to the project wait for the pre-warm and reuse its connection
+    pub(crate) async fn start_pre_warm(self: &Arc<Self>, ctx: &Context) -> Result<()> {
+        let timeout = Duration::from_secs(get_env_with_default(
+            OCKAM_PRE_WARM_TIMEOUT,
+            DEFAULT_PRE_WARM_TIMEOUT,
+        )?);
+        if timeout.is_zero() || self.trust_context().is_err() {
+            return Ok(());
+        }
+        let mut steps = vec![];
+        if self.project_address().is_some() {
+            steps.push(PreWarmStep::ProjectConnection);
+        }
+        steps.extend([PreWarmStep::Credential, PreWarmStep::Attributes]);
+        self.pre_warm.starting(&steps);
+        self.readiness.starting(&[NodeService::PreWarm]);
+
+        let node_manager = self.clone();
+        let ctx = Arc::new(ctx.async_try_clone().await?);
+        tokio::spawn(async move {
+            match tokio::time::timeout(timeout, node_manager.pre_warm(ctx, timeout)).await {
+                Ok(Ok(())) => {
+                    info!("the node {} is pre-warmed", node_manager.node_name);
+                    node_manager.readiness.ready(NodeService::PreWarm);
+                }
+                Ok(Err(e)) => {
+                    warn!(
+                        "the pre-warm of the node {} failed: {e}",
+                        node_manager.node_name
+                    );
+                    node_manager.readiness.failed(NodeService::PreWarm, e);
+                }
+                Err(_) => {
+                    warn!(
+                        "the pre-warm of the node {} timed out after {}s",
+                        node_manager.node_name,
+                        timeout.as_secs()
+                    );
+                    node_manager.pre_warm.timed_out(timeout);
+                    node_manager.readiness.failed(
+                        NodeService::PreWarm,
+                        format!("timed out after {}s", timeout.as_secs()),
+                    );
+                }
+            }
+            node_manager.pre_warm.finished.notify_waiters();
+        });
+        Ok(())
+    }
+
+    /// Return the connection to the project opened by the pre-warm, if `addr` is the address
+    /// of the project.
+    ///
+    /// If the pre-warm is still running, wait for it to finish instead of opening a second
+    /// connection to the project. The warmed connection is only returned once
+    pub(super) async fn take_pre_warmed_connection(
+        &self,
+        addr: &MultiAddr,
+        timeout: Option<Duration>,
+    ) -> Option<Connection> {
+        if self.project_address().as_ref() != Some(addr) {
+            return None;
+        }
+        // create the notification before checking the readiness, so that it is not missed
+        let finished = self.pre_warm.finished.notified();
+        if self.readiness.is_starting(NodeService::PreWarm) {
+            debug!("waiting for the pre-warm of the node to connect to {addr}");
+            let timeout = timeout.unwrap_or(Duration::from_secs(DEFAULT_PRE_WARM_TIMEOUT));
+            let _ = tokio::time::timeout(timeout, finished).await;
+        }
+        self.pre_warm.connection.lock().unwrap().take()
+    }
+
+    async fn pre_warm(&self, ctx: Arc<Context>, timeout: Duration) -> Result<()> {
+        if let Some(project) = self.project_address() {
+            self.pre_warm_step(PreWarmStep::ProjectConnection, async {
+                let identifier = self.get_client_identifier(None).await?;
+                let connection = self
+                    .connect(ctx.clone(), &project, identifier, None, None, Some(timeout))
+                    .await?;
+                // keep the connection for the first request connecting to the project
+                self.pre_warm.connection.lock().unwrap().replace(connection);
+                Ok(())
+            })
+            .await?;
+        }
+
+        let trust_context = self.trust_context()?.clone();
+        let credential = self
+            .pre_warm_step(
+                PreWarmStep::Credential,
+                trust_context
+                    .authority()?
+                    .credential(&ctx, &self.identifier),
+            )
+            .await?;
+
+        self.pre_warm_step(PreWarmStep::Attributes, async {
+            self.credentials()
+                .credentials_verification()
+                .receive_presented_credential(
+                    &self.identifier,
+                    &trust_context.authorities().await?,
+                    &credential,
+                )
+                .await
+        })
+        .await
+    }
+
+    async fn pre_warm_step<T>(
+        &self,
+        step: PreWarmStep,
+        run: impl std::future::Future<Output = Result<T>>,
+    ) -> Result<T> {
+        match run.await {
+            Ok(result) => {
+                debug!("pre-warm: the {step} step succeeded");
+                self.pre_warm.set(step, Readiness::Ready);
+                Ok(result)
+            }
+            Err(e) => {
+                self.pre_warm.set(step, Readiness::Failed(e.to_string()));
+                Err(ApiError::core(format!("the {step} step failed: {e}")))
+            }
+        }
+    }
+
+    /// Address of the project of this node, if it has one
+    fn project_address(&self) -> Option<MultiAddr> {
+        let project = self.node_project().ok()??;
+        MultiAddr::from_str(&format!("/project/{}", project.name)).ok()
+    }
+}
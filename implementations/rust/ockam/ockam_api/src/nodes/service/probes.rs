@@ -0,0 +1,381 @@
+use ockam::identity::storage::Storage;
+use ockam::identity::utils::now;
+use ockam::{Address, Context, Result, Route, Routed, Worker};
+use ockam_core::api::{Error, RequestHeader, Response};
+use ockam_core::compat::sync::Arc;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_node::{DelayedEvent, MessageSendReceiveOptions};
+use ockam_transport_tcp::PortalMessage;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::{timeout, Instant};
+use tracing::{debug, warn};
+
+use crate::nodes::models::portal::{InletProbe, InletProbeResults, ProbeResult};
+
+use super::{NodeManager, NodeManagerWorker};
+
+/// Maximum number of results kept for each inlet. The oldest results are dropped first
+const MAX_PROBE_RESULTS: usize = 1440;
+
+const PROBES_ID: &str = "inlet_probes";
+const PROBE_RESULTS_KEY: &str = "results";
+
+/// Persisted results of the inlet probes, as a list per inlet alias
+#[derive(Clone)]
+pub(crate) struct ProbeStorage {
+    storage: Arc<dyn Storage>,
+}
+
+impl ProbeStorage {
+    pub(crate) fn new(storage: Arc<dyn Storage>) -> Self {
+        Self { storage }
+    }
+
+    async fn save(&self, alias: &str, result: ProbeResult) -> Result<()> {
+        let mut results = self.results(alias).await?;
+        results.push(result);
+        if results.len() > MAX_PROBE_RESULTS {
+            results.drain(..results.len() - MAX_PROBE_RESULTS);
+        }
+        self.storage
+            .set(
+                &Self::results_id(alias),
+                PROBE_RESULTS_KEY.to_string(),
+                minicbor::to_vec(&results)?,
+            )
+            .await
+    }
+
+    async fn results(&self, alias: &str) -> Result<Vec<ProbeResult>> {
+        match self
+            .storage
+            .get(&Self::results_id(alias), PROBE_RESULTS_KEY)
+            .await?
+        {
+            Some(bytes) => Ok(minicbor::decode(&bytes)?),
+            None => Ok(vec![]),
+        }
+    }
+
+    async fn delete(&self, alias: &str) -> Result<()> {
+        self.storage
+            .del(&Self::results_id(alias), PROBE_RESULTS_KEY)
+            .await
+    }
+
+    fn results_id(alias: &str) -> String {
+        format!("{PROBES_ID}.{alias}")
+    }
+}
+
+/// Exchange a portal ping/pong with the outlet, over the route used by the inlet, and
+/// return the time it took. The outlet connects to its target before answering
+async fn portal_handshake(
+    ctx: &Context,
+    outlet_route: &Route,
+    outlet_target: Option<&String>,
+    handshake_timeout: Duration,
+) -> Result<Duration> {
+    let ping = match outlet_target {
+        Some(target) => PortalMessage::PingWithTarget(target.clone()),
+        None => PortalMessage::Ping,
+    };
+    let start = Instant::now();
+    let pong = ctx
+        .send_and_receive_extended::<PortalMessage>(
+            outlet_route.clone(),
+            ping,
+            MessageSendReceiveOptions::new().with_timeout(handshake_timeout),
+        )
+        .await?;
+    let elapsed = start.elapsed();
+    let return_route = pong.return_route();
+    match pong.body() {
+        PortalMessage::Pong => {
+            // release the connection opened by the outlet for this handshake
+            ctx.send(return_route, PortalMessage::Disconnect).await?;
+            Ok(elapsed)
+        }
+        other => Err(ockam_core::Error::new(
+            Origin::Api,
+            Kind::Protocol,
+            format!("unexpected reply from the outlet: {other:?}"),
+        )),
+    }
+}
+
+/// Measure the portal handshake with the outlet, then open a connection to the inlet
+/// and wait for the first byte sent back by the target
+async fn run_probe(
+    ctx: &Context,
+    bind_addr: &str,
+    outlet_route: &Route,
+    outlet_target: Option<&String>,
+    probe: &InletProbe,
+) -> ProbeResult {
+    let failure = |handshake_ms, error: String| ProbeResult {
+        timestamp: now().map(|t| *t).unwrap_or_default(),
+        handshake_ms,
+        first_byte_ms: None,
+        error: Some(error),
+    };
+
+    let handshake_ms = match portal_handshake(ctx, outlet_route, outlet_target, probe.timeout).await
+    {
+        Ok(elapsed) => Some(elapsed.as_millis() as u64),
+        Err(e) => return failure(None, format!("the portal handshake failed: {e}")),
+    };
+
+    let start = Instant::now();
+    let mut stream = match timeout(probe.timeout, TcpStream::connect(bind_addr)).await {
+        Ok(Ok(stream)) => stream,
+        Ok(Err(e)) => return failure(handshake_ms, format!("cannot connect to the inlet: {e}")),
+        Err(_) => {
+            return failure(
+                handshake_ms,
+                "timeout when connecting to the inlet".to_string(),
+            )
+        }
+    };
+
+    if let Some(payload) = probe.payload.as_ref() {
+        if let Err(e) = stream.write_all(payload.as_bytes()).await {
+            return failure(handshake_ms, format!("cannot send the probe payload: {e}"));
+        }
+    }
+
+    let remaining = probe.timeout.saturating_sub(start.elapsed());
+    let mut buffer = [0u8; 1];
+    match timeout(remaining, stream.read(&mut buffer)).await {
+        Ok(Ok(n)) if n > 0 => ProbeResult {
+            timestamp: now().map(|t| *t).unwrap_or_default(),
+            handshake_ms,
+            first_byte_ms: Some(start.elapsed().as_millis() as u64),
+            error: None,
+        },
+        Ok(Ok(_)) => failure(
+            handshake_ms,
+            "the connection was closed before receiving any data".to_string(),
+        ),
+        Ok(Err(e)) => failure(handshake_ms, format!("cannot receive data: {e}")),
+        Err(_) => failure(
+            handshake_ms,
+            format!("no data received in {:?}", probe.timeout),
+        ),
+    }
+}
+
+/// This worker periodically probes an inlet and persists the results
+struct InletProber {
+    alias: String,
+    bind_addr: String,
+    outlet_route: Route,
+    outlet_target: Option<String>,
+    probe: InletProbe,
+    storage: ProbeStorage,
+    event: Option<DelayedEvent<()>>,
+}
+
+#[ockam_core::worker]
+impl Worker for InletProber {
+    type Message = ();
+    type Context = Context;
+
+    async fn initialize(&mut self, ctx: &mut Context) -> Result<()> {
+        let mut event = DelayedEvent::create(ctx, ctx.address(), ()).await?;
+        event.schedule(self.probe.interval).await?;
+        self.event = Some(event);
+        Ok(())
+    }
+
+    async fn shutdown(&mut self, _ctx: &mut Context) -> Result<()> {
+        if let Some(event) = self.event.as_mut() {
+            event.cancel();
+        }
+        Ok(())
+    }
+
+    async fn handle_message(&mut self, ctx: &mut Context, _msg: Routed<()>) -> Result<()> {
+        let result = run_probe(
+            ctx,
+            &self.bind_addr,
+            &self.outlet_route,
+            self.outlet_target.as_ref(),
+            &self.probe,
+        )
+        .await;
+        if let Some(error) = result.error.as_ref() {
+            warn!(alias = %self.alias, %error, "inlet probe failed");
+        } else {
+            debug!(alias = %self.alias, first_byte_ms = ?result.first_byte_ms, "inlet probe succeeded");
+        }
+        if let Err(e) = self.storage.save(&self.alias, result).await {
+            warn!(alias = %self.alias, "Cannot persist the inlet probe result: {e}");
+        }
+        if let Some(event) = self.event.as_mut() {
+            event.schedule(self.probe.interval).await?;
+        }
+        Ok(())
+    }
+}
+
+impl NodeManagerWorker {
+    pub(super) async fn get_inlet_probe_results(
+        &self,
+        req: &RequestHeader,
+        alias: &str,
+    ) -> Result<Response<InletProbeResults>, Response<Error>> {
+        match self.node_manager.inlet_probe_results(alias).await {
+            Ok(results) => Ok(Response::ok(req).body(InletProbeResults {
+                alias: alias.to_string(),
+                results,
+            })),
+            Err(err) => Err(Response::internal_error(req, &err.to_string())),
+        }
+    }
+}
+
+impl NodeManager {
+    fn inlet_probe_address(alias: &str) -> Address {
+        Address::from_string(format!("inlet_probe.{alias}"))
+    }
+
+    /// Start probing an inlet periodically
+    pub(super) async fn start_inlet_probe(
+        &self,
+        ctx: &Context,
+        alias: &str,
+        probe: InletProbe,
+    ) -> Result<()> {
+        let Some(mut info) = self.registry.inlets.get(alias).await else {
+            return Err(ockam_core::Error::new(
+                Origin::Api,
+                Kind::NotFound,
+                format!("the inlet {alias} does not exist"),
+            ));
+        };
+        // the probe is recorded with the inlet, to be restarted if the inlet is rebound
+        // or reconnected
+        info.probe = Some(probe.clone());
+        self.registry
+            .inlets
+            .insert(alias.to_string(), info.clone())
+            .await;
+        let prober = InletProber {
+            alias: alias.to_string(),
+            bind_addr: info.bind_addr,
+            outlet_route: info.outlet_route,
+            outlet_target: info.outlet_target,
+            probe,
+            storage: self.probe_storage.clone(),
+            event: None,
+        };
+        ctx.start_worker(Self::inlet_probe_address(alias), prober)
+            .await
+    }
+
+    /// Probe an inlet on its new bind address or outlet route, keeping the results of the
+    /// previous probes
+    pub(super) async fn restart_inlet_probe(
+        &self,
+        ctx: &Context,
+        alias: &str,
+        probe: InletProbe,
+    ) -> Result<()> {
+        self.stop_inlet_prober(ctx, alias).await?;
+        self.start_inlet_probe(ctx, alias, probe).await
+    }
+
+    /// Stop probing an inlet, if it was probed, and delete its results
+    pub(super) async fn stop_inlet_probe(&self, ctx: &Context, alias: &str) -> Result<()> {
+        self.stop_inlet_prober(ctx, alias).await?;
+        self.probe_storage.delete(alias).await
+    }
+
+    async fn stop_inlet_prober(&self, ctx: &Context, alias: &str) -> Result<()> {
+        if let Err(err) = ctx.stop_worker(Self::inlet_probe_address(alias)).await {
+            if err.code().kind != Kind::NotFound {
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    /// Return the results of the probes of an inlet, the most recent last
+    pub async fn inlet_probe_results(&self, alias: &str) -> Result<Vec<ProbeResult>> {
+        self.probe_storage.results(alias).await
+    }
+
+    /// Return the result of the last probe of an inlet
+    pub(super) async fn last_inlet_probe_result(&self, alias: &str) -> Option<ProbeResult> {
+        self.probe_storage
+            .results(alias)
+            .await
+            .ok()
+            .and_then(|mut results| results.pop())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam::identity::storage::InMemoryStorage;
+    use ockam_core::route;
+    use ockam_transport_tcp::{TcpInletOptions, TcpOutletOptions, TcpTransport};
+    use tokio::net::TcpListener;
+
+    #[ockam_macros::test]
+    async fn test_inlet_probe(ctx: &mut Context) -> Result<()> {
+        // the target answers every connection, including the one opened for the handshake
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let target = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut request = [0u8; 4];
+                    if stream.read_exact(&mut request).await.is_ok() {
+                        let _ = stream.write_all(b"pong").await;
+                    }
+                });
+            }
+        });
+
+        let tcp = TcpTransport::create(ctx).await?;
+        tcp.create_outlet("outlet", target, TcpOutletOptions::new())
+            .await?;
+        let outlet_route = route!["outlet"];
+        let (bind_addr, _) = tcp
+            .create_inlet("127.0.0.1:0", outlet_route.clone(), TcpInletOptions::new())
+            .await?;
+
+        let probe =
+            InletProbe::new(Duration::from_secs(60), Duration::from_secs(5)).with_payload("ping");
+        let result = run_probe(ctx, &bind_addr.to_string(), &outlet_route, None, &probe).await;
+        assert!(result.is_success(), "{result:?}");
+        assert!(result.handshake_ms.is_some());
+        assert!(result.first_byte_ms.is_some());
+
+        // without an outlet, the handshake fails
+        let probe = InletProbe::new(Duration::from_secs(60), Duration::from_millis(500));
+        let failure = run_probe(
+            ctx,
+            &bind_addr.to_string(),
+            &route!["unknown"],
+            None,
+            &probe,
+        )
+        .await;
+        assert!(!failure.is_success());
+        assert_eq!(failure.handshake_ms, None);
+
+        let storage = ProbeStorage::new(InMemoryStorage::create());
+        storage.save("db", result.clone()).await?;
+        assert_eq!(storage.results("db").await?, vec![result]);
+        storage.delete("db").await?;
+        assert!(storage.results("db").await?.is_empty());
+
+        ctx.stop().await
+    }
+}
@@ -0,0 +1,261 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+
+use ockam::Result;
+use ockam_core::api::{Error, RequestHeader, Response};
+
+use crate::error::ApiError;
+use crate::nodes::models::base::{ServiceReadinessStatus, STARTING};
+
+use super::{NodeManager, NodeManagerWorker};
+
+/// Services of a node which depend on each other
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum NodeService {
+    Policies,
+    SecureChannelListener,
+    Relay,
+    Uppercase,
+    Credentials,
+    Echoer,
+    /// Services created on request, once their dependencies are ready
+    KafkaServices,
+    Outlets,
+    /// Connection to the project and retrieval of the node credential at startup
+    PreWarm,
+}
+
+impl NodeService {
+    /// Services which must be ready before this service can be started
+    pub fn dependencies(&self) -> &'static [NodeService] {
+        match self {
+            NodeService::KafkaServices => &[NodeService::SecureChannelListener, NodeService::Relay],
+            NodeService::Outlets => &[NodeService::Policies],
+            _ => &[],
+        }
+    }
+}
+
+impl Display for NodeService {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            NodeService::Policies => "policies",
+            NodeService::SecureChannelListener => "secure channel listener",
+            NodeService::Relay => "relay",
+            NodeService::Uppercase => "uppercase",
+            NodeService::Credentials => "credentials",
+            NodeService::Echoer => "echoer",
+            NodeService::KafkaServices => "kafka services",
+            NodeService::Outlets => "outlets",
+            NodeService::PreWarm => "pre-warm",
+        })
+    }
+}
+
+/// Readiness of a service started by the node manager
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Readiness {
+    Starting,
+    Ready,
+    Failed(String),
+}
+
+impl Display for Readiness {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Readiness::Starting => f.write_str(STARTING),
+            Readiness::Ready => f.write_str("ready"),
+            Readiness::Failed(e) => write!(f, "failed: {e}"),
+        }
+    }
+}
+
+/// Readiness of the services started by the node manager.
+///
+/// A service is only started once the services it depends on are ready, so that a service
+/// failing at startup is reported instead of making its dependents fail later on.
+/// Services which were not started on this node, for example the default services of a node
+/// created from a configuration, are not considered as dependencies
+#[derive(Debug, Clone, Default)]
+pub struct ServicesReadiness {
+    services: Arc<RwLock<BTreeMap<NodeService, Readiness>>>,
+}
+
+impl ServicesReadiness {
+    /// Declare services which are going to be started
+    pub fn starting(&self, services: &[NodeService]) {
+        let mut guard = self.services.write().unwrap();
+        for service in services {
+            guard.insert(*service, Readiness::Starting);
+        }
+    }
+
+    pub fn ready(&self, service: NodeService) {
+        self.set(service, Readiness::Ready)
+    }
+
+    pub fn failed(&self, service: NodeService, error: impl Display) {
+        self.set(service, Readiness::Failed(error.to_string()))
+    }
+
+    /// Return true if the service was declared as starting and is not started yet
+    pub fn is_starting(&self, service: NodeService) -> bool {
+        self.services.read().unwrap().get(&service) == Some(&Readiness::Starting)
+    }
+
+    /// Return an error if a dependency of a service is still starting or failed to start
+    pub fn check(&self, service: NodeService) -> std::result::Result<(), String> {
+        let guard = self.services.read().unwrap();
+        for dependency in service.dependencies() {
+            match guard.get(dependency) {
+                None | Some(Readiness::Ready) => (),
+                Some(readiness) => {
+                    return Err(format!(
+                        "the {service} cannot be started, the {dependency} service is {readiness}"
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Readiness of each service, in their startup order
+    pub fn list(&self) -> Vec<ServiceReadinessStatus> {
+        self.services
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(service, readiness)| {
+                ServiceReadinessStatus::new(service.to_string(), readiness.to_string())
+            })
+            .collect()
+    }
+
+    fn set(&self, service: NodeService, readiness: Readiness) {
+        self.services.write().unwrap().insert(service, readiness);
+    }
+}
+
+impl NodeManager {
+    /// Readiness of the services started by this node manager
+    pub fn readiness(&self) -> &ServicesReadiness {
+        &self.readiness
+    }
+
+    /// Start a service once its dependencies are ready, and record whether it is ready
+    pub(super) async fn start_service<T>(
+        &self,
+        service: NodeService,
+        start: impl Future<Output = Result<T>>,
+    ) -> Result<T> {
+        if let Err(e) = self.readiness.check(service) {
+            self.readiness.failed(service, &e);
+            return Err(ApiError::core(e));
+        }
+        match start.await {
+            Ok(started) => {
+                debug!("the {service} service is ready");
+                self.readiness.ready(service);
+                Ok(started)
+            }
+            Err(e) => {
+                error!("the {service} service failed to start: {e}");
+                self.readiness.failed(service, &e);
+                Err(e)
+            }
+        }
+    }
+}
+
+impl NodeManagerWorker {
+    /// Reject a request creating a service whose dependencies are not ready
+    pub(super) fn check_readiness(
+        &self,
+        req: &RequestHeader,
+        service: NodeService,
+    ) -> std::result::Result<(), Response<Error>> {
+        self.node_manager
+            .readiness
+            .check(service)
+            .map_err(|e| Response::service_unavailable(req, &e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::start_manager_for_tests;
+    use ockam_node::Context;
+
+    #[test]
+    fn test_check_dependencies() {
+        let readiness = ServicesReadiness::default();
+
+        // services which were not started on this node are not dependencies
+        assert!(readiness.check(NodeService::KafkaServices).is_ok());
+
+        readiness.starting(&[NodeService::SecureChannelListener, NodeService::Relay]);
+        assert!(readiness.check(NodeService::KafkaServices).is_err());
+        // a service without dependencies can always be started
+        assert!(readiness.check(NodeService::Uppercase).is_ok());
+
+        readiness.ready(NodeService::SecureChannelListener);
+        readiness.failed(NodeService::Relay, "boom");
+        let error = readiness.check(NodeService::KafkaServices).unwrap_err();
+        assert!(error.contains("failed: boom"), "{error}");
+
+        readiness.ready(NodeService::Relay);
+        assert!(readiness.check(NodeService::KafkaServices).is_ok());
+    }
+
+    #[ockam_macros::test]
+    async fn test_default_services_are_ready(context: &mut Context) -> Result<()> {
+        let handle = start_manager_for_tests(context).await?;
+        let status = handle.node_manager.readiness().list();
+
+        for service in [
+            NodeService::SecureChannelListener,
+            NodeService::Relay,
+            NodeService::Uppercase,
+            NodeService::Echoer,
+        ] {
+            let readiness = status
+                .iter()
+                .find(|s| s.service == service.to_string())
+                .map(|s| s.readiness.as_str());
+            assert_eq!(readiness, Some("ready"), "{service}");
+        }
+
+        context.stop().await
+    }
+
+    #[ockam_macros::test]
+    async fn test_failed_service_is_recorded(context: &mut Context) -> Result<()> {
+        let handle = start_manager_for_tests(context).await?;
+        let node_manager = &handle.node_manager;
+
+        let result = node_manager
+            .start_service(NodeService::Relay, async {
+                Err::<(), _>(ApiError::core("boom"))
+            })
+            .await;
+        assert!(result.is_err());
+
+        let relay = node_manager
+            .readiness()
+            .list()
+            .into_iter()
+            .find(|s| s.service == NodeService::Relay.to_string())
+            .unwrap();
+        assert!(relay.readiness.starts_with("failed"), "{}", relay.readiness);
+        // the services depending on the relay cannot be started anymore
+        assert!(node_manager
+            .readiness()
+            .check(NodeService::KafkaServices)
+            .is_err());
+
+        context.stop().await
+    }
+}
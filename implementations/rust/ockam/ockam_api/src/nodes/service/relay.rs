@@ -20,7 +20,7 @@ use crate::nodes::models::secure_channel::{
     CreateSecureChannelRequest, CreateSecureChannelResponse,
 };
 use crate::nodes::service::in_memory_node::InMemoryNode;
-use crate::nodes::BackgroundNode;
+use crate::nodes::{random_idempotency_key, BackgroundNode};
 use crate::session::sessions::{Replacer, Session};
 use crate::session::sessions::{MAX_CONNECT_TIME, MAX_RECOVERY_TIME};
 
@@ -127,6 +127,11 @@ impl NodeManager {
                 let registry_info = info.clone();
                 let registry_remote_address = registry_info.remote_address().to_string();
                 let relay_info = RelayInfo::from(info);
+                self.stop_worker_on_shutdown(
+                    format!("relay {registry_remote_address}"),
+                    registry_info.worker_address().clone(),
+                )
+                .await;
                 self.registry
                     .relays
                     .insert(registry_remote_address, registry_info)
@@ -157,6 +162,8 @@ impl NodeManager {
 
         if let Some(relay_to_delete) = self.registry.relays.remove(remote_address).await {
             debug!(%remote_address, "Successfully removed relay from node registry");
+            self.remove_shutdown_hook(&format!("relay {remote_address}"))
+                .await;
 
             match ctx
                 .stop_worker(relay_to_delete.worker_address().clone())
@@ -213,6 +220,13 @@ impl InMemoryNode {
         authorized: Option<Identifier>,
     ) -> Result<RelayInfo> {
         debug!(addr = %address, alias = ?alias, at_rust_node = ?at_rust_node, "Handling CreateRelay request");
+        // the caller cannot know if a static route leads to a project
+        let expanded = self.expand_static_routes(address).await?;
+        let at_rust_node = if &expanded != address {
+            !expanded.starts_with(Project::CODE)
+        } else {
+            at_rust_node
+        };
         let connection_ctx = Arc::new(ctx.async_try_clone().await?);
         let connection = self
             .make_connection(
@@ -365,10 +379,14 @@ impl Relays for BackgroundNode {
         alias: Option<String>,
         authorized: Option<Identifier>,
     ) -> miette::Result<RelayInfo> {
+        // a relay created with a static route is checked again by the node, once the
+        // route is expanded
         let at_rust_node = !address.starts_with(Project::CODE);
         let body = CreateRelay::new(address.clone(), alias, at_rust_node, authorized);
-        self.ask(ctx, Request::post("/node/forwarder").body(body))
-            .await
+        let req = Request::post("/node/forwarder")
+            .body(body)
+            .idempotency_key(random_idempotency_key());
+        self.ask(ctx, req).await
     }
 }
 
@@ -404,6 +422,8 @@ impl SecureChannelsCreation for InMemoryNode {
                 Some(vec![authorized]),
                 credential_name,
                 timeout,
+                self.node_manager.secure_channel_compression,
+                None,
             )
             .await
             .into_diagnostic()
@@ -0,0 +1,327 @@
+use std::net::{SocketAddr, SocketAddrV4, SocketAddrV6};
+
+use minicbor::Decoder;
+
+use ockam_core::api::{Error, RequestHeader, Response};
+use ockam_core::{Address, Result, LOCAL};
+use ockam_multiaddr::proto::{DnsAddr, Ip4, Ip6, Node, Project, Secure, Service, Tcp, Worker};
+use ockam_multiaddr::{MultiAddr, Protocol};
+use ockam_node::Context;
+use ockam_transport_tcp::TcpSenderInfo;
+
+use crate::cli_state::{StateDirTrait, StateItemTrait};
+use crate::nodes::models::route::{HopKind, ResolveRoute, ResolvedHop, ResolvedRoute};
+
+use super::{NodeManager, NodeManagerWorker};
+
+impl NodeManagerWorker {
+    pub(super) async fn resolve_route(
+        &self,
+        ctx: &Context,
+        req: &RequestHeader,
+        dec: &mut Decoder<'_>,
+    ) -> Result<Response<ResolvedRoute>, Response<Error>> {
+        let request: ResolveRoute = dec.decode()?;
+        match self.node_manager.resolve_route(ctx, &request.addr).await {
+            Ok(route) => Ok(Response::ok(req).body(route)),
+            Err(err) => Err(Response::bad_request(
+                req,
+                &format!("Unable to resolve {}: {}", request.addr, err),
+            )),
+        }
+    }
+}
+
+impl NodeManager {
+    /// Resolve a [`MultiAddr`] hop by hop, without sending any message.
+    /// The static routes referenced by the address are expanded first.
+    ///
+    /// Each hop is classified as a local worker, a secure channel, a transport
+    /// or a relay, and is marked as reachable when a corresponding worker or
+    /// connection currently exists on this node.
+    ///
+    /// The hops following a transport, a secure channel or a relay are on another node,
+    /// so their reachability cannot be determined locally.
+    pub async fn resolve_route(&self, ctx: &Context, addr: &MultiAddr) -> Result<ResolvedRoute> {
+        let addr = &self.expand_static_routes(addr).await?;
+        let workers = ctx.list_workers().await?;
+        let senders = self.tcp_transport.registry().get_all_sender_workers();
+
+        let mut hops = vec![];
+        // true once a hop leads to another node
+        let mut remote = false;
+        let mut it = addr.iter();
+        while let Some(p) = it.next() {
+            let mut hop_addr = MultiAddr::default();
+            hop_addr.push_back_value(&p)?;
+
+            let hop = match p.code() {
+                Worker::CODE | Service::CODE if remote => {
+                    ResolvedHop::new(hop_addr.to_string(), HopKind::RemoteWorker, None, None)
+                }
+                Worker::CODE | Service::CODE => {
+                    let value = match p.code() {
+                        Worker::CODE => p.cast::<Worker>().map(|w| w.to_string()),
+                        _ => p.cast::<Service>().map(|s| s.to_string()),
+                    };
+                    let reachable = value
+                        .map(|v| workers.contains(&Address::new(LOCAL, v)))
+                        .unwrap_or(false);
+                    ResolvedHop::new(
+                        hop_addr.to_string(),
+                        HopKind::LocalWorker,
+                        Some(reachable),
+                        None,
+                    )
+                }
+                Secure::CODE if remote => ResolvedHop::new(
+                    hop_addr.to_string(),
+                    HopKind::SecureChannel,
+                    None,
+                    Some("on a remote node".to_string()),
+                ),
+                Secure::CODE => {
+                    let address = p.cast::<Secure>().map(|s| Address::new(LOCAL, &*s));
+                    let channel = match &address {
+                        Some(address) => self.registry.secure_channels.get_by_addr(address).await,
+                        None => None,
+                    };
+                    let detail = channel.as_ref().map(|c| format!("route: {}", c.route()));
+                    let reachable =
+                        channel.is_some() || address.map(|a| workers.contains(&a)).unwrap_or(false);
+                    ResolvedHop::new(
+                        hop_addr.to_string(),
+                        HopKind::SecureChannel,
+                        Some(reachable),
+                        detail,
+                    )
+                }
+                code @ (Ip4::CODE | Ip6::CODE | DnsAddr::CODE) => {
+                    let port = match it.next() {
+                        Some(port) if port.code() == Tcp::CODE => {
+                            hop_addr.push_back_value(&port)?;
+                            port.cast::<Tcp>().map(|p| *p)
+                        }
+                        _ => None,
+                    };
+                    let socket_addrs: Vec<SocketAddr> = match (code, port) {
+                        (Ip4::CODE, Some(port)) => p
+                            .cast::<Ip4>()
+                            .map(|ip| vec![SocketAddrV4::new(*ip, port).into()])
+                            .unwrap_or_default(),
+                        (Ip6::CODE, Some(port)) => p
+                            .cast::<Ip6>()
+                            .map(|ip| vec![SocketAddrV6::new(*ip, port, 0, 0).into()])
+                            .unwrap_or_default(),
+                        (DnsAddr::CODE, Some(port)) => match p.cast::<DnsAddr>() {
+                            Some(dns) => self.lookup_host(&format!("{}:{port}", &*dns)).await,
+                            None => vec![],
+                        },
+                        _ => vec![],
+                    };
+                    transport_hop(hop_addr, &socket_addrs, &senders)
+                }
+                Node::CODE => {
+                    let name = p.cast::<Node>().map(|n| n.to_string()).unwrap_or_default();
+                    let api_address = self.cli_state.nodes.get(&name).ok().and_then(|n| {
+                        n.config()
+                            .setup()
+                            .api_transport()
+                            .ok()
+                            .map(|t| t.addr.to_string())
+                    });
+                    let socket_addrs = match api_address {
+                        Some(address) => self.lookup_host(&address).await,
+                        None => vec![],
+                    };
+                    transport_hop(hop_addr, &socket_addrs, &senders)
+                }
+                Project::CODE => {
+                    let name = p.cast::<Project>().map(|p| p.to_string());
+                    ResolvedHop::new(
+                        hop_addr.to_string(),
+                        HopKind::Relay,
+                        None,
+                        name.map(|n| format!("project: {n}")),
+                    )
+                }
+                _ => ResolvedHop::new(hop_addr.to_string(), HopKind::Unknown, Some(false), None),
+            };
+            if matches!(
+                hop.kind,
+                HopKind::SecureChannel | HopKind::Transport | HopKind::Relay
+            ) {
+                remote = true;
+            }
+            hops.push(hop);
+        }
+        Ok(ResolvedRoute::new(addr.to_string(), hops))
+    }
+
+    /// Resolve the addresses of a host with the DNS options of the node, without blocking.
+    /// A host which cannot be resolved is not reachable
+    async fn lookup_host(&self, peer: &str) -> Vec<SocketAddr> {
+        self.tcp_transport
+            .dns_options()
+            .lookup(peer)
+            .await
+            .unwrap_or_default()
+    }
+}
+
+/// Describe a transport hop, marking it as reachable if a tcp connection
+/// to one of its socket addresses is currently open
+fn transport_hop(
+    hop_addr: MultiAddr,
+    socket_addrs: &[SocketAddr],
+    senders: &[TcpSenderInfo],
+) -> ResolvedHop {
+    if socket_addrs.is_empty() {
+        return ResolvedHop::new(
+            hop_addr.to_string(),
+            HopKind::Transport,
+            Some(false),
+            Some("address could not be resolved".to_string()),
+        );
+    }
+    let connection = senders
+        .iter()
+        .find(|info| socket_addrs.contains(&info.socket_address()));
+    match connection {
+        Some(info) => ResolvedHop::new(
+            hop_addr.to_string(),
+            HopKind::Transport,
+            Some(true),
+            Some(format!(
+                "tcp connection {} to {}",
+                info.address(),
+                info.socket_address()
+            )),
+        ),
+        // There is no open connection, but one can be created on demand
+        None => ResolvedHop::new(
+            hop_addr.to_string(),
+            HopKind::Transport,
+            None,
+            Some(format!("no open tcp connection to {}", socket_addrs[0])),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::str::FromStr;
+    use ockam_transport_tcp::{TcpConnectionOptions, TcpListenerOptions};
+
+    use crate::echoer::Echoer;
+    use crate::test_utils::start_manager_for_tests;
+
+    #[ockam_macros::test]
+    async fn test_resolve_route(context: &mut Context) -> Result<()> {
+        let handler = start_manager_for_tests(context).await?;
+        let node_manager = &handler.node_manager;
+        context.start_worker("resolved_worker", Echoer).await?;
+        let listener = handler
+            .tcp
+            .listen("127.0.0.1:0", TcpListenerOptions::new())
+            .await?;
+        let port = listener.socket_address().port();
+        handler
+            .tcp
+            .connect(format!("127.0.0.1:{port}"), TcpConnectionOptions::new())
+            .await?;
+
+        let hops = |route: &ResolvedRoute| {
+            route
+                .hops
+                .iter()
+                .map(|h| (h.kind, h.reachable))
+                .collect::<Vec<_>>()
+        };
+
+        // each hop is classified, and checked against the connections and workers of the node
+        let route = resolve(
+            node_manager,
+            context,
+            format!(
+                "/ip4/127.0.0.1/tcp/{port}/service/resolved_worker/secure/missing/project/default"
+            ),
+        )
+        .await?;
+        assert_eq!(
+            hops(&route),
+            vec![
+                (HopKind::Transport, Some(true)),
+                (HopKind::RemoteWorker, None),
+                (HopKind::SecureChannel, None),
+                (HopKind::Relay, None),
+            ]
+        );
+
+        // the hops before the first transport or secure channel are on this node
+        let route = resolve(
+            node_manager,
+            context,
+            format!("/service/resolved_worker/secure/missing/ip4/127.0.0.1/tcp/{port}/service/api"),
+        )
+        .await?;
+        assert_eq!(
+            hops(&route),
+            vec![
+                (HopKind::LocalWorker, Some(true)),
+                (HopKind::SecureChannel, Some(false)),
+                (HopKind::Transport, Some(true)),
+                (HopKind::RemoteWorker, None),
+            ]
+        );
+        assert!(!route.is_reachable());
+
+        // a host name is resolved, and a connection can be created on demand to an address
+        // without any open connection
+        let route = resolve(
+            node_manager,
+            context,
+            format!("/dnsaddr/localhost/tcp/{port}/service/resolved_worker"),
+        )
+        .await?;
+        assert_eq!(
+            hops(&route),
+            vec![
+                (HopKind::Transport, Some(true)),
+                (HopKind::RemoteWorker, None)
+            ]
+        );
+        assert!(route.is_reachable());
+        let route = resolve(
+            node_manager,
+            context,
+            "/ip4/127.0.0.1/tcp/1/service/missing".to_string(),
+        )
+        .await?;
+        assert_eq!(
+            hops(&route),
+            vec![(HopKind::Transport, None), (HopKind::RemoteWorker, None)]
+        );
+
+        context.stop().await
+    }
+
+    async fn resolve(
+        node_manager: &NodeManager,
+        context: &Context,
+        addr: String,
+    ) -> Result<ResolvedRoute> {
+        node_manager
+            .resolve_route(context, &MultiAddr::from_str(&addr)?)
+            .await
+    }
+
+    #[test]
+    fn test_unresolved_transport_hop() -> Result<()> {
+        let hop = transport_hop(MultiAddr::from_str("/dnsaddr/unknown/tcp/80")?, &[], &[]);
+        assert_eq!(hop.kind, HopKind::Transport);
+        assert_eq!(hop.reachable, Some(false));
+        Ok(())
+    }
+}
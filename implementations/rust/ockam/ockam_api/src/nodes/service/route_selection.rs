@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use minicbor::Decoder;
+
+use ockam::identity::utils::now;
+use ockam::identity::{Identifier, TimestampInSeconds};
+use ockam::Result;
+use ockam_core::api::{Error, RequestHeader, Response};
+use ockam_core::env::get_env_with_default;
+use ockam_multiaddr::proto::{Project, Secure, Service};
+use ockam_multiaddr::MultiAddr;
+use ockam_node::Context;
+
+use crate::error::ApiError;
+use crate::nodes::connection::Connection;
+use crate::nodes::models::route::{RouteSelectionKind, SelectRoute, SelectedRoute};
+use crate::DefaultAddress;
+
+use super::{NodeManager, NodeManagerWorker};
+
+/// Environment variable setting the default time, in seconds, during which a selected
+/// route is reused before the candidates are tried again
+const OCKAM_ROUTE_SELECTION_TTL: &str = "OCKAM_ROUTE_SELECTION_TTL";
+
+const DEFAULT_ROUTE_SELECTION_TTL: u64 = 300;
+
+/// Maximum time spent trying to reach a node with each candidate route
+const ROUTE_SELECTION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A route selected for a node, reused until it expires
+#[derive(Debug, Clone)]
+struct RouteSelection {
+    addr: MultiAddr,
+    kind: RouteSelectionKind,
+    expires_at: TimestampInSeconds,
+}
+
+/// Routes selected for each destination node
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RouteSelections {
+    selections: Arc<RwLock<HashMap<Identifier, RouteSelection>>>,
+}
+
+impl RouteSelections {
+    fn get(&self, identifier: &Identifier, now: TimestampInSeconds) -> Option<RouteSelection> {
+        let mut guard = self.selections.write().unwrap();
+        match guard.get(identifier) {
+            Some(selection) if selection.expires_at > now => Some(selection.clone()),
+            Some(_) => {
+                guard.remove(identifier);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn insert(&self, identifier: Identifier, selection: RouteSelection) {
+        self.selections
+            .write()
+            .unwrap()
+            .insert(identifier, selection);
+    }
+}
+
+/// Return the routes which can be used to reach a node, in the order in which they are tried:
+/// its direct addresses, then the relays it created on other nodes, then its project relay
+fn candidates(request: &SelectRoute) -> Result<Vec<(MultiAddr, RouteSelectionKind)>> {
+    let mut candidates: Vec<(MultiAddr, RouteSelectionKind)> = request
+        .direct
+        .iter()
+        .map(|addr| (addr.clone(), RouteSelectionKind::Direct))
+        .collect();
+
+    if let Some(relay_name) = &request.relay_name {
+        let forwarder = format!("forward_to_{relay_name}");
+        for relay in &request.relays {
+            let mut addr = relay.clone();
+            addr.push_back(Service::new(forwarder.as_str()))?;
+            candidates.push((addr, RouteSelectionKind::Relay));
+        }
+        if let Some(project) = &request.project {
+            let mut addr = MultiAddr::default();
+            addr.push_back(Project::new(project.as_str()))?;
+            addr.push_back(Service::new(forwarder.as_str()))?;
+            candidates.push((addr, RouteSelectionKind::ProjectRelay));
+        }
+    } else if !request.relays.is_empty() || request.project.is_some() {
+        return Err(ApiError::core(
+            "a relay name is required to reach a node through a relay",
+        ));
+    }
+
+    Ok(candidates)
+}
+
+impl NodeManagerWorker {
+    pub(super) async fn select_route(
+        &self,
+        ctx: &Context,
+        req: &RequestHeader,
+        dec: &mut Decoder<'_>,
+    ) -> Result<Response<SelectedRoute>, Response<Error>> {
+        let request: SelectRoute = dec.decode()?;
+        let identifier = request.identifier.clone();
+        match self.node_manager.select_route(ctx, request).await {
+            Ok(Some(route)) => Ok(Response::ok(req).body(route)),
+            Ok(None) => Err(Response::not_found(
+                req,
+                &format!("No route to {identifier} could be established"),
+            )),
+            Err(err) => Err(Response::bad_request(req, &err.to_string())),
+        }
+    }
+}
+
+impl NodeManager {
+    /// Select a route to a node by trying, in order, its direct addresses, the relays it
+    /// created on other nodes and its project relay.
+    ///
+    /// A route is selected once a secure channel to the api service of the node, authorized
+    /// to its identifier, can be created through it. The selected route is reused until its
+    /// time to live expires, unless a refresh is requested.
+    /// `None` is returned if none of the candidates can reach the node
+    pub async fn select_route(
+        &self,
+        ctx: &Context,
+        request: SelectRoute,
+    ) -> Result<Option<SelectedRoute>> {
+        let now = now()?;
+        if !request.refresh {
+            if let Some(selection) = self.route_selections.get(&request.identifier, now) {
+                debug!(identifier = %request.identifier, addr = %selection.addr, "reusing the selected route");
+                return Ok(Some(SelectedRoute {
+                    identifier: request.identifier.to_string(),
+                    addr: selection.addr.to_string(),
+                    kind: selection.kind,
+                    cached: true,
+                    expires_at: *selection.expires_at,
+                }));
+            }
+        }
+
+        let ttl = match request.ttl {
+            Some(ttl) => ttl,
+            None => get_env_with_default(OCKAM_ROUTE_SELECTION_TTL, DEFAULT_ROUTE_SELECTION_TTL)?,
+        };
+        let connection_ctx = Arc::new(ctx.async_try_clone().await?);
+        for (addr, kind) in candidates(&request)? {
+            let mut probe = addr.clone();
+            probe.push_back(Secure::new(DefaultAddress::SECURE_CHANNEL_LISTENER))?;
+            match self
+                .make_connection(
+                    connection_ctx.clone(),
+                    &probe,
+                    None,
+                    Some(request.identifier.clone()),
+                    None,
+                    Some(ROUTE_SELECTION_TIMEOUT),
+                )
+                .await
+            {
+                Ok(connection) => {
+                    self.close_probe(ctx, &connection).await;
+                    info!(identifier = %request.identifier, %addr, %kind, "selected a route");
+                    let expires_at = TimestampInSeconds(*now + ttl);
+                    self.route_selections.insert(
+                        request.identifier.clone(),
+                        RouteSelection {
+                            addr: addr.clone(),
+                            kind,
+                            expires_at,
+                        },
+                    );
+                    return Ok(Some(SelectedRoute {
+                        identifier: request.identifier.to_string(),
+                        addr: addr.to_string(),
+                        kind,
+                        cached: false,
+                        expires_at: *expires_at,
+                    }));
+                }
+                Err(e) => {
+                    debug!(identifier = %request.identifier, %addr, %kind, "cannot reach the node: {e}")
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Stop the secure channel and the tcp connection created to test a route
+    pub(super) async fn close_probe(&self, ctx: &Context, connection: &Connection) {
+        for encryptor in &connection.secure_channel_encryptors {
+            if let Err(error) = self.delete_secure_channel(ctx, encryptor).await {
+                debug!("cannot delete secure channel `{encryptor}`: {error}");
+            }
+        }
+        if let Some(tcp_connection) = connection.tcp_connection.as_ref() {
+            if let Err(error) = self
+                .tcp_transport
+                .disconnect(tcp_connection.sender_address().clone())
+                .await
+            {
+                debug!("cannot stop tcp worker `{tcp_connection}`: {error}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_candidates() -> Result<()> {
+        let identifier = Identifier::from_str("I0123456789abcdef0123456789abcdef01234567")?;
+        let request = SelectRoute::new(identifier.clone())
+            .with_direct(vec![MultiAddr::from_str("/dnsaddr/db.local/tcp/4000")?])
+            .with_relays(
+                vec![MultiAddr::from_str("/dnsaddr/relay.local/tcp/4000")?],
+                Some("db".to_string()),
+            )
+            .with_project(Some("default".to_string()));
+
+        let addrs: Vec<(String, RouteSelectionKind)> = candidates(&request)?
+            .into_iter()
+            .map(|(addr, kind)| (addr.to_string(), kind))
+            .collect();
+        assert_eq!(
+            addrs,
+            vec![
+                (
+                    "/dnsaddr/db.local/tcp/4000".to_string(),
+                    RouteSelectionKind::Direct
+                ),
+                (
+                    "/dnsaddr/relay.local/tcp/4000/service/forward_to_db".to_string(),
+                    RouteSelectionKind::Relay
+                ),
+                (
+                    "/project/default/service/forward_to_db".to_string(),
+                    RouteSelectionKind::ProjectRelay
+                ),
+            ]
+        );
+
+        // relays can't be used without the name of the relay
+        let request = SelectRoute::new(identifier).with_relays(
+            vec![MultiAddr::from_str("/dnsaddr/relay.local/tcp/4000")?],
+            None,
+        );
+        assert!(candidates(&request).is_err());
+        Ok(())
+    }
+}
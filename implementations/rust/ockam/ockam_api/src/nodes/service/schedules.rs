@@ -0,0 +1,507 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+use minicbor::Decoder;
+
+use ockam::identity::storage::Storage;
+use ockam::identity::utils::now;
+use ockam::{Context, Result};
+use ockam_core::api::{Error, RequestHeader, Response, Status};
+use ockam_core::env::get_env;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::AsyncTryClone;
+use tokio::task::JoinHandle;
+
+use crate::error::ApiError;
+use crate::nodes::models::events::NodeEventType;
+use crate::nodes::models::schedules::{CreateSchedule, Schedule, ScheduleList, ScheduledTask};
+
+use super::{NodeManager, NodeManagerWorker};
+
+/// Environment variable setting how often, in seconds, a node checks if some of its
+/// scheduled tasks must be executed. The scheduled tasks are only executed manually
+/// when it is set to 0
+const OCKAM_SCHEDULER_INTERVAL: &str = "OCKAM_SCHEDULER_INTERVAL";
+
+/// Default interval between two checks of the scheduled tasks, in seconds
+const DEFAULT_SCHEDULER_INTERVAL: u64 = 60;
+
+const SCHEDULE_KEY: &str = "schedule";
+
+/// Persisted schedules of a node, by schedule name
+#[derive(Clone)]
+pub(crate) struct ScheduleStorage {
+    storage: Arc<dyn Storage>,
+}
+
+impl ScheduleStorage {
+    pub(crate) fn new(storage: Arc<dyn Storage>) -> Self {
+        Self { storage }
+    }
+
+    async fn put(&self, schedule: &Schedule) -> Result<()> {
+        self.storage
+            .set(
+                &schedule.name,
+                SCHEDULE_KEY.to_string(),
+                minicbor::to_vec(schedule)?,
+            )
+            .await
+    }
+
+    async fn get(&self, name: &str) -> Result<Option<Schedule>> {
+        match self.storage.get(name, SCHEDULE_KEY).await? {
+            Some(bytes) => Ok(Some(minicbor::decode(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn list(&self) -> Result<Vec<Schedule>> {
+        let mut schedules = vec![];
+        for name in self.storage.keys(SCHEDULE_KEY).await? {
+            if let Some(schedule) = self.get(&name).await? {
+                schedules.push(schedule);
+            }
+        }
+        Ok(schedules)
+    }
+
+    async fn delete(&self, name: &str) -> Result<()> {
+        self.storage.del(name, SCHEDULE_KEY).await
+    }
+}
+
+/// Background tasks executing the schedules of a node: the scheduler and the executions
+/// of the schedules, by schedule name. A schedule is never executed twice at the same time,
+/// and the tasks are aborted when the node is stopped
+#[derive(Clone, Default)]
+pub(crate) struct ScheduleTasks {
+    scheduler: Arc<Mutex<Option<JoinHandle<()>>>>,
+    runs: Arc<Mutex<HashMap<String, JoinHandle<()>>>>,
+}
+
+impl ScheduleTasks {
+    fn set_scheduler(&self, scheduler: JoinHandle<()>) {
+        if let Some(previous) = self.scheduler.lock().unwrap().replace(scheduler) {
+            previous.abort();
+        }
+    }
+
+    fn is_running(&self, name: &str) -> bool {
+        self.runs.lock().unwrap().contains_key(name)
+    }
+
+    /// Abort the scheduler and the running executions
+    pub(crate) fn abort(&self) {
+        if let Some(scheduler) = self.scheduler.lock().unwrap().take() {
+            scheduler.abort();
+        }
+        for (_, run) in self.runs.lock().unwrap().drain() {
+            run.abort();
+        }
+    }
+}
+
+impl NodeManagerWorker {
+    pub(super) async fn create_schedule(
+        &self,
+        req: &RequestHeader,
+        dec: &mut Decoder<'_>,
+    ) -> Result<Response<Schedule>, Response<Error>> {
+        let request: CreateSchedule = dec.decode()?;
+        match self.node_manager.create_schedule(request).await {
+            Ok(schedule) => Ok(Response::ok(req).body(schedule)),
+            Err(err) if err.code().kind == Kind::AlreadyExists => {
+                Err(Response::error(req, &err.to_string(), Status::Conflict))
+            }
+            Err(err) => Err(Response::internal_error(req, &err.to_string())),
+        }
+    }
+
+    pub(super) async fn list_schedules(
+        &self,
+        req: &RequestHeader,
+    ) -> Result<Response<ScheduleList>, Response<Error>> {
+        match self.node_manager.list_schedules().await {
+            Ok(schedules) => Ok(Response::ok(req).body(ScheduleList { schedules })),
+            Err(err) => Err(Response::internal_error(req, &err.to_string())),
+        }
+    }
+
+    pub(super) async fn delete_schedule(
+        &self,
+        req: &RequestHeader,
+        name: &str,
+    ) -> Result<Response<()>, Response<Error>> {
+        match self.node_manager.delete_schedule(name).await {
+            Ok(true) => Ok(Response::ok(req)),
+            Ok(false) => Err(Response::not_found(
+                req,
+                &format!("Schedule with name {name} not found"),
+            )),
+            Err(err) => Err(Response::internal_error(req, &err.to_string())),
+        }
+    }
+
+    /// Start the execution of a schedule in the background, and return the schedule.
+    ///
+    /// The task is not awaited since some tasks send requests to this worker.
+    /// Its outcome is recorded in the schedule
+    pub(super) async fn run_schedule(
+        &self,
+        ctx: &Context,
+        req: &RequestHeader,
+        name: &str,
+    ) -> Result<Response<Schedule>, Response<Error>> {
+        let schedule = match self.node_manager.schedule_storage.get(name).await {
+            Ok(Some(schedule)) => schedule,
+            Ok(None) => {
+                return Err(Response::not_found(
+                    req,
+                    &format!("Schedule with name {name} not found"),
+                ))
+            }
+            Err(err) => return Err(Response::internal_error(req, &err.to_string())),
+        };
+        let node_manager: Arc<NodeManager> = (**self.node_manager).clone();
+        if !node_manager.spawn_schedule_run(ctx, name).await? {
+            return Err(Response::error(
+                req,
+                &format!("The schedule {name} is already running"),
+                Status::Conflict,
+            ));
+        }
+        Ok(Response::ok(req).body(schedule))
+    }
+}
+
+impl NodeManager {
+    /// Create a schedule, executed for the first time at its next occurrence
+    pub async fn create_schedule(&self, request: CreateSchedule) -> Result<Schedule> {
+        if self.schedule_storage.get(&request.name).await?.is_some() {
+            return Err(ockam_core::Error::new(
+                Origin::Node,
+                Kind::AlreadyExists,
+                format!("a schedule named {} already exists", request.name),
+            ));
+        }
+        let schedule = Schedule {
+            next_run: request.recurrence.next_run(*now()?),
+            name: request.name,
+            task: request.task,
+            recurrence: request.recurrence,
+            last_run: None,
+            last_error: None,
+        };
+        self.schedule_storage.put(&schedule).await?;
+        info!(name = %schedule.name, task = %schedule.task, recurrence = %schedule.recurrence, "created a schedule");
+        Ok(schedule)
+    }
+
+    pub async fn list_schedules(&self) -> Result<Vec<Schedule>> {
+        self.schedule_storage.list().await
+    }
+
+    /// Delete a schedule, return false if it does not exist
+    pub async fn delete_schedule(&self, name: &str) -> Result<bool> {
+        if self.schedule_storage.get(name).await?.is_none() {
+            return Ok(false);
+        }
+        self.schedule_storage.delete(name).await?;
+        Ok(true)
+    }
+
+    /// Execute the task of a schedule now, record the outcome, and compute its next execution
+    pub async fn run_schedule(&self, ctx: &Context, name: &str) -> Result<Schedule> {
+        let mut schedule = self
+            .schedule_storage
+            .get(name)
+            .await?
+            .ok_or_else(|| ApiError::core(format!("the schedule {name} does not exist")))?;
+        let result = self.run_scheduled_task(ctx, schedule.task).await;
+        let now = *now()?;
+        schedule.last_run = Some(now);
+        schedule.next_run = schedule.recurrence.next_run(now);
+        schedule.last_error = match result {
+            Ok(()) => {
+                info!(%name, task = %schedule.task, "the scheduled task succeeded");
+                None
+            }
+            Err(e) => {
+                warn!(%name, task = %schedule.task, "the scheduled task failed: {e}");
+                Some(e.to_string())
+            }
+        };
+        // the schedule is only updated if it was not deleted while its task was running
+        if self.schedule_storage.get(name).await?.is_some() {
+            self.schedule_storage.put(&schedule).await?;
+        }
+        Ok(schedule)
+    }
+
+    /// Start the execution of a schedule in the background.
+    /// Return false if the schedule is already running
+    pub(super) async fn spawn_schedule_run(
+        self: &Arc<Self>,
+        ctx: &Context,
+        name: &str,
+    ) -> Result<bool> {
+        if self.schedule_tasks.is_running(name) {
+            return Ok(false);
+        }
+        let node_manager = self.clone();
+        let ctx = ctx.async_try_clone().await?;
+        let schedule_name = name.to_string();
+        // the runs stay locked until the task is registered, so that it is removed after that
+        let mut runs = self.schedule_tasks.runs.lock().unwrap();
+        if runs.contains_key(name) {
+            return Ok(false);
+        }
+        let run = tokio::spawn(async move {
+            if let Err(e) = node_manager.run_schedule(&ctx, &schedule_name).await {
+                warn!("the schedule {schedule_name} could not be run: {e}");
+            }
+            node_manager
+                .schedule_tasks
+                .runs
+                .lock()
+                .unwrap()
+                .remove(&schedule_name);
+        });
+        runs.insert(name.to_string(), run);
+        Ok(true)
+    }
+
+    async fn run_scheduled_task(&self, ctx: &Context, task: ScheduledTask) -> Result<()> {
+        match task {
+            ScheduledTask::RotateIdentityKey => {
+                self.identities()
+                    .identities_creation()
+                    .rotate_identity(&self.identifier)
+                    .await?;
+                // the purpose keys are attested with the previous key of the identity
+                let purpose_keys = self.identities().purpose_keys().purpose_keys_creation();
+                purpose_keys
+                    .create_secure_channel_purpose_key(&self.identifier)
+                    .await?;
+                if purpose_keys
+                    .get_credential_purpose_key(&self.identifier)
+                    .await
+                    .is_ok()
+                {
+                    purpose_keys
+                        .create_credential_purpose_key(&self.identifier)
+                        .await?;
+                }
+                // the credential of the node is issued again for its new key
+                if self.has_authority() {
+                    self.refresh_credential(ctx).await?;
+                }
+                Ok(())
+            }
+            ScheduledTask::RefreshCredentials => self.refresh_credential(ctx).await,
+            ScheduledTask::RekeyKafkaChannels => {
+                let mut replaced = 0;
+                for info in self.registry.kafka_services.values().await {
+                    if let Some(controller) = info.secure_channel_controller() {
+                        replaced += controller.rekey(ctx).await?;
+                    }
+                }
+                debug!("replaced {replaced} kafka secure channels");
+                Ok(())
+            }
+        }
+    }
+
+    fn has_authority(&self) -> bool {
+        self.trust_context
+            .as_ref()
+            .map(|trust_context| trust_context.authority().is_ok())
+            .unwrap_or(false)
+    }
+
+    /// Get a new credential for the node from the authority of its trust context
+    async fn refresh_credential(&self, ctx: &Context) -> Result<()> {
+        let trust_context = self.trust_context()?;
+        let credential = trust_context
+            .authority()?
+            .refresh_credential(ctx, &self.identifier)
+            .await?;
+        self.credentials()
+            .credentials_verification()
+            .receive_presented_credential(
+                &self.identifier,
+                &trust_context.authorities().await?,
+                &credential,
+            )
+            .await?;
+        self.publish_event(
+            ctx,
+            NodeEventType::CredentialRefreshed,
+            self.identifier.to_string(),
+            None,
+        )
+        .await;
+        Ok(())
+    }
+
+    /// Start the execution of the schedules whose next execution time has passed.
+    /// A schedule which can not be run does not prevent the other ones from running
+    async fn run_due_schedules(self: &Arc<Self>, ctx: &Context) -> Result<()> {
+        let now = *now()?;
+        for schedule in self.schedule_storage.list().await? {
+            if schedule.next_run <= now {
+                if let Err(e) = self.spawn_schedule_run(ctx, &schedule.name).await {
+                    warn!(name = %schedule.name, "the schedule could not be run: {e}");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Start a background task executing the schedules of this node when they are due.
+    ///
+    /// The schedules are checked periodically, and the task stops when the node manager
+    /// is dropped or the node is stopped. A schedule missed while the node was stopped is
+    /// executed once at restart
+    pub(crate) async fn start_scheduler(self: &Arc<Self>, ctx: &Context) -> Result<()> {
+        let interval = Duration::from_secs(scheduler_interval());
+        if interval.is_zero() {
+            return Ok(());
+        }
+        let node_manager: Weak<NodeManager> = Arc::downgrade(self);
+        let ctx = ctx.async_try_clone().await?;
+        let scheduler = tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let node_manager = match node_manager.upgrade() {
+                    Some(node_manager) => node_manager,
+                    None => break,
+                };
+                if let Err(e) = node_manager.run_due_schedules(&ctx).await {
+                    warn!(
+                        "the schedules of the node {} cannot be run: {e}",
+                        node_manager.node_name
+                    );
+                }
+            }
+        });
+        self.schedule_tasks.set_scheduler(scheduler);
+        Ok(())
+    }
+}
+
+/// Return the interval between two checks of the scheduled tasks, as configured with
+/// `OCKAM_SCHEDULER_INTERVAL`. The default interval is used when it is invalid
+fn scheduler_interval() -> u64 {
+    get_env(OCKAM_SCHEDULER_INTERVAL)
+        .unwrap_or_else(|e| {
+            warn!(%e, "invalid {OCKAM_SCHEDULER_INTERVAL}, the schedules are checked every {DEFAULT_SCHEDULER_INTERVAL} seconds");
+            None
+        })
+        .unwrap_or(DEFAULT_SCHEDULER_INTERVAL)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::models::schedules::Recurrence;
+    use crate::test_utils::start_manager_for_tests;
+    use ockam::identity::storage::InMemoryStorage;
+
+    #[tokio::test]
+    async fn test_schedule_storage() -> Result<()> {
+        let storage = ScheduleStorage::new(InMemoryStorage::create());
+        let schedule = |name: &str, task| Schedule {
+            name: name.to_string(),
+            task,
+            recurrence: Recurrence::Every(3600),
+            last_run: None,
+            next_run: 1_700_000_000,
+            last_error: None,
+        };
+        storage
+            .put(&schedule("rekey", ScheduledTask::RekeyKafkaChannels))
+            .await?;
+        storage
+            .put(&schedule("refresh", ScheduledTask::RefreshCredentials))
+            .await?;
+
+        let mut updated = schedule("rekey", ScheduledTask::RekeyKafkaChannels);
+        updated.last_run = Some(1_700_000_000);
+        updated.last_error = Some("no kafka service".to_string());
+        storage.put(&updated).await?;
+
+        assert_eq!(storage.get("rekey").await?, Some(updated));
+        assert_eq!(storage.list().await?.len(), 2);
+
+        storage.delete("rekey").await?;
+        assert_eq!(storage.get("rekey").await?, None);
+        assert_eq!(storage.list().await?.len(), 1);
+        Ok(())
+    }
+    #[ockam_macros::test]
+    async fn test_run_schedules(context: &mut Context) -> Result<()> {
+        let handle = start_manager_for_tests(context).await?;
+        let node_manager: Arc<NodeManager> = (**handle.node_manager).clone();
+        let create = |name: &str, task| CreateSchedule {
+            name: name.to_string(),
+            task,
+            recurrence: Recurrence::Every(3600),
+        };
+
+        // rotating the identity key attests a new secure channel purpose key
+        let purpose_keys = node_manager
+            .identities()
+            .purpose_keys()
+            .purpose_keys_creation();
+        let identifier = node_manager.identifier().clone();
+        let purpose_key = purpose_keys
+            .get_or_create_secure_channel_purpose_key(&identifier)
+            .await?;
+        node_manager
+            .create_schedule(create("rotate", ScheduledTask::RotateIdentityKey))
+            .await?;
+        let schedule = node_manager.run_schedule(context, "rotate").await?;
+        assert!(schedule.last_run.is_some());
+        assert_eq!(schedule.last_error, None);
+        let rotated = purpose_keys
+            .get_secure_channel_purpose_key(&identifier)
+            .await?;
+        assert_ne!(
+            rotated.data().subject_latest_change_hash,
+            purpose_key.data().subject_latest_change_hash
+        );
+
+        // a failure is recorded in the schedule
+        node_manager
+            .create_schedule(create("refresh", ScheduledTask::RefreshCredentials))
+            .await?;
+        node_manager
+            .create_schedule(create("rekey", ScheduledTask::RekeyKafkaChannels))
+            .await?;
+        let schedule = node_manager.run_schedule(context, "refresh").await?;
+        assert!(schedule.last_error.is_some());
+
+        // all the due schedules are run, even when some of them fail
+        for mut schedule in node_manager.list_schedules().await? {
+            schedule.last_run = None;
+            schedule.next_run = 0;
+            node_manager.schedule_storage.put(&schedule).await?;
+        }
+        node_manager.run_due_schedules(context).await?;
+        let mut schedules = vec![];
+        for _ in 0..50 {
+            schedules = node_manager.list_schedules().await?;
+            if schedules.iter().all(|s| s.last_run.is_some()) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        assert!(schedules.iter().all(|s| s.last_run.is_some()));
+        assert!(schedules.iter().all(|s| s.next_run > 0));
+
+        context.stop().await
+    }
+}
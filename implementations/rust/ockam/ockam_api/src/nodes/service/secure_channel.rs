@@ -7,7 +7,8 @@ use ockam::identity::models::CredentialAndPurposeKey;
 use ockam::identity::TrustEveryonePolicy;
 use ockam::identity::Vault;
 use ockam::identity::{
-    Identifier, Identities, SecureChannelListenerOptions, SecureChannelOptions, SecureChannels,
+    Identifier, Identities, SecureChannelCompression, SecureChannelListenerLimits,
+    SecureChannelListenerOptions, SecureChannelOptions, SecureChannels,
     TrustMultiIdentifiersPolicy,
 };
 use ockam::identity::{SecureChannel, SecureChannelListener};
@@ -21,12 +22,14 @@ use ockam_node::Context;
 
 use crate::cli_state::traits::StateDirTrait;
 use crate::cli_state::StateItemTrait;
+use crate::nodes::models::events::NodeEventType;
 use crate::nodes::models::secure_channel::{
     CreateSecureChannelListenerRequest, CreateSecureChannelRequest, CreateSecureChannelResponse,
     DeleteSecureChannelListenerRequest, DeleteSecureChannelListenerResponse,
-    DeleteSecureChannelRequest, DeleteSecureChannelResponse, SecureChannelListenersList,
-    ShowSecureChannelListenerRequest, ShowSecureChannelListenerResponse, ShowSecureChannelRequest,
-    ShowSecureChannelResponse,
+    DeleteSecureChannelRequest, DeleteSecureChannelResponse, ListenerLimits,
+    SecureChannelListenersList, ShowSecureChannelListenerRequest,
+    ShowSecureChannelListenerResponse, ShowSecureChannelRequest, ShowSecureChannelResponse,
+    UpdateSecureChannelListenerLimitsRequest,
 };
 use crate::nodes::registry::{SecureChannelInfo, SecureChannelListenerInfo};
 use crate::nodes::service::NodeIdentities;
@@ -57,6 +60,8 @@ impl NodeManagerWorker {
             timeout,
             identity_name: identity,
             credential_name,
+            compression,
+            replay_window,
             ..
         } = dec.decode()?;
 
@@ -92,6 +97,8 @@ impl NodeManagerWorker {
                 authorized_identifiers,
                 credential_name,
                 timeout,
+                compression.unwrap_or(false),
+                replay_window,
             )
             .await?;
 
@@ -133,7 +140,23 @@ impl NodeManagerWorker {
         let body: ShowSecureChannelRequest = dec.decode()?;
         let sc_address = Address::from(body.channel);
         let info = self.node_manager.get_secure_channel(&sc_address).await;
-        Ok(Response::ok(req).body(ShowSecureChannelResponse::new(info)))
+        let entry = self
+            .node_manager
+            .secure_channels
+            .secure_channel_registry()
+            .get_channel_by_encryptor_address(&sc_address);
+        let last_heartbeat = entry
+            .as_ref()
+            .and_then(|entry| entry.last_heartbeat())
+            .map(|timestamp| *timestamp);
+        let replay_protection = entry.as_ref().map(|entry| entry.replay_protection_stats());
+        let compression = entry.map(|entry| entry.compression_stats());
+        Ok(Response::ok(req).body(
+            ShowSecureChannelResponse::new(info)
+                .with_last_heartbeat(last_heartbeat)
+                .with_replay_protection(replay_protection)
+                .with_compression(compression),
+        ))
     }
 }
 
@@ -150,6 +173,8 @@ impl NodeManagerWorker {
             authorized_identifiers,
             vault_name,
             identity_name,
+            limits,
+            compression,
             ..
         } = dec.decode()?;
 
@@ -179,6 +204,8 @@ impl NodeManagerWorker {
                 authorized_identifiers,
                 vault_name,
                 identity_name,
+                limits.unwrap_or_default(),
+                compression.unwrap_or(false),
                 ctx,
             )
             .await?;
@@ -186,6 +213,39 @@ impl NodeManagerWorker {
         Ok(Response::ok(req))
     }
 
+    pub async fn update_secure_channel_listener_limits(
+        &self,
+        req: &RequestHeader,
+        dec: &mut Decoder<'_>,
+    ) -> Result<Response<ShowSecureChannelListenerResponse>, Response<Error>> {
+        let body: UpdateSecureChannelListenerLimitsRequest = dec.decode()?;
+        let address = Address::from(body.addr);
+        let info = match self
+            .node_manager
+            .get_secure_channel_listener(&address)
+            .await
+        {
+            Some(info) => info,
+            None => {
+                return Err(Response::not_found(
+                    req,
+                    &format!("Secure Channel Listener, {}, not found.", address),
+                ))
+            }
+        };
+        match info.listener().limits() {
+            Some(limits) => body.limits.apply(limits),
+            None => {
+                return Err(Response::bad_request(
+                    req,
+                    &format!("Secure Channel Listener, {}, has no limits.", address),
+                ))
+            }
+        }
+        debug!(%address, "Updated the limits of the secure channel listener");
+        Ok(Response::ok(req).body(ShowSecureChannelListenerResponse::new(&info)))
+    }
+
     pub async fn delete_secure_channel_listener(
         &self,
         ctx: &Context,
@@ -257,6 +317,7 @@ impl NodeManagerWorker {
 
 /// SECURE CHANNELS
 impl NodeManager {
+    #[allow(clippy::too_many_arguments)]
     pub async fn create_secure_channel(
         &self,
         ctx: &Context,
@@ -265,8 +326,10 @@ impl NodeManager {
         authorized_identifiers: Option<Vec<Identifier>>,
         credential_name: Option<String>,
         timeout: Option<Duration>,
+        compression: bool,
+        replay_window: Option<u64>,
     ) -> Result<SecureChannel> {
-        let identifier = self.get_identifier(identity_name.clone()).await?;
+        let identifier = self.get_client_identifier(identity_name.clone()).await?;
         let credential = self
             .get_credential(ctx, &identifier, credential_name, timeout)
             .await?;
@@ -290,6 +353,8 @@ impl NodeManager {
                 authorized_identifiers,
                 timeout,
                 credential,
+                compression,
+                replay_window,
             )
             .await?;
 
@@ -336,6 +401,7 @@ impl NodeManager {
         Ok(credential)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub(crate) async fn create_secure_channel_internal(
         &self,
         ctx: &Context,
@@ -344,10 +410,24 @@ impl NodeManager {
         authorized_identifiers: Option<Vec<Identifier>>,
         timeout: Option<Duration>,
         credential: Option<CredentialAndPurposeKey>,
+        compression: bool,
+        replay_window: Option<u64>,
     ) -> Result<SecureChannel> {
         debug!(%sc_route, "Creating secure channel");
         let options = SecureChannelOptions::new();
 
+        let options = if let Some(replay_window) = replay_window {
+            options.with_replay_window(replay_window)
+        } else {
+            options
+        };
+
+        let options = if compression {
+            options.with_compression(SecureChannelCompression::Deflate)
+        } else {
+            options
+        };
+
         let options = if let Some(timeout) = timeout {
             options.with_timeout(timeout)
         } else {
@@ -382,8 +462,15 @@ impl NodeManager {
 
         self.registry
             .secure_channels
-            .insert(sc_route, sc.clone(), authorized_identifiers)
+            .insert(sc_route.clone(), sc.clone(), authorized_identifiers)
             .await;
+        self.publish_event(
+            ctx,
+            NodeEventType::SecureChannelOpened,
+            sc.encryptor_address().to_string(),
+            Some(format!("route: {sc_route}")),
+        )
+        .await;
 
         Ok(sc)
     }
@@ -392,6 +479,13 @@ impl NodeManager {
         debug!(%addr, "deleting secure channel");
         self.secure_channels.stop_secure_channel(ctx, addr).await?;
         self.registry.secure_channels.remove_by_addr(addr).await;
+        self.publish_event(
+            ctx,
+            NodeEventType::SecureChannelClosed,
+            addr.to_string(),
+            None,
+        )
+        .await;
         Ok(())
     }
 
@@ -414,6 +508,8 @@ impl NodeManager {
         authorized_identifiers: Option<Vec<Identifier>>,
         vault_name: Option<String>,
         identity_name: Option<String>,
+        limits: ListenerLimits,
+        compression: bool,
         ctx: &Context,
     ) -> Result<SecureChannelListener> {
         debug!(
@@ -424,8 +520,19 @@ impl NodeManager {
         let secure_channels = self.build_secure_channels(vault_name.clone()).await?;
         let identifier = self.get_identifier(identity_name.clone()).await?;
 
-        let options =
-            SecureChannelListenerOptions::new().as_consumer(&self.api_transport_flow_control_id);
+        // the listeners always have limits, without restrictions by default,
+        // so that they can be restricted while they are running
+        let listener_limits = SecureChannelListenerLimits::new();
+        limits.apply(&listener_limits);
+        let options = SecureChannelListenerOptions::new()
+            .as_consumer(&self.api_transport_flow_control_id)
+            .with_limits(listener_limits);
+
+        let options = if compression {
+            options.with_compression(SecureChannelCompression::Deflate)
+        } else {
+            options
+        };
 
         let options = match authorized_identifiers {
             Some(ids) => options.with_trust_policy(TrustMultiIdentifiersPolicy::new(ids)),
@@ -451,15 +558,31 @@ impl NodeManager {
             .await;
 
         // TODO: Clean
-        // Add Echoer, Uppercase and Cred Exch as a consumer by default
+        // Add Echoer, Uppercase, Discard, File Receiver and Cred Exch as a consumer by default
         ctx.flow_controls()
             .add_consumer(DefaultAddress::ECHO_SERVICE, listener.flow_control_id());
 
+        ctx.flow_controls()
+            .add_consumer(DefaultAddress::TRACER_SERVICE, listener.flow_control_id());
+
+        ctx.flow_controls().add_consumer(
+            DefaultAddress::HOLE_PUNCH_SERVICE,
+            listener.flow_control_id(),
+        );
+
         ctx.flow_controls().add_consumer(
             DefaultAddress::UPPERCASE_SERVICE,
             listener.flow_control_id(),
         );
 
+        ctx.flow_controls()
+            .add_consumer(DefaultAddress::DISCARD_SERVICE, listener.flow_control_id());
+
+        ctx.flow_controls().add_consumer(
+            DefaultAddress::FILE_RECEIVER_SERVICE,
+            listener.flow_control_id(),
+        );
+
         ctx.flow_controls().add_consumer(
             DefaultAddress::CREDENTIALS_SERVICE,
             listener.flow_control_id(),
@@ -524,6 +647,14 @@ impl NodeManager {
         }
     }
 
+    /// Return the identifier used by the node when it acts as a client on behalf of a user:
+    /// the identity named in the request, otherwise the client identity configured for the
+    /// node, otherwise the identity of the node
+    pub async fn get_client_identifier(&self, identity_name: Option<String>) -> Result<Identifier> {
+        self.get_identifier(identity_name.or_else(|| self.client_identity.clone()))
+            .await
+    }
+
     async fn get_identities(&self, vault_name: Option<String>) -> Result<Arc<Identities>> {
         self.node_identities().get_identities(vault_name).await
     }
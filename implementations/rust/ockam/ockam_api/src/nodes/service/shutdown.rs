@@ -0,0 +1,163 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+use ockam::{Address, Context, Result};
+use ockam_core::compat::sync::Arc;
+use ockam_core::AsyncTryClone;
+use ockam_node::compat::asynchronous::RwLock;
+use tokio::time::{timeout_at, Instant};
+use tracing::{debug, warn};
+
+use super::NodeManager;
+
+/// Default time given to the shutdown hooks to complete before the node stops
+pub const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+type ShutdownFuture = Pin<Box<dyn Future<Output = Result<()>> + Send>>;
+type ShutdownHook = Box<dyn FnOnce(Arc<Context>) -> ShutdownFuture + Send + Sync>;
+
+/// Cleanup functions registered by the node subsystems, to be executed
+/// when the node is stopped gracefully
+#[derive(Default)]
+pub(crate) struct ShutdownHooks {
+    hooks: RwLock<Vec<(String, ShutdownHook)>>,
+}
+
+impl ShutdownHooks {
+    /// Register a hook. A hook registered with the same name is replaced
+    pub(crate) async fn register(&self, name: String, hook: ShutdownHook) {
+        let mut hooks = self.hooks.write().await;
+        hooks.retain(|(n, _)| n != &name);
+        hooks.push((name, hook));
+    }
+
+    /// Remove a hook. Return false if it did not exist
+    pub(crate) async fn remove(&self, name: &str) -> bool {
+        let mut hooks = self.hooks.write().await;
+        let len = hooks.len();
+        hooks.retain(|(n, _)| n != name);
+        hooks.len() != len
+    }
+
+    /// Run all the hooks, in the reverse order of their registration, so that a
+    /// subsystem is cleaned up before the subsystems it was built upon.
+    ///
+    /// Failing hooks are logged. The hooks which could not run before the timeout are skipped.
+    /// Return the number of hooks which completed successfully
+    pub(crate) async fn run(&self, ctx: Arc<Context>, drain_timeout: Duration) -> usize {
+        let hooks = std::mem::take(&mut *self.hooks.write().await);
+        let deadline = Instant::now() + drain_timeout;
+        let total = hooks.len();
+        let mut completed = 0;
+        for (i, (name, hook)) in hooks.into_iter().rev().enumerate() {
+            match timeout_at(deadline, hook(ctx.clone())).await {
+                Ok(Ok(())) => {
+                    debug!(%name, "shutdown hook completed");
+                    completed += 1;
+                }
+                Ok(Err(err)) => warn!(%name, %err, "shutdown hook failed"),
+                Err(_) => {
+                    warn!(
+                        %name,
+                        skipped = total - i,
+                        "the shutdown hooks did not complete in {drain_timeout:?}"
+                    );
+                    break;
+                }
+            }
+        }
+        completed
+    }
+}
+
+impl NodeManager {
+    /// Register an async cleanup function which is executed when the node is stopped
+    /// gracefully, before its workers are stopped. For example to flush some data,
+    /// or to notify a peer.
+    ///
+    /// The hooks are executed in the reverse order of their registration.
+    /// A hook registered with the same name as an existing hook replaces it
+    pub async fn register_shutdown_hook<F, Fut>(&self, name: impl Into<String>, hook: F)
+    where
+        F: FnOnce(Arc<Context>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let hook: ShutdownHook = Box::new(move |ctx| -> ShutdownFuture { Box::pin(hook(ctx)) });
+        self.registry
+            .shutdown_hooks
+            .register(name.into(), hook)
+            .await
+    }
+
+    /// Remove a shutdown hook, for example when the resource it cleans up is deleted.
+    /// Return false if it did not exist
+    pub async fn remove_shutdown_hook(&self, name: &str) -> bool {
+        self.registry.shutdown_hooks.remove(name).await
+    }
+
+    /// Run the shutdown hooks, giving them at most `drain_timeout` to complete.
+    /// Each hook is only executed once
+    pub async fn run_shutdown_hooks(&self, ctx: &Context, drain_timeout: Duration) -> Result<()> {
+        let ctx = Arc::new(ctx.async_try_clone().await?);
+        let completed = self.registry.shutdown_hooks.run(ctx, drain_timeout).await;
+        debug!(%completed, "shutdown hooks executed");
+        Ok(())
+    }
+
+    /// Register a hook stopping a worker when the node shuts down
+    pub(super) async fn stop_worker_on_shutdown(&self, name: String, address: Address) {
+        self.register_shutdown_hook(
+            name,
+            move |ctx| async move { ctx.stop_worker(address).await },
+        )
+        .await
+    }
+
+    /// Register a hook stopping a processor when the node shuts down
+    pub(super) async fn stop_processor_on_shutdown(&self, name: String, address: Address) {
+        self.register_shutdown_hook(
+            name,
+            move |ctx| async move { ctx.stop_processor(address).await },
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[ockam::test]
+    async fn test_shutdown_hooks(ctx: &mut Context) -> Result<()> {
+        let hooks = ShutdownHooks::default();
+        let calls = Arc::new(Mutex::new(vec![]));
+        for name in ["first", "second", "removed", "slow", "last"] {
+            let calls = calls.clone();
+            let hook: ShutdownHook = Box::new(move |_| -> ShutdownFuture {
+                Box::pin(async move {
+                    if name == "slow" {
+                        tokio::time::sleep(Duration::from_secs(1)).await;
+                    }
+                    calls.lock().unwrap().push(name);
+                    Ok(())
+                })
+            });
+            hooks.register(name.to_string(), hook).await;
+        }
+        assert!(hooks.remove("removed").await);
+        assert!(!hooks.remove("unknown").await);
+
+        // the hooks run in reverse order, and the hooks registered before "slow" are skipped
+        let child = Arc::new(ctx.async_try_clone().await?);
+        let completed = hooks.run(child.clone(), Duration::from_millis(100)).await;
+        assert_eq!(completed, 1);
+        assert_eq!(*calls.lock().unwrap(), vec!["last"]);
+
+        // the hooks only run once
+        assert_eq!(hooks.run(child, Duration::from_millis(100)).await, 0);
+
+        ctx.stop().await
+    }
+}
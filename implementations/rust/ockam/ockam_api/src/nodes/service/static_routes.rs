@@ -0,0 +1,302 @@
+use std::collections::BTreeMap;
+
+use minicbor::Decoder;
+
+use ockam::identity::storage::Storage;
+use ockam::Result;
+use ockam_core::api::{Error, RequestHeader, Response};
+use ockam_core::compat::sync::Arc;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_multiaddr::proto::Route;
+use ockam_multiaddr::{MultiAddr, ProtoValue, Protocol};
+use tracing::debug;
+
+use crate::nodes::models::route::{SetStaticRoute, StaticRoute, StaticRouteList};
+
+use super::{NodeManager, NodeManagerWorker};
+
+const STATIC_ROUTES_ID: &str = "static_routes";
+const STATIC_ROUTES_KEY: &str = "routes";
+
+/// Maximum number of routes which can be nested in a route
+const MAX_STATIC_ROUTE_DEPTH: usize = 16;
+
+/// Maximum number of hops of an expanded address
+const MAX_EXPANDED_HOPS: usize = 256;
+
+/// Persisted static routes: addresses registered under a name, so that they
+/// can be referenced as `/route/<name>` in other addresses
+#[derive(Clone)]
+pub(crate) struct StaticRoutesStorage {
+    storage: Arc<dyn Storage>,
+}
+
+impl StaticRoutesStorage {
+    pub(crate) fn new(storage: Arc<dyn Storage>) -> Self {
+        Self { storage }
+    }
+
+    async fn routes(&self) -> Result<BTreeMap<String, MultiAddr>> {
+        match self
+            .storage
+            .get(STATIC_ROUTES_ID, STATIC_ROUTES_KEY)
+            .await?
+        {
+            Some(bytes) => Ok(minicbor::decode(&bytes)?),
+            None => Ok(BTreeMap::new()),
+        }
+    }
+
+    async fn save(&self, routes: &BTreeMap<String, MultiAddr>) -> Result<()> {
+        self.storage
+            .set(
+                STATIC_ROUTES_ID,
+                STATIC_ROUTES_KEY.to_string(),
+                minicbor::to_vec(routes)?,
+            )
+            .await
+    }
+}
+
+/// Replace the `/route/<name>` hops of an address with the addresses registered
+/// under these names, recursively.
+///
+/// An error is returned if a route does not exist, if a route references itself,
+/// directly or through other routes, or if the routes are nested too deeply or expand
+/// to too many hops
+fn expand(routes: &BTreeMap<String, MultiAddr>, addr: &MultiAddr) -> Result<MultiAddr> {
+    if !addr.iter().any(|p| p.code() == Route::CODE) {
+        return Ok(addr.clone());
+    }
+
+    let mut expanded = MultiAddr::default();
+    let mut expanded_hops = 0;
+    // hops left to expand, the next one last, with the names of the routes they come from
+    let mut hops: Vec<(ProtoValue<'static>, Vec<String>)> =
+        addr.iter().rev().map(|p| (p.to_owned(), vec![])).collect();
+    while let Some((hop, mut path)) = hops.pop() {
+        let name = match hop.cast::<Route>() {
+            Some(route) => route.to_string(),
+            None => {
+                if expanded_hops == MAX_EXPANDED_HOPS {
+                    return Err(ockam_core::Error::new(
+                        Origin::Api,
+                        Kind::Invalid,
+                        format!("the address {addr} expands to more than {MAX_EXPANDED_HOPS} hops"),
+                    ));
+                }
+                expanded.push_back_value(&hop)?;
+                expanded_hops += 1;
+                continue;
+            }
+        };
+        if path.contains(&name) {
+            return Err(ockam_core::Error::new(
+                Origin::Api,
+                Kind::Invalid,
+                format!(
+                    "the route {name} references itself: {} -> {name}",
+                    path.join(" -> ")
+                ),
+            ));
+        }
+        if path.len() == MAX_STATIC_ROUTE_DEPTH {
+            return Err(ockam_core::Error::new(
+                Origin::Api,
+                Kind::Invalid,
+                format!(
+                    "the route {name} is nested in more than {MAX_STATIC_ROUTE_DEPTH} routes: {}",
+                    path.join(" -> ")
+                ),
+            ));
+        }
+        let route_addr = routes.get(&name).ok_or_else(|| {
+            ockam_core::Error::new(
+                Origin::Api,
+                Kind::NotFound,
+                format!("the route {name} does not exist"),
+            )
+        })?;
+        path.push(name);
+        for p in route_addr.iter().rev() {
+            hops.push((p.to_owned(), path.clone()));
+        }
+    }
+    Ok(expanded)
+}
+
+impl NodeManagerWorker {
+    pub(super) async fn set_static_route(
+        &self,
+        req: &RequestHeader,
+        name: &str,
+        dec: &mut Decoder<'_>,
+    ) -> Result<Response<StaticRoute>, Response<Error>> {
+        let request: SetStaticRoute = dec.decode()?;
+        match self
+            .node_manager
+            .set_static_route(name, &request.addr)
+            .await
+        {
+            Ok(route) => Ok(Response::ok(req).body(route)),
+            Err(err) => Err(Response::bad_request(req, &err.to_string())),
+        }
+    }
+
+    pub(super) async fn get_static_route(
+        &self,
+        req: &RequestHeader,
+        name: &str,
+    ) -> Result<Response<StaticRoute>, Response<Error>> {
+        match self.node_manager.static_route(name).await? {
+            Some(route) => Ok(Response::ok(req).body(route)),
+            None => Err(Response::not_found(req, &format!("Route {name} not found"))),
+        }
+    }
+
+    pub(super) async fn list_static_routes(
+        &self,
+        req: &RequestHeader,
+    ) -> Result<Response<StaticRouteList>, Response<Error>> {
+        let routes = self.node_manager.static_routes().await?;
+        Ok(Response::ok(req).body(StaticRouteList::new(routes)))
+    }
+
+    pub(super) async fn delete_static_route(
+        &self,
+        req: &RequestHeader,
+        name: &str,
+    ) -> Result<Response<()>, Response<Error>> {
+        if self.node_manager.delete_static_route(name).await? {
+            Ok(Response::ok(req))
+        } else {
+            Err(Response::not_found(req, &format!("Route {name} not found")))
+        }
+    }
+}
+
+impl NodeManager {
+    /// Register an address under a name, replacing the address previously registered.
+    /// The route is rejected if it references a missing route or itself
+    pub async fn set_static_route(&self, name: &str, addr: &MultiAddr) -> Result<StaticRoute> {
+        let mut routes = self.static_routes.routes().await?;
+        routes.insert(name.to_string(), addr.clone());
+        // any new cycle goes through this route
+        let expanded = expand(&routes, addr)?;
+        self.static_routes.save(&routes).await?;
+        debug!(%name, %addr, "static route registered");
+        Ok(StaticRoute::new(name, addr, Some(&expanded)))
+    }
+
+    /// Return a static route with its expanded address
+    pub async fn static_route(&self, name: &str) -> Result<Option<StaticRoute>> {
+        let routes = self.static_routes.routes().await?;
+        Ok(routes.get(name).map(|addr| {
+            let expanded = expand(&routes, addr).ok();
+            StaticRoute::new(name, addr, expanded.as_ref())
+        }))
+    }
+
+    /// Return all the static routes, sorted by name
+    pub async fn static_routes(&self) -> Result<Vec<StaticRoute>> {
+        let routes = self.static_routes.routes().await?;
+        Ok(routes
+            .iter()
+            .map(|(name, addr)| {
+                let expanded = expand(&routes, addr).ok();
+                StaticRoute::new(name, addr, expanded.as_ref())
+            })
+            .collect())
+    }
+
+    /// Delete a static route, returning false if it does not exist.
+    /// The routes referencing it can no longer be expanded
+    pub async fn delete_static_route(&self, name: &str) -> Result<bool> {
+        let mut routes = self.static_routes.routes().await?;
+        if routes.remove(name).is_none() {
+            return Ok(false);
+        }
+        self.static_routes.save(&routes).await?;
+        debug!(%name, "static route deleted");
+        Ok(true)
+    }
+
+    /// Replace the `/route/<name>` hops of an address with the registered addresses
+    pub async fn expand_static_routes(&self, addr: &MultiAddr) -> Result<MultiAddr> {
+        if !addr.iter().any(|p| p.code() == Route::CODE) {
+            return Ok(addr.clone());
+        }
+        expand(&self.static_routes.routes().await?, addr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_expand_static_routes() -> Result<()> {
+        let mut routes = BTreeMap::new();
+        routes.insert(
+            "relay".to_string(),
+            MultiAddr::from_str("/project/default/service/forward_to_db")?,
+        );
+        routes.insert(
+            "backend".to_string(),
+            MultiAddr::from_str("/route/relay/secure/api/service/outlet")?,
+        );
+
+        let expanded = expand(&routes, &MultiAddr::from_str("/route/backend")?)?;
+        assert_eq!(
+            expanded.to_string(),
+            "/project/default/service/forward_to_db/secure/api/service/outlet"
+        );
+
+        // a missing route cannot be expanded
+        assert!(expand(&routes, &MultiAddr::from_str("/route/missing")?).is_err());
+
+        // a cycle is detected, even through another route
+        routes.insert(
+            "relay".to_string(),
+            MultiAddr::from_str("/route/backend/service/echo")?,
+        );
+        assert!(expand(&routes, &MultiAddr::from_str("/route/relay")?).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_expand_static_routes_is_bounded() -> Result<()> {
+        // each route doubles the number of hops of the next one
+        let mut routes = BTreeMap::new();
+        routes.insert("r0".to_string(), MultiAddr::from_str("/service/echo")?);
+        for i in 1..=10 {
+            routes.insert(
+                format!("r{i}"),
+                MultiAddr::from_str(&format!("/route/r{}/route/r{}", i - 1, i - 1))?,
+            );
+        }
+        assert_eq!(
+            expand(&routes, &MultiAddr::from_str("/route/r8")?)?
+                .iter()
+                .count(),
+            MAX_EXPANDED_HOPS
+        );
+        assert!(expand(&routes, &MultiAddr::from_str("/route/r9")?).is_err());
+
+        // a chain of routes cannot be nested too deeply
+        let mut routes = BTreeMap::new();
+        routes.insert("c0".to_string(), MultiAddr::from_str("/service/echo")?);
+        for i in 1..=MAX_STATIC_ROUTE_DEPTH {
+            routes.insert(
+                format!("c{i}"),
+                MultiAddr::from_str(&format!("/route/c{}", i - 1))?,
+            );
+        }
+        let deepest = format!("/route/c{}", MAX_STATIC_ROUTE_DEPTH - 1);
+        assert!(expand(&routes, &MultiAddr::from_str(&deepest)?).is_ok());
+        let too_deep = format!("/route/c{MAX_STATIC_ROUTE_DEPTH}");
+        assert!(expand(&routes, &MultiAddr::from_str(&too_deep)?).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,355 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+use minicbor::Decoder;
+use sysinfo::{ProcessExt, System, SystemExt};
+
+use ockam::identity::storage::Storage;
+use ockam::identity::utils::now;
+use ockam::Result;
+use ockam_core::api::{Error, RequestHeader, Response};
+use ockam_core::env::get_env_with_default;
+
+use crate::nodes::models::stats::{GetNodeStats, NodeStats, NodeStatsList};
+
+use super::{NodeManager, NodeManagerWorker};
+
+/// Environment variable setting how often, in seconds, a node records a snapshot of its
+/// statistics. The statistics are not recorded when it is set to 0
+const OCKAM_NODE_STATS_INTERVAL: &str = "OCKAM_NODE_STATS_INTERVAL";
+
+/// Environment variable setting for how many days the statistics of a node are kept
+const OCKAM_NODE_STATS_RETENTION_DAYS: &str = "OCKAM_NODE_STATS_RETENTION_DAYS";
+
+/// Default interval between two snapshots of the statistics, in seconds
+const DEFAULT_NODE_STATS_INTERVAL: u64 = 60;
+
+/// Default retention of the statistics, in days
+const DEFAULT_NODE_STATS_RETENTION_DAYS: u64 = 30;
+
+/// Snapshots older than this are downsampled
+const RECENT_STATS_RETENTION: u64 = 24 * 3600;
+
+/// Length of the intervals of the downsampled snapshots
+const DOWNSAMPLED_INTERVAL: u64 = 3600;
+
+/// Each snapshot of the last day is stored under its timestamp
+const RECENT_STATS_KEY: &str = "node_stats_recent";
+/// Each downsampled snapshot is stored under the index of its interval
+const DOWNSAMPLED_STATS_KEY: &str = "node_stats_downsampled";
+
+/// Persisted snapshots of the statistics of a node.
+///
+/// The snapshots of the last day are kept as they were recorded, older snapshots are
+/// merged into one snapshot per hour, and removed once they are older than the retention.
+/// Every snapshot is stored under its own key, so that recording a snapshot doesn't rewrite
+/// the previous ones
+#[derive(Clone)]
+pub(crate) struct StatsStorage {
+    storage: Arc<dyn Storage>,
+    /// The recent snapshots older than this have already been downsampled
+    downsampled_until: Arc<Mutex<u64>>,
+}
+
+impl StatsStorage {
+    pub(crate) fn new(storage: Arc<dyn Storage>) -> Self {
+        Self {
+            storage,
+            downsampled_until: Default::default(),
+        }
+    }
+
+    /// Store a snapshot. The older snapshots are downsampled and expired once per
+    /// downsampling interval, when all the snapshots of an interval are older than a day
+    async fn save(&self, stats: NodeStats, retention: u64) -> Result<()> {
+        let now = stats.timestamp;
+        self.storage
+            .set(
+                &now.to_string(),
+                RECENT_STATS_KEY.to_string(),
+                minicbor::to_vec(&stats)?,
+            )
+            .await?;
+
+        let recent_limit = now.saturating_sub(RECENT_STATS_RETENTION) / DOWNSAMPLED_INTERVAL
+            * DOWNSAMPLED_INTERVAL;
+        if recent_limit > *self.downsampled_until.lock().unwrap() {
+            self.downsample(recent_limit, now.saturating_sub(retention))
+                .await?;
+            *self.downsampled_until.lock().unwrap() = recent_limit;
+        }
+        Ok(())
+    }
+
+    /// Merge the recent snapshots taken before `recent_limit` into the downsampled snapshots,
+    /// then remove the downsampled snapshots taken before `retention_limit`
+    async fn downsample(&self, recent_limit: u64, retention_limit: u64) -> Result<()> {
+        let mut old: BTreeMap<u64, Vec<(String, NodeStats)>> = BTreeMap::new();
+        for (id, stats) in self.read(RECENT_STATS_KEY).await? {
+            if stats.timestamp < recent_limit {
+                old.entry(stats.timestamp / DOWNSAMPLED_INTERVAL)
+                    .or_default()
+                    .push((id, stats));
+            }
+        }
+        for (interval, snapshots) in old {
+            let interval = interval.to_string();
+            let mut stats: Vec<NodeStats> =
+                match self.storage.get(&interval, DOWNSAMPLED_STATS_KEY).await? {
+                    Some(bytes) => vec![minicbor::decode(&bytes)?],
+                    None => vec![],
+                };
+            stats.extend(snapshots.iter().map(|(_, s)| s.clone()));
+            self.storage
+                .set(
+                    &interval,
+                    DOWNSAMPLED_STATS_KEY.to_string(),
+                    minicbor::to_vec(merge(&stats))?,
+                )
+                .await?;
+            for (id, _) in snapshots {
+                self.storage.del(&id, RECENT_STATS_KEY).await?;
+            }
+        }
+
+        for (id, stats) in self.read(DOWNSAMPLED_STATS_KEY).await? {
+            if stats.timestamp < retention_limit {
+                self.storage.del(&id, DOWNSAMPLED_STATS_KEY).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Return the snapshots taken over the time range `[from, until)`, oldest first
+    async fn range(&self, from: Option<u64>, until: Option<u64>) -> Result<Vec<NodeStats>> {
+        let mut stats = self.read(DOWNSAMPLED_STATS_KEY).await?;
+        stats.extend(self.read(RECENT_STATS_KEY).await?);
+        let mut stats: Vec<NodeStats> = stats
+            .into_iter()
+            .map(|(_, s)| s)
+            .filter(|s| {
+                from.map(|f| s.timestamp >= f).unwrap_or(true)
+                    && until.map(|u| s.timestamp < u).unwrap_or(true)
+            })
+            .collect();
+        stats.sort_by_key(|s| s.timestamp);
+        Ok(stats)
+    }
+
+    /// Return the snapshots stored under a key, with their ids
+    async fn read(&self, key: &str) -> Result<Vec<(String, NodeStats)>> {
+        let mut stats = vec![];
+        for id in self.storage.keys(key).await? {
+            if let Some(bytes) = self.storage.get(&id, key).await? {
+                stats.push((id, minicbor::decode(&bytes)?));
+            }
+        }
+        Ok(stats)
+    }
+}
+
+/// Merge snapshots into one snapshot.
+///
+/// A merged snapshot can be merged again with other snapshots, since the counts and the
+/// memory are averaged with the duration of each snapshot as weight
+fn merge(stats: &[NodeStats]) -> NodeStats {
+    // a snapshot without a duration still counts in the average
+    let weight = |s: &NodeStats| s.duration.max(1);
+    let total_weight: u64 = stats.iter().map(weight).sum();
+    let average = |value: fn(&NodeStats) -> u64| {
+        let sum: u128 = stats
+            .iter()
+            .map(|s| value(s) as u128 * weight(s) as u128)
+            .sum();
+        (sum / total_weight.max(1) as u128) as u64
+    };
+    NodeStats {
+        timestamp: stats.iter().map(|s| s.timestamp).max().unwrap_or_default(),
+        duration: stats.iter().map(|s| s.duration).sum(),
+        secure_channels: average(|s| s.secure_channels),
+        inlets: average(|s| s.inlets),
+        outlets: average(|s| s.outlets),
+        portal_sessions: average(|s| s.portal_sessions),
+        portal_bytes_received: stats.iter().map(|s| s.portal_bytes_received).sum(),
+        portal_bytes_sent: stats.iter().map(|s| s.portal_bytes_sent).sum(),
+        memory: average(|s| s.memory),
+    }
+}
+
+/// Bytes received and sent by each portal session at the previous snapshot
+type PortalCounters = BTreeMap<String, (u64, u64)>;
+
+impl NodeManagerWorker {
+    pub(super) async fn get_node_stats(
+        &self,
+        req: &RequestHeader,
+        dec: &mut Decoder<'_>,
+    ) -> Result<Response<NodeStatsList>, Response<Error>> {
+        let request: GetNodeStats = dec.decode()?;
+        match self
+            .node_manager
+            .get_node_stats(request.from, request.until)
+            .await
+        {
+            Ok(stats) => Ok(Response::ok(req).body(NodeStatsList::new(stats))),
+            Err(err) => Err(Response::internal_error(req, &err.to_string())),
+        }
+    }
+}
+
+impl NodeManager {
+    /// Return the statistics recorded over the time range `[from, until)`, oldest first
+    pub async fn get_node_stats(
+        &self,
+        from: Option<u64>,
+        until: Option<u64>,
+    ) -> Result<Vec<NodeStats>> {
+        self.stats_storage.range(from, until).await
+    }
+
+    /// Take a snapshot of the statistics of the node. The portal bytes are the bytes
+    /// exchanged by the open portal sessions since the previous snapshot
+    async fn node_stats(&self, duration: u64, previous: &mut PortalCounters) -> Result<NodeStats> {
+        let mut current = PortalCounters::new();
+        let (mut bytes_received, mut bytes_sent) = (0, 0);
+        for session in self.tcp_transport.registry().get_all_portal_sessions() {
+            let address = session.address().to_string();
+            let (received, sent) = (session.bytes_received(), session.bytes_sent());
+            let (previous_received, previous_sent) =
+                previous.get(&address).copied().unwrap_or_default();
+            bytes_received += received.saturating_sub(previous_received);
+            bytes_sent += sent.saturating_sub(previous_sent);
+            current.insert(address, (received, sent));
+        }
+        let portal_sessions = current.len() as u64;
+        *previous = current;
+
+        Ok(NodeStats {
+            timestamp: *now()?,
+            duration,
+            secure_channels: self.registry.secure_channels.list().await.len() as u64,
+            inlets: self.registry.inlets.keys().await.len() as u64,
+            outlets: self.registry.outlets.keys().await.len() as u64,
+            portal_sessions,
+            portal_bytes_received: bytes_received,
+            portal_bytes_sent: bytes_sent,
+            memory: process_memory(),
+        })
+    }
+
+    /// Start a background task recording the statistics of this node periodically.
+    ///
+    /// The task stops when the node manager is dropped
+    pub(crate) fn start_stats_recorder(self: &Arc<Self>) -> Result<()> {
+        let interval =
+            get_env_with_default(OCKAM_NODE_STATS_INTERVAL, DEFAULT_NODE_STATS_INTERVAL)?;
+        if interval == 0 {
+            return Ok(());
+        }
+        let retention = get_env_with_default(
+            OCKAM_NODE_STATS_RETENTION_DAYS,
+            DEFAULT_NODE_STATS_RETENTION_DAYS,
+        )? * 24
+            * 3600;
+        let node_manager: Weak<NodeManager> = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut portal_counters = PortalCounters::new();
+            loop {
+                tokio::time::sleep(Duration::from_secs(interval)).await;
+                let node_manager = match node_manager.upgrade() {
+                    Some(node_manager) => node_manager,
+                    None => break,
+                };
+                let result = match node_manager
+                    .node_stats(interval, &mut portal_counters)
+                    .await
+                {
+                    Ok(stats) => node_manager.stats_storage.save(stats, retention).await,
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = result {
+                    warn!(
+                        "the statistics of the node {} cannot be recorded: {e}",
+                        node_manager.node_name
+                    );
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+/// Resident memory of the current process, in bytes, or 0 if it can't be read
+fn process_memory() -> u64 {
+    let pid = match sysinfo::get_current_pid() {
+        Ok(pid) => pid,
+        Err(_) => return 0,
+    };
+    let mut sys = System::new();
+    if !sys.refresh_process(pid) {
+        return 0;
+    }
+    sys.process(pid).map(|p| p.memory()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam::identity::storage::InMemoryStorage;
+
+    fn stats(timestamp: u64, secure_channels: u64, bytes: u64) -> NodeStats {
+        NodeStats {
+            timestamp,
+            duration: 60,
+            secure_channels,
+            portal_bytes_received: bytes,
+            portal_bytes_sent: bytes,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_merge() {
+        let merged = merge(&[stats(3660, 3, 20), stats(3720, 5, 30)]);
+        assert_eq!(merged.timestamp, 3720);
+        assert_eq!(merged.duration, 120);
+        assert_eq!(merged.secure_channels, 4);
+        assert_eq!(merged.portal_bytes_received, 50);
+
+        // merging a merged snapshot again gives the same result as merging all the snapshots
+        let all = [stats(3600, 1, 10), stats(3660, 3, 20), stats(3720, 5, 30)];
+        assert_eq!(merge(&[all[0].clone(), merged]), merge(&all));
+    }
+
+    #[tokio::test]
+    async fn test_stats_storage_downsamples_and_expires() -> Result<()> {
+        let storage = StatsStorage::new(InMemoryStorage::create());
+        let day = 24 * 3600;
+        let retention = 3 * day;
+
+        storage.save(stats(60, 2, 100), retention).await?;
+        storage.save(stats(120, 4, 100), retention).await?;
+        assert_eq!(storage.range(None, None).await?.len(), 2);
+        // each snapshot is stored under its own key
+        assert_eq!(storage.storage.keys(RECENT_STATS_KEY).await?.len(), 2);
+
+        // the first snapshots are merged once they are older than a day
+        storage.save(stats(day + 3600, 1, 1), retention).await?;
+        let all = storage.range(None, None).await?;
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].secure_channels, 3);
+        assert_eq!(all[0].portal_bytes_sent, 200);
+        assert_eq!(storage.range(Some(day), None).await?.len(), 1);
+        assert_eq!(storage.storage.keys(RECENT_STATS_KEY).await?.len(), 1);
+        assert_eq!(storage.storage.keys(DOWNSAMPLED_STATS_KEY).await?.len(), 1);
+
+        // and removed once they are older than the retention
+        storage.save(stats(4 * day, 1, 1), retention).await?;
+        let all = storage.range(None, None).await?;
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].timestamp, day + 3600);
+        assert_eq!(all[1].timestamp, 4 * day);
+        Ok(())
+    }
+}
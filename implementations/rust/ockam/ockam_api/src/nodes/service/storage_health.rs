@@ -0,0 +1,176 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock, Weak};
+use std::time::Duration;
+
+use ockam::identity::storage::LmdbStorage;
+use ockam::Result;
+use ockam_core::env::get_env_with_default;
+
+use crate::error::ApiError;
+use crate::nodes::models::base::StorageHealthStatus;
+
+use super::NodeManager;
+
+/// Environment variable setting how often, in seconds, a node checks that its databases and its
+/// vault can still be used. They are not checked when it is set to 0
+const OCKAM_STORAGE_HEALTH_CHECK_INTERVAL: &str = "OCKAM_STORAGE_HEALTH_CHECK_INTERVAL";
+
+/// Default interval between two health checks, in seconds
+const DEFAULT_STORAGE_HEALTH_CHECK_INTERVAL: u64 = 30;
+
+const HEALTHY: &str = "healthy";
+
+/// Storage checked by [`StorageHealth`]
+#[derive(Clone)]
+enum MonitoredStorage {
+    Lmdb(LmdbStorage),
+    /// A storage kept in a file, like the vault of the node
+    File(PathBuf),
+}
+
+impl MonitoredStorage {
+    async fn check(&self) -> Result<()> {
+        match self {
+            MonitoredStorage::Lmdb(storage) => storage.check_health().await,
+            MonitoredStorage::File(path) => {
+                // the file is only opened to check that it can still be read
+                tokio::fs::File::open(path).await.map(|_| ()).map_err(|e| {
+                    ApiError::core(format!("the file {} can't be read: {e}", path.display()))
+                })
+            }
+        }
+    }
+}
+
+/// Health of the databases and of the vault of a node.
+///
+/// A storage which can't be used anymore, for example because its file was removed, is
+/// reported as unavailable. It is not created again, since the node would silently lose its
+/// data, and the node must be restarted once the storage is restored
+#[derive(Clone, Default)]
+pub struct StorageHealth {
+    storages: Arc<RwLock<BTreeMap<String, (MonitoredStorage, String)>>>,
+}
+
+impl StorageHealth {
+    /// Register a database to check and return it
+    pub(crate) fn add(&self, name: &str, storage: LmdbStorage) -> LmdbStorage {
+        self.insert(name, MonitoredStorage::Lmdb(storage.clone()));
+        storage
+    }
+
+    /// Register a storage file to check
+    pub(crate) fn add_file(&self, name: &str, path: PathBuf) {
+        self.insert(name, MonitoredStorage::File(path))
+    }
+
+    fn insert(&self, name: &str, storage: MonitoredStorage) {
+        self.storages
+            .write()
+            .unwrap()
+            .insert(name.to_string(), (storage, HEALTHY.to_string()));
+    }
+
+    /// Result of the last check of each storage
+    pub fn list(&self) -> Vec<StorageHealthStatus> {
+        self.storages
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(name, (_, health))| StorageHealthStatus::new(name, health))
+            .collect()
+    }
+
+    /// Check each storage and mark the ones which can't be used as unavailable
+    pub async fn check(&self) {
+        let storages: Vec<(String, MonitoredStorage)> = self
+            .storages
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(name, (storage, _))| (name.clone(), storage.clone()))
+            .collect();
+        for (name, storage) in storages {
+            let health = match storage.check().await {
+                Ok(()) => HEALTHY.to_string(),
+                Err(e) => format!("unavailable: {e}"),
+            };
+            if let Some(entry) = self.storages.write().unwrap().get_mut(&name) {
+                // the failure is only logged when the storage becomes unavailable
+                if health != HEALTHY && entry.1 == HEALTHY {
+                    error!(
+                        "the {name} storage can't be used, the node must be restarted: {health}"
+                    );
+                }
+                entry.1 = health;
+            }
+        }
+    }
+}
+
+impl NodeManager {
+    /// Health of the databases of this node
+    pub fn storage_health(&self) -> &StorageHealth {
+        &self.storage_health
+    }
+
+    /// Start a background task checking the databases of this node periodically.
+    ///
+    /// The task stops when the node manager is dropped
+    pub(crate) fn start_storage_health_checks(self: &Arc<Self>) -> Result<()> {
+        let interval = get_env_with_default(
+            OCKAM_STORAGE_HEALTH_CHECK_INTERVAL,
+            DEFAULT_STORAGE_HEALTH_CHECK_INTERVAL,
+        )?;
+        if interval == 0 {
+            return Ok(());
+        }
+        let node_manager: Weak<NodeManager> = Arc::downgrade(self);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(Duration::from_secs(interval)).await;
+                let storage_health = match node_manager.upgrade() {
+                    Some(node_manager) => node_manager.storage_health.clone(),
+                    None => break,
+                };
+                storage_health.check().await;
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam::identity::storage::Storage;
+
+    #[tokio::test]
+    async fn test_removed_storages_are_unavailable() -> Result<()> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.lmdb");
+        let vault_path = dir.path().join("vault.json");
+        std::fs::write(&vault_path, "{}").unwrap();
+        let health = StorageHealth::default();
+        let storage = health.add("test", LmdbStorage::new(&path).await?);
+        health.add_file("vault", vault_path.clone());
+        storage.set("id", "key".to_string(), vec![1]).await?;
+
+        health.check().await;
+        assert!(health.list().iter().all(|s| s.health == HEALTHY));
+
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(&vault_path).unwrap();
+        health.check().await;
+        assert!(health
+            .list()
+            .iter()
+            .all(|s| s.health.starts_with("unavailable")));
+
+        // the removed files are not created again
+        assert!(!path.exists());
+        assert!(!vault_path.exists());
+        Ok(())
+    }
+}
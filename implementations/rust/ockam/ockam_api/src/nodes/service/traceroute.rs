@@ -0,0 +1,225 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use minicbor::Decoder;
+
+use ockam::{Address, Context, Result};
+use ockam_abac::Expr;
+use ockam_core::api::{Error, RequestHeader, Response};
+use ockam_core::{AsyncTryClone, NeutralMessage};
+use ockam_multiaddr::proto::{Secure, Service};
+use ockam_multiaddr::{MultiAddr, Protocol};
+use ockam_node::{MessageSendReceiveOptions, WorkerBuilder};
+
+use crate::error::ApiError;
+use crate::nodes::connection::Connection;
+use crate::nodes::models::route::{TraceRoute, TracedHop, TracedRoute};
+use crate::nodes::models::services::StartTracerServiceRequest;
+use crate::tracer::{now_micros, TraceProbe, Tracer};
+use crate::DefaultAddress;
+
+use super::{NodeFeature, NodeManager, NodeManagerWorker};
+
+/// Default maximum time to wait for a trace probe to come back
+const DEFAULT_TRACE_ROUTE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Return the prefixes of an address ending with each of its secure channels, followed by
+/// a tracer, and the segment of the address reaching each secure channel.
+///
+/// The tracers can only be reached through a secure channel, so that they are not exposed to
+/// the transports of a node. Since a tracer drops the messages which are not probes, the
+/// handshake of a secure channel can't go through a tracer, so a probe is sent to each prefix
+fn traced_prefixes(addr: &MultiAddr) -> Result<Vec<(MultiAddr, String)>> {
+    let mut prefixes = vec![];
+    let mut prefix = MultiAddr::default();
+    let mut segment = MultiAddr::default();
+    for p in addr.iter() {
+        prefix.push_back_value(&p)?;
+        segment.push_back_value(&p)?;
+        if p.code() == Secure::CODE {
+            let mut traced = prefix.clone();
+            traced.push_back(Service::new(DefaultAddress::TRACER_SERVICE))?;
+            prefixes.push((traced, segment.to_string()));
+            segment = MultiAddr::default();
+        }
+    }
+    if prefixes.is_empty() || !segment.is_empty() {
+        return Err(ApiError::core(format!(
+            "the address {addr} must end with a secure channel"
+        )));
+    }
+    Ok(prefixes)
+}
+
+impl NodeManagerWorker {
+    pub(super) async fn start_tracer_service(
+        &self,
+        ctx: &Context,
+        req: &RequestHeader,
+        dec: &mut Decoder<'_>,
+    ) -> Result<Response, Response<Error>> {
+        let request: StartTracerServiceRequest = dec.decode()?;
+        match self
+            .node_manager
+            .start_tracer_service(ctx, request.policy)
+            .await
+        {
+            Ok(()) => Ok(Response::ok(req)),
+            Err(err) => Err(Response::bad_request(req, &err.to_string())),
+        }
+    }
+
+    pub(super) async fn trace_route(
+        &self,
+        ctx: &Context,
+        req: &RequestHeader,
+        dec: &mut Decoder<'_>,
+    ) -> Result<Response<TracedRoute>, Response<Error>> {
+        let request: TraceRoute = dec.decode()?;
+        match self.node_manager.trace_route(ctx, request).await {
+            Ok(route) => Ok(Response::ok(req).body(route)),
+            Err(err) => Err(Response::bad_request(req, &err.to_string())),
+        }
+    }
+}
+
+impl NodeManager {
+    /// Start the tracer of this node, which records the time at which trace probes reach it.
+    ///
+    /// The tracer is not started by default, and can only be started when the route tracing
+    /// feature is enabled. It only receives the messages sent through the secure channels
+    /// accepted by the default secure channel listener of the node
+    pub async fn start_tracer_service(&self, ctx: &Context, policy: Option<Expr>) -> Result<()> {
+        self.check_feature(NodeFeature::ROUTE_TRACING)?;
+        let addr: Address = DefaultAddress::TRACER_SERVICE.into();
+        if self.registry.tracer_services.contains_key(&addr).await {
+            return Err(ApiError::core("The tracer is already started"));
+        }
+        let ac = self.service_access_control(&addr, policy).await?;
+        WorkerBuilder::new(Tracer::new(&self.node_name))
+            .with_address(addr.clone())
+            .with_incoming_access_control_arc(ac)
+            .start(ctx)
+            .await?;
+        self.registry
+            .tracer_services
+            .insert(addr, Default::default())
+            .await;
+        Ok(())
+    }
+
+    /// Send a probe to the end of each secure channel of a route, and return the time taken
+    /// by the probes to reach each of them.
+    ///
+    /// The route tracing feature must be enabled on this node, and the nodes at the end of the
+    /// secure channels must have started their tracer
+    pub async fn trace_route(&self, ctx: &Context, request: TraceRoute) -> Result<TracedRoute> {
+        self.check_feature(NodeFeature::ROUTE_TRACING)?;
+        let prefixes = traced_prefixes(&request.addr)?;
+        let timeout = request
+            .timeout
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_TRACE_ROUTE_TIMEOUT);
+
+        let mut hops = vec![];
+        let mut previous_one_way = 0;
+        let mut round_trip = 0;
+        let mut traced_addr = String::new();
+        for (traced, segment) in prefixes {
+            let connection_ctx = Arc::new(ctx.async_try_clone().await?);
+            let connection = self
+                .make_connection(connection_ctx, &traced, None, None, None, Some(timeout))
+                .await?;
+            let result = self.send_trace_probe(ctx, &connection, timeout).await;
+            self.close_probe(ctx, &connection).await;
+            let (start, probe, probe_round_trip) = result?;
+
+            let hop = match probe.hops.as_slice() {
+                [hop] => hop.clone(),
+                hops => {
+                    return Err(ApiError::core(format!(
+                        "the probe sent to {traced} went through {} tracers instead of 1",
+                        hops.len()
+                    )))
+                }
+            };
+            let one_way = hop.timestamp.saturating_sub(start);
+            hops.push(TracedHop {
+                segment,
+                node: hop.node,
+                latency: one_way.saturating_sub(previous_one_way),
+            });
+            previous_one_way = one_way;
+            round_trip = probe_round_trip;
+            traced_addr = traced.to_string();
+        }
+        Ok(TracedRoute {
+            addr: traced_addr,
+            hops,
+            round_trip,
+        })
+    }
+
+    /// Send a probe and return the time at which it was sent, the probe which came back
+    /// and its round trip time, in microseconds
+    async fn send_trace_probe(
+        &self,
+        ctx: &Context,
+        connection: &Connection,
+        timeout: Duration,
+    ) -> Result<(u64, TraceProbe, u64)> {
+        let route = connection.route(self.tcp_transport()).await?;
+        let payload = minicbor::to_vec(TraceProbe::new())?;
+        let start = now_micros();
+        let response: NeutralMessage = ctx
+            .send_and_receive_extended::<NeutralMessage>(
+                route,
+                NeutralMessage::from(payload),
+                MessageSendReceiveOptions::new().with_timeout(timeout),
+            )
+            .await?
+            .body();
+        let round_trip = now_micros().saturating_sub(start);
+        let probe = TraceProbe::decode(&Vec::<u8>::from(response))
+            .ok_or_else(|| ApiError::core("the response to the trace probe is not a probe"))?;
+        Ok((start, probe, round_trip))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_traced_prefixes() -> Result<()> {
+        let prefixes = traced_prefixes(&MultiAddr::from_str(
+            "/node/relay/secure/api/service/forward_to_db/secure/api",
+        )?)?;
+        assert_eq!(
+            prefixes
+                .iter()
+                .map(|(traced, segment)| (traced.to_string(), segment.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "/node/relay/secure/api/service/tracer".to_string(),
+                    "/node/relay/secure/api"
+                ),
+                (
+                    "/node/relay/secure/api/service/forward_to_db/secure/api/service/tracer"
+                        .to_string(),
+                    "/service/forward_to_db/secure/api"
+                ),
+            ]
+        );
+
+        // the route must end with a secure channel
+        assert!(traced_prefixes(&MultiAddr::from_str("/node/n1/service/echo")?).is_err());
+        assert!(traced_prefixes(&MultiAddr::from_str(
+            "/dnsaddr/relay.local/tcp/4000/service/forward_to_db"
+        )?)
+        .is_err());
+        Ok(())
+    }
+}
@@ -297,6 +297,24 @@ impl NodeManagerWorker {
             Err(_err) => body.address.into(),
         };
 
+        if let Some(streams) = self
+            .node_manager
+            .tcp_transport
+            .registry()
+            .get_shared_streams(&sender_address)
+        {
+            if !streams.is_empty() {
+                return Err(Response::bad_request(
+                    req,
+                    &format!(
+                        "Connection {} is still used by {} secure channels or portals",
+                        sender_address,
+                        streams.len()
+                    ),
+                ));
+            }
+        }
+
         match self
             .node_manager
             .tcp_transport
@@ -0,0 +1,410 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use minicbor::{Decode, Decoder, Encode};
+
+use ockam::identity::storage::Storage;
+use ockam::identity::utils::now;
+use ockam::identity::{BandwidthUsage, BandwidthUsageRegistry, PeersRegistry};
+use ockam::{Address, Context, Result, Routed, Worker};
+use ockam_core::api::{Error, RequestHeader, Response};
+use ockam_core::compat::sync::Arc;
+use ockam_node::DelayedEvent;
+use tracing::warn;
+
+use crate::nodes::models::usage::{BandwidthUsageList, GetBandwidthUsage, IdentityBandwidthUsage};
+use crate::nodes::service::peers::PeersStorage;
+
+use super::{NodeManager, NodeManagerWorker};
+
+/// Address of the worker persisting the bandwidth usage
+pub(super) const USAGE_RECORDER_ADDRESS: &str = "bandwidth_usage_recorder";
+
+/// Time between two snapshots of the bandwidth usage counters
+const USAGE_PERSISTENCE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The snapshots taken during the same period are merged into a single record,
+/// so the usage can be queried with a one-hour resolution
+const USAGE_RECORD_PERIOD: Duration = Duration::from_secs(60 * 60);
+
+/// Records older than this are deleted
+const USAGE_RETENTION: Duration = Duration::from_secs(90 * 24 * 60 * 60);
+
+const USAGE_ID: &str = "bandwidth_usage";
+const USAGE_INDEX_KEY: &str = "identifiers";
+const USAGE_RECORDS_KEY: &str = "records";
+
+/// Bytes exchanged with a peer identity during one record period
+#[derive(Debug, Clone, Copy, Decode, Encode, PartialEq, Eq)]
+#[rustfmt::skip]
+#[cbor(map)]
+struct UsageRecord {
+    /// Time of the last snapshot merged into this record, in seconds since the Unix epoch
+    #[n(1)] timestamp: u64,
+    #[n(2)] bytes_sent: u64,
+    #[n(3)] bytes_received: u64,
+}
+
+/// Persisted bandwidth usage, per peer identity and over time
+///
+/// Records are stored as a list per identity, along with an index of all the identities
+/// having some records. There is at most one record per identity and per [`USAGE_RECORD_PERIOD`],
+/// and the records are kept for [`USAGE_RETENTION`]
+#[derive(Clone)]
+pub(crate) struct UsageStorage {
+    storage: Arc<dyn Storage>,
+}
+
+impl UsageStorage {
+    pub(crate) fn new(storage: Arc<dyn Storage>) -> Self {
+        Self { storage }
+    }
+
+    /// Add the usage of each identity, recorded at the given timestamp, and delete
+    /// the records which are older than the retention period
+    async fn save(&self, timestamp: u64, usage: Vec<(String, BandwidthUsage)>) -> Result<()> {
+        let period = USAGE_RECORD_PERIOD.as_secs();
+        let oldest = timestamp.saturating_sub(USAGE_RETENTION.as_secs());
+        let usage: BTreeMap<String, BandwidthUsage> = usage.into_iter().collect();
+
+        // the identities without any record left are removed from the index
+        let previous_identifiers = self.identifiers().await?;
+        let count = previous_identifiers.len();
+        let mut identifiers = vec![];
+        for identifier in previous_identifiers {
+            if usage.contains_key(&identifier) || self.expire_records(&identifier, oldest).await? {
+                identifiers.push(identifier);
+            }
+        }
+        let mut index_changed = identifiers.len() != count;
+
+        for (identifier, usage) in usage {
+            let mut records = self.records(&identifier).await?;
+            records.retain(|r| r.timestamp >= oldest);
+            match records.last_mut() {
+                Some(last) if last.timestamp / period == timestamp / period => {
+                    last.timestamp = timestamp;
+                    last.bytes_sent = last.bytes_sent.saturating_add(usage.bytes_sent);
+                    last.bytes_received = last.bytes_received.saturating_add(usage.bytes_received);
+                }
+                _ => records.push(UsageRecord {
+                    timestamp,
+                    bytes_sent: usage.bytes_sent,
+                    bytes_received: usage.bytes_received,
+                }),
+            }
+            self.set_records(&identifier, &records).await?;
+            if !identifiers.contains(&identifier) {
+                identifiers.push(identifier);
+                index_changed = true;
+            }
+        }
+
+        if !index_changed {
+            return Ok(());
+        }
+        self.storage
+            .set(
+                USAGE_ID,
+                USAGE_INDEX_KEY.to_string(),
+                minicbor::to_vec(&identifiers)?,
+            )
+            .await
+    }
+
+    /// Delete the records of an identity which are older than `oldest`.
+    /// Return false if the identity has no record left
+    async fn expire_records(&self, identifier: &str, oldest: u64) -> Result<bool> {
+        let mut records = self.records(identifier).await?;
+        let count = records.len();
+        records.retain(|r| r.timestamp >= oldest);
+        if records.is_empty() {
+            self.storage
+                .del(&Self::records_id(identifier), USAGE_RECORDS_KEY)
+                .await?;
+            return Ok(false);
+        }
+        if records.len() != count {
+            self.set_records(identifier, &records).await?;
+        }
+        Ok(true)
+    }
+
+    async fn set_records(&self, identifier: &str, records: &[UsageRecord]) -> Result<()> {
+        self.storage
+            .set(
+                &Self::records_id(identifier),
+                USAGE_RECORDS_KEY.to_string(),
+                minicbor::to_vec(records)?,
+            )
+            .await
+    }
+
+    /// Sum the usage of each identity over the time range `[from, until)`
+    async fn aggregate(
+        &self,
+        identifier: Option<&str>,
+        from: Option<u64>,
+        until: Option<u64>,
+    ) -> Result<BTreeMap<String, BandwidthUsage>> {
+        let identifiers = match identifier {
+            Some(identifier) => vec![identifier.to_string()],
+            None => self.identifiers().await?,
+        };
+        let mut result = BTreeMap::new();
+        for identifier in identifiers {
+            let mut total = BandwidthUsage::default();
+            let mut found = false;
+            for record in self.records(&identifier).await? {
+                if from.map(|f| record.timestamp < f).unwrap_or(false)
+                    || until.map(|u| record.timestamp >= u).unwrap_or(false)
+                {
+                    continue;
+                }
+                found = true;
+                total.add(&BandwidthUsage {
+                    bytes_sent: record.bytes_sent,
+                    bytes_received: record.bytes_received,
+                });
+            }
+            if found {
+                result.insert(identifier, total);
+            }
+        }
+        Ok(result)
+    }
+
+    async fn identifiers(&self) -> Result<Vec<String>> {
+        match self.storage.get(USAGE_ID, USAGE_INDEX_KEY).await? {
+            Some(bytes) => Ok(minicbor::decode(&bytes)?),
+            None => Ok(vec![]),
+        }
+    }
+
+    async fn records(&self, identifier: &str) -> Result<Vec<UsageRecord>> {
+        match self
+            .storage
+            .get(&Self::records_id(identifier), USAGE_RECORDS_KEY)
+            .await?
+        {
+            Some(bytes) => Ok(minicbor::decode(&bytes)?),
+            None => Ok(vec![]),
+        }
+    }
+
+    fn records_id(identifier: &str) -> String {
+        format!("{USAGE_ID}.{identifier}")
+    }
+}
+
+/// This worker periodically takes the bandwidth usage counters and the peers of the
+/// secure channels and persists them
+pub(super) struct UsageRecorder {
+    usage: BandwidthUsageRegistry,
+    storage: UsageStorage,
+    peers: PeersRegistry,
+    peers_storage: PeersStorage,
+    event: Option<DelayedEvent<()>>,
+}
+
+impl UsageRecorder {
+    pub(super) fn new(
+        usage: BandwidthUsageRegistry,
+        storage: UsageStorage,
+        peers: PeersRegistry,
+        peers_storage: PeersStorage,
+    ) -> Self {
+        Self {
+            usage,
+            storage,
+            peers,
+            peers_storage,
+            event: None,
+        }
+    }
+
+    async fn persist(&self) -> Result<()> {
+        let usage = self
+            .usage
+            .take()
+            .into_iter()
+            .map(|(identifier, usage)| (identifier.to_string(), usage))
+            .collect();
+        self.storage.save(*now()?, usage).await?;
+        let peers = self
+            .peers
+            .take()
+            .into_iter()
+            .map(|(identifier, activity)| (identifier.to_string(), activity))
+            .collect();
+        self.peers_storage.save(peers).await
+    }
+}
+
+#[ockam_core::worker]
+impl Worker for UsageRecorder {
+    type Message = ();
+    type Context = Context;
+
+    async fn initialize(&mut self, ctx: &mut Context) -> Result<()> {
+        let mut event = DelayedEvent::create(ctx, ctx.address(), ()).await?;
+        event.schedule(USAGE_PERSISTENCE_INTERVAL).await?;
+        self.event = Some(event);
+        Ok(())
+    }
+
+    async fn shutdown(&mut self, _ctx: &mut Context) -> Result<()> {
+        if let Some(event) = self.event.as_mut() {
+            event.cancel();
+        }
+        // persist the counters collected since the last snapshot
+        self.persist().await
+    }
+
+    async fn handle_message(&mut self, _ctx: &mut Context, _msg: Routed<()>) -> Result<()> {
+        if let Err(e) = self.persist().await {
+            warn!("Cannot persist the bandwidth usage and the peers: {e}");
+        }
+        if let Some(event) = self.event.as_mut() {
+            event.schedule(USAGE_PERSISTENCE_INTERVAL).await?;
+        }
+        Ok(())
+    }
+}
+
+impl NodeManagerWorker {
+    pub(super) async fn get_bandwidth_usage(
+        &self,
+        req: &RequestHeader,
+        dec: &mut Decoder<'_>,
+    ) -> Result<Response<BandwidthUsageList>, Response<Error>> {
+        let request: GetBandwidthUsage = dec.decode()?;
+        match self
+            .node_manager
+            .get_bandwidth_usage(request.identifier.as_deref(), request.from, request.until)
+            .await
+        {
+            Ok(usage) => Ok(Response::ok(req).body(BandwidthUsageList::new(usage))),
+            Err(err) => Err(Response::internal_error(req, &err.to_string())),
+        }
+    }
+}
+
+impl NodeManager {
+    pub(super) async fn start_usage_recorder(&self, ctx: &Context) -> Result<()> {
+        let recorder = UsageRecorder::new(
+            self.secure_channels.bandwidth_usage(),
+            self.usage_storage.clone(),
+            self.secure_channels.peers(),
+            self.peers_storage.clone(),
+        );
+        ctx.start_worker(Address::from_string(USAGE_RECORDER_ADDRESS), recorder)
+            .await
+    }
+
+    /// Return the bytes exchanged with each peer identity over the time range `[from, until)`.
+    /// The usage which has not been persisted yet is included when the range is still open
+    pub async fn get_bandwidth_usage(
+        &self,
+        identifier: Option<&str>,
+        from: Option<u64>,
+        until: Option<u64>,
+    ) -> Result<Vec<IdentityBandwidthUsage>> {
+        let mut usage = self
+            .usage_storage
+            .aggregate(identifier, from, until)
+            .await?;
+
+        let now = *now()?;
+        if until.map(|u| u > now).unwrap_or(true) {
+            for (id, current) in self.secure_channels.bandwidth_usage().list() {
+                let id = id.to_string();
+                if identifier.map(|i| i == id).unwrap_or(true) {
+                    usage.entry(id).or_default().add(&current);
+                }
+            }
+        }
+
+        Ok(usage
+            .into_iter()
+            .map(|(id, u)| IdentityBandwidthUsage::new(id, u.bytes_sent, u.bytes_received))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam::identity::storage::InMemoryStorage;
+
+    #[tokio::test]
+    async fn test_usage_storage_aggregates_over_time_range() -> Result<()> {
+        let storage = UsageStorage::new(InMemoryStorage::create());
+        let usage = |sent, received| BandwidthUsage {
+            bytes_sent: sent,
+            bytes_received: received,
+        };
+
+        storage
+            .save(100, vec![("alice".into(), usage(10, 20))])
+            .await?;
+        storage
+            .save(
+                200,
+                vec![("alice".into(), usage(1, 2)), ("bob".into(), usage(5, 5))],
+            )
+            .await?;
+
+        let all = storage.aggregate(None, None, None).await?;
+        assert_eq!(all.get("alice"), Some(&usage(11, 22)));
+        assert_eq!(all.get("bob"), Some(&usage(5, 5)));
+
+        let before_200 = storage.aggregate(None, None, Some(200)).await?;
+        assert_eq!(before_200.get("alice"), Some(&usage(10, 20)));
+        assert_eq!(before_200.get("bob"), None);
+
+        let bob = storage.aggregate(Some("bob"), Some(150), None).await?;
+        assert_eq!(bob.len(), 1);
+        assert_eq!(bob.get("bob"), Some(&usage(5, 5)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_usage_storage_is_bounded() -> Result<()> {
+        let storage = UsageStorage::new(InMemoryStorage::create());
+        let usage = BandwidthUsage {
+            bytes_sent: 1,
+            bytes_received: 2,
+        };
+        let period = USAGE_RECORD_PERIOD.as_secs();
+        let start = 1_000 * period;
+
+        // the snapshots taken during the same period are merged into one record
+        for minute in 0..60 {
+            storage
+                .save(start + minute * 60, vec![("alice".into(), usage)])
+                .await?;
+        }
+        storage
+            .save(start + period, vec![("bob".into(), usage)])
+            .await?;
+        assert_eq!(storage.records("alice").await?.len(), 1);
+        let all = storage.aggregate(None, None, None).await?;
+        assert_eq!(
+            all.get("alice"),
+            Some(&BandwidthUsage {
+                bytes_sent: 60,
+                bytes_received: 120,
+            })
+        );
+
+        // the records older than the retention period are deleted,
+        // along with the identities which have no record left
+        let later = start + USAGE_RETENTION.as_secs() + period - 30;
+        storage.save(later, vec![("bob".into(), usage)]).await?;
+        assert!(storage.records("alice").await?.is_empty());
+        assert_eq!(storage.identifiers().await?, vec!["bob".to_string()]);
+        assert_eq!(storage.records("bob").await?.len(), 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,76 @@
+use crate::DefaultAddress;
+
+use ockam::identity::Identifier;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Configuration for a standalone relay node
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Configuration {
+    /// path where the identity of the relay node is persisted
+    pub storage_path: PathBuf,
+
+    /// path where secrets should be persisted
+    pub vault_path: PathBuf,
+
+    /// listener address for the TCP listener, for example "0.0.0.0:4000"
+    pub tcp_listener_address: String,
+
+    /// service name for the secure channel listener, for example "api"
+    /// The default is DefaultAddress::SECURE_CHANNEL_LISTENER
+    #[serde(default)]
+    pub secure_channel_listener_name: Option<String>,
+
+    /// service name for the relay service, for example "forwarding_service"
+    /// The default is DefaultAddress::RELAY_SERVICE
+    #[serde(default)]
+    pub relay_service_name: Option<String>,
+
+    /// Identities allowed to create secure channels with the relay node, and then to
+    /// create and use relays. Any identity is allowed if not set
+    #[serde(default)]
+    pub trusted_identifiers: Option<Vec<Identifier>>,
+
+    /// Authority of the project served by the relay node. When set, the relays can only be
+    /// created and used by the identities presenting a credential issued by that authority
+    #[serde(default)]
+    pub authority: Option<RelayNodeAuthority>,
+
+    /// File where the metrics of the relay node are periodically exported,
+    /// in the Prometheus text format. The metrics are not exported if not set
+    #[serde(default)]
+    pub metrics_path: Option<PathBuf>,
+}
+
+/// Authority trusted by a relay node
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelayNodeAuthority {
+    /// Hex-encoded change history of the authority identity
+    pub identity: String,
+
+    /// Trust context identifier set by the authority in the credentials it issues,
+    /// which is the identifier of its project
+    pub trust_context_id: String,
+}
+
+/// Local and private functions for the relay node configuration
+impl Configuration {
+    /// Return the address for the TCP listener
+    pub(crate) fn tcp_listener_address(&self) -> String {
+        self.tcp_listener_address.clone()
+    }
+
+    /// Return the service name for the secure_channel_listener
+    pub(crate) fn secure_channel_listener_name(&self) -> String {
+        self.secure_channel_listener_name
+            .clone()
+            .unwrap_or(DefaultAddress::SECURE_CHANNEL_LISTENER.into())
+    }
+
+    /// Return the service name for the relay service
+    pub(crate) fn relay_service_name(&self) -> String {
+        self.relay_service_name
+            .clone()
+            .unwrap_or(DefaultAddress::RELAY_SERVICE.into())
+    }
+}
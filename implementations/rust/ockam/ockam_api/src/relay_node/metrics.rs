@@ -0,0 +1,72 @@
+use core::fmt::Write as _;
+use core::time::Duration;
+use std::path::PathBuf;
+
+use ockam::RelayServiceMetrics;
+use tracing::warn;
+
+/// Interval between two exports of the metrics
+const EXPORT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Return the metrics of a relay service in the Prometheus text format
+pub fn relay_metrics_to_prometheus(metrics: &RelayServiceMetrics) -> String {
+    let mut out = String::new();
+    let mut write_metric = |name: &str, kind: &str, help: &str, value: u64| {
+        let _ = writeln!(out, "# HELP {name} {help}");
+        let _ = writeln!(out, "# TYPE {name} {kind}");
+        let _ = writeln!(out, "{name} {value}");
+    };
+    write_metric(
+        "ockam_relay_registrations_total",
+        "counter",
+        "Number of relays registered since the relay node started",
+        metrics.registrations(),
+    );
+    write_metric(
+        "ockam_relay_active_relays",
+        "gauge",
+        "Number of relays currently running",
+        metrics.active_relays(),
+    );
+    write_metric(
+        "ockam_relay_forwarded_messages_total",
+        "counter",
+        "Number of messages forwarded by the relays",
+        metrics.forwarded_messages(),
+    );
+    out
+}
+
+/// Periodically export the metrics of a relay service to a file, which can be collected
+/// with the textfile collector of the Prometheus node exporter
+pub(crate) fn start_metrics_export(metrics: RelayServiceMetrics, path: PathBuf) {
+    tokio::spawn(async move {
+        // the file is replaced at once so that it is never collected partially written
+        let tmp_path = path.with_extension("tmp");
+        loop {
+            let report = relay_metrics_to_prometheus(&metrics);
+            let result = match tokio::fs::write(&tmp_path, report).await {
+                Ok(()) => tokio::fs::rename(&tmp_path, &path).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                warn!("Failed to export the relay metrics to {path:?}: {e}");
+            }
+            tokio::time::sleep(EXPORT_INTERVAL).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relay_metrics_to_prometheus() {
+        let report = relay_metrics_to_prometheus(&RelayServiceMetrics::default());
+        let lines: Vec<&str> = report.lines().collect();
+        assert!(lines.contains(&"# TYPE ockam_relay_active_relays gauge"));
+        assert!(lines.contains(&"ockam_relay_registrations_total 0"));
+        assert!(lines.contains(&"ockam_relay_forwarded_messages_total 0"));
+    }
+}
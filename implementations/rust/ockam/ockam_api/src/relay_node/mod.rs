@@ -0,0 +1,7 @@
+mod configuration;
+mod metrics;
+mod node;
+
+pub use configuration::*;
+pub use metrics::*;
+pub use node::*;
@@ -0,0 +1,244 @@
+use std::path::Path;
+
+use tracing::info;
+
+use ockam::identity::storage::{LmdbStorage, Storage};
+use ockam::identity::{
+    AuthorityService, Identifier, IdentitiesStorage, SecureChannelListenerOptions, SecureChannels,
+    TrustContext, TrustEveryonePolicy, TrustMultiIdentifiersPolicy, Vault,
+};
+use ockam::{RelayService, RelayServiceMetrics, RelayServiceOptions};
+use ockam_abac::expr::{eq, ident, str};
+use ockam_abac::{AbacAccessControl, Env};
+use ockam_core::compat::sync::Arc;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::flow_control::FlowControlId;
+use ockam_core::{Error, Result};
+use ockam_node::Context;
+use ockam_transport_tcp::{TcpListenerOptions, TcpTransport};
+
+use crate::actions;
+use crate::relay_node::{start_metrics_export, Configuration};
+
+const RELAY_NODE_ID: &str = "relay_node";
+const RELAY_NODE_IDENTIFIER_KEY: &str = "identifier";
+
+/// This struct represents a standalone relay node: a node only running a secure channel
+/// listener and a relay service, so that other nodes can register relays on it and be
+/// reached through these relays.
+///
+/// A relay node doesn't use the state of the command line. Its identity and its secrets
+/// are persisted at the paths given in its configuration
+pub struct RelayNode {
+    identifier: Identifier,
+    secure_channels: Arc<SecureChannels>,
+    authority: Option<Identifier>,
+    metrics: RelayServiceMetrics,
+}
+
+/// Public functions to:
+///   - create a relay node
+///   - start its services
+impl RelayNode {
+    /// Return the identity identifier for this relay node
+    pub fn identifier(&self) -> Identifier {
+        self.identifier.clone()
+    }
+
+    /// Return the metrics of the relay service
+    pub fn metrics(&self) -> RelayServiceMetrics {
+        self.metrics.clone()
+    }
+
+    /// Create the identity of the relay node when the node is started for the first time,
+    /// or retrieve it from its storage
+    pub async fn create(configuration: &Configuration) -> Result<RelayNode> {
+        debug!(?configuration, "creating the relay node");
+        create_ockam_directory_if_necessary(&configuration.vault_path)?;
+        let vault = Vault::create_with_persistent_storage_path(&configuration.vault_path).await?;
+
+        create_ockam_directory_if_necessary(&configuration.storage_path)?;
+        let storage: Arc<dyn Storage> =
+            Arc::new(LmdbStorage::new(&configuration.storage_path).await?);
+        let secure_channels = SecureChannels::builder()
+            .with_vault(vault)
+            .with_identities_repository(Arc::new(IdentitiesStorage::new(storage.clone())))
+            .build();
+
+        let identifier = match storage
+            .get(RELAY_NODE_ID, RELAY_NODE_IDENTIFIER_KEY)
+            .await?
+        {
+            Some(identifier) => minicbor::decode(&identifier)?,
+            None => {
+                let identity = secure_channels
+                    .identities()
+                    .identities_creation()
+                    .create_identity()
+                    .await?;
+                storage
+                    .set(
+                        RELAY_NODE_ID,
+                        RELAY_NODE_IDENTIFIER_KEY.to_string(),
+                        minicbor::to_vec(identity.identifier())?,
+                    )
+                    .await?;
+                identity.identifier().clone()
+            }
+        };
+        info!(identifier=%identifier, "retrieved the relay node identifier");
+
+        let authority = match &configuration.authority {
+            Some(authority) => {
+                let identity = hex::decode(&authority.identity)
+                    .map_err(|e| Error::new(Origin::Node, Kind::Invalid, e))?;
+                let authority = secure_channels
+                    .identities()
+                    .identities_creation()
+                    .import(None, &identity)
+                    .await?;
+                info!(authority=%authority.identifier(), "trusting the project authority");
+                Some(authority.identifier().clone())
+            }
+            None => None,
+        };
+
+        Ok(RelayNode {
+            identifier,
+            secure_channels,
+            authority,
+            metrics: RelayServiceMetrics::default(),
+        })
+    }
+
+    /// Start the secure channel listener service, using TCP as a transport
+    /// The TCP listener is connected to the secure channel listener so that it can only
+    /// be used to create secure channels.
+    pub async fn start_secure_channel_listener(
+        &self,
+        ctx: &Context,
+        configuration: &Configuration,
+    ) -> Result<FlowControlId> {
+        let tcp_listener_options = TcpListenerOptions::new();
+        let tcp_listener_flow_control_id = tcp_listener_options.spawner_flow_control_id().clone();
+
+        let options =
+            SecureChannelListenerOptions::new().as_consumer(&tcp_listener_flow_control_id);
+        let options = match &configuration.trusted_identifiers {
+            Some(identifiers) => {
+                options.with_trust_policy(TrustMultiIdentifiersPolicy::new(identifiers.clone()))
+            }
+            None => options.with_trust_policy(TrustEveryonePolicy),
+        };
+        // credentials presented by the other side of the secure channels are verified
+        // with the project authority, and their attributes are stored
+        let options = match (&self.authority, &configuration.authority) {
+            (Some(authority), Some(authority_configuration)) => {
+                options.with_trust_context(TrustContext::new(
+                    authority_configuration.trust_context_id.clone(),
+                    Some(AuthorityService::new(
+                        self.secure_channels.identities().credentials(),
+                        authority.clone(),
+                        None,
+                    )),
+                ))
+            }
+            _ => options,
+        };
+        let secure_channel_listener_flow_control_id = options.spawner_flow_control_id().clone();
+
+        let listener_name = configuration.secure_channel_listener_name();
+        self.secure_channels
+            .create_secure_channel_listener(ctx, &self.identifier(), listener_name.clone(), options)
+            .await?;
+        info!("started a secure channel listener with name '{listener_name}'");
+
+        let tcp = TcpTransport::create(ctx).await?;
+        let listener = tcp
+            .listen(configuration.tcp_listener_address(), tcp_listener_options)
+            .await?;
+
+        info!("started a TCP listener at {listener:?}");
+        Ok(secure_channel_listener_flow_control_id)
+    }
+
+    /// Start the relay service. The relay service and the relays it creates can only be
+    /// reached through the secure channels of the relay node
+    pub async fn start_relay_service(
+        &self,
+        ctx: &Context,
+        secure_channel_flow_control_id: &FlowControlId,
+        configuration: &Configuration,
+    ) -> Result<()> {
+        let name = configuration.relay_service_name();
+        let mut options = RelayServiceOptions::new()
+            .service_as_consumer(secure_channel_flow_control_id)
+            .relay_as_consumer(secure_channel_flow_control_id)
+            .with_metrics(self.metrics.clone());
+        if let Some(authority) = &configuration.authority {
+            let abac = self.create_abac_policy(&name, &authority.trust_context_id);
+            options = options
+                .with_service_incoming_access_control(abac.clone())
+                .with_relays_incoming_access_control(abac);
+        }
+        RelayService::create(ctx, name.clone(), options).await?;
+        info!("started a relay service at '{name}'");
+
+        if let Some(path) = &configuration.metrics_path {
+            start_metrics_export(self.metrics.clone(), path.clone());
+            info!("exporting the relay metrics to {path:?}");
+        }
+        Ok(())
+    }
+}
+
+/// Private relay node functions
+impl RelayNode {
+    /// Return an Abac incoming policy checking that the sender presented a credential
+    /// for the project of the relay node
+    fn create_abac_policy(&self, address: &str, trust_context_id: &str) -> Arc<AbacAccessControl> {
+        let rule = eq([
+            ident("resource.trust_context_id"),
+            ident("subject.trust_context_id"),
+        ]);
+        let mut env = Env::new();
+        env.put("resource.id", str(address));
+        env.put("action.id", str(actions::HANDLE_MESSAGE.as_str()));
+        env.put("resource.trust_context_id", str(trust_context_id));
+        Arc::new(AbacAccessControl::new(
+            self.secure_channels.identities().repository(),
+            rule,
+            env,
+        ))
+    }
+}
+
+/// Start all the necessary services for a relay node
+pub async fn start_relay_node(ctx: &Context, configuration: &Configuration) -> Result<RelayNode> {
+    debug!("starting relay node");
+    let relay_node = RelayNode::create(configuration).await?;
+
+    let secure_channel_flow_control_id = relay_node
+        .start_secure_channel_listener(ctx, configuration)
+        .await?;
+    debug!("secure channel listener started");
+
+    relay_node
+        .start_relay_service(ctx, &secure_channel_flow_control_id, configuration)
+        .await?;
+    debug!("relay service started");
+
+    info!("relay node started");
+    Ok(relay_node)
+}
+
+/// Create a directory to save storage files if they haven't been  created before
+fn create_ockam_directory_if_necessary(path: &Path) -> Result<()> {
+    // a relative path without a directory is stored in the current directory
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() && !parent.exists() => {
+            std::fs::create_dir_all(parent).map_err(|e| Error::new(Origin::Node, Kind::Io, e))
+        }
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,112 @@
+use minicbor::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+/// Version of the [`ResourceList`] schema.
+/// It must be incremented when the fields of the list, or of its items, are renamed or removed
+pub const RESOURCE_LIST_SCHEMA_VERSION: u32 = 1;
+
+/// Common response body for the endpoints and commands listing resources:
+/// nodes, identities, vaults, credentials, inlets, outlets, relays.
+///
+/// The field names are part of the machine-readable output of the command line (`--output json`)
+/// and must stay stable, independently from the way the resources are displayed.
+#[derive(Debug, Clone, Decode, Encode, Serialize, Deserialize, PartialEq, Eq)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct ResourceList<T> {
+    /// Not sent by the nodes created before the introduction of the schema version
+    #[n(2)] pub schema_version: Option<u32>,
+    /// The items keep the index used by the previous list responses, so that older clients
+    /// can still decode them
+    #[n(1)] pub items: Vec<T>,
+}
+
+impl<T> ResourceList<T> {
+    pub fn new(items: Vec<T>) -> Self {
+        Self {
+            schema_version: Some(RESOURCE_LIST_SCHEMA_VERSION),
+            items,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+}
+
+impl<T> From<Vec<T>> for ResourceList<T> {
+    fn from(items: Vec<T>) -> Self {
+        Self::new(items)
+    }
+}
+
+impl<T> IntoIterator for ResourceList<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+impl<T: Serialize> ResourceList<T> {
+    /// Pretty-printed JSON representation of the list
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Decode, Encode, Serialize, Deserialize, PartialEq, Eq)]
+    #[rustfmt::skip]
+    #[cbor(map)]
+    struct Item {
+        #[n(1)] name: String,
+    }
+
+    /// Shape of the list responses before the introduction of `ResourceList`
+    #[derive(Debug, Clone, Decode, Encode)]
+    #[rustfmt::skip]
+    #[cbor(map)]
+    struct LegacyList {
+        #[n(1)] list: Vec<Item>,
+    }
+
+    #[test]
+    fn test_resource_list_json_fields() {
+        let list = ResourceList::new(vec![Item { name: "a".into() }]);
+        let json: serde_json::Value = serde_json::from_str(&list.to_json().unwrap()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "schema_version": RESOURCE_LIST_SCHEMA_VERSION,
+                "items": [{ "name": "a" }]
+            })
+        );
+    }
+
+    #[test]
+    fn test_resource_list_can_be_decoded_as_legacy_list() {
+        let list = ResourceList::new(vec![Item { name: "a".into() }]);
+        let legacy: LegacyList = minicbor::decode(&minicbor::to_vec(&list).unwrap()).unwrap();
+        assert_eq!(legacy.list, list.items);
+    }
+
+    #[test]
+    fn test_legacy_list_can_be_decoded_as_resource_list() {
+        let legacy = LegacyList {
+            list: vec![Item { name: "a".into() }],
+        };
+        let list: ResourceList<Item> =
+            minicbor::decode(&minicbor::to_vec(&legacy).unwrap()).unwrap();
+        assert_eq!(list.schema_version, None);
+        assert_eq!(list.items, legacy.list);
+    }
+}
@@ -0,0 +1,80 @@
+use ockam::identity::Identifier;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Configuration of a self-hosted project: an authority node and a relay node
+/// running in the same process, with enrollment tickets for the project members
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelfHostedProjectConfiguration {
+    /// Name of the project, used as `/project/<name>` by its members
+    pub name: String,
+
+    /// Identifier of the project, set as the trust context identifier in the credentials
+    /// issued by the authority. A random identifier is generated when the project is
+    /// bootstrapped for the first time if not set
+    #[serde(default)]
+    pub project_identifier: Option<String>,
+
+    /// Directory where the identities, secrets and state of the project nodes are persisted
+    pub storage_directory: PathBuf,
+
+    /// Host name or IP address under which the project nodes are reachable by the members
+    pub public_host: String,
+
+    /// listener address for the TCP listener of the authority node, for example "0.0.0.0:4000"
+    pub authority_listener_address: String,
+
+    /// listener address for the TCP listener of the relay node, for example "0.0.0.0:4001"
+    pub relay_listener_address: String,
+
+    /// Identities allowed to issue enrollment tokens with the authority node
+    #[serde(default)]
+    pub enrollers: Vec<Identifier>,
+
+    /// Enrollment tickets to generate for the project members
+    #[serde(default)]
+    pub tickets: Vec<TicketConfiguration>,
+
+    /// File where the metrics of the relay node are exported. They are not exported if not set
+    #[serde(default)]
+    pub relay_metrics_path: Option<PathBuf>,
+}
+
+/// Enrollment ticket to generate when bootstrapping a self-hosted project
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TicketConfiguration {
+    /// Attributes given to the member enrolled with the ticket
+    #[serde(default)]
+    pub attributes: HashMap<String, String>,
+
+    /// Time, in seconds, during which the ticket can be used.
+    /// The default is the maximum duration of the enrollment tokens of the authority
+    #[serde(default)]
+    pub expires_in: Option<u64>,
+}
+
+/// Local and private functions for the self-hosted project configuration
+impl SelfHostedProjectConfiguration {
+    pub(crate) fn state_path(&self) -> PathBuf {
+        self.storage_directory.join("project.json")
+    }
+
+    pub(crate) fn authority_storage_path(&self) -> PathBuf {
+        self.storage_directory
+            .join("authority")
+            .join("storage.lmdb")
+    }
+
+    pub(crate) fn authority_vault_path(&self) -> PathBuf {
+        self.storage_directory.join("authority").join("vault.json")
+    }
+
+    pub(crate) fn relay_storage_path(&self) -> PathBuf {
+        self.storage_directory.join("relay").join("storage.lmdb")
+    }
+
+    pub(crate) fn relay_vault_path(&self) -> PathBuf {
+        self.storage_directory.join("relay").join("vault.json")
+    }
+}
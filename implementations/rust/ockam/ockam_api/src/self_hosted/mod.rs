@@ -0,0 +1,5 @@
+mod configuration;
+mod project;
+
+pub use configuration::*;
+pub use project::*;
@@ -0,0 +1,313 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use ockam::identity::storage::LmdbStorage;
+use ockam::identity::{Identifier, IdentitiesStorage, SecureChannels, Vault};
+use ockam_core::compat::sync::Arc;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Error, Result};
+use ockam_multiaddr::MultiAddr;
+use ockam_node::Context;
+
+use crate::authority_node::{self, Authority, TrustedIdentity};
+use crate::bootstrapped_identities_store::PreTrustedIdentities;
+use crate::config::cli::TrustContextConfig;
+use crate::config::lookup::{ProjectAuthority, ProjectLookup};
+use crate::identity::EnrollmentTicket;
+use crate::relay_node::{self, RelayNode, RelayNodeAuthority};
+use crate::self_hosted::SelfHostedProjectConfiguration;
+use crate::DefaultAddress;
+
+/// State of a self-hosted project, persisted so that the project keeps
+/// the same identifier and the same authority when it is bootstrapped again
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SelfHostedProjectState {
+    project_identifier: String,
+    authority_identifier: Identifier,
+}
+
+/// A project running without the Orchestrator: an authority node issuing credentials to the
+/// project members, and a relay node acting as the project node, where the members can
+/// create relays once they present a credential issued by the authority.
+///
+/// The enrollment tickets generated when bootstrapping the project contain the routes to
+/// both nodes and the trust context of the project, so that they can be used as they are
+/// with `ockam project enroll`
+pub struct SelfHostedProject {
+    authority: Authority,
+    relay_node: RelayNode,
+    project: ProjectLookup,
+    trust_context: TrustContextConfig,
+    tickets: Vec<EnrollmentTicket>,
+}
+
+impl SelfHostedProject {
+    /// Return the authority node of the project
+    pub fn authority(&self) -> &Authority {
+        &self.authority
+    }
+
+    /// Return the relay node of the project
+    pub fn relay_node(&self) -> &RelayNode {
+        &self.relay_node
+    }
+
+    /// Return the description of the project given to its members
+    pub fn project(&self) -> &ProjectLookup {
+        &self.project
+    }
+
+    /// Return the trust context of the project
+    pub fn trust_context(&self) -> &TrustContextConfig {
+        &self.trust_context
+    }
+
+    /// Return the enrollment tickets generated when the project was bootstrapped
+    pub fn tickets(&self) -> &[EnrollmentTicket] {
+        &self.tickets
+    }
+
+    /// Issue a new enrollment ticket for a project member
+    pub async fn create_ticket(
+        &self,
+        attributes: HashMap<String, String>,
+        expires_in: Option<Duration>,
+    ) -> Result<EnrollmentTicket> {
+        let one_time_code = self
+            .authority
+            .issue_enrollment_token(attributes, expires_in)
+            .await?;
+        Ok(EnrollmentTicket::new(
+            one_time_code,
+            Some(self.project.clone()),
+            Some(self.trust_context.clone()),
+        ))
+    }
+}
+
+/// Start an authority node and a relay node for a self-hosted project, and generate
+/// the enrollment tickets of its members.
+///
+/// The identities of the nodes and the project identifier are created the first time
+/// the project is bootstrapped, and retrieved from the storage directory afterwards
+pub async fn bootstrap_project(
+    ctx: &Context,
+    configuration: &SelfHostedProjectConfiguration,
+) -> Result<SelfHostedProject> {
+    debug!(?configuration, "bootstrapping a self-hosted project");
+    let state = load_or_create_state(configuration).await?;
+    let project_identifier = state.project_identifier.clone();
+    let authority_identifier = state.authority_identifier.clone();
+
+    let trusted_identities = configuration
+        .enrollers
+        .iter()
+        .map(|enroller| {
+            let attributes = HashMap::from([("ockam-role".to_string(), "enroller".to_string())]);
+            let entry = TrustedIdentity::new(enroller, &attributes)
+                .attributes_entry(project_identifier.clone(), &authority_identifier);
+            (enroller.clone(), entry)
+        })
+        .collect();
+    let authority_configuration = authority_node::Configuration {
+        identifier: authority_identifier.clone(),
+        storage_path: configuration.authority_storage_path(),
+        vault_path: configuration.authority_vault_path(),
+        postgres_connection: None,
+        project_identifier: project_identifier.clone(),
+        tcp_listener_address: configuration.authority_listener_address.clone(),
+        secure_channel_listener_name: None,
+        authenticator_name: None,
+        trusted_identities: PreTrustedIdentities::new_from_hashmap(trusted_identities),
+        no_direct_authentication: false,
+        no_token_enrollment: false,
+        okta: None,
+        issuer_credential: None,
+        max_in_flight_requests_per_identity: None,
+        max_queued_requests_per_identity: None,
+    };
+    let authority = authority_node::start_node(ctx, &authority_configuration).await?;
+    let authority_identity = authority
+        .secure_channels()
+        .identities()
+        .export_identity(&authority_identifier)
+        .await?;
+
+    let relay_configuration = relay_node::Configuration {
+        storage_path: configuration.relay_storage_path(),
+        vault_path: configuration.relay_vault_path(),
+        tcp_listener_address: configuration.relay_listener_address.clone(),
+        secure_channel_listener_name: None,
+        relay_service_name: None,
+        trusted_identifiers: None,
+        authority: Some(RelayNodeAuthority {
+            identity: hex::encode(&authority_identity),
+            trust_context_id: project_identifier.clone(),
+        }),
+        metrics_path: configuration.relay_metrics_path.clone(),
+    };
+    let relay_node = relay_node::start_relay_node(ctx, &relay_configuration).await?;
+
+    let project = ProjectLookup {
+        node_route: Some(public_route(
+            &configuration.public_host,
+            &configuration.relay_listener_address,
+        )?),
+        id: project_identifier.clone(),
+        name: configuration.name.clone(),
+        identity_id: Some(relay_node.identifier()),
+        authority: Some(ProjectAuthority::new(
+            authority_identifier,
+            public_route(
+                &configuration.public_host,
+                &configuration.authority_listener_address,
+            )?,
+            authority_identity,
+        )),
+        okta: None,
+    };
+    let trust_context = TrustContextConfig::try_from(project.clone())
+        .map_err(|e| Error::new(Origin::Node, Kind::Invalid, e))?;
+
+    let mut self_hosted_project = SelfHostedProject {
+        authority,
+        relay_node,
+        project,
+        trust_context,
+        tickets: vec![],
+    };
+    for ticket in &configuration.tickets {
+        let ticket = self_hosted_project
+            .create_ticket(
+                ticket.attributes.clone(),
+                ticket.expires_in.map(Duration::from_secs),
+            )
+            .await?;
+        self_hosted_project.tickets.push(ticket);
+    }
+
+    info!(
+        project = %configuration.name,
+        id = %project_identifier,
+        "self-hosted project started"
+    );
+    Ok(self_hosted_project)
+}
+
+/// Return the state of a project bootstrapped before, or create the project identifier
+/// and the authority identity if the project is bootstrapped for the first time
+async fn load_or_create_state(
+    configuration: &SelfHostedProjectConfiguration,
+) -> Result<SelfHostedProjectState> {
+    let state_path = configuration.state_path();
+    if state_path.exists() {
+        let contents = std::fs::read_to_string(&state_path)
+            .map_err(|e| Error::new(Origin::Node, Kind::Io, e))?;
+        let state: SelfHostedProjectState = serde_json::from_str(&contents)
+            .map_err(|e| Error::new(Origin::Node, Kind::Invalid, e))?;
+        if let Some(project_identifier) = &configuration.project_identifier {
+            if *project_identifier != state.project_identifier {
+                return Err(Error::new(
+                    Origin::Node,
+                    Kind::Conflict,
+                    format!(
+                        "the project was bootstrapped with the identifier {}",
+                        state.project_identifier
+                    ),
+                ));
+            }
+        }
+        return Ok(state);
+    }
+
+    // the authority identity is created in the vault and the storage used by the authority node
+    create_directory_if_necessary(&configuration.authority_vault_path())?;
+    create_directory_if_necessary(&configuration.authority_storage_path())?;
+    let authority_identifier = {
+        let vault =
+            Vault::create_with_persistent_storage_path(&configuration.authority_vault_path())
+                .await?;
+        let storage = LmdbStorage::new(configuration.authority_storage_path()).await?;
+        let secure_channels = SecureChannels::builder()
+            .with_vault(vault)
+            .with_identities_repository(Arc::new(IdentitiesStorage::new(Arc::new(storage))))
+            .build();
+        secure_channels
+            .identities()
+            .identities_creation()
+            .create_identity()
+            .await?
+            .identifier()
+            .clone()
+    };
+
+    let state = SelfHostedProjectState {
+        project_identifier: configuration
+            .project_identifier
+            .clone()
+            .unwrap_or_else(|| hex::encode(rand::random::<[u8; 16]>())),
+        authority_identifier,
+    };
+    let contents =
+        serde_json::to_string_pretty(&state).map_err(|e| Error::new(Origin::Node, Kind::Io, e))?;
+    std::fs::write(&state_path, contents).map_err(|e| Error::new(Origin::Node, Kind::Io, e))?;
+    info!(id = %state.project_identifier, "created a self-hosted project");
+    Ok(state)
+}
+
+/// Return the route to the secure channel listener of a project node, as seen by the members
+fn public_route(public_host: &str, listener_address: &str) -> Result<MultiAddr> {
+    let port = SocketAddr::from_str(listener_address)
+        .map_err(|e| Error::new(Origin::Node, Kind::Invalid, e))?
+        .port();
+    MultiAddr::from_str(&format!(
+        "/dnsaddr/{public_host}/tcp/{port}/service/{}",
+        DefaultAddress::SECURE_CHANNEL_LISTENER
+    ))
+    .map_err(|e| Error::new(Origin::Node, Kind::Invalid, e))
+}
+
+/// Create a directory to save storage files if they haven't been  created before
+fn create_directory_if_necessary(path: &Path) -> Result<()> {
+    // a relative path without a directory is stored in the current directory
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() && !parent.exists() => {
+            std::fs::create_dir_all(parent).map_err(|e| Error::new(Origin::Node, Kind::Io, e))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_public_route() -> Result<()> {
+        assert_eq!(
+            public_route("ockam.example.com", "0.0.0.0:4000")?.to_string(),
+            "/dnsaddr/ockam.example.com/tcp/4000/service/api"
+        );
+        assert!(public_route("ockam.example.com", "4000").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_directory_if_necessary() -> Result<()> {
+        // paths without a parent directory must not make the bootstrap panic
+        create_directory_if_necessary(Path::new("/"))?;
+        create_directory_if_necessary(Path::new("project.json"))?;
+
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("authority").join("vault.json");
+        create_directory_if_necessary(&path)?;
+        assert!(directory.path().join("authority").exists());
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+use minicbor::{Decode, Encode};
+use ockam::{Any, Context, Result, Routed, Worker};
+use ockam_core::NeutralMessage;
+
+/// Tag identifying the trace probes, so that the other messages are forwarded untouched
+const TRACE_PROBE_TAG: &str = "ockam_trace_probe";
+
+/// Probe sent along a route, where each tracer appends the time at which it received it
+#[derive(Debug, Clone, Default, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct TraceProbe {
+    #[n(1)] tag: String,
+    #[n(2)] pub hops: Vec<TraceHop>,
+}
+
+impl TraceProbe {
+    pub fn new() -> Self {
+        Self {
+            tag: TRACE_PROBE_TAG.to_string(),
+            hops: vec![],
+        }
+    }
+
+    /// Decode a probe, return `None` if the payload is not a probe
+    pub fn decode(payload: &[u8]) -> Option<Self> {
+        minicbor::decode::<TraceProbe>(payload)
+            .ok()
+            .filter(|probe| probe.tag == TRACE_PROBE_TAG)
+    }
+}
+
+/// Time at which a tracer received a probe
+#[derive(Debug, Clone, Decode, Encode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct TraceHop {
+    /// Name of the node of the tracer
+    #[n(1)] pub node: String,
+    /// Time, in microseconds since the Unix epoch, measured with the clock of the node
+    #[n(2)] pub timestamp: u64,
+}
+
+/// Time, in microseconds since the Unix epoch
+pub fn now_micros() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_micros() as u64)
+        .unwrap_or_default()
+}
+
+/// Worker recording the time at which trace probes reach a node.
+///
+/// A tracer is placed at the end of a route going through a secure channel: it adds the
+/// time at which it received a probe and sends the probe back along its return route.
+/// The messages which are not probes, or which should be forwarded further, are dropped
+pub struct Tracer {
+    node_name: String,
+}
+
+impl Tracer {
+    pub fn new(node_name: impl Into<String>) -> Self {
+        Self {
+            node_name: node_name.into(),
+        }
+    }
+}
+
+#[ockam::worker]
+impl Worker for Tracer {
+    type Context = Context;
+    type Message = Any;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<Any>) -> Result<()> {
+        let mut probe = match TraceProbe::decode(msg.payload()) {
+            Some(probe) => probe,
+            None => {
+                warn!(from = %msg.return_route(), "dropping a message which is not a trace probe");
+                return Ok(());
+            }
+        };
+        if msg.onward_route().len() > 1 {
+            warn!(to = %msg.onward_route(), "dropping a trace probe which is not for this tracer");
+            return Ok(());
+        }
+        probe.hops.push(TraceHop {
+            node: self.node_name.clone(),
+            timestamp: now_micros(),
+        });
+        debug!(to = %msg.return_route(), "sending the trace probe back");
+        ctx.send(
+            msg.return_route(),
+            NeutralMessage::from(minicbor::to_vec(&probe)?),
+        )
+        .await
+    }
+}
@@ -2,6 +2,8 @@ use miette::miette;
 use std::net::{SocketAddrV4, SocketAddrV6};
 
 use ockam::TcpTransport;
+use ockam_abac::ExplainMode;
+use ockam_core::env::{get_env, get_env_with_default};
 use ockam_core::errcode::{Kind, Origin};
 use ockam_core::flow_control::FlowControlId;
 use ockam_core::{Address, Error, Result, Route, TransportType, LOCAL};
@@ -9,6 +11,7 @@ use ockam_multiaddr::proto::{
     DnsAddr, Ip4, Ip6, Node, Project, Secure, Service, Space, Tcp, Worker,
 };
 use ockam_multiaddr::{Code, MultiAddr, Protocol};
+use ockam_node::MailboxOptions;
 use ockam_transport_tcp::{TcpConnection, TcpConnectionOptions, TCP};
 
 use crate::error::ApiError;
@@ -84,6 +87,25 @@ pub async fn multiaddr_to_route(
     ma: &MultiAddr,
     tcp: &TcpTransport,
 ) -> Option<MultiAddrToRouteResult> {
+    multiaddr_to_route_with_sharing(ma, tcp, false).await
+}
+
+/// Same as [`multiaddr_to_route`], but the TCP connection is shared with the other
+/// channels and portals to the same peer when `shared` is true, see [`TcpConnectionOptions::shared`].
+/// Each user of a shared connection still gets its own sender address and flow control id
+pub async fn multiaddr_to_route_with_sharing(
+    ma: &MultiAddr,
+    tcp: &TcpTransport,
+    shared: bool,
+) -> Option<MultiAddrToRouteResult> {
+    let connection_options = || {
+        let options = TcpConnectionOptions::new();
+        if shared {
+            options.shared()
+        } else {
+            options
+        }
+    };
     let mut rb = Route::new();
     let mut it = ma.iter().peekable();
 
@@ -102,8 +124,7 @@ pub async fn multiaddr_to_route(
                 let port = it.next()?.cast::<Tcp>()?;
                 let socket_addr = SocketAddrV4::new(*ip4, *port);
 
-                let options = TcpConnectionOptions::new();
-                flow_control_id = Some(options.flow_control_id().clone());
+                let options = connection_options();
 
                 let connection = match tcp.connect(socket_addr.to_string(), options).await {
                     Ok(c) => c,
@@ -114,6 +135,7 @@ pub async fn multiaddr_to_route(
                 };
 
                 number_of_tcp_hops += 1;
+                flow_control_id = Some(connection.flow_control_id().clone());
                 rb = rb.append(connection.sender_address().clone());
 
                 tcp_connection = Some(connection);
@@ -127,8 +149,7 @@ pub async fn multiaddr_to_route(
                 let port = it.next()?.cast::<Tcp>()?;
                 let socket_addr = SocketAddrV6::new(*ip6, *port, 0, 0);
 
-                let options = TcpConnectionOptions::new();
-                flow_control_id = Some(options.flow_control_id().clone());
+                let options = connection_options();
 
                 let connection = match tcp.connect(socket_addr.to_string(), options).await {
                     Ok(c) => c,
@@ -139,6 +160,7 @@ pub async fn multiaddr_to_route(
                 };
 
                 number_of_tcp_hops += 1;
+                flow_control_id = Some(connection.flow_control_id().clone());
                 rb = rb.append(connection.sender_address().clone());
 
                 tcp_connection = Some(connection);
@@ -153,8 +175,7 @@ pub async fn multiaddr_to_route(
                     if p.code() == Tcp::CODE {
                         let port = p.cast::<Tcp>()?;
 
-                        let options = TcpConnectionOptions::new();
-                        flow_control_id = Some(options.flow_control_id().clone());
+                        let options = connection_options();
                         let peer = format!("{}:{}", &*host, *port);
 
                         let connection = match tcp.connect(&peer, options).await {
@@ -166,6 +187,7 @@ pub async fn multiaddr_to_route(
                         };
 
                         number_of_tcp_hops += 1;
+                        flow_control_id = Some(connection.flow_control_id().clone());
                         rb = rb.append(connection.sender_address().clone());
 
                         tcp_connection = Some(connection);
@@ -281,11 +303,7 @@ pub fn try_multiaddr_to_addr(ma: &MultiAddr) -> Result<Address, Error> {
 
 /// Try to convert an Ockam Route into a MultiAddr.
 pub fn route_to_multiaddr(r: &Route) -> Option<MultiAddr> {
-    let mut ma = MultiAddr::default();
-    for a in r.iter() {
-        ma.try_extend(&try_address_to_multiaddr(a).ok()?).ok()?
-    }
-    Some(ma)
+    MultiAddr::from_route(r).ok()
 }
 
 /// Try to convert an Ockam Address to a MultiAddr.
@@ -371,6 +389,36 @@ pub fn local_worker(code: &Code) -> Result<bool> {
     }
 }
 
+/// Return the options of the mailboxes of some workers, as configured by two environment
+/// variables for their capacity and their overflow policy. An invalid value is ignored
+pub(crate) fn mailbox_options_from_env(capacity_var: &str, overflow_var: &str) -> MailboxOptions {
+    let default = MailboxOptions::default();
+    let capacity =
+        get_env_with_default(capacity_var, default.capacity() as u64).unwrap_or_else(|e| {
+            warn!(%e, "invalid {capacity_var}, using the default mailbox capacity");
+            default.capacity() as u64
+        });
+    let overflow = get_env_with_default(overflow_var, default.overflow()).unwrap_or_else(|e| {
+        warn!(%e, "invalid {overflow_var}, using the default overflow policy");
+        default.overflow()
+    });
+    MailboxOptions::new(capacity as usize, overflow)
+}
+
+/// Environment variable setting how the policies of a node explain the accesses they deny
+const OCKAM_POLICY_EXPLAIN: &str = "OCKAM_POLICY_EXPLAIN";
+
+/// Return the explain mode of the policies, as configured with `OCKAM_POLICY_EXPLAIN`.
+/// Denials are not explained when it is not set or invalid
+pub(crate) fn policy_explain_mode() -> ExplainMode {
+    get_env::<ExplainMode>(OCKAM_POLICY_EXPLAIN)
+        .unwrap_or_else(|e| {
+            warn!(%e, "invalid {OCKAM_POLICY_EXPLAIN}, denials are not explained");
+            None
+        })
+        .unwrap_or_default()
+}
+
 #[cfg(test)]
 pub mod test_utils {
     use ockam::identity::storage::InMemoryStorage;
@@ -526,3 +574,42 @@ pub mod test_utils {
         Ok(identity)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam_node::Context;
+    use ockam_transport_tcp::TcpListenerOptions;
+
+    #[ockam_macros::test]
+    async fn test_multiaddr_to_route_shares_connections_only_when_asked(
+        ctx: &mut Context,
+    ) -> Result<()> {
+        let tcp = TcpTransport::create(ctx).await?;
+        let listener = tcp.listen("127.0.0.1:0", TcpListenerOptions::new()).await?;
+        let addr = MultiAddr::try_from(
+            format!(
+                "/ip4/127.0.0.1/tcp/{}/service/api",
+                listener.socket_address().port()
+            )
+            .as_str(),
+        )?;
+
+        let connection =
+            |result: Option<MultiAddrToRouteResult>| result.and_then(|r| r.tcp_connection).unwrap();
+
+        // connections are not shared by default
+        let first = connection(multiaddr_to_route(&addr, &tcp).await);
+        let second = connection(multiaddr_to_route(&addr, &tcp).await);
+        assert_ne!(first.receiver_address(), second.receiver_address());
+
+        // a shared connection is used through a separate stream, with its own flow control
+        let first = connection(multiaddr_to_route_with_sharing(&addr, &tcp, true).await);
+        let second = connection(multiaddr_to_route_with_sharing(&addr, &tcp, true).await);
+        assert_eq!(first.receiver_address(), second.receiver_address());
+        assert_ne!(first.sender_address(), second.sender_address());
+        assert_ne!(first.flow_control_id(), second.flow_control_id());
+
+        ctx.stop().await
+    }
+}
@@ -340,6 +340,7 @@ async fn default_configuration() -> Result<Configuration> {
         identifier: "I4dba4b2e53b2ed95967b3bab350b6c9ad9c624e5".try_into()?,
         storage_path,
         vault_path,
+        postgres_connection: None,
         project_identifier: "123456".to_string(),
         tcp_listener_address: format!("127.0.0.1:{}", port),
         secure_channel_listener_name: None,
@@ -348,6 +349,9 @@ async fn default_configuration() -> Result<Configuration> {
         no_direct_authentication: true,
         no_token_enrollment: true,
         okta: None,
+        issuer_credential: None,
+        max_in_flight_requests_per_identity: None,
+        max_queued_requests_per_identity: None,
     };
 
     // Hack to create Authority Identity using the same vault and storage
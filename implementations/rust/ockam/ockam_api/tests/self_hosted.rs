@@ -0,0 +1,130 @@
+use minicbor::bytes::ByteSlice;
+use ockam::identity::{secure_channels, SecureChannelOptions, TrustIdentifierPolicy};
+use ockam::remote::{RemoteRelay, RemoteRelayOptions};
+use ockam::route;
+use ockam_api::enroll::enrollment::Enrollment;
+use ockam_api::nodes::NodeManager;
+use ockam_api::self_hosted::{
+    bootstrap_project, SelfHostedProjectConfiguration, TicketConfiguration,
+};
+use ockam_core::Result;
+use ockam_node::Context;
+use ockam_transport_tcp::{TcpConnectionOptions, TcpTransport};
+use rand::{thread_rng, Rng};
+use std::collections::HashMap;
+
+/// Bootstrap a self-hosted project, enroll a member with one of its tickets, then
+/// create a relay on the relay node of the project with the member credential
+#[ockam_macros::test]
+async fn bootstrap_and_enroll_member(ctx: &mut Context) -> Result<()> {
+    let storage_directory = tempfile::tempdir().unwrap();
+    let authority_port = thread_rng().gen_range(10000..37500);
+    let relay_port = thread_rng().gen_range(37500..65535);
+    let configuration = SelfHostedProjectConfiguration {
+        name: "default".to_string(),
+        project_identifier: Some("project42".to_string()),
+        storage_directory: storage_directory.path().to_path_buf(),
+        public_host: "localhost".to_string(),
+        authority_listener_address: format!("127.0.0.1:{authority_port}"),
+        relay_listener_address: format!("127.0.0.1:{relay_port}"),
+        enrollers: vec![],
+        tickets: vec![TicketConfiguration {
+            attributes: HashMap::from([("role".to_string(), "member".to_string())]),
+            expires_in: None,
+        }],
+        relay_metrics_path: None,
+    };
+    let project = bootstrap_project(ctx, &configuration).await?;
+    assert_eq!(project.project().id, "project42");
+    assert_eq!(project.tickets().len(), 1);
+
+    // the member only uses what is given in the ticket to enroll
+    let ticket = project.tickets()[0].clone();
+    let project_lookup = ticket.project.clone().unwrap();
+    let project_authority = project_lookup.authority.clone().unwrap();
+    assert_eq!(
+        project_authority.identity_id(),
+        &project.authority().identifier()
+    );
+
+    let secure_channels = secure_channels();
+    let member = secure_channels
+        .identities()
+        .identities_creation()
+        .create_identity()
+        .await?
+        .identifier()
+        .clone();
+    let tcp = TcpTransport::create(ctx).await?;
+    let authority_node = NodeManager::authority_node(
+        &tcp,
+        secure_channels.clone(),
+        project_authority.identity_id(),
+        project_authority.address(),
+        &member,
+    )
+    .await?;
+    authority_node
+        .present_token(ctx, &ticket.one_time_code)
+        .await
+        .unwrap();
+    let credential = authority_node.issue_credential(ctx).await.unwrap();
+
+    // the credential is issued by the project authority, with the ticket attributes
+    secure_channels
+        .identities()
+        .identities_creation()
+        .import(
+            Some(project_authority.identity_id()),
+            project_authority.identity(),
+        )
+        .await?;
+    let data = secure_channels
+        .identities()
+        .credentials()
+        .credentials_verification()
+        .verify_credential(
+            Some(&member),
+            &[project_authority.identity_id().clone()],
+            &credential,
+        )
+        .await?;
+    let attributes = data.credential_data.subject_attributes.map;
+    assert_eq!(
+        Some(&b"project42".to_vec().into()),
+        attributes.get::<ByteSlice>(b"trust_context_id".as_slice().into())
+    );
+    assert_eq!(
+        Some(&b"member".to_vec().into()),
+        attributes.get::<ByteSlice>(b"role".as_slice().into())
+    );
+
+    // the credential lets the member create a relay on the relay node
+    let connection = tcp
+        .connect(
+            format!("localhost:{relay_port}"),
+            TcpConnectionOptions::new(),
+        )
+        .await?;
+    let channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            &member,
+            route![connection, "api"],
+            SecureChannelOptions::new()
+                .with_trust_policy(TrustIdentifierPolicy::new(
+                    project.relay_node().identifier(),
+                ))
+                .with_credential(credential),
+        )
+        .await?;
+    let relay = RemoteRelay::create(
+        ctx,
+        route![channel.encryptor_address().clone()],
+        RemoteRelayOptions::new(),
+    )
+    .await?;
+    assert!(!relay.remote_address().is_empty());
+
+    ctx.stop().await
+}
@@ -5,7 +5,6 @@ use std::str::FromStr;
 use std::sync::Arc;
 use tracing::{debug, info, trace, warn};
 
-use ockam_api::address::get_free_address;
 use ockam_api::cli_state::{CliState, StateDirTrait};
 use ockam_api::cloud::project::Project;
 use ockam_api::cloud::share::InvitationListKind;
@@ -83,7 +82,7 @@ impl AppState {
         let outlets = node_manager.list_outlets().await;
 
         let outlet_socket_addr = outlets
-            .list
+            .items
             .iter()
             .find(|o| o.alias == alias)
             .map(|o| o.socket_addr.to_string());
@@ -304,7 +303,11 @@ impl AppState {
         }
         let from = match socket_addr {
             Some(socket_addr) => *socket_addr,
-            None => get_free_address()?,
+            None => self
+                .state()
+                .await
+                .nodes
+                .allocate_port(local_node_name, &format!("tcp-inlet:{service_name}"))?,
         };
         if let Some(enrollment_ticket_hex) = enrollment_ticket_hex {
             background_node_client
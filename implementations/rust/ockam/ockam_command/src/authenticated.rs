@@ -1,6 +1,6 @@
-use crate::node::get_node_name;
-use crate::util::node_rpc;
+use crate::node::{get_node_name, NodeOpts};
 use crate::util::parsers::identity_identifier_parser;
+use crate::util::{node_rpc, parse_node_name};
 use crate::Result;
 use crate::{docs, CommandGlobalOpts};
 use clap::{Args, Subcommand};
@@ -11,7 +11,9 @@ use ockam::Context;
 use ockam_api::address::extract_address_value;
 use ockam_api::auth::AuthorizationApi;
 use ockam_api::is_local_node;
+use ockam_api::nodes::models::attributes::FetchAttributes;
 use ockam_api::nodes::BackgroundNode;
+use ockam_core::api::Request;
 use ockam_multiaddr::MultiAddr;
 use termimad::{minimad::TextTemplate, MadSkin};
 
@@ -53,6 +55,24 @@ pub enum AuthenticatedSubcommand {
         /// Address to connect to.
         addr: MultiAddr,
     },
+
+    /// Fetch the attributes of an identity from the authenticated service of the authority
+    /// of a node, and store them on that node
+    Fetch {
+        /// Address of the authenticated service of the authority
+        addr: MultiAddr,
+
+        /// Subject identifier
+        #[arg(long, value_name = "IDENTIFIER", value_parser = identity_identifier_parser)]
+        id: Identifier,
+
+        /// Maximum time to wait for the other node, in seconds
+        #[arg(long, value_name = "SECONDS")]
+        timeout: Option<u64>,
+
+        #[command(flatten)]
+        node_opts: NodeOpts,
+    },
 }
 
 impl AuthenticatedCommand {
@@ -80,6 +100,21 @@ async fn run_impl(
             let entries = node.list_identifiers(&ctx).await?;
             print_entries(&entries);
         }
+        AuthenticatedSubcommand::Fetch {
+            addr,
+            id,
+            timeout,
+            node_opts,
+        } => {
+            let at = get_node_name(&opts.state, &node_opts.at_node);
+            let node_name = parse_node_name(&at)?;
+            let node = BackgroundNode::create(&ctx, &opts.state, &node_name).await?;
+            let request = FetchAttributes::new(addr.clone(), id.clone()).with_timeout(*timeout);
+            let entry: AttributesEntry = node
+                .ask(&ctx, Request::post("/node/attributes/fetch").body(request))
+                .await?;
+            print_entries(&[(id.clone(), entry)]);
+        }
     }
     Ok(())
 }
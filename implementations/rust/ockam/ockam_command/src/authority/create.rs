@@ -76,6 +76,32 @@ pub struct CreateCommand {
     #[arg(group = "trusted", long, value_name = "PATH")]
     reload_from_trusted_identities_file: Option<PathBuf>,
 
+    /// Connection string of a Postgres database storing the members and enrollment tokens.
+    /// Several authority nodes of the same project can share that database, one of them
+    /// being elected as the leader to modify members and issue enrollment tokens.
+    /// The connection uses TLS, verified with the root certificates of the system, unless
+    /// the connection string contains "sslmode=disable". Use "sslmode=require" to refuse
+    /// servers which don't support TLS.
+    /// Example: "host=localhost user=ockam dbname=authority sslmode=require"
+    #[arg(long, value_name = "CONNECTION_STRING", default_value = None)]
+    postgres_connection: Option<String>,
+
+    /// Hex-encoded credential issued to this authority by another authority.
+    /// The attributes of that credential prefixed with "ockam-issuer:" define which
+    /// attributes this authority is allowed to attest, as a sub-authority
+    #[arg(long, value_name = "HEX_CREDENTIAL", default_value = None)]
+    issuer_credential: Option<String>,
+
+    /// Maximum number of requests of the same identity handled at the same time by the
+    /// credential issuer and the enrollment services
+    #[arg(long, value_name = "NUMBER", default_value = None)]
+    max_in_flight_requests_per_identity: Option<usize>,
+
+    /// Maximum number of requests of the same identity waiting to be handled by the
+    /// credential issuer and the enrollment services. Further requests are rejected
+    #[arg(long, value_name = "NUMBER", default_value = None)]
+    max_queued_requests_per_identity: Option<usize>,
+
     /// Okta: URL used for accessing the Okta API
     #[arg(long, value_name = "URL", default_value = None)]
     tenant_base_url: Option<String>,
@@ -145,6 +171,26 @@ async fn spawn_background_node(
         args.push("--no-token-enrollment".to_string());
     }
 
+    if let Some(postgres_connection) = &cmd.postgres_connection {
+        args.push("--postgres-connection".to_string());
+        args.push(postgres_connection.clone());
+    }
+
+    if let Some(issuer_credential) = &cmd.issuer_credential {
+        args.push("--issuer-credential".to_string());
+        args.push(issuer_credential.clone());
+    }
+
+    if let Some(max_in_flight) = cmd.max_in_flight_requests_per_identity {
+        args.push("--max-in-flight-requests-per-identity".to_string());
+        args.push(max_in_flight.to_string());
+    }
+
+    if let Some(max_queued) = cmd.max_queued_requests_per_identity {
+        args.push("--max-queued-requests-per-identity".to_string());
+        args.push(max_queued.to_string());
+    }
+
     if let Some(trusted_identities) = &cmd.trusted_identities {
         args.push("--trusted-identities".to_string());
         args.push(trusted_identities.to_string());
@@ -334,6 +380,7 @@ async fn start_authority_node(
         identifier,
         storage_path: opts.state.identities.identities_repository_path()?,
         vault_path: opts.state.vaults.default()?.vault_file_path().clone(),
+        postgres_connection: cmd.postgres_connection,
         project_identifier: cmd.project_identifier,
         tcp_listener_address: cmd.tcp_listener_address,
         secure_channel_listener_name: None,
@@ -342,6 +389,9 @@ async fn start_authority_node(
         no_direct_authentication: cmd.no_direct_authentication,
         no_token_enrollment: cmd.no_token_enrollment,
         okta: okta_configuration,
+        issuer_credential: cmd.issuer_credential,
+        max_in_flight_requests_per_identity: cmd.max_in_flight_requests_per_identity,
+        max_queued_requests_per_identity: cmd.max_queued_requests_per_identity,
     };
     authority_node::start_node(&ctx, &configuration)
         .await
@@ -1,8 +1,10 @@
 use clap::{arg, Args};
 
 use colorful::Colorful;
+use miette::IntoDiagnostic;
 use ockam::Context;
 use ockam_api::cli_state::StateDirTrait;
+use ockam_api::resource_list::ResourceList;
 
 use crate::{
     fmt_log, terminal::OckamColor, util::node_rpc, vault::default_vault_name, CommandGlobalOpts,
@@ -50,7 +52,9 @@ async fn run_impl(
         ),
     )?;
 
-    opts.terminal.stdout().plain(list).write_line()?;
+    let json = ResourceList::new(credentials).to_json().into_diagnostic()?;
+
+    opts.terminal.stdout().plain(list).json(json).write_line()?;
 
     Ok(())
 }
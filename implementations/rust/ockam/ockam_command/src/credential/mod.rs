@@ -93,6 +93,7 @@ pub async fn validate_encoded_cred(
     Ok(())
 }
 
+#[derive(serde::Serialize)]
 pub struct CredentialOutput {
     name: String,
     credential: String,
@@ -0,0 +1,37 @@
+use clap::Args;
+
+use ockam::Context;
+
+use crate::feature::update_feature;
+use crate::node::{initialize_node_if_default, NodeOpts};
+use crate::util::node_rpc;
+use crate::{docs, CommandGlobalOpts};
+
+const LONG_ABOUT: &str = include_str!("./static/disable/long_about.txt");
+const AFTER_LONG_HELP: &str = include_str!("./static/disable/after_long_help.txt");
+
+/// Disable a feature on a node
+#[derive(Clone, Debug, Args)]
+#[command(
+    arg_required_else_help = true,
+    long_about = docs::about(LONG_ABOUT),
+    after_long_help = docs::after_help(AFTER_LONG_HELP),
+)]
+pub struct DisableCommand {
+    /// Name of the feature
+    name: String,
+
+    #[command(flatten)]
+    node_opts: NodeOpts,
+}
+
+impl DisableCommand {
+    pub fn run(self, opts: CommandGlobalOpts) {
+        initialize_node_if_default(&opts, &self.node_opts.at_node);
+        node_rpc(rpc, (opts, self));
+    }
+}
+
+async fn rpc(ctx: Context, (opts, cmd): (CommandGlobalOpts, DisableCommand)) -> miette::Result<()> {
+    update_feature(&ctx, &opts, &cmd.node_opts, &cmd.name, Some(false)).await
+}
@@ -0,0 +1,101 @@
+use clap::Args;
+use colorful::Colorful;
+use miette::{miette, IntoDiagnostic};
+use tokio::sync::Mutex;
+use tokio::try_join;
+
+use ockam::Context;
+use ockam_api::cli_state::StateDirTrait;
+use ockam_api::nodes::models::features::{FeatureList, FeatureStatus};
+use ockam_api::nodes::BackgroundNode;
+use ockam_core::api::Request;
+
+use crate::node::{get_node_name, initialize_node_if_default, NodeOpts};
+use crate::output::Output;
+use crate::terminal::OckamColor;
+use crate::util::{node_rpc, parse_node_name};
+use crate::{docs, CommandGlobalOpts};
+
+const LONG_ABOUT: &str = include_str!("./static/list/long_about.txt");
+const AFTER_LONG_HELP: &str = include_str!("./static/list/after_long_help.txt");
+
+/// List the features of a node
+#[derive(Clone, Debug, Args)]
+#[command(
+    long_about = docs::about(LONG_ABOUT),
+    after_long_help = docs::after_help(AFTER_LONG_HELP),
+)]
+pub struct ListCommand {
+    #[command(flatten)]
+    node_opts: NodeOpts,
+}
+
+impl ListCommand {
+    pub fn run(self, opts: CommandGlobalOpts) {
+        initialize_node_if_default(&opts, &self.node_opts.at_node);
+        node_rpc(rpc, (opts, self));
+    }
+}
+
+async fn rpc(ctx: Context, (opts, cmd): (CommandGlobalOpts, ListCommand)) -> miette::Result<()> {
+    run_impl(&ctx, opts, cmd).await
+}
+
+async fn run_impl(ctx: &Context, opts: CommandGlobalOpts, cmd: ListCommand) -> miette::Result<()> {
+    let at = get_node_name(&opts.state, &cmd.node_opts.at_node);
+    let node_name = parse_node_name(&at)?;
+
+    if !opts.state.nodes.get(&node_name)?.is_running() {
+        return Err(miette!("The node '{}' is not running", node_name));
+    }
+
+    let node = BackgroundNode::create(ctx, &opts.state, &node_name).await?;
+    let is_finished: Mutex<bool> = Mutex::new(false);
+
+    let get_features = async {
+        let features: FeatureList = node.ask(ctx, Request::get("/node/features")).await?;
+        *is_finished.lock().await = true;
+        Ok(features)
+    };
+
+    let output_messages = vec![format!(
+        "Listing features on {}...\n",
+        node_name
+            .to_string()
+            .color(OckamColor::PrimaryResource.color())
+    )];
+
+    let progress_output = opts
+        .terminal
+        .progress_output(&output_messages, &is_finished);
+
+    let (features, _) = try_join!(get_features, progress_output)?;
+
+    let list = opts.terminal.build_list(
+        &features.items,
+        &format!("Features on {node_name}"),
+        &format!("No features found on {node_name}."),
+    )?;
+    let json = serde_json::to_string_pretty(&features).into_diagnostic()?;
+    opts.terminal.stdout().plain(list).json(json).write_line()?;
+
+    Ok(())
+}
+
+impl Output for FeatureStatus {
+    fn output(&self) -> crate::Result<String> {
+        let state = match (self.enabled, self.is_set) {
+            (true, true) => "enabled",
+            (false, true) => "disabled",
+            (true, false) => "enabled by default",
+            (false, false) => "disabled by default",
+        };
+        Ok(format!(
+            "Feature {} is {state}\n{}",
+            self.name
+                .to_string()
+                .color(OckamColor::PrimaryResource.color()),
+            self.description
+        ))
+    }
+}
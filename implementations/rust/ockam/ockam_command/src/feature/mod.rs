@@ -0,0 +1,107 @@
+mod disable;
+mod enable;
+mod list;
+mod reset;
+
+pub(crate) use disable::DisableCommand;
+pub(crate) use enable::EnableCommand;
+pub(crate) use list::ListCommand;
+pub(crate) use reset::ResetCommand;
+
+use clap::{Args, Subcommand};
+use colorful::Colorful;
+
+use ockam::Context;
+use ockam_api::nodes::models::features::{FeatureStatus, SetFeature};
+use ockam_api::nodes::BackgroundNode;
+use ockam_core::api::Request;
+
+use crate::node::{get_node_name, NodeOpts};
+use crate::terminal::OckamColor;
+use crate::util::parse_node_name;
+use crate::{docs, fmt_ok, CommandGlobalOpts};
+
+const LONG_ABOUT: &str = include_str!("./static/long_about.txt");
+
+/// Manage the experimental features of a node
+#[derive(Clone, Debug, Args)]
+#[command(
+    arg_required_else_help = true,
+    subcommand_required = true,
+    long_about = docs::about(LONG_ABOUT),
+)]
+pub struct FeatureCommand {
+    #[command(subcommand)]
+    subcommand: FeatureSubcommand,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum FeatureSubcommand {
+    #[command(display_order = 800)]
+    List(ListCommand),
+    #[command(display_order = 801)]
+    Enable(EnableCommand),
+    #[command(display_order = 802)]
+    Disable(DisableCommand),
+    #[command(display_order = 803)]
+    Reset(ResetCommand),
+}
+
+impl FeatureCommand {
+    pub fn run(self, options: CommandGlobalOpts) {
+        match self.subcommand {
+            FeatureSubcommand::List(c) => c.run(options),
+            FeatureSubcommand::Enable(c) => c.run(options),
+            FeatureSubcommand::Disable(c) => c.run(options),
+            FeatureSubcommand::Reset(c) => c.run(options),
+        }
+    }
+}
+
+/// Enable or disable a feature on a node, or reset it to its default state when no
+/// setting is given
+async fn update_feature(
+    ctx: &Context,
+    opts: &CommandGlobalOpts,
+    node_opts: &NodeOpts,
+    name: &str,
+    enabled: Option<bool>,
+) -> miette::Result<()> {
+    let at = get_node_name(&opts.state, &node_opts.at_node);
+    let node_name = parse_node_name(&at)?;
+    let node = BackgroundNode::create(ctx, &opts.state, &node_name).await?;
+
+    let path = format!("/node/features/{name}");
+    let feature: FeatureStatus = match enabled {
+        Some(enabled) => {
+            node.ask(ctx, Request::put(path).body(SetFeature::new(enabled)))
+                .await?
+        }
+        None => node.ask(ctx, Request::delete(path)).await?,
+    };
+
+    let state = if feature.enabled {
+        "enabled"
+    } else {
+        "disabled"
+    };
+    let message = match enabled {
+        Some(_) => format!(
+            "The feature {} is now {state} on node {}",
+            feature.name.color(OckamColor::PrimaryResource.color()),
+            node_name.color(OckamColor::PrimaryResource.color())
+        ),
+        None => format!(
+            "The feature {} has its default state on node {}, it is {state}",
+            feature.name.color(OckamColor::PrimaryResource.color()),
+            node_name.color(OckamColor::PrimaryResource.color())
+        ),
+    };
+    opts.terminal
+        .stdout()
+        .plain(fmt_ok!("{message}"))
+        .machine(state)
+        .json(serde_json::json!(&feature))
+        .write_line()?;
+    Ok(())
+}
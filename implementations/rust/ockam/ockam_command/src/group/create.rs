@@ -0,0 +1,42 @@
+use clap::Args;
+
+use ockam::Context;
+use ockam_core::api::Request;
+
+use crate::group::update_group;
+use crate::node::{initialize_node_if_default, NodeOpts};
+use crate::util::node_rpc;
+use crate::{docs, CommandGlobalOpts};
+
+const LONG_ABOUT: &str = include_str!("./static/create/long_about.txt");
+const AFTER_LONG_HELP: &str = include_str!("./static/create/after_long_help.txt");
+
+/// Create a group of identities on a node
+#[derive(Clone, Debug, Args)]
+#[command(
+    arg_required_else_help = true,
+    long_about = docs::about(LONG_ABOUT),
+    after_long_help = docs::after_help(AFTER_LONG_HELP),
+)]
+pub struct CreateCommand {
+    /// Name of the group
+    name: String,
+
+    #[command(flatten)]
+    node_opts: NodeOpts,
+}
+
+impl CreateCommand {
+    pub fn run(self, opts: CommandGlobalOpts) {
+        initialize_node_if_default(&opts, &self.node_opts.at_node);
+        node_rpc(rpc, (opts, self));
+    }
+}
+
+async fn rpc(ctx: Context, (opts, cmd): (CommandGlobalOpts, CreateCommand)) -> miette::Result<()> {
+    let request = Request::put(format!("/node/groups/{}", cmd.name));
+    update_group(&ctx, &opts, &cmd.node_opts, request, |node_name| {
+        format!("Created the group {} on node {node_name}", cmd.name)
+    })
+    .await
+}
@@ -0,0 +1,50 @@
+use clap::Args;
+use colorful::Colorful;
+
+use ockam::Context;
+use ockam_api::nodes::BackgroundNode;
+use ockam_core::api::Request;
+
+use crate::node::{get_node_name, initialize_node_if_default, NodeOpts};
+use crate::util::{node_rpc, parse_node_name};
+use crate::{docs, fmt_ok, CommandGlobalOpts};
+
+const LONG_ABOUT: &str = include_str!("./static/delete/long_about.txt");
+const AFTER_LONG_HELP: &str = include_str!("./static/delete/after_long_help.txt");
+
+/// Delete a group of identities of a node
+#[derive(Clone, Debug, Args)]
+#[command(
+    arg_required_else_help = true,
+    long_about = docs::about(LONG_ABOUT),
+    after_long_help = docs::after_help(AFTER_LONG_HELP),
+)]
+pub struct DeleteCommand {
+    /// Name of the group to delete
+    name: String,
+
+    #[command(flatten)]
+    node_opts: NodeOpts,
+}
+
+impl DeleteCommand {
+    pub fn run(self, opts: CommandGlobalOpts) {
+        initialize_node_if_default(&opts, &self.node_opts.at_node);
+        node_rpc(rpc, (opts, self));
+    }
+}
+
+async fn rpc(ctx: Context, (opts, cmd): (CommandGlobalOpts, DeleteCommand)) -> miette::Result<()> {
+    let at = get_node_name(&opts.state, &cmd.node_opts.at_node);
+    let node_name = parse_node_name(&at)?;
+    let node = BackgroundNode::create(&ctx, &opts.state, &node_name).await?;
+    node.tell(&ctx, Request::delete(format!("/node/groups/{}", cmd.name)))
+        .await?;
+    let name = cmd.name;
+    opts.terminal
+        .stdout()
+        .plain(fmt_ok!("Deleted the group {name} on node {node_name}"))
+        .machine(&name)
+        .write_line()?;
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use clap::Args;
+
+use ockam::identity::Identifier;
+use ockam::Context;
+use ockam_core::api::Request;
+
+use crate::group::update_group;
+use crate::node::{initialize_node_if_default, NodeOpts};
+use crate::util::node_rpc;
+use crate::util::parsers::identity_identifier_parser;
+use crate::{docs, CommandGlobalOpts};
+
+const ADD_LONG_ABOUT: &str = include_str!("./static/add_member/long_about.txt");
+const ADD_AFTER_LONG_HELP: &str = include_str!("./static/add_member/after_long_help.txt");
+const REMOVE_LONG_ABOUT: &str = include_str!("./static/remove_member/long_about.txt");
+const REMOVE_AFTER_LONG_HELP: &str = include_str!("./static/remove_member/after_long_help.txt");
+
+/// Add an identity to a group of a node
+#[derive(Clone, Debug, Args)]
+#[command(
+    arg_required_else_help = true,
+    long_about = docs::about(ADD_LONG_ABOUT),
+    after_long_help = docs::after_help(ADD_AFTER_LONG_HELP),
+)]
+pub struct AddMemberCommand {
+    /// Name of the group
+    group: String,
+
+    /// Identifier of the identity to add
+    #[arg(value_parser = identity_identifier_parser)]
+    member: Identifier,
+
+    #[command(flatten)]
+    node_opts: NodeOpts,
+}
+
+impl AddMemberCommand {
+    pub fn run(self, opts: CommandGlobalOpts) {
+        initialize_node_if_default(&opts, &self.node_opts.at_node);
+        node_rpc(add_member, (opts, self));
+    }
+}
+
+async fn add_member(
+    ctx: Context,
+    (opts, cmd): (CommandGlobalOpts, AddMemberCommand),
+) -> miette::Result<()> {
+    let request = Request::put(format!("/node/groups/{}/members/{}", cmd.group, cmd.member));
+    update_group(&ctx, &opts, &cmd.node_opts, request, |node_name| {
+        format!(
+            "Added {} to the group {} on node {node_name}",
+            cmd.member, cmd.group
+        )
+    })
+    .await
+}
+
+/// Remove an identity from a group of a node
+#[derive(Clone, Debug, Args)]
+#[command(
+    arg_required_else_help = true,
+    long_about = docs::about(REMOVE_LONG_ABOUT),
+    after_long_help = docs::after_help(REMOVE_AFTER_LONG_HELP),
+)]
+pub struct RemoveMemberCommand {
+    /// Name of the group
+    group: String,
+
+    /// Identifier of the identity to remove
+    #[arg(value_parser = identity_identifier_parser)]
+    member: Identifier,
+
+    #[command(flatten)]
+    node_opts: NodeOpts,
+}
+
+impl RemoveMemberCommand {
+    pub fn run(self, opts: CommandGlobalOpts) {
+        initialize_node_if_default(&opts, &self.node_opts.at_node);
+        node_rpc(remove_member, (opts, self));
+    }
+}
+
+async fn remove_member(
+    ctx: Context,
+    (opts, cmd): (CommandGlobalOpts, RemoveMemberCommand),
+) -> miette::Result<()> {
+    let request = Request::delete(format!("/node/groups/{}/members/{}", cmd.group, cmd.member));
+    update_group(&ctx, &opts, &cmd.node_opts, request, |node_name| {
+        format!(
+            "Removed {} from the group {} on node {node_name}",
+            cmd.member, cmd.group
+        )
+    })
+    .await
+}
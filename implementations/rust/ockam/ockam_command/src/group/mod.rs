@@ -0,0 +1,100 @@
+mod create;
+mod delete;
+mod list;
+mod member;
+
+pub(crate) use create::CreateCommand;
+pub(crate) use delete::DeleteCommand;
+pub(crate) use list::ListCommand;
+pub(crate) use member::{AddMemberCommand, RemoveMemberCommand};
+
+use clap::{Args, Subcommand};
+use colorful::Colorful;
+
+use ockam::Context;
+use ockam_api::nodes::models::groups::Group;
+use ockam_api::nodes::BackgroundNode;
+use ockam_core::api::Request;
+
+use crate::node::{get_node_name, NodeOpts};
+use crate::output::Output;
+use crate::terminal::OckamColor;
+use crate::util::parse_node_name;
+use crate::{docs, fmt_ok, CommandGlobalOpts};
+
+const LONG_ABOUT: &str = include_str!("./static/long_about.txt");
+
+/// Manage the groups of identities of a node
+#[derive(Clone, Debug, Args)]
+#[command(
+    arg_required_else_help = true,
+    subcommand_required = true,
+    long_about = docs::about(LONG_ABOUT),
+)]
+pub struct GroupCommand {
+    #[command(subcommand)]
+    subcommand: GroupSubcommand,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum GroupSubcommand {
+    #[command(display_order = 800)]
+    Create(CreateCommand),
+    #[command(display_order = 801)]
+    Delete(DeleteCommand),
+    #[command(display_order = 802)]
+    List(ListCommand),
+    #[command(display_order = 803)]
+    AddMember(AddMemberCommand),
+    #[command(display_order = 804)]
+    RemoveMember(RemoveMemberCommand),
+}
+
+impl GroupCommand {
+    pub fn run(self, options: CommandGlobalOpts) {
+        match self.subcommand {
+            GroupSubcommand::Create(c) => c.run(options),
+            GroupSubcommand::Delete(c) => c.run(options),
+            GroupSubcommand::List(c) => c.run(options),
+            GroupSubcommand::AddMember(c) => c.run(options),
+            GroupSubcommand::RemoveMember(c) => c.run(options),
+        }
+    }
+}
+
+/// Send a request changing a group of a node and show the group once changed
+async fn update_group(
+    ctx: &Context,
+    opts: &CommandGlobalOpts,
+    node_opts: &NodeOpts,
+    request: Request<()>,
+    message: impl FnOnce(&str) -> String,
+) -> miette::Result<()> {
+    let at = get_node_name(&opts.state, &node_opts.at_node);
+    let node_name = parse_node_name(&at)?;
+    let node = BackgroundNode::create(ctx, &opts.state, &node_name).await?;
+    let group: Group = node.ask(ctx, request).await?;
+    opts.terminal
+        .stdout()
+        .plain(fmt_ok!("{}\n{}", message(&node_name), group.output()?))
+        .machine(&group.name)
+        .json(serde_json::json!(&group))
+        .write_line()?;
+    Ok(())
+}
+
+impl Output for Group {
+    fn output(&self) -> crate::Result<String> {
+        let members = if self.members.is_empty() {
+            "No members".to_string()
+        } else {
+            format!("Members:\n  {}", self.members.join("\n  "))
+        };
+        Ok(format!(
+            "Group {}\n{members}",
+            self.name
+                .to_string()
+                .color(OckamColor::PrimaryResource.color())
+        ))
+    }
+}
@@ -23,6 +23,10 @@ pub struct DeleteCommand {
     /// Confirm the deletion without prompting
     #[arg(display_order = 901, long, short)]
     yes: bool,
+
+    /// Delete the identity instead of moving it to the trash. It can not be restored
+    #[arg(display_order = 901, long)]
+    permanent: bool,
 }
 
 impl DeleteCommand {
@@ -41,7 +45,7 @@ async fn run_impl(
         .terminal
         .confirmed_with_flag_or_prompt(cmd.yes, "Are you sure you want to delete this identity?")?
     {
-        state.delete_identity(idt)?;
+        state.delete_identity(idt, cmd.permanent)?;
         opts.terminal
             .stdout()
             .plain(fmt_ok!(
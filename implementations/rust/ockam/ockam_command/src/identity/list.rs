@@ -6,11 +6,13 @@ use crate::{docs, CommandGlobalOpts};
 use clap::Args;
 use colorful::Colorful;
 
+use miette::IntoDiagnostic;
 use ockam_api::cli_state::traits::StateDirTrait;
+use ockam_api::resource_list::ResourceList;
 
 use ockam_node::Context;
 use serde::Serialize;
-use serde_json::json;
+use std::collections::BTreeMap;
 use std::fmt::Write;
 use tokio::sync::Mutex;
 use tokio::try_join;
@@ -49,7 +51,8 @@ impl ListCommand {
                     identity.name().to_string(),
                     identity.identifier().to_string(),
                     opts.state.identities.default()?.name() == identity.name(),
-                );
+                )
+                .with_metadata(identity.metadata().clone());
                 *is_finished.lock().await = true;
                 Ok(i)
             };
@@ -76,7 +79,7 @@ impl ListCommand {
         opts.terminal
             .stdout()
             .plain(list)
-            .json(json!(&identities))
+            .json(ResourceList::new(identities).to_json().into_diagnostic()?)
             .write_line()?;
         Ok(())
     }
@@ -87,6 +90,8 @@ pub struct IdentityListOutput {
     pub name: String,
     pub identifier: String,
     pub is_default: bool,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
 }
 
 impl IdentityListOutput {
@@ -95,8 +100,14 @@ impl IdentityListOutput {
             name,
             identifier,
             is_default,
+            metadata: BTreeMap::new(),
         }
     }
+
+    pub fn with_metadata(mut self, metadata: BTreeMap<String, String>) -> Self {
+        self.metadata = metadata;
+        self
+    }
 }
 
 impl Output for IdentityListOutput {
@@ -119,6 +130,9 @@ impl Output for IdentityListOutput {
                 .to_string()
                 .color(OckamColor::PrimaryResource.color())
         )?;
+        for (key, value) in &self.metadata {
+            write!(output, "\n{key}: {value}")?;
+        }
         Ok(output)
     }
 }
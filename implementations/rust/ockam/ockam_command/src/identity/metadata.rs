@@ -0,0 +1,70 @@
+use std::fmt::Write;
+
+use clap::Args;
+use miette::IntoDiagnostic;
+
+use ockam_api::cli_state::traits::StateDirTrait;
+
+use crate::identity::get_identity_name;
+use crate::util::local_cmd;
+use crate::util::parsers::key_value_parser;
+use crate::{docs, CommandGlobalOpts};
+
+const LONG_ABOUT: &str = include_str!("./static/metadata/long_about.txt");
+const PREVIEW_TAG: &str = include_str!("../static/preview_tag.txt");
+const AFTER_LONG_HELP: &str = include_str!("./static/metadata/after_long_help.txt");
+
+/// Show or change the metadata of an identity
+#[derive(Clone, Debug, Args)]
+#[command(
+    long_about = docs::about(LONG_ABOUT),
+    before_help = docs::before_help(PREVIEW_TAG),
+    after_long_help = docs::after_help(AFTER_LONG_HELP)
+)]
+pub struct MetadataCommand {
+    /// Name of the identity
+    name: Option<String>,
+
+    /// Metadata entries in `key=value` format to set on the identity
+    #[arg(long = "set", value_name = "KEY=VALUE", value_parser = key_value_parser)]
+    set: Vec<(String, String)>,
+
+    /// Keys of the metadata entries to remove from the identity
+    #[arg(long = "remove", value_name = "KEY")]
+    remove: Vec<String>,
+}
+
+impl MetadataCommand {
+    pub fn run(self, opts: CommandGlobalOpts) {
+        local_cmd(run_impl(opts, self));
+    }
+}
+
+fn run_impl(opts: CommandGlobalOpts, cmd: MetadataCommand) -> miette::Result<()> {
+    let name = get_identity_name(&opts.state, &cmd.name);
+    let mut state = opts.state.identities.get(&name)?;
+    for key in &cmd.remove {
+        state.remove_metadata(key)?;
+    }
+    for (key, value) in &cmd.set {
+        state.set_metadata(key, value)?;
+    }
+
+    let metadata = state.metadata();
+    let plain = if metadata.is_empty() {
+        format!("The identity named '{name}' has no metadata")
+    } else {
+        let mut buf = String::new();
+        for (key, value) in metadata {
+            writeln!(buf, "{key}: {value}").into_diagnostic()?;
+        }
+        buf
+    };
+
+    opts.terminal
+        .stdout()
+        .plain(plain)
+        .json(serde_json::to_string_pretty(metadata).into_diagnostic()?)
+        .write_line()?;
+    Ok(())
+}
@@ -2,6 +2,8 @@ mod create;
 mod default;
 mod delete;
 mod list;
+mod metadata;
+mod restore;
 mod show;
 
 pub use create::CreateCommand;
@@ -10,6 +12,8 @@ pub(crate) use list::ListCommand;
 pub(crate) use show::ShowCommand;
 
 use crate::identity::default::DefaultCommand;
+use crate::identity::metadata::MetadataCommand;
+use crate::identity::restore::RestoreCommand;
 use crate::{docs, CommandGlobalOpts};
 use clap::{Args, Subcommand};
 use ockam_api::cli_state::traits::StateDirTrait;
@@ -36,6 +40,8 @@ pub enum IdentitySubcommand {
     List(ListCommand),
     Default(DefaultCommand),
     Delete(DeleteCommand),
+    Restore(RestoreCommand),
+    Metadata(MetadataCommand),
 }
 
 impl IdentityCommand {
@@ -45,7 +51,9 @@ impl IdentityCommand {
             IdentitySubcommand::Show(c) => c.run(options),
             IdentitySubcommand::List(c) => c.run(options),
             IdentitySubcommand::Delete(c) => c.run(options),
+            IdentitySubcommand::Restore(c) => c.run(options),
             IdentitySubcommand::Default(c) => c.run(options),
+            IdentitySubcommand::Metadata(c) => c.run(options),
         }
     }
 }
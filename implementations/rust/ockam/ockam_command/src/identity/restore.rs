@@ -0,0 +1,28 @@
+use clap::Args;
+
+use crate::util::{local_cmd, restore_deleted};
+use crate::{docs, CommandGlobalOpts};
+
+const LONG_ABOUT: &str = include_str!("./static/restore/long_about.txt");
+const AFTER_LONG_HELP: &str = include_str!("./static/restore/after_long_help.txt");
+
+/// Restore a deleted identity
+#[derive(Clone, Debug, Args)]
+#[command(
+long_about = docs::about(LONG_ABOUT),
+after_long_help = docs::after_help(AFTER_LONG_HELP)
+)]
+pub struct RestoreCommand {
+    /// Name of the identity to be restored
+    name: Option<String>,
+}
+
+impl RestoreCommand {
+    pub fn run(self, options: CommandGlobalOpts) {
+        local_cmd(run_impl(options, self));
+    }
+}
+
+fn run_impl(opts: CommandGlobalOpts, cmd: RestoreCommand) -> miette::Result<()> {
+    restore_deleted(&opts, &opts.state.identities, "identity", cmd.name)
+}
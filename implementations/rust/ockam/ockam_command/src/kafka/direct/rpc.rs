@@ -5,7 +5,7 @@ use tokio::{sync::Mutex, try_join};
 
 use ockam::Context;
 use ockam_api::nodes::models::services::{StartKafkaDirectRequest, StartServiceRequest};
-use ockam_api::nodes::BackgroundNode;
+use ockam_api::nodes::{random_idempotency_key, BackgroundNode};
 use ockam_api::port_range::PortRange;
 use ockam_core::api::Request;
 use ockam_multiaddr::MultiAddr;
@@ -62,7 +62,9 @@ pub async fn start(ctx: Context, (opts, args): (CommandGlobalOpts, ArgOpts)) ->
             consumer_route,
         );
         let payload = StartServiceRequest::new(payload, &addr);
-        let req = Request::post(endpoint).body(payload);
+        let req = Request::post(endpoint)
+            .body(payload)
+            .idempotency_key(random_idempotency_key());
         start_service_impl(&ctx, &node, &kafka_entity, req).await?;
 
         *is_finished.lock().await = true;
@@ -7,7 +7,7 @@ use tokio::{sync::Mutex, try_join};
 use ockam::Context;
 use ockam_api::nodes::models::services::StartKafkaOutletRequest;
 use ockam_api::nodes::models::services::StartServiceRequest;
-use ockam_api::nodes::BackgroundNode;
+use ockam_api::nodes::{random_idempotency_key, BackgroundNode};
 use ockam_core::api::Request;
 
 use crate::node::get_node_name;
@@ -52,7 +52,9 @@ async fn rpc(ctx: Context, (opts, cmd): (CommandGlobalOpts, CreateCommand)) -> m
     let send_req = async {
         let payload = StartKafkaOutletRequest::new(bootstrap_server);
         let payload = StartServiceRequest::new(payload, &addr);
-        let req = Request::post("/node/services/kafka_outlet").body(payload);
+        let req = Request::post("/node/services/kafka_outlet")
+            .body(payload)
+            .idempotency_key(random_idempotency_key());
         let node_name = get_node_name(&opts.state, &node_opts.at_node);
         let node = BackgroundNode::create(&ctx, &opts.state, &node_name).await?;
 
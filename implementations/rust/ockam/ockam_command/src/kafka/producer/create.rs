@@ -37,6 +37,25 @@ pub struct CreateCommand {
     /// The route to the project in ockam orchestrator, expected something like /project/<name>
     #[arg(long, default_value_t = kafka_default_project_route())]
     project_route: MultiAddr,
+    /// Name of a record header which is sent in clear text to the broker, for example to
+    /// filter the records. When this option is set, the other headers are encrypted along
+    /// with the record value. Can be used several times
+    #[arg(long = "cleartext-header", value_name = "HEADER")]
+    cleartext_headers: Vec<String>,
+    /// Name of a topic which can be produced to through this service, or a prefix of topic
+    /// names ending with `*`. When this option is set, the other topics are rejected.
+    /// Can be used several times
+    #[arg(long = "topic", value_name = "TOPIC")]
+    topics: Vec<String>,
+    /// Glob pattern of the topics which have their records encrypted, for example
+    /// `payments.*`. When this option is set, the records of the other topics are sent
+    /// in clear text. Can be used several times
+    #[arg(long = "encrypted-topic", value_name = "PATTERN")]
+    encrypted_topics: Vec<String>,
+    /// Glob pattern of the topics which have their records sent in clear text, even if
+    /// they match an encrypted topic pattern. Can be used several times
+    #[arg(long = "cleartext-topic", value_name = "PATTERN")]
+    cleartext_topics: Vec<String>,
 }
 
 impl CreateCommand {
@@ -50,6 +69,11 @@ impl CreateCommand {
             bootstrap_server: self.bootstrap_server,
             brokers_port_range: self.brokers_port_range,
             project_route: self.project_route,
+            cleartext_headers: self.cleartext_headers,
+            sign_offset_commits: false,
+            topics: self.topics,
+            encrypted_topics: self.encrypted_topics,
+            cleartext_topics: self.cleartext_topics,
         };
         node_rpc(rpc, (opts, arg_opts));
     }
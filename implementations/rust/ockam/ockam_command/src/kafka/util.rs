@@ -1,17 +1,17 @@
 use std::net::SocketAddr;
 
 use colorful::Colorful;
+use miette::IntoDiagnostic;
 use tokio::{sync::Mutex, try_join};
 
 use ockam::Context;
-use ockam_api::nodes::models::services::{StartKafkaProducerRequest, StartServiceRequest};
-use ockam_api::nodes::BackgroundNode;
+use ockam_api::nodes::models::services::{KafkaServiceConfig, StartServiceRequest};
+use ockam_api::nodes::{random_idempotency_key, BackgroundNode};
 use ockam_api::port_range::PortRange;
 use ockam_core::api::Request;
 use ockam_multiaddr::MultiAddr;
 
 use crate::node::{get_node_name, NodeOpts};
-use crate::service::start::start_service_impl;
 use crate::terminal::OckamColor;
 use crate::util::process_nodes_multiaddr;
 use crate::{display_parse_logs, fmt_log, fmt_ok, CommandGlobalOpts};
@@ -24,6 +24,11 @@ pub struct ArgOpts {
     pub bootstrap_server: SocketAddr,
     pub brokers_port_range: PortRange,
     pub project_route: MultiAddr,
+    pub cleartext_headers: Vec<String>,
+    pub sign_offset_commits: bool,
+    pub topics: Vec<String>,
+    pub encrypted_topics: Vec<String>,
+    pub cleartext_topics: Vec<String>,
 }
 
 pub async fn rpc(ctx: Context, (opts, args): (CommandGlobalOpts, ArgOpts)) -> miette::Result<()> {
@@ -35,6 +40,11 @@ pub async fn rpc(ctx: Context, (opts, args): (CommandGlobalOpts, ArgOpts)) -> mi
         bootstrap_server,
         brokers_port_range,
         project_route,
+        cleartext_headers,
+        sign_offset_commits,
+        topics,
+        encrypted_topics,
+        cleartext_topics,
     } = args;
 
     opts.terminal
@@ -49,18 +59,35 @@ pub async fn rpc(ctx: Context, (opts, args): (CommandGlobalOpts, ArgOpts)) -> mi
         let node_name = get_node_name(&opts.state, &node_opts.at_node);
         let node = BackgroundNode::create(&ctx, &opts.state, &node_name).await?;
 
-        let payload = StartKafkaProducerRequest::new(
+        let mut payload = KafkaServiceConfig::new(
             bootstrap_server.to_owned(),
             brokers_port_range,
             project_route,
         );
+        if !cleartext_headers.is_empty() {
+            payload = payload.with_cleartext_headers(cleartext_headers);
+        }
+        if sign_offset_commits {
+            payload = payload.with_signed_offset_commits();
+        }
+        if !topics.is_empty() {
+            payload = payload.with_topics(topics);
+        }
+        if !encrypted_topics.is_empty() {
+            payload = payload.with_encrypted_topics(encrypted_topics);
+        }
+        if !cleartext_topics.is_empty() {
+            payload = payload.with_cleartext_topics(cleartext_topics);
+        }
         let payload = StartServiceRequest::new(payload, &addr);
-        let req = Request::post(endpoint).body(payload);
-        start_service_impl(&ctx, &node, &kafka_entity, req).await?;
+        let req = Request::post(endpoint)
+            .body(payload)
+            .idempotency_key(random_idempotency_key());
+        let config: KafkaServiceConfig = node.ask(&ctx, req).await?;
 
         *is_finished.lock().await = true;
 
-        Ok::<_, crate::Error>(())
+        Ok::<_, crate::Error>(config)
     };
 
     let msgs = vec![
@@ -84,7 +111,7 @@ pub async fn rpc(ctx: Context, (opts, args): (CommandGlobalOpts, ArgOpts)) -> mi
         ),
     ];
     let progress_output = opts.terminal.progress_output(&msgs, &is_finished);
-    let (_, _) = try_join!(send_req, progress_output)?;
+    let (config, _) = try_join!(send_req, progress_output)?;
 
     opts.terminal
         .stdout()
@@ -102,6 +129,7 @@ pub async fn rpc(ctx: Context, (opts, args): (CommandGlobalOpts, ArgOpts)) -> mi
                     .color(OckamColor::PrimaryResource.color())
             ),
         )
+        .json(serde_json::to_string_pretty(&config).into_diagnostic()?)
         .write_line()?;
 
     Ok(())
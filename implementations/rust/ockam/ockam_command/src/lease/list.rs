@@ -91,7 +91,10 @@ impl Output for Token {
 
         writeln!(output, "Token {id}")?;
         writeln!(output, "Expires {expires_at} {status}")?;
-        write!(output, "{}", self.token)?;
+        // the secret of a token is only returned when the token is created
+        if !self.token.is_empty() {
+            write!(output, "{}", self.token)?;
+        }
 
         Ok(output)
     }
@@ -49,7 +49,15 @@ async fn run_impl(
         .set("issued_for", &token.issued_for)
         .set("created_at", &token.created_at)
         .set("expires_at", &token.expires)
-        .set("token", &token.token)
+        .set(
+            "token",
+            // the secret of a token is only returned when the token is created
+            if token.token.is_empty() {
+                "<hidden>"
+            } else {
+                &token.token
+            },
+        )
         .set("status", &token.status);
 
     let skin = MadSkin::default();
@@ -27,7 +27,9 @@ mod docs;
 pub mod enroll;
 mod environment;
 pub mod error;
+mod feature;
 mod flow_control;
+mod group;
 pub mod identity;
 mod kafka;
 mod lease;
@@ -43,7 +45,9 @@ mod policy;
 mod project;
 mod relay;
 mod reset;
+mod route;
 mod run;
+mod schedule;
 mod secure_channel;
 mod service;
 #[cfg(feature = "orchestrator")]
@@ -64,7 +68,9 @@ mod worker;
 
 use crate::admin::AdminCommand;
 use crate::authority::AuthorityCommand;
+use crate::feature::FeatureCommand;
 use crate::flow_control::FlowControlCommand;
+use crate::group::GroupCommand;
 use crate::logs::setup_logging;
 use crate::node::NodeSubcommand;
 use crate::run::RunCommand;
@@ -101,6 +107,8 @@ use policy::PolicyCommand;
 use project::ProjectCommand;
 use relay::RelayCommand;
 use reset::ResetCommand;
+use route::RouteCommand;
+use schedule::ScheduleCommand;
 use secure_channel::{listener::SecureChannelListenerCommand, SecureChannelCommand};
 use service::ServiceCommand;
 #[cfg(feature = "orchestrator")]
@@ -315,6 +323,10 @@ pub enum OckamSubcommand {
     Service(ServiceCommand),
     Message(MessageCommand),
     Relay(RelayCommand),
+    Route(RouteCommand),
+    Schedule(ScheduleCommand),
+    Feature(FeatureCommand),
+    Group(GroupCommand),
 
     TcpListener(TcpListenerCommand),
     TcpConnection(TcpConnectionCommand),
@@ -438,6 +450,10 @@ impl OckamCommand {
             OckamSubcommand::Service(c) => c.run(options),
             OckamSubcommand::Message(c) => c.run(options),
             OckamSubcommand::Relay(c) => c.run(options),
+            OckamSubcommand::Route(c) => c.run(options),
+            OckamSubcommand::Schedule(c) => c.run(options),
+            OckamSubcommand::Group(c) => c.run(options),
+            OckamSubcommand::Feature(c) => c.run(options),
 
             OckamSubcommand::KafkaOutlet(c) => c.run(options),
             OckamSubcommand::TcpListener(c) => c.run(options),
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+use std::net::{Ipv4Addr, Ipv6Addr};
 use std::sync::Arc;
 use std::{path::PathBuf, process, str::FromStr};
 
@@ -9,13 +11,22 @@ use minicbor::{Decoder, Encode};
 use tokio::sync::Mutex;
 use tokio::time::{sleep, Duration};
 use tokio::try_join;
+use tracing::warn;
 
+use ockam::identity::IdentitiesRepository;
 use ockam::{Address, AsyncTryClone, TcpListenerOptions};
 use ockam::{Context, TcpTransport};
+use ockam_api::bootstrapped_identities_store::BootstrapedIdentityStore;
 use ockam_api::cli_state::traits::{StateDirTrait, StateItemTrait};
-use ockam_api::cli_state::{add_project_info_to_node_state, init_node_state, random_name};
+use ockam_api::cli_state::{
+    add_project_info_to_node_state, init_node_state, random_name, NodeEnvironment, NodeState,
+    NodeTemplateConfig,
+};
+use ockam_api::knock::IdentityKnockVerifier;
 use ockam_api::nodes::models::transport::CreateTransportJson;
-use ockam_api::nodes::service::NodeManagerTrustOptions;
+use ockam_api::nodes::service::{
+    ManagementAccess, NodeManagerTrustOptions, DEFAULT_SHUTDOWN_TIMEOUT,
+};
 use ockam_api::nodes::BackgroundNode;
 use ockam_api::nodes::InMemoryNode;
 use ockam_api::{
@@ -28,16 +39,21 @@ use ockam_api::{
 };
 use ockam_core::api::{Request, ResponseHeader, Status};
 use ockam_core::{route, LOCAL};
+use ockam_transport_tcp::{AllowedNetwork, KnockOptions};
 
-use crate::node::util::{spawn_node, NodeManagerDefaults};
+use crate::node::template::set_template_policies;
+use crate::node::util::{spawn_node, NodeManagerDefaults, SpawnNodeOptions};
+use crate::node::NodeSubcommand;
 use crate::secure_channel::listener::create as secure_channel_listener;
 use crate::service::config::Config;
 use crate::terminal::OckamColor;
 use crate::util::api::TrustContextOpts;
+use crate::util::duration::duration_parser;
+use crate::util::parsers::{allowed_network_parser, key_value_parser};
 use crate::util::{api, parse_node_name};
 use crate::util::{embedded_node_that_is_not_stopped, exitcode};
 use crate::util::{local_cmd, node_rpc};
-use crate::{docs, shutdown, CommandGlobalOpts, Result};
+use crate::{docs, shutdown, CommandGlobalOpts, OckamCommand, OckamSubcommand, Result};
 use crate::{fmt_log, fmt_ok};
 
 use super::show::is_node_up;
@@ -74,6 +90,38 @@ pub struct CreateCommand {
     )]
     pub tcp_listener_address: String,
 
+    /// Only accept the connections to the TCP listener of the node from a given network,
+    /// written `address/prefix_length`, like `192.168.1.0/24`, or as a single address.
+    /// Can be repeated. The loopback addresses are always allowed.
+    /// Defaults to the networks given when the node was created if it is restarted
+    #[arg(display_order = 900, long, value_name = "NETWORK", value_parser = allowed_network_parser)]
+    pub tcp_listener_allowed_network: Vec<AllowedNetwork>,
+
+    /// Protect the TCP listener of the node with port knocking: only accept the connections
+    /// of the hosts which recently sent a knock, with `ockam tcp-listener knock`, signed by an
+    /// identity having this attribute. The loopback addresses never have to knock.
+    /// Defaults to the attribute given when the node was created if it is restarted
+    #[arg(display_order = 900, long, value_name = "KEY=VALUE", value_parser = key_value_parser)]
+    pub tcp_listener_knock_attribute: Option<(String, String)>,
+
+    /// Name of the identity used by the node to create secure channels when a command
+    /// does not specify one. The node uses its own identity if not set.
+    /// Defaults to the identity given when the node was created if it is restarted
+    #[arg(display_order = 900, long, value_name = "IDENTITY_NAME")]
+    pub client_identity: Option<String>,
+
+    /// Compress the messages of the secure channels created by the node, and accepted by
+    /// its default secure channel listener, when the other side supports it.
+    /// Compression should not be enabled on channels carrying both secrets and data
+    /// controlled by an attacker. Kept when the node is restarted
+    #[arg(display_order = 900, long)]
+    pub secure_channel_compression: bool,
+
+    /// Reuse a single TCP connection for all the secure channels and portals created by
+    /// the node to the same peer. Kept when the node is restarted
+    #[arg(display_order = 900, long)]
+    pub share_tcp_connections: bool,
+
     /// `node create` started a child process to run this node in foreground.
     #[arg(long, hide = true)]
     pub child_process: bool,
@@ -109,6 +157,45 @@ pub struct CreateCommand {
 
     #[command(flatten)]
     pub trust_context_opts: TrustContextOpts,
+
+    /// Maximum time given to the node to clean up its resources, like relays, portals
+    /// and services, when it is stopped
+    #[arg(long, value_name = "DURATION", default_value = "10s", value_parser = duration_parser)]
+    pub shutdown_timeout: Duration,
+
+    /// Which requests the node accepts to manage it: `open` accepts every request,
+    /// `admin` only accepts the requests sent over a secure channel by identities having the
+    /// `ockam-admin=true` attribute, and `admin-or-local` also accepts the requests sent
+    /// without a secure channel from this machine, like the requests of this command line.
+    /// Defaults to `admin-or-local`, or to the access given when the node was created if it is
+    /// restarted
+    #[arg(long, value_name = "ACCESS")]
+    pub management_access: Option<ManagementAccess>,
+
+    /// Environment variable of the node process, in `key=value` format. Can be repeated.
+    /// The variables are set again every time the node is restarted.
+    /// Their values are stored encrypted with the vault of the node
+    #[arg(display_order = 900, long = "env", value_name = "KEY=VALUE", value_parser = key_value_parser)]
+    pub environment: Vec<(String, String)>,
+
+    /// Working directory of the node process, kept when the node is restarted
+    #[arg(display_order = 900, long, value_name = "PATH")]
+    pub working_directory: Option<PathBuf>,
+
+    /// Additional arguments of the node process, given after `--`.
+    /// They are given again every time the node is restarted
+    #[arg(last = true, value_name = "ARGS")]
+    pub extra_args: Vec<String>,
+
+    /// Name of the node template used to configure the node.
+    /// The arguments given to this command take precedence over the template
+    #[arg(
+        display_order = 900,
+        long,
+        value_name = "TEMPLATE_NAME",
+        conflicts_with = "foreground"
+    )]
+    pub template: Option<String>,
 }
 
 impl Default for CreateCommand {
@@ -118,6 +205,11 @@ impl Default for CreateCommand {
             node_name: random_name(),
             exit_on_eof: false,
             tcp_listener_address: node_manager_defaults.tcp_listener_address,
+            tcp_listener_allowed_network: vec![],
+            tcp_listener_knock_attribute: None,
+            client_identity: None,
+            secure_channel_compression: false,
+            share_tcp_connections: false,
             foreground: false,
             child_process: false,
             launch_config: None,
@@ -129,6 +221,12 @@ impl Default for CreateCommand {
             authority_identity: None,
             credential: None,
             trust_context_opts: node_manager_defaults.trust_context_opts,
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+            management_access: None,
+            environment: vec![],
+            working_directory: None,
+            extra_args: vec![],
+            template: None,
         }
     }
 }
@@ -168,6 +266,102 @@ impl CreateCommand {
     pub fn logging_to_stdout(&self) -> bool {
         !self.logging_to_file()
     }
+
+    /// Parse the arguments of a foreground node again with its additional arguments, so that
+    /// they are applied like they are for a node started in a child process.
+    /// The additional arguments are the ones given after `--`, or the ones recorded when
+    /// the node was created
+    fn with_extra_args(self, opts: &CommandGlobalOpts) -> miette::Result<Self> {
+        let extra_args = if self.extra_args.is_empty() {
+            match opts.state.nodes.get(&self.node_name) {
+                Ok(node_state) => node_state.config().setup().extra_args.clone(),
+                Err(_) => vec![],
+            }
+        } else {
+            self.extra_args.clone()
+        };
+        if extra_args.is_empty() {
+            return Ok(self);
+        }
+
+        let mut args: Vec<String> = std::env::args().take_while(|arg| arg != "--").collect();
+        args.extend(extra_args.iter().cloned());
+        let command = OckamCommand::try_parse_from(args).into_diagnostic()?;
+        match command.subcommand {
+            OckamSubcommand::Node(node) => match node.subcommand {
+                NodeSubcommand::Create(cmd) => Ok(Self { extra_args, ..*cmd }),
+                _ => Err(miette!(
+                    "The additional arguments can only be given to a node"
+                )),
+            },
+            _ => Err(miette!(
+                "The additional arguments can only be given to a node"
+            )),
+        }
+    }
+
+    /// Record the environment variables, working directory and additional arguments
+    /// given to this command, so that they are reused when the node process is restarted.
+    /// The values of the environment variables are encrypted with the vault of the node
+    async fn set_process_setup(&self, node_state: &NodeState) -> miette::Result<()> {
+        let mut setup = node_state.config().setup_mut();
+        if !self.environment.is_empty() {
+            let vault = node_state.config().vault().await?;
+            let environment = NodeEnvironment::encrypt(
+                &vault,
+                &self.environment.iter().cloned().collect::<BTreeMap<_, _>>(),
+            )
+            .await
+            .into_diagnostic()?;
+            setup = setup.set_environment(environment);
+        }
+        if let Some(working_directory) = &self.working_directory {
+            let working_directory = std::fs::canonicalize(working_directory)
+                .into_diagnostic()
+                .wrap_err(format!("Invalid working directory {working_directory:?}"))?;
+            setup = setup.set_working_directory(working_directory);
+        }
+        if !self.extra_args.is_empty() {
+            setup = setup.set_extra_args(self.extra_args.clone());
+        }
+        if self.secure_channel_compression {
+            setup = setup.set_secure_channel_compression(true);
+        }
+        if self.share_tcp_connections {
+            setup = setup.set_share_tcp_connections(true);
+        }
+        node_state.set_setup(&setup)?;
+        Ok(())
+    }
+
+    /// Complete the arguments of this command with the values of a template.
+    /// The arguments given on the command line take precedence over the template
+    pub(crate) fn with_template(mut self, template: &NodeTemplateConfig) -> miette::Result<Self> {
+        self.vault = self.vault.or_else(|| template.vault.clone());
+        self.identity = self.identity.or_else(|| template.identity.clone());
+        if let Some(address) = &template.tcp_listener_address {
+            if self.tcp_listener_address == CreateCommand::default().tcp_listener_address {
+                self.tcp_listener_address = address.clone();
+            }
+        }
+        if self.tcp_listener_allowed_network.is_empty() {
+            self.tcp_listener_allowed_network = template
+                .tcp_listener_allowed_networks
+                .iter()
+                .map(|n| allowed_network_parser(n).map_err(|e| miette!("{e}")))
+                .collect::<miette::Result<Vec<_>>>()?;
+        }
+        self.management_access = self.management_access.or(template.management_access);
+        if self.launch_config.is_none() {
+            if let Some(services) = &template.startup_services {
+                self.launch_config = Some(
+                    serde_json::from_value(serde_json::json!({ "startup_services": services }))
+                        .into_diagnostic()?,
+                );
+            }
+        }
+        Ok(self)
+    }
 }
 
 pub fn parse_launch_config(config_or_path: &str) -> Result<Config> {
@@ -201,6 +395,15 @@ pub(crate) async fn background_mode(
         ));
     }
 
+    let template = match &cmd.template {
+        Some(name) => Some(opts.state.node_templates.get(name)?.config().clone()),
+        None => None,
+    };
+    let cmd = match &template {
+        Some(template) => cmd.with_template(template)?,
+        None => cmd,
+    };
+
     let is_finished: Mutex<bool> = Mutex::new(false);
 
     let send_req = async {
@@ -243,12 +446,100 @@ pub(crate) async fn background_mode(
     Ok(())
 }
 
+/// Options of the api transport listener. If some networks are allowed, or if the listener
+/// is protected by port knocking, the loopback addresses are allowed as well so that the node
+/// can still be managed by the command line
+fn api_listener_options(
+    allowed_networks: &[AllowedNetwork],
+    knock: Option<KnockOptions>,
+) -> TcpListenerOptions {
+    let options = match knock {
+        Some(knock) => TcpListenerOptions::new().with_knock(
+            loopback_networks()
+                .into_iter()
+                .fold(knock, |knock, network| knock.with_exempt_network(network)),
+        ),
+        None => TcpListenerOptions::new(),
+    };
+    if allowed_networks.is_empty() {
+        return options;
+    }
+    loopback_networks()
+        .into_iter()
+        .chain(allowed_networks.iter().copied())
+        .fold(options, |options, network| {
+            options.with_allowed_network(network)
+        })
+}
+
+fn loopback_networks() -> Vec<AllowedNetwork> {
+    [
+        AllowedNetwork::new(Ipv4Addr::LOCALHOST.into(), 8),
+        AllowedNetwork::new(Ipv6Addr::LOCALHOST.into(), 128),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// Port knocking options of the api transport listener: the knocks must be signed by an
+/// identity having the attribute `key=value`, either pre-trusted or known by the node
+async fn api_listener_knock_options(
+    opts: &CommandGlobalOpts,
+    node_name: &str,
+    pre_trusted_identities: Option<PreTrustedIdentities>,
+    (key, value): &(String, String),
+) -> miette::Result<KnockOptions> {
+    let vault = opts.state.nodes.get(node_name)?.config().vault().await?;
+    let identities = opts.state.get_identities(vault).await?;
+    let repository = opts.state.identities.identities_repository().await?;
+    let attributes_reader = match pre_trusted_identities {
+        Some(pre_trusted_identities) => {
+            BootstrapedIdentityStore::new(Arc::new(pre_trusted_identities), repository)
+                .as_attributes_reader()
+        }
+        None => repository.as_attributes_reader(),
+    };
+    let verifier = IdentityKnockVerifier::new(identities, attributes_reader, key, value);
+    Ok(KnockOptions::new(Arc::new(verifier)))
+}
+
 // Create a new node in the foreground (i.e. in this OS process)
 fn foreground_mode(opts: CommandGlobalOpts, cmd: CreateCommand) -> miette::Result<()> {
+    // The child process of a background node is given its additional arguments by its parent
+    let cmd = if cmd.child_process {
+        cmd
+    } else {
+        cmd.with_extra_args(&opts)?
+    };
     embedded_node_that_is_not_stopped(run_foreground_node, (opts, cmd))?;
     Ok(())
 }
 
+/// Set the environment variables and the working directory recorded for a node in the
+/// process running that node. The values of the variables are decrypted with the vault of the node
+async fn apply_process_setup(node_state: &NodeState) -> miette::Result<()> {
+    let setup = node_state.config().setup();
+    if !setup.environment.is_empty() {
+        let vault = node_state.config().vault().await?;
+        let variables = setup
+            .environment
+            .decrypt(&vault)
+            .await
+            .into_diagnostic()
+            .wrap_err("Cannot decrypt the environment of the node")?;
+        for (name, value) in variables {
+            std::env::set_var(name, value);
+        }
+    }
+    if let Some(working_directory) = &setup.working_directory {
+        std::env::set_current_dir(working_directory)
+            .into_diagnostic()
+            .wrap_err(format!("Invalid working directory {working_directory:?}"))?;
+    }
+    Ok(())
+}
+
 async fn run_foreground_node(
     ctx: Context,
     (opts, cmd): (CommandGlobalOpts, CreateCommand),
@@ -274,6 +565,13 @@ async fn run_foreground_node(
     )
     .await?;
 
+    // The child process of a background node is started with the setup recorded by its parent
+    let node_state = opts.state.nodes.get(&node_name)?;
+    if !cmd.child_process {
+        cmd.set_process_setup(&node_state).await?;
+    }
+    apply_process_setup(&node_state).await?;
+
     let trust_context_config = cmd
         .trust_context_opts
         .to_config(&opts.state)?
@@ -281,20 +579,82 @@ async fn run_foreground_node(
         .with_credential_name(cmd.credential.as_ref())
         .build();
 
+    let allowed_networks = if cmd.tcp_listener_allowed_network.is_empty() {
+        opts.state
+            .nodes
+            .get(&node_name)?
+            .config()
+            .setup()
+            .api_allowed_networks
+            .iter()
+            .map(|network| network.parse::<AllowedNetwork>().into_diagnostic())
+            .collect::<miette::Result<Vec<_>>>()?
+    } else {
+        cmd.tcp_listener_allowed_network.clone()
+    };
+
+    let knock_attribute = match &cmd.tcp_listener_knock_attribute {
+        Some(attribute) => Some(attribute.clone()),
+        None => opts
+            .state
+            .nodes
+            .get(&node_name)?
+            .config()
+            .setup()
+            .api_knock_attribute
+            .as_deref()
+            .map(|attribute| key_value_parser(attribute).map_err(|e| miette!("{e}")))
+            .transpose()?,
+    };
+    let pre_trusted_identities = load_pre_trusted_identities(&cmd)?;
+    let knock = match &knock_attribute {
+        Some(attribute) => Some(
+            api_listener_knock_options(
+                &opts,
+                &node_name,
+                pre_trusted_identities.clone(),
+                attribute,
+            )
+            .await?,
+        ),
+        None => None,
+    };
+
     let tcp = TcpTransport::create(&ctx).await.into_diagnostic()?;
-    let options = TcpListenerOptions::new();
+    let options = api_listener_options(&allowed_networks, knock);
     let listener = tcp
         .listen(&cmd.tcp_listener_address, options)
         .await
         .into_diagnostic()?;
 
     let node_state = opts.state.nodes.get(&node_name)?;
+    let management_access = cmd
+        .management_access
+        .or(node_state.config().setup().management_access)
+        .unwrap_or_default();
+    let client_identity = match &cmd.client_identity {
+        Some(identity_name) => {
+            opts.state.identities.get(identity_name)?;
+            Some(identity_name.clone())
+        }
+        None => node_state.config().setup().client_identity.clone(),
+    };
+    let secure_channel_compression =
+        cmd.secure_channel_compression || node_state.config().setup().secure_channel_compression;
+    let share_tcp_connections =
+        cmd.share_tcp_connections || node_state.config().setup().share_tcp_connections;
     node_state.set_pid(process::id() as i32)?;
     node_state.set_setup(
         &node_state
             .config()
             .setup_mut()
             .set_verbose(opts.global_args.verbose)
+            .set_management_access(management_access)
+            .set_api_allowed_networks(allowed_networks.iter().map(|n| n.to_string()).collect())
+            .set_api_knock_attribute(knock_attribute.map(|(key, value)| format!("{key}={value}")))
+            .set_client_identity(client_identity)
+            .set_secure_channel_compression(secure_channel_compression)
+            .set_share_tcp_connections(share_tcp_connections)
             .set_api_transport(
                 CreateTransportJson::new(
                     TransportType::Tcp,
@@ -302,11 +662,10 @@ async fn run_foreground_node(
                     &listener.socket_address().to_string(),
                 )
                 .into_diagnostic()?,
-            ),
+            )
+            .set_started(),
     )?;
 
-    let pre_trusted_identities = load_pre_trusted_identities(&cmd)?;
-
     let node_man = InMemoryNode::new(
         &ctx,
         NodeManagerGeneralOptions::new(
@@ -315,7 +674,10 @@ async fn run_foreground_node(
             pre_trusted_identities,
             cmd.launch_config.is_none(),
             true,
-        ),
+        )
+        .with_management_access(management_access)
+        .with_secure_channel_compression(secure_channel_compression)
+        .with_share_tcp_connections(share_tcp_connections),
         NodeManagerTransportOptions::new(
             listener.flow_control_id().clone(),
             tcp.async_try_clone().await.into_diagnostic()?,
@@ -324,7 +686,8 @@ async fn run_foreground_node(
     )
     .await
     .into_diagnostic()?;
-    let node_manager_worker = NodeManagerWorker::new(Arc::new(node_man));
+    let node_man = Arc::new(node_man);
+    let node_manager_worker = NodeManagerWorker::new(node_man.clone());
 
     ctx.flow_controls()
         .add_consumer(NODEMANAGER_ADDR, listener.flow_control_id());
@@ -359,6 +722,14 @@ async fn run_foreground_node(
     )
     .await?;
 
+    // Give the node subsystems a chance to clean up their resources before stopping
+    if let Err(err) = node_man
+        .run_shutdown_hooks(&ctx, cmd.shutdown_timeout)
+        .await
+    {
+        warn!(%err, "cannot run the node shutdown hooks");
+    }
+
     // Try to stop node; it might have already been stopped or deleted (e.g. when running `node delete --all`)
     if let Ok(state) = opts.state.nodes.get(&node_name) {
         let _ = state.kill_process(false);
@@ -450,6 +821,18 @@ pub async fn spawn_background_node(
         cmd.identity.as_deref(),
     )
     .await?;
+    let node_state = opts.state.nodes.get(&node_name)?;
+    cmd.set_process_setup(&node_state).await?;
+
+    if let Some(template) = &cmd.template {
+        let template = opts.state.node_templates.get(template)?.config().clone();
+        // Remove the node if its template can not be fully applied, instead of
+        // starting it half-configured
+        if let Err(e) = set_template_policies(&node_state, &template).await {
+            let _ = opts.state.nodes.delete_sigkill(&node_name, false);
+            return Err(e);
+        }
+    }
 
     let trust_context_path = match cmd.trust_context_opts.trust_context.clone() {
         Some(tc) => {
@@ -465,18 +848,26 @@ pub async fn spawn_background_node(
         opts,
         &node_name,
         &cmd.tcp_listener_address,
-        cmd.trust_context_opts.project_path.as_ref(),
-        cmd.trusted_identities.as_ref(),
-        cmd.trusted_identities_file.as_ref(),
-        cmd.reload_from_trusted_identities_file.as_ref(),
-        cmd.launch_config
-            .as_ref()
-            .map(|config| serde_json::to_string(config).unwrap()),
-        cmd.authority_identity.as_ref(),
-        cmd.credential.as_ref(),
-        trust_context_path.as_ref(),
-        cmd.trust_context_opts.project.as_ref(),
-        cmd.logging_to_file(),
+        SpawnNodeOptions {
+            project: cmd.trust_context_opts.project_path.as_ref(),
+            trusted_identities: cmd.trusted_identities.as_ref(),
+            trusted_identities_file: cmd.trusted_identities_file.as_ref(),
+            reload_from_trusted_identities_file: cmd.reload_from_trusted_identities_file.as_ref(),
+            launch_config: cmd
+                .launch_config
+                .as_ref()
+                .map(|config| serde_json::to_string(config).unwrap()),
+            authority_identity: cmd.authority_identity.as_ref(),
+            credential: cmd.credential.as_ref(),
+            trust_context: trust_context_path.as_ref(),
+            project_name: cmd.trust_context_opts.project.as_ref(),
+            logging_to_file: cmd.logging_to_file(),
+            shutdown_timeout: Some(cmd.shutdown_timeout),
+            management_access: cmd.management_access,
+            allowed_networks: &cmd.tcp_listener_allowed_network,
+            knock_attribute: cmd.tcp_listener_knock_attribute.as_ref(),
+            client_identity: cmd.client_identity.as_ref(),
+        },
     )?;
 
     Ok(())
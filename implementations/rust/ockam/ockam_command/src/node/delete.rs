@@ -1,11 +1,14 @@
 use clap::Args;
 use colorful::Colorful;
+use ockam::Context;
 use ockam_api::cli_state::StateDirTrait;
+use ockam_api::nodes::models::dry_run::DryRun;
+use ockam_api::nodes::BackgroundNode;
 
 use crate::node::get_default_node_name;
 use crate::node::util::{delete_all_nodes, delete_node};
 use crate::terminal::tui::DeleteMode;
-use crate::util::local_cmd;
+use crate::util::{api, local_cmd, node_rpc, print_dry_run};
 use crate::{docs, fmt_ok, fmt_warn, CommandGlobalOpts};
 
 const LONG_ABOUT: &str = include_str!("./static/delete/long_about.txt");
@@ -33,14 +36,45 @@ pub struct DeleteCommand {
     /// Confirm the deletion without prompting
     #[arg(display_order = 901, long, short)]
     yes: bool,
+
+    /// Delete the configuration and the data of the node(s) instead of moving them to the trash.
+    /// They can not be restored
+    #[arg(display_order = 901, long)]
+    permanent: bool,
+
+    /// Only show what would be stopped and deleted, including the portal sessions which would be cut
+    #[arg(display_order = 902, long, conflicts_with = "all")]
+    dry_run: bool,
 }
 
 impl DeleteCommand {
     pub fn run(self, opts: CommandGlobalOpts) {
-        local_cmd(run_impl(opts, self));
+        if self.dry_run {
+            node_rpc(run_dry_run_impl, (opts, self));
+        } else {
+            local_cmd(run_impl(opts, self));
+        }
     }
 }
 
+async fn run_dry_run_impl(
+    ctx: Context,
+    (opts, cmd): (CommandGlobalOpts, DeleteCommand),
+) -> miette::Result<()> {
+    let node_name = cmd
+        .node_name
+        .unwrap_or_else(|| get_default_node_name(&opts.state));
+    let dry_run = if opts.state.nodes.get(&node_name)?.is_running() {
+        let node = BackgroundNode::create(&ctx, &opts.state, &node_name).await?;
+        node.ask(&ctx, api::dry_run_delete_node()).await?
+    } else {
+        DryRun::new(vec![format!(
+            "delete the configuration and the data of the node {node_name}"
+        )])
+    };
+    print_dry_run(&opts, &dry_run)
+}
+
 fn run_impl(opts: CommandGlobalOpts, cmd: DeleteCommand) -> miette::Result<()> {
     let nodes_names = opts.state.nodes.list_items_names()?;
     if nodes_names.is_empty() {
@@ -72,7 +106,7 @@ fn run_impl(opts: CommandGlobalOpts, cmd: DeleteCommand) -> miette::Result<()> {
                 cmd.yes,
                 "Are you sure you want to delete all nodes?",
             )? {
-                delete_all_nodes(&opts, cmd.force)?;
+                delete_all_nodes(&opts, cmd.force, cmd.permanent)?;
                 opts.terminal
                     .stdout()
                     .plain(fmt_ok!("All nodes have been deleted"))
@@ -95,7 +129,7 @@ fn run_impl(opts: CommandGlobalOpts, cmd: DeleteCommand) -> miette::Result<()> {
                 let output = selected_node_names
                     .iter()
                     .map(|name| {
-                        if opts.state.nodes.delete_sigkill(name, cmd.force).is_ok() {
+                        if delete_node(&opts, name, cmd.force, cmd.permanent).is_ok() {
                             fmt_ok!("Node '{name}' deleted\n")
                         } else {
                             fmt_warn!("Failed to delete Node '{name}'\n")
@@ -111,7 +145,7 @@ fn run_impl(opts: CommandGlobalOpts, cmd: DeleteCommand) -> miette::Result<()> {
                 cmd.yes,
                 format!("Are you sure you want to delete the node {node_name}?"),
             )? {
-                delete_node(&opts, &node_name, cmd.force)?;
+                delete_node(&opts, &node_name, cmd.force, cmd.permanent)?;
                 opts.terminal
                     .stdout()
                     .plain(fmt_ok!("Node with name '{node_name}' has been deleted"))
@@ -126,7 +160,7 @@ fn run_impl(opts: CommandGlobalOpts, cmd: DeleteCommand) -> miette::Result<()> {
                 cmd.yes,
                 format!("Are you sure you want to delete the default node '{node_name}'?"),
             )? {
-                delete_node(&opts, &node_name, cmd.force)?;
+                delete_node(&opts, &node_name, cmd.force, cmd.permanent)?;
                 opts.terminal
                     .stdout()
                     .plain(fmt_ok!("Node with name '{node_name}' has been deleted"))
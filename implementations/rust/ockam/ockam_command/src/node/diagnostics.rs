@@ -0,0 +1,92 @@
+use std::path::PathBuf;
+
+use clap::Args;
+use colorful::Colorful;
+use miette::{miette, IntoDiagnostic};
+
+use ockam::Context;
+use ockam_api::cli_state::StateDirTrait;
+use ockam_api::nodes::models::diagnostics::{DiagnosticBundle, DEFAULT_DIAGNOSTICS_LOG_LINES};
+use ockam_api::nodes::service::add_logs_to_diagnostics;
+use ockam_api::nodes::BackgroundNode;
+
+use crate::node::{get_node_name, initialize_node_if_default};
+use crate::terminal::OckamColor;
+use crate::util::{api, node_rpc};
+use crate::{docs, fmt_ok, CommandGlobalOpts};
+
+const LONG_ABOUT: &str = include_str!("./static/diagnostics/long_about.txt");
+const PREVIEW_TAG: &str = include_str!("../static/preview_tag.txt");
+const AFTER_LONG_HELP: &str = include_str!("./static/diagnostics/after_long_help.txt");
+
+/// Export the diagnostic bundle of a node
+#[derive(Clone, Debug, Args)]
+#[command(
+    long_about = docs::about(LONG_ABOUT),
+    before_help = docs::before_help(PREVIEW_TAG),
+    after_long_help = docs::after_help(AFTER_LONG_HELP)
+)]
+pub struct DiagnosticsCommand {
+    /// Name of the node
+    node_name: Option<String>,
+
+    /// Path of the file where the bundle is written.
+    /// The default is <NODE_NAME>-diagnostics-<TIMESTAMP>.json.gz in the current directory
+    #[arg(long, short, value_name = "PATH")]
+    output: Option<PathBuf>,
+
+    /// Replace the IP addresses and host names found in the bundle
+    #[arg(long)]
+    redact_addresses: bool,
+
+    /// Number of lines kept from the end of each log file of the node
+    #[arg(long, value_name = "LINES", default_value_t = DEFAULT_DIAGNOSTICS_LOG_LINES)]
+    log_lines: u32,
+}
+
+impl DiagnosticsCommand {
+    pub fn run(self, opts: CommandGlobalOpts) {
+        initialize_node_if_default(&opts, &self.node_name);
+        node_rpc(run_impl, (opts, self))
+    }
+}
+
+async fn run_impl(
+    ctx: Context,
+    (opts, cmd): (CommandGlobalOpts, DiagnosticsCommand),
+) -> miette::Result<()> {
+    let node_name = get_node_name(&opts.state, &cmd.node_name);
+    let node_state = opts.state.nodes.get(&node_name)?;
+    if !node_state.is_running() {
+        return Err(miette!("The node '{}' is not running", node_name));
+    }
+
+    let node = BackgroundNode::create(&ctx, &opts.state, &node_name).await?;
+    let bundle: DiagnosticBundle = node
+        .ask(&ctx, api::get_diagnostics(cmd.redact_addresses))
+        .await?;
+    // the logs are read here rather than sent by the node, since they can be large
+    let data = add_logs_to_diagnostics(
+        &bundle,
+        &node_state,
+        cmd.log_lines as usize,
+        cmd.redact_addresses,
+    )
+    .into_diagnostic()?;
+
+    let path = cmd.output.unwrap_or_else(|| bundle.file_name().into());
+    std::fs::write(&path, &data).into_diagnostic()?;
+
+    let path = path.display().to_string();
+    opts.terminal
+        .stdout()
+        .plain(fmt_ok!(
+            "Exported the diagnostic bundle of the node {} to {}",
+            node_name.color(OckamColor::PrimaryResource.color()),
+            path.clone().color(OckamColor::PrimaryResource.color())
+        ))
+        .machine(&path)
+        .json(serde_json::json!({ "node": node_name, "path": path, "size": data.len() }))
+        .write_line()?;
+    Ok(())
+}
@@ -7,12 +7,15 @@ use serde::Serialize;
 use tokio::sync::Mutex;
 use tokio::try_join;
 
+use ockam::identity::TimestampInSeconds;
 use ockam::Context;
-use ockam_api::cli_state::StateDirTrait;
+use ockam_api::cli_state::nodes::NodeSortKey;
+use ockam_api::cli_state::{StateDirTrait, StateItemTrait};
 use ockam_api::nodes::models::base::NodeStatus;
 use ockam_api::nodes::BackgroundNode;
+use ockam_api::resource_list::ResourceList;
 
-use crate::output::Output;
+use crate::output::{human_readable_time, Output};
 use crate::terminal::OckamColor;
 use crate::util::{api, node_rpc};
 use crate::{docs, CommandGlobalOpts, Result};
@@ -28,7 +31,12 @@ const AFTER_LONG_HELP: &str = include_str!("./static/list/after_long_help.txt");
     before_help = docs::before_help(PREVIEW_TAG),
     after_long_help = docs::after_help(AFTER_LONG_HELP)
 )]
-pub struct ListCommand {}
+pub struct ListCommand {
+    /// Sort the nodes by name, or by their creation, last start or last stop time,
+    /// the oldest first. Possible values: name, created, started, stopped
+    #[arg(long, value_name = "KEY", default_value_t = NodeSortKey::default())]
+    sort_by: NodeSortKey,
+}
 
 impl ListCommand {
     pub fn run(self, options: CommandGlobalOpts) {
@@ -38,7 +46,7 @@ impl ListCommand {
 
 async fn run_impl(
     ctx: Context,
-    (opts, _cmd): (CommandGlobalOpts, ListCommand),
+    (opts, cmd): (CommandGlobalOpts, ListCommand),
 ) -> miette::Result<()> {
     // Before printing node states we verify them.
     // We send a QueryStatus request to every node on
@@ -47,17 +55,15 @@ async fn run_impl(
     // This should only happen if the node has failed in the past,
     // and has been restarted by something that is not this CLI.
     let mut default = String::new();
-    let node_names: Vec<_> = {
-        let nodes_states = opts.state.nodes.list()?;
-        // default node
-        if let Ok(state) = opts.state.nodes.default() {
-            default = state.name().to_string();
-        }
-        nodes_states.iter().map(|s| s.name().to_string()).collect()
-    };
+    let nodes_states = opts.state.nodes.list_sorted(cmd.sort_by)?;
+    // default node
+    if let Ok(state) = opts.state.nodes.default() {
+        default = state.name().to_string();
+    }
 
     let mut nodes: Vec<NodeListOutput> = Vec::new();
-    for node_name in node_names {
+    for node_state in nodes_states {
+        let node_name = node_state.name().to_string();
         let node = BackgroundNode::create(&ctx, &opts.state, &node_name).await?;
 
         let is_finished: Mutex<bool> = Mutex::new(false);
@@ -95,19 +101,23 @@ async fn run_impl(
 
         let (node_status, _) = try_join!(get_node_status, progress_output)?;
 
-        nodes.push(NodeListOutput::new(
-            node_status.node_name.to_string(),
-            node_status.status.to_string(),
-            node_status.pid,
-            node_status.node_name == default,
-        ));
+        let setup = node_state.config().setup();
+        nodes.push(NodeListOutput {
+            node_name: node_status.node_name.to_string(),
+            status: node_status.status.to_string(),
+            pid: node_status.pid,
+            is_default: node_status.node_name == default,
+            created_at: setup.created_at,
+            last_started_at: setup.last_started_at,
+            last_stopped_at: setup.last_stopped_at,
+        });
     }
 
     let plain = opts
         .terminal
         .build_list(&nodes, "Nodes", "No nodes found on this system.")?;
 
-    let json = serde_json::to_string_pretty(&nodes).into_diagnostic()?;
+    let json = ResourceList::new(nodes).to_json().into_diagnostic()?;
 
     opts.terminal
         .stdout()
@@ -124,17 +134,9 @@ pub struct NodeListOutput {
     pub status: String,
     pub pid: i32,
     pub is_default: bool,
-}
-
-impl NodeListOutput {
-    pub fn new(node_name: String, status: String, pid: i32, is_default: bool) -> Self {
-        Self {
-            node_name,
-            status,
-            pid,
-            is_default,
-        }
-    }
+    pub created_at: Option<u64>,
+    pub last_started_at: Option<u64>,
+    pub last_stopped_at: Option<u64>,
 }
 
 impl Output for NodeListOutput {
@@ -159,7 +161,7 @@ impl Output for NodeListOutput {
             false => "".to_string(),
         };
 
-        let output = formatdoc! {"
+        let mut output = formatdoc! {"
         Node {node_name}{default} {status}
         {pid}",
         node_name = self
@@ -167,6 +169,18 @@ impl Output for NodeListOutput {
             .to_string()
             .color(OckamColor::PrimaryResource.color()),
         };
+        for (label, time) in [
+            ("Created at", self.created_at),
+            ("Last started at", self.last_started_at),
+            ("Last stopped at", self.last_stopped_at),
+        ] {
+            if let Some(time) = time {
+                output.push_str(&format!(
+                    "\n{label} {}",
+                    human_readable_time(TimestampInSeconds(time))
+                ));
+            }
+        }
 
         Ok(output)
     }
@@ -4,24 +4,32 @@ use colorful::Colorful;
 pub use create::CreateCommand;
 use default::DefaultCommand;
 use delete::DeleteCommand;
+use diagnostics::DiagnosticsCommand;
 use list::ListCommand;
 use logs::LogCommand;
 use ockam_api::cli_state::{CliState, StateDirTrait};
+use peers::PeersCommand;
+use restore::RestoreCommand;
 use show::ShowCommand;
 use start::StartCommand;
 use stop::StopCommand;
+use template::TemplateCommand;
 
 use crate::{docs, fmt_log, terminal::OckamColor, CommandGlobalOpts, PARSER_LOGS};
 
 mod create;
 mod default;
 mod delete;
+mod diagnostics;
 mod list;
 mod logs;
 mod models;
+mod peers;
+mod restore;
 mod show;
 mod start;
 mod stop;
+pub mod template;
 pub mod util;
 pub use create::*;
 
@@ -48,9 +56,15 @@ pub enum NodeSubcommand {
     #[command(display_order = 800)]
     Delete(DeleteCommand),
     #[command(display_order = 800)]
+    Restore(RestoreCommand),
+    #[command(display_order = 800)]
     List(ListCommand),
     #[command(display_order = 800)]
     Logs(LogCommand),
+    #[command(display_order = 800)]
+    Diagnostics(DiagnosticsCommand),
+    #[command(display_order = 800)]
+    Peers(PeersCommand),
     Show(ShowCommand),
     #[command(display_order = 800)]
     Start(StartCommand),
@@ -58,6 +72,8 @@ pub enum NodeSubcommand {
     Stop(StopCommand),
     #[command(display_order = 800)]
     Default(DefaultCommand),
+    #[command(display_order = 800)]
+    Template(TemplateCommand),
 }
 
 impl NodeCommand {
@@ -65,12 +81,16 @@ impl NodeCommand {
         match self.subcommand {
             NodeSubcommand::Create(c) => c.run(options),
             NodeSubcommand::Delete(c) => c.run(options),
+            NodeSubcommand::Restore(c) => c.run(options),
             NodeSubcommand::List(c) => c.run(options),
             NodeSubcommand::Show(c) => c.run(options),
             NodeSubcommand::Start(c) => c.run(options),
             NodeSubcommand::Stop(c) => c.run(options),
             NodeSubcommand::Logs(c) => c.run(options),
+            NodeSubcommand::Diagnostics(c) => c.run(options),
+            NodeSubcommand::Peers(c) => c.run(options),
             NodeSubcommand::Default(c) => c.run(options),
+            NodeSubcommand::Template(c) => c.run(options),
         }
     }
 }
@@ -2,6 +2,9 @@ use std::fmt::Display;
 
 use colorful::Colorful;
 
+use ockam_api::nodes::models::base::{
+    ClockSkewList, PreWarmStepStatus, ServiceReadinessStatus, StorageHealthStatus,
+};
 use ockam_multiaddr::{
     proto::{DnsAddr, Node, Tcp},
     MultiAddr,
@@ -31,6 +34,13 @@ pub struct ShowNodeResponse {
     pub inlets: Vec<ShowInletStatus>,
     pub outlets: Vec<ShowOutletStatus>,
     pub services: Vec<ShowServiceStatus>,
+    pub readiness: Vec<ServiceReadinessStatus>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pre_warm: Vec<PreWarmStepStatus>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub storage: Vec<StorageHealthStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clock_skew: Option<ClockSkewList>,
 }
 #[derive(Debug, Serialize)]
 pub struct RouteToNode {
@@ -71,6 +81,10 @@ impl ShowNodeResponse {
             inlets: Default::default(),
             outlets: Default::default(),
             services: Default::default(),
+            readiness: Default::default(),
+            pre_warm: Default::default(),
+            storage: Default::default(),
+            clock_skew: None,
         }
     }
 }
@@ -152,6 +166,64 @@ impl Display for ShowNodeResponse {
             }
         }
 
+        if !self.readiness.is_empty() {
+            writeln!(buffer, "  Readiness:")?;
+            for e in &self.readiness {
+                if e.readiness == "ready" {
+                    writeln!(buffer, "    {}: {}", e.service, e.readiness)?;
+                } else {
+                    writeln!(
+                        buffer,
+                        "    {}: {}",
+                        e.service,
+                        e.readiness.clone().light_red()
+                    )?;
+                }
+            }
+        }
+
+        if !self.pre_warm.is_empty() {
+            writeln!(buffer, "  Pre-warm:")?;
+            for e in &self.pre_warm {
+                if e.status == "ready" {
+                    writeln!(buffer, "    {}: {}", e.step, e.status)?;
+                } else {
+                    writeln!(buffer, "    {}: {}", e.step, e.status.clone().light_red())?;
+                }
+            }
+        }
+
+        if !self.storage.is_empty() {
+            writeln!(buffer, "  Storage:")?;
+            for e in &self.storage {
+                if e.health == "healthy" {
+                    writeln!(buffer, "    {}: {}", e.name, e.health)?;
+                } else {
+                    writeln!(buffer, "    {}: {}", e.name, e.health.clone().light_red())?;
+                }
+            }
+        }
+
+        if let Some(clock_skew) = &self.clock_skew {
+            writeln!(
+                buffer,
+                "  Clock Skew (tolerance: {}s):",
+                clock_skew.tolerance
+            )?;
+            for e in &clock_skew.list {
+                writeln!(buffer, "    Identity: {}", e.identifier)?;
+                if e.exceeds_tolerance {
+                    writeln!(
+                        buffer,
+                        "      Skew: {}",
+                        format!("{}s (exceeds the tolerance)", e.skew).light_red()
+                    )?;
+                } else {
+                    writeln!(buffer, "      Skew: {}s", e.skew)?;
+                }
+            }
+        }
+
         Ok(())
     }
 }
@@ -0,0 +1,115 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use clap::Args;
+use colorful::Colorful;
+use miette::{miette, IntoDiagnostic};
+use time::OffsetDateTime;
+
+use ockam::identity::Identifier;
+use ockam::Context;
+use ockam_api::cli_state::StateDirTrait;
+use ockam_api::nodes::models::peers::{PeerList, PeerStatus};
+use ockam_api::nodes::BackgroundNode;
+
+use crate::node::{get_node_name, initialize_node_if_default};
+use crate::output::Output;
+use crate::terminal::OckamColor;
+use crate::util::duration::duration_parser;
+use crate::util::{api, node_rpc};
+use crate::{docs, CommandGlobalOpts, Result};
+
+const LONG_ABOUT: &str = include_str!("./static/peers/long_about.txt");
+const PREVIEW_TAG: &str = include_str!("../static/preview_tag.txt");
+const AFTER_LONG_HELP: &str = include_str!("./static/peers/after_long_help.txt");
+
+/// List the identities a node established secure channels with
+#[derive(Clone, Debug, Args)]
+#[command(
+    long_about = docs::about(LONG_ABOUT),
+    before_help = docs::before_help(PREVIEW_TAG),
+    after_long_help = docs::after_help(AFTER_LONG_HELP)
+)]
+pub struct PeersCommand {
+    /// Name of the node
+    node_name: Option<String>,
+
+    /// Only show the secure channels established with this identity
+    #[arg(long, value_name = "IDENTIFIER")]
+    identifier: Option<Identifier>,
+
+    /// Only list the identities seen over this duration, for example 30m, 12h or 7d
+    #[arg(long, value_name = "DURATION", value_parser = duration_parser, conflicts_with = "identifier")]
+    since: Option<Duration>,
+}
+
+impl PeersCommand {
+    pub fn run(self, opts: CommandGlobalOpts) {
+        initialize_node_if_default(&opts, &self.node_name);
+        node_rpc(run_impl, (opts, self))
+    }
+}
+
+async fn run_impl(
+    ctx: Context,
+    (opts, cmd): (CommandGlobalOpts, PeersCommand),
+) -> miette::Result<()> {
+    let node_name = get_node_name(&opts.state, &cmd.node_name);
+    if !opts.state.nodes.get(&node_name)?.is_running() {
+        return Err(miette!("The node '{}' is not running", node_name));
+    }
+    let node = BackgroundNode::create(&ctx, &opts.state, &node_name).await?;
+
+    if let Some(identifier) = &cmd.identifier {
+        let peer: PeerStatus = node.ask(&ctx, api::get_peer(identifier)).await?;
+        opts.terminal
+            .stdout()
+            .plain(peer.output()?)
+            .json(serde_json::to_string_pretty(&peer).into_diagnostic()?)
+            .write_line()?;
+        return Ok(());
+    }
+
+    let seen_since = match cmd.since {
+        Some(since) => {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .into_diagnostic()?
+                .as_secs();
+            Some(now.saturating_sub(since.as_secs()))
+        }
+        None => None,
+    };
+    let peers: PeerList = node.ask(&ctx, api::list_peers(seen_since)).await?;
+    let list = opts.terminal.build_list(
+        &peers.list,
+        &format!("Identities seen by {node_name}"),
+        &format!("No secure channels were established by {node_name}."),
+    )?;
+    opts.terminal
+        .stdout()
+        .plain(list)
+        .json(serde_json::to_string_pretty(&peers).into_diagnostic()?)
+        .write_line()?;
+    Ok(())
+}
+
+impl Output for PeerStatus {
+    fn output(&self) -> Result<String> {
+        let time = |timestamp: u64| {
+            OffsetDateTime::from_unix_timestamp(timestamp as i64)
+                .map_or_else(|_| timestamp.to_string(), |t| t.to_string())
+        };
+        Ok(format!(
+            "{}\n\
+             First seen {}, last seen {}\n\
+             Channels initiated {}, accepted {}",
+            self.identifier
+                .as_str()
+                .color(OckamColor::PrimaryResource.color()),
+            time(self.first_seen),
+            time(self.last_seen),
+            self.channels_initiated,
+            self.channels_accepted,
+        ))
+    }
+}
@@ -1,10 +1,15 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
 use clap::Args;
 use miette::IntoDiagnostic;
+use ockam_api::nodes::models::base::NodeStatus;
 use ockam_api::nodes::models::secure_channel::SecureChannelListenersList;
 use ockam_api::nodes::models::services::ServiceList;
+use ockam_api::nodes::models::stats::{NodeStats, NodeStatsList};
 use ockam_api::nodes::models::transport::TransportList;
 use ockam_api::nodes::BackgroundNode;
 use ockam_node::Context;
+use time::OffsetDateTime;
 use tokio_retry::strategy::FixedInterval;
 use tracing::{info, trace, warn};
 
@@ -13,6 +18,8 @@ use ockam_api::nodes::models::portal::{InletList, OutletList};
 
 use crate::node::get_node_name;
 use crate::node::util::check_default;
+use crate::output::Output;
+use crate::util::duration::duration_parser;
 use crate::util::{api, node_rpc};
 use crate::{docs, CommandGlobalOpts, Result};
 
@@ -40,6 +47,14 @@ pub struct ShowCommand {
     /// Name of the node to retrieve the details from
     #[arg()]
     node_name: Option<String>,
+
+    /// Show the statistics recorded by the node instead of its details
+    #[arg(long)]
+    history: bool,
+
+    /// How far back the statistics are shown, for example 30m, 12h or 7d
+    #[arg(long, value_name = "DURATION", default_value = "1h", value_parser = duration_parser, requires = "history")]
+    since: Duration,
 }
 
 impl ShowCommand {
@@ -54,6 +69,9 @@ async fn run_impl(
 ) -> miette::Result<()> {
     let node_name = get_node_name(&opts.state, &cmd.node_name);
     let mut node = BackgroundNode::create(&ctx, &opts.state, &node_name).await?;
+    if cmd.history {
+        return print_history(&opts, &ctx, &node, cmd.since).await;
+    }
     let is_default = check_default(&opts, &node_name);
     print_query_status(&opts, &ctx, &node_name, &mut node, false, is_default).await?;
     Ok(())
@@ -101,6 +119,12 @@ pub async fn print_query_status(
                 Err(_) => String::from("None"),
             });
 
+            // Get the readiness of the services started by the node
+            let status: NodeStatus = node.ask(ctx, api::query_status()).await?;
+            node_info.readiness = status.services.unwrap_or_default();
+            node_info.pre_warm = status.pre_warm.unwrap_or_default();
+            node_info.storage = status.storage.unwrap_or_default();
+
             // Get list of services for the node
             let services: ServiceList = node.ask(ctx, api::list_services()).await?;
             node_info.services = services
@@ -128,15 +152,14 @@ pub async fn print_query_status(
 
             // Get list of inlets
             let inlets: InletList = node.ask(ctx, api::list_inlets()).await?;
-            node_info.inlets = inlets.list.into_iter().map(ShowInletStatus::from).collect();
+            node_info.inlets = inlets.into_iter().map(ShowInletStatus::from).collect();
 
             // Get list of outlets
             let outlets: OutletList = node.ask(ctx, api::list_outlets()).await?;
-            node_info.outlets = outlets
-                .list
-                .into_iter()
-                .map(ShowOutletStatus::from)
-                .collect();
+            node_info.outlets = outlets.into_iter().map(ShowOutletStatus::from).collect();
+
+            // Get the clock skews measured with the issuers of credentials
+            node_info.clock_skew = Some(node.ask(ctx, api::list_clock_skews()).await?);
 
             node_info
         };
@@ -151,6 +174,58 @@ pub async fn print_query_status(
     Ok(())
 }
 
+/// Print the statistics recorded by a node over the last `since` duration
+async fn print_history(
+    opts: &CommandGlobalOpts,
+    ctx: &Context,
+    node: &BackgroundNode,
+    since: Duration,
+) -> miette::Result<()> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .into_diagnostic()?
+        .as_secs();
+    let stats: NodeStatsList = node
+        .ask(
+            ctx,
+            api::get_node_stats(now.saturating_sub(since.as_secs())),
+        )
+        .await?;
+    let list = opts.terminal.build_list(
+        &stats.list,
+        &format!("Statistics of {}", node.node_name()),
+        "No statistics were recorded over this period.",
+    )?;
+    opts.terminal
+        .clone()
+        .stdout()
+        .plain(list)
+        .json(serde_json::to_string_pretty(&stats).into_diagnostic()?)
+        .write_line()?;
+    Ok(())
+}
+
+impl Output for NodeStats {
+    fn output(&self) -> Result<String> {
+        let time = OffsetDateTime::from_unix_timestamp(self.timestamp as i64)
+            .map_or_else(|_| self.timestamp.to_string(), |t| t.to_string());
+        Ok(format!(
+            "{time} ({}s)\n\
+             Secure channels {}, inlets {}, outlets {}, portal sessions {}\n\
+             Portal bytes received {}, sent {}\n\
+             Memory {} MB",
+            self.duration,
+            self.secure_channels,
+            self.inlets,
+            self.outlets,
+            self.portal_sessions,
+            self.portal_bytes_received,
+            self.portal_bytes_sent,
+            self.memory / (1024 * 1024),
+        ))
+    }
+}
+
 /// Send message(s) to a node to determine if it is 'up' and
 /// responding to requests.
 ///
@@ -158,6 +233,7 @@ pub async fn print_query_status(
 /// appear to be 'up', retry the test at time intervals up to
 /// a maximum number of retries. A use case for this is to
 /// allow a node time to start up and become ready.
+/// The node is only ready once its default services are started.
 pub async fn is_node_up(
     ctx: &Context,
     node_name: &str,
@@ -175,6 +251,7 @@ pub async fn is_node_up(
 
     let cli_state = cli_state.clone();
     let now = std::time::Instant::now();
+    let mut is_up = false;
     for timeout_duration in retries {
         let node_state = cli_state.nodes.get(node_name)?;
         // The node is down if it has not stored its default tcp listener in its state file.
@@ -187,19 +264,31 @@ pub async fn is_node_up(
         // Test if node is up
         // If node is down, we expect it won't reply and the timeout
         // will trigger the next loop (i.e. no need to sleep here).
-        let result = node
+        let result: miette::Result<NodeStatus> = node
             .set_timeout(timeout_duration)
-            .tell(ctx, api::query_status())
+            .ask(ctx, api::query_status())
             .await;
-        if result.is_ok() {
-            let elapsed = now.elapsed();
-            info!(%node_name, ?elapsed, "node is up");
-            return Ok(true);
-        } else {
-            trace!(%node_name, "node is initializing");
-            tokio::time::sleep(timeout_duration).await;
+        match result {
+            Ok(status) if wait_until_ready && status.is_starting() => {
+                trace!(%node_name, "node is starting its services");
+                is_up = true;
+                tokio::time::sleep(timeout_duration).await;
+            }
+            Ok(_) => {
+                let elapsed = now.elapsed();
+                info!(%node_name, ?elapsed, "node is up");
+                return Ok(true);
+            }
+            Err(_) => {
+                trace!(%node_name, "node is initializing");
+                tokio::time::sleep(timeout_duration).await;
+            }
         }
     }
+    if is_up {
+        warn!(%node_name, "node is up but some of its services are still starting");
+        return Ok(true);
+    }
     warn!(%node_name, "node didn't respond in time");
     Ok(false)
 }
@@ -6,7 +6,7 @@ use ockam_api::nodes::BackgroundNode;
 use ockam_node::Context;
 
 use crate::node::show::print_query_status;
-use crate::node::util::{check_default, spawn_node};
+use crate::node::util::{check_default, spawn_node, SpawnNodeOptions};
 use crate::node::{get_node_name, initialize_node_if_default};
 use crate::util::node_rpc;
 use crate::{docs, fmt_err, CommandGlobalOpts};
@@ -61,20 +61,15 @@ async fn run_impl(
     opts.global_args.verbose = node_setup.verbose;
 
     // Restart node
+    // Restarted nodes log to files, their other options are read from their configuration
     spawn_node(
         &opts,
-        &node_name,                                    // The selected node name
-        &node_setup.api_transport()?.addr.to_string(), // The selected node api address
-        None,                                          // No project information available
-        None,                                          // No trusted identities
-        None,                                          // "
-        None,                                          // "
-        None,                                          // Launch config
-        None,                                          // Authority Identity
-        None,                                          // Credential
-        None,                                          // Trust Context
-        None,                                          // Project Name
-        true,                                          // Restarted nodes will log to files
+        &node_name,
+        &node_setup.api_transport()?.addr.to_string(),
+        SpawnNodeOptions {
+            logging_to_file: true,
+            ..Default::default()
+        },
     )?;
 
     // Print node status
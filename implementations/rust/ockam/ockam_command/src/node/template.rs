@@ -0,0 +1,143 @@
+use std::path::PathBuf;
+
+use clap::{Args, Subcommand};
+use colorful::Colorful;
+use miette::IntoDiagnostic;
+
+use ockam_abac::{Action, PolicyStorage, Resource};
+use ockam_api::cli_state::traits::StateDirTrait;
+use ockam_api::cli_state::{NodeState, NodeTemplateConfig};
+
+use crate::node::CreateCommand;
+use crate::terminal::OckamColor;
+use crate::util::local_cmd;
+use crate::{fmt_ok, CommandGlobalOpts};
+
+/// Manage the templates used to create nodes
+#[derive(Clone, Debug, Args)]
+#[command(arg_required_else_help = true, subcommand_required = true)]
+pub struct TemplateCommand {
+    #[command(subcommand)]
+    subcommand: TemplateSubcommand,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+enum TemplateSubcommand {
+    Import(ImportCommand),
+    Export(ExportCommand),
+    List(ListCommand),
+    Delete(DeleteCommand),
+}
+
+/// Import a node template from a JSON file
+#[derive(Clone, Debug, Args)]
+struct ImportCommand {
+    /// Name of the template
+    name: String,
+
+    /// Path of the JSON file describing the template
+    #[arg(long, value_name = "PATH")]
+    file: PathBuf,
+}
+
+/// Export a node template as JSON
+#[derive(Clone, Debug, Args)]
+struct ExportCommand {
+    /// Name of the template
+    name: String,
+
+    /// Write the template to a file instead of the standard output
+    #[arg(long, value_name = "PATH")]
+    output: Option<PathBuf>,
+}
+
+/// List the node templates
+#[derive(Clone, Debug, Args)]
+struct ListCommand;
+
+/// Delete a node template
+#[derive(Clone, Debug, Args)]
+struct DeleteCommand {
+    /// Name of the template
+    name: String,
+}
+
+impl TemplateCommand {
+    pub fn run(self, opts: CommandGlobalOpts) {
+        local_cmd(run_impl(opts, self));
+    }
+}
+
+fn run_impl(opts: CommandGlobalOpts, cmd: TemplateCommand) -> miette::Result<()> {
+    let templates = &opts.state.node_templates;
+    match cmd.subcommand {
+        TemplateSubcommand::Import(c) => {
+            let contents = std::fs::read_to_string(&c.file).into_diagnostic()?;
+            templates.import(&c.name, &contents)?;
+            opts.terminal
+                .stdout()
+                .plain(fmt_ok!(
+                    "Imported the node template {}",
+                    c.name.color(OckamColor::PrimaryResource.color())
+                ))
+                .write_line()?;
+        }
+        TemplateSubcommand::Export(c) => {
+            let contents = templates.export(&c.name)?;
+            match c.output {
+                Some(path) => std::fs::write(path, contents).into_diagnostic()?,
+                None => opts.terminal.stdout().plain(contents).write_line()?,
+            }
+        }
+        TemplateSubcommand::List(_) => {
+            let names = templates.list_items_names()?;
+            opts.terminal
+                .stdout()
+                .plain(names.join("\n"))
+                .json(serde_json::to_string_pretty(&names).into_diagnostic()?)
+                .write_line()?;
+        }
+        TemplateSubcommand::Delete(c) => {
+            // fail if the template doesn't exist
+            templates.get(&c.name)?;
+            templates.delete(&c.name)?;
+            opts.terminal
+                .stdout()
+                .plain(fmt_ok!(
+                    "Deleted the node template {}",
+                    c.name.color(OckamColor::PrimaryResource.color())
+                ))
+                .write_line()?;
+        }
+    }
+    Ok(())
+}
+
+/// Create a background node configured by a template: its vault and identity,
+/// its transports, its startup services and its policies
+pub fn create_node_from_template(opts: CommandGlobalOpts, template_name: &str, node_name: &str) {
+    let cmd = CreateCommand {
+        node_name: node_name.to_string(),
+        template: Some(template_name.to_string()),
+        ..Default::default()
+    };
+    cmd.run(opts)
+}
+
+/// Write the policies of a template to the policies storage of a node.
+/// This is done before the node is started, so that it never serves requests without them
+pub(crate) async fn set_template_policies(
+    node_state: &NodeState,
+    template: &NodeTemplateConfig,
+) -> miette::Result<()> {
+    let storage = node_state.policies_storage().await?;
+    for policy in &template.policies {
+        let resource = Resource::new(&policy.resource);
+        let action = Action::new(&policy.action);
+        storage
+            .set_policy(&resource, &action, &policy.expression()?)
+            .await
+            .into_diagnostic()?;
+    }
+    Ok(())
+}
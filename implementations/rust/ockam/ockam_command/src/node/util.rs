@@ -2,13 +2,16 @@ use std::env::current_exe;
 use std::fs::OpenOptions;
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
+use std::time::Duration;
 
 use miette::Context as _;
 use miette::{miette, IntoDiagnostic};
 use rand::random;
 
 use ockam_api::cli_state::StateDirTrait;
+use ockam_api::nodes::service::ManagementAccess;
 use ockam_core::env::get_env_with_default;
+use ockam_transport_tcp::AllowedNetwork;
 
 use crate::util::api::TrustContextOpts;
 use crate::CommandGlobalOpts;
@@ -29,16 +32,31 @@ impl Default for NodeManagerDefaults {
     }
 }
 
-pub fn delete_node(opts: &CommandGlobalOpts, name: &str, force: bool) -> miette::Result<()> {
-    opts.state.nodes.delete_sigkill(name, force)?;
+/// Delete a node. Unless the deletion is permanent, the node is kept in the trash,
+/// from where it can be restored
+pub fn delete_node(
+    opts: &CommandGlobalOpts,
+    name: &str,
+    force: bool,
+    permanent: bool,
+) -> miette::Result<()> {
+    if permanent {
+        opts.state.nodes.delete_sigkill(name, force)?;
+    } else {
+        opts.state.nodes.soft_delete_sigkill(name, force)?;
+    }
     Ok(())
 }
 
-pub fn delete_all_nodes(opts: &CommandGlobalOpts, force: bool) -> miette::Result<()> {
+pub fn delete_all_nodes(
+    opts: &CommandGlobalOpts,
+    force: bool,
+    permanent: bool,
+) -> miette::Result<()> {
     let nodes_states = opts.state.nodes.list()?;
     let mut deletion_errors = Vec::new();
     for s in nodes_states {
-        if let Err(e) = opts.state.nodes.delete_sigkill(s.name(), force) {
+        if let Err(e) = delete_node(opts, s.name(), force, permanent) {
             deletion_errors.push((s.name().to_string(), e));
         }
     }
@@ -59,22 +77,50 @@ pub fn check_default(opts: &CommandGlobalOpts, name: &str) -> bool {
 }
 
 /// A utility function to spawn a new node into foreground mode
-#[allow(clippy::too_many_arguments)]
+/// Options of a node process started with [`spawn_node`].
+/// The options which are not set are read from the configuration of the node
+#[derive(Default)]
+pub struct SpawnNodeOptions<'a> {
+    pub project: Option<&'a PathBuf>,
+    pub trusted_identities: Option<&'a String>,
+    pub trusted_identities_file: Option<&'a PathBuf>,
+    pub reload_from_trusted_identities_file: Option<&'a PathBuf>,
+    pub launch_config: Option<String>,
+    pub authority_identity: Option<&'a String>,
+    pub credential: Option<&'a String>,
+    pub trust_context: Option<&'a PathBuf>,
+    pub project_name: Option<&'a String>,
+    pub logging_to_file: bool,
+    pub shutdown_timeout: Option<Duration>,
+    pub management_access: Option<ManagementAccess>,
+    pub allowed_networks: &'a [AllowedNetwork],
+    pub knock_attribute: Option<&'a (String, String)>,
+    pub client_identity: Option<&'a String>,
+}
+
 pub fn spawn_node(
     opts: &CommandGlobalOpts,
     name: &str,
     address: &str,
-    project: Option<&PathBuf>,
-    trusted_identities: Option<&String>,
-    trusted_identities_file: Option<&PathBuf>,
-    reload_from_trusted_identities_file: Option<&PathBuf>,
-    launch_config: Option<String>,
-    authority_identity: Option<&String>,
-    credential: Option<&String>,
-    trust_context: Option<&PathBuf>,
-    project_name: Option<&String>,
-    logging_to_file: bool,
+    options: SpawnNodeOptions,
 ) -> miette::Result<()> {
+    let SpawnNodeOptions {
+        project,
+        trusted_identities,
+        trusted_identities_file,
+        reload_from_trusted_identities_file,
+        launch_config,
+        authority_identity,
+        credential,
+        trust_context,
+        project_name,
+        logging_to_file,
+        shutdown_timeout,
+        management_access,
+        allowed_networks,
+        knock_attribute,
+        client_identity,
+    } = options;
     let mut args = vec![
         match opts.global_args.verbose {
             0 => "-vv".to_string(),
@@ -149,6 +195,35 @@ pub fn spawn_node(
         args.push(project_name.to_string());
     }
 
+    if let Some(shutdown_timeout) = shutdown_timeout {
+        args.push("--shutdown-timeout".to_string());
+        args.push(format!("{}ms", shutdown_timeout.as_millis()));
+    }
+
+    if let Some(management_access) = management_access {
+        args.push("--management-access".to_string());
+        args.push(management_access.to_string());
+    }
+
+    for network in allowed_networks {
+        args.push("--tcp-listener-allowed-network".to_string());
+        args.push(network.to_string());
+    }
+
+    if let Some((key, value)) = knock_attribute {
+        args.push("--tcp-listener-knock-attribute".to_string());
+        args.push(format!("{key}={value}"));
+    }
+
+    if let Some(client_identity) = client_identity {
+        args.push("--client-identity".to_string());
+        args.push(client_identity.to_string());
+    }
+
+    // Additional arguments recorded when the node was created
+    let node_state = opts.state.nodes.get(name)?;
+    args.extend(node_state.config().setup().extra_args.iter().cloned());
+
     args.push(name.to_owned());
 
     run_ockam(opts, name, args, logging_to_file)
@@ -170,6 +245,13 @@ pub fn run_ockam(
 
     let mut cmd = Command::new(ockam_exe);
 
+    // Working directory recorded when the node was created. The environment variables are
+    // decrypted and set by the node process itself
+    let setup = node_state.config().setup();
+    if let Some(working_directory) = &setup.working_directory {
+        cmd.current_dir(working_directory);
+    }
+
     if logging_to_file {
         let (mlog, elog) = { (node_state.stdout_log(), node_state.stderr_log()) };
         let main_log_file = OpenOptions::new()
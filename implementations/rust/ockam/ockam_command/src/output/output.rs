@@ -14,9 +14,10 @@ use ockam::identity::models::{
 use ockam::identity::{Credential, Identifier, Identity, TimestampInSeconds};
 use serde::{Serialize, Serializer};
 
-use ockam_api::cli_state::{ProjectConfigCompact, StateItemTrait, VaultState};
+use ockam_api::cli_state::{DeletedItem, ProjectConfigCompact, StateItemTrait, VaultState};
 use ockam_api::cloud::project::Project;
 use ockam_api::cloud::space::Space;
+use ockam_api::nodes::models::dry_run::DryRun;
 use ockam_api::nodes::models::portal::{InletStatus, OutletStatus};
 use ockam_api::nodes::models::secure_channel::{
     CreateSecureChannelResponse, ShowSecureChannelResponse,
@@ -245,7 +246,7 @@ impl Output for ShowSecureChannelResponse {
     fn output(&self) -> Result<String> {
         let s = match &self.channel {
             Some(addr) => {
-                format!(
+                let mut s = format!(
                     "\n  Secure Channel:\n{} {}\n{} {}\n{} {}",
                     "  •         At: ".light_magenta(),
                     route_to_multiaddr(&route![addr.to_string()])
@@ -262,7 +263,42 @@ impl Output for ShowSecureChannelResponse {
                         .map(|id| id.clone().light_yellow().to_string())
                         .collect::<Vec<String>>()
                         .join("\n\t")
-                )
+                );
+                if let Some(replay) = &self.replay_protection {
+                    s.push_str(&format!(
+                        "\n{} {}",
+                        "  •     Replay: ".light_magenta(),
+                        format!(
+                            "window {}, {} out of window, {} duplicates, {} reordered",
+                            replay.window,
+                            replay.out_of_window,
+                            replay.duplicates,
+                            replay.reordered
+                        )
+                        .light_yellow()
+                    ));
+                }
+                if let Some(compression) = &self.compression {
+                    let description = match &compression.algorithm {
+                        Some(algorithm) => format!(
+                            "{algorithm}, {} compressed and {} uncompressed messages{}",
+                            compression.compressed_messages,
+                            compression.uncompressed_messages,
+                            compression
+                                .ratio()
+                                .map(|ratio| format!(", ratio {:.2}", ratio))
+                                .unwrap_or_default()
+                        ),
+                        None => "none".to_string(),
+                    };
+                    s.push_str(&format!(
+                        "
+{} {}",
+                        "  •Compression: ".light_magenta(),
+                        description.light_yellow()
+                    ));
+                }
+                s
             }
             None => format!("{}", "Channel not found".red()),
         };
@@ -361,6 +397,59 @@ From {} to {}"#,
     }
 }
 
+impl Output for DeletedItem {
+    fn output(&self) -> Result<String> {
+        let deleted_at = time::OffsetDateTime::from_unix_timestamp(self.deleted_at as i64)
+            .map_or_else(|_| self.deleted_at.to_string(), |t| t.to_string());
+        Ok(format!(
+            "{}\nDeleted at {deleted_at}",
+            self.name
+                .as_str()
+                .color(OckamColor::PrimaryResource.color())
+        ))
+    }
+}
+
+impl Output for DryRun {
+    fn output(&self) -> Result<String> {
+        let mut output = String::new();
+        if self.changes.is_empty() {
+            writeln!(output, "Nothing would be changed")?;
+            return Ok(output);
+        }
+        writeln!(output, "Dry run, nothing was changed. The command would:")?;
+        for change in &self.changes {
+            writeln!(output, "  - {change}")?;
+        }
+        if !self.dependents.is_empty() {
+            writeln!(output, "Affected resources:")?;
+            for dependent in &self.dependents {
+                writeln!(
+                    output,
+                    "  - {}",
+                    dependent.color(OckamColor::PrimaryResource.color())
+                )?;
+            }
+        }
+        if !self.sessions.is_empty() {
+            writeln!(output, "Affected portal sessions:")?;
+            for session in &self.sessions {
+                writeln!(
+                    output,
+                    "  - {} {} with {}",
+                    session.portal_type,
+                    session
+                        .address
+                        .as_str()
+                        .color(OckamColor::PrimaryResource.color()),
+                    session.peer.as_deref().unwrap_or("unknown peer")
+                )?;
+            }
+        }
+        Ok(output)
+    }
+}
+
 impl Output for VaultState {
     fn output(&self) -> Result<String> {
         let mut output = String::new();
@@ -377,7 +466,7 @@ impl Output for VaultState {
     }
 }
 
-fn human_readable_time(time: TimestampInSeconds) -> String {
+pub(crate) fn human_readable_time(time: TimestampInSeconds) -> String {
     use time::format_description::well_known::iso8601::*;
     use time::Error::Format;
     use time::OffsetDateTime;
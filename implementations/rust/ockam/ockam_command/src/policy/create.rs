@@ -2,13 +2,14 @@ use clap::Args;
 
 use ockam::Context;
 use ockam_abac::{Action, Expr, Resource};
+use ockam_api::nodes::models::dry_run::DryRun;
 use ockam_api::nodes::models::policy::Policy;
 use ockam_api::nodes::BackgroundNode;
 use ockam_core::api::Request;
 
 use crate::node::get_node_name;
 use crate::policy::policy_path;
-use crate::util::{node_rpc, parse_node_name};
+use crate::util::{node_rpc, parse_node_name, print_dry_run};
 use crate::CommandGlobalOpts;
 
 #[derive(Clone, Debug, Args)]
@@ -24,6 +25,10 @@ pub struct CreateCommand {
 
     #[arg(short, long)]
     expression: Expr,
+
+    /// Only show the policy which would be replaced, and the inlets and outlets using it
+    #[arg(long)]
+    dry_run: bool,
 }
 
 impl CreateCommand {
@@ -44,8 +49,13 @@ async fn run_impl(
     let at = get_node_name(&opts.state, &cmd.at);
     let node_name = parse_node_name(&at)?;
     let bdy = Policy::new(cmd.expression);
-    let req = Request::post(policy_path(&cmd.resource, &cmd.action)).body(bdy);
+    let path = policy_path(&cmd.resource, &cmd.action);
     let node = BackgroundNode::create(ctx, &opts.state, &node_name).await?;
-    node.tell(ctx, req).await?;
+    if cmd.dry_run {
+        let req = Request::post(format!("{path}/dry_run")).body(bdy);
+        let dry_run: DryRun = node.ask(ctx, req).await?;
+        return print_dry_run(&opts, &dry_run);
+    }
+    node.tell(ctx, Request::post(path).body(bdy)).await?;
     Ok(())
 }
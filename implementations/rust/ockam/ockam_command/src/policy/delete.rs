@@ -3,12 +3,13 @@ use colorful::Colorful;
 
 use ockam::Context;
 use ockam_abac::{Action, Resource};
+use ockam_api::nodes::models::dry_run::DryRun;
 use ockam_api::nodes::BackgroundNode;
 use ockam_core::api::Request;
 
 use crate::node::get_node_name;
 use crate::policy::policy_path;
-use crate::util::{node_rpc, parse_node_name};
+use crate::util::{node_rpc, parse_node_name, print_dry_run};
 use crate::{fmt_ok, CommandGlobalOpts};
 
 #[derive(Clone, Debug, Args)]
@@ -25,6 +26,10 @@ pub struct DeleteCommand {
     /// Confirm the deletion without prompting
     #[arg(display_order = 901, long, short)]
     yes: bool,
+
+    /// Only show the policy which would be deleted, and the inlets and outlets using it
+    #[arg(display_order = 902, long)]
+    dry_run: bool,
 }
 
 impl DeleteCommand {
@@ -44,6 +49,15 @@ async fn run_impl(
 ) -> miette::Result<()> {
     let at = get_node_name(&opts.state, &cmd.at);
     let node_name = parse_node_name(&at)?;
+    if cmd.dry_run {
+        let req = Request::delete(format!(
+            "{}/dry_run",
+            policy_path(&cmd.resource, &cmd.action)
+        ));
+        let node = BackgroundNode::create(ctx, &opts.state, &node_name).await?;
+        let dry_run: DryRun = node.ask(ctx, req).await?;
+        return print_dry_run(&opts, &dry_run);
+    }
     if opts
         .terminal
         .confirmed_with_flag_or_prompt(cmd.yes, "Are you sure you want to delete this policy?")?
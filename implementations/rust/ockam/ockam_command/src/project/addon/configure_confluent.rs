@@ -4,9 +4,12 @@ use colorful::Colorful;
 
 use ockam::Context;
 use ockam_api::cloud::addon::{Addons, ConfluentConfig};
+use ockam_api::cloud::operations_queue::QueuedReply;
 use ockam_api::nodes::InMemoryNode;
 
-use crate::project::addon::{check_configuration_completion, get_project_id};
+use crate::project::addon::{
+    check_configuration_completion, get_project_id, write_queued_operation,
+};
 use crate::util::node_rpc;
 use crate::{docs, fmt_ok, CommandGlobalOpts};
 
@@ -61,12 +64,17 @@ async fn run_impl(
     let controller = node.create_controller().await?;
 
     let response = controller
-        .configure_confluent_addon(&ctx, project_id.clone(), config)
+        .configure_confluent_addon_or_enqueue(&ctx, &opts.state, project_id.clone(), config)
         .await?;
-    check_configuration_completion(&opts, &ctx, &node, project_id, response.operation_id).await?;
-
-    opts.terminal
-        .write_line(&fmt_ok!("Confluent addon configured successfully"))?;
+    match response {
+        QueuedReply::Sent(response) => {
+            check_configuration_completion(&opts, &ctx, &node, project_id, response.operation_id)
+                .await?;
+            opts.terminal
+                .write_line(&fmt_ok!("Confluent addon configured successfully"))?;
+        }
+        QueuedReply::Queued(operation) => write_queued_operation(&opts, &operation)?,
+    }
 
     Ok(())
 }
@@ -7,10 +7,13 @@ use miette::{miette, IntoDiagnostic};
 
 use ockam::Context;
 use ockam_api::cloud::addon::Addons;
+use ockam_api::cloud::operations_queue::QueuedReply;
 use ockam_api::cloud::project::InfluxDBTokenLeaseManagerConfig;
 use ockam_api::nodes::InMemoryNode;
 
-use crate::project::addon::{check_configuration_completion, get_project_id};
+use crate::project::addon::{
+    check_configuration_completion, get_project_id, write_queued_operation,
+};
 use crate::util::node_rpc;
 use crate::{docs, fmt_ok, CommandGlobalOpts};
 
@@ -159,11 +162,16 @@ async fn run_impl(
     let controller = node.create_controller().await?;
 
     let response = controller
-        .configure_influxdb_addon(&ctx, project_id.clone(), config)
+        .configure_influxdb_addon_or_enqueue(&ctx, &opts.state, project_id.clone(), config)
         .await?;
-    check_configuration_completion(&opts, &ctx, &node, project_id, response.operation_id).await?;
-
-    opts.terminal
-        .write_line(&fmt_ok!("InfluxDB addon configured successfully"))?;
+    match response {
+        QueuedReply::Sent(response) => {
+            check_configuration_completion(&opts, &ctx, &node, project_id, response.operation_id)
+                .await?;
+            opts.terminal
+                .write_line(&fmt_ok!("InfluxDB addon configured successfully"))?;
+        }
+        QueuedReply::Queued(operation) => write_queued_operation(&opts, &operation)?,
+    }
     Ok(())
 }
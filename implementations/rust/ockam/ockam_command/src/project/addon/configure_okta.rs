@@ -10,13 +10,16 @@ use rustls::{Certificate, ClientConfig, ClientConnection, Connection, RootCertSt
 
 use ockam::Context;
 use ockam_api::cloud::addon::Addons;
+use ockam_api::cloud::operations_queue::QueuedReply;
 use ockam_api::cloud::project::OktaConfig;
 use ockam_api::enroll::oidc_service::OidcService;
 use ockam_api::enroll::okta_oidc_provider::OktaOidcProvider;
 use ockam_api::minicbor_url::Url;
 use ockam_api::nodes::InMemoryNode;
 
-use crate::project::addon::{check_configuration_completion, get_project_id};
+use crate::project::addon::{
+    check_configuration_completion, get_project_id, write_queued_operation,
+};
 use crate::util::node_rpc;
 use crate::{docs, fmt_ok, CommandGlobalOpts, Result};
 
@@ -122,12 +125,17 @@ async fn run_impl(
     let controller = node.create_controller().await?;
 
     let response = controller
-        .configure_okta_addon(&ctx, project_id.clone(), okta_config)
+        .configure_okta_addon_or_enqueue(&ctx, &opts.state, project_id.clone(), okta_config)
         .await?;
-    check_configuration_completion(&opts, &ctx, &node, project_id, response.operation_id).await?;
-
-    opts.terminal
-        .write_line(&fmt_ok!("Okta addon configured successfully"))?;
+    match response {
+        QueuedReply::Sent(response) => {
+            check_configuration_completion(&opts, &ctx, &node, project_id, response.operation_id)
+                .await?;
+            opts.terminal
+                .write_line(&fmt_ok!("Okta addon configured successfully"))?;
+        }
+        QueuedReply::Queued(operation) => write_queued_operation(&opts, &operation)?,
+    }
 
     Ok(())
 }
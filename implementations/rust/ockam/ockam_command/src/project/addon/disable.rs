@@ -4,10 +4,11 @@ use colorful::Colorful;
 
 use ockam::Context;
 use ockam_api::cloud::addon::Addons;
+use ockam_api::cloud::operations_queue::QueuedReply;
 use ockam_api::nodes::InMemoryNode;
 
 use crate::operation::util::check_for_completion;
-use crate::project::addon::get_project_id;
+use crate::project::addon::{get_project_id, write_queued_operation};
 use crate::util::node_rpc;
 use crate::{fmt_ok, CommandGlobalOpts};
 
@@ -51,11 +52,17 @@ async fn run_impl(
     let node = InMemoryNode::start(&ctx, &opts.state).await?;
     let controller = node.create_controller().await?;
 
-    let response = controller.disable_addon(&ctx, project_id, addon_id).await?;
-    let operation_id = response.operation_id;
-    check_for_completion(&opts, &ctx, &controller, &operation_id).await?;
-
-    opts.terminal
-        .write_line(&fmt_ok!("Addon disabled successfully"))?;
+    let response = controller
+        .disable_addon_or_enqueue(&ctx, &opts.state, project_id, addon_id)
+        .await?;
+    match response {
+        QueuedReply::Sent(response) => {
+            let operation_id = response.operation_id;
+            check_for_completion(&opts, &ctx, &controller, &operation_id).await?;
+            opts.terminal
+                .write_line(&fmt_ok!("Addon disabled successfully"))?;
+        }
+        QueuedReply::Queued(operation) => write_queued_operation(&opts, &operation)?,
+    }
     Ok(())
 }
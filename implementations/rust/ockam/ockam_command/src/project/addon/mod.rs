@@ -9,7 +9,7 @@ use core::fmt::Write;
 use clap::{Args, Subcommand};
 use miette::Context as _;
 
-use ockam_api::cli_state::{CliState, StateDirTrait, StateItemTrait};
+use ockam_api::cli_state::{CliState, QueuedOperation, StateDirTrait, StateItemTrait};
 use ockam_api::cloud::addon::Addon;
 use ockam_api::cloud::project::Projects;
 use ockam_api::nodes::InMemoryNode;
@@ -27,7 +27,7 @@ use crate::util::api::CloudOpts;
 
 use crate::operation::util::check_for_completion;
 use crate::project::util::check_project_readiness;
-use crate::{CommandGlobalOpts, Result};
+use crate::{fmt_log, fmt_warn, CommandGlobalOpts, Result};
 
 /// Manage addons for a project
 #[derive(Clone, Debug, Args)]
@@ -128,3 +128,15 @@ async fn check_configuration_completion(
     let _ = check_project_readiness(opts, ctx, node, project).await?;
     Ok(())
 }
+
+/// Tell the user that an operation was queued because the Orchestrator is unreachable
+fn write_queued_operation(opts: &CommandGlobalOpts, operation: &QueuedOperation) -> Result<()> {
+    opts.terminal.write_line(&fmt_warn!(
+        "The Orchestrator is unreachable, the operation {} is queued",
+        operation.id
+    ))?;
+    opts.terminal.write_line(&fmt_log!(
+        "Run 'ockam project operations replay' to send it again once the Orchestrator is reachable"
+    ))?;
+    Ok(())
+}
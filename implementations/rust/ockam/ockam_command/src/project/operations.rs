@@ -0,0 +1,141 @@
+use std::fmt::Write;
+
+use clap::{Args, Subcommand};
+use colorful::Colorful;
+use miette::{miette, IntoDiagnostic};
+
+use ockam::Context;
+use ockam_api::cli_state::QueuedOperation;
+use ockam_api::nodes::InMemoryNode;
+
+use crate::output::Output;
+use crate::terminal::OckamColor;
+use crate::util::api::CloudOpts;
+use crate::util::{local_cmd, node_rpc};
+use crate::{docs, fmt_err, fmt_ok, fmt_warn, CommandGlobalOpts};
+
+const LONG_ABOUT: &str = include_str!("./static/operations/long_about.txt");
+
+/// Inspect and replay the operations queued while the Orchestrator was unreachable
+#[derive(Clone, Debug, Args)]
+#[command(
+    arg_required_else_help = true,
+    subcommand_required = true,
+    long_about = docs::about(LONG_ABOUT),
+)]
+pub struct OperationsCommand {
+    #[command(subcommand)]
+    subcommand: OperationsSubcommand,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum OperationsSubcommand {
+    /// List the queued operations, the oldest first
+    List,
+    /// Send the queued operations to the Orchestrator
+    Replay {
+        #[command(flatten)]
+        cloud_opts: CloudOpts,
+    },
+    /// Remove an operation from the queue without sending it
+    Delete {
+        /// Identifier of the operation
+        id: String,
+    },
+}
+
+impl OperationsCommand {
+    pub fn run(self, opts: CommandGlobalOpts) {
+        match self.subcommand {
+            OperationsSubcommand::List => local_cmd(list(&opts)),
+            OperationsSubcommand::Replay { .. } => node_rpc(replay, opts),
+            OperationsSubcommand::Delete { id } => local_cmd(delete(&opts, &id)),
+        }
+    }
+}
+
+fn list(opts: &CommandGlobalOpts) -> miette::Result<()> {
+    let operations = opts.state.queued_operations()?;
+    let plain = opts.terminal.build_list(
+        &operations,
+        "Queued operations",
+        "No operations are waiting for the Orchestrator.",
+    )?;
+    let json = serde_json::to_string_pretty(&operations).into_diagnostic()?;
+    opts.terminal
+        .stdout()
+        .plain(plain)
+        .json(json)
+        .write_line()?;
+    Ok(())
+}
+
+fn delete(opts: &CommandGlobalOpts, id: &str) -> miette::Result<()> {
+    if !opts.state.remove_queued_operation(id)? {
+        return Err(miette!("There is no queued operation with the id {id}"));
+    }
+    opts.terminal
+        .stdout()
+        .plain(fmt_ok!("The operation {id} was removed from the queue"))
+        .machine(id)
+        .write_line()?;
+    Ok(())
+}
+
+async fn replay(ctx: Context, opts: CommandGlobalOpts) -> miette::Result<()> {
+    let node = InMemoryNode::start(&ctx, &opts.state).await?;
+    let controller = node.create_controller().await?;
+    let replayed = controller
+        .replay_queued_operations(&ctx, &opts.state)
+        .await?;
+
+    let mut plain = String::new();
+    for operation in &replayed.succeeded {
+        writeln!(plain, "{}", fmt_ok!("{}", operation.description)).into_diagnostic()?;
+    }
+    for (operation, error) in &replayed.rejected {
+        writeln!(
+            plain,
+            "{}",
+            fmt_err!("{} was rejected: {error}", operation.description)
+        )
+        .into_diagnostic()?;
+    }
+    if !replayed.remaining.is_empty() {
+        writeln!(
+            plain,
+            "{}",
+            fmt_warn!(
+                "The Orchestrator is unreachable, {} operations are still queued",
+                replayed.remaining.len()
+            )
+        )
+        .into_diagnostic()?;
+    }
+    let json = serde_json::to_string_pretty(&replayed).into_diagnostic()?;
+    opts.terminal
+        .stdout()
+        .plain(plain)
+        .json(json)
+        .write_line()?;
+    Ok(())
+}
+
+impl Output for QueuedOperation {
+    fn output(&self) -> crate::error::Result<String> {
+        let mut output = String::new();
+        writeln!(
+            output,
+            "Operation {}",
+            self.id
+                .to_string()
+                .color(OckamColor::PrimaryResource.color())
+        )?;
+        writeln!(output, "Description {}", self.description)?;
+        write!(output, "Attempts {}", self.attempts)?;
+        if let Some(error) = &self.last_error {
+            write!(output, "\nLast error {error}")?;
+        }
+        Ok(output)
+    }
+}
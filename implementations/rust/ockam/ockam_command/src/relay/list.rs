@@ -10,6 +10,7 @@ use ockam_api::address::extract_address_value;
 use ockam_api::cli_state::StateDirTrait;
 use ockam_api::nodes::models::relay::RelayInfo;
 use ockam_api::nodes::BackgroundNode;
+use ockam_api::resource_list::ResourceList;
 use ockam_core::api::Request;
 
 use crate::node::{get_node_name, initialize_node_if_default};
@@ -57,9 +58,10 @@ async fn run_impl(
     let is_finished: Mutex<bool> = Mutex::new(false);
 
     let get_relays = async {
+        // the relays are still returned as a plain list, to be decoded by older clients
         let relay_infos: Vec<RelayInfo> = node.ask(&ctx, Request::get("/node/forwarder")).await?;
         *is_finished.lock().await = true;
-        Ok(relay_infos)
+        Ok(ResourceList::new(relay_infos))
     };
 
     let output_messages = vec![format!(
@@ -77,11 +79,11 @@ async fn run_impl(
     trace!(?relays, "Relays retrieved");
 
     let plain = opts.terminal.build_list(
-        &relays,
+        &relays.items,
         &format!("Relays on Node {node_name}"),
         &format!("No Relays found on node {node_name}."),
     )?;
-    let json = serde_json::to_string_pretty(&relays).into_diagnostic()?;
+    let json = relays.to_json().into_diagnostic()?;
 
     opts.terminal
         .stdout()
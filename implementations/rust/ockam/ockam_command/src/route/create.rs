@@ -0,0 +1,80 @@
+use clap::Args;
+use colorful::Colorful;
+
+use ockam::Context;
+use ockam_api::nodes::models::route::{SetStaticRoute, StaticRoute};
+use ockam_api::nodes::BackgroundNode;
+use ockam_core::api::Request;
+use ockam_multiaddr::MultiAddr;
+
+use crate::node::{get_node_name, initialize_node_if_default, NodeOpts};
+use crate::terminal::OckamColor;
+use crate::util::{node_rpc, parse_node_name};
+use crate::{docs, fmt_log, fmt_ok, CommandGlobalOpts};
+
+const LONG_ABOUT: &str = include_str!("./static/create/long_about.txt");
+const AFTER_LONG_HELP: &str = include_str!("./static/create/after_long_help.txt");
+
+/// Register a static route on a node
+#[derive(Clone, Debug, Args)]
+#[command(
+    arg_required_else_help = true,
+    long_about = docs::about(LONG_ABOUT),
+    after_long_help = docs::after_help(AFTER_LONG_HELP),
+)]
+pub struct CreateCommand {
+    /// Name of the route, referenced as /route/NAME
+    name: String,
+
+    /// Address of the route, which can reference other routes
+    addr: MultiAddr,
+
+    #[command(flatten)]
+    node_opts: NodeOpts,
+}
+
+impl CreateCommand {
+    pub fn run(self, opts: CommandGlobalOpts) {
+        initialize_node_if_default(&opts, &self.node_opts.at_node);
+        node_rpc(rpc, (opts, self));
+    }
+}
+
+async fn rpc(ctx: Context, (opts, cmd): (CommandGlobalOpts, CreateCommand)) -> miette::Result<()> {
+    run_impl(&ctx, (opts, cmd)).await
+}
+
+async fn run_impl(
+    ctx: &Context,
+    (opts, cmd): (CommandGlobalOpts, CreateCommand),
+) -> miette::Result<()> {
+    let at = get_node_name(&opts.state, &cmd.node_opts.at_node);
+    let node_name = parse_node_name(&at)?;
+    let node = BackgroundNode::create(ctx, &opts.state, &node_name).await?;
+
+    let req =
+        Request::put(format!("/node/routes/{}", cmd.name)).body(SetStaticRoute::new(cmd.addr));
+    let route: StaticRoute = node.ask(ctx, req).await?;
+
+    opts.terminal
+        .stdout()
+        .plain(
+            fmt_ok!(
+                "Route {} registered on node {}\n",
+                format!("/route/{}", route.name).color(OckamColor::PrimaryResource.color()),
+                node_name.color(OckamColor::PrimaryResource.color())
+            ) + &fmt_log!(
+                "It stands for {}",
+                route
+                    .expanded_addr
+                    .as_ref()
+                    .unwrap_or(&route.addr)
+                    .to_string()
+                    .color(OckamColor::PrimaryResource.color())
+            ),
+        )
+        .machine(format!("/route/{}", route.name))
+        .json(serde_json::json!(&route))
+        .write_line()?;
+    Ok(())
+}
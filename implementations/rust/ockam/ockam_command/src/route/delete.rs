@@ -0,0 +1,59 @@
+use clap::Args;
+use colorful::Colorful;
+
+use ockam::Context;
+use ockam_api::nodes::BackgroundNode;
+use ockam_core::api::Request;
+
+use crate::node::{get_node_name, initialize_node_if_default, NodeOpts};
+use crate::util::{node_rpc, parse_node_name};
+use crate::{docs, fmt_ok, CommandGlobalOpts};
+
+const LONG_ABOUT: &str = include_str!("./static/delete/long_about.txt");
+const AFTER_LONG_HELP: &str = include_str!("./static/delete/after_long_help.txt");
+
+/// Delete a static route of a node
+#[derive(Clone, Debug, Args)]
+#[command(
+    arg_required_else_help = true,
+    long_about = docs::about(LONG_ABOUT),
+    after_long_help = docs::after_help(AFTER_LONG_HELP),
+)]
+pub struct DeleteCommand {
+    /// Name of the route to delete
+    name: String,
+
+    #[command(flatten)]
+    node_opts: NodeOpts,
+}
+
+impl DeleteCommand {
+    pub fn run(self, opts: CommandGlobalOpts) {
+        initialize_node_if_default(&opts, &self.node_opts.at_node);
+        node_rpc(rpc, (opts, self));
+    }
+}
+
+async fn rpc(ctx: Context, (opts, cmd): (CommandGlobalOpts, DeleteCommand)) -> miette::Result<()> {
+    run_impl(&ctx, (opts, cmd)).await
+}
+
+async fn run_impl(
+    ctx: &Context,
+    (opts, cmd): (CommandGlobalOpts, DeleteCommand),
+) -> miette::Result<()> {
+    let at = get_node_name(&opts.state, &cmd.node_opts.at_node);
+    let node_name = parse_node_name(&at)?;
+    let node = BackgroundNode::create(ctx, &opts.state, &node_name).await?;
+    node.tell(ctx, Request::delete(format!("/node/routes/{}", cmd.name)))
+        .await?;
+    let name = cmd.name;
+    opts.terminal
+        .stdout()
+        .plain(fmt_ok!(
+            "Deleted route '/route/{name}' on node '{node_name}'"
+        ))
+        .machine(format!("/route/{name}"))
+        .write_line()?;
+    Ok(())
+}
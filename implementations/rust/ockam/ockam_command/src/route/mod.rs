@@ -0,0 +1,59 @@
+mod create;
+mod delete;
+mod list;
+mod select;
+mod show;
+mod trace;
+
+pub(crate) use create::CreateCommand;
+pub(crate) use delete::DeleteCommand;
+pub(crate) use list::ListCommand;
+pub(crate) use select::SelectCommand;
+pub(crate) use show::ShowCommand;
+pub(crate) use trace::TraceCommand;
+
+use crate::{docs, CommandGlobalOpts};
+use clap::{Args, Subcommand};
+
+const LONG_ABOUT: &str = include_str!("./static/long_about.txt");
+
+/// Manage the static routes of a node
+#[derive(Clone, Debug, Args)]
+#[command(
+    arg_required_else_help = true,
+    subcommand_required = true,
+    long_about = docs::about(LONG_ABOUT),
+)]
+pub struct RouteCommand {
+    #[command(subcommand)]
+    subcommand: RouteSubcommand,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum RouteSubcommand {
+    #[command(display_order = 800)]
+    Create(CreateCommand),
+    #[command(display_order = 801)]
+    Delete(DeleteCommand),
+    #[command(display_order = 802)]
+    List(ListCommand),
+    #[command(display_order = 803)]
+    Show(ShowCommand),
+    #[command(display_order = 804)]
+    Select(SelectCommand),
+    #[command(display_order = 805)]
+    Trace(TraceCommand),
+}
+
+impl RouteCommand {
+    pub fn run(self, options: CommandGlobalOpts) {
+        match self.subcommand {
+            RouteSubcommand::Create(c) => c.run(options),
+            RouteSubcommand::Delete(c) => c.run(options),
+            RouteSubcommand::List(c) => c.run(options),
+            RouteSubcommand::Show(c) => c.run(options),
+            RouteSubcommand::Select(c) => c.run(options),
+            RouteSubcommand::Trace(c) => c.run(options),
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use clap::Args;
+use colorful::Colorful;
+use miette::IntoDiagnostic;
+
+use ockam::identity::Identifier;
+use ockam::Context;
+use ockam_api::nodes::models::route::{SelectRoute, SelectedRoute};
+use ockam_api::nodes::BackgroundNode;
+use ockam_core::api::Request;
+use ockam_multiaddr::MultiAddr;
+
+use crate::node::{get_node_name, initialize_node_if_default, NodeOpts};
+use crate::output::Output;
+use crate::terminal::OckamColor;
+use crate::util::{node_rpc, parse_node_name};
+use crate::{docs, CommandGlobalOpts};
+
+const LONG_ABOUT: &str = include_str!("./static/select/long_about.txt");
+const AFTER_LONG_HELP: &str = include_str!("./static/select/after_long_help.txt");
+
+/// Select a working route to a node, falling back to its relays
+#[derive(Clone, Debug, Args)]
+#[command(
+    arg_required_else_help = true,
+    long_about = docs::about(LONG_ABOUT),
+    after_long_help = docs::after_help(AFTER_LONG_HELP),
+)]
+pub struct SelectCommand {
+    /// Identifier of the node to reach
+    identifier: Identifier,
+
+    /// Address of the node, tried first. Can be repeated
+    #[arg(long, value_name = "ADDRESS")]
+    direct: Vec<MultiAddr>,
+
+    /// Address of a node where the node created a relay, tried next. Can be repeated
+    #[arg(long, value_name = "ADDRESS")]
+    relay: Vec<MultiAddr>,
+
+    /// Name of the relay created by the node
+    #[arg(long, value_name = "RELAY_NAME")]
+    relay_name: Option<String>,
+
+    /// Project where the node created a relay, tried last
+    #[arg(long, value_name = "PROJECT_NAME")]
+    project: Option<String>,
+
+    /// Time during which the selected route is reused, in seconds
+    #[arg(long, value_name = "SECONDS")]
+    ttl: Option<u64>,
+
+    /// Try the routes again, even if a route was previously selected
+    #[arg(long)]
+    refresh: bool,
+
+    #[command(flatten)]
+    node_opts: NodeOpts,
+}
+
+impl SelectCommand {
+    pub fn run(self, opts: CommandGlobalOpts) {
+        initialize_node_if_default(&opts, &self.node_opts.at_node);
+        node_rpc(rpc, (opts, self));
+    }
+}
+
+async fn rpc(ctx: Context, (opts, cmd): (CommandGlobalOpts, SelectCommand)) -> miette::Result<()> {
+    run_impl(&ctx, (opts, cmd)).await
+}
+
+async fn run_impl(
+    ctx: &Context,
+    (opts, cmd): (CommandGlobalOpts, SelectCommand),
+) -> miette::Result<()> {
+    let at = get_node_name(&opts.state, &cmd.node_opts.at_node);
+    let node_name = parse_node_name(&at)?;
+    let node = BackgroundNode::create(ctx, &opts.state, &node_name).await?;
+
+    let request = SelectRoute::new(cmd.identifier)
+        .with_direct(cmd.direct)
+        .with_relays(cmd.relay, cmd.relay_name)
+        .with_project(cmd.project)
+        .with_ttl(cmd.ttl)
+        .with_refresh(cmd.refresh);
+    let route: SelectedRoute = node
+        .ask(ctx, Request::post("/node/select_route").body(request))
+        .await?;
+    let json = serde_json::to_string_pretty(&route).into_diagnostic()?;
+    opts.terminal
+        .stdout()
+        .plain(route.output()?)
+        .machine(&route.addr)
+        .json(json)
+        .write_line()?;
+    Ok(())
+}
+
+impl Output for SelectedRoute {
+    fn output(&self) -> crate::Result<String> {
+        let cached = if self.cached {
+            " (previously selected)"
+        } else {
+            ""
+        };
+        Ok(format!(
+            "Route to {}\nAddress {} through a {} route{cached}",
+            self.identifier,
+            self.addr
+                .as_str()
+                .color(OckamColor::PrimaryResource.color()),
+            self.kind
+        ))
+    }
+}
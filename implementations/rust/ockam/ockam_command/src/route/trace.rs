@@ -0,0 +1,94 @@
+use clap::Args;
+use colorful::Colorful;
+use miette::IntoDiagnostic;
+
+use ockam::Context;
+use ockam_api::nodes::models::route::{TraceRoute, TracedRoute};
+use ockam_api::nodes::BackgroundNode;
+use ockam_core::api::Request;
+use ockam_multiaddr::MultiAddr;
+
+use crate::node::{get_node_name, initialize_node_if_default, NodeOpts};
+use crate::output::Output;
+use crate::terminal::OckamColor;
+use crate::util::{node_rpc, parse_node_name};
+use crate::{docs, CommandGlobalOpts};
+
+const LONG_ABOUT: &str = include_str!("./static/trace/long_about.txt");
+const AFTER_LONG_HELP: &str = include_str!("./static/trace/after_long_help.txt");
+
+/// Trace a route, measuring the latency of each of its hops
+#[derive(Clone, Debug, Args)]
+#[command(
+    arg_required_else_help = true,
+    long_about = docs::about(LONG_ABOUT),
+    after_long_help = docs::after_help(AFTER_LONG_HELP),
+)]
+pub struct TraceCommand {
+    /// Address of the route to trace
+    addr: MultiAddr,
+
+    /// Maximum time to wait for the probe to come back, in seconds
+    #[arg(long, value_name = "SECONDS")]
+    timeout: Option<u64>,
+
+    #[command(flatten)]
+    node_opts: NodeOpts,
+}
+
+impl TraceCommand {
+    pub fn run(self, opts: CommandGlobalOpts) {
+        initialize_node_if_default(&opts, &self.node_opts.at_node);
+        node_rpc(rpc, (opts, self));
+    }
+}
+
+async fn rpc(ctx: Context, (opts, cmd): (CommandGlobalOpts, TraceCommand)) -> miette::Result<()> {
+    run_impl(&ctx, (opts, cmd)).await
+}
+
+async fn run_impl(
+    ctx: &Context,
+    (opts, cmd): (CommandGlobalOpts, TraceCommand),
+) -> miette::Result<()> {
+    let at = get_node_name(&opts.state, &cmd.node_opts.at_node);
+    let node_name = parse_node_name(&at)?;
+    let node = BackgroundNode::create(ctx, &opts.state, &node_name).await?;
+
+    let request = TraceRoute::new(cmd.addr).with_timeout(cmd.timeout);
+    let route: TracedRoute = node
+        .ask(ctx, Request::post("/node/traceroute").body(request))
+        .await?;
+    let json = serde_json::to_string_pretty(&route).into_diagnostic()?;
+    opts.terminal
+        .stdout()
+        .plain(route.output()?)
+        .json(json)
+        .write_line()?;
+    Ok(())
+}
+
+impl Output for TracedRoute {
+    fn output(&self) -> crate::Result<String> {
+        let mut output = format!(
+            "Route {}\n",
+            self.addr
+                .as_str()
+                .color(OckamColor::PrimaryResource.color())
+        );
+        for (i, hop) in self.hops.iter().enumerate() {
+            output.push_str(&format!(
+                "{:>3}  {:<50} {:<20} {:.3} ms\n",
+                i + 1,
+                hop.segment,
+                hop.node,
+                hop.latency as f64 / 1000.0
+            ));
+        }
+        output.push_str(&format!(
+            "Round trip: {:.3} ms",
+            self.round_trip as f64 / 1000.0
+        ));
+        Ok(output)
+    }
+}
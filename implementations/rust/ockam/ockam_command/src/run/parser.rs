@@ -148,10 +148,12 @@ impl ConfigRunner {
 /// nodes:
 ///   telegraf:
 ///     enrollment-token: $OCKAM_TELEGRAF_TOKEN
+///     routes:
+///       influxdb: /project/default/service/forward_to_influxdb/secure/api/service/outlet
 ///     tcp-inlets:
 ///       telegraf:
 ///         from: '127.0.0.1:8087'
-///         to: /project/default/service/forward_to_influxdb/secure/api/service/outlet
+///         to: /route/influxdb
 ///         access_control: '(= subject.component "influxdb")'
 ///
 ///   influxdb:
@@ -177,6 +179,8 @@ pub struct NodeConfig {
     pub depends_on: Option<String>,
     #[serde(rename(deserialize = "enrollment-ticket"))]
     pub enrollment_ticket: Option<String>,
+    /// Static routes of the node, which can be referenced as `/route/<name>`
+    pub routes: Option<HashMap<String, String>>,
     #[serde(rename(deserialize = "tcp-inlets"))]
     pub tcp_inlets: Option<HashMap<String, InletConfig>>,
     #[serde(rename(deserialize = "tcp-outlets"))]
@@ -257,6 +261,14 @@ impl NodeConfig {
         // TODO: all commands should support both `/node/{name}` and `{name}` formats.
         let node_name_formatted = format!("/node/{node_name}");
 
+        // Routes are created first since the inlets, outlets and relays can reference them.
+        if let Some(routes) = &self.routes {
+            for (name, addr) in routes {
+                let args = &["route", "create", name, addr, "--at", &node_name_formatted];
+                insert_command("route", name, None, args, false)?;
+            }
+        }
+
         if let Some(tcp_inlets) = &self.tcp_inlets {
             for (name, inlet) in tcp_inlets {
                 // TODO: store inlets in CliState; Then check if the inlet already exists. If it doesn't, create it.
@@ -377,6 +389,28 @@ fn binary_path() -> &'static str {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_config_with_routes() {
+        let config = r#"
+            nodes:
+              telegraf:
+                routes:
+                  influxdb: /project/default/service/forward_to_influxdb/secure/api/service/outlet
+                tcp-inlets:
+                  telegraf:
+                    from: '127.0.0.1:8087'
+                    to: /route/influxdb
+        "#;
+
+        let mut sut = ConfigRunner::new();
+        sut.parse(config, false).unwrap();
+
+        assert_eq!(sut.commands_sorted.len(), 3);
+        assert_eq!(sut.commands_sorted[0].id, "node/telegraf");
+        assert_eq!(sut.commands_sorted[1].id, "route/influxdb");
+        assert_eq!(sut.commands_sorted[2].id, "inlet/telegraf");
+    }
+
     #[test]
     fn test_parse_config_with_depends_on() {
         let config = r#"
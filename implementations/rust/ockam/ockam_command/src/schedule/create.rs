@@ -0,0 +1,126 @@
+use std::time::Duration;
+
+use clap::Args;
+use colorful::Colorful;
+
+use ockam::Context;
+use ockam_api::nodes::models::schedules::{CreateSchedule, Recurrence, Schedule, ScheduledTask};
+use ockam_api::nodes::BackgroundNode;
+use ockam_core::api::Request;
+
+use crate::node::{get_node_name, initialize_node_if_default, NodeOpts};
+use crate::terminal::OckamColor;
+use crate::util::duration::duration_parser;
+use crate::util::{node_rpc, parse_node_name};
+use crate::{docs, fmt_log, fmt_ok, CommandGlobalOpts};
+
+const LONG_ABOUT: &str = include_str!("./static/create/long_about.txt");
+const AFTER_LONG_HELP: &str = include_str!("./static/create/after_long_help.txt");
+
+/// Create a schedule on a node
+#[derive(Clone, Debug, Args)]
+#[command(
+    arg_required_else_help = true,
+    long_about = docs::about(LONG_ABOUT),
+    after_long_help = docs::after_help(AFTER_LONG_HELP),
+)]
+pub struct CreateCommand {
+    /// Name of the schedule
+    name: String,
+
+    /// Task to execute: rotate-identity-key, refresh-credentials or rekey-kafka-channels
+    #[arg(long, value_name = "TASK")]
+    task: ScheduledTask,
+
+    /// Execute the task at this interval, for example 1h or 90d
+    #[arg(long, value_name = "DURATION", value_parser = duration_parser, conflicts_with = "daily_at", required_unless_present = "daily_at")]
+    every: Option<Duration>,
+
+    /// Execute the task every day at this UTC time, formatted as HH:MM
+    #[arg(long, value_name = "HH:MM", value_parser = daily_at_parser)]
+    daily_at: Option<u16>,
+
+    #[command(flatten)]
+    node_opts: NodeOpts,
+}
+
+impl CreateCommand {
+    pub fn run(self, opts: CommandGlobalOpts) {
+        initialize_node_if_default(&opts, &self.node_opts.at_node);
+        node_rpc(rpc, (opts, self));
+    }
+
+    fn recurrence(&self) -> Recurrence {
+        match (self.every, self.daily_at) {
+            (Some(every), _) => Recurrence::Every(every.as_secs()),
+            (None, Some(minutes)) => Recurrence::DailyAt(minutes),
+            (None, None) => unreachable!("clap requires --every or --daily-at"),
+        }
+    }
+}
+
+/// Parse a time of the day as a number of minutes after midnight
+fn daily_at_parser(arg: &str) -> Result<u16, String> {
+    let invalid = || format!("Invalid time {arg}, expected HH:MM");
+    let (hours, minutes) = arg.split_once(':').ok_or_else(invalid)?;
+    let hours: u16 = hours.parse().map_err(|_| invalid())?;
+    let minutes: u16 = minutes.parse().map_err(|_| invalid())?;
+    if hours > 23 || minutes > 59 {
+        return Err(invalid());
+    }
+    Ok(hours * 60 + minutes)
+}
+
+async fn rpc(ctx: Context, (opts, cmd): (CommandGlobalOpts, CreateCommand)) -> miette::Result<()> {
+    run_impl(&ctx, (opts, cmd)).await
+}
+
+async fn run_impl(
+    ctx: &Context,
+    (opts, cmd): (CommandGlobalOpts, CreateCommand),
+) -> miette::Result<()> {
+    let at = get_node_name(&opts.state, &cmd.node_opts.at_node);
+    let node_name = parse_node_name(&at)?;
+    let node = BackgroundNode::create(ctx, &opts.state, &node_name).await?;
+
+    let req = Request::post("/node/schedules").body(CreateSchedule::new(
+        cmd.name.clone(),
+        cmd.task,
+        cmd.recurrence(),
+    ));
+    let schedule: Schedule = node.ask(ctx, req).await?;
+
+    opts.terminal
+        .stdout()
+        .plain(
+            fmt_ok!(
+                "Schedule {} created on node {}\n",
+                schedule
+                    .name
+                    .clone()
+                    .color(OckamColor::PrimaryResource.color()),
+                node_name.color(OckamColor::PrimaryResource.color())
+            ) + &fmt_log!(
+                "The task {} is executed {}",
+                schedule.task,
+                schedule.recurrence
+            ),
+        )
+        .machine(&schedule.name)
+        .json(serde_json::json!(&schedule))
+        .write_line()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_daily_at_parser() {
+        assert_eq!(daily_at_parser("02:00"), Ok(120));
+        assert_eq!(daily_at_parser("23:59"), Ok(23 * 60 + 59));
+        assert!(daily_at_parser("24:00").is_err());
+        assert!(daily_at_parser("2h").is_err());
+    }
+}
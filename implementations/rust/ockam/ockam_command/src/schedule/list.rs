@@ -0,0 +1,108 @@
+use clap::Args;
+use colorful::Colorful;
+use miette::{miette, IntoDiagnostic};
+use time::OffsetDateTime;
+use tokio::sync::Mutex;
+use tokio::try_join;
+
+use ockam::Context;
+use ockam_api::cli_state::StateDirTrait;
+use ockam_api::nodes::models::schedules::{Schedule, ScheduleList};
+use ockam_api::nodes::BackgroundNode;
+use ockam_core::api::Request;
+
+use crate::node::{get_node_name, initialize_node_if_default, NodeOpts};
+use crate::output::Output;
+use crate::terminal::OckamColor;
+use crate::util::{node_rpc, parse_node_name};
+use crate::{docs, CommandGlobalOpts};
+
+const LONG_ABOUT: &str = include_str!("./static/list/long_about.txt");
+const AFTER_LONG_HELP: &str = include_str!("./static/list/after_long_help.txt");
+
+/// List the schedules of a node
+#[derive(Clone, Debug, Args)]
+#[command(
+    long_about = docs::about(LONG_ABOUT),
+    after_long_help = docs::after_help(AFTER_LONG_HELP),
+)]
+pub struct ListCommand {
+    #[command(flatten)]
+    node_opts: NodeOpts,
+}
+
+impl ListCommand {
+    pub fn run(self, opts: CommandGlobalOpts) {
+        initialize_node_if_default(&opts, &self.node_opts.at_node);
+        node_rpc(rpc, (opts, self));
+    }
+}
+
+async fn rpc(ctx: Context, (opts, cmd): (CommandGlobalOpts, ListCommand)) -> miette::Result<()> {
+    run_impl(&ctx, opts, cmd).await
+}
+
+async fn run_impl(ctx: &Context, opts: CommandGlobalOpts, cmd: ListCommand) -> miette::Result<()> {
+    let at = get_node_name(&opts.state, &cmd.node_opts.at_node);
+    let node_name = parse_node_name(&at)?;
+
+    if !opts.state.nodes.get(&node_name)?.is_running() {
+        return Err(miette!("The node '{}' is not running", node_name));
+    }
+
+    let node = BackgroundNode::create(ctx, &opts.state, &node_name).await?;
+    let is_finished: Mutex<bool> = Mutex::new(false);
+
+    let get_schedules = async {
+        let schedules: ScheduleList = node.ask(ctx, Request::get("/node/schedules")).await?;
+        *is_finished.lock().await = true;
+        Ok(schedules)
+    };
+
+    let output_messages = vec![format!(
+        "Listing schedules on {}...\n",
+        node_name
+            .to_string()
+            .color(OckamColor::PrimaryResource.color())
+    )];
+
+    let progress_output = opts
+        .terminal
+        .progress_output(&output_messages, &is_finished);
+
+    let (schedules, _) = try_join!(get_schedules, progress_output)?;
+
+    let list = opts.terminal.build_list(
+        &schedules.schedules,
+        &format!("Schedules on {node_name}"),
+        &format!("No schedules found on {node_name}."),
+    )?;
+    let json = serde_json::to_string_pretty(&schedules).into_diagnostic()?;
+    opts.terminal.stdout().plain(list).json(json).write_line()?;
+
+    Ok(())
+}
+
+fn format_time(timestamp: u64) -> String {
+    OffsetDateTime::from_unix_timestamp(timestamp as i64)
+        .map_or_else(|_| timestamp.to_string(), |t| t.to_string())
+}
+
+impl Output for Schedule {
+    fn output(&self) -> crate::Result<String> {
+        let last_run = match (self.last_run, &self.last_error) {
+            (None, _) => "never".to_string(),
+            (Some(last_run), None) => format!("{} (succeeded)", format_time(last_run)),
+            (Some(last_run), Some(error)) => {
+                format!("{} (failed: {error})", format_time(last_run))
+            }
+        };
+        Ok(format!(
+            "Schedule {}\nTask {}, {}\nLast run {last_run}\nNext run {}",
+            self.name.clone().color(OckamColor::PrimaryResource.color()),
+            self.task,
+            self.recurrence,
+            format_time(self.next_run)
+        ))
+    }
+}
@@ -0,0 +1,49 @@
+mod create;
+mod delete;
+mod list;
+mod run;
+
+pub(crate) use create::CreateCommand;
+pub(crate) use delete::DeleteCommand;
+pub(crate) use list::ListCommand;
+pub(crate) use run::RunCommand;
+
+use crate::{docs, CommandGlobalOpts};
+use clap::{Args, Subcommand};
+
+const LONG_ABOUT: &str = include_str!("./static/long_about.txt");
+
+/// Manage the scheduled tasks of a node
+#[derive(Clone, Debug, Args)]
+#[command(
+    arg_required_else_help = true,
+    subcommand_required = true,
+    long_about = docs::about(LONG_ABOUT),
+)]
+pub struct ScheduleCommand {
+    #[command(subcommand)]
+    subcommand: ScheduleSubcommand,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum ScheduleSubcommand {
+    #[command(display_order = 800)]
+    Create(CreateCommand),
+    #[command(display_order = 801)]
+    Delete(DeleteCommand),
+    #[command(display_order = 802)]
+    List(ListCommand),
+    #[command(display_order = 803)]
+    Run(RunCommand),
+}
+
+impl ScheduleCommand {
+    pub fn run(self, options: CommandGlobalOpts) {
+        match self.subcommand {
+            ScheduleSubcommand::Create(c) => c.run(options),
+            ScheduleSubcommand::Delete(c) => c.run(options),
+            ScheduleSubcommand::List(c) => c.run(options),
+            ScheduleSubcommand::Run(c) => c.run(options),
+        }
+    }
+}
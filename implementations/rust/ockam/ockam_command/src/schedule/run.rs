@@ -0,0 +1,67 @@
+use clap::Args;
+
+use ockam::Context;
+use ockam_api::nodes::models::schedules::Schedule;
+use ockam_api::nodes::BackgroundNode;
+use ockam_core::api::Request;
+
+use crate::node::{get_node_name, initialize_node_if_default, NodeOpts};
+use crate::util::{node_rpc, parse_node_name};
+use crate::{docs, fmt_log, fmt_ok, CommandGlobalOpts};
+
+const LONG_ABOUT: &str = include_str!("./static/run/long_about.txt");
+const AFTER_LONG_HELP: &str = include_str!("./static/run/after_long_help.txt");
+
+/// Execute the task of a schedule now
+#[derive(Clone, Debug, Args)]
+#[command(
+    arg_required_else_help = true,
+    long_about = docs::about(LONG_ABOUT),
+    after_long_help = docs::after_help(AFTER_LONG_HELP),
+)]
+pub struct RunCommand {
+    /// Name of the schedule to run
+    name: String,
+
+    #[command(flatten)]
+    node_opts: NodeOpts,
+}
+
+impl RunCommand {
+    pub fn run(self, opts: CommandGlobalOpts) {
+        initialize_node_if_default(&opts, &self.node_opts.at_node);
+        node_rpc(rpc, (opts, self));
+    }
+}
+
+async fn rpc(ctx: Context, (opts, cmd): (CommandGlobalOpts, RunCommand)) -> miette::Result<()> {
+    run_impl(&ctx, (opts, cmd)).await
+}
+
+async fn run_impl(
+    ctx: &Context,
+    (opts, cmd): (CommandGlobalOpts, RunCommand),
+) -> miette::Result<()> {
+    let at = get_node_name(&opts.state, &cmd.node_opts.at_node);
+    let node_name = parse_node_name(&at)?;
+    let node = BackgroundNode::create(ctx, &opts.state, &node_name).await?;
+    let schedule: Schedule = node
+        .ask(
+            ctx,
+            Request::post(format!("/node/schedules/{}/run", cmd.name)),
+        )
+        .await?;
+    opts.terminal
+        .stdout()
+        .plain(
+            fmt_ok!(
+                "Started the task {} of the schedule '{}' on node '{node_name}'\n",
+                schedule.task,
+                schedule.name
+            ) + &fmt_log!("Its outcome is shown by `ockam schedule list`"),
+        )
+        .machine(&schedule.name)
+        .json(serde_json::json!(&schedule))
+        .write_line()?;
+    Ok(())
+}
@@ -61,6 +61,15 @@ pub struct CreateCommand {
     /// Name of a stored Credential to use within this Secure Channel
     #[arg(short, long)]
     pub credential: Option<String>,
+
+    /// Propose to the listener to compress the messages of the channel
+    #[arg(long)]
+    pub compression: bool,
+
+    /// Accept messages arriving at most that many messages behind the most recent one,
+    /// for example on lossy networks. Larger windows keep more keys to decrypt late messages
+    #[arg(long, value_name = "MESSAGES")]
+    pub replay_window: Option<u64>,
 }
 
 impl CreateCommand {
@@ -122,7 +131,9 @@ async fn rpc(ctx: Context, (opts, cmd): (CommandGlobalOpts, CreateCommand)) -> m
             authorized_identifiers,
             Some(identity_name),
             cmd.credential.clone(),
-        );
+        )
+        .with_compression(cmd.compression)
+        .with_replay_window(cmd.replay_window);
         let request = Request::post("/node/secure_channel").body(payload);
         let response: CreateSecureChannelResponse = node.ask(&ctx, request).await?;
         *is_finished.lock().await = true;
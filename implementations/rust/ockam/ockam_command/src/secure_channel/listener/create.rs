@@ -9,6 +9,7 @@ use ockam_api::nodes::{BackgroundNode, NODEMANAGER_ADDR};
 use ockam_core::api::{Request, Status};
 use ockam_core::{Address, Route};
 
+use super::limits::ListenerLimitsArgs;
 use crate::node::{get_node_name, initialize_node_if_default, NodeOpts};
 use crate::util::{api, exitcode, node_rpc, parse_node_name};
 use crate::{docs, fmt_log, fmt_ok, terminal::OckamColor, CommandGlobalOpts};
@@ -40,6 +41,13 @@ pub struct CreateCommand {
     /// Name of the Identity that the secure-channel listener will use
     #[arg(value_name = "IDENTITY_NAME", long)]
     identity: Option<String>,
+
+    #[command(flatten)]
+    limits: ListenerLimitsArgs,
+
+    /// Compress the messages of the channels when their initiator proposes it
+    #[arg(long)]
+    compression: bool,
 }
 
 impl CreateCommand {
@@ -66,7 +74,9 @@ async fn run_impl(
             cmd.authorized,
             cmd.vault,
             cmd.identity,
-        ),
+            cmd.limits.to_limits(),
+        )
+        .with_compression(cmd.compression),
     );
     let result = node.tell(ctx, req).await;
     match result {
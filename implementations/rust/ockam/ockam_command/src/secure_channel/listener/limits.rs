@@ -0,0 +1,130 @@
+use clap::Args;
+use colorful::Colorful;
+
+use ockam::Context;
+use ockam_api::nodes::models::secure_channel::{
+    ListenerLimits, ShowSecureChannelListenerResponse, UpdateSecureChannelListenerLimitsRequest,
+};
+use ockam_api::nodes::BackgroundNode;
+use ockam_core::api::Request;
+use ockam_core::Address;
+
+use crate::node::{get_node_name, initialize_node_if_default, NodeOpts};
+use crate::util::{node_rpc, parse_node_name};
+use crate::{docs, fmt_log, fmt_ok, terminal::OckamColor, CommandGlobalOpts};
+
+const LONG_ABOUT: &str = include_str!("./static/limits/long_about.txt");
+const AFTER_LONG_HELP: &str = include_str!("./static/limits/after_long_help.txt");
+
+/// Limits on the handshakes and channels of a Secure Channel Listener
+#[derive(Clone, Debug, Default, Args)]
+pub struct ListenerLimitsArgs {
+    /// Maximum number of handshakes in progress, 0 for no limit
+    #[arg(long, value_name = "COUNT")]
+    max_handshakes: Option<u64>,
+
+    /// Maximum number of channels created by the listener, 0 for no limit
+    #[arg(long, value_name = "COUNT")]
+    max_channels: Option<u64>,
+
+    /// Maximum number of handshakes waiting for a handshake in progress to complete.
+    /// The handshakes exceeding this number are rejected
+    #[arg(long, value_name = "COUNT")]
+    max_queued_handshakes: Option<u64>,
+}
+
+impl ListenerLimitsArgs {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.max_handshakes.is_none()
+            && self.max_channels.is_none()
+            && self.max_queued_handshakes.is_none()
+    }
+
+    pub(crate) fn to_limits(&self) -> Option<ListenerLimits> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(ListenerLimits::new(
+                self.max_handshakes,
+                self.max_channels,
+                self.max_queued_handshakes,
+            ))
+        }
+    }
+}
+
+/// Change the limits of a Secure Channel Listener
+#[derive(Clone, Debug, Args)]
+#[command(
+    arg_required_else_help = true,
+    long_about = docs::about(LONG_ABOUT),
+    after_long_help = docs::after_help(AFTER_LONG_HELP),
+)]
+pub struct LimitsCommand {
+    /// Address of the channel listener
+    address: Address,
+
+    #[command(flatten)]
+    limits: ListenerLimitsArgs,
+
+    #[command(flatten)]
+    node_opts: NodeOpts,
+}
+
+impl LimitsCommand {
+    pub fn run(self, opts: CommandGlobalOpts) {
+        initialize_node_if_default(&opts, &self.node_opts.at_node);
+        node_rpc(rpc, (opts, self));
+    }
+}
+
+async fn rpc(ctx: Context, (opts, cmd): (CommandGlobalOpts, LimitsCommand)) -> miette::Result<()> {
+    run_impl(&ctx, (opts, cmd)).await
+}
+
+async fn run_impl(
+    ctx: &Context,
+    (opts, cmd): (CommandGlobalOpts, LimitsCommand),
+) -> miette::Result<()> {
+    let at = get_node_name(&opts.state, &cmd.node_opts.at_node);
+    let node_name = parse_node_name(&at)?;
+    let node = BackgroundNode::create(ctx, &opts.state, &node_name).await?;
+
+    let req = Request::put("/node/secure_channel_listener").body(
+        UpdateSecureChannelListenerLimitsRequest::new(
+            &cmd.address,
+            cmd.limits.to_limits().unwrap_or_default(),
+        ),
+    );
+    let listener: ShowSecureChannelListenerResponse = node.ask(ctx, req).await?;
+
+    let address = format!("/service/{}", cmd.address.address());
+    opts.terminal
+        .stdout()
+        .plain(
+            fmt_ok!(
+                "Limits of the Secure Channel Listener at {} updated\n",
+                address.clone().color(OckamColor::PrimaryResource.color())
+            ) + &fmt_log!("{}", display_limits(&listener)),
+        )
+        .machine(address)
+        .write_line()?;
+    Ok(())
+}
+
+/// Display the limits of a listener and its current number of handshakes and channels
+pub(crate) fn display_limits(listener: &ShowSecureChannelListenerResponse) -> String {
+    let limit = |max: Option<u64>| match max {
+        Some(max) => max.to_string(),
+        None => "unlimited".to_string(),
+    };
+    format!(
+        "Handshakes {} out of {}, {} queued at most\nChannels {} out of {}\nRejected handshakes {}",
+        listener.handshakes.unwrap_or_default(),
+        limit(listener.max_handshakes),
+        listener.max_queued_handshakes.unwrap_or_default(),
+        listener.channels.unwrap_or_default(),
+        limit(listener.max_channels),
+        listener.rejected_handshakes.unwrap_or_default(),
+    )
+}
@@ -1,10 +1,12 @@
 pub mod create;
 pub mod delete;
+pub mod limits;
 pub mod list;
 pub mod show;
 
 pub(crate) use create::CreateCommand;
 pub(crate) use delete::DeleteCommand;
+pub(crate) use limits::LimitsCommand;
 pub(crate) use list::ListCommand;
 pub(crate) use show::ShowCommand;
 
@@ -29,6 +31,8 @@ pub enum SecureChannelListenerSubcommand {
     List(ListCommand),
     #[command(display_order = 803)]
     Show(ShowCommand),
+    #[command(display_order = 804)]
+    Limits(LimitsCommand),
 }
 
 impl SecureChannelListenerCommand {
@@ -38,6 +42,7 @@ impl SecureChannelListenerCommand {
             SecureChannelListenerSubcommand::Delete(c) => c.run(options),
             SecureChannelListenerSubcommand::List(c) => c.run(options),
             SecureChannelListenerSubcommand::Show(c) => c.run(options),
+            SecureChannelListenerSubcommand::Limits(c) => c.run(options),
         }
     }
 }
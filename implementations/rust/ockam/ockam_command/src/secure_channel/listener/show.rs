@@ -1,9 +1,11 @@
 use clap::Args;
 
 use ockam::Context;
+use ockam_api::nodes::models::secure_channel::ShowSecureChannelListenerResponse;
 use ockam_api::nodes::BackgroundNode;
 use ockam_core::Address;
 
+use super::limits::display_limits;
 use crate::node::{get_node_name, initialize_node_if_default, NodeOpts};
 use crate::util::{api, node_rpc, parse_node_name};
 use crate::{docs, CommandGlobalOpts};
@@ -49,10 +51,12 @@ async fn run_impl(
 
     let node = BackgroundNode::create(ctx, &opts.state, &node_name).await?;
     let req = api::show_secure_channel_listener(address);
-    node.tell(ctx, req).await?;
+    let listener: ShowSecureChannelListenerResponse = node.ask(ctx, req).await?;
+    let address = format!("/service/{}", cmd.address.address());
     opts.terminal
         .stdout()
-        .plain(format!("/service/{}", cmd.address.address()))
+        .plain(format!("{address}\n{}", display_limits(&listener)))
+        .machine(address)
         .write_line()?;
     Ok(())
 }
@@ -1,9 +1,12 @@
 use clap::{Args, Subcommand};
 use colorful::Colorful;
-use miette::miette;
+use miette::{miette, IntoDiagnostic};
 use minicbor::Encode;
+use std::path::PathBuf;
 
 use ockam::Context;
+use ockam_abac::Expr;
+use ockam_api::cloud::project::InfluxDBTokenLeaseManagerConfig;
 use ockam_api::nodes::BackgroundNode;
 use ockam_api::DefaultAddress;
 use ockam_core::api::Request;
@@ -29,9 +32,95 @@ pub enum StartSubCommand {
         #[arg(long, default_value_t = hop_default_addr())]
         addr: String,
     },
+    /// Start a service returning the attributes of the identities known by the node
     Authenticated {
         #[arg(long, default_value_t = authenticated_default_addr())]
         addr: String,
+
+        /// Policy expression checked for each incoming request
+        #[arg(long)]
+        policy: Option<Expr>,
+    },
+    /// Start a service sending back the messages it receives
+    Echo {
+        #[arg(long, default_value_t = echo_default_addr())]
+        addr: String,
+
+        /// Policy expression checked for each incoming message
+        #[arg(long)]
+        policy: Option<Expr>,
+    },
+    /// Start a service sending back the messages it receives, in uppercase
+    Uppercase {
+        #[arg(long, default_value_t = uppercase_default_addr())]
+        addr: String,
+
+        /// Policy expression checked for each incoming message
+        #[arg(long)]
+        policy: Option<Expr>,
+    },
+    /// Start a service dropping the messages it receives
+    Discard {
+        #[arg(long, default_value_t = discard_default_addr())]
+        addr: String,
+
+        /// Policy expression checked for each incoming message
+        #[arg(long)]
+        policy: Option<Expr>,
+    },
+    /// Start a service storing the files sent to it in a directory
+    FileReceiver {
+        #[arg(long, default_value_t = file_receiver_default_addr())]
+        addr: String,
+
+        /// Directory where the received files are stored
+        #[arg(long)]
+        dir: PathBuf,
+
+        /// Maximum size of a received file, in bytes
+        #[arg(long)]
+        max_file_size: Option<u64>,
+
+        /// Policy expression checked for each incoming message
+        #[arg(long)]
+        policy: Option<Expr>,
+    },
+    /// Start the tracer of the node, sending back the trace probes it receives through a secure channel
+    Tracer {
+        /// Policy expression checked for each incoming message
+        #[arg(long)]
+        policy: Option<Expr>,
+    },
+    /// Start a service leasing short-lived InfluxDB tokens to the identities reaching it through a secure channel
+    InfluxdbTokenLease {
+        /// Url of the InfluxDB instance
+        #[arg(long, value_name = "ENDPOINT_URL")]
+        endpoint_url: String,
+
+        /// InfluxDB token with permissions to create and delete tokens
+        #[arg(long, value_name = "INFLUXDB_TOKEN")]
+        token: String,
+
+        /// InfluxDB organization ID
+        #[arg(long, value_name = "ORGANIZATION_ID")]
+        org_id: String,
+
+        /// Path of a JSON file with the permissions of the leased tokens
+        #[arg(long, value_name = "PERMISSIONS_JSON_PATH")]
+        permissions_path: PathBuf,
+
+        /// Time to live of the leased tokens, in seconds
+        #[arg(long, value_name = "MAX_TTL_SECS", default_value = "10800")]
+        max_ttl: i32,
+
+        /// Policy expression of the identities which can lease a token. When it is not
+        /// given, only the identities allowed by the admin policy can lease a token
+        #[arg(long)]
+        user_access_rule: Option<String>,
+
+        /// Policy expression of the identities which can list and revoke all the leases
+        #[arg(long)]
+        admin_access_rule: Option<String>,
     },
     Credentials {
         #[arg(long)]
@@ -60,6 +149,22 @@ fn authenticated_default_addr() -> String {
     DefaultAddress::AUTHENTICATED_SERVICE.to_string()
 }
 
+fn echo_default_addr() -> String {
+    DefaultAddress::ECHO_SERVICE.to_string()
+}
+
+fn uppercase_default_addr() -> String {
+    DefaultAddress::UPPERCASE_SERVICE.to_string()
+}
+
+fn discard_default_addr() -> String {
+    DefaultAddress::DISCARD_SERVICE.to_string()
+}
+
+fn file_receiver_default_addr() -> String {
+    DefaultAddress::FILE_RECEIVER_SERVICE.to_string()
+}
+
 fn credentials_default_addr() -> String {
     DefaultAddress::CREDENTIALS_SERVICE.to_string()
 }
@@ -89,11 +194,71 @@ async fn run_impl(ctx: &Context, opts: CommandGlobalOpts, cmd: StartCommand) ->
             start_hop_service(ctx, &node, &addr).await?;
             addr
         }
-        StartSubCommand::Authenticated { addr, .. } => {
-            let req = api::start_authenticated_service(&addr);
+        StartSubCommand::Authenticated { addr, policy } => {
+            let req = api::start_authenticated_service(&addr, policy);
             start_service_impl(ctx, &node, "Authenticated", req).await?;
             addr
         }
+        StartSubCommand::Echo { addr, policy } => {
+            let req = api::start_echoer_service(&addr, policy);
+            start_service_impl(ctx, &node, "Echo", req).await?;
+            addr
+        }
+        StartSubCommand::Uppercase { addr, policy } => {
+            let req = api::start_uppercase_service(&addr, policy);
+            start_service_impl(ctx, &node, "Uppercase", req).await?;
+            addr
+        }
+        StartSubCommand::Discard { addr, policy } => {
+            let req = api::start_discard_service(&addr, policy);
+            start_service_impl(ctx, &node, "Discard", req).await?;
+            addr
+        }
+        StartSubCommand::FileReceiver {
+            addr,
+            dir,
+            max_file_size,
+            policy,
+        } => {
+            // the directory is resolved here since the node may run in another directory
+            let dir = dir.canonicalize().into_diagnostic()?;
+            let req = api::start_file_receiver_service(
+                &addr,
+                &dir.to_string_lossy(),
+                max_file_size,
+                policy,
+            );
+            start_service_impl(ctx, &node, "File Receiver", req).await?;
+            addr
+        }
+        StartSubCommand::Tracer { policy } => {
+            let req = api::start_tracer_service(policy);
+            start_service_impl(ctx, &node, "Tracer", req).await?;
+            DefaultAddress::TRACER_SERVICE.to_string()
+        }
+        StartSubCommand::InfluxdbTokenLease {
+            endpoint_url,
+            token,
+            org_id,
+            permissions_path,
+            max_ttl,
+            user_access_rule,
+            admin_access_rule,
+        } => {
+            let permissions = std::fs::read_to_string(permissions_path).into_diagnostic()?;
+            let config = InfluxDBTokenLeaseManagerConfig::new(
+                endpoint_url,
+                token,
+                org_id,
+                permissions,
+                max_ttl,
+                user_access_rule,
+                admin_access_rule,
+            );
+            let req = api::start_influxdb_token_lease_manager(config);
+            start_service_impl(ctx, &node, "InfluxDB Token Lease", req).await?;
+            DefaultAddress::INFLUXDB_TOKEN_LEASE.to_string()
+        }
         StartSubCommand::Credentials {
             identity,
             addr,
@@ -4,6 +4,7 @@ use miette::IntoDiagnostic;
 use serde_json::json;
 
 use ockam_api::address::extract_address_value;
+use ockam_api::config::lookup::InternetAddress;
 use ockam_api::nodes::models::transport::TransportStatus;
 use ockam_api::nodes::BackgroundNode;
 use ockam_node::Context;
@@ -58,18 +59,18 @@ impl CreateCommand {
                 }
                 let from = get_node_name(&opts.state, &self.node_opts.from);
                 let to = response.socket_addr().into_diagnostic()?;
+                let to_multiaddr = InternetAddress::from(to).multiaddr().into_diagnostic()?;
                 if opts.global_args.no_color {
                     println!("\n  TCP Connection:");
                     println!("    From: /node/{from}");
-                    println!("    To: {} (/ip4/{}/tcp/{})", to, to.ip(), to.port());
+                    println!("    To: {to} ({to_multiaddr})");
                     println!("    Address: {}", response.multiaddr().into_diagnostic()?);
                 } else {
                     println!("\n  TCP Connection:");
                     println!("{}", format!("    From: /node/{from}").light_magenta());
                     println!(
                         "{}",
-                        format!("    To: {} (/ip4/{}/tcp/{})", to, to.ip(), to.port())
-                            .light_magenta()
+                        format!("    To: {to} ({to_multiaddr})").light_magenta()
                     );
                     println!(
                         "{}",
@@ -14,9 +14,9 @@ use ockam::identity::Identifier;
 use ockam::Context;
 use ockam_abac::Resource;
 use ockam_api::cli_state::{StateDirTrait, StateItemTrait};
-use ockam_api::nodes::models::portal::CreateInlet;
 use ockam_api::nodes::models::portal::InletStatus;
-use ockam_api::nodes::BackgroundNode;
+use ockam_api::nodes::models::portal::{CreateInlet, InletProbe};
+use ockam_api::nodes::{random_idempotency_key, BackgroundNode};
 use ockam_core::api::{Reply, Request, Status};
 use ockam_core::errcode::{Kind, Origin};
 use ockam_core::{route, Error};
@@ -28,14 +28,21 @@ use crate::policy::{add_default_project_policy, has_policy};
 use crate::tcp::util::alias_parser;
 use crate::terminal::OckamColor;
 use crate::util::duration::duration_parser;
-use crate::util::parsers::socket_addr_parser;
+use crate::util::parsers::{outlet_target_parser, socket_addr_parser};
 use crate::util::{
-    find_available_port, node_rpc, parse_node_name, port_is_free_guard, process_nodes_multiaddr,
+    cancel_on_ctrl_c, find_available_port, node_rpc, parse_node_name, process_nodes_multiaddr,
 };
 use crate::{display_parse_logs, docs, fmt_log, fmt_ok, CommandGlobalOpts};
 
 const AFTER_LONG_HELP: &str = include_str!("./static/create/after_long_help.txt");
 
+/// Additional time given to a node to create an inlet, after the outlet is available
+const INLET_CREATION_MARGIN: Duration = Duration::from_secs(10);
+
+/// Additional time given to the node to open a UDP hole to the node of the outlet,
+/// and to create a secure channel through it
+const HOLE_PUNCH_MARGIN: Duration = Duration::from_secs(20);
+
 /// Create TCP Inlets
 #[derive(Clone, Debug, Args)]
 #[command(after_long_help = docs::after_help(AFTER_LONG_HELP))]
@@ -45,8 +52,9 @@ pub struct CreateCommand {
     at: Option<String>,
 
     /// Address on which to accept tcp connections.
-    #[arg(long, display_order = 900, id = "SOCKET_ADDRESS", hide_default_value = true, default_value_t = default_from_addr(), value_parser = socket_addr_parser)]
-    from: SocketAddr,
+    /// By default, the port used the last time this inlet was created on the node is reused if it is free
+    #[arg(long, display_order = 900, id = "SOCKET_ADDRESS", value_parser = socket_addr_parser)]
+    from: Option<SocketAddr>,
 
     /// Route to a tcp outlet.
     #[arg(long, display_order = 900, id = "ROUTE", default_value_t = default_to_addr())]
@@ -67,6 +75,34 @@ pub struct CreateCommand {
     /// Time to wait before retrying to connect to outlet.
     #[arg(long, display_order = 900, id = "RETRY", default_value = "20s", value_parser = duration_parser)]
     retry_wait: Duration,
+
+    /// Ask the outlet to connect to this `host:port` target instead of its own target.
+    /// The target must be allowed by the outlet
+    #[arg(long, display_order = 900, id = "TARGET", value_parser = outlet_target_parser)]
+    outlet_target: Option<String>,
+
+    /// Periodically open a connection through the inlet to measure the latency of the portal.
+    /// The results are shown by `ockam tcp-inlet show`
+    #[arg(long, display_order = 900, id = "PROBE_INTERVAL", value_parser = duration_parser)]
+    probe_interval: Option<Duration>,
+
+    /// Time to wait for the first byte sent by the outlet target when probing the inlet
+    #[arg(long, display_order = 900, id = "PROBE_TIMEOUT", default_value = "10s", value_parser = duration_parser)]
+    probe_timeout: Duration,
+
+    /// Data to send when probing the inlet, for targets waiting for the client to speak first
+    #[arg(
+        long,
+        display_order = 900,
+        id = "PROBE_PAYLOAD",
+        requires = "PROBE_INTERVAL"
+    )]
+    probe_payload: Option<String>,
+
+    /// Address of a UDP Rendezvous service, used to open a direct UDP path to the node of the
+    /// outlet instead of going through a relay. The relayed route is used if the path can't be opened
+    #[arg(long, display_order = 900, id = "UDP_RENDEZVOUS", value_parser = socket_addr_parser)]
+    udp_rendezvous: Option<SocketAddr>,
 }
 
 pub(crate) fn default_from_addr() -> SocketAddr {
@@ -84,31 +120,49 @@ impl CreateCommand {
         initialize_node_if_default(&opts, &self.at);
         node_rpc(rpc, (opts, self));
     }
+
+    /// Return the address to bind, reserved for this inlet on the node so that
+    /// the same port is used when the inlet is created again
+    fn bind_address(
+        &self,
+        opts: &CommandGlobalOpts,
+        node_name: &str,
+    ) -> miette::Result<SocketAddr> {
+        let purpose = format!(
+            "tcp-inlet:{}",
+            self.alias.clone().unwrap_or_else(|| self.to.to_string())
+        );
+        match self.from {
+            Some(from) => {
+                opts.state.nodes.reserve_port(node_name, &purpose, &from)?;
+                Ok(from)
+            }
+            None => Ok(opts.state.nodes.allocate_port(node_name, &purpose)?),
+        }
+    }
 }
 
 async fn rpc(
     ctx: Context,
     (opts, mut cmd): (CommandGlobalOpts, CreateCommand),
 ) -> miette::Result<()> {
+    let node_name = get_node_name(&opts.state, &cmd.at);
+    let node_name = parse_node_name(&node_name)?;
+    let from = cmd.bind_address(&opts, &node_name)?;
+
     opts.terminal.write_line(&fmt_log!(
         "Creating TCP Inlet at {}...\n",
-        cmd.from
-            .to_string()
-            .color(OckamColor::PrimaryResource.color())
+        from.to_string().color(OckamColor::PrimaryResource.color())
     ))?;
     display_parse_logs(&opts);
 
     cmd.to = process_nodes_multiaddr(&cmd.to, &opts.state)?;
 
-    let node_name = get_node_name(&opts.state, &cmd.at);
-    let node_name = parse_node_name(&node_name)?;
-
-    let node = BackgroundNode::create(&ctx, &opts.state, &node_name).await?;
+    let mut node = BackgroundNode::create(&ctx, &opts.state, &node_name).await?;
+    node.set_cancellation_token(cancel_on_ctrl_c());
     let is_finished: Mutex<bool> = Mutex::new(false);
     let progress_bar = opts.terminal.progress_spinner();
     let create_inlet = async {
-        port_is_free_guard(&cmd.from)?;
-
         let project = opts
             .state
             .nodes
@@ -133,18 +187,15 @@ async fn rpc(
             false
         };
 
+        // the inlet is created only once, even if it is created by a retried request
+        let idempotency_key = random_idempotency_key();
         let inlet = loop {
             let req = {
                 let mut payload = if via_project {
-                    CreateInlet::via_project(
-                        cmd.from.to_string(),
-                        cmd.to.clone(),
-                        route![],
-                        route![],
-                    )
+                    CreateInlet::via_project(from.to_string(), cmd.to.clone(), route![], route![])
                 } else {
                     CreateInlet::to_node(
-                        cmd.from.to_string(),
+                        from.to_string(),
                         cmd.to.clone(),
                         route![],
                         route![],
@@ -155,8 +206,30 @@ async fn rpc(
                     payload.set_alias(a)
                 }
                 payload.set_wait_ms(cmd.connection_wait.as_millis() as u64);
+                if let Some(target) = cmd.outlet_target.as_ref() {
+                    payload.set_outlet_target(target)
+                }
+                if let Some(interval) = cmd.probe_interval {
+                    let probe = InletProbe::new(interval, cmd.probe_timeout);
+                    payload.set_probe(match cmd.probe_payload.as_ref() {
+                        Some(data) => probe.with_payload(data),
+                        None => probe,
+                    });
+                }
+                if let Some(rendezvous) = cmd.udp_rendezvous {
+                    payload.set_udp_rendezvous(rendezvous.to_string())
+                }
+                let deadline = match cmd.udp_rendezvous {
+                    Some(_) => cmd.connection_wait + INLET_CREATION_MARGIN + HOLE_PUNCH_MARGIN,
+                    None => cmd.connection_wait + INLET_CREATION_MARGIN,
+                };
 
-                Request::post("/node/inlet").body(payload)
+                // The node stops creating the inlet if it takes longer than the time
+                // allowed to wait for the outlet
+                Request::post("/node/inlet")
+                    .body(payload)
+                    .deadline(deadline)
+                    .idempotency_key(&idempotency_key)
             };
 
             let result: Reply<InletStatus> = node.ask_and_get_reply(&ctx, req).await?;
@@ -207,9 +280,7 @@ async fn rpc(
         ),
         format!(
             "Hosting TCP Socket at {}...",
-            &cmd.from
-                .to_string()
-                .color(OckamColor::PrimaryResource.color())
+            &from.to_string().color(OckamColor::PrimaryResource.color())
         ),
         format!(
             "Establishing connection to outlet {}...",
@@ -235,9 +306,7 @@ async fn rpc(
         .plain(
             fmt_ok!(
                 "TCP Inlet {} on node {} is now sending traffic\n",
-                &cmd.from
-                    .to_string()
-                    .color(OckamColor::PrimaryResource.color()),
+                &from.to_string().color(OckamColor::PrimaryResource.color()),
                 &node_name
                     .to_string()
                     .color(OckamColor::PrimaryResource.color())
@@ -3,6 +3,7 @@ use colorful::Colorful;
 use miette::{miette, IntoDiagnostic};
 
 use ockam::Context;
+use ockam_api::nodes::models::dry_run::DryRun;
 use ockam_api::nodes::models::portal::InletStatus;
 use ockam_api::nodes::BackgroundNode;
 use ockam_core::api::Request;
@@ -10,7 +11,7 @@ use ockam_core::api::Request;
 use crate::fmt_ok;
 use crate::node::{get_node_name, initialize_node_if_default, NodeOpts};
 use crate::tcp::util::alias_parser;
-use crate::util::{node_rpc, parse_node_name};
+use crate::util::{api, node_rpc, parse_node_name, print_dry_run};
 use crate::{docs, CommandGlobalOpts};
 
 const AFTER_LONG_HELP: &str = include_str!("./static/delete/after_long_help.txt");
@@ -30,6 +31,10 @@ pub struct DeleteCommand {
     /// Confirm the deletion without prompting
     #[arg(display_order = 901, long, short)]
     yes: bool,
+
+    /// Only show what would be deleted, including the sessions which would be cut
+    #[arg(display_order = 902, long)]
+    dry_run: bool,
 }
 
 impl DeleteCommand {
@@ -57,13 +62,17 @@ pub async fn run_impl(
             "TCP inlet with alias {alias} was not found on Node {node_name}"
         ))?;
 
+    if cmd.dry_run {
+        let dry_run: DryRun = node.ask(&ctx, api::dry_run_delete_inlet(&alias)).await?;
+        return print_dry_run(&opts, &dry_run);
+    }
+
     // Proceed with the deletion
     if opts
         .terminal
         .confirmed_with_flag_or_prompt(cmd.yes, "Are you sure you want to delete this TCP inlet?")?
     {
-        node.tell(&ctx, Request::delete(format!("/node/inlet/{alias}")))
-            .await?;
+        node.tell(&ctx, api::delete_inlet(&alias)).await?;
 
         opts.terminal
             .stdout()
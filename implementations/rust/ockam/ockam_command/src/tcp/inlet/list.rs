@@ -70,11 +70,11 @@ async fn run_impl(
     let (inlets, _) = try_join!(get_inlets, progress_output)?;
 
     let plain = opts.terminal.build_list(
-        &inlets.list,
+        &inlets.items,
         "Inlets",
         &format!("No TCP Inlets found on {node_name}"),
     )?;
-    let json = serde_json::to_string_pretty(&inlets.list).into_diagnostic()?;
+    let json = inlets.to_json().into_diagnostic()?;
     opts.terminal
         .stdout()
         .plain(plain)
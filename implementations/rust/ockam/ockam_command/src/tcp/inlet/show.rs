@@ -54,14 +54,26 @@ pub async fn run_impl(
         alias,
         bind_addr,
         outlet_route,
+        last_probe,
         ..
     } = inlet_status;
-    let plain = formatdoc! {r#"
+    let mut plain = formatdoc! {r#"
         Inlet:
           Alias: {alias}
           TCP Address: {bind_addr}
           To Outlet Address: {outlet_route}
     "#};
+    if let Some(probe) = last_probe {
+        let result = match (probe.error, probe.handshake_ms, probe.first_byte_ms) {
+            (Some(error), _, _) => format!("failed: {error}"),
+            (None, handshake, first_byte) => format!(
+                "handshake {}ms, first byte {}ms",
+                handshake.unwrap_or_default(),
+                first_byte.unwrap_or_default()
+            ),
+        };
+        plain.push_str(&format!("  Last Probe: {result}\n"));
+    }
     let machine = bind_addr;
     opts.terminal
         .stdout()
@@ -0,0 +1,82 @@
+use std::time::Duration;
+
+use clap::Args;
+use miette::IntoDiagnostic;
+use tracing::debug;
+
+use ockam::identity::Identifier;
+use ockam::Context;
+use ockam_api::nodes::InMemoryNode;
+use ockam_multiaddr::MultiAddr;
+
+use crate::identity::{get_identity_name, initialize_identity_if_default};
+use crate::util::api::{CloudOpts, TrustContextOpts};
+use crate::util::duration::duration_parser;
+use crate::util::{node_rpc, process_nodes_multiaddr};
+use crate::{docs, CommandGlobalOpts};
+
+const AFTER_LONG_HELP: &str = include_str!("./static/stdio/after_long_help.txt");
+
+/// Stream the standard input and output of this command to a TCP Outlet.
+///
+/// No local port is opened: this command can be used as an OpenSSH ProxyCommand
+/// to connect to an SSH server exposed by an outlet, with the identity of the command
+#[derive(Clone, Debug, Args)]
+#[command(arg_required_else_help = true, after_long_help = docs::after_help(AFTER_LONG_HELP))]
+pub struct StdioCommand {
+    /// Route to a tcp outlet.
+    #[arg(long, display_order = 900, id = "ROUTE")]
+    to: MultiAddr,
+
+    /// Authorized identity for secure channel connection
+    #[arg(long, name = "AUTHORIZED", display_order = 900)]
+    authorized: Option<Identifier>,
+
+    /// Time to wait for the outlet to be available.
+    #[arg(long, display_order = 900, id = "WAIT", default_value = "5s", value_parser = duration_parser)]
+    connection_wait: Duration,
+
+    #[command(flatten)]
+    cloud_opts: CloudOpts,
+
+    #[command(flatten)]
+    trust_context_opts: TrustContextOpts,
+}
+
+impl StdioCommand {
+    pub fn run(self, opts: CommandGlobalOpts) {
+        initialize_identity_if_default(&opts, &self.cloud_opts.identity);
+        node_rpc(rpc, (opts, self));
+    }
+}
+
+async fn rpc(ctx: Context, (opts, cmd): (CommandGlobalOpts, StdioCommand)) -> miette::Result<()> {
+    let to = process_nodes_multiaddr(&cmd.to, &opts.state)?;
+    let identity_name = get_identity_name(&opts.state, &cmd.cloud_opts.identity);
+    let trust_context_config = cmd.trust_context_opts.to_config(&opts.state)?.build();
+    let node = InMemoryNode::start_node(
+        &ctx,
+        &opts.state,
+        None,
+        Some(identity_name),
+        cmd.trust_context_opts.project_path.as_ref(),
+        trust_context_config,
+    )
+    .await?;
+
+    let inlet = node
+        .create_stream_inlet(
+            &ctx,
+            &to,
+            Some(cmd.connection_wait),
+            cmd.authorized,
+            tokio::io::stdin(),
+            tokio::io::stdout(),
+        )
+        .await
+        .into_diagnostic()?;
+    // stdout only carries the bytes sent by the outlet, nothing else can be displayed
+    debug!(address = %inlet.address(), "streaming stdio to {to}");
+    inlet.closed().await;
+    Ok(())
+}
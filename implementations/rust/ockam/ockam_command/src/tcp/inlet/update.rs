@@ -0,0 +1,96 @@
+use std::net::SocketAddr;
+
+use clap::Args;
+use colorful::Colorful;
+use miette::IntoDiagnostic;
+
+use ockam::Context;
+use ockam_api::nodes::models::portal::{InletStatus, RebindInlet};
+use ockam_api::nodes::BackgroundNode;
+use ockam_core::api::Request;
+
+use crate::node::{get_node_name, initialize_node_if_default, NodeOpts};
+use crate::tcp::util::alias_parser;
+use crate::terminal::OckamColor;
+use crate::util::parsers::socket_addr_parser;
+use crate::util::{node_rpc, parse_node_name};
+use crate::{docs, fmt_ok, CommandGlobalOpts};
+
+const AFTER_LONG_HELP: &str = include_str!("./static/update/after_long_help.txt");
+
+/// Move a TCP Inlet to a new address, without closing its open connections
+#[derive(Clone, Debug, Args)]
+#[command(after_long_help = docs::after_help(AFTER_LONG_HELP))]
+pub struct UpdateCommand {
+    /// Name of the inlet
+    #[arg(display_order = 900, required = true, id = "ALIAS", value_parser = alias_parser)]
+    alias: String,
+
+    /// New address on which to accept tcp connections
+    #[arg(long, display_order = 900, id = "SOCKET_ADDRESS", value_parser = socket_addr_parser)]
+    from: SocketAddr,
+
+    /// Node on which the inlet was started
+    #[command(flatten)]
+    node_opts: NodeOpts,
+}
+
+impl UpdateCommand {
+    pub fn run(self, opts: CommandGlobalOpts) {
+        initialize_node_if_default(&opts, &self.node_opts.at_node);
+        node_rpc(run_impl, (opts, self))
+    }
+}
+
+pub async fn run_impl(
+    ctx: Context,
+    (opts, cmd): (CommandGlobalOpts, UpdateCommand),
+) -> miette::Result<()> {
+    let node_name = get_node_name(&opts.state, &cmd.node_opts.at_node);
+    let node_name = parse_node_name(&node_name)?;
+    let alias = cmd.alias;
+
+    // The new address is reused the next time this inlet is created on the node
+    let purpose = format!("tcp-inlet:{alias}");
+    let previous = opts
+        .state
+        .nodes
+        .port_reservations()?
+        .get(&node_name, &purpose);
+    opts.state
+        .nodes
+        .reserve_port(&node_name, &purpose, &cmd.from)?;
+
+    let node = BackgroundNode::create(&ctx, &opts.state, &node_name).await?;
+    let inlet_status: InletStatus = match node
+        .ask(
+            &ctx,
+            Request::put(format!("/node/inlet/{alias}"))
+                .body(RebindInlet::new(cmd.from.to_string())),
+        )
+        .await
+    {
+        Ok(inlet_status) => inlet_status,
+        Err(e) => {
+            // The inlet still listens on its previous address
+            opts.state
+                .nodes
+                .restore_port_reservation(&node_name, &purpose, previous)?;
+            return Err(e);
+        }
+    };
+
+    opts.terminal
+        .stdout()
+        .plain(fmt_ok!(
+            "TCP inlet {alias} on node {node_name} is now listening at {}",
+            inlet_status
+                .bind_addr
+                .clone()
+                .color(OckamColor::PrimaryResource.color())
+        ))
+        .machine(&inlet_status.bind_addr)
+        .json(serde_json::to_string(&inlet_status).into_diagnostic()?)
+        .write_line()?;
+    Ok(())
+}
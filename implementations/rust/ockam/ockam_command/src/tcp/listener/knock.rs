@@ -0,0 +1,73 @@
+use std::net::SocketAddr;
+
+use clap::Args;
+use colorful::Colorful;
+use miette::IntoDiagnostic;
+
+use ockam::Context;
+use ockam_api::knock::Knock;
+use ockam_transport_tcp::send_knock;
+
+use crate::util::node_rpc;
+use crate::util::parsers::socket_addr_parser;
+use crate::{docs, fmt_ok, CommandGlobalOpts};
+
+const AFTER_LONG_HELP: &str = include_str!("./static/knock/after_long_help.txt");
+
+/// Send a knock to a TCP listener protected by port knocking, so that the connections
+/// from this host are accepted for a while
+#[derive(Clone, Debug, Args)]
+#[command(after_long_help = docs::after_help(AFTER_LONG_HELP))]
+pub struct KnockCommand {
+    /// Address of the listener (eg. 192.168.1.10:4000)
+    #[arg(value_parser = socket_addr_parser)]
+    pub address: SocketAddr,
+
+    /// Name of the identity signing the knock. Defaults to the default identity
+    #[arg(long, value_name = "IDENTITY_NAME")]
+    pub identity: Option<String>,
+
+    /// Name of the vault storing the key of the identity. Defaults to the default vault
+    #[arg(long, value_name = "VAULT_NAME")]
+    pub vault: Option<String>,
+}
+
+impl KnockCommand {
+    pub fn run(self, opts: CommandGlobalOpts) {
+        node_rpc(run_impl, (opts, self));
+    }
+}
+
+async fn run_impl(
+    _ctx: Context,
+    (opts, cmd): (CommandGlobalOpts, KnockCommand),
+) -> miette::Result<()> {
+    let identities = match &cmd.vault {
+        Some(vault) => {
+            let vault = opts.state.vaults.get(vault)?.get().await?;
+            opts.state.get_identities(vault).await?
+        }
+        None => opts.state.default_identities().await?,
+    };
+    let identifier = opts
+        .state
+        .identities
+        .get_or_default(cmd.identity.as_deref())?
+        .identifier();
+
+    let knock = Knock::create(&identities, &identifier, cmd.address)
+        .await
+        .into_diagnostic()?;
+    send_knock(cmd.address, &knock).await.into_diagnostic()?;
+
+    let address = cmd.address;
+    opts.terminal
+        .stdout()
+        .plain(fmt_ok!("A knock was sent to the TCP listener at {address}"))
+        .json(serde_json::json!({
+            "address": address.to_string(),
+            "identifier": identifier.to_string(),
+        }))
+        .write_line()?;
+    Ok(())
+}
@@ -1,10 +1,12 @@
 mod create;
 mod delete;
+mod knock;
 mod list;
 mod show;
 
 pub(crate) use create::CreateCommand;
 pub(crate) use delete::DeleteCommand;
+pub(crate) use knock::KnockCommand;
 pub(crate) use list::ListCommand;
 pub(crate) use show::ShowCommand;
 
@@ -26,6 +28,9 @@ pub enum TcpListenerSubCommand {
     /// Delete tcp listener on the selected node
     Delete(DeleteCommand),
 
+    /// Send a knock to a tcp listener protected by port knocking
+    Knock(KnockCommand),
+
     /// List tcp listeners registered on the selected node
     List(ListCommand),
 
@@ -38,6 +43,7 @@ impl TcpListenerCommand {
         match self.subcommand {
             TcpListenerSubCommand::Create(c) => c.run(options),
             TcpListenerSubCommand::Delete(c) => c.run(options),
+            TcpListenerSubCommand::Knock(c) => c.run(options),
             TcpListenerSubCommand::List(c) => c.run(options),
             TcpListenerSubCommand::Show(c) => c.run(options),
         }
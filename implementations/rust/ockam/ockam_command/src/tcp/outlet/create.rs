@@ -6,20 +6,24 @@ use miette::IntoDiagnostic;
 use tokio::sync::Mutex;
 use tokio::try_join;
 
+use ockam::identity::Identifier;
 use ockam::Context;
 use ockam_abac::Resource;
 use ockam_api::address::extract_address_value;
 use ockam_api::cli_state::{StateDirTrait, StateItemTrait};
-use ockam_api::nodes::models::portal::{CreateOutlet, OutletStatus};
-use ockam_api::nodes::BackgroundNode;
+use ockam_api::nodes::models::portal::{CreateOutlet, OutletStatus, OutletTargets};
+use ockam_api::nodes::{random_idempotency_key, BackgroundNode};
 use ockam_core::api::Request;
+use ockam_transport_tcp::{AllowedTarget, OutletProtocol};
 
 use crate::node::{get_node_name, initialize_node_if_default};
 use crate::policy::{add_default_project_policy, has_policy};
 use crate::tcp::util::alias_parser;
 use crate::terminal::OckamColor;
 use crate::util::node_rpc;
-use crate::util::parsers::socket_addr_parser;
+use crate::util::parsers::{
+    allowed_target_parser, identity_target_parser, outlet_protocol_parser, socket_addr_parser,
+};
 use crate::{display_parse_logs, fmt_log};
 use crate::{docs, fmt_ok, CommandGlobalOpts};
 
@@ -44,6 +48,26 @@ pub struct CreateCommand {
     /// Assign a name to this outlet.
     #[arg(long, display_order = 900, id = "ALIAS", value_parser = alias_parser)]
     alias: Option<String>,
+
+    /// Allow the inlets to request this target instead of the outlet target.
+    /// Written `host:port`, or `host:*` to allow any port. Can be repeated
+    #[arg(long, display_order = 903, id = "TARGET", value_parser = allowed_target_parser)]
+    allow_target: Vec<AllowedTarget>,
+
+    /// Allow only the inlets of a given identity to request a target.
+    /// Written `<identifier>=<host:port>`. Can be repeated
+    #[arg(long, display_order = 903, id = "IDENTITY_TARGET", value_parser = identity_target_parser)]
+    allow_target_for: Vec<(Identifier, AllowedTarget)>,
+
+    /// Close the connections which don't use this protocol, checked on their first bytes
+    /// before anything is sent to the target: `tls` or `http`
+    #[arg(long, display_order = 904, id = "PROTOCOL", value_parser = outlet_protocol_parser)]
+    protocol: Option<OutletProtocol>,
+
+    /// Accept the requests of the inlets to open a direct UDP path to this node,
+    /// with the UDP Rendezvous service given by the inlet
+    #[arg(long, display_order = 904)]
+    accept_hole_punching: bool,
 }
 
 impl CreateCommand {
@@ -89,12 +113,33 @@ pub async fn run_impl(
     let is_finished: Mutex<bool> = Mutex::new(false);
 
     let send_req = async {
-        let payload = CreateOutlet::new(
+        let mut payload = CreateOutlet::new(
             cmd.to,
             extract_address_value(&cmd.from)?.into(),
             cmd.alias,
             true,
         );
+        let targets = cmd
+            .allow_target
+            .iter()
+            .fold(OutletTargets::default(), |targets, t| {
+                targets.allow(t.to_string())
+            });
+        let targets = cmd
+            .allow_target_for
+            .iter()
+            .fold(targets, |targets, (identifier, t)| {
+                targets.allow_for(identifier.clone(), t.to_string())
+            });
+        if !targets.is_empty() {
+            payload.set_targets(targets);
+        }
+        if let Some(protocol) = cmd.protocol {
+            payload.set_protocol(protocol.to_string());
+        }
+        if cmd.accept_hole_punching {
+            payload.set_accept_hole_punching();
+        }
         let res = send_request(&ctx, &opts, payload, node_name.clone()).await;
         *is_finished.lock().await = true;
         res
@@ -152,6 +197,8 @@ pub async fn send_request(
 ) -> crate::Result<OutletStatus> {
     let node_name = get_node_name(&opts.state, &to_node.into());
     let node = BackgroundNode::create(ctx, &opts.state, &node_name).await?;
-    let req = Request::post("/node/outlet").body(payload);
+    let req = Request::post("/node/outlet")
+        .body(payload)
+        .idempotency_key(random_idempotency_key());
     Ok(node.ask(ctx, req).await?)
 }
@@ -1,6 +1,6 @@
 use clap::Args;
 use colorful::Colorful;
-use miette::miette;
+use miette::{miette, IntoDiagnostic};
 use tokio::sync::Mutex;
 use tokio::try_join;
 
@@ -8,6 +8,7 @@ use ockam_api::address::extract_address_value;
 use ockam_api::cli_state::StateDirTrait;
 use ockam_api::nodes::models::portal::OutletList;
 use ockam_api::nodes::BackgroundNode;
+use ockam_api::resource_list::ResourceList;
 use ockam_core::api::Request;
 use ockam_node::Context;
 
@@ -69,12 +70,12 @@ async fn run_impl(
     let (outlets, _) = try_join!(send_req, progress_output)?;
 
     let list = opts.terminal.build_list(
-        &outlets.list,
+        &outlets.items,
         &format!("Outlets on Node {node_name}"),
         &format!("No TCP Outlets found on node {node_name}."),
     )?;
     let json: Vec<_> = outlets
-        .list
+        .items
         .iter()
         .map(|outlet| {
             Ok(serde_json::json!({
@@ -85,11 +86,8 @@ async fn run_impl(
         })
         .flat_map(|res: Result<_, ockam_core::Error>| res.ok())
         .collect();
-    opts.terminal
-        .stdout()
-        .plain(list)
-        .json(serde_json::json!(json))
-        .write_line()?;
+    let json = ResourceList::new(json).to_json().into_diagnostic()?;
+    opts.terminal.stdout().plain(list).json(json).write_line()?;
 
     Ok(())
 }
@@ -1,6 +1,7 @@
 pub mod create;
 mod delete;
 pub mod list;
+mod routes;
 mod show;
 
 use crate::{docs, CommandGlobalOpts};
@@ -8,6 +9,7 @@ use clap::{Args, Subcommand};
 use create::CreateCommand;
 use delete::DeleteCommand;
 use list::ListCommand;
+use routes::RoutesCommand;
 use show::ShowCommand;
 
 const LONG_ABOUT: &str = include_str!("./static/long_about.txt");
@@ -31,6 +33,7 @@ pub enum TcpOutletSubCommand {
     Create(CreateCommand),
     Delete(DeleteCommand),
     List(ListCommand),
+    Routes(RoutesCommand),
     Show(ShowCommand),
 }
 
@@ -40,6 +43,7 @@ impl TcpOutletCommand {
             TcpOutletSubCommand::Create(c) => c.run(options),
             TcpOutletSubCommand::Delete(c) => c.run(options),
             TcpOutletSubCommand::List(c) => c.run(options),
+            TcpOutletSubCommand::Routes(c) => c.run(options),
             TcpOutletSubCommand::Show(c) => c.run(options),
         }
     }
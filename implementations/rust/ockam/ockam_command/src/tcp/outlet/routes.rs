@@ -0,0 +1,92 @@
+use clap::Args;
+use colorful::Colorful;
+
+use ockam::Context;
+use ockam_api::nodes::models::portal::{OutletRoute, OutletRoutes};
+use ockam_api::nodes::BackgroundNode;
+use ockam_core::api::Request;
+
+use crate::node::{get_node_name, initialize_node_if_default, NodeOpts};
+use crate::tcp::util::alias_parser;
+use crate::terminal::OckamColor;
+use crate::util::parsers::outlet_route_parser;
+use crate::util::{node_rpc, parse_node_name};
+use crate::{docs, fmt_ok, CommandGlobalOpts};
+
+const AFTER_LONG_HELP: &str = include_str!("./static/routes/after_long_help.txt");
+
+/// Show or replace the routes selecting the target of a TCP Outlet
+/// with the attributes of the connecting identities
+#[derive(Clone, Debug, Args)]
+#[command(after_long_help = docs::after_help(AFTER_LONG_HELP))]
+pub struct RoutesCommand {
+    /// Alias of the outlet
+    #[arg(display_order = 900, required = true, id = "ALIAS", value_parser = alias_parser)]
+    alias: String,
+
+    /// Node of the outlet. If none are provided, the default node will be used
+    #[command(flatten)]
+    node_opts: NodeOpts,
+
+    /// Route the connections of the identities having an attribute to a target, written
+    /// `<attribute>=<value>=<host:port>`. The routes are tried in order and replace the
+    /// current routes. Can be repeated
+    #[arg(
+        long = "route",
+        display_order = 901,
+        value_name = "ROUTE",
+        value_parser = outlet_route_parser
+    )]
+    routes: Vec<OutletRoute>,
+
+    /// Remove all the routes of the outlet
+    #[arg(long, display_order = 902, conflicts_with = "routes")]
+    clear: bool,
+}
+
+impl RoutesCommand {
+    pub fn run(self, opts: CommandGlobalOpts) {
+        initialize_node_if_default(&opts, &self.node_opts.at_node);
+        node_rpc(run_impl, (opts, self))
+    }
+}
+
+async fn run_impl(
+    ctx: Context,
+    (opts, cmd): (CommandGlobalOpts, RoutesCommand),
+) -> miette::Result<()> {
+    let node_name = get_node_name(&opts.state, &cmd.node_opts.at_node);
+    let node_name = parse_node_name(&node_name)?;
+    let node = BackgroundNode::create(&ctx, &opts.state, &node_name).await?;
+    let alias = cmd.alias;
+    let path = format!("/node/outlet/{alias}/routes");
+
+    let routes: OutletRoutes = if cmd.clear || !cmd.routes.is_empty() {
+        let routes = OutletRoutes { routes: cmd.routes };
+        let routes = node.ask(&ctx, Request::put(path).body(routes)).await?;
+        opts.terminal.write_line(&fmt_ok!(
+            "The routes of the TCP outlet {} are replaced",
+            alias.clone().color(OckamColor::PrimaryResource.color())
+        ))?;
+        routes
+    } else {
+        node.ask(&ctx, Request::get(path)).await?
+    };
+
+    let plain = if routes.routes.is_empty() {
+        format!("The TCP outlet {alias} has no routes")
+    } else {
+        routes
+            .routes
+            .iter()
+            .map(|route| format!("{}={} -> {}", route.attribute, route.value, route.target))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    opts.terminal
+        .stdout()
+        .plain(plain)
+        .json(serde_json::json!(routes))
+        .write_line()?;
+    Ok(())
+}
@@ -1,9 +1,12 @@
-use crate::util::local_cmd;
+use crate::util::{local_cmd, node_rpc};
 use crate::{docs, util::api::TrustContextOpts, CommandGlobalOpts};
 use clap::Args;
 use indoc::formatdoc;
 use miette::{miette, IntoDiagnostic};
-use ockam_api::cli_state::{random_name, StateDirTrait};
+use ockam::Context;
+use ockam_api::cli_state::{random_name, StateDirTrait, StateItemTrait};
+use ockam_api::config::bootstrap::TrustContextBootstrap;
+use ockam_api::config::cli::TrustContextConfig;
 
 const LONG_ABOUT: &str = include_str!("./static/create/long_about.txt");
 const AFTER_LONG_HELP: &str = include_str!("./static/create/after_long_help.txt");
@@ -24,16 +27,67 @@ pub struct CreateCommand {
     #[arg(long)]
     credential: Option<String>,
 
+    /// Create a trust context from a bootstrap URI, shared by the operator of an authority:
+    /// ockam://trust-context?authority=<HEX_IDENTITY>&route=<MULTIADDR>&credential=issuer|none
+    #[arg(long, value_name = "URI", conflicts_with = "credential")]
+    bootstrap: Option<String>,
+
+    /// The expected identifier of the authority of the bootstrap URI.
+    /// The identifier is confirmed interactively if not provided
+    #[arg(long, value_name = "IDENTIFIER", requires = "bootstrap")]
+    fingerprint: Option<String>,
+
+    /// Trust the authority of the bootstrap URI without prompting
+    #[arg(long, short, requires = "bootstrap")]
+    yes: bool,
+
     #[command(flatten)]
     trust_context_opts: TrustContextOpts,
 }
 
 impl CreateCommand {
     pub fn run(self, opts: CommandGlobalOpts) {
-        local_cmd(run_impl(opts, self));
+        if self.bootstrap.is_some() {
+            node_rpc(run_bootstrap_impl, (opts, self));
+        } else {
+            local_cmd(run_impl(opts, self));
+        }
     }
 }
 
+async fn run_bootstrap_impl(
+    _ctx: Context,
+    (opts, cmd): (CommandGlobalOpts, CreateCommand),
+) -> miette::Result<()> {
+    let uri = cmd.bootstrap.clone().unwrap_or_default();
+    let bootstrap: TrustContextBootstrap = uri.parse().into_diagnostic()?;
+    let identifier = bootstrap.authority_identifier().await.into_diagnostic()?;
+
+    let fingerprint = match cmd.fingerprint {
+        Some(fingerprint) => fingerprint,
+        None => {
+            let route = bootstrap
+                .authority_route()
+                .map(|r| r.to_string())
+                .unwrap_or_else(|| "no route".to_string());
+            if !opts.terminal.confirmed_with_flag_or_prompt(
+                cmd.yes,
+                format!("Do you trust the authority {identifier} ({route})?"),
+            )? {
+                return Ok(());
+            }
+            identifier.to_string()
+        }
+    };
+
+    let state = opts
+        .state
+        .trust_contexts
+        .create_from_bootstrap(&cmd.name, &uri, Some(&fingerprint))
+        .await?;
+    display_trust_context(&opts, &cmd.name, state.config())
+}
+
 fn run_impl(opts: CommandGlobalOpts, cmd: CreateCommand) -> miette::Result<()> {
     let config = cmd
         .trust_context_opts
@@ -44,33 +98,41 @@ fn run_impl(opts: CommandGlobalOpts, cmd: CreateCommand) -> miette::Result<()> {
 
     if let Some(c) = config {
         opts.state.trust_contexts.create(&cmd.name, c.clone())?;
-
-        let auth = if let Ok(auth) = c.authority() {
-            auth.identity_str()
-        } else {
-            "None"
-        };
-
-        let output = formatdoc!(
-            r#"
-            Trust Context:
-                Name: {}
-                ID: {}
-                Authority: {}
-            "#,
-            cmd.name,
-            c.id(),
-            auth
-        );
-
-        opts.terminal
-            .stdout()
-            .plain(output)
-            .json(serde_json::to_string_pretty(&c).into_diagnostic()?)
-            .write_line()?;
+        display_trust_context(&opts, &cmd.name, &c)?;
     } else {
         return Err(miette!("Unable to create trust context"));
     }
 
     Ok(())
 }
+
+fn display_trust_context(
+    opts: &CommandGlobalOpts,
+    name: &str,
+    config: &TrustContextConfig,
+) -> miette::Result<()> {
+    let auth = if let Ok(auth) = config.authority() {
+        auth.identity_str()
+    } else {
+        "None"
+    };
+
+    let output = formatdoc!(
+        r#"
+        Trust Context:
+            Name: {}
+            ID: {}
+            Authority: {}
+        "#,
+        name,
+        config.id(),
+        auth
+    );
+
+    opts.terminal
+        .stdout()
+        .plain(output)
+        .json(serde_json::to_string_pretty(config).into_diagnostic()?)
+        .write_line()?;
+    Ok(())
+}
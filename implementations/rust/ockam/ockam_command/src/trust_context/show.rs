@@ -1,5 +1,6 @@
 use clap::Args;
-use ockam_api::cli_state::traits::StateDirTrait;
+use ockam_api::cli_state::traits::{StateDirTrait, StateItemTrait};
+use ockam_api::config::bootstrap::TrustContextBootstrap;
 
 use crate::util::local_cmd;
 use crate::{docs, CommandGlobalOpts};
@@ -36,6 +37,10 @@ fn run_impl(opts: CommandGlobalOpts, cmd: ShowCommand) -> miette::Result<()> {
         for line in state.to_string().lines() {
             output.push_str(&format!("{:2}{}\n", "", line));
         }
+        // the bootstrap URI can be shared to configure the same trust context on other machines
+        if let Ok(bootstrap) = TrustContextBootstrap::try_from(state.config()) {
+            output.push_str(&format!("{:2}Bootstrap URI: {}\n", "", bootstrap));
+        }
         output
     };
     opts.terminal.stdout().plain(plain_output).write_line()?;
@@ -9,11 +9,15 @@ use minicbor::Decoder;
 use regex::Regex;
 
 use ockam::identity::Identifier;
+use ockam_abac::Expr;
 use ockam_api::cli_state::CliState;
+use ockam_api::cloud::project::InfluxDBTokenLeaseManagerConfig;
 use ockam_api::nodes::models::flow_controls::AddConsumer;
 use ockam_api::nodes::models::services::{
     StartAuthenticatedServiceRequest, StartAuthenticatorRequest, StartCredentialsService,
-    StartHopServiceRequest, StartOktaIdentityProviderRequest,
+    StartDiscardServiceRequest, StartEchoerServiceRequest, StartFileReceiverServiceRequest,
+    StartHopServiceRequest, StartInfluxDbTokenLeaseManagerRequest,
+    StartOktaIdentityProviderRequest, StartTracerServiceRequest, StartUppercaseServiceRequest,
 };
 use ockam_api::nodes::*;
 use ockam_api::trust_context::TrustContextConfigBuilder;
@@ -34,6 +38,35 @@ pub(crate) fn query_status() -> Request<()> {
     Request::get("/node")
 }
 
+/// Construct a request to list the clock skews measured by a node
+pub(crate) fn list_clock_skews() -> Request<()> {
+    Request::get("/node/clock_skew")
+}
+
+/// Construct a request to get the statistics recorded by a node since a given time
+pub(crate) fn get_node_stats(from: u64) -> Request<models::stats::GetNodeStats> {
+    Request::get("/node/stats").body(models::stats::GetNodeStats::new(Some(from), None))
+}
+
+/// Construct a request to list the identities a node established secure channels with,
+/// seen since a given time
+pub(crate) fn list_peers(seen_since: Option<u64>) -> Request<models::peers::GetPeers> {
+    Request::get("/node/peers").body(models::peers::GetPeers::new(seen_since))
+}
+
+/// Construct a request to get the secure channels a node established with an identity
+pub(crate) fn get_peer(identifier: &Identifier) -> Request<()> {
+    Request::get(format!("/node/peers/{identifier}"))
+}
+
+/// Construct a request to export the diagnostic bundle of a node
+pub(crate) fn get_diagnostics(
+    redact_addresses: bool,
+) -> Request<models::diagnostics::GetDiagnostics> {
+    Request::get("/node/diagnostics")
+        .body(models::diagnostics::GetDiagnostics::new(redact_addresses))
+}
+
 /// Construct a request to query node tcp listeners
 pub(crate) fn list_tcp_listeners() -> Request<()> {
     Request::get("/node/tcp/listener")
@@ -73,6 +106,21 @@ pub(crate) fn list_workers() -> Request<()> {
     Request::get("/node/workers")
 }
 
+/// Construct a request to delete an inlet
+pub(crate) fn delete_inlet(alias: &str) -> Request<()> {
+    Request::delete(format!("/node/inlet/{alias}"))
+}
+
+/// Construct a request returning what would be changed by the deletion of an inlet
+pub(crate) fn dry_run_delete_inlet(alias: &str) -> Request<()> {
+    Request::delete(format!("/node/inlet/{alias}/dry_run"))
+}
+
+/// Construct a request returning what would be stopped by the deletion of a node
+pub(crate) fn dry_run_delete_node() -> Request<()> {
+    Request::delete("/node/dry_run")
+}
+
 pub(crate) fn delete_secure_channel(
     addr: &Address,
 ) -> Request<models::secure_channel::DeleteSecureChannelRequest> {
@@ -98,6 +146,7 @@ pub(crate) fn create_secure_channel_listener(
         authorized_identifiers,
         None,
         identity_name,
+        None,
     );
 
     let mut buf = vec![];
@@ -133,9 +182,82 @@ pub(crate) fn start_hop_service(addr: &str) -> Request<StartHopServiceRequest> {
     Request::post(node_service(DefaultAddress::HOP_SERVICE)).body(payload)
 }
 
+/// Construct a request to start the tracer of a node
+pub(crate) fn start_tracer_service(policy: Option<Expr>) -> Request<StartTracerServiceRequest> {
+    let payload = StartTracerServiceRequest::new(policy);
+    Request::post(node_service(DefaultAddress::TRACER_SERVICE)).body(payload)
+}
+
+/// Construct a request to start an InfluxDB token lease manager
+pub(crate) fn start_influxdb_token_lease_manager(
+    config: InfluxDBTokenLeaseManagerConfig,
+) -> Request<StartInfluxDbTokenLeaseManagerRequest> {
+    let payload = StartInfluxDbTokenLeaseManagerRequest::new(config);
+    Request::post(node_service(DefaultAddress::INFLUXDB_TOKEN_LEASE)).body(payload)
+}
+
+/// Construct a request to start an Echoer Service
+pub(crate) fn start_echoer_service(
+    addr: &str,
+    policy: Option<Expr>,
+) -> Request<StartEchoerServiceRequest> {
+    let mut payload = StartEchoerServiceRequest::new(addr);
+    if let Some(policy) = policy {
+        payload = payload.with_policy(policy);
+    }
+    Request::post(node_service(DefaultAddress::ECHO_SERVICE)).body(payload)
+}
+
+/// Construct a request to start an Uppercase Service
+pub(crate) fn start_uppercase_service(
+    addr: &str,
+    policy: Option<Expr>,
+) -> Request<StartUppercaseServiceRequest> {
+    let mut payload = StartUppercaseServiceRequest::new(addr);
+    if let Some(policy) = policy {
+        payload = payload.with_policy(policy);
+    }
+    Request::post(node_service(DefaultAddress::UPPERCASE_SERVICE)).body(payload)
+}
+
+/// Construct a request to start a Discard Service
+pub(crate) fn start_discard_service(
+    addr: &str,
+    policy: Option<Expr>,
+) -> Request<StartDiscardServiceRequest> {
+    let mut payload = StartDiscardServiceRequest::new(addr);
+    if let Some(policy) = policy {
+        payload = payload.with_policy(policy);
+    }
+    Request::post(node_service(DefaultAddress::DISCARD_SERVICE)).body(payload)
+}
+
+/// Construct a request to start a File Receiver Service
+pub(crate) fn start_file_receiver_service(
+    addr: &str,
+    directory: &str,
+    max_file_size: Option<u64>,
+    policy: Option<Expr>,
+) -> Request<StartFileReceiverServiceRequest> {
+    let mut payload = StartFileReceiverServiceRequest::new(addr, directory);
+    if let Some(max_file_size) = max_file_size {
+        payload = payload.with_max_file_size(max_file_size);
+    }
+    if let Some(policy) = policy {
+        payload = payload.with_policy(policy);
+    }
+    Request::post(node_service(DefaultAddress::FILE_RECEIVER_SERVICE)).body(payload)
+}
+
 /// Construct a request to start an Authenticated Service
-pub(crate) fn start_authenticated_service(addr: &str) -> Request<StartAuthenticatedServiceRequest> {
-    let payload = StartAuthenticatedServiceRequest::new(addr);
+pub(crate) fn start_authenticated_service(
+    addr: &str,
+    policy: Option<Expr>,
+) -> Request<StartAuthenticatedServiceRequest> {
+    let mut payload = StartAuthenticatedServiceRequest::new(addr);
+    if let Some(policy) = policy {
+        payload = payload.with_policy(policy);
+    }
     Request::post(node_service(DefaultAddress::AUTHENTICATED_SERVICE)).body(payload)
 }
 
@@ -4,21 +4,25 @@ use std::{
     str::FromStr,
 };
 
+use colorful::Colorful;
 use miette::Context as _;
 use miette::{miette, IntoDiagnostic};
 use tracing::error;
 
 use ockam::{Address, Context, NodeBuilder};
-use ockam_api::cli_state::{CliState, StateDirTrait, StateItemTrait};
-use ockam_api::config::lookup::{InternetAddress, LookupMeta};
+use ockam_api::cli_state::{CliState, StateDirTrait, StateItemTrait, DELETED_ITEMS_RETENTION};
+use ockam_api::config::lookup::LookupMeta;
+use ockam_api::nodes::models::dry_run::DryRun;
 use ockam_core::DenyAll;
-use ockam_multiaddr::proto::{DnsAddr, Ip4, Ip6, Project, Space, Tcp};
+use ockam_multiaddr::proto::{Project, Space};
 use ockam_multiaddr::{
     proto::{self, Node},
     MultiAddr, Protocol,
 };
+use ockam_node::api::CancellationToken;
 
-use crate::Result;
+use crate::output::Output;
+use crate::{fmt_ok, CommandGlobalOpts, Result};
 
 pub mod api;
 pub mod duration;
@@ -36,6 +40,22 @@ pub async fn stop_node(ctx: Context) {
     }
 }
 
+/// Return a token which is cancelled when the user presses Ctrl+C.
+///
+/// The requests using this token stop waiting for their response and return an error, so
+/// that the command terminates normally and cleans up its resources. The node finishes
+/// processing the requests, and stops waiting at their deadline
+pub fn cancel_on_ctrl_c() -> CancellationToken {
+    let token = CancellationToken::new();
+    let cancelled = token.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            cancelled.cancel();
+        }
+    });
+    token
+}
+
 pub fn local_cmd(res: miette::Result<()>) {
     if let Err(e) = res {
         error!(%e, "Failed to run command");
@@ -223,13 +243,8 @@ pub fn clean_nodes_multiaddr(
                 let alias = p.cast::<Node>().expect("Failed to parse node name");
                 let node_state = cli_state.nodes.get(alias.to_string())?;
                 let node_setup = node_state.config().setup();
-                let addr = &node_setup.api_transport()?.addr;
-                match addr {
-                    InternetAddress::Dns(dns, _) => new_ma.push_back(DnsAddr::new(dns))?,
-                    InternetAddress::V4(v4) => new_ma.push_back(Ip4(*v4.ip()))?,
-                    InternetAddress::V6(v6) => new_ma.push_back(Ip6(*v6.ip()))?,
-                }
-                new_ma.push_back(Tcp(addr.port()))?;
+                let addr = node_setup.api_transport()?.addr.multiaddr()?;
+                new_ma.try_extend(addr.iter())?;
             }
             Project::CODE => {
                 // Parse project name from the MultiAddr.
@@ -248,6 +263,54 @@ pub fn clean_nodes_multiaddr(
     Ok((new_ma, lookup_meta))
 }
 
+/// Restore a deleted item of a state directory or, when no name is given,
+/// list the deleted items which can still be restored
+pub fn restore_deleted<S: StateDirTrait>(
+    opts: &CommandGlobalOpts,
+    state: &S,
+    resource: &str,
+    name: Option<String>,
+) -> miette::Result<()> {
+    match name {
+        Some(name) => {
+            state.restore(&name)?;
+            opts.terminal
+                .stdout()
+                .plain(fmt_ok!("The {resource} named '{name}' has been restored"))
+                .machine(&name)
+                .json(serde_json::json!({ "name": &name }))
+                .write_line()?;
+        }
+        None => {
+            let deleted = state.list_deleted()?;
+            let list = opts.terminal.build_list(
+                &deleted,
+                &format!("Deleted {resource}s"),
+                &format!(
+                    "There are no deleted {resource}s, they are kept for {} days",
+                    DELETED_ITEMS_RETENTION.as_secs() / (24 * 60 * 60)
+                ),
+            )?;
+            opts.terminal
+                .stdout()
+                .plain(list)
+                .json(serde_json::to_string_pretty(&deleted).into_diagnostic()?)
+                .write_line()?;
+        }
+    }
+    Ok(())
+}
+
+/// Print the changes which a request sent as a dry run would have made
+pub fn print_dry_run(opts: &CommandGlobalOpts, dry_run: &DryRun) -> miette::Result<()> {
+    opts.terminal
+        .stdout()
+        .plain(dry_run.output()?)
+        .json(serde_json::to_string_pretty(dry_run).into_diagnostic()?)
+        .write_line()?;
+    Ok(())
+}
+
 pub fn comma_separated<T: AsRef<str>>(data: &[T]) -> String {
     use itertools::Itertools;
 
@@ -4,21 +4,21 @@ use std::str::FromStr;
 use miette::miette;
 
 use ockam::identity::Identifier;
-use ockam_transport_tcp::resolve_peer;
+use ockam_api::address::is_valid_target;
+use ockam_api::nodes::models::portal::OutletRoute;
+use ockam_transport_tcp::{resolve_peer, AllowedNetwork, AllowedTarget, OutletProtocol};
 
 use crate::Result;
 
 /// Helper function for parsing a socket from user input
 /// It is possible to just input a `port`. In that case the address will be assumed to be
-/// 127.0.0.1:<port>
+/// 127.0.0.1:<port>. IPv6 addresses must be bracketed, for example `[::1]:<port>`
 pub(crate) fn socket_addr_parser(input: &str) -> Result<SocketAddr> {
-    let addr: Vec<&str> = input.split(':').collect();
-
-    let address = match addr.len() {
+    let address = match input.parse::<u16>() {
         // Only the port is available
-        1 => format!("127.0.0.1:{}", addr[0]),
+        Ok(port) => format!("127.0.0.1:{port}"),
         // Both the ip and port are available
-        _ => input.to_string(),
+        Err(_) => input.to_string(),
     };
     Ok(resolve_peer(address.to_string())
         .map_err(|e| miette!("cannot parse the address {address} as a socket address: {e}"))?)
@@ -30,11 +30,76 @@ pub(crate) fn identity_identifier_parser(input: &str) -> Result<Identifier> {
     Identifier::from_str(input).map_err(|_| miette!("Invalid identity identifier: {input}").into())
 }
 
+/// Helper fn for parsing a `host:port` target, requested by an inlet to an outlet
+pub(crate) fn outlet_target_parser(input: &str) -> Result<String> {
+    if is_valid_target(input) {
+        Ok(input.to_string())
+    } else {
+        Err(miette!("Invalid target {input}, expected host:port or [ipv6]:port").into())
+    }
+}
+
+/// Helper fn for parsing the route of an outlet: `<attribute>=<value>=<host:port>`.
+/// The value can contain `=`
+pub(crate) fn outlet_route_parser(input: &str) -> Result<OutletRoute> {
+    let invalid = || miette!("Invalid route {input}, expected <attribute>=<value>=<host:port>");
+    let (attribute_value, target) = input.rsplit_once('=').ok_or_else(invalid)?;
+    let (attribute, value) = attribute_value.split_once('=').ok_or_else(invalid)?;
+    if attribute.is_empty() {
+        return Err(invalid().into());
+    }
+    Ok(OutletRoute {
+        attribute: attribute.to_string(),
+        value: value.to_string(),
+        target: outlet_target_parser(target)?,
+    })
+}
+
+/// Helper fn for parsing a target which can be requested from an outlet:
+/// `host:port` or `host:*` to allow any port
+pub(crate) fn allowed_target_parser(input: &str) -> Result<AllowedTarget> {
+    AllowedTarget::from_str(input)
+        .map_err(|_| miette!("Invalid target {input}, expected host:port or host:*").into())
+}
+
+/// Helper fn for parsing a protocol which can be checked by an outlet: `tls` or `http`
+pub(crate) fn outlet_protocol_parser(input: &str) -> Result<OutletProtocol> {
+    OutletProtocol::from_str(input)
+        .map_err(|_| miette!("Invalid protocol {input}, expected tls or http").into())
+}
+
+pub(crate) fn allowed_network_parser(input: &str) -> Result<AllowedNetwork> {
+    AllowedNetwork::from_str(input).map_err(|_| {
+        miette!("Invalid network {input}, expected address/prefix_length or address").into()
+    })
+}
+
+/// Helper fn for parsing a `key=value` pair. The value can contain `=`
+pub(crate) fn key_value_parser(input: &str) -> Result<(String, String)> {
+    let (key, value) = input
+        .split_once('=')
+        .ok_or_else(|| miette!("Invalid value {input}, expected <key>=<value>"))?;
+    if key.is_empty() {
+        return Err(miette!("Invalid value {input}, the key can't be empty").into());
+    }
+    Ok((key.to_string(), value.to_string()))
+}
+
+/// Helper fn for parsing a target which can only be requested by a given identity:
+/// `<identifier>=<host:port>`
+pub(crate) fn identity_target_parser(input: &str) -> Result<(Identifier, AllowedTarget)> {
+    let (identifier, target) = input
+        .split_once('=')
+        .ok_or_else(|| miette!("Invalid value {input}, expected <identifier>=<host:port>"))?;
+    Ok((
+        identity_identifier_parser(identifier)?,
+        allowed_target_parser(target)?,
+    ))
+}
+
 #[cfg(test)]
 mod tests {
-    use std::net::Ipv6Addr;
-
-    use ockam_core::compat::net::{IpAddr, Ipv4Addr, SocketAddr};
+    use ockam_core::compat::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 
     use super::*;
 
@@ -96,5 +161,44 @@ mod tests {
         assert!(socket_addr_parser(invalid_input).is_err());
         let invalid_input = "192,166,0.1:9999";
         assert!(socket_addr_parser(invalid_input).is_err());
+
+        let invalid_input = "::1:9999";
+        assert!(socket_addr_parser(invalid_input).is_err());
+    }
+
+    #[test]
+    fn test_ipv6_unspecified_and_port() {
+        let result = socket_addr_parser("[::]:9999").unwrap();
+        assert_eq!(
+            SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 9999),
+            result
+        );
+    }
+
+    #[test]
+    fn test_outlet_target() {
+        assert!(outlet_target_parser("localhost:5432").is_ok());
+        assert!(outlet_target_parser("10.0.0.7:5432").is_ok());
+        assert!(outlet_target_parser("[::1]:5432").is_ok());
+        assert!(outlet_target_parser("[fd00::7]:5432").is_ok());
+
+        assert!(outlet_target_parser("::1:5432").is_err());
+        assert!(outlet_target_parser("[localhost]:5432").is_err());
+        assert!(outlet_target_parser(":5432").is_err());
+        assert!(outlet_target_parser("[::1]").is_err());
+    }
+
+    #[test]
+    fn test_outlet_route() {
+        let route = outlet_route_parser("tier=gold=fast.internal:5432").unwrap();
+        assert_eq!(route.attribute, "tier");
+        assert_eq!(route.value, "gold");
+        assert_eq!(route.target, "fast.internal:5432");
+        let route = outlet_route_parser("key=a=b=[::1]:5432").unwrap();
+        assert_eq!(route.value, "a=b");
+
+        assert!(outlet_route_parser("tier=gold").is_err());
+        assert!(outlet_route_parser("=gold=fast.internal:5432").is_err());
+        assert!(outlet_route_parser("tier=gold=::1:5432").is_err());
     }
 }
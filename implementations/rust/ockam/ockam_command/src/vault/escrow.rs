@@ -0,0 +1,231 @@
+use std::path::{Path, PathBuf};
+
+use clap::{Args, Subcommand};
+use colorful::Colorful;
+use miette::{miette, IntoDiagnostic};
+
+use ockam::Context;
+use ockam_api::cli_state::traits::StateDirTrait;
+use ockam_api::cli_state::{KeyEscrowConfig, VaultState};
+use ockam_api::resource_list::ResourceList;
+use ockam_vault::{EscrowedSecret, SecretShare, X25519SecretKey, X25519_SECRET_KEY_LENGTH};
+
+use crate::terminal::OckamColor;
+use crate::util::node_rpc;
+use crate::{fmt_ok, CommandGlobalOpts};
+
+/// Escrow the keys of a vault and recover them
+#[derive(Clone, Debug, Args)]
+#[command(arg_required_else_help = true, subcommand_required = true)]
+pub struct EscrowCommand {
+    #[command(subcommand)]
+    subcommand: EscrowSubcommand,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+enum EscrowSubcommand {
+    Set(SetCommand),
+    List(ListCommand),
+    DecryptShare(DecryptShareCommand),
+    Recover(RecoverCommand),
+}
+
+/// Escrow the keys of a vault, including the keys it already contains
+#[derive(Clone, Debug, Args)]
+struct SetCommand {
+    /// Name of the vault, the default vault if not set
+    vault: Option<String>,
+
+    /// Hex-encoded X25519 public key of a holder of a recovery key. Can be repeated
+    #[arg(long = "recovery-key", value_name = "HEX", required = true)]
+    recovery_keys: Vec<String>,
+
+    /// Number of recovery keys needed to recover a key, at least 2.
+    /// A majority of the recovery keys is needed if not set
+    #[arg(long, value_name = "THRESHOLD")]
+    threshold: Option<u8>,
+}
+
+/// List the escrowed keys of a vault. Use `--output json` to export them for the holders
+/// of the recovery keys
+#[derive(Clone, Debug, Args)]
+struct ListCommand {
+    /// Name of the vault, the default vault if not set
+    vault: Option<String>,
+}
+
+/// Decrypt the share of an escrowed key with a recovery key
+#[derive(Clone, Debug, Args)]
+struct DecryptShareCommand {
+    /// Identifier of the escrowed key
+    key_id: String,
+
+    /// Path of the escrowed keys exported with `ockam vault escrow list --output json`
+    #[arg(long, value_name = "PATH")]
+    escrowed_keys: PathBuf,
+
+    /// Path of the file containing the hex-encoded X25519 secret recovery key
+    #[arg(long, value_name = "PATH")]
+    recovery_secret_key_file: PathBuf,
+}
+
+/// Recover an escrowed key into its vault, from the shares decrypted by the holders
+/// of the recovery keys
+#[derive(Clone, Debug, Args)]
+struct RecoverCommand {
+    /// Identifier of the escrowed key
+    key_id: String,
+
+    /// Name of the vault, the default vault if not set
+    #[arg(long, value_name = "VAULT_NAME")]
+    vault: Option<String>,
+
+    /// Share decrypted with `ockam vault escrow decrypt-share`. Can be repeated
+    #[arg(long = "share", value_name = "HEX", required = true)]
+    shares: Vec<String>,
+}
+
+impl EscrowCommand {
+    pub fn run(self, opts: CommandGlobalOpts) {
+        node_rpc(rpc, (opts, self));
+    }
+}
+
+async fn rpc(_ctx: Context, (opts, cmd): (CommandGlobalOpts, EscrowCommand)) -> miette::Result<()> {
+    run_impl(opts, cmd).await
+}
+
+async fn run_impl(opts: CommandGlobalOpts, cmd: EscrowCommand) -> miette::Result<()> {
+    match cmd.subcommand {
+        EscrowSubcommand::Set(c) => {
+            let vault = get_vault(&opts, c.vault.as_deref())?;
+            let key_escrow = KeyEscrowConfig::new(c.recovery_keys, c.threshold)?;
+            let threshold = key_escrow.threshold()?;
+            vault.set_key_escrow(Some(key_escrow))?;
+            // open the vault with its new configuration to escrow the keys it already contains
+            let vault = get_vault(&opts, Some(vault.name()))?;
+            vault.get().await?;
+            opts.terminal
+                .stdout()
+                .plain(fmt_ok!(
+                    "The keys of the vault {} are escrowed, {} recovery keys are needed to recover them",
+                    vault.name().color(OckamColor::PrimaryResource.color()),
+                    threshold
+                ))
+                .write_line()?;
+        }
+        EscrowSubcommand::List(c) => {
+            let vault = get_vault(&opts, c.vault.as_deref())?;
+            let escrowed_keys = vault.escrowed_keys().await?;
+            let plain = escrowed_keys
+                .iter()
+                .map(|escrowed| {
+                    format!(
+                        "{} ({:?}), {} shares needed to recover it",
+                        escrowed.key_id(),
+                        escrowed.attributes().secret_type(),
+                        escrowed.threshold()
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            let json = serde_json::to_string_pretty(&ResourceList::new(escrowed_keys))
+                .into_diagnostic()?;
+            opts.terminal
+                .stdout()
+                .plain(plain)
+                .json(json)
+                .write_line()?;
+        }
+        EscrowSubcommand::DecryptShare(c) => {
+            let escrowed = read_escrowed_key(&c.escrowed_keys, &c.key_id)?;
+            let recovery_key =
+                std::fs::read_to_string(&c.recovery_secret_key_file).into_diagnostic()?;
+            let recovery_key: [u8; X25519_SECRET_KEY_LENGTH] = hex::decode(recovery_key.trim())
+                .ok()
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or_else(|| miette!("Invalid recovery key"))?;
+            let share = escrowed
+                .decrypt_share(&X25519SecretKey::new(recovery_key))
+                .into_diagnostic()?;
+            opts.terminal
+                .stdout()
+                .plain(encode_share(&share))
+                .machine(encode_share(&share))
+                .write_line()?;
+        }
+        EscrowSubcommand::Recover(c) => {
+            let vault = get_vault(&opts, c.vault.as_deref())?;
+            let escrowed = vault
+                .escrowed_keys()
+                .await?
+                .into_iter()
+                .find(|escrowed| escrowed.key_id() == &c.key_id)
+                .ok_or_else(|| miette!("The key {} is not escrowed", c.key_id))?;
+            let shares = c
+                .shares
+                .iter()
+                .map(|share| decode_share(share))
+                .collect::<miette::Result<Vec<_>>>()?;
+            let key_id = vault.recover_key(&escrowed, &shares).await?;
+            opts.terminal
+                .stdout()
+                .plain(fmt_ok!(
+                    "Recovered the key {} into the vault {}",
+                    key_id.color(OckamColor::PrimaryResource.color()),
+                    vault.name().color(OckamColor::PrimaryResource.color())
+                ))
+                .write_line()?;
+        }
+    }
+    Ok(())
+}
+
+fn get_vault(opts: &CommandGlobalOpts, name: Option<&str>) -> miette::Result<VaultState> {
+    Ok(match name {
+        Some(name) => opts.state.vaults.get(name)?,
+        None => opts.state.vaults.default()?,
+    })
+}
+
+/// Read an escrowed key from the escrowed keys exported by `ockam vault escrow list`
+fn read_escrowed_key(path: &Path, key_id: &str) -> miette::Result<EscrowedSecret> {
+    let contents = std::fs::read_to_string(path).into_diagnostic()?;
+    let escrowed_keys: ResourceList<EscrowedSecret> =
+        serde_json::from_str(&contents).into_diagnostic()?;
+    escrowed_keys
+        .into_iter()
+        .find(|escrowed| escrowed.key_id() == key_id)
+        .ok_or_else(|| miette!("The key {key_id} is not in {}", path.display()))
+}
+
+/// A share is exchanged as the hex encoding of its index followed by its value
+fn encode_share(share: &SecretShare) -> String {
+    let mut bytes = vec![share.index()];
+    bytes.extend_from_slice(share.value());
+    hex::encode(bytes)
+}
+
+fn decode_share(share: &str) -> miette::Result<SecretShare> {
+    let bytes = hex::decode(share.trim()).map_err(|_| miette!("Invalid share {share}"))?;
+    match bytes.split_first() {
+        Some((index, value)) if *index != 0 => Ok(SecretShare::new(*index, value.to_vec())),
+        _ => Err(miette!("Invalid share {share}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_share() {
+        let share = SecretShare::new(3, vec![1, 2, 255]);
+        let encoded = encode_share(&share);
+        assert_eq!(encoded, "030102ff");
+        assert_eq!(decode_share(&encoded).unwrap(), share);
+
+        assert!(decode_share("000102").is_err());
+        assert!(decode_share("not hex").is_err());
+    }
+}
@@ -6,6 +6,7 @@ use std::fmt::Write;
 use ockam_api::cli_state::traits::StateDirTrait;
 use ockam_api::cli_state::StateItemTrait;
 use ockam_api::cli_state::VaultConfig;
+use ockam_api::resource_list::ResourceList;
 
 use crate::output::Output;
 use crate::terminal::OckamColor;
@@ -92,7 +93,7 @@ fn run_impl(opts: CommandGlobalOpts) -> miette::Result<()> {
         .terminal
         .build_list(&output, "Vaults", "No vaults found on this system.")?;
 
-    let json = serde_json::to_string_pretty(&output).into_diagnostic()?;
+    let json = ResourceList::new(output).to_json().into_diagnostic()?;
 
     opts.terminal
         .stdout()
@@ -1,14 +1,18 @@
 mod create;
 mod default;
 mod delete;
+mod escrow;
 mod list;
 mod show;
+mod usage;
 
 use crate::vault::create::CreateCommand;
 use crate::vault::default::DefaultCommand;
 use crate::vault::delete::DeleteCommand;
+use crate::vault::escrow::EscrowCommand;
 use crate::vault::list::ListCommand;
 use crate::vault::show::ShowCommand;
+use crate::vault::usage::UsageCommand;
 use crate::{docs, CommandGlobalOpts};
 
 use clap::{Args, Subcommand};
@@ -36,6 +40,8 @@ pub enum VaultSubcommand {
     Delete(DeleteCommand),
     List(ListCommand),
     Default(DefaultCommand),
+    Usage(UsageCommand),
+    Escrow(EscrowCommand),
 }
 
 impl VaultCommand {
@@ -46,6 +52,8 @@ impl VaultCommand {
             VaultSubcommand::List(cmd) => cmd.run(opts),
             VaultSubcommand::Delete(cmd) => cmd.run(opts),
             VaultSubcommand::Default(cmd) => cmd.run(opts),
+            VaultSubcommand::Usage(cmd) => cmd.run(opts),
+            VaultSubcommand::Escrow(cmd) => cmd.run(opts),
         }
     }
 }
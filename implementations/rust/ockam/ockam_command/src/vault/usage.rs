@@ -0,0 +1,102 @@
+use clap::Args;
+use colorful::Colorful;
+use miette::IntoDiagnostic;
+use std::fmt::Write;
+use time::OffsetDateTime;
+
+use ockam::Context;
+use ockam_api::cli_state::traits::StateDirTrait;
+use ockam_api::resource_list::ResourceList;
+
+use crate::output::Output;
+use crate::terminal::OckamColor;
+use crate::util::node_rpc;
+use crate::{docs, CommandGlobalOpts};
+
+const LONG_ABOUT: &str = include_str!("./static/usage/long_about.txt");
+const PREVIEW_TAG: &str = include_str!("../static/preview_tag.txt");
+const AFTER_LONG_HELP: &str = include_str!("./static/usage/after_long_help.txt");
+
+/// Show the usage of the keys of a vault
+#[derive(Clone, Debug, Args)]
+#[command(
+    long_about = docs::about(LONG_ABOUT),
+    before_help = docs::before_help(PREVIEW_TAG),
+    after_long_help = docs::after_help(AFTER_LONG_HELP)
+)]
+pub struct UsageCommand {
+    /// Name of the vault
+    pub name: Option<String>,
+}
+
+impl UsageCommand {
+    pub fn run(self, opts: CommandGlobalOpts) {
+        node_rpc(rpc, (opts, self));
+    }
+}
+
+async fn rpc(_ctx: Context, (opts, cmd): (CommandGlobalOpts, UsageCommand)) -> miette::Result<()> {
+    run_impl(opts, cmd).await
+}
+
+#[derive(serde::Serialize)]
+pub struct KeyUsageOutput {
+    key_id: String,
+    signs: u64,
+    dh_operations: u64,
+    last_used_at: Option<u64>,
+}
+
+impl Output for KeyUsageOutput {
+    fn output(&self) -> crate::error::Result<String> {
+        let mut output = String::new();
+        writeln!(
+            output,
+            "Key {}",
+            self.key_id
+                .to_string()
+                .color(OckamColor::PrimaryResource.color())
+        )?;
+        writeln!(output, "Signatures {}", self.signs)?;
+        writeln!(output, "Key exchanges {}", self.dh_operations)?;
+        let last_used_at = self
+            .last_used_at
+            .and_then(|t| OffsetDateTime::from_unix_timestamp(t as i64).ok())
+            .map_or("never".to_string(), |t| t.to_string());
+        write!(output, "Last used {last_used_at}")?;
+        Ok(output)
+    }
+}
+
+async fn run_impl(opts: CommandGlobalOpts, cmd: UsageCommand) -> miette::Result<()> {
+    let name = cmd
+        .name
+        .unwrap_or(opts.state.vaults.default()?.name().to_string());
+    let state = opts.state.vaults.get(name)?;
+
+    let output = state
+        .key_usage()
+        .await?
+        .into_iter()
+        .map(|(key_id, usage)| KeyUsageOutput {
+            key_id,
+            signs: usage.signs,
+            dh_operations: usage.dh_operations,
+            last_used_at: usage.last_used_at,
+        })
+        .collect::<Vec<KeyUsageOutput>>();
+
+    let plain =
+        opts.terminal
+            .build_list(&output, "Keys", "No key of this vault has been used yet.")?;
+
+    let json = ResourceList::new(output).to_json().into_diagnostic()?;
+
+    opts.terminal
+        .stdout()
+        .plain(plain)
+        .json(json)
+        .write_line()?;
+
+    Ok(())
+}
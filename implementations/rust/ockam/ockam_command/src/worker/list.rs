@@ -1,6 +1,8 @@
+use std::fmt::Write;
+
 use clap::Args;
 use colorful::Colorful;
-use miette::miette;
+use miette::{miette, IntoDiagnostic};
 use tokio::sync::Mutex;
 use tokio::try_join;
 
@@ -78,18 +80,38 @@ async fn run_impl(
         &format!("Workers on {node_name}"),
         &format!("No workers found on {node_name}."),
     )?;
-    opts.terminal.stdout().plain(list).write_line()?;
+    let json = serde_json::to_string_pretty(&workers).into_diagnostic()?;
+    opts.terminal.stdout().plain(list).json(json).write_line()?;
 
     Ok(())
 }
 
 impl Output for WorkerStatus {
     fn output(&self) -> crate::Result<String> {
-        Ok(format!(
+        let mut output = format!(
             "Worker {}",
             self.addr
                 .to_string()
                 .color(OckamColor::PrimaryResource.color())
-        ))
+        );
+        if let Some(message_type) = &self.message_type {
+            write!(output, "\nMessage type {message_type}")?;
+        }
+        if let Some(mailbox_depth) = self.mailbox_depth {
+            write!(output, "\nMailbox depth {mailbox_depth}")?;
+            if let Some(mailbox_capacity) = self.mailbox_capacity {
+                write!(output, " out of {mailbox_capacity}")?;
+            }
+        }
+        if let Some(overflow_policy) = &self.overflow_policy {
+            write!(output, "\nOverflow policy {overflow_policy}")?;
+        }
+        if let Some(overflow_messages) = self.overflow_messages.filter(|o| *o > 0) {
+            write!(output, "\nOverflowed messages {overflow_messages}")?;
+        }
+        if let Some(processed_messages) = self.processed_messages {
+            write!(output, "\nProcessed messages {processed_messages}")?;
+        }
+        Ok(output)
     }
 }
@@ -154,3 +154,21 @@ force_kill_node() {
     fail "Log file should be empty"
   fi
 }
+
+@test "node - only accept management requests from administrators" {
+  run_success "$OCKAM" node create n1 --management-access admin-or-local
+  run_success "$OCKAM" tcp-inlet list --at n1
+
+  # the requests of the command line are not sent over a secure channel
+  run_success "$OCKAM" node create n2 --management-access admin
+  run_failure "$OCKAM" tcp-inlet list --at n2
+}
+
+@test "node - restrict the networks allowed to connect to the node" {
+  run_success "$OCKAM" node create n1 --tcp-listener-allowed-network 10.0.0.0/8
+  # the command line connects from a loopback address, which is always allowed
+  run_success "$OCKAM" node show n1
+  assert_output --partial "/service/api"
+
+  run_failure "$OCKAM" node create n2 --tcp-listener-allowed-network 10.0.0.0/33
+}
@@ -133,6 +133,23 @@ teardown() {
   run_success curl --fail --head --max-time 10 "127.0.0.1:$port"
 }
 
+@test "portals - move a tcp inlet to a new address and move tcp traffic through it" {
+  port="$(random_port)"
+  new_port="$(random_port)"
+  run_success "$OCKAM" node create n1
+  run_success "$OCKAM" node create n2
+
+  run_success "$OCKAM" tcp-outlet create --at /node/n1 --to 127.0.0.1:5000
+  run_success "$OCKAM" tcp-inlet create --at /node/n2 --from "127.0.0.1:$port" --to /node/n1/service/outlet --alias "test-inlet"
+
+  run_success "$OCKAM" tcp-inlet update "test-inlet" --at /node/n2 --from "127.0.0.1:$new_port"
+  run_success "$OCKAM" tcp-inlet show "test-inlet" --at /node/n2 --output json
+  assert_output --partial "\"bind_addr\":\"127.0.0.1:$new_port\""
+
+  run_success curl --fail --head --max-time 10 "127.0.0.1:$new_port"
+  run_failure curl --fail --head --max-time 2 "127.0.0.1:$port"
+}
+
 @test "portals - create an inlet/outlet pair with relay through a relay and move tcp traffic through it" {
   port="$(random_port)"
   run_success "$OCKAM" node create relay
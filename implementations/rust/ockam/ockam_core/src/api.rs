@@ -1,6 +1,7 @@
 #![allow(missing_docs)]
 
 use core::fmt::{self, Display, Formatter};
+use core::time::Duration;
 use hashbrown::HashMap;
 
 use minicbor::data::Type;
@@ -34,6 +35,14 @@ pub struct RequestHeader {
     #[n(3)] method: Option<Method>,
     /// Indicator if a request body is expected after this header.
     #[n(4)] has_body: bool,
+    /// Time, in milliseconds, that the client is willing to wait for the response.
+    ///
+    /// The server can abort the processing of the request once this time has elapsed
+    /// since nobody is waiting for its response anymore.
+    #[n(5)] deadline: Option<u64>,
+    /// Key identifying an operation sent several times, for example when it is replayed
+    /// after a loss of connectivity, so that the server only applies it once.
+    #[n(6)] idempotency_key: Option<String>,
 }
 
 impl RequestHeader {
@@ -43,6 +52,8 @@ impl RequestHeader {
             method: Some(method),
             path: path.into(),
             has_body,
+            deadline: None,
+            idempotency_key: None,
         }
     }
 }
@@ -219,8 +230,10 @@ pub enum Status {
     #[n(404)] NotFound,
     #[n(409)] Conflict,
     #[n(405)] MethodNotAllowed,
+    #[n(408)] RequestTimeout,
     #[n(500)] InternalServerError,
     #[n(501)] NotImplemented,
+    #[n(503)] ServiceUnavailable,
 }
 
 impl Display for Status {
@@ -233,8 +246,10 @@ impl Display for Status {
             Status::NotFound => "404 NotFound",
             Status::Conflict => "409 Conflict",
             Status::MethodNotAllowed => "405 MethodNotAllowed",
+            Status::RequestTimeout => "408 RequestTimeout",
             Status::InternalServerError => "500 InternalServerError",
             Status::NotImplemented => "501 NotImplemented",
+            Status::ServiceUnavailable => "503 ServiceUnavailable",
         })
     }
 }
@@ -278,6 +293,16 @@ impl RequestHeader {
     pub fn has_body(&self) -> bool {
         self.has_body
     }
+
+    /// Return the time the client is willing to wait for the response, if any
+    pub fn deadline(&self) -> Option<Duration> {
+        self.deadline.map(Duration::from_millis)
+    }
+
+    /// Return the key identifying the operation of this request, if any
+    pub fn idempotency_key(&self) -> Option<&str> {
+        self.idempotency_key.as_deref()
+    }
 }
 
 impl ResponseHeader {
@@ -459,6 +484,21 @@ impl<T> Request<T> {
         self
     }
 
+    /// Set the time the client is willing to wait for the response.
+    /// The deadline is sent to the server so that it can stop processing the request
+    /// once the client is not waiting for it anymore
+    pub fn deadline(mut self, deadline: Duration) -> Self {
+        self.header.deadline = Some(deadline.as_millis() as u64);
+        self
+    }
+
+    /// Set a key identifying the operation of this request.
+    /// A server receiving several requests with the same key only applies the first one
+    pub fn idempotency_key<K: Into<String>>(mut self, key: K) -> Self {
+        self.header.idempotency_key = Some(key.into());
+        self
+    }
+
     pub fn header(&self) -> &RequestHeader {
         &self.header
     }
@@ -603,6 +643,17 @@ impl Response {
         Self::error(r, msg, Status::NotFound)
     }
 
+    /// Create an error response when the request could not be processed before its deadline
+    pub fn request_timeout(r: &RequestHeader, msg: &str) -> Response<Error> {
+        Self::error(r, msg, Status::RequestTimeout)
+    }
+
+    /// Create an error response when the service cannot process the request at the moment,
+    /// but another instance of the same service might
+    pub fn service_unavailable(r: &RequestHeader, msg: &str) -> Response<Error> {
+        Self::error(r, msg, Status::ServiceUnavailable)
+    }
+
     pub fn not_implemented(re: Id) -> Response {
         Response::builder(re, Status::NotImplemented)
     }
@@ -843,7 +894,9 @@ mod tests {
         Status::BadRequest,
         Status::NotFound,
         Status::MethodNotAllowed,
+        Status::RequestTimeout,
         Status::InternalServerError,
         Status::NotImplemented,
+        Status::ServiceUnavailable,
     ];
 }
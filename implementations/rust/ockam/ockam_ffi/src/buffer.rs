@@ -0,0 +1,96 @@
+use crate::error::{Error, Result};
+use libc::c_char;
+use std::ffi::{CStr, CString};
+
+/// Bytes allocated by the library. They must be released with `ockam_buffer_free`
+#[repr(C)]
+#[derive(Debug)]
+pub struct OckamBuffer {
+    /// Pointer to the first byte
+    pub data: *mut u8,
+    /// Number of bytes
+    pub len: usize,
+}
+
+impl From<Vec<u8>> for OckamBuffer {
+    fn from(bytes: Vec<u8>) -> Self {
+        let len = bytes.len();
+        let data = Box::into_raw(bytes.into_boxed_slice()) as *mut u8;
+        Self { data, len }
+    }
+}
+
+/// Release a buffer returned by the library
+#[no_mangle]
+pub extern "C" fn ockam_buffer_free(buffer: OckamBuffer) {
+    if !buffer.data.is_null() {
+        unsafe {
+            drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+                buffer.data,
+                buffer.len,
+            )))
+        };
+    }
+}
+
+/// Release a string returned by the library
+///
+/// # Safety
+///
+/// `string` must be NULL or a string returned by the library, which was not released yet
+#[no_mangle]
+pub unsafe extern "C" fn ockam_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+/// Read a NULL-terminated UTF-8 string passed by the caller
+pub(crate) fn read_str<'a>(string: *const c_char, name: &str) -> Result<&'a str> {
+    if string.is_null() {
+        return Err(Error::InvalidArgument(format!("{name} is NULL")));
+    }
+    unsafe { CStr::from_ptr(string) }
+        .to_str()
+        .map_err(|_| Error::InvalidArgument(format!("{name} is not a valid UTF-8 string")))
+}
+
+/// Read bytes passed by the caller
+pub(crate) fn read_bytes<'a>(data: *const u8, len: usize, name: &str) -> Result<&'a [u8]> {
+    if len == 0 {
+        return Ok(&[]);
+    }
+    if data.is_null() {
+        return Err(Error::InvalidArgument(format!("{name} is NULL")));
+    }
+    Ok(unsafe { std::slice::from_raw_parts(data, len) })
+}
+
+/// Write a value to an output parameter passed by the caller
+pub(crate) fn write_out<T>(out: *mut T, value: T, name: &str) -> Result<()> {
+    if out.is_null() {
+        return Err(Error::InvalidArgument(format!("{name} is NULL")));
+    }
+    unsafe { out.write(value) };
+    Ok(())
+}
+
+/// Write a string to an output parameter. It must be released with `ockam_string_free`
+pub(crate) fn write_string(out: *mut *mut c_char, value: String, name: &str) -> Result<()> {
+    let value = CString::new(value)
+        .map_err(|_| Error::Failed("the string contains a NULL character".to_string()))?;
+    let value = value.into_raw();
+    if let Err(e) = write_out(out, value, name) {
+        unsafe { ockam_string_free(value) };
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Write bytes to an output parameter. They must be released with `ockam_buffer_free`
+pub(crate) fn write_buffer(out: *mut OckamBuffer, value: Vec<u8>, name: &str) -> Result<()> {
+    if out.is_null() {
+        return Err(Error::InvalidArgument(format!("{name} is NULL")));
+    }
+    write_out(out, value.into(), name)
+}
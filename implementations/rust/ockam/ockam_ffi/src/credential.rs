@@ -0,0 +1,57 @@
+use crate::buffer::{read_bytes, read_str, write_string};
+use crate::error::{run, Error, OckamStatus};
+use crate::identity::get_identity;
+use libc::c_char;
+use ockam_identity::models::{CredentialAndPurposeKey, Identifier};
+
+/// Verify a credential issued by an authority.
+///
+/// `credential` is the CBOR encoding of the credential and the purpose key attestation
+/// of the authority. `subject` is the expected subject identifier, or NULL to accept any
+/// subject. On success the identifier of the subject is written to `verified_subject`,
+/// unless it is NULL, and must be released with `ockam_string_free`.
+#[no_mangle]
+pub extern "C" fn ockam_credential_verify(
+    authority: u64,
+    credential: *const u8,
+    credential_len: usize,
+    subject: *const c_char,
+    verified_subject: *mut *mut c_char,
+) -> OckamStatus {
+    run(|| {
+        let (authority, node) = get_identity(authority)?;
+        let credential: CredentialAndPurposeKey =
+            minicbor::decode(read_bytes(credential, credential_len, "credential")?)?;
+        let subject = if subject.is_null() {
+            None
+        } else {
+            let subject = read_str(subject, "subject")?;
+            Some(
+                Identifier::try_from(subject)
+                    .map_err(|_| Error::InvalidArgument(format!("invalid identifier {subject}")))?,
+            )
+        };
+
+        let data = node.block_on(
+            node.secure_channels()
+                .identities()
+                .credentials()
+                .credentials_verification()
+                .verify_credential(
+                    subject.as_ref(),
+                    &[authority.identifier().clone()],
+                    &credential,
+                ),
+        )?;
+
+        if !verified_subject.is_null() {
+            let verified = data
+                .credential_data
+                .subject
+                .map(|s| s.to_string())
+                .unwrap_or_default();
+            write_string(verified_subject, verified, "verified_subject")?;
+        }
+        Ok(())
+    })
+}
@@ -0,0 +1,103 @@
+use libc::c_char;
+use std::cell::RefCell;
+use std::ffi::CString;
+use std::fmt::Display;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr::null;
+
+/// Status returned by every function of the C API
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OckamStatus {
+    /// The function succeeded
+    Ok = 0,
+    /// An argument was NULL or malformed
+    InvalidArgument = 1,
+    /// A handle does not refer to a live resource of the expected type
+    InvalidHandle = 2,
+    /// The operation failed
+    Failed = 3,
+}
+
+/// Error raised by the implementation of the C API
+#[derive(Debug)]
+pub(crate) enum Error {
+    InvalidArgument(String),
+    InvalidHandle(u64),
+    Failed(String),
+}
+
+pub(crate) type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    fn status(&self) -> OckamStatus {
+        match self {
+            Error::InvalidArgument(_) => OckamStatus::InvalidArgument,
+            Error::InvalidHandle(_) => OckamStatus::InvalidHandle,
+            Error::Failed(_) => OckamStatus::Failed,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::InvalidArgument(message) => write!(f, "invalid argument: {message}"),
+            Error::InvalidHandle(handle) => write!(f, "invalid handle: {handle}"),
+            Error::Failed(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl From<ockam_core::Error> for Error {
+    fn from(error: ockam_core::Error) -> Self {
+        Error::Failed(error.to_string())
+    }
+}
+
+impl From<minicbor::decode::Error> for Error {
+    fn from(error: minicbor::decode::Error) -> Self {
+        Error::InvalidArgument(error.to_string())
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = RefCell::new(None);
+}
+
+/// Run the implementation of a C function, converting its result into a status.
+/// Panics must not unwind across the FFI boundary, so they are reported as failures
+pub(crate) fn run(f: impl FnOnce() -> Result<()>) -> OckamStatus {
+    let result = match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(result) => result,
+        Err(_) => Err(Error::Failed("unexpected panic".to_string())),
+    };
+    match result {
+        Ok(()) => {
+            set_last_error(None);
+            OckamStatus::Ok
+        }
+        Err(error) => {
+            tracing::debug!(%error, "C API call failed");
+            let status = error.status();
+            set_last_error(Some(error.to_string()));
+            status
+        }
+    }
+}
+
+fn set_last_error(message: Option<String>) {
+    let message = message.map(|m| CString::new(m.replace('\0', "")).unwrap());
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = message);
+}
+
+/// Return a description of the last error which happened on the calling thread, or NULL.
+/// The string is owned by the library: it must not be freed and is only valid until the
+/// next call to the library on the same thread.
+#[no_mangle]
+pub extern "C" fn ockam_last_error() -> *const c_char {
+    LAST_ERROR.with(|last_error| match last_error.borrow().as_ref() {
+        Some(message) => message.as_ptr(),
+        None => null(),
+    })
+}
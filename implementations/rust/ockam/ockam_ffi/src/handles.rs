@@ -0,0 +1,52 @@
+use crate::error::{Error, Result};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Resources of one type exposed to C code. Each resource is referred to by a
+/// handle which stays valid until the resource is removed
+pub(crate) struct Handles<T> {
+    next: AtomicU64,
+    items: Mutex<HashMap<u64, Arc<T>>>,
+}
+
+impl<T> Handles<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            // 0 is reserved to represent an invalid handle
+            next: AtomicU64::new(1),
+            items: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub(crate) fn insert(&self, item: T) -> u64 {
+        let handle = self.next.fetch_add(1, Ordering::Relaxed);
+        self.items.lock().unwrap().insert(handle, Arc::new(item));
+        handle
+    }
+
+    pub(crate) fn get(&self, handle: u64) -> Result<Arc<T>> {
+        self.items
+            .lock()
+            .unwrap()
+            .get(&handle)
+            .cloned()
+            .ok_or(Error::InvalidHandle(handle))
+    }
+
+    pub(crate) fn remove(&self, handle: u64) -> Result<Arc<T>> {
+        self.items
+            .lock()
+            .unwrap()
+            .remove(&handle)
+            .ok_or(Error::InvalidHandle(handle))
+    }
+
+    /// Remove all the resources matching a predicate
+    pub(crate) fn remove_if(&self, predicate: impl Fn(&T) -> bool) {
+        self.items
+            .lock()
+            .unwrap()
+            .retain(|_, item| !predicate(item))
+    }
+}
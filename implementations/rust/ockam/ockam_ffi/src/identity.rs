@@ -0,0 +1,115 @@
+use crate::buffer::{read_bytes, write_buffer, write_out, write_string, OckamBuffer};
+use crate::error::{run, OckamStatus, Result};
+use crate::handles::Handles;
+use crate::node::{Node, NODES};
+use libc::c_char;
+use ockam_identity::models::Identifier;
+use once_cell::sync::Lazy;
+use std::sync::Arc;
+
+/// Identities created or imported on a node
+pub(crate) static IDENTITIES: Lazy<Handles<IdentityResource>> = Lazy::new(Handles::new);
+
+/// Identity known by a node. Only the identities created on that node can
+/// be used to create secure channels
+pub(crate) struct IdentityResource {
+    node: u64,
+    identifier: Identifier,
+}
+
+impl IdentityResource {
+    pub(crate) fn node(&self) -> u64 {
+        self.node
+    }
+
+    pub(crate) fn identifier(&self) -> &Identifier {
+        &self.identifier
+    }
+}
+
+/// Return an identity and the node it belongs to
+pub(crate) fn get_identity(identity: u64) -> Result<(Arc<IdentityResource>, Arc<Node>)> {
+    let identity = IDENTITIES.get(identity)?;
+    let node = NODES.get(identity.node)?;
+    Ok((identity, node))
+}
+
+/// Create a new identity on a node. Its keys are stored in the node vault
+#[no_mangle]
+pub extern "C" fn ockam_identity_create(node: u64, identity: *mut u64) -> OckamStatus {
+    run(|| {
+        let n = NODES.get(node)?;
+        let created = n.block_on(
+            n.secure_channels()
+                .identities()
+                .identities_creation()
+                .create_identity(),
+        )?;
+        let handle = IDENTITIES.insert(IdentityResource {
+            node,
+            identifier: created.identifier().clone(),
+        });
+        write_out(identity, handle, "identity")
+    })
+}
+
+/// Import an identity exported with [`ockam_identity_export`], for example the identity
+/// of an authority. Its change history is verified before being stored on the node
+#[no_mangle]
+pub extern "C" fn ockam_identity_import(
+    node: u64,
+    data: *const u8,
+    data_len: usize,
+    identity: *mut u64,
+) -> OckamStatus {
+    run(|| {
+        let n = NODES.get(node)?;
+        let data = read_bytes(data, data_len, "data")?;
+        let imported = n.block_on(
+            n.secure_channels()
+                .identities()
+                .identities_creation()
+                .import(None, data),
+        )?;
+        let handle = IDENTITIES.insert(IdentityResource {
+            node,
+            identifier: imported.identifier().clone(),
+        });
+        write_out(identity, handle, "identity")
+    })
+}
+
+/// Return the identifier of an identity, as a string to release with `ockam_string_free`
+#[no_mangle]
+pub extern "C" fn ockam_identity_identifier(
+    identity: u64,
+    identifier: *mut *mut c_char,
+) -> OckamStatus {
+    run(|| {
+        let identity = IDENTITIES.get(identity)?;
+        write_string(identifier, identity.identifier.to_string(), "identifier")
+    })
+}
+
+/// Export the change history of an identity, as a buffer to release with `ockam_buffer_free`
+#[no_mangle]
+pub extern "C" fn ockam_identity_export(identity: u64, exported: *mut OckamBuffer) -> OckamStatus {
+    run(|| {
+        let (identity, node) = get_identity(identity)?;
+        let data = node.block_on(
+            node.secure_channels()
+                .identities()
+                .export_identity(&identity.identifier),
+        )?;
+        write_buffer(exported, data, "exported")
+    })
+}
+
+/// Release an identity handle. The identity itself stays known by its node
+#[no_mangle]
+pub extern "C" fn ockam_identity_destroy(identity: u64) -> OckamStatus {
+    run(|| {
+        IDENTITIES.remove(identity)?;
+        Ok(())
+    })
+}
@@ -0,0 +1,26 @@
+//!
+//! This crate exposes Ockam identities and secure channels through a C API, so that
+//! non-Rust applications can embed Ockam's identity layer directly.
+//!
+//! Resources (nodes, identities, secure channels) are created and owned by the library.
+//! They are referred to by opaque `uint64_t` handles which must be released with the
+//! corresponding `*_destroy` function. `0` is never a valid handle.
+//!
+//! Every function returns an `OckamStatus`. When the status is not `OCKAM_STATUS_OK`
+//! a message describing the error can be retrieved with `ockam_last_error`.
+//! Results are returned through output parameters.
+//!
+//! The C header is generated by `cbindgen` in `include/ockam.h` when the crate is built.
+//!
+
+mod buffer;
+mod credential;
+mod error;
+mod handles;
+mod identity;
+mod node;
+mod secure_channel;
+mod transport;
+
+pub use buffer::OckamBuffer;
+pub use error::OckamStatus;
@@ -0,0 +1,85 @@
+use crate::buffer::write_out;
+use crate::error::{run, OckamStatus};
+use crate::handles::Handles;
+use crate::identity::IDENTITIES;
+use crate::secure_channel::SECURE_CHANNELS;
+use ockam_core::flow_control::FlowControlId;
+use ockam_identity::secure_channels::secure_channels;
+use ockam_identity::SecureChannels;
+use ockam_node::{Context, NodeBuilder};
+use ockam_transport_tcp::TcpTransport;
+use once_cell::sync::Lazy;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+/// Nodes created with [`ockam_node_create`]
+pub(crate) static NODES: Lazy<Handles<Node>> = Lazy::new(Handles::new);
+
+/// An Ockam node running on its own runtime. Identities and secure channels
+/// are always attached to a node
+pub(crate) struct Node {
+    context: Context,
+    secure_channels: Arc<SecureChannels>,
+    tcp: TcpTransport,
+    /// Flow controls of the TCP listeners, from which secure channel listeners accept messages
+    tcp_listeners: Mutex<Vec<FlowControlId>>,
+}
+
+impl Node {
+    pub(crate) fn context(&self) -> &Context {
+        &self.context
+    }
+
+    pub(crate) fn secure_channels(&self) -> Arc<SecureChannels> {
+        self.secure_channels.clone()
+    }
+
+    pub(crate) fn tcp(&self) -> &TcpTransport {
+        &self.tcp
+    }
+
+    pub(crate) fn add_tcp_listener(&self, flow_control_id: FlowControlId) {
+        self.tcp_listeners.lock().unwrap().push(flow_control_id)
+    }
+
+    pub(crate) fn tcp_listeners(&self) -> Vec<FlowControlId> {
+        self.tcp_listeners.lock().unwrap().clone()
+    }
+
+    /// Run a future on the node runtime and wait for its result
+    pub(crate) fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.context.runtime().block_on(future)
+    }
+}
+
+/// Create a node with an in-memory vault and identities storage, and a TCP transport.
+/// The node must be released with [`ockam_node_destroy`]
+#[no_mangle]
+pub extern "C" fn ockam_node_create(node: *mut u64) -> OckamStatus {
+    run(|| {
+        let (context, mut executor) = NodeBuilder::new().no_logging().build();
+        let runtime = context.runtime().clone();
+        runtime.spawn(async move { executor.start_router().await });
+        let tcp = runtime.block_on(TcpTransport::create(&context))?;
+
+        let handle = NODES.insert(Node {
+            context,
+            secure_channels: secure_channels(),
+            tcp,
+            tcp_listeners: Mutex::new(vec![]),
+        });
+        write_out(node, handle, "node")
+    })
+}
+
+/// Stop a node. All the identities and secure channels created on this node are released
+#[no_mangle]
+pub extern "C" fn ockam_node_destroy(node: u64) -> OckamStatus {
+    run(|| {
+        let removed = NODES.remove(node)?;
+        SECURE_CHANNELS.remove_if(|channel| channel.node() == node);
+        IDENTITIES.remove_if(|identity| identity.node() == node);
+        removed.block_on(removed.context.stop())?;
+        Ok(())
+    })
+}
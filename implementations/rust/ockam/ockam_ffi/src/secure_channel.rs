@@ -0,0 +1,278 @@
+use crate::buffer::{read_bytes, read_str, write_buffer, write_out, OckamBuffer};
+use crate::error::{run, Error, OckamStatus};
+use crate::handles::Handles;
+use crate::identity::get_identity;
+use crate::node::NODES;
+use libc::c_char;
+use ockam_core::{route, Address, Route};
+use ockam_identity::models::Identifier;
+use ockam_identity::{
+    DecryptionRequest, DecryptionResponse, EncryptionRequest, EncryptionResponse,
+    SecureChannelListenerOptions, SecureChannelOptions, SecureChannelRegistryEntry,
+};
+use once_cell::sync::Lazy;
+
+/// Secure channels created on a node, or accepted by one of its listeners
+pub(crate) static SECURE_CHANNELS: Lazy<Handles<SecureChannelResource>> = Lazy::new(Handles::new);
+
+/// One side of a secure channel. Messages are encrypted and decrypted through the
+/// API addresses of the channel, so that the caller can send them with its own transport
+pub(crate) struct SecureChannelResource {
+    node: u64,
+    encryptor: Address,
+    encryptor_api: Address,
+    decryptor_api: Address,
+}
+
+impl SecureChannelResource {
+    pub(crate) fn node(&self) -> u64 {
+        self.node
+    }
+
+    fn new(node: u64, entry: &SecureChannelRegistryEntry) -> Self {
+        Self {
+            node,
+            encryptor: entry.encryptor_messaging_address().clone(),
+            encryptor_api: entry.encryptor_api_address().clone(),
+            decryptor_api: entry.decryptor_api_address().clone(),
+        }
+    }
+}
+
+/// Accept secure channels for an identity at the given worker address.
+/// The channels are accepted from local workers and from the node TCP listeners
+#[no_mangle]
+pub extern "C" fn ockam_secure_channel_listener_create(
+    identity: u64,
+    address: *const c_char,
+) -> OckamStatus {
+    run(|| {
+        let (identity, node) = get_identity(identity)?;
+        let address = read_str(address, "address")?;
+        let options = node
+            .tcp_listeners()
+            .iter()
+            .fold(SecureChannelListenerOptions::new(), |options, id| {
+                options.as_consumer(id)
+            });
+        node.block_on(node.secure_channels().create_secure_channel_listener(
+            node.context(),
+            identity.identifier(),
+            address,
+            options,
+        ))?;
+        Ok(())
+    })
+}
+
+/// Create a secure channel to a listener. `route` is made of addresses separated by `=>`,
+/// for example "<tcp route> => bob_listener".
+/// The channel must be released with [`ockam_secure_channel_destroy`]
+#[no_mangle]
+pub extern "C" fn ockam_secure_channel_create(
+    identity: u64,
+    route: *const c_char,
+    channel: *mut u64,
+) -> OckamStatus {
+    run(|| {
+        let (identity, node) = get_identity(identity)?;
+        let route = read_str(route, "route")?;
+        let route = Route::parse(route)
+            .ok_or_else(|| Error::InvalidArgument(format!("invalid route {route}")))?;
+        let created = node.block_on(node.secure_channels().create_secure_channel(
+            node.context(),
+            identity.identifier(),
+            route,
+            SecureChannelOptions::new(),
+        ))?;
+        let entry = node
+            .secure_channels()
+            .secure_channel_registry()
+            .get_channel_by_encryptor_address(created.encryptor_address())
+            .ok_or_else(|| Error::Failed("the secure channel is not registered".to_string()))?;
+        let handle = SECURE_CHANNELS.insert(SecureChannelResource::new(identity.node(), &entry));
+        write_out(channel, handle, "channel")
+    })
+}
+
+/// Return a secure channel accepted by a listener of `identity`, and initiated by the
+/// identity with the given identifier
+#[no_mangle]
+pub extern "C" fn ockam_secure_channel_accepted(
+    identity: u64,
+    peer: *const c_char,
+    channel: *mut u64,
+) -> OckamStatus {
+    run(|| {
+        let (identity, node) = get_identity(identity)?;
+        let peer = read_str(peer, "peer")?;
+        let peer = Identifier::try_from(peer)
+            .map_err(|_| Error::InvalidArgument(format!("invalid identifier {peer}")))?;
+        let entry = node
+            .secure_channels()
+            .secure_channel_registry()
+            .get_channel_list()
+            .into_iter()
+            .find(|entry| {
+                !entry.is_initiator()
+                    && entry.my_id() == identity.identifier()
+                    && entry.their_id() == &peer
+            })
+            .ok_or_else(|| Error::Failed(format!("no secure channel accepted from {peer}")))?;
+        let handle = SECURE_CHANNELS.insert(SecureChannelResource::new(identity.node(), &entry));
+        write_out(channel, handle, "channel")
+    })
+}
+
+/// Encrypt a message for the other side of the channel.
+/// The ciphertext is written to `ciphertext` and must be released with `ockam_buffer_free`
+#[no_mangle]
+pub extern "C" fn ockam_secure_channel_encrypt(
+    channel: u64,
+    plaintext: *const u8,
+    plaintext_len: usize,
+    ciphertext: *mut OckamBuffer,
+) -> OckamStatus {
+    run(|| {
+        let channel = SECURE_CHANNELS.get(channel)?;
+        let node = NODES.get(channel.node)?;
+        let plaintext = read_bytes(plaintext, plaintext_len, "plaintext")?;
+        let response: EncryptionResponse = node.block_on(node.context().send_and_receive(
+            route![channel.encryptor_api.clone()],
+            EncryptionRequest(plaintext.to_vec()),
+        ))?;
+        match response {
+            EncryptionResponse::Ok(encrypted) => write_buffer(ciphertext, encrypted, "ciphertext"),
+            EncryptionResponse::Err(e) => Err(e.into()),
+        }
+    })
+}
+
+/// Decrypt a message encrypted by the other side of the channel.
+/// The plaintext is written to `plaintext` and must be released with `ockam_buffer_free`
+#[no_mangle]
+pub extern "C" fn ockam_secure_channel_decrypt(
+    channel: u64,
+    ciphertext: *const u8,
+    ciphertext_len: usize,
+    plaintext: *mut OckamBuffer,
+) -> OckamStatus {
+    run(|| {
+        let channel = SECURE_CHANNELS.get(channel)?;
+        let node = NODES.get(channel.node)?;
+        let ciphertext = read_bytes(ciphertext, ciphertext_len, "ciphertext")?;
+        let response: DecryptionResponse = node.block_on(node.context().send_and_receive(
+            route![channel.decryptor_api.clone()],
+            DecryptionRequest(ciphertext.to_vec()),
+        ))?;
+        match response {
+            DecryptionResponse::Ok(decrypted) => write_buffer(plaintext, decrypted, "plaintext"),
+            DecryptionResponse::Err(e) => Err(e.into()),
+        }
+    })
+}
+
+/// Stop a secure channel and release its handle
+#[no_mangle]
+pub extern "C" fn ockam_secure_channel_destroy(channel: u64) -> OckamStatus {
+    run(|| {
+        let channel = SECURE_CHANNELS.remove(channel)?;
+        let node = NODES.get(channel.node)?;
+        node.block_on(
+            node.secure_channels()
+                .stop_secure_channel(node.context(), &channel.encryptor),
+        )?;
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::ockam_buffer_free;
+    use crate::identity::{ockam_identity_create, ockam_identity_identifier};
+    use crate::node::{ockam_node_create, ockam_node_destroy};
+    use std::ffi::{CStr, CString};
+    use std::ptr::null_mut;
+
+    #[test]
+    fn test_secure_channel_encrypt_decrypt() {
+        let mut node = 0;
+        assert_eq!(ockam_node_create(&mut node), OckamStatus::Ok);
+
+        let (mut alice, mut bob) = (0, 0);
+        assert_eq!(ockam_identity_create(node, &mut alice), OckamStatus::Ok);
+        assert_eq!(ockam_identity_create(node, &mut bob), OckamStatus::Ok);
+
+        let listener = CString::new("bob_listener").unwrap();
+        assert_eq!(
+            ockam_secure_channel_listener_create(bob, listener.as_ptr()),
+            OckamStatus::Ok
+        );
+
+        let mut alice_channel = 0;
+        assert_eq!(
+            ockam_secure_channel_create(alice, listener.as_ptr(), &mut alice_channel),
+            OckamStatus::Ok
+        );
+
+        let mut alice_identifier = null_mut();
+        assert_eq!(
+            ockam_identity_identifier(alice, &mut alice_identifier),
+            OckamStatus::Ok
+        );
+        let mut bob_channel = 0;
+        assert_eq!(
+            ockam_secure_channel_accepted(bob, alice_identifier, &mut bob_channel),
+            OckamStatus::Ok
+        );
+        unsafe { crate::buffer::ockam_string_free(alice_identifier) };
+
+        let message = b"Hello, Bob!";
+        let mut ciphertext = OckamBuffer {
+            data: null_mut(),
+            len: 0,
+        };
+        assert_eq!(
+            ockam_secure_channel_encrypt(
+                alice_channel,
+                message.as_ptr(),
+                message.len(),
+                &mut ciphertext
+            ),
+            OckamStatus::Ok
+        );
+        let mut plaintext = OckamBuffer {
+            data: null_mut(),
+            len: 0,
+        };
+        assert_eq!(
+            ockam_secure_channel_decrypt(
+                bob_channel,
+                ciphertext.data,
+                ciphertext.len,
+                &mut plaintext
+            ),
+            OckamStatus::Ok
+        );
+        let decrypted = unsafe { std::slice::from_raw_parts(plaintext.data, plaintext.len) };
+        assert_eq!(decrypted, message);
+        ockam_buffer_free(ciphertext);
+        ockam_buffer_free(plaintext);
+
+        // an invalid message is rejected
+        assert_eq!(
+            ockam_secure_channel_decrypt(bob_channel, null_mut(), 0, &mut plaintext),
+            OckamStatus::Failed
+        );
+        let error = unsafe { CStr::from_ptr(crate::error::ockam_last_error()) };
+        assert!(!error.to_bytes().is_empty());
+
+        assert_eq!(ockam_secure_channel_destroy(alice_channel), OckamStatus::Ok);
+        assert_eq!(
+            ockam_secure_channel_destroy(alice_channel),
+            OckamStatus::InvalidHandle
+        );
+        assert_eq!(ockam_node_destroy(node), OckamStatus::Ok);
+    }
+}
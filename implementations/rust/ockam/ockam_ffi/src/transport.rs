@@ -0,0 +1,35 @@
+use crate::buffer::{read_str, write_string};
+use crate::error::{run, OckamStatus};
+use crate::node::NODES;
+use libc::c_char;
+use ockam_transport_tcp::{TcpConnectionOptions, TcpListenerOptions};
+
+/// Listen for TCP connections on `address`, for example "127.0.0.1:4000".
+/// Secure channel listeners created afterwards on this node accept channels from these connections
+#[no_mangle]
+pub extern "C" fn ockam_tcp_listen(node: u64, address: *const c_char) -> OckamStatus {
+    run(|| {
+        let n = NODES.get(node)?;
+        let address = read_str(address, "address")?;
+        let listener = n.block_on(n.tcp().listen(address, TcpListenerOptions::new()))?;
+        n.add_tcp_listener(listener.flow_control_id().clone());
+        Ok(())
+    })
+}
+
+/// Connect to a TCP listener at `address`. The route to the remote node is written to `route`
+/// and must be released with `ockam_string_free`. Remote addresses are appended to that route
+/// with `=>`, for example "<route> => bob_listener"
+#[no_mangle]
+pub extern "C" fn ockam_tcp_connect(
+    node: u64,
+    address: *const c_char,
+    route: *mut *mut c_char,
+) -> OckamStatus {
+    run(|| {
+        let n = NODES.get(node)?;
+        let address = read_str(address, "address")?;
+        let connection = n.block_on(n.tcp().connect(address, TcpConnectionOptions::new()))?;
+        write_string(route, connection.sender_address().to_string(), "route")
+    })
+}
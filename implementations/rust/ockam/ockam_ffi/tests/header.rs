@@ -0,0 +1,45 @@
+use std::path::Path;
+
+/// The functions exported by the library, which must all be declared in the C header
+const EXPORTED_FUNCTIONS: &[&str] = &[
+    "ockam_node_create",
+    "ockam_node_destroy",
+    "ockam_tcp_listen",
+    "ockam_tcp_connect",
+    "ockam_identity_create",
+    "ockam_identity_import",
+    "ockam_identity_identifier",
+    "ockam_identity_export",
+    "ockam_identity_destroy",
+    "ockam_credential_verify",
+    "ockam_secure_channel_listener_create",
+    "ockam_secure_channel_create",
+    "ockam_secure_channel_accepted",
+    "ockam_secure_channel_encrypt",
+    "ockam_secure_channel_decrypt",
+    "ockam_secure_channel_destroy",
+    "ockam_last_error",
+    "ockam_buffer_free",
+    "ockam_string_free",
+];
+
+/// The header is generated by the build script, when the crate is built
+#[test]
+fn generated_header_declares_the_exported_functions() {
+    let header = Path::new(env!("CARGO_MANIFEST_DIR")).join("include/ockam.h");
+    let header = std::fs::read_to_string(&header)
+        .unwrap_or_else(|e| panic!("cannot read the header {}: {e}", header.display()));
+
+    for function in EXPORTED_FUNCTIONS {
+        assert!(
+            header.contains(&format!("{function}(")),
+            "the function {function} is not declared in the C header"
+        );
+    }
+    for declaration in ["OckamStatus", "OckamBuffer", "OCKAM_STATUS_OK"] {
+        assert!(
+            header.contains(declaration),
+            "{declaration} is not declared in the C header"
+        );
+    }
+}
@@ -0,0 +1,79 @@
+//! Compare the duration of the lookups of the identities having a given key
+//! in a large Sqlite storage, with and without an index on the keys.
+//!
+//! Run with `cargo bench -p ockam_identity --features sqlite --bench sqlite_storage_keys`
+
+use std::time::{Duration, Instant};
+
+use ockam_core::Result;
+use ockam_identity::storage::{SqliteStorage, Storage};
+use tempfile::NamedTempFile;
+
+const IDENTITIES: usize = 20_000;
+const KEYS_PER_IDENTITY: usize = 5;
+const LOOKUPS: usize = 200;
+
+fn main() -> Result<()> {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+
+    runtime.block_on(async {
+        let temp_path = NamedTempFile::new().unwrap().into_temp_path();
+        let storage = SqliteStorage::new(temp_path.to_path_buf()).await?;
+        populate(&storage);
+
+        report("indexed", bench(&storage).await?);
+
+        storage
+            .conn()
+            .lock()
+            .unwrap()
+            .execute("DROP INDEX idx_identity_key;", [])
+            .unwrap();
+        report("full table scan", bench(&storage).await?);
+        Ok(())
+    })
+}
+
+/// Insert all the rows in a single transaction, the storage inserts them one by one
+fn populate(storage: &SqliteStorage) {
+    let conn = storage.conn();
+    let mut conn = conn.lock().unwrap();
+    let transaction = conn.transaction().unwrap();
+    for identity in 0..IDENTITIES {
+        for key in 0..KEYS_PER_IDENTITY {
+            transaction
+                .execute(
+                    "INSERT INTO identity (identity_id, key, value) VALUES (?1, ?2, ?3)",
+                    (format!("I{identity}"), format!("key{key}"), vec![0u8; 32]),
+                )
+                .unwrap();
+        }
+    }
+    transaction
+        .execute(
+            "INSERT INTO identity (identity_id, key, value) VALUES ('I0', 'rare', x'00')",
+            [],
+        )
+        .unwrap();
+    transaction.commit().unwrap();
+}
+
+/// Look up a key which is only set for a single identity
+async fn bench(storage: &SqliteStorage) -> Result<Duration> {
+    let start = Instant::now();
+    for _ in 0..LOOKUPS {
+        assert_eq!(storage.keys("rare").await?.len(), 1);
+    }
+    Ok(start.elapsed())
+}
+
+fn report(name: &str, elapsed: Duration) {
+    println!(
+        "{name}: {:?} per lookup in {} rows",
+        elapsed / LOOKUPS as u32,
+        IDENTITIES * KEYS_PER_IDENTITY + 1
+    );
+}
@@ -1,8 +1,9 @@
+use crate::credentials::credentials_cache::CredentialsCache;
 use crate::credentials::credentials_retriever::CredentialsRetriever;
 use crate::models::{CredentialAndPurposeKey, Identifier, TimestampInSeconds};
 use crate::utils::{add_seconds, now};
 use crate::{Credentials, IdentityError};
-use tracing::debug;
+use tracing::{debug, warn};
 
 use ockam_core::compat::sync::Arc;
 use ockam_core::compat::sync::RwLock;
@@ -16,6 +17,7 @@ pub struct AuthorityService {
     identifier: Identifier,
     own_credential: Option<Arc<dyn CredentialsRetriever>>,
     inner_cache: Arc<RwLock<Option<CachedCredential>>>,
+    credentials_cache: Option<CredentialsCache>,
 }
 
 #[derive(Clone)]
@@ -36,27 +38,66 @@ impl AuthorityService {
             identifier,
             own_credential,
             inner_cache: Arc::new(RwLock::new(None)),
+            credentials_cache: None,
         }
     }
 
+    /// Keep the retrieved credentials in a persistent cache, so that they can be used
+    /// after a restart, before the authority can be contacted again
+    pub fn with_credentials_cache(mut self, credentials_cache: CredentialsCache) -> Self {
+        self.credentials_cache = Some(credentials_cache);
+        self
+    }
+
     /// Retrieve the credential for an identity within this authority
     pub async fn credential(
         &self,
         ctx: &Context,
         subject: &Identifier,
     ) -> Result<CredentialAndPurposeKey> {
+        // add an extra minute to have a bit of leeway for clock skew
+        let now = add_seconds(&now()?, 60);
         {
             // check if we have a valid cached credential
             let guard = self.inner_cache.read().unwrap();
-            let now = now()?;
             if let Some(cache) = guard.as_ref() {
-                // add an extra minute to have a bit of leeway for clock skew
-                if cache.valid_until > add_seconds(&now, 60) {
+                if cache.valid_until > now {
                     return Ok(cache.credential.clone());
                 }
             }
         }
 
+        // then check if a credential was cached before the node restarted
+        if let Some(credentials_cache) = &self.credentials_cache {
+            match self
+                .cached_credential(credentials_cache, subject, now)
+                .await
+            {
+                Ok(Some(credential)) => return Ok(credential),
+                Ok(None) => (),
+                Err(e) => warn!("the cached credential of {subject} cannot be used: {e}"),
+            }
+        }
+
+        self.retrieve_credential(ctx, subject).await
+    }
+
+    /// Retrieve a new credential for an identity within this authority, even if
+    /// a valid credential is cached
+    pub async fn refresh_credential(
+        &self,
+        ctx: &Context,
+        subject: &Identifier,
+    ) -> Result<CredentialAndPurposeKey> {
+        self.retrieve_credential(ctx, subject).await
+    }
+
+    /// Retrieve a credential from the authority, then cache it
+    async fn retrieve_credential(
+        &self,
+        ctx: &Context,
+        subject: &Identifier,
+    ) -> Result<CredentialAndPurposeKey> {
         // in order to keep the locking schema simple, we allow multiple concurrent retrievals
         let retriever = self
             .own_credential
@@ -65,21 +106,61 @@ impl AuthorityService {
         let credential = retriever.retrieve(ctx, subject).await?;
         debug!("retrieved a credential for subject {}", subject);
 
+        let valid_until = self.verify_credential(subject, &credential).await?;
+        debug!("the retrieved credential is valid");
+
+        if let Some(credentials_cache) = &self.credentials_cache {
+            if let Err(e) = credentials_cache
+                .put(&self.identifier, subject, &credential, valid_until)
+                .await
+            {
+                warn!("the credential of {subject} cannot be cached: {e}");
+            }
+        }
+
+        Ok(credential)
+    }
+
+    /// Return a credential of the persistent cache, if it is still valid,
+    /// and keep it in memory
+    async fn cached_credential(
+        &self,
+        credentials_cache: &CredentialsCache,
+        subject: &Identifier,
+        now: TimestampInSeconds,
+    ) -> Result<Option<CredentialAndPurposeKey>> {
+        let credential = match credentials_cache
+            .get(&self.identifier, subject, now)
+            .await?
+        {
+            Some(credential) => credential,
+            None => return Ok(None),
+        };
+        self.verify_credential(subject, &credential).await?;
+        debug!("using the cached credential of {subject}");
+        Ok(Some(credential))
+    }
+
+    /// Verify a credential issued by this authority, keep it in memory
+    /// and return its expiration date
+    async fn verify_credential(
+        &self,
+        subject: &Identifier,
+        credential: &CredentialAndPurposeKey,
+    ) -> Result<TimestampInSeconds> {
         let credential_data = self
             .credentials
             .credentials_verification()
-            .verify_credential(Some(subject), &[self.identifier.clone()], &credential)
+            .verify_credential(Some(subject), &[self.identifier.clone()], credential)
             .await?;
-
-        debug!("the retrieved credential is valid");
+        let valid_until = credential_data.credential_data.expires_at;
 
         let mut guard = self.inner_cache.write().unwrap();
         *guard = Some(CachedCredential {
             credential: credential.clone(),
-            valid_until: credential_data.credential_data.expires_at,
+            valid_until,
         });
-
-        Ok(credential)
+        Ok(valid_until)
     }
 
     /// Issuer [`Identifier`]
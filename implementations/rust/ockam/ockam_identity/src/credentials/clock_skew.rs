@@ -0,0 +1,127 @@
+use tracing::warn;
+
+use ockam_core::compat::collections::BTreeMap;
+use ockam_core::compat::sync::{Arc, RwLock};
+use ockam_core::compat::vec::Vec;
+
+use crate::models::Identifier;
+use crate::TimestampInSeconds;
+
+/// Default tolerance on the difference between the clock of this machine and the clocks
+/// of the identities issuing credentials
+pub const DEFAULT_CLOCK_SKEW_TOLERANCE: TimestampInSeconds = TimestampInSeconds(5);
+
+/// Clock skew measured with another identity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockSkewMeasurement {
+    /// Difference, in seconds, between the clock of the other identity and the local clock.
+    /// It is positive when the other clock is ahead
+    pub skew: i64,
+    /// Local time of the measurement
+    pub measured_at: TimestampInSeconds,
+}
+
+/// Clock skew with the identities issuing credentials.
+///
+/// The skew is measured by comparing the timestamps of the credentials with the local clock:
+///  - the creation time of a credential which was just issued gives the skew in both directions
+///  - a presented credential created in the future shows that the clock of its issuer is ahead
+///  - a presented credential which already expired shows that the clock of its issuer is behind
+///
+/// A skew larger than the tolerance is reported since it leads to credentials being rejected
+/// as created in the future, or as expired too early
+#[derive(Clone)]
+pub struct ClockSkew {
+    tolerance: TimestampInSeconds,
+    measurements: Arc<RwLock<BTreeMap<Identifier, ClockSkewMeasurement>>>,
+}
+
+impl Default for ClockSkew {
+    fn default() -> Self {
+        Self::new(DEFAULT_CLOCK_SKEW_TOLERANCE)
+    }
+}
+
+impl ClockSkew {
+    /// Create a new clock skew tracker with a given tolerance
+    pub fn new(tolerance: TimestampInSeconds) -> Self {
+        Self {
+            tolerance,
+            measurements: Default::default(),
+        }
+    }
+
+    /// Tolerance applied when checking the validity period of credentials
+    pub fn tolerance(&self) -> TimestampInSeconds {
+        self.tolerance
+    }
+
+    /// Record a timestamp created by `identifier` at the local time `now`
+    pub fn record(
+        &self,
+        identifier: &Identifier,
+        timestamp: TimestampInSeconds,
+        now: TimestampInSeconds,
+    ) -> ClockSkewMeasurement {
+        let measurement = ClockSkewMeasurement {
+            skew: *timestamp as i64 - *now as i64,
+            measured_at: now,
+        };
+        if self.exceeds_tolerance(&measurement) {
+            warn!(
+                %identifier,
+                skew = measurement.skew,
+                tolerance = *self.tolerance,
+                "the clock of this machine is not synchronized with the clock of another identity, credentials might be rejected"
+            );
+        }
+        self.measurements
+            .write()
+            .unwrap()
+            .insert(identifier.clone(), measurement);
+        measurement
+    }
+
+    /// Return true if a skew is larger than the tolerance
+    pub fn exceeds_tolerance(&self, measurement: &ClockSkewMeasurement) -> bool {
+        measurement.skew.unsigned_abs() > *self.tolerance
+    }
+
+    /// Return the last measurement for a given identity
+    pub fn measurement(&self, identifier: &Identifier) -> Option<ClockSkewMeasurement> {
+        self.measurements.read().unwrap().get(identifier).copied()
+    }
+
+    /// Return the last measurement for each identity
+    pub fn measurements(&self) -> Vec<(Identifier, ClockSkewMeasurement)> {
+        self.measurements
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(identifier, measurement)| (identifier.clone(), *measurement))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_skew_measurements() {
+        let clock_skew = ClockSkew::new(TimestampInSeconds(10));
+        let issuer = Identifier::try_from("I0000000000000000000000000000000000000001").unwrap();
+        let now = TimestampInSeconds(1_000);
+
+        let ahead = clock_skew.record(&issuer, TimestampInSeconds(1_004), now);
+        assert_eq!(ahead.skew, 4);
+        assert!(!clock_skew.exceeds_tolerance(&ahead));
+
+        // only the last measurement is kept
+        let behind = clock_skew.record(&issuer, TimestampInSeconds(970), now);
+        assert_eq!(behind.skew, -30);
+        assert!(clock_skew.exceeds_tolerance(&behind));
+        assert_eq!(clock_skew.measurement(&issuer), Some(behind));
+        assert_eq!(clock_skew.measurements(), vec![(issuer, behind)]);
+    }
+}
@@ -1,5 +1,7 @@
 use crate::models::{CredentialData, PurposeKeyAttestationData};
-use crate::{CredentialsCreation, CredentialsVerification, IdentitiesRepository, PurposeKeys};
+use crate::{
+    ClockSkew, CredentialsCreation, CredentialsVerification, IdentitiesRepository, PurposeKeys,
+};
 
 use ockam_core::compat::sync::Arc;
 use ockam_vault::{VaultForSigning, VaultForVerifyingSignatures};
@@ -20,6 +22,7 @@ pub struct Credentials {
     verifying_vault: Arc<dyn VaultForVerifyingSignatures>,
     purpose_keys: Arc<PurposeKeys>,
     identities_repository: Arc<dyn IdentitiesRepository>,
+    clock_skew: ClockSkew,
 }
 
 impl Credentials {
@@ -35,9 +38,16 @@ impl Credentials {
             verifying_vault,
             purpose_keys,
             identities_repository,
+            clock_skew: ClockSkew::default(),
         }
     }
 
+    /// Set the [`ClockSkew`] measured with the issuers of the verified credentials
+    pub fn with_clock_skew(mut self, clock_skew: ClockSkew) -> Self {
+        self.clock_skew = clock_skew;
+        self
+    }
+
     /// [`PurposeKeys`]
     pub fn purpose_keys(&self) -> Arc<PurposeKeys> {
         self.purpose_keys.clone()
@@ -60,11 +70,14 @@ impl Credentials {
 
     /// Return [`CredentialsVerification`]
     pub fn credentials_verification(&self) -> Arc<CredentialsVerification> {
-        Arc::new(CredentialsVerification::new(
-            self.purpose_keys.purpose_keys_verification(),
-            self.verifying_vault.clone(),
-            self.identities_repository.clone(),
-        ))
+        Arc::new(
+            CredentialsVerification::new(
+                self.purpose_keys.purpose_keys_verification(),
+                self.verifying_vault.clone(),
+                self.identities_repository.clone(),
+            )
+            .with_clock_skew(self.clock_skew.clone()),
+        )
     }
 }
 
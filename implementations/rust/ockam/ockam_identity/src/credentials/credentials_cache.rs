@@ -0,0 +1,275 @@
+use minicbor::{Decode, Encode};
+use ockam_core::compat::rand::{thread_rng, RngCore};
+use ockam_core::compat::string::{String, ToString};
+use ockam_core::compat::sync::Arc;
+use ockam_core::compat::vec::Vec;
+use ockam_core::Result;
+use ockam_vault::{
+    AeadSecretKeyHandle, HKDFNumberOfOutputs, SecretBufferHandle, VaultError,
+    VaultForSecureChannels, X25519PublicKey, X25519SecretKeyHandle,
+};
+use tracing::{debug, warn};
+
+use crate::models::{CredentialAndPurposeKey, Identifier, TimestampInSeconds};
+use crate::storage::Storage;
+
+/// Context mixed in the derivation of the keys encrypting the cached credentials
+const CREDENTIALS_CACHE_CONTEXT: &[u8] = b"OCKAM_CREDENTIALS_CACHE";
+
+/// Credentials kept in a persistent storage, so that a node which restarts can present
+/// its credentials before it can contact their authority again.
+///
+/// Each credential is encrypted with an AEAD key derived from a static X25519 key of a vault.
+/// The cache can only be read with access to that vault, and the key is deleted with the
+/// credential. Expired credentials are never returned
+#[derive(Clone)]
+pub struct CredentialsCache {
+    vault: Arc<dyn VaultForSecureChannels>,
+    storage: Arc<dyn Storage>,
+}
+
+/// Credential encrypted in a [`CredentialsCache`]
+#[derive(Debug, Clone, Encode, Decode)]
+#[rustfmt::skip]
+#[cbor(map)]
+struct EncryptedCredential {
+    #[n(1)] wrapping_key: X25519PublicKey,
+    #[b(2)] nonce: Vec<u8>,
+    #[b(3)] cipher_text: Vec<u8>,
+    #[n(4)] expires_at: TimestampInSeconds,
+}
+
+impl CredentialsCache {
+    /// Create a cache encrypting the credentials with the keys of a vault
+    pub fn new(vault: Arc<dyn VaultForSecureChannels>, storage: Arc<dyn Storage>) -> Self {
+        Self { vault, storage }
+    }
+
+    /// Return the credential cached for a subject and an authority, unless it expired
+    /// or can't be decrypted anymore, in which case it is removed from the cache
+    pub async fn get(
+        &self,
+        authority: &Identifier,
+        subject: &Identifier,
+        now: TimestampInSeconds,
+    ) -> Result<Option<CredentialAndPurposeKey>> {
+        let entry = match self.get_entry(authority, subject).await? {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+        if entry.expires_at <= now {
+            debug!("the cached credential of {subject} expired");
+            self.delete(authority, subject).await?;
+            return Ok(None);
+        }
+
+        let decrypted = match self
+            .vault
+            .get_x25519_secret_key_handle(&entry.wrapping_key)
+            .await
+        {
+            Ok(wrapping_key) => {
+                let aead_key = self.aead_key(&wrapping_key, &entry.wrapping_key).await?;
+                let decrypted = self
+                    .vault
+                    .aead_decrypt(
+                        &aead_key,
+                        &entry.cipher_text,
+                        &entry.nonce,
+                        &aad(authority, subject),
+                    )
+                    .await;
+                self.vault.delete_aead_secret_key(aead_key).await?;
+                decrypted
+            }
+            Err(e) => Err(e),
+        };
+
+        match decrypted {
+            Ok(credential) => Ok(Some(minicbor::decode(&credential)?)),
+            Err(e) => {
+                warn!("the cached credential of {subject} cannot be decrypted: {e}");
+                self.delete(authority, subject).await?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Cache a credential until it expires, replacing the previous credential
+    /// cached for the same subject and authority
+    pub async fn put(
+        &self,
+        authority: &Identifier,
+        subject: &Identifier,
+        credential: &CredentialAndPurposeKey,
+        expires_at: TimestampInSeconds,
+    ) -> Result<()> {
+        self.delete(authority, subject).await?;
+
+        let wrapping_key = self.vault.generate_static_x25519_secret_key().await?;
+        let public_key = self.vault.get_x25519_public_key(&wrapping_key).await?;
+        let aead_key = self.aead_key(&wrapping_key, &public_key).await?;
+
+        let mut nonce = [0u8; 12];
+        thread_rng().fill_bytes(&mut nonce);
+        let cipher_text = self
+            .vault
+            .aead_encrypt(
+                &aead_key,
+                &minicbor::to_vec(credential)?,
+                &nonce,
+                &aad(authority, subject),
+            )
+            .await;
+        self.vault.delete_aead_secret_key(aead_key).await?;
+
+        let entry = EncryptedCredential {
+            wrapping_key: public_key,
+            nonce: nonce.to_vec(),
+            cipher_text: cipher_text?,
+            expires_at,
+        };
+        self.storage
+            .set(
+                &subject.to_string(),
+                entry_key(authority),
+                minicbor::to_vec(&entry)?,
+            )
+            .await
+    }
+
+    /// Remove the credential cached for a subject and an authority, with its key
+    pub async fn delete(&self, authority: &Identifier, subject: &Identifier) -> Result<()> {
+        if let Some(entry) = self.get_entry(authority, subject).await? {
+            if let Ok(wrapping_key) = self
+                .vault
+                .get_x25519_secret_key_handle(&entry.wrapping_key)
+                .await
+            {
+                self.vault
+                    .delete_static_x25519_secret_key(wrapping_key)
+                    .await?;
+            }
+            self.storage
+                .del(&subject.to_string(), &entry_key(authority))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn get_entry(
+        &self,
+        authority: &Identifier,
+        subject: &Identifier,
+    ) -> Result<Option<EncryptedCredential>> {
+        match self
+            .storage
+            .get(&subject.to_string(), &entry_key(authority))
+            .await?
+        {
+            Some(entry) => Ok(Some(minicbor::decode(&entry)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Derive the AEAD key of a cached credential from its static X25519 key
+    async fn aead_key(
+        &self,
+        wrapping_key: &X25519SecretKeyHandle,
+        public_key: &X25519PublicKey,
+    ) -> Result<AeadSecretKeyHandle> {
+        let dh = self.vault.x25519_ecdh(wrapping_key, public_key).await?;
+        let salt = self
+            .vault
+            .import_secret_buffer(self.vault.hash(CREDENTIALS_CACHE_CONTEXT).await?.0.to_vec())
+            .await?;
+        let hkdf_output = self
+            .vault
+            .hkdf(&salt, Some(&dh), HKDFNumberOfOutputs::Two)
+            .await;
+        self.vault.delete_secret_buffer(dh).await?;
+        self.vault.delete_secret_buffer(salt).await?;
+
+        let [unused, key]: [SecretBufferHandle; 2] = hkdf_output?
+            .0
+             .0
+            .try_into()
+            .map_err(|_| VaultError::InvalidHkdfOutputType)?;
+        self.vault.delete_secret_buffer(unused).await?;
+        self.vault.convert_secret_buffer_to_aead_key(key).await
+    }
+}
+
+/// Key of the cached credentials issued by an authority
+fn entry_key(authority: &Identifier) -> String {
+    format!("cached_credential:{authority}")
+}
+
+/// Bind an encrypted credential to its subject and authority
+fn aad(authority: &Identifier, subject: &Identifier) -> Vec<u8> {
+    format!("{authority}:{subject}").into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CredentialSchemaIdentifier;
+    use crate::storage::InMemoryStorage;
+    use crate::utils::{add_seconds, now, AttributesBuilder};
+    use crate::{identities, Vault};
+    use core::time::Duration;
+
+    #[tokio::test]
+    async fn test_credentials_cache() -> Result<()> {
+        let identities = identities();
+        let authority = identities.identities_creation().create_identity().await?;
+        let subject = identities.identities_creation().create_identity().await?;
+        let credential = identities
+            .credentials()
+            .credentials_creation()
+            .issue_credential(
+                authority.identifier(),
+                subject.identifier(),
+                AttributesBuilder::with_schema(CredentialSchemaIdentifier(1))
+                    .with_attribute("role", "member")
+                    .build(),
+                Duration::from_secs(60),
+            )
+            .await?;
+
+        let vault = Vault::create_secure_channel_vault();
+        let cache = CredentialsCache::new(vault, InMemoryStorage::create());
+        let now = now()?;
+        cache
+            .put(
+                authority.identifier(),
+                subject.identifier(),
+                &credential,
+                add_seconds(&now, 60),
+            )
+            .await?;
+
+        let cached = cache
+            .get(authority.identifier(), subject.identifier(), now)
+            .await?;
+        assert_eq!(cached, Some(credential));
+
+        // the credential is only cached for its subject and authority
+        assert!(cache
+            .get(subject.identifier(), authority.identifier(), now)
+            .await?
+            .is_none());
+
+        // an expired credential is removed from the cache
+        let later = add_seconds(&now, 120);
+        assert!(cache
+            .get(authority.identifier(), subject.identifier(), later)
+            .await?
+            .is_none());
+        assert!(cache
+            .get(authority.identifier(), subject.identifier(), now)
+            .await?
+            .is_none());
+        Ok(())
+    }
+}
@@ -96,6 +96,7 @@ impl CredentialsCreation {
         let res = CredentialAndPurposeKey {
             credential,
             purpose_key_attestation: issuer_purpose_key.attestation().clone(),
+            issuer_credential: None,
         };
 
         Ok(res)
@@ -1,6 +1,11 @@
-use crate::models::{Attributes, CredentialAndPurposeKey, CredentialSchemaIdentifier, Identifier};
-use crate::utils::AttributesBuilder;
-use crate::{Credentials, IdentitiesRepository, IdentitySecureChannelLocalInfo};
+use crate::models::{
+    Attributes, CredentialAndPurposeKey, CredentialData, CredentialSchemaIdentifier, Identifier,
+};
+use crate::utils::{now, AttributesBuilder};
+use crate::{
+    Credentials, IdentitiesRepository, IdentityError, IdentitySecureChannelLocalInfo, IssuerScope,
+    ATTRIBUTE_VALIDITY_SUFFIX,
+};
 
 use ockam_core::api::{Method, RequestHeader, Response};
 use ockam_core::compat::boxed::Box;
@@ -34,6 +39,7 @@ pub struct CredentialsIssuer {
     credentials: Arc<Credentials>,
     issuer: Identifier,
     subject_attributes: Attributes,
+    issuer_credential: Option<CredentialAndPurposeKey>,
 }
 
 impl CredentialsIssuer {
@@ -53,9 +59,18 @@ impl CredentialsIssuer {
             credentials,
             issuer: issuer.clone(),
             subject_attributes,
+            issuer_credential: None,
         }
     }
 
+    /// Issue credentials as a sub-authority. The issuer credential is attached to each
+    /// issued credential so that it can be verified by the identities trusting the authority
+    /// which delegated its issuing rights
+    pub fn with_issuer_credential(mut self, issuer_credential: CredentialAndPurposeKey) -> Self {
+        self.issuer_credential = Some(issuer_credential);
+        self
+    }
+
     async fn issue_credential(
         &self,
         subject: &Identifier,
@@ -76,17 +91,35 @@ impl CredentialsIssuer {
                 .map
                 .insert(key.clone().into(), value.clone().into());
         }
+        // keep the validity constraints of the member attributes in the credential
+        for (name, validity) in entry.attributes_validity().into_iter().flatten() {
+            subject_attributes.map.insert(
+                format!("{name}{ATTRIBUTE_VALIDITY_SUFFIX}")
+                    .into_bytes()
+                    .into(),
+                validity.to_attribute_value()?.into(),
+            );
+        }
+
+        let mut ttl = MAX_CREDENTIAL_VALIDITY;
+        if let Some(issuer_credential) = &self.issuer_credential {
+            // check the scope now to return a clear error instead of an invalid credential
+            let issuer_data =
+                CredentialData::get_data(&issuer_credential.credential.get_versioned_data()?)?;
+            IssuerScope::from_attributes(&issuer_data.subject_attributes)
+                .ok_or(IdentityError::InvalidIssuerDelegation)?
+                .check(&subject_attributes)?;
+            // an issued credential cannot outlive the issuer credential
+            let remaining = issuer_data.expires_at.saturating_sub(*now()?);
+            ttl = ttl.min(Duration::from_secs(remaining));
+        }
 
-        let credential = self
+        let mut credential = self
             .credentials
             .credentials_creation()
-            .issue_credential(
-                &self.issuer,
-                subject,
-                subject_attributes,
-                MAX_CREDENTIAL_VALIDITY,
-            )
+            .issue_credential(&self.issuer, subject, subject_attributes, ttl)
             .await?;
+        credential.issuer_credential = self.issuer_credential.clone().map(Box::new);
 
         Ok(Some(credential))
     }
@@ -8,7 +8,8 @@ use ockam_core::compat::sync::Arc;
 use ockam_core::{async_trait, Address, Result, Route};
 use ockam_node::{Context, DEFAULT_TIMEOUT};
 
-use crate::models::CredentialAndPurposeKey;
+use crate::models::{CredentialAndPurposeKey, CredentialData};
+use crate::utils::now;
 use crate::{Identifier, SecureChannels, SecureClient};
 
 /// Trait for retrieving a credential for a given identity
@@ -98,10 +99,19 @@ impl CredentialsRetriever for RemoteCredentialsRetriever {
     ) -> Result<CredentialAndPurposeKey> {
         debug!("Getting credential from: {}", &self.issuer.route);
         let client = self.make_secure_client(ctx, for_identity).await?;
-        let credential = client
+        let credential: CredentialAndPurposeKey = client
             .ask(ctx, "credential_issuer", Request::post("/"))
             .await?
             .success()?;
+
+        // the credential was just created by the issuer, so that its creation time
+        // measures the skew between the clock of the issuer and ours
+        let data = CredentialData::get_data(&credential.credential.get_versioned_data()?)?;
+        self.secure_channels.identities().clock_skew().record(
+            &self.issuer.identifier,
+            data.created_at,
+            now()?,
+        );
         Ok(credential)
     }
 }
@@ -1,9 +1,9 @@
-use crate::identities::AttributesEntry;
+use crate::identities::{extract_attributes_validity, AttributesEntry};
 use crate::models::{CredentialAndPurposeKey, CredentialData, Identifier, PurposePublicKey};
 use crate::utils::now;
 use crate::{
-    CredentialAndPurposeKeyData, IdentitiesRepository, IdentityError, PurposeKeyVerification,
-    TimestampInSeconds,
+    ClockSkew, CredentialAndPurposeKeyData, IdentitiesRepository, IdentityError, IssuerScope,
+    PurposeKeyVerification, MAX_DELEGATION_DEPTH,
 };
 
 use ockam_core::compat::collections::BTreeMap;
@@ -12,15 +12,12 @@ use ockam_core::compat::vec::Vec;
 use ockam_core::Result;
 use ockam_vault::VaultForVerifyingSignatures;
 
-/// We allow Credentials to be created in the future related to this machine's time due to
-/// possible time dyssynchronization
-const MAX_ALLOWED_TIME_DRIFT: TimestampInSeconds = TimestampInSeconds(5);
-
 /// Service for managing [`Credential`]s
 pub struct CredentialsVerification {
     purpose_keys_verification: Arc<PurposeKeyVerification>,
     verifying_vault: Arc<dyn VaultForVerifyingSignatures>,
     identities_repository: Arc<dyn IdentitiesRepository>,
+    clock_skew: ClockSkew,
 }
 
 impl CredentialsVerification {
@@ -34,23 +31,89 @@ impl CredentialsVerification {
             purpose_keys_verification,
             verifying_vault,
             identities_repository,
+            clock_skew: ClockSkew::default(),
         }
     }
 
+    /// Set the [`ClockSkew`] measured with the issuers of the verified credentials
+    pub fn with_clock_skew(mut self, clock_skew: ClockSkew) -> Self {
+        self.clock_skew = clock_skew;
+        self
+    }
+
     /// [`IdentitiesRepository`]
     pub fn identities_repository(&self) -> Arc<dyn IdentitiesRepository> {
         self.identities_repository.clone()
     }
+
+    /// [`ClockSkew`]
+    pub fn clock_skew(&self) -> ClockSkew {
+        self.clock_skew.clone()
+    }
 }
 
 impl CredentialsVerification {
     /// Verify a [`Credential`]
-    // TODO: Move to CredentialsVerification
+    ///
+    /// The Credential must be issued by one of the `authorities`, or by a sub-authority whose
+    /// issuer credential allows it to attest all the Credential attributes. In that case the
+    /// chain of issuer credentials is verified up to one of the `authorities`
     pub async fn verify_credential(
         &self,
         expected_subject: Option<&Identifier>,
         authorities: &[Identifier],
         credential_and_purpose_key: &CredentialAndPurposeKey,
+    ) -> Result<CredentialAndPurposeKeyData> {
+        // the credential followed by the credentials of its successive issuers
+        let mut chain = vec![credential_and_purpose_key];
+        let mut current = credential_and_purpose_key;
+        while let Some(issuer_credential) = &current.issuer_credential {
+            if chain.len() > MAX_DELEGATION_DEPTH {
+                return Err(IdentityError::InvalidIssuerDelegation.into());
+            }
+            current = issuer_credential.as_ref();
+            chain.push(current);
+        }
+
+        // start with the credential issued by a trusted authority
+        let mut issuer: Option<(CredentialAndPurposeKeyData, Option<IssuerScope>)> = None;
+        for credential in chain.into_iter().rev() {
+            let data = self.verify_credential_signature(credential).await?;
+            let issuer_identifier = &data.purpose_key_data.subject;
+            match &issuer {
+                None => {
+                    if !authorities.contains(issuer_identifier) {
+                        return Err(IdentityError::UnknownAuthority.into());
+                    }
+                }
+                Some((issuer_data, issuer_scope)) => {
+                    let issuer_scope = issuer_scope
+                        .as_ref()
+                        .ok_or(IdentityError::InvalidIssuerDelegation)?;
+                    if issuer_data.credential_data.subject.as_ref() != Some(issuer_identifier)
+                        || data.credential_data.expires_at > issuer_data.credential_data.expires_at
+                    {
+                        return Err(IdentityError::InvalidIssuerDelegation.into());
+                    }
+                    issuer_scope.check(&data.credential_data.subject_attributes)?;
+                }
+            }
+            let scope = IssuerScope::from_attributes(&data.credential_data.subject_attributes);
+            issuer = Some((data, scope));
+        }
+
+        let (data, _) = issuer.ok_or(IdentityError::CredentialVerificationFailed)?;
+        if expected_subject.is_some() && data.credential_data.subject.as_ref() != expected_subject {
+            // We expected credential that belongs to someone else
+            return Err(IdentityError::CredentialVerificationFailed.into());
+        }
+        Ok(data)
+    }
+
+    /// Verify the signature and validity of a [`Credential`], without checking its issuer
+    async fn verify_credential_signature(
+        &self,
+        credential_and_purpose_key: &CredentialAndPurposeKey,
     ) -> Result<CredentialAndPurposeKeyData> {
         let purpose_key_data = self
             .purpose_keys_verification
@@ -60,10 +123,6 @@ impl CredentialsVerification {
             )
             .await?;
 
-        if !authorities.contains(&purpose_key_data.subject) {
-            return Err(IdentityError::UnknownAuthority.into());
-        }
-
         let public_key = match purpose_key_data.public_key.clone() {
             PurposePublicKey::SecureChannelStatic(_) => {
                 return Err(IdentityError::InvalidKeyType.into())
@@ -111,11 +170,6 @@ impl CredentialsVerification {
             return Err(IdentityError::CredentialVerificationFailed.into());
         }
 
-        if expected_subject.is_some() && credential_data.subject.as_ref() != expected_subject {
-            // We expected credential that belongs to someone else
-            return Err(IdentityError::CredentialVerificationFailed.into());
-        }
-
         if credential_data.created_at < purpose_key_data.created_at {
             // Credential validity time range should be inside the purpose key validity time range
             return Err(IdentityError::CredentialVerificationFailed.into());
@@ -128,16 +182,28 @@ impl CredentialsVerification {
 
         let now = now()?;
 
-        if credential_data.created_at > now
-            && credential_data.created_at - now > MAX_ALLOWED_TIME_DRIFT
-        {
-            // Credential can't be created in the future
-            return Err(IdentityError::CredentialVerificationFailed.into());
+        // We allow Credentials to be created in the future, or to be expired, related to this
+        // machine's time up to a given tolerance due to possible time dyssynchronization
+        let tolerance = self.clock_skew.tolerance();
+        if credential_data.created_at > now {
+            // The clock of the issuer is ahead of ours
+            self.clock_skew
+                .record(&purpose_key_data.subject, credential_data.created_at, now);
+            if credential_data.created_at - now > tolerance {
+                // Credential can't be created in the future
+                return Err(IdentityError::CredentialVerificationFailed.into());
+            }
         }
 
         if credential_data.expires_at < now {
-            // Credential expired
-            return Err(IdentityError::CredentialVerificationFailed.into());
+            // The credential is still presented as valid, so the clock of the issuer
+            // may be behind ours
+            self.clock_skew
+                .record(&purpose_key_data.subject, credential_data.expires_at, now);
+            if credential_data.expires_at + tolerance < now {
+                // Credential expired
+                return Err(IdentityError::CredentialVerificationFailed.into());
+            }
         }
 
         if let Some(_subject_latest_change_hash) = &credential_data.subject_latest_change_hash {
@@ -163,7 +229,8 @@ impl CredentialsVerification {
         })
     }
 
-    /// Receive someone's [`Credential`]: verify and put attributes from it to the storage
+    /// Receive someone's [`Credential`]: verify and merge its attributes with the attributes
+    /// attested by other authorities in the storage
     pub async fn receive_presented_credential(
         &self,
         subject: &Identifier,
@@ -179,20 +246,22 @@ impl CredentialsVerification {
             .await?;
 
         let map = credential_data.credential_data.subject_attributes.map;
-        let map: BTreeMap<_, _> = map
+        let mut map: BTreeMap<_, _> = map
             .into_iter()
             .map(|(k, v)| (Vec::<u8>::from(k), Vec::<u8>::from(v)))
             .collect();
+        let validity = extract_attributes_validity(&mut map)?;
 
         self.identities_repository
-            .put_attributes(
+            .merge_attributes(
                 subject,
                 AttributesEntry::new(
                     map,
                     now()?,
                     Some(credential_data.credential_data.expires_at),
                     Some(credential_data.purpose_key_data.subject),
-                ),
+                )
+                .with_validity(validity),
             )
             .await?;
 
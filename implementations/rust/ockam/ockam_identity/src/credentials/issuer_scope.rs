@@ -0,0 +1,144 @@
+use crate::models::Attributes;
+use crate::{IdentityError, ATTRIBUTE_VALIDITY_SUFFIX, TRUST_CONTEXT_ID};
+
+use minicbor::bytes::ByteSlice;
+use ockam_core::compat::collections::BTreeMap;
+use ockam_core::compat::vec::Vec;
+use ockam_core::Result;
+
+/// Prefix of the attributes delegating the right to attest an attribute.
+///
+/// An authority makes an identity a sub-authority by attesting, for example,
+/// `ockam-issuer:site = factory-7`. Credentials issued by that sub-authority can then
+/// only attest `site = factory-7`
+pub const ISSUER_SCOPE_PREFIX: &str = "ockam-issuer:";
+
+/// Value of a delegated attribute allowing the sub-authority to attest any value
+pub const ANY_ATTRIBUTE_VALUE: &str = "*";
+
+/// Maximum number of sub-authorities between a trusted authority and the subject of a Credential
+pub const MAX_DELEGATION_DEPTH: usize = 3;
+
+/// Attributes that a sub-authority is allowed to attest
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IssuerScope {
+    allowed: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+impl IssuerScope {
+    /// Return the scope delegated by the attributes of a sub-authority credential,
+    /// or None if the credential does not delegate any issuing right.
+    /// The sub-authority can always attest the trust context of its own credential
+    pub fn from_attributes(attributes: &Attributes) -> Option<IssuerScope> {
+        let prefix = ISSUER_SCOPE_PREFIX.as_bytes();
+        let mut allowed: BTreeMap<Vec<u8>, Vec<u8>> = attributes
+            .map
+            .iter()
+            .filter_map(|(key, value)| {
+                key.strip_prefix(prefix)
+                    .map(|name| (name.to_vec(), value.to_vec()))
+            })
+            .collect();
+        if allowed.is_empty() {
+            return None;
+        }
+        if let Some(trust_context_id) = attributes.map.get::<ByteSlice>(TRUST_CONTEXT_ID.into()) {
+            allowed.insert(TRUST_CONTEXT_ID.to_vec(), trust_context_id.to_vec());
+        }
+        Some(IssuerScope { allowed })
+    }
+
+    /// Return true if the sub-authority can attest this attribute value
+    pub fn allows(&self, key: &[u8], value: &[u8]) -> bool {
+        // delegating a right further is allowed as long as it is not broader
+        if let Some(name) = key.strip_prefix(ISSUER_SCOPE_PREFIX.as_bytes()) {
+            if name == TRUST_CONTEXT_ID {
+                return false;
+            }
+            return match self.allowed.get(name) {
+                Some(allowed) if allowed == ANY_ATTRIBUTE_VALUE.as_bytes() => true,
+                Some(allowed) => allowed == value,
+                None => false,
+            };
+        }
+        // restricting the validity of an attribute which can be issued does not broaden the scope
+        if let Some(name) = key.strip_suffix(ATTRIBUTE_VALIDITY_SUFFIX.as_bytes()) {
+            if self.allowed.contains_key(name) {
+                return true;
+            }
+        }
+        match self.allowed.get(key) {
+            Some(allowed) if key == TRUST_CONTEXT_ID => allowed == value,
+            Some(allowed) => allowed == ANY_ATTRIBUTE_VALUE.as_bytes() || allowed == value,
+            None => false,
+        }
+    }
+
+    /// Check that all the attributes of a Credential are in this scope
+    pub fn check(&self, attributes: &Attributes) -> Result<()> {
+        if attributes
+            .map
+            .iter()
+            .all(|(key, value)| self.allows(key, value))
+        {
+            Ok(())
+        } else {
+            Err(IdentityError::InvalidIssuerDelegation.into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CredentialSchemaIdentifier;
+    use crate::utils::AttributesBuilder;
+
+    #[test]
+    fn test_issuer_scope() {
+        let delegation = AttributesBuilder::with_schema(CredentialSchemaIdentifier(1))
+            .with_attribute(TRUST_CONTEXT_ID.to_vec(), b"project".to_vec())
+            .with_attribute("ockam-issuer:site", "factory-7")
+            .with_attribute("ockam-issuer:team", "*")
+            .build();
+        let scope = IssuerScope::from_attributes(&delegation).unwrap();
+
+        let attributes = |attributes: &[(&str, &str)]| {
+            attributes
+                .iter()
+                .fold(
+                    AttributesBuilder::with_schema(CredentialSchemaIdentifier(1)),
+                    |builder, (k, v)| builder.with_attribute(*k, *v),
+                )
+                .build()
+        };
+
+        assert!(scope
+            .check(&attributes(&[
+                ("trust_context_id", "project"),
+                ("site", "factory-7"),
+                ("team", "blue"),
+            ]))
+            .is_ok());
+        assert!(scope.check(&attributes(&[("site", "factory-8")])).is_err());
+        assert!(scope.check(&attributes(&[("role", "admin")])).is_err());
+        assert!(scope
+            .check(&attributes(&[("trust_context_id", "other")]))
+            .is_err());
+
+        // rights can be delegated further, but not broadened
+        assert!(scope
+            .check(&attributes(&[("ockam-issuer:site", "factory-7")]))
+            .is_ok());
+        assert!(scope
+            .check(&attributes(&[("ockam-issuer:site", "*")]))
+            .is_err());
+        assert!(scope
+            .check(&attributes(&[("ockam-issuer:team", "*")]))
+            .is_ok());
+
+        // a credential without delegated attributes does not allow to issue credentials
+        let member = attributes(&[("site", "factory-7")]);
+        assert_eq!(IssuerScope::from_attributes(&member), None);
+    }
+}
@@ -1,21 +1,27 @@
 mod authority_service;
+mod clock_skew;
 #[allow(clippy::module_inception)]
 mod credentials;
+mod credentials_cache;
 mod credentials_creation;
 mod credentials_issuer;
 mod credentials_retriever;
 mod credentials_server;
 mod credentials_server_worker;
 mod credentials_verification;
+mod issuer_scope;
 mod one_time_code;
 mod trust_context;
 
 pub use authority_service::*;
+pub use clock_skew::*;
 pub use credentials::*;
+pub use credentials_cache::*;
 pub use credentials_creation::*;
 pub use credentials_issuer::*;
 pub use credentials_retriever::*;
 pub use credentials_server::*;
 pub use credentials_verification::*;
+pub use issuer_scope::*;
 pub use one_time_code::*;
 pub use trust_context::*;
@@ -50,6 +50,22 @@ pub enum IdentityError {
     InvalidHex,
     /// Secret Key doesn't correspond to the Identity
     WrongSecretKey,
+    /// No cipher suite is supported by both sides of a Secure Channel
+    NoCommonCipherSuite,
+    /// A Credential was issued by a sub-authority which was not allowed to attest its attributes
+    InvalidIssuerDelegation,
+    /// The validity period or the schedule of an attribute is invalid
+    InvalidAttributeValidity,
+    /// A change history has more changes than accepted
+    ChangeHistoryTooLong,
+    /// A change history is larger than accepted
+    ChangeHistoryTooLarge,
+    /// ChildIdentityAttestation Verification Failed
+    ChildIdentityVerificationFailed,
+    /// A child Identity was revoked by its parent
+    ChildIdentityRevoked,
+    /// A compressed Secure Channel message cannot be decompressed
+    InvalidCompressedMessage,
 }
 
 impl ockam_core::compat::error::Error for IdentityError {}
@@ -62,8 +78,13 @@ impl core::fmt::Display for IdentityError {
 impl From<IdentityError> for Error {
     #[track_caller]
     fn from(err: IdentityError) -> Self {
-        let kind = Kind::Unknown; // FIXME: fill these in with more
-                                  // meaningful error kinds
+        let kind = match err {
+            IdentityError::ChangeHistoryTooLong | IdentityError::ChangeHistoryTooLarge => {
+                Kind::ResourceExhausted
+            }
+            // FIXME: fill these in with more meaningful error kinds
+            _ => Kind::Unknown,
+        };
         Error::new(Origin::Identity, kind, err)
     }
 }
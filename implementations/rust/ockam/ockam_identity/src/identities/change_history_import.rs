@@ -0,0 +1,303 @@
+use core::fmt::{Display, Formatter};
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Error, Result};
+use tracing::{info, warn};
+
+use crate::models::{ChangeHash, ChangeHistory, Identifier};
+use crate::{IdentitiesCreation, Identity};
+
+/// Tracing target of the events recorded when an imported change history is stored or rejected
+const AUDIT_TARGET: &str = "ockam_identity::audit";
+
+/// Outcome of the verified import of a change history
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChangeHistoryImport {
+    /// The identity was not known, its change history was stored
+    Created,
+    /// The stored change history was a prefix of the imported one, and was replaced by it
+    Updated {
+        /// Number of changes of the stored change history
+        known_changes: usize,
+        /// Number of changes of the imported change history
+        changes: usize,
+    },
+    /// The imported change history was already stored
+    Unchanged,
+}
+
+/// Conflict between an imported change history and the change history stored for the
+/// same identifier. The stored change history is never overwritten when there is a conflict
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChangeHistoryConflict {
+    /// Both change histories have a different change at the same position: the identity was
+    /// forked, for example by rotating its key twice from the same change
+    Fork {
+        /// Identifier of the identity
+        identifier: Identifier,
+        /// Position of the first change which differs
+        change_index: usize,
+        /// Hash of the stored change at that position
+        known_change: ChangeHash,
+        /// Hash of the imported change at that position
+        imported_change: ChangeHash,
+    },
+    /// The imported change history is a prefix of the stored one: it misses the latest changes
+    Outdated {
+        /// Identifier of the identity
+        identifier: Identifier,
+        /// Number of changes of the stored change history
+        known_changes: usize,
+        /// Number of changes of the imported change history
+        changes: usize,
+    },
+}
+
+impl ChangeHistoryConflict {
+    /// Identifier of the identity whose change histories conflict
+    pub fn identifier(&self) -> &Identifier {
+        match self {
+            ChangeHistoryConflict::Fork { identifier, .. } => identifier,
+            ChangeHistoryConflict::Outdated { identifier, .. } => identifier,
+        }
+    }
+
+    /// Compare an imported identity to the stored version of the same identity
+    fn check(imported: &Identity, known: &Identity) -> Result<(), ChangeHistoryConflict> {
+        for (change_index, (imported_change, known_change)) in
+            imported.changes().iter().zip(known.changes()).enumerate()
+        {
+            if imported_change.change_hash() != known_change.change_hash() {
+                return Err(ChangeHistoryConflict::Fork {
+                    identifier: imported.identifier().clone(),
+                    change_index,
+                    known_change: known_change.change_hash().clone(),
+                    imported_change: imported_change.change_hash().clone(),
+                });
+            }
+        }
+        if imported.changes().len() < known.changes().len() {
+            return Err(ChangeHistoryConflict::Outdated {
+                identifier: imported.identifier().clone(),
+                known_changes: known.changes().len(),
+                changes: imported.changes().len(),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Display for ChangeHistoryConflict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ChangeHistoryConflict::Fork {
+                identifier,
+                change_index,
+                known_change,
+                imported_change,
+            } => write!(
+                f,
+                "the change history of {identifier} was forked at change {change_index}: \
+                 the stored change is {known_change}, the imported change is {imported_change}"
+            ),
+            ChangeHistoryConflict::Outdated {
+                identifier,
+                known_changes,
+                changes,
+            } => write!(
+                f,
+                "the change history of {identifier} has {changes} changes \
+                 but {known_changes} changes are already stored"
+            ),
+        }
+    }
+}
+
+impl ockam_core::compat::error::Error for ChangeHistoryConflict {}
+
+impl From<ChangeHistoryConflict> for Error {
+    #[track_caller]
+    fn from(conflict: ChangeHistoryConflict) -> Self {
+        let identifier = conflict.identifier().clone();
+        let error = match &conflict {
+            ChangeHistoryConflict::Fork { change_index, .. } => {
+                Error::new(Origin::Identity, Kind::Conflict, conflict.clone())
+                    .context("conflict", "fork")
+                    .context("change_index", change_index)
+            }
+            ChangeHistoryConflict::Outdated {
+                known_changes,
+                changes,
+                ..
+            } => Error::new(Origin::Identity, Kind::Conflict, conflict.clone())
+                .context("conflict", "outdated")
+                .context("known_changes", known_changes)
+                .context("changes", changes),
+        };
+        error.context("identifier", identifier)
+    }
+}
+
+impl IdentitiesCreation {
+    /// Verify a change history and store it, unless it conflicts with the change history
+    /// already stored for the same identifier.
+    ///
+    /// The stored change history is only replaced when it is a prefix of the imported one.
+    /// A fork, or an imported change history missing some of the stored changes, is rejected
+    /// with a `Kind::Conflict` error built from a [`ChangeHistoryConflict`].
+    /// Each outcome is recorded as an event with the `ockam_identity::audit` target
+    pub async fn import_verified(
+        &self,
+        expected_identifier: Option<&Identifier>,
+        change_history: ChangeHistory,
+    ) -> Result<ChangeHistoryImport> {
+        let identity = Identity::import_from_change_history_with_limits(
+            expected_identifier,
+            change_history,
+            self.verifying_vault.clone(),
+            &self.change_history_limits,
+        )
+        .await?;
+        self.store_verified(&identity).await
+    }
+
+    /// Store a verified identity unless it conflicts with the stored version of the same identity
+    pub(super) async fn store_verified(&self, identity: &Identity) -> Result<ChangeHistoryImport> {
+        let identifier = identity.identifier();
+        let outcome = match self.repository.retrieve_identity(identifier).await? {
+            Some(known) => {
+                let known = Identity::import_from_change_history(
+                    Some(identifier),
+                    known,
+                    self.verifying_vault.clone(),
+                )
+                .await?;
+                if let Err(conflict) = ChangeHistoryConflict::check(identity, &known) {
+                    warn!(
+                        target: AUDIT_TARGET,
+                        identifier = %identifier,
+                        conflict = %conflict,
+                        "rejected a conflicting change history"
+                    );
+                    return Err(conflict.into());
+                }
+                if identity.changes().len() == known.changes().len() {
+                    return Ok(ChangeHistoryImport::Unchanged);
+                }
+                ChangeHistoryImport::Updated {
+                    known_changes: known.changes().len(),
+                    changes: identity.changes().len(),
+                }
+            }
+            None => ChangeHistoryImport::Created,
+        };
+
+        self.repository
+            .update_identity(identifier, identity.change_history())
+            .await?;
+        info!(
+            target: AUDIT_TARGET,
+            identifier = %identifier,
+            outcome = ?outcome,
+            "stored a change history"
+        );
+        Ok(outcome)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_import_verified() -> Result<()> {
+        let identities = crate::identities();
+        let creation = identities.identities_creation();
+
+        // an identity managed by another node, which rotates its key twice from the same change
+        let other = crate::identities();
+        let other_creation = other.identities_creation();
+        let identity = other_creation.create_identity().await?;
+        let identifier = identity.identifier().clone();
+        let rotated = other
+            .identities_keys()
+            .rotate_key_with_options(
+                identity.clone(),
+                other_creation.identity_builder().build_options().await?,
+            )
+            .await?;
+        let forked = other
+            .identities_keys()
+            .rotate_key_with_options(
+                identity.clone(),
+                other_creation.identity_builder().build_options().await?,
+            )
+            .await?;
+
+        assert_eq!(
+            creation
+                .import_verified(Some(&identifier), identity.change_history().clone())
+                .await?,
+            ChangeHistoryImport::Created
+        );
+        assert_eq!(
+            creation
+                .import_verified(Some(&identifier), identity.change_history().clone())
+                .await?,
+            ChangeHistoryImport::Unchanged
+        );
+        assert_eq!(
+            creation
+                .import_verified(Some(&identifier), rotated.change_history().clone())
+                .await?,
+            ChangeHistoryImport::Updated {
+                known_changes: 1,
+                changes: 2
+            }
+        );
+
+        // a fork is rejected and the stored change history is kept
+        let error = creation
+            .import_verified(Some(&identifier), forked.change_history().clone())
+            .await
+            .unwrap_err();
+        assert_eq!(error.code().kind, Kind::Conflict);
+        assert_eq!(
+            identities.repository().get_identity(&identifier).await?,
+            rotated.change_history().clone()
+        );
+
+        // so is an outdated change history
+        let error = creation
+            .import_verified(Some(&identifier), identity.change_history().clone())
+            .await
+            .unwrap_err();
+        assert_eq!(error.code().kind, Kind::Conflict);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_conflict_check() -> Result<()> {
+        let identities = crate::identities();
+        let identity = identities.identities_creation().create_identity().await?;
+        let options = identities
+            .identities_creation()
+            .identity_builder()
+            .build_options()
+            .await?;
+        let rotated = identities
+            .identities_keys()
+            .rotate_key_with_options(identity.clone(), options)
+            .await?;
+
+        assert_eq!(ChangeHistoryConflict::check(&rotated, &identity), Ok(()));
+        assert_eq!(
+            ChangeHistoryConflict::check(&identity, &rotated),
+            Err(ChangeHistoryConflict::Outdated {
+                identifier: identity.identifier().clone(),
+                known_changes: 2,
+                changes: 1,
+            })
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,309 @@
+use ockam_core::compat::sync::Arc;
+use ockam_core::compat::vec::Vec;
+use ockam_core::Result;
+use ockam_vault::VerifyingPublicKey;
+
+use crate::models::{
+    ChildIdentityAttestation, ChildIdentityAttestationData, Identifier, VersionedData,
+};
+use crate::utils::now;
+use crate::{
+    ChildIdentitiesRepository, IdentitiesCreation, IdentitiesReader, Identity, IdentityError,
+    IdentityOptions, TimestampInSeconds,
+};
+
+/// We allow child identities to be attested in the future related to this machine's time due to
+/// possible time dyssynchronization
+const MAX_ALLOWED_TIME_DRIFT: TimestampInSeconds = TimestampInSeconds(5);
+
+/// This struct supports the creation, verification and revocation of child identities.
+///
+/// A child identity is an identity whose first change records an attestation signed by
+/// a parent identity, so that a root identity can have one identity per device or application,
+/// each of which can be revoked individually
+pub struct ChildIdentities {
+    identities_creation: Arc<IdentitiesCreation>,
+    identities_reader: Arc<dyn IdentitiesReader>,
+    repository: Arc<dyn ChildIdentitiesRepository>,
+}
+
+impl ChildIdentities {
+    /// Create a new child identities module
+    pub fn new(
+        identities_creation: Arc<IdentitiesCreation>,
+        identities_reader: Arc<dyn IdentitiesReader>,
+        repository: Arc<dyn ChildIdentitiesRepository>,
+    ) -> Self {
+        Self {
+            identities_creation,
+            identities_reader,
+            repository,
+        }
+    }
+
+    /// Create a child identity of `parent` with a new key and store it.
+    /// The secret key of `parent` is expected to exist in the Vault
+    pub async fn create_child_identity(&self, parent: &Identifier) -> Result<Identity> {
+        let options = self
+            .identities_creation
+            .identity_builder()
+            .build_options()
+            .await?;
+        self.create_child_identity_with_options(parent, options)
+            .await
+    }
+
+    /// Create a child identity of `parent` with the given options and store it.
+    /// The attestation of the parent is valid for the same period as the first key of the child
+    pub async fn create_child_identity_with_options(
+        &self,
+        parent: &Identifier,
+        options: IdentityOptions,
+    ) -> Result<Identity> {
+        let parent_identity = self.get_identity(parent).await?;
+        let parent_secret_key = self
+            .identities_creation
+            .identities_keys()
+            .get_secret_key(&parent_identity)
+            .await?;
+
+        let identity_vault = self.identities_creation.identity_vault();
+        let child_public_key = identity_vault
+            .get_verifying_public_key(options.signing_secret_key_handle())
+            .await?;
+
+        let attestation_data = ChildIdentityAttestationData {
+            parent: parent.clone(),
+            parent_change_hash: parent_identity.latest_change_hash()?.clone(),
+            child_primary_public_key: child_public_key.into(),
+            created_at: options.created_at(),
+            expires_at: options.expires_at(),
+        };
+
+        let versioned_data = VersionedData {
+            version: 1,
+            data: minicbor::to_vec(&attestation_data)?,
+        };
+        let versioned_data = minicbor::to_vec(&versioned_data)?;
+
+        let hash = self
+            .identities_creation
+            .verifying_vault()
+            .sha256(&versioned_data)
+            .await?;
+        let signature = identity_vault.sign(&parent_secret_key, &hash.0).await?;
+
+        let attestation = ChildIdentityAttestation {
+            data: versioned_data,
+            signature: signature.into(),
+        };
+
+        self.identities_creation
+            .create_identity_with_options(options.with_parent(attestation))
+            .await
+    }
+
+    /// Verify that `child` was attested by its parent, which must be `expected_parent` if set,
+    /// and that it was not revoked. The parent identity must be known to the repository
+    pub async fn verify_child_identity(
+        &self,
+        expected_parent: Option<&Identifier>,
+        child: &Identity,
+    ) -> Result<ChildIdentityAttestationData> {
+        let attestation_data = self.verify_attestation(child).await?;
+
+        if let Some(expected_parent) = expected_parent {
+            if expected_parent != &attestation_data.parent {
+                return Err(IdentityError::ChildIdentityVerificationFailed.into());
+            }
+        }
+
+        if self
+            .repository
+            .get_revocation(child.identifier())
+            .await?
+            .is_some()
+        {
+            return Err(IdentityError::ChildIdentityRevoked.into());
+        }
+
+        Ok(attestation_data)
+    }
+
+    /// Revoke the child identity `child` of `parent`. The other children of `parent` stay valid
+    pub async fn revoke_child_identity(
+        &self,
+        parent: &Identifier,
+        child: &Identifier,
+    ) -> Result<()> {
+        let child = self.get_identity(child).await?;
+        let attestation_data = self.verify_attestation(&child).await?;
+        if parent != &attestation_data.parent {
+            return Err(IdentityError::ChildIdentityVerificationFailed.into());
+        }
+
+        self.repository
+            .revoke_child_identity(parent, child.identifier())
+            .await
+    }
+
+    /// Return true if the child identity `child` was revoked by its parent
+    pub async fn is_revoked(&self, child: &Identifier) -> Result<bool> {
+        Ok(self.repository.get_revocation(child).await?.is_some())
+    }
+
+    /// List the child identities revoked by `parent`
+    pub async fn list_revoked_child_identities(
+        &self,
+        parent: &Identifier,
+    ) -> Result<Vec<Identifier>> {
+        self.repository.list_revoked_child_identities(parent).await
+    }
+}
+
+impl ChildIdentities {
+    async fn get_identity(&self, identifier: &Identifier) -> Result<Identity> {
+        let change_history = self.identities_reader.get_identity(identifier).await?;
+        Identity::import_from_change_history(
+            Some(identifier),
+            change_history,
+            self.identities_creation.verifying_vault(),
+        )
+        .await
+    }
+
+    /// Verify the attestation recorded in the first change of `child`
+    async fn verify_attestation(&self, child: &Identity) -> Result<ChildIdentityAttestationData> {
+        let attestation = match child.parent_attestation() {
+            Some(attestation) => attestation,
+            None => return Err(IdentityError::ChildIdentityVerificationFailed.into()),
+        };
+
+        let versioned_data = attestation.get_versioned_data()?;
+        if versioned_data.version != 1 {
+            return Err(IdentityError::ChildIdentityVerificationFailed.into());
+        }
+
+        let attestation_data = ChildIdentityAttestationData::get_data(&versioned_data)?;
+
+        let first_change = match child.changes().first() {
+            Some(first_change) => first_change,
+            None => return Err(IdentityError::EmptyIdentity.into()),
+        };
+        if &VerifyingPublicKey::from(attestation_data.child_primary_public_key.clone())
+            != first_change.primary_public_key()
+        {
+            // The attestation was issued for another identity
+            return Err(IdentityError::ChildIdentityVerificationFailed.into());
+        }
+
+        let parent = self.get_identity(&attestation_data.parent).await?;
+
+        // The parent key might have been rotated since the attestation was issued
+        let parent_change = match parent
+            .changes()
+            .iter()
+            .find(|change| change.change_hash() == &attestation_data.parent_change_hash)
+        {
+            Some(parent_change) => parent_change,
+            None => return Err(IdentityError::ChildIdentityVerificationFailed.into()),
+        };
+
+        let verifying_vault = self.identities_creation.verifying_vault();
+        let hash = verifying_vault.sha256(&attestation.data).await?;
+        if !verifying_vault
+            .verify_signature(
+                parent_change.primary_public_key(),
+                &hash.0,
+                &attestation.signature.clone().into(),
+            )
+            .await?
+        {
+            return Err(IdentityError::ChildIdentityVerificationFailed.into());
+        }
+
+        let now = now()?;
+        if attestation_data.created_at > now + MAX_ALLOWED_TIME_DRIFT
+            || attestation_data.expires_at < now
+        {
+            return Err(IdentityError::ChildIdentityVerificationFailed.into());
+        }
+
+        Ok(attestation_data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::identities;
+
+    #[tokio::test]
+    async fn test_child_identities() -> Result<()> {
+        let identities = identities();
+        let identities_creation = identities.identities_creation();
+        let child_identities = identities.child_identities();
+
+        let parent = identities_creation.create_identity().await?;
+        let other = identities_creation.create_identity().await?;
+        let device = child_identities
+            .create_child_identity(parent.identifier())
+            .await?;
+        let application = child_identities
+            .create_child_identity(parent.identifier())
+            .await?;
+
+        let attestation_data = child_identities
+            .verify_child_identity(Some(parent.identifier()), &device)
+            .await?;
+        assert_eq!(&attestation_data.parent, parent.identifier());
+
+        // the link to the parent survives an export and an import
+        let exported = device.export()?;
+        let imported =
+            Identity::import(None, &exported, identities.vault().verifying_vault).await?;
+        assert!(imported.parent_attestation().is_some());
+
+        // an identity created without a parent is not a child identity
+        assert!(child_identities
+            .verify_child_identity(None, &parent)
+            .await
+            .is_err());
+        assert!(child_identities
+            .verify_child_identity(Some(other.identifier()), &device)
+            .await
+            .is_err());
+
+        // only the parent can revoke a child, and only that child is revoked
+        assert!(child_identities
+            .revoke_child_identity(other.identifier(), device.identifier())
+            .await
+            .is_err());
+        child_identities
+            .revoke_child_identity(parent.identifier(), device.identifier())
+            .await?;
+        assert!(child_identities.is_revoked(device.identifier()).await?);
+        assert!(child_identities
+            .verify_child_identity(None, &device)
+            .await
+            .is_err());
+        child_identities
+            .verify_child_identity(None, &application)
+            .await?;
+        assert_eq!(
+            child_identities
+                .list_revoked_child_identities(parent.identifier())
+                .await?,
+            vec![device.identifier().clone()]
+        );
+
+        // a rotation of the parent key keeps its children valid
+        identities_creation
+            .rotate_identity(parent.identifier())
+            .await?;
+        child_identities
+            .verify_child_identity(Some(parent.identifier()), &application)
+            .await?;
+        Ok(())
+    }
+}
@@ -1,8 +1,10 @@
 use crate::identities::{IdentitiesKeys, IdentitiesRepository};
 use crate::purpose_keys::storage::{PurposeKeysRepository, PurposeKeysStorage};
 use crate::{
-    Credentials, CredentialsServer, CredentialsServerModule, Identifier, IdentitiesBuilder,
-    IdentitiesCreation, IdentitiesReader, IdentitiesStorage, Identity, PurposeKeys, Vault,
+    ChangeHistoryLimits, ChildIdentities, ChildIdentitiesRepository, ChildIdentitiesStorage,
+    ClockSkew, Credentials, CredentialsServer, CredentialsServerModule, GroupsRepository,
+    GroupsStorage, Identifier, IdentitiesBuilder, IdentitiesCreation, IdentitiesReader,
+    IdentitiesStorage, Identity, PurposeKeys, Vault,
 };
 
 use ockam_core::compat::sync::Arc;
@@ -15,6 +17,10 @@ pub struct Identities {
     vault: Vault,
     identities_repository: Arc<dyn IdentitiesRepository>,
     purpose_keys_repository: Arc<dyn PurposeKeysRepository>,
+    child_identities_repository: Arc<dyn ChildIdentitiesRepository>,
+    groups_repository: Arc<dyn GroupsRepository>,
+    clock_skew: ClockSkew,
+    change_history_limits: ChangeHistoryLimits,
 }
 
 impl Identities {
@@ -33,6 +39,26 @@ impl Identities {
         self.purpose_keys_repository.clone()
     }
 
+    /// Return the repository for the revocations of child identities
+    pub fn child_identities_repository(&self) -> Arc<dyn ChildIdentitiesRepository> {
+        self.child_identities_repository.clone()
+    }
+
+    /// Return the repository for the groups of identities
+    pub fn groups_repository(&self) -> Arc<dyn GroupsRepository> {
+        self.groups_repository.clone()
+    }
+
+    /// Return the clock skew measured with the issuers of credentials
+    pub fn clock_skew(&self) -> ClockSkew {
+        self.clock_skew.clone()
+    }
+
+    /// Return the limits on the change histories of the identities received from other nodes
+    pub fn change_history_limits(&self) -> ChangeHistoryLimits {
+        self.change_history_limits.clone()
+    }
+
     /// Get an [`Identity`] from the repository
     pub async fn get_identity(&self, identifier: &Identifier) -> Result<Identity> {
         let change_history = self.identities_repository.get_identity(identifier).await?;
@@ -69,10 +95,22 @@ impl Identities {
 
     /// Return the identities creation service
     pub fn identities_creation(&self) -> Arc<IdentitiesCreation> {
-        Arc::new(IdentitiesCreation::new(
-            self.repository(),
-            self.vault.identity_vault.clone(),
-            self.vault.verifying_vault.clone(),
+        Arc::new(
+            IdentitiesCreation::new(
+                self.repository(),
+                self.vault.identity_vault.clone(),
+                self.vault.verifying_vault.clone(),
+            )
+            .with_change_history_limits(self.change_history_limits.clone()),
+        )
+    }
+
+    /// Return the child identities service
+    pub fn child_identities(&self) -> Arc<ChildIdentities> {
+        Arc::new(ChildIdentities::new(
+            self.identities_creation(),
+            self.identities_reader(),
+            self.child_identities_repository.clone(),
         ))
     }
 
@@ -83,12 +121,15 @@ impl Identities {
 
     /// Return the identities credentials service
     pub fn credentials(&self) -> Arc<Credentials> {
-        Arc::new(Credentials::new(
-            self.vault.credential_vault.clone(),
-            self.vault.verifying_vault.clone(),
-            self.purpose_keys(),
-            self.identities_repository.clone(),
-        ))
+        Arc::new(
+            Credentials::new(
+                self.vault.credential_vault.clone(),
+                self.vault.verifying_vault.clone(),
+                self.purpose_keys(),
+                self.identities_repository.clone(),
+            )
+            .with_clock_skew(self.clock_skew.clone()),
+        )
     }
 
     /// Return the identities credentials server
@@ -103,11 +144,19 @@ impl Identities {
         vault: Vault,
         identities_repository: Arc<dyn IdentitiesRepository>,
         purpose_keys_repository: Arc<dyn PurposeKeysRepository>,
+        child_identities_repository: Arc<dyn ChildIdentitiesRepository>,
+        groups_repository: Arc<dyn GroupsRepository>,
+        clock_skew: ClockSkew,
+        change_history_limits: ChangeHistoryLimits,
     ) -> Identities {
         Identities {
             vault,
             identities_repository,
             purpose_keys_repository,
+            child_identities_repository,
+            groups_repository,
+            clock_skew,
+            change_history_limits,
         }
     }
 
@@ -117,6 +166,10 @@ impl Identities {
             vault: Vault::create(),
             repository: IdentitiesStorage::create(),
             purpose_keys_repository: PurposeKeysStorage::create(),
+            child_identities_repository: ChildIdentitiesStorage::create(),
+            groups_repository: GroupsStorage::create(),
+            clock_skew: ClockSkew::default(),
+            change_history_limits: ChangeHistoryLimits::default(),
         }
     }
 }
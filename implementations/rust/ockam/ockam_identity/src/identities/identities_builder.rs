@@ -1,7 +1,10 @@
-use crate::identities::{Identities, IdentitiesRepository, IdentitiesStorage};
+use crate::identities::{
+    ChildIdentitiesRepository, ChildIdentitiesStorage, GroupsRepository, GroupsStorage, Identities,
+    IdentitiesRepository, IdentitiesStorage,
+};
 use crate::purpose_keys::storage::{PurposeKeysRepository, PurposeKeysStorage};
 use crate::storage::Storage;
-use crate::{Vault, VaultStorage};
+use crate::{ChangeHistoryLimits, ClockSkew, TimestampInSeconds, Vault, VaultStorage};
 
 use ockam_core::compat::sync::Arc;
 
@@ -11,6 +14,10 @@ pub struct IdentitiesBuilder {
     pub(crate) vault: Vault,
     pub(crate) repository: Arc<dyn IdentitiesRepository>,
     pub(crate) purpose_keys_repository: Arc<dyn PurposeKeysRepository>,
+    pub(crate) child_identities_repository: Arc<dyn ChildIdentitiesRepository>,
+    pub(crate) groups_repository: Arc<dyn GroupsRepository>,
+    pub(crate) clock_skew: ClockSkew,
+    pub(crate) change_history_limits: ChangeHistoryLimits,
 }
 
 /// Return a default identities
@@ -31,9 +38,14 @@ impl IdentitiesBuilder {
         self
     }
 
-    /// Set a specific storage for identities
+    /// Set a specific storage for identities.
+    /// The revocations of child identities and the groups are stored in the same storage
     pub fn with_identities_storage(self, storage: Arc<dyn Storage>) -> Self {
-        self.with_identities_repository(Arc::new(IdentitiesStorage::new(storage)))
+        self.with_identities_repository(Arc::new(IdentitiesStorage::new(storage.clone())))
+            .with_child_identities_repository(Arc::new(ChildIdentitiesStorage::new(
+                storage.clone(),
+            )))
+            .with_groups_repository(Arc::new(GroupsStorage::new(storage)))
     }
 
     /// Set a specific repository for identities
@@ -56,12 +68,43 @@ impl IdentitiesBuilder {
         self
     }
 
+    /// Set a specific repository for the revocations of child identities
+    pub fn with_child_identities_repository(
+        mut self,
+        repository: Arc<dyn ChildIdentitiesRepository>,
+    ) -> Self {
+        self.child_identities_repository = repository;
+        self
+    }
+
+    /// Set a specific repository for the groups of identities
+    pub fn with_groups_repository(mut self, repository: Arc<dyn GroupsRepository>) -> Self {
+        self.groups_repository = repository;
+        self
+    }
+
+    /// Set the tolerance on the clock skew with the issuers of credentials
+    pub fn with_clock_skew_tolerance(mut self, tolerance: TimestampInSeconds) -> Self {
+        self.clock_skew = ClockSkew::new(tolerance);
+        self
+    }
+
+    /// Set the limits on the change histories of the identities received from other nodes
+    pub fn with_change_history_limits(mut self, limits: ChangeHistoryLimits) -> Self {
+        self.change_history_limits = limits;
+        self
+    }
+
     /// Build identities
     pub fn build(self) -> Arc<Identities> {
         Arc::new(Identities::new(
             self.vault,
             self.repository,
             self.purpose_keys_repository,
+            self.child_identities_repository,
+            self.groups_repository,
+            self.clock_skew,
+            self.change_history_limits,
         ))
     }
 }
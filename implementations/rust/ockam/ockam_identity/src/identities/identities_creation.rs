@@ -4,14 +4,15 @@ use ockam_vault::{SigningSecretKeyHandle, VaultForSigning, VaultForVerifyingSign
 
 use crate::identities::identity_builder::IdentityBuilder;
 use crate::models::{ChangeHistory, Identifier};
-use crate::{IdentitiesKeys, IdentitiesRepository, Identity, IdentityError};
-use crate::{IdentityHistoryComparison, IdentityOptions};
+use crate::IdentityOptions;
+use crate::{ChangeHistoryLimits, IdentitiesKeys, IdentitiesRepository, Identity, IdentityError};
 
 /// This struct supports functions for the creation and import of identities using an IdentityVault
 pub struct IdentitiesCreation {
     pub(super) repository: Arc<dyn IdentitiesRepository>,
     pub(super) identity_vault: Arc<dyn VaultForSigning>,
     pub(super) verifying_vault: Arc<dyn VaultForVerifyingSignatures>,
+    pub(super) change_history_limits: ChangeHistoryLimits,
 }
 
 impl IdentitiesCreation {
@@ -25,9 +26,19 @@ impl IdentitiesCreation {
             repository,
             identity_vault,
             verifying_vault,
+            change_history_limits: Default::default(),
         }
     }
 
+    /// Set the limits on the change histories of the imported identities
+    pub fn with_change_history_limits(
+        mut self,
+        change_history_limits: ChangeHistoryLimits,
+    ) -> Self {
+        self.change_history_limits = change_history_limits;
+        self
+    }
+
     /// Return the identities keys management service
     pub fn identities_keys(&self) -> Arc<IdentitiesKeys> {
         Arc::new(IdentitiesKeys::new(
@@ -43,8 +54,13 @@ impl IdentitiesCreation {
         expected_identifier: Option<&Identifier>,
         data: &[u8],
     ) -> Result<Identity> {
-        let identity =
-            Identity::import(expected_identifier, data, self.verifying_vault.clone()).await?;
+        let identity = Identity::import_with_limits(
+            expected_identifier,
+            data,
+            self.verifying_vault.clone(),
+            &self.change_history_limits,
+        )
+        .await?;
 
         self.update_identity(&identity).await?;
 
@@ -58,10 +74,11 @@ impl IdentitiesCreation {
         expected_identifier: Option<&Identifier>,
         change_history: ChangeHistory,
     ) -> Result<Identity> {
-        let identity = Identity::import_from_change_history(
+        let identity = Identity::import_from_change_history_with_limits(
             expected_identifier,
             change_history,
             self.verifying_vault.clone(),
+            &self.change_history_limits,
         )
         .await?;
 
@@ -72,11 +89,14 @@ impl IdentitiesCreation {
 
     /// Get an instance of [`IdentityBuilder`]
     pub fn identity_builder(&self) -> IdentityBuilder {
-        IdentityBuilder::new(Arc::new(Self::new(
-            self.repository.clone(),
-            self.identity_vault.clone(),
-            self.verifying_vault.clone(),
-        )))
+        IdentityBuilder::new(Arc::new(
+            Self::new(
+                self.repository.clone(),
+                self.identity_vault.clone(),
+                self.verifying_vault.clone(),
+            )
+            .with_change_history_limits(self.change_history_limits.clone()),
+        ))
     }
 
     /// Create an `Identity` and store it
@@ -168,38 +188,11 @@ impl IdentitiesCreation {
     /// Compare Identity that was received by any side-channel (e.g., Secure Channel) to the
     /// version we have observed and stored before.
     ///   - Do nothing if they're equal
-    ///   - Throw an error if the received version has conflict or is older that previously observed
+    ///   - Return a [`crate::ChangeHistoryConflict`] error if the received version was forked
+    ///     from the stored version, or is older than the stored version
     ///   - Update stored Identity if the received version is newer
     pub async fn update_identity(&self, identity: &Identity) -> Result<()> {
-        if let Some(known_identity) = self
-            .repository
-            .retrieve_identity(identity.identifier())
-            .await?
-        {
-            let known_identity = Identity::import_from_change_history(
-                Some(identity.identifier()),
-                known_identity,
-                self.verifying_vault.clone(),
-            )
-            .await?;
-
-            match identity.compare(&known_identity) {
-                IdentityHistoryComparison::Conflict | IdentityHistoryComparison::Older => {
-                    return Err(IdentityError::ConsistencyError.into());
-                }
-                IdentityHistoryComparison::Newer => {
-                    self.repository
-                        .update_identity(identity.identifier(), identity.change_history())
-                        .await?;
-                }
-                IdentityHistoryComparison::Equal => {}
-            }
-        } else {
-            self.repository
-                .update_identity(identity.identifier(), identity.change_history())
-                .await?;
-        }
-
+        self.store_verified(identity).await?;
         Ok(())
     }
 }
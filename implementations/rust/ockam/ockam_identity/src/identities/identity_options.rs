@@ -1,3 +1,4 @@
+use crate::models::ChildIdentityAttestation;
 use crate::TimestampInSeconds;
 use ockam_vault::SigningSecretKeyHandle;
 
@@ -7,6 +8,7 @@ pub struct IdentityOptions {
     pub(super) revoke_all_purpose_keys: bool,
     pub(super) created_at: TimestampInSeconds,
     pub(super) expires_at: TimestampInSeconds,
+    pub(super) parent: Option<ChildIdentityAttestation>,
 }
 
 impl IdentityOptions {
@@ -22,9 +24,16 @@ impl IdentityOptions {
             revoke_all_purpose_keys,
             created_at,
             expires_at,
+            parent: None,
         }
     }
 
+    /// Link the new Identity to a parent Identity. Only valid for the first key of an Identity
+    pub fn with_parent(mut self, parent: ChildIdentityAttestation) -> Self {
+        self.parent = Some(parent);
+        self
+    }
+
     /// New key
     pub fn signing_secret_key_handle(&self) -> &SigningSecretKeyHandle {
         &self.signing_secret_key_handle
@@ -44,4 +53,9 @@ impl IdentityOptions {
     pub fn expires_at(&self) -> TimestampInSeconds {
         self.expires_at
     }
+
+    /// Attestation linking the Identity to its parent
+    pub fn parent(&self) -> Option<&ChildIdentityAttestation> {
+        self.parent.as_ref()
+    }
 }
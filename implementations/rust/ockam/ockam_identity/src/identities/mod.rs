@@ -1,3 +1,5 @@
+mod change_history_import;
+mod child_identities;
 #[allow(clippy::module_inception)]
 mod identities;
 mod identities_builder;
@@ -9,6 +11,8 @@ mod identity_options;
 /// Identities storage functions
 pub mod storage;
 
+pub use change_history_import::*;
+pub use child_identities::*;
 pub use identities::*;
 pub use identities_builder::*;
 pub use identities_creation::*;
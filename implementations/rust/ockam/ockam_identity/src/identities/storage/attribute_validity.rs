@@ -0,0 +1,276 @@
+use crate::models::TimestampInSeconds;
+use crate::IdentityError;
+use core::str::from_utf8;
+use minicbor::{Decode, Encode};
+use ockam_core::compat::collections::BTreeMap;
+use ockam_core::compat::string::{String, ToString};
+use ockam_core::compat::vec::Vec;
+use ockam_core::Result;
+use serde::{Deserialize, Serialize};
+
+/// Suffix of the credential attribute carrying the validity of another attribute.
+/// For example the validity of the `role` attribute is stored as `role.validity`
+pub const ATTRIBUTE_VALIDITY_SUFFIX: &str = ".validity";
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Days of the week, as used in a [`DailyWindow`]
+const WEEK_DAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+/// Validity of an attribute: the attribute is only taken into account when
+/// evaluating a policy if the current time is inside the validity period and,
+/// when a schedule is defined, inside one of its time windows
+#[derive(Debug, Clone, Default, Encode, Decode, PartialEq, Eq, Serialize, Deserialize)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct AttributeValidity {
+    #[n(1)] not_before: Option<TimestampInSeconds>,
+    #[n(2)] not_after: Option<TimestampInSeconds>,
+    #[n(3)] schedule: Vec<DailyWindow>,
+}
+
+impl AttributeValidity {
+    /// Create a validity without any constraint
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The attribute is not valid before this time
+    pub fn with_not_before(mut self, not_before: TimestampInSeconds) -> Self {
+        self.not_before = Some(not_before);
+        self
+    }
+
+    /// The attribute is not valid after this time
+    pub fn with_not_after(mut self, not_after: TimestampInSeconds) -> Self {
+        self.not_after = Some(not_after);
+        self
+    }
+
+    /// Add a time window to the schedule of this attribute
+    pub fn with_window(mut self, window: DailyWindow) -> Self {
+        self.schedule.push(window);
+        self
+    }
+
+    /// Start of the validity period
+    pub fn not_before(&self) -> Option<TimestampInSeconds> {
+        self.not_before
+    }
+
+    /// End of the validity period
+    pub fn not_after(&self) -> Option<TimestampInSeconds> {
+        self.not_after
+    }
+
+    /// Time windows during which the attribute is valid. The attribute is
+    /// valid at any time if the schedule is empty
+    pub fn schedule(&self) -> &[DailyWindow] {
+        &self.schedule
+    }
+
+    /// Return true if the attribute is valid at the given time
+    pub fn is_valid_at(&self, now: TimestampInSeconds) -> bool {
+        if self.not_before.map_or(false, |t| now < t) {
+            return false;
+        }
+        if self.not_after.map_or(false, |t| now > t) {
+            return false;
+        }
+        self.schedule.is_empty() || self.schedule.iter().any(|w| w.contains(now))
+    }
+
+    /// Encode this validity as the value of a credential attribute
+    pub fn to_attribute_value(&self) -> Result<Vec<u8>> {
+        Ok(minicbor::to_vec(self)?)
+    }
+
+    /// Decode a validity from the value of a credential attribute
+    pub fn from_attribute_value(value: &[u8]) -> Result<Self> {
+        Ok(minicbor::decode(value)?)
+    }
+}
+
+/// Remove the `<name>.validity` attributes from a set of credential attributes
+/// and return the decoded validity of each constrained attribute
+pub fn extract_attributes_validity(
+    attrs: &mut BTreeMap<Vec<u8>, Vec<u8>>,
+) -> Result<BTreeMap<String, AttributeValidity>> {
+    let keys: Vec<Vec<u8>> = attrs
+        .keys()
+        .filter(|k| k.ends_with(ATTRIBUTE_VALIDITY_SUFFIX.as_bytes()))
+        .cloned()
+        .collect();
+    let mut validity = BTreeMap::new();
+    for key in keys {
+        let value = attrs.remove(&key).unwrap_or_default();
+        let name = from_utf8(&key[..key.len() - ATTRIBUTE_VALIDITY_SUFFIX.len()])
+            .map_err(|_| IdentityError::InvalidAttributeValidity)?;
+        validity.insert(
+            name.to_string(),
+            AttributeValidity::from_attribute_value(&value)?,
+        );
+    }
+    Ok(validity)
+}
+
+/// A daily time window, in UTC, optionally restricted to some days of the week.
+/// A window ending before it starts spans midnight, e.g. 22:00-06:00
+#[derive(Debug, Clone, Copy, Encode, Decode, PartialEq, Eq, Serialize, Deserialize)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct DailyWindow {
+    /// Start of the window, in seconds since midnight
+    #[n(1)] start: u32,
+    /// End of the window, in seconds since midnight
+    #[n(2)] end: u32,
+    /// Days of the week when the window applies, as a bit mask where Monday is
+    /// the lowest bit. 0 means every day
+    #[n(3)] days: u8,
+}
+
+impl DailyWindow {
+    /// Create a window applying every day, from `start` to `end` seconds since midnight
+    pub fn new(start: u32, end: u32) -> Result<Self> {
+        if start >= SECONDS_PER_DAY as u32 || end > SECONDS_PER_DAY as u32 || start == end {
+            return Err(IdentityError::InvalidAttributeValidity.into());
+        }
+        Ok(Self {
+            start,
+            end,
+            days: 0,
+        })
+    }
+
+    /// Restrict this window to some days of the week, 0 being Monday
+    pub fn on_days(mut self, days: &[u8]) -> Result<Self> {
+        for day in days {
+            if *day > 6 {
+                return Err(IdentityError::InvalidAttributeValidity.into());
+            }
+            self.days |= 1 << day;
+        }
+        Ok(self)
+    }
+
+    /// Parse a window like `09:00-17:00`, `mon-fri 09:00-17:00` or `sat,sun 10:00-12:00`
+    pub fn parse(s: &str) -> Result<Self> {
+        let invalid = || IdentityError::InvalidAttributeValidity;
+        let mut parts = s.split_whitespace();
+        let (days, times) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(times), None, None) => (None, times),
+            (Some(days), Some(times), None) => (Some(days), times),
+            _ => return Err(invalid().into()),
+        };
+        let (start, end) = times.split_once('-').ok_or_else(invalid)?;
+        let window = Self::new(parse_time(start)?, parse_time(end)?)?;
+        match days {
+            None => Ok(window),
+            Some(days) => window.on_days(&parse_days(days)?),
+        }
+    }
+
+    /// Return true if the given time is inside this window
+    pub fn contains(&self, now: TimestampInSeconds) -> bool {
+        let day = *now / SECONDS_PER_DAY;
+        let time = (*now % SECONDS_PER_DAY) as u32;
+        // a window spanning midnight started on the previous day for the early hours
+        let (inside, day) = if self.start < self.end {
+            (self.start <= time && time < self.end, day)
+        } else if time >= self.start {
+            (true, day)
+        } else {
+            (time < self.end, day.wrapping_sub(1))
+        };
+        // the 1st of January 1970 was a Thursday
+        let week_day = (day.wrapping_add(3)) % 7;
+        inside && (self.days == 0 || self.days & (1 << week_day) != 0)
+    }
+}
+
+fn parse_time(s: &str) -> Result<u32> {
+    let invalid = || IdentityError::InvalidAttributeValidity;
+    let (hours, minutes) = s.split_once(':').ok_or_else(invalid)?;
+    let hours: u32 = hours.parse().map_err(|_| invalid())?;
+    let minutes: u32 = minutes.parse().map_err(|_| invalid())?;
+    if hours > 24 || minutes > 59 || (hours == 24 && minutes != 0) {
+        return Err(invalid().into());
+    }
+    Ok(hours * 3600 + minutes * 60)
+}
+
+fn parse_days(s: &str) -> Result<Vec<u8>> {
+    let day = |d: &str| -> Result<u8> {
+        let d = String::from(d).to_lowercase();
+        WEEK_DAYS
+            .iter()
+            .position(|w| *w == d)
+            .map(|p| p as u8)
+            .ok_or_else(|| IdentityError::InvalidAttributeValidity.into())
+    };
+    let mut days = Vec::new();
+    for range in s.split(',') {
+        match range.split_once('-') {
+            Some((first, last)) => {
+                let (first, last) = (day(first)?, day(last)?);
+                let mut d = first;
+                loop {
+                    days.push(d);
+                    if d == last {
+                        break;
+                    }
+                    d = (d + 1) % 7;
+                }
+            }
+            None => days.push(day(range)?),
+        }
+    }
+    Ok(days)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Monday 2023-10-16 00:00:00 UTC
+    const MONDAY: u64 = 1697414400;
+
+    fn at(day: u64, hours: u64, minutes: u64) -> TimestampInSeconds {
+        TimestampInSeconds(MONDAY + day * SECONDS_PER_DAY + hours * 3600 + minutes * 60)
+    }
+
+    #[test]
+    fn test_attribute_validity() -> Result<()> {
+        let office_hours = AttributeValidity::new()
+            .with_not_after(at(7, 0, 0))
+            .with_window(DailyWindow::parse("mon-fri 09:00-17:00")?);
+        assert!(office_hours.is_valid_at(at(0, 9, 0)));
+        assert!(office_hours.is_valid_at(at(4, 16, 59)));
+        assert!(!office_hours.is_valid_at(at(0, 17, 0)));
+        assert!(!office_hours.is_valid_at(at(5, 10, 0)));
+        assert!(!office_hours.is_valid_at(at(7, 10, 0)));
+
+        let nights = AttributeValidity::new().with_window(DailyWindow::parse("sun 22:00-06:00")?);
+        assert!(nights.is_valid_at(at(6, 23, 0)));
+        assert!(nights.is_valid_at(at(7, 5, 0)));
+        assert!(!nights.is_valid_at(at(0, 23, 0)));
+
+        let encoded = office_hours.to_attribute_value()?;
+        assert_eq!(
+            AttributeValidity::from_attribute_value(&encoded)?,
+            office_hours
+        );
+
+        let mut attrs = BTreeMap::new();
+        attrs.insert(b"role".to_vec(), b"admin".to_vec());
+        attrs.insert(b"role.validity".to_vec(), encoded);
+        let validity = extract_attributes_validity(&mut attrs)?;
+        assert_eq!(attrs.len(), 1);
+        assert_eq!(validity.get("role"), Some(&office_hours));
+
+        assert!(DailyWindow::parse("09:00").is_err());
+        assert!(DailyWindow::parse("noday 09:00-10:00").is_err());
+        assert!(DailyWindow::parse("25:00-26:00").is_err());
+        Ok(())
+    }
+}
@@ -1,8 +1,12 @@
 use crate::models::{Identifier, TimestampInSeconds};
+use crate::AttributeValidity;
+use core::cmp::max;
+use core::str::from_utf8;
 use minicbor::{Decode, Encode};
 use ockam_core::compat::borrow::ToOwned;
-use ockam_core::compat::{collections::BTreeMap, vec::Vec};
+use ockam_core::compat::{collections::BTreeMap, string::String, vec::Vec};
 use serde::{Deserialize, Serialize};
+use tracing::warn;
 
 /// An entry on the AuthenticatedIdentities table.
 #[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, Serialize, Deserialize)]
@@ -14,6 +18,51 @@ pub struct AttributesEntry {
     #[n(2)] added: TimestampInSeconds,
     #[n(3)] expires: Option<TimestampInSeconds>,
     #[n(4)] attested_by: Option<Identifier>,
+    #[serde(default)]
+    #[n(5)] validity: Option<BTreeMap<String, AttributeValidity>>,
+    #[serde(default)]
+    #[n(6)] provenance: Option<BTreeMap<Vec<u8>, AttributeProvenance>>,
+}
+
+/// Provenance of a single attribute: who attested it, when it was added,
+/// and when it expires
+#[derive(Debug, Clone, Encode, Decode, PartialEq, Eq, Serialize, Deserialize)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct AttributeProvenance {
+    #[n(1)] attested_by: Option<Identifier>,
+    #[n(2)] added: TimestampInSeconds,
+    #[n(3)] expires: Option<TimestampInSeconds>,
+}
+
+impl AttributeProvenance {
+    /// Constructor
+    pub fn new(
+        attested_by: Option<Identifier>,
+        added: TimestampInSeconds,
+        expires: Option<TimestampInSeconds>,
+    ) -> Self {
+        Self {
+            attested_by,
+            added,
+            expires,
+        }
+    }
+
+    /// Who attested this attribute
+    pub fn attested_by(&self) -> Option<&Identifier> {
+        self.attested_by.as_ref()
+    }
+
+    /// Date that the attribute was added
+    pub fn added(&self) -> TimestampInSeconds {
+        self.added
+    }
+
+    /// Expiration time for this attribute
+    pub fn expires(&self) -> Option<TimestampInSeconds> {
+        self.expires
+    }
 }
 
 impl AttributesEntry {
@@ -33,14 +82,195 @@ impl AttributesEntry {
             added,
             expires,
             attested_by,
+            validity: None,
+            provenance: None,
         }
     }
 
+    /// Restrict some attributes of this entry to a validity period or a schedule
+    pub fn with_validity(mut self, validity: BTreeMap<String, AttributeValidity>) -> Self {
+        self.validity = if validity.is_empty() {
+            None
+        } else {
+            Some(validity)
+        };
+        self
+    }
+
     /// The entry attributes
     pub fn attrs(&self) -> &BTreeMap<Vec<u8>, Vec<u8>> {
         &self.attrs
     }
 
+    /// Return true if some attributes of this entry have a validity constraint
+    pub fn has_validity(&self) -> bool {
+        self.validity.is_some()
+    }
+
+    /// Validity constraints of the attributes of this entry, by attribute name
+    pub fn attributes_validity(&self) -> Option<&BTreeMap<String, AttributeValidity>> {
+        self.validity.as_ref()
+    }
+
+    /// Validity constraint of an attribute, if any
+    pub fn validity(&self, key: &str) -> Option<&AttributeValidity> {
+        self.validity.as_ref().and_then(|v| v.get(key))
+    }
+
+    /// Return true if the attribute can be used at the given time.
+    /// Attributes without a validity constraint are always active, until they expire
+    pub fn is_active(&self, key: &[u8], now: TimestampInSeconds) -> bool {
+        if let Some(expires) = self.provenance(key).and_then(|p| p.expires) {
+            if expires <= now {
+                return false;
+            }
+        }
+        match from_utf8(key).ok().and_then(|key| self.validity(key)) {
+            Some(validity) => validity.is_valid_at(now),
+            None => true,
+        }
+    }
+
+    /// Provenance of an attribute. Unless this entry was merged with other entries,
+    /// all its attributes have the provenance of the entry
+    pub fn provenance(&self, key: &[u8]) -> Option<AttributeProvenance> {
+        if !self.attrs.contains_key(key) {
+            return None;
+        }
+        match self.provenance.as_ref().and_then(|p| p.get(key)) {
+            Some(provenance) => Some(provenance.clone()),
+            None => Some(self.entry_provenance()),
+        }
+    }
+
+    /// Merge the attributes of another entry, possibly attested by another authority.
+    ///
+    /// The attributes of `other` replace the attributes previously attested by the attester
+    /// of `other`. An attribute still attested by another authority is not replaced: the
+    /// conflicting attribute of `other` is ignored. Each attribute keeps its own provenance
+    pub fn merge(self, mut other: AttributesEntry) -> Self {
+        let current = match other.attested_by.as_ref() {
+            Some(attester) => {
+                let current = self
+                    .without_attester(attester)
+                    .and_then(|current| current.without_expired(other.added));
+                match current {
+                    Some(current) => current,
+                    None => return other,
+                }
+            }
+            None => self,
+        };
+
+        if let Some(attester) = other.attested_by.as_ref() {
+            let conflicts: Vec<Vec<u8>> = other
+                .attrs
+                .keys()
+                .filter(|k| current.attrs.contains_key(*k))
+                .cloned()
+                .collect();
+            for key in conflicts {
+                warn!(
+                    %attester,
+                    attribute = %String::from_utf8_lossy(&key),
+                    "ignoring an attribute already attested by another authority"
+                );
+                other.attrs.remove(&key);
+                if let Some(provenance) = other.provenance.as_mut() {
+                    provenance.remove(&key);
+                }
+                if let (Some(validity), Ok(key)) = (other.validity.as_mut(), from_utf8(&key)) {
+                    validity.remove(key);
+                }
+            }
+        }
+
+        let mut provenance = current.attributes_provenance();
+        provenance.extend(other.attributes_provenance());
+
+        let mut validity = current.validity.unwrap_or_default();
+        for key in other.attrs.keys().filter_map(|k| from_utf8(k).ok()) {
+            validity.remove(key);
+        }
+        validity.extend(other.validity.unwrap_or_default());
+
+        let mut attrs = current.attrs;
+        attrs.extend(other.attrs);
+        Self::from_provenance(attrs, validity, provenance)
+    }
+
+    /// Remove the attributes attested by a given identity.
+    /// Return `None` if no attributes remain
+    pub fn without_attester(self, attester: &Identifier) -> Option<Self> {
+        let mut provenance = self.attributes_provenance();
+        provenance.retain(|_, p| p.attested_by.as_ref() != Some(attester));
+        if provenance.is_empty() {
+            return None;
+        }
+
+        let mut attrs = self.attrs;
+        attrs.retain(|k, _| provenance.contains_key(k));
+        let mut validity = self.validity.unwrap_or_default();
+        validity.retain(|k, _| attrs.contains_key(k.as_bytes()));
+        Some(Self::from_provenance(attrs, validity, provenance))
+    }
+
+    /// Remove the attributes which expired at the given time.
+    /// Return `None` if no attributes remain
+    pub fn without_expired(self, now: TimestampInSeconds) -> Option<Self> {
+        let mut provenance = self.attributes_provenance();
+        let count = provenance.len();
+        provenance.retain(|_, p| p.expires.map_or(true, |expires| expires > now));
+        if provenance.is_empty() {
+            return None;
+        }
+        if provenance.len() == count {
+            return Some(self);
+        }
+
+        let mut attrs = self.attrs;
+        attrs.retain(|k, _| provenance.contains_key(k));
+        let mut validity = self.validity.unwrap_or_default();
+        validity.retain(|k, _| attrs.contains_key(k.as_bytes()));
+        Some(Self::from_provenance(attrs, validity, provenance))
+    }
+
+    /// Provenance of each attribute of this entry
+    fn attributes_provenance(&self) -> BTreeMap<Vec<u8>, AttributeProvenance> {
+        self.attrs
+            .keys()
+            .filter_map(|k| Some((k.clone(), self.provenance(k)?)))
+            .collect()
+    }
+
+    fn entry_provenance(&self) -> AttributeProvenance {
+        AttributeProvenance::new(self.attested_by.clone(), self.added, self.expires)
+    }
+
+    /// Create an entry from attributes having different provenances. The entry is attested by
+    /// the attester of the most recent attribute, and it expires with its last attribute
+    fn from_provenance(
+        attrs: BTreeMap<Vec<u8>, Vec<u8>>,
+        validity: BTreeMap<String, AttributeValidity>,
+        provenance: BTreeMap<Vec<u8>, AttributeProvenance>,
+    ) -> Self {
+        let latest = provenance.values().max_by_key(|p| p.added);
+        let expires = provenance
+            .values()
+            .map(|p| p.expires)
+            .reduce(|a, b| Some(max(a?, b?)))
+            .flatten();
+        Self {
+            attrs,
+            added: latest.map_or(TimestampInSeconds(0), |p| p.added),
+            expires,
+            attested_by: latest.and_then(|p| p.attested_by.clone()),
+            validity: None,
+            provenance: Some(provenance),
+        }
+        .with_validity(validity)
+    }
+
     /// Expiration time for this entry
     pub fn expires(&self) -> Option<TimestampInSeconds> {
         self.expires
@@ -56,3 +286,92 @@ impl AttributesEntry {
         self.attested_by.to_owned()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attributes_provenance() {
+        let authority1 = Identifier::try_from("I0000000000000000000000000000000000000001").unwrap();
+        let authority2 = Identifier::try_from("I0000000000000000000000000000000000000002").unwrap();
+        let attrs = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.as_bytes().to_vec(), v.as_bytes().to_vec()))
+                .collect::<BTreeMap<_, _>>()
+        };
+
+        let entry1 = AttributesEntry::new(
+            attrs(&[("role", "user"), ("team", "blue")]),
+            TimestampInSeconds(10),
+            Some(TimestampInSeconds(100)),
+            Some(authority1.clone()),
+        );
+        let entry2 = AttributesEntry::new(
+            attrs(&[("role", "admin"), ("project", "p1")]),
+            TimestampInSeconds(20),
+            Some(TimestampInSeconds(50)),
+            Some(authority2.clone()),
+        );
+
+        // an authority can not replace the attributes attested by another authority
+        let merged = entry1.merge(entry2);
+        assert_eq!(
+            merged.attrs(),
+            &attrs(&[("project", "p1"), ("role", "user"), ("team", "blue")])
+        );
+        assert_eq!(merged.attested_by(), Some(authority2.clone()));
+        assert_eq!(merged.added(), TimestampInSeconds(20));
+        assert_eq!(merged.expires(), Some(TimestampInSeconds(100)));
+        let team = merged.provenance(b"team").unwrap();
+        assert_eq!(team.attested_by(), Some(&authority1));
+        assert_eq!(team.added(), TimestampInSeconds(10));
+
+        // the attributes expire separately
+        assert!(merged.is_active(b"project", TimestampInSeconds(40)));
+        assert!(!merged.is_active(b"project", TimestampInSeconds(60)));
+        assert!(merged.is_active(b"team", TimestampInSeconds(60)));
+        let unexpired = merged
+            .clone()
+            .without_expired(TimestampInSeconds(60))
+            .unwrap();
+        assert_eq!(
+            unexpired.attrs(),
+            &attrs(&[("role", "user"), ("team", "blue")])
+        );
+        assert!(unexpired.without_expired(TimestampInSeconds(100)).is_none());
+
+        // a new entry from an authority replaces the attributes it attested before
+        let updated = merged.clone().merge(AttributesEntry::new(
+            attrs(&[("team", "red")]),
+            TimestampInSeconds(30),
+            None,
+            Some(authority1.clone()),
+        ));
+        assert_eq!(
+            updated.attrs(),
+            &attrs(&[("project", "p1"), ("team", "red")])
+        );
+        assert_eq!(updated.expires(), None);
+
+        // an attribute can be attested by another authority once it expired
+        let replaced = merged.clone().merge(AttributesEntry::new(
+            attrs(&[("project", "p2")]),
+            TimestampInSeconds(60),
+            None,
+            Some(authority1.clone()),
+        ));
+        assert_eq!(replaced.attrs(), &attrs(&[("project", "p2")]));
+        assert_eq!(replaced.attested_by(), Some(authority1.clone()));
+
+        // the attributes of one authority can be revoked
+        let revoked = merged.without_attester(&authority2).unwrap();
+        assert_eq!(
+            revoked.attrs(),
+            &attrs(&[("role", "user"), ("team", "blue")])
+        );
+        assert_eq!(revoked.attested_by(), Some(authority1.clone()));
+        assert!(revoked.without_attester(&authority1).is_none());
+    }
+}
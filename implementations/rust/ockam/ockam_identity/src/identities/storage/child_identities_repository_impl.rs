@@ -0,0 +1,68 @@
+use ockam_core::async_trait;
+use ockam_core::compat::boxed::Box;
+use ockam_core::compat::string::ToString;
+use ockam_core::compat::sync::Arc;
+use ockam_core::compat::vec::Vec;
+use ockam_core::Result;
+
+use crate::identity::IdentityConstants;
+use crate::models::Identifier;
+use crate::storage::{InMemoryStorage, Storage};
+use crate::ChildIdentitiesRepository;
+
+/// Implementation of [`ChildIdentitiesRepository`] trait based on an underlying [`Storage`]
+#[derive(Clone)]
+pub struct ChildIdentitiesStorage {
+    storage: Arc<dyn Storage>,
+}
+
+impl ChildIdentitiesStorage {
+    /// Create a new storage for the revocations of child identities
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self { storage }
+    }
+
+    /// Create a new storage for the revocations of child identities with an in-memory storage
+    pub fn create() -> Arc<Self> {
+        Arc::new(Self::new(InMemoryStorage::create()))
+    }
+}
+
+#[async_trait]
+impl ChildIdentitiesRepository for ChildIdentitiesStorage {
+    async fn revoke_child_identity(&self, parent: &Identifier, child: &Identifier) -> Result<()> {
+        self.storage
+            .set(
+                &child.to_string(),
+                IdentityConstants::REVOKED_BY_PARENT_KEY.to_string(),
+                minicbor::to_vec(parent)?,
+            )
+            .await
+    }
+
+    async fn get_revocation(&self, child: &Identifier) -> Result<Option<Identifier>> {
+        match self
+            .storage
+            .get(&child.to_string(), IdentityConstants::REVOKED_BY_PARENT_KEY)
+            .await?
+        {
+            Some(parent) => Ok(Some(minicbor::decode(&parent)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn list_revoked_child_identities(&self, parent: &Identifier) -> Result<Vec<Identifier>> {
+        let mut children = Vec::new();
+        for id in self
+            .storage
+            .keys(IdentityConstants::REVOKED_BY_PARENT_KEY)
+            .await?
+        {
+            let child = Identifier::try_from(id)?;
+            if self.get_revocation(&child).await?.as_ref() == Some(parent) {
+                children.push(child)
+            }
+        }
+        Ok(children)
+    }
+}
@@ -0,0 +1,19 @@
+use ockam_core::async_trait;
+use ockam_core::compat::boxed::Box;
+use ockam_core::compat::vec::Vec;
+use ockam_core::Result;
+
+use crate::models::Identifier;
+
+/// Repository for the revocations of child identities
+#[async_trait]
+pub trait ChildIdentitiesRepository: Send + Sync + 'static {
+    /// Record that the child identity `child` was revoked by its parent `parent`
+    async fn revoke_child_identity(&self, parent: &Identifier, child: &Identifier) -> Result<()>;
+
+    /// Return the parent which revoked the child identity `child`, if it was revoked
+    async fn get_revocation(&self, child: &Identifier) -> Result<Option<Identifier>>;
+
+    /// List the child identities revoked by `parent`
+    async fn list_revoked_child_identities(&self, parent: &Identifier) -> Result<Vec<Identifier>>;
+}
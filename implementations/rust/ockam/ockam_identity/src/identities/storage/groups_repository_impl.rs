@@ -0,0 +1,274 @@
+use minicbor::{Decode, Encode};
+use ockam_core::async_trait;
+use ockam_core::compat::boxed::Box;
+use ockam_core::compat::format;
+use ockam_core::compat::string::{String, ToString};
+use ockam_core::compat::sync::Arc;
+use ockam_core::compat::vec::Vec;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Error, Result};
+use ockam_node::compat::asynchronous::Mutex;
+
+use crate::identity::IdentityConstants;
+use crate::models::{Identifier, TimestampInSeconds};
+use crate::storage::{InMemoryStorage, Storage};
+use crate::utils::now;
+use crate::GroupsRepository;
+
+/// Implementation of [`GroupsRepository`] trait based on an underlying [`Storage`].
+///
+/// Each group is stored by name with its creation date and its members, and the names of
+/// the groups of each identity are stored by identifier, to get them without reading all
+/// the groups.
+///
+/// The record of a group is the reference for its memberships: it is written last when
+/// a member is added and first when a member is removed or the group is deleted, so that
+/// each change takes effect with a single write. The groups of an identity are checked
+/// against their records, which ignores the groups left by an interrupted change
+#[derive(Clone)]
+pub struct GroupsStorage {
+    storage: Arc<dyn Storage>,
+    // serializes the changes, which read and write several entries
+    changes: Arc<Mutex<()>>,
+}
+
+/// Creation date and members of a group
+#[derive(Encode, Decode)]
+#[rustfmt::skip]
+#[cbor(map)]
+struct GroupRecord {
+    #[n(1)] created_at: TimestampInSeconds,
+    #[n(2)] members: Vec<Identifier>,
+}
+
+impl GroupsStorage {
+    /// Create a new storage for groups
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self {
+            storage,
+            changes: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Create a new storage for groups with an in-memory storage
+    pub fn create() -> Arc<Self> {
+        Arc::new(Self::new(InMemoryStorage::create()))
+    }
+
+    async fn get_group(&self, group: &str) -> Result<Option<GroupRecord>> {
+        match self
+            .storage
+            .get(group, IdentityConstants::GROUP_KEY)
+            .await?
+        {
+            Some(record) => Ok(Some(minicbor::decode(&record)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn put_group(&self, group: &str, record: &GroupRecord) -> Result<()> {
+        self.storage
+            .set(
+                group,
+                IdentityConstants::GROUP_KEY.to_string(),
+                minicbor::to_vec(record)?,
+            )
+            .await
+    }
+
+    /// Return the groups of an identity, including the groups of interrupted changes
+    async fn get_member_of(&self, member: &Identifier) -> Result<Vec<String>> {
+        match self
+            .storage
+            .get(&member.to_string(), IdentityConstants::MEMBER_OF_KEY)
+            .await?
+        {
+            Some(groups) => Ok(minicbor::decode(&groups)?),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    async fn put_member_of(&self, member: &Identifier, groups: Vec<String>) -> Result<()> {
+        let id = member.to_string();
+        if groups.is_empty() {
+            self.storage
+                .del(&id, IdentityConstants::MEMBER_OF_KEY)
+                .await
+        } else {
+            self.storage
+                .set(
+                    &id,
+                    IdentityConstants::MEMBER_OF_KEY.to_string(),
+                    minicbor::to_vec(groups)?,
+                )
+                .await
+        }
+    }
+
+    async fn remove_member_of(&self, group: &str, member: &Identifier) -> Result<()> {
+        let mut groups = self.get_member_of(member).await?;
+        groups.retain(|g| g != group);
+        self.put_member_of(member, groups).await
+    }
+
+    fn group_not_found(group: &str) -> Error {
+        Error::new(
+            Origin::Identity,
+            Kind::NotFound,
+            format!("the group {group} does not exist"),
+        )
+    }
+}
+
+#[async_trait]
+impl GroupsRepository for GroupsStorage {
+    async fn create_group(&self, group: &str) -> Result<()> {
+        let _changes = self.changes.lock().await;
+        if self.get_group(group).await?.is_some() {
+            return Ok(());
+        }
+        let record = GroupRecord {
+            created_at: now()?,
+            members: Vec::new(),
+        };
+        self.put_group(group, &record).await
+    }
+
+    async fn delete_group(&self, group: &str) -> Result<()> {
+        let _changes = self.changes.lock().await;
+        let record = match self.get_group(group).await? {
+            Some(record) => record,
+            None => return Ok(()),
+        };
+        // all the memberships are removed with the record
+        self.storage
+            .del(group, IdentityConstants::GROUP_KEY)
+            .await?;
+        for member in record.members {
+            self.remove_member_of(group, &member).await?;
+        }
+        Ok(())
+    }
+
+    async fn has_group(&self, group: &str) -> Result<bool> {
+        Ok(self.get_group(group).await?.is_some())
+    }
+
+    async fn list_groups(&self) -> Result<Vec<String>> {
+        self.storage.keys(IdentityConstants::GROUP_KEY).await
+    }
+
+    async fn add_member(&self, group: &str, member: &Identifier) -> Result<()> {
+        let _changes = self.changes.lock().await;
+        let mut record = self
+            .get_group(group)
+            .await?
+            .ok_or_else(|| Self::group_not_found(group))?;
+        let mut groups = self.get_member_of(member).await?;
+        if !groups.iter().any(|g| g == group) {
+            groups.push(group.to_string());
+            self.put_member_of(member, groups).await?;
+        }
+        if record.members.contains(member) {
+            return Ok(());
+        }
+        record.members.push(member.clone());
+        self.put_group(group, &record).await
+    }
+
+    async fn remove_member(&self, group: &str, member: &Identifier) -> Result<()> {
+        let _changes = self.changes.lock().await;
+        if let Some(mut record) = self.get_group(group).await? {
+            if record.members.contains(member) {
+                record.members.retain(|m| m != member);
+                self.put_group(group, &record).await?;
+            }
+        }
+        self.remove_member_of(group, member).await
+    }
+
+    async fn list_members(&self, group: &str) -> Result<Vec<Identifier>> {
+        Ok(self
+            .get_group(group)
+            .await?
+            .map(|record| record.members)
+            .unwrap_or_default())
+    }
+
+    async fn get_groups(&self, member: &Identifier) -> Result<Vec<String>> {
+        let mut groups = Vec::new();
+        for group in self.get_member_of(member).await? {
+            let is_member = match self.get_group(&group).await? {
+                Some(record) => record.members.contains(member),
+                None => false,
+            };
+            if is_member {
+                groups.push(group);
+            }
+        }
+        Ok(groups)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::str::FromStr;
+
+    #[tokio::test]
+    async fn test_groups() -> Result<()> {
+        let repository = GroupsStorage::create();
+        let alice = Identifier::from_str("I0123456789abcdef0123456789abcdef01234567")?;
+        let bob = Identifier::from_str("I89abcdef0123456789abcdef0123456789abcdef")?;
+
+        // a member can only be added to an existing group
+        assert!(repository.add_member("admins", &alice).await.is_err());
+
+        repository.create_group("admins").await?;
+        repository.create_group("developers").await?;
+        repository.add_member("admins", &alice).await?;
+        repository.add_member("developers", &alice).await?;
+        repository.add_member("developers", &bob).await?;
+        repository.add_member("developers", &bob).await?;
+
+        assert_eq!(
+            repository.list_groups().await?,
+            vec!["admins", "developers"]
+        );
+        assert_eq!(
+            repository.get_groups(&alice).await?,
+            vec!["admins", "developers"]
+        );
+        assert_eq!(repository.list_members("developers").await?.len(), 2);
+
+        repository.remove_member("developers", &alice).await?;
+        assert_eq!(repository.get_groups(&alice).await?, vec!["admins"]);
+
+        repository.delete_group("developers").await?;
+        assert!(repository.get_groups(&bob).await?.is_empty());
+        assert!(repository.list_members("developers").await?.is_empty());
+        assert_eq!(repository.list_groups().await?, vec!["admins"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_changes_are_ignored() -> Result<()> {
+        let repository = GroupsStorage::create();
+        let alice = Identifier::from_str("I0123456789abcdef0123456789abcdef01234567")?;
+        repository.create_group("admins").await?;
+        repository.add_member("admins", &alice).await?;
+
+        // the group was deleted but the groups of alice were not updated yet
+        repository
+            .storage
+            .del("admins", IdentityConstants::GROUP_KEY)
+            .await?;
+        assert!(repository.get_groups(&alice).await?.is_empty());
+
+        // a group created again with the same name has no members
+        repository.create_group("admins").await?;
+        assert!(repository.get_groups(&alice).await?.is_empty());
+        assert!(repository.list_members("admins").await?.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,37 @@
+use ockam_core::async_trait;
+use ockam_core::compat::boxed::Box;
+use ockam_core::compat::string::String;
+use ockam_core::compat::vec::Vec;
+use ockam_core::Result;
+
+use crate::models::Identifier;
+
+/// Repository for named groups of identities.
+///
+/// The groups of an identity can be checked by a policy with `(member_of "group")`
+#[async_trait]
+pub trait GroupsRepository: Send + Sync + 'static {
+    /// Create a group. Creating an existing group does nothing
+    async fn create_group(&self, group: &str) -> Result<()>;
+
+    /// Delete a group and all its memberships
+    async fn delete_group(&self, group: &str) -> Result<()>;
+
+    /// Return true if a group exists
+    async fn has_group(&self, group: &str) -> Result<bool>;
+
+    /// List the names of all the groups
+    async fn list_groups(&self) -> Result<Vec<String>>;
+
+    /// Add an identity to an existing group
+    async fn add_member(&self, group: &str, member: &Identifier) -> Result<()>;
+
+    /// Remove an identity from a group
+    async fn remove_member(&self, group: &str, member: &Identifier) -> Result<()>;
+
+    /// List the members of a group
+    async fn list_members(&self, group: &str) -> Result<Vec<Identifier>>;
+
+    /// Return the names of the groups an identity is a member of
+    async fn get_groups(&self, member: &Identifier) -> Result<Vec<String>>;
+}
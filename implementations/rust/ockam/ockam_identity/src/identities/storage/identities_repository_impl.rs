@@ -2,7 +2,7 @@ use ockam_core::async_trait;
 use ockam_core::compat::boxed::Box;
 use ockam_core::compat::collections::BTreeMap;
 use ockam_core::compat::string::ToString;
-use ockam_core::compat::sync::Arc;
+use ockam_core::compat::sync::{Arc, RwLock};
 use ockam_core::compat::vec::Vec;
 use ockam_core::Result;
 
@@ -11,14 +11,15 @@ use crate::models::{ChangeHistory, Identifier};
 use crate::storage::{InMemoryStorage, Storage};
 use crate::utils::now;
 use crate::{
-    AttributesEntry, IdentitiesReader, IdentitiesRepository, IdentitiesWriter,
-    IdentityAttributesReader, IdentityAttributesWriter,
+    AttributesChangesListener, AttributesEntry, IdentitiesReader, IdentitiesRepository,
+    IdentitiesWriter, IdentityAttributesReader, IdentityAttributesWriter,
 };
 
 /// Implementation of `IdentityAttributes` trait based on an underlying `Storage`
 #[derive(Clone)]
 pub struct IdentitiesStorage {
     storage: Arc<dyn Storage>,
+    listeners: Arc<RwLock<Vec<Arc<dyn AttributesChangesListener>>>>,
 }
 
 #[async_trait]
@@ -38,18 +39,31 @@ impl IdentitiesRepository for IdentitiesStorage {
     fn as_identities_writer(&self) -> Arc<dyn IdentitiesWriter> {
         Arc::new(self.clone())
     }
+
+    fn subscribe_to_attributes_changes(&self, listener: Arc<dyn AttributesChangesListener>) {
+        self.listeners.write().unwrap().push(listener);
+    }
 }
 
 impl IdentitiesStorage {
     /// Create a new storage for attributes
     pub fn new(storage: Arc<dyn Storage>) -> Self {
-        Self { storage }
+        Self {
+            storage,
+            listeners: Default::default(),
+        }
     }
 
     /// Create a new storage for attributes
     pub fn create() -> Arc<Self> {
         Arc::new(Self::new(InMemoryStorage::create()))
     }
+
+    fn notify_attributes_changed(&self, identity: &Identifier) {
+        for listener in self.listeners.read().unwrap().iter() {
+            listener.attributes_changed(identity);
+        }
+    }
 }
 
 #[async_trait]
@@ -67,15 +81,16 @@ impl IdentityAttributesReader for IdentitiesStorage {
 
         let entry: AttributesEntry = minicbor::decode(&entry)?;
 
+        // the attributes attested by several authorities expire separately
         let now = now()?;
-        match entry.expires() {
-            Some(exp) if exp <= now => {
+        match entry.without_expired(now) {
+            Some(entry) => Ok(Some(entry)),
+            None => {
                 self.storage
                     .del(&id, IdentityConstants::ATTRIBUTES_KEY)
                     .await?;
                 Ok(None)
             }
-            _ => Ok(Some(entry)),
         }
     }
 
@@ -105,9 +120,33 @@ impl IdentityAttributesWriter for IdentitiesStorage {
             )
             .await?;
 
+        self.notify_attributes_changed(sender);
         Ok(())
     }
 
+    async fn merge_attributes(&self, identity: &Identifier, entry: AttributesEntry) -> Result<()> {
+        let entry = match self.get_attributes(identity).await? {
+            Some(existing) => existing.merge(entry),
+            None => entry,
+        };
+        self.put_attributes(identity, entry).await
+    }
+
+    async fn delete_attributes_attested_by(
+        &self,
+        identity: &Identifier,
+        attester: &Identifier,
+    ) -> Result<()> {
+        let entry = match self.get_attributes(identity).await? {
+            Some(entry) => entry,
+            None => return Ok(()),
+        };
+        match entry.without_attester(attester) {
+            Some(entry) => self.put_attributes(identity, entry).await,
+            None => self.delete(identity).await,
+        }
+    }
+
     /// Store an attribute name/value pair for a given identity
     async fn put_attribute_value(
         &self,
@@ -130,7 +169,10 @@ impl IdentityAttributesWriter for IdentitiesStorage {
                 identity.to_string().as_str(),
                 IdentityConstants::ATTRIBUTES_KEY,
             )
-            .await
+            .await?;
+
+        self.notify_attributes_changed(identity);
+        Ok(())
     }
 }
 
@@ -24,6 +24,19 @@ pub trait IdentitiesRepository:
 
     /// Restrict this repository as a writer for identities
     fn as_identities_writer(&self) -> Arc<dyn IdentitiesWriter>;
+
+    /// Register a listener notified every time the attributes of an identity are
+    /// stored or deleted
+    fn subscribe_to_attributes_changes(&self, listener: Arc<dyn AttributesChangesListener>);
+}
+
+/// Listener notified when the attributes of an identity change, for example to
+/// re-evaluate the policies of the sessions opened by that identity.
+///
+/// The notification is sent after the change is stored and must not block
+pub trait AttributesChangesListener: Send + Sync + 'static {
+    /// The attributes of `identity` were stored or deleted
+    fn attributes_changed(&self, identity: &Identifier);
 }
 
 /// Trait implementing read access to attributes
@@ -43,6 +56,17 @@ pub trait IdentityAttributesWriter: Send + Sync + 'static {
     /// Previous values gets overridden.
     async fn put_attributes(&self, identity: &Identifier, entry: AttributesEntry) -> Result<()>;
 
+    /// Merge the attributes associated with the given identity identifier with new attributes,
+    /// for example attested by another authority. Each attribute keeps its own provenance
+    async fn merge_attributes(&self, identity: &Identifier, entry: AttributesEntry) -> Result<()>;
+
+    /// Remove the attributes of the given identity identifier which were attested by `attester`
+    async fn delete_attributes_attested_by(
+        &self,
+        identity: &Identifier,
+        attester: &Identifier,
+    ) -> Result<()>;
+
     /// Store an attribute name/value pair for a given identity
     async fn put_attribute_value(
         &self,
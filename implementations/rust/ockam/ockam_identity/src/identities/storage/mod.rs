@@ -1,7 +1,17 @@
+mod attribute_validity;
 mod attributes_entry;
+mod child_identities_repository_impl;
+mod child_identities_repository_trait;
+mod groups_repository_impl;
+mod groups_repository_trait;
 mod identities_repository_impl;
 mod identities_repository_trait;
 
+pub use attribute_validity::*;
 pub use attributes_entry::*;
+pub use child_identities_repository_impl::*;
+pub use child_identities_repository_trait::*;
+pub use groups_repository_impl::*;
+pub use groups_repository_trait::*;
 pub use identities_repository_impl::*;
 pub use identities_repository_trait::*;
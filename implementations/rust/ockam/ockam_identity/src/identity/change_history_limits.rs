@@ -0,0 +1,114 @@
+use core::sync::atomic::{AtomicUsize, Ordering};
+use ockam_core::compat::sync::Arc;
+use ockam_core::Result;
+use tracing::warn;
+
+use crate::models::ChangeHistory;
+use crate::IdentityError;
+
+/// Default maximum number of changes accepted in the change history of an identity
+pub const DEFAULT_MAX_CHANGES: usize = 1024;
+
+/// Default maximum size, in bytes, of the change history of an identity
+pub const DEFAULT_MAX_CHANGE_HISTORY_SIZE: usize = 256 * 1024;
+
+/// Limits on the change histories accepted when importing and verifying an identity.
+///
+/// Each change of a history is verified with one or two signatures, so a peer sending
+/// a very long change history could make a node spend a lot of time verifying it.
+/// The histories exceeding the limits are rejected before being decoded or verified,
+/// and the rejections are counted
+#[derive(Debug, Clone)]
+pub struct ChangeHistoryLimits {
+    max_changes: usize,
+    max_size: usize,
+    rejected: Arc<AtomicUsize>,
+}
+
+impl Default for ChangeHistoryLimits {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_CHANGES, DEFAULT_MAX_CHANGE_HISTORY_SIZE)
+    }
+}
+
+impl ChangeHistoryLimits {
+    /// Create limits on the number of changes and on the size of a change history
+    pub fn new(max_changes: usize, max_size: usize) -> Self {
+        Self {
+            max_changes,
+            max_size,
+            rejected: Default::default(),
+        }
+    }
+
+    /// Maximum number of changes of a change history
+    pub fn max_changes(&self) -> usize {
+        self.max_changes
+    }
+
+    /// Maximum size, in bytes, of a change history
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    /// Number of change histories rejected because they exceeded the limits
+    pub fn rejected(&self) -> usize {
+        self.rejected.load(Ordering::Relaxed)
+    }
+
+    /// Check the size of an exported change history, before decoding it
+    pub fn check_size(&self, size: usize) -> Result<()> {
+        if size > self.max_size {
+            self.rejected.fetch_add(1, Ordering::Relaxed);
+            warn!(
+                size,
+                max_size = self.max_size,
+                "rejecting a change history exceeding the maximum size"
+            );
+            return Err(IdentityError::ChangeHistoryTooLarge.into());
+        }
+        Ok(())
+    }
+
+    /// Check the number of changes and the size of the data of a change history,
+    /// before verifying it
+    pub fn check(&self, change_history: &ChangeHistory) -> Result<()> {
+        let changes = change_history.0.len();
+        if changes > self.max_changes {
+            self.rejected.fetch_add(1, Ordering::Relaxed);
+            warn!(
+                changes,
+                max_changes = self.max_changes,
+                "rejecting a change history exceeding the maximum number of changes"
+            );
+            return Err(IdentityError::ChangeHistoryTooLong.into());
+        }
+        let size = change_history.0.iter().map(|c| c.data.len()).sum();
+        self.check_size(size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::identities;
+
+    #[tokio::test]
+    async fn test_change_history_limits() -> Result<()> {
+        let identities = identities();
+        let identity = identities.identities_creation().create_identity().await?;
+        let mut change_history = identity.change_history().clone();
+
+        let limits = ChangeHistoryLimits::default();
+        limits.check(&change_history)?;
+
+        change_history.0.push(change_history.0[0].clone());
+        let limits = ChangeHistoryLimits::new(1, DEFAULT_MAX_CHANGE_HISTORY_SIZE);
+        assert!(limits.check(&change_history).is_err());
+        assert!(limits
+            .check_size(DEFAULT_MAX_CHANGE_HISTORY_SIZE + 1)
+            .is_err());
+        assert_eq!(limits.rejected(), 2);
+        Ok(())
+    }
+}
@@ -10,4 +10,10 @@ impl IdentityConstants {
     pub const CREDENTIALS_PURPOSE_KEY: &'static str = "C_PK";
     /// Attributes key for AttributesStorage
     pub const ATTRIBUTES_KEY: &'static str = "ATTRIBUTES";
+    /// Key used to persist the revocation of a child Identity by its parent
+    pub const REVOKED_BY_PARENT_KEY: &'static str = "REVOKED_BY_PARENT";
+    /// Key used to persist a group of identities
+    pub const GROUP_KEY: &'static str = "GROUP";
+    /// Key used to persist the groups an Identity is a member of
+    pub const MEMBER_OF_KEY: &'static str = "MEMBER_OF";
 }
@@ -1,7 +1,7 @@
-use crate::models::{Change, ChangeHash, ChangeHistory, Identifier};
+use crate::models::{Change, ChangeHash, ChangeHistory, ChildIdentityAttestation, Identifier};
 use crate::verified_change::VerifiedChange;
-use crate::IdentityError;
 use crate::IdentityHistoryComparison;
+use crate::{ChangeHistoryLimits, IdentityError};
 
 use core::cmp::Ordering;
 use core::fmt;
@@ -76,12 +76,29 @@ impl Identity {
         self.change_history.export()
     }
 
-    /// Import and verify Identity from the ChangeHistory
+    /// Import and verify Identity from the ChangeHistory, with the default [`ChangeHistoryLimits`]
     pub async fn import_from_change_history(
         expected_identifier: Option<&Identifier>,
         change_history: ChangeHistory,
         vault: Arc<dyn VaultForVerifyingSignatures>,
     ) -> Result<Identity> {
+        Self::import_from_change_history_with_limits(
+            expected_identifier,
+            change_history,
+            vault,
+            &ChangeHistoryLimits::default(),
+        )
+        .await
+    }
+
+    /// Import and verify Identity from the ChangeHistory, if it doesn't exceed the given limits
+    pub async fn import_from_change_history_with_limits(
+        expected_identifier: Option<&Identifier>,
+        change_history: ChangeHistory,
+        vault: Arc<dyn VaultForVerifyingSignatures>,
+        limits: &ChangeHistoryLimits,
+    ) -> Result<Identity> {
+        limits.check(&change_history)?;
         let verified_changes =
             Self::check_entire_consistency(&change_history.0, vault.clone()).await?;
         Self::verify_all_existing_changes(&verified_changes, &change_history.0, vault).await?;
@@ -103,15 +120,38 @@ impl Identity {
         Ok(identity)
     }
 
-    /// Create an Identity from serialized data
+    /// Create an Identity from serialized data, with the default [`ChangeHistoryLimits`]
     pub async fn import(
         expected_identifier: Option<&Identifier>,
         data: &[u8],
         vault: Arc<dyn VaultForVerifyingSignatures>,
     ) -> Result<Identity> {
+        Self::import_with_limits(
+            expected_identifier,
+            data,
+            vault,
+            &ChangeHistoryLimits::default(),
+        )
+        .await
+    }
+
+    /// Create an Identity from serialized data, if it doesn't exceed the given limits
+    pub async fn import_with_limits(
+        expected_identifier: Option<&Identifier>,
+        data: &[u8],
+        vault: Arc<dyn VaultForVerifyingSignatures>,
+        limits: &ChangeHistoryLimits,
+    ) -> Result<Identity> {
+        limits.check_size(data.len())?;
         let change_history = ChangeHistory::import(data)?;
 
-        Self::import_from_change_history(expected_identifier, change_history, vault).await
+        Self::import_from_change_history_with_limits(
+            expected_identifier,
+            change_history,
+            vault,
+            limits,
+        )
+        .await
     }
 }
 
@@ -134,6 +174,14 @@ impl Identity {
         }
     }
 
+    /// Attestation linking this `Identity` to a parent `Identity`, recorded in its first change.
+    /// The attestation is not verified, see [`crate::ChildIdentities::verify_child_identity`]
+    pub fn parent_attestation(&self) -> Option<&ChildIdentityAttestation> {
+        self.changes
+            .first()
+            .and_then(|change| change.data().parent.as_ref())
+    }
+
     /// Add a new key change to the change history
     pub async fn add_change(
         self,
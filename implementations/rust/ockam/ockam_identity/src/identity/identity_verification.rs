@@ -93,6 +93,11 @@ impl Identity {
                     // Corrupted changes sequence
                     return Err(IdentityError::IdentityVerificationFailed.into());
                 }
+
+                if change_details.change_data.parent.is_some() {
+                    // Only the first change can link the identity to a parent
+                    return Err(IdentityError::IdentityVerificationFailed.into());
+                }
             } else if change_details.change_data.previous_change.is_some() {
                 // Should be empty
                 return Err(IdentityError::IdentityVerificationFailed.into());
@@ -1,9 +1,11 @@
+mod change_history_limits;
 mod constants;
 mod history_comparison;
 #[allow(clippy::module_inception)]
 mod identity;
 mod identity_verification;
 
+pub use change_history_limits::*;
 pub use constants::*;
 pub use history_comparison::*;
 pub use identity::*;
@@ -1,4 +1,4 @@
-use crate::models::{ChangeHash, TimestampInSeconds};
+use crate::models::{ChangeHash, ChildIdentityAttestation, TimestampInSeconds};
 use minicbor::{Decode, Encode};
 use ockam_core::compat::vec::Vec;
 use ockam_vault::{
@@ -56,6 +56,10 @@ pub struct ChangeData {
     #[n(4)] pub created_at: TimestampInSeconds,
     /// Expiration [`TimestampInSeconds`] (UTC)
     #[n(5)] pub expires_at: TimestampInSeconds,
+    /// Attestation linking this [`super::super::identity::Identity`] to a parent
+    /// [`super::super::identity::Identity`]. It can only be set in the very first
+    /// [`Change`] in the [`ChangeHistory`]
+    #[n(6)] pub parent: Option<ChildIdentityAttestation>,
 }
 
 /// [`Change`]'s public key
@@ -0,0 +1,48 @@
+use ockam_core::compat::vec::Vec;
+
+use crate::models::{ChangeHash, Identifier, PrimaryPublicKey, TimestampInSeconds};
+
+use minicbor::{Decode, Encode};
+use ockam_vault::{ECDSASHA256CurveP256Signature, EdDSACurve25519Signature};
+
+/// Attestation, signed by a parent [`super::super::identity::Identity`], linking a child
+/// [`super::super::identity::Identity`] to its parent. It is recorded in the first [`super::Change`]
+/// of the child
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct ChildIdentityAttestation {
+    /// CBOR serialized [`super::VersionedData`]
+    /// where VersionedData::data is CBOR serialized [`ChildIdentityAttestationData`]
+    #[cbor(with = "minicbor::bytes")]
+    #[n(1)] pub data: Vec<u8>,
+    /// Signature over data field using a key from the parent [`super::super::identity::Identity`]
+    #[n(2)] pub signature: ChildIdentityAttestationSignature,
+}
+
+/// Signature over data field using a key from the parent [`super::super::identity::Identity`]
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+#[rustfmt::skip]
+pub enum ChildIdentityAttestationSignature {
+    /// Signature using EdDSA Ed25519 key from the parent [`super::super::identity::Identity`]
+    #[n(1)] EdDSACurve25519(#[n(0)] EdDSACurve25519Signature),
+    /// Signature using ECDSA P256 key from the parent [`super::super::identity::Identity`]
+    #[n(2)] ECDSASHA256CurveP256(#[n(0)] ECDSASHA256CurveP256Signature),
+}
+
+/// Data inside a [`ChildIdentityAttestation`]
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+#[rustfmt::skip]
+#[cbor(map)]
+pub struct ChildIdentityAttestationData {
+    /// [`Identifier`] of the parent [`super::super::identity::Identity`]
+    #[n(1)] pub parent: Identifier,
+    /// [`ChangeHash`] of the [`super::Change`] of the parent whose key signed the attestation
+    #[n(2)] pub parent_change_hash: ChangeHash,
+    /// Public Key of the first [`super::Change`] of the child
+    #[n(3)] pub child_primary_public_key: PrimaryPublicKey,
+    /// Creation [`TimestampInSeconds`] (UTC)
+    #[n(4)] pub created_at: TimestampInSeconds,
+    /// Expiration [`TimestampInSeconds`] (UTC)
+    #[n(5)] pub expires_at: TimestampInSeconds,
+}
@@ -1,5 +1,6 @@
 use crate::models::{Credential, PurposeKeyAttestation};
 use minicbor::{Decode, Encode};
+use ockam_core::compat::boxed::Box;
 
 /// [`Credential`] and the corresponding [`PurposeKeyAttestation`] that was used to issue that
 /// [`Credential`] and will be used to verify it
@@ -12,4 +13,7 @@ pub struct CredentialAndPurposeKey {
     /// Corresponding [`PurposeKeyAttestation`] that was used to issue that
     /// [`Credential`] and will be used to verify it
     #[n(2)] pub purpose_key_attestation: PurposeKeyAttestation,
+    /// When the [`Credential`] was issued by a sub-authority, the credential issued to that
+    /// sub-authority which delegates the right to attest some attributes
+    #[n(3)] pub issuer_credential: Option<Box<CredentialAndPurposeKey>>,
 }
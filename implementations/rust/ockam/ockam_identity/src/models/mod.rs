@@ -1,4 +1,5 @@
 mod change_history;
+mod child_identity_attestation;
 mod credential;
 mod credential_and_purpose_key;
 mod identifiers;
@@ -8,6 +9,7 @@ mod utils;
 mod versioned_data;
 
 pub use change_history::*;
+pub use child_identity_attestation::*;
 pub use credential::*;
 pub use credential_and_purpose_key::*;
 pub use identifiers::*;
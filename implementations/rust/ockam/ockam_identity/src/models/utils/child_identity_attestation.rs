@@ -0,0 +1,44 @@
+use crate::models::utils::get_versioned_data;
+use crate::models::{
+    ChildIdentityAttestation, ChildIdentityAttestationData, ChildIdentityAttestationSignature,
+    VersionedData,
+};
+
+use ockam_core::Result;
+use ockam_vault::Signature;
+
+impl ChildIdentityAttestation {
+    /// Extract [`VersionedData`]
+    pub fn get_versioned_data(&self) -> Result<VersionedData> {
+        get_versioned_data(&self.data)
+    }
+}
+
+impl ChildIdentityAttestationData {
+    /// Extract [`ChildIdentityAttestationData`] from [`VersionedData`]
+    pub fn get_data(versioned_data: &VersionedData) -> Result<Self> {
+        Ok(minicbor::decode(&versioned_data.data)?)
+    }
+}
+
+impl From<ChildIdentityAttestationSignature> for Signature {
+    fn from(value: ChildIdentityAttestationSignature) -> Self {
+        match value {
+            ChildIdentityAttestationSignature::EdDSACurve25519(value) => {
+                Self::EdDSACurve25519(value)
+            }
+            ChildIdentityAttestationSignature::ECDSASHA256CurveP256(value) => {
+                Self::ECDSASHA256CurveP256(value)
+            }
+        }
+    }
+}
+
+impl From<Signature> for ChildIdentityAttestationSignature {
+    fn from(value: Signature) -> Self {
+        match value {
+            Signature::EdDSACurve25519(value) => Self::EdDSACurve25519(value),
+            Signature::ECDSASHA256CurveP256(value) => Self::ECDSASHA256CurveP256(value),
+        }
+    }
+}
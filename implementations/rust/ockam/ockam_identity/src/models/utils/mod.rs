@@ -6,6 +6,7 @@ fn get_versioned_data<'a, T: Decode<'a, ()>>(data: &'a [u8]) -> Result<T> {
 }
 
 mod change_history;
+mod child_identity_attestation;
 mod credentials;
 mod identifiers;
 mod purpose_key_attestation;
@@ -20,6 +20,10 @@ pub(crate) struct Addresses {
     pub(crate) encryptor: Address,
     // Used to decrypt messages that were received though some channel other than Ockam Routing from the other end of the channel
     pub(crate) encryptor_api: Address,
+    // Used to receive heartbeat timer events when heartbeats are enabled
+    pub(crate) encryptor_heartbeat: Address,
+    // Used to receive credentials refresh timer events when credentials are refreshed
+    pub(crate) encryptor_credentials_refresh: Address,
 }
 
 impl Addresses {
@@ -35,6 +39,12 @@ impl Addresses {
         let encryptor = Address::random_tagged(&format!("SecureChannel.{}.encryptor", role_str));
         let encryptor_api =
             Address::random_tagged(&format!("SecureChannel.{}.encryptor.api", role_str));
+        let encryptor_heartbeat =
+            Address::random_tagged(&format!("SecureChannel.{}.encryptor.heartbeat", role_str));
+        let encryptor_credentials_refresh = Address::random_tagged(&format!(
+            "SecureChannel.{}.encryptor.credentials_refresh",
+            role_str
+        ));
 
         Self {
             decryptor_internal,
@@ -42,6 +52,8 @@ impl Addresses {
             decryptor_api,
             encryptor,
             encryptor_api,
+            encryptor_heartbeat,
+            encryptor_credentials_refresh,
         }
     }
 }
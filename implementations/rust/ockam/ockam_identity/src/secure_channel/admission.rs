@@ -0,0 +1,287 @@
+use core::time::Duration;
+use ockam_core::compat::collections::{BTreeMap, BTreeSet};
+use ockam_core::compat::sync::{Arc, Mutex};
+use ockam_core::compat::vec::Vec;
+use ockam_core::Address;
+use ockam_node::Context;
+
+use crate::models::TimestampInSeconds;
+use crate::utils::now;
+
+/// Default duration after which a handshake still in progress can be stopped,
+/// to admit a new handshake when the maximum number of handshakes is reached
+pub const DEFAULT_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Limits on the secure channels created by a listener, to protect a node from
+/// a flood of handshakes.
+///
+/// The limits are shared with the listener and can be changed while it is running.
+/// Handshakes exceeding the maximum number of handshakes in progress are queued
+/// up to `max_queued_handshakes` and rejected beyond that, while handshakes exceeding
+/// the maximum number of channels are always rejected
+#[derive(Debug, Clone, Default)]
+pub struct SecureChannelListenerLimits {
+    state: Arc<Mutex<AdmissionState>>,
+}
+
+#[derive(Debug)]
+struct AdmissionState {
+    max_handshakes: Option<usize>,
+    max_channels: Option<usize>,
+    max_queued_handshakes: usize,
+    handshake_timeout: Duration,
+    /// Handshakes in progress with the time when they started, by address of their worker
+    handshakes: BTreeMap<Address, Option<TimestampInSeconds>>,
+    /// Channels established by the listener, by address of their worker
+    channels: BTreeSet<Address>,
+    rejected: u64,
+}
+
+impl Default for AdmissionState {
+    fn default() -> Self {
+        Self {
+            max_handshakes: None,
+            max_channels: None,
+            max_queued_handshakes: 0,
+            handshake_timeout: DEFAULT_HANDSHAKE_TIMEOUT,
+            handshakes: Default::default(),
+            channels: Default::default(),
+            rejected: 0,
+        }
+    }
+}
+
+impl SecureChannelListenerLimits {
+    /// Create limits which do not restrict the listener
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit the number of handshakes in progress
+    pub fn with_max_handshakes(self, max_handshakes: usize) -> Self {
+        self.set_max_handshakes(Some(max_handshakes));
+        self
+    }
+
+    /// Limit the number of channels established by the listener
+    pub fn with_max_channels(self, max_channels: usize) -> Self {
+        self.set_max_channels(Some(max_channels));
+        self
+    }
+
+    /// Queue up to `max_queued_handshakes` handshakes when the maximum number
+    /// of handshakes in progress is reached, instead of rejecting them
+    pub fn with_max_queued_handshakes(self, max_queued_handshakes: usize) -> Self {
+        self.set_max_queued_handshakes(max_queued_handshakes);
+        self
+    }
+
+    /// Stop the handshakes in progress for longer than `timeout` when a new
+    /// handshake cannot be admitted
+    pub fn with_handshake_timeout(self, timeout: Duration) -> Self {
+        self.set_handshake_timeout(timeout);
+        self
+    }
+
+    /// Change the maximum number of handshakes in progress. `None` removes the limit
+    pub fn set_max_handshakes(&self, max_handshakes: Option<usize>) {
+        self.state.lock().unwrap().max_handshakes = max_handshakes;
+    }
+
+    /// Change the maximum number of channels. `None` removes the limit.
+    /// The channels already established are not closed
+    pub fn set_max_channels(&self, max_channels: Option<usize>) {
+        self.state.lock().unwrap().max_channels = max_channels;
+    }
+
+    /// Change the maximum number of queued handshakes, 0 to reject the handshakes
+    /// exceeding the maximum number of handshakes in progress
+    pub fn set_max_queued_handshakes(&self, max_queued_handshakes: usize) {
+        self.state.lock().unwrap().max_queued_handshakes = max_queued_handshakes;
+    }
+
+    /// Change the duration after which a handshake in progress can be stopped
+    pub fn set_handshake_timeout(&self, timeout: Duration) {
+        self.state.lock().unwrap().handshake_timeout = timeout;
+    }
+
+    /// Maximum number of handshakes in progress
+    pub fn max_handshakes(&self) -> Option<usize> {
+        self.state.lock().unwrap().max_handshakes
+    }
+
+    /// Maximum number of channels
+    pub fn max_channels(&self) -> Option<usize> {
+        self.state.lock().unwrap().max_channels
+    }
+
+    /// Maximum number of queued handshakes
+    pub fn max_queued_handshakes(&self) -> usize {
+        self.state.lock().unwrap().max_queued_handshakes
+    }
+
+    /// Number of handshakes in progress
+    pub fn handshakes(&self) -> usize {
+        self.state.lock().unwrap().handshakes.len()
+    }
+
+    /// Number of channels established by the listener
+    pub fn channels(&self) -> usize {
+        self.state.lock().unwrap().channels.len()
+    }
+
+    /// Number of handshakes rejected because a limit was reached
+    pub fn rejected(&self) -> u64 {
+        self.state.lock().unwrap().rejected
+    }
+
+    /// Admit a new handshake performed by the worker at `address`, which notifies
+    /// the listener at `listener_address` when it completes or stops.
+    ///
+    /// When the maximum number of handshakes is reached, the handshakes which
+    /// timed out are evicted and the addresses of their workers returned, to be stopped
+    pub(crate) fn admit(
+        &self,
+        address: &Address,
+        listener_address: &Address,
+    ) -> (Admission, Vec<Address>) {
+        let mut state = self.state.lock().unwrap();
+        if state
+            .max_channels
+            .map_or(false, |max| state.channels.len() >= max)
+        {
+            return (Admission::ChannelsLimit, vec![]);
+        }
+
+        let mut evicted = vec![];
+        if state
+            .max_handshakes
+            .map_or(false, |max| state.handshakes.len() >= max)
+        {
+            if let Ok(now) = now() {
+                let timeout = state.handshake_timeout.as_secs();
+                state.handshakes.retain(|address, started_at| {
+                    let expired = started_at.map_or(false, |t| *t + timeout <= *now);
+                    if expired {
+                        evicted.push(address.clone());
+                    }
+                    !expired
+                });
+            }
+            if state
+                .max_handshakes
+                .map_or(false, |max| state.handshakes.len() >= max)
+            {
+                return (Admission::HandshakesLimit, evicted);
+            }
+        }
+
+        state.handshakes.insert(address.clone(), now().ok());
+        let ticket = AdmissionTicket {
+            limits: self.clone(),
+            address: address.clone(),
+            listener_address: listener_address.clone(),
+        };
+        (Admission::Admitted(ticket), evicted)
+    }
+
+    /// Return true if a handshake exceeding the maximum number of handshakes
+    /// can be queued behind `queued` handshakes
+    pub(crate) fn can_queue(&self, queued: usize) -> bool {
+        queued < self.state.lock().unwrap().max_queued_handshakes
+    }
+
+    pub(crate) fn record_rejection(&self) {
+        self.state.lock().unwrap().rejected += 1;
+    }
+}
+
+/// Result of the admission of a new handshake
+pub(crate) enum Admission {
+    Admitted(AdmissionTicket),
+    HandshakesLimit,
+    ChannelsLimit,
+}
+
+/// A handshake admitted by a listener. It is counted as a handshake in progress,
+/// then as an established channel, until the ticket is released or dropped
+pub(crate) struct AdmissionTicket {
+    limits: SecureChannelListenerLimits,
+    address: Address,
+    listener_address: Address,
+}
+
+impl AdmissionTicket {
+    /// Count the handshake as an established channel and let the listener
+    /// admit a queued handshake
+    pub(crate) async fn handshake_completed(&self, ctx: &Context) {
+        {
+            let mut state = self.limits.state.lock().unwrap();
+            state.handshakes.remove(&self.address);
+            state.channels.insert(self.address.clone());
+        }
+        self.notify_listener(ctx).await
+    }
+
+    /// Release the ticket and let the listener admit a queued handshake
+    pub(crate) async fn release(self, ctx: &Context) {
+        let listener_address = self.listener_address.clone();
+        drop(self);
+        // the listener might already be stopped
+        let _ = ctx.send(listener_address, ()).await;
+    }
+
+    async fn notify_listener(&self, ctx: &Context) {
+        // the listener might already be stopped
+        let _ = ctx.send(self.listener_address.clone(), ()).await;
+    }
+}
+
+impl Drop for AdmissionTicket {
+    fn drop(&mut self) {
+        let mut state = self.limits.state.lock().unwrap();
+        state.handshakes.remove(&self.address);
+        state.channels.remove(&self.address);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_admission_limits() {
+        let limits = SecureChannelListenerLimits::new()
+            .with_max_handshakes(1)
+            .with_max_channels(2);
+        let listener = Address::random_local();
+
+        let (first, _) = limits.admit(&Address::random_local(), &listener);
+        let first = match first {
+            Admission::Admitted(ticket) => ticket,
+            _ => panic!("the first handshake should be admitted"),
+        };
+        let (second, evicted) = limits.admit(&Address::random_local(), &listener);
+        assert!(matches!(second, Admission::HandshakesLimit));
+        assert!(evicted.is_empty());
+
+        // a completed handshake counts as a channel and frees a handshake slot
+        {
+            let mut state = limits.state.lock().unwrap();
+            state.handshakes.remove(&first.address);
+            state.channels.insert(first.address.clone());
+        }
+        let (second, _) = limits.admit(&Address::random_local(), &listener);
+        assert!(matches!(second, Admission::Admitted(_)));
+        assert_eq!(limits.handshakes(), 1);
+        assert_eq!(limits.channels(), 1);
+
+        // the limits can be changed at runtime
+        limits.set_max_channels(Some(1));
+        let (third, _) = limits.admit(&Address::random_local(), &listener);
+        assert!(matches!(third, Admission::ChannelsLimit));
+
+        drop(first);
+        assert_eq!(limits.channels(), 0);
+    }
+}
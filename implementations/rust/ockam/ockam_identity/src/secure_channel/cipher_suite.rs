@@ -0,0 +1,184 @@
+use cfg_if::cfg_if;
+use core::fmt;
+use minicbor::{Decode, Encode};
+use ockam_core::compat::vec::Vec;
+
+/// Cipher suite used by a Secure Channel: Diffie-Hellman function, AEAD cipher and hash function
+///
+/// The vault implements a single cipher suite, selected at compile time with the
+/// `OCKAM_XX_*` features. Suites which are not compiled in can be configured but are
+/// never selected during the negotiation.
+///
+/// The initiator proposes its suites in the handshake message 1, the responder returns the
+/// suite it selected with its identity, and the initiator checks that this suite is one of
+/// the suites it proposed. Since the Noise protocol name of the selected suite is mixed in the
+/// handshake hash, both sides also fail the handshake if they use different suites
+#[derive(Debug, Clone, Copy, Encode, Decode, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[rustfmt::skip]
+#[cbor(index_only)]
+pub enum SecureChannelCipherSuite {
+    /// X25519, AES-256-GCM and SHA-256
+    #[n(1)] X25519Aes256GcmSha256,
+    /// X25519, AES-128-GCM and SHA-256
+    #[n(2)] X25519Aes128GcmSha256,
+    /// X25519, ChaCha20-Poly1305 and BLAKE2s
+    #[n(3)] X25519ChaChaPolyBlake2s,
+}
+
+impl SecureChannelCipherSuite {
+    /// All the cipher suites which can be configured
+    pub const ALL: [SecureChannelCipherSuite; 3] = [
+        SecureChannelCipherSuite::X25519Aes256GcmSha256,
+        SecureChannelCipherSuite::X25519Aes128GcmSha256,
+        SecureChannelCipherSuite::X25519ChaChaPolyBlake2s,
+    ];
+
+    /// Cipher suite implemented by the vault
+    pub fn compiled() -> Self {
+        cfg_if! {
+            if #[cfg(feature = "OCKAM_XX_25519_AES128_GCM_SHA256")] {
+                SecureChannelCipherSuite::X25519Aes128GcmSha256
+            } else if #[cfg(feature = "OCKAM_XX_25519_ChaChaPolyBLAKE2s")] {
+                SecureChannelCipherSuite::X25519ChaChaPolyBlake2s
+            } else {
+                SecureChannelCipherSuite::X25519Aes256GcmSha256
+            }
+        }
+    }
+
+    /// Return true if this cipher suite can be used at runtime
+    pub fn is_available(&self) -> bool {
+        *self == Self::compiled()
+    }
+
+    /// Name of the Noise protocol using this cipher suite, padded to 32 bytes
+    pub fn protocol_name(&self) -> &'static [u8; 32] {
+        match self {
+            SecureChannelCipherSuite::X25519Aes256GcmSha256 => {
+                b"Noise_XX_25519_AESGCM_SHA256\0\0\0\0"
+            }
+            SecureChannelCipherSuite::X25519Aes128GcmSha256 => b"OCKAM_XX_25519_AES128_GCM_SHA256",
+            SecureChannelCipherSuite::X25519ChaChaPolyBlake2s => {
+                b"OCKAM_XX_25519_ChaChaPolyBLAKE2s"
+            }
+        }
+    }
+
+    /// Check the cipher suite selected by the responder, which must be one of the suites
+    /// proposed by the initiator and available at runtime.
+    /// A responder which doesn't support the negotiation doesn't return any suite,
+    /// in that case it uses the default suite
+    pub(crate) fn check_selected(
+        proposed: &[SecureChannelCipherSuite],
+        selected: Option<SecureChannelCipherSuite>,
+    ) -> Option<SecureChannelCipherSuite> {
+        let selected = selected.unwrap_or_default();
+        (selected.is_available() && proposed.contains(&selected)).then_some(selected)
+    }
+
+    /// Select the first cipher suite proposed by the initiator which is accepted
+    /// by the responder and available at runtime.
+    /// An empty list of accepted suites means that any available suite is accepted
+    pub(crate) fn negotiate(
+        proposed: &[SecureChannelCipherSuite],
+        accepted: &[SecureChannelCipherSuite],
+    ) -> Option<SecureChannelCipherSuite> {
+        proposed
+            .iter()
+            .find(|s| s.is_available() && (accepted.is_empty() || accepted.contains(s)))
+            .copied()
+    }
+
+    /// Keep only the cipher suites which can be used at runtime
+    pub(crate) fn available(suites: &[SecureChannelCipherSuite]) -> Vec<SecureChannelCipherSuite> {
+        suites
+            .iter()
+            .filter(|s| s.is_available())
+            .copied()
+            .collect()
+    }
+}
+
+impl Default for SecureChannelCipherSuite {
+    fn default() -> Self {
+        Self::compiled()
+    }
+}
+
+impl fmt::Display for SecureChannelCipherSuite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecureChannelCipherSuite::X25519Aes256GcmSha256 => {
+                write!(f, "X25519_AES256_GCM_SHA256")
+            }
+            SecureChannelCipherSuite::X25519Aes128GcmSha256 => {
+                write!(f, "X25519_AES128_GCM_SHA256")
+            }
+            SecureChannelCipherSuite::X25519ChaChaPolyBlake2s => {
+                write!(f, "X25519_ChaChaPoly_BLAKE2s")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SecureChannelCipherSuite::*;
+    use super::*;
+
+    #[test]
+    fn test_negotiate_cipher_suite() {
+        let compiled = SecureChannelCipherSuite::compiled();
+        let other = SecureChannelCipherSuite::ALL
+            .into_iter()
+            .find(|s| *s != compiled)
+            .unwrap();
+
+        // the first proposed suite which is available and accepted is selected
+        assert_eq!(
+            SecureChannelCipherSuite::negotiate(&[other, compiled], &[]),
+            Some(compiled)
+        );
+        assert_eq!(
+            SecureChannelCipherSuite::negotiate(&[compiled], &[other, compiled]),
+            Some(compiled)
+        );
+        // no common suite
+        assert_eq!(
+            SecureChannelCipherSuite::negotiate(&[compiled], &[other]),
+            None
+        );
+        // suites which are not compiled in are never selected
+        assert_eq!(
+            SecureChannelCipherSuite::negotiate(&[other], &[other]),
+            None
+        );
+        assert_eq!(
+            SecureChannelCipherSuite::available(&[
+                X25519Aes256GcmSha256,
+                X25519ChaChaPolyBlake2s,
+                X25519Aes128GcmSha256
+            ]),
+            vec![compiled]
+        );
+
+        // the initiator only accepts a suite it proposed
+        assert_eq!(
+            SecureChannelCipherSuite::check_selected(&[other, compiled], Some(compiled)),
+            Some(compiled)
+        );
+        assert_eq!(
+            SecureChannelCipherSuite::check_selected(&[compiled], Some(other)),
+            None
+        );
+        // a responder without negotiation uses the default suite
+        assert_eq!(
+            SecureChannelCipherSuite::check_selected(&[compiled], None),
+            Some(compiled)
+        );
+        assert_eq!(
+            SecureChannelCipherSuite::check_selected(&[other], None),
+            None
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use core::fmt;
+use minicbor::{Decode, Encode};
+use ockam_core::compat::sync::{Arc, RwLock};
+use ockam_core::compat::vec::Vec;
+use ockam_core::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::IdentityError;
+
+/// Messages smaller than this are never compressed
+pub(crate) const MIN_COMPRESSED_SIZE: usize = 256;
+
+/// Maximum size of a decompressed message, so that a small message can't expand without limit
+const MAX_DECOMPRESSED_SIZE: usize = 16 * 1024 * 1024;
+
+/// Compression level of deflate, favouring speed over size
+const DEFLATE_LEVEL: u8 = 3;
+
+/// Compression applied to the messages of a Secure Channel before they are encrypted.
+///
+/// Compression is only used when the initiator proposes it and the listener accepts it.
+/// Since the size of a compressed message depends on its content, it should not be enabled
+/// on channels carrying both secrets and data controlled by an attacker
+#[derive(
+    Debug, Clone, Copy, Encode, Decode, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord,
+)]
+#[rustfmt::skip]
+#[cbor(index_only)]
+pub enum SecureChannelCompression {
+    /// Deflate (RFC 1951)
+    #[n(1)] Deflate,
+}
+
+impl fmt::Display for SecureChannelCompression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecureChannelCompression::Deflate => write!(f, "deflate"),
+        }
+    }
+}
+
+/// Counters of the compression of the messages sent on a Secure Channel
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CompressionStats {
+    /// Compression negotiated with the other side, if any
+    pub compression: Option<SecureChannelCompression>,
+    /// Number of messages sent compressed
+    pub compressed_messages: u64,
+    /// Number of messages sent uncompressed, because they were too small or not compressible
+    pub uncompressed_messages: u64,
+    /// Size of the compressed messages before compression
+    pub bytes_in: u64,
+    /// Size of the compressed messages after compression
+    pub bytes_out: u64,
+}
+
+impl CompressionStats {
+    /// Size of the compressed messages after compression relative to their original size,
+    /// if some messages were compressed
+    pub fn ratio(&self) -> Option<f64> {
+        if self.bytes_in == 0 {
+            None
+        } else {
+            Some(self.bytes_out as f64 / self.bytes_in as f64)
+        }
+    }
+}
+
+/// Compression state of a Secure Channel, shared by its encryptor, its decryptor and
+/// the registry
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ChannelCompression {
+    /// Compressions proposed by the initiator, or accepted by the responder
+    supported: Vec<SecureChannelCompression>,
+    stats: Arc<RwLock<CompressionStats>>,
+}
+
+impl ChannelCompression {
+    pub(crate) fn new(supported: Vec<SecureChannelCompression>) -> Self {
+        Self {
+            supported,
+            stats: Default::default(),
+        }
+    }
+
+    pub(crate) fn supported(&self) -> &[SecureChannelCompression] {
+        &self.supported
+    }
+
+    pub(crate) fn stats(&self) -> CompressionStats {
+        *self.stats.read().unwrap()
+    }
+
+    /// Select the first compression proposed by the other side which is supported
+    /// on this side, and start compressing the messages with it
+    pub(crate) fn select(
+        &self,
+        proposed: &[SecureChannelCompression],
+    ) -> Option<SecureChannelCompression> {
+        let selected = proposed
+            .iter()
+            .find(|c| self.supported.contains(c))
+            .copied();
+        if selected.is_some() {
+            self.stats.write().unwrap().compression = selected;
+        }
+        selected
+    }
+
+    /// Start compressing the messages with the compression selected by the other side,
+    /// if it was proposed by this side
+    pub(crate) fn accept(&self, selected: SecureChannelCompression) -> bool {
+        let accepted = self.supported.contains(&selected);
+        if accepted {
+            self.stats.write().unwrap().compression = Some(selected);
+        }
+        accepted
+    }
+
+    /// Return true if a compression was negotiated with the other side
+    pub(crate) fn is_negotiated(&self) -> bool {
+        self.stats.read().unwrap().compression.is_some()
+    }
+
+    /// Compress a message if a compression was negotiated and if it makes the message smaller
+    pub(crate) fn compress(&self, message: &[u8]) -> Option<Vec<u8>> {
+        let compression = self.stats.read().unwrap().compression?;
+        // the lock is not held while compressing, only to update the counters
+        let compressed = if message.len() >= MIN_COMPRESSED_SIZE {
+            let compressed = match compression {
+                SecureChannelCompression::Deflate => {
+                    miniz_oxide::deflate::compress_to_vec(message, DEFLATE_LEVEL)
+                }
+            };
+            Some(compressed).filter(|compressed| compressed.len() < message.len())
+        } else {
+            None
+        };
+
+        let mut stats = self.stats.write().unwrap();
+        match &compressed {
+            Some(compressed) => {
+                stats.compressed_messages += 1;
+                stats.bytes_in += message.len() as u64;
+                stats.bytes_out += compressed.len() as u64;
+            }
+            None => stats.uncompressed_messages += 1,
+        }
+        compressed
+    }
+
+    /// Decompress a message compressed by the other side.
+    /// Compressed messages are rejected unless a compression was negotiated
+    pub(crate) fn decompress(&self, message: &[u8]) -> Result<Vec<u8>> {
+        if !self.is_negotiated() {
+            return Err(IdentityError::InvalidCompressedMessage.into());
+        }
+        miniz_oxide::inflate::decompress_to_vec_with_limit(message, MAX_DECOMPRESSED_SIZE)
+            .map_err(|_| IdentityError::InvalidCompressedMessage.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam_core::compat::vec;
+
+    #[test]
+    fn test_compress_after_negotiation() -> Result<()> {
+        let initiator = ChannelCompression::new(vec![SecureChannelCompression::Deflate]);
+        let responder = ChannelCompression::new(vec![SecureChannelCompression::Deflate]);
+        let message = b"a very compressible message ".repeat(20);
+
+        // nothing is compressed before the negotiation
+        assert_eq!(initiator.compress(&message), None);
+
+        let selected = responder.select(initiator.supported());
+        assert_eq!(selected, Some(SecureChannelCompression::Deflate));
+        assert!(initiator.accept(SecureChannelCompression::Deflate));
+
+        let compressed = initiator.compress(&message).unwrap();
+        assert!(compressed.len() < message.len());
+        assert_eq!(responder.decompress(&compressed)?, message);
+
+        // small messages are sent as they are
+        assert_eq!(initiator.compress(b"small"), None);
+
+        let stats = initiator.stats();
+        assert_eq!(stats.compressed_messages, 1);
+        assert_eq!(stats.uncompressed_messages, 2);
+        assert_eq!(stats.bytes_in, message.len() as u64);
+        assert!(stats.ratio().unwrap() < 0.5);
+        Ok(())
+    }
+
+    #[test]
+    fn test_no_compression_without_agreement() {
+        let responder = ChannelCompression::new(vec![]);
+        assert_eq!(responder.select(&[SecureChannelCompression::Deflate]), None);
+        assert_eq!(responder.compress(&[0u8; 1024]), None);
+
+        // compressed messages are rejected before a compression is negotiated
+        let initiator = ChannelCompression::new(vec![SecureChannelCompression::Deflate]);
+        let compressed = miniz_oxide::deflate::compress_to_vec(&[0u8; 1024], DEFLATE_LEVEL);
+        assert!(initiator.decompress(&compressed).is_err());
+        assert!(initiator.accept(SecureChannelCompression::Deflate));
+        assert!(initiator.decompress(&compressed).is_ok());
+        assert!(initiator.decompress(b"not deflate").is_err());
+    }
+}
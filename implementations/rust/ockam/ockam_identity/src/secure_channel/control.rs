@@ -0,0 +1,26 @@
+use ockam_core::compat::vec::Vec;
+use ockam_core::Message;
+use serde::{Deserialize, Serialize};
+
+use crate::secure_channel::{CredentialsRequest, SecureChannelCompression};
+
+/// Control messages are sent as encrypted transport messages with an empty onward route.
+/// They are handled by the decryptor instead of being forwarded.
+///
+/// A `Ping` is always answered with a `Pong`, even if heartbeats are not enabled on that side.
+/// New variants must be added at the end so that heartbeats can still be decoded by older nodes
+#[derive(Serialize, Deserialize, Message, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ControlMessage {
+    Ping,
+    Pong,
+    /// The other side requires a credential for one of its trust contexts
+    CredentialsRequired(CredentialsRequest),
+    /// CBOR-encoded list of credentials, presented after a `CredentialsRequired` message
+    Credentials(Vec<u8>),
+    /// Compressions proposed by the initiator, by order of preference
+    CompressionProposed(Vec<SecureChannelCompression>),
+    /// Compression selected by the responder, which starts compressing its messages
+    CompressionSelected(SecureChannelCompression),
+    /// A compressed transport message
+    Compressed(Vec<u8>),
+}
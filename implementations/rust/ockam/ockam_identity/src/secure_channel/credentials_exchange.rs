@@ -0,0 +1,160 @@
+use ockam_core::compat::collections::BTreeMap;
+use ockam_core::compat::string::String;
+use ockam_core::compat::sync::Arc;
+use ockam_core::compat::vec::Vec;
+use ockam_core::Result;
+use ockam_node::Context;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::models::{CredentialAndPurposeKey, Identifier};
+use crate::secure_channel::{CredentialsRefreshMetrics, SecureChannelCredentialsRefresh};
+use crate::{CredentialsRetriever, Identities, TrustContext};
+
+/// Configuration of the credentials exchanged once a secure channel is created
+#[derive(Clone, Default)]
+pub(crate) struct CredentialsExchange {
+    /// If true, the channel is created even if the other side did not present a valid
+    /// credential for the trust context, and a credential is requested once the channel is ready
+    pub(crate) on_demand: bool,
+    /// Retrievers used to present a credential when the other side requires one,
+    /// indexed by trust context id
+    pub(crate) retrievers: BTreeMap<String, Arc<dyn CredentialsRetriever>>,
+    /// If set, fresh credentials are regularly requested once the channel is ready.
+    /// The secure channels default is used if it is not set
+    pub(crate) refresh: Option<SecureChannelCredentialsRefresh>,
+}
+
+/// Request for a credential issued by one of the authorities of a trust context
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct CredentialsRequest {
+    pub(crate) trust_context_id: String,
+    pub(crate) authorities: Vec<Identifier>,
+}
+
+/// Credentials requested and presented by one side of a secure channel after the handshake
+pub(crate) struct ChannelCredentials {
+    identities: Arc<Identities>,
+    identifier: Identifier,
+    their_identifier: Identifier,
+    trust_context: Option<TrustContext>,
+    exchange: CredentialsExchange,
+    refresh_metrics: CredentialsRefreshMetrics,
+}
+
+impl ChannelCredentials {
+    pub(crate) fn new(
+        identities: Arc<Identities>,
+        identifier: Identifier,
+        their_identifier: Identifier,
+        trust_context: Option<TrustContext>,
+        exchange: CredentialsExchange,
+        refresh_metrics: CredentialsRefreshMetrics,
+    ) -> Self {
+        Self {
+            identities,
+            identifier,
+            their_identifier,
+            trust_context,
+            exchange,
+            refresh_metrics,
+        }
+    }
+
+    /// Return the request to send to the other side, if credentials are requested on demand
+    pub(crate) async fn request(&self) -> Result<Option<CredentialsRequest>> {
+        match &self.trust_context {
+            Some(trust_context) if self.exchange.on_demand => Ok(Some(CredentialsRequest {
+                trust_context_id: trust_context.id().into(),
+                authorities: trust_context.authorities().await?,
+            })),
+            _ => Ok(None),
+        }
+    }
+
+    /// Return the request to regularly send to the other side, if credentials are refreshed
+    pub(crate) async fn refresh_request(&self) -> Result<Option<CredentialsRequest>> {
+        match &self.trust_context {
+            Some(trust_context) if self.exchange.refresh.is_some() => {
+                Ok(Some(CredentialsRequest {
+                    trust_context_id: trust_context.id().into(),
+                    authorities: trust_context.authorities().await?,
+                }))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Retrieve the credentials requested by the other side.
+    /// The credentials are retrieved with the retriever registered for the requested trust context
+    /// or, if there is none, with the authority of our own trust context if it is the same
+    pub(crate) async fn present(
+        &self,
+        ctx: &Context,
+        request: &CredentialsRequest,
+    ) -> Result<Vec<CredentialAndPurposeKey>> {
+        if let Some(retriever) = self.exchange.retrievers.get(&request.trust_context_id) {
+            return Ok(vec![retriever.retrieve(ctx, &self.identifier).await?]);
+        }
+        match &self.trust_context {
+            Some(trust_context) if trust_context.id() == request.trust_context_id => {
+                Ok(trust_context
+                    .get_credential(ctx, &self.identifier)
+                    .await
+                    .into_iter()
+                    .collect())
+            }
+            _ => {
+                warn!(
+                    "no credential can be presented for the trust context {}",
+                    request.trust_context_id
+                );
+                Ok(vec![])
+            }
+        }
+    }
+
+    /// Verify the credentials presented by the other side and store their attributes.
+    /// Credentials are only accepted if they were requested on demand or refreshed
+    pub(crate) async fn receive(&self, credentials: Vec<CredentialAndPurposeKey>) -> Result<()> {
+        let is_refreshed = self.exchange.refresh.is_some();
+        let trust_context = match &self.trust_context {
+            Some(trust_context) if self.exchange.on_demand || is_refreshed => trust_context,
+            _ => {
+                warn!(
+                    "ignoring the credentials presented by {} which were not requested",
+                    self.their_identifier
+                );
+                return Ok(());
+            }
+        };
+        let authorities = trust_context.authorities().await?;
+        for credential in &credentials {
+            if let Err(err) = self
+                .identities
+                .credentials()
+                .credentials_verification()
+                .receive_presented_credential(&self.their_identifier, &authorities, credential)
+                .await
+            {
+                warn!(
+                    "a credential presented by {} could not be validated {}",
+                    self.their_identifier, err
+                );
+                if is_refreshed {
+                    self.refresh_metrics.record_failed();
+                }
+            } else {
+                if is_refreshed {
+                    self.refresh_metrics.record_succeeded();
+                }
+                debug!(
+                    "received a credential for {} in the trust context {}",
+                    self.their_identifier,
+                    trust_context.id()
+                );
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,134 @@
+use core::sync::atomic::{AtomicU64, Ordering};
+use core::time::Duration;
+use ockam_core::compat::rand::{thread_rng, Rng};
+use ockam_core::compat::sync::Arc;
+use ockam_core::Result;
+use ockam_node::DelayedEvent;
+
+use crate::secure_channel::CredentialsRequest;
+
+/// Credentials refresh configuration for a Secure Channel
+///
+/// When a refresh is configured on the side verifying the credentials of the other side,
+/// fresh credentials are requested every `interval`, plus or minus a random `jitter`, so that
+/// long-lived channels don't keep relying on credentials presented when they were created,
+/// and so that many channels created at the same time don't contact the authority all at once
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SecureChannelCredentialsRefresh {
+    interval: Duration,
+    jitter: Duration,
+}
+
+impl SecureChannelCredentialsRefresh {
+    /// Create a refresh configuration. The jitter can't be larger than the interval
+    pub fn new(interval: Duration, jitter: Duration) -> Self {
+        Self {
+            interval,
+            jitter: jitter.min(interval),
+        }
+    }
+
+    /// Create a refresh configuration with a jitter of a tenth of the interval
+    pub fn with_default_jitter(interval: Duration) -> Self {
+        Self::new(interval, interval / 10)
+    }
+
+    /// Average time between two refreshes
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Maximum random variation of the time between two refreshes
+    pub fn jitter(&self) -> Duration {
+        self.jitter
+    }
+
+    /// Return the time to wait before the next refresh
+    pub(crate) fn next_delay(&self) -> Duration {
+        let jitter = self.jitter.as_millis() as u64;
+        if jitter == 0 {
+            return self.interval;
+        }
+        let offset = Duration::from_millis(thread_rng().gen_range(0..=2 * jitter));
+        (self.interval + offset)
+            .saturating_sub(self.jitter)
+            .max(Duration::from_secs(1))
+    }
+}
+
+/// Counters of the credentials refreshes of all the Secure Channels
+#[derive(Clone, Debug, Default)]
+pub struct CredentialsRefreshMetrics {
+    requested: Arc<AtomicU64>,
+    succeeded: Arc<AtomicU64>,
+    failed: Arc<AtomicU64>,
+}
+
+impl CredentialsRefreshMetrics {
+    /// Number of refresh requests sent to the other side of a channel
+    pub fn requested(&self) -> u64 {
+        self.requested.load(Ordering::Relaxed)
+    }
+
+    /// Number of valid credentials received on a channel with a refresh
+    pub fn succeeded(&self) -> u64 {
+        self.succeeded.load(Ordering::Relaxed)
+    }
+
+    /// Number of invalid credentials received on a channel with a refresh
+    pub fn failed(&self) -> u64 {
+        self.failed.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn record_requested(&self) {
+        self.requested.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_succeeded(&self) {
+        self.succeeded.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_failed(&self) {
+        self.failed.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Timer of the credentials refreshes of a channel, driven by its encryptor
+pub(crate) struct CredentialsRefreshTimer {
+    pub(crate) refresh: SecureChannelCredentialsRefresh,
+    pub(crate) event: DelayedEvent<()>,
+    pub(crate) request: CredentialsRequest,
+    pub(crate) metrics: CredentialsRefreshMetrics,
+}
+
+impl CredentialsRefreshTimer {
+    /// Schedule the next refresh
+    pub(crate) async fn schedule(&mut self) -> Result<()> {
+        self.event.schedule(self.refresh.next_delay()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_delay() {
+        let refresh =
+            SecureChannelCredentialsRefresh::new(Duration::from_secs(60), Duration::from_secs(6));
+        for _ in 0..100 {
+            let delay = refresh.next_delay();
+            assert!(delay >= Duration::from_secs(54));
+            assert!(delay <= Duration::from_secs(66));
+        }
+
+        let refresh = SecureChannelCredentialsRefresh::new(Duration::from_secs(60), Duration::ZERO);
+        assert_eq!(refresh.next_delay(), Duration::from_secs(60));
+
+        // the jitter is capped by the interval
+        let refresh =
+            SecureChannelCredentialsRefresh::new(Duration::from_secs(2), Duration::from_secs(10));
+        assert_eq!(refresh.jitter(), Duration::from_secs(2));
+        assert!(refresh.next_delay() >= Duration::from_secs(1));
+    }
+}
@@ -1,14 +1,17 @@
 use ockam_core::compat::sync::Arc;
 use ockam_core::compat::vec::Vec;
-use ockam_core::{Any, Result, Routed, TransportMessage};
+use ockam_core::{route, Any, Result, Routed, TransportMessage};
 use ockam_core::{Decodable, LocalMessage};
 use ockam_node::Context;
 
 use crate::models::Identifier;
-use crate::secure_channel::encryptor::{Encryptor, KEY_RENEWAL_INTERVAL};
+use crate::secure_channel::encryptor::{unframe, Encryptor, KEY_RENEWAL_INTERVAL};
 use crate::secure_channel::key_tracker::KeyTracker;
-use crate::secure_channel::nonce_tracker::NonceTracker;
-use crate::secure_channel::Addresses;
+use crate::secure_channel::nonce_tracker::{previous_keys_for_window, NonceTracker};
+use crate::secure_channel::{
+    Addresses, ChannelCompression, ChannelCredentials, ChannelUsage, ControlMessage, LastHeartbeat,
+    ReplayCounters, DEFAULT_REPLAY_WINDOW,
+};
 use crate::{DecryptionRequest, DecryptionResponse, IdentityError, IdentitySecureChannelLocalInfo};
 
 use ockam_vault::{AeadSecretKeyHandle, VaultForSecureChannels};
@@ -20,24 +23,54 @@ pub(crate) struct DecryptorHandler {
     pub(crate) addresses: Addresses,
     pub(crate) their_identity_id: Identifier,
     pub(crate) decryptor: Decryptor,
+    pub(crate) last_heartbeat: LastHeartbeat,
+    pub(crate) usage: ChannelUsage,
+    pub(crate) credentials: Option<ChannelCredentials>,
+    pub(crate) compression: Option<ChannelCompression>,
 }
 
 impl DecryptorHandler {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         role: &'static str,
         addresses: Addresses,
         key: AeadSecretKeyHandle,
         vault: Arc<dyn VaultForSecureChannels>,
         their_identity_id: Identifier,
+        last_heartbeat: LastHeartbeat,
+        usage: ChannelUsage,
+        replay_counters: ReplayCounters,
     ) -> Self {
         Self {
             role,
             addresses,
             their_identity_id,
-            decryptor: Decryptor::new(key, vault),
+            decryptor: Decryptor::new(key, vault).with_replay_counters(replay_counters),
+            last_heartbeat,
+            usage,
+            credentials: None,
+            compression: None,
         }
     }
 
+    /// Request and present credentials after the handshake
+    pub(crate) fn with_credentials(mut self, credentials: ChannelCredentials) -> Self {
+        self.credentials = Some(credentials);
+        self
+    }
+
+    /// Negotiate the compression of the messages with the other side
+    pub(crate) fn with_compression(mut self, compression: ChannelCompression) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    /// Receive Noise transport messages, see [`Decryptor::with_noise_interop`]
+    pub(crate) fn with_noise_interop(mut self, first_nonce: u64) -> Self {
+        self.decryptor = self.decryptor.with_noise_interop(first_nonce);
+        self
+    }
+
     pub(crate) async fn handle_decrypt_api(
         &mut self,
         ctx: &mut Context,
@@ -79,7 +112,10 @@ impl DecryptorHandler {
         );
 
         // Decode raw payload binary
-        let payload = Vec::<u8>::decode(&msg.into_transport_message().payload)?;
+        let payload = unframe(
+            &msg.into_transport_message().payload,
+            self.decryptor.is_noise_interop(),
+        )?;
 
         // Decrypt the binary
         let decrypted_payload = self.decryptor.decrypt(&payload).await?;
@@ -87,6 +123,25 @@ impl DecryptorHandler {
         // Encrypted data should be a TransportMessage
         let mut transport_message = TransportMessage::decode(&decrypted_payload)?;
 
+        // Messages with an empty onward route are control messages, or compressed messages
+        if transport_message.onward_route.is_empty() {
+            match ControlMessage::decode(&transport_message.payload) {
+                Ok(ControlMessage::Compressed(compressed)) => {
+                    let compression = self
+                        .compression
+                        .as_ref()
+                        .ok_or(IdentityError::InvalidCompressedMessage)?;
+                    let decompressed = compression.decompress(&compressed)?;
+                    transport_message = TransportMessage::decode(&decompressed)?;
+                    if transport_message.onward_route.is_empty() {
+                        return Err(IdentityError::InvalidCompressedMessage.into());
+                    }
+                }
+                message => return self.handle_control_message(ctx, message).await,
+            }
+        }
+        self.usage.received(payload.len());
+
         // Add encryptor hop in the return_route (instead of our address)
         transport_message
             .return_route
@@ -115,6 +170,89 @@ impl DecryptorHandler {
         }
     }
 
+    async fn handle_control_message(
+        &mut self,
+        ctx: &mut Context,
+        message: Result<ControlMessage>,
+    ) -> Result<()> {
+        // Any control message shows that the other side is alive
+        self.last_heartbeat.mark();
+
+        match message {
+            // Answer pings through our encryptor so that the other side knows we are alive
+            Ok(ControlMessage::Ping) => {
+                debug!(
+                    "SecureChannel {} received a heartbeat at {}",
+                    self.role, &self.addresses.decryptor_remote
+                );
+                self.send_control_message(ctx, ControlMessage::Pong).await?;
+            }
+            Ok(ControlMessage::CredentialsRequired(request)) => {
+                debug!(
+                    "SecureChannel {} received a credentials request for the trust context {}",
+                    self.role, request.trust_context_id
+                );
+                let credentials = match &self.credentials {
+                    Some(credentials) => credentials.present(ctx, &request).await,
+                    None => Ok(vec![]),
+                };
+                match credentials {
+                    Ok(credentials) if !credentials.is_empty() => {
+                        let credentials = minicbor::to_vec(credentials)?;
+                        self.send_control_message(ctx, ControlMessage::Credentials(credentials))
+                            .await?;
+                    }
+                    Ok(_) => {}
+                    Err(err) => warn!(
+                        "SecureChannel {} could not retrieve a credential for the trust context {}: {}",
+                        self.role, request.trust_context_id, err
+                    ),
+                }
+            }
+            Ok(ControlMessage::Credentials(credentials)) => {
+                if let Some(channel_credentials) = &self.credentials {
+                    channel_credentials
+                        .receive(minicbor::decode(&credentials)?)
+                        .await?;
+                }
+            }
+            // The responder selects the first proposed compression that it accepts
+            Ok(ControlMessage::CompressionProposed(proposed)) => {
+                if let Some(selected) = self.compression.as_ref().and_then(|c| c.select(&proposed))
+                {
+                    debug!(
+                        "SecureChannel {} compresses its messages with {}",
+                        self.role, selected
+                    );
+                    self.send_control_message(ctx, ControlMessage::CompressionSelected(selected))
+                        .await?;
+                }
+            }
+            Ok(ControlMessage::CompressionSelected(selected)) => {
+                if let Some(compression) = &self.compression {
+                    if compression.accept(selected) {
+                        debug!(
+                            "SecureChannel {} compresses its messages with {}",
+                            self.role, selected
+                        );
+                    }
+                }
+            }
+            Ok(ControlMessage::Pong) | Ok(ControlMessage::Compressed(_)) | Err(_) => {}
+        }
+        Ok(())
+    }
+
+    /// Send a control message to the other side through our encryptor
+    async fn send_control_message(&self, ctx: &mut Context, message: ControlMessage) -> Result<()> {
+        ctx.send_from_address(
+            route![self.addresses.encryptor.clone()],
+            message,
+            self.addresses.decryptor_remote.clone(),
+        )
+        .await
+    }
+
     /// Remove the channel keys on shutdown
     pub(crate) async fn shutdown(&self) -> Result<()> {
         self.decryptor.shutdown().await
@@ -125,6 +263,10 @@ pub(crate) struct Decryptor {
     vault: Arc<dyn VaultForSecureChannels>,
     key_tracker: KeyTracker,
     nonce_tracker: NonceTracker,
+    replay_counters: ReplayCounters,
+    /// Nonce of the next message in Noise interop mode, where the messages are
+    /// received in order and don't carry their nonce
+    noise_interop_nonce: Option<u64>,
 }
 
 impl Decryptor {
@@ -132,10 +274,35 @@ impl Decryptor {
         Self {
             vault,
             key_tracker: KeyTracker::new(key, KEY_RENEWAL_INTERVAL),
-            nonce_tracker: NonceTracker::new(),
+            nonce_tracker: NonceTracker::new(DEFAULT_REPLAY_WINDOW),
+            replay_counters: ReplayCounters::default(),
+            noise_interop_nonce: None,
         }
     }
 
+    /// Receive Noise transport messages, starting with the given nonce
+    pub(crate) fn with_noise_interop(mut self, first_nonce: u64) -> Self {
+        self.noise_interop_nonce = Some(first_nonce);
+        self
+    }
+
+    /// Return true if the messages are received as Noise transport messages
+    pub(crate) fn is_noise_interop(&self) -> bool {
+        self.noise_interop_nonce.is_some()
+    }
+
+    /// Use the replay window of these counters and update them when messages are
+    /// rejected or reordered. The keys of the previous intervals are kept for the whole window
+    pub(crate) fn with_replay_counters(mut self, replay_counters: ReplayCounters) -> Self {
+        let window = replay_counters.get().window;
+        self.nonce_tracker = NonceTracker::new(window);
+        self.key_tracker = self
+            .key_tracker
+            .with_previous_keys(previous_keys_for_window(window));
+        self.replay_counters = replay_counters;
+        self
+    }
+
     /// Restore 12-byte nonce needed for AES GCM from 8 byte that we use for noise
     fn convert_nonce_from_small(b: &[u8]) -> Result<(u64, [u8; 12])> {
         let bytes: [u8; 8] = b.try_into().map_err(|_| IdentityError::InvalidNonce)?;
@@ -146,12 +313,24 @@ impl Decryptor {
     }
 
     pub async fn decrypt(&mut self, payload: &[u8]) -> Result<Vec<u8>> {
+        if let Some(nonce) = self.noise_interop_nonce {
+            return self.decrypt_noise_message(payload, nonce).await;
+        }
+
         if payload.len() < 8 {
             return Err(IdentityError::InvalidNonce.into());
         }
 
         let (nonce, nonce_buffer) = Self::convert_nonce_from_small(&payload[..8])?;
-        let nonce_tracker = self.nonce_tracker.mark(nonce)?;
+        let nonce_tracker = match self.nonce_tracker.mark(nonce) {
+            Ok(nonce_tracker) => nonce_tracker,
+            Err(rejection) => {
+                debug!("Rejecting the message with nonce {nonce}: {rejection:?}");
+                self.replay_counters.rejected(rejection);
+                return Err(rejection.into());
+            }
+        };
+        let reordered = self.nonce_tracker.is_reordered(nonce);
 
         // get the key corresponding to the current nonce and
         // rekey if necessary
@@ -170,6 +349,9 @@ impl Decryptor {
 
         if result.is_ok() {
             self.nonce_tracker = nonce_tracker;
+            if reordered {
+                self.replay_counters.reordered();
+            }
             if let Some(key_to_delete) = self.key_tracker.update_key(key)? {
                 self.vault.delete_aead_secret_key(key_to_delete).await?;
             }
@@ -177,14 +359,29 @@ impl Decryptor {
         result
     }
 
+    /// Decrypt a Noise transport message: the message must have the next nonce,
+    /// and the key is never renewed
+    async fn decrypt_noise_message(&mut self, payload: &[u8], nonce: u64) -> Result<Vec<u8>> {
+        if nonce == u64::MAX {
+            return Err(IdentityError::NonceOverflow.into());
+        }
+        let nonce_buffer = Encryptor::convert_nonce_from_u64(nonce).1;
+        let plaintext = self
+            .vault
+            .aead_decrypt(&self.key_tracker.current_key, payload, &nonce_buffer, &[])
+            .await?;
+        self.noise_interop_nonce = Some(nonce + 1);
+        Ok(plaintext)
+    }
+
     /// Remove the channel keys on shutdown
     pub(crate) async fn shutdown(&self) -> Result<()> {
         self.vault
             .delete_aead_secret_key(self.key_tracker.current_key.clone())
             .await?;
-        if let Some(previous_key) = self.key_tracker.previous_key.clone() {
+        for previous_key in self.key_tracker.previous_keys.iter().cloned() {
             self.vault.delete_aead_secret_key(previous_key).await?;
-        };
+        }
         Ok(())
     }
 }
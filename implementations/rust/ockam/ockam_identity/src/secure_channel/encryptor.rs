@@ -1,7 +1,7 @@
 use ockam_core::compat::sync::Arc;
 use ockam_core::compat::vec::Vec;
 use ockam_core::errcode::{Kind, Origin};
-use ockam_core::{Error, Result};
+use ockam_core::{Decodable, Encodable, Error, NeutralMessage, Result};
 use ockam_vault::{AeadSecretKeyHandle, VaultForSecureChannels};
 
 use crate::IdentityError;
@@ -10,6 +10,7 @@ pub(crate) struct Encryptor {
     key: AeadSecretKeyHandle,
     nonce: u64,
     vault: Arc<dyn VaultForSecureChannels>,
+    noise_interop: bool,
 }
 
 // To simplify the implementation we use the same constant for the size of the message
@@ -54,7 +55,8 @@ impl Encryptor {
 
         self.nonce += 1;
 
-        if current_nonce > 0 && current_nonce % KEY_RENEWAL_INTERVAL == 0 {
+        // Noise only renews the keys when the application asks for it
+        if !self.noise_interop && current_nonce > 0 && current_nonce % KEY_RENEWAL_INTERVAL == 0 {
             let new_key = Self::rekey(&self.vault, &self.key).await?;
             let old_key = core::mem::replace(&mut self.key, new_key);
             self.vault.delete_aead_secret_key(old_key).await?;
@@ -67,6 +69,11 @@ impl Encryptor {
             .aead_encrypt(&self.key, payload, &nonce, &[])
             .await?;
 
+        // Noise transport messages don't carry their nonce, both sides count them
+        if self.noise_interop {
+            return Ok(cipher_text);
+        }
+
         let mut res = Vec::new();
         res.extend_from_slice(&small_nonce);
         res.append(&mut cipher_text);
@@ -79,7 +86,24 @@ impl Encryptor {
         nonce: u64,
         vault: Arc<dyn VaultForSecureChannels>,
     ) -> Self {
-        Self { key, nonce, vault }
+        Self {
+            key,
+            nonce,
+            vault,
+            noise_interop: false,
+        }
+    }
+
+    /// Send the ciphertexts as Noise transport messages: without their nonce, and without
+    /// ever renewing the key
+    pub(crate) fn with_noise_interop(mut self, noise_interop: bool) -> Self {
+        self.noise_interop = noise_interop;
+        self
+    }
+
+    /// Return the payload of the message sent to the other side of the channel
+    pub(crate) fn frame(&self, message: Vec<u8>) -> Result<NeutralMessage> {
+        frame(message, self.noise_interop)
     }
 
     pub(crate) async fn shutdown(&self) -> Result<()> {
@@ -97,3 +121,23 @@ impl Encryptor {
         }
     }
 }
+
+/// Return the payload of a message sent to the other side of a channel during, or after,
+/// the handshake. It is a BARE encoded byte array, except in Noise interop mode
+/// where the message is sent as it is, like any other Noise implementation does
+pub(crate) fn frame(message: Vec<u8>, noise_interop: bool) -> Result<NeutralMessage> {
+    if noise_interop {
+        Ok(NeutralMessage::from(message))
+    } else {
+        Ok(NeutralMessage::from(message.encode()?))
+    }
+}
+
+/// Return the message received from the other side of a channel, see [`frame`]
+pub(crate) fn unframe(payload: &[u8], noise_interop: bool) -> Result<Vec<u8>> {
+    if noise_interop {
+        Ok(payload.to_vec())
+    } else {
+        Vec::<u8>::decode(payload)
+    }
+}
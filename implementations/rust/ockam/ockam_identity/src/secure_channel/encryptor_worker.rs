@@ -1,12 +1,18 @@
 use ockam_core::compat::boxed::Box;
-use ockam_core::{async_trait, Decodable, Encodable, Route};
+use ockam_core::{async_trait, route, Decodable, Encodable, Route};
 use ockam_core::{Any, Result, Routed, TransportMessage, Worker};
-use ockam_node::Context;
-use tracing::debug;
+use ockam_node::{Context, DelayedEvent};
+use tracing::{debug, warn};
 
+use crate::models::TimestampInSeconds;
 use crate::secure_channel::addresses::Addresses;
 use crate::secure_channel::api::{EncryptionRequest, EncryptionResponse};
 use crate::secure_channel::encryptor::Encryptor;
+use crate::secure_channel::{
+    ChannelCompression, ChannelUsage, ControlMessage, CredentialsRefreshTimer, LastHeartbeat,
+    SecureChannelHeartbeats,
+};
+use crate::utils::now;
 use crate::IdentityError;
 
 pub(crate) struct EncryptorWorker {
@@ -15,6 +21,13 @@ pub(crate) struct EncryptorWorker {
     addresses: Addresses,
     remote_route: Route,
     encryptor: Encryptor,
+    heartbeats: Option<SecureChannelHeartbeats>,
+    heartbeat: Option<DelayedEvent<()>>,
+    last_heartbeat: LastHeartbeat,
+    started_at: TimestampInSeconds,
+    usage: ChannelUsage,
+    credentials_refresh: Option<CredentialsRefreshTimer>,
+    compression: Option<ChannelCompression>,
 }
 
 impl EncryptorWorker {
@@ -23,15 +36,41 @@ impl EncryptorWorker {
         addresses: Addresses,
         remote_route: Route,
         encryptor: Encryptor,
+        heartbeats: Option<SecureChannelHeartbeats>,
+        heartbeat: Option<DelayedEvent<()>>,
+        last_heartbeat: LastHeartbeat,
+        usage: ChannelUsage,
     ) -> Self {
         Self {
             role,
             addresses,
             remote_route,
             encryptor,
+            heartbeats,
+            heartbeat,
+            last_heartbeat,
+            started_at: now().unwrap_or(TimestampInSeconds(0)),
+            usage,
+            credentials_refresh: None,
+            compression: None,
         }
     }
 
+    /// Regularly request fresh credentials from the other side
+    pub(crate) fn with_credentials_refresh(
+        mut self,
+        credentials_refresh: CredentialsRefreshTimer,
+    ) -> Self {
+        self.credentials_refresh = Some(credentials_refresh);
+        self
+    }
+
+    /// Compress the messages once a compression is negotiated with the other side
+    pub(crate) fn with_compression(mut self, compression: ChannelCompression) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
     async fn handle_encrypt_api(
         &mut self,
         ctx: &mut <Self as Worker>::Context,
@@ -84,19 +123,110 @@ impl EncryptorWorker {
             msg.into_transport_message().payload,
         );
 
+        // A compressed message is sent as a control message wrapping the compressed
+        // transport message
+        let mut plaintext = msg.encode()?;
+        if let Some(compressed) = self
+            .compression
+            .as_ref()
+            .and_then(|c| c.compress(&plaintext))
+        {
+            let message = ControlMessage::Compressed(compressed);
+            plaintext = TransportMessage::v1(route![], route![], message.encode()?).encode()?;
+        }
+
         // Encrypt the message
-        let encrypted_payload = self.encryptor.encrypt(&msg.encode()?).await?;
+        let encrypted_payload = self.encryptor.encrypt(&plaintext).await?;
+        self.usage.sent(encrypted_payload.len());
 
         // Send the message to the decryptor on the other side
         ctx.send_from_address(
             self.remote_route.clone(),
-            encrypted_payload,
+            self.encryptor.frame(encrypted_payload)?,
             self.addresses.encryptor.clone(),
         )
         .await?;
 
         Ok(())
     }
+
+    async fn handle_heartbeat(&mut self, ctx: &mut <Self as Worker>::Context) -> Result<()> {
+        let heartbeats = match self.heartbeats {
+            Some(heartbeats) => heartbeats,
+            None => return Ok(()),
+        };
+
+        // The other side didn't answer for too long, stop the channel so that the
+        // workers relying on it can detect it and recover
+        if self.last_heartbeat.is_expired(&heartbeats, self.started_at) {
+            warn!(
+                "SecureChannel {} at {} missed {} heartbeats, stopping it",
+                self.role,
+                &self.addresses.encryptor,
+                heartbeats.miss_threshold()
+            );
+            return ctx.stop_worker(self.addresses.encryptor.clone()).await;
+        }
+
+        // A heartbeat is a message with an empty onward route
+        let msg = TransportMessage::v1(route![], route![], ControlMessage::Ping.encode()?);
+        let encrypted_payload = self.encryptor.encrypt(&msg.encode()?).await?;
+        // Failing to send is not fatal, it will be detected as missed heartbeats
+        if let Err(err) = ctx
+            .send_from_address(
+                self.remote_route.clone(),
+                self.encryptor.frame(encrypted_payload)?,
+                self.addresses.encryptor.clone(),
+            )
+            .await
+        {
+            debug!(
+                "SecureChannel {} could not send a heartbeat: {}",
+                self.role, err
+            );
+        }
+
+        if let Some(heartbeat) = &mut self.heartbeat {
+            heartbeat.schedule(heartbeats.interval()).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn handle_credentials_refresh(
+        &mut self,
+        ctx: &mut <Self as Worker>::Context,
+    ) -> Result<()> {
+        let credentials_refresh = match &mut self.credentials_refresh {
+            Some(credentials_refresh) => credentials_refresh,
+            None => return Ok(()),
+        };
+
+        debug!(
+            "SecureChannel {} requesting fresh credentials for the trust context {}",
+            self.role, credentials_refresh.request.trust_context_id
+        );
+        let message = ControlMessage::CredentialsRequired(credentials_refresh.request.clone());
+        let msg = TransportMessage::v1(route![], route![], message.encode()?);
+        let encrypted_payload = self.encryptor.encrypt(&msg.encode()?).await?;
+        // Failing to send is not fatal, the credentials will be requested again
+        match ctx
+            .send_from_address(
+                self.remote_route.clone(),
+                self.encryptor.frame(encrypted_payload)?,
+                self.addresses.encryptor.clone(),
+            )
+            .await
+        {
+            Ok(()) => credentials_refresh.metrics.record_requested(),
+            Err(err) => debug!(
+                "SecureChannel {} could not request fresh credentials: {}",
+                self.role, err
+            ),
+        }
+
+        credentials_refresh.schedule().await
+    }
 }
 
 #[async_trait]
@@ -104,6 +234,16 @@ impl Worker for EncryptorWorker {
     type Message = Any;
     type Context = Context;
 
+    async fn initialize(&mut self, _context: &mut Self::Context) -> Result<()> {
+        if let (Some(heartbeat), Some(heartbeats)) = (&mut self.heartbeat, &self.heartbeats) {
+            heartbeat.schedule(heartbeats.interval()).await?;
+        }
+        if let Some(credentials_refresh) = &mut self.credentials_refresh {
+            credentials_refresh.schedule().await?;
+        }
+        Ok(())
+    }
+
     async fn handle_message(
         &mut self,
         ctx: &mut Self::Context,
@@ -115,6 +255,10 @@ impl Worker for EncryptorWorker {
             self.handle_encrypt(ctx, msg).await?;
         } else if msg_addr == self.addresses.encryptor_api {
             self.handle_encrypt_api(ctx, msg).await?;
+        } else if msg_addr == self.addresses.encryptor_heartbeat {
+            self.handle_heartbeat(ctx).await?;
+        } else if msg_addr == self.addresses.encryptor_credentials_refresh {
+            self.handle_credentials_refresh(ctx).await?;
         } else {
             return Err(IdentityError::UnknownChannelMsgDestination.into());
         }
@@ -123,6 +267,12 @@ impl Worker for EncryptorWorker {
     }
 
     async fn shutdown(&mut self, context: &mut Self::Context) -> Result<()> {
+        if let Some(heartbeat) = &mut self.heartbeat {
+            heartbeat.cancel();
+        }
+        if let Some(credentials_refresh) = &mut self.credentials_refresh {
+            credentials_refresh.event.cancel();
+        }
         let _ = context
             .stop_worker(self.addresses.decryptor_internal.clone())
             .await;
@@ -1,4 +1,3 @@
-use cfg_if::cfg_if;
 use ockam_core::compat::sync::Arc;
 use ockam_core::compat::vec::Vec;
 use ockam_core::errcode::{Kind, Origin};
@@ -10,9 +9,11 @@ use ockam_vault::{
 use sha2::{Digest, Sha256};
 use Status::*;
 
+use crate::secure_channel::decryptor::Decryptor;
+use crate::secure_channel::encryptor::Encryptor;
 use crate::secure_channel::handshake::error::XXError;
 use crate::secure_channel::handshake::handshake_state_machine::{HandshakeKeys, Status};
-use crate::secure_channel::Role;
+use crate::secure_channel::{Role, SecureChannelCipherSuite};
 
 /// The number of bytes in a SHA256 digest
 pub const SHA256_SIZE: usize = 32;
@@ -216,6 +217,53 @@ impl Handshake {
             _ => None,
         }
     }
+
+    /// Noise interop mode: once the keys have been computed, wait for the identity of
+    /// the other party before declaring the handshake ready
+    pub(super) fn wait_for_identity(&mut self) -> Result<()> {
+        let keys = self
+            .get_handshake_keys()
+            .ok_or(XXError::InvalidInternalState)?;
+        self.state.status = WaitingForIdentity(keys);
+        Ok(())
+    }
+
+    /// Noise interop mode: the identity of the other party has been verified
+    pub(super) fn set_ready(&mut self) -> Result<()> {
+        match self.state.status.clone() {
+            WaitingForIdentity(keys) => {
+                self.state.status = Ready(keys);
+                Ok(())
+            }
+            _ => Err(XXError::InvalidInternalState.into()),
+        }
+    }
+
+    /// Noise interop mode: encrypt an identity payload as the first transport message
+    pub(super) async fn encrypt_identity_payload(&self, payload: &[u8]) -> Result<Vec<u8>> {
+        match &self.state.status {
+            WaitingForIdentity(keys) => {
+                Encryptor::new(keys.encryption_key.clone(), 0, self.vault.clone())
+                    .with_noise_interop(true)
+                    .encrypt(payload)
+                    .await
+            }
+            _ => Err(XXError::InvalidInternalState.into()),
+        }
+    }
+
+    /// Noise interop mode: decrypt the identity payload sent as the first transport message
+    pub(super) async fn decrypt_identity_payload(&self, message: &[u8]) -> Result<Vec<u8>> {
+        match &self.state.status {
+            WaitingForIdentity(keys) => {
+                Decryptor::new(keys.decryption_key.clone(), self.vault.clone())
+                    .with_noise_interop(0)
+                    .decrypt(message)
+                    .await
+            }
+            _ => Err(XXError::InvalidInternalState.into()),
+        }
+    }
 }
 
 impl Handshake {
@@ -231,7 +279,7 @@ impl Handshake {
         // We currently don't use any payload for message 1
         Ok(Handshake {
             vault,
-            protocol_name: *PROTOCOL_NAME,
+            protocol_name: *SecureChannelCipherSuite::compiled().protocol_name(),
             state: HandshakeState::new(static_key, ephemeral_key),
         })
     }
@@ -352,16 +400,6 @@ impl Handshake {
     }
 }
 
-cfg_if! {
-    if #[cfg(any(not(feature = "disable_default_noise_protocol"), feature = "OCKAM_XX_25519_AES256_GCM_SHA256"))] {
-        pub const PROTOCOL_NAME: &[u8; 32] = b"Noise_XX_25519_AESGCM_SHA256\0\0\0\0";
-    } else if #[cfg(feature = "OCKAM_XX_25519_AES128_GCM_SHA256")] {
-        pub const PROTOCOL_NAME: &[u8; 32] = b"OCKAM_XX_25519_AES128_GCM_SHA256";
-    } else if #[cfg(feature = "OCKAM_XX_25519_ChaChaPolyBLAKE2s")] {
-        pub const PROTOCOL_NAME: &[u8; 32] = b"OCKAM_XX_25519_ChaChaPolyBLAKE2s";
-    }
-}
-
 /// Static functions
 impl Handshake {
     /// Protocol name, used as a secret during the handshake initialization, padded to 32 bytes
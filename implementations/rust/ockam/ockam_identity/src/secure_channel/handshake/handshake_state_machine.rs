@@ -10,7 +10,8 @@ use crate::models::{
     ChangeHistory, CredentialAndPurposeKey, Identifier, PurposeKeyAttestation, PurposePublicKey,
 };
 use crate::{
-    Identities, Identity, IdentityError, SecureChannelTrustInfo, TrustContext, TrustPolicy,
+    Identities, Identity, IdentityError, SecureChannelCipherSuite, SecureChannelTrustInfo,
+    TrustContext, TrustPolicy,
 };
 
 /// Interface for a state machine in a key exchange protocol
@@ -33,6 +34,9 @@ pub(super) enum Event {
 pub(super) enum Action {
     NoAction,
     SendMessage(Vec<u8>),
+    /// Send several messages in sequence. In Noise interop mode the last handshake message
+    /// is immediately followed by a transport message carrying the identity payload
+    SendMessages(Vec<Vec<u8>>),
 }
 
 /// List of possible states for the initiator or responder sides of the exchange
@@ -42,6 +46,9 @@ pub(super) enum Status {
     WaitingForMessage1,
     WaitingForMessage2,
     WaitingForMessage3,
+    /// Noise interop mode only: the Noise handshake is done but the identity of the
+    /// other party has not been received yet
+    WaitingForIdentity(HandshakeKeys),
     Ready(HandshakeKeys),
 }
 
@@ -58,6 +65,14 @@ pub(super) struct HandshakeKeys {
 pub(super) struct HandshakeResults {
     pub(super) handshake_keys: HandshakeKeys,
     pub(super) their_identifier: Identifier,
+    /// Nonce of the first message sent by the encryptor, and received by the decryptor
+    pub(super) first_nonce: u64,
+    /// Exchange Noise transport messages after the handshake
+    pub(super) noise_interop: bool,
+    /// Cipher suite selected during the handshake
+    pub(super) cipher_suite: SecureChannelCipherSuite,
+    /// True if the other party did not present a valid credential and must be asked for one
+    pub(super) credentials_required: bool,
 }
 
 /// This struct implements functions common to both initiator and the responder state machines
@@ -68,10 +83,25 @@ pub(super) struct CommonStateMachine {
     pub(super) credentials: Vec<CredentialAndPurposeKey>,
     pub(super) trust_policy: Arc<dyn TrustPolicy>,
     pub(super) trust_context: Option<TrustContext>,
+    /// If true, identity payloads are exchanged in transport messages after a plain Noise XX
+    /// handshake instead of being embedded in handshake messages 2 and 3
+    pub(super) noise_interop: bool,
+    /// Cipher suites proposed by the initiator, or accepted by the responder, by order of preference.
+    /// If empty, the default cipher suite is used
+    pub(super) cipher_suites: Vec<SecureChannelCipherSuite>,
+    /// Cipher suite selected during the handshake
+    pub(super) cipher_suite: SecureChannelCipherSuite,
+    /// True if the responder selected the cipher suite from suites proposed by the initiator
+    cipher_suite_negotiated: bool,
+    /// If true, a missing or invalid credential does not fail the handshake,
+    /// a credential is requested once the channel is created instead
+    pub(super) credentials_on_demand: bool,
+    credentials_required: bool,
     their_identifier: Option<Identifier>,
 }
 
 impl CommonStateMachine {
+    #[allow(clippy::too_many_arguments)]
     pub(super) fn new(
         identities: Arc<Identities>,
         identifier: Identifier,
@@ -79,6 +109,9 @@ impl CommonStateMachine {
         credentials: Vec<CredentialAndPurposeKey>,
         trust_policy: Arc<dyn TrustPolicy>,
         trust_context: Option<TrustContext>,
+        noise_interop: bool,
+        cipher_suites: Vec<SecureChannelCipherSuite>,
+        credentials_on_demand: bool,
     ) -> Self {
         Self {
             identities,
@@ -87,6 +120,12 @@ impl CommonStateMachine {
             credentials,
             trust_policy,
             trust_context,
+            noise_interop,
+            cipher_suites,
+            cipher_suite: SecureChannelCipherSuite::default(),
+            cipher_suite_negotiated: false,
+            credentials_on_demand,
+            credentials_required: false,
             their_identifier: None,
         }
     }
@@ -109,6 +148,7 @@ impl CommonStateMachine {
             change_history,
             purpose_key_attestation: self.purpose_key_attestation.clone(),
             credentials: self.credentials.clone(),
+            cipher_suite: None,
         };
         Ok(minicbor::to_vec(payload)?)
     }
@@ -121,10 +161,11 @@ impl CommonStateMachine {
         peer: IdentityAndCredentials,
         peer_public_key: &X25519PublicKey,
     ) -> Result<()> {
-        let identity = Identity::import_from_change_history(
+        let identity = Identity::import_from_change_history_with_limits(
             None,
             peer.change_history.clone(),
             self.identities.vault().verifying_vault,
+            &self.identities.change_history_limits(),
         )
         .await?;
 
@@ -161,9 +202,10 @@ impl CommonStateMachine {
     }
 
     /// Verify that the credentials sent by the other party are valid using a trust context
-    /// and store them
+    /// and store them.
+    /// When credentials are requested on demand, the handshake does not fail if no credential is valid
     async fn verify_credentials(
-        &self,
+        &mut self,
         their_identifier: &Identifier,
         credentials: Vec<CredentialAndPurposeKey>,
     ) -> Result<()> {
@@ -184,6 +226,7 @@ impl CommonStateMachine {
                 "got a trust context to check the credentials. There are {} credentials to check",
                 credentials.len()
             );
+            let mut valid_credentials = 0;
             for credential in &credentials {
                 let result = self
                     .identities
@@ -196,14 +239,21 @@ impl CommonStateMachine {
                     )
                     .await;
 
-                if let Some(err) = result.err() {
-                    warn!("a credential could not be validated {}", err.to_string());
-                    // TODO: consider the possibility of keep going when a credential validation fails
-                    return Err(
-                        IdentityError::SecureChannelVerificationFailedIncorrectCredential.into(),
-                    );
+                match result {
+                    Ok(()) => valid_credentials += 1,
+                    Err(err) if self.credentials_on_demand => {
+                        debug!("a credential could not be validated {}", err.to_string());
+                    }
+                    Err(err) => {
+                        warn!("a credential could not be validated {}", err.to_string());
+                        return Err(
+                            IdentityError::SecureChannelVerificationFailedIncorrectCredential
+                                .into(),
+                        );
+                    }
                 }
             }
+            self.credentials_required = self.credentials_on_demand && valid_credentials == 0;
         } else if !credentials.is_empty() {
             warn!("no credentials have been received");
             // we cannot validate credentials without a trust context
@@ -213,6 +263,61 @@ impl CommonStateMachine {
         Ok(())
     }
 
+    /// Prepare the payload of message 1, containing the cipher suites proposed by the initiator.
+    /// The payload is empty when no cipher suite is configured, so that the default suite is used
+    pub(super) fn make_cipher_suites_payload(&mut self) -> Result<Vec<u8>> {
+        if self.cipher_suites.is_empty() {
+            return Ok(vec![]);
+        }
+        // the responder can only select one of the suites available on both sides
+        self.cipher_suite = *SecureChannelCipherSuite::available(&self.cipher_suites)
+            .first()
+            .ok_or(IdentityError::NoCommonCipherSuite)?;
+        Ok(minicbor::to_vec(&self.cipher_suites)?)
+    }
+
+    /// Select the cipher suite to use from the suites proposed by the initiator in message 1
+    pub(super) fn select_cipher_suite(&mut self, message1_payload: &[u8]) -> Result<()> {
+        self.cipher_suite_negotiated = !message1_payload.is_empty();
+        let proposed: Vec<SecureChannelCipherSuite> = if self.cipher_suite_negotiated {
+            minicbor::decode(message1_payload)?
+        } else {
+            vec![SecureChannelCipherSuite::default()]
+        };
+        self.cipher_suite = SecureChannelCipherSuite::negotiate(&proposed, &self.cipher_suites)
+            .ok_or(IdentityError::NoCommonCipherSuite)?;
+        debug!("Selected the cipher suite {}", self.cipher_suite);
+        Ok(())
+    }
+
+    /// Add the selected cipher suite to the identity payload of the responder,
+    /// when the initiator proposed some cipher suites
+    pub(super) fn add_selected_cipher_suite(&self, identity_payload: Vec<u8>) -> Result<Vec<u8>> {
+        if !self.cipher_suite_negotiated {
+            return Ok(identity_payload);
+        }
+        let mut payload: IdentityAndCredentials = minicbor::decode(&identity_payload)?;
+        payload.cipher_suite = Some(self.cipher_suite);
+        Ok(minicbor::to_vec(payload)?)
+    }
+
+    /// Check the cipher suite selected by the responder and use it for the channel
+    pub(super) fn accept_selected_cipher_suite(
+        &mut self,
+        selected: Option<SecureChannelCipherSuite>,
+    ) -> Result<()> {
+        if self.cipher_suites.is_empty() {
+            return Ok(());
+        }
+        self.cipher_suite = SecureChannelCipherSuite::check_selected(&self.cipher_suites, selected)
+            .ok_or(IdentityError::NoCommonCipherSuite)?;
+        debug!(
+            "The responder selected the cipher suite {}",
+            self.cipher_suite
+        );
+        Ok(())
+    }
+
     /// Return the results of the full handshake
     ///  - the other party identity
     ///  - the encryption and decryption keys to use on the next messages to exchange
@@ -224,6 +329,11 @@ impl CommonStateMachine {
             (Some(their_identifier), Some(handshake_keys)) => Some(HandshakeResults {
                 their_identifier,
                 handshake_keys,
+                // in interop mode the nonce 0 was used to send the identity payload
+                first_nonce: if self.noise_interop { 1 } else { 0 },
+                noise_interop: self.noise_interop,
+                cipher_suite: self.cipher_suite,
+                credentials_required: self.credentials_required,
             }),
             _ => None,
         }
@@ -244,4 +354,6 @@ pub(super) struct IdentityAndCredentials {
     /// Credentials associated to the identity along with corresponding Credentials Purpose Keys
     /// to verify those Credentials
     #[n(3)] pub(super) credentials: Vec<CredentialAndPurposeKey>,
+    /// Cipher suite selected by the responder, when the initiator proposed some cipher suites
+    #[n(4)] pub(super) cipher_suite: Option<SecureChannelCipherSuite>,
 }
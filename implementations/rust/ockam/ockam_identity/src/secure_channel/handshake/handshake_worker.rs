@@ -3,19 +3,21 @@ use core::time::Duration;
 use ockam_core::compat::{boxed::Box, vec::Vec};
 use ockam_core::errcode::{Kind, Origin};
 use ockam_core::{
-    AllowAll, Any, Decodable, DenyAll, Error, Mailbox, Mailboxes, OutgoingAccessControl, Route,
-    Routed,
+    route, AllowAll, Any, DenyAll, Error, Mailbox, Mailboxes, OutgoingAccessControl, Route, Routed,
 };
-use ockam_core::{AllowOnwardAddress, Result, Worker};
+use ockam_core::{AllowOnwardAddress, AllowSourceAddress, Result, Worker};
 use ockam_node::callback::CallbackSender;
-use ockam_node::{Context, WorkerBuilder};
+use ockam_node::{Context, DelayedEvent, WorkerBuilder};
 use tracing::{debug, info};
 
 use crate::models::{CredentialAndPurposeKey, Identifier};
+use crate::secure_channel::admission::AdmissionTicket;
 use crate::secure_channel::decryptor::DecryptorHandler;
-use crate::secure_channel::encryptor::Encryptor;
+use crate::secure_channel::encryptor::{frame, unframe, Encryptor};
 use crate::secure_channel::encryptor_worker::EncryptorWorker;
-use crate::secure_channel::handshake::handshake_state_machine::Action::SendMessage;
+use crate::secure_channel::handshake::handshake_state_machine::Action::{
+    SendMessage, SendMessages,
+};
 use crate::secure_channel::handshake::handshake_state_machine::Event::{
     Initialize, ReceivedMessage,
 };
@@ -24,10 +26,15 @@ use crate::secure_channel::handshake::handshake_state_machine::{
 };
 use crate::secure_channel::handshake::initiator_state_machine::InitiatorStateMachine;
 use crate::secure_channel::handshake::responder_state_machine::ResponderStateMachine;
-use crate::secure_channel::{Addresses, Role};
+use crate::secure_channel::{
+    Addresses, ChannelCompression, ChannelCredentials, ChannelUsage, ControlMessage,
+    CredentialsExchange, CredentialsRefreshTimer, LastHeartbeat, ReplayCounters, Role,
+    SecureChannelHeartbeats,
+};
+use crate::utils::now;
 use crate::{
-    IdentityError, SecureChannelPurposeKey, SecureChannelRegistryEntry, SecureChannels,
-    TrustContext, TrustPolicy,
+    IdentityError, SecureChannelCipherSuite, SecureChannelCompression, SecureChannelPurposeKey,
+    SecureChannelRegistryEntry, SecureChannels, TrustContext, TrustPolicy,
 };
 
 /// This struct implements a Worker receiving and sending messages
@@ -41,6 +48,19 @@ pub(crate) struct HandshakeWorker {
     role: Role,
     remote_route: Option<Route>,
     decryptor_handler: Option<DecryptorHandler>,
+    heartbeats: Option<SecureChannelHeartbeats>,
+    replay_window: u64,
+    trust_context: Option<TrustContext>,
+    credentials_exchange: CredentialsExchange,
+    /// Compressions proposed by the initiator, or accepted by the responder
+    compression: Vec<SecureChannelCompression>,
+    /// Admission of the handshake by the listener which created this worker
+    admission: Option<AdmissionTicket>,
+    /// Record the peer of the channel in the peers of the secure channels.
+    /// The peers of a listener trusting everyone are not recorded, since anyone could add them
+    record_peer: bool,
+    /// Send and receive the handshake messages as a Noise implementation does
+    noise_interop: bool,
 }
 
 #[ockam_core::worker]
@@ -58,14 +78,9 @@ impl Worker for HandshakeWorker {
                     self.remote_route.clone(),
                     self.addresses.decryptor_remote.clone()
                 );
-                context
-                    .send_from_address(
-                        self.remote_route()?,
-                        message,
-                        self.addresses.decryptor_remote.clone(),
-                    )
-                    .await
+                self.send_messages(context, vec![message]).await
             }
+            SendMessages(messages) => self.send_messages(context, messages).await,
             Action::NoAction => Ok(()),
         }
     }
@@ -98,26 +113,26 @@ impl Worker for HandshakeWorker {
         };
 
         let transport_message = message.into_transport_message();
-        if let SendMessage(message) = self
+        let messages = match self
             .state_machine
-            .on_event(ReceivedMessage(Vec::<u8>::decode(
+            .on_event(ReceivedMessage(unframe(
                 &transport_message.payload,
+                self.noise_interop,
             )?))
             .await?
         {
+            SendMessage(message) => vec![message],
+            SendMessages(messages) => messages,
+            Action::NoAction => vec![],
+        };
+
+        if !messages.is_empty() {
             // set the remote route by taking the most up to date message return route
             // In the case of the initiator the first return route mentions the secure channel listener
             // address so we need to wait for the return route corresponding to the remote handshake worker
             // when it has been spawned
             self.remote_route = Some(transport_message.return_route);
-
-            context
-                .send_from_address(
-                    self.remote_route()?,
-                    message,
-                    self.addresses.decryptor_remote.clone(),
-                )
-                .await?
+            self.send_messages(context, messages).await?;
         };
 
         // if we reached the final state we can make a pair of encryptor/decryptor
@@ -127,6 +142,9 @@ impl Worker for HandshakeWorker {
             if let Some(callback_sender) = self.callback_sender.take() {
                 callback_sender.send(())?;
             }
+            if let Some(admission) = &self.admission {
+                admission.handshake_completed(context).await;
+            }
         };
 
         Ok(())
@@ -138,6 +156,10 @@ impl Worker for HandshakeWorker {
             .secure_channel_registry
             .unregister_channel(&self.addresses.encryptor);
 
+        if let Some(admission) = self.admission.take() {
+            admission.release(context).await;
+        }
+
         if let Some(handler) = &self.decryptor_handler {
             handler.shutdown().await?
         }
@@ -161,10 +183,18 @@ impl HandshakeWorker {
         trust_context: Option<TrustContext>,
         remote_route: Option<Route>,
         timeout: Option<Duration>,
+        heartbeats: Option<SecureChannelHeartbeats>,
+        noise_interop: bool,
+        cipher_suites: Vec<SecureChannelCipherSuite>,
+        replay_window: u64,
+        credentials_exchange: CredentialsExchange,
+        compression: Vec<SecureChannelCompression>,
+        admission: Option<AdmissionTicket>,
         role: Role,
     ) -> Result<()> {
         let vault = secure_channels.identities.vault().secure_channel_vault;
         let identities = secure_channels.identities();
+        let record_peer = role.is_initiator() || !trust_policy.trusts_everyone();
         let state_machine: Box<dyn StateMachine> = if role.is_initiator() {
             Box::new(
                 InitiatorStateMachine::new(
@@ -174,7 +204,10 @@ impl HandshakeWorker {
                     purpose_key,
                     credentials,
                     trust_policy,
-                    trust_context,
+                    trust_context.clone(),
+                    noise_interop,
+                    cipher_suites.clone(),
+                    credentials_exchange.on_demand,
                 )
                 .await?,
             )
@@ -187,7 +220,10 @@ impl HandshakeWorker {
                     purpose_key,
                     credentials,
                     trust_policy,
-                    trust_context,
+                    trust_context.clone(),
+                    noise_interop,
+                    cipher_suites,
+                    credentials_exchange.on_demand,
                 )
                 .await?,
             )
@@ -209,6 +245,14 @@ impl HandshakeWorker {
             remote_route: remote_route.clone(),
             addresses: addresses.clone(),
             decryptor_handler: None,
+            heartbeats,
+            replay_window,
+            trust_context,
+            credentials_exchange,
+            compression,
+            admission,
+            record_peer,
+            noise_interop,
         };
 
         WorkerBuilder::new(worker)
@@ -252,6 +296,20 @@ impl HandshakeWorker {
         })
     }
 
+    /// Send handshake messages to the other party's handshake worker
+    async fn send_messages(&self, context: &Context, messages: Vec<Vec<u8>>) -> Result<()> {
+        for message in messages {
+            context
+                .send_from_address(
+                    self.remote_route()?,
+                    frame(message, self.noise_interop)?,
+                    self.addresses.decryptor_remote.clone(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
     /// Create mailboxes and access rights for the workers involved in the secure channel creation
     pub(crate) fn create_mailboxes(
         addresses: &Addresses,
@@ -288,6 +346,39 @@ impl HandshakeWorker {
         context: &Context,
         handshake_results: HandshakeResults,
     ) -> Result<DecryptorHandler> {
+        let last_heartbeat = LastHeartbeat::default();
+        let replay_counters = ReplayCounters::new(self.replay_window);
+        let usage = ChannelUsage::new(
+            self.secure_channels.bandwidth_usage.clone(),
+            handshake_results.their_identifier.clone(),
+        );
+        if self.record_peer {
+            if let Ok(now) = now() {
+                self.secure_channels.peers.record_channel(
+                    &handshake_results.their_identifier,
+                    self.role.is_initiator(),
+                    now,
+                );
+            }
+        }
+
+        // the credentials of the other side are refreshed with the default of the secure channels
+        // if the channel doesn't configure its own refresh
+        let mut credentials_exchange = self.credentials_exchange.clone();
+        credentials_exchange.refresh = credentials_exchange
+            .refresh
+            .or(self.secure_channels.credentials_refresh);
+        let credentials = ChannelCredentials::new(
+            self.secure_channels.identities(),
+            self.identifier.clone(),
+            handshake_results.their_identifier.clone(),
+            self.trust_context.clone(),
+            credentials_exchange.clone(),
+            self.secure_channels.credentials_refresh_metrics.clone(),
+        );
+        let credentials_refresh_request = credentials.refresh_request().await?;
+        let compression = ChannelCompression::new(self.compression.clone());
+
         // create a decryptor to delegate the processing of all messages after the handshake
         let decryptor = DecryptorHandler::new(
             self.role.str(),
@@ -295,20 +386,66 @@ impl HandshakeWorker {
             handshake_results.handshake_keys.decryption_key,
             self.secure_channels.identities.vault().secure_channel_vault,
             handshake_results.their_identifier.clone(),
-        );
+            last_heartbeat.clone(),
+            usage.clone(),
+            replay_counters.clone(),
+        )
+        .with_credentials(credentials)
+        .with_compression(compression.clone());
+        let decryptor = if handshake_results.noise_interop {
+            decryptor.with_noise_interop(handshake_results.first_nonce)
+        } else {
+            decryptor
+        };
 
         // create a separate encryptor worker which will be started independently
         {
-            let encryptor = EncryptorWorker::new(
+            let heartbeat = match self.heartbeats {
+                Some(_) => Some(
+                    DelayedEvent::create(context, self.addresses.encryptor_heartbeat.clone(), ())
+                        .await?,
+                ),
+                None => None,
+            };
+            let heartbeat_source_address = heartbeat.as_ref().map(|h| h.address());
+
+            let credentials_refresh =
+                match (credentials_exchange.refresh, credentials_refresh_request) {
+                    (Some(refresh), Some(request)) => Some(CredentialsRefreshTimer {
+                        refresh,
+                        event: DelayedEvent::create(
+                            context,
+                            self.addresses.encryptor_credentials_refresh.clone(),
+                            (),
+                        )
+                        .await?,
+                        request,
+                        metrics: self.secure_channels.credentials_refresh_metrics.clone(),
+                    }),
+                    _ => None,
+                };
+            let credentials_refresh_source_address =
+                credentials_refresh.as_ref().map(|r| r.event.address());
+
+            let mut encryptor = EncryptorWorker::new(
                 self.role.str(),
                 self.addresses.clone(),
                 self.remote_route()?,
                 Encryptor::new(
                     handshake_results.handshake_keys.encryption_key,
-                    0,
+                    handshake_results.first_nonce,
                     self.secure_channels.identities.vault().secure_channel_vault,
-                ),
+                )
+                .with_noise_interop(handshake_results.noise_interop),
+                self.heartbeats,
+                heartbeat,
+                last_heartbeat.clone(),
+                usage,
             );
+            if let Some(credentials_refresh) = credentials_refresh {
+                encryptor = encryptor.with_credentials_refresh(credentials_refresh);
+            }
+            encryptor = encryptor.with_compression(compression.clone());
 
             let next_hop = self.remote_route()?.next()?.clone();
             let main_mailbox = Mailbox::new(
@@ -322,8 +459,24 @@ impl HandshakeWorker {
                 Arc::new(AllowAll),
             );
 
+            let mut additional_mailboxes = vec![api_mailbox];
+            if let Some(heartbeat_source_address) = heartbeat_source_address {
+                additional_mailboxes.push(Mailbox::new(
+                    self.addresses.encryptor_heartbeat.clone(),
+                    Arc::new(AllowSourceAddress(heartbeat_source_address)),
+                    Arc::new(DenyAll),
+                ));
+            }
+            if let Some(credentials_refresh_source_address) = credentials_refresh_source_address {
+                additional_mailboxes.push(Mailbox::new(
+                    self.addresses.encryptor_credentials_refresh.clone(),
+                    Arc::new(AllowSourceAddress(credentials_refresh_source_address)),
+                    Arc::new(DenyAll),
+                ));
+            }
+
             WorkerBuilder::new(encryptor)
-                .with_mailboxes(Mailboxes::new(main_mailbox, vec![api_mailbox]))
+                .with_mailboxes(Mailboxes::new(main_mailbox, additional_mailboxes))
                 .start(context)
                 .await?;
         }
@@ -342,6 +495,7 @@ impl HandshakeWorker {
             .expect("the remote route should not be empty")
             .clone();
 
+        let credentials_required = handshake_results.credentials_required;
         let info = SecureChannelRegistryEntry::new(
             self.addresses.encryptor.clone(),
             self.addresses.encryptor_api.clone(),
@@ -351,12 +505,49 @@ impl HandshakeWorker {
             self.identifier.clone(),
             handshake_results.their_identifier,
             their_decryptor_address,
-        );
+        )
+        .with_last_heartbeat(last_heartbeat)
+        .with_cipher_suite(handshake_results.cipher_suite)
+        .with_replay_counters(replay_counters)
+        .with_compression(compression.clone());
 
         self.secure_channels
             .secure_channel_registry()
             .register_channel(info)?;
 
+        // The initiator proposes its compressions, the responder starts compressing the
+        // messages once it selects one of them
+        if self.role.is_initiator() && !compression.supported().is_empty() {
+            context
+                .send_from_address(
+                    route![self.addresses.encryptor.clone()],
+                    ControlMessage::CompressionProposed(compression.supported().to_vec()),
+                    self.addresses.decryptor_remote.clone(),
+                )
+                .await?;
+        }
+
+        // The other side did not present a valid credential, ask for one now that it can be
+        // sent over the channel
+        if credentials_required {
+            if let Some(credentials) = decryptor.credentials.as_ref() {
+                if let Some(request) = credentials.request().await? {
+                    debug!(
+                        "SecureChannel {} requesting a credential for the trust context {}",
+                        self.role.str(),
+                        request.trust_context_id
+                    );
+                    context
+                        .send_from_address(
+                            route![self.addresses.encryptor.clone()],
+                            ControlMessage::CredentialsRequired(request),
+                            self.addresses.decryptor_remote.clone(),
+                        )
+                        .await?;
+                }
+            }
+        }
+
         Ok(decryptor)
     }
 }
@@ -17,7 +17,9 @@ use crate::secure_channel::handshake::handshake_state_machine::{
     Action, CommonStateMachine, Event, HandshakeKeys, HandshakeResults, IdentityAndCredentials,
     StateMachine, Status,
 };
-use crate::{Identities, Role, SecureChannelPurposeKey, TrustContext, TrustPolicy};
+use crate::{
+    Identities, Role, SecureChannelCipherSuite, SecureChannelPurposeKey, TrustContext, TrustPolicy,
+};
 
 /// Implementation of a state machine for the key exchange on the initiator side
 #[async_trait]
@@ -28,17 +30,35 @@ impl StateMachine for InitiatorStateMachine {
             // Initialize the handshake and send message 1
             (Initial, Initialize) => {
                 self.initialize_handshake().await?;
-                let message1 = self.encode_message1(&[]).await?;
+                let cipher_suites_payload = self.make_cipher_suites_payload()?;
+                let message1 = self.encode_message1(&cipher_suites_payload).await?;
 
                 // Send message 1 and wait for message 2
                 self.handshake.state.status = WaitingForMessage2;
                 Ok(SendMessage(message1))
             }
             // Process message 2 and send message 3
+            (WaitingForMessage2, ReceivedMessage(message)) if self.common.noise_interop => {
+                // The identities are exchanged after a plain Noise XX handshake
+                self.decode_message2(&message).await?;
+                let message3 = self.encode_message3(&[]).await?;
+                self.set_final_state(Initiator).await?;
+                self.handshake.wait_for_identity()?;
+                let identity_payload = self
+                    .identity_payload
+                    .take()
+                    .ok_or(XXError::InvalidInternalState)?;
+                let identity_message = self
+                    .handshake
+                    .encrypt_identity_payload(&identity_payload)
+                    .await?;
+                Ok(SendMessages(vec![message3, identity_message]))
+            }
             (WaitingForMessage2, ReceivedMessage(message)) => {
                 let message2_payload = self.decode_message2(&message).await?;
                 let their_identity_payload: IdentityAndCredentials =
                     minicbor::decode(&message2_payload)?;
+                self.accept_selected_cipher_suite(their_identity_payload.cipher_suite)?;
                 self.verify_identity(their_identity_payload, &self.handshake.state.rs()?.clone())
                     .await?;
                 let identity_payload = self
@@ -49,6 +69,16 @@ impl StateMachine for InitiatorStateMachine {
                 self.set_final_state(Initiator).await?;
                 Ok(SendMessage(message3))
             }
+            // Noise interop mode: process the identity sent by the responder
+            (WaitingForIdentity(_), ReceivedMessage(message)) => {
+                let payload = self.handshake.decrypt_identity_payload(&message).await?;
+                let their_identity_payload: IdentityAndCredentials = minicbor::decode(&payload)?;
+                self.accept_selected_cipher_suite(their_identity_payload.cipher_suite)?;
+                self.verify_identity(their_identity_payload, &self.handshake.state.rs()?.clone())
+                    .await?;
+                self.handshake.set_ready()?;
+                Ok(NoAction)
+            }
             // incorrect state / event
             (s, e) => Err(Error::new(
                 Origin::Channel,
@@ -78,6 +108,8 @@ impl InitiatorStateMachine {
     delegate! {
         to self.common {
             async fn verify_identity(&mut self, peer: IdentityAndCredentials, peer_public_key: &X25519PublicKey) -> Result<()>;
+            fn make_cipher_suites_payload(&mut self) -> Result<Vec<u8>>;
+            fn accept_selected_cipher_suite(&mut self, selected: Option<SecureChannelCipherSuite>) -> Result<()>;
             fn make_handshake_results(&self, handshake_keys: Option<HandshakeKeys>) -> Option<HandshakeResults>;
         }
     }
@@ -95,6 +127,7 @@ impl InitiatorStateMachine {
 }
 
 impl InitiatorStateMachine {
+    #[allow(clippy::too_many_arguments)]
     pub async fn new(
         vault: Arc<dyn VaultForSecureChannels>,
         identities: Arc<Identities>,
@@ -103,6 +136,9 @@ impl InitiatorStateMachine {
         credentials: Vec<CredentialAndPurposeKey>,
         trust_policy: Arc<dyn TrustPolicy>,
         trust_context: Option<TrustContext>,
+        noise_interop: bool,
+        cipher_suites: Vec<SecureChannelCipherSuite>,
+        credentials_on_demand: bool,
     ) -> Result<InitiatorStateMachine> {
         let common = CommonStateMachine::new(
             identities,
@@ -111,6 +147,9 @@ impl InitiatorStateMachine {
             credentials,
             trust_policy,
             trust_context,
+            noise_interop,
+            cipher_suites,
+            credentials_on_demand,
         );
         let identity_payload = common.make_identity_payload().await?;
 
@@ -17,7 +17,9 @@ use crate::secure_channel::handshake::handshake_state_machine::{
     Action, CommonStateMachine, Event, HandshakeKeys, HandshakeResults, IdentityAndCredentials,
     StateMachine, Status,
 };
-use crate::{Identities, Role, SecureChannelPurposeKey, TrustContext, TrustPolicy};
+use crate::{
+    Identities, Role, SecureChannelCipherSuite, SecureChannelPurposeKey, TrustContext, TrustPolicy,
+};
 
 /// Implementation of a state machine for the key exchange on the responder side
 #[async_trait]
@@ -33,17 +35,31 @@ impl StateMachine for ResponderStateMachine {
             }
             // Process message 1 and send message 2
             (WaitingForMessage1, ReceivedMessage(message)) => {
-                self.decode_message1(&message).await?;
-                let identity_payload = self
-                    .identity_payload
-                    .take()
-                    .ok_or(XXError::InvalidInternalState)?;
+                let message1_payload = self.decode_message1(&message).await?;
+                self.select_cipher_suite(&message1_payload)?;
+                // In Noise interop mode the identity is sent after the handshake
+                let identity_payload = if self.common.noise_interop {
+                    vec![]
+                } else {
+                    self.take_identity_payload()?
+                };
                 let message2 = self.encode_message2(&identity_payload).await?;
 
                 self.handshake.state.status = WaitingForMessage3;
                 Ok(SendMessage(message2))
             }
             // Process message 3
+            (WaitingForMessage3, ReceivedMessage(message)) if self.common.noise_interop => {
+                self.decode_message3(&message).await?;
+                self.set_final_state(Responder).await?;
+                self.handshake.wait_for_identity()?;
+                let identity_payload = self.take_identity_payload()?;
+                let identity_message = self
+                    .handshake
+                    .encrypt_identity_payload(&identity_payload)
+                    .await?;
+                Ok(SendMessage(identity_message))
+            }
             (WaitingForMessage3, ReceivedMessage(message)) => {
                 let message3_payload = self.decode_message3(&message).await?;
                 let their_identity_payload: IdentityAndCredentials =
@@ -53,6 +69,15 @@ impl StateMachine for ResponderStateMachine {
                 self.set_final_state(Responder).await?;
                 Ok(NoAction)
             }
+            // Noise interop mode: process the identity sent by the initiator
+            (WaitingForIdentity(_), ReceivedMessage(message)) => {
+                let payload = self.handshake.decrypt_identity_payload(&message).await?;
+                let their_identity_payload: IdentityAndCredentials = minicbor::decode(&payload)?;
+                self.verify_identity(their_identity_payload, &self.handshake.state.rs()?.clone())
+                    .await?;
+                self.handshake.set_ready()?;
+                Ok(NoAction)
+            }
             // incorrect state / event
             (s, e) => Err(Error::new(
                 Origin::Channel,
@@ -81,6 +106,8 @@ impl ResponderStateMachine {
     delegate! {
         to self.common {
             async fn verify_identity(&mut self, peer: IdentityAndCredentials, peer_public_key: &X25519PublicKey) -> Result<()>;
+            fn select_cipher_suite(&mut self, message1_payload: &[u8]) -> Result<()>;
+            fn add_selected_cipher_suite(&self, identity_payload: Vec<u8>) -> Result<Vec<u8>>;
             fn make_handshake_results(&self, handshake_keys: Option<HandshakeKeys>) -> Option<HandshakeResults>;
         }
     }
@@ -98,6 +125,7 @@ impl ResponderStateMachine {
 }
 
 impl ResponderStateMachine {
+    #[allow(clippy::too_many_arguments)]
     pub async fn new(
         vault: Arc<dyn VaultForSecureChannels>,
         identities: Arc<Identities>,
@@ -106,6 +134,9 @@ impl ResponderStateMachine {
         credentials: Vec<CredentialAndPurposeKey>,
         trust_policy: Arc<dyn TrustPolicy>,
         trust_context: Option<TrustContext>,
+        noise_interop: bool,
+        cipher_suites: Vec<SecureChannelCipherSuite>,
+        credentials_on_demand: bool,
     ) -> Result<ResponderStateMachine> {
         let common = CommonStateMachine::new(
             identities,
@@ -114,6 +145,9 @@ impl ResponderStateMachine {
             credentials,
             trust_policy,
             trust_context,
+            noise_interop,
+            cipher_suites,
+            credentials_on_demand,
         );
         let identity_payload = common.make_identity_payload().await?;
 
@@ -123,4 +157,13 @@ impl ResponderStateMachine {
             identity_payload: Some(identity_payload),
         })
     }
+
+    /// Return the identity payload to send to the initiator, with the selected cipher suite
+    fn take_identity_payload(&mut self) -> Result<Vec<u8>> {
+        let identity_payload = self
+            .identity_payload
+            .take()
+            .ok_or(XXError::InvalidInternalState)?;
+        self.add_selected_cipher_suite(identity_payload)
+    }
 }
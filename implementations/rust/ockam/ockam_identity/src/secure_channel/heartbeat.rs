@@ -0,0 +1,78 @@
+use core::sync::atomic::{AtomicU64, Ordering};
+use core::time::Duration;
+use ockam_core::compat::sync::Arc;
+
+use crate::models::TimestampInSeconds;
+use crate::utils::now;
+
+/// Heartbeats configuration for a Secure Channel
+///
+/// When heartbeats are enabled a `Ping` is sent to the other side every `interval`,
+/// and the channel is stopped if nothing was heard from the other side for
+/// `interval * miss_threshold`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SecureChannelHeartbeats {
+    pub(crate) interval: Duration,
+    pub(crate) miss_threshold: u32,
+}
+
+impl SecureChannelHeartbeats {
+    /// Create a heartbeats configuration
+    pub fn new(interval: Duration, miss_threshold: u32) -> Self {
+        Self {
+            interval,
+            miss_threshold: miss_threshold.max(1),
+        }
+    }
+
+    /// Time between two heartbeats
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Number of missed heartbeats after which the peer is considered dead
+    pub fn miss_threshold(&self) -> u32 {
+        self.miss_threshold
+    }
+
+    /// Maximum time without hearing from the peer
+    pub(crate) fn max_silence(&self) -> Duration {
+        self.interval * self.miss_threshold
+    }
+}
+
+/// Time of the last heartbeat received on a Secure Channel, shared between the decryptor,
+/// the encryptor and the registry
+#[derive(Clone, Debug, Default)]
+pub(crate) struct LastHeartbeat(Arc<AtomicU64>);
+
+impl LastHeartbeat {
+    /// Record that a heartbeat was just received
+    pub(crate) fn mark(&self) {
+        if let Ok(now) = now() {
+            self.0.store(*now, Ordering::Relaxed)
+        }
+    }
+
+    /// Return the time of the last received heartbeat, if any
+    pub(crate) fn get(&self) -> Option<TimestampInSeconds> {
+        match self.0.load(Ordering::Relaxed) {
+            0 => None,
+            timestamp => Some(TimestampInSeconds(timestamp)),
+        }
+    }
+
+    /// Return true if the peer has been silent for longer than allowed.
+    /// The silence is measured from `started_at` if no heartbeat was received yet
+    pub(crate) fn is_expired(
+        &self,
+        heartbeats: &SecureChannelHeartbeats,
+        started_at: TimestampInSeconds,
+    ) -> bool {
+        let last = self.get().unwrap_or(started_at);
+        match now() {
+            Ok(now) => now.saturating_sub(*last) > heartbeats.max_silence().as_secs(),
+            Err(_) => false,
+        }
+    }
+}
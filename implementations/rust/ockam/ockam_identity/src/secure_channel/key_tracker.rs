@@ -1,3 +1,4 @@
+use ockam_core::compat::collections::VecDeque;
 use ockam_core::Result;
 use ockam_vault::AeadSecretKeyHandle;
 use tracing::debug;
@@ -7,7 +8,9 @@ use crate::IdentityError;
 
 pub(crate) struct KeyTracker {
     pub(crate) current_key: AeadSecretKeyHandle,
-    pub(crate) previous_key: Option<AeadSecretKeyHandle>,
+    /// Keys of the previous intervals, the most recent one first
+    pub(crate) previous_keys: VecDeque<AeadSecretKeyHandle>,
+    max_previous_keys: usize,
     number_of_rekeys: u64,
     max_rekeys_reached: bool,
     renewal_interval: u64,
@@ -19,10 +22,17 @@ impl KeyTracker {
             current_key,
             number_of_rekeys: 0,
             max_rekeys_reached: false,
-            previous_key: None,
+            previous_keys: VecDeque::new(),
+            max_previous_keys: 1,
             renewal_interval,
         }
     }
+
+    /// Keep the keys of that many previous intervals, to decrypt the messages arriving late
+    pub(crate) fn with_previous_keys(mut self, max_previous_keys: usize) -> Self {
+        self.max_previous_keys = max_previous_keys.max(1);
+        self
+    }
 }
 
 impl KeyTracker {
@@ -33,11 +43,11 @@ impl KeyTracker {
     ///
     /// This is either:
     ///   - the current key if the nonce falls into the current interval
-    ///   - the previous key if the nonce falls before the current interval
+    ///   - the key of a previous interval if the nonce falls into one of the kept previous intervals
     ///   - nothing if the the nonce falls after the current interval -> this indicates that a new key must be created
     ///   - an error if
-    ///      - if the the nonce falls before the previous interval
-    ///      - if it the previous nonce but is not set
+    ///      - if the the nonce falls before the kept previous intervals
+    ///      - if the key of that previous interval is not set
     ///      - we reached the maximum number of rekeyings
     pub(crate) fn get_key(&self, nonce: u64) -> Result<Option<AeadSecretKeyHandle>> {
         debug!(
@@ -72,17 +82,18 @@ impl KeyTracker {
                 warn!("This nonce is too far in the future: {}", nonce);
                 Err(IdentityError::InvalidNonce.into())
             }
-        // else return the previous key (if there is one) if the nonce is not too old
-        } else if current_interval_start - nonce <= self.renewal_interval {
-            if let Some(previous) = self.previous_key.clone() {
+        // else return the key of its previous interval (if there is one) if the nonce is not too old
+        } else {
+            let index = (current_interval_start - nonce - 1) / self.renewal_interval;
+            if index >= self.max_previous_keys as u64 {
+                warn!("This nonce is too old: {}", nonce);
+                Err(IdentityError::InvalidNonce.into())
+            } else if let Some(previous) = self.previous_keys.get(index as usize).cloned() {
                 Ok(Some(previous))
             } else {
                 warn!("There should be a previous key for this nonce: {}", nonce);
                 Err(IdentityError::InvalidNonce.into())
             }
-        } else {
-            warn!("This nonce is too old: {}", nonce);
-            Err(IdentityError::InvalidNonce.into())
         }
     }
 
@@ -92,13 +103,13 @@ impl KeyTracker {
         decryption_key: AeadSecretKeyHandle,
     ) -> Result<Option<AeadSecretKeyHandle>> {
         let mut key_to_delete = None;
-        // if the key used for the decryption is not the current key nor the previous key
+        // if the key used for the decryption is not the current key nor a previous key
         // this means that a rekeying happened
-        if decryption_key != self.current_key && Some(decryption_key.clone()) != self.previous_key {
-            if let Some(previous) = self.previous_key.clone() {
-                key_to_delete = Some(previous)
+        if decryption_key != self.current_key && !self.previous_keys.contains(&decryption_key) {
+            self.previous_keys.push_front(self.current_key.clone());
+            if self.previous_keys.len() > self.max_previous_keys {
+                key_to_delete = self.previous_keys.pop_back();
             }
-            self.previous_key.replace(self.current_key.clone());
             self.current_key = decryption_key;
             if u64::MAX - self.number_of_rekeys * self.renewal_interval < self.renewal_interval {
                 self.max_rekeys_reached = true;
@@ -153,7 +164,8 @@ mod tests {
             current_key: handle.clone(),
             number_of_rekeys: 5,
             max_rekeys_reached: false,
-            previous_key: Some(previous_handle.clone()),
+            previous_keys: VecDeque::from(vec![previous_handle.clone()]),
+            max_previous_keys: 1,
             renewal_interval: 10,
         };
 
@@ -207,7 +219,8 @@ mod tests {
             current_key: handle,
             number_of_rekeys: 5,
             max_rekeys_reached: true,
-            previous_key: Some(previous_handle),
+            previous_keys: VecDeque::from(vec![previous_handle]),
+            max_previous_keys: 1,
             renewal_interval: 10,
         };
 
@@ -233,7 +246,8 @@ mod tests {
             current_key: handle.clone(),
             number_of_rekeys: 5,
             max_rekeys_reached: false,
-            previous_key: Some(previous_handle.clone()),
+            previous_keys: VecDeque::from(vec![previous_handle.clone()]),
+            max_previous_keys: 1,
             renewal_interval: 10,
         };
 
@@ -248,7 +262,7 @@ mod tests {
             "the previous key id must be returned in order to be deleted",
         );
         assert_eq!(key_tracker.current_key, new_handle);
-        assert_eq!(key_tracker.previous_key, Some(handle));
+        assert_eq!(key_tracker.previous_keys, VecDeque::from(vec![handle]));
     }
 
     #[test]
@@ -266,7 +280,8 @@ mod tests {
             current_key: handle,
             number_of_rekeys: u64::MAX / 10 - 1,
             max_rekeys_reached: false,
-            previous_key: Some(previous_handle),
+            previous_keys: VecDeque::from(vec![previous_handle]),
+            max_previous_keys: 1,
             renewal_interval: 10,
         };
 
@@ -287,4 +302,30 @@ mod tests {
             "the maximum number of rekeys is reached now"
         );
     }
+
+    #[test]
+    fn test_keys_of_several_previous_intervals() {
+        let handles: Vec<AeadSecretKeyHandle> = (0..4)
+            .map(|i| AeadSecretKeyHandle(Aes256GcmSecretKeyHandle(HandleToSecret::new(vec![i]))))
+            .collect();
+        let mut key_tracker = KeyTracker::new(handles[0].clone(), 10).with_previous_keys(2);
+
+        assert_eq!(key_tracker.update_key(handles[1].clone()).unwrap(), None);
+        assert_eq!(key_tracker.update_key(handles[2].clone()).unwrap(), None);
+        assert_eq!(key_tracker.get_key(25).unwrap(), Some(handles[2].clone()));
+        assert_eq!(key_tracker.get_key(15).unwrap(), Some(handles[1].clone()));
+        assert_eq!(key_tracker.get_key(5).unwrap(), Some(handles[0].clone()));
+
+        assert_eq!(
+            key_tracker.update_key(handles[3].clone()).unwrap(),
+            Some(handles[0].clone()),
+            "the oldest key must be returned in order to be deleted",
+        );
+        assert_eq!(key_tracker.get_key(15).unwrap(), Some(handles[1].clone()));
+        assert_eq!(
+            key_tracker.get_key(9).ok(),
+            None,
+            "this nonce is too far in the past"
+        );
+    }
 }
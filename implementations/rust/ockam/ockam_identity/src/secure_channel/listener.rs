@@ -1,11 +1,14 @@
 use ockam_core::compat::boxed::Box;
+use ockam_core::compat::collections::VecDeque;
 use ockam_core::compat::sync::Arc;
 use ockam_core::compat::vec::Vec;
-use ockam_core::{Address, Any, Result, Routed, Worker};
-use ockam_node::Context;
+use ockam_core::{Address, AllowAll, Any, DenyAll, Mailbox, Mailboxes, Result, Routed, Worker};
+use ockam_node::{Context, WorkerBuilder};
+use tracing::{debug, warn};
 
 use crate::models::{CredentialAndPurposeKey, Identifier};
 use crate::secure_channel::addresses::Addresses;
+use crate::secure_channel::admission::{Admission, AdmissionTicket, SecureChannelListenerLimits};
 use crate::secure_channel::handshake_worker::HandshakeWorker;
 use crate::secure_channel::options::SecureChannelListenerOptions;
 use crate::secure_channel::role::Role;
@@ -15,6 +18,10 @@ pub(crate) struct IdentityChannelListener {
     secure_channels: Arc<SecureChannels>,
     identifier: Identifier,
     options: SecureChannelListenerOptions,
+    /// Handshakes waiting for a handshake in progress to complete
+    queued_handshakes: VecDeque<Routed<Any>>,
+    /// Address notified by the handshake workers when they complete or stop
+    admission_address: Address,
 }
 
 impl IdentityChannelListener {
@@ -22,11 +29,14 @@ impl IdentityChannelListener {
         secure_channels: Arc<SecureChannels>,
         identifier: Identifier,
         options: SecureChannelListenerOptions,
+        admission_address: Address,
     ) -> Self {
         Self {
             secure_channels,
             identifier,
             options,
+            queued_handshakes: VecDeque::new(),
+            admission_address,
         }
     }
 
@@ -39,9 +49,22 @@ impl IdentityChannelListener {
     ) -> Result<()> {
         options.setup_flow_control_for_listener(ctx.flow_controls(), &address);
 
-        let listener = Self::new(secure_channels.clone(), identifier.clone(), options);
+        let admission_address = Address::random_tagged("IdentityChannelListener.admission");
+        let listener = Self::new(
+            secure_channels.clone(),
+            identifier.clone(),
+            options,
+            admission_address.clone(),
+        );
 
-        ctx.start_worker(address, listener).await?;
+        let main_mailbox = Mailbox::new(address, Arc::new(AllowAll), Arc::new(AllowAll));
+        // only receives the notifications of the local handshake workers
+        let admission_mailbox =
+            Mailbox::new(admission_address, Arc::new(AllowAll), Arc::new(DenyAll));
+        WorkerBuilder::new(listener)
+            .with_mailboxes(Mailboxes::new(main_mailbox, vec![admission_mailbox]))
+            .start(ctx)
+            .await?;
 
         Ok(())
     }
@@ -65,19 +88,104 @@ impl IdentityChannelListener {
         };
         Ok(credentials)
     }
-}
 
-#[ockam_core::worker]
-impl Worker for IdentityChannelListener {
-    type Message = Any;
-    type Context = Context;
+    /// Admit a new handshake, queue it or reject it depending on the listener limits.
+    /// The workers of the handshakes which timed out are stopped to make room for new ones
+    async fn admit(&mut self, ctx: &mut Context, message: Routed<Any>) -> Result<()> {
+        let limits = match &self.options.limits {
+            Some(limits) => limits.clone(),
+            None => {
+                let addresses = Addresses::generate(Role::Responder);
+                return self.start_handshake(ctx, message, addresses, None).await;
+            }
+        };
 
-    async fn handle_message(
+        let (addresses, admission) = self.try_admit(ctx, &limits).await;
+        match admission {
+            Admission::Admitted(ticket) => {
+                self.start_handshake(ctx, message, addresses, Some(ticket))
+                    .await
+            }
+            Admission::HandshakesLimit if limits.can_queue(self.queued_handshakes.len()) => {
+                debug!("queueing a handshake since the maximum number of handshakes is reached");
+                self.queued_handshakes.push_back(message);
+                Ok(())
+            }
+            Admission::HandshakesLimit => {
+                limits.record_rejection();
+                warn!(
+                    "rejecting a handshake from {} since the maximum number of handshakes is reached",
+                    message.return_route()
+                );
+                Ok(())
+            }
+            Admission::ChannelsLimit => {
+                limits.record_rejection();
+                warn!(
+                    "rejecting a handshake from {} since the maximum number of channels is reached",
+                    message.return_route()
+                );
+                Ok(())
+            }
+        }
+    }
+
+    /// Admit the queued handshakes while the limits allow it
+    async fn admit_queued_handshakes(&mut self, ctx: &mut Context) -> Result<()> {
+        let limits = match &self.options.limits {
+            Some(limits) => limits.clone(),
+            // handshakes are only queued when there are limits
+            None => return Ok(()),
+        };
+
+        while let Some(message) = self.queued_handshakes.pop_front() {
+            let (addresses, admission) = self.try_admit(ctx, &limits).await;
+            match admission {
+                Admission::Admitted(ticket) => {
+                    self.start_handshake(ctx, message, addresses, Some(ticket))
+                        .await?
+                }
+                Admission::HandshakesLimit => {
+                    self.queued_handshakes.push_front(message);
+                    return Ok(());
+                }
+                Admission::ChannelsLimit => {
+                    limits.record_rejection();
+                    warn!(
+                        "rejecting a queued handshake from {} since the maximum number of channels is reached",
+                        message.return_route()
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Try to admit a handshake with new responder addresses, stopping the workers
+    /// of the handshakes which timed out
+    async fn try_admit(
+        &self,
+        ctx: &Context,
+        limits: &SecureChannelListenerLimits,
+    ) -> (Addresses, Admission) {
+        let addresses = Addresses::generate(Role::Responder);
+        let (admission, evicted) =
+            limits.admit(&addresses.decryptor_remote, &self.admission_address);
+        for address in evicted {
+            debug!("stopping the handshake at {address} which timed out");
+            let _ = ctx.stop_worker(address).await;
+        }
+        (addresses, admission)
+    }
+
+    /// Start a handshake worker and forward it the first message of the handshake
+    async fn start_handshake(
         &mut self,
-        ctx: &mut Self::Context,
-        message: Routed<Self::Message>,
+        ctx: &mut Context,
+        message: Routed<Any>,
+        addresses: Addresses,
+        admission: Option<AdmissionTicket>,
     ) -> Result<()> {
-        let addresses = Addresses::generate(Role::Responder);
         let flow_control_id = self.options.setup_flow_control_for_channel(
             ctx.flow_controls(),
             &addresses,
@@ -110,6 +218,13 @@ impl Worker for IdentityChannelListener {
             self.options.trust_context.clone(),
             None,
             None,
+            self.options.heartbeats,
+            self.options.noise_interop,
+            self.options.cipher_suites.clone(),
+            self.options.replay_window,
+            self.options.credentials_exchange.clone(),
+            self.options.compression.clone(),
+            admission,
             Role::Responder,
         )
         .await?;
@@ -124,3 +239,22 @@ impl Worker for IdentityChannelListener {
         ctx.forward(local_message).await
     }
 }
+
+#[ockam_core::worker]
+impl Worker for IdentityChannelListener {
+    type Message = Any;
+    type Context = Context;
+
+    async fn handle_message(
+        &mut self,
+        ctx: &mut Self::Context,
+        message: Routed<Self::Message>,
+    ) -> Result<()> {
+        if message.msg_addr() == self.admission_address {
+            // a handshake completed or stopped
+            self.admit_queued_handshakes(ctx).await
+        } else {
+            self.admit(ctx, message).await
+        }
+    }
+}
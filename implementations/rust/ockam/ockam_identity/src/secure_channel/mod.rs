@@ -1,35 +1,61 @@
 /// Access control data for workers
 pub mod access_control;
 mod addresses;
+mod admission;
 mod api;
+mod cipher_suite;
+mod compression;
+mod control;
+mod credentials_exchange;
+mod credentials_refresh;
 mod decryptor;
 mod encryptor;
 mod encryptor_worker;
 mod handshake;
+mod heartbeat;
 mod key_tracker;
 mod listener;
 mod local_info;
 mod nonce_tracker;
 mod options;
+mod peer;
+mod peers;
 mod registry;
 mod role;
 /// List of trust policies to setup ABAC controls
 pub mod trust_policy;
+mod usage;
 
 pub use access_control::*;
 pub(crate) use addresses::*;
+pub use admission::{SecureChannelListenerLimits, DEFAULT_HANDSHAKE_TIMEOUT};
 pub use api::*;
+pub use cipher_suite::*;
+pub(crate) use compression::ChannelCompression;
+pub use compression::{CompressionStats, SecureChannelCompression};
+pub(crate) use control::ControlMessage;
+pub(crate) use credentials_exchange::*;
+pub use credentials_refresh::*;
 pub(crate) use handshake::*;
+pub(crate) use heartbeat::LastHeartbeat;
+pub use heartbeat::SecureChannelHeartbeats;
 pub(crate) use listener::*;
 pub use local_info::*;
+pub(crate) use nonce_tracker::ReplayCounters;
+pub use nonce_tracker::{ReplayProtectionStats, DEFAULT_REPLAY_WINDOW, MAX_REPLAY_WINDOW};
 pub use options::*;
+pub use peer::*;
+pub use peers::{PeerActivity, PeersRegistry};
 pub use registry::*;
 pub(crate) use role::*;
 pub use trust_policy::*;
+pub(crate) use usage::ChannelUsage;
+pub use usage::{BandwidthUsage, BandwidthUsageRegistry};
 
 #[cfg(test)]
 mod tests {
-    use crate::secure_channel::{decryptor::Decryptor, encryptor::Encryptor};
+    use crate::secure_channel::decryptor::Decryptor;
+    use crate::secure_channel::encryptor::{frame, unframe, Encryptor};
     use ockam_core::compat::rand::RngCore;
     use ockam_core::Result;
     use ockam_vault::{SoftwareVaultForSecureChannels, VaultForSecureChannels};
@@ -131,6 +157,43 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_encrypt_decrypt_noise_interop() {
+        let (encryptor, decryptor) = create_encryptor_decryptor().await.unwrap();
+        let mut encryptor = encryptor.with_noise_interop(true);
+        let mut decryptor = decryptor.with_noise_interop(0);
+
+        // the messages are decrypted in order, past the key renewal interval
+        let mut ciphertexts = vec![];
+        for n in 0..100 {
+            let msg = vec![n];
+            let ciphertext = encryptor.encrypt(&msg).await.unwrap();
+            // a ciphertext and its tag, without any nonce
+            assert_eq!(ciphertext.len(), msg.len() + 16);
+            assert_eq!(msg, decryptor.decrypt(&ciphertext).await.unwrap());
+            ciphertexts.push(ciphertext);
+        }
+
+        // the nonces are implicit: replayed and skipped messages are rejected
+        assert!(decryptor.decrypt(&ciphertexts[99]).await.is_err());
+        let _lost = encryptor.encrypt(&[1]).await.unwrap();
+        let msg = encryptor.encrypt(&[2]).await.unwrap();
+        assert!(decryptor.decrypt(&msg).await.is_err());
+    }
+
+    #[test]
+    fn test_frame_noise_interop() {
+        let message = vec![1, 2, 3];
+        let framed: Vec<u8> = frame(message.clone(), false).unwrap().into();
+        assert_eq!(framed, vec![3, 1, 2, 3]);
+        assert_eq!(unframe(&framed, false).unwrap(), message);
+
+        // Noise messages are sent as they are
+        let framed: Vec<u8> = frame(message.clone(), true).unwrap().into();
+        assert_eq!(framed, message);
+        assert_eq!(unframe(&framed, true).unwrap(), message);
+    }
+
     async fn create_encryptor_decryptor() -> Result<(Encryptor, Decryptor)> {
         let vault1 = SoftwareVaultForSecureChannels::create();
         let vault2 = SoftwareVaultForSecureChannels::create();
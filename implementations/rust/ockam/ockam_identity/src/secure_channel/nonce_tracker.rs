@@ -1,65 +1,201 @@
+use core::sync::atomic::{AtomicU64, Ordering};
+use ockam_core::compat::sync::Arc;
+use ockam_core::compat::vec::Vec;
+
 use crate::secure_channel::encryptor::KEY_RENEWAL_INTERVAL;
 use crate::IdentityError;
 
-/// fails compilation if [`KEY_RENEWAL_INTERVAL`] + 1 is bigger than [`BitmapType::BITS`].
-///
-/// the +1 is needed since the current nonce is also marked as received, taking an extra bit
-/// even though we could check `current_nonce`, this compromise is for the sake of simplicity
-const _: [(); (KEY_RENEWAL_INTERVAL + 1 > BitmapType::BITS as u64) as usize] = [];
 type BitmapType = u64;
+const BITMAP_TYPE_BITS: u64 = BitmapType::BITS as u64;
+
+/// Default replay window of a Secure Channel.
+///
+/// Only the current and the previous keys need to be kept after a rekeying to decrypt
+/// the messages of that window
+pub const DEFAULT_REPLAY_WINDOW: u64 = KEY_RENEWAL_INTERVAL;
+
+/// Largest replay window of a Secure Channel.
+///
+/// One key per [`KEY_RENEWAL_INTERVAL`] messages of the window is kept after a rekeying
+/// to decrypt the messages arriving late
+pub const MAX_REPLAY_WINDOW: u64 = 64 * KEY_RENEWAL_INTERVAL;
+
+/// Return the number of previous keys to keep to decrypt the messages of a replay window
+pub(crate) fn previous_keys_for_window(window: u64) -> usize {
+    let window = window.min(MAX_REPLAY_WINDOW);
+    (((window + KEY_RENEWAL_INTERVAL - 1) / KEY_RENEWAL_INTERVAL) as usize).max(1)
+}
 
-#[derive(Debug)]
+/// Reason for rejecting a nonce
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum NonceRejection {
+    /// The nonce is too old for the replay window, or too far ahead of the last received nonce
+    OutOfWindow,
+    /// A message with the same nonce was already received
+    Duplicate,
+}
+
+impl From<NonceRejection> for ockam_core::Error {
+    fn from(_: NonceRejection) -> Self {
+        IdentityError::InvalidNonce.into()
+    }
+}
+
+#[derive(Clone, Debug)]
 pub(crate) struct NonceTracker {
-    nonce_bitmap: BitmapType,
+    /// Received nonces of the window. The bitmap is used as a ring: the bit of a nonce `n`
+    /// is the bit `n % bits` of the bitmap, and it is cleared when the window moves past it
+    nonce_bitmap: Vec<BitmapType>,
     current_nonce: u64,
+    window: u64,
 }
 
 impl NonceTracker {
-    pub(crate) fn new() -> Self {
+    /// Create a tracker accepting messages arriving at most `window` nonces
+    /// behind the most recent one
+    pub(crate) fn new(window: u64) -> Self {
+        let window = window.min(MAX_REPLAY_WINDOW);
+        // the current nonce is also marked as received, taking an extra bit
+        let words = window / BITMAP_TYPE_BITS + 1;
         Self {
-            nonce_bitmap: 0,
+            nonce_bitmap: vec![0; words as usize],
             current_nonce: 0,
+            window,
+        }
+    }
+
+    /// Return the index of the word of the bitmap containing the bit of that nonce, and the bit
+    fn position(&self, nonce: u64) -> (usize, BitmapType) {
+        let bit = nonce % (self.nonce_bitmap.len() as u64 * BITMAP_TYPE_BITS);
+        #[allow(trivial_numeric_casts)]
+        let mask = (1 as BitmapType) << (bit % BITMAP_TYPE_BITS);
+        ((bit / BITMAP_TYPE_BITS) as usize, mask)
+    }
+
+    fn is_marked(&self, nonce: u64) -> bool {
+        let (word, mask) = self.position(nonce);
+        self.nonce_bitmap[word] & mask != 0
+    }
+
+    fn set(&mut self, nonce: u64, marked: bool) {
+        let (word, mask) = self.position(nonce);
+        if marked {
+            self.nonce_bitmap[word] |= mask;
+        } else {
+            self.nonce_bitmap[word] &= !mask;
         }
     }
 
+    /// Return true if a message with that nonce arrives after a more recent message
+    pub(crate) fn is_reordered(&self, nonce: u64) -> bool {
+        nonce < self.current_nonce
+    }
+
     /// Mark a nonce as received, reject all invalid nonce values
-    pub(crate) fn mark(&self, nonce: u64) -> ockam_core::Result<NonceTracker> {
+    pub(crate) fn mark(&self, nonce: u64) -> Result<NonceTracker, NonceRejection> {
         let new_tracker = if nonce > self.current_nonce {
             // normal case, we increase the nonce and move the window
             let relative_shift: u64 = nonce - self.current_nonce;
             if relative_shift > KEY_RENEWAL_INTERVAL {
-                return Err(IdentityError::InvalidNonce.into());
+                return Err(NonceRejection::OutOfWindow);
             }
-            NonceTracker {
-                nonce_bitmap: self.nonce_bitmap.overflowing_shl(relative_shift as u32).0 | 1,
-                current_nonce: nonce,
+            let mut new_tracker = self.clone();
+            // the nonces skipped by the window are not received yet
+            for skipped in self.current_nonce + 1..nonce {
+                new_tracker.set(skipped, false);
             }
+            new_tracker.set(nonce, true);
+            new_tracker.current_nonce = nonce;
+            new_tracker
         } else {
             // first message or an out of order message
             let relative: u64 = self.current_nonce - nonce;
-            if relative > KEY_RENEWAL_INTERVAL {
-                return Err(IdentityError::InvalidNonce.into());
+            if relative > self.window {
+                return Err(NonceRejection::OutOfWindow);
             }
 
-            #[allow(trivial_numeric_casts)]
-            let bit = (1 as BitmapType).overflowing_shl(relative as u32).0;
-            if self.nonce_bitmap & bit != 0 {
+            if self.is_marked(nonce) {
                 // we already processed this nonce
-                return Err(IdentityError::InvalidNonce.into());
-            }
-            NonceTracker {
-                nonce_bitmap: self.nonce_bitmap | bit,
-                current_nonce: self.current_nonce,
+                return Err(NonceRejection::Duplicate);
             }
+            let mut new_tracker = self.clone();
+            new_tracker.set(nonce, true);
+            new_tracker
         };
 
         Ok(new_tracker)
     }
 }
 
+/// Replay protection counters of a Secure Channel
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ReplayProtectionStats {
+    /// Number of nonces a message can lag behind the most recent one and still be accepted
+    pub window: u64,
+    /// Messages dropped because they were too old for the window, or too far ahead
+    pub out_of_window: u64,
+    /// Messages dropped because they had already been received
+    pub duplicates: u64,
+    /// Messages accepted even though a more recent message had already been received
+    pub reordered: u64,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    out_of_window: AtomicU64,
+    duplicates: AtomicU64,
+    reordered: AtomicU64,
+}
+
+/// Replay protection counters, shared between the decryptor and the registry
+#[derive(Clone, Debug)]
+pub(crate) struct ReplayCounters {
+    window: u64,
+    counters: Arc<Counters>,
+}
+
+impl Default for ReplayCounters {
+    fn default() -> Self {
+        Self::new(DEFAULT_REPLAY_WINDOW)
+    }
+}
+
+impl ReplayCounters {
+    pub(crate) fn new(window: u64) -> Self {
+        Self {
+            window: window.min(MAX_REPLAY_WINDOW),
+            counters: Default::default(),
+        }
+    }
+
+    /// Record a rejected message
+    pub(crate) fn rejected(&self, rejection: NonceRejection) {
+        let counter = match rejection {
+            NonceRejection::OutOfWindow => &self.counters.out_of_window,
+            NonceRejection::Duplicate => &self.counters.duplicates,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record an accepted message which arrived out of order
+    pub(crate) fn reordered(&self) {
+        self.counters.reordered.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Return the current value of the counters
+    pub(crate) fn get(&self) -> ReplayProtectionStats {
+        ReplayProtectionStats {
+            window: self.window,
+            out_of_window: self.counters.out_of_window.load(Ordering::Relaxed),
+            duplicates: self.counters.duplicates.load(Ordering::Relaxed),
+            reordered: self.counters.reordered.load(Ordering::Relaxed),
+        }
+    }
+}
+
 #[test]
 pub fn check_nonce_tracker() {
-    let mut tracker = NonceTracker::new();
+    let mut tracker = NonceTracker::new(DEFAULT_REPLAY_WINDOW);
     tracker = tracker.mark(0).unwrap();
     tracker = tracker.mark(1).unwrap();
     tracker.mark(0).unwrap_err();
@@ -81,3 +217,62 @@ pub fn check_nonce_tracker() {
         tracker = tracker.mark(n).unwrap();
     }
 }
+
+#[test]
+pub fn check_nonce_tracker_window() {
+    let mut tracker = NonceTracker::new(2);
+    tracker = tracker.mark(0).unwrap();
+    tracker = tracker.mark(4).unwrap();
+    assert_eq!(tracker.mark(1).unwrap_err(), NonceRejection::OutOfWindow);
+    assert!(tracker.is_reordered(2));
+    tracker = tracker.mark(2).unwrap();
+    assert_eq!(tracker.mark(2).unwrap_err(), NonceRejection::Duplicate);
+    assert_eq!(tracker.mark(4).unwrap_err(), NonceRejection::Duplicate);
+    // the forward jump is not limited by the window
+    tracker = tracker.mark(4 + KEY_RENEWAL_INTERVAL).unwrap();
+    assert!(!tracker.is_reordered(5 + KEY_RENEWAL_INTERVAL));
+
+    // a window of 0 only accepts messages in order
+    let mut tracker = NonceTracker::new(0);
+    tracker = tracker.mark(0).unwrap();
+    tracker = tracker.mark(2).unwrap();
+    assert_eq!(tracker.mark(1).unwrap_err(), NonceRejection::OutOfWindow);
+}
+
+#[test]
+pub fn check_nonce_tracker_large_window() {
+    let window = 4 * KEY_RENEWAL_INTERVAL;
+    let mut tracker = NonceTracker::new(window);
+    for n in 0..=window + 11 {
+        if n != 10 && n != 11 {
+            tracker = tracker.mark(n).unwrap();
+        }
+    }
+    // nonces older than the default window are accepted within the larger window
+    tracker = tracker.mark(11).unwrap();
+    assert_eq!(tracker.mark(11).unwrap_err(), NonceRejection::Duplicate);
+    assert_eq!(tracker.mark(10).unwrap_err(), NonceRejection::OutOfWindow);
+
+    // the bits reused by the ring are cleared when the window moves past them
+    let skipped = 2 * window + 5;
+    for n in window + 12..=2 * window + 10 {
+        if n != skipped {
+            tracker = tracker.mark(n).unwrap();
+        }
+    }
+    tracker = tracker.mark(skipped).unwrap();
+    assert_eq!(
+        tracker.mark(skipped).unwrap_err(),
+        NonceRejection::Duplicate
+    );
+
+    assert_eq!(NonceTracker::new(u64::MAX).window, MAX_REPLAY_WINDOW);
+}
+
+#[test]
+pub fn check_previous_keys_for_window() {
+    assert_eq!(previous_keys_for_window(0), 1);
+    assert_eq!(previous_keys_for_window(DEFAULT_REPLAY_WINDOW), 1);
+    assert_eq!(previous_keys_for_window(DEFAULT_REPLAY_WINDOW + 1), 2);
+    assert_eq!(previous_keys_for_window(u64::MAX), 64);
+}
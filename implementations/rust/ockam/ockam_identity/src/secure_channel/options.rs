@@ -1,11 +1,16 @@
+use ockam_core::compat::string::String;
 use ockam_core::compat::sync::Arc;
 use ockam_core::compat::vec::Vec;
 use ockam_core::flow_control::{FlowControlId, FlowControlOutgoingAccessControl, FlowControls};
 use ockam_core::{Address, OutgoingAccessControl, Result};
 
 use crate::models::CredentialAndPurposeKey;
-use crate::secure_channel::Addresses;
-use crate::{TrustContext, TrustEveryonePolicy, TrustPolicy};
+use crate::secure_channel::{
+    Addresses, CredentialsExchange, SecureChannelCipherSuite, SecureChannelCompression,
+    SecureChannelCredentialsRefresh, SecureChannelHeartbeats, SecureChannelListenerLimits,
+    DEFAULT_REPLAY_WINDOW, MAX_REPLAY_WINDOW,
+};
+use crate::{CredentialsRetriever, TrustContext, TrustEveryonePolicy, TrustPolicy};
 
 use core::fmt;
 use core::fmt::Formatter;
@@ -21,6 +26,12 @@ pub struct SecureChannelOptions {
     pub(crate) trust_context: Option<TrustContext>,
     pub(crate) credentials: Vec<CredentialAndPurposeKey>,
     pub(crate) timeout: Duration,
+    pub(crate) heartbeats: Option<SecureChannelHeartbeats>,
+    pub(crate) noise_interop: bool,
+    pub(crate) cipher_suites: Vec<SecureChannelCipherSuite>,
+    pub(crate) replay_window: u64,
+    pub(crate) credentials_exchange: CredentialsExchange,
+    pub(crate) compression: Vec<SecureChannelCompression>,
 }
 
 impl fmt::Debug for SecureChannelOptions {
@@ -43,6 +54,12 @@ impl SecureChannelOptions {
             trust_context: None,
             credentials: vec![],
             timeout: DEFAULT_TIMEOUT,
+            heartbeats: None,
+            noise_interop: false,
+            cipher_suites: vec![],
+            replay_window: DEFAULT_REPLAY_WINDOW,
+            credentials_exchange: CredentialsExchange::default(),
+            compression: vec![],
         }
     }
 
@@ -76,6 +93,72 @@ impl SecureChannelOptions {
         self
     }
 
+    /// Send heartbeats every `interval` and stop the channel after `miss_threshold`
+    /// intervals without hearing from the other side
+    pub fn with_heartbeats(mut self, interval: Duration, miss_threshold: u32) -> Self {
+        self.heartbeats = Some(SecureChannelHeartbeats::new(interval, miss_threshold));
+        self
+    }
+
+    /// Run a plain Noise XX handshake and exchange identities in the first transport messages.
+    /// The other side must use the same mode
+    #[cfg(feature = "noise_interop")]
+    pub fn with_noise_interop(mut self) -> Self {
+        self.noise_interop = true;
+        self
+    }
+
+    /// Propose these cipher suites to the listener, by order of preference.
+    /// The channel creation fails if none of them is accepted by the listener
+    pub fn with_cipher_suites(mut self, cipher_suites: Vec<SecureChannelCipherSuite>) -> Self {
+        self.cipher_suites = cipher_suites;
+        self
+    }
+
+    /// Accept messages arriving at most `window` messages behind the most recent one.
+    /// A window of 0 drops all reordered messages. The default is [`DEFAULT_REPLAY_WINDOW`],
+    /// a larger window, up to [`MAX_REPLAY_WINDOW`], keeps more keys to decrypt late messages
+    pub fn with_replay_window(mut self, window: u64) -> Self {
+        self.replay_window = window.min(MAX_REPLAY_WINDOW);
+        self
+    }
+
+    /// Create the channel even if the listener does not present a valid credential for the
+    /// trust context, and ask it to present one once the channel is created.
+    /// Until then, the listener identity has no attributes for the trust context
+    pub fn with_credentials_on_demand(mut self) -> Self {
+        self.credentials_exchange.on_demand = true;
+        self
+    }
+
+    /// Present a credential obtained with `retriever` when the listener requires
+    /// a credential for the trust context `trust_context_id`
+    pub fn with_credentials_retriever(
+        mut self,
+        trust_context_id: impl Into<String>,
+        retriever: Arc<dyn CredentialsRetriever>,
+    ) -> Self {
+        self.credentials_exchange
+            .retrievers
+            .insert(trust_context_id.into(), retriever);
+        self
+    }
+
+    /// Ask the listener for fresh credentials every `interval`, plus or minus a random `jitter`,
+    /// instead of the default refresh of the secure channels
+    pub fn with_credentials_refresh(mut self, interval: Duration, jitter: Duration) -> Self {
+        self.credentials_exchange.refresh =
+            Some(SecureChannelCredentialsRefresh::new(interval, jitter));
+        self
+    }
+
+    /// Propose to compress the messages of the channel with `compression`, before they
+    /// are encrypted. The messages are only compressed if the listener accepts it
+    pub fn with_compression(mut self, compression: SecureChannelCompression) -> Self {
+        self.compression.push(compression);
+        self
+    }
+
     /// Freshly generated [`FlowControlId`]
     pub fn producer_flow_control_id(&self) -> FlowControlId {
         self.flow_control_id.clone()
@@ -130,6 +213,13 @@ pub struct SecureChannelListenerOptions {
     pub(crate) trust_policy: Arc<dyn TrustPolicy>,
     pub(crate) trust_context: Option<TrustContext>,
     pub(crate) credentials: Vec<CredentialAndPurposeKey>,
+    pub(crate) heartbeats: Option<SecureChannelHeartbeats>,
+    pub(crate) noise_interop: bool,
+    pub(crate) cipher_suites: Vec<SecureChannelCipherSuite>,
+    pub(crate) replay_window: u64,
+    pub(crate) limits: Option<SecureChannelListenerLimits>,
+    pub(crate) credentials_exchange: CredentialsExchange,
+    pub(crate) compression: Vec<SecureChannelCompression>,
 }
 
 impl fmt::Debug for SecureChannelListenerOptions {
@@ -150,6 +240,13 @@ impl SecureChannelListenerOptions {
             trust_policy: Arc::new(TrustEveryonePolicy),
             trust_context: None,
             credentials: vec![],
+            heartbeats: None,
+            noise_interop: false,
+            cipher_suites: vec![],
+            replay_window: DEFAULT_REPLAY_WINDOW,
+            limits: None,
+            credentials_exchange: CredentialsExchange::default(),
+            compression: vec![],
         }
     }
 
@@ -186,6 +283,79 @@ impl SecureChannelListenerOptions {
         self
     }
 
+    /// Send heartbeats on spawned channels every `interval` and stop them after
+    /// `miss_threshold` intervals without hearing from the other side
+    pub fn with_heartbeats(mut self, interval: Duration, miss_threshold: u32) -> Self {
+        self.heartbeats = Some(SecureChannelHeartbeats::new(interval, miss_threshold));
+        self
+    }
+
+    /// Accept channels using a plain Noise XX handshake, with identities exchanged in the
+    /// first transport messages
+    #[cfg(feature = "noise_interop")]
+    pub fn with_noise_interop(mut self) -> Self {
+        self.noise_interop = true;
+        self
+    }
+
+    /// Only accept channels using one of these cipher suites.
+    /// By default, any cipher suite available in the vault is accepted
+    pub fn with_cipher_suites(mut self, cipher_suites: Vec<SecureChannelCipherSuite>) -> Self {
+        self.cipher_suites = cipher_suites;
+        self
+    }
+
+    /// Accept messages arriving at most `window` messages behind the most recent one
+    /// on spawned channels. The default is [`DEFAULT_REPLAY_WINDOW`], and the window cannot be
+    /// larger than [`MAX_REPLAY_WINDOW`]
+    pub fn with_replay_window(mut self, window: u64) -> Self {
+        self.replay_window = window.min(MAX_REPLAY_WINDOW);
+        self
+    }
+
+    /// Limit the handshakes in progress and the channels created by the listener.
+    /// The limits can be changed while the listener is running
+    pub fn with_limits(mut self, limits: SecureChannelListenerLimits) -> Self {
+        self.limits = Some(limits);
+        self
+    }
+
+    /// Accept channels from initiators which do not present a valid credential for the
+    /// trust context, and ask them to present one once the channel is created.
+    /// Until then, the initiator identity has no attributes for the trust context
+    pub fn with_credentials_on_demand(mut self) -> Self {
+        self.credentials_exchange.on_demand = true;
+        self
+    }
+
+    /// Present a credential obtained with `retriever` when an initiator requires
+    /// a credential for the trust context `trust_context_id`
+    pub fn with_credentials_retriever(
+        mut self,
+        trust_context_id: impl Into<String>,
+        retriever: Arc<dyn CredentialsRetriever>,
+    ) -> Self {
+        self.credentials_exchange
+            .retrievers
+            .insert(trust_context_id.into(), retriever);
+        self
+    }
+
+    /// Ask the initiators of the spawned channels for fresh credentials every `interval`,
+    /// plus or minus a random `jitter`, instead of the default refresh of the secure channels
+    pub fn with_credentials_refresh(mut self, interval: Duration, jitter: Duration) -> Self {
+        self.credentials_exchange.refresh =
+            Some(SecureChannelCredentialsRefresh::new(interval, jitter));
+        self
+    }
+
+    /// Accept to compress the messages of the spawned channels with `compression`,
+    /// when their initiator proposes it. The messages are not compressed by default
+    pub fn with_compression(mut self, compression: SecureChannelCompression) -> Self {
+        self.compression.push(compression);
+        self
+    }
+
     /// Freshly generated [`FlowControlId`]
     pub fn spawner_flow_control_id(&self) -> FlowControlId {
         self.flow_control_id.clone()
@@ -0,0 +1,79 @@
+use ockam_core::compat::string::String;
+use ockam_core::compat::sync::Arc;
+use ockam_core::compat::vec::Vec;
+use ockam_core::{LocalMessage, Result};
+
+use crate::models::Identifier;
+use crate::{AttributesEntry, IdentityAttributesReader, IdentitySecureChannelLocalInfo};
+
+/// Authenticated peer of a Secure Channel, as seen by a worker receiving
+/// a message through that channel
+///
+/// The peer identifier is taken from the message local info and its attributes
+/// are read from the local attributes storage, where they are stored when the
+/// peer presents its credentials. No request is sent to the authority.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecureChannelPeer {
+    identifier: Identifier,
+    attributes: Option<AttributesEntry>,
+}
+
+impl SecureChannelPeer {
+    /// Return the peer of the Secure Channel which delivered this message.
+    /// An error is returned if the message did not come through a Secure Channel
+    pub async fn from_local_message(
+        local_message: &LocalMessage,
+        attributes_reader: Arc<dyn IdentityAttributesReader>,
+    ) -> Result<Self> {
+        let identifier =
+            IdentitySecureChannelLocalInfo::find_info(local_message)?.their_identity_id();
+        let attributes = attributes_reader.get_attributes(&identifier).await?;
+        Ok(Self {
+            identifier,
+            attributes,
+        })
+    }
+
+    /// Identifier of the peer
+    pub fn identifier(&self) -> &Identifier {
+        &self.identifier
+    }
+
+    /// Attributes of the peer, if it presented a credential
+    pub fn attributes(&self) -> Option<&AttributesEntry> {
+        self.attributes.as_ref()
+    }
+
+    /// Value of a given attribute of the peer
+    pub fn attribute(&self, name: &str) -> Option<&[u8]> {
+        self.attributes
+            .as_ref()
+            .and_then(|a| a.attrs().get(name.as_bytes()))
+            .map(|v| v.as_slice())
+    }
+
+    /// Value of a given attribute of the peer, if it is a valid UTF-8 string
+    pub fn attribute_str(&self, name: &str) -> Option<String> {
+        self.attribute(name)
+            .and_then(|v| core::str::from_utf8(v).ok())
+            .map(|v| v.into())
+    }
+
+    /// Return true if the peer has the given attribute with the given value
+    pub fn has_attribute(&self, name: &str, value: &str) -> bool {
+        self.attribute(name) == Some(value.as_bytes())
+    }
+
+    /// Names of all the attributes of the peer
+    pub fn attribute_names(&self) -> Vec<String> {
+        self.attributes
+            .as_ref()
+            .map(|a| {
+                a.attrs()
+                    .keys()
+                    .map(|k| String::from_utf8_lossy(k).into())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
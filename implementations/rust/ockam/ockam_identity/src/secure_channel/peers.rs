@@ -0,0 +1,103 @@
+use ockam_core::compat::collections::BTreeMap;
+use ockam_core::compat::sync::{Arc, RwLock};
+use ockam_core::compat::vec::Vec;
+
+use crate::models::{Identifier, TimestampInSeconds};
+
+/// Secure Channels established with a peer over a period of time
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PeerActivity {
+    /// Time of the first channel established with the peer
+    pub first_seen: TimestampInSeconds,
+    /// Time of the last channel established with the peer
+    pub last_seen: TimestampInSeconds,
+    /// Number of channels initiated by this side
+    pub channels_initiated: u64,
+    /// Number of channels initiated by the peer
+    pub channels_accepted: u64,
+}
+
+impl PeerActivity {
+    fn new(timestamp: TimestampInSeconds) -> Self {
+        Self {
+            first_seen: timestamp,
+            last_seen: timestamp,
+            channels_initiated: 0,
+            channels_accepted: 0,
+        }
+    }
+
+    /// Total number of channels established with the peer
+    pub fn channels(&self) -> u64 {
+        self.channels_initiated
+            .saturating_add(self.channels_accepted)
+    }
+
+    /// Add the activity of another period to this one
+    pub fn merge(&mut self, other: &PeerActivity) {
+        self.first_seen = self.first_seen.min(other.first_seen);
+        self.last_seen = self.last_seen.max(other.last_seen);
+        self.channels_initiated = self
+            .channels_initiated
+            .saturating_add(other.channels_initiated);
+        self.channels_accepted = self
+            .channels_accepted
+            .saturating_add(other.channels_accepted);
+    }
+}
+
+/// Secure Channels established with each authenticated peer identity, since the
+/// activity was last taken
+#[derive(Clone, Debug, Default)]
+pub struct PeersRegistry {
+    peers: Arc<RwLock<BTreeMap<Identifier, PeerActivity>>>,
+}
+
+impl PeersRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a channel established with a peer
+    pub fn record_channel(
+        &self,
+        identifier: &Identifier,
+        initiated: bool,
+        timestamp: TimestampInSeconds,
+    ) {
+        let mut peers = self.peers.write().unwrap();
+        let activity = peers
+            .entry(identifier.clone())
+            .or_insert_with(|| PeerActivity::new(timestamp));
+        activity.first_seen = activity.first_seen.min(timestamp);
+        activity.last_seen = activity.last_seen.max(timestamp);
+        if initiated {
+            activity.channels_initiated = activity.channels_initiated.saturating_add(1);
+        } else {
+            activity.channels_accepted = activity.channels_accepted.saturating_add(1);
+        }
+    }
+
+    /// Return the activity of a peer since it was last taken
+    pub fn get(&self, identifier: &Identifier) -> Option<PeerActivity> {
+        self.peers.read().unwrap().get(identifier).copied()
+    }
+
+    /// Return the activity of all peers since it was last taken
+    pub fn list(&self) -> Vec<(Identifier, PeerActivity)> {
+        self.peers
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(k, v)| (k.clone(), *v))
+            .collect()
+    }
+
+    /// Return the activity of all peers and reset it.
+    /// This is used to periodically persist the peers
+    pub fn take(&self) -> Vec<(Identifier, PeerActivity)> {
+        let peers = core::mem::take(&mut *self.peers.write().unwrap());
+        peers.into_iter().collect()
+    }
+}
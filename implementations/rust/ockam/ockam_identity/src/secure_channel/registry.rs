@@ -3,7 +3,11 @@ use ockam_core::compat::sync::{Arc, RwLock};
 use ockam_core::compat::vec::Vec;
 use ockam_core::{Address, Result};
 
-use crate::models::Identifier;
+use crate::models::{Identifier, TimestampInSeconds};
+use crate::secure_channel::{
+    ChannelCompression, CompressionStats, LastHeartbeat, ReplayCounters, ReplayProtectionStats,
+    SecureChannelCipherSuite,
+};
 use crate::IdentityError;
 
 /// Known information about particular SecureChannel
@@ -17,6 +21,10 @@ pub struct SecureChannelRegistryEntry {
     my_id: Identifier,
     their_id: Identifier,
     their_decryptor_address: Address,
+    last_heartbeat: LastHeartbeat,
+    cipher_suite: SecureChannelCipherSuite,
+    replay_counters: ReplayCounters,
+    compression: ChannelCompression,
 }
 
 impl SecureChannelRegistryEntry {
@@ -41,9 +49,33 @@ impl SecureChannelRegistryEntry {
             my_id,
             their_id,
             their_decryptor_address,
+            last_heartbeat: LastHeartbeat::default(),
+            cipher_suite: SecureChannelCipherSuite::default(),
+            replay_counters: ReplayCounters::default(),
+            compression: ChannelCompression::default(),
         }
     }
 
+    pub(crate) fn with_last_heartbeat(mut self, last_heartbeat: LastHeartbeat) -> Self {
+        self.last_heartbeat = last_heartbeat;
+        self
+    }
+
+    pub(crate) fn with_cipher_suite(mut self, cipher_suite: SecureChannelCipherSuite) -> Self {
+        self.cipher_suite = cipher_suite;
+        self
+    }
+
+    pub(crate) fn with_replay_counters(mut self, replay_counters: ReplayCounters) -> Self {
+        self.replay_counters = replay_counters;
+        self
+    }
+
+    pub(crate) fn with_compression(mut self, compression: ChannelCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
     /// Encryptor messaging address
     pub fn encryptor_messaging_address(&self) -> &Address {
         &self.encryptor_messaging_address
@@ -83,6 +115,26 @@ impl SecureChannelRegistryEntry {
     pub fn their_decryptor_address(&self) -> Address {
         self.their_decryptor_address.clone()
     }
+
+    /// Time of the last heartbeat received from the other side, if any
+    pub fn last_heartbeat(&self) -> Option<TimestampInSeconds> {
+        self.last_heartbeat.get()
+    }
+
+    /// Cipher suite selected during the handshake
+    pub fn cipher_suite(&self) -> SecureChannelCipherSuite {
+        self.cipher_suite
+    }
+
+    /// Replay window and counters of the messages rejected or reordered on this channel
+    pub fn replay_protection_stats(&self) -> ReplayProtectionStats {
+        self.replay_counters.get()
+    }
+
+    /// Compression negotiated on this channel and counters of the messages sent compressed
+    pub fn compression_stats(&self) -> CompressionStats {
+        self.compression.stats()
+    }
 }
 
 /// Registry of all known Secure Channels
@@ -25,6 +25,10 @@ impl<F: TrustPolicy, S: TrustPolicy> TrustPolicy for AllTrustPolicy<F, S> {
     async fn check(&self, trust_info: &SecureChannelTrustInfo) -> Result<bool> {
         Ok(self.first.check(trust_info).await? && self.second.check(trust_info).await?)
     }
+
+    fn trusts_everyone(&self) -> bool {
+        self.first.trusts_everyone() && self.second.trusts_everyone()
+    }
 }
 
 #[cfg(test)]
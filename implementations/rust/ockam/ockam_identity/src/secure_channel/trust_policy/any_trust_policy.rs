@@ -26,6 +26,10 @@ impl<F: TrustPolicy, S: TrustPolicy> TrustPolicy for AnyTrustPolicy<F, S> {
         // TODO: is the short circuit here a side channel?
         Ok(self.first.check(trust_info).await? || self.second.check(trust_info).await?)
     }
+
+    fn trusts_everyone(&self) -> bool {
+        self.first.trusts_everyone() || self.second.trusts_everyone()
+    }
 }
 
 #[cfg(test)]
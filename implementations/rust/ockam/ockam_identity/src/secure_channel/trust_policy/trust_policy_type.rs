@@ -36,6 +36,11 @@ pub trait TrustPolicy: Send + Sync + 'static {
     /// Check SecureChannel
     async fn check(&self, trust_info: &SecureChannelTrustInfo) -> Result<bool>;
 
+    /// Return true if the check succeeds for any participant
+    fn trusts_everyone(&self) -> bool {
+        false
+    }
+
     /// Run both `TrustPolicy` checks and succeed only if both succeeded
     fn and<O: TrustPolicy>(self, other: O) -> AllTrustPolicy<Self, O>
     where
@@ -59,6 +64,10 @@ impl<T: TrustPolicy + ?Sized> TrustPolicy for Box<T> {
     async fn check(&self, trust_info: &SecureChannelTrustInfo) -> Result<bool> {
         T::check(&**self, trust_info).await
     }
+
+    fn trusts_everyone(&self) -> bool {
+        T::trusts_everyone(&**self)
+    }
 }
 
 #[async_trait]
@@ -66,4 +75,8 @@ impl<T: TrustPolicy + ?Sized> TrustPolicy for Arc<T> {
     async fn check(&self, trust_info: &SecureChannelTrustInfo) -> Result<bool> {
         T::check(&**self, trust_info).await
     }
+
+    fn trusts_everyone(&self) -> bool {
+        T::trusts_everyone(&**self)
+    }
 }
@@ -0,0 +1,112 @@
+use ockam_core::compat::collections::BTreeMap;
+use ockam_core::compat::sync::{Arc, RwLock};
+use ockam_core::compat::vec::Vec;
+
+use crate::models::Identifier;
+
+/// Number of encrypted bytes exchanged with a peer
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BandwidthUsage {
+    /// Bytes sent to the peer
+    pub bytes_sent: u64,
+    /// Bytes received from the peer
+    pub bytes_received: u64,
+}
+
+impl BandwidthUsage {
+    /// Total number of bytes exchanged with the peer
+    pub fn total(&self) -> u64 {
+        self.bytes_sent.saturating_add(self.bytes_received)
+    }
+
+    /// Add the bytes of another usage to this one
+    pub fn add(&mut self, other: &BandwidthUsage) {
+        self.bytes_sent = self.bytes_sent.saturating_add(other.bytes_sent);
+        self.bytes_received = self.bytes_received.saturating_add(other.bytes_received);
+    }
+}
+
+/// Bytes counters aggregated per authenticated peer identity, over all the
+/// Secure Channels established with that peer. Since portals use Secure Channels,
+/// their traffic is accounted for as well
+#[derive(Clone, Debug, Default)]
+pub struct BandwidthUsageRegistry {
+    counters: Arc<RwLock<BTreeMap<Identifier, BandwidthUsage>>>,
+}
+
+impl BandwidthUsageRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record bytes sent to a peer
+    pub fn record_sent(&self, identifier: &Identifier, bytes: usize) {
+        self.record(identifier, |usage| {
+            usage.bytes_sent = usage.bytes_sent.saturating_add(bytes as u64)
+        })
+    }
+
+    /// Record bytes received from a peer
+    pub fn record_received(&self, identifier: &Identifier, bytes: usize) {
+        self.record(identifier, |usage| {
+            usage.bytes_received = usage.bytes_received.saturating_add(bytes as u64)
+        })
+    }
+
+    /// Return the bytes exchanged with a peer since the counters were last taken
+    pub fn get(&self, identifier: &Identifier) -> BandwidthUsage {
+        self.counters
+            .read()
+            .unwrap()
+            .get(identifier)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Return the bytes exchanged with all peers since the counters were last taken
+    pub fn list(&self) -> Vec<(Identifier, BandwidthUsage)> {
+        self.counters
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(k, v)| (k.clone(), *v))
+            .collect()
+    }
+
+    /// Return the bytes exchanged with all peers and reset the counters.
+    /// This is used to periodically persist the usage
+    pub fn take(&self) -> Vec<(Identifier, BandwidthUsage)> {
+        let counters = core::mem::take(&mut *self.counters.write().unwrap());
+        counters.into_iter().collect()
+    }
+
+    fn record(&self, identifier: &Identifier, f: impl FnOnce(&mut BandwidthUsage)) {
+        let mut counters = self.counters.write().unwrap();
+        f(counters.entry(identifier.clone()).or_default())
+    }
+}
+
+/// Counters for a single Secure Channel, shared by its encryptor and decryptor
+#[derive(Clone, Debug)]
+pub(crate) struct ChannelUsage {
+    registry: BandwidthUsageRegistry,
+    their_identifier: Identifier,
+}
+
+impl ChannelUsage {
+    pub(crate) fn new(registry: BandwidthUsageRegistry, their_identifier: Identifier) -> Self {
+        Self {
+            registry,
+            their_identifier,
+        }
+    }
+
+    pub(crate) fn sent(&self, bytes: usize) {
+        self.registry.record_sent(&self.their_identifier, bytes)
+    }
+
+    pub(crate) fn received(&self, bytes: usize) {
+        self.registry.record_received(&self.their_identifier, bytes)
+    }
+}
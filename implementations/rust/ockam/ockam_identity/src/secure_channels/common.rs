@@ -3,6 +3,8 @@ use core::fmt::Formatter;
 use ockam_core::flow_control::FlowControlId;
 use ockam_core::Address;
 
+use crate::SecureChannelListenerLimits;
+
 /// Result of [`super::SecureChannels::create_secure_channel()`] call.
 #[derive(Debug, Clone)]
 pub struct SecureChannel {
@@ -61,6 +63,7 @@ impl SecureChannel {
 pub struct SecureChannelListener {
     address: Address,
     flow_control_id: FlowControlId,
+    limits: Option<SecureChannelListenerLimits>,
 }
 
 impl fmt::Display for SecureChannelListener {
@@ -79,8 +82,14 @@ impl SecureChannelListener {
         Self {
             address,
             flow_control_id,
+            limits: None,
         }
     }
+    /// Set the limits applied by the listener
+    pub fn with_limits(mut self, limits: Option<SecureChannelListenerLimits>) -> Self {
+        self.limits = limits;
+        self
+    }
     /// [`Address`] of the corresponding
     /// [`SecureChannelListener`](super::super::SecureChannelListener) Worker that can be used
     /// to stop it
@@ -91,4 +100,8 @@ impl SecureChannelListener {
     pub fn flow_control_id(&self) -> &FlowControlId {
         &self.flow_control_id
     }
+    /// Limits applied by the listener, which can be changed while it is running
+    pub fn limits(&self) -> Option<&SecureChannelListenerLimits> {
+        self.limits.as_ref()
+    }
 }
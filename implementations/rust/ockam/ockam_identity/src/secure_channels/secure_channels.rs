@@ -7,8 +7,9 @@ use crate::identities::Identities;
 use crate::models::Identifier;
 use crate::secure_channel::handshake_worker::HandshakeWorker;
 use crate::secure_channel::{
-    Addresses, IdentityChannelListener, Role, SecureChannelListenerOptions, SecureChannelOptions,
-    SecureChannelRegistry,
+    Addresses, BandwidthUsageRegistry, CredentialsRefreshMetrics, IdentityChannelListener,
+    PeersRegistry, Role, SecureChannelCredentialsRefresh, SecureChannelListenerOptions,
+    SecureChannelOptions, SecureChannelRegistry,
 };
 use crate::{SecureChannel, SecureChannelListener, SecureChannelsBuilder, Vault};
 
@@ -17,6 +18,10 @@ use crate::{SecureChannel, SecureChannelListener, SecureChannelsBuilder, Vault};
 pub struct SecureChannels {
     pub(crate) identities: Arc<Identities>,
     pub(crate) secure_channel_registry: SecureChannelRegistry,
+    pub(crate) bandwidth_usage: BandwidthUsageRegistry,
+    pub(crate) peers: PeersRegistry,
+    pub(crate) credentials_refresh: Option<SecureChannelCredentialsRefresh>,
+    pub(crate) credentials_refresh_metrics: CredentialsRefreshMetrics,
 }
 
 impl SecureChannels {
@@ -24,10 +29,15 @@ impl SecureChannels {
     pub(crate) fn new(
         identities: Arc<Identities>,
         secure_channel_registry: SecureChannelRegistry,
+        credentials_refresh: Option<SecureChannelCredentialsRefresh>,
     ) -> Self {
         Self {
             identities,
             secure_channel_registry,
+            bandwidth_usage: BandwidthUsageRegistry::new(),
+            peers: PeersRegistry::new(),
+            credentials_refresh,
+            credentials_refresh_metrics: CredentialsRefreshMetrics::default(),
         }
     }
 
@@ -46,11 +56,32 @@ impl SecureChannels {
         self.secure_channel_registry.clone()
     }
 
+    /// Return the bytes exchanged with each peer over its secure channels
+    pub fn bandwidth_usage(&self) -> BandwidthUsageRegistry {
+        self.bandwidth_usage.clone()
+    }
+
+    /// Return the channels established with each peer since they were last taken
+    pub fn peers(&self) -> PeersRegistry {
+        self.peers.clone()
+    }
+
+    /// Return the default credentials refresh of the channels which don't configure one
+    pub fn credentials_refresh(&self) -> Option<SecureChannelCredentialsRefresh> {
+        self.credentials_refresh
+    }
+
+    /// Return the counters of the credentials refreshes of all the channels
+    pub fn credentials_refresh_metrics(&self) -> CredentialsRefreshMetrics {
+        self.credentials_refresh_metrics.clone()
+    }
+
     /// Create a builder for secure channels
     pub fn builder() -> SecureChannelsBuilder {
         SecureChannelsBuilder {
             identities_builder: Identities::builder(),
             registry: SecureChannelRegistry::new(),
+            credentials_refresh: None,
         }
     }
 }
@@ -67,6 +98,7 @@ impl SecureChannels {
         let address = address.into();
         let options = options.into();
         let flow_control_id = options.flow_control_id.clone();
+        let limits = options.limits.clone();
 
         IdentityChannelListener::create(
             ctx,
@@ -77,7 +109,7 @@ impl SecureChannels {
         )
         .await?;
 
-        Ok(SecureChannelListener::new(address, flow_control_id))
+        Ok(SecureChannelListener::new(address, flow_control_id).with_limits(limits))
     }
 
     /// Initiate a SecureChannel using `Route` to the SecureChannel listener and [`SecureChannelOptions`]
@@ -117,6 +149,13 @@ impl SecureChannels {
             options.trust_context,
             Some(route),
             Some(options.timeout),
+            options.heartbeats,
+            options.noise_interop,
+            options.cipher_suites.clone(),
+            options.replay_window,
+            options.credentials_exchange,
+            options.compression,
+            None,
             Role::Initiator,
         )
         .await?;
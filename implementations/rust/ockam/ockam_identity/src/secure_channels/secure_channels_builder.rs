@@ -1,10 +1,10 @@
 use ockam_core::compat::sync::Arc;
 
-use crate::identities::{Identities, IdentitiesRepository};
-use crate::secure_channel::SecureChannelRegistry;
+use crate::identities::{GroupsRepository, Identities, IdentitiesRepository};
+use crate::secure_channel::{SecureChannelCredentialsRefresh, SecureChannelRegistry};
 use crate::secure_channels::SecureChannels;
 use crate::storage::Storage;
-use crate::{IdentitiesBuilder, Vault, VaultStorage};
+use crate::{ChangeHistoryLimits, IdentitiesBuilder, TimestampInSeconds, Vault, VaultStorage};
 
 /// This struct supports all the services related to secure channels
 #[derive(Clone)]
@@ -12,6 +12,7 @@ pub struct SecureChannelsBuilder {
     // FIXME: This is very strange dependency
     pub(crate) identities_builder: IdentitiesBuilder,
     pub(crate) registry: SecureChannelRegistry,
+    pub(crate) credentials_refresh: Option<SecureChannelCredentialsRefresh>,
 }
 
 /// Create default, in-memory, secure channels (mostly for examples and testing)
@@ -46,13 +47,35 @@ impl SecureChannelsBuilder {
         self
     }
 
+    /// Set a specific repository for the groups of identities
+    pub fn with_groups_repository(mut self, repository: Arc<dyn GroupsRepository>) -> Self {
+        self.identities_builder = self.identities_builder.with_groups_repository(repository);
+        self
+    }
+
     /// Set a specific identities
     pub fn with_identities(mut self, identities: Arc<Identities>) -> Self {
         self.identities_builder = self
             .identities_builder
             .with_identities_repository(identities.repository())
             .with_vault(identities.vault())
-            .with_purpose_keys_repository(identities.purpose_keys_repository());
+            .with_purpose_keys_repository(identities.purpose_keys_repository())
+            .with_child_identities_repository(identities.child_identities_repository())
+            .with_groups_repository(identities.groups_repository());
+        self.identities_builder.clock_skew = identities.clock_skew();
+        self.identities_builder.change_history_limits = identities.change_history_limits();
+        self
+    }
+
+    /// Set the tolerance on the clock skew with the issuers of credentials
+    pub fn with_clock_skew_tolerance(mut self, tolerance: TimestampInSeconds) -> Self {
+        self.identities_builder = self.identities_builder.with_clock_skew_tolerance(tolerance);
+        self
+    }
+
+    /// Set the limits on the change histories of the identities of the other parties
+    pub fn with_change_history_limits(mut self, limits: ChangeHistoryLimits) -> Self {
+        self.identities_builder = self.identities_builder.with_change_history_limits(limits);
         self
     }
 
@@ -62,10 +85,21 @@ impl SecureChannelsBuilder {
         self
     }
 
+    /// Regularly request fresh credentials on the channels which don't configure
+    /// their own credentials refresh
+    pub fn with_credentials_refresh(mut self, refresh: SecureChannelCredentialsRefresh) -> Self {
+        self.credentials_refresh = Some(refresh);
+        self
+    }
+
     /// Return the vault used by this builder
     /// Build secure channels
     pub fn build(self) -> Arc<SecureChannels> {
         let identities = self.identities_builder.build();
-        Arc::new(SecureChannels::new(identities, self.registry.clone()))
+        Arc::new(SecureChannels::new(
+            identities,
+            self.registry.clone(),
+            self.credentials_refresh,
+        ))
     }
 }
@@ -0,0 +1,99 @@
+use ockam_core::compat::collections::BTreeMap;
+use ockam_core::compat::string::{String, ToString};
+use serde_json::{json, Value};
+use tracing::warn;
+
+use crate::identity::IdentityConstants;
+use crate::AttributesEntry;
+
+/// Render the values stored for some keys as JSON.
+///
+/// The SQL storages write this rendering in a `value_json` column next to the CBOR `value`
+/// column, so that the stored data can be inspected with SQL by reporting and debugging tools.
+/// The rendering is never read back by the storages: the CBOR value stays the reference
+pub trait JsonRenderer: Send + Sync + 'static {
+    /// Return a JSON rendering of a value, or `None` if the values stored for this key
+    /// are not rendered
+    fn render(&self, key: &str, value: &[u8]) -> Option<String>;
+}
+
+/// Render the attributes of the identities, with their values decoded as UTF-8 strings
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AttributesJsonRenderer;
+
+impl JsonRenderer for AttributesJsonRenderer {
+    fn render(&self, key: &str, value: &[u8]) -> Option<String> {
+        if key != IdentityConstants::ATTRIBUTES_KEY {
+            return None;
+        }
+        match minicbor::decode::<AttributesEntry>(value) {
+            Ok(entry) => Some(attributes_to_json(&entry).to_string()),
+            Err(e) => {
+                warn!(%e, "cannot render stored attributes as JSON");
+                None
+            }
+        }
+    }
+}
+
+fn attributes_to_json(entry: &AttributesEntry) -> Value {
+    let mut attributes = BTreeMap::new();
+    let mut provenance = BTreeMap::new();
+    for (key, value) in entry.attrs() {
+        let name = String::from_utf8_lossy(key).to_string();
+        attributes.insert(name.clone(), String::from_utf8_lossy(value).to_string());
+        if let Some(p) = entry.provenance(key) {
+            provenance.insert(
+                name,
+                json!({
+                    "attested_by": p.attested_by(),
+                    "added": p.added(),
+                    "expires": p.expires(),
+                }),
+            );
+        }
+    }
+    json!({
+        "attributes": attributes,
+        "added": entry.added(),
+        "expires": entry.expires(),
+        "attested_by": entry.attested_by(),
+        "provenance": provenance,
+        "validity": entry.attributes_validity(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Identifier, TimestampInSeconds};
+    use core::str::FromStr;
+
+    #[test]
+    fn test_render_attributes() {
+        let attester = Identifier::from_str("I0123456789abcdef0123456789abcdef01234567").unwrap();
+        let entry = AttributesEntry::new(
+            [(b"role".to_vec(), b"member".to_vec())].into(),
+            TimestampInSeconds(100),
+            Some(TimestampInSeconds(200)),
+            Some(attester.clone()),
+        );
+        let value = minicbor::to_vec(&entry).unwrap();
+
+        let rendered = AttributesJsonRenderer
+            .render(IdentityConstants::ATTRIBUTES_KEY, &value)
+            .unwrap();
+        let rendered: Value = serde_json::from_str(&rendered).unwrap();
+        assert_eq!(rendered["attributes"]["role"], "member");
+        assert_eq!(rendered["added"], 100);
+        assert_eq!(rendered["expires"], 200);
+        assert_eq!(rendered["attested_by"], attester.to_string());
+
+        // other keys and invalid values are not rendered
+        assert_eq!(AttributesJsonRenderer.render("other", &value), None);
+        assert_eq!(
+            AttributesJsonRenderer.render(IdentityConstants::ATTRIBUTES_KEY, &[0xff]),
+            None
+        );
+    }
+}
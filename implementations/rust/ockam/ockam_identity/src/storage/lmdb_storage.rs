@@ -12,7 +12,7 @@ use crate::storage::Storage;
 use core::str;
 use lmdb::{Cursor, Database, Environment, Transaction};
 use std::fmt;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use tokio_retry::strategy::{jitter, FixedInterval};
 use tokio_retry::Retry;
 use tracing::debug;
@@ -24,6 +24,7 @@ pub struct LmdbStorage {
     pub env: Arc<Environment>,
     /// lmdb database file
     pub map: Database,
+    path: PathBuf,
 }
 
 impl fmt::Debug for LmdbStorage {
@@ -62,9 +63,36 @@ impl LmdbStorage {
         Ok(LmdbStorage {
             env: Arc::new(env),
             map,
+            path: p,
         })
     }
 
+    /// Path of the database file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Return an error if the database file was removed or if the database can't be read.
+    ///
+    /// A removed database file is not created again: the environment of this storage still
+    /// uses the removed file, and LMDB doesn't support opening the same file twice in a process
+    pub async fn check_health(&self) -> Result<()> {
+        let d = self.clone();
+        let t = move || {
+            if !d.path.exists() {
+                return Err(Error::new(
+                    Origin::Node,
+                    Kind::NotFound,
+                    format!("the database file {} was removed", d.path.display()),
+                ));
+            }
+            let r = d.env.begin_ro_txn().map_err(map_lmdb_err)?;
+            r.open_ro_cursor(d.map).map_err(map_lmdb_err)?;
+            Ok(())
+        };
+        task::spawn_blocking(t).await.map_err(map_join_err)?
+    }
+
     /// Write a new binary value for a given key in the database
     pub async fn write(&self, k: String, v: Vec<u8>) -> Result<()> {
         let d = self.clone();
@@ -3,9 +3,18 @@ mod storage;
 
 mod memory;
 
+/// JSON rendering of the values written by the SQL storages
+#[cfg(any(feature = "sqlite", feature = "postgres"))]
+pub mod json_view;
 /// LMDB implementation of the Storage trait
 #[cfg(feature = "std")]
 pub mod lmdb_storage;
+/// Postgres implementation of the Storage trait
+#[cfg(feature = "postgres")]
+pub mod postgres_storage;
+/// Timing of the statements executed by the SQL storages
+#[cfg(any(feature = "sqlite", feature = "postgres"))]
+pub mod sql_instrumentation;
 /// Sqlite implementation of the Storage trait
 #[cfg(feature = "sqlite")]
 pub mod sqlite_storage;
@@ -18,3 +27,11 @@ pub use lmdb_storage::*;
 
 #[cfg(feature = "sqlite")]
 pub use sqlite_storage::*;
+
+#[cfg(feature = "postgres")]
+pub use postgres_storage::*;
+
+#[cfg(any(feature = "sqlite", feature = "postgres"))]
+pub use json_view::*;
+#[cfg(any(feature = "sqlite", feature = "postgres"))]
+pub use sql_instrumentation::*;
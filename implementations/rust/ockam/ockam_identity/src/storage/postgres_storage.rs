@@ -0,0 +1,335 @@
+use core::fmt;
+use core::future::Future;
+use core::time::Duration;
+use ockam_core::async_trait;
+use ockam_core::compat::string::{String, ToString};
+use ockam_core::compat::sync::Arc;
+use ockam_core::compat::vec::Vec;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Error, Result};
+use ockam_node::tokio::sync::Mutex;
+use tokio_postgres::config::SslMode;
+use tokio_postgres::{Client, Config, NoTls};
+use tokio_postgres_rustls::MakeRustlsConnect;
+use tracing::{debug, error, warn};
+
+use crate::storage::{JsonRenderer, SqlQueryInstrumentation, Storage};
+
+/// Storage using a Postgres database.
+///
+/// Contrary to the other storages, the same database can be used concurrently by several
+/// nodes, for example to share the members of a project between several authority nodes
+#[derive(Clone)]
+pub struct PostgresStorage {
+    client: PostgresClient,
+    instrumentation: SqlQueryInstrumentation,
+    json_renderer: Option<Arc<dyn JsonRenderer>>,
+}
+
+impl fmt::Debug for PostgresStorage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PostgresStorage")
+    }
+}
+
+impl PostgresStorage {
+    const CREATE_IDENTITY_TABLE_SQL: &'static str = "CREATE TABLE IF NOT EXISTS identity (
+        identity_id TEXT NOT NULL,
+        key TEXT NOT NULL,
+        value BYTEA,
+        PRIMARY KEY (identity_id, key)
+    );";
+    // used to list the identities having a given key, without scanning the whole table
+    const CREATE_IDENTITY_KEY_INDEX_SQL: &'static str =
+        "CREATE INDEX IF NOT EXISTS idx_identity_key ON identity (key);";
+    // JSON rendering of the values, for the tables created before it was introduced
+    const ADD_IDENTITY_VALUE_JSON_SQL: &'static str =
+        "ALTER TABLE identity ADD COLUMN IF NOT EXISTS value_json JSONB;";
+
+    /// Connect to the database described by a connection string,
+    /// for example `host=localhost user=ockam dbname=authority sslmode=require`,
+    /// and create the storage tables if necessary.
+    ///
+    /// See [`connect_to_postgres`] for the use of TLS
+    pub async fn new(connection_string: &str) -> Result<Self> {
+        debug!("connect to the Postgres database");
+        let client = PostgresClient::connect(connection_string).await?;
+        client
+            .run(|client| async move {
+                client
+                    .batch_execute(
+                        &(Self::CREATE_IDENTITY_TABLE_SQL.to_owned()
+                            + Self::CREATE_IDENTITY_KEY_INDEX_SQL
+                            + Self::ADD_IDENTITY_VALUE_JSON_SQL),
+                    )
+                    .await
+            })
+            .await?;
+        Ok(PostgresStorage {
+            client,
+            instrumentation: Default::default(),
+            json_renderer: None,
+        })
+    }
+
+    /// Write a JSON rendering of the values in the `value_json` column, so that they can
+    /// be queried with SQL. The column is `NULL` for the values which are not rendered
+    pub fn with_json_renderer(self, json_renderer: Arc<dyn JsonRenderer>) -> Self {
+        Self {
+            json_renderer: Some(json_renderer),
+            ..self
+        }
+    }
+
+    /// Log the statements taking longer than a given duration
+    pub fn with_slow_query_threshold(self, threshold: Duration) -> Self {
+        Self {
+            instrumentation: SqlQueryInstrumentation::new(Some(threshold)),
+            ..self
+        }
+    }
+
+    /// Getter for the Postgres client, which can be shared with other Postgres tables
+    pub fn client(&self) -> PostgresClient {
+        self.client.clone()
+    }
+}
+
+/// Client of a Postgres database which opens a new connection when its connection is closed,
+/// for example when the database restarts
+#[derive(Clone)]
+pub struct PostgresClient {
+    connection_string: Arc<String>,
+    client: Arc<Mutex<Arc<Client>>>,
+}
+
+impl PostgresClient {
+    /// Connect to the database described by a connection string
+    pub async fn connect(connection_string: &str) -> Result<Self> {
+        let client = connect_to_postgres(connection_string).await?;
+        Ok(Self {
+            connection_string: Arc::new(connection_string.to_string()),
+            client: Arc::new(Mutex::new(Arc::new(client))),
+        })
+    }
+
+    /// Return a client with an open connection, connecting again if the connection was closed
+    pub async fn get(&self) -> Result<Arc<Client>> {
+        let mut client = self.client.lock().await;
+        if client.is_closed() {
+            warn!("the Postgres connection was closed, connecting again");
+            *client = Arc::new(connect_to_postgres(&self.connection_string).await?);
+        }
+        Ok(client.clone())
+    }
+
+    /// Run some statements. If they fail because the connection was closed, they are run
+    /// again once on a new connection
+    pub async fn run<T, F, Fut>(&self, f: F) -> Result<T>
+    where
+        F: Fn(Arc<Client>) -> Fut,
+        Fut: Future<Output = core::result::Result<T, tokio_postgres::Error>>,
+    {
+        let client = self.get().await?;
+        match f(client.clone()).await {
+            Err(err) if client.is_closed() => {
+                warn!(%err, "the Postgres connection was closed, retrying on a new connection");
+                f(self.get().await?).await.map_err(map_postgres_err)
+            }
+            result => result.map_err(map_postgres_err),
+        }
+    }
+}
+
+/// Open a connection to a Postgres database.
+/// The connection is driven by a background task until the returned client is dropped.
+///
+/// The connection uses TLS unless the connection string contains `sslmode=disable`. The
+/// certificate of the server is verified with the root certificates of the system. With the
+/// default `sslmode=prefer` the connection falls back to plain text when the server doesn't
+/// support TLS, while `sslmode=require` makes TLS mandatory
+pub async fn connect_to_postgres(connection_string: &str) -> Result<Client> {
+    let config: Config = connection_string.parse().map_err(map_postgres_err)?;
+    if config.get_ssl_mode() == SslMode::Disable {
+        let (client, connection) = config.connect(NoTls).await.map_err(map_postgres_err)?;
+        spawn_connection(connection);
+        Ok(client)
+    } else {
+        let (client, connection) = config
+            .connect(tls_connector()?)
+            .await
+            .map_err(map_postgres_err)?;
+        spawn_connection(connection);
+        Ok(client)
+    }
+}
+
+fn spawn_connection<C>(connection: C)
+where
+    C: Future<Output = core::result::Result<(), tokio_postgres::Error>> + Send + 'static,
+{
+    ockam_node::tokio::spawn(async move {
+        if let Err(err) = connection.await {
+            error!(%err, "the Postgres connection was closed");
+        }
+    });
+}
+
+/// Return a TLS connector trusting the root certificates of the system
+fn tls_connector() -> Result<MakeRustlsConnect> {
+    let certificates = rustls_native_certs::load_native_certs()
+        .map_err(|e| Error::new(Origin::Application, Kind::Io, e))?;
+    let mut roots = rustls::RootCertStore::empty();
+    let certificates: Vec<Vec<u8>> = certificates.into_iter().map(|c| c.0).collect();
+    let (_, ignored) = roots.add_parsable_certificates(&certificates);
+    if ignored > 0 {
+        debug!(%ignored, "some root certificates of the system can't be used");
+    }
+    let config = rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    Ok(MakeRustlsConnect::new(config))
+}
+
+#[async_trait]
+impl Storage for PostgresStorage {
+    async fn get(&self, id: &str, key: &str) -> Result<Option<Vec<u8>>> {
+        let row = self
+            .instrumentation
+            .run("postgres", "identity", "get", async {
+                self.client
+                    .run(|client| async move {
+                        client
+                            .query_opt(
+                                "SELECT value FROM identity WHERE identity_id = $1 AND key = $2",
+                                &[&id, &key],
+                            )
+                            .await
+                    })
+                    .await
+            })
+            .await?;
+        Ok(row.and_then(|row| row.get(0)))
+    }
+
+    async fn set(&self, id: &str, key: String, val: Vec<u8>) -> Result<()> {
+        let json = self
+            .json_renderer
+            .as_ref()
+            .and_then(|renderer| renderer.render(&key, &val));
+        // the statement can be run again on a new connection, so its parameters are borrowed
+        let (key, val, json) = (&key, &val, &json);
+        self.instrumentation
+            .run("postgres", "identity", "set", async {
+                self.client
+                    .run(|client| async move {
+                        client
+                            .execute(
+                                "INSERT INTO identity (identity_id, key, value, value_json)
+                                 VALUES ($1, $2, $3, $4::TEXT::JSONB)
+                                 ON CONFLICT (identity_id, key)
+                                 DO UPDATE SET value = EXCLUDED.value, value_json = EXCLUDED.value_json",
+                                &[&id, &key, &val, &json],
+                            )
+                            .await
+                    })
+                    .await
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn del(&self, id: &str, key: &str) -> Result<()> {
+        self.instrumentation
+            .run("postgres", "identity", "del", async {
+                self.client
+                    .run(|client| async move {
+                        client
+                            .execute(
+                                "DELETE FROM identity WHERE identity_id = $1 AND key = $2",
+                                &[&id, &key],
+                            )
+                            .await
+                    })
+                    .await
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn keys(&self, namespace: &str) -> Result<Vec<String>> {
+        let rows = self
+            .instrumentation
+            .run("postgres", "identity", "keys", async {
+                self.client
+                    .run(|client| async move {
+                        client
+                            .query(
+                                "SELECT identity_id FROM identity WHERE key = $1",
+                                &[&namespace],
+                            )
+                            .await
+                    })
+                    .await
+            })
+            .await?;
+        Ok(rows.iter().map(|row| row.get(0)).collect())
+    }
+}
+
+/// Convert a Postgres error to an Ockam error
+pub fn map_postgres_err(err: tokio_postgres::Error) -> Error {
+    Error::new(Origin::Application, Kind::Io, err)
+}
+
+/// These tests run against the Postgres database of the connection string given by
+/// OCKAM_TEST_POSTGRES_CONNECTION, for example `host=localhost user=postgres sslmode=disable`.
+/// They are skipped when this variable is not set
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::random;
+
+    fn test_connection_string() -> Option<String> {
+        std::env::var("OCKAM_TEST_POSTGRES_CONNECTION").ok()
+    }
+
+    #[tokio::test]
+    async fn test_postgres_storage() -> Result<()> {
+        let Some(connection_string) = test_connection_string() else {
+            return Ok(());
+        };
+        let storage = PostgresStorage::new(&connection_string).await?;
+        let id = format!("test-{}", random::<u64>());
+
+        storage.set(&id, "key".to_string(), vec![1, 2, 3]).await?;
+        assert_eq!(storage.get(&id, "key").await?, Some(vec![1, 2, 3]));
+        assert!(storage.keys("key").await?.contains(&id));
+
+        storage.del(&id, "key").await?;
+        assert_eq!(storage.get(&id, "key").await?, None);
+        assert!(!storage.keys("key").await?.contains(&id));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_postgres_storage_reconnects() -> Result<()> {
+        let Some(connection_string) = test_connection_string() else {
+            return Ok(());
+        };
+        let storage = PostgresStorage::new(&connection_string).await?;
+        let id = format!("test-{}", random::<u64>());
+        storage.set(&id, "key".to_string(), vec![1]).await?;
+
+        // the server closes the connection of the storage
+        let client = storage.client().get().await?;
+        let _ = client
+            .simple_query("SELECT pg_terminate_backend(pg_backend_pid())")
+            .await;
+
+        assert_eq!(storage.get(&id, "key").await?, Some(vec![1]));
+        storage.del(&id, "key").await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,63 @@
+use core::future::Future;
+use core::time::Duration;
+use ockam_core::Result;
+use std::time::Instant;
+use tracing::{debug_span, trace, warn, Instrument};
+
+/// Timing of the statements executed by a SQL storage.
+///
+/// Each statement is executed in a `sql_query` span, labelled with the database, the table
+/// and the storage operation, so that its duration can be collected by a tracing subscriber.
+/// Statements taking longer than an optional threshold are logged as warnings, which helps
+/// detecting slow queries, like full table scans, in production
+#[derive(Clone, Debug, Default)]
+pub struct SqlQueryInstrumentation {
+    slow_query_threshold: Option<Duration>,
+}
+
+impl SqlQueryInstrumentation {
+    /// Create an instrumentation logging the statements slower than a threshold.
+    /// `None` disables the slow queries log
+    pub fn new(slow_query_threshold: Option<Duration>) -> Self {
+        Self {
+            slow_query_threshold,
+        }
+    }
+
+    /// Duration above which a statement is logged as slow
+    pub fn slow_query_threshold(&self) -> Option<Duration> {
+        self.slow_query_threshold
+    }
+
+    /// Execute a statement and record its duration
+    pub(crate) async fn run<T>(
+        &self,
+        database: &'static str,
+        table: &'static str,
+        label: &'static str,
+        statement: impl Future<Output = Result<T>>,
+    ) -> Result<T> {
+        let span = debug_span!("sql_query", database, table, label);
+        let started_at = Instant::now();
+        let result = statement.instrument(span).await;
+        let elapsed = started_at.elapsed();
+
+        match self.slow_query_threshold {
+            Some(threshold) if elapsed >= threshold => warn!(
+                database,
+                table,
+                label,
+                elapsed_ms = elapsed.as_millis() as u64,
+                "slow SQL query"
+            ),
+            _ => trace!(
+                database,
+                table,
+                label,
+                elapsed_us = elapsed.as_micros() as u64,
+                "SQL query executed"
+            ),
+        }
+        result
+    }
+}
@@ -1,4 +1,5 @@
 use core::str;
+use core::time::Duration;
 use ockam_core::async_trait;
 use ockam_core::compat::sync::{Arc, Mutex};
 use ockam_core::compat::vec::Vec;
@@ -12,6 +13,7 @@ use tokio_retry::strategy::{jitter, FixedInterval};
 use tokio_retry::Retry;
 use tracing::debug;
 
+use crate::storage::{JsonRenderer, SqlQueryInstrumentation};
 use Storage;
 
 /// Storage using the Sqlite database
@@ -19,6 +21,8 @@ use Storage;
 pub struct SqliteStorage {
     /// Sqlite Connection
     conn: Arc<Mutex<Connection>>,
+    instrumentation: SqlQueryInstrumentation,
+    json_renderer: Option<Arc<dyn JsonRenderer>>,
 }
 
 impl fmt::Debug for SqliteStorage {
@@ -36,6 +40,9 @@ impl SqliteStorage {
     );";
     const CREATE_IDENTITY_INDEX_SQL: &str =
         "CREATE UNIQUE INDEX IF NOT EXISTS idx_identity_id_key ON identity (identity_id, key);";
+    // used to list the identities having a given key, without scanning the whole table
+    const CREATE_IDENTITY_KEY_INDEX_SQL: &str =
+        "CREATE INDEX IF NOT EXISTS idx_identity_key ON identity (key);";
 
     const CREATE_POLICY_TABLE_SQL: &str = "CREATE TABLE IF NOT EXISTS policy (
         id INTEGER PRIMARY KEY,
@@ -69,15 +76,65 @@ impl SqliteStorage {
                 &("PRAGMA encoding = 'UTF-8';".to_owned()
                     + SqliteStorage::CREATE_IDENTITY_TABLE_SQL
                     + SqliteStorage::CREATE_IDENTITY_INDEX_SQL
+                    + SqliteStorage::CREATE_IDENTITY_KEY_INDEX_SQL
                     + SqliteStorage::CREATE_POLICY_TABLE_SQL
                     + SqliteStorage::CREATE_POLICY_INDEX_SQL),
             )
             .map_err(map_sqlite_err)?;
+        Self::add_value_json_column(&conn)?;
         Ok(SqliteStorage {
             conn: Arc::new(Mutex::new(conn)),
+            instrumentation: Default::default(),
+            json_renderer: None,
         })
     }
 
+    /// Add the JSON rendering of the values to the tables created before it was introduced
+    fn add_value_json_column(conn: &Connection) -> Result<()> {
+        let exists = conn
+            .prepare("SELECT 1 FROM pragma_table_info('identity') WHERE name = 'value_json';")
+            .and_then(|mut stmt| stmt.exists([]))
+            .map_err(map_sqlite_err)?;
+        if !exists {
+            conn.execute("ALTER TABLE identity ADD COLUMN value_json TEXT;", [])
+                .map_err(map_sqlite_err)?;
+        }
+        Ok(())
+    }
+
+    /// Write a JSON rendering of the values in the `value_json` column, so that they can
+    /// be queried with the JSON functions of Sqlite. The column is `NULL` for the values
+    /// which are not rendered
+    pub fn with_json_renderer(self, json_renderer: Arc<dyn JsonRenderer>) -> Self {
+        Self {
+            json_renderer: Some(json_renderer),
+            ..self
+        }
+    }
+
+    /// Log the statements taking longer than a given duration
+    pub fn with_slow_query_threshold(self, threshold: Duration) -> Self {
+        Self {
+            instrumentation: SqlQueryInstrumentation::new(Some(threshold)),
+            ..self
+        }
+    }
+
+    /// Execute a blocking statement on the identity table
+    async fn run<T: Send + 'static>(
+        &self,
+        label: &'static str,
+        statement: impl FnOnce() -> Result<T> + Send + 'static,
+    ) -> Result<T> {
+        self.instrumentation
+            .run("sqlite", "identity", label, async move {
+                task::spawn_blocking(statement)
+                    .await
+                    .map_err(map_join_err)?
+            })
+            .await
+    }
+
     /// Getter for Sqlite Connection
     pub fn conn(&self) -> Arc<Mutex<Connection>> {
         Arc::clone(&self.conn)
@@ -102,22 +159,26 @@ impl Storage for SqliteStorage {
                 .map_err(map_sqlite_err)?;
             Ok(Some(result))
         };
-        task::spawn_blocking(t).await.map_err(map_join_err)?
+        self.run("get", t).await
     }
 
     async fn set(&self, id: &str, key: String, val: Vec<u8>) -> Result<()> {
         let conn = self.conn();
         let id = String::from(id);
+        let json = self
+            .json_renderer
+            .as_ref()
+            .and_then(|renderer| renderer.render(&key, &val));
         let t = move || {
             let conn = conn.lock().unwrap();
             conn.execute(
-                "INSERT OR REPLACE INTO identity (identity_id, key, value) VALUES (?1, ?2, ?3)",
-                params![id, key, val],
+                "INSERT OR REPLACE INTO identity (identity_id, key, value, value_json) VALUES (?1, ?2, ?3, ?4)",
+                params![id, key, val, json],
             )
             .map_err(map_sqlite_err)?;
             Ok(())
         };
-        task::spawn_blocking(t).await.map_err(map_join_err)?
+        self.run("set", t).await
     }
 
     async fn del(&self, id: &str, key: &str) -> Result<()> {
@@ -133,7 +194,7 @@ impl Storage for SqliteStorage {
             .map_err(map_sqlite_err)?;
             Ok(())
         };
-        task::spawn_blocking(t).await.map_err(map_join_err)?
+        self.run("del", t).await
     }
 
     async fn keys(&self, namespace: &str) -> Result<Vec<String>> {
@@ -151,7 +212,7 @@ impl Storage for SqliteStorage {
                 .collect();
             result
         };
-        task::spawn_blocking(t).await.map_err(map_join_err)?
+        self.run("keys", t).await
     }
 }
 
@@ -189,4 +250,58 @@ mod test {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_slow_query_threshold() -> Result<()> {
+        let temp_path = NamedTempFile::new().unwrap().into_temp_path();
+        let db = SqliteStorage::new(temp_path.to_path_buf())
+            .await?
+            .with_slow_query_threshold(Duration::ZERO);
+
+        // statements are still executed when they are logged as slow
+        db.set("1", String::from("2"), vec![1, 2, 3, 4]).await?;
+        assert_eq!(db.get("1", "2").await?, Some(vec![1, 2, 3, 4]));
+        Ok(())
+    }
+
+    struct KeyRenderer;
+
+    impl JsonRenderer for KeyRenderer {
+        fn render(&self, key: &str, value: &[u8]) -> Option<String> {
+            (key == "rendered").then(|| format!("{{\"length\": {}}}", value.len()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_json_renderer() -> Result<()> {
+        let temp_path = NamedTempFile::new().unwrap().into_temp_path();
+        let db = SqliteStorage::new(temp_path.to_path_buf())
+            .await?
+            .with_json_renderer(Arc::new(KeyRenderer));
+
+        db.set("1", String::from("rendered"), vec![1, 2, 3]).await?;
+        db.set("1", String::from("other"), vec![1, 2, 3]).await?;
+        // the CBOR value is still the one which is read
+        assert_eq!(db.get("1", "rendered").await?, Some(vec![1, 2, 3]));
+
+        let conn = db.conn();
+        let conn = conn.lock().unwrap();
+        let length: Option<i64> = conn
+            .query_row(
+                "SELECT json_extract(value_json, '$.length') FROM identity WHERE key = 'rendered'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(length, Some(3));
+        let other: Option<String> = conn
+            .query_row(
+                "SELECT value_json FROM identity WHERE key = 'other'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(other, None);
+        Ok(())
+    }
 }
@@ -4,7 +4,7 @@ use ockam_core::compat::vec::Vec;
 use ockam_core::Result;
 
 use crate::models::{Attributes, CredentialSchemaIdentifier, TimestampInSeconds};
-use crate::IdentityError;
+use crate::{AttributeValidity, IdentityError, ATTRIBUTE_VALIDITY_SUFFIX};
 
 /// Create a new timestamp using the system time
 #[cfg(feature = "std")]
@@ -49,6 +49,20 @@ impl AttributesBuilder {
         self
     }
 
+    /// Add an attribute which is only valid during the given period and schedule.
+    /// The validity is attested as a separate `<key>.validity` attribute
+    pub fn with_constrained_attribute(
+        self,
+        key: &str,
+        value: impl Into<Vec<u8>>,
+        validity: &AttributeValidity,
+    ) -> Result<Self> {
+        let validity_key = format!("{key}{ATTRIBUTE_VALIDITY_SUFFIX}");
+        Ok(self
+            .with_attribute(key, value)
+            .with_attribute(validity_key, validity.to_attribute_value()?))
+    }
+
     /// Build the corresponding [`Attributes`]
     pub fn build(self) -> Attributes {
         Attributes {
@@ -2,8 +2,9 @@ use ockam_core::compat::sync::Arc;
 use ockam_node::{InMemoryKeyValueStorage, KeyValueStorage};
 use ockam_vault::legacy::{KeyId, StoredSecret};
 use ockam_vault::{
-    SoftwareVaultForSecureChannels, SoftwareVaultForSigning, SoftwareVaultForVerifyingSignatures,
-    VaultForSecureChannels, VaultForSigning, VaultForVerifyingSignatures,
+    KeyEscrow, KeyUsageTracker, SoftwareVaultForSecureChannels, SoftwareVaultForSigning,
+    SoftwareVaultForVerifyingSignatures, VaultForSecureChannels, VaultForSigning,
+    VaultForVerifyingSignatures,
 };
 
 /// Storage for Vault persistent values
@@ -20,6 +21,10 @@ pub struct Vault {
     pub credential_vault: Arc<dyn VaultForSigning>,
     /// Vault used for verifying signature and sha256
     pub verifying_vault: Arc<dyn VaultForVerifyingSignatures>,
+    /// Usage of the keys of the software vaults, when it is tracked
+    pub key_usage: Option<KeyUsageTracker>,
+    /// Escrow of the signing keys of the software vaults, when it is configured
+    pub key_escrow: Option<KeyEscrow>,
 }
 
 impl Vault {
@@ -35,6 +40,8 @@ impl Vault {
             secure_channel_vault,
             credential_vault,
             verifying_vault,
+            key_usage: None,
+            key_escrow: None,
         }
     }
 
@@ -82,7 +89,102 @@ impl Vault {
         path: &std::path::Path,
     ) -> ockam_core::Result<Vault> {
         let storage = ockam_vault::storage::PersistentStorage::create(path).await?;
-        Ok(Self::create_with_persistent_storage(storage))
+        let key_usage_storage =
+            ockam_vault::storage::PersistentKeyUsageStorage::create(&Self::key_usage_path(path))
+                .await?;
+        Ok(Self::create_with_key_usage_tracker(
+            storage,
+            KeyUsageTracker::new(key_usage_storage),
+        ))
+    }
+
+    /// Path of the file storing the usage of the keys of a vault persisted at `path`
+    #[cfg(feature = "std")]
+    pub fn key_usage_path(path: &std::path::Path) -> std::path::PathBuf {
+        path.with_extension("usage.jsonl")
+    }
+
+    /// Create Software Vaults with [`PersistentStorage`] with a given path, escrowing
+    /// the signing keys and the static X25519 keys according to a [`KeyEscrowPolicy`].
+    /// The keys already in the vault are escrowed too
+    #[cfg(feature = "std")]
+    pub async fn create_with_persistent_storage_path_and_key_escrow(
+        path: &std::path::Path,
+        policy: ockam_vault::KeyEscrowPolicy,
+    ) -> ockam_core::Result<Vault> {
+        let storage = ockam_vault::storage::PersistentStorage::create(path).await?;
+        let key_usage_storage =
+            ockam_vault::storage::PersistentKeyUsageStorage::create(&Self::key_usage_path(path))
+                .await?;
+        let key_escrow_storage =
+            ockam_vault::storage::PersistentKeyEscrowStorage::create(&Self::key_escrow_path(path))
+                .await?;
+        let key_escrow = KeyEscrow::new(policy, key_escrow_storage);
+        key_escrow.escrow_existing_keys(storage.as_ref()).await?;
+        Ok(Self::create_with_key_escrow(
+            storage,
+            KeyUsageTracker::new(key_usage_storage),
+            key_escrow,
+        ))
+    }
+
+    /// Path of the file storing the escrowed keys of a vault persisted at `path`
+    #[cfg(feature = "std")]
+    pub fn key_escrow_path(path: &std::path::Path) -> std::path::PathBuf {
+        path.with_extension("escrow.json")
+    }
+
+    /// Create Software Vaults with a given [`VaultStorage`], counting the usage of their keys
+    /// and escrowing the identity, credential and secure channel keys when they are created
+    pub fn create_with_key_escrow(
+        storage: VaultStorage,
+        key_usage: KeyUsageTracker,
+        key_escrow: KeyEscrow,
+    ) -> Vault {
+        let mut vault = Self::new(
+            Arc::new(
+                SoftwareVaultForSigning::new(storage.clone())
+                    .with_key_usage_tracker(key_usage.clone())
+                    .with_key_escrow(key_escrow.clone()),
+            ),
+            Arc::new(
+                SoftwareVaultForSecureChannels::new(storage.clone())
+                    .with_key_usage_tracker(key_usage.clone())
+                    .with_key_escrow(key_escrow.clone()),
+            ),
+            Arc::new(
+                SoftwareVaultForSigning::new(storage)
+                    .with_key_usage_tracker(key_usage.clone())
+                    .with_key_escrow(key_escrow.clone()),
+            ),
+            Arc::new(SoftwareVaultForVerifyingSignatures {}),
+        );
+        vault.key_usage = Some(key_usage);
+        vault.key_escrow = Some(key_escrow);
+        vault
+    }
+
+    /// Create Software Vaults with a given [`VaultStorage`], counting the usage of their keys
+    pub fn create_with_key_usage_tracker(
+        storage: VaultStorage,
+        key_usage: KeyUsageTracker,
+    ) -> Vault {
+        let mut vault = Self::new(
+            Arc::new(
+                SoftwareVaultForSigning::new(storage.clone())
+                    .with_key_usage_tracker(key_usage.clone()),
+            ),
+            Arc::new(
+                SoftwareVaultForSecureChannels::new(storage.clone())
+                    .with_key_usage_tracker(key_usage.clone()),
+            ),
+            Arc::new(
+                SoftwareVaultForSigning::new(storage).with_key_usage_tracker(key_usage.clone()),
+            ),
+            Arc::new(SoftwareVaultForVerifyingSignatures {}),
+        );
+        vault.key_usage = Some(key_usage);
+        vault
     }
 
     /// Create Software Vaults with a given [`VaultStorage`]r
@@ -3,17 +3,19 @@ use ockam_core::compat::sync::Arc;
 use ockam_core::{route, Address, AllowAll, Any, DenyAll, Mailboxes, Result, Routed, Worker};
 use ockam_identity::models::{CredentialSchemaIdentifier, Identifier};
 use ockam_identity::secure_channels::secure_channels;
-use ockam_identity::utils::AttributesBuilder;
+use ockam_identity::utils::{now, AttributesBuilder};
 use ockam_identity::{
-    AuthorityService, DecryptionResponse, EncryptionRequest, EncryptionResponse,
-    IdentityAccessControlBuilder, IdentitySecureChannelLocalInfo, SecureChannelListenerOptions,
-    SecureChannelOptions, SecureChannels, TrustContext, TrustEveryonePolicy, TrustIdentifierPolicy,
-    Vault,
+    AttributesEntry, AuthorityService, DecryptionResponse, EncryptionRequest, EncryptionResponse,
+    IdentityAccessControlBuilder, IdentityAttributesWriter, IdentitySecureChannelLocalInfo,
+    SecureChannelCipherSuite, SecureChannelCompression, SecureChannelListenerOptions,
+    SecureChannelOptions, SecureChannelPeer, SecureChannels, TrustContext, TrustEveryonePolicy,
+    TrustIdentifierPolicy, Vault,
 };
 use ockam_node::{Context, MessageReceiveOptions, WorkerBuilder};
 use ockam_vault::{
     SoftwareVaultForSecureChannels, SoftwareVaultForSigning, SoftwareVaultForVerifyingSignatures,
 };
+use std::collections::BTreeMap;
 use std::sync::atomic::{AtomicU8, Ordering};
 
 #[ockam_macros::test]
@@ -85,6 +87,464 @@ async fn test_channel(ctx: &mut Context) -> Result<()> {
     ctx.stop().await
 }
 
+#[ockam_macros::test]
+async fn test_channel_peer_attributes(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    let attributes = AttributesEntry::new(
+        BTreeMap::from([(b"role".to_vec(), b"admin".to_vec())]),
+        now()?,
+        None,
+        None,
+    );
+    secure_channels
+        .identities()
+        .repository()
+        .put_attributes(alice.identifier(), attributes)
+        .await?;
+
+    let bob_listener = secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "bob_listener",
+            SecureChannelListenerOptions::new(),
+        )
+        .await?;
+
+    let alice_channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route!["bob_listener"],
+            SecureChannelOptions::new(),
+        )
+        .await?;
+
+    let mut child_ctx = ctx
+        .new_detached_with_mailboxes(Mailboxes::main(
+            "child",
+            Arc::new(AllowAll),
+            Arc::new(AllowAll),
+        ))
+        .await?;
+
+    ctx.flow_controls()
+        .add_consumer("child", bob_listener.flow_control_id());
+
+    child_ctx
+        .send(
+            route![alice_channel, child_ctx.address()],
+            "Hello, Bob!".to_string(),
+        )
+        .await?;
+
+    let msg = child_ctx.receive::<String>().await?;
+
+    let peer = SecureChannelPeer::from_local_message(
+        msg.local_message(),
+        secure_channels
+            .identities()
+            .repository()
+            .as_attributes_reader(),
+    )
+    .await?;
+    assert_eq!(peer.identifier(), alice.identifier());
+    assert!(peer.has_attribute("role", "admin"));
+    assert_eq!(peer.attribute_str("role"), Some("admin".to_string()));
+    assert_eq!(peer.attribute("project"), None);
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn test_channel_bandwidth_usage(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    let bob_listener = secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "bob_listener",
+            SecureChannelListenerOptions::new(),
+        )
+        .await?;
+
+    let alice_channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route!["bob_listener"],
+            SecureChannelOptions::new(),
+        )
+        .await?;
+
+    let mut child_ctx = ctx
+        .new_detached_with_mailboxes(Mailboxes::main(
+            "child",
+            Arc::new(AllowAll),
+            Arc::new(AllowAll),
+        ))
+        .await?;
+
+    ctx.flow_controls()
+        .add_consumer("child", bob_listener.flow_control_id());
+
+    child_ctx
+        .send(
+            route![alice_channel, child_ctx.address()],
+            "Hello, Bob!".to_string(),
+        )
+        .await?;
+    child_ctx.receive::<String>().await?;
+
+    let usage = secure_channels.bandwidth_usage();
+
+    // alice sent bytes to bob and bob received them from alice
+    let sent_to_bob = usage.get(bob.identifier());
+    let received_from_alice = usage.get(alice.identifier());
+    assert!(sent_to_bob.bytes_sent > 0);
+    assert_eq!(sent_to_bob.bytes_received, 0);
+    assert_eq!(received_from_alice.bytes_received, sent_to_bob.bytes_sent);
+
+    // taking the counters resets them
+    assert_eq!(usage.take().len(), 2);
+    assert_eq!(usage.get(bob.identifier()).total(), 0);
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn test_channel_peers(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "bob_listener",
+            SecureChannelListenerOptions::new(),
+        )
+        .await?;
+    secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "bob_trusting_alice",
+            SecureChannelListenerOptions::new()
+                .with_trust_policy(TrustIdentifierPolicy::new(alice.identifier().clone())),
+        )
+        .await?;
+
+    for listener in ["bob_listener", "bob_trusting_alice"] {
+        secure_channels
+            .create_secure_channel(
+                ctx,
+                alice.identifier(),
+                route![listener],
+                SecureChannelOptions::new(),
+            )
+            .await?;
+    }
+    ctx.sleep(Duration::from_millis(100)).await;
+
+    // the initiator records both channels, the listener trusting everyone records nothing
+    let peers = secure_channels.peers();
+    let bob_activity = peers.get(bob.identifier()).unwrap();
+    assert_eq!(bob_activity.channels_initiated, 2);
+    assert_eq!(bob_activity.channels_accepted, 0);
+    let alice_activity = peers.get(alice.identifier()).unwrap();
+    assert_eq!(alice_activity.channels_initiated, 0);
+    assert_eq!(alice_activity.channels_accepted, 1);
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn test_channel_cipher_suite_negotiation(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    let compiled = SecureChannelCipherSuite::compiled();
+    let other = SecureChannelCipherSuite::ALL
+        .into_iter()
+        .find(|s| *s != compiled)
+        .unwrap();
+
+    secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "bob_listener",
+            SecureChannelListenerOptions::new().with_cipher_suites(vec![other, compiled]),
+        )
+        .await?;
+    secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "bob_restricted_listener",
+            SecureChannelListenerOptions::new().with_cipher_suites(vec![other]),
+        )
+        .await?;
+
+    // the preferred suite is not available, the next one is selected
+    let alice_channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route!["bob_listener"],
+            SecureChannelOptions::new().with_cipher_suites(vec![other, compiled]),
+        )
+        .await?;
+    let entry = secure_channels
+        .secure_channel_registry()
+        .get_channel_by_encryptor_address(alice_channel.encryptor_address())
+        .unwrap();
+    assert_eq!(entry.cipher_suite(), compiled);
+    // both sides use the suite selected by the listener
+    assert!(secure_channels
+        .secure_channel_registry()
+        .get_channel_list()
+        .iter()
+        .all(|entry| entry.cipher_suite() == compiled));
+
+    // no proposed suite is available
+    let res = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route!["bob_listener"],
+            SecureChannelOptions::new().with_cipher_suites(vec![other]),
+        )
+        .await;
+    assert!(res.is_err());
+
+    // the listener does not accept the proposed suite
+    let res = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route!["bob_restricted_listener"],
+            SecureChannelOptions::new()
+                .with_cipher_suites(vec![compiled])
+                .with_timeout(Duration::from_millis(500)),
+        )
+        .await;
+    assert!(res.is_err());
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn test_channel_compression(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    let bob_listener = secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "bob_listener",
+            SecureChannelListenerOptions::new().with_compression(SecureChannelCompression::Deflate),
+        )
+        .await?;
+    let alice_channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route!["bob_listener"],
+            SecureChannelOptions::new().with_compression(SecureChannelCompression::Deflate),
+        )
+        .await?;
+
+    let mut child_ctx = ctx
+        .new_detached_with_mailboxes(Mailboxes::main(
+            "child",
+            Arc::new(AllowAll),
+            Arc::new(AllowAll),
+        ))
+        .await?;
+    ctx.flow_controls()
+        .add_consumer("child", bob_listener.flow_control_id());
+    ctx.flow_controls()
+        .add_consumer("child", alice_channel.flow_control_id());
+
+    // a round trip makes sure that the compression is negotiated
+    child_ctx
+        .send(
+            route![alice_channel.clone(), child_ctx.address()],
+            "Hello, Bob!".to_string(),
+        )
+        .await?;
+    let msg = child_ctx.receive::<String>().await?;
+    child_ctx
+        .send(msg.return_route(), "Hello, Alice!".to_string())
+        .await?;
+    child_ctx.receive::<String>().await?;
+
+    let compressible = "Hello again, Bob! ".repeat(100);
+    child_ctx
+        .send(
+            route![alice_channel.clone(), child_ctx.address()],
+            compressible.clone(),
+        )
+        .await?;
+    let msg = child_ctx.receive::<String>().await?;
+    assert_eq!(msg.body(), compressible);
+
+    let stats = secure_channels
+        .secure_channel_registry()
+        .get_channel_by_encryptor_address(alice_channel.encryptor_address())
+        .unwrap()
+        .compression_stats();
+    assert_eq!(stats.compression, Some(SecureChannelCompression::Deflate));
+    assert_eq!(stats.compressed_messages, 1);
+    assert!(stats.ratio().unwrap() < 0.5);
+
+    // the messages are not compressed if the listener doesn't accept it
+    secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "bob_plain_listener",
+            SecureChannelListenerOptions::new(),
+        )
+        .await?;
+    let alice_channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route!["bob_plain_listener"],
+            SecureChannelOptions::new().with_compression(SecureChannelCompression::Deflate),
+        )
+        .await?;
+    let stats = secure_channels
+        .secure_channel_registry()
+        .get_channel_by_encryptor_address(alice_channel.encryptor_address())
+        .unwrap()
+        .compression_stats();
+    assert_eq!(stats.compression, None);
+
+    ctx.stop().await
+}
+
+/// Forward the messages to the next hop of their route and record their payloads
+#[cfg(feature = "noise_interop")]
+struct RecordingHop {
+    payloads: Arc<std::sync::Mutex<Vec<Vec<u8>>>>,
+}
+
+#[cfg(feature = "noise_interop")]
+#[ockam_core::async_trait]
+impl Worker for RecordingHop {
+    type Message = Any;
+    type Context = Context;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<Any>) -> Result<()> {
+        let mut message = msg.into_local_message();
+        let transport_message = message.transport_mut();
+        self.payloads
+            .lock()
+            .unwrap()
+            .push(transport_message.payload.clone());
+        transport_message.onward_route.step()?;
+        transport_message
+            .return_route
+            .modify()
+            .prepend(ctx.address());
+        ctx.forward(message).await
+    }
+}
+
+#[cfg(feature = "noise_interop")]
+#[ockam_macros::test]
+async fn test_channel_noise_interop(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    let payloads = Arc::new(std::sync::Mutex::new(vec![]));
+    ctx.start_worker(
+        "hop",
+        RecordingHop {
+            payloads: payloads.clone(),
+        },
+    )
+    .await?;
+
+    let bob_options = SecureChannelListenerOptions::new()
+        .with_trust_policy(TrustIdentifierPolicy::new(alice.identifier().clone()))
+        .with_noise_interop();
+    let bob_listener = secure_channels
+        .create_secure_channel_listener(ctx, bob.identifier(), "bob_listener", bob_options)
+        .await?;
+
+    let alice_options = SecureChannelOptions::new()
+        .with_trust_policy(TrustIdentifierPolicy::new(bob.identifier().clone()))
+        .with_noise_interop();
+    let alice_channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route!["hop", "bob_listener"],
+            alice_options,
+        )
+        .await?;
+
+    let mut child_ctx = ctx
+        .new_detached_with_mailboxes(Mailboxes::main(
+            "child",
+            Arc::new(AllowAll),
+            Arc::new(AllowAll),
+        ))
+        .await?;
+
+    ctx.flow_controls()
+        .add_consumer("child", bob_listener.flow_control_id());
+
+    child_ctx
+        .send(
+            route![alice_channel.clone(), child_ctx.address()],
+            "Hello, Bob!".to_string(),
+        )
+        .await?;
+
+    let msg = child_ctx.receive::<String>().await?;
+
+    let local_info = IdentitySecureChannelLocalInfo::find_info(msg.local_message())?;
+    assert_eq!(&local_info.their_identity_id(), alice.identifier());
+    assert_eq!("Hello, Bob!", msg.body());
+
+    // The handshake messages are sent as specified by Noise XX, without any framing:
+    // e, then e, ee, s, es and an empty payload, then s, se and an empty payload
+    let payloads = payloads.lock().unwrap().clone();
+    let lengths: Vec<usize> = payloads.iter().map(|p| p.len()).collect();
+    assert_eq!(lengths[..3], [32, 32 + 48 + 16, 48 + 16]);
+
+    ctx.stop().await
+}
+
 #[ockam_macros::test]
 async fn test_channel_send_credentials(context: &mut Context) -> Result<()> {
     let secure_channels = secure_channels();
@@ -1136,3 +1596,60 @@ async fn should_stop_encryptor__and__decryptor__in__secure_channel(
 
     ctx.stop().await
 }
+
+#[ockam_macros::test]
+async fn should_stop_secure_channel__when__heartbeats_are_missed(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels();
+    let identities_creation = secure_channels.identities().identities_creation();
+
+    let alice = identities_creation.create_identity().await?;
+    let bob = identities_creation.create_identity().await?;
+
+    secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            bob.identifier(),
+            "bob_listener",
+            SecureChannelListenerOptions::new(),
+        )
+        .await?;
+
+    let alice_channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            alice.identifier(),
+            route!["bob_listener"],
+            SecureChannelOptions::new().with_heartbeats(Duration::from_secs(1), 2),
+        )
+        .await?;
+
+    // bob answers alice's heartbeats even though heartbeats are not enabled on his side
+    ctx.sleep(Duration::from_millis(1500)).await;
+    let alice_channel_data = secure_channels
+        .secure_channel_registry()
+        .get_channel_by_encryptor_address(alice_channel.encryptor_address())
+        .unwrap();
+    assert!(alice_channel_data.last_heartbeat().is_some());
+
+    let bob_channel = secure_channels
+        .secure_channel_registry()
+        .get_channel_list()
+        .into_iter()
+        .find(|c| !c.is_initiator())
+        .unwrap();
+    secure_channels
+        .stop_secure_channel(ctx, bob_channel.encryptor_messaging_address())
+        .await?;
+
+    // alice doesn't get any answer anymore and stops her side of the channel
+    ctx.sleep(Duration::from_secs(4)).await;
+    assert!(secure_channels
+        .secure_channel_registry()
+        .get_channel_by_encryptor_address(alice_channel.encryptor_address())
+        .is_none());
+
+    let workers = ctx.list_workers().await?;
+    assert!(!workers.contains(alice_channel.encryptor_address()));
+
+    ctx.stop().await
+}
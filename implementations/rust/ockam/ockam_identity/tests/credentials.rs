@@ -4,11 +4,11 @@ use std::time::Duration;
 use ockam_core::compat::sync::Arc;
 use ockam_core::{async_trait, Any, DenyAll};
 use ockam_core::{route, Result, Routed, Worker};
-use ockam_identity::models::CredentialSchemaIdentifier;
+use ockam_identity::models::{CredentialSchemaIdentifier, TimestampInSeconds};
 use ockam_identity::secure_channels::secure_channels;
 use ockam_identity::utils::AttributesBuilder;
 use ockam_identity::{
-    AuthorityService, CredentialAccessControl, CredentialsMemoryRetriever,
+    AuthorityService, CredentialAccessControl, CredentialsMemoryRetriever, Identities,
     SecureChannelListenerOptions, SecureChannelOptions, TrustContext, TrustIdentifierPolicy,
 };
 use ockam_node::{Context, WorkerBuilder};
@@ -310,6 +310,159 @@ async fn access_control(ctx: &mut Context) -> Result<()> {
     ctx.stop().await
 }
 
+#[ockam_macros::test]
+async fn credentials_on_demand(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels();
+    let identities = secure_channels.identities();
+    let identities_creation = identities.identities_creation();
+    let identities_repository = identities.repository();
+    let credentials = identities.credentials();
+
+    let authority = identities_creation.create_identity().await?;
+    let server = identities_creation.create_identity().await?;
+    let client = identities_creation.create_identity().await?;
+
+    let trust_context = TrustContext::new(
+        "test_trust_context_id".to_string(),
+        Some(AuthorityService::new(
+            credentials.clone(),
+            authority.identifier().clone(),
+            None,
+        )),
+    );
+
+    let server_credential = credentials
+        .credentials_creation()
+        .issue_credential(
+            authority.identifier(),
+            server.identifier(),
+            AttributesBuilder::with_schema(CredentialSchemaIdentifier(0))
+                .with_attribute("is_admin", "true")
+                .build(),
+            Duration::from_secs(60),
+        )
+        .await?;
+    let client_credential = credentials
+        .credentials_creation()
+        .issue_credential(
+            authority.identifier(),
+            client.identifier(),
+            AttributesBuilder::with_schema(CredentialSchemaIdentifier(0))
+                .with_attribute("is_user", "true")
+                .build(),
+            Duration::from_secs(60),
+        )
+        .await?;
+
+    secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            server.identifier(),
+            "listener",
+            SecureChannelListenerOptions::new()
+                .with_trust_context(trust_context.clone())
+                .with_credential(server_credential)
+                .with_credentials_on_demand(),
+        )
+        .await?;
+
+    // The client does not present its credential during the handshake,
+    // it is retrieved when the listener requests it
+    let _channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            client.identifier(),
+            route!["listener"],
+            SecureChannelOptions::new()
+                .with_trust_context(trust_context)
+                .with_credentials_retriever(
+                    "test_trust_context_id",
+                    Arc::new(CredentialsMemoryRetriever::new(client_credential)),
+                ),
+        )
+        .await?;
+    ctx.sleep(Duration::from_millis(250)).await;
+
+    let attrs = identities_repository
+        .get_attributes(client.identifier())
+        .await?
+        .unwrap();
+    assert_eq!(
+        attrs.attrs().get("is_user".as_bytes()).unwrap().as_slice(),
+        b"true"
+    );
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn credentials_refresh(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels();
+    let identities = secure_channels.identities();
+    let identities_creation = identities.identities_creation();
+    let credentials = identities.credentials();
+
+    let authority = identities_creation.create_identity().await?;
+    let server = identities_creation.create_identity().await?;
+    let client = identities_creation.create_identity().await?;
+
+    let trust_context = TrustContext::new(
+        "test_trust_context_id".to_string(),
+        Some(AuthorityService::new(
+            credentials.clone(),
+            authority.identifier().clone(),
+            None,
+        )),
+    );
+
+    let client_credential = credentials
+        .credentials_creation()
+        .issue_credential(
+            authority.identifier(),
+            client.identifier(),
+            AttributesBuilder::with_schema(CredentialSchemaIdentifier(0))
+                .with_attribute("is_user", "true")
+                .build(),
+            Duration::from_secs(60),
+        )
+        .await?;
+
+    secure_channels
+        .create_secure_channel_listener(
+            ctx,
+            server.identifier(),
+            "listener",
+            SecureChannelListenerOptions::new()
+                .with_trust_context(trust_context.clone())
+                .with_credentials_refresh(Duration::from_secs(1), Duration::ZERO),
+        )
+        .await?;
+
+    // The client presents its credential during the handshake,
+    // and again each time the listener asks for a fresh one
+    let _channel = secure_channels
+        .create_secure_channel(
+            ctx,
+            client.identifier(),
+            route!["listener"],
+            SecureChannelOptions::new()
+                .with_credential(client_credential.clone())
+                .with_credentials_retriever(
+                    "test_trust_context_id",
+                    Arc::new(CredentialsMemoryRetriever::new(client_credential)),
+                ),
+        )
+        .await?;
+    ctx.sleep(Duration::from_millis(1500)).await;
+
+    let metrics = secure_channels.credentials_refresh_metrics();
+    assert_eq!(metrics.requested(), 1);
+    assert_eq!(metrics.succeeded(), 1);
+    assert_eq!(metrics.failed(), 0);
+
+    ctx.stop().await
+}
+
 struct CountingWorker {
     msgs_count: Arc<AtomicI8>,
 }
@@ -329,3 +482,111 @@ impl Worker for CountingWorker {
         Ok(())
     }
 }
+
+#[ockam_macros::test]
+async fn delegated_credential(ctx: &mut Context) -> Result<()> {
+    let secure_channels = secure_channels();
+    let identities = secure_channels.identities();
+    let identities_creation = identities.identities_creation();
+    let credentials = identities.credentials();
+    let credentials_creation = credentials.credentials_creation();
+    let credentials_verification = credentials.credentials_verification();
+
+    let authority = identities_creation.create_identity().await?;
+    let sub_authority = identities_creation.create_identity().await?;
+    let member = identities_creation.create_identity().await?;
+    let authorities = [authority.identifier().clone()];
+
+    let issuer_credential = credentials_creation
+        .issue_credential(
+            authority.identifier(),
+            sub_authority.identifier(),
+            AttributesBuilder::with_schema(CredentialSchemaIdentifier(0))
+                .with_attribute("ockam-issuer:site", "factory-7")
+                .build(),
+            Duration::from_secs(120),
+        )
+        .await?;
+
+    // the credential is only accepted with the issuer credential of the sub-authority
+    let mut credential = credentials_creation
+        .issue_credential(
+            sub_authority.identifier(),
+            member.identifier(),
+            AttributesBuilder::with_schema(CredentialSchemaIdentifier(0))
+                .with_attribute("site", "factory-7")
+                .build(),
+            Duration::from_secs(60),
+        )
+        .await?;
+    assert!(credentials_verification
+        .verify_credential(Some(member.identifier()), &authorities, &credential)
+        .await
+        .is_err());
+    credential.issuer_credential = Some(Box::new(issuer_credential.clone()));
+    let data = credentials_verification
+        .verify_credential(Some(member.identifier()), &authorities, &credential)
+        .await?;
+    assert_eq!(&data.purpose_key_data.subject, sub_authority.identifier());
+
+    // the sub-authority cannot attest attributes outside of its scope
+    let mut credential = credentials_creation
+        .issue_credential(
+            sub_authority.identifier(),
+            member.identifier(),
+            AttributesBuilder::with_schema(CredentialSchemaIdentifier(0))
+                .with_attribute("site", "factory-8")
+                .build(),
+            Duration::from_secs(60),
+        )
+        .await?;
+    credential.issuer_credential = Some(Box::new(issuer_credential));
+    assert!(credentials_verification
+        .verify_credential(Some(member.identifier()), &authorities, &credential)
+        .await
+        .is_err());
+
+    ctx.stop().await
+}
+
+#[ockam_macros::test]
+async fn expired_credential_measures_clock_skew(ctx: &mut Context) -> Result<()> {
+    let identities = Identities::builder()
+        .with_clock_skew_tolerance(TimestampInSeconds(1))
+        .build();
+    let identities_creation = identities.identities_creation();
+    let credentials = identities.credentials();
+
+    let authority = identities_creation.create_identity().await?;
+    let member = identities_creation.create_identity().await?;
+    let credential = credentials
+        .credentials_creation()
+        .issue_credential(
+            authority.identifier(),
+            member.identifier(),
+            AttributesBuilder::with_schema(CredentialSchemaIdentifier(0))
+                .with_attribute("role", "member")
+                .build(),
+            Duration::ZERO,
+        )
+        .await?;
+
+    // a credential presented after its expiration shows that the clock
+    // of its issuer might be behind ours
+    ctx.sleep(Duration::from_millis(2100)).await;
+    assert!(credentials
+        .credentials_verification()
+        .verify_credential(
+            Some(member.identifier()),
+            &[authority.identifier().clone()],
+            &credential
+        )
+        .await
+        .is_err());
+    let clock_skew = identities.clock_skew();
+    let measurement = clock_skew.measurement(authority.identifier()).unwrap();
+    assert!(measurement.skew <= -2);
+    assert!(clock_skew.exceeds_tolerance(&measurement));
+
+    ctx.stop().await
+}
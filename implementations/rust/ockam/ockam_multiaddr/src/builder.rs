@@ -0,0 +1,166 @@
+//! Programmatic construction of [`MultiAddr`]s.
+
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::String;
+use ockam_core::{Address, Route, LOCAL};
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use crate::proto::{DnsAddr, Ip4, Ip6, Node, Project, Secure, Service, Space, Tcp, Worker};
+use crate::{Error, MultiAddr, Protocol};
+
+/// Builder of [`MultiAddr`]s.
+///
+/// The protocols which can only start an address (`/project`, `/space`, `/node` and
+/// transport addresses) are constructors of the builder, and the local hops (`/service`,
+/// `/secure`, `/worker`) are appended to it, so that an address with an invalid protocol
+/// ordering can not be expressed:
+///
+/// ```
+/// # use ockam_multiaddr::MultiAddrBuilder;
+/// let addr = MultiAddrBuilder::project("default")
+///     .service("forward_to_n1")
+///     .secure("api")
+///     .service("echo")
+///     .build()
+///     .unwrap();
+/// assert_eq!(
+///     addr.to_string(),
+///     "/project/default/service/forward_to_n1/secure/api/service/echo"
+/// );
+/// ```
+///
+/// Each value is validated when it is added. The first error is returned by
+/// [`MultiAddrBuilder::build`].
+#[derive(Debug)]
+pub struct MultiAddrBuilder {
+    addr: MultiAddr,
+    error: Option<Error>,
+}
+
+impl MultiAddrBuilder {
+    /// Start an address on the local node
+    pub fn local() -> Self {
+        Self {
+            addr: MultiAddr::default(),
+            error: None,
+        }
+    }
+
+    /// Start an address with `/project/<name>`
+    pub fn project(name: impl Into<String>) -> Self {
+        Self::local().push_name(name.into(), Project::new)
+    }
+
+    /// Start an address with `/space/<name>`
+    pub fn space(name: impl Into<String>) -> Self {
+        Self::local().push_name(name.into(), Space::new)
+    }
+
+    /// Start an address with `/node/<name>`
+    pub fn node(name: impl Into<String>) -> Self {
+        Self::local().push_name(name.into(), Node::new)
+    }
+
+    /// Start an address with a TCP transport address.
+    /// The host can be an IPv4 address, an IPv6 address or a DNS name
+    pub fn tcp(host: &str, port: u16) -> Self {
+        let mut builder = Self::local();
+        if let Ok(ip) = host.parse::<Ipv4Addr>() {
+            builder = builder.push(Ip4::new(ip));
+        } else if let Ok(ip) = host.parse::<Ipv6Addr>() {
+            builder = builder.push(Ip6::new(ip));
+        } else {
+            builder = builder.push_name(host.to_owned(), DnsAddr::new);
+        }
+        builder.push(Tcp::new(port))
+    }
+
+    /// Append `/service/<name>`
+    pub fn service(self, name: impl Into<String>) -> Self {
+        self.push_name(name.into(), Service::new)
+    }
+
+    /// Append `/secure/<name>`, the address of a secure channel
+    pub fn secure(self, name: impl Into<String>) -> Self {
+        self.push_name(name.into(), Secure::new)
+    }
+
+    /// Append `/worker/<name>`
+    pub fn worker(self, name: impl Into<String>) -> Self {
+        self.push_name(name.into(), Worker::new)
+    }
+
+    /// Return the address or the first invalid value which was added to it
+    pub fn build(self) -> Result<MultiAddr, Error> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.addr),
+        }
+    }
+
+    fn push_name<'a, P: Protocol<'a>>(self, name: String, make: impl FnOnce(String) -> P) -> Self {
+        if self.error.is_some() {
+            return self;
+        }
+        if name.is_empty() || name.contains(|c: char| c == '/' || c.is_whitespace()) {
+            return self.fail(Error::message(format!(
+                "invalid value {name:?} for protocol {}",
+                P::PREFIX
+            )));
+        }
+        self.push(make(name))
+    }
+
+    fn push<'a, P: Protocol<'a>>(mut self, p: P) -> Self {
+        if self.error.is_some() {
+            return self;
+        }
+        match self.addr.push_back(p) {
+            Ok(()) => self,
+            Err(error) => self.fail(error),
+        }
+    }
+
+    fn fail(mut self, error: Error) -> Self {
+        self.error = Some(error);
+        self
+    }
+}
+
+impl MultiAddr {
+    /// Convert a route made of local addresses to a `MultiAddr` of `/service` hops
+    pub fn from_route(route: &Route) -> Result<MultiAddr, Error> {
+        let mut builder = MultiAddrBuilder::local();
+        for address in route.iter() {
+            if address.transport_type() != LOCAL {
+                return Err(Error::message(format!(
+                    "unsupported transport type {} for address {address}",
+                    address.transport_type()
+                )));
+            }
+            builder = builder.service(address.address());
+        }
+        builder.build()
+    }
+
+    /// Convert this address to a route.
+    /// This is only possible when all the protocols are local hops: `/service`, `/secure` or `/worker`
+    pub fn to_local_route(&self) -> Result<Route, Error> {
+        let mut route = Route::new();
+        for p in self.iter() {
+            let address = match p.code() {
+                Service::CODE => p.cast::<Service>().map(|s| Address::new(LOCAL, &*s)),
+                Secure::CODE => p.cast::<Secure>().map(|s| Address::new(LOCAL, &*s)),
+                Worker::CODE => p.cast::<Worker>().map(|s| Address::new(LOCAL, &*s)),
+                code => {
+                    return Err(Error::message(format!(
+                        "{self} is not a local address: unexpected protocol with code {code}"
+                    )))
+                }
+            };
+            route = route.append(address.ok_or_else(|| Error::invalid_proto(p.code()))?);
+        }
+        Ok(route.into())
+    }
+}
@@ -6,11 +6,13 @@
 //! - [`Protocol`]: A type that can be read from and written to strings and bytes.
 //! - [`Codec`]: A type that understands protocols.
 //! - [`ProtoValue`]: A section of a MultiAddr.
+//! - [`MultiAddrBuilder`]: A builder of MultiAddrs with a valid protocol ordering.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
 extern crate alloc;
 
+mod builder;
 mod error;
 mod registry;
 
@@ -28,6 +30,7 @@ use std::net::{SocketAddrV4, SocketAddrV6};
 use tinyvec::{Array, ArrayVec, TinyVec};
 
 use crate::proto::{DnsAddr, Ip4, Ip6, Tcp};
+pub use builder::MultiAddrBuilder;
 pub use error::Error;
 use ockam_core::env::FromString;
 pub use registry::{Registry, RegistryBuilder};
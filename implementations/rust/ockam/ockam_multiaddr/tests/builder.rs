@@ -0,0 +1,75 @@
+use ockam_core::{route, Address, Route, TransportType};
+use ockam_multiaddr::{MultiAddr, MultiAddrBuilder};
+use std::str::FromStr;
+
+#[test]
+fn build_valid_addresses() {
+    let addr = MultiAddrBuilder::project("p")
+        .service("echo")
+        .build()
+        .unwrap();
+    assert_eq!(
+        addr,
+        MultiAddr::from_str("/project/p/service/echo").unwrap()
+    );
+
+    let addr = MultiAddrBuilder::tcp("127.0.0.1", 4000)
+        .secure("api")
+        .worker("w")
+        .build()
+        .unwrap();
+    assert_eq!(
+        addr,
+        MultiAddr::from_str("/ip4/127.0.0.1/tcp/4000/secure/api/worker/w").unwrap()
+    );
+
+    let addr = MultiAddrBuilder::tcp("::1", 4000).build().unwrap();
+    assert_eq!(addr, MultiAddr::from_str("/ip6/::1/tcp/4000").unwrap());
+
+    let addr = MultiAddrBuilder::tcp("localhost", 4000).build().unwrap();
+    assert_eq!(
+        addr,
+        MultiAddr::from_str("/dnsaddr/localhost/tcp/4000").unwrap()
+    );
+}
+
+#[test]
+fn reject_invalid_values() {
+    assert!(MultiAddrBuilder::project("").build().is_err());
+    assert!(MultiAddrBuilder::node("n1")
+        .service("forward/to")
+        .service("echo")
+        .build()
+        .is_err());
+    assert!(MultiAddrBuilder::local()
+        .service("with space")
+        .build()
+        .is_err());
+}
+
+#[test]
+fn convert_to_and_from_routes() {
+    let route: Route = route!["relay", "api", "echo"];
+    let addr = MultiAddr::from_route(&route).unwrap();
+    assert_eq!(
+        addr,
+        MultiAddr::from_str("/service/relay/service/api/service/echo").unwrap()
+    );
+    assert_eq!(addr.to_local_route().unwrap(), route);
+
+    let addr = MultiAddrBuilder::local()
+        .secure("sc")
+        .worker("w")
+        .build()
+        .unwrap();
+    assert_eq!(addr.to_local_route().unwrap(), route!["sc", "w"]);
+
+    let remote = MultiAddrBuilder::node("n1")
+        .service("echo")
+        .build()
+        .unwrap();
+    assert!(remote.to_local_route().is_err());
+
+    let tcp: Route = route![Address::new(TransportType::new(1), "127.0.0.1:4000")];
+    assert!(MultiAddr::from_route(&tcp).is_err());
+}
@@ -1,5 +1,7 @@
 use core::fmt;
-use ockam_multiaddr::proto::{DnsAddr, Ip4, Ip6, Node, Project, Secure, Service, Space, Tcp};
+use ockam_multiaddr::proto::{
+    DnsAddr, Ip4, Ip6, Node, Project, Route, Secure, Service, Space, Tcp,
+};
 use ockam_multiaddr::{Code, Match, MultiAddr, Protocol};
 use quickcheck::{quickcheck, Arbitrary, Gen};
 use rand::distributions::{Alphanumeric, DistString};
@@ -132,6 +134,10 @@ quickcheck! {
                         addr.push_back(Space::new("space")).unwrap();
                         prot.push_back(Space::CODE);
                     }
+                    Route::CODE => {
+                        addr.push_back(Route::new("route")).unwrap();
+                        prot.push_back(Route::CODE);
+                    }
                     _ => unreachable!()
                 }
             }
@@ -153,6 +159,7 @@ const PROTOS: &[Code] = &[
     Node::CODE,
     Project::CODE,
     Space::CODE,
+    Route::CODE,
 ];
 
 impl Arbitrary for Addr {
@@ -169,6 +176,7 @@ impl Arbitrary for Addr {
                 Project::CODE => a.push_back(Project::new(gen_string())).unwrap(),
                 Space::CODE => a.push_back(Space::new(gen_string())).unwrap(),
                 Node::CODE => a.push_back(Node::new(gen_string())).unwrap(),
+                Route::CODE => a.push_back(Route::new(gen_string())).unwrap(),
                 _ => unreachable!(),
             }
         }
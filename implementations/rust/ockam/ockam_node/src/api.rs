@@ -2,19 +2,76 @@
 
 use minicbor::{Decode, Encode};
 
+#[cfg(feature = "std")]
+use core::sync::atomic::{AtomicBool, Ordering};
 use ockam_core::api::Reply::Successful;
 use ockam_core::api::{Error, Reply, Request, Response};
+#[cfg(feature = "std")]
+use ockam_core::compat::sync::Arc;
 use ockam_core::compat::time::Duration;
 use ockam_core::compat::vec::Vec;
+#[cfg(feature = "std")]
+use ockam_core::errcode::{Kind, Origin};
 use ockam_core::{LocalInfo, Result, Route};
 
 use crate::{Context, MessageSendReceiveOptions};
 
+/// A token which can be used to cancel the requests sent by one or several clients.
+///
+/// Cancelling the token makes all the pending and future requests return a [`Kind::Cancelled`]
+/// error instead of waiting for their reply.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    state: Arc<CancellationState>,
+}
+
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+struct CancellationState {
+    cancelled: AtomicBool,
+    notify: tokio::sync::Notify,
+}
+
+#[cfg(feature = "std")]
+impl CancellationToken {
+    /// Create a new token, which is not cancelled
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel the token and wake up all the requests waiting on it
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::Release);
+        self.state.notify.notify_waiters();
+    }
+
+    /// Return true if the token has been cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::Acquire)
+    }
+
+    /// Wait until the token is cancelled
+    pub async fn cancelled(&self) {
+        loop {
+            // the notification future must be created before checking the flag
+            // in order not to miss a call to `cancel`
+            let notified = self.state.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
 /// This struct provides some support for making requests to another node
 /// and receiving replies
 pub struct Client {
     route: Route,
     timeout: Option<Duration>,
+    #[cfg(feature = "std")]
+    cancellation: Option<CancellationToken>,
 }
 
 impl Client {
@@ -24,9 +81,18 @@ impl Client {
         Self {
             route: route.clone(),
             timeout,
+            #[cfg(feature = "std")]
+            cancellation: None,
         }
     }
 
+    /// Abort the requests waiting for a reply when the token is cancelled
+    #[cfg(feature = "std")]
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
     /// Send a request of type T and receive a reply of type R
     ///
     /// The result is a `Result<Reply<R>>` where `Reply<R>` can contain a value of type `R` but
@@ -122,6 +188,18 @@ impl Client {
     where
         T: Encode<()>,
     {
+        // The effective timeout is the shortest one of the client timeout and the request
+        // deadline. It is sent to the server so that it can stop processing the request
+        // when nobody waits for its response anymore
+        let timeout = match (timeout, req.header().deadline()) {
+            (Some(t), Some(d)) => Some(t.min(d)),
+            (t, d) => t.or(d),
+        };
+        let req = match timeout {
+            Some(t) => req.deadline(t),
+            None => req,
+        };
+
         let mut buf = Vec::new();
         req.encode(&mut buf)?;
         trace! {
@@ -139,9 +217,27 @@ impl Client {
 
         // TODO: Check IdentityId is the same we sent message to?
         // TODO: Check response id matches request id?
-        let resp = ctx
-            .send_and_receive_extended::<Vec<u8>>(self.route.clone(), buf, options)
-            .await?;
+        let resp = ctx.send_and_receive_extended::<Vec<u8>>(self.route.clone(), buf, options);
+
+        #[cfg(feature = "std")]
+        let resp = match &self.cancellation {
+            Some(token) => {
+                tokio::select! {
+                    resp = resp => resp?,
+                    _ = token.cancelled() => {
+                        return Err(ockam_core::Error::new(
+                            Origin::Api,
+                            Kind::Cancelled,
+                            format!("the request {} has been cancelled", req.header().id()),
+                        ));
+                    }
+                }
+            }
+            None => resp.await?,
+        };
+        #[cfg(not(feature = "std"))]
+        let resp = resp.await?;
+
         let local_info = resp.local_message().local_info().to_vec();
         let body = resp.body();
 
@@ -4,8 +4,8 @@ pub type MessageSender<T> = crate::tokio::sync::mpsc::Sender<T>;
 pub type MessageReceiver<T> = crate::tokio::sync::mpsc::Receiver<T>;
 
 /// Create message channel
-pub fn message_channel<T>() -> (MessageSender<T>, MessageReceiver<T>) {
-    crate::tokio::sync::mpsc::channel(16)
+pub fn message_channel<T>(capacity: usize) -> (MessageSender<T>, MessageReceiver<T>) {
+    crate::tokio::sync::mpsc::channel(capacity)
 }
 
 /// Router sender
@@ -1,13 +1,14 @@
-use crate::channel_types::{SmallReceiver, SmallSender};
+use crate::channel_types::SmallSender;
+use crate::mailbox::MailboxReceiver;
 use crate::tokio::runtime::Handle;
-use crate::{error::*, AsyncDropSender, NodeMessage};
+use crate::{error::*, AsyncDropSender, MetricsSources, NodeMessage, WorkerInfo};
 use core::sync::atomic::AtomicUsize;
 use ockam_core::compat::collections::HashMap;
 use ockam_core::compat::sync::{Arc, RwLock};
 use ockam_core::compat::time::Duration;
 use ockam_core::compat::{string::String, vec::Vec};
 use ockam_core::flow_control::FlowControls;
-use ockam_core::{async_trait, Address, Mailboxes, RelayMessage, Result, TransportType};
+use ockam_core::{async_trait, Address, Mailboxes, Result, TransportType};
 
 #[cfg(feature = "std")]
 use core::fmt::{Debug, Formatter};
@@ -21,12 +22,14 @@ pub struct Context {
     pub(super) mailboxes: Mailboxes,
     pub(super) sender: SmallSender<NodeMessage>,
     pub(super) rt: Handle,
-    pub(super) receiver: SmallReceiver<RelayMessage>,
+    pub(super) receiver: MailboxReceiver,
     pub(super) async_drop_sender: Option<AsyncDropSender>,
     pub(super) mailbox_count: Arc<AtomicUsize>,
     /// List of transports used to resolve external addresses to local workers in routes
     pub(super) transports: Arc<RwLock<HashMap<TransportType, Arc<dyn Transport>>>>,
     pub(super) flow_controls: FlowControls,
+    /// Sources of metrics reported by the metrics collector of the node
+    pub(super) metrics_sources: MetricsSources,
 }
 
 /// This trait can be used to integrate transports into a node
@@ -128,6 +131,23 @@ impl Context {
             .take_workers()
     }
 
+    /// Return diagnostic information about all the workers and processors on a node,
+    /// such as the type of their messages and the number of messages in their mailboxes
+    pub async fn list_workers_info(&self) -> Result<Vec<WorkerInfo>> {
+        let (msg, mut reply_rx) = NodeMessage::list_workers_info();
+
+        self.sender
+            .send(msg)
+            .await
+            .map_err(NodeError::from_send_err)?;
+
+        reply_rx
+            .recv()
+            .await
+            .ok_or_else(|| NodeError::NodeState(NodeReason::Unknown).internal())??
+            .take_workers_info()
+    }
+
     /// Send a shutdown acknowledgement to the router
     pub(crate) async fn send_stop_ack(&self) -> Result<()> {
         self.sender
@@ -1,3 +1,4 @@
+use core::sync::atomic::AtomicUsize;
 use core::time::Duration;
 
 use ockam_core::compat::collections::HashMap;
@@ -11,9 +12,10 @@ use ockam_core::{
 use ockam_transport_core::Transport;
 
 use crate::async_drop::AsyncDrop;
-use crate::channel_types::{message_channel, small_channel, SmallReceiver, SmallSender};
+use crate::channel_types::{small_channel, SmallReceiver, SmallSender};
+use crate::mailbox::mailbox_channel;
 use crate::tokio::{self, runtime::Handle};
-use crate::{debugger, Context};
+use crate::{debugger, Context, MailboxOptions, MetricsSources};
 use crate::{error::*, relay::CtrlSignal, router::SenderPair, NodeMessage};
 
 /// A special type of `Context` that has no worker relay and inherits
@@ -59,11 +61,14 @@ impl Context {
         rt: Handle,
         sender: SmallSender<NodeMessage>,
         mailboxes: Mailboxes,
+        mailbox_options: MailboxOptions,
         async_drop_sender: Option<AsyncDropSender>,
         transports: Arc<RwLock<HashMap<TransportType, Arc<dyn Transport>>>>,
         flow_controls: &FlowControls,
+        metrics_sources: MetricsSources,
     ) -> (Self, SenderPair, SmallReceiver<CtrlSignal>) {
-        let (mailbox_tx, receiver) = message_channel();
+        let mailbox_count = Arc::new(AtomicUsize::new(0));
+        let (mailbox_tx, receiver) = mailbox_channel(mailbox_options, mailbox_count.clone());
         let (ctrl_tx, ctrl_rx) = small_channel();
         (
             Self {
@@ -72,9 +77,10 @@ impl Context {
                 mailboxes,
                 receiver,
                 async_drop_sender,
-                mailbox_count: Arc::new(0.into()),
+                mailbox_count,
                 transports,
                 flow_controls: flow_controls.clone(),
+                metrics_sources,
             },
             SenderPair {
                 msgs: mailbox_tx,
@@ -87,14 +93,17 @@ impl Context {
     pub(crate) fn copy_with_mailboxes(
         &self,
         mailboxes: Mailboxes,
+        mailbox_options: MailboxOptions,
     ) -> (Context, SenderPair, SmallReceiver<CtrlSignal>) {
         Context::new(
             self.runtime().clone(),
             self.sender().clone(),
             mailboxes,
+            mailbox_options,
             None,
             self.transports.clone(),
             &self.flow_controls,
+            self.metrics_sources.clone(),
         )
     }
 
@@ -107,9 +116,11 @@ impl Context {
             self.runtime().clone(),
             self.sender().clone(),
             mailboxes,
+            MailboxOptions::default(),
             Some(drop_sender),
             self.transports.clone(),
             &self.flow_controls,
+            self.metrics_sources.clone(),
         )
     }
 
@@ -169,7 +180,7 @@ impl Context {
 
         // Create a "detached relay" and register it with the router
         let (msg, mut rx) =
-            NodeMessage::start_worker(addresses, sender, true, Arc::clone(&self.mailbox_count));
+            NodeMessage::start_worker(addresses, sender, true, ctx.mailbox_count(), None);
         self.sender
             .send(msg)
             .await
@@ -195,7 +206,7 @@ mod tests {
 
         // after a copy with new mailboxes the list of transports should be intact
         let mailboxes = Mailboxes::new(Mailbox::deny_all("address"), vec![]);
-        let (copy, _, _) = ctx.copy_with_mailboxes(mailboxes.clone());
+        let (copy, _, _) = ctx.copy_with_mailboxes(mailboxes.clone(), MailboxOptions::default());
         assert!(copy.is_transport_registered(transport.transport_type()));
 
         // after a detached copy with new mailboxes the list of transports should be intact
@@ -0,0 +1,25 @@
+use ockam_core::compat::string::String;
+use ockam_core::compat::sync::{Arc, RwLock};
+use ockam_core::compat::vec::Vec;
+
+use crate::Context;
+
+/// Metrics of a service running on a node
+///
+/// The registered sources are reported by the metrics collector of the node, next to the
+/// metrics of the runtime, when the `metrics` feature is enabled and `OCKAM_METRICS_PATH` is set.
+pub trait MetricsSource: Send + Sync + 'static {
+    /// Return the current value of each metric, by name
+    fn collect(&self) -> Vec<(String, u64)>;
+}
+
+/// Sources of metrics shared by all the contexts of a node
+pub(crate) type MetricsSources = Arc<RwLock<Vec<Arc<dyn MetricsSource>>>>;
+
+impl Context {
+    /// Register a source of metrics reported by the metrics collector of the node
+    pub fn register_metrics_source(&self, source: Arc<dyn MetricsSource>) {
+        let mut sources = self.metrics_sources.write().unwrap();
+        sources.push(source);
+    }
+}
@@ -1,6 +1,7 @@
 #[allow(clippy::module_inception)]
 mod context;
 mod context_lifecycle;
+mod metrics_sources;
 mod receive_message;
 mod register_router;
 mod send_message;
@@ -10,6 +11,7 @@ mod worker_lifecycle;
 
 pub use context::*;
 pub use context_lifecycle::*;
+pub use metrics_sources::*;
 pub use receive_message::*;
 pub use register_router::*;
 pub use send_message::*;
@@ -272,10 +272,7 @@ impl Context {
         }
 
         // Send the packed user message with associated route
-        sender
-            .send(relay_msg)
-            .await
-            .map_err(NodeError::from_send_err)?;
+        sender.send(relay_msg).await?;
 
         Ok(())
     }
@@ -357,10 +354,7 @@ impl Context {
         }
 
         // Forward the message
-        sender
-            .send(relay_msg)
-            .await
-            .map_err(NodeError::from_send_err)?;
+        sender.send(relay_msg).await?;
 
         Ok(())
     }
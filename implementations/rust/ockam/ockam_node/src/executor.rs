@@ -4,13 +4,15 @@ use crate::channel_types::SmallSender;
 use crate::{
     router::{Router, SenderPair},
     tokio::runtime::{Handle, Runtime},
-    NodeMessage,
+    MetricsSources, NodeMessage,
 };
 use core::future::Future;
 use ockam_core::{Address, Result};
 
 #[cfg(feature = "metrics")]
 use crate::metrics::Metrics;
+#[cfg(feature = "metrics")]
+use ockam_core::compat::sync::Arc;
 
 // This import is available on emebedded but we don't use the metrics
 // collector, thus don't need it in scope.
@@ -37,6 +39,8 @@ pub struct Executor {
     /// Metrics collection endpoint
     #[cfg(feature = "metrics")]
     metrics: Arc<Metrics>,
+    /// Sources of metrics registered by the workers of the node
+    metrics_sources: MetricsSources,
 }
 
 impl Executor {
@@ -44,13 +48,15 @@ impl Executor {
     pub fn new(flow_controls: &FlowControls) -> Self {
         let rt = Runtime::new().unwrap();
         let router = Router::new(flow_controls);
+        let metrics_sources = MetricsSources::default();
         #[cfg(feature = "metrics")]
-        let metrics = Metrics::new(&rt, router.get_metrics_readout());
+        let metrics = Metrics::new(&rt, router.get_metrics_readout(), metrics_sources.clone());
         Self {
             rt,
             router,
             #[cfg(feature = "metrics")]
             metrics,
+            metrics_sources,
         }
     }
 
@@ -64,6 +70,11 @@ impl Executor {
         self.router.sender()
     }
 
+    /// Get access to the sources of metrics shared by the contexts of the node
+    pub(crate) fn metrics_sources(&self) -> MetricsSources {
+        self.metrics_sources.clone()
+    }
+
     /// Get access to the underlying async runtime (by default `tokio`)
     pub(crate) fn runtime(&self) -> &Handle {
         self.rt.handle()
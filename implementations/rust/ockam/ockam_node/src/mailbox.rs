@@ -0,0 +1,228 @@
+use crate::channel_types::{message_channel, MessageReceiver, MessageSender};
+use crate::error::NodeError;
+use core::fmt;
+use core::str::FromStr;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use ockam_core::compat::sync::Arc;
+use ockam_core::env::FromString;
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Error, RelayMessage, Result};
+
+#[cfg(feature = "std")]
+use crate::tokio::sync::mpsc::error::TrySendError;
+
+/// Default number of messages which can wait in the mailbox of a worker
+pub const DEFAULT_MAILBOX_CAPACITY: usize = 16;
+
+/// Policy applied to a message sent to a worker whose mailbox is full
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Wait until the worker takes a message out of its mailbox.
+    /// The senders are slowed down to the pace of the worker
+    #[default]
+    Block,
+    /// Reject the new message and close the mailbox, which stops the worker.
+    /// Since no message is ever lost silently, this can be used for workers, like portals,
+    /// which relay a stream and would corrupt it by skipping messages
+    Close,
+}
+
+impl fmt::Display for OverflowPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OverflowPolicy::Block => write!(f, "block"),
+            OverflowPolicy::Close => write!(f, "close"),
+        }
+    }
+}
+
+impl FromStr for OverflowPolicy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "block" => Ok(OverflowPolicy::Block),
+            "close" => Ok(OverflowPolicy::Close),
+            _ => Err(Error::new(
+                Origin::Node,
+                Kind::Invalid,
+                "the overflow policy must be one of: block, close",
+            )),
+        }
+    }
+}
+
+impl FromString for OverflowPolicy {
+    fn from_string(s: &str) -> Result<Self> {
+        s.parse()
+    }
+}
+
+/// Capacity of the mailbox of a worker and policy applied when it is full.
+///
+/// Bounding the mailbox of a worker prevents the memory of a node from growing
+/// when the worker stalls while other workers keep sending it messages.
+/// Without the `std` feature all the overflow policies block the senders
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MailboxOptions {
+    capacity: usize,
+    overflow: OverflowPolicy,
+}
+
+impl Default for MailboxOptions {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAILBOX_CAPACITY, OverflowPolicy::default())
+    }
+}
+
+impl MailboxOptions {
+    /// Create mailbox options. A mailbox holds at least one message
+    pub fn new(capacity: usize, overflow: OverflowPolicy) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            overflow,
+        }
+    }
+
+    /// Set the capacity of the mailbox
+    pub fn with_capacity(self, capacity: usize) -> Self {
+        Self::new(capacity, self.overflow)
+    }
+
+    /// Set the overflow policy of the mailbox
+    pub fn with_overflow(self, overflow: OverflowPolicy) -> Self {
+        Self::new(self.capacity, overflow)
+    }
+
+    /// Maximum number of messages waiting in the mailbox
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Policy applied when the mailbox is full
+    pub fn overflow(&self) -> OverflowPolicy {
+        self.overflow
+    }
+}
+
+/// Sender of messages to the mailbox of a worker, applying its [`OverflowPolicy`]
+#[derive(Debug, Clone)]
+pub struct MailboxSender {
+    sender: MessageSender<RelayMessage>,
+    options: MailboxOptions,
+    /// Number of messages waiting in the mailbox
+    mailbox_count: Arc<AtomicUsize>,
+    /// Number of messages rejected because the mailbox was full
+    overflow_count: Arc<AtomicUsize>,
+    /// Set when the mailbox overflowed with the [`OverflowPolicy::Close`] policy
+    closed: Arc<AtomicBool>,
+}
+
+/// Receiving end of the mailbox of a worker
+#[derive(Debug)]
+pub(crate) struct MailboxReceiver {
+    receiver: MessageReceiver<RelayMessage>,
+    closed: Arc<AtomicBool>,
+}
+
+/// Create the mailbox of a worker, counting its messages with `mailbox_count`
+pub(crate) fn mailbox_channel(
+    options: MailboxOptions,
+    mailbox_count: Arc<AtomicUsize>,
+) -> (MailboxSender, MailboxReceiver) {
+    let (sender, receiver) = message_channel(options.capacity());
+    let closed = Arc::new(AtomicBool::new(false));
+    (
+        MailboxSender {
+            sender,
+            options,
+            mailbox_count,
+            overflow_count: Default::default(),
+            closed: closed.clone(),
+        },
+        MailboxReceiver { receiver, closed },
+    )
+}
+
+impl MailboxSender {
+    /// Options of the mailbox
+    pub fn options(&self) -> MailboxOptions {
+        self.options
+    }
+
+    /// Number of messages rejected because the mailbox was full
+    pub fn overflow_count(&self) -> usize {
+        self.overflow_count.load(Ordering::Relaxed)
+    }
+
+    /// Send a message to the mailbox
+    pub async fn send(&self, msg: RelayMessage) -> Result<()> {
+        // the message is counted before being sent, so that the receiver never
+        // takes it out of the mailbox before it is counted
+        self.mailbox_count.fetch_add(1, Ordering::Acquire);
+        let result = self.send_impl(msg).await;
+        if result.is_err() {
+            self.mailbox_count.fetch_sub(1, Ordering::Acquire);
+        }
+        result
+    }
+
+    #[cfg(feature = "std")]
+    async fn send_impl(&self, msg: RelayMessage) -> Result<()> {
+        if self.options.overflow == OverflowPolicy::Block {
+            return self
+                .sender
+                .send(msg)
+                .await
+                .map_err(NodeError::from_send_err);
+        }
+        if self.closed.load(Ordering::Acquire) {
+            return Err(self.closed_error(&msg));
+        }
+        match self.sender.try_send(msg) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Closed(msg)) => Err(NodeError::from_send_err(
+                crate::tokio::sync::mpsc::error::SendError(msg),
+            )),
+            Err(TrySendError::Full(msg)) => {
+                // Since the mailbox is full, the worker is busy and checks
+                // that its mailbox was closed before taking its next message
+                self.closed.store(true, Ordering::Release);
+                self.overflow_count.fetch_add(1, Ordering::Relaxed);
+                warn!("The mailbox of {} is full, closing it", msg.destination());
+                Err(self.closed_error(&msg))
+            }
+        }
+    }
+
+    #[cfg(not(feature = "std"))]
+    async fn send_impl(&self, msg: RelayMessage) -> Result<()> {
+        self.sender
+            .send(msg)
+            .await
+            .map_err(NodeError::from_send_err)
+    }
+
+    #[cfg(feature = "std")]
+    fn closed_error(&self, msg: &RelayMessage) -> Error {
+        Error::new(
+            Origin::Node,
+            Kind::ResourceExhausted,
+            format!(
+                "the mailbox of {} is full and was closed",
+                msg.destination()
+            ),
+        )
+    }
+}
+
+impl MailboxReceiver {
+    /// Wait for the next message of the mailbox.
+    /// Return `None` once the mailbox is closed, so that the worker stops
+    pub(crate) async fn recv(&mut self) -> Option<RelayMessage> {
+        if self.closed.load(Ordering::Acquire) {
+            return None;
+        }
+        self.receiver.recv().await
+    }
+}
@@ -1,11 +1,15 @@
-use crate::channel_types::{small_channel, MessageSender, SmallReceiver, SmallSender};
+use crate::channel_types::{small_channel, SmallReceiver, SmallSender};
 use crate::{
     error::{NodeError, NodeReason, RouterReason, WorkerReason},
     router::SenderPair,
+    MailboxOptions, MailboxSender,
+};
+use core::{
+    fmt,
+    sync::atomic::{AtomicUsize, Ordering},
 };
-use core::{fmt, sync::atomic::AtomicUsize};
 use ockam_core::compat::{string::String, sync::Arc, vec::Vec};
-use ockam_core::{Address, Error, RelayMessage, Result, TransportType};
+use ockam_core::{Address, Error, Result, TransportType};
 
 /// Messages sent from the Node to the Executor
 #[derive(Debug)]
@@ -20,11 +24,15 @@ pub enum NodeMessage {
         detached: bool,
         /// A mechanism to read channel fill-state for a worker
         mailbox_count: Arc<AtomicUsize>,
+        /// Statistics of the messages handled by the worker, none for a detached context
+        stats: Option<WorkerStats>,
         /// Reply channel for command confirmation
         reply: SmallSender<NodeReplyResult>,
     },
     /// Return a list of all worker addresses
     ListWorkers(SmallSender<NodeReplyResult>),
+    /// Return information about all the workers and processors
+    ListWorkersInfo(SmallSender<NodeReplyResult>),
     /// Add an existing address to a cluster
     SetCluster(Address, String, SmallSender<NodeReplyResult>),
     /// Stop an existing worker
@@ -54,6 +62,7 @@ impl fmt::Display for NodeMessage {
         match self {
             NodeMessage::StartWorker { .. } => write!(f, "StartWorker"),
             NodeMessage::ListWorkers(_) => write!(f, "ListWorkers"),
+            NodeMessage::ListWorkersInfo(_) => write!(f, "ListWorkersInfo"),
             NodeMessage::SetCluster(_, _, _) => write!(f, "SetCluster"),
             NodeMessage::StopWorker(_, _, _) => write!(f, "StopWorker"),
             NodeMessage::StartProcessor(_, _, _) => write!(f, "StartProcessor"),
@@ -78,11 +87,15 @@ impl NodeMessage {
     ///               relay behind it that can respond to shutdown
     ///               commands.  Setting this to `true` will disable
     ///               stop ACK support in the router
+    ///
+    /// * `stats`: statistics of the messages handled by the worker,
+    ///            updated by its relay
     pub fn start_worker(
         addrs: Vec<Address>,
         senders: SenderPair,
         detached: bool,
         mailbox_count: Arc<AtomicUsize>,
+        stats: Option<WorkerStats>,
     ) -> (Self, SmallReceiver<NodeReplyResult>) {
         let (reply, rx) = small_channel();
         (
@@ -91,6 +104,7 @@ impl NodeMessage {
                 senders,
                 detached,
                 mailbox_count,
+                stats,
                 reply,
             },
             rx,
@@ -118,6 +132,12 @@ impl NodeMessage {
         (Self::ListWorkers(tx), rx)
     }
 
+    /// Create a list workers information message and reply receiver
+    pub fn list_workers_info() -> (Self, SmallReceiver<NodeReplyResult>) {
+        let (tx, rx) = small_channel();
+        (Self::ListWorkersInfo(tx), rx)
+    }
+
     /// Create a set cluster message and reply receiver
     pub fn set_cluster(addr: Address, label: String) -> (Self, SmallReceiver<NodeReplyResult>) {
         let (tx, rx) = small_channel();
@@ -154,6 +174,53 @@ impl NodeMessage {
     }
 }
 
+/// Statistics of the messages handled by a worker, shared by its relay and the router
+#[derive(Debug, Clone)]
+pub struct WorkerStats {
+    /// Name of the type of the messages handled by the worker
+    pub(crate) message_type: &'static str,
+    /// Number of messages handled by the worker so far
+    pub(crate) processed_messages: Arc<AtomicUsize>,
+}
+
+impl WorkerStats {
+    /// Create the statistics of a worker handling messages of type `M`
+    pub fn new<M>() -> Self {
+        Self {
+            message_type: core::any::type_name::<M>(),
+            processed_messages: Default::default(),
+        }
+    }
+
+    /// Count a message handled by the worker
+    pub(crate) fn message_processed(&self) {
+        self.processed_messages.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Information about a worker or a processor, to diagnose a running node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkerInfo {
+    /// Primary address
+    pub address: Address,
+    /// All the addresses of the worker, starting with the primary address
+    pub addresses: Vec<Address>,
+    /// True for a processor
+    pub processor: bool,
+    /// True for a detached context, which does not handle messages with a worker
+    pub detached: bool,
+    /// Name of the type of the messages handled by a worker
+    pub message_type: Option<String>,
+    /// Number of messages waiting in the mailbox, if it can be known
+    pub mailbox_depth: Option<usize>,
+    /// Capacity and overflow policy of the mailbox, unless the worker is stopping
+    pub mailbox_options: Option<MailboxOptions>,
+    /// Number of messages dropped or rejected because the mailbox was full
+    pub overflow_messages: Option<usize>,
+    /// Number of messages handled by a worker so far
+    pub processed_messages: Option<usize>,
+}
+
 /// The reply/result of a Node
 pub type NodeReplyResult = core::result::Result<RouterReply, Error>;
 
@@ -164,12 +231,14 @@ pub enum RouterReply {
     Ok,
     /// A list of worker addresses
     Workers(Vec<Address>),
+    /// Information about a list of workers
+    WorkersInfo(Vec<WorkerInfo>),
     /// Message sender to a specific worker
     Sender {
         /// The address a message is being sent to
         addr: Address,
         /// The relay sender
-        sender: MessageSender<RelayMessage>,
+        sender: MailboxSender,
     },
     /// Indicate the 'ready' state of an address
     State(bool),
@@ -259,13 +328,18 @@ impl RouterReply {
         Ok(Self::Workers(v))
     }
 
+    /// Return [RouterReply::WorkersInfo] for the given workers
+    pub fn workers_info(v: Vec<WorkerInfo>) -> NodeReplyResult {
+        Ok(Self::WorkersInfo(v))
+    }
+
     /// Return [RouterReply::Sender] for the given information
-    pub fn sender(addr: Address, sender: MessageSender<RelayMessage>) -> NodeReplyResult {
+    pub fn sender(addr: Address, sender: MailboxSender) -> NodeReplyResult {
         Ok(RouterReply::Sender { addr, sender })
     }
 
     /// Consume the wrapper and return [RouterReply::Sender]
-    pub fn take_sender(self) -> Result<(Address, MessageSender<RelayMessage>)> {
+    pub fn take_sender(self) -> Result<(Address, MailboxSender)> {
         match self {
             Self::Sender { addr, sender } => Ok((addr, sender)),
             _ => Err(NodeError::NodeState(NodeReason::Unknown).internal()),
@@ -280,6 +354,14 @@ impl RouterReply {
         }
     }
 
+    /// Consume the wrapper and return [RouterReply::WorkersInfo]
+    pub fn take_workers_info(self) -> Result<Vec<WorkerInfo>> {
+        match self {
+            Self::WorkersInfo(w) => Ok(w),
+            _ => Err(NodeError::NodeState(NodeReason::Unknown).internal()),
+        }
+    }
+
     /// Consume the wrapper and return [RouterReply::State]
     pub fn take_state(self) -> Result<bool> {
         match self {
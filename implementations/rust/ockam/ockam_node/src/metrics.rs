@@ -1,4 +1,5 @@
 use crate::tokio::{runtime::Runtime, time};
+use crate::MetricsSources;
 use core::{
     sync::atomic::{AtomicBool, AtomicUsize, Ordering},
     time::Duration,
@@ -10,6 +11,7 @@ use std::{fs::OpenOptions, io::Write};
 pub struct Metrics {
     rt: Arc<Runtime>,
     router: (Arc<AtomicUsize>, Arc<AtomicUsize>),
+    sources: MetricsSources,
 }
 
 impl Metrics {
@@ -17,10 +19,12 @@ impl Metrics {
     pub(crate) fn new(
         rt: &Arc<Runtime>,
         router: (Arc<AtomicUsize>, Arc<AtomicUsize>),
+        sources: MetricsSources,
     ) -> Arc<Self> {
         Arc::new(Self {
             rt: Arc::clone(rt),
             router,
+            sources,
         })
     }
 
@@ -41,8 +45,10 @@ impl Metrics {
             .open(path)
             .expect("failed to open or create metrics collection file");
 
-        file.write_all(b"Worker busy time (% since last poll)\n")
-            .expect("failed to write metrics");
+        file.write_all(
+            b"Worker busy time (% since last poll), then the metrics of the node services\n",
+        )
+        .expect("failed to write metrics");
 
         let freq_ms = 100;
         let mut acc = MetricsReport::default();
@@ -85,10 +91,19 @@ impl Metrics {
             acc.tokio_busy_ms.insert(wid, raw_ms);
         }
 
+        let sources = self
+            .sources
+            .read()
+            .unwrap()
+            .iter()
+            .flat_map(|source| source.collect())
+            .collect();
+
         MetricsReport {
             tokio_busy_ms,
             router_addr_count,
             router_cluster_count,
+            sources,
         }
     }
 }
@@ -99,6 +114,7 @@ pub struct MetricsReport {
     tokio_busy_ms: BTreeMap<usize, u128>,
     router_addr_count: usize,
     router_cluster_count: usize,
+    sources: Vec<(String, u64)>,
 }
 
 impl MetricsReport {
@@ -107,6 +123,11 @@ impl MetricsReport {
         self.tokio_busy_ms
             .iter()
             .map(|(wid, depth)| format!("({}:{}%)", wid, depth))
+            .chain(
+                self.sources
+                    .iter()
+                    .map(|(name, value)| format!("({}:{})", name, value)),
+            )
             .collect::<Vec<String>>()
             .join(",")
     }
@@ -2,7 +2,7 @@ use ockam_core::compat::sync::Arc;
 use ockam_core::flow_control::FlowControls;
 use ockam_core::{Address, AllowAll, Mailbox, Mailboxes};
 
-use crate::{debugger, Context, Executor};
+use crate::{debugger, Context, Executor, MailboxOptions};
 
 /// A minimal worker implementation that does nothing
 pub struct NullWorker;
@@ -62,9 +62,11 @@ impl NodeBuilder {
                 Mailbox::new(addr, Arc::new(AllowAll), Arc::new(AllowAll)),
                 vec![],
             ),
+            MailboxOptions::default(),
             None,
             Default::default(),
             &flow_controls,
+            exe.metrics_sources(),
         );
 
         debugger::log_inherit_context("NODE", &ctx, &ctx);
@@ -1,6 +1,6 @@
 use crate::debugger;
 use crate::error::{NodeError, NodeReason};
-use crate::{relay::ProcessorRelay, Context, NodeMessage};
+use crate::{relay::ProcessorRelay, Context, MailboxOptions, NodeMessage};
 use ockam_core::compat::sync::Arc;
 use ockam_core::{
     errcode::{Kind, Origin},
@@ -151,7 +151,7 @@ where
     let main_address = mailboxes.main_address().clone();
 
     // Pass it to the context
-    let (ctx, sender, ctrl_rx) = context.copy_with_mailboxes(mailboxes);
+    let (ctx, sender, ctrl_rx) = context.copy_with_mailboxes(mailboxes, MailboxOptions::default());
 
     debugger::log_inherit_context("PROCESSOR", context, &ctx);
 
@@ -1,7 +1,7 @@
 use crate::channel_types::SmallReceiver;
 use crate::relay::CtrlSignal;
 use crate::tokio::runtime::Handle;
-use crate::{parser, Context};
+use crate::{parser, Context, WorkerStats};
 use ockam_core::{Message, RelayMessage, Result, Routed, Worker};
 
 /// Worker relay machinery
@@ -12,11 +12,12 @@ use ockam_core::{Message, RelayMessage, Result, Routed, Worker};
 pub struct WorkerRelay<W> {
     worker: W,
     ctx: Context,
+    stats: WorkerStats,
 }
 
 impl<W: Worker> WorkerRelay<W> {
-    pub fn new(worker: W, ctx: Context) -> Self {
-        Self { worker, ctx }
+    pub fn new(worker: W, ctx: Context, stats: WorkerStats) -> Self {
+        Self { worker, ctx, stats }
     }
 }
 
@@ -71,7 +72,9 @@ where
 
         // Call the worker handle function - pass errors up
         let routed = Self::wrap_direct_message(relay_msg)?;
-        self.worker.handle_message(&mut self.ctx, routed).await?;
+        let result = self.worker.handle_message(&mut self.ctx, routed).await;
+        self.stats.message_processed();
+        result?;
 
         // Signal to the outer loop that we would like to run again
         Ok(true)
@@ -164,8 +167,14 @@ where
     }
 
     /// Build and spawn a new worker relay, returning a send handle to it
-    pub(crate) fn init(rt: &Handle, worker: W, ctx: Context, ctrl_rx: SmallReceiver<CtrlSignal>) {
-        let relay = WorkerRelay::new(worker, ctx);
+    pub(crate) fn init(
+        rt: &Handle,
+        worker: W,
+        ctx: Context,
+        stats: WorkerStats,
+        ctrl_rx: SmallReceiver<CtrlSignal>,
+    ) {
+        let relay = WorkerRelay::new(worker, ctx, stats);
         rt.spawn(relay.run(ctrl_rx));
     }
 }
@@ -13,20 +13,20 @@ use std::sync::atomic::AtomicUsize;
 use record::{AddressMeta, AddressRecord, InternalMap};
 use state::{NodeState, RouterState};
 
-use crate::channel_types::{router_channel, MessageSender, RouterReceiver, SmallSender};
+use crate::channel_types::{router_channel, RouterReceiver, SmallSender};
 use crate::{
     error::{NodeError, NodeReason},
     relay::CtrlSignal,
-    NodeMessage, NodeReplyResult, RouterReply, ShutdownType,
+    MailboxSender, NodeMessage, NodeReplyResult, RouterReply, ShutdownType,
 };
 use ockam_core::compat::{collections::BTreeMap, sync::Arc};
 use ockam_core::flow_control::FlowControls;
-use ockam_core::{Address, RelayMessage, Result, TransportType};
+use ockam_core::{Address, Result, TransportType};
 
 /// A pair of senders to a worker relay
 #[derive(Debug)]
 pub struct SenderPair {
-    pub msgs: MessageSender<RelayMessage>,
+    pub msgs: MailboxSender,
     pub ctrl: SmallSender<CtrlSignal>,
 }
 
@@ -97,6 +97,7 @@ impl Router {
                 AddressMeta {
                     processor: false,
                     detached: true,
+                    stats: None,
                 },
             ),
         );
@@ -182,8 +183,12 @@ impl Router {
                 senders,
                 detached,
                 mailbox_count,
+                stats,
                 ref reply,
-            } => start_worker::exec(self, addrs, senders, detached, mailbox_count, reply).await?,
+            } => {
+                start_worker::exec(self, addrs, senders, detached, mailbox_count, stats, reply)
+                    .await?
+            }
             StopWorker(ref addr, ref detached, ref reply) => {
                 stop_worker::exec(self, addr, *detached, reply).await?
             }
@@ -248,6 +253,17 @@ impl Router {
                 .await
                 .map_err(|_| NodeError::NodeState(NodeReason::Unknown).internal())?,
 
+            ListWorkersInfo(sender) => sender
+                .send(RouterReply::workers_info(
+                    self.map
+                        .address_records_map()
+                        .values()
+                        .map(|record| record.info())
+                        .collect(),
+                ))
+                .await
+                .map_err(|_| NodeError::NodeState(NodeReason::Unknown).internal())?,
+
             SetCluster(addr, label, reply) => {
                 debug!("Setting cluster on address {}", addr);
                 let msg = self.map.set_cluster(label, addr);
@@ -1,19 +1,19 @@
-use crate::channel_types::{MessageSender, SmallSender};
+use crate::channel_types::SmallSender;
 use crate::relay::CtrlSignal;
 use crate::{
     error::{NodeError, NodeReason},
-    NodeReplyResult, RouterReply,
+    MailboxSender, NodeReplyResult, RouterReply, WorkerInfo, WorkerStats,
 };
 use core::sync::atomic::{AtomicUsize, Ordering};
 use ockam_core::{
     compat::{
         collections::{BTreeMap, BTreeSet},
-        string::String,
+        string::{String, ToString},
         sync::Arc,
         vec::Vec,
     },
     flow_control::FlowControls,
-    Address, RelayMessage, Result,
+    Address, Result,
 };
 
 /// Address states and associated logic
@@ -229,12 +229,14 @@ impl InternalMap {
 pub struct AddressMeta {
     pub processor: bool,
     pub detached: bool,
+    /// Statistics updated by the relay of a worker
+    pub stats: Option<WorkerStats>,
 }
 
 #[derive(Debug)]
 pub struct AddressRecord {
     address_set: Vec<Address>,
-    sender: Option<MessageSender<RelayMessage>>,
+    sender: Option<MailboxSender>,
     ctrl_tx: SmallSender<CtrlSignal>,
     state: AddressState,
     ready: ReadyState,
@@ -247,7 +249,7 @@ impl AddressRecord {
         &self.address_set
     }
 
-    pub fn sender(&self) -> MessageSender<RelayMessage> {
+    pub fn sender(&self) -> MailboxSender {
         self.sender.clone().expect("No such sender!")
     }
 
@@ -257,7 +259,7 @@ impl AddressRecord {
 
     pub fn new(
         address_set: Vec<Address>,
-        sender: MessageSender<RelayMessage>,
+        sender: MailboxSender,
         ctrl_tx: SmallSender<CtrlSignal>,
         msg_count: Arc<AtomicUsize>,
         meta: AddressMeta,
@@ -273,8 +275,24 @@ impl AddressRecord {
         }
     }
 
-    pub fn increment_msg_count(&self) {
-        self.msg_count.fetch_add(1, Ordering::Acquire);
+    /// Return diagnostic information about this worker
+    pub fn info(&self) -> WorkerInfo {
+        WorkerInfo {
+            address: self.address_set[0].clone(),
+            addresses: self.address_set.clone(),
+            processor: self.meta.processor,
+            detached: self.meta.detached,
+            message_type: self.meta.stats.as_ref().map(|s| s.message_type.to_string()),
+            // the mailbox of processors is not tracked
+            mailbox_depth: (!self.meta.processor).then(|| self.msg_count.load(Ordering::Acquire)),
+            mailbox_options: self.sender.as_ref().map(|s| s.options()),
+            overflow_messages: self.sender.as_ref().map(|s| s.overflow_count()),
+            processed_messages: self
+                .meta
+                .stats
+                .as_ref()
+                .map(|s| s.processed_messages.load(Ordering::Relaxed)),
+        }
     }
 
     /// Signal this worker to stop -- it will no longer be able to receive messages
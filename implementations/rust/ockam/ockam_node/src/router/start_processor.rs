@@ -48,6 +48,7 @@ async fn start(
         AddressMeta {
             processor: true,
             detached: false,
+            stats: None,
         },
     );
 
@@ -2,7 +2,7 @@ use super::{AddressMeta, AddressRecord, NodeState, Router, SenderPair};
 use crate::channel_types::SmallSender;
 use crate::{
     error::{NodeError, NodeReason},
-    NodeReplyResult, RouterReason, RouterReply,
+    NodeReplyResult, RouterReason, RouterReply, WorkerStats,
 };
 use core::sync::atomic::AtomicUsize;
 #[cfg(feature = "std")]
@@ -19,10 +19,11 @@ pub(super) async fn exec(
     senders: SenderPair,
     detached: bool,
     metrics: Arc<AtomicUsize>,
+    stats: Option<WorkerStats>,
     reply: &SmallSender<NodeReplyResult>,
 ) -> Result<()> {
     match router.state.node_state() {
-        NodeState::Running => start(router, addrs, senders, detached, metrics, reply).await,
+        NodeState::Running => start(router, addrs, senders, detached, metrics, stats, reply).await,
         NodeState::Stopping(_) => reject(reply).await,
         NodeState::Dead => unreachable!(),
     }?;
@@ -35,6 +36,7 @@ async fn start(
     senders: SenderPair,
     detached: bool,
     metrics: Arc<AtomicUsize>,
+    stats: Option<WorkerStats>,
     reply: &SmallSender<NodeReplyResult>,
 ) -> Result<()> {
     let primary_addr = addrs
@@ -58,6 +60,7 @@ async fn start(
         AddressMeta {
             processor: false,
             detached,
+            stats,
         },
     );
 
@@ -33,7 +33,6 @@ pub(super) async fn resolve(
     match router.map.get_address_record(&primary_address) {
         Some(record) if record.check() => {
             trace!("{} OK", base);
-            record.increment_msg_count();
             reply.send(RouterReply::sender(addr.clone(), record.sender()))
         }
         Some(_) => {
@@ -1,6 +1,6 @@
 use crate::debugger;
 use crate::error::{NodeError, NodeReason};
-use crate::{relay::WorkerRelay, Context, NodeMessage};
+use crate::{relay::WorkerRelay, Context, MailboxOptions, NodeMessage, WorkerStats};
 use ockam_core::compat::sync::Arc;
 use ockam_core::{
     errcode::{Kind, Origin},
@@ -40,6 +40,7 @@ where
             outgoing_ac: Arc::new(AllowAll),
             worker: self.worker,
             address: address.into(),
+            mailbox_options: MailboxOptions::default(),
         }
     }
 
@@ -48,6 +49,7 @@ where
         WorkerBuilderMultipleAddresses {
             mailboxes,
             worker: self.worker,
+            mailbox_options: MailboxOptions::default(),
         }
     }
 }
@@ -58,6 +60,7 @@ where
 {
     mailboxes: Mailboxes,
     worker: W,
+    mailbox_options: MailboxOptions,
 }
 
 impl<W> WorkerBuilderMultipleAddresses<W>
@@ -66,7 +69,13 @@ where
 {
     /// Consume this builder and start a new Ockam [`Worker`] from the given context
     pub async fn start(self, context: &Context) -> Result<()> {
-        start(context, self.mailboxes, self.worker).await
+        start(context, self.mailboxes, self.mailbox_options, self.worker).await
+    }
+
+    /// Set the capacity of the mailbox of the worker and the policy applied when it is full
+    pub fn with_mailbox_options(mut self, mailbox_options: MailboxOptions) -> Self {
+        self.mailbox_options = mailbox_options;
+        self
     }
 }
 
@@ -78,6 +87,7 @@ where
     outgoing_ac: Arc<dyn OutgoingAccessControl>,
     address: Address,
     worker: W,
+    mailbox_options: MailboxOptions,
 }
 
 impl<W> WorkerBuilderOneAddress<W>
@@ -89,6 +99,7 @@ where
         start(
             context,
             Mailboxes::main(self.address, self.incoming_ac, self.outgoing_ac),
+            self.mailbox_options,
             self.worker,
         )
         .await
@@ -134,10 +145,21 @@ where
         self.outgoing_ac = outgoing_access_control.clone();
         self
     }
+
+    /// Set the capacity of the mailbox of the worker and the policy applied when it is full
+    pub fn with_mailbox_options(mut self, mailbox_options: MailboxOptions) -> Self {
+        self.mailbox_options = mailbox_options;
+        self
+    }
 }
 
 /// Consume this builder and start a new Ockam [`Worker`] from the given context
-async fn start<W>(context: &Context, mailboxes: Mailboxes, worker: W) -> Result<()>
+async fn start<W>(
+    context: &Context,
+    mailboxes: Mailboxes,
+    mailbox_options: MailboxOptions,
+    worker: W,
+) -> Result<()>
 where
     W: Worker<Context = Context>,
 {
@@ -151,16 +173,18 @@ where
     let addresses = mailboxes.addresses();
 
     // Pass it to the context
-    let (ctx, sender, ctrl_rx) = context.copy_with_mailboxes(mailboxes);
+    let (ctx, sender, ctrl_rx) = context.copy_with_mailboxes(mailboxes, mailbox_options);
 
     debugger::log_inherit_context("WORKER", context, &ctx);
 
     // Then initialise the worker message relay
-    WorkerRelay::init(context.runtime(), worker, ctx, ctrl_rx);
+    let mailbox_count = ctx.mailbox_count();
+    let stats = WorkerStats::new::<W::Message>();
+    WorkerRelay::init(context.runtime(), worker, ctx, stats.clone(), ctrl_rx);
 
     // Send start request to router
     let (msg, mut rx) =
-        NodeMessage::start_worker(addresses, sender, false, context.mailbox_count());
+        NodeMessage::start_worker(addresses, sender, false, mailbox_count, Some(stats));
     context
         .sender()
         .send(msg)
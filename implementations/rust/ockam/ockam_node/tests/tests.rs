@@ -1,14 +1,19 @@
 use core::sync::atomic::{AtomicBool, Ordering};
 use core::time::Duration;
+use ockam_core::api::{Request, RequestHeader};
 use ockam_core::compat::{
     boxed::Box,
     string::{String, ToString},
     sync::Arc,
 };
+use ockam_core::errcode::Kind;
 use ockam_core::{async_trait, Address, AllowAll, Any, Decodable, DenyAll, Message, LOCAL};
 use ockam_core::{route, Processor, Result, Routed, Worker};
+use ockam_node::api::{CancellationToken, Client};
 use ockam_node::compat::futures::FutureExt;
-use ockam_node::{Context, MessageReceiveOptions, NodeBuilder};
+use ockam_node::{
+    Context, MailboxOptions, MessageReceiveOptions, NodeBuilder, OverflowPolicy, WorkerBuilder,
+};
 use serde::{Deserialize, Serialize};
 use std::sync::atomic::{AtomicI8, AtomicU32};
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -560,3 +565,124 @@ async fn starting_worker_with_dup_address_should_fail(ctx: &mut Context) -> Resu
         .is_err());
     ctx.stop().await
 }
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn client_request__cancelled__should_not_wait_for_the_reply(ctx: &mut Context) -> Result<()> {
+    let mut server = ctx.new_detached("server", AllowAll, AllowAll).await?;
+    let token = CancellationToken::new();
+    let client = Client::new(&route!["server"], Some(Duration::from_secs(10)))
+        .with_cancellation(token.clone());
+
+    // the server never replies, it only checks the request deadline before cancelling
+    let cancel = async {
+        let request = server.receive::<Vec<u8>>().await?.body();
+        let header: RequestHeader = minicbor::decode(&request)?;
+        assert_eq!(header.deadline(), Some(Duration::from_secs(10)));
+        token.cancel();
+        Ok::<(), ockam_core::Error>(())
+    };
+    let (reply, cancelled) = tokio::join!(client.ask::<(), ()>(ctx, Request::get("/")), cancel);
+    cancelled?;
+
+    let error = reply.err().expect("the request should be cancelled");
+    assert_eq!(error.code().kind, Kind::Cancelled);
+    assert!(token.is_cancelled());
+    ctx.stop().await
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn list_workers_info__stuck_mailbox__should_report_mailbox_depth(
+    ctx: &mut Context,
+) -> Result<()> {
+    let worker = SimpleWorker {
+        initialize_was_called: Default::default(),
+        shutdown_was_called: Default::default(),
+    };
+    ctx.start_worker("simple_worker", worker).await?;
+    let _: String = ctx
+        .send_and_receive(route!["simple_worker"], "Hello".to_string())
+        .await?;
+
+    // nobody receives the messages sent to this context
+    let _stuck = ctx.new_detached("stuck", AllowAll, AllowAll).await?;
+    ctx.send(route!["stuck"], "1".to_string()).await?;
+    ctx.send(route!["stuck"], "2".to_string()).await?;
+
+    let workers = ctx.list_workers_info().await?;
+    let worker = workers
+        .iter()
+        .find(|w| w.address == "simple_worker".into())
+        .unwrap();
+    assert_eq!(
+        worker.message_type.as_deref(),
+        Some("alloc::string::String")
+    );
+    assert_eq!(worker.processed_messages, Some(1));
+    assert_eq!(worker.mailbox_depth, Some(0));
+
+    let stuck = workers
+        .iter()
+        .find(|w| w.address == "stuck".into())
+        .unwrap();
+    assert!(stuck.detached);
+    assert_eq!(stuck.processed_messages, None);
+    assert_eq!(stuck.mailbox_depth, Some(2));
+    ctx.stop().await
+}
+
+struct StalledWorker {
+    stalled: Arc<tokio::sync::Mutex<()>>,
+}
+
+#[ockam_core::worker]
+impl Worker for StalledWorker {
+    type Message = String;
+    type Context = Context;
+
+    async fn handle_message(&mut self, _ctx: &mut Context, _msg: Routed<String>) -> Result<()> {
+        drop(self.stalled.lock().await);
+        Ok(())
+    }
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn mailbox_overflow__full_mailbox__should_close_the_mailbox(ctx: &mut Context) -> Result<()> {
+    let stalled = Arc::new(tokio::sync::Mutex::new(()));
+    let guard = stalled.lock().await;
+    WorkerBuilder::new(StalledWorker {
+        stalled: stalled.clone(),
+    })
+    .with_address("closing")
+    .with_mailbox_options(MailboxOptions::new(1, OverflowPolicy::Close))
+    .start(ctx)
+    .await?;
+
+    // the first message is handled by the stalled worker, the second one fills its mailbox
+    ctx.send(route!["closing"], "1".to_string()).await?;
+    sleep(Duration::from_millis(100)).await;
+    ctx.send(route!["closing"], "2".to_string()).await?;
+
+    let error = ctx
+        .send(route!["closing"], "3".to_string())
+        .await
+        .unwrap_err();
+    assert_eq!(error.code().kind, Kind::ResourceExhausted);
+
+    let workers = ctx.list_workers_info().await?;
+    let worker = workers
+        .iter()
+        .find(|w| w.address == "closing".into())
+        .unwrap();
+    assert_eq!(worker.overflow_messages, Some(1));
+
+    // once its current message is handled, the worker stops instead of handling the next one
+    drop(guard);
+    sleep(Duration::from_millis(100)).await;
+    let workers = ctx.list_workers_info().await?;
+    assert!(!workers.iter().any(|w| w.address == "closing".into()));
+
+    ctx.stop().await
+}
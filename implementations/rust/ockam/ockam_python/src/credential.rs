@@ -0,0 +1,117 @@
+use crate::error::to_py_err;
+use crate::runtime::Runtime;
+use ockam::identity::models::CredentialAndPurposeKey;
+use ockam::identity::{identities, Identifier};
+use pyo3::prelude::*;
+use std::collections::HashMap;
+
+/// Verify a hex-encoded credential against the hex-encoded identity of the authority which
+/// issued it. If `subject` is given, the credential must have been issued to that identifier.
+/// Return the attributes attested by the credential
+#[pyfunction]
+#[pyo3(signature = (credential, authority, subject = None))]
+pub(crate) fn verify_credential(
+    py: Python<'_>,
+    credential: &str,
+    authority: &str,
+    subject: Option<&str>,
+) -> PyResult<HashMap<String, String>> {
+    let credential: CredentialAndPurposeKey =
+        minicbor::decode(&hex::decode(credential).map_err(to_py_err)?).map_err(to_py_err)?;
+    let authority = hex::decode(authority).map_err(to_py_err)?;
+    let subject = subject
+        .map(Identifier::try_from)
+        .transpose()
+        .map_err(to_py_err)?;
+
+    let runtime = Runtime::get()?;
+    let data = runtime
+        .block_on(py, async {
+            // the authority is only imported in a transient, in-memory, storage
+            let identities = identities();
+            let authority = identities
+                .identities_creation()
+                .import(None, &authority)
+                .await?;
+            identities
+                .credentials()
+                .credentials_verification()
+                .verify_credential(
+                    subject.as_ref(),
+                    &[authority.identifier().clone()],
+                    &credential,
+                )
+                .await
+        })
+        .map_err(to_py_err)?;
+
+    Ok(data
+        .credential_data
+        .subject_attributes
+        .map
+        .iter()
+        .map(|(k, v)| {
+            (
+                String::from_utf8_lossy(k).to_string(),
+                String::from_utf8_lossy(v).to_string(),
+            )
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam::identity::models::CredentialSchemaIdentifier;
+    use ockam::identity::utils::AttributesBuilder;
+    use std::time::Duration;
+
+    #[test]
+    fn test_verify_credential() -> PyResult<()> {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let runtime = Runtime::get()?;
+            let (authority, other, member, credential) = runtime
+                .block_on(py, async {
+                    let identities = identities();
+                    let creation = identities.identities_creation();
+                    let authority = creation.create_identity().await?;
+                    let other = creation.create_identity().await?;
+                    let member = creation.create_identity().await?;
+                    let credential = identities
+                        .credentials()
+                        .credentials_creation()
+                        .issue_credential(
+                            authority.identifier(),
+                            member.identifier(),
+                            AttributesBuilder::with_schema(CredentialSchemaIdentifier(0))
+                                .with_attribute("role", "member")
+                                .build(),
+                            Duration::from_secs(60),
+                        )
+                        .await?;
+                    ockam_core::Result::Ok((
+                        hex::encode(authority.export()?),
+                        hex::encode(other.export()?),
+                        member.identifier().to_string(),
+                        hex::encode(minicbor::to_vec(credential)?),
+                    ))
+                })
+                .map_err(to_py_err)?;
+
+            let attributes = verify_credential(py, &credential, &authority, Some(&member))?;
+            assert_eq!(attributes.get("role"), Some(&"member".to_string()));
+
+            // the credential must be issued by the authority, to the given subject
+            assert!(verify_credential(py, &credential, &other, None).is_err());
+            let other_identifier =
+                Identifier::try_from("I0000000000000000000000000000000000000001")
+                    .map_err(to_py_err)?
+                    .to_string();
+            assert!(
+                verify_credential(py, &credential, &authority, Some(&other_identifier)).is_err()
+            );
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,13 @@
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::PyErr;
+use std::fmt::Display;
+
+/// Convert an Ockam error into a Python `RuntimeError`
+pub(crate) fn to_py_err(error: impl Display) -> PyErr {
+    PyRuntimeError::new_err(error.to_string())
+}
+
+/// Convert a `miette` report into a Python `RuntimeError`, keeping the whole chain of causes
+pub(crate) fn report_to_py_err(report: miette::Report) -> PyErr {
+    PyRuntimeError::new_err(format!("{report:?}"))
+}
@@ -0,0 +1,50 @@
+//!
+//! This crate exposes the Ockam local state and the clients of background nodes to Python,
+//! so that automation scripts can create nodes, issue enrollment tickets and query the status
+//! of nodes without shelling out to the `ockam` command.
+//!
+//! The Python module is only built with the `extension-module` feature:
+//!
+//! ```sh
+//! maturin build --features extension-module
+//! ```
+//!
+//! The `python` feature builds the bindings without the extension module, so that they
+//! can be tested with `cargo test --features python`.
+//!
+//! ```python
+//! import ockam
+//!
+//! state = ockam.CliState()
+//! node = ockam.Node(state, "n1")
+//! print(node.client().status())
+//! node.stop()
+//! ```
+//!
+
+#[cfg(feature = "python")]
+mod credential;
+#[cfg(feature = "python")]
+mod error;
+#[cfg(feature = "python")]
+mod node;
+#[cfg(feature = "python")]
+mod runtime;
+#[cfg(feature = "python")]
+mod state;
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+
+/// Python module `ockam`
+#[cfg(feature = "python")]
+#[pymodule]
+fn ockam(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add_class::<state::PyCliState>()?;
+    m.add_class::<node::PyNode>()?;
+    m.add_class::<node::PyBackgroundNode>()?;
+    m.add_class::<node::PyInMemoryNode>()?;
+    m.add_class::<node::PyNodeStatus>()?;
+    m.add_function(wrap_pyfunction!(credential::verify_credential, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,468 @@
+use crate::error::{report_to_py_err, to_py_err};
+use crate::runtime::Runtime;
+use crate::state::PyCliState;
+use miette::{miette, IntoDiagnostic};
+use ockam::identity::Identifier;
+use ockam::{Address, AsyncTryClone, Context, TcpListenerOptions, TcpTransport};
+use ockam_api::authenticator::enrollment_tokens::{Members, TokenIssuer};
+use ockam_api::cli_state::{
+    add_project_info_to_node_state, init_node_state, CliState, StateDirTrait, StateItemTrait,
+};
+use ockam_api::config::lookup::{ProjectAuthority, ProjectLookup};
+use ockam_api::identity::EnrollmentTicket;
+use ockam_api::nodes::models::base::NodeStatus;
+use ockam_api::nodes::models::services::ServiceList;
+use ockam_api::nodes::models::transport::{CreateTransportJson, TransportMode, TransportType};
+use ockam_api::nodes::service::{
+    NodeManagerGeneralOptions, NodeManagerTransportOptions, NodeManagerTrustOptions,
+    DEFAULT_SHUTDOWN_TIMEOUT,
+};
+use ockam_api::nodes::{
+    BackgroundNode, Credentials, InMemoryNode, NodeManagerWorker, NODEMANAGER_ADDR,
+};
+use ockam_core::api::Request;
+use pyo3::prelude::*;
+use std::collections::HashMap;
+use std::process;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Status of a node, as returned by the node itself
+#[pyclass(name = "NodeStatus")]
+pub(crate) struct PyNodeStatus {
+    #[pyo3(get)]
+    node_name: String,
+    #[pyo3(get)]
+    status: String,
+    #[pyo3(get)]
+    workers: u32,
+    #[pyo3(get)]
+    pid: i32,
+}
+
+#[pymethods]
+impl PyNodeStatus {
+    fn __repr__(&self) -> String {
+        format!(
+            "NodeStatus(node_name={:?}, status={:?}, workers={}, pid={})",
+            self.node_name, self.status, self.workers, self.pid
+        )
+    }
+}
+
+impl From<NodeStatus> for PyNodeStatus {
+    fn from(status: NodeStatus) -> Self {
+        Self {
+            node_name: status.node_name,
+            status: status.status,
+            workers: status.workers,
+            pid: status.pid,
+        }
+    }
+}
+
+/// Client of a node running in the background, as started with `ockam node create`
+#[pyclass(name = "BackgroundNode")]
+pub(crate) struct PyBackgroundNode {
+    inner: BackgroundNode,
+}
+
+#[pymethods]
+impl PyBackgroundNode {
+    /// Connect to the node with the given name
+    #[new]
+    fn new(py: Python<'_>, state: &PyCliState, name: &str) -> PyResult<Self> {
+        let runtime = Runtime::get()?;
+        let inner = runtime
+            .block_on(py, BackgroundNode::new(runtime.tcp(), state.inner(), name))
+            .map_err(report_to_py_err)?;
+        Ok(Self { inner })
+    }
+
+    /// Return the status of the node
+    fn status(&self, py: Python<'_>) -> PyResult<PyNodeStatus> {
+        let runtime = Runtime::get()?;
+        let status: NodeStatus = runtime
+            .block_on(py, self.inner.ask(runtime.context(), Request::get("/node")))
+            .map_err(report_to_py_err)?;
+        Ok(status.into())
+    }
+
+    /// Return the address and type of each service started on the node
+    fn list_services(&self, py: Python<'_>) -> PyResult<Vec<(String, String)>> {
+        let runtime = Runtime::get()?;
+        let services: ServiceList = runtime
+            .block_on(
+                py,
+                self.inner
+                    .ask(runtime.context(), Request::get("/node/services")),
+            )
+            .map_err(report_to_py_err)?;
+        Ok(services
+            .list
+            .into_iter()
+            .map(|s| (s.addr, s.service_type))
+            .collect())
+    }
+
+    /// Return the addresses of the secure channels created by the node
+    fn list_secure_channels(&self, py: Python<'_>) -> PyResult<Vec<String>> {
+        let runtime = Runtime::get()?;
+        runtime
+            .block_on(
+                py,
+                self.inner
+                    .ask(runtime.context(), Request::get("/node/secure_channel")),
+            )
+            .map_err(report_to_py_err)
+    }
+
+    /// Return the credential of the node identity, hex-encoded
+    #[pyo3(signature = (overwrite = false, identity = None))]
+    fn get_credential(
+        &self,
+        py: Python<'_>,
+        overwrite: bool,
+        identity: Option<String>,
+    ) -> PyResult<String> {
+        let runtime = Runtime::get()?;
+        let credential = runtime
+            .block_on(
+                py,
+                self.inner
+                    .get_credential(runtime.context(), overwrite, identity),
+            )
+            .map_err(report_to_py_err)?;
+        Ok(hex::encode(
+            minicbor::to_vec(credential).map_err(to_py_err)?,
+        ))
+    }
+}
+
+/// Node created and run by the Python process. Like a node started with `ockam node create`,
+/// it is recorded in the local state and answers the requests of the `ockam` command and of
+/// the `BackgroundNode` clients, until it is stopped or the Python process exits.
+///
+/// A Python process runs a single node at a time
+#[pyclass(name = "Node")]
+pub(crate) struct PyNode {
+    state: PyCliState,
+    name: String,
+    running: Option<RunningNode>,
+}
+
+/// Node manager and TCP listener of a node running in the Python process
+struct RunningNode {
+    node: Arc<InMemoryNode>,
+    listener: Address,
+}
+
+#[pymethods]
+impl PyNode {
+    /// Create a node with the given name, or restart it if it already exists in the local state.
+    /// The node is created with a new identity, stored in the given vault
+    #[new]
+    #[pyo3(signature = (state, name, tcp_listener_address = "127.0.0.1:0", vault = None, identity = None))]
+    fn new(
+        py: Python<'_>,
+        state: &PyCliState,
+        name: &str,
+        tcp_listener_address: &str,
+        vault: Option<&str>,
+        identity: Option<&str>,
+    ) -> PyResult<Self> {
+        let runtime = Runtime::get()?;
+        let running = runtime
+            .block_on(
+                py,
+                start_node(
+                    runtime.context(),
+                    runtime.tcp(),
+                    state.inner(),
+                    name,
+                    tcp_listener_address,
+                    vault,
+                    identity,
+                ),
+            )
+            .map_err(report_to_py_err)?;
+        Ok(Self {
+            state: state.clone(),
+            name: name.to_string(),
+            running: Some(running),
+        })
+    }
+
+    /// Name of the node
+    #[getter]
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    /// Return a client sending requests to the node over its TCP listener
+    fn client(&self, py: Python<'_>) -> PyResult<PyBackgroundNode> {
+        if self.running.is_none() {
+            return Err(to_py_err("the node has been stopped"));
+        }
+        PyBackgroundNode::new(py, &self.state, &self.name)
+    }
+
+    /// Run the shutdown hooks of the node, then stop it. Its state is kept
+    fn stop(&mut self, py: Python<'_>) -> PyResult<()> {
+        if let Some(running) = self.running.take() {
+            let runtime = Runtime::get()?;
+            runtime
+                .block_on(py, stop_node(runtime.context(), runtime.tcp(), running))
+                .map_err(report_to_py_err)?;
+            self.state
+                .inner()
+                .nodes
+                .get(&self.name)
+                .and_then(|node| node.set_stopped())
+                .map_err(to_py_err)?;
+        }
+        Ok(())
+    }
+}
+
+/// Start a node in the current process, as `ockam node create --foreground` does
+async fn start_node(
+    ctx: &Context,
+    tcp: &TcpTransport,
+    state: &CliState,
+    name: &str,
+    tcp_listener_address: &str,
+    vault: Option<&str>,
+    identity: Option<&str>,
+) -> miette::Result<RunningNode> {
+    if !state.nodes.exists(name) {
+        init_node_state(state, name, vault, identity).await?;
+    }
+    add_project_info_to_node_state(name, state, None).await?;
+
+    let listener = tcp
+        .listen(tcp_listener_address, TcpListenerOptions::new())
+        .await
+        .into_diagnostic()?;
+    let node_state = state.nodes.get(name)?;
+    node_state.set_pid(process::id() as i32)?;
+    node_state.set_setup(
+        &node_state
+            .config()
+            .setup_mut()
+            .set_api_transport(
+                CreateTransportJson::new(
+                    TransportType::Tcp,
+                    TransportMode::Listen,
+                    &listener.socket_address().to_string(),
+                )
+                .into_diagnostic()?,
+            )
+            .set_started(),
+    )?;
+
+    let node = InMemoryNode::new(
+        ctx,
+        NodeManagerGeneralOptions::new(state.clone(), name.to_string(), None, true, true),
+        NodeManagerTransportOptions::new(
+            listener.flow_control_id().clone(),
+            tcp.async_try_clone().await.into_diagnostic()?,
+        ),
+        NodeManagerTrustOptions::new(None),
+    )
+    .await
+    .into_diagnostic()?;
+    let node = Arc::new(node);
+    ctx.flow_controls()
+        .add_consumer(NODEMANAGER_ADDR, listener.flow_control_id());
+    ctx.start_worker(NODEMANAGER_ADDR, NodeManagerWorker::new(node.clone()))
+        .await
+        .into_diagnostic()?;
+    Ok(RunningNode {
+        node,
+        listener: listener.processor_address().clone(),
+    })
+}
+
+/// Stop a node started with [`start_node`]
+async fn stop_node(ctx: &Context, tcp: &TcpTransport, running: RunningNode) -> miette::Result<()> {
+    running
+        .node
+        .run_shutdown_hooks(ctx, DEFAULT_SHUTDOWN_TIMEOUT)
+        .await
+        .into_diagnostic()?;
+    ctx.stop_worker(NODEMANAGER_ADDR).await.into_diagnostic()?;
+    running.node.stop(ctx).await.into_diagnostic()?;
+    tcp.stop_listener(&running.listener).await.into_diagnostic()
+}
+
+/// Node running in the Python process. It is used to talk to the authority of a project
+#[pyclass(name = "InMemoryNode")]
+pub(crate) struct PyInMemoryNode {
+    state: PyCliState,
+    inner: Option<InMemoryNode>,
+}
+
+impl PyInMemoryNode {
+    fn node(&self) -> PyResult<&InMemoryNode> {
+        self.inner
+            .as_ref()
+            .ok_or_else(|| to_py_err("the node has been stopped"))
+    }
+}
+
+/// Return the project with the given name, or the default project, and its authority
+async fn project_authority(
+    state: &CliState,
+    name: Option<&str>,
+) -> miette::Result<(ProjectLookup, ProjectAuthority)> {
+    let project = match name {
+        Some(name) => state.projects.get(name)?,
+        None => state.projects.default()?,
+    };
+    let config = project.config();
+    let authority =
+        ProjectAuthority::from_raw(&config.authority_access_route, &config.authority_identity)
+            .await
+            .into_diagnostic()?
+            .ok_or_else(|| miette!("missing authority in project {}", project.name()))?;
+    let lookup = ProjectLookup::from_project(config)
+        .await
+        .into_diagnostic()?;
+    Ok((lookup, authority))
+}
+
+#[pymethods]
+impl PyInMemoryNode {
+    /// Start a node using the default identity of the local state
+    #[new]
+    fn new(py: Python<'_>, state: &PyCliState) -> PyResult<Self> {
+        let runtime = Runtime::get()?;
+        let inner = runtime
+            .block_on(py, InMemoryNode::start(runtime.context(), state.inner()))
+            .map_err(report_to_py_err)?;
+        Ok(Self {
+            state: state.clone(),
+            inner: Some(inner),
+        })
+    }
+
+    /// Create an enrollment ticket for a project, hex-encoded.
+    /// The members enrolling with this ticket get the given attributes
+    #[pyo3(signature = (attributes = HashMap::new(), expires_in = None, project = None, identity = None))]
+    fn create_ticket(
+        &self,
+        py: Python<'_>,
+        attributes: HashMap<String, String>,
+        expires_in: Option<u64>,
+        project: Option<&str>,
+        identity: Option<String>,
+    ) -> PyResult<String> {
+        let runtime = Runtime::get()?;
+        let node = self.node()?;
+        let state = self.state.inner();
+        let attributes = attributes
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect();
+        let ticket = runtime
+            .block_on(py, async {
+                let (project, authority) = project_authority(state, project).await?;
+                let authority_node = node
+                    .create_authority_client(authority.identity_id(), authority.address(), identity)
+                    .await?;
+                let token = authority_node
+                    .create_token(
+                        runtime.context(),
+                        attributes,
+                        expires_in.map(Duration::from_secs),
+                    )
+                    .await?;
+                EnrollmentTicket::new(token, Some(project), None)
+                    .hex_encoded()
+                    .into_diagnostic()
+            })
+            .map_err(report_to_py_err)?;
+        Ok(ticket)
+    }
+
+    /// Add a member with the given attributes to a project
+    #[pyo3(signature = (identifier, attributes = HashMap::new(), project = None, identity = None))]
+    fn add_member(
+        &self,
+        py: Python<'_>,
+        identifier: &str,
+        attributes: HashMap<String, String>,
+        project: Option<&str>,
+        identity: Option<String>,
+    ) -> PyResult<()> {
+        let runtime = Runtime::get()?;
+        let node = self.node()?;
+        let state = self.state.inner();
+        let identifier = Identifier::try_from(identifier).map_err(to_py_err)?;
+        let attributes = attributes
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect();
+        runtime
+            .block_on(py, async {
+                let (_, authority) = project_authority(state, project).await?;
+                node.create_authority_client(authority.identity_id(), authority.address(), identity)
+                    .await?
+                    .add_member(runtime.context(), identifier, attributes)
+                    .await
+            })
+            .map_err(report_to_py_err)
+    }
+
+    /// Stop the node
+    fn stop(&mut self, py: Python<'_>) -> PyResult<()> {
+        if let Some(node) = self.inner.take() {
+            let runtime = Runtime::get()?;
+            runtime
+                .block_on(py, node.stop(runtime.context()))
+                .map_err(to_py_err)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_and_stop_node() -> PyResult<()> {
+        pyo3::prepare_freethreaded_python();
+        let state = PyCliState::from(CliState::test().map_err(to_py_err)?);
+        let is_running = |name: &str| -> PyResult<bool> {
+            Ok(state
+                .inner()
+                .nodes
+                .get(name)
+                .map_err(to_py_err)?
+                .is_running())
+        };
+        Python::with_gil(|py| {
+            let mut node = PyNode::new(py, &state, "n1", "127.0.0.1:0", None, None)?;
+            assert!(is_running("n1")?);
+
+            // the node answers the requests sent over its TCP listener
+            let client = node.client(py)?;
+            let status = client.status(py)?;
+            assert_eq!(status.node_name, "n1");
+            assert_eq!(status.pid, process::id() as i32);
+            assert!(!client.list_services(py)?.is_empty());
+
+            // the state of the node is kept once it is stopped
+            node.stop(py)?;
+            assert!(!is_running("n1")?);
+            assert!(node.client(py).is_err());
+            assert_eq!(
+                state.inner().nodes.list_items_names().map_err(to_py_err)?,
+                vec!["n1"]
+            );
+            Ok(())
+        })
+    }
+}
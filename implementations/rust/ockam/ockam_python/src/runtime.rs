@@ -0,0 +1,46 @@
+use crate::error::to_py_err;
+use ockam::{Context, NodeBuilder, TcpTransport};
+use once_cell::sync::OnceCell;
+use pyo3::{PyResult, Python};
+use std::future::Future;
+
+/// Node running in the Python process. All the clients share this node and its TCP transport,
+/// since a TCP transport can only be created once per node
+pub(crate) struct Runtime {
+    context: Context,
+    tcp: TcpTransport,
+}
+
+static RUNTIME: OnceCell<Runtime> = OnceCell::new();
+
+impl Runtime {
+    /// Return the node of the Python process, starting it on first use
+    pub(crate) fn get() -> PyResult<&'static Runtime> {
+        RUNTIME.get_or_try_init(|| {
+            let (context, mut executor) = NodeBuilder::new().no_logging().build();
+            let runtime = context.runtime().clone();
+            runtime.spawn(async move { executor.start_router().await });
+            let tcp = runtime
+                .block_on(TcpTransport::create(&context))
+                .map_err(to_py_err)?;
+            Ok(Runtime { context, tcp })
+        })
+    }
+
+    pub(crate) fn context(&self) -> &Context {
+        &self.context
+    }
+
+    pub(crate) fn tcp(&self) -> &TcpTransport {
+        &self.tcp
+    }
+
+    /// Run a future to completion, releasing the GIL while waiting
+    pub(crate) fn block_on<F>(&self, py: Python<'_>, future: F) -> F::Output
+    where
+        F: Future + Send,
+        F::Output: Send,
+    {
+        py.allow_threads(|| self.context.runtime().block_on(future))
+    }
+}
@@ -0,0 +1,93 @@
+use crate::error::to_py_err;
+use ockam_api::cli_state::{CliState, StateDirTrait, StateItemTrait};
+use pyo3::prelude::*;
+
+/// Local Ockam state: nodes, identities, vaults and projects, as stored in `$OCKAM_HOME`
+#[pyclass(name = "CliState")]
+#[derive(Clone)]
+pub(crate) struct PyCliState {
+    inner: CliState,
+}
+
+impl PyCliState {
+    pub(crate) fn inner(&self) -> &CliState {
+        &self.inner
+    }
+}
+
+impl From<CliState> for PyCliState {
+    fn from(inner: CliState) -> Self {
+        Self { inner }
+    }
+}
+
+#[pymethods]
+impl PyCliState {
+    /// Load the local state, creating it if it doesn't exist yet
+    #[new]
+    fn new() -> PyResult<Self> {
+        Ok(Self {
+            inner: CliState::initialize().map_err(to_py_err)?,
+        })
+    }
+
+    /// Names of all the nodes
+    fn nodes(&self) -> PyResult<Vec<String>> {
+        self.inner.nodes.list_items_names().map_err(to_py_err)
+    }
+
+    /// Name of the default node, if any
+    fn default_node(&self) -> Option<String> {
+        self.inner
+            .nodes
+            .default()
+            .ok()
+            .map(|n| n.name().to_string())
+    }
+
+    /// Return true if the process of a node is running
+    fn is_node_running(&self, name: &str) -> PyResult<bool> {
+        Ok(self.inner.nodes.get(name).map_err(to_py_err)?.is_running())
+    }
+
+    /// Process id of a node, if it was started
+    fn node_pid(&self, name: &str) -> PyResult<Option<i32>> {
+        self.inner
+            .nodes
+            .get(name)
+            .map_err(to_py_err)?
+            .pid()
+            .map_err(to_py_err)
+    }
+
+    /// Stop a node and delete its state
+    #[pyo3(signature = (name, force = false))]
+    fn delete_node(&self, name: &str, force: bool) -> PyResult<()> {
+        self.inner
+            .nodes
+            .delete_sigkill(name, force)
+            .map_err(to_py_err)
+    }
+
+    /// Names of all the identities
+    fn identities(&self) -> PyResult<Vec<String>> {
+        self.inner.identities.list_items_names().map_err(to_py_err)
+    }
+
+    /// Identifier of an identity, or of the default identity if no name is given
+    #[pyo3(signature = (name = None))]
+    fn identifier(&self, name: Option<&str>) -> PyResult<String> {
+        Ok(self
+            .inner
+            .identities
+            .get_or_default(name)
+            .map_err(to_py_err)?
+            .identifier()
+            .to_string())
+    }
+
+    /// Names of all the projects
+    fn projects(&self) -> PyResult<Vec<String>> {
+        self.inner.projects.list_items_names().map_err(to_py_err)
+    }
+}
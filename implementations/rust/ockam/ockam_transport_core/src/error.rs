@@ -41,6 +41,12 @@ pub enum TransportError {
     /// Excessive length of header, possible DoS attack
     /// https://github.com/advisories/GHSA-9mcr-873m-xcxp
     AttackAttmept,
+    /// The target requested by an Inlet is not allowed by the Outlet
+    TargetNotAllowed,
+    /// The protocol is not known or not supported
+    InvalidProtocol,
+    /// The connection is still used by other secure channels or portals
+    ConnectionInUse,
 }
 
 impl ockam_core::compat::error::Error for TransportError {}
@@ -63,6 +69,9 @@ impl core::fmt::Display for TransportError {
             Self::PortalInvalidState => write!(f, "portal entered invalid state"),
             Self::InvalidRouterResponseType => write!(f, "router responded with invalid type"),
             Self::AttackAttmept => write!(f, "excessive length of header, possible DoS attack"),
+            Self::TargetNotAllowed => write!(f, "the requested portal target is not allowed"),
+            Self::InvalidProtocol => write!(f, "unknown protocol"),
+            Self::ConnectionInUse => write!(f, "the connection is still in use"),
         }
     }
 }
@@ -88,6 +97,9 @@ impl From<TransportError> for Error {
             PortalInvalidState => Kind::Invalid,
             InvalidRouterResponseType => Kind::Invalid,
             AttackAttmept => Kind::Misuse,
+            TargetNotAllowed => Kind::Misuse,
+            InvalidProtocol => Kind::Misuse,
+            ConnectionInUse => Kind::Conflict,
         };
 
         Error::new(Origin::Transport, kind, err)
@@ -25,7 +25,12 @@ mod transport;
 
 use ockam_core::TransportType;
 pub use options::{TcpConnectionOptions, TcpListenerOptions};
-pub use portal::{PortalInternalMessage, PortalMessage, MAX_PAYLOAD_SIZE};
+pub use portal::{
+    AllowedTarget, HttpRequestValidator, InletConnectionAccepted, OutletProtocol,
+    OutletProtocolValidator, OutletTargetAccessControl, OutletTargetRouter, PortalInternalMessage,
+    PortalMessage, ProtocolVerdict, StreamInlet, TlsClientHelloValidator, DEFAULT_VALIDATION_BYTES,
+    MAX_PAYLOAD_SIZE,
+};
 pub use registry::*;
 pub use transport::common::*;
 pub use transport::*;
@@ -1,4 +1,5 @@
 use crate::workers::Addresses;
+use crate::{AllowedNetwork, KnockOptions};
 use ockam_core::compat::sync::Arc;
 use ockam_core::flow_control::{FlowControlId, FlowControlOutgoingAccessControl, FlowControls};
 use ockam_core::{Address, AllowAll, IncomingAccessControl, OutgoingAccessControl};
@@ -13,6 +14,7 @@ pub(crate) struct TcpConnectionAccessControl {
 pub struct TcpConnectionOptions {
     pub(super) consumer: Vec<FlowControlId>,
     pub(crate) flow_control_id: FlowControlId,
+    pub(crate) shared: bool,
 }
 
 impl TcpConnectionOptions {
@@ -22,9 +24,20 @@ impl TcpConnectionOptions {
         Self {
             consumer: vec![],
             flow_control_id: FlowControls::generate_flow_control_id(),
+            shared: false,
         }
     }
 
+    /// Share the connection with other users connecting to the same peer.
+    /// If a shared connection to that peer already exists it is reused instead of dialing a new
+    /// one. Each secure channel or portal using the connection gets its own stream over it,
+    /// with its own [`FlowControlId`], so that it only receives its own messages.
+    /// The connection is only closed when all its users disconnected
+    pub fn shared(mut self) -> Self {
+        self.shared = true;
+        self
+    }
+
     /// Mark that this Connection is a Consumer for to the given [`FlowControlId`]
     pub fn as_consumer(mut self, id: &FlowControlId) -> Self {
         self.consumer.push(id.clone());
@@ -71,6 +84,8 @@ impl TcpConnectionOptions {
 #[derive(Debug)]
 pub struct TcpListenerOptions {
     pub(crate) flow_control_id: FlowControlId,
+    pub(crate) allowed_networks: Vec<AllowedNetwork>,
+    pub(crate) knock: Option<KnockOptions>,
 }
 
 impl TcpListenerOptions {
@@ -81,9 +96,35 @@ impl TcpListenerOptions {
     pub fn new() -> Self {
         Self {
             flow_control_id: FlowControls::generate_flow_control_id(),
+            allowed_networks: vec![],
+            knock: None,
         }
     }
 
+    /// Only accept the connections of the peers from a given network. Can be called
+    /// several times to allow several networks. Every peer is accepted if no network is allowed
+    pub fn with_allowed_network(mut self, network: AllowedNetwork) -> Self {
+        self.allowed_networks.push(network);
+        self
+    }
+
+    /// Networks from which connections are accepted
+    pub fn allowed_networks(&self) -> &[AllowedNetwork] {
+        &self.allowed_networks
+    }
+
+    /// Protect the listener with port knocking: only accept the connections of the peers
+    /// which recently sent a valid knock on the same port, over UDP
+    pub fn with_knock(mut self, knock: KnockOptions) -> Self {
+        self.knock = Some(knock);
+        self
+    }
+
+    /// Port knocking options, if the listener is protected by port knocking
+    pub fn knock(&self) -> Option<&KnockOptions> {
+        self.knock.as_ref()
+    }
+
     /// Getter for freshly generated [`FlowControlId`]
     pub fn spawner_flow_control_id(&self) -> FlowControlId {
         self.flow_control_id.clone()
@@ -1,3 +1,4 @@
+use crate::TcpPortalType;
 use ockam_core::Address;
 
 /// Enumerate all portal types
@@ -16,6 +17,15 @@ impl PortalType {
     }
 }
 
+impl From<&PortalType> for TcpPortalType {
+    fn from(portal_type: &PortalType) -> Self {
+        match portal_type {
+            PortalType::Inlet => TcpPortalType::Inlet,
+            PortalType::Outlet => TcpPortalType::Outlet,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub(super) struct Addresses {
     pub(super) internal: Address,
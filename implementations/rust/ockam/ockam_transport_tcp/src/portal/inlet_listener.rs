@@ -1,12 +1,12 @@
 use crate::portal::addresses::{Addresses, PortalType};
-use crate::{portal::TcpPortalWorker, TcpInletOptions, TcpRegistry};
+use crate::{portal::TcpPortalWorker, InletConnectionAccepted, TcpInletOptions, TcpRegistry};
 use ockam_core::compat::net::SocketAddr;
 use ockam_core::{async_trait, compat::boxed::Box};
 use ockam_core::{Address, Processor, Result, Route};
 use ockam_node::Context;
 use ockam_transport_core::TransportError;
 use tokio::net::TcpListener;
-use tracing::{debug, error};
+use tracing::{debug, error, warn};
 
 /// A TCP Portal Inlet listen processor
 ///
@@ -94,14 +94,27 @@ impl Processor for TcpInletListenProcessor {
         TcpPortalWorker::start_new_inlet(
             ctx,
             self.registry.clone(),
+            ctx.address(),
             stream,
             peer,
             outlet_listener_route,
+            self.options.outlet_target.clone(),
             addresses,
             self.options.incoming_access_control.clone(),
+            self.options.mailbox_options,
         )
         .await?;
 
+        if let Some(notifications) = &self.options.connection_notifications {
+            let notification = InletConnectionAccepted {
+                inlet_address: ctx.address().to_string(),
+                peer: peer.to_string(),
+            };
+            if let Err(err) = ctx.send(notifications.clone(), notification).await {
+                warn!(%notifications, %err, "could not notify the inlet connection");
+            }
+        }
+
         Ok(true)
     }
 }
@@ -2,12 +2,18 @@ mod addresses;
 mod inlet_listener;
 pub mod options;
 mod outlet_listener;
+mod outlet_targets;
 mod portal_message;
 mod portal_receiver;
 mod portal_worker;
+mod protocol_validation;
+mod stream_inlet;
 
 pub(crate) use inlet_listener::*;
 pub(crate) use outlet_listener::*;
+pub use outlet_targets::*;
 pub use portal_message::*;
 pub(crate) use portal_receiver::*;
 pub(crate) use portal_worker::*;
+pub use protocol_validation::*;
+pub use stream_inlet::*;
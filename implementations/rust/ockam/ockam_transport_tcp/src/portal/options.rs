@@ -1,12 +1,20 @@
 use crate::portal::addresses::Addresses;
+use crate::portal::{
+    check_target, AllowedTarget, OutletProtocolValidator, OutletTargetAccessControl,
+    OutletTargetRouter,
+};
 use ockam_core::compat::sync::Arc;
 use ockam_core::flow_control::{FlowControlId, FlowControls};
-use ockam_core::{Address, AllowAll, IncomingAccessControl};
+use ockam_core::{Address, AllowAll, IncomingAccessControl, LocalMessage, Result};
+use ockam_node::MailboxOptions;
 
 /// Trust Options for an Inlet
 #[derive(Debug)]
 pub struct TcpInletOptions {
     pub(super) incoming_access_control: Arc<dyn IncomingAccessControl>,
+    pub(super) connection_notifications: Option<Address>,
+    pub(super) outlet_target: Option<String>,
+    pub(super) mailbox_options: MailboxOptions,
 }
 
 impl TcpInletOptions {
@@ -14,6 +22,9 @@ impl TcpInletOptions {
     pub fn new() -> Self {
         Self {
             incoming_access_control: Arc::new(AllowAll),
+            connection_notifications: None,
+            outlet_target: None,
+            mailbox_options: MailboxOptions::default(),
         }
     }
 
@@ -35,6 +46,27 @@ impl TcpInletOptions {
         self
     }
 
+    /// Send an [`InletConnectionAccepted`](crate::InletConnectionAccepted) message to the given
+    /// address every time the Inlet accepts a new TCP connection
+    pub fn with_connection_notifications(mut self, address: impl Into<Address>) -> Self {
+        self.connection_notifications = Some(address.into());
+        self
+    }
+
+    /// Request the Outlet to connect to the given `host:port` target instead of its
+    /// configured target. The Outlet must allow that target, see [`TcpOutletOptions::with_allowed_target`]
+    pub fn with_outlet_target(mut self, target: impl Into<String>) -> Self {
+        self.outlet_target = Some(target.into());
+        self
+    }
+
+    /// Set the capacity of the mailboxes of the portal workers created for each connection,
+    /// and the policy applied when they are full
+    pub fn with_mailbox_options(mut self, mailbox_options: MailboxOptions) -> Self {
+        self.mailbox_options = mailbox_options;
+        self
+    }
+
     pub(super) fn setup_flow_control(
         &self,
         flow_controls: &FlowControls,
@@ -62,6 +94,11 @@ impl Default for TcpInletOptions {
 pub struct TcpOutletOptions {
     pub(super) consumer: Vec<FlowControlId>,
     pub(super) incoming_access_control: Arc<dyn IncomingAccessControl>,
+    pub(super) allowed_targets: Vec<AllowedTarget>,
+    pub(super) target_access_control: Option<Arc<dyn OutletTargetAccessControl>>,
+    pub(super) target_router: Option<Arc<dyn OutletTargetRouter>>,
+    pub(super) protocol_validator: Option<Arc<dyn OutletProtocolValidator>>,
+    pub(super) mailbox_options: MailboxOptions,
 }
 
 impl TcpOutletOptions {
@@ -70,6 +107,11 @@ impl TcpOutletOptions {
         Self {
             consumer: vec![],
             incoming_access_control: Arc::new(AllowAll),
+            allowed_targets: vec![],
+            target_access_control: None,
+            target_router: None,
+            protocol_validator: None,
+            mailbox_options: MailboxOptions::default(),
         }
     }
 
@@ -91,6 +133,52 @@ impl TcpOutletOptions {
         self
     }
 
+    /// Allow Inlets to request a connection to this target instead of the configured
+    /// target of the Outlet. No other target can be requested by default
+    pub fn with_allowed_target(mut self, target: AllowedTarget) -> Self {
+        self.allowed_targets.push(target);
+        self
+    }
+
+    /// Further restrict the allowed targets depending on the sender of the request
+    pub fn with_target_access_control(
+        mut self,
+        access_control: Arc<dyn OutletTargetAccessControl>,
+    ) -> Self {
+        self.target_access_control = Some(access_control);
+        self
+    }
+
+    /// Select the target of the connections requested without a target depending on
+    /// their sender, instead of always connecting to the configured target of the Outlet
+    pub fn with_target_router(mut self, router: Arc<dyn OutletTargetRouter>) -> Self {
+        self.target_router = Some(router);
+        self
+    }
+
+    /// Inspect the first bytes of each connection and close the connections which are
+    /// rejected by the validator, before anything is sent to the target
+    pub fn with_protocol_validator(mut self, validator: Arc<dyn OutletProtocolValidator>) -> Self {
+        self.protocol_validator = Some(validator);
+        self
+    }
+
+    /// Set the capacity of the mailboxes of the portal workers created for each connection,
+    /// and the policy applied when they are full
+    pub fn with_mailbox_options(mut self, mailbox_options: MailboxOptions) -> Self {
+        self.mailbox_options = mailbox_options;
+        self
+    }
+
+    pub(super) fn check_target(&self, target: &str, msg: &LocalMessage) -> Result<()> {
+        check_target(
+            &self.allowed_targets,
+            self.target_access_control.as_deref(),
+            target,
+            msg,
+        )
+    }
+
     /// Mark that this Outlet listener is a Consumer for to the given [`FlowControlId`]
     /// Also, in this case spawned Outlets will be marked as Consumers with [`FlowControlId`]
     /// of the message that was used to create the Outlet
@@ -1,10 +1,10 @@
 use crate::portal::addresses::{Addresses, PortalType};
-use crate::{portal::TcpPortalWorker, PortalMessage, TcpOutletOptions, TcpRegistry};
+use crate::{portal::TcpPortalWorker, DnsOptions, PortalMessage, TcpOutletOptions, TcpRegistry};
 use ockam_core::{async_trait, Address, DenyAll, Result, Routed, Worker};
 use ockam_node::{Context, WorkerBuilder};
 use ockam_transport_core::TransportError;
 use std::net::SocketAddr;
-use tracing::debug;
+use tracing::{debug, warn};
 
 /// A TCP Portal Outlet listen worker
 ///
@@ -14,15 +14,22 @@ use tracing::debug;
 pub(crate) struct TcpOutletListenWorker {
     registry: TcpRegistry,
     peer: SocketAddr,
+    dns_options: DnsOptions,
     options: TcpOutletOptions,
 }
 
 impl TcpOutletListenWorker {
     /// Create a new `TcpOutletListenWorker`
-    fn new(registry: TcpRegistry, peer: SocketAddr, options: TcpOutletOptions) -> Self {
+    fn new(
+        registry: TcpRegistry,
+        peer: SocketAddr,
+        dns_options: DnsOptions,
+        options: TcpOutletOptions,
+    ) -> Self {
         Self {
             registry,
             peer,
+            dns_options,
             options,
         }
     }
@@ -32,13 +39,14 @@ impl TcpOutletListenWorker {
         registry: TcpRegistry,
         address: Address,
         peer: SocketAddr,
+        dns_options: DnsOptions,
         options: TcpOutletOptions,
     ) -> Result<()> {
         let access_control = options.incoming_access_control.clone();
 
         options.setup_flow_control_for_outlet_listener(ctx.flow_controls(), &address);
 
-        let worker = Self::new(registry, peer, options);
+        let worker = Self::new(registry, peer, dns_options, options);
         WorkerBuilder::new(worker)
             .with_address(address)
             .with_incoming_access_control_arc(access_control)
@@ -75,10 +83,30 @@ impl Worker for TcpOutletListenWorker {
         let return_route = msg.return_route();
         let src_addr = msg.src_addr();
 
-        if let PortalMessage::Ping = msg.body() {
-        } else {
-            return Err(TransportError::Protocol.into());
-        }
+        let peer = match msg.as_body() {
+            PortalMessage::Ping => match &self.options.target_router {
+                Some(router) => match router.route(msg.local_message()).await {
+                    Ok(Some(target)) => {
+                        debug!(%target, %src_addr, "Outlet target selected by the router");
+                        self.dns_options.resolve(&target).await?
+                    }
+                    Ok(None) => self.peer,
+                    Err(err) => {
+                        warn!(%err, %src_addr, "Cannot select an outlet target, using the default target");
+                        self.peer
+                    }
+                },
+                None => self.peer,
+            },
+            PortalMessage::PingWithTarget(target) => {
+                if let Err(err) = self.options.check_target(target, msg.local_message()) {
+                    warn!(%target, %src_addr, "Outlet target not allowed");
+                    return Err(err);
+                }
+                self.dns_options.resolve(target).await?
+            }
+            _ => return Err(TransportError::Protocol.into()),
+        };
 
         let addresses = Addresses::generate(PortalType::Outlet);
 
@@ -88,10 +116,14 @@ impl Worker for TcpOutletListenWorker {
         TcpPortalWorker::start_new_outlet(
             ctx,
             self.registry.clone(),
-            self.peer,
+            ctx.address(),
+            peer,
             return_route.clone(),
+            msg.local_message().local_info().to_vec(),
             addresses.clone(),
+            self.options.protocol_validator.clone(),
             self.options.incoming_access_control.clone(),
+            self.options.mailbox_options,
         )
         .await?;
 
@@ -0,0 +1,130 @@
+use core::fmt::{self, Debug, Display, Formatter};
+use core::str::FromStr;
+use ockam_core::compat::boxed::Box;
+use ockam_core::compat::string::String;
+use ockam_core::{async_trait, Error, LocalMessage};
+use ockam_transport_core::TransportError;
+
+/// A target which can be requested by an Inlet when it connects to an Outlet,
+/// instead of the target configured for that Outlet.
+///
+/// It is written `host:port`, where the host is matched exactly, without being
+/// resolved, and the port can be `*` to allow any port on that host
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AllowedTarget {
+    host: String,
+    port: Option<u16>,
+}
+
+impl AllowedTarget {
+    /// Allow a host on a given port, or on any port if `port` is `None`
+    pub fn new(host: impl Into<String>, port: Option<u16>) -> Self {
+        Self {
+            host: host.into(),
+            port,
+        }
+    }
+
+    /// Return true if the requested `host:port` target is allowed
+    pub fn matches(&self, target: &str) -> bool {
+        match target.rsplit_once(':') {
+            Some((host, port)) => {
+                host.eq_ignore_ascii_case(&self.host)
+                    && match self.port {
+                        Some(allowed) => port.parse::<u16>().ok() == Some(allowed),
+                        None => port.parse::<u16>().is_ok(),
+                    }
+            }
+            None => false,
+        }
+    }
+}
+
+impl FromStr for AllowedTarget {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (host, port) = s.rsplit_once(':').ok_or(TransportError::InvalidAddress)?;
+        if host.is_empty() {
+            return Err(TransportError::InvalidAddress.into());
+        }
+        let port = match port {
+            "*" => None,
+            port => Some(
+                port.parse::<u16>()
+                    .map_err(|_| TransportError::InvalidAddress)?,
+            ),
+        };
+        Ok(Self::new(host, port))
+    }
+}
+
+impl Display for AllowedTarget {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.port {
+            Some(port) => write!(f, "{}:{port}", self.host),
+            None => write!(f, "{}:*", self.host),
+        }
+    }
+}
+
+/// Additional restriction on the targets which can be requested from an Outlet,
+/// depending on the sender of the request, for example its identity.
+///
+/// It is only checked for targets which are already part of the Outlet allowlist
+pub trait OutletTargetAccessControl: Debug + Send + Sync + 'static {
+    /// Return true if the sender of `msg` can connect to `target`
+    fn is_target_allowed(&self, target: &str, msg: &LocalMessage) -> bool;
+}
+
+/// Select the target of a connection requested from an Outlet without a target,
+/// depending on the sender of the request, for example the attributes of its identity.
+///
+/// The selected target is not checked against the Outlet allowlist since it is
+/// chosen by the Outlet itself
+#[async_trait]
+pub trait OutletTargetRouter: Debug + Send + Sync + 'static {
+    /// Return the `host:port` target of the connection requested by the sender of `msg`,
+    /// or `None` to connect to the configured target of the Outlet
+    async fn route(&self, msg: &LocalMessage) -> Result<Option<String>, Error>;
+}
+
+/// Return an error if the target is not allowed
+pub(crate) fn check_target(
+    allowed: &[AllowedTarget],
+    access_control: Option<&dyn OutletTargetAccessControl>,
+    target: &str,
+    msg: &LocalMessage,
+) -> Result<(), Error> {
+    let is_allowed = allowed.iter().any(|a| a.matches(target))
+        && access_control.map_or(true, |ac| ac.is_target_allowed(target, msg));
+    if is_allowed {
+        Ok(())
+    } else {
+        Err(TransportError::TargetNotAllowed.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ockam_core::compat::string::ToString;
+
+    #[test]
+    fn test_allowed_targets() {
+        let db: AllowedTarget = "db.internal:5432".parse().unwrap();
+        assert!(db.matches("db.internal:5432"));
+        assert!(db.matches("DB.internal:5432"));
+        assert!(!db.matches("db.internal:5433"));
+        assert!(!db.matches("other.internal:5432"));
+
+        let any_port: AllowedTarget = "10.0.0.1:*".parse().unwrap();
+        assert!(any_port.matches("10.0.0.1:80"));
+        assert!(!any_port.matches("10.0.0.1:http"));
+        assert_eq!(any_port.to_string(), "10.0.0.1:*");
+
+        assert!("db.internal".parse::<AllowedTarget>().is_err());
+        assert!(":80".parse::<AllowedTarget>().is_err());
+    }
+}
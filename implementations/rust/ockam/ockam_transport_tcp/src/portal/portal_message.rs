@@ -13,6 +13,9 @@ pub enum PortalMessage {
     Disconnect,
     /// Message with binary payload
     Payload(Vec<u8>),
+    /// First message that Inlet sends to the Outlet when it requests a connection
+    /// to a specific `host:port` target. The target must be allowed by the Outlet
+    PingWithTarget(String),
 }
 
 /// An internal message type for a Portal
@@ -22,5 +25,14 @@ pub enum PortalInternalMessage {
     Disconnect,
 }
 
+/// Notification sent by an Inlet when it accepts a new TCP connection
+#[derive(Serialize, Deserialize, Message, Debug, Clone)]
+pub struct InletConnectionAccepted {
+    /// Address of the Inlet listener processor
+    pub inlet_address: String,
+    /// Socket address of the TCP client
+    pub peer: String,
+}
+
 ///Maximum allowed size for a payload
 pub const MAX_PAYLOAD_SIZE: usize = 48 * 1024;
@@ -1,10 +1,11 @@
 use crate::portal::portal_message::MAX_PAYLOAD_SIZE;
-use crate::{PortalInternalMessage, PortalMessage, TcpRegistry};
+use crate::portal::PortalReadHalf;
+use crate::{PortalInternalMessage, PortalMessage, TcpPortalSessionInfo, TcpRegistry};
 use ockam_core::compat::vec::Vec;
 use ockam_core::{async_trait, Encodable, LocalMessage, Route, TransportMessage};
 use ockam_core::{route, Address, Processor, Result};
 use ockam_node::Context;
-use tokio::{io::AsyncReadExt, net::tcp::OwnedReadHalf};
+use tokio::io::AsyncReadExt;
 use tracing::{error, warn};
 
 /// A TCP Portal receiving message processor
@@ -14,8 +15,9 @@ use tracing::{error, warn};
 /// [`TcpPortalWorker::start_receiver`](crate::TcpPortalWorker::start_receiver)
 pub(crate) struct TcpPortalRecvProcessor {
     registry: TcpRegistry,
+    session: TcpPortalSessionInfo,
     buf: Vec<u8>,
-    read_half: OwnedReadHalf,
+    read_half: PortalReadHalf,
     sender_address: Address,
     onward_route: Route,
 }
@@ -24,12 +26,14 @@ impl TcpPortalRecvProcessor {
     /// Create a new `TcpPortalRecvProcessor`
     pub fn new(
         registry: TcpRegistry,
-        read_half: OwnedReadHalf,
+        session: TcpPortalSessionInfo,
+        read_half: PortalReadHalf,
         sender_address: Address,
         onward_route: Route,
     ) -> Self {
         Self {
             registry,
+            session,
             buf: Vec::with_capacity(MAX_PAYLOAD_SIZE),
             read_half,
             sender_address,
@@ -91,6 +95,8 @@ impl Processor for TcpPortalRecvProcessor {
             return Ok(false);
         }
 
+        self.session.record_received(self.buf.len());
+
         // Loop just in case buf was extended (should not happen though)
         for chunk in self.buf.chunks(MAX_PAYLOAD_SIZE) {
             let msg = TransportMessage::v1(
@@ -1,19 +1,28 @@
 use crate::portal::addresses::{Addresses, PortalType};
-use crate::{portal::TcpPortalRecvProcessor, PortalInternalMessage, PortalMessage, TcpRegistry};
+use crate::portal::ProtocolValidation;
+use crate::{
+    portal::TcpPortalRecvProcessor, OutletProtocolValidator, PortalInternalMessage, PortalMessage,
+    ProtocolVerdict, TcpPortalSessionInfo, TcpRegistry,
+};
 use core::time::Duration;
 use ockam_core::compat::{boxed::Box, net::SocketAddr, sync::Arc};
 use ockam_core::{
-    async_trait, AllowAll, AllowOnwardAddresses, AllowSourceAddress, Decodable, DenyAll,
-    IncomingAccessControl, Mailbox, Mailboxes,
+    async_trait, Address, AllowAll, AllowOnwardAddresses, AllowSourceAddress, Decodable, DenyAll,
+    IncomingAccessControl, LocalInfo, Mailbox, Mailboxes,
 };
 use ockam_core::{Any, Result, Route, Routed, Worker};
-use ockam_node::{Context, ProcessorBuilder, WorkerBuilder};
+use ockam_node::{Context, MailboxOptions, ProcessorBuilder, WorkerBuilder};
 use ockam_transport_core::TransportError;
-use tokio::io::AsyncWriteExt;
-use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
 use tokio::net::TcpStream;
 use tracing::{debug, info, trace, warn};
 
+/// Stream of bytes received by a portal, usually the read half of a TCP connection
+pub(crate) type PortalReadHalf = Box<dyn AsyncRead + Send + Unpin>;
+
+/// Stream of bytes sent by a portal, usually the write half of a TCP connection
+pub(crate) type PortalWriteHalf = Box<dyn AsyncWrite + Send + Unpin>;
+
 /// Enumerate all `TcpPortalWorker` states
 ///
 /// Possible state transitions are:
@@ -22,7 +31,10 @@ use tracing::{debug, info, trace, warn};
 /// `Inlet`: `SendPing` -> `ReceivePong` -> `Initialized`
 #[derive(Clone)]
 enum State {
-    SendPing { ping_route: Route },
+    SendPing {
+        ping_route: Route,
+        outlet_target: Option<String>,
+    },
     SendPong { pong_route: Route },
     ReceivePong,
     Initialized,
@@ -37,59 +49,119 @@ enum State {
 pub(crate) struct TcpPortalWorker {
     registry: TcpRegistry,
     state: State,
-    write_half: Option<OwnedWriteHalf>,
-    read_half: Option<OwnedReadHalf>,
-    peer: SocketAddr,
+    write_half: Option<PortalWriteHalf>,
+    read_half: Option<PortalReadHalf>,
+    peer: Option<SocketAddr>,
     addresses: Addresses,
     remote_route: Option<Route>,
     is_disconnecting: bool,
     portal_type: PortalType,
+    session: TcpPortalSessionInfo,
+    validation: Option<ProtocolValidation>,
 }
 
 impl TcpPortalWorker {
     /// Start a new `TcpPortalWorker` of type [`TypeName::Inlet`]
+    #[allow(clippy::too_many_arguments)]
     pub(super) async fn start_new_inlet(
         ctx: &Context,
         registry: TcpRegistry,
+        listener_address: Address,
         stream: TcpStream,
         peer: SocketAddr,
         ping_route: Route,
+        outlet_target: Option<String>,
         addresses: Addresses,
         access_control: Arc<dyn IncomingAccessControl>,
+        mailbox_options: MailboxOptions,
     ) -> Result<()> {
+        let (rx, tx) = stream.into_split();
         Self::start(
             ctx,
             registry,
-            peer,
-            State::SendPing { ping_route },
-            Some(stream),
+            Some(listener_address),
+            Some(peer),
+            State::SendPing {
+                ping_route,
+                outlet_target,
+            },
+            Some((Box::new(rx), Box::new(tx))),
             addresses,
+            None,
             PortalType::Inlet,
             access_control,
+            mailbox_options,
         )
-        .await
+        .await?;
+        Ok(())
     }
 
-    /// Start a new `TcpPortalWorker` of type [`TypeName::Outlet`]
+    /// Start a new `TcpPortalWorker` of type [`TypeName::Inlet`] for a connection
+    /// which is not a TCP stream, for example the standard input and output of the process
+    #[allow(clippy::too_many_arguments)]
+    pub(super) async fn start_new_stream_inlet(
+        ctx: &Context,
+        registry: TcpRegistry,
+        read_half: PortalReadHalf,
+        write_half: PortalWriteHalf,
+        ping_route: Route,
+        outlet_target: Option<String>,
+        addresses: Addresses,
+        access_control: Arc<dyn IncomingAccessControl>,
+        mailbox_options: MailboxOptions,
+    ) -> Result<()> {
+        Self::start(
+            ctx,
+            registry,
+            None,
+            None,
+            State::SendPing {
+                ping_route,
+                outlet_target,
+            },
+            Some((read_half, write_half)),
+            addresses,
+            None,
+            PortalType::Inlet,
+            access_control,
+            mailbox_options,
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Start a new `TcpPortalWorker` of type [`TypeName::Outlet`]. `local_info` is the
+    /// [`LocalInfo`] of the ping sent by the inlet. The first bytes sent by the inlet
+    /// are checked by the `protocol_validator` before being sent to the target
+    #[allow(clippy::too_many_arguments)]
     pub(super) async fn start_new_outlet(
         ctx: &Context,
         registry: TcpRegistry,
+        listener_address: Address,
         peer: SocketAddr,
         pong_route: Route,
+        local_info: Vec<LocalInfo>,
         addresses: Addresses,
+        protocol_validator: Option<Arc<dyn OutletProtocolValidator>>,
         access_control: Arc<dyn IncomingAccessControl>,
+        mailbox_options: MailboxOptions,
     ) -> Result<()> {
-        Self::start(
+        let session = Self::start(
             ctx,
             registry,
-            peer,
+            Some(listener_address),
+            Some(peer),
             State::SendPong { pong_route },
             None,
             addresses,
+            protocol_validator.map(ProtocolValidation::new),
             PortalType::Outlet,
             access_control,
+            mailbox_options,
         )
-        .await
+        .await?;
+        session.set_remote_local_info(local_info);
+        Ok(())
     }
 
     /// Start a new `TcpPortalWorker`
@@ -97,13 +169,16 @@ impl TcpPortalWorker {
     async fn start(
         ctx: &Context,
         registry: TcpRegistry,
-        peer: SocketAddr,
+        listener_address: Option<Address>,
+        peer: Option<SocketAddr>,
         state: State,
-        stream: Option<TcpStream>,
+        stream: Option<(PortalReadHalf, PortalWriteHalf)>,
         addresses: Addresses,
+        validation: Option<ProtocolValidation>,
         portal_type: PortalType,
         access_control: Arc<dyn IncomingAccessControl>,
-    ) -> Result<()> {
+        mailbox_options: MailboxOptions,
+    ) -> Result<TcpPortalSessionInfo> {
         info!(
             "Creating new {:?} at internal: {}, remote: {}",
             portal_type.str(),
@@ -112,13 +187,17 @@ impl TcpPortalWorker {
         );
 
         let (rx, tx) = match stream {
-            Some(s) => {
-                let (rx, tx) = s.into_split();
-                (Some(rx), Some(tx))
-            }
+            Some((rx, tx)) => (Some(rx), Some(tx)),
             None => (None, None),
         };
 
+        let session = TcpPortalSessionInfo::new(
+            addresses.internal.clone(),
+            listener_address,
+            (&portal_type).into(),
+            peer,
+        );
+
         let worker = Self {
             registry,
             state,
@@ -129,6 +208,8 @@ impl TcpPortalWorker {
             remote_route: None,
             is_disconnecting: false,
             portal_type,
+            session: session.clone(),
+            validation,
         };
 
         let internal_mailbox = Mailbox::new(
@@ -146,10 +227,11 @@ impl TcpPortalWorker {
         // start worker
         WorkerBuilder::new(worker)
             .with_mailboxes(Mailboxes::new(internal_mailbox, vec![remote_mailbox]))
+            .with_mailbox_options(mailbox_options)
             .start(ctx)
             .await?;
 
-        Ok(())
+        Ok(session)
     }
 }
 
@@ -157,6 +239,7 @@ enum DisconnectionReason {
     FailedTx,
     FailedRx,
     Remote,
+    Rejected,
 }
 
 impl TcpPortalWorker {
@@ -170,6 +253,7 @@ impl TcpPortalWorker {
             let next_hop = onward_route.next()?.clone();
             let receiver = TcpPortalRecvProcessor::new(
                 self.registry.clone(),
+                self.session.clone(),
                 rx,
                 self.addresses.internal.clone(),
                 onward_route,
@@ -252,7 +336,7 @@ impl TcpPortalWorker {
             DisconnectionReason::FailedTx => {
                 self.notify_remote_about_disconnection(ctx).await?;
             }
-            DisconnectionReason::FailedRx => {
+            DisconnectionReason::FailedRx | DisconnectionReason::Rejected => {
                 self.notify_remote_about_disconnection(ctx).await?;
                 self.stop_receiver(ctx).await?;
             }
@@ -272,14 +356,20 @@ impl TcpPortalWorker {
         Ok(())
     }
 
-    async fn handle_send_ping(&self, ctx: &Context, ping_route: Route) -> Result<State> {
+    async fn handle_send_ping(
+        &self,
+        ctx: &Context,
+        ping_route: Route,
+        outlet_target: Option<String>,
+    ) -> Result<State> {
+        let ping = match outlet_target {
+            Some(target) => PortalMessage::PingWithTarget(target),
+            None => PortalMessage::Ping,
+        };
+
         // Force creation of Outlet on the other side
-        ctx.send_from_address(
-            ping_route,
-            PortalMessage::Ping,
-            self.addresses.remote.clone(),
-        )
-        .await?;
+        ctx.send_from_address(ping_route, ping, self.addresses.remote.clone())
+            .await?;
 
         debug!("Inlet at: {} sent ping", self.addresses.internal);
 
@@ -296,12 +386,13 @@ impl TcpPortalWorker {
         .await?;
 
         if self.write_half.is_none() {
-            let stream = TcpStream::connect(self.peer)
+            let peer = self.peer.ok_or(TransportError::PortalInvalidState)?;
+            let stream = TcpStream::connect(peer)
                 .await
                 .map_err(TransportError::from)?;
             let (rx, tx) = stream.into_split();
-            self.write_half = Some(tx);
-            self.read_half = Some(rx);
+            self.write_half = Some(Box::new(tx));
+            self.read_half = Some(Box::new(rx));
 
             self.start_receiver(ctx, pong_route.clone()).await?;
 
@@ -327,8 +418,13 @@ impl Worker for TcpPortalWorker {
         let state = self.clone_state();
 
         match state {
-            State::SendPing { ping_route } => {
-                self.state = self.handle_send_ping(ctx, ping_route.clone()).await?;
+            State::SendPing {
+                ping_route,
+                outlet_target,
+            } => {
+                self.state = self
+                    .handle_send_ping(ctx, ping_route.clone(), outlet_target)
+                    .await?;
             }
             State::SendPong { pong_route } => {
                 self.state = self.handle_send_pong(ctx, pong_route.clone()).await?;
@@ -339,12 +435,30 @@ impl Worker for TcpPortalWorker {
         }
 
         self.registry.add_portal_worker(&self.addresses.remote);
+        self.registry.add_portal_session(self.session.clone());
 
         Ok(())
     }
 
-    async fn shutdown(&mut self, _ctx: &mut Self::Context) -> Result<()> {
+    async fn shutdown(&mut self, ctx: &mut Self::Context) -> Result<()> {
         self.registry.remove_portal_worker(&self.addresses.remote);
+        self.registry
+            .remove_portal_session(&self.addresses.internal);
+
+        // The worker was stopped while its connection was still open, for example to
+        // forcibly terminate the session: close the connection on both sides
+        if !self.is_disconnecting {
+            if let Some(remote_route) = self.remote_route.take() {
+                let _ = ctx
+                    .send_from_address(
+                        remote_route,
+                        PortalMessage::Disconnect,
+                        self.addresses.remote.clone(),
+                    )
+                    .await;
+            }
+            let _ = ctx.stop_processor(self.addresses.receiver.clone()).await;
+        }
 
         Ok(())
     }
@@ -375,6 +489,7 @@ impl Worker for TcpPortalWorker {
                     return Err(TransportError::PortalInvalidState.into());
                 }
 
+                let local_info = msg.local_message().local_info().to_vec();
                 let msg = PortalMessage::decode(msg.payload())?;
 
                 if let PortalMessage::Pong = msg {
@@ -383,6 +498,7 @@ impl Worker for TcpPortalWorker {
                 }
 
                 self.start_receiver(ctx, return_route.clone()).await?;
+                self.session.set_remote_local_info(local_info);
 
                 debug!("Inlet at: {} received pong", self.addresses.internal);
 
@@ -422,12 +538,32 @@ impl Worker for TcpPortalWorker {
 
                     match msg {
                         PortalMessage::Payload(payload) => {
+                            // Nothing is sent to the target until the validator accepts
+                            // the first bytes of the connection
+                            let payload = match self.validation.as_mut().map(|v| v.push(&payload)) {
+                                None => payload,
+                                Some(ProtocolVerdict::NeedMoreData) => return Ok(()),
+                                Some(ProtocolVerdict::Accept) => self
+                                    .validation
+                                    .take()
+                                    .map(|v| v.into_buffer())
+                                    .unwrap_or_default(),
+                                Some(ProtocolVerdict::Reject) => {
+                                    warn!(
+                                        "Outlet at: {} rejected a connection which doesn't use the expected protocol",
+                                        self.addresses.internal
+                                    );
+                                    self.start_disconnection(ctx, DisconnectionReason::Rejected)
+                                        .await?;
+                                    return Ok(());
+                                }
+                            };
                             if let Some(tx) = &mut self.write_half {
                                 match tx.write_all(&payload).await {
-                                    Ok(()) => {}
+                                    Ok(()) => self.session.record_sent(payload.len()),
                                     Err(err) => {
                                         warn!(
-                                            "Failed to send message to peer {} with error: {}",
+                                            "Failed to send message to peer {:?} with error: {}",
                                             self.peer, err
                                         );
                                         self.start_disconnection(
@@ -445,7 +581,9 @@ impl Worker for TcpPortalWorker {
                             self.start_disconnection(ctx, DisconnectionReason::Remote)
                                 .await?;
                         }
-                        PortalMessage::Ping | PortalMessage::Pong => {
+                        PortalMessage::Ping
+                        | PortalMessage::Pong
+                        | PortalMessage::PingWithTarget(_) => {
                             return Err(TransportError::Protocol.into());
                         }
                     }
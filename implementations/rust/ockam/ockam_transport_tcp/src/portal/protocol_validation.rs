@@ -0,0 +1,242 @@
+use core::fmt::{self, Debug, Display, Formatter};
+use core::str::FromStr;
+use ockam_core::compat::string::String;
+use ockam_core::compat::sync::Arc;
+use ockam_core::compat::vec::Vec;
+use ockam_core::Error;
+use ockam_transport_core::TransportError;
+
+/// Default number of bytes which can be buffered by an Outlet before its validator
+/// must accept or reject a connection
+pub const DEFAULT_VALIDATION_BYTES: usize = 4096;
+
+/// Result of the inspection of the first bytes sent to an Outlet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProtocolVerdict {
+    /// The connection uses the expected protocol, the buffered bytes are sent to the target
+    Accept,
+    /// The connection doesn't use the expected protocol, it is closed
+    Reject,
+    /// More bytes are needed to decide
+    NeedMoreData,
+}
+
+/// Inspect the first bytes sent by an Inlet to an Outlet, before anything is sent to the
+/// target of the Outlet, and reject the connections which don't use the expected protocol.
+///
+/// The bytes are buffered until the validator accepts or rejects them. The connection is
+/// rejected if no decision is made after [`OutletProtocolValidator::max_bytes`] bytes
+pub trait OutletProtocolValidator: Debug + Send + Sync + 'static {
+    /// Decide if the bytes received so far start with the expected protocol
+    fn validate(&self, data: &[u8]) -> ProtocolVerdict;
+
+    /// Maximum number of bytes buffered before a decision
+    fn max_bytes(&self) -> usize {
+        DEFAULT_VALIDATION_BYTES
+    }
+}
+
+/// Accept connections starting with a TLS ClientHello record
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TlsClientHelloValidator;
+
+impl OutletProtocolValidator for TlsClientHelloValidator {
+    fn validate(&self, data: &[u8]) -> ProtocolVerdict {
+        // A handshake record (22), a TLS 1.x version (3, 1..=4), a length on 2 bytes,
+        // then a ClientHello message (1)
+        let expected: [fn(u8) -> bool; 6] = [
+            |b| b == 22,
+            |b| b == 3,
+            |b| b <= 4,
+            |_| true,
+            |_| true,
+            |b| b == 1,
+        ];
+        for (byte, is_expected) in data.iter().zip(expected.iter()) {
+            if !is_expected(*byte) {
+                return ProtocolVerdict::Reject;
+            }
+        }
+        if data.len() < expected.len() {
+            ProtocolVerdict::NeedMoreData
+        } else {
+            ProtocolVerdict::Accept
+        }
+    }
+}
+
+/// Accept connections starting with an HTTP/1.x request line
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HttpRequestValidator;
+
+impl HttpRequestValidator {
+    const METHODS: [&'static [u8]; 9] = [
+        b"GET ",
+        b"HEAD ",
+        b"POST ",
+        b"PUT ",
+        b"DELETE ",
+        b"CONNECT ",
+        b"OPTIONS ",
+        b"TRACE ",
+        b"PATCH ",
+    ];
+}
+
+impl OutletProtocolValidator for HttpRequestValidator {
+    fn validate(&self, data: &[u8]) -> ProtocolVerdict {
+        let method = Self::METHODS.iter().find(|method| {
+            let length = method.len().min(data.len());
+            data[..length] == method[..length]
+        });
+        let method = match method {
+            Some(method) if data.len() >= method.len() => method,
+            Some(_) => return ProtocolVerdict::NeedMoreData,
+            None => return ProtocolVerdict::Reject,
+        };
+
+        let line = match data.iter().position(|b| *b == b'\n') {
+            Some(end) => &data[method.len()..end],
+            None => return ProtocolVerdict::NeedMoreData,
+        };
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        let is_request_line = line.ends_with(b" HTTP/1.0") || line.ends_with(b" HTTP/1.1");
+        if is_request_line && !line.starts_with(b" ") {
+            ProtocolVerdict::Accept
+        } else {
+            ProtocolVerdict::Reject
+        }
+    }
+}
+
+/// Protocols which can be checked by an Outlet with a built-in validator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutletProtocol {
+    /// See [`TlsClientHelloValidator`]
+    Tls,
+    /// See [`HttpRequestValidator`]
+    Http,
+}
+
+impl OutletProtocol {
+    /// Validator of this protocol
+    pub fn validator(&self) -> Arc<dyn OutletProtocolValidator> {
+        match self {
+            OutletProtocol::Tls => Arc::new(TlsClientHelloValidator),
+            OutletProtocol::Http => Arc::new(HttpRequestValidator),
+        }
+    }
+}
+
+impl FromStr for OutletProtocol {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "tls" => Ok(OutletProtocol::Tls),
+            "http" => Ok(OutletProtocol::Http),
+            _ => Err(TransportError::InvalidProtocol.into()),
+        }
+    }
+}
+
+impl Display for OutletProtocol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            OutletProtocol::Tls => write!(f, "tls"),
+            OutletProtocol::Http => write!(f, "http"),
+        }
+    }
+}
+
+/// State of the validation of a connection by an Outlet portal worker
+pub(crate) struct ProtocolValidation {
+    validator: Arc<dyn OutletProtocolValidator>,
+    buffer: Vec<u8>,
+}
+
+impl ProtocolValidation {
+    pub(crate) fn new(validator: Arc<dyn OutletProtocolValidator>) -> Self {
+        Self {
+            validator,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Buffer a payload and return the verdict of the validator. The verdict is `Reject`
+    /// when no decision can be made after the maximum number of bytes
+    pub(crate) fn push(&mut self, payload: &[u8]) -> ProtocolVerdict {
+        self.buffer.extend_from_slice(payload);
+        match self.validator.validate(&self.buffer) {
+            ProtocolVerdict::NeedMoreData if self.buffer.len() >= self.validator.max_bytes() => {
+                ProtocolVerdict::Reject
+            }
+            verdict => verdict,
+        }
+    }
+
+    /// Return the bytes buffered so far
+    pub(crate) fn into_buffer(self) -> Vec<u8> {
+        self.buffer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tls_client_hello() {
+        let validator = TlsClientHelloValidator;
+        assert_eq!(
+            validator.validate(&[22, 3, 1, 2, 0, 1, 0, 1, 252, 3, 3]),
+            ProtocolVerdict::Accept
+        );
+        assert_eq!(validator.validate(&[22, 3]), ProtocolVerdict::NeedMoreData);
+        assert_eq!(
+            validator.validate(b"GET / HTTP/1.1"),
+            ProtocolVerdict::Reject
+        );
+        // a ServerHello
+        assert_eq!(
+            validator.validate(&[22, 3, 3, 0, 64, 2]),
+            ProtocolVerdict::Reject
+        );
+    }
+
+    #[test]
+    fn test_http_request() {
+        let validator = HttpRequestValidator;
+        assert_eq!(
+            validator.validate(b"GET /index.html HTTP/1.1\r\nHost: example.com\r\n"),
+            ProtocolVerdict::Accept
+        );
+        assert_eq!(validator.validate(b"PO"), ProtocolVerdict::NeedMoreData);
+        assert_eq!(
+            validator.validate(b"POST /api HTTP/1.1"),
+            ProtocolVerdict::NeedMoreData
+        );
+        assert_eq!(
+            validator.validate(b"SSH-2.0-OpenSSH"),
+            ProtocolVerdict::Reject
+        );
+        assert_eq!(
+            validator.validate(b"GET /index.html SMTP\r\n"),
+            ProtocolVerdict::Reject
+        );
+    }
+
+    #[test]
+    fn test_validation_limit() {
+        let mut validation = ProtocolValidation::new(Arc::new(HttpRequestValidator));
+        assert_eq!(validation.push(b"GET /"), ProtocolVerdict::NeedMoreData);
+        let path = [b'a'; DEFAULT_VALIDATION_BYTES];
+        assert_eq!(validation.push(&path), ProtocolVerdict::Reject);
+
+        assert_eq!(
+            "TLS".parse::<OutletProtocol>().unwrap(),
+            OutletProtocol::Tls
+        );
+        assert!("smtp".parse::<OutletProtocol>().is_err());
+    }
+}
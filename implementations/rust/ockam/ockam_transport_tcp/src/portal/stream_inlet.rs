@@ -0,0 +1,110 @@
+use crate::portal::addresses::{Addresses, PortalType};
+use crate::portal::TcpPortalWorker;
+use crate::{TcpInletOptions, TcpRegistry};
+use core::pin::Pin;
+use core::task::{Context as TaskContext, Poll};
+use ockam_core::compat::boxed::Box;
+use ockam_core::{Address, Result, Route};
+use ockam_node::Context;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::oneshot;
+
+/// An Inlet streaming the bytes of a single connection, for example the standard
+/// input and output of the process, instead of accepting TCP connections.
+///
+/// It is created by [`TcpTransport::create_stream_inlet`](crate::TcpTransport::create_stream_inlet)
+#[derive(Debug)]
+pub struct StreamInlet {
+    address: Address,
+    closed: oneshot::Receiver<()>,
+}
+
+impl StreamInlet {
+    /// Start a portal worker streaming `reader` and `writer` to the Outlet
+    pub(crate) async fn start<R, W>(
+        ctx: &Context,
+        registry: TcpRegistry,
+        outlet_route: Route,
+        options: TcpInletOptions,
+        reader: R,
+        writer: W,
+    ) -> Result<Self>
+    where
+        R: AsyncRead + Send + Unpin + 'static,
+        W: AsyncWrite + Send + Unpin + 'static,
+    {
+        let addresses = Addresses::generate(PortalType::Inlet);
+        options.setup_flow_control(ctx.flow_controls(), &addresses, outlet_route.next()?);
+
+        let (closed_tx, closed_rx) = oneshot::channel();
+        TcpPortalWorker::start_new_stream_inlet(
+            ctx,
+            registry,
+            Box::new(reader),
+            Box::new(ClosingWriter::new(writer, closed_tx)),
+            outlet_route,
+            options.outlet_target.clone(),
+            addresses.clone(),
+            options.incoming_access_control.clone(),
+            options.mailbox_options,
+        )
+        .await?;
+
+        Ok(Self {
+            address: addresses.internal,
+            closed: closed_rx,
+        })
+    }
+
+    /// Address of the portal worker
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    /// Wait until the connection is closed, either by the reader reaching its end
+    /// or by the Outlet
+    pub async fn closed(self) {
+        // the sender is never used, it is dropped with the portal worker
+        let _ = self.closed.await;
+    }
+}
+
+/// Writer which notifies the [`StreamInlet`] when it is dropped,
+/// which happens when the portal worker stops
+struct ClosingWriter<W> {
+    inner: W,
+    _closed: oneshot::Sender<()>,
+}
+
+impl<W> ClosingWriter<W> {
+    fn new(inner: W, closed: oneshot::Sender<()>) -> Self {
+        Self {
+            inner,
+            _closed: closed,
+        }
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for ClosingWriter<W> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        buf: &[u8],
+    ) -> Poll<core::result::Result<usize, std::io::Error>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(
+        mut self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+    ) -> Poll<core::result::Result<(), std::io::Error>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(
+        mut self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+    ) -> Poll<core::result::Result<(), std::io::Error>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
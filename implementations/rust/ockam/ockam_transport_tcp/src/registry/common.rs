@@ -1,8 +1,11 @@
 use core::fmt;
 use core::fmt::Formatter;
+use core::sync::atomic::{AtomicU64, Ordering};
+use ockam_core::compat::sync::{Arc, RwLock};
 use ockam_core::flow_control::FlowControlId;
-use ockam_core::Address;
+use ockam_core::{Address, LocalInfo};
 use std::net::SocketAddr;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Tcp connection mode
 #[derive(Copy, Debug, Clone)]
@@ -157,3 +160,112 @@ impl TcpListenerInfo {
         &self.flow_control_id
     }
 }
+
+/// Tcp portal type
+#[derive(Copy, Debug, Clone, PartialEq, Eq)]
+pub enum TcpPortalType {
+    /// The session was accepted by an inlet
+    Inlet,
+    /// The session was opened by an outlet
+    Outlet,
+}
+
+impl fmt::Display for TcpPortalType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TcpPortalType::Inlet => write!(f, "inlet"),
+            TcpPortalType::Outlet => write!(f, "outlet"),
+        }
+    }
+}
+
+/// Information about specific Tcp portal session (corresponds to one specific Tcp connection
+/// accepted by an inlet or opened by an outlet)
+#[derive(Debug, Clone)]
+pub struct TcpPortalSessionInfo {
+    address: Address,
+    listener_address: Option<Address>,
+    portal_type: TcpPortalType,
+    peer: Option<SocketAddr>,
+    started_at: u64,
+    stats: Arc<TcpPortalSessionStats>,
+}
+
+#[derive(Debug, Default)]
+struct TcpPortalSessionStats {
+    bytes_received: AtomicU64,
+    bytes_sent: AtomicU64,
+    remote_local_info: RwLock<Vec<LocalInfo>>,
+}
+
+impl TcpPortalSessionInfo {
+    /// Constructor. The session is considered started now
+    pub fn new(
+        address: Address,
+        listener_address: Option<Address>,
+        portal_type: TcpPortalType,
+        peer: Option<SocketAddr>,
+    ) -> Self {
+        let started_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        Self {
+            address,
+            listener_address,
+            portal_type,
+            peer,
+            started_at,
+            stats: Default::default(),
+        }
+    }
+
+    /// Internal address of the portal worker, identifying the session
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+    /// Address of the inlet listener processor or outlet listener worker which created the
+    /// session. There is no listener for an inlet streaming a single connection
+    pub fn listener_address(&self) -> Option<&Address> {
+        self.listener_address.as_ref()
+    }
+    /// [`TcpPortalType`] for this session
+    pub fn portal_type(&self) -> TcpPortalType {
+        self.portal_type
+    }
+    /// Socket address of the Tcp client for an inlet, or of the target for an outlet
+    pub fn peer(&self) -> Option<SocketAddr> {
+        self.peer
+    }
+    /// Start time of the session, in seconds since the Unix epoch
+    pub fn started_at(&self) -> u64 {
+        self.started_at
+    }
+    /// Number of bytes read from the Tcp connection
+    pub fn bytes_received(&self) -> u64 {
+        self.stats.bytes_received.load(Ordering::Relaxed)
+    }
+    /// Number of bytes written to the Tcp connection
+    pub fn bytes_sent(&self) -> u64 {
+        self.stats.bytes_sent.load(Ordering::Relaxed)
+    }
+    /// [`LocalInfo`] of the first message received from the other side of the portal,
+    /// for example the identity of the other side of the secure channel it came through
+    pub fn remote_local_info(&self) -> Vec<LocalInfo> {
+        self.stats.remote_local_info.read().unwrap().clone()
+    }
+
+    pub(crate) fn record_received(&self, bytes: usize) {
+        self.stats
+            .bytes_received
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+    pub(crate) fn record_sent(&self, bytes: usize) {
+        self.stats
+            .bytes_sent
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+    pub(crate) fn set_remote_local_info(&self, local_info: Vec<LocalInfo>) {
+        *self.stats.remote_local_info.write().unwrap() = local_info;
+    }
+}
@@ -1,5 +1,6 @@
-use crate::{TcpListenerInfo, TcpReceiverInfo, TcpRegistry, TcpSenderInfo};
+use crate::{TcpListenerInfo, TcpPortalSessionInfo, TcpReceiverInfo, TcpRegistry, TcpSenderInfo};
 use ockam_core::Address;
+use std::net::SocketAddr;
 
 impl TcpRegistry {
     pub(crate) fn add_portal_worker(&self, addr: &Address) {
@@ -12,6 +13,16 @@ impl TcpRegistry {
             lock.remove_portal_worker(addr);
         }
     }
+    pub(crate) fn add_portal_session(&self, info: TcpPortalSessionInfo) {
+        if let Ok(mut lock) = self.registry.write() {
+            lock.add_portal_session(info);
+        }
+    }
+    pub(crate) fn remove_portal_session(&self, addr: &Address) {
+        if let Ok(mut lock) = self.registry.write() {
+            lock.remove_portal_session(addr);
+        }
+    }
     pub(crate) fn add_portal_receiver_processor(&self, addr: &Address) {
         if let Ok(mut lock) = self.registry.write() {
             lock.add_portal_receiver_processor(addr);
@@ -62,6 +73,29 @@ impl TcpRegistry {
             lock.remove_sender_worker(addr);
         }
     }
+    pub(crate) fn add_shared_sender(&self, addr: &Address) {
+        if let Ok(mut lock) = self.registry.write() {
+            lock.add_shared_sender(addr);
+        }
+    }
+    pub(crate) fn get_shared_sender(&self, socket: &SocketAddr) -> Option<TcpSenderInfo> {
+        self.registry
+            .read()
+            .ok()
+            .and_then(|lock| lock.get_shared_sender(socket))
+    }
+    pub(crate) fn add_shared_stream(&self, sender: &Address, stream: &Address) {
+        if let Ok(mut lock) = self.registry.write() {
+            lock.add_shared_stream(sender, stream);
+        }
+    }
+    /// Return the sender of the stream, and true if it was the last stream of that sender
+    pub(crate) fn remove_shared_stream(&self, stream: &Address) -> Option<(Address, bool)> {
+        self.registry
+            .write()
+            .ok()
+            .and_then(|mut lock| lock.remove_shared_stream(stream))
+    }
     pub(crate) fn add_receiver_processor(&self, info: TcpReceiverInfo) {
         if let Ok(mut lock) = self.registry.write() {
             lock.add_receiver_processor(info);
@@ -1,15 +1,20 @@
-use crate::{TcpListenerInfo, TcpReceiverInfo, TcpSenderInfo};
+use crate::{TcpListenerInfo, TcpPortalSessionInfo, TcpReceiverInfo, TcpSenderInfo};
+use ockam_core::compat::collections::BTreeMap;
 use ockam_core::Address;
+use std::net::SocketAddr;
 
 #[derive(Default)]
 pub(super) struct InternalRegistry {
     pub(super) portal_workers: Vec<Address>,
+    pub(super) portal_sessions: Vec<TcpPortalSessionInfo>,
     pub(super) portal_receiver_processors: Vec<Address>,
     pub(super) inlet_listener_processors: Vec<Address>,
     pub(super) outlet_listener_workers: Vec<Address>,
     pub(super) listener_processors: Vec<TcpListenerInfo>,
     pub(super) sender_workers: Vec<TcpSenderInfo>,
     pub(super) receiver_processors: Vec<TcpReceiverInfo>,
+    /// Streams of each shared sender worker, one for each user of the connection
+    pub(super) shared_senders: BTreeMap<Address, Vec<Address>>,
 }
 
 impl InternalRegistry {
@@ -19,6 +24,12 @@ impl InternalRegistry {
     pub(super) fn remove_portal_worker(&mut self, addr: &Address) {
         self.portal_workers.retain(|x| x != addr);
     }
+    pub(super) fn add_portal_session(&mut self, info: TcpPortalSessionInfo) {
+        self.portal_sessions.push(info)
+    }
+    pub(super) fn remove_portal_session(&mut self, addr: &Address) {
+        self.portal_sessions.retain(|x| x.address() != addr);
+    }
     pub(super) fn add_portal_receiver_processor(&mut self, addr: &Address) {
         self.portal_receiver_processors.push(addr.clone())
     }
@@ -48,6 +59,34 @@ impl InternalRegistry {
     }
     pub(super) fn remove_sender_worker(&mut self, addr: &Address) {
         self.sender_workers.retain(|x| x.address() != addr);
+        self.shared_senders.remove(addr);
+    }
+    pub(super) fn add_shared_sender(&mut self, addr: &Address) {
+        self.shared_senders.insert(addr.clone(), vec![]);
+    }
+    pub(super) fn get_shared_sender(&self, socket: &SocketAddr) -> Option<TcpSenderInfo> {
+        self.sender_workers
+            .iter()
+            .find(|x| {
+                &x.socket_address() == socket && self.shared_senders.contains_key(x.address())
+            })
+            .cloned()
+    }
+    pub(super) fn add_shared_stream(&mut self, sender: &Address, stream: &Address) {
+        if let Some(streams) = self.shared_senders.get_mut(sender) {
+            streams.push(stream.clone())
+        }
+    }
+    pub(super) fn remove_shared_stream(&mut self, stream: &Address) -> Option<(Address, bool)> {
+        let (sender, streams) = self
+            .shared_senders
+            .iter_mut()
+            .find(|(_, streams)| streams.contains(stream))?;
+        streams.retain(|x| x != stream);
+        Some((sender.clone(), streams.is_empty()))
+    }
+    pub(super) fn get_shared_streams(&self, sender: &Address) -> Option<Vec<Address>> {
+        self.shared_senders.get(sender).cloned()
     }
     pub(super) fn add_receiver_processor(&mut self, info: TcpReceiverInfo) {
         self.receiver_processors.push(info)
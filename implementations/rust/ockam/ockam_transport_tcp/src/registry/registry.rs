@@ -1,6 +1,7 @@
 use crate::registry::internal::InternalRegistry;
-use crate::{TcpListenerInfo, TcpReceiverInfo, TcpSenderInfo};
+use crate::{TcpListenerInfo, TcpPortalSessionInfo, TcpReceiverInfo, TcpSenderInfo};
 use ockam_core::compat::sync::{Arc, RwLock};
+use ockam_core::Address;
 
 /// Registry of all active workers and processors in TCP Transport to ease their lifecycle management
 #[derive(Default, Clone)]
@@ -23,4 +24,15 @@ impl TcpRegistry {
     pub fn get_all_listeners(&self) -> Vec<TcpListenerInfo> {
         self.registry.read().unwrap().listener_processors.clone()
     }
+
+    /// Return the active portal sessions
+    pub fn get_all_portal_sessions(&self) -> Vec<TcpPortalSessionInfo> {
+        self.registry.read().unwrap().portal_sessions.clone()
+    }
+
+    /// Return the streams of a shared connection given the [`Address`] of its sender worker,
+    /// one for each user of the connection, or `None` if the connection is not shared
+    pub fn get_shared_streams(&self, sender: &Address) -> Option<Vec<Address>> {
+        self.registry.read().unwrap().get_shared_streams(sender)
+    }
 }
@@ -0,0 +1,136 @@
+use core::fmt::{self, Display, Formatter};
+use core::str::FromStr;
+use ockam_core::compat::net::IpAddr;
+use ockam_core::Error;
+use ockam_transport_core::TransportError;
+
+/// A network from which a TCP listener accepts connections.
+///
+/// It is written in the CIDR notation, `address/prefix_length`, for example `10.0.0.0/8`
+/// or `fd00::/8`. A single address, without prefix length, only allows that address
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AllowedNetwork {
+    address: IpAddr,
+    prefix_length: u8,
+}
+
+impl AllowedNetwork {
+    /// Allow the addresses starting with the first `prefix_length` bits of `address`
+    pub fn new(address: IpAddr, prefix_length: u8) -> Result<Self, Error> {
+        if prefix_length > max_prefix_length(&address) {
+            return Err(TransportError::InvalidAddress.into());
+        }
+        Ok(Self {
+            address,
+            prefix_length,
+        })
+    }
+
+    /// Return true if a peer address is part of this network.
+    /// IPv4 addresses mapped to IPv6 addresses are compared as IPv4 addresses
+    pub fn contains(&self, peer: &IpAddr) -> bool {
+        match (self.address, unmap(peer)) {
+            (IpAddr::V4(network), IpAddr::V4(peer)) => {
+                mask(u32::from(network).into(), 32, self.prefix_length)
+                    == mask(u32::from(peer).into(), 32, self.prefix_length)
+            }
+            (IpAddr::V6(network), IpAddr::V6(peer)) => {
+                mask(u128::from(network), 128, self.prefix_length)
+                    == mask(u128::from(peer), 128, self.prefix_length)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Return true if a peer is allowed to connect to a listener.
+/// Every peer is allowed if no network is configured
+pub(crate) fn is_peer_allowed(allowed: &[AllowedNetwork], peer: &IpAddr) -> bool {
+    allowed.is_empty() || allowed.iter().any(|network| network.contains(peer))
+}
+
+fn max_prefix_length(address: &IpAddr) -> u8 {
+    match address {
+        IpAddr::V4(_) => 32,
+        IpAddr::V6(_) => 128,
+    }
+}
+
+fn unmap(address: &IpAddr) -> IpAddr {
+    match address {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(*address),
+        IpAddr::V4(_) => *address,
+    }
+}
+
+/// Keep the first `prefix_length` bits of an address of `bits` bits
+fn mask(address: u128, bits: u8, prefix_length: u8) -> u128 {
+    if prefix_length == 0 {
+        0
+    } else {
+        address >> (bits - prefix_length)
+    }
+}
+
+impl FromStr for AllowedNetwork {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (address, prefix_length) = match s.split_once('/') {
+            Some((address, prefix_length)) => (
+                address,
+                Some(
+                    prefix_length
+                        .parse::<u8>()
+                        .map_err(|_| TransportError::InvalidAddress)?,
+                ),
+            ),
+            None => (s, None),
+        };
+        let address = address
+            .parse::<IpAddr>()
+            .map_err(|_| TransportError::InvalidAddress)?;
+        let address = unmap(&address);
+        Self::new(
+            address,
+            prefix_length.unwrap_or_else(|| max_prefix_length(&address)),
+        )
+    }
+}
+
+impl Display for AllowedNetwork {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.address, self.prefix_length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ockam_core::compat::string::ToString;
+
+    #[test]
+    fn test_allowed_networks() {
+        let lan: AllowedNetwork = "192.168.1.0/24".parse().unwrap();
+        assert!(lan.contains(&"192.168.1.42".parse().unwrap()));
+        assert!(lan.contains(&"::ffff:192.168.1.42".parse().unwrap()));
+        assert!(!lan.contains(&"192.168.2.42".parse().unwrap()));
+        assert!(!lan.contains(&"fd00::1".parse().unwrap()));
+        assert_eq!(lan.to_string(), "192.168.1.0/24");
+
+        let localhost: AllowedNetwork = "::1".parse().unwrap();
+        assert!(localhost.contains(&"::1".parse().unwrap()));
+        assert!(!localhost.contains(&"::2".parse().unwrap()));
+        assert_eq!(localhost.to_string(), "::1/128");
+
+        let any: AllowedNetwork = "0.0.0.0/0".parse().unwrap();
+        assert!(any.contains(&"8.8.8.8".parse().unwrap()));
+
+        assert!(is_peer_allowed(&[], &"8.8.8.8".parse().unwrap()));
+        assert!(!is_peer_allowed(&[lan], &"8.8.8.8".parse().unwrap()));
+
+        assert!("192.168.1.0/33".parse::<AllowedNetwork>().is_err());
+        assert!("localhost/8".parse::<AllowedNetwork>().is_err());
+    }
+}
@@ -129,13 +129,14 @@ pub fn resolve_peer(peer: String) -> Result<SocketAddr> {
     }
 
     // Try to resolve hostname
-    if let Ok(mut iter) = peer.to_socket_addrs() {
-        // Prefer ip4
-        if let Some(p) = iter.find(|x| x.is_ipv4()) {
-            return Ok(p);
+    if let Ok(iter) = peer.to_socket_addrs() {
+        let addresses: Vec<SocketAddr> = iter.collect();
+        // Prefer ip4, and use ip6 for the hosts which only have ip6 addresses
+        if let Some(p) = addresses.iter().find(|x| x.is_ipv4()) {
+            return Ok(*p);
         }
-        if let Some(p) = iter.find(|x| x.is_ipv6()) {
-            return Ok(p);
+        if let Some(p) = addresses.first() {
+            return Ok(*p);
         }
     }
 
@@ -200,5 +201,15 @@ mod test {
 
         let result = parse_socket_addr("127.0.0.1:8080");
         assert!(result.is_ok());
+
+        let result = parse_socket_addr("[::1]:8080");
+        assert!(result.unwrap().is_ipv6());
+
+        let result = parse_socket_addr("[::]:0");
+        assert!(result.is_ok());
+
+        let result = parse_socket_addr("::1:8080");
+        assert!(result.is_err());
+        assert_transport_error(result, TransportError::InvalidAddress);
     }
 }
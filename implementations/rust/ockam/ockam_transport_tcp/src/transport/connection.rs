@@ -1,7 +1,9 @@
-use crate::transport::common::{resolve_peer, TcpConnection};
-use crate::workers::{Addresses, TcpRecvProcessor, TcpSendWorker};
+use crate::transport::common::TcpConnection;
+use crate::workers::{Addresses, TcpRecvProcessor, TcpSendWorker, TcpStreamWorker};
 use crate::{TcpConnectionMode, TcpConnectionOptions, TcpTransport};
+use ockam_core::compat::net::SocketAddr;
 use ockam_core::{Address, Result};
+use ockam_transport_core::TransportError;
 
 impl TcpTransport {
     /// Establish an outgoing TCP connection.
@@ -22,8 +24,61 @@ impl TcpTransport {
         options: TcpConnectionOptions,
     ) -> Result<TcpConnection> {
         // Resolve peer address
-        let socket = resolve_peer(peer.into())?;
+        let socket = self.dns_options().resolve(&peer.into()).await?;
 
+        if options.shared {
+            return self.connect_shared(socket, options).await;
+        }
+
+        self.dial(socket, options).await
+    }
+
+    /// Open a stream on the shared connection to that peer, dialing the connection if needed
+    async fn connect_shared(
+        &self,
+        socket: SocketAddr,
+        options: TcpConnectionOptions,
+    ) -> Result<TcpConnection> {
+        let connection = match self.registry.get_shared_sender(&socket) {
+            Some(info) => TcpConnection::new(
+                info.address().clone(),
+                info.receiver_address().clone(),
+                socket,
+                *info.mode(),
+                info.flow_control_id().clone(),
+            ),
+            None => {
+                let connection = self.dial(socket, TcpConnectionOptions::new()).await?;
+                self.registry.add_shared_sender(connection.sender_address());
+                connection
+            }
+        };
+
+        let stream_address = TcpStreamWorker::start(
+            &self.ctx,
+            &self.registry,
+            connection.sender_address(),
+            connection.receiver_address(),
+            connection.flow_control_id(),
+            &options.flow_control_id,
+            &options.consumer,
+        )
+        .await?;
+
+        Ok(TcpConnection::new(
+            stream_address,
+            connection.receiver_address().clone(),
+            socket,
+            connection.mode(),
+            options.flow_control_id,
+        ))
+    }
+
+    async fn dial(
+        &self,
+        socket: SocketAddr,
+        options: TcpConnectionOptions,
+    ) -> Result<TcpConnection> {
         let (read_half, write_half) = TcpSendWorker::connect(socket).await?;
 
         let mode = TcpConnectionMode::Outgoing;
@@ -66,8 +121,27 @@ impl TcpTransport {
         ))
     }
 
-    /// Interrupt an active TCP connection given its Sender `Address`
+    /// Interrupt an active TCP connection given its Sender `Address`.
+    ///
+    /// For a shared connection, only the stream of that user is closed. The connection itself
+    /// is interrupted once all its users disconnected, and it can't be interrupted directly
+    /// while it is still in use
     pub async fn disconnect(&self, address: impl Into<Address>) -> Result<()> {
-        self.ctx.stop_worker(address.into()).await
+        let address = address.into();
+        if let Some((sender, last)) = self.registry.remove_shared_stream(&address) {
+            self.ctx.stop_worker(address).await?;
+            if last {
+                self.ctx.stop_worker(sender).await?;
+            }
+            return Ok(());
+        }
+
+        if let Some(streams) = self.registry.get_shared_streams(&address) {
+            if !streams.is_empty() {
+                return Err(TransportError::ConnectionInUse.into());
+            }
+        }
+
+        self.ctx.stop_worker(address).await
     }
 }
@@ -0,0 +1,259 @@
+use core::fmt;
+use core::str::FromStr;
+use core::time::Duration;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, OnceLock};
+
+use hickory_resolver::config::{
+    LookupIpStrategy, NameServerConfig, NameServerConfigGroup, Protocol, ResolverConfig,
+    ResolverOpts, ServerOrderingStrategy,
+};
+use hickory_resolver::TokioAsyncResolver;
+use ockam_core::Result;
+use ockam_transport_core::TransportError;
+use tracing::{debug, warn};
+
+use crate::transport::common::parse_socket_addr;
+
+/// Maximum time spent waiting for the answer of a resolver when no timeout is configured
+const DEFAULT_RESOLVER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// IP version used when a host name resolves to both IPv4 and IPv6 addresses
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IpPreference {
+    /// Use an IPv4 address if there is one
+    #[default]
+    Ipv4,
+    /// Use an IPv6 address if there is one
+    Ipv6,
+}
+
+impl FromStr for IpPreference {
+    type Err = ockam_core::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "ipv4" => Ok(IpPreference::Ipv4),
+            "ipv6" => Ok(IpPreference::Ipv6),
+            _ => Err(TransportError::InvalidAddress.into()),
+        }
+    }
+}
+
+impl fmt::Display for IpPreference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IpPreference::Ipv4 => write!(f, "ipv4"),
+            IpPreference::Ipv6 => write!(f, "ipv6"),
+        }
+    }
+}
+
+/// Options controlling how the TCP transport resolves the host names of the
+/// peers it connects to, for its connections and its outlets.
+///
+/// A host name is resolved, in order, with:
+///  - the static overrides set for this host
+///  - the custom resolvers, queried one after the other, over UDP then over TCP when an
+///    answer is truncated, if any. Their answers are cached for their time to live
+///  - the resolver of the system otherwise
+///
+/// The resolution never blocks the runtime
+#[derive(Clone, Debug, Default)]
+pub struct DnsOptions {
+    ip_preference: IpPreference,
+    resolvers: Vec<SocketAddr>,
+    overrides: HashMap<String, Vec<IpAddr>>,
+    timeout: Option<Duration>,
+    custom_resolver: CustomResolver,
+}
+
+/// Resolver querying the custom DNS servers, created on first use and shared by the
+/// clones of the options, so that they share its cache
+#[derive(Clone, Default)]
+struct CustomResolver(Arc<OnceLock<TokioAsyncResolver>>);
+
+impl fmt::Debug for CustomResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CustomResolver")
+    }
+}
+
+impl DnsOptions {
+    /// Resolve host names with the system resolver, preferring IPv4 addresses
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the IP version used when a host has both IPv4 and IPv6 addresses
+    pub fn with_ip_preference(mut self, ip_preference: IpPreference) -> Self {
+        self.ip_preference = ip_preference;
+        self
+    }
+
+    /// Query these DNS servers instead of the resolver of the system
+    pub fn with_resolvers(mut self, resolvers: Vec<SocketAddr>) -> Self {
+        self.resolvers = resolvers;
+        self.custom_resolver = CustomResolver::default();
+        self
+    }
+
+    /// Resolve a host to static addresses, without querying any resolver
+    pub fn with_override(mut self, host: impl Into<String>, addresses: Vec<IpAddr>) -> Self {
+        self.overrides.insert(host.into().to_lowercase(), addresses);
+        self
+    }
+
+    /// Maximum time spent resolving a host name
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self.custom_resolver = CustomResolver::default();
+        self
+    }
+
+    /// Getter
+    pub fn ip_preference(&self) -> IpPreference {
+        self.ip_preference
+    }
+
+    /// Getter
+    pub fn resolvers(&self) -> &[SocketAddr] {
+        &self.resolvers
+    }
+
+    /// Getter
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Resolve a peer, given as `host:port` or as a socket address
+    pub async fn resolve(&self, peer: &str) -> Result<SocketAddr> {
+        let addresses = self.lookup(peer).await?;
+        self.select(&addresses).ok_or_else(|| {
+            warn!(%peer, "no address found for the host");
+            TransportError::InvalidAddress.into()
+        })
+    }
+
+    /// Return all the addresses of a peer, given as `host:port` or as a socket address
+    pub async fn lookup(&self, peer: &str) -> Result<Vec<SocketAddr>> {
+        if let Ok(socket_addr) = parse_socket_addr(peer) {
+            return Ok(vec![socket_addr]);
+        }
+        let (host, port) = peer
+            .rsplit_once(':')
+            .ok_or(TransportError::InvalidAddress)?;
+        let port: u16 = port.parse().map_err(|_| TransportError::InvalidAddress)?;
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+
+        let addresses = if let Some(addresses) = self.overrides.get(&host.to_lowercase()) {
+            debug!(%host, ?addresses, "using the static addresses of the host");
+            addresses.clone()
+        } else if !self.resolvers.is_empty() {
+            self.query_resolvers(host).await?
+        } else {
+            self.query_system(host, port).await?
+        };
+        Ok(addresses
+            .into_iter()
+            .map(|ip| SocketAddr::new(ip, port))
+            .collect())
+    }
+
+    /// Select an address with the preferred IP version, or any address otherwise
+    fn select(&self, addresses: &[SocketAddr]) -> Option<SocketAddr> {
+        let preferred = |address: &&SocketAddr| match self.ip_preference {
+            IpPreference::Ipv4 => address.is_ipv4(),
+            IpPreference::Ipv6 => address.is_ipv6(),
+        };
+        addresses
+            .iter()
+            .find(preferred)
+            .or_else(|| addresses.first())
+            .copied()
+    }
+
+    /// Resolve a host with the resolver of the system, on a blocking thread
+    async fn query_system(&self, host: &str, port: u16) -> Result<Vec<IpAddr>> {
+        let lookup = tokio::net::lookup_host((host, port));
+        let result = match self.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, lookup).await.map_err(|_| {
+                warn!(%host, "the resolution of the host timed out");
+                TransportError::InvalidAddress
+            })?,
+            None => lookup.await,
+        };
+        let addresses = result.map_err(|_| TransportError::InvalidAddress)?;
+        Ok(addresses.map(|a| a.ip()).collect())
+    }
+
+    async fn query_resolvers(&self, host: &str) -> Result<Vec<IpAddr>> {
+        let resolver = self
+            .custom_resolver
+            .0
+            .get_or_init(|| self.create_resolver());
+        match resolver.lookup_ip(host).await {
+            Ok(lookup) => Ok(lookup.iter().collect()),
+            Err(e) => {
+                debug!(%host, "cannot resolve the host with the DNS servers: {e}");
+                Ok(vec![])
+            }
+        }
+    }
+
+    /// Create a resolver querying the custom DNS servers in order, over UDP, and over TCP
+    /// when an answer is truncated
+    fn create_resolver(&self) -> TokioAsyncResolver {
+        let mut name_servers = NameServerConfigGroup::with_capacity(2 * self.resolvers.len());
+        for resolver in &self.resolvers {
+            name_servers.push(NameServerConfig::new(*resolver, Protocol::Udp));
+            name_servers.push(NameServerConfig::new(*resolver, Protocol::Tcp));
+        }
+        let mut options = ResolverOpts::default();
+        options.timeout = self.timeout.unwrap_or(DEFAULT_RESOLVER_TIMEOUT);
+        options.ip_strategy = LookupIpStrategy::Ipv4AndIpv6;
+        // the servers are queried in their configuration order
+        options.server_ordering_strategy = ServerOrderingStrategy::UserProvidedOrder;
+        TokioAsyncResolver::tokio(
+            ResolverConfig::from_parts(None, vec![], name_servers),
+            options,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    #[tokio::test]
+    async fn test_resolve_with_overrides() -> Result<()> {
+        let v4 = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 7));
+        let v6 = IpAddr::V6(Ipv6Addr::LOCALHOST);
+        let options = DnsOptions::new().with_override("DB.internal", vec![v6, v4]);
+        assert_eq!(
+            options.resolve("db.internal:5432").await?,
+            SocketAddr::new(v4, 5432)
+        );
+
+        let options = options.with_ip_preference(IpPreference::Ipv6);
+        assert_eq!(
+            options.resolve("db.internal:5432").await?,
+            SocketAddr::new(v6, 5432)
+        );
+
+        // socket addresses are not resolved
+        assert_eq!(
+            options.resolve("127.0.0.1:80").await?,
+            "127.0.0.1:80".parse().unwrap()
+        );
+
+        // all the addresses of a host can be returned
+        assert_eq!(
+            options.lookup("db.internal:5432").await?,
+            vec![SocketAddr::new(v6, 5432), SocketAddr::new(v4, 5432)]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,210 @@
+use core::fmt;
+use core::time::Duration;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use ockam_core::{async_trait, Result};
+use ockam_transport_core::TransportError;
+use tokio::net::UdpSocket;
+
+use crate::AllowedNetwork;
+
+/// Default time during which a peer can connect to a listener after a successful knock
+const DEFAULT_KNOCK_WINDOW: Duration = Duration::from_secs(30);
+
+/// Minimum time between two knocks of the same peer which are verified
+const MIN_KNOCK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Maximum number of knocks verified per second by a listener, for all the peers
+const MAX_KNOCKS_PER_SECOND: u32 = 10;
+
+/// Verify the knocks received by a TCP listener protected by port knocking.
+///
+/// A knock is a single UDP datagram sent to the port of the listener, see
+/// [`send_knock`]. Its content is opaque to the transport, for example an identity
+/// and a signature, and it is up to the verifier to decide if the peer can connect.
+/// The knocks are rate limited before being verified
+#[async_trait]
+pub trait KnockVerifier: Send + Sync + 'static {
+    /// Return true if the peer sending `knock` can connect to the listener bound to `listener`
+    async fn verify(&self, listener: SocketAddr, peer: SocketAddr, knock: &[u8]) -> Result<bool>;
+}
+
+/// Port knocking options of a TCP listener.
+///
+/// The connections of a peer are closed as soon as they are accepted, unless the
+/// peer sent a valid knock, over UDP, on the same port as the listener, during the
+/// last `window`. The peers of the exempt networks never have to knock
+#[derive(Clone)]
+pub struct KnockOptions {
+    pub(crate) verifier: Arc<dyn KnockVerifier>,
+    pub(crate) window: Duration,
+    pub(crate) exempt_networks: Vec<AllowedNetwork>,
+}
+
+impl KnockOptions {
+    /// Verify the knocks with `verifier`, and open the listener for 30 seconds after a knock
+    pub fn new(verifier: Arc<dyn KnockVerifier>) -> Self {
+        Self {
+            verifier,
+            window: DEFAULT_KNOCK_WINDOW,
+            exempt_networks: vec![],
+        }
+    }
+
+    /// Time during which a peer can connect to the listener after a valid knock
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Accept the connections of the peers from a given network without a knock.
+    /// Can be called several times to exempt several networks
+    pub fn with_exempt_network(mut self, network: AllowedNetwork) -> Self {
+        self.exempt_networks.push(network);
+        self
+    }
+
+    /// Getter
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Networks from which connections are accepted without a knock
+    pub fn exempt_networks(&self) -> &[AllowedNetwork] {
+        &self.exempt_networks
+    }
+}
+
+impl fmt::Debug for KnockOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KnockOptions")
+            .field("window", &self.window)
+            .field("exempt_networks", &self.exempt_networks)
+            .finish()
+    }
+}
+
+/// Peers which sent a valid knock, with the time until which they can connect
+#[derive(Clone, Debug, Default)]
+pub(crate) struct KnockedPeers {
+    peers: Arc<Mutex<HashMap<IpAddr, Instant>>>,
+}
+
+impl KnockedPeers {
+    /// Allow a peer to connect during `window`
+    pub(crate) fn open(&self, peer: IpAddr, window: Duration) {
+        let now = Instant::now();
+        let mut peers = self.peers.lock().unwrap();
+        peers.retain(|_, until| *until > now);
+        peers.insert(peer, now + window);
+    }
+
+    /// Return true if a peer knocked during the last window
+    pub(crate) fn is_open(&self, peer: &IpAddr) -> bool {
+        self.peers
+            .lock()
+            .unwrap()
+            .get(peer)
+            .map(|until| *until > Instant::now())
+            .unwrap_or(false)
+    }
+}
+
+/// Limit the number of knocks verified by a listener, so that unauthenticated datagrams
+/// can't be used to make it import identities and verify signatures continuously
+#[derive(Debug)]
+pub(crate) struct KnockRateLimiter {
+    last_knocks: HashMap<IpAddr, Instant>,
+    window_start: Instant,
+    knocks_in_window: u32,
+}
+
+impl Default for KnockRateLimiter {
+    fn default() -> Self {
+        Self {
+            last_knocks: HashMap::new(),
+            window_start: Instant::now(),
+            knocks_in_window: 0,
+        }
+    }
+}
+
+impl KnockRateLimiter {
+    /// Return true if the knock of a peer can be verified now
+    pub(crate) fn allow(&mut self, peer: IpAddr) -> bool {
+        let now = Instant::now();
+        self.last_knocks
+            .retain(|_, at| now.duration_since(*at) < MIN_KNOCK_INTERVAL);
+        if self.last_knocks.contains_key(&peer) {
+            return false;
+        }
+        if now.duration_since(self.window_start) >= Duration::from_secs(1) {
+            self.window_start = now;
+            self.knocks_in_window = 0;
+        }
+        if self.knocks_in_window >= MAX_KNOCKS_PER_SECOND {
+            return false;
+        }
+        self.knocks_in_window += 1;
+        self.last_knocks.insert(peer, now);
+        true
+    }
+}
+
+/// Send a knock to a TCP listener protected by port knocking.
+/// The knock is sent to the port of the listener, over UDP
+pub async fn send_knock(listener: SocketAddr, knock: &[u8]) -> Result<()> {
+    let bind: SocketAddr = if listener.is_ipv4() {
+        (Ipv4Addr::UNSPECIFIED, 0).into()
+    } else {
+        (Ipv6Addr::UNSPECIFIED, 0).into()
+    };
+    let socket = UdpSocket::bind(bind)
+        .await
+        .map_err(|_| TransportError::BindFailed)?;
+    socket
+        .send_to(knock, listener)
+        .await
+        .map_err(|_| TransportError::GenericIo)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_knocked_peers() {
+        let peers = KnockedPeers::default();
+        let peer: IpAddr = "10.0.0.7".parse().unwrap();
+        assert!(!peers.is_open(&peer));
+
+        peers.open(peer, Duration::from_secs(30));
+        assert!(peers.is_open(&peer));
+        assert!(!peers.is_open(&"10.0.0.8".parse().unwrap()));
+
+        peers.open(peer, Duration::ZERO);
+        assert!(!peers.is_open(&peer));
+    }
+
+    #[test]
+    fn test_knock_rate_limiter() {
+        let mut limiter = KnockRateLimiter::default();
+        let peer: IpAddr = "10.0.0.7".parse().unwrap();
+        assert!(limiter.allow(peer));
+        // the same peer must wait before knocking again
+        assert!(!limiter.allow(peer));
+
+        // the other peers are limited as a whole
+        let mut allowed = 1;
+        for i in 0..2 * MAX_KNOCKS_PER_SECOND {
+            if limiter.allow(IpAddr::from([10, 0, 1, i as u8])) {
+                allowed += 1;
+            }
+        }
+        assert_eq!(allowed, MAX_KNOCKS_PER_SECOND);
+    }
+}
@@ -4,7 +4,7 @@ use ockam_node::Context;
 use ockam_transport_core::Transport;
 use std::sync::Arc;
 
-use crate::{TcpConnectionOptions, TcpRegistry, TcpTransport, TCP};
+use crate::{DnsOptions, TcpConnectionOptions, TcpRegistry, TcpTransport, TCP};
 
 impl TcpTransport {
     /// Create a TCP transport
@@ -21,6 +21,7 @@ impl TcpTransport {
         let tcp = Self {
             ctx: ctx.async_try_clone().await?,
             registry: TcpRegistry::default(),
+            dns_options: Default::default(),
         };
         // make the TCP transport available in the list of supported transports for
         // later address resolution when socket addresses will need to be instantiated as TCP
@@ -39,6 +40,15 @@ impl TcpTransport {
     pub fn registry(&self) -> &TcpRegistry {
         &self.registry
     }
+    /// Options used to resolve the host names of the peers
+    pub fn dns_options(&self) -> DnsOptions {
+        self.dns_options.read().unwrap().clone()
+    }
+    /// Set the options used to resolve the host names of the peers, for the
+    /// connections and the outlets created afterwards
+    pub fn set_dns_options(&self, options: DnsOptions) {
+        *self.dns_options.write().unwrap() = options;
+    }
 }
 
 #[async_trait]
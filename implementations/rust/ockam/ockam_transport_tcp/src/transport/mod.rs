@@ -1,16 +1,23 @@
+pub(crate) mod allowed_networks;
 pub(crate) mod common;
 mod connection;
+mod dns;
+pub(crate) mod knock;
 mod lifecycle;
 mod listener;
 mod portals;
 
+pub use allowed_networks::*;
 pub use common::*;
+pub use dns::*;
+pub use knock::{send_knock, KnockOptions, KnockVerifier};
 
 pub use crate::portal::options::*;
 
 use crate::TcpRegistry;
 use ockam_core::{async_trait, AsyncTryClone, Result};
 use ockam_node::{Context, HasContext};
+use std::sync::{Arc, RwLock};
 
 /// High level management interface for TCP transports
 ///
@@ -55,6 +62,7 @@ use ockam_node::{Context, HasContext};
 pub struct TcpTransport {
     ctx: Context,
     registry: TcpRegistry,
+    dns_options: Arc<RwLock<DnsOptions>>,
 }
 
 /// This trait adds a `create_tcp_transport` method to any struct returning a Context.
@@ -1,8 +1,12 @@
 use crate::portal::TcpInletListenProcessor;
-use crate::transport::common::{parse_socket_addr, resolve_peer};
-use crate::{portal::TcpOutletListenWorker, TcpInletOptions, TcpOutletOptions, TcpTransport};
+use crate::transport::common::parse_socket_addr;
+use crate::{
+    portal::TcpOutletListenWorker, StreamInlet, TcpInletOptions, TcpOutletOptions, TcpTransport,
+};
 use ockam_core::compat::net::SocketAddr;
-use ockam_core::{Address, Result, Route};
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{Address, Error, Result, Route};
+use tokio::io::{AsyncRead, AsyncWrite};
 
 impl TcpTransport {
     /// Create Tcp Inlet that listens on bind_addr, transforms Tcp stream into Ockam Routable
@@ -39,6 +43,53 @@ impl TcpTransport {
         .await
     }
 
+    /// Create Tcp Inlet for a single connection: the bytes read from `reader` are forwarded
+    /// to the Outlet using outlet_route, and the bytes sent back by the Outlet are written to
+    /// `writer`. This can be used to stream the standard input and output of the process
+    /// without listening on a local port.
+    ///
+    /// The Inlet stops when `reader` is closed or when the Outlet disconnects,
+    /// see [`StreamInlet::closed`]
+    ///
+    /// ```rust
+    /// use ockam_transport_tcp::{TcpInletOptions, TcpTransport};
+    /// # use ockam_node::Context;
+    /// # use ockam_core::{Result, route};
+    /// # async fn test(ctx: Context) -> Result<()> {
+    /// let tcp = TcpTransport::create(&ctx).await?;
+    /// let inlet = tcp
+    ///     .create_stream_inlet(
+    ///         route!["outlet"],
+    ///         TcpInletOptions::new(),
+    ///         tokio::io::empty(),
+    ///         tokio::io::sink(),
+    ///     )
+    ///     .await?;
+    /// inlet.closed().await;
+    /// # Ok(()) }
+    /// ```
+    pub async fn create_stream_inlet<R, W>(
+        &self,
+        outlet_route: impl Into<Route>,
+        options: TcpInletOptions,
+        reader: R,
+        writer: W,
+    ) -> Result<StreamInlet>
+    where
+        R: AsyncRead + Send + Unpin + 'static,
+        W: AsyncWrite + Send + Unpin + 'static,
+    {
+        StreamInlet::start(
+            &self.ctx,
+            self.registry.clone(),
+            outlet_route.into(),
+            options,
+            reader,
+            writer,
+        )
+        .await
+    }
+
     /// Stop inlet at addr
     ///
     /// ```rust
@@ -83,12 +134,14 @@ impl TcpTransport {
         options: TcpOutletOptions,
     ) -> Result<()> {
         // Resolve peer address
-        let peer_addr = resolve_peer(peer.into())?;
+        let dns_options = self.dns_options();
+        let peer_addr = dns_options.resolve(&peer.into()).await?;
         TcpOutletListenWorker::start(
             &self.ctx,
             self.registry.clone(),
             address.into(),
             peer_addr,
+            dns_options,
             options,
         )
         .await?;
@@ -103,8 +156,15 @@ impl TcpTransport {
         peer: SocketAddr,
         options: TcpOutletOptions,
     ) -> Result<()> {
-        TcpOutletListenWorker::start(&self.ctx, self.registry.clone(), address, peer, options)
-            .await?;
+        TcpOutletListenWorker::start(
+            &self.ctx,
+            self.registry.clone(),
+            address,
+            peer,
+            self.dns_options(),
+            options,
+        )
+        .await?;
 
         Ok(())
     }
@@ -126,4 +186,24 @@ impl TcpTransport {
         self.ctx.stop_worker(addr).await?;
         Ok(())
     }
+
+    /// Forcibly terminate the portal session whose portal worker has the internal address
+    /// `addr`, see [`TcpPortalSessionInfo::address`](crate::TcpPortalSessionInfo::address).
+    /// Its Tcp connection is closed and the other side of the portal is notified, while the
+    /// inlet or outlet which created the session keeps accepting new sessions
+    pub async fn disconnect_portal_session(&self, addr: &Address) -> Result<()> {
+        if !self
+            .registry
+            .get_all_portal_sessions()
+            .iter()
+            .any(|session| session.address() == addr)
+        {
+            return Err(Error::new(
+                Origin::Transport,
+                Kind::NotFound,
+                format!("portal session {addr} not found"),
+            ));
+        }
+        self.ctx.stop_worker(addr.clone()).await
+    }
 }
@@ -0,0 +1,91 @@
+use crate::transport::knock::{KnockRateLimiter, KnockedPeers};
+use crate::KnockOptions;
+use ockam_core::{async_trait, compat::net::SocketAddr};
+use ockam_core::{Address, Processor, Result};
+use ockam_node::Context;
+use ockam_transport_core::TransportError;
+use tokio::net::UdpSocket;
+use tracing::{debug, info, warn};
+
+/// Maximum size of a knock, which must fit in a single UDP datagram
+const MAX_KNOCK_SIZE: usize = 8192;
+
+/// A processor receiving the knocks sent to a TCP listener protected by port knocking.
+///
+/// It listens on the same address and port as the TCP listener, over UDP, and opens the
+/// listener to the peers sending a valid knock.
+pub(crate) struct KnockProcessor {
+    socket: UdpSocket,
+    listener: SocketAddr,
+    options: KnockOptions,
+    knocked_peers: KnockedPeers,
+    rate_limiter: KnockRateLimiter,
+}
+
+impl KnockProcessor {
+    pub(crate) async fn start(
+        ctx: &Context,
+        addr: SocketAddr,
+        options: KnockOptions,
+        knocked_peers: KnockedPeers,
+    ) -> Result<Address> {
+        debug!("Binding the knock socket to {}", addr);
+        let socket = UdpSocket::bind(addr)
+            .await
+            .map_err(|_| TransportError::BindFailed)?;
+        let listener = socket
+            .local_addr()
+            .map_err(|_| TransportError::BindFailed)?;
+
+        let address = Address::random_tagged("KnockProcessor");
+        let processor = Self {
+            socket,
+            listener,
+            options,
+            knocked_peers,
+            rate_limiter: KnockRateLimiter::default(),
+        };
+        ctx.start_processor(address.clone(), processor).await?;
+
+        Ok(address)
+    }
+}
+
+#[async_trait]
+impl Processor for KnockProcessor {
+    type Context = Context;
+
+    async fn initialize(&mut self, ctx: &mut Context) -> Result<()> {
+        ctx.set_cluster(crate::CLUSTER_NAME).await
+    }
+
+    async fn process(&mut self, _ctx: &mut Self::Context) -> Result<bool> {
+        let mut buffer = vec![0u8; MAX_KNOCK_SIZE];
+        let (length, peer) = self
+            .socket
+            .recv_from(&mut buffer)
+            .await
+            .map_err(|_| TransportError::GenericIo)?;
+
+        if !self.rate_limiter.allow(peer.ip()) {
+            debug!(%peer, "ignoring a knock, too many knocks were received");
+            return Ok(true);
+        }
+
+        match self
+            .options
+            .verifier
+            .verify(self.listener, peer, &buffer[..length])
+            .await
+        {
+            Ok(true) => {
+                info!(%peer, "opening the TCP listener after a valid knock");
+                self.knocked_peers.open(peer.ip(), self.options.window);
+            }
+            Ok(false) => warn!(%peer, "ignoring an invalid knock"),
+            Err(e) => warn!(%peer, "cannot verify a knock: {e}"),
+        }
+
+        Ok(true)
+    }
+}
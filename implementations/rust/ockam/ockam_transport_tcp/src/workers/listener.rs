@@ -1,11 +1,13 @@
-use crate::workers::{Addresses, TcpRecvProcessor};
+use crate::transport::allowed_networks::is_peer_allowed;
+use crate::transport::knock::KnockedPeers;
+use crate::workers::{Addresses, KnockProcessor, TcpRecvProcessor};
 use crate::{TcpConnectionMode, TcpListenerInfo, TcpListenerOptions, TcpRegistry, TcpSendWorker};
 use ockam_core::{async_trait, compat::net::SocketAddr};
 use ockam_core::{Address, Processor, Result};
 use ockam_node::Context;
 use ockam_transport_core::TransportError;
 use tokio::net::TcpListener;
-use tracing::debug;
+use tracing::{debug, warn};
 
 /// A TCP Listen processor
 ///
@@ -17,6 +19,7 @@ pub(crate) struct TcpListenProcessor {
     inner: TcpListener,
     socket_address: SocketAddr,
     options: TcpListenerOptions,
+    knock: Option<(Address, KnockedPeers)>,
 }
 
 impl TcpListenProcessor {
@@ -35,17 +38,45 @@ impl TcpListenProcessor {
         let address = Address::random_tagged("TcpListenProcessor");
         options.setup_flow_control_for_listener(ctx.flow_controls(), &address);
 
+        // The knocks are received on the same address and port as the listener, over UDP
+        let knock = match &options.knock {
+            Some(knock_options) => {
+                let knocked_peers = KnockedPeers::default();
+                let knock_address =
+                    KnockProcessor::start(ctx, saddr, knock_options.clone(), knocked_peers.clone())
+                        .await?;
+                Some((knock_address, knocked_peers))
+            }
+            None => None,
+        };
+
         let processor = Self {
             registry,
             inner,
             socket_address: saddr,
             options,
+            knock,
         };
 
         ctx.start_processor(address.clone(), processor).await?;
 
         Ok((saddr, address))
     }
+
+    /// Return true if the listener is not protected by port knocking, or if the peer
+    /// is exempt from knocking, or if it recently sent a valid knock
+    fn is_knock_valid(&self, peer: &SocketAddr) -> bool {
+        let (options, (_, knocked_peers)) = match (&self.options.knock, &self.knock) {
+            (Some(options), Some(knock)) => (options, knock),
+            _ => return true,
+        };
+        let ip = peer.ip();
+        options
+            .exempt_networks
+            .iter()
+            .any(|network| network.contains(&ip))
+            || knocked_peers.is_open(&ip)
+    }
 }
 
 #[async_trait]
@@ -66,6 +97,9 @@ impl Processor for TcpListenProcessor {
 
     async fn shutdown(&mut self, ctx: &mut Self::Context) -> Result<()> {
         self.registry.remove_listener_processor(&ctx.address());
+        if let Some((knock_address, _)) = &self.knock {
+            let _ = ctx.stop_processor(knock_address.clone()).await;
+        }
 
         Ok(())
     }
@@ -75,6 +109,14 @@ impl Processor for TcpListenProcessor {
 
         // Wait for an incoming connection
         let (stream, peer) = self.inner.accept().await.map_err(TransportError::from)?;
+        if !is_peer_allowed(&self.options.allowed_networks, &peer.ip()) {
+            warn!(%peer, "rejecting a TCP connection from a peer outside of the allowed networks");
+            return Ok(true);
+        }
+        if !self.is_knock_valid(&peer) {
+            warn!(%peer, "rejecting a TCP connection from a peer which did not knock");
+            return Ok(true);
+        }
         debug!("TCP connection accepted");
 
         let mode = TcpConnectionMode::Incoming;
@@ -1,9 +1,13 @@
 mod addresses;
+mod knock;
 mod listener;
 mod receiver;
 mod sender;
+mod stream;
 
 pub(crate) use addresses::*;
+pub(crate) use knock::*;
 pub(crate) use listener::*;
 pub(crate) use receiver::*;
 pub(crate) use sender::*;
+pub(crate) use stream::*;
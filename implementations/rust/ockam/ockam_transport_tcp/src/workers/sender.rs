@@ -171,6 +171,15 @@ impl Worker for TcpSendWorker {
     }
 
     async fn shutdown(&mut self, ctx: &mut Self::Context) -> Result<()> {
+        // Close the streams of the users of a shared connection
+        let streams = self
+            .registry
+            .get_shared_streams(self.addresses.sender_address())
+            .unwrap_or_default();
+        for stream in streams {
+            let _ = ctx.stop_worker(stream).await;
+        }
+
         self.registry
             .remove_sender_worker(self.addresses.sender_address());
 
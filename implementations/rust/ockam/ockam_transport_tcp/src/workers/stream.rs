@@ -0,0 +1,126 @@
+use crate::TcpRegistry;
+use ockam_core::compat::sync::Arc;
+use ockam_core::flow_control::{FlowControlId, FlowControlOutgoingAccessControl};
+use ockam_core::{
+    async_trait, Address, AllowAll, AllowOnwardAddress, AllowSourceAddress, Any, Mailbox,
+    Mailboxes, Result, Routed, Worker,
+};
+use ockam_node::{Context, WorkerBuilder};
+use tracing::trace;
+
+/// Stream of one user of a shared TCP connection.
+///
+/// The user sends its messages to the local address of its stream, which forwards them
+/// to the sender of the connection. The messages received on the connection for this user
+/// come back through the remote address of the stream, which is a producer with its own
+/// [`FlowControlId`]: a user of the connection only receives the messages sent to its own
+/// stream. Since each stream has its own mailbox, a slow user does not block the messages
+/// received for the other users of the connection
+pub(crate) struct TcpStreamWorker {
+    local_address: Address,
+    remote_address: Address,
+    sender_address: Address,
+}
+
+impl TcpStreamWorker {
+    /// Start a stream on a shared connection, and return the local address of the stream,
+    /// which is used instead of the address of the sender of the connection
+    pub(crate) async fn start(
+        ctx: &Context,
+        registry: &TcpRegistry,
+        sender_address: &Address,
+        receiver_address: &Address,
+        connection_flow_control_id: &FlowControlId,
+        flow_control_id: &FlowControlId,
+        consumer: &[FlowControlId],
+    ) -> Result<Address> {
+        let local_address = Address::random_tagged("TcpStreamWorker_local");
+        let remote_address = Address::random_tagged("TcpStreamWorker_remote");
+
+        let flow_controls = ctx.flow_controls();
+        flow_controls.add_consumer(remote_address.clone(), connection_flow_control_id);
+        flow_controls.add_producer(
+            remote_address.clone(),
+            flow_control_id,
+            None,
+            vec![local_address.clone()],
+        );
+        for id in consumer {
+            flow_controls.add_consumer(local_address.clone(), id);
+        }
+
+        let local_mailbox = Mailbox::new(
+            local_address.clone(),
+            Arc::new(AllowAll),
+            Arc::new(AllowOnwardAddress(sender_address.clone())),
+        );
+        let remote_mailbox = Mailbox::new(
+            remote_address.clone(),
+            Arc::new(AllowSourceAddress(receiver_address.clone())),
+            Arc::new(FlowControlOutgoingAccessControl::new(
+                flow_controls,
+                flow_control_id.clone(),
+                None,
+            )),
+        );
+
+        let worker = Self {
+            local_address: local_address.clone(),
+            remote_address,
+            sender_address: sender_address.clone(),
+        };
+        WorkerBuilder::new(worker)
+            .with_mailboxes(Mailboxes::new(local_mailbox, vec![remote_mailbox]))
+            .start(ctx)
+            .await?;
+        registry.add_shared_stream(sender_address, &local_address);
+
+        Ok(local_address)
+    }
+}
+
+#[async_trait]
+impl Worker for TcpStreamWorker {
+    type Context = Context;
+    type Message = Any;
+
+    async fn initialize(&mut self, ctx: &mut Self::Context) -> Result<()> {
+        ctx.set_cluster(crate::CLUSTER_NAME).await
+    }
+
+    async fn handle_message(
+        &mut self,
+        ctx: &mut Context,
+        msg: Routed<Self::Message>,
+    ) -> Result<()> {
+        let recipient = msg.msg_addr();
+        let mut local_message = msg.into_local_message();
+        let transport = local_message.transport_mut();
+        if recipient == self.remote_address {
+            // A message received on the connection: the user replies through the stream
+            // instead of the sender of the connection
+            transport.onward_route.step()?;
+            transport
+                .return_route
+                .modify()
+                .pop_front()
+                .prepend(self.local_address.clone());
+            trace!(stream = %self.local_address, "Message received on a shared connection");
+            ctx.forward_from_address(local_message, self.remote_address.clone())
+                .await
+        } else {
+            // A message sent by the user: the reply comes back to the stream
+            transport
+                .onward_route
+                .modify()
+                .pop_front()
+                .prepend(self.sender_address.clone());
+            transport
+                .return_route
+                .modify()
+                .prepend(self.remote_address.clone());
+            ctx.forward_from_address(local_message, self.local_address.clone())
+                .await
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use core::time::Duration;
+use ockam_core::compat::net::SocketAddr;
+use ockam_core::compat::sync::Arc;
+use ockam_core::{async_trait, route, Result, Routed, Worker};
+use ockam_node::{Context, MessageSendReceiveOptions};
+use ockam_transport_tcp::{
+    send_knock, KnockOptions, KnockVerifier, TcpConnectionOptions, TcpListenerOptions, TcpTransport,
+};
+
+pub struct Echoer;
+
+#[ockam_core::worker]
+impl Worker for Echoer {
+    type Message = String;
+    type Context = Context;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<String>) -> Result<()> {
+        ctx.send(msg.return_route(), msg.body()).await
+    }
+}
+
+struct Password;
+
+#[async_trait]
+impl KnockVerifier for Password {
+    async fn verify(&self, _listener: SocketAddr, _peer: SocketAddr, knock: &[u8]) -> Result<bool> {
+        Ok(knock == b"open sesame")
+    }
+}
+
+fn send_options() -> MessageSendReceiveOptions {
+    MessageSendReceiveOptions::new().with_timeout(Duration::from_secs(1))
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn tcp_knock__listener_is_only_open_after_a_valid_knock(ctx: &mut Context) -> Result<()> {
+    let options = TcpListenerOptions::new().with_knock(KnockOptions::new(Arc::new(Password)));
+    ctx.flow_controls()
+        .add_consumer("echoer", &options.spawner_flow_control_id());
+    ctx.start_worker("echoer", Echoer).await?;
+
+    let transport = TcpTransport::create(ctx).await?;
+    let listener = transport.listen("127.0.0.1:0", options).await?;
+
+    // the connection is closed by the listener
+    let connection = transport
+        .connect(&listener.socket_string(), TcpConnectionOptions::new())
+        .await?;
+    let reply = ctx
+        .send_and_receive_extended::<String>(
+            route![connection, "echoer"],
+            "hello".to_string(),
+            send_options(),
+        )
+        .await;
+    assert!(reply.is_err());
+
+    send_knock(*listener.socket_address(), b"wrong password").await?;
+    ctx.sleep(Duration::from_millis(200)).await;
+    let connection = transport
+        .connect(&listener.socket_string(), TcpConnectionOptions::new())
+        .await?;
+    let reply = ctx
+        .send_and_receive_extended::<String>(
+            route![connection, "echoer"],
+            "hello".to_string(),
+            send_options(),
+        )
+        .await;
+    assert!(reply.is_err());
+
+    // knocks are rate limited
+    ctx.sleep(Duration::from_secs(1)).await;
+    send_knock(*listener.socket_address(), b"open sesame").await?;
+    ctx.sleep(Duration::from_millis(200)).await;
+    let connection = transport
+        .connect(&listener.socket_string(), TcpConnectionOptions::new())
+        .await?;
+    let reply: String = ctx
+        .send_and_receive_extended::<String>(
+            route![connection, "echoer"],
+            "hello".to_string(),
+            send_options(),
+        )
+        .await?
+        .body();
+    assert_eq!(reply, "hello");
+
+    ctx.stop().await
+}
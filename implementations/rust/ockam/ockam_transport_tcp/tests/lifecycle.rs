@@ -187,3 +187,64 @@ async fn tcp_lifecycle__stop_listener__should_stop_accepting_connections(
 
     Ok(())
 }
+
+#[allow(non_snake_case)]
+#[ockam_macros::test]
+async fn tcp_lifecycle__shared_connection__should_be_reused_until_last_disconnect(
+    ctx: &mut Context,
+) -> Result<()> {
+    let options = TcpListenerOptions::new();
+    ctx.flow_controls()
+        .add_consumer("echoer", &options.spawner_flow_control_id());
+    ctx.start_worker("echoer", Echoer).await?;
+
+    let transport = TcpTransport::create(ctx).await?;
+    let listener = transport.listen("127.0.0.1:0", options).await?;
+
+    let msg: String = rand::thread_rng()
+        .sample_iter(&rand::distributions::Alphanumeric)
+        .take(256)
+        .map(char::from)
+        .collect();
+
+    let connection1 = transport
+        .connect(
+            &listener.socket_string(),
+            TcpConnectionOptions::new().shared(),
+        )
+        .await?;
+    let connection2 = transport
+        .connect(
+            &listener.socket_string(),
+            TcpConnectionOptions::new().shared(),
+        )
+        .await?;
+    assert_ne!(connection1.sender_address(), connection2.sender_address());
+    assert_ne!(connection1.flow_control_id(), connection2.flow_control_id());
+    let senders = transport.registry().get_all_sender_workers();
+    assert_eq!(senders.len(), 1);
+
+    let res = transport.disconnect(senders[0].address().clone()).await;
+    assert!(
+        res.is_err(),
+        "Should not disconnect a connection still in use"
+    );
+
+    transport.disconnect(connection1.clone()).await?;
+    let reply: String = ctx
+        .send_and_receive(route![connection2.clone(), "echoer"], msg.clone())
+        .await?;
+    assert_eq!(reply, msg, "Should receive the same message");
+
+    transport.disconnect(connection2.clone()).await?;
+    let res = ctx
+        .send(route![connection2.clone(), "echoer"], msg.clone())
+        .await;
+    assert!(res.is_err(), "Should not send messages after disconnection");
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
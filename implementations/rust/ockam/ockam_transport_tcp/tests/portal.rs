@@ -4,10 +4,12 @@ use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream};
 
 use ockam_core::compat::rand::random;
-use ockam_core::{route, Result};
+use ockam_core::compat::sync::Arc;
+use ockam_core::{async_trait, route, LocalMessage, Result};
 use ockam_node::Context;
 use ockam_transport_tcp::{
-    TcpConnectionOptions, TcpInletOptions, TcpListenerOptions, TcpOutletOptions, TcpTransport,
+    HttpRequestValidator, OutletTargetRouter, TcpConnectionOptions, TcpInletOptions,
+    TcpListenerOptions, TcpOutletOptions, TcpPortalType, TcpTransport,
 };
 
 const LENGTH: usize = 32;
@@ -118,6 +120,217 @@ async fn portal__reverse_flow__should_succeed(ctx: &mut Context) -> Result<()> {
     Ok(())
 }
 
+#[derive(Debug)]
+struct FixedRouter(String);
+
+#[async_trait]
+impl OutletTargetRouter for FixedRouter {
+    async fn route(&self, _msg: &LocalMessage) -> Result<Option<String>> {
+        Ok(Some(self.0.clone()))
+    }
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test(timeout = 5000)]
+async fn portal__target_router__should_select_the_target(ctx: &mut Context) -> Result<()> {
+    let payload1 = generate_binary();
+    let payload2 = generate_binary();
+
+    let tcp = TcpTransport::create(ctx).await?;
+    let default_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let routed_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let router = FixedRouter(routed_listener.local_addr().unwrap().to_string());
+    tcp.create_outlet(
+        "outlet",
+        default_listener.local_addr().unwrap().to_string(),
+        TcpOutletOptions::new().with_target_router(Arc::new(router)),
+    )
+    .await?;
+    let (inlet_addr, _) = tcp
+        .create_inlet("127.0.0.1:0", route!["outlet"], TcpInletOptions::new())
+        .await?;
+
+    let handle = tokio::spawn(async move {
+        let (mut stream, _) = routed_listener.accept().await.unwrap();
+
+        read_assert_binary(&mut stream, payload1).await;
+        write_binary(&mut stream, payload2).await;
+    });
+
+    // Wait till listener is up
+    tokio::time::sleep(Duration::from_millis(250)).await;
+
+    let mut stream = TcpStream::connect(inlet_addr).await.unwrap();
+    write_binary(&mut stream, payload1).await;
+    read_assert_binary(&mut stream, payload2).await;
+
+    let res = handle.await;
+    assert!(res.is_ok());
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test(timeout = 10000)]
+async fn portal__protocol_validator__should_reject_other_protocols(
+    ctx: &mut Context,
+) -> Result<()> {
+    let request = b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n";
+
+    let tcp = TcpTransport::create(ctx).await?;
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    tcp.create_outlet(
+        "outlet",
+        listener.local_addr().unwrap().to_string(),
+        TcpOutletOptions::new().with_protocol_validator(Arc::new(HttpRequestValidator)),
+    )
+    .await?;
+    let (inlet_addr, _) = tcp
+        .create_inlet("127.0.0.1:0", route!["outlet"], TcpInletOptions::new())
+        .await?;
+
+    let handle = tokio::spawn(async move {
+        // an HTTP request is forwarded as is
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut buf = [0u8; 64];
+        let length = stream.read(&mut buf).await.unwrap();
+        assert_eq!(&buf[..length], request);
+
+        // nothing is received from a connection using another protocol
+        let (mut stream, _) = listener.accept().await.unwrap();
+        assert_eq!(stream.read(&mut buf).await.unwrap(), 0);
+    });
+
+    let mut stream = TcpStream::connect(inlet_addr).await.unwrap();
+    stream.write_all(request).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(250)).await;
+
+    let mut stream = TcpStream::connect(inlet_addr).await.unwrap();
+    stream.write_all(b"SSH-2.0-OpenSSH_9.0\r\n").await.unwrap();
+    let mut buf = [0u8; LENGTH];
+    assert_eq!(stream.read(&mut buf).await.unwrap(), 0);
+    assert!(handle.await.is_ok());
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test(timeout = 10000)]
+async fn portal__disconnect_session__should_close_connection(ctx: &mut Context) -> Result<()> {
+    let payload1 = generate_binary();
+    let payload2 = generate_binary();
+
+    let tcp = TcpTransport::create(ctx).await?;
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    tcp.create_outlet(
+        "outlet",
+        listener.local_addr().unwrap().to_string(),
+        TcpOutletOptions::new(),
+    )
+    .await?;
+    let (inlet_addr, inlet_listener) = tcp
+        .create_inlet("127.0.0.1:0", route!["outlet"], TcpInletOptions::new())
+        .await?;
+
+    let handle = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        read_assert_binary(&mut stream, payload1).await;
+        write_binary(&mut stream, payload2).await;
+        // the connection is closed once the session is disconnected
+        let mut buf = [0u8; LENGTH];
+        assert_eq!(stream.read(&mut buf).await.unwrap(), 0);
+    });
+
+    let mut stream = TcpStream::connect(inlet_addr).await.unwrap();
+    write_binary(&mut stream, payload1).await;
+    read_assert_binary(&mut stream, payload2).await;
+
+    let sessions = tcp.registry().get_all_portal_sessions();
+    let inlet_session = sessions
+        .iter()
+        .find(|s| s.listener_address() == Some(&inlet_listener))
+        .unwrap();
+    assert_eq!(inlet_session.portal_type(), TcpPortalType::Inlet);
+    assert_eq!(inlet_session.bytes_received(), LENGTH as u64);
+    assert_eq!(inlet_session.bytes_sent(), LENGTH as u64);
+
+    tcp.disconnect_portal_session(inlet_session.address())
+        .await?;
+    let mut buf = [0u8; LENGTH];
+    assert_eq!(stream.read(&mut buf).await.unwrap(), 0);
+    assert!(handle.await.is_ok());
+
+    // the inlet still accepts new connections
+    tokio::time::sleep(Duration::from_millis(250)).await;
+    assert!(tcp
+        .registry()
+        .get_all_portal_sessions()
+        .iter()
+        .all(|s| s.address() != inlet_session.address()));
+    assert!(TcpStream::connect(inlet_addr).await.is_ok());
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
+
+#[allow(non_snake_case)]
+#[ockam_macros::test(timeout = 10000)]
+async fn portal__stream_inlet__should_succeed(ctx: &mut Context) -> Result<()> {
+    let payload1 = generate_binary();
+    let payload2 = generate_binary();
+
+    let tcp = TcpTransport::create(ctx).await?;
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let bind_address = listener.local_addr().unwrap().to_string();
+    tcp.create_outlet("outlet", bind_address, TcpOutletOptions::new())
+        .await?;
+
+    let handle = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+
+        read_assert_binary(&mut stream, payload1).await;
+        write_binary(&mut stream, payload2).await;
+    });
+
+    // The inlet streams one end of an in-memory pipe instead of a TCP connection
+    let (client, inlet_side) = tokio::io::duplex(4 * LENGTH);
+    let (reader, writer) = tokio::io::split(inlet_side);
+    let inlet = tcp
+        .create_stream_inlet(route!["outlet"], TcpInletOptions::new(), reader, writer)
+        .await?;
+
+    let (mut client_rx, mut client_tx) = tokio::io::split(client);
+    client_tx.write_all(&payload1).await.unwrap();
+    let mut payload = [0u8; LENGTH];
+    client_rx.read_exact(&mut payload).await.unwrap();
+    assert_eq!(payload, payload2);
+
+    let res = handle.await;
+    assert!(res.is_ok());
+
+    // The inlet is closed when the client closes its end of the pipe
+    drop(client_rx);
+    drop(client_tx);
+    inlet.closed().await;
+
+    if let Err(e) = ctx.stop().await {
+        println!("Unclean stop: {}", e)
+    }
+
+    Ok(())
+}
+
 #[allow(non_snake_case)]
 #[ockam_macros::test(timeout = 15000)]
 async fn portal__tcp_connection__should_succeed(ctx: &mut Context) -> Result<()> {
@@ -1,7 +1,8 @@
 use super::message::PunchMessage;
 use crate::{hole_puncher::worker::UdpHolePunchWorker, PunchError};
+use core::time::Duration;
 use ockam_core::{Address, AllowOnwardAddress, AllowSourceAddress, Result, Route};
-use ockam_node::Context;
+use ockam_node::{Context, MessageReceiveOptions};
 
 /// High level management interface for UDP NAT Hole Punchers
 ///
@@ -117,6 +118,23 @@ impl UdpHolePuncher {
         Ok(())
     }
 
+    /// Wait until Hole Puncher successfully opens a hole to the peer or the
+    /// given timeout expires
+    pub async fn wait_for_hole_open_timeout(&mut self, timeout: Duration) -> Result<()> {
+        self.ctx
+            .send(self.worker_main_addr.clone(), PunchMessage::WaitForHoleOpen)
+            .await?;
+        self.ctx
+            .receive_extended::<()>(MessageReceiveOptions::new().with_timeout(timeout))
+            .await?;
+        Ok(())
+    }
+
+    /// Stop this Hole Puncher's worker
+    pub async fn stop(&self) -> Result<()> {
+        self.ctx.stop_worker(self.worker_main_addr.clone()).await
+    }
+
     /// Address of this UDP NAT Hole Puncher's worker.
     pub fn address(&self) -> Address {
         self.worker_local_addr.clone()
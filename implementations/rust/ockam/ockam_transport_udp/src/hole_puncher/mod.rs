@@ -1,7 +1,9 @@
 pub use error::PunchError;
 pub use handle::UdpHolePuncher;
+pub use peer_route::{PeerRoute, DEFAULT_HOLE_OPEN_TIMEOUT};
 
 mod error;
 mod handle;
 mod message;
+mod peer_route;
 mod worker;
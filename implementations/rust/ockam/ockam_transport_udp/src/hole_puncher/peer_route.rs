@@ -0,0 +1,77 @@
+use crate::hole_puncher::UdpHolePuncher;
+use core::time::Duration;
+use ockam_core::{route, Result, Route};
+use ockam_node::Context;
+use tracing::{debug, info};
+
+/// Default time to wait for a hole to be opened before falling back
+/// to the relayed route
+pub const DEFAULT_HOLE_OPEN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Route to a peer node, either through a direct UDP NAT hole or through
+/// a relayed route, e.g. a TCP connection to a relay
+///
+/// The route is not encrypted by itself: a secure channel must be created
+/// on top of it, so that messages are encrypted end-to-end whichever path
+/// is used.
+pub enum PeerRoute {
+    /// A hole was opened to the peer. Messages are sent directly over UDP
+    Direct(UdpHolePuncher),
+    /// The hole could not be opened. Messages are sent over the fallback route
+    Relayed(Route),
+}
+
+impl PeerRoute {
+    /// Route to use to reach the peer
+    pub fn route(&self) -> Route {
+        match self {
+            PeerRoute::Direct(puncher) => route![puncher.address()],
+            PeerRoute::Relayed(route) => route.clone(),
+        }
+    }
+
+    /// Return true if the peer is reached over a direct UDP path
+    pub fn is_direct(&self) -> bool {
+        matches!(self, PeerRoute::Direct(_))
+    }
+}
+
+impl UdpHolePuncher {
+    /// Try to open a hole to the peer puncher with the given Rendezvous service
+    /// and return a [`PeerRoute`].
+    ///
+    /// If the Rendezvous service can't be reached, or if the hole is not open
+    /// after `timeout`, the puncher is stopped and the `fallback_route` is returned
+    /// instead.
+    pub async fn create_or_fallback<S: AsRef<str>, R: Into<Route>>(
+        ctx: &mut Context,
+        puncher_name: S,
+        peer_puncher_name: S,
+        rendezvous_route: R,
+        timeout: Duration,
+        fallback_route: Route,
+    ) -> Result<PeerRoute> {
+        let mut puncher =
+            match UdpHolePuncher::create(ctx, puncher_name, peer_puncher_name, rendezvous_route)
+                .await
+            {
+                Ok(puncher) => puncher,
+                Err(e) => {
+                    info!("Cannot start a UDP hole puncher ({e}). Using the relayed route");
+                    return Ok(PeerRoute::Relayed(fallback_route));
+                }
+            };
+
+        match puncher.wait_for_hole_open_timeout(timeout).await {
+            Ok(()) => {
+                debug!("UDP hole opened at {}", puncher.address());
+                Ok(PeerRoute::Direct(puncher))
+            }
+            Err(e) => {
+                info!("UDP hole could not be opened ({e}). Using the relayed route");
+                puncher.stop().await?;
+                Ok(PeerRoute::Relayed(fallback_route))
+            }
+        }
+    }
+}
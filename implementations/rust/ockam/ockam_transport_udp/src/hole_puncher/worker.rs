@@ -357,10 +357,12 @@ impl Worker for UdpHolePunchWorker {
                     let inner_msg = PunchMessage::decode(msg.payload())?;
                     match inner_msg {
                         PunchMessage::WaitForHoleOpen => {
-                            self.wait_for_hole_open_addr = Some(sender_addr)
-                            // TODO: If the hole is already open, the handle
-                            // will not be informed until it's closed and
-                            // opened again. Is this what we want?
+                            self.wait_for_hole_open_addr = Some(sender_addr);
+                            // If the hole is already open, inform the handle now
+                            // instead of waiting for it to be closed and opened again
+                            if self.hole_open {
+                                self.set_hole_open(ctx).await?;
+                            }
                         }
                         _ => return Err(PunchError::Internal.into()),
                     }
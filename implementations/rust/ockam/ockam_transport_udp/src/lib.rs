@@ -9,7 +9,7 @@
 // with command `cargo run --example client`
 use ockam_core::TransportType;
 
-pub use hole_puncher::{PunchError, UdpHolePuncher};
+pub use hole_puncher::{PeerRoute, PunchError, UdpHolePuncher, DEFAULT_HOLE_OPEN_TIMEOUT};
 pub use rendezvous_service::UdpRendezvousService;
 pub use transport::UdpTransport;
 pub use transport::UdpTransportExtension;
@@ -1,7 +1,7 @@
 use ockam_core::compat::rand::{self, Rng};
 use ockam_core::{route, Address, AllowAll, Result, Routed, Worker};
 use ockam_node::{Context, MessageReceiveOptions, MessageSendReceiveOptions};
-use ockam_transport_udp::{UdpTransport, UDP};
+use ockam_transport_udp::{UdpHolePuncher, UdpRendezvousService, UdpTransport, UDP};
 use std::net::SocketAddr;
 use std::time::Duration;
 use tracing::{debug, error, trace};
@@ -192,6 +192,35 @@ async fn send_receive(ctx: &mut Context) -> Result<()> {
     Ok(())
 }
 
+/// A hole puncher should fall back to the relayed route when the peer
+/// puncher never shows up
+#[ockam_macros::test]
+async fn hole_puncher_falls_back_to_relayed_route(ctx: &mut Context) -> Result<()> {
+    let bind_addr = *utils::available_local_ports(1).await?.first().unwrap();
+
+    let transport = UdpTransport::create(ctx).await?;
+    transport.listen(bind_addr.to_string()).await?;
+    UdpRendezvousService::start(ctx, "rendezvous").await?;
+
+    let rendezvous_route = route![(UDP, bind_addr.to_string()), "rendezvous"];
+    let fallback_route = route!["relay", "peer"];
+    let peer_route = UdpHolePuncher::create_or_fallback(
+        ctx,
+        "alice",
+        "bob",
+        rendezvous_route,
+        Duration::from_secs(2),
+        fallback_route.clone(),
+    )
+    .await?;
+
+    assert!(!peer_route.is_direct());
+    assert_eq!(peer_route.route(), fallback_route);
+
+    ctx.stop().await?;
+    Ok(())
+}
+
 pub struct Echoer {
     prev_src_addr: Option<String>,
 }
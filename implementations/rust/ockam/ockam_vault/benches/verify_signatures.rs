@@ -0,0 +1,83 @@
+//! Compare the throughput of signature verifications made inline on the runtime
+//! threads with verifications offloaded to a pool of threads.
+//!
+//! Run with `cargo bench -p ockam_vault --bench verify_signatures`
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use ockam_core::Result;
+use ockam_vault::{
+    OffloadedVaultForVerifyingSignatures, SigningKeyType, SoftwareVaultForSigning,
+    SoftwareVaultForVerifyingSignatures, VaultForSigning, VaultForVerifyingSignatures,
+    VerifyingPoolOptions,
+};
+
+const VERIFICATIONS: usize = 2_000;
+const RUNTIME_THREADS: usize = 2;
+
+fn main() -> Result<()> {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(RUNTIME_THREADS)
+        .enable_all()
+        .build()
+        .unwrap();
+
+    runtime.block_on(async {
+        let inline = SoftwareVaultForVerifyingSignatures::create();
+        let elapsed = bench(inline).await?;
+        report("inline", elapsed);
+
+        let offloaded =
+            OffloadedVaultForVerifyingSignatures::create(VerifyingPoolOptions::default())?;
+        let parallelism = offloaded.options().parallelism();
+        let elapsed = bench(offloaded).await?;
+        report(&format!("offloaded ({parallelism} threads)"), elapsed);
+        Ok(())
+    })
+}
+
+/// Verify signatures concurrently while a task measures how long the runtime
+/// takes to schedule it, as a worker waiting for its next message would
+async fn bench(vault: Arc<dyn VaultForVerifyingSignatures>) -> Result<(Duration, Duration)> {
+    let signing_vault = SoftwareVaultForSigning::create();
+    let handle = signing_vault
+        .generate_signing_secret_key(SigningKeyType::EdDSACurve25519)
+        .await?;
+    let public_key = signing_vault.get_verifying_public_key(&handle).await?;
+    let signature = signing_vault.sign(&handle, b"data").await?;
+
+    let probe = tokio::spawn(async {
+        let mut max_delay = Duration::ZERO;
+        for _ in 0..100 {
+            let start = Instant::now();
+            tokio::time::sleep(Duration::from_millis(1)).await;
+            max_delay = max_delay.max(start.elapsed().saturating_sub(Duration::from_millis(1)));
+        }
+        max_delay
+    });
+
+    let start = Instant::now();
+    let mut verifications = Vec::with_capacity(VERIFICATIONS);
+    for _ in 0..VERIFICATIONS {
+        let (vault, public_key, signature) = (vault.clone(), public_key.clone(), signature.clone());
+        verifications.push(tokio::spawn(async move {
+            vault
+                .verify_signature(&public_key, b"data", &signature)
+                .await
+        }));
+    }
+    for verification in verifications {
+        assert!(verification.await.unwrap()?);
+    }
+    let elapsed = start.elapsed();
+
+    Ok((elapsed, probe.await.unwrap()))
+}
+
+fn report(name: &str, (elapsed, max_delay): (Duration, Duration)) {
+    println!(
+        "{name}: {:.0} verifications/s, max scheduling delay {max_delay:?}",
+        VERIFICATIONS as f64 / elapsed.as_secs_f64()
+    );
+}
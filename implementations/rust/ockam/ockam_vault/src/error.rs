@@ -33,6 +33,14 @@ pub enum VaultError {
     InvalidSha256Len,
     /// Invalid Signature Size
     InvalidSignatureSize,
+    /// The threshold of a key escrow is 0 or greater than the number of recovery keys
+    InvalidEscrowThreshold,
+    /// The shares of an escrowed key are invalid or don't reconstruct the key
+    InvalidEscrowShare,
+    /// Fewer shares than the threshold were provided to recover a key
+    NotEnoughEscrowShares,
+    /// No share of an escrowed key is encrypted to a recovery key
+    UnknownRecoveryKey,
 }
 
 impl ockam_core::compat::error::Error for VaultError {}
@@ -54,6 +62,13 @@ impl core::fmt::Display for VaultError {
             Self::KeyNotFound => write!(f, "key not found"),
             Self::InvalidSha256Len => write!(f, "invalid sha256 len"),
             Self::InvalidSignatureSize => write!(f, "invalid signature len"),
+            Self::InvalidEscrowThreshold => write!(
+                f,
+                "the escrow threshold must be between 2 and the number of recovery keys"
+            ),
+            Self::InvalidEscrowShare => write!(f, "invalid escrow shares"),
+            Self::NotEnoughEscrowShares => write!(f, "not enough escrow shares to recover the key"),
+            Self::UnknownRecoveryKey => write!(f, "no escrow share for this recovery key"),
         }
     }
 }
@@ -64,7 +79,7 @@ impl From<VaultError> for Error {
         use VaultError::*;
         let kind = match err {
             InvalidPublicKey | InvalidKeyType | InvalidHkdfOutputType => Kind::Misuse,
-            UnknownEcdhKeyType => Kind::NotFound,
+            UnknownEcdhKeyType | UnknownRecoveryKey => Kind::NotFound,
             _ => Kind::Invalid,
         };
 
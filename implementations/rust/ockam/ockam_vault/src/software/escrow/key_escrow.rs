@@ -0,0 +1,483 @@
+use aes_gcm::aead::{Aead, NewAead, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use ockam_core::compat::rand::thread_rng;
+use ockam_core::compat::sync::Arc;
+use ockam_core::compat::vec::Vec;
+use ockam_core::Result;
+use ockam_node::{InMemoryKeyValueStorage, KeyValueStorage};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use zeroize::Zeroize;
+
+use crate::legacy::{KeyId, Secret, SecretAttributes, StoredSecret};
+use crate::{SecretShare, VaultError, X25519PublicKey, X25519SecretKey, X25519_PUBLIC_KEY_LENGTH};
+
+use super::shamir;
+
+/// Context of the derivation of the keys encrypting the shares
+const KEY_ESCROW_INFO: &[u8] = b"ockam_key_escrow";
+
+/// Nonce of the encryption of the shares, each share being encrypted with a different key
+const SHARE_NONCE: [u8; 12] = [0u8; 12];
+
+/// Minimum number of shares needed to recover a key, so that no single holder of a
+/// recovery key can recover it
+pub const MIN_ESCROW_THRESHOLD: u8 = 2;
+
+/// Storage for the escrowed keys of a vault
+pub type KeyEscrowStorage = Arc<dyn KeyValueStorage<KeyId, EscrowedSecret>>;
+
+/// Escrow of the signing keys and of the static X25519 keys of a vault.
+///
+/// Each key is split into one share per recovery key, any `threshold` shares
+/// reconstructing the key. Each share is encrypted to its recovery key, so that the
+/// key can only be recovered when `threshold` holders of a recovery key decrypt their share
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyEscrowPolicy {
+    threshold: u8,
+    recovery_keys: Vec<X25519PublicKey>,
+}
+
+impl KeyEscrowPolicy {
+    /// Create a policy where `threshold` of the recovery keys are needed to recover a key.
+    /// The threshold must be at least [`MIN_ESCROW_THRESHOLD`]
+    pub fn new(threshold: u8, recovery_keys: Vec<X25519PublicKey>) -> Result<Self> {
+        if threshold < MIN_ESCROW_THRESHOLD
+            || threshold as usize > recovery_keys.len()
+            || recovery_keys.len() > u8::MAX as usize
+        {
+            return Err(VaultError::InvalidEscrowThreshold.into());
+        }
+        Ok(Self {
+            threshold,
+            recovery_keys,
+        })
+    }
+
+    /// Create a policy where a majority of the recovery keys are needed to recover a key
+    pub fn with_majority(recovery_keys: Vec<X25519PublicKey>) -> Result<Self> {
+        let threshold = u8::try_from(recovery_keys.len() / 2 + 1)
+            .map_err(|_| VaultError::InvalidEscrowThreshold)?;
+        Self::new(threshold, recovery_keys)
+    }
+
+    /// Number of shares needed to recover a key
+    pub fn threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    /// Public keys the shares are encrypted to
+    pub fn recovery_keys(&self) -> &[X25519PublicKey] {
+        &self.recovery_keys
+    }
+
+    /// Split a key into shares encrypted to the recovery keys
+    pub fn escrow(&self, key_id: &KeyId, stored: &StoredSecret) -> Result<EscrowedSecret> {
+        let shares = shamir::split(
+            stored.secret().as_ref(),
+            self.threshold,
+            self.recovery_keys.len() as u8,
+        )?;
+        let shares = shares
+            .iter()
+            .zip(self.recovery_keys.iter())
+            .map(|(share, recovery_key)| EncryptedShare::encrypt(key_id, share, recovery_key))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(EscrowedSecret {
+            key_id: key_id.clone(),
+            attributes: stored.attributes(),
+            threshold: self.threshold,
+            shares,
+        })
+    }
+}
+
+/// Generate a recovery key pair. The secret key is meant to be kept offline by its holder
+pub fn generate_recovery_key() -> (X25519SecretKey, X25519PublicKey) {
+    let secret = x25519_dalek::StaticSecret::random_from_rng(thread_rng());
+    let public = x25519_dalek::PublicKey::from(&secret);
+    (
+        X25519SecretKey::new(secret.to_bytes()),
+        X25519PublicKey(public.to_bytes()),
+    )
+}
+
+/// A signing key split into shares, each share being encrypted to a recovery key
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EscrowedSecret {
+    key_id: KeyId,
+    attributes: SecretAttributes,
+    threshold: u8,
+    shares: Vec<EncryptedShare>,
+}
+
+impl EscrowedSecret {
+    /// Identifier of the key in its vault
+    pub fn key_id(&self) -> &KeyId {
+        &self.key_id
+    }
+
+    /// Number of shares needed to recover the key
+    pub fn threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    /// Attributes of the key, giving its type
+    pub fn attributes(&self) -> SecretAttributes {
+        self.attributes
+    }
+
+    /// Return true if the key was escrowed with the threshold and the recovery keys of a policy
+    pub fn is_escrowed_with(&self, policy: &KeyEscrowPolicy) -> bool {
+        self.threshold == policy.threshold
+            && self.shares.len() == policy.recovery_keys.len()
+            && policy
+                .recovery_keys
+                .iter()
+                .all(|key| self.shares.iter().any(|share| share.recovery_key == key.0))
+    }
+
+    /// Decrypt the share encrypted to a recovery key.
+    ///
+    /// This is done by the holder of the recovery key, who then sends the share
+    /// to the party recovering the key
+    pub fn decrypt_share(&self, recovery_secret_key: &X25519SecretKey) -> Result<SecretShare> {
+        let secret = x25519_dalek::StaticSecret::from(*recovery_secret_key.key());
+        let recovery_key = x25519_dalek::PublicKey::from(&secret).to_bytes();
+        let share = self
+            .shares
+            .iter()
+            .find(|share| share.recovery_key == recovery_key)
+            .ok_or(VaultError::UnknownRecoveryKey)?;
+        share.decrypt(&self.key_id, &secret)
+    }
+
+    /// Reconstruct the key from at least `threshold` decrypted shares
+    pub fn recover(&self, shares: &[SecretShare]) -> Result<StoredSecret> {
+        if shares.len() < self.threshold as usize {
+            return Err(VaultError::NotEnoughEscrowShares.into());
+        }
+        let secret = shamir::combine(shares)?;
+        StoredSecret::create(Secret::new(secret), self.attributes)
+    }
+}
+
+/// A share of a key, encrypted with a key agreed between an ephemeral key and a recovery key
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct EncryptedShare {
+    recovery_key: [u8; X25519_PUBLIC_KEY_LENGTH],
+    ephemeral_key: [u8; X25519_PUBLIC_KEY_LENGTH],
+    ciphertext: Vec<u8>,
+}
+
+impl EncryptedShare {
+    fn encrypt(
+        key_id: &KeyId,
+        share: &SecretShare,
+        recovery_key: &X25519PublicKey,
+    ) -> Result<Self> {
+        let ephemeral_secret = x25519_dalek::StaticSecret::random_from_rng(thread_rng());
+        let ephemeral_key = x25519_dalek::PublicKey::from(&ephemeral_secret).to_bytes();
+        let shared_secret =
+            ephemeral_secret.diffie_hellman(&x25519_dalek::PublicKey::from(recovery_key.0));
+
+        let mut plaintext = Vec::with_capacity(1 + share.value().len());
+        plaintext.push(share.index());
+        plaintext.extend_from_slice(share.value());
+        let ciphertext = Self::cipher(shared_secret.as_bytes(), &ephemeral_key, &recovery_key.0)?
+            .encrypt(
+                Nonce::from_slice(&SHARE_NONCE),
+                Payload {
+                    msg: &plaintext,
+                    aad: key_id.as_bytes(),
+                },
+            )
+            .map_err(|_| VaultError::AeadAesGcmEncrypt);
+        plaintext.zeroize();
+
+        Ok(Self {
+            recovery_key: recovery_key.0,
+            ephemeral_key,
+            ciphertext: ciphertext?,
+        })
+    }
+
+    fn decrypt(
+        &self,
+        key_id: &KeyId,
+        recovery_secret: &x25519_dalek::StaticSecret,
+    ) -> Result<SecretShare> {
+        let shared_secret =
+            recovery_secret.diffie_hellman(&x25519_dalek::PublicKey::from(self.ephemeral_key));
+        let mut plaintext = Self::cipher(
+            shared_secret.as_bytes(),
+            &self.ephemeral_key,
+            &self.recovery_key,
+        )?
+        .decrypt(
+            Nonce::from_slice(&SHARE_NONCE),
+            Payload {
+                msg: &self.ciphertext,
+                aad: key_id.as_bytes(),
+            },
+        )
+        .map_err(|_| VaultError::AeadAesGcmDecrypt)?;
+        let share = match plaintext.split_first() {
+            Some((index, value)) if *index != 0 => Ok(SecretShare::new(*index, value.to_vec())),
+            _ => Err(VaultError::InvalidEscrowShare.into()),
+        };
+        plaintext.zeroize();
+        share
+    }
+
+    /// Derive the key encrypting a share. Since a new ephemeral key is used for each share,
+    /// each key only encrypts one message, with a constant nonce
+    fn cipher(
+        shared_secret: &[u8],
+        ephemeral_key: &[u8; X25519_PUBLIC_KEY_LENGTH],
+        recovery_key: &[u8; X25519_PUBLIC_KEY_LENGTH],
+    ) -> Result<Aes256Gcm> {
+        let mut info = Vec::with_capacity(KEY_ESCROW_INFO.len() + 2 * X25519_PUBLIC_KEY_LENGTH);
+        info.extend_from_slice(KEY_ESCROW_INFO);
+        info.extend_from_slice(ephemeral_key);
+        info.extend_from_slice(recovery_key);
+
+        let mut key = [0u8; 32];
+        hkdf::Hkdf::<Sha256>::new(None, shared_secret)
+            .expand(&info, &mut key)
+            .map_err(|_| VaultError::HkdfExpandError)?;
+        let cipher = Aes256Gcm::new(Key::from_slice(&key));
+        key.zeroize();
+        Ok(cipher)
+    }
+}
+
+/// Escrow of the keys of a vault, according to a policy, into a storage
+#[derive(Clone)]
+pub struct KeyEscrow {
+    policy: KeyEscrowPolicy,
+    storage: KeyEscrowStorage,
+}
+
+impl KeyEscrow {
+    /// Constructor
+    pub fn new(policy: KeyEscrowPolicy, storage: KeyEscrowStorage) -> Self {
+        Self { policy, storage }
+    }
+
+    /// Create a key escrow with an in-memory storage
+    pub fn create(policy: KeyEscrowPolicy) -> Self {
+        Self::new(policy, InMemoryKeyValueStorage::create())
+    }
+
+    /// Policy of the escrow
+    pub fn policy(&self) -> &KeyEscrowPolicy {
+        &self.policy
+    }
+
+    /// Escrow a key
+    pub async fn escrow(&self, key_id: &KeyId, secret: &StoredSecret) -> Result<()> {
+        let escrowed = self.policy.escrow(key_id, secret)?;
+        self.storage.put(key_id.clone(), escrowed).await
+    }
+
+    /// Escrow the signing keys and the static X25519 keys of a vault storage which are not
+    /// escrowed yet, or which were escrowed with another policy. Return the number of keys
+    /// escrowed
+    pub async fn escrow_existing_keys(
+        &self,
+        secrets: &dyn KeyValueStorage<KeyId, StoredSecret>,
+    ) -> Result<usize> {
+        let mut escrowed_keys = 0;
+        for key_id in secrets.keys().await? {
+            if let Some(escrowed) = self.storage.get(&key_id).await? {
+                if escrowed.is_escrowed_with(&self.policy) {
+                    continue;
+                }
+            }
+            let secret = match secrets.get(&key_id).await? {
+                Some(secret) => secret,
+                None => continue,
+            };
+            if matches!(
+                secret.attributes(),
+                SecretAttributes::Ed25519 | SecretAttributes::NistP256 | SecretAttributes::X25519
+            ) {
+                self.escrow(&key_id, &secret).await?;
+                escrowed_keys += 1;
+            }
+        }
+        Ok(escrowed_keys)
+    }
+
+    /// Return the escrowed key with a given identifier
+    pub async fn get(&self, key_id: &KeyId) -> Result<Option<EscrowedSecret>> {
+        self.storage.get(key_id).await
+    }
+
+    /// Return all the escrowed keys
+    pub async fn escrowed_secrets(&self) -> Result<Vec<EscrowedSecret>> {
+        let mut escrowed_secrets = Vec::new();
+        for key_id in self.storage.keys().await? {
+            if let Some(escrowed) = self.storage.get(&key_id).await? {
+                escrowed_secrets.push(escrowed);
+            }
+        }
+        Ok(escrowed_secrets)
+    }
+
+    /// Delete an escrowed key
+    pub async fn delete(&self, key_id: &KeyId) -> Result<()> {
+        self.storage.delete(key_id).await.map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        EdDSACurve25519SecretKey, SigningKeyType, SigningSecret, SoftwareVaultForSecureChannels,
+        SoftwareVaultForSigning, VaultForSecureChannels, VaultForSigning,
+    };
+    use ockam_core::compat::vec;
+
+    #[test]
+    fn test_escrow_and_recover() -> Result<()> {
+        let recovery_keys: Vec<_> = (0..3).map(|_| generate_recovery_key()).collect();
+        let policy = KeyEscrowPolicy::new(
+            2,
+            recovery_keys
+                .iter()
+                .map(|(_, public)| public.clone())
+                .collect(),
+        )?;
+        let secret = SigningSecret::EdDSACurve25519(EdDSACurve25519SecretKey::new([7u8; 32]));
+        let escrowed = policy.escrow(&"key".to_string(), &secret.clone().into())?;
+
+        let share_0 = escrowed.decrypt_share(&recovery_keys[0].0)?;
+        let share_2 = escrowed.decrypt_share(&recovery_keys[2].0)?;
+        assert!(escrowed.recover(&[share_0.clone()]).is_err());
+        assert!(SigningSecret::try_from(escrowed.recover(&[share_0, share_2])?)? == secret);
+
+        // a share can only be decrypted by its recovery key
+        let (other, _) = generate_recovery_key();
+        assert!(escrowed.decrypt_share(&other).is_err());
+
+        assert!(KeyEscrowPolicy::new(3, vec![recovery_keys[0].1.clone()]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_escrow_threshold() -> Result<()> {
+        let recovery_keys: Vec<_> = (0..5).map(|_| generate_recovery_key().1).collect();
+
+        // a single holder can't recover a key
+        assert!(KeyEscrowPolicy::new(1, recovery_keys.clone()).is_err());
+        assert!(KeyEscrowPolicy::with_majority(recovery_keys[..1].to_vec()).is_err());
+
+        assert_eq!(
+            KeyEscrowPolicy::with_majority(recovery_keys[..2].to_vec())?.threshold(),
+            2
+        );
+        assert_eq!(
+            KeyEscrowPolicy::with_majority(recovery_keys.clone())?.threshold(),
+            3
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_recover_a_vault_key() -> Result<()> {
+        let recovery_keys: Vec<_> = (0..3).map(|_| generate_recovery_key()).collect();
+        let policy = KeyEscrowPolicy::new(
+            2,
+            recovery_keys
+                .iter()
+                .map(|(_, public)| public.clone())
+                .collect(),
+        )?;
+        let key_escrow = KeyEscrow::create(policy);
+        let vault = SoftwareVaultForSigning::new(InMemoryKeyValueStorage::create())
+            .with_key_escrow(key_escrow.clone());
+        let handle = vault
+            .generate_signing_secret_key(SigningKeyType::EdDSACurve25519)
+            .await?;
+        let public_key = vault.get_verifying_public_key(&handle).await?;
+
+        let escrowed = key_escrow.escrowed_secrets().await?;
+        assert_eq!(escrowed.len(), 1);
+        let escrowed = &escrowed[0];
+
+        // the key is recovered in the vault of a new device
+        let new_vault = SoftwareVaultForSigning::create();
+        let share_1 = escrowed.decrypt_share(&recovery_keys[1].0)?;
+        assert!(new_vault
+            .recover_key(escrowed, &[share_1.clone()])
+            .await
+            .is_err());
+        let share_2 = escrowed.decrypt_share(&recovery_keys[2].0)?;
+        let recovered = new_vault.recover_key(escrowed, &[share_1, share_2]).await?;
+        assert_eq!(recovered, handle);
+        assert_eq!(
+            new_vault.get_verifying_public_key(&recovered).await?,
+            public_key
+        );
+
+        // the escrowed key is deleted with the key
+        vault.delete_signing_secret_key(handle).await?;
+        assert!(key_escrow.escrowed_secrets().await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_escrow_existing_and_secure_channel_keys() -> Result<()> {
+        let recovery_keys: Vec<_> = (0..2).map(|_| generate_recovery_key()).collect();
+        let policy = KeyEscrowPolicy::with_majority(
+            recovery_keys
+                .iter()
+                .map(|(_, public)| public.clone())
+                .collect(),
+        )?;
+        let storage = InMemoryKeyValueStorage::create();
+
+        // keys created before the escrow is configured
+        let signing_vault = SoftwareVaultForSigning::new(storage.clone());
+        signing_vault
+            .generate_signing_secret_key(SigningKeyType::EdDSACurve25519)
+            .await?;
+        let secure_channels_vault = SoftwareVaultForSecureChannels::new(storage.clone());
+        secure_channels_vault
+            .generate_static_x25519_secret_key()
+            .await?;
+
+        let key_escrow = KeyEscrow::create(policy);
+        assert_eq!(key_escrow.escrow_existing_keys(storage.as_ref()).await?, 2);
+        assert_eq!(key_escrow.escrow_existing_keys(storage.as_ref()).await?, 0);
+
+        // the static X25519 keys created afterwards are escrowed too
+        let secure_channels_vault = SoftwareVaultForSecureChannels::new(storage.clone())
+            .with_key_escrow(key_escrow.clone());
+        let handle = secure_channels_vault
+            .generate_static_x25519_secret_key()
+            .await?;
+        let public_key = secure_channels_vault.get_x25519_public_key(&handle).await?;
+        let escrowed = key_escrow
+            .get(&hex::encode(handle.0.value()))
+            .await?
+            .unwrap();
+
+        let shares = [
+            escrowed.decrypt_share(&recovery_keys[0].0)?,
+            escrowed.decrypt_share(&recovery_keys[1].0)?,
+        ];
+        let new_vault = SoftwareVaultForSecureChannels::create();
+        let recovered = new_vault
+            .recover_static_x25519_secret(&escrowed, &shares)
+            .await?;
+        assert_eq!(
+            new_vault.get_x25519_public_key(&recovered).await?,
+            public_key
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,5 @@
+mod key_escrow;
+mod shamir;
+
+pub use key_escrow::*;
+pub use shamir::SecretShare;
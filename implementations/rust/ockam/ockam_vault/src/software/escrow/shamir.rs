@@ -0,0 +1,164 @@
+//! Shamir secret sharing over GF(2^8), splitting each byte of a secret independently.
+//!
+//! Any `threshold` shares reconstruct the secret, fewer shares reveal nothing about it.
+
+use ockam_core::compat::collections::BTreeSet;
+use ockam_core::compat::rand::{thread_rng, RngCore};
+use ockam_core::compat::vec::Vec;
+use ockam_core::Result;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::VaultError;
+
+/// A share of a secret. The index is the point where the polynomials are evaluated, it is never 0
+#[derive(Clone, Debug, PartialEq, Eq, Zeroize, ZeroizeOnDrop)]
+pub struct SecretShare {
+    index: u8,
+    value: Vec<u8>,
+}
+
+impl SecretShare {
+    /// Constructor
+    pub fn new(index: u8, value: Vec<u8>) -> Self {
+        Self { index, value }
+    }
+
+    /// Index of the share
+    pub fn index(&self) -> u8 {
+        self.index
+    }
+
+    /// Value of the share, with the same length as the secret
+    pub fn value(&self) -> &[u8] {
+        &self.value
+    }
+}
+
+/// Split a secret into `shares` shares, any `threshold` of them reconstructing the secret
+pub(crate) fn split(secret: &[u8], threshold: u8, shares: u8) -> Result<Vec<SecretShare>> {
+    if threshold == 0 || threshold > shares {
+        return Err(VaultError::InvalidEscrowThreshold.into());
+    }
+    let mut result: Vec<SecretShare> = (1..=shares)
+        .map(|index| SecretShare::new(index, Vec::with_capacity(secret.len())))
+        .collect();
+    let mut coefficients = ockam_core::compat::vec![0u8; threshold as usize];
+    let mut rng = thread_rng();
+    for byte in secret {
+        coefficients[0] = *byte;
+        rng.fill_bytes(&mut coefficients[1..]);
+        for share in result.iter_mut() {
+            let value = evaluate(&coefficients, share.index);
+            share.value.push(value);
+        }
+    }
+    coefficients.zeroize();
+    Ok(result)
+}
+
+/// Reconstruct a secret from shares, which must have distinct indexes and the same length.
+///
+/// The result is only the original secret if at least `threshold` shares are provided
+pub(crate) fn combine(shares: &[SecretShare]) -> Result<Vec<u8>> {
+    let length = match shares.first() {
+        Some(share) => share.value.len(),
+        None => return Err(VaultError::InvalidEscrowShare.into()),
+    };
+    let indexes: BTreeSet<u8> = shares.iter().map(|s| s.index).collect();
+    if indexes.len() != shares.len()
+        || indexes.contains(&0)
+        || shares.iter().any(|s| s.value.len() != length)
+    {
+        return Err(VaultError::InvalidEscrowShare.into());
+    }
+
+    // Lagrange interpolation at x = 0
+    let mut secret = ockam_core::compat::vec![0u8; length];
+    for share in shares {
+        let mut basis = 1u8;
+        for other in shares.iter().filter(|s| s.index != share.index) {
+            basis = mul(basis, div(other.index, other.index ^ share.index));
+        }
+        for (byte, value) in secret.iter_mut().zip(share.value.iter()) {
+            *byte ^= mul(*value, basis);
+        }
+    }
+    Ok(secret)
+}
+
+/// Evaluate a polynomial at x, with Horner's method
+fn evaluate(coefficients: &[u8], x: u8) -> u8 {
+    coefficients
+        .iter()
+        .rev()
+        .fold(0u8, |acc, coefficient| mul(acc, x) ^ coefficient)
+}
+
+/// Multiplication in GF(2^8) with the AES polynomial x^8 + x^4 + x^3 + x + 1
+fn mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0u8;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        let carry = a & 0x80;
+        a <<= 1;
+        if carry != 0 {
+            a ^= 0x1b;
+        }
+        b >>= 1;
+    }
+    product
+}
+
+/// Division in GF(2^8), b must not be 0
+fn div(a: u8, b: u8) -> u8 {
+    // b^254 is the inverse of b since the multiplicative group has 255 elements
+    let mut inverse = 1u8;
+    let mut power = b;
+    let mut exponent = 254u8;
+    while exponent != 0 {
+        if exponent & 1 != 0 {
+            inverse = mul(inverse, power);
+        }
+        power = mul(power, power);
+        exponent >>= 1;
+    }
+    mul(a, inverse)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_operations() {
+        assert_eq!(mul(0x53, 0xca), 0x01);
+        for a in 1..=255u8 {
+            assert_eq!(mul(div(1, a), a), 1);
+        }
+    }
+
+    #[test]
+    fn test_split_and_combine() -> Result<()> {
+        let secret = b"a secret key of thirty two bytes".to_vec();
+        let shares = split(&secret, 3, 5)?;
+        assert_eq!(shares.len(), 5);
+
+        // any 3 shares reconstruct the secret
+        assert_eq!(combine(&shares[0..3])?, secret);
+        assert_eq!(
+            combine(&[shares[4].clone(), shares[1].clone(), shares[3].clone()])?,
+            secret
+        );
+        assert_eq!(combine(&shares)?, secret);
+
+        // 2 shares don't
+        assert_ne!(combine(&shares[0..2])?, secret);
+
+        // the shares must be distinct
+        assert!(combine(&[shares[0].clone(), shares[0].clone()]).is_err());
+        assert!(split(&secret, 4, 3).is_err());
+        Ok(())
+    }
+}
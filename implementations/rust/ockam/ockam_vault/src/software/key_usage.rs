@@ -0,0 +1,394 @@
+use core::fmt;
+use core::time::Duration;
+use ockam_core::compat::boxed::Box;
+use ockam_core::compat::collections::BTreeMap;
+use ockam_core::compat::sync::{Arc, Mutex};
+use ockam_core::compat::vec::Vec;
+use ockam_core::{async_trait, Result};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::legacy::KeyId;
+
+/// Storage for the usage of the keys of a vault
+pub type KeyUsageStorage = Arc<dyn KeyUsageRepository>;
+
+/// Repository for the usage of the keys of a vault.
+///
+/// The operations are added to the usage of a key instead of replacing it, so that
+/// an implementation can append them to its storage
+#[async_trait]
+pub trait KeyUsageRepository: Send + Sync + 'static {
+    /// Add some operations to the usage of a key and return its updated usage
+    async fn add(&self, key_id: &KeyId, operations: KeyUsage) -> Result<KeyUsage>;
+
+    /// Return the usage of a key
+    async fn get(&self, key_id: &KeyId) -> Result<Option<KeyUsage>>;
+
+    /// Return the usage of all the keys which were used
+    async fn usages(&self) -> Result<Vec<(KeyId, KeyUsage)>>;
+
+    /// Forget the usage of a key
+    async fn delete(&self, key_id: &KeyId) -> Result<()>;
+}
+
+/// Storage for the usage of the keys of a vault, kept in memory
+#[derive(Default)]
+pub struct InMemoryKeyUsageStorage {
+    usages: Mutex<BTreeMap<KeyId, KeyUsage>>,
+}
+
+impl InMemoryKeyUsageStorage {
+    /// Create a new in-memory storage
+    pub fn create() -> KeyUsageStorage {
+        Arc::new(Self::default())
+    }
+}
+
+#[async_trait]
+impl KeyUsageRepository for InMemoryKeyUsageStorage {
+    async fn add(&self, key_id: &KeyId, operations: KeyUsage) -> Result<KeyUsage> {
+        let mut usages = self.usages.lock().unwrap();
+        let usage = usages.entry(key_id.clone()).or_default();
+        usage.add(&operations);
+        Ok(usage.clone())
+    }
+
+    async fn get(&self, key_id: &KeyId) -> Result<Option<KeyUsage>> {
+        Ok(self.usages.lock().unwrap().get(key_id).cloned())
+    }
+
+    async fn usages(&self) -> Result<Vec<(KeyId, KeyUsage)>> {
+        let usages = self.usages.lock().unwrap();
+        Ok(usages.iter().map(|(k, u)| (k.clone(), u.clone())).collect())
+    }
+
+    async fn delete(&self, key_id: &KeyId) -> Result<()> {
+        self.usages.lock().unwrap().remove(key_id);
+        Ok(())
+    }
+}
+
+/// Number of operations performed with a secret key
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyUsage {
+    /// Number of signatures
+    pub signs: u64,
+    /// Number of Diffie-Hellman operations
+    pub dh_operations: u64,
+    /// Time of the last operation, in seconds since the Unix epoch
+    pub last_used_at: Option<u64>,
+}
+
+impl KeyUsage {
+    /// Add the operations of another usage to this one
+    pub fn add(&mut self, other: &KeyUsage) {
+        self.signs += other.signs;
+        self.dh_operations += other.dh_operations;
+        self.last_used_at = self.last_used_at.max(other.last_used_at);
+    }
+}
+
+/// Alert raised when a key is used more than a threshold within a time window
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyUsageAlert {
+    /// Key used too often
+    pub key_id: KeyId,
+    /// Number of operations performed with the key in the current window
+    pub operations: u64,
+    /// Threshold which was exceeded
+    pub threshold: KeyUsageThreshold,
+}
+
+impl fmt::Display for KeyUsageAlert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the key {} was used {} times in less than {} seconds",
+            self.key_id,
+            self.operations,
+            self.threshold.window.as_secs()
+        )
+    }
+}
+
+/// Maximum number of operations performed with a key within a time window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyUsageThreshold {
+    /// Maximum number of operations
+    pub max_operations: u64,
+    /// Duration of the window, rounded to seconds
+    pub window: Duration,
+}
+
+impl KeyUsageThreshold {
+    /// Create a new threshold
+    pub fn new(max_operations: u64, window: Duration) -> Self {
+        Self {
+            max_operations,
+            window,
+        }
+    }
+}
+
+/// Handler of the alerts raised by a [`KeyUsageTracker`], for example to notify
+/// the subscribers to the events of a node
+pub trait KeyUsageAlertHandler: Send + Sync + 'static {
+    /// Handle an alert. This is called once per key and time window, while the
+    /// operation using the key is running, so it must not block
+    fn handle_alert(&self, alert: &KeyUsageAlert);
+}
+
+/// Default alert handler, logging the alerts
+struct LogKeyUsageAlert;
+
+impl KeyUsageAlertHandler for LogKeyUsageAlert {
+    fn handle_alert(&self, alert: &KeyUsageAlert) {
+        warn!("key usage anomaly: {alert}");
+    }
+}
+
+/// Operations performed with a key in the current window of its threshold
+#[derive(Debug, Clone, Copy)]
+struct UsageWindow {
+    started_at: u64,
+    operations: u64,
+}
+
+/// Count the operations performed with the secret keys of a vault.
+///
+/// The counters are persisted in a [`KeyUsageStorage`]. An optional threshold
+/// raises an alert when a key is suddenly used much more than usual, for example
+/// when a leaked key is used to sign on behalf of an identity.
+///
+/// Recording the usage of a key never fails the operation made with the key: storage
+/// errors are only logged
+#[derive(Clone)]
+pub struct KeyUsageTracker {
+    storage: KeyUsageStorage,
+    threshold: Arc<Mutex<Option<KeyUsageThreshold>>>,
+    alert_handler: Arc<Mutex<Arc<dyn KeyUsageAlertHandler>>>,
+    windows: Arc<Mutex<BTreeMap<KeyId, UsageWindow>>>,
+}
+
+impl KeyUsageTracker {
+    /// Create a tracker persisting the usage of the keys in a given storage
+    pub fn new(storage: KeyUsageStorage) -> Self {
+        Self {
+            storage,
+            threshold: Default::default(),
+            alert_handler: Arc::new(Mutex::new(Arc::new(LogKeyUsageAlert))),
+            windows: Default::default(),
+        }
+    }
+
+    /// Create a tracker with an [`InMemoryKeyUsageStorage`]
+    pub fn create() -> Self {
+        Self::new(InMemoryKeyUsageStorage::create())
+    }
+
+    /// Raise an alert when a key is used more than a threshold
+    pub fn with_threshold(self, threshold: KeyUsageThreshold) -> Self {
+        self.set_threshold(Some(threshold));
+        self
+    }
+
+    /// Handle the alerts with a custom handler instead of logging them
+    pub fn with_alert_handler(self, alert_handler: Arc<dyn KeyUsageAlertHandler>) -> Self {
+        self.set_alert_handler(alert_handler);
+        self
+    }
+
+    /// Change the handler of the alerts. The change applies to all the clones of this tracker
+    pub fn set_alert_handler(&self, alert_handler: Arc<dyn KeyUsageAlertHandler>) {
+        *self.alert_handler.lock().unwrap() = alert_handler;
+    }
+
+    /// Change the alert threshold. `None` disables the alerts
+    pub fn set_threshold(&self, threshold: Option<KeyUsageThreshold>) {
+        *self.threshold.lock().unwrap() = threshold;
+        self.windows.lock().unwrap().clear();
+    }
+
+    /// Alert threshold
+    pub fn threshold(&self) -> Option<KeyUsageThreshold> {
+        *self.threshold.lock().unwrap()
+    }
+
+    /// Record a signature made with a key
+    pub async fn record_sign(&self, key_id: &KeyId) {
+        self.record(
+            key_id,
+            KeyUsage {
+                signs: 1,
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
+    /// Record a Diffie-Hellman operation made with a key
+    pub async fn record_dh(&self, key_id: &KeyId) {
+        self.record(
+            key_id,
+            KeyUsage {
+                dh_operations: 1,
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
+    /// Return the usage of a key
+    pub async fn usage(&self, key_id: &KeyId) -> Result<Option<KeyUsage>> {
+        self.storage.get(key_id).await
+    }
+
+    /// Return the usage of all the keys which were used
+    pub async fn usages(&self) -> Result<Vec<(KeyId, KeyUsage)>> {
+        self.storage.usages().await
+    }
+
+    /// Forget the usage of a deleted key
+    pub async fn delete(&self, key_id: &KeyId) -> Result<()> {
+        self.windows.lock().unwrap().remove(key_id);
+        self.storage.delete(key_id).await
+    }
+
+    async fn record(&self, key_id: &KeyId, mut operations: KeyUsage) {
+        let now = now();
+        operations.last_used_at = now;
+        if let Err(e) = self.storage.add(key_id, operations).await {
+            warn!("cannot record the usage of the key {key_id}: {e}");
+        }
+
+        if let Some(now) = now {
+            if let Some(alert) = self.check_threshold(key_id, now) {
+                let alert_handler = self.alert_handler.lock().unwrap().clone();
+                alert_handler.handle_alert(&alert);
+            }
+        }
+    }
+
+    /// Count an operation in the current window of the key and return an alert
+    /// when the threshold is exceeded for the first time in that window
+    fn check_threshold(&self, key_id: &KeyId, now: u64) -> Option<KeyUsageAlert> {
+        let threshold = self.threshold()?;
+        let mut windows = self.windows.lock().unwrap();
+        let window = windows.entry(key_id.clone()).or_insert(UsageWindow {
+            started_at: now,
+            operations: 0,
+        });
+        if now.saturating_sub(window.started_at) >= threshold.window.as_secs() {
+            *window = UsageWindow {
+                started_at: now,
+                operations: 0,
+            };
+        }
+        window.operations += 1;
+        if window.operations == threshold.max_operations + 1 {
+            Some(KeyUsageAlert {
+                key_id: key_id.clone(),
+                operations: window.operations,
+                threshold,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+fn now() -> Option<u64> {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()
+        .map(|now| now.as_secs())
+}
+
+#[cfg(not(feature = "std"))]
+fn now() -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct CountAlerts(Mutex<Vec<KeyUsageAlert>>);
+
+    impl KeyUsageAlertHandler for CountAlerts {
+        fn handle_alert(&self, alert: &KeyUsageAlert) {
+            self.0.lock().unwrap().push(alert.clone());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_key_usage() -> Result<()> {
+        let alerts = Arc::new(CountAlerts(Mutex::new(vec![])));
+        let tracker = KeyUsageTracker::create()
+            .with_threshold(KeyUsageThreshold::new(2, Duration::from_secs(3600)))
+            .with_alert_handler(alerts.clone());
+        let key_id: KeyId = "key".into();
+
+        tracker.record_sign(&key_id).await;
+        tracker.record_sign(&key_id).await;
+        tracker.record_dh(&key_id).await;
+        tracker.record_sign(&key_id).await;
+
+        let usage = tracker.usage(&key_id).await?.unwrap();
+        assert_eq!(usage.signs, 3);
+        assert_eq!(usage.dh_operations, 1);
+        assert!(usage.last_used_at.is_some());
+
+        // a single alert is raised per window
+        let alerts = alerts.0.lock().unwrap().clone();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].key_id, key_id);
+        assert_eq!(alerts[0].operations, 3);
+
+        tracker.delete(&key_id).await?;
+        assert!(tracker.usages().await?.is_empty());
+        Ok(())
+    }
+
+    struct FailingStorage;
+
+    #[async_trait]
+    impl KeyUsageRepository for FailingStorage {
+        async fn add(&self, _key_id: &KeyId, _operations: KeyUsage) -> Result<KeyUsage> {
+            Err(ockam_core::Error::new(
+                ockam_core::errcode::Origin::Vault,
+                ockam_core::errcode::Kind::Io,
+                "disk full",
+            ))
+        }
+
+        async fn get(&self, _key_id: &KeyId) -> Result<Option<KeyUsage>> {
+            Ok(None)
+        }
+
+        async fn usages(&self) -> Result<Vec<(KeyId, KeyUsage)>> {
+            Ok(vec![])
+        }
+
+        async fn delete(&self, _key_id: &KeyId) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_storage_errors_do_not_stop_the_alerts() {
+        let alerts = Arc::new(CountAlerts(Mutex::new(vec![])));
+        let tracker = KeyUsageTracker::new(Arc::new(FailingStorage))
+            .with_threshold(KeyUsageThreshold::new(1, Duration::from_secs(3600)));
+        // the handler can be changed on a clone of the tracker used by a vault
+        tracker.clone().set_alert_handler(alerts.clone());
+        let key_id: KeyId = "key".into();
+
+        tracker.record_sign(&key_id).await;
+        tracker.record_sign(&key_id).await;
+
+        assert_eq!(alerts.0.lock().unwrap().len(), 1);
+    }
+}
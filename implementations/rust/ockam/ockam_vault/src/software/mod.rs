@@ -1,7 +1,15 @@
+mod escrow;
+mod key_usage;
+#[cfg(feature = "std")]
+mod offloaded_vault_for_verifying_signatures;
 mod vault_for_secure_channels;
 mod vault_for_signing;
 mod vault_for_verifying_signatures;
 
+pub use escrow::*;
+pub use key_usage::*;
+#[cfg(feature = "std")]
+pub use offloaded_vault_for_verifying_signatures::*;
 pub use vault_for_secure_channels::*;
 pub use vault_for_signing::*;
 pub use vault_for_verifying_signatures::*;
@@ -0,0 +1,197 @@
+use crate::{
+    Sha256Output, Signature, SoftwareVaultForVerifyingSignatures, VaultForVerifyingSignatures,
+    VerifyingPublicKey,
+};
+
+use ockam_core::compat::boxed::Box;
+use ockam_core::compat::sync::{Arc, Mutex};
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{async_trait, Error, Result};
+use ockam_node::tokio::sync::{mpsc, oneshot};
+
+/// Default number of signature verifications which can wait for a thread of the pool
+pub const DEFAULT_VERIFYING_QUEUE_CAPACITY: usize = 256;
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// Number of threads verifying signatures and capacity of the queue of pending verifications
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerifyingPoolOptions {
+    parallelism: usize,
+    queue_capacity: usize,
+}
+
+impl Default for VerifyingPoolOptions {
+    fn default() -> Self {
+        let parallelism = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
+        Self::new(parallelism, DEFAULT_VERIFYING_QUEUE_CAPACITY)
+    }
+}
+
+impl VerifyingPoolOptions {
+    /// Create pool options. A pool has at least one thread and queues at least one verification
+    pub fn new(parallelism: usize, queue_capacity: usize) -> Self {
+        Self {
+            parallelism: parallelism.max(1),
+            queue_capacity: queue_capacity.max(1),
+        }
+    }
+
+    /// Set the number of threads verifying signatures
+    pub fn with_parallelism(self, parallelism: usize) -> Self {
+        Self::new(parallelism, self.queue_capacity)
+    }
+
+    /// Set the maximum number of verifications waiting for a thread
+    pub fn with_queue_capacity(self, queue_capacity: usize) -> Self {
+        Self::new(self.parallelism, queue_capacity)
+    }
+
+    /// Number of threads verifying signatures
+    pub fn parallelism(&self) -> usize {
+        self.parallelism
+    }
+
+    /// Maximum number of verifications waiting for a thread
+    pub fn queue_capacity(&self) -> usize {
+        self.queue_capacity
+    }
+}
+
+/// [`VaultForVerifyingSignatures`] implementation verifying the signatures on a
+/// dedicated pool of threads.
+///
+/// Verifying a signature is CPU-bound, and a worker verifying signatures inline
+/// blocks the runtime thread it runs on. With this vault the worker only waits for
+/// the result, and the verifications are spread over the threads of the pool.
+/// When the queue of the pool is full, the callers wait for a verification to complete.
+/// The threads are stopped when the vault is dropped
+pub struct OffloadedVaultForVerifyingSignatures {
+    jobs: mpsc::Sender<Job>,
+    options: VerifyingPoolOptions,
+}
+
+impl OffloadedVaultForVerifyingSignatures {
+    /// Start the threads of the pool
+    pub fn new(options: VerifyingPoolOptions) -> Result<Self> {
+        let (jobs, receiver) = mpsc::channel::<Job>(options.queue_capacity());
+        let receiver = Arc::new(Mutex::new(receiver));
+        for i in 0..options.parallelism() {
+            let receiver = receiver.clone();
+            std::thread::Builder::new()
+                .name(format!("ockam-verifying-{i}"))
+                .spawn(move || loop {
+                    // the lock is released before running the job, so that
+                    // another thread can wait for the next job
+                    let job = receiver.lock().unwrap().blocking_recv();
+                    match job {
+                        Some(job) => job(),
+                        None => break,
+                    }
+                })
+                .map_err(|e| Error::new(Origin::Vault, Kind::Io, e))?;
+        }
+        Ok(Self { jobs, options })
+    }
+
+    /// Create a vault verifying the signatures on a pool of threads
+    pub fn create(options: VerifyingPoolOptions) -> Result<Arc<Self>> {
+        Ok(Arc::new(Self::new(options)?))
+    }
+
+    /// Options of the pool
+    pub fn options(&self) -> VerifyingPoolOptions {
+        self.options
+    }
+}
+
+#[async_trait]
+impl VaultForVerifyingSignatures for OffloadedVaultForVerifyingSignatures {
+    async fn sha256(&self, data: &[u8]) -> Result<Sha256Output> {
+        SoftwareVaultForVerifyingSignatures::compute_sha256(data)
+    }
+
+    async fn verify_signature(
+        &self,
+        verifying_public_key: &VerifyingPublicKey,
+        data: &[u8],
+        signature: &Signature,
+    ) -> Result<bool> {
+        let (verifying_public_key, data, signature) = (
+            verifying_public_key.clone(),
+            data.to_vec(),
+            signature.clone(),
+        );
+        let (result_sender, result_receiver) = oneshot::channel();
+        let job: Job = Box::new(move || {
+            let result = SoftwareVaultForVerifyingSignatures::new().verify_signature_sync(
+                &verifying_public_key,
+                &data,
+                &signature,
+            );
+            // the caller might have stopped waiting
+            let _ = result_sender.send(result);
+        });
+
+        self.jobs
+            .send(job)
+            .await
+            .map_err(|_| Self::pool_stopped())?;
+        result_receiver.await.map_err(|_| Self::pool_stopped())?
+    }
+}
+
+impl OffloadedVaultForVerifyingSignatures {
+    fn pool_stopped() -> Error {
+        Error::new(
+            Origin::Vault,
+            Kind::Shutdown,
+            "the signature verification pool is stopped",
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SigningKeyType, SoftwareVaultForSigning, VaultForSigning};
+
+    #[tokio::test]
+    async fn test_offloaded_verification() -> Result<()> {
+        let signing_vault = SoftwareVaultForSigning::create();
+        let vault = OffloadedVaultForVerifyingSignatures::create(VerifyingPoolOptions::new(2, 1))?;
+
+        for key_type in [
+            SigningKeyType::EdDSACurve25519,
+            SigningKeyType::ECDSASHA256CurveP256,
+        ] {
+            let handle = signing_vault.generate_signing_secret_key(key_type).await?;
+            let public_key = signing_vault.get_verifying_public_key(&handle).await?;
+            let signature = signing_vault.sign(&handle, b"data").await?;
+
+            // more concurrent verifications than the pool can run and queue at once
+            let mut verifications = vec![];
+            for _ in 0..8 {
+                let (vault, public_key, signature) =
+                    (vault.clone(), public_key.clone(), signature.clone());
+                verifications.push(tokio::spawn(async move {
+                    vault
+                        .verify_signature(&public_key, b"data", &signature)
+                        .await
+                }));
+            }
+            for verification in verifications {
+                assert!(verification.await.unwrap()?);
+            }
+
+            assert!(
+                !vault
+                    .verify_signature(&public_key, b"other data", &signature)
+                    .await?
+            );
+        }
+        Ok(())
+    }
+}
@@ -1,10 +1,10 @@
 use super::aes::make_aes;
 
 use crate::{
-    AeadSecret, AeadSecretKeyHandle, BufferSecret, HKDFNumberOfOutputs, HandleToSecret, HashOutput,
-    HkdfOutput, SecretBufferHandle, SoftwareVaultForVerifyingSignatures, VaultError,
-    VaultForSecureChannels, X25519PublicKey, X25519SecretKey, X25519SecretKeyHandle,
-    AEAD_SECRET_LENGTH,
+    AeadSecret, AeadSecretKeyHandle, BufferSecret, EscrowedSecret, HKDFNumberOfOutputs,
+    HandleToSecret, HashOutput, HkdfOutput, KeyEscrow, KeyUsageTracker, SecretBufferHandle,
+    SecretShare, SoftwareVaultForVerifyingSignatures, VaultError, VaultForSecureChannels,
+    X25519PublicKey, X25519SecretKey, X25519SecretKeyHandle, AEAD_SECRET_LENGTH,
 };
 
 use ockam_core::compat::collections::BTreeMap;
@@ -24,6 +24,8 @@ pub struct SoftwareVaultForSecureChannels {
     ephemeral_x25519_secrets: Arc<RwLock<BTreeMap<X25519SecretKeyHandle, X25519SecretKey>>>,
     // Use String as a key for backwards compatibility
     static_x25519_secrets: Arc<dyn KeyValueStorage<KeyId, StoredSecret>>,
+    key_usage: Option<KeyUsageTracker>,
+    key_escrow: Option<KeyEscrow>,
 }
 
 impl SoftwareVaultForSecureChannels {
@@ -34,6 +36,24 @@ impl SoftwareVaultForSecureChannels {
             ephemeral_aead_secrets: Default::default(),
             ephemeral_x25519_secrets: Default::default(),
             static_x25519_secrets: storage,
+            key_usage: None,
+            key_escrow: None,
+        }
+    }
+
+    /// Count the Diffie-Hellman operations made with the static X25519 secrets
+    pub fn with_key_usage_tracker(self, key_usage: KeyUsageTracker) -> Self {
+        Self {
+            key_usage: Some(key_usage),
+            ..self
+        }
+    }
+
+    /// Escrow the static X25519 secrets created or imported by this vault
+    pub fn with_key_escrow(self, key_escrow: KeyEscrow) -> Self {
+        Self {
+            key_escrow: Some(key_escrow),
+            ..self
         }
     }
 
@@ -51,14 +71,34 @@ impl SoftwareVaultForSecureChannels {
     ) -> Result<X25519SecretKeyHandle> {
         let public_key = Self::compute_public_key_from_secret(&secret);
         let handle = Self::compute_handle_for_public_key(&public_key);
+        let key_id = hex::encode(handle.0.value());
+        let secret: StoredSecret = secret.into();
 
-        self.static_x25519_secrets
-            .put(hex::encode(handle.0.value()), secret.into())
-            .await?;
+        if let Some(key_escrow) = &self.key_escrow {
+            key_escrow.escrow(&key_id, &secret).await?;
+        }
+        self.static_x25519_secrets.put(key_id, secret).await?;
 
         Ok(handle)
     }
 
+    /// Recover an escrowed static X25519 secret from the shares decrypted by the holders of
+    /// the recovery keys, and import it into this vault
+    pub async fn recover_static_x25519_secret(
+        &self,
+        escrowed_secret: &EscrowedSecret,
+        shares: &[SecretShare],
+    ) -> Result<X25519SecretKeyHandle> {
+        let secret: X25519SecretKey = escrowed_secret.recover(shares)?.try_into()?;
+        let public_key = Self::compute_public_key_from_secret(&secret);
+        let handle = Self::compute_handle_for_public_key(&public_key);
+        // fewer than `threshold` shares, or shares of another key, reconstruct another key
+        if &hex::encode(handle.0.value()) != escrowed_secret.key_id() {
+            return Err(VaultError::InvalidEscrowShare.into());
+        }
+        self.import_static_x25519_secret(secret).await
+    }
+
     /// Return Secret Buffer.
     pub fn get_secret_buffer(&self, handle: &SecretBufferHandle) -> Option<Vec<u8>> {
         self.ephemeral_buffer_secrets
@@ -212,6 +252,20 @@ impl VaultForSecureChannels for SoftwareVaultForSecureChannels {
         let stored_secret = self.get_x25519_secret(secret_key_handle).await?;
         let dh = Self::ecdh_internal(stored_secret, peer_public_key.clone())?;
 
+        // only the static secrets are long-lived enough for their usage to be tracked
+        if let Some(key_usage) = &self.key_usage {
+            let is_ephemeral = self
+                .ephemeral_x25519_secrets
+                .read()
+                .unwrap()
+                .contains_key(secret_key_handle);
+            if !is_ephemeral {
+                key_usage
+                    .record_dh(&hex::encode(secret_key_handle.0.value()))
+                    .await;
+            }
+        }
+
         Ok(self.import_buffer_secret_impl(dh))
     }
 
@@ -304,11 +358,14 @@ impl VaultForSecureChannels for SoftwareVaultForSecureChannels {
         &self,
         secret_key_handle: X25519SecretKeyHandle,
     ) -> Result<bool> {
-        Ok(self
-            .static_x25519_secrets
-            .delete(&hex::encode(secret_key_handle.0.value()))
-            .await?
-            .is_some())
+        let key_id = hex::encode(secret_key_handle.0.value());
+        if let Some(key_usage) = &self.key_usage {
+            key_usage.delete(&key_id).await?;
+        }
+        if let Some(key_escrow) = &self.key_escrow {
+            key_escrow.delete(&key_id).await?;
+        }
+        Ok(self.static_x25519_secrets.delete(&key_id).await?.is_some())
     }
 
     async fn generate_ephemeral_x25519_secret_key(&self) -> Result<X25519SecretKeyHandle> {
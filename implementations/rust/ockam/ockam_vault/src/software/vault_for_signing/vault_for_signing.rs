@@ -1,8 +1,8 @@
 use crate::{
     ECDSASHA256CurveP256PublicKey, ECDSASHA256CurveP256SecretKey, ECDSASHA256CurveP256Signature,
-    EdDSACurve25519PublicKey, EdDSACurve25519SecretKey, EdDSACurve25519Signature, HandleToSecret,
-    Signature, SigningKeyType, SigningSecret, SigningSecretKeyHandle, VaultError, VaultForSigning,
-    VerifyingPublicKey,
+    EdDSACurve25519PublicKey, EdDSACurve25519SecretKey, EdDSACurve25519Signature, EscrowedSecret,
+    HandleToSecret, KeyEscrow, KeyUsageTracker, SecretShare, Signature, SigningKeyType,
+    SigningSecret, SigningSecretKeyHandle, VaultError, VaultForSigning, VerifyingPublicKey,
 };
 use crate::{
     ECDSA_SHA256_CURVEP256_PUBLIC_KEY_LENGTH, ECDSA_SHA256_CURVEP256_SECRET_KEY_LENGTH,
@@ -25,12 +25,34 @@ use sha2::{Digest, Sha256};
 pub struct SoftwareVaultForSigning {
     // Use String as a key for backwards compatibility
     secrets: Arc<dyn KeyValueStorage<KeyId, StoredSecret>>,
+    key_usage: Option<KeyUsageTracker>,
+    key_escrow: Option<KeyEscrow>,
 }
 
 impl SoftwareVaultForSigning {
     /// Constructor
     pub fn new(secrets: Arc<dyn KeyValueStorage<KeyId, StoredSecret>>) -> Self {
-        Self { secrets }
+        Self {
+            secrets,
+            key_usage: None,
+            key_escrow: None,
+        }
+    }
+
+    /// Count the signatures made with the secret keys
+    pub fn with_key_usage_tracker(self, key_usage: KeyUsageTracker) -> Self {
+        Self {
+            key_usage: Some(key_usage),
+            ..self
+        }
+    }
+
+    /// Escrow the keys created or imported by this vault
+    pub fn with_key_escrow(self, key_escrow: KeyEscrow) -> Self {
+        Self {
+            key_escrow: Some(key_escrow),
+            ..self
+        }
     }
 
     /// Create Software implementation Vault with [`InMemoryKeyVaultStorage`]
@@ -42,14 +64,34 @@ impl SoftwareVaultForSigning {
     pub async fn import_key(&self, key: SigningSecret) -> Result<SigningSecretKeyHandle> {
         let public_key = Self::compute_public_key_from_secret(&key)?;
         let handle = Self::compute_handle_for_public_key(&public_key)?;
+        let key_id = Self::key_id(&handle);
+        let key: StoredSecret = key.into();
 
-        self.secrets
-            .put(hex::encode(handle.handle().value()), key.into())
-            .await?;
+        if let Some(key_escrow) = &self.key_escrow {
+            key_escrow.escrow(&key_id, &key).await?;
+        }
+        self.secrets.put(key_id, key).await?;
 
         Ok(handle)
     }
 
+    /// Recover an escrowed key from the shares decrypted by the holders of the recovery keys,
+    /// and import it into this vault
+    pub async fn recover_key(
+        &self,
+        escrowed_secret: &EscrowedSecret,
+        shares: &[SecretShare],
+    ) -> Result<SigningSecretKeyHandle> {
+        let key: SigningSecret = escrowed_secret.recover(shares)?.try_into()?;
+        let public_key = Self::compute_public_key_from_secret(&key)?;
+        let handle = Self::compute_handle_for_public_key(&public_key)?;
+        // fewer than `threshold` shares, or shares of another key, reconstruct another key
+        if &Self::key_id(&handle) != escrowed_secret.key_id() {
+            return Err(VaultError::InvalidEscrowShare.into());
+        }
+        self.import_key(key).await
+    }
+
     /// Return the total number of keys
     pub async fn number_of_keys(&self) -> Result<usize> {
         Ok(self.secrets.keys().await?.len())
@@ -65,6 +107,12 @@ impl VaultForSigning for SoftwareVaultForSigning {
     ) -> Result<Signature> {
         let signing_secret = self.get_stored_secret(signing_secret_key_handle).await?;
 
+        if let Some(key_usage) = &self.key_usage {
+            key_usage
+                .record_sign(&Self::key_id(signing_secret_key_handle))
+                .await;
+        }
+
         match signing_secret {
             SigningSecret::EdDSACurve25519(secret) => {
                 use ed25519_dalek::Signer;
@@ -138,10 +186,14 @@ impl VaultForSigning for SoftwareVaultForSigning {
         &self,
         signing_secret_key_handle: SigningSecretKeyHandle,
     ) -> Result<bool> {
-        self.secrets
-            .delete(&hex::encode(signing_secret_key_handle.handle().value()))
-            .await
-            .map(|r| r.is_some())
+        let key_id = Self::key_id(&signing_secret_key_handle);
+        if let Some(key_usage) = &self.key_usage {
+            key_usage.delete(&key_id).await?;
+        }
+        if let Some(key_escrow) = &self.key_escrow {
+            key_escrow.delete(&key_id).await?;
+        }
+        self.secrets.delete(&key_id).await.map(|r| r.is_some())
     }
 }
 
@@ -215,18 +267,17 @@ impl SoftwareVaultForSigning {
         Ok(handle)
     }
 
+    fn key_id(signing_secret_key_handle: &SigningSecretKeyHandle) -> KeyId {
+        hex::encode(signing_secret_key_handle.handle().value())
+    }
+
     async fn get_stored_secret(
         &self,
         signing_secret_key_handle: &SigningSecretKeyHandle,
     ) -> Result<SigningSecret> {
-        let handle = match signing_secret_key_handle {
-            SigningSecretKeyHandle::EdDSACurve25519(handle) => handle,
-            SigningSecretKeyHandle::ECDSASHA256CurveP256(handle) => handle,
-        };
-
         let stored_secret = self
             .secrets
-            .get(&hex::encode(handle.value()))
+            .get(&Self::key_id(signing_secret_key_handle))
             .await?
             .ok_or(VaultError::KeyNotFound)?;
 
@@ -75,7 +75,7 @@ impl SoftwareVaultForVerifyingSignatures {
 
 impl SoftwareVaultForVerifyingSignatures {
     /// Verify a signature
-    fn verify_signature_sync(
+    pub(crate) fn verify_signature_sync(
         &self,
         verifying_public_key: &VerifyingPublicKey,
         data: &[u8],
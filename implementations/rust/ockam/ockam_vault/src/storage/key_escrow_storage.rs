@@ -0,0 +1,53 @@
+use ockam_core::compat::collections::BTreeMap;
+use ockam_core::compat::sync::Arc;
+use ockam_core::{async_trait, Result};
+use ockam_node::{FileValueStorage, KeyValueStorage, ValueStorage};
+use std::path::Path;
+
+use crate::legacy::KeyId;
+use crate::{EscrowedSecret, KeyEscrowStorage};
+
+/// Storage for the escrowed keys of a vault, backed by a file.
+/// The file only contains encrypted shares, it can be backed up separately from the vault
+pub struct PersistentKeyEscrowStorage {
+    storage: FileValueStorage<BTreeMap<KeyId, EscrowedSecret>>,
+}
+
+impl PersistentKeyEscrowStorage {
+    /// Create a new file storage for the escrowed keys of a vault
+    pub async fn create(path: &Path) -> Result<KeyEscrowStorage> {
+        let storage = FileValueStorage::create(path).await?;
+        Ok(Arc::new(PersistentKeyEscrowStorage { storage }))
+    }
+}
+
+#[async_trait]
+impl KeyValueStorage<KeyId, EscrowedSecret> for PersistentKeyEscrowStorage {
+    async fn put(&self, key_id: KeyId, escrowed: EscrowedSecret) -> Result<()> {
+        let t = move |mut v: BTreeMap<KeyId, EscrowedSecret>| {
+            v.insert(key_id.clone(), escrowed.clone());
+            Ok(v)
+        };
+        self.storage.update_value(t).await
+    }
+
+    async fn get(&self, key_id: &KeyId) -> Result<Option<EscrowedSecret>> {
+        let k = key_id.clone();
+        let t = move |v: BTreeMap<KeyId, EscrowedSecret>| Ok(v.get(&k).cloned());
+        self.storage.read_value(t).await
+    }
+
+    async fn delete(&self, key_id: &KeyId) -> Result<Option<EscrowedSecret>> {
+        let k = key_id.clone();
+        let t = move |mut v: BTreeMap<KeyId, EscrowedSecret>| {
+            let r = v.remove(&k);
+            Ok((v, r))
+        };
+        self.storage.modify_value(t).await
+    }
+
+    async fn keys(&self) -> Result<Vec<KeyId>> {
+        let t = |v: BTreeMap<KeyId, EscrowedSecret>| Ok(v.into_keys().collect());
+        self.storage.read_value(t).await
+    }
+}
@@ -0,0 +1,199 @@
+use ockam_core::compat::collections::BTreeMap;
+use ockam_core::compat::sync::{Arc, Mutex};
+use ockam_core::errcode::{Kind, Origin};
+use ockam_core::{async_trait, Error, Result};
+use ockam_node::tokio::task;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+use crate::legacy::KeyId;
+use crate::{KeyUsage, KeyUsageRepository, KeyUsageStorage};
+
+/// Storage for the usage of the keys of a vault, backed by a file.
+///
+/// Each operation is appended to the file as a JSON line, so that recording the usage of
+/// a key never rewrites the file. When the storage is created, the lines are summed up per
+/// key and the file is atomically replaced with one line per key
+pub struct PersistentKeyUsageStorage {
+    path: PathBuf,
+    usages: Mutex<BTreeMap<KeyId, KeyUsage>>,
+}
+
+/// Line of the file storing the usage of the keys
+#[derive(Serialize, Deserialize)]
+struct KeyUsageLine {
+    key_id: KeyId,
+    #[serde(flatten)]
+    operations: KeyUsage,
+    #[serde(default, skip_serializing_if = "core::ops::Not::not")]
+    deleted: bool,
+}
+
+impl PersistentKeyUsageStorage {
+    /// Create a new file storage for the usage of the keys of a vault
+    pub async fn create(path: &Path) -> Result<KeyUsageStorage> {
+        let path = path.to_path_buf();
+        let (path, usages) = task::spawn_blocking(move || {
+            let usages = Self::compact(&path)?;
+            Ok::<_, Error>((path, usages))
+        })
+        .await
+        .map_err(|e| Error::new(Origin::Vault, Kind::Io, e))??;
+        Ok(Arc::new(PersistentKeyUsageStorage {
+            path,
+            usages: Mutex::new(usages),
+        }))
+    }
+
+    /// Sum up the lines of the file per key and replace the file with the result
+    fn compact(path: &Path) -> Result<BTreeMap<KeyId, KeyUsage>> {
+        let mut usages: BTreeMap<KeyId, KeyUsage> = BTreeMap::new();
+        if path.exists() {
+            let file = File::open(path).map_err(|e| map_io_err(path, e))?;
+            for line in BufReader::new(file).lines() {
+                let line = line.map_err(|e| map_io_err(path, e))?;
+                // the last line can be incomplete if the process stopped while writing it
+                let line: KeyUsageLine = match serde_json::from_str(&line) {
+                    Ok(line) => line,
+                    Err(e) => {
+                        warn!("skipping an invalid line of {path:?}: {e}");
+                        continue;
+                    }
+                };
+                if line.deleted {
+                    usages.remove(&line.key_id);
+                } else {
+                    usages.entry(line.key_id).or_default().add(&line.operations);
+                }
+            }
+        }
+
+        let mut data = vec![];
+        for (key_id, usage) in usages.iter() {
+            data.extend(Self::serialize_line(key_id, usage.clone(), false)?);
+        }
+        let temp_path = path.with_extension("jsonl.tmp");
+        let mut file = Self::open(&temp_path, false)?;
+        file.write_all(&data)
+            .and_then(|_| file.sync_all())
+            .map_err(|e| map_io_err(&temp_path, e))?;
+        std::fs::rename(&temp_path, path).map_err(|e| map_io_err(path, e))?;
+        Ok(usages)
+    }
+
+    /// Append a line to the file. The line is written with a single write
+    /// so that the lines appended by several processes are not interleaved
+    async fn append(&self, key_id: &KeyId, operations: KeyUsage, deleted: bool) -> Result<()> {
+        let data = Self::serialize_line(key_id, operations, deleted)?;
+        let path = self.path.clone();
+        task::spawn_blocking(move || {
+            Self::open(&path, true)?
+                .write_all(&data)
+                .map_err(|e| map_io_err(&path, e))
+        })
+        .await
+        .map_err(|e| Error::new(Origin::Vault, Kind::Io, e))?
+    }
+
+    fn serialize_line(key_id: &KeyId, operations: KeyUsage, deleted: bool) -> Result<Vec<u8>> {
+        let line = KeyUsageLine {
+            key_id: key_id.clone(),
+            operations,
+            deleted,
+        };
+        let mut data = serde_json::to_vec(&line)
+            .map_err(|e| Error::new(Origin::Vault, Kind::Serialization, e))?;
+        data.push(b'\n');
+        Ok(data)
+    }
+
+    fn open(path: &Path, append: bool) -> Result<File> {
+        let mut options = OpenOptions::new();
+        options.create(true);
+        if append {
+            options.append(true);
+        } else {
+            options.write(true).truncate(true);
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        options.open(path).map_err(|e| map_io_err(path, e))
+    }
+}
+
+#[async_trait]
+impl KeyUsageRepository for PersistentKeyUsageStorage {
+    async fn add(&self, key_id: &KeyId, operations: KeyUsage) -> Result<KeyUsage> {
+        let usage = {
+            let mut usages = self.usages.lock().unwrap();
+            let usage = usages.entry(key_id.clone()).or_default();
+            usage.add(&operations);
+            usage.clone()
+        };
+        self.append(key_id, operations, false).await?;
+        Ok(usage)
+    }
+
+    async fn get(&self, key_id: &KeyId) -> Result<Option<KeyUsage>> {
+        Ok(self.usages.lock().unwrap().get(key_id).cloned())
+    }
+
+    async fn usages(&self) -> Result<Vec<(KeyId, KeyUsage)>> {
+        let usages = self.usages.lock().unwrap();
+        Ok(usages.iter().map(|(k, u)| (k.clone(), u.clone())).collect())
+    }
+
+    async fn delete(&self, key_id: &KeyId) -> Result<()> {
+        if self.usages.lock().unwrap().remove(key_id).is_some() {
+            self.append(key_id, KeyUsage::default(), true).await?;
+        }
+        Ok(())
+    }
+}
+
+fn map_io_err(path: &Path, err: std::io::Error) -> Error {
+    Error::new(Origin::Vault, Kind::Io, format!("{err} for path {path:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[tokio::test]
+    async fn test_key_usage_storage() -> Result<()> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("vault.usage.jsonl");
+        let storage = PersistentKeyUsageStorage::create(&path).await?;
+        let sign = KeyUsage {
+            signs: 1,
+            dh_operations: 0,
+            last_used_at: Some(10),
+        };
+        storage.add(&"key1".into(), sign.clone()).await?;
+        storage.add(&"key1".into(), sign.clone()).await?;
+        storage.add(&"key2".into(), sign.clone()).await?;
+        storage.delete(&"key2".into()).await?;
+        // each operation is appended to the file
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 4);
+
+        // a line left incomplete by a crash is skipped
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"key_id\":\"key1\",\"si").unwrap();
+
+        // the usage is restored and the file is compacted when the storage is created again
+        let storage = PersistentKeyUsageStorage::create(&path).await?;
+        let usage = storage.get(&"key1".into()).await?.unwrap();
+        assert_eq!(usage.signs, 2);
+        assert_eq!(usage.last_used_at, Some(10));
+        assert!(storage.get(&"key2".into()).await?.is_none());
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 1);
+        Ok(())
+    }
+}
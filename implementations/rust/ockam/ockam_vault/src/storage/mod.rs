@@ -1,4 +1,12 @@
 /// Storage of secrets to a file
 mod persistent_storage;
 
+/// Storage of the usage of the keys to a file
+mod key_usage_storage;
+
+/// Storage of the escrowed keys to a file
+mod key_escrow_storage;
+
+pub use key_escrow_storage::*;
+pub use key_usage_storage::*;
 pub use persistent_storage::*;
@@ -1,25 +1,44 @@
 use crate::error::Error;
 use aws_config::SdkConfig;
-use aws_sdk_kms::error::SdkError;
+use aws_sdk_kms::error::{ProvideErrorMetadata, SdkError};
 use aws_sdk_kms::operation::schedule_key_deletion::ScheduleKeyDeletionError;
 use aws_sdk_kms::primitives::Blob;
 use aws_sdk_kms::types::{KeySpec, KeyUsageType, MessageType, SigningAlgorithmSpec};
 use aws_sdk_kms::Client;
+use core::future::Future;
+use core::time::Duration;
+use ockam_core::compat::sync::{Arc, RwLock};
 use ockam_core::{async_trait, Result};
 use ockam_vault::{
     ECDSASHA256CurveP256PublicKey, ECDSASHA256CurveP256Signature, HandleToSecret, Signature,
     SigningSecretKeyHandle, VerifyingPublicKey,
 };
 use sha2::{Digest, Sha256};
+use std::time::Instant;
 use tracing as log;
 
+/// Error codes returned by AWS KMS when the credentials signing a request
+/// are expired or no longer valid
+const AUTH_FAILURE_CODES: [&str; 3] = [
+    "ExpiredTokenException",
+    "InvalidSignatureException",
+    "UnrecognizedClientException",
+];
+
 /// AWS KMS client.
 #[derive(Debug, Clone)]
 pub struct AwsKmsClient {
-    client: Client,
+    state: Arc<RwLock<ClientState>>,
     config: AwsKmsConfig,
 }
 
+/// SDK client, replaced when the credentials are refreshed
+#[derive(Debug)]
+struct ClientState {
+    client: Client,
+    refreshed_at: Instant,
+}
+
 /// Defines how to populate the initial keys at vault startup
 #[derive(Debug, Clone)]
 pub enum InitialKeysDiscovery {
@@ -36,12 +55,19 @@ pub struct AwsKmsConfig {
     multi_region: bool,
     sdk_config: SdkConfig,
     initial_keys_discovery: InitialKeysDiscovery,
+    /// Load the SDK configuration from the environment again when refreshing the credentials
+    load_from_env: bool,
+    credentials_refresh_interval: Option<Duration>,
 }
 
 impl AwsKmsConfig {
-    /// Create a new configuration for the AWS KMS
+    /// Create a new configuration for the AWS KMS, loaded from the environment.
+    /// The configuration is loaded again when the credentials are refreshed
     pub async fn default() -> Result<AwsKmsConfig> {
-        Ok(Self::new(aws_config::load_from_env().await))
+        Ok(AwsKmsConfig {
+            load_from_env: true,
+            ..Self::new(aws_config::load_from_env().await)
+        })
     }
 
     /// Create a new configuration for the AWS KMS
@@ -50,6 +76,8 @@ impl AwsKmsConfig {
             multi_region: false,
             sdk_config,
             initial_keys_discovery: InitialKeysDiscovery::ListFromAwsKms,
+            load_from_env: false,
+            credentials_refresh_interval: None,
         }
     }
 
@@ -65,13 +93,80 @@ impl AwsKmsConfig {
             ..self
         }
     }
+
+    /// Refresh the credentials periodically, before they are rejected by AWS KMS.
+    /// Rejected credentials are always refreshed before sending the request again
+    pub fn with_credentials_refresh_interval(self, interval: Duration) -> Self {
+        Self {
+            credentials_refresh_interval: Some(interval),
+            ..self
+        }
+    }
 }
 
 impl AwsKmsClient {
     /// Create a new AWS KMS client.
     pub async fn new(config: AwsKmsConfig) -> Result<AwsKmsClient> {
-        let client = Client::new(&config.sdk_config);
-        Ok(Self { client, config })
+        let state = ClientState {
+            client: Client::new(&config.sdk_config),
+            refreshed_at: Instant::now(),
+        };
+        Ok(Self {
+            state: Arc::new(RwLock::new(state)),
+            config,
+        })
+    }
+
+    /// Fetch new credentials, for example after the credentials of an STS session were rotated.
+    ///
+    /// The SDK client is created again so that its credentials cache is emptied, after
+    /// loading the configuration from the environment again if it was loaded from there
+    pub async fn refresh_credentials(&self) -> Result<()> {
+        let sdk_config = if self.config.load_from_env {
+            aws_config::load_from_env().await
+        } else {
+            self.config.sdk_config.clone()
+        };
+        *self.state.write().unwrap() = ClientState {
+            client: Client::new(&sdk_config),
+            refreshed_at: Instant::now(),
+        };
+        log::debug!("refreshed the aws credentials");
+        Ok(())
+    }
+
+    /// Return the SDK client, after refreshing the credentials if the refresh interval elapsed
+    async fn client(&self) -> Result<Client> {
+        if let Some(interval) = self.config.credentials_refresh_interval {
+            let refreshed_at = self.state.read().unwrap().refreshed_at;
+            if refreshed_at.elapsed() >= interval {
+                self.refresh_credentials().await?;
+            }
+        }
+        Ok(self.state.read().unwrap().client.clone())
+    }
+
+    /// Send a request to AWS KMS, and send it again with refreshed credentials if they
+    /// were rejected, since they might have been rotated after they were fetched
+    async fn send_with_refresh<T, E, R, F, Fut>(
+        &self,
+        operation: &str,
+        send: F,
+    ) -> Result<core::result::Result<T, SdkError<E, R>>>
+    where
+        E: ProvideErrorMetadata,
+        F: Fn(Client) -> Fut,
+        Fut: Future<Output = core::result::Result<T, SdkError<E, R>>>,
+    {
+        let result = send(self.client().await?).await;
+        match result {
+            Err(err) if is_auth_failure(&err) => {
+                log::warn!(%operation, "credentials rejected, refreshing them to send the request again");
+                self.refresh_credentials().await?;
+                Ok(send(self.client().await?).await)
+            }
+            result => Ok(result),
+        }
     }
 
     fn cast_handle_to_kid(handle: &SigningSecretKeyHandle) -> Result<String> {
@@ -88,15 +183,19 @@ impl AwsKmsClient {
     /// Create a new NIST P-256 key-pair in AWS KMS and return its ID.
     pub async fn create_key(&self) -> Result<SigningSecretKeyHandle> {
         log::trace!("create new key");
-        let mut client = self
-            .client
-            .create_key()
-            .key_usage(KeyUsageType::SignVerify)
-            .key_spec(KeySpec::EccNistP256);
-        if self.config.multi_region {
-            client = client.multi_region(true)
-        }
-        let output = match client.send().await {
+        let result = self
+            .send_with_refresh("create key", |client| {
+                let mut request = client
+                    .create_key()
+                    .key_usage(KeyUsageType::SignVerify)
+                    .key_spec(KeySpec::EccNistP256);
+                if self.config.multi_region {
+                    request = request.multi_region(true)
+                }
+                request.send()
+            })
+            .await?;
+        let output = match result {
             Ok(out) => out,
             Err(err) => {
                 log::error!(%err, "failed to create new key");
@@ -120,12 +219,16 @@ impl AwsKmsClient {
         let key = Self::cast_handle_to_kid(key)?;
         log::trace!(%key, "schedule key for deletion");
         const DAYS: i32 = 7;
-        let client = self
-            .client
-            .schedule_key_deletion()
-            .key_id(&key)
-            .pending_window_in_days(DAYS);
-        match client.send().await {
+        let result = self
+            .send_with_refresh("schedule key deletion", |client| {
+                client
+                    .schedule_key_deletion()
+                    .key_id(&key)
+                    .pending_window_in_days(DAYS)
+                    .send()
+            })
+            .await?;
+        match result {
             Err(SdkError::ServiceError(err))
                 if matches!(err.err(), ScheduleKeyDeletionError::NotFoundException(_)) =>
             {
@@ -152,11 +255,10 @@ impl AwsKmsClient {
         let key = Self::cast_handle_to_kid(key)?;
         log::trace!(%key, "get public key");
         let output = self
-            .client
-            .get_public_key()
-            .key_id(&key)
-            .send()
-            .await
+            .send_with_refresh("get public key", |client| {
+                client.get_public_key().key_id(&key).send()
+            })
+            .await?
             .map_err(|err| {
                 log::error!(%key, %err, "failed to get public key");
                 Error::Export {
@@ -193,20 +295,25 @@ impl AwsKmsClient {
     pub async fn sign(&self, key: &SigningSecretKeyHandle, message: &[u8]) -> Result<Signature> {
         let key = Self::cast_handle_to_kid(key)?;
         log::trace!(%key, "sign message");
-        let client = self
-            .client
-            .sign()
-            .key_id(&key)
-            .signing_algorithm(SigningAlgorithmSpec::EcdsaSha256)
-            .message(digest(message))
-            .message_type(MessageType::Digest);
-        let output = client.send().await.map_err(|err| {
-            log::error!(%key, %err, "failed to sign message");
-            Error::Sign {
-                keyid: key.to_string(),
-                error: err.to_string(),
-            }
-        })?;
+        let digest = digest(message);
+        let output = self
+            .send_with_refresh("sign", |client| {
+                client
+                    .sign()
+                    .key_id(&key)
+                    .signing_algorithm(SigningAlgorithmSpec::EcdsaSha256)
+                    .message(digest.clone())
+                    .message_type(MessageType::Digest)
+                    .send()
+            })
+            .await?
+            .map_err(|err| {
+                log::error!(%key, %err, "failed to sign message");
+                Error::Sign {
+                    keyid: key.to_string(),
+                    error: err.to_string(),
+                }
+            })?;
         if let Some(sig) = output.signature() {
             log::debug!(%key, "signed message");
             let sig = p256::ecdsa::Signature::from_der(sig.as_ref())
@@ -240,6 +347,9 @@ pub trait KmsClient {
 
     /// Sign a message
     async fn sign(&self, key: &SigningSecretKeyHandle, message: &[u8]) -> Result<Signature>;
+
+    /// Refresh the credentials used to access the KMS
+    async fn refresh_credentials(&self) -> Result<()>;
 }
 
 #[async_trait]
@@ -258,10 +368,13 @@ impl KmsClient for AwsKmsClient {
                 // There shouldn't be more than 2-3 active keys in the KMS,
                 // however, technically we have a software limit of 100 keys here
                 // If there are more keys - `list_keys` will return an Error
-                let output = self.client.list_keys().send().await.map_err(|err| {
-                    log::error!(%err, "failed to list all keys");
-                    Error::MissingKeys
-                })?;
+                let output = self
+                    .send_with_refresh("list keys", |client| client.list_keys().send())
+                    .await?
+                    .map_err(|err| {
+                        log::error!(%err, "failed to list all keys");
+                        Error::MissingKeys
+                    })?;
 
                 if output.truncated() {
                     return Err(Error::TruncatedKeysList.into());
@@ -294,8 +407,108 @@ impl KmsClient for AwsKmsClient {
     async fn sign(&self, key: &SigningSecretKeyHandle, message: &[u8]) -> Result<Signature> {
         self.sign(key, message).await
     }
+
+    async fn refresh_credentials(&self) -> Result<()> {
+        self.refresh_credentials().await
+    }
+}
+
+/// Return true if an error was returned because the credentials were rejected
+fn is_auth_failure<E: ProvideErrorMetadata, R>(err: &SdkError<E, R>) -> bool {
+    err.as_service_error()
+        .and_then(|err| err.code())
+        .map_or(false, |code| AUTH_FAILURE_CODES.contains(&code))
 }
 
 fn digest(data: &[u8]) -> Blob {
     Blob::new(Sha256::digest(data).to_vec())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_config::retry::RetryConfig;
+    use aws_sdk_kms::error::ErrorMetadata;
+    use aws_sdk_kms::operation::sign::SignError;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    fn service_error(code: &str) -> SdkError<SignError, ()> {
+        SdkError::service_error(
+            SignError::generic(ErrorMetadata::builder().code(code).build()),
+            (),
+        )
+    }
+
+    async fn client() -> Result<AwsKmsClient> {
+        let sdk_config = SdkConfig::builder()
+            .retry_config(RetryConfig::disabled())
+            .build();
+        AwsKmsClient::new(AwsKmsConfig::new(sdk_config)).await
+    }
+
+    #[test]
+    fn test_is_auth_failure() {
+        for code in AUTH_FAILURE_CODES {
+            assert!(is_auth_failure(&service_error(code)));
+        }
+        assert!(!is_auth_failure(&service_error("NotFoundException")));
+        assert!(!is_auth_failure(&SdkError::<SignError, ()>::timeout_error(
+            "timeout"
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_request_is_sent_again_with_refreshed_credentials() -> Result<()> {
+        let client = client().await?;
+
+        let attempts = &AtomicUsize::new(0);
+        let result = client
+            .send_with_refresh("sign", move |_| async move {
+                match attempts.fetch_add(1, Ordering::SeqCst) {
+                    0 => Err(service_error("ExpiredTokenException")),
+                    _ => Ok(()),
+                }
+            })
+            .await?;
+
+        assert!(result.is_ok());
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_request_is_sent_again_only_once() -> Result<()> {
+        let client = client().await?;
+
+        let attempts = &AtomicUsize::new(0);
+        let result = client
+            .send_with_refresh("sign", move |_| async move {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err::<(), _>(service_error("ExpiredTokenException"))
+            })
+            .await?;
+
+        assert!(result.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_request_is_not_sent_again_for_other_errors() -> Result<()> {
+        let client = client().await?;
+        let refreshed_at = client.state.read().unwrap().refreshed_at;
+
+        let attempts = &AtomicUsize::new(0);
+        let result = client
+            .send_with_refresh("sign", move |_| async move {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err::<(), _>(service_error("NotFoundException"))
+            })
+            .await?;
+
+        assert!(result.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+        assert_eq!(client.state.read().unwrap().refreshed_at, refreshed_at);
+        Ok(())
+    }
+}
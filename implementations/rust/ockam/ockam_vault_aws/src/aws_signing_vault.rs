@@ -68,6 +68,12 @@ impl AwsSigningVault {
     pub async fn number_of_keys(&self) -> Result<usize> {
         Ok(self.keys.read().unwrap().len())
     }
+
+    /// Fetch new credentials to access the KMS, for example after they were rotated.
+    /// This is also done when signing with credentials rejected by the KMS
+    pub async fn refresh_credentials(&self) -> Result<()> {
+        self.client.refresh_credentials().await
+    }
 }
 
 #[async_trait]